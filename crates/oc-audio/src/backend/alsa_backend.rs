@@ -0,0 +1,205 @@
+//! Native ALSA audio backend
+//!
+//! Writes directly to an ALSA PCM from a dedicated thread. It opens the
+//! configured device, or `default` when none is set. In exclusive mode the
+//! configured device is expected to be a hardware one (e.g. `hw:1,0`),
+//! bypassing dmix and any sound server, with a small period for low latency.
+
+use super::{AudioBackend, AudioCallback, BackendOptions};
+use alsa::pcm::{Access, Format, HwParams, PCM};
+use alsa::{Direction, ValueOr};
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// Output sample rate requested from the device
+const SAMPLE_RATE: u32 = 48000;
+/// Output channel count requested when the options leave it open
+const DEFAULT_CHANNELS: u32 = 2;
+
+/// Native ALSA backend
+pub struct AlsaAudioBackend {
+    /// Opened PCM (moved into the playback thread while running)
+    pcm: Option<PCM>,
+    /// Negotiated sample rate
+    sample_rate: Option<u32>,
+    /// Negotiated channel count
+    channels: Option<u16>,
+    /// Negotiated period in frames
+    period_frames: usize,
+    /// Whether the hardware device was opened directly
+    exclusive: bool,
+    callback: Arc<Mutex<Option<AudioCallback>>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<PCM>>,
+}
+
+impl AlsaAudioBackend {
+    /// Create a new, uninitialized ALSA backend
+    pub fn new() -> Self {
+        Self {
+            pcm: None,
+            sample_rate: None,
+            channels: None,
+            period_frames: 0,
+            exclusive: false,
+            callback: Arc::new(Mutex::new(None)),
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Open and configure a PCM device
+    fn open(device: &str, options: &BackendOptions) -> Result<(PCM, u32, u32, usize), alsa::Error> {
+        let pcm = PCM::new(device, Direction::Playback, false)?;
+        {
+            let hwp = HwParams::any(&pcm)?;
+            hwp.set_access(Access::RWInterleaved)?;
+            hwp.set_format(Format::s16())?;
+            let channels = match options.channels {
+                0 => DEFAULT_CHANNELS,
+                n => n as u32,
+            };
+            hwp.set_channels_near(channels)?;
+            hwp.set_rate(SAMPLE_RATE, ValueOr::Nearest)?;
+            if let Some(frames) = options.buffer_frames {
+                let period = hwp.set_period_size_near(frames as alsa::pcm::Frames, ValueOr::Nearest)?;
+                hwp.set_buffer_size_near(period * 4)?;
+            }
+            pcm.hw_params(&hwp)?;
+        }
+        let (rate, channels, period) = {
+            let hwp = pcm.hw_params_current()?;
+            (hwp.get_rate()?, hwp.get_channels()?, hwp.get_period_size()?)
+        };
+        Ok((pcm, rate, channels, period.max(64) as usize))
+    }
+}
+
+impl AudioBackend for AlsaAudioBackend {
+    fn name(&self) -> &'static str {
+        "ALSA"
+    }
+
+    fn init(&mut self, options: &BackendOptions) -> Result<(), String> {
+        let device = options.device.as_deref().unwrap_or("default");
+        // Only a device the user picked says which card to take over
+        let exclusive = options.exclusive && options.device.is_some();
+        if options.exclusive && !exclusive {
+            tracing::warn!("Exclusive mode needs an ALSA output device such as hw:0,0, using the default device");
+        }
+        let (pcm, rate, channels, period) = Self::open(device, options)
+            .map_err(|e| format!("Failed to open ALSA device {}: {}", device, e))?;
+
+        tracing::info!(
+            "ALSA device {}: {} Hz, {} channels, {} frame period",
+            device,
+            rate,
+            channels,
+            period
+        );
+        self.pcm = Some(pcm);
+        self.sample_rate = Some(rate);
+        self.channels = Some(channels as u16);
+        self.period_frames = period;
+        self.exclusive = exclusive;
+        Ok(())
+    }
+
+    fn set_callback(&mut self, callback: AudioCallback) {
+        *self.callback.lock() = Some(callback);
+    }
+
+    fn start(&mut self) -> Result<(), String> {
+        if self.thread.is_some() {
+            return Ok(());
+        }
+        let pcm = self.pcm.take().ok_or("Device not initialized")?;
+        let samples = self.period_frames * self.channels.unwrap_or(2) as usize;
+        let callback = Arc::clone(&self.callback);
+        let running = Arc::clone(&self.running);
+        running.store(true, Ordering::Release);
+
+        let thread = std::thread::Builder::new()
+            .name("alsa-output".to_string())
+            .spawn(move || {
+                let mut mixed = vec![0.0f32; samples];
+                let mut output = vec![0i16; samples];
+                if let Ok(io) = pcm.io_i16() {
+                    while running.load(Ordering::Acquire) {
+                        let cb = callback.lock().clone();
+                        match cb {
+                            Some(cb) => cb(&mut mixed),
+                            None => mixed.fill(0.0),
+                        }
+                        for (out, &sample) in output.iter_mut().zip(&mixed) {
+                            *out = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+                        }
+                        if let Err(e) = io.writei(&output) {
+                            // Underruns are recoverable; anything else ends playback
+                            if let Err(e) = pcm.try_recover(e, true) {
+                                tracing::error!("ALSA write failed: {}", e);
+                                break;
+                            }
+                        }
+                    }
+                }
+                let _ = pcm.drop();
+                pcm
+            })
+            .map_err(|e| format!("Failed to spawn audio thread: {}", e))?;
+
+        self.thread = Some(thread);
+        tracing::info!("ALSA stream started");
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), String> {
+        self.running.store(false, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            let pcm = thread.join().map_err(|_| "Audio thread panicked")?;
+            // Keep the device so playback can be restarted
+            let _ = pcm.prepare();
+            self.pcm = Some(pcm);
+            tracing::info!("ALSA stream stopped");
+        }
+        Ok(())
+    }
+
+    fn sample_rate(&self) -> Option<u32> {
+        self.sample_rate
+    }
+
+    fn channels(&self) -> Option<u16> {
+        self.channels
+    }
+
+    fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+}
+
+impl Default for AlsaAudioBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for AlsaAudioBackend {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_start_requires_init() {
+        let mut backend = AlsaAudioBackend::new();
+        assert!(backend.start().is_err());
+        assert!(backend.sample_rate().is_none());
+    }
+}
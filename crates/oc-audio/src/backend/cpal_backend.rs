@@ -3,15 +3,12 @@
 //! Audio backend implementation using the cpal (Cross-Platform Audio Library).
 
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{Device, Host, Stream, StreamConfig, SupportedStreamConfig};
+use cpal::{BufferSize, Device, Host, SampleFormat, Stream, StreamConfig, SupportedStreamConfig};
 use std::sync::Arc;
 use parking_lot::Mutex;
+use super::{AudioBackend, AudioSample, BackendOptions};
 
-/// Sample type for audio output
-pub type AudioSample = f32;
-
-/// Audio callback function type
-pub type AudioCallback = Arc<dyn Fn(&mut [AudioSample]) + Send + Sync>;
+pub use super::AudioCallback;
 
 /// cpal audio backend
 pub struct CpalAudioBackend {
@@ -20,6 +17,10 @@ pub struct CpalAudioBackend {
     config: Option<SupportedStreamConfig>,
     stream: Option<Stream>,
     callback: Arc<Mutex<Option<AudioCallback>>>,
+    /// Requested device period in frames
+    buffer_frames: Option<u32>,
+    /// Name of the device to open (None = default output device)
+    device_name: Option<String>,
 }
 
 impl CpalAudioBackend {
@@ -33,15 +34,24 @@ impl CpalAudioBackend {
             config: None,
             stream: None,
             callback: Arc::new(Mutex::new(None)),
+            buffer_frames: None,
+            device_name: None,
         })
     }
 
     /// Initialize the audio device
     pub fn init(&mut self) -> Result<(), String> {
-        // Get default output device
-        let device = self.host
-            .default_output_device()
-            .ok_or("No output device available")?;
+        // Get the configured output device, or the default one
+        let device = match &self.device_name {
+            Some(name) => self.host
+                .output_devices()
+                .map_err(|e| format!("Failed to list output devices: {}", e))?
+                .find(|device| device.name().is_ok_and(|n| n == *name))
+                .ok_or_else(|| format!("No output device named {}", name))?,
+            None => self.host
+                .default_output_device()
+                .ok_or("No output device available")?,
+        };
         
         tracing::info!("Audio device: {}", device.name().unwrap_or_else(|_| "Unknown".to_string()));
         
@@ -58,6 +68,39 @@ impl CpalAudioBackend {
         Ok(())
     }
 
+    /// Switch to a device configuration with `channels` outputs, if supported
+    ///
+    /// Keeps the current configuration when the device has no matching one.
+    pub fn select_channels(&mut self, channels: u16) {
+        let (Some(device), Some(current)) = (self.device.as_ref(), self.config.as_ref()) else {
+            return;
+        };
+        if current.channels() == channels {
+            return;
+        }
+
+        let rate = current.sample_rate();
+        let found = device.supported_output_configs().ok().and_then(|mut configs| {
+            configs.find(|c| {
+                c.channels() == channels
+                    && c.sample_format() == SampleFormat::F32
+                    && c.min_sample_rate() <= rate
+                    && c.max_sample_rate() >= rate
+            })
+        });
+        match found {
+            Some(range) => {
+                tracing::info!("Audio output switched to {} channels", channels);
+                self.config = Some(range.with_sample_rate(rate));
+            }
+            None => tracing::info!(
+                "Audio device has no {} channel mode, using {} channels",
+                channels,
+                current.channels()
+            ),
+        }
+    }
+
     /// Set the audio callback
     pub fn set_callback<F>(&mut self, callback: F)
     where
@@ -77,7 +120,10 @@ impl CpalAudioBackend {
         let callback = Arc::clone(&self.callback);
         
         // Create stream config
-        let stream_config: StreamConfig = config.clone().into();
+        let mut stream_config: StreamConfig = config.clone().into();
+        if let Some(frames) = self.buffer_frames {
+            stream_config.buffer_size = BufferSize::Fixed(frames);
+        }
         
         // Build output stream
         let stream = device
@@ -129,6 +175,45 @@ impl CpalAudioBackend {
     }
 }
 
+impl AudioBackend for CpalAudioBackend {
+    fn name(&self) -> &'static str {
+        "cpal"
+    }
+
+    fn init(&mut self, options: &BackendOptions) -> Result<(), String> {
+        if options.exclusive {
+            tracing::warn!("cpal does not support exclusive mode, using shared mode");
+        }
+        self.buffer_frames = options.buffer_frames;
+        self.device_name = options.device.clone();
+        CpalAudioBackend::init(self)?;
+        if options.channels > 0 {
+            self.select_channels(options.channels);
+        }
+        Ok(())
+    }
+
+    fn set_callback(&mut self, callback: AudioCallback) {
+        *self.callback.lock() = Some(callback);
+    }
+
+    fn start(&mut self) -> Result<(), String> {
+        CpalAudioBackend::start(self)
+    }
+
+    fn stop(&mut self) -> Result<(), String> {
+        CpalAudioBackend::stop(self)
+    }
+
+    fn sample_rate(&self) -> Option<u32> {
+        CpalAudioBackend::sample_rate(self)
+    }
+
+    fn channels(&self) -> Option<u16> {
+        CpalAudioBackend::channels(self)
+    }
+}
+
 impl Default for CpalAudioBackend {
     fn default() -> Self {
         Self::new().unwrap_or_else(|e| {
@@ -139,6 +224,8 @@ impl Default for CpalAudioBackend {
                 config: None,
                 stream: None,
                 callback: Arc::new(Mutex::new(None)),
+                buffer_frames: None,
+                device_name: None,
             }
         })
     }
@@ -1,7 +1,178 @@
 //! Audio backends
+//!
+//! cpal covers WASAPI (shared mode), CoreAudio, and PulseAudio/PipeWire
+//! through their ALSA plugins; native ALSA adds exclusive access to a
+//! hardware device. SDL2, native PulseAudio/PipeWire and WASAPI exclusive
+//! backends are not implemented.
 
+#[cfg(target_os = "linux")]
+pub mod alsa_backend;
 pub mod cpal_backend;
 pub mod null;
 
+#[cfg(target_os = "linux")]
+pub use alsa_backend::AlsaAudioBackend;
 pub use cpal_backend::CpalAudioBackend;
 pub use null::NullAudioBackend;
+
+use crate::ring_buffer::AudioRingBuffer;
+use oc_core::config::{AudioBackend as AudioBackendKind, AudioConfig};
+use std::sync::Arc;
+
+/// Sample type for audio output
+pub type AudioSample = f32;
+
+/// Audio callback filling interleaved output samples
+pub type AudioCallback = Arc<dyn Fn(&mut [AudioSample]) + Send + Sync>;
+
+/// Period size requested in exclusive/low-latency mode
+pub const LOW_LATENCY_FRAMES: u32 = 256;
+
+/// Options passed to a backend on initialization
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BackendOptions {
+    /// Output device name (None = system default)
+    pub device: Option<String>,
+    /// Bypass the system mixer and open the hardware device directly
+    pub exclusive: bool,
+    /// Device period in frames (None = backend default)
+    pub buffer_frames: Option<u32>,
+    /// Requested output channels (0 = device default)
+    ///
+    /// Devices may open with fewer; the mixer downmixes to whatever
+    /// [`AudioBackend::channels`] reports.
+    pub channels: u16,
+}
+
+impl BackendOptions {
+    /// Options for an audio configuration
+    pub fn from_config(config: &AudioConfig) -> Self {
+        let device = config.output_device.trim();
+        Self {
+            device: (!device.is_empty()).then(|| device.to_string()),
+            exclusive: config.exclusive_mode,
+            buffer_frames: config.exclusive_mode.then_some(LOW_LATENCY_FRAMES),
+            channels: config.channel_layout.num_channels(),
+        }
+    }
+}
+
+/// Audio output backend
+pub trait AudioBackend {
+    /// Backend name
+    fn name(&self) -> &'static str;
+
+    /// Open the output device
+    fn init(&mut self, options: &BackendOptions) -> Result<(), String>;
+
+    /// Set the callback that fills output buffers
+    fn set_callback(&mut self, callback: AudioCallback);
+
+    /// Start audio playback
+    fn start(&mut self) -> Result<(), String>;
+
+    /// Stop audio playback
+    fn stop(&mut self) -> Result<(), String>;
+
+    /// Output sample rate, once initialized
+    fn sample_rate(&self) -> Option<u32>;
+
+    /// Output channel count, once initialized
+    fn channels(&self) -> Option<u16>;
+
+    /// Whether the device is opened in exclusive mode
+    fn is_exclusive(&self) -> bool {
+        false
+    }
+
+    /// Play audio from a ring buffer filled by the mixer
+    fn set_ring_buffer(&mut self, ring: Arc<AudioRingBuffer>) {
+        self.set_callback(Arc::new(move |data| {
+            ring.pop(data);
+        }));
+    }
+}
+
+/// Create the backend selected in the configuration
+///
+/// `Auto` tries each real backend in turn. If the selected backend cannot
+/// be opened, a warning is logged and [`NullAudioBackend`] is returned so
+/// emulation can continue without sound.
+pub fn create_backend(kind: AudioBackendKind, options: &BackendOptions) -> Box<dyn AudioBackend> {
+    let candidates: &[AudioBackendKind] = match kind {
+        // Only native ALSA can open the device exclusively
+        AudioBackendKind::Auto if options.exclusive => &[AudioBackendKind::Alsa, AudioBackendKind::Cpal],
+        AudioBackendKind::Auto => &[AudioBackendKind::Cpal, AudioBackendKind::Alsa],
+        AudioBackendKind::Null => &[],
+        _ => std::slice::from_ref(&kind),
+    };
+
+    for &candidate in candidates {
+        let mut backend = match open_backend(candidate) {
+            Ok(backend) => backend,
+            Err(e) => {
+                tracing::warn!("{:?} audio backend unavailable: {}", candidate, e);
+                continue;
+            }
+        };
+        match backend.init(options) {
+            Ok(()) => {
+                tracing::info!("Using {} audio backend", backend.name());
+                return backend;
+            }
+            Err(e) => tracing::warn!("{} audio backend failed to initialize: {}", backend.name(), e),
+        }
+    }
+
+    if kind != AudioBackendKind::Null {
+        tracing::warn!("No audio backend available, falling back to null output (no sound)");
+    }
+    Box::new(NullAudioBackend::new())
+}
+
+/// Construct an uninitialized backend
+fn open_backend(kind: AudioBackendKind) -> Result<Box<dyn AudioBackend>, String> {
+    match kind {
+        AudioBackendKind::Cpal => Ok(Box::new(CpalAudioBackend::new()?)),
+        #[cfg(target_os = "linux")]
+        AudioBackendKind::Alsa => Ok(Box::new(AlsaAudioBackend::new())),
+        #[cfg(not(target_os = "linux"))]
+        AudioBackendKind::Alsa => Err("ALSA is only available on Linux".to_string()),
+        AudioBackendKind::Null => Ok(Box::new(NullAudioBackend::new())),
+        AudioBackendKind::Auto => Err("Auto is not a concrete backend".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_null_backend_selected() {
+        let backend = create_backend(AudioBackendKind::Null, &BackendOptions::default());
+        assert_eq!(backend.name(), "Null");
+    }
+
+    #[test]
+    fn test_auto_backend_always_succeeds() {
+        // Falls back to Null on machines without audio devices
+        let mut backend = create_backend(AudioBackendKind::Auto, &BackendOptions::default());
+        backend.set_ring_buffer(Arc::new(AudioRingBuffer::new(64, 2)));
+        assert!(!backend.name().is_empty());
+    }
+
+    #[test]
+    fn test_options_from_config() {
+        let config = AudioConfig {
+            exclusive_mode: true,
+            output_device: " hw:1,0 ".to_string(),
+            ..Default::default()
+        };
+        let options = BackendOptions::from_config(&config);
+        assert_eq!(options.device.as_deref(), Some("hw:1,0"));
+        assert!(options.exclusive);
+        assert_eq!(options.buffer_frames, Some(LOW_LATENCY_FRAMES));
+        assert_eq!(options.channels, 2);
+        assert_eq!(BackendOptions::from_config(&AudioConfig::default()).device, None);
+    }
+}
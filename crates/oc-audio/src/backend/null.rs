@@ -1,5 +1,7 @@
 //! Null audio backend
 
+use super::{AudioBackend, AudioCallback, BackendOptions};
+
 /// Null audio backend (no sound output)
 pub struct NullAudioBackend;
 
@@ -9,6 +11,34 @@ impl NullAudioBackend {
     }
 }
 
+impl AudioBackend for NullAudioBackend {
+    fn name(&self) -> &'static str {
+        "Null"
+    }
+
+    fn init(&mut self, _options: &BackendOptions) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn set_callback(&mut self, _callback: AudioCallback) {}
+
+    fn start(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn sample_rate(&self) -> Option<u32> {
+        None
+    }
+
+    fn channels(&self) -> Option<u16> {
+        None
+    }
+}
+
 impl Default for NullAudioBackend {
     fn default() -> Self {
         Self::new()
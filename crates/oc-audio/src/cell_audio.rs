@@ -2,8 +2,10 @@
 //!
 //! Emulates the PS3's cellAudio library for audio output.
 
+use crate::mixer::{AudioMixer, ChannelLayout, Sample, SourceId};
+use crate::recorder::AudioRecorder;
 use std::sync::Arc;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 
 /// Audio port configuration
 #[derive(Debug, Clone, Copy)]
@@ -16,6 +18,13 @@ pub struct AudioPortConfig {
     pub attributes: u64,
 }
 
+impl AudioPortConfig {
+    /// Channel layout of the port, if the channel count is valid
+    pub fn layout(&self) -> Option<ChannelLayout> {
+        ChannelLayout::from_channels(self.num_channels as usize)
+    }
+}
+
 impl Default for AudioPortConfig {
     fn default() -> Self {
         Self {
@@ -47,6 +56,8 @@ pub struct AudioPort {
     pub read_index: u64,
     /// Write index (tag)
     pub tag: u64,
+    /// Mixer source fed by this port
+    pub source: Option<SourceId>,
 }
 
 impl AudioPort {
@@ -57,6 +68,7 @@ impl AudioPort {
             state: AudioPortState::Opened,
             read_index: 0,
             tag: 0,
+            source: None,
         }
     }
 
@@ -90,6 +102,10 @@ impl Default for CellAudioConfig {
 pub struct CellAudio {
     config: CellAudioConfig,
     ports: Arc<RwLock<Vec<Option<AudioPort>>>>,
+    /// Mixer that port audio is routed to
+    mixer: Option<Arc<Mutex<AudioMixer>>>,
+    /// Recorder receiving per-port audio
+    recorder: Option<Arc<Mutex<AudioRecorder>>>,
 }
 
 impl CellAudio {
@@ -103,9 +119,24 @@ impl CellAudio {
         Self {
             config,
             ports: Arc::new(RwLock::new(vec![None; 8])), // Max 8 ports
+            mixer: None,
+            recorder: None,
         }
     }
 
+    /// Route port audio to a mixer
+    ///
+    /// Each opened port becomes a mixer source with the port's channel
+    /// layout, so 5.1/7.1 ports keep their channels up to the final mix.
+    pub fn set_mixer(&mut self, mixer: Arc<Mutex<AudioMixer>>) {
+        self.mixer = Some(mixer);
+    }
+
+    /// Tap audio written to ports into a recorder
+    pub fn set_recorder(&mut self, recorder: Arc<Mutex<AudioRecorder>>) {
+        self.recorder = Some(recorder);
+    }
+
     /// Initialize audio system (cellAudioInit)
     pub fn init(&self) -> Result<(), String> {
         tracing::info!("cellAudio initialized with sample rate: {} Hz", self.config.sample_rate);
@@ -116,7 +147,9 @@ impl CellAudio {
     pub fn quit(&self) -> Result<(), String> {
         let mut ports = self.ports.write();
         for port in ports.iter_mut() {
-            *port = None;
+            if let Some(port) = port.take() {
+                self.remove_source(&port);
+            }
         }
         tracing::info!("cellAudio quit");
         Ok(())
@@ -124,13 +157,18 @@ impl CellAudio {
 
     /// Open an audio port (cellAudioPortOpen)
     pub fn port_open(&self, config: AudioPortConfig) -> Result<u32, String> {
+        let layout = config
+            .layout()
+            .ok_or_else(|| format!("Unsupported channel count: {}", config.num_channels))?;
         let mut ports = self.ports.write();
         
         for (idx, port) in ports.iter_mut().enumerate() {
             if port.is_none() {
                 let port_num = idx as u32;
-                *port = Some(AudioPort::new(port_num, config));
-                tracing::debug!("Audio port {} opened", port_num);
+                let mut new_port = AudioPort::new(port_num, config);
+                new_port.source = self.mixer.as_ref().map(|m| m.lock().add_source(layout));
+                *port = Some(new_port);
+                tracing::debug!("Audio port {} opened ({:?})", port_num, layout);
                 return Ok(port_num);
             }
         }
@@ -143,7 +181,9 @@ impl CellAudio {
         let mut ports = self.ports.write();
         
         if let Some(port) = ports.get_mut(port_num as usize) {
-            *port = None;
+            if let Some(port) = port.take() {
+                self.remove_source(&port);
+            }
             tracing::debug!("Audio port {} closed", port_num);
             Ok(())
         } else {
@@ -177,6 +217,35 @@ impl CellAudio {
         }
     }
 
+    /// Queue interleaved samples from a port for mixing
+    ///
+    /// Samples of a stopped port are discarded, like on hardware.
+    pub fn write_port(&self, port_num: u32, samples: &[Sample]) -> Result<(), String> {
+        let ports = self.ports.read();
+        let Some(Some(port)) = ports.get(port_num as usize) else {
+            return Err("Invalid port number".to_string());
+        };
+        if port.state != AudioPortState::Started {
+            return Ok(());
+        }
+        if let Some(recorder) = self.recorder.as_ref() {
+            recorder
+                .lock()
+                .write_port(port_num, port.config.num_channels as usize, samples);
+        }
+        match (self.mixer.as_ref(), port.source) {
+            (Some(mixer), Some(source)) => mixer.lock().write_to_source(source, samples),
+            _ => Ok(()),
+        }
+    }
+
+    /// Remove the mixer source of a closed port
+    fn remove_source(&self, port: &AudioPort) {
+        if let (Some(mixer), Some(source)) = (self.mixer.as_ref(), port.source) {
+            mixer.lock().remove_source(source);
+        }
+    }
+
     /// Get port config
     pub fn get_port_config(&self, port_num: u32) -> Option<AudioPortConfig> {
         let ports = self.ports.read();
@@ -227,4 +296,36 @@ mod tests {
         assert!(audio.port_close(port1).is_ok());
         assert!(audio.port_close(port2).is_ok());
     }
+
+    #[test]
+    fn test_surround_port_reaches_mixer() {
+        let mixer = Arc::new(Mutex::new(AudioMixer::new(ChannelLayout::Surround71)));
+        let mut audio = CellAudio::new();
+        audio.set_mixer(Arc::clone(&mixer));
+
+        let config = AudioPortConfig {
+            num_channels: 8,
+            ..Default::default()
+        };
+        let port = audio.port_open(config).unwrap();
+        audio.port_start(port).unwrap();
+
+        let frame = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8];
+        audio.write_port(port, &frame).unwrap();
+
+        let mut output = [0.0; 8];
+        mixer.lock().mix(&mut output, 1);
+        for (out, expected) in output.iter().zip(frame) {
+            assert!((out - expected).abs() < 1e-6);
+        }
+
+        audio.port_close(port).unwrap();
+        assert!(audio.write_port(port, &frame).is_err());
+        assert!(audio
+            .port_open(AudioPortConfig {
+                num_channels: 3,
+                ..Default::default()
+            })
+            .is_err());
+    }
 }
@@ -0,0 +1,128 @@
+//! Channel layout conversion
+//!
+//! Converts interleaved audio between mono, stereo, 5.1 and 7.1. Channel
+//! order follows cellAudio: L, R, C, LFE, Ls, Rs, Lb, Rb. Surround audio
+//! folded to stereo either with the ITU-R BS.775 coefficients or as a
+//! Dolby Surround (Lt/Rt) matrix encode.
+
+use crate::mixer::{ChannelLayout, Sample};
+pub use oc_core::config::AudioDownmix;
+
+/// -3 dB
+const FOLD: f32 = std::f32::consts::FRAC_1_SQRT_2;
+/// Dolby Surround matrix coefficients for the same- and opposite-side surround
+const DOLBY_NEAR: f32 = 0.8718;
+const DOLBY_FAR: f32 = 0.4899;
+
+/// Expand one frame to the full 7.1 channel set
+fn expand_frame(frame: &[Sample], from: ChannelLayout) -> [Sample; 8] {
+    let mut full = [0.0; 8];
+    match from {
+        ChannelLayout::Mono => {
+            full[0] = frame[0];
+            full[1] = frame[0];
+        }
+        _ => full[..frame.len()].copy_from_slice(frame),
+    }
+    full
+}
+
+/// Fold a 7.1 frame down to `to`
+fn fold_frame(full: &[Sample; 8], has_surround: bool, to: ChannelLayout, mode: AudioDownmix, out: &mut Vec<Sample>) {
+    let [l, r, c, lfe, ls, rs, lb, rb] = *full;
+    match to {
+        ChannelLayout::Surround71 => out.extend_from_slice(full),
+        ChannelLayout::Surround51 => out.extend_from_slice(&[l, r, c, lfe, ls + lb * FOLD, rs + rb * FOLD]),
+        ChannelLayout::Stereo | ChannelLayout::Mono => {
+            let (left, right) = if has_surround {
+                let sl = ls + lb * FOLD;
+                let sr = rs + rb * FOLD;
+                match mode {
+                    AudioDownmix::Itu => {
+                        let norm = 1.0 / (1.0 + 2.0 * FOLD);
+                        ((l + c * FOLD + sl * FOLD) * norm, (r + c * FOLD + sr * FOLD) * norm)
+                    }
+                    AudioDownmix::DolbySurround => {
+                        let norm = 1.0 / (1.0 + FOLD + DOLBY_NEAR + DOLBY_FAR);
+                        (
+                            (l + c * FOLD - sl * DOLBY_NEAR - sr * DOLBY_FAR) * norm,
+                            (r + c * FOLD + sl * DOLBY_FAR + sr * DOLBY_NEAR) * norm,
+                        )
+                    }
+                }
+            } else {
+                (l, r)
+            };
+            if to == ChannelLayout::Mono {
+                out.push((left + right) * 0.5);
+            } else {
+                out.extend_from_slice(&[left, right]);
+            }
+        }
+    }
+}
+
+/// Convert interleaved samples between layouts, appending to `output`
+///
+/// Extra channels are zero-filled when converting to a wider layout.
+pub fn remix(
+    input: &[Sample],
+    from: ChannelLayout,
+    to: ChannelLayout,
+    mode: AudioDownmix,
+    output: &mut Vec<Sample>,
+) {
+    let in_channels = from.num_channels();
+    if from == to {
+        output.extend_from_slice(&input[..input.len() / in_channels * in_channels]);
+        return;
+    }
+
+    let has_surround = in_channels > 2;
+    output.reserve(input.len() / in_channels * to.num_channels());
+    for frame in input.chunks_exact(in_channels) {
+        fold_frame(&expand_frame(frame, from), has_surround, to, mode, output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upmix_stereo_to_71() {
+        let mut out = Vec::new();
+        remix(&[0.5, -0.5], ChannelLayout::Stereo, ChannelLayout::Surround71, AudioDownmix::Itu, &mut out);
+        assert_eq!(out, [0.5, -0.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_itu_downmix() {
+        // Center only: equal on both sides, LFE dropped
+        let mut out = Vec::new();
+        let frame = [0.0, 0.0, 1.0, 1.0, 0.0, 0.0];
+        remix(&frame, ChannelLayout::Surround51, ChannelLayout::Stereo, AudioDownmix::Itu, &mut out);
+        assert_eq!(out.len(), 2);
+        assert!((out[0] - out[1]).abs() < 1e-6);
+        assert!((out[0] - FOLD / (1.0 + 2.0 * FOLD)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_dolby_surround_phase() {
+        // A left surround source appears out of phase between Lt and Rt
+        let mut out = Vec::new();
+        let frame = [0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0];
+        remix(&frame, ChannelLayout::Surround71, ChannelLayout::Stereo, AudioDownmix::DolbySurround, &mut out);
+        assert!(out[0] < 0.0 && out[1] > 0.0);
+        assert!(out[0].abs() > out[1].abs());
+    }
+
+    #[test]
+    fn test_71_to_51_folds_back_channels() {
+        let mut out = Vec::new();
+        let frame = [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+        remix(&frame, ChannelLayout::Surround71, ChannelLayout::Surround51, AudioDownmix::Itu, &mut out);
+        assert_eq!(out.len(), 6);
+        assert!((out[4] - FOLD).abs() < 1e-6);
+    }
+}
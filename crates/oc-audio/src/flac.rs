@@ -0,0 +1,424 @@
+//! Minimal FLAC encoder
+//!
+//! Encodes 16-bit PCM using fixed linear predictors with Rice-coded
+//! residuals, choosing the cheapest predictor order per subframe. Channels
+//! are coded independently. Good enough for audio dumps; a reference
+//! encoder will compress better.
+
+use std::io::{self, Seek, SeekFrom, Write};
+
+/// Samples per channel in each frame
+const BLOCK_SIZE: usize = 4096;
+/// Highest fixed predictor order
+const MAX_ORDER: usize = 4;
+/// Highest Rice parameter (15 is the escape code)
+const MAX_RICE_PARAM: u32 = 14;
+/// Size of the STREAMINFO block body
+const STREAMINFO_LEN: usize = 34;
+
+/// MSB-first bit writer
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            acc: 0,
+            bits: 0,
+        }
+    }
+
+    /// Write the low `bits` bits of `value` (at most 32)
+    fn put(&mut self, value: u64, bits: u32) {
+        if bits == 0 {
+            return;
+        }
+        self.acc = (self.acc << bits) | (value & ((1u64 << bits) - 1));
+        self.bits += bits;
+        while self.bits >= 8 {
+            self.bits -= 8;
+            self.bytes.push((self.acc >> self.bits) as u8);
+        }
+        self.acc &= (1u64 << self.bits) - 1;
+    }
+
+    /// Write `q` zero bits followed by a one
+    fn put_unary(&mut self, mut q: u32) {
+        while q >= 32 {
+            self.put(0, 32);
+            q -= 32;
+        }
+        self.put(1, q + 1);
+    }
+
+    /// Pad with zero bits to a byte boundary
+    fn align(&mut self) {
+        if self.bits > 0 {
+            self.put(0, 8 - self.bits);
+        }
+    }
+
+    /// Write a frame number in FLAC's UTF-8-like coding
+    fn put_utf8(&mut self, n: u64) {
+        if n < 0x80 {
+            self.put(n, 8);
+            return;
+        }
+        let continuation = (1..=6u32).find(|&c| n < 1u64 << (6 + 5 * c)).unwrap_or(6);
+        let prefix = !(0xFFu64 >> (continuation + 1)) & 0xFF;
+        self.put(prefix | (n >> (6 * continuation)), 8);
+        for i in (0..continuation).rev() {
+            self.put(0x80 | ((n >> (6 * i)) & 0x3F), 8);
+        }
+    }
+}
+
+/// CRC-8 (polynomial 0x07) over frame headers
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// CRC-16 (polynomial 0x8005) over whole frames
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// Residual of the fixed predictor of `order` at `i`
+fn fixed_residual(x: &[i32], i: usize, order: usize) -> i32 {
+    let x = |k: usize| x[i - k] as i64;
+    let predicted = match order {
+        0 => 0,
+        1 => x(1),
+        2 => 2 * x(1) - x(2),
+        3 => 3 * x(1) - 3 * x(2) + x(3),
+        _ => 4 * x(1) - 6 * x(2) + 4 * x(3) - x(4),
+    };
+    (x(0) - predicted) as i32
+}
+
+/// Zigzag-fold a signed residual
+fn fold(r: i32) -> u32 {
+    ((r << 1) ^ (r >> 31)) as u32
+}
+
+/// Encode one channel of a block as a subframe
+fn write_subframe(w: &mut BitWriter, x: &[i32]) {
+    let n = x.len();
+    let verbatim_bits = n as u64 * 16;
+
+    // Pick the predictor order and Rice parameter with the smallest estimate
+    let mut best: Option<(u64, usize, u32)> = None;
+    for order in 0..=MAX_ORDER.min(n.saturating_sub(1)) {
+        let sum: u64 = (order..n).map(|i| fold(fixed_residual(x, i, order)) as u64).sum();
+        let count = (n - order) as u64;
+        for k in 0..=MAX_RICE_PARAM {
+            let bits = order as u64 * 16 + 10 + count * (1 + k as u64) + (sum >> k);
+            if best.is_none_or(|(b, _, _)| bits < b) {
+                best = Some((bits, order, k));
+            }
+        }
+    }
+
+    match best {
+        Some((bits, order, k)) if bits < verbatim_bits => {
+            w.put(0, 1);
+            w.put(0b001000 | order as u64, 6);
+            w.put(0, 1);
+            for &sample in &x[..order] {
+                w.put(sample as u32 as u64, 16);
+            }
+            // Rice coding, partition order 0
+            w.put(0, 2);
+            w.put(0, 4);
+            w.put(k as u64, 4);
+            for i in order..n {
+                let u = fold(fixed_residual(x, i, order));
+                w.put_unary(u >> k);
+                w.put(u as u64, k);
+            }
+        }
+        _ => {
+            w.put(0, 1);
+            w.put(0b000001, 6);
+            w.put(0, 1);
+            for &sample in x {
+                w.put(sample as u32 as u64, 16);
+            }
+        }
+    }
+}
+
+/// Streaming FLAC file writer
+pub struct FlacWriter<W: Write + Seek> {
+    writer: W,
+    sample_rate: u32,
+    channels: usize,
+    /// Interleaved samples not yet encoded
+    pending: Vec<i16>,
+    frame_number: u64,
+    total_frames: u64,
+    min_frame_size: u32,
+    max_frame_size: u32,
+}
+
+impl<W: Write + Seek> FlacWriter<W> {
+    /// Start a FLAC stream (1-8 channels)
+    pub fn new(mut writer: W, sample_rate: u32, channels: usize) -> io::Result<Self> {
+        if !(1..=8).contains(&channels) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "FLAC supports 1-8 channels"));
+        }
+        writer.write_all(b"fLaC")?;
+        // Last metadata block, type STREAMINFO
+        writer.write_all(&[0x80, 0, 0, STREAMINFO_LEN as u8])?;
+        writer.write_all(&[0; STREAMINFO_LEN])?;
+
+        Ok(Self {
+            writer,
+            sample_rate,
+            channels,
+            pending: Vec::with_capacity(BLOCK_SIZE * channels),
+            frame_number: 0,
+            total_frames: 0,
+            min_frame_size: u32::MAX,
+            max_frame_size: 0,
+        })
+    }
+
+    /// Append interleaved samples
+    pub fn write_samples(&mut self, samples: &[f32]) -> io::Result<()> {
+        self.pending
+            .extend(samples.iter().map(|&s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16));
+        let block = BLOCK_SIZE * self.channels;
+        while self.pending.len() >= block {
+            let rest = self.pending.split_off(block);
+            let full = std::mem::replace(&mut self.pending, rest);
+            self.write_frame(&full)?;
+        }
+        Ok(())
+    }
+
+    /// Encode one block of interleaved samples
+    fn write_frame(&mut self, samples: &[i16]) -> io::Result<()> {
+        let n = samples.len() / self.channels;
+        let mut w = BitWriter::new();
+
+        w.put(0xFFF8, 16);
+        let size_code = if n == BLOCK_SIZE { 0b1100 } else { 0b0111 };
+        w.put(size_code, 4);
+        let rate_code = match self.sample_rate {
+            32000 => 0b1000,
+            44100 => 0b1001,
+            48000 => 0b1010,
+            96000 => 0b1011,
+            _ => 0b0000,
+        };
+        w.put(rate_code, 4);
+        w.put(self.channels as u64 - 1, 4);
+        w.put(0b100, 3); // 16 bits per sample
+        w.put(0, 1);
+        w.put_utf8(self.frame_number);
+        if size_code == 0b0111 {
+            w.put(n as u64 - 1, 16);
+        }
+        let header_crc = crc8(&w.bytes);
+        w.put(header_crc as u64, 8);
+
+        let mut channel = Vec::with_capacity(n);
+        for ch in 0..self.channels {
+            channel.clear();
+            channel.extend(samples.iter().skip(ch).step_by(self.channels).map(|&s| s as i32));
+            write_subframe(&mut w, &channel);
+        }
+        w.align();
+        let frame_crc = crc16(&w.bytes);
+        w.put(frame_crc as u64, 16);
+
+        self.writer.write_all(&w.bytes)?;
+        let size = w.bytes.len() as u32;
+        self.min_frame_size = self.min_frame_size.min(size);
+        self.max_frame_size = self.max_frame_size.max(size);
+        self.frame_number += 1;
+        self.total_frames += n as u64;
+        Ok(())
+    }
+
+    /// Number of frames (samples per channel) written
+    pub fn frames_written(&self) -> u64 {
+        self.total_frames + (self.pending.len() / self.channels) as u64
+    }
+
+    /// Encode remaining samples and fill in the stream header
+    pub fn finish(mut self) -> io::Result<W> {
+        let whole = self.pending.len() / self.channels * self.channels;
+        if whole > 0 {
+            let pending = std::mem::take(&mut self.pending);
+            self.write_frame(&pending[..whole])?;
+        }
+
+        let mut w = BitWriter::new();
+        w.put(BLOCK_SIZE as u64, 16);
+        w.put(BLOCK_SIZE as u64, 16);
+        w.put(if self.frame_number == 0 { 0 } else { self.min_frame_size as u64 }, 24);
+        w.put(self.max_frame_size as u64, 24);
+        w.put(self.sample_rate as u64, 20);
+        w.put(self.channels as u64 - 1, 3);
+        w.put(15, 5); // 16 bits per sample
+        w.put(self.total_frames >> 32, 4);
+        w.put(self.total_frames & 0xFFFF_FFFF, 32);
+        w.bytes.extend_from_slice(&[0; 16]); // MD5 unknown
+
+        self.writer.seek(SeekFrom::Start(8))?;
+        self.writer.write_all(&w.bytes)?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// MSB-first bit reader for decoding test output
+    struct BitReader<'a> {
+        data: &'a [u8],
+        pos: usize,
+    }
+
+    impl BitReader<'_> {
+        fn get(&mut self, bits: u32) -> u64 {
+            let mut value = 0;
+            for _ in 0..bits {
+                let bit = (self.data[self.pos / 8] >> (7 - self.pos % 8)) & 1;
+                value = (value << 1) | bit as u64;
+                self.pos += 1;
+            }
+            value
+        }
+
+        fn get_signed16(&mut self) -> i32 {
+            self.get(16) as u16 as i16 as i32
+        }
+
+        fn align(&mut self) {
+            self.pos = self.pos.div_ceil(8) * 8;
+        }
+    }
+
+    /// Decode a stream produced by [`FlacWriter`] back to interleaved samples
+    fn decode(data: &[u8]) -> (u32, usize, Vec<i16>) {
+        assert_eq!(&data[..4], b"fLaC");
+        let mut r = BitReader { data, pos: 8 * 8 };
+        r.get(16 + 16 + 24 + 24);
+        let sample_rate = r.get(20) as u32;
+        let channels = r.get(3) as usize + 1;
+        r.get(5);
+        let total = r.get(36) as usize;
+        r.pos = (8 + STREAMINFO_LEN) * 8;
+
+        let mut out = Vec::new();
+        while out.len() < total * channels {
+            let start = r.pos / 8;
+            assert_eq!(r.get(16), 0xFFF8);
+            let size_code = r.get(4);
+            r.get(4 + 4 + 3 + 1);
+            let first = r.get(8);
+            let extra = (first as u8).leading_ones().saturating_sub(1);
+            r.get(8 * extra);
+            let n = if size_code == 0b0111 { r.get(16) as usize + 1 } else { BLOCK_SIZE };
+            assert_eq!(r.get(8) as u8, crc8(&data[start..r.pos / 8 - 1]));
+
+            let mut decoded = vec![Vec::new(); channels];
+            for channel in decoded.iter_mut() {
+                r.get(1);
+                let kind = r.get(6);
+                r.get(1);
+                if kind == 1 {
+                    channel.extend((0..n).map(|_| r.get_signed16()));
+                    continue;
+                }
+                let order = (kind & 0x7) as usize;
+                channel.extend((0..order).map(|_| r.get_signed16()));
+                r.get(2 + 4);
+                let k = r.get(4) as u32;
+                for i in order..n {
+                    let mut q = 0;
+                    while r.get(1) == 0 {
+                        q += 1;
+                    }
+                    let u = (q << k) | r.get(k) as u32;
+                    let residual = ((u >> 1) as i32) ^ -((u & 1) as i32);
+                    channel.push(0);
+                    let predicted = channel[i] - fixed_residual(channel, i, order);
+                    channel[i] = predicted + residual;
+                }
+            }
+            r.align();
+            assert_eq!(r.get(16) as u16, crc16(&data[start..r.pos / 8 - 2]));
+
+            for i in 0..n {
+                for channel in &decoded {
+                    out.push(channel[i] as i16);
+                }
+            }
+        }
+        (sample_rate, channels, out)
+    }
+
+    #[test]
+    fn test_flac_round_trip() {
+        // A 440 Hz tone with some noise, spanning a partial final block
+        let frames = BLOCK_SIZE * 2 + 123;
+        let mut samples = Vec::with_capacity(frames * 2);
+        let mut noise = 1u32;
+        for i in 0..frames {
+            noise = noise.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let t = i as f32 / 48000.0;
+            samples.push((t * 440.0 * std::f32::consts::TAU).sin() * 0.5);
+            samples.push(((noise >> 16) as f32 / 65536.0 - 0.5) * 0.1);
+        }
+
+        let mut writer = FlacWriter::new(Cursor::new(Vec::new()), 48000, 2).unwrap();
+        writer.write_samples(&samples).unwrap();
+        assert_eq!(writer.frames_written(), frames as u64);
+        let data = writer.finish().unwrap().into_inner();
+
+        let (rate, channels, decoded) = decode(&data);
+        assert_eq!((rate, channels), (48000, 2));
+        let expected: Vec<i16> = samples
+            .iter()
+            .map(|&s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+            .collect();
+        assert_eq!(decoded, expected);
+        // The tone channel should compress well below verbatim size
+        assert!(data.len() < frames * 4);
+    }
+
+    #[test]
+    fn test_utf8_frame_numbers() {
+        let mut w = BitWriter::new();
+        w.put_utf8(0x7F);
+        w.put_utf8(0x80);
+        w.put_utf8(0x1234);
+        assert_eq!(w.bytes, [0x7F, 0xC2, 0x80, 0xE1, 0x88, 0xB4]);
+    }
+}
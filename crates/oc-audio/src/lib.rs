@@ -3,10 +3,18 @@
 pub mod backend;
 pub mod cell_audio;
 pub mod codec;
+pub mod downmix;
+pub mod flac;
 pub mod mixer;
+pub mod music;
+pub mod recorder;
 pub mod resampler;
+pub mod ring_buffer;
 pub mod spdif;
 pub mod thread;
 pub mod time_stretch;
 
+pub use mixer::AudioMixer;
+pub use recorder::AudioRecorder;
+pub use ring_buffer::AudioRingBuffer;
 pub use thread::AudioThread;
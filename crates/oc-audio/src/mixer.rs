@@ -2,7 +2,14 @@
 //!
 //! Provides audio mixing capabilities for multiple audio sources.
 
+use crate::downmix::{remix, AudioDownmix};
+use crate::recorder::AudioRecorder;
+use crate::resampler::{AudioResampler, ResamplerQuality};
+use crate::ring_buffer::AudioRingBuffer;
+use crate::time_stretch::{DynamicRateConfig, DynamicRateController};
+use parking_lot::Mutex;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Audio sample format
 pub type Sample = f32;
@@ -17,6 +24,27 @@ pub enum ChannelLayout {
 }
 
 impl ChannelLayout {
+    /// Layout for a channel count (1, 2, 6 or 8)
+    pub fn from_channels(channels: usize) -> Option<Self> {
+        match channels {
+            1 => Some(ChannelLayout::Mono),
+            2 => Some(ChannelLayout::Stereo),
+            6 => Some(ChannelLayout::Surround51),
+            8 => Some(ChannelLayout::Surround71),
+            _ => None,
+        }
+    }
+
+    /// Widest layout a device with `channels` outputs can play
+    pub fn for_device(channels: usize) -> Self {
+        match channels {
+            0 | 1 => ChannelLayout::Mono,
+            2..=5 => ChannelLayout::Stereo,
+            6 | 7 => ChannelLayout::Surround51,
+            _ => ChannelLayout::Surround71,
+        }
+    }
+
     pub fn num_channels(&self) -> usize {
         match self {
             ChannelLayout::Mono => 1,
@@ -27,6 +55,17 @@ impl ChannelLayout {
     }
 }
 
+impl From<oc_core::config::AudioChannelLayout> for ChannelLayout {
+    fn from(layout: oc_core::config::AudioChannelLayout) -> Self {
+        use oc_core::config::AudioChannelLayout;
+        match layout {
+            AudioChannelLayout::Stereo => ChannelLayout::Stereo,
+            AudioChannelLayout::Surround51 => ChannelLayout::Surround51,
+            AudioChannelLayout::Surround71 => ChannelLayout::Surround71,
+        }
+    }
+}
+
 /// Audio source identifier
 pub type SourceId = u32;
 
@@ -67,6 +106,16 @@ impl AudioSource {
     }
 }
 
+/// Rate-adapting stage between the mixer and the output ring buffer
+struct DynamicRateStage {
+    controller: DynamicRateController,
+    resampler: AudioResampler,
+    /// Mixed samples before rate adjustment
+    mixed: Vec<Sample>,
+    /// Samples after rate adjustment
+    resampled: Vec<Sample>,
+}
+
 /// Audio mixer
 pub struct AudioMixer {
     /// Audio sources
@@ -77,6 +126,16 @@ pub struct AudioMixer {
     output_layout: ChannelLayout,
     /// Next source ID
     next_id: SourceId,
+    /// Downmix method for sources wider than the output
+    downmix: AudioDownmix,
+    /// Dynamic rate control stage (None = fixed rate)
+    dynamic_rate: Option<DynamicRateStage>,
+    /// Source samples converted to the output layout
+    remixed: Vec<Sample>,
+    /// Recorder tapping the final mix
+    recorder: Option<Arc<Mutex<AudioRecorder>>>,
+    /// Emulation speed output is played at (None = unpaced, output muted)
+    speed: Option<f64>,
 }
 
 impl AudioMixer {
@@ -87,6 +146,11 @@ impl AudioMixer {
             master_volume: 1.0,
             output_layout,
             next_id: 0,
+            downmix: AudioDownmix::default(),
+            dynamic_rate: None,
+            remixed: Vec::new(),
+            recorder: None,
+            speed: Some(1.0),
         }
     }
 
@@ -142,7 +206,43 @@ impl AudioMixer {
         self.master_volume
     }
 
+    /// Get output channel layout
+    pub fn output_layout(&self) -> ChannelLayout {
+        self.output_layout
+    }
+
+    /// Change the output channel layout (e.g. to match the device)
+    pub fn set_output_layout(&mut self, layout: ChannelLayout) {
+        if layout == self.output_layout {
+            return;
+        }
+        self.output_layout = layout;
+        if let Some(stage) = self.dynamic_rate.as_mut() {
+            let (input_rate, output_rate) = (stage.resampler.input_rate(), stage.resampler.output_rate());
+            stage.resampler = AudioResampler::with_quality(
+                input_rate,
+                output_rate,
+                layout.num_channels(),
+                ResamplerQuality::Medium,
+            );
+            stage.mixed.clear();
+        }
+        tracing::debug!("Audio mixer output layout set to {:?}", layout);
+    }
+
+    /// Set the downmix method for sources wider than the output
+    pub fn set_downmix(&mut self, downmix: AudioDownmix) {
+        self.downmix = downmix;
+    }
+
+    /// Get the downmix method
+    pub fn downmix(&self) -> AudioDownmix {
+        self.downmix
+    }
+
     /// Mix audio sources into output buffer
+    ///
+    /// Each source is converted from its own layout to the output layout.
     pub fn mix(&mut self, output: &mut [Sample], frames: usize) {
         let channels = self.output_layout.num_channels();
         let samples_needed = frames * channels;
@@ -154,13 +254,13 @@ impl AudioMixer {
 
         // Mix all sources
         for source in self.sources.values_mut() {
-            let source_samples = source.read_samples(samples_needed);
+            let source_samples = source.read_samples(frames * source.layout.num_channels());
+            self.remixed.clear();
+            remix(&source_samples, source.layout, self.output_layout, self.downmix, &mut self.remixed);
             
             // Apply volume and mix into output
-            for (i, &sample) in source_samples.iter().enumerate() {
-                if i < samples_needed {
-                    output[i] += sample * source.volume * self.master_volume;
-                }
+            for (out, &sample) in output[..samples_needed].iter_mut().zip(&self.remixed) {
+                *out += sample * source.volume * self.master_volume;
             }
         }
 
@@ -168,6 +268,109 @@ impl AudioMixer {
         for sample in output[..samples_needed].iter_mut() {
             *sample = sample.clamp(-1.0, 1.0);
         }
+
+        if let Some(recorder) = self.recorder.as_ref() {
+            recorder.lock().write_mix(channels, &output[..samples_needed]);
+        }
+    }
+
+    /// Attach a recorder that receives the final mix
+    pub fn set_recorder(&mut self, recorder: Arc<Mutex<AudioRecorder>>) {
+        self.recorder = Some(recorder);
+    }
+
+    /// Enable dynamic rate control for [`mix_to_ring`](Self::mix_to_ring)
+    ///
+    /// Mixed audio at `input_rate` is resampled to the device's
+    /// `output_rate`, with a small rate adjustment that tracks the fill
+    /// level of the ring buffer, so speed fluctuations stretch the audio
+    /// slightly instead of causing underruns.
+    pub fn enable_dynamic_rate(&mut self, input_rate: u32, output_rate: u32, config: DynamicRateConfig) {
+        let channels = self.output_layout.num_channels();
+        self.dynamic_rate = Some(DynamicRateStage {
+            controller: DynamicRateController::new(config),
+            resampler: AudioResampler::with_quality(
+                input_rate,
+                output_rate,
+                channels,
+                ResamplerQuality::Medium,
+            ),
+            mixed: Vec::new(),
+            resampled: Vec::new(),
+        });
+        tracing::debug!(
+            "Dynamic rate control enabled ({} -> {} Hz, ±{:.2}% pitch)",
+            input_rate,
+            output_rate,
+            config.pitch_tolerance * 100.0
+        );
+    }
+
+    /// Disable dynamic rate control
+    pub fn disable_dynamic_rate(&mut self) {
+        self.dynamic_rate = None;
+    }
+
+    /// Set the maximum pitch deviation of dynamic rate control
+    pub fn set_pitch_tolerance(&mut self, tolerance: f32) {
+        if let Some(stage) = self.dynamic_rate.as_mut() {
+            stage.controller.set_pitch_tolerance(tolerance);
+        }
+    }
+
+    /// Current playback rate (1.0 when dynamic rate control is off)
+    pub fn playback_rate(&self) -> f64 {
+        self.dynamic_rate
+            .as_ref()
+            .map_or(1.0, |stage| stage.controller.rate())
+    }
+
+    /// Play output at the emulation `speed`, muting it while emulation is unpaced
+    ///
+    /// The speed scales the dynamic rate, so audio keeps in step with the
+    /// picture in turbo and slow motion at the cost of its pitch.
+    pub fn set_speed(&mut self, speed: Option<f64>) {
+        self.speed = speed.filter(|speed| *speed > 0.0);
+    }
+
+    /// Speed output is played at
+    pub fn speed(&self) -> Option<f64> {
+        self.speed
+    }
+
+    /// Mix `frames` frames and push them into an output ring buffer
+    ///
+    /// Returns the number of frames pushed, which differs from `frames`
+    /// while dynamic rate control is adjusting the playback rate.
+    pub fn mix_to_ring(&mut self, ring: &AudioRingBuffer, frames: usize) -> usize {
+        let channels = self.output_layout.num_channels();
+        let mut stage = self.dynamic_rate.take();
+        let mut mixed = stage
+            .as_mut()
+            .map(|s| std::mem::take(&mut s.mixed))
+            .unwrap_or_default();
+        mixed.resize(frames * channels, 0.0);
+        self.mix(&mut mixed, frames);
+
+        let pushed = match (stage.as_mut(), self.speed) {
+            // The sources are still drained and the mix recorded
+            (_, None) => 0,
+            (Some(s), Some(speed)) => {
+                let rate = s.controller.update(ring.fill_level()) * speed;
+                s.resampler.set_rate_adjust(rate);
+                s.resampled.clear();
+                // Length always matches the channel count
+                let _ = s.resampler.resample(&mixed, &mut s.resampled);
+                ring.push(&s.resampled)
+            }
+            (None, Some(_)) => ring.push(&mixed),
+        };
+
+        if let Some(s) = stage.as_mut() {
+            s.mixed = mixed;
+        }
+        self.dynamic_rate = stage;
+        pushed / channels
     }
 
     /// Clear all sources
@@ -236,4 +439,84 @@ mod tests {
         assert_eq!(ChannelLayout::Surround51.num_channels(), 6);
         assert_eq!(ChannelLayout::Surround71.num_channels(), 8);
     }
+
+    #[test]
+    fn test_dynamic_rate_follows_fill_level() {
+        let mut mixer = AudioMixer::new(ChannelLayout::Stereo);
+        let id = mixer.add_source(ChannelLayout::Stereo);
+        mixer.enable_dynamic_rate(
+            48000,
+            48000,
+            DynamicRateConfig {
+                target_fill: 0.5,
+                pitch_tolerance: 0.02,
+                smoothing: 1.0,
+            },
+        );
+
+        // Nearly full buffer: playback speeds up and fewer frames are pushed
+        let ring = AudioRingBuffer::new(10_000, 2);
+        ring.push(&vec![0.0; 2 * 9_000]);
+        mixer.write_to_source(id, &vec![0.25; 2 * 1000]).unwrap();
+        let pushed = mixer.mix_to_ring(&ring, 1000);
+        assert!(mixer.playback_rate() > 1.0);
+        assert!(pushed < 1000);
+
+        // Empty buffer: playback slows down, bounded by the tolerance
+        ring.clear();
+        mixer.write_to_source(id, &vec![0.25; 2 * 1000]).unwrap();
+        let pushed = mixer.mix_to_ring(&ring, 1000);
+        assert!((mixer.playback_rate() - 0.98).abs() < 1e-6);
+        assert!(pushed >= 1000);
+
+        mixer.disable_dynamic_rate();
+        assert_eq!(mixer.playback_rate(), 1.0);
+    }
+
+    #[test]
+    fn test_speed_scales_output() {
+        let mut mixer = AudioMixer::new(ChannelLayout::Stereo);
+        let id = mixer.add_source(ChannelLayout::Stereo);
+        let config = DynamicRateConfig { pitch_tolerance: 0.0, ..Default::default() };
+        mixer.enable_dynamic_rate(48000, 48000, config);
+        let ring = AudioRingBuffer::new(10_000, 2);
+
+        // Double speed plays the same audio in half the time
+        mixer.set_speed(Some(2.0));
+        mixer.write_to_source(id, &vec![0.25; 2 * 1000]).unwrap();
+        let pushed = mixer.mix_to_ring(&ring, 1000);
+        assert!((490..=510).contains(&pushed));
+
+        // Unpaced output is dropped, but the source is still drained
+        mixer.set_speed(None);
+        mixer.write_to_source(id, &vec![0.25; 2 * 1000]).unwrap();
+        assert_eq!(mixer.mix_to_ring(&ring, 1000), 0);
+        assert!(mixer.sources[&id].buffer.is_empty());
+    }
+
+    #[test]
+    fn test_mix_surround_source_to_stereo() {
+        let mut mixer = AudioMixer::new(ChannelLayout::Stereo);
+        let id = mixer.add_source(ChannelLayout::Surround71);
+        mixer.set_downmix(AudioDownmix::Itu);
+
+        // Two 7.1 frames with only the center channel set
+        let mut frame = [0.0; 8];
+        frame[2] = 0.5;
+        mixer.write_to_source(id, &[frame, frame].concat()).unwrap();
+
+        let mut output = vec![0.0; 4];
+        mixer.mix(&mut output, 2);
+        assert!(output.iter().all(|&s| s > 0.0));
+        assert!((output[0] - output[1]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_layout_for_device() {
+        assert_eq!(ChannelLayout::for_device(2), ChannelLayout::Stereo);
+        assert_eq!(ChannelLayout::for_device(6), ChannelLayout::Surround51);
+        assert_eq!(ChannelLayout::for_device(8), ChannelLayout::Surround71);
+        assert_eq!(ChannelLayout::from_channels(6), Some(ChannelLayout::Surround51));
+        assert_eq!(ChannelLayout::from_channels(3), None);
+    }
 }
@@ -0,0 +1,271 @@
+//! Custom soundtrack playback
+//!
+//! Decodes MP3 and FLAC tracks from the user's music folder on the host
+//! and plays them as stereo at the mixer rate, for games that let the
+//! player replace their soundtrack through cellMusic. Tracks are decoded
+//! whole on a worker thread so the emulation thread never waits on them.
+
+use crate::downmix::{remix, AudioDownmix};
+use crate::mixer::{ChannelLayout, Sample};
+use crate::resampler::AudioResampler;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Decoded track
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedTrack {
+    pub sample_rate: u32,
+    pub channels: usize,
+    /// Interleaved samples
+    pub samples: Vec<Sample>,
+}
+
+/// Decode an MP3 or FLAC file
+pub fn decode_track(path: &Path) -> Result<DecodedTrack, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| format!("Unsupported audio file {}: {}", path.display(), e))?;
+    let mut format = probed.format;
+    let track = format
+        .default_track()
+        .ok_or_else(|| format!("{} has no audio track", path.display()))?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| format!("Unsupported codec in {}: {}", path.display(), e))?;
+
+    let mut decoded = DecodedTrack {
+        sample_rate: track.codec_params.sample_rate.unwrap_or(0),
+        channels: track.codec_params.channels.map(|c| c.count()).unwrap_or(0),
+        samples: Vec::new(),
+    };
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        match decoder.decode(&packet) {
+            Ok(buffer) => {
+                let spec = *buffer.spec();
+                decoded.sample_rate = spec.rate;
+                decoded.channels = spec.channels.count();
+                let mut samples = SampleBuffer::<Sample>::new(buffer.capacity() as u64, spec);
+                samples.copy_interleaved_ref(buffer);
+                decoded.samples.extend_from_slice(samples.samples());
+            }
+            // Skip corrupt frames like a hardware player would
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(format!("Failed to decode {}: {}", path.display(), e)),
+        }
+    }
+
+    if decoded.sample_rate == 0 || decoded.channels == 0 {
+        return Err(format!("{} has no audio", path.display()));
+    }
+    Ok(decoded)
+}
+
+/// Convert a decoded track to interleaved stereo at `output_rate`
+pub fn to_stereo(track: &DecodedTrack, output_rate: u32) -> Result<Vec<Sample>, String> {
+    let mut stereo = Vec::with_capacity(track.samples.len() / track.channels * 2);
+    match ChannelLayout::from_channels(track.channels) {
+        Some(layout) => remix(&track.samples, layout, ChannelLayout::Stereo, AudioDownmix::default(), &mut stereo),
+        // Odd layouts keep their front pair
+        None => {
+            for frame in track.samples.chunks_exact(track.channels) {
+                stereo.extend_from_slice(&[frame[0], frame[1.min(track.channels - 1)]]);
+            }
+        }
+    }
+    if track.sample_rate == output_rate {
+        return Ok(stereo);
+    }
+
+    let mut resampled = Vec::new();
+    AudioResampler::new(track.sample_rate, output_rate, 2).resample(&stereo, &mut resampled)?;
+    Ok(resampled)
+}
+
+/// Player of one custom soundtrack track at a time
+pub struct MusicPlayer {
+    output_rate: u32,
+    /// Track being decoded on the worker thread
+    loading: Option<Receiver<Result<Vec<Sample>, String>>>,
+    /// Interleaved stereo samples of the loaded track
+    samples: Vec<Sample>,
+    /// Position in `samples`
+    position: usize,
+    /// Whether a track is loaded
+    loaded: bool,
+    /// Error of the last track that failed to load
+    error: Option<String>,
+}
+
+impl MusicPlayer {
+    /// Create a player producing stereo at `output_rate`
+    pub fn new(output_rate: u32) -> Self {
+        Self {
+            output_rate,
+            loading: None,
+            samples: Vec::new(),
+            position: 0,
+            loaded: false,
+            error: None,
+        }
+    }
+
+    /// Start decoding `path`, replacing the current track
+    pub fn load(&mut self, path: PathBuf) {
+        self.unload();
+        let (tx, rx) = mpsc::channel();
+        let output_rate = self.output_rate;
+        std::thread::spawn(move || {
+            let _ = tx.send(decode_track(&path).and_then(|track| to_stereo(&track, output_rate)));
+        });
+        self.loading = Some(rx);
+    }
+
+    /// Drop the current track
+    pub fn unload(&mut self) {
+        self.loading = None;
+        self.samples = Vec::new();
+        self.position = 0;
+        self.loaded = false;
+        self.error = None;
+    }
+
+    fn poll(&mut self) {
+        let Some(rx) = &self.loading else {
+            return;
+        };
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => Err("Decoder thread exited".to_string()),
+        };
+        self.loading = None;
+        match result {
+            Ok(samples) => {
+                self.samples = samples;
+                self.loaded = true;
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// Take up to `frames` stereo frames scaled by `volume`
+    ///
+    /// Returns nothing while the track is still being decoded.
+    pub fn fill(&mut self, frames: usize, volume: f32) -> Vec<Sample> {
+        self.poll();
+        let end = (self.position + frames * 2).min(self.samples.len());
+        let samples = self.samples[self.position..end].iter().map(|&s| s * volume).collect();
+        self.position = end;
+        samples
+    }
+
+    /// Check if a track is still being decoded
+    pub fn is_loading(&self) -> bool {
+        self.loading.is_some()
+    }
+
+    /// Check if the loaded track has played to its end
+    pub fn is_finished(&mut self) -> bool {
+        self.poll();
+        self.loaded && self.position >= self.samples.len()
+    }
+
+    /// Take the error of a track that failed to load
+    pub fn take_error(&mut self) -> Option<String> {
+        self.poll();
+        self.error.take()
+    }
+
+    /// Length of the loaded track in frames
+    pub fn frames(&self) -> usize {
+        self.samples.len() / 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flac::FlacWriter;
+    use std::time::{Duration, Instant};
+
+    fn write_flac(name: &str, sample_rate: u32, channels: usize, frames: usize) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("oc_audio_music_{}_{}.flac", name, std::process::id()));
+        let samples: Vec<f32> = (0..frames * channels).map(|i| ((i / channels) as f32 * 0.05).sin() * 0.5).collect();
+        let mut writer = FlacWriter::new(File::create(&path).unwrap(), sample_rate, channels).unwrap();
+        writer.write_samples(&samples).unwrap();
+        writer.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn test_decode_flac() {
+        let path = write_flac("decode", 44100, 2, 10000);
+        let track = decode_track(&path).unwrap();
+        assert_eq!(track.sample_rate, 44100);
+        assert_eq!(track.channels, 2);
+        assert_eq!(track.samples.len(), 20000);
+        assert!((track.samples[200] - (100.0f32 * 0.05).sin() * 0.5).abs() < 0.001);
+        let _ = std::fs::remove_file(path);
+
+        assert!(decode_track(Path::new("/nonexistent/track.mp3")).is_err());
+    }
+
+    #[test]
+    fn test_to_stereo() {
+        let mono = DecodedTrack { sample_rate: 48000, channels: 1, samples: vec![0.1, 0.2, 0.3] };
+        assert_eq!(to_stereo(&mono, 48000).unwrap(), [0.1, 0.1, 0.2, 0.2, 0.3, 0.3]);
+
+        let stereo = DecodedTrack { sample_rate: 24000, channels: 2, samples: vec![0.0; 2000] };
+        let resampled = to_stereo(&stereo, 48000).unwrap();
+        assert!((resampled.len() as i64 - 4000).abs() <= 8);
+    }
+
+    #[test]
+    fn test_music_player() {
+        let path = write_flac("player", 48000, 1, 5000);
+        let mut player = MusicPlayer::new(48000);
+        player.load(path.clone());
+
+        let start = Instant::now();
+        while player.is_loading() && start.elapsed() < Duration::from_secs(10) {
+            player.fill(0, 1.0);
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(player.frames(), 5000);
+
+        let block = player.fill(4096, 0.5);
+        assert_eq!(block.len(), 8192);
+        assert_eq!(block[2], block[3]);
+        assert!(!player.is_finished());
+        assert_eq!(player.fill(4096, 1.0).len(), (5000 - 4096) * 2);
+        assert!(player.is_finished());
+
+        player.unload();
+        assert!(!player.is_finished());
+        let _ = std::fs::remove_file(path);
+    }
+}
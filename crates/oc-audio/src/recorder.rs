@@ -0,0 +1,382 @@
+//! Audio dump recorder
+//!
+//! Taps the final mix (and optionally each cellAudio port) into
+//! timestamped WAV or FLAC files, for debugging audio issues and
+//! capturing game music.
+
+use crate::flac::FlacWriter;
+use crate::mixer::Sample;
+pub use oc_core::config::AudioDumpFormat;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Streaming 16-bit PCM WAV writer
+pub struct WavWriter<W: Write + Seek> {
+    writer: W,
+    channels: usize,
+    data_bytes: u32,
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    /// Write a WAV header; sizes are filled in by [`finish`](Self::finish)
+    pub fn new(mut writer: W, sample_rate: u32, channels: usize) -> io::Result<Self> {
+        let block_align = channels as u16 * 2;
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&0u32.to_le_bytes())?;
+        writer.write_all(b"WAVEfmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        writer.write_all(&1u16.to_le_bytes())?; // PCM
+        writer.write_all(&(channels as u16).to_le_bytes())?;
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+        writer.write_all(&block_align.to_le_bytes())?;
+        writer.write_all(&16u16.to_le_bytes())?;
+        writer.write_all(b"data")?;
+        writer.write_all(&0u32.to_le_bytes())?;
+        Ok(Self {
+            writer,
+            channels,
+            data_bytes: 0,
+        })
+    }
+
+    /// Append interleaved samples
+    pub fn write_samples(&mut self, samples: &[f32]) -> io::Result<()> {
+        for &sample in samples {
+            let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            self.writer.write_all(&value.to_le_bytes())?;
+        }
+        self.data_bytes = self.data_bytes.saturating_add(samples.len() as u32 * 2);
+        Ok(())
+    }
+
+    /// Number of frames (samples per channel) written
+    pub fn frames_written(&self) -> u64 {
+        (self.data_bytes / 2) as u64 / self.channels as u64
+    }
+
+    /// Fill in the chunk sizes
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer.write_all(&(36 + self.data_bytes).to_le_bytes())?;
+        self.writer.seek(SeekFrom::Start(40))?;
+        self.writer.write_all(&self.data_bytes.to_le_bytes())?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Writer for one dump file
+enum DumpWriter {
+    Wav(WavWriter<BufWriter<File>>),
+    Flac(FlacWriter<BufWriter<File>>),
+}
+
+impl DumpWriter {
+    fn write(&mut self, samples: &[Sample]) -> io::Result<()> {
+        match self {
+            Self::Wav(w) => w.write_samples(samples),
+            Self::Flac(w) => w.write_samples(samples),
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Self::Wav(w) => w.finish().map(|_| ()),
+            Self::Flac(w) => w.finish().map(|_| ()),
+        }
+    }
+}
+
+/// An open dump file
+struct DumpFile {
+    path: PathBuf,
+    channels: usize,
+    writer: DumpWriter,
+}
+
+impl DumpFile {
+    fn create(path: PathBuf, format: AudioDumpFormat, sample_rate: u32, channels: usize) -> io::Result<Self> {
+        let file = BufWriter::new(File::create(&path)?);
+        let writer = match format {
+            AudioDumpFormat::Wav => DumpWriter::Wav(WavWriter::new(file, sample_rate, channels)?),
+            AudioDumpFormat::Flac => DumpWriter::Flac(FlacWriter::new(file, sample_rate, channels)?),
+        };
+        tracing::info!("Recording audio to {}", path.display());
+        Ok(Self {
+            path,
+            channels,
+            writer,
+        })
+    }
+}
+
+/// Records audio to files while a session is active
+pub struct AudioRecorder {
+    /// Directory dump files are written to
+    directory: PathBuf,
+    /// File format
+    format: AudioDumpFormat,
+    /// Sample rate of recorded audio
+    sample_rate: u32,
+    /// Also record each cellAudio port to its own file
+    record_ports: bool,
+    /// File name prefix of the active session (None = not recording)
+    session: Option<String>,
+    /// Final mix file
+    mix: Option<DumpFile>,
+    /// Per-port files
+    ports: HashMap<u32, DumpFile>,
+}
+
+impl AudioRecorder {
+    /// Create an idle recorder writing to `directory`
+    pub fn new(directory: impl Into<PathBuf>, format: AudioDumpFormat) -> Self {
+        Self {
+            directory: directory.into(),
+            format,
+            sample_rate: 48000,
+            record_ports: false,
+            session: None,
+            mix: None,
+            ports: HashMap::new(),
+        }
+    }
+
+    /// Directory dump files are written to
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Set the file format (takes effect from the next session)
+    pub fn set_format(&mut self, format: AudioDumpFormat) {
+        self.format = format;
+    }
+
+    /// Get the file format
+    pub fn format(&self) -> AudioDumpFormat {
+        self.format
+    }
+
+    /// Set the sample rate of recorded audio (takes effect from the next session)
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// Enable or disable per-port files
+    pub fn set_record_ports(&mut self, record_ports: bool) {
+        self.record_ports = record_ports;
+    }
+
+    /// Whether per-port files are recorded
+    pub fn records_ports(&self) -> bool {
+        self.record_ports
+    }
+
+    /// Whether a session is active
+    pub fn is_recording(&self) -> bool {
+        self.session.is_some()
+    }
+
+    /// Start a new session; files are created when audio first arrives
+    pub fn start(&mut self) -> Result<(), String> {
+        if self.is_recording() {
+            return Ok(());
+        }
+        std::fs::create_dir_all(&self.directory)
+            .map_err(|e| format!("Failed to create {}: {}", self.directory.display(), e))?;
+        self.session = Some(format!("oc-audio-{}", timestamp()));
+        Ok(())
+    }
+
+    /// End the session, returning the files written
+    pub fn stop(&mut self) -> Vec<PathBuf> {
+        self.session = None;
+        let mut files = Vec::new();
+        let mut ports: Vec<_> = self.ports.drain().collect();
+        ports.sort_by_key(|(port, _)| *port);
+        for file in self.mix.take().into_iter().chain(ports.into_iter().map(|(_, f)| f)) {
+            match file.writer.finish() {
+                Ok(()) => files.push(file.path),
+                Err(e) => tracing::error!("Failed to finish {}: {}", file.path.display(), e),
+            }
+        }
+        if !files.is_empty() {
+            tracing::info!("Audio recording stopped, {} file(s) written", files.len());
+        }
+        files
+    }
+
+    /// Files of the active session
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.mix
+            .iter()
+            .chain(self.ports.values())
+            .map(|f| f.path.clone())
+            .collect()
+    }
+
+    /// Record a block of the final mix
+    pub fn write_mix(&mut self, channels: usize, samples: &[Sample]) {
+        let Some(name) = self.file_name("mix") else {
+            return;
+        };
+        let (format, rate) = (self.format, self.sample_rate);
+        Self::write_to(&mut self.mix, name, format, rate, channels, samples);
+    }
+
+    /// Record a block submitted to a cellAudio port
+    pub fn write_port(&mut self, port: u32, channels: usize, samples: &[Sample]) {
+        if !self.record_ports {
+            return;
+        }
+        let Some(name) = self.file_name(&format!("port{}", port)) else {
+            return;
+        };
+        let (format, rate) = (self.format, self.sample_rate);
+        let mut file = self.ports.remove(&port);
+        Self::write_to(&mut file, name, format, rate, channels, samples);
+        if let Some(file) = file {
+            self.ports.insert(port, file);
+        }
+    }
+
+    /// Path for a stream of the active session
+    fn file_name(&self, stream: &str) -> Option<PathBuf> {
+        let session = self.session.as_ref()?;
+        let ext = match self.format {
+            AudioDumpFormat::Wav => "wav",
+            AudioDumpFormat::Flac => "flac",
+        };
+        Some(self.directory.join(format!("{}-{}.{}", session, stream, ext)))
+    }
+
+    /// Write to a dump file, opening it on first use
+    fn write_to(
+        slot: &mut Option<DumpFile>,
+        path: PathBuf,
+        format: AudioDumpFormat,
+        sample_rate: u32,
+        channels: usize,
+        samples: &[Sample],
+    ) {
+        if slot.is_none() {
+            match DumpFile::create(path, format, sample_rate, channels) {
+                Ok(file) => *slot = Some(file),
+                Err(e) => {
+                    tracing::error!("Failed to create audio dump: {}", e);
+                    return;
+                }
+            }
+        }
+        let Some(file) = slot.as_mut() else {
+            return;
+        };
+        if file.channels != channels {
+            tracing::warn!(
+                "Channel count changed from {} to {} while recording {}, dropping audio",
+                file.channels,
+                channels,
+                file.path.display()
+            );
+            return;
+        }
+        if let Err(e) = file.writer.write(samples) {
+            tracing::error!("Audio dump write failed, closing {}: {}", file.path.display(), e);
+            *slot = None;
+        }
+    }
+}
+
+impl Drop for AudioRecorder {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Current UTC time as `YYYYMMDD-HHMMSS`
+fn timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, rem) = ((secs / 86400) as i64, secs % 86400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_wav_header() {
+        let mut writer = WavWriter::new(Cursor::new(Vec::new()), 48000, 2).unwrap();
+        writer.write_samples(&[0.5, -0.5, 1.0, -1.0]).unwrap();
+        assert_eq!(writer.frames_written(), 2);
+        let data = writer.finish().unwrap().into_inner();
+
+        assert_eq!(data.len(), 44 + 8);
+        assert_eq!(&data[..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(data[4..8].try_into().unwrap()), 36 + 8);
+        assert_eq!(u32::from_le_bytes(data[40..44].try_into().unwrap()), 8);
+        assert_eq!(i16::from_le_bytes([data[48], data[49]]), i16::MAX);
+    }
+
+    #[test]
+    fn test_recorder_session() {
+        let dir = std::env::temp_dir().join(format!("oc-audio-recorder-{}", std::process::id()));
+        let mut recorder = AudioRecorder::new(&dir, AudioDumpFormat::Wav);
+        recorder.set_record_ports(true);
+
+        // Nothing is written outside a session
+        recorder.write_mix(2, &[0.0; 4]);
+        assert!(recorder.files().is_empty());
+
+        recorder.start().unwrap();
+        recorder.write_mix(2, &[0.25; 8]);
+        recorder.write_port(3, 8, &[0.1; 16]);
+        assert_eq!(recorder.files().len(), 2);
+
+        let files = recorder.stop();
+        assert_eq!(files.len(), 2);
+        assert!(files[0].to_string_lossy().ends_with("-mix.wav"));
+        assert!(files[1].to_string_lossy().ends_with("-port3.wav"));
+        assert_eq!(std::fs::metadata(&files[0]).unwrap().len(), 44 + 16);
+        assert!(!recorder.is_recording());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_timestamp_format() {
+        let ts = timestamp();
+        assert_eq!(ts.len(), 15);
+        assert_eq!(&ts[8..9], "-");
+        assert!(ts.starts_with("20"));
+    }
+}
@@ -29,6 +29,8 @@ pub struct AudioResampler {
     input_buffer: Vec<f32>,
     /// Current position in resampling
     position: f64,
+    /// Playback rate multiplier applied on top of the rate conversion
+    rate_adjust: f64,
 }
 
 impl AudioResampler {
@@ -51,12 +53,28 @@ impl AudioResampler {
             num_channels,
             input_buffer: Vec::new(),
             position: 0.0,
+            rate_adjust: 1.0,
         }
     }
 
-    /// Get resampling ratio
+    /// Get resampling ratio (input frames consumed per output frame)
     pub fn ratio(&self) -> f64 {
-        self.input_rate as f64 / self.output_rate as f64
+        self.input_rate as f64 / self.output_rate as f64 * self.rate_adjust
+    }
+
+    /// Set the playback rate multiplier
+    ///
+    /// Values above 1.0 consume input faster (higher pitch), values below
+    /// 1.0 slower. Used by dynamic rate control to track buffer fill.
+    pub fn set_rate_adjust(&mut self, adjust: f64) {
+        if adjust > 0.0 {
+            self.rate_adjust = adjust;
+        }
+    }
+
+    /// Get the playback rate multiplier
+    pub fn rate_adjust(&self) -> f64 {
+        self.rate_adjust
     }
 
     /// Resample audio data
@@ -71,19 +89,10 @@ impl AudioResampler {
         let ratio = self.ratio();
         let input_frames = self.input_buffer.len() / self.num_channels;
 
-        // Calculate how many output frames we can generate
-        let mut output_frames = 0;
-        while self.position + ratio < input_frames as f64 {
-            output_frames += 1;
-            self.position += ratio;
-        }
-
-        // Reserve space for output
-        output.reserve(output_frames * self.num_channels);
-
-        // Reset position for resampling
-        let mut pos = 0.0;
-        for _ in 0..output_frames {
+        // Continue from where the previous call left off
+        let mut pos = self.position;
+        output.reserve(((input_frames as f64 - pos) / ratio).max(0.0) as usize * self.num_channels);
+        while pos + ratio < input_frames as f64 {
             match self.quality {
                 ResamplerQuality::Low => {
                     self.resample_linear(pos, output);
@@ -99,12 +108,9 @@ impl AudioResampler {
         }
 
         // Remove consumed samples from buffer
-        let consumed_frames = pos.floor() as usize;
-        let consumed_samples = consumed_frames * self.num_channels;
-        if consumed_samples > 0 && consumed_samples <= self.input_buffer.len() {
-            self.input_buffer.drain(..consumed_samples);
-            self.position = pos - consumed_frames as f64;
-        }
+        let consumed_frames = (pos.floor() as usize).min(input_frames);
+        self.input_buffer.drain(..consumed_frames * self.num_channels);
+        self.position = pos - consumed_frames as f64;
 
         Ok(())
     }
@@ -303,4 +309,21 @@ mod tests {
 
         assert!(resampler.resample(&input, &mut output).is_err());
     }
+
+    #[test]
+    fn test_resampler_rate_adjust() {
+        let mut resampler = AudioResampler::with_quality(48000, 48000, 1, ResamplerQuality::Low);
+        resampler.set_rate_adjust(1.25);
+        assert!((resampler.ratio() - 1.25).abs() < 1e-9);
+
+        // Output length follows the adjusted rate across calls
+        let input = vec![0.25; 1000];
+        let mut output = Vec::new();
+        for _ in 0..10 {
+            resampler.resample(&input, &mut output).unwrap();
+        }
+        let expected = 10_000.0 / 1.25;
+        assert!((output.len() as f64 - expected).abs() <= 2.0);
+        assert!(output.iter().all(|&s| (s - 0.25).abs() < 1e-6));
+    }
 }
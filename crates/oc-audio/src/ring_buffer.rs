@@ -0,0 +1,131 @@
+//! Audio output ring buffer
+//!
+//! Sits between the mixer (producer, emulation thread) and the audio
+//! backend callback (consumer, device thread).
+
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Interleaved sample ring buffer shared between mixer and backend
+pub struct AudioRingBuffer {
+    /// Buffered samples
+    samples: Mutex<VecDeque<f32>>,
+    /// Capacity in frames
+    capacity: usize,
+    /// Number of channels
+    num_channels: usize,
+    /// Times the consumer ran out of samples
+    underruns: AtomicU64,
+    /// Times the producer had samples dropped
+    overruns: AtomicU64,
+}
+
+impl AudioRingBuffer {
+    /// Create a ring buffer holding `capacity` frames of `num_channels`
+    pub fn new(capacity: usize, num_channels: usize) -> Self {
+        let num_channels = num_channels.max(1);
+        Self {
+            samples: Mutex::new(VecDeque::with_capacity(capacity * num_channels)),
+            capacity: capacity.max(1),
+            num_channels,
+            underruns: AtomicU64::new(0),
+            overruns: AtomicU64::new(0),
+        }
+    }
+
+    /// Push interleaved samples, returning the number accepted
+    ///
+    /// Samples that do not fit are dropped.
+    pub fn push(&self, input: &[f32]) -> usize {
+        let mut samples = self.samples.lock();
+        let space = self.capacity * self.num_channels - samples.len();
+        // Only accept whole frames
+        let accepted = input.len().min(space) / self.num_channels * self.num_channels;
+        samples.extend(&input[..accepted]);
+        if accepted < input.len() {
+            self.overruns.fetch_add(1, Ordering::Relaxed);
+        }
+        accepted
+    }
+
+    /// Pop interleaved samples into `output`, padding with silence
+    ///
+    /// Returns the number of real samples written.
+    pub fn pop(&self, output: &mut [f32]) -> usize {
+        let mut samples = self.samples.lock();
+        let available = samples.len().min(output.len());
+        for (out, sample) in output.iter_mut().zip(samples.drain(..available)) {
+            *out = sample;
+        }
+        if available < output.len() {
+            output[available..].fill(0.0);
+            self.underruns.fetch_add(1, Ordering::Relaxed);
+        }
+        available
+    }
+
+    /// Buffered frames
+    pub fn len(&self) -> usize {
+        self.samples.lock().len() / self.num_channels
+    }
+
+    /// Whether the buffer is empty
+    pub fn is_empty(&self) -> bool {
+        self.samples.lock().is_empty()
+    }
+
+    /// Capacity in frames
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of channels
+    pub fn num_channels(&self) -> usize {
+        self.num_channels
+    }
+
+    /// Fill level (0.0 - 1.0)
+    pub fn fill_level(&self) -> f32 {
+        self.len() as f32 / self.capacity as f32
+    }
+
+    /// Times the consumer ran out of samples
+    pub fn underruns(&self) -> u64 {
+        self.underruns.load(Ordering::Relaxed)
+    }
+
+    /// Times the producer had samples dropped
+    pub fn overruns(&self) -> u64 {
+        self.overruns.load(Ordering::Relaxed)
+    }
+
+    /// Discard all buffered samples
+    pub fn clear(&self) {
+        self.samples.lock().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_buffer_push_pop() {
+        let ring = AudioRingBuffer::new(4, 2);
+        assert_eq!(ring.push(&[0.1, 0.2, 0.3, 0.4]), 4);
+        assert_eq!(ring.len(), 2);
+        assert!((ring.fill_level() - 0.5).abs() < 1e-6);
+
+        // Overflow drops whole frames
+        assert_eq!(ring.push(&[1.0; 6]), 4);
+        assert_eq!(ring.overruns(), 1);
+
+        let mut output = [9.0; 10];
+        assert_eq!(ring.pop(&mut output), 8);
+        assert_eq!(&output[..2], &[0.1, 0.2]);
+        assert_eq!(&output[8..], &[0.0, 0.0]);
+        assert_eq!(ring.underruns(), 1);
+        assert!(ring.is_empty());
+    }
+}
@@ -216,6 +216,88 @@ impl Default for AudioTimeStretcher {
     }
 }
 
+/// Dynamic rate control configuration
+#[derive(Debug, Clone, Copy)]
+pub struct DynamicRateConfig {
+    /// Output buffer fill level to steer towards (0.0 - 1.0)
+    pub target_fill: f32,
+    /// Maximum playback rate deviation (0.005 = ±0.5% pitch)
+    pub pitch_tolerance: f32,
+    /// How quickly the rate follows the fill level (0.0 - 1.0)
+    pub smoothing: f32,
+}
+
+impl Default for DynamicRateConfig {
+    fn default() -> Self {
+        Self {
+            target_fill: 0.5,
+            pitch_tolerance: 0.005,
+            smoothing: 0.05,
+        }
+    }
+}
+
+/// Adapts playback rate to the fill level of the output buffer
+///
+/// When emulation runs slow the buffer drains and playback is slowed
+/// slightly; when it runs fast the buffer fills and playback speeds up.
+/// The deviation never exceeds the pitch tolerance.
+pub struct DynamicRateController {
+    config: DynamicRateConfig,
+    rate: f64,
+}
+
+impl DynamicRateController {
+    /// Create a new controller
+    pub fn new(config: DynamicRateConfig) -> Self {
+        Self { config, rate: 1.0 }
+    }
+
+    /// Update with the current fill level and return the new rate
+    ///
+    /// The rate is the number of input frames consumed per output frame.
+    pub fn update(&mut self, fill: f32) -> f64 {
+        let tolerance = self.config.pitch_tolerance.clamp(0.0, 0.1) as f64;
+        let target = self.config.target_fill.clamp(0.01, 0.99);
+        let error = fill.clamp(0.0, 1.0) - target;
+
+        // Full deviation when the buffer is empty or full
+        let span = if error >= 0.0 { 1.0 - target } else { target };
+        let wanted = 1.0 + (error / span) as f64 * tolerance;
+
+        let smoothing = self.config.smoothing.clamp(0.0, 1.0) as f64;
+        self.rate += (wanted - self.rate) * smoothing;
+        self.rate = self.rate.clamp(1.0 - tolerance, 1.0 + tolerance);
+        self.rate
+    }
+
+    /// Current rate
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Get configuration
+    pub fn config(&self) -> &DynamicRateConfig {
+        &self.config
+    }
+
+    /// Set the maximum pitch deviation
+    pub fn set_pitch_tolerance(&mut self, tolerance: f32) {
+        self.config.pitch_tolerance = tolerance.clamp(0.0, 0.1);
+    }
+
+    /// Reset to normal speed
+    pub fn reset(&mut self) {
+        self.rate = 1.0;
+    }
+}
+
+impl Default for DynamicRateController {
+    fn default() -> Self {
+        Self::new(DynamicRateConfig::default())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -303,4 +385,22 @@ mod tests {
 
         assert!(stretcher.stretch(&input, 1.0, &mut output).is_err());
     }
+
+    #[test]
+    fn test_dynamic_rate_controller() {
+        let mut controller = DynamicRateController::new(DynamicRateConfig {
+            target_fill: 0.5,
+            pitch_tolerance: 0.01,
+            smoothing: 1.0,
+        });
+
+        assert!((controller.update(0.5) - 1.0).abs() < 1e-9);
+        // Full buffer plays faster, empty buffer slower, both bounded
+        assert!((controller.update(1.0) - 1.01).abs() < 1e-6);
+        assert!((controller.update(0.0) - 0.99).abs() < 1e-6);
+        assert!((controller.update(0.75) - 1.005).abs() < 1e-6);
+
+        controller.set_pitch_tolerance(0.0);
+        assert_eq!(controller.update(1.0), 1.0);
+    }
 }
@@ -0,0 +1,176 @@
+//! Command line arguments
+
+use oc_core::config::LogLevel;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Usage text printed by `--help`
+pub const USAGE: &str = "\
+Usage: oxidized-cell-cli [OPTIONS] <GAME>
+
+Boot a game folder, EBOOT.BIN, SELF or ELF without the user interface.
+
+Options:
+  -c, --config <FILE>         Settings file to use instead of the user's config.toml
+      --title-id <ID>         Title ID of the game (read from PARAM.SFO by default)
+  -f, --frames <N>            Stop after N frames
+  -t, --timeout <SECONDS>     Stop after SECONDS of wall-clock time
+      --replay <FILE>         Feed the input recorded in FILE back to the game
+      --record <FILE>         Record the run's input to FILE
+  -s, --screenshot-at <N>     Save a screenshot after frame N (repeatable)
+      --screenshot-dir <DIR>  Folder screenshots are saved to (default: current folder)
+      --log-level <LEVEL>     off, error, warn, info, debug or trace
+  -h, --help                  Print this help
+  -V, --version               Print the version
+
+Exit codes:
+  0  the run reached its frame or time limit, or the game exited with code 0
+  1  the game exited with a non-zero code
+  2  the arguments were invalid
+  3  the game failed to boot
+  4  the emulator crashed
+  5  the replay diverged from its recording
+  6  a screenshot could not be saved";
+
+/// What the command line asks for
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Run(Box<Args>),
+    Help,
+    Version,
+}
+
+/// Options of a headless run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Args {
+    pub game: PathBuf,
+    pub config: Option<PathBuf>,
+    pub title_id: Option<String>,
+    pub frames: Option<u64>,
+    pub timeout: Option<Duration>,
+    pub replay: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+    /// Frames to save a screenshot after, in order
+    pub screenshots: Vec<u64>,
+    pub screenshot_dir: PathBuf,
+    pub log_level: Option<LogLevel>,
+}
+
+impl Command {
+    /// Parse the arguments after the program name
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args {
+            screenshot_dir: PathBuf::from("."),
+            ..Args::default()
+        };
+        let mut game = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // Both "--frames 10" and "--frames=10"
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+                _ => (arg.clone(), None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("{} needs a value", flag))
+            };
+            match flag.as_str() {
+                "-h" | "--help" => return Ok(Command::Help),
+                "-V" | "--version" => return Ok(Command::Version),
+                "-c" | "--config" => parsed.config = Some(PathBuf::from(value()?)),
+                "--title-id" => parsed.title_id = Some(value()?),
+                "-f" | "--frames" => parsed.frames = Some(parse_number(&flag, &value()?)?),
+                "-t" | "--timeout" => {
+                    let value = value()?;
+                    let seconds = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|seconds| seconds.is_finite() && *seconds > 0.0)
+                        .ok_or_else(|| format!("{} takes a positive number of seconds, not {}", flag, value))?;
+                    parsed.timeout = Some(Duration::from_secs_f64(seconds));
+                }
+                "--replay" => parsed.replay = Some(PathBuf::from(value()?)),
+                "--record" => parsed.record = Some(PathBuf::from(value()?)),
+                "-s" | "--screenshot-at" => parsed.screenshots.push(parse_number(&flag, &value()?)?),
+                "--screenshot-dir" => parsed.screenshot_dir = PathBuf::from(value()?),
+                "--log-level" => parsed.log_level = Some(parse_log_level(&value()?)?),
+                _ if flag.starts_with('-') && flag.len() > 1 => return Err(format!("Unknown option {}", flag)),
+                _ if game.is_some() => return Err(format!("Unexpected argument {}", arg)),
+                _ => game = Some(PathBuf::from(arg)),
+            }
+        }
+
+        parsed.game = game.ok_or("No game was given")?;
+        if parsed.replay.is_some() && parsed.record.is_some() {
+            return Err("--replay and --record can't be used together".to_string());
+        }
+        parsed.screenshots.sort_unstable();
+        parsed.screenshots.dedup();
+        Ok(Command::Run(Box::new(parsed)))
+    }
+}
+
+/// Parse a frame number, which starts at 1
+fn parse_number(flag: &str, value: &str) -> Result<u64, String> {
+    value
+        .parse::<u64>()
+        .ok()
+        .filter(|&n| n > 0)
+        .ok_or_else(|| format!("{} takes a frame number from 1, not {}", flag, value))
+}
+
+fn parse_log_level(value: &str) -> Result<LogLevel, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "off" => Ok(LogLevel::Off),
+        "error" => Ok(LogLevel::Error),
+        "warn" => Ok(LogLevel::Warn),
+        "info" => Ok(LogLevel::Info),
+        "debug" => Ok(LogLevel::Debug),
+        "trace" => Ok(LogLevel::Trace),
+        _ => Err(format!("Unknown log level {}", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        Command::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_run() {
+        let command = parse(&[
+            "-f", "600", "--timeout=2.5", "--screenshot-at", "300", "-s", "60", "-s", "300",
+            "--log-level", "WARN", "game/PS3_GAME/USRDIR/EBOOT.BIN",
+        ])
+        .unwrap();
+        let Command::Run(args) = command else {
+            panic!("expected a run");
+        };
+        assert_eq!(args.game, PathBuf::from("game/PS3_GAME/USRDIR/EBOOT.BIN"));
+        assert_eq!(args.frames, Some(600));
+        assert_eq!(args.timeout, Some(Duration::from_millis(2500)));
+        assert_eq!(args.screenshots, vec![60, 300]);
+        assert_eq!(args.screenshot_dir, PathBuf::from("."));
+        assert_eq!(args.log_level, Some(LogLevel::Warn));
+
+        assert_eq!(parse(&["--help", "game"]), Ok(Command::Help));
+        assert_eq!(parse(&["-V"]), Ok(Command::Version));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["a.elf", "b.elf"]).is_err());
+        assert!(parse(&["--frames"]).is_err());
+        assert!(parse(&["--frames", "0", "a.elf"]).is_err());
+        assert!(parse(&["--timeout", "-1", "a.elf"]).is_err());
+        assert!(parse(&["--bogus", "a.elf"]).is_err());
+        assert!(parse(&["--replay", "r.txt", "--record", "r.txt", "a.elf"]).is_err());
+    }
+}
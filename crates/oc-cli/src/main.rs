@@ -0,0 +1,239 @@
+//! Oxidized-Cell headless runner
+//!
+//! Boots a game without the user interface, runs it for a number of frames
+//! or seconds and reports how the run ended through the exit code, for CI
+//! regression testing and servers.
+
+mod args;
+
+use args::{Args, Command, USAGE};
+use oc_core::config::{Config, ReplayMode};
+use oc_debug::CrashKind;
+use oc_integration::{capture, Emulator};
+use oc_vfs::Sfo;
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Instant;
+
+/// How a run ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Outcome {
+    FrameLimit,
+    TimedOut,
+    Exited(i32),
+    BootFailed(String),
+    Crashed(String),
+    Diverged(String),
+    ScreenshotFailed(String),
+}
+
+impl Outcome {
+    /// Process exit code documented in the usage text
+    fn code(&self) -> u8 {
+        match self {
+            Outcome::FrameLimit | Outcome::TimedOut | Outcome::Exited(0) => 0,
+            Outcome::Exited(_) => 1,
+            Outcome::BootFailed(_) => 3,
+            Outcome::Crashed(_) => 4,
+            Outcome::Diverged(_) => 5,
+            Outcome::ScreenshotFailed(_) => 6,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Outcome::FrameLimit => "reached the frame limit".to_string(),
+            Outcome::TimedOut => "reached the time limit".to_string(),
+            Outcome::Exited(code) => format!("game exited with code {}", code),
+            Outcome::BootFailed(e) => format!("boot failed: {}", e),
+            Outcome::Crashed(e) => format!("crashed: {}", e),
+            Outcome::Diverged(e) => format!("replay diverged: {}", e),
+            Outcome::ScreenshotFailed(e) => format!("screenshot failed: {}", e),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match Command::parse(std::env::args().skip(1)) {
+        Ok(Command::Run(args)) => args,
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Version) => {
+            println!("oxidized-cell-cli {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let config = match load_config(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    oc_core::logging::init(&config);
+    oc_debug::crash_dump::install_panic_hook();
+
+    let outcome = run(&args, config);
+    let code = outcome.code();
+    println!("result: {} (exit code {})", outcome.describe(), code);
+    ExitCode::from(code)
+}
+
+/// The settings for the run: the config file with the command line on top
+fn load_config(args: &Args) -> Result<Config, String> {
+    let mut config = match &args.config {
+        Some(path) => Config::load_from(path).map_err(|e| format!("Failed to load {}: {}", path.display(), e))?,
+        None => Config::load().unwrap_or_default(),
+    };
+    if let Some(level) = args.log_level {
+        config.debug.log_level = level;
+    }
+    // Only the command line starts a recording or replay
+    match (&args.replay, &args.record) {
+        (Some(path), _) => {
+            config.debug.replay_mode = ReplayMode::Replay;
+            config.debug.replay_path = path.clone();
+        }
+        (None, Some(path)) => {
+            config.debug.replay_mode = ReplayMode::Record;
+            config.debug.replay_path = path.clone();
+        }
+        (None, None) => config.debug.replay_mode = ReplayMode::Off,
+    }
+    Ok(config)
+}
+
+/// Title ID from the PARAM.SFO of the game folder `path` is in
+fn find_title_id(path: &Path) -> Option<String> {
+    path.ancestors()
+        .take(4)
+        .flat_map(|dir| [dir.join("PARAM.SFO"), dir.join("PS3_GAME").join("PARAM.SFO")])
+        .find(|sfo| sfo.is_file())
+        .and_then(|sfo| Sfo::load(&sfo).ok())
+        .and_then(|sfo| sfo.title_id().map(str::to_string))
+}
+
+/// Boot the game and run it until a limit is reached or it ends
+fn run(args: &Args, config: Config) -> Outcome {
+    // Per-game settings apply as they do in the user interface
+    let title_id = args.title_id.clone().or_else(|| find_title_id(&args.game));
+    let config = match title_id.as_deref() {
+        Some(title_id) if args.config.is_none() => config.for_title(title_id).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring settings of {}: {}", title_id, e);
+            config.clone()
+        }),
+        _ => config,
+    };
+
+    let mut emulator = Emulator::new(config);
+    if let Err(e) = emulator.boot(&args.game, title_id.as_deref()) {
+        return Outcome::BootFailed(e.to_string());
+    }
+    let Some(runner) = emulator.runner().cloned() else {
+        return Outcome::BootFailed("the emulator has no runner".to_string());
+    };
+
+    let started = Instant::now();
+    let mut screenshots = args.screenshots.iter().copied().peekable();
+    let outcome = loop {
+        if args.timeout.is_some_and(|timeout| started.elapsed() >= timeout) {
+            break Outcome::TimedOut;
+        }
+
+        runner.borrow_mut().poll_debugger();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| runner.borrow_mut().run_frame()));
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::error!("Emulator frame error: {}", e),
+            Err(_) => {
+                let message = oc_debug::crash_dump::take_panic().unwrap_or_else(|| String::from("unknown panic"));
+                let report = runner.borrow().write_crash_dump(CrashKind::HostPanic, &message, None);
+                break Outcome::Crashed(report.summary);
+            }
+        }
+        if let Some(report) = runner.borrow().take_crash() {
+            break Outcome::Crashed(report.summary);
+        }
+
+        let frame = runner.borrow().frame_count();
+        if screenshots.next_if(|&at| at <= frame).is_some() {
+            let path = args.screenshot_dir.join(format!("frame_{:06}.png", frame));
+            let saved = runner
+                .read()
+                .get_framebuffer()
+                .ok_or_else(|| "There is no frame to capture".to_string())
+                .and_then(|image| capture::save_screenshot(&image, &path));
+            match saved {
+                Ok(()) => println!("screenshot: {}", path.display()),
+                Err(e) => break Outcome::ScreenshotFailed(e),
+            }
+        }
+        if let Some(divergence) = runner.borrow().replay_divergence() {
+            break Outcome::Diverged(divergence);
+        }
+        if let Some(code) = runner.borrow().exit_code() {
+            break Outcome::Exited(code);
+        }
+        if args.frames.is_some_and(|frames| frame >= frames) {
+            break Outcome::FrameLimit;
+        }
+    };
+
+    {
+        let runner = runner.borrow();
+        println!(
+            "frames: {} in {:.2}s ({:.1} fps)",
+            runner.frame_count(),
+            started.elapsed().as_secs_f64(),
+            runner.fps()
+        );
+        match runner.finish_replay() {
+            Ok(Some(status)) => println!("replay: {}", status),
+            Ok(None) => {}
+            Err(e) => eprintln!("error: {}", e),
+        }
+    }
+    drop(runner);
+    if let Err(e) = emulator.stop() {
+        eprintln!("error: Failed to shut down: {}", e);
+    }
+    if let Some(report) = emulator.take_missing_features().filter(|report| !report.is_empty()) {
+        println!("missing features: {}", report.summary());
+        if let Some(path) = &report.path {
+            println!("missing feature report: {}", path.display());
+        }
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outcome_codes() {
+        assert_eq!(Outcome::FrameLimit.code(), 0);
+        assert_eq!(Outcome::Exited(0).code(), 0);
+        assert_eq!(Outcome::Exited(-1).code(), 1);
+        assert_eq!(Outcome::Crashed(String::new()).code(), 4);
+    }
+
+    #[test]
+    fn test_boot_failure() {
+        let args = Args {
+            game: "/nonexistent/EBOOT.BIN".into(),
+            frames: Some(1),
+            ..Args::default()
+        };
+        let outcome = run(&args, Config::default());
+        assert!(matches!(outcome, Outcome::BootFailed(_)));
+        assert_eq!(outcome.code(), 3);
+    }
+}
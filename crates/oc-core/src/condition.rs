@@ -0,0 +1,440 @@
+//! Breakpoint condition expressions
+//!
+//! A small C-like expression language shared by PPU and SPU breakpoints,
+//! e.g. `r3 == 0x10 && mem32(r1 + 0x70) != 0` or `hits % 100 == 0`.
+//! Values are unsigned 64-bit and arithmetic wraps; comparisons and logical
+//! operators give 1 or 0, and a condition is met when it isn't zero.
+//!
+//! Names are resolved by a [`ConditionContext`] when the condition is
+//! evaluated, except `hits`: how often the breakpoint has been reached,
+//! counting this time. `mem8`, `mem16`, `mem32` and `mem64` read big-endian
+//! values from memory.
+
+use crate::error::ConditionError;
+use std::fmt;
+
+/// Registers and memory of the thread a condition is evaluated against
+pub trait ConditionContext {
+    /// Value of a register or other named variable
+    fn variable(&self, name: &str) -> Option<u64>;
+
+    /// Read a big-endian value of `size` bytes (1, 2, 4 or 8)
+    fn read_memory(&self, addr: u64, size: usize) -> Option<u64>;
+}
+
+/// Index of a numbered register such as `r3`, if below `count`
+pub fn register_index(name: &str, prefix: &str, count: usize) -> Option<usize> {
+    let digits = name.strip_prefix(prefix)?;
+    // No leading zeros or signs, so each register has one spelling
+    if digits.is_empty() || (digits.len() > 1 && digits.starts_with('0')) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok().filter(|&index| index < count)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnaryOp {
+    Neg,
+    Not,
+    BitNot,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Or,
+    And,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Shl,
+    Shr,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinaryOp {
+    /// Binding strength, higher binds tighter
+    fn precedence(self) -> u8 {
+        match self {
+            Self::Or => 1,
+            Self::And => 2,
+            Self::BitOr => 3,
+            Self::BitXor => 4,
+            Self::BitAnd => 5,
+            Self::Eq | Self::Ne => 6,
+            Self::Lt | Self::Le | Self::Gt | Self::Ge => 7,
+            Self::Shl | Self::Shr => 8,
+            Self::Add | Self::Sub => 9,
+            Self::Mul | Self::Div | Self::Rem => 10,
+        }
+    }
+
+    fn apply(self, a: u64, b: u64) -> Result<u64, ConditionError> {
+        Ok(match self {
+            // Short-circuited by the caller
+            Self::Or | Self::And => unreachable!(),
+            Self::BitOr => a | b,
+            Self::BitXor => a ^ b,
+            Self::BitAnd => a & b,
+            Self::Eq => (a == b) as u64,
+            Self::Ne => (a != b) as u64,
+            Self::Lt => (a < b) as u64,
+            Self::Le => (a <= b) as u64,
+            Self::Gt => (a > b) as u64,
+            Self::Ge => (a >= b) as u64,
+            Self::Shl => if b < 64 { a << b } else { 0 },
+            Self::Shr => if b < 64 { a >> b } else { 0 },
+            Self::Add => a.wrapping_add(b),
+            Self::Sub => a.wrapping_sub(b),
+            Self::Mul => a.wrapping_mul(b),
+            Self::Div => a.checked_div(b).ok_or(ConditionError::DivisionByZero)?,
+            Self::Rem => a.checked_rem(b).ok_or(ConditionError::DivisionByZero)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Number(u64),
+    Variable(String),
+    /// Memory read of the given size in bytes
+    Memory(usize, Box<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn evaluate(&self, ctx: &dyn ConditionContext, hits: u64) -> Result<u64, ConditionError> {
+        match self {
+            Self::Number(value) => Ok(*value),
+            Self::Variable(name) if name == "hits" => Ok(hits),
+            Self::Variable(name) => {
+                ctx.variable(name).ok_or_else(|| ConditionError::UnknownVariable(name.clone()))
+            }
+            Self::Memory(size, addr) => {
+                let addr = addr.evaluate(ctx, hits)?;
+                ctx.read_memory(addr, *size).ok_or(ConditionError::BadRead { addr, size: *size })
+            }
+            Self::Unary(op, operand) => {
+                let value = operand.evaluate(ctx, hits)?;
+                Ok(match op {
+                    UnaryOp::Neg => value.wrapping_neg(),
+                    UnaryOp::Not => (value == 0) as u64,
+                    UnaryOp::BitNot => !value,
+                })
+            }
+            Self::Binary(BinaryOp::Or, a, b) => {
+                Ok((a.evaluate(ctx, hits)? != 0 || b.evaluate(ctx, hits)? != 0) as u64)
+            }
+            Self::Binary(BinaryOp::And, a, b) => {
+                Ok((a.evaluate(ctx, hits)? != 0 && b.evaluate(ctx, hits)? != 0) as u64)
+            }
+            Self::Binary(op, a, b) => op.apply(a.evaluate(ctx, hits)?, b.evaluate(ctx, hits)?),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(u64),
+    Ident(String),
+    Binary(BinaryOp),
+    /// `!` or `~`; `-` is lexed as [`BinaryOp::Sub`]
+    Unary(UnaryOp),
+    LParen,
+    RParen,
+}
+
+/// Split the source into tokens with their byte offsets
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ConditionError> {
+    const OPERATORS: [(&str, Token); 20] = [
+        ("||", Token::Binary(BinaryOp::Or)),
+        ("&&", Token::Binary(BinaryOp::And)),
+        ("==", Token::Binary(BinaryOp::Eq)),
+        ("!=", Token::Binary(BinaryOp::Ne)),
+        ("<=", Token::Binary(BinaryOp::Le)),
+        (">=", Token::Binary(BinaryOp::Ge)),
+        ("<<", Token::Binary(BinaryOp::Shl)),
+        (">>", Token::Binary(BinaryOp::Shr)),
+        ("|", Token::Binary(BinaryOp::BitOr)),
+        ("^", Token::Binary(BinaryOp::BitXor)),
+        ("&", Token::Binary(BinaryOp::BitAnd)),
+        ("<", Token::Binary(BinaryOp::Lt)),
+        (">", Token::Binary(BinaryOp::Gt)),
+        ("+", Token::Binary(BinaryOp::Add)),
+        ("-", Token::Binary(BinaryOp::Sub)),
+        ("*", Token::Binary(BinaryOp::Mul)),
+        ("/", Token::Binary(BinaryOp::Div)),
+        ("%", Token::Binary(BinaryOp::Rem)),
+        ("!", Token::Unary(UnaryOp::Not)),
+        ("~", Token::Unary(UnaryOp::BitNot)),
+    ];
+
+    let mut tokens = Vec::new();
+    let mut offset = 0;
+    while let Some(c) = source[offset..].chars().next() {
+        let rest = &source[offset..];
+        let word_len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let syntax_error = || ConditionError::Syntax {
+            offset,
+            found: format!("'{}'", &rest[..word_len.max(c.len_utf8())]),
+        };
+
+        let (token, len) = if c.is_whitespace() {
+            offset += c.len_utf8();
+            continue;
+        } else if c.is_ascii_digit() {
+            let word = &rest[..word_len];
+            let value = match word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")) {
+                Some(hex) => u64::from_str_radix(hex, 16),
+                None => word.parse(),
+            };
+            (Token::Number(value.map_err(|_| syntax_error())?), word_len)
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            (Token::Ident(rest[..word_len].to_ascii_lowercase()), word_len)
+        } else if c == '(' {
+            (Token::LParen, 1)
+        } else if c == ')' {
+            (Token::RParen, 1)
+        } else {
+            let (text, token) = OPERATORS
+                .iter()
+                .find(|(text, _)| rest.starts_with(text))
+                .ok_or_else(syntax_error)?;
+            (token.clone(), text.len())
+        };
+        tokens.push((offset, token));
+        offset += len;
+    }
+    Ok(tokens)
+}
+
+/// Precedence-climbing parser over the token list
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(_, token)| token.clone());
+        self.pos += 1;
+        token
+    }
+
+    /// Error for the token at the current position
+    fn unexpected(&self) -> ConditionError {
+        match self.tokens.get(self.pos) {
+            Some((offset, token)) => ConditionError::Syntax {
+                offset: *offset,
+                found: match token {
+                    Token::Number(value) => format!("number {}", value),
+                    Token::Ident(name) => format!("'{}'", name),
+                    _ => "operator".to_string(),
+                },
+            },
+            None => ConditionError::Syntax { offset: self.end, found: "end of expression".to_string() },
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ConditionError> {
+        if self.peek() != Some(&expected) {
+            return Err(self.unexpected());
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn expression(&mut self, min_precedence: u8) -> Result<Expr, ConditionError> {
+        let mut lhs = self.unary()?;
+        while let Some(&Token::Binary(op)) = self.peek() {
+            if op.precedence() < min_precedence {
+                break;
+            }
+            self.pos += 1;
+            // Left associative: the right side only takes tighter operators
+            let rhs = self.expression(op.precedence() + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ConditionError> {
+        let op = match self.peek() {
+            Some(Token::Binary(BinaryOp::Sub)) => UnaryOp::Neg,
+            Some(&Token::Unary(op)) => op,
+            _ => return self.primary(),
+        };
+        self.pos += 1;
+        Ok(Expr::Unary(op, Box::new(self.unary()?)))
+    }
+
+    fn primary(&mut self) -> Result<Expr, ConditionError> {
+        match self.peek().cloned() {
+            Some(Token::Number(value)) => {
+                self.pos += 1;
+                Ok(Expr::Number(value))
+            }
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let expr = self.expression(0)?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                if self.peek() != Some(&Token::LParen) {
+                    return Ok(Expr::Variable(name));
+                }
+                let size = match name.as_str() {
+                    "mem8" => 1,
+                    "mem16" => 2,
+                    "mem32" => 4,
+                    "mem64" => 8,
+                    _ => return Err(ConditionError::UnknownFunction(name)),
+                };
+                self.pos += 1;
+                let addr = self.expression(0)?;
+                self.expect(Token::RParen)?;
+                Ok(Expr::Memory(size, Box::new(addr)))
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+}
+
+/// A parsed breakpoint condition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    source: String,
+    expr: Expr,
+}
+
+impl Condition {
+    /// Parse a condition expression
+    pub fn parse(source: &str) -> Result<Self, ConditionError> {
+        let mut parser = Parser { tokens: tokenize(source)?, pos: 0, end: source.len() };
+        let expr = parser.expression(0)?;
+        if parser.next().is_some() {
+            parser.pos -= 1;
+            return Err(parser.unexpected());
+        }
+        Ok(Self { source: source.trim().to_string(), expr })
+    }
+
+    /// Expression text as entered
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Compute the value of the expression
+    pub fn evaluate(&self, ctx: &dyn ConditionContext, hits: u64) -> Result<u64, ConditionError> {
+        self.expr.evaluate(ctx, hits)
+    }
+
+    /// Whether the condition holds; evaluation errors count as not met
+    pub fn is_met(&self, ctx: &dyn ConditionContext, hits: u64) -> bool {
+        match self.evaluate(ctx, hits) {
+            Ok(value) => value != 0,
+            Err(e) => {
+                tracing::debug!("Condition \"{}\" failed: {}", self.source, e);
+                false
+            }
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestContext;
+
+    impl ConditionContext for TestContext {
+        fn variable(&self, name: &str) -> Option<u64> {
+            match name {
+                "r1" => Some(0x1000),
+                "r3" => Some(0x10),
+                _ => None,
+            }
+        }
+
+        fn read_memory(&self, addr: u64, size: usize) -> Option<u64> {
+            (addr == 0x1070).then_some(0xAABB_CCDD_EEFF_0011 >> (64 - size * 8))
+        }
+    }
+
+    fn eval(source: &str) -> Result<u64, ConditionError> {
+        Condition::parse(source)?.evaluate(&TestContext, 7)
+    }
+
+    #[test]
+    fn test_condition_evaluation() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(7));
+        assert_eq!(eval("(1 + 2) * 3"), Ok(9));
+        assert_eq!(eval("10 - 4 - 3"), Ok(3));
+        assert_eq!(eval("1 << 4 | 1"), Ok(17));
+        assert_eq!(eval("-1"), Ok(u64::MAX));
+        assert_eq!(eval("!0 + ~0xFFFFFFFFFFFFFFFE"), Ok(2));
+        assert_eq!(eval("R3 == 0x10 && mem32(r1 + 0x70) != 0"), Ok(1));
+        assert_eq!(eval("mem16(r1+0x70)"), Ok(0xAABB));
+        assert_eq!(eval("hits % 7 == 0"), Ok(1));
+        // The right side of a short-circuited operator isn't evaluated
+        assert_eq!(eval("r3 == 0 && mem8(0) == 0"), Ok(0));
+        assert_eq!(eval("r3 || 1 / 0"), Ok(1));
+
+        assert_eq!(eval("r4"), Err(ConditionError::UnknownVariable("r4".to_string())));
+        assert_eq!(eval("mem8(4)"), Err(ConditionError::BadRead { addr: 4, size: 1 }));
+        assert_eq!(eval("5 % (r3 - 16)"), Err(ConditionError::DivisionByZero));
+        assert!(!Condition::parse("mem64(8)").unwrap().is_met(&TestContext, 1));
+
+        assert_eq!(register_index("r31", "r", 32), Some(31));
+        assert_eq!(register_index("r32", "r", 32), None);
+        assert_eq!(register_index("r03", "r", 32), None);
+        assert_eq!(register_index("rx", "r", 32), None);
+    }
+
+    #[test]
+    fn test_condition_syntax_errors() {
+        assert_eq!(Condition::parse(" r3 == 1 ").unwrap().to_string(), "r3 == 1");
+        assert_eq!(
+            Condition::parse("r3 == "),
+            Err(ConditionError::Syntax { offset: 6, found: "end of expression".to_string() })
+        );
+        assert_eq!(
+            Condition::parse("r3 = 1"),
+            Err(ConditionError::Syntax { offset: 3, found: "'='".to_string() })
+        );
+        assert_eq!(
+            Condition::parse("(r3 r4"),
+            Err(ConditionError::Syntax { offset: 4, found: "'r4'".to_string() })
+        );
+        assert!(matches!(Condition::parse("0x1g"), Err(ConditionError::Syntax { offset: 0, .. })));
+        assert_eq!(
+            Condition::parse("peek(r1)"),
+            Err(ConditionError::UnknownFunction("peek".to_string()))
+        );
+    }
+}
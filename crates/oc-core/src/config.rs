@@ -1,7 +1,10 @@
 //! Configuration system for oxidized-cell emulator
 
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::sync::{Arc, Weak};
 
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +18,9 @@ pub struct Config {
     pub input: InputConfig,
     pub paths: PathConfig,
     pub debug: DebugConfig,
+    pub ui: UiConfig,
+    pub capture: CaptureConfig,
+    pub network: NetworkConfig,
 }
 
 /// General emulator settings
@@ -23,7 +29,96 @@ pub struct Config {
 pub struct GeneralConfig {
     pub start_paused: bool,
     pub confirm_exit: bool,
+    /// Keep a ring of background savestates to recover from crashes and lockups
     pub auto_save_state: bool,
+    /// Seconds between autosaves
+    pub autosave_interval: u32,
+    /// Autosaves kept per game, the oldest is replaced
+    pub autosave_count: u32,
+    /// Where the game compatibility database is refreshed from (empty to disable)
+    pub compat_db_url: String,
+    /// Load the newest savestate when continuing a game from the game list
+    pub resume_from_savestate: bool,
+    /// Key held to run in turbo
+    pub turbo_key: String,
+    /// Key toggling slow motion
+    pub slow_motion_key: String,
+    /// Key opening the quick menu over the game
+    pub quick_menu_key: String,
+    /// Report background downloads games start as finished instead of failed
+    pub complete_background_downloads: bool,
+    /// Free space reported to games on /dev_hdd0, in GB
+    pub hdd_free_space_gb: u32,
+    /// Copy the disc's data when a game installs to the HDD instead of only showing progress
+    pub copy_disc_game_data: bool,
+    /// Region of the emulated console, as its IDPS reports it
+    pub console_region: ConsoleRegion,
+    /// IDPS reported to games as 32 hex digits, empty for one matching `console_region`
+    pub console_id: String,
+}
+
+/// Console region, the target ID in the console's IDPS
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum ConsoleRegion {
+    Japan,
+    #[default]
+    Usa,
+    Europe,
+    Korea,
+    UnitedKingdom,
+    Mexico,
+    Australia,
+    SouthAsia,
+    Taiwan,
+    Russia,
+    China,
+    HongKong,
+}
+
+impl ConsoleRegion {
+    /// Every retail region, in target ID order
+    pub const ALL: [ConsoleRegion; 12] = [
+        Self::Japan,
+        Self::Usa,
+        Self::Europe,
+        Self::Korea,
+        Self::UnitedKingdom,
+        Self::Mexico,
+        Self::Australia,
+        Self::SouthAsia,
+        Self::Taiwan,
+        Self::Russia,
+        Self::China,
+        Self::HongKong,
+    ];
+
+    /// Target ID byte of the IDPS
+    pub fn target_id(&self) -> u8 {
+        0x83 + Self::ALL.iter().position(|region| region == self).unwrap_or_default() as u8
+    }
+
+    /// Region with the given IDPS target ID
+    pub fn from_target_id(target_id: u8) -> Option<Self> {
+        Self::ALL.get(target_id.checked_sub(0x83)? as usize).copied()
+    }
+
+    /// Display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Japan => "Japan",
+            Self::Usa => "USA",
+            Self::Europe => "Europe",
+            Self::Korea => "Korea",
+            Self::UnitedKingdom => "United Kingdom",
+            Self::Mexico => "Mexico",
+            Self::Australia => "Australia",
+            Self::SouthAsia => "South Asia",
+            Self::Taiwan => "Taiwan",
+            Self::Russia => "Russia",
+            Self::China => "China",
+            Self::HongKong => "Hong Kong",
+        }
+    }
 }
 
 /// CPU emulation settings
@@ -73,8 +168,21 @@ pub struct GpuConfig {
     pub resolution_scale: u32,
     pub anisotropic_filter: u32,
     pub vsync: bool,
+    /// Frames per second emulation is paced to, 0 for unlimited
     pub frame_limit: u32,
+    /// Speed multiplier while turbo is held, 0 for unlimited
+    pub turbo_speed: f32,
+    /// Speed multiplier in slow motion
+    pub slow_motion_speed: f32,
+    /// Output 50 Hz PAL video instead of 59.94 Hz NTSC
+    pub pal_50hz: bool,
+    /// Most frames skipped in a row while emulation is behind, 0 to never skip
+    pub frame_skip: u32,
     pub shader_cache: bool,
+    /// Save each texture the game decodes as a PNG named by its hash
+    pub dump_textures: bool,
+    /// Upload textures from the title's replacement folder in place of the game's
+    pub replace_textures: bool,
     pub write_color_buffers: bool,
     pub write_depth_buffer: bool,
 }
@@ -96,6 +204,23 @@ pub struct AudioConfig {
     pub volume: f32,
     pub buffer_duration_ms: u32,
     pub time_stretching: bool,
+    /// Output device to open (an ALSA PCM name or a cpal device name), empty for the system default
+    pub output_device: String,
+    /// Open the device exclusively with a small buffer for low latency
+    pub exclusive_mode: bool,
+    /// Speaker layout presented to games
+    pub channel_layout: AudioChannelLayout,
+    /// How surround audio is folded down when the device has fewer channels
+    pub downmix: AudioDownmix,
+    /// File format of audio dumps
+    pub dump_format: AudioDumpFormat,
+    /// Also dump each cellAudio port to its own file
+    pub dump_ports: bool,
+    /// Directory audio dumps are written to
+    pub dump_path: PathBuf,
+    /// Render SSPlayer and MultiStream (SCREAM) voices on the host mixer
+    /// instead of waiting on their SPU workload
+    pub hle_sound_middleware: bool,
 }
 
 /// Audio backend type
@@ -103,9 +228,51 @@ pub struct AudioConfig {
 pub enum AudioBackend {
     #[default]
     Auto,
+    /// Cross-platform backend (WASAPI, CoreAudio, ALSA via cpal)
+    Cpal,
+    /// Native ALSA (Linux only)
+    Alsa,
     Null,
 }
 
+/// Audio output speaker layout
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum AudioChannelLayout {
+    #[default]
+    Stereo,
+    Surround51,
+    Surround71,
+}
+
+impl AudioChannelLayout {
+    /// Number of output channels
+    pub fn num_channels(&self) -> u16 {
+        match self {
+            Self::Stereo => 2,
+            Self::Surround51 => 6,
+            Self::Surround71 => 8,
+        }
+    }
+}
+
+/// Surround-to-stereo downmix method
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum AudioDownmix {
+    /// Matrix-encoded Lt/Rt that Pro Logic II receivers can decode back
+    #[default]
+    DolbySurround,
+    /// ITU-R BS.775 fold-down
+    Itu,
+}
+
+/// Audio dump file format
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum AudioDumpFormat {
+    #[default]
+    Wav,
+    Flac,
+}
+
 /// Input settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -113,15 +280,252 @@ pub enum AudioBackend {
 pub struct InputConfig {
     pub controller: ControllerConfig,
     pub keyboard_mapping: KeyboardMapping,
+    /// Named gamepad mapping profiles
+    pub profiles: Vec<InputProfileConfig>,
+    /// Profile name to use for each title ID
+    pub game_profiles: BTreeMap<String, String>,
+    /// Keyboard and mouse pad emulation
+    pub keyboard_pad: KeyboardPadConfig,
+    /// Emulated PlayStation Move
+    pub move_controller: MoveConfig,
+    /// Guitar and drum emulation
+    pub instruments: InstrumentConfig,
+    /// Emulated PlayStation Eye
+    pub camera: CameraConfig,
+}
+
+/// Keyboard and mouse pad emulation settings
+///
+/// Buttons come from [`KeyboardMapping`]; this adds the analog sticks.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct KeyboardPadConfig {
+    /// Drive a pad port from the keyboard and mouse
+    pub enabled: bool,
+    pub left_stick_up: String,
+    pub left_stick_down: String,
+    pub left_stick_left: String,
+    pub left_stick_right: String,
+    /// Time for a stick key to reach full deflection, in milliseconds
+    pub stick_ramp_ms: u32,
+    pub mouse_mode: MouseMode,
+    /// Stick deflection per point of mouse movement, relative to the default
+    pub mouse_sensitivity: f32,
+    /// Time for a mouse flick to spring back to center, in milliseconds
+    pub mouse_decay_ms: u32,
+    /// PS3 button for the left mouse button (empty = unmapped)
+    pub mouse_left: String,
+    /// PS3 button for the right mouse button (empty = unmapped)
+    pub mouse_right: String,
+}
+
+/// What mouse movement drives
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MouseMode {
+    Off,
+    #[default]
+    RightStick,
+    /// Tilt the Sixaxis
+    Sixaxis,
+}
+
+/// Emulated PlayStation Move settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MoveConfig {
+    pub source: MoveSource,
+    /// Distance from the camera the sphere is tracked at, in millimeters
+    pub distance_mm: f32,
+}
+
+/// What drives the emulated Move
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveSource {
+    #[default]
+    Off,
+    /// Mouse pointer for tracking, mouse buttons for the trigger and Move button
+    Mouse,
+    /// Gyroscope and buttons of a DualShock 4 or DualSense
+    Ds4,
+    /// Gyroscope of a DualShock 4 or DualSense moving a pointer, without calibration
+    Ds4Pointer,
+}
+
+/// Music game instrument a pad port presents to games
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InstrumentKind {
+    /// Regular controller
+    #[default]
+    None,
+    /// Guitar Hero / Rock Band guitar
+    Guitar,
+    /// Guitar Hero / Rock Band drum kit
+    Drums,
+}
+
+/// Guitar and drum emulation settings
+///
+/// The binding maps take instrument inputs ("green", "strum_up", "kick",
+/// ...) to comma-separated host inputs ("pad:south", "key:F1",
+/// "midi:38"). Inputs not listed keep the default layout; "none" unbinds
+/// one.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct InstrumentConfig {
+    /// Instrument played on the keyboard or a MIDI kit, on its own port
+    pub device: InstrumentKind,
+    /// Read notes from a MIDI input device
+    pub midi: bool,
+    /// MIDI input device name (empty = first device)
+    pub midi_device: String,
+    pub guitar_bindings: BTreeMap<String, String>,
+    pub drum_bindings: BTreeMap<String, String>,
+}
+
+/// Emulated PlayStation Eye settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct CameraConfig {
+    /// Connect a PlayStation Eye for games to use
+    pub enabled: bool,
+    /// Host webcam name (empty = first webcam); a test pattern is shown if none opens
+    pub device: String,
+}
+
+/// Named gamepad mapping profile
+///
+/// `bindings` maps PS3 inputs ("cross", "l2", "left_x", ...) to host inputs
+/// ("pad:south", "axis:left_z", ...). PS3 inputs not listed keep the
+/// standard layout; "none" unbinds one.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct InputProfileConfig {
+    pub name: String,
+    pub bindings: BTreeMap<String, String>,
+    pub left_stick: StickResponse,
+    pub right_stick: StickResponse,
+    /// Light-gun pointer response of the emulated Move
+    pub pointer: PointerResponse,
+}
+
+/// Analog stick response settings
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StickResponse {
+    /// Radial dead zone (0.0 to 1.0)
+    pub deadzone: f32,
+    /// Output scale applied after the curve
+    pub sensitivity: f32,
+    pub curve: ResponseCurve,
+    pub invert_x: bool,
+    pub invert_y: bool,
+}
+
+/// Stick response curve
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseCurve {
+    #[default]
+    Linear,
+    /// Finer control near the center
+    Quadratic,
+    /// Even finer control near the center
+    Cubic,
+}
+
+/// Light-gun pointer response
+///
+/// Lines the crosshair of a game up with the host pointer without the
+/// game's own calibration.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PointerResponse {
+    /// Scale of horizontal movement away from the view center
+    pub sensitivity_x: f32,
+    /// Scale of vertical movement away from the view center
+    pub sensitivity_y: f32,
+    /// Horizontal shift, in view widths (positive is right)
+    pub offset_x: f32,
+    /// Vertical shift, in view heights (positive is down)
+    pub offset_y: f32,
 }
 
 /// Controller configuration
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ControllerConfig {
-    pub player1: Option<String>,
-    pub player2: Option<String>,
-    pub player3: Option<String>,
-    pub player4: Option<String>,
+    /// Per-port device assignment and profile, indexed by port
+    ///
+    /// Ports past the end of the list take any controller.
+    pub ports: Vec<PadPortConfig>,
+    /// Gyroscope drift of each port's controller, measured at rest and subtracted from its motion
+    #[serde(default)]
+    pub gyro_offsets: Vec<[i16; 3]>,
+    /// Read DualShock 3 controllers directly over HID (pressure, motion, rumble)
+    #[serde(default)]
+    pub ds3_passthrough: bool,
+    /// Read DualShock 4/DualSense controllers directly over HID (motion, touchpad)
+    #[serde(default)]
+    pub ds4_passthrough: bool,
+    /// PS3 buttons for the DualShock 4/DualSense touchpad and extra buttons
+    #[serde(default)]
+    pub ds4_extra: Ds4ExtraMapping,
+    /// Hand whitelisted HID devices (Buzz buzzers, dance mats, wheels) to games as raw USB devices
+    pub usb_passthrough: bool,
+    /// Extra devices to pass through, as "VID:PID" in hex (e.g. "054c:0002")
+    pub usb_passthrough_devices: Vec<String>,
+    /// Emulate a Buzz! controller whose four buzzers are played on controller ports 1-4
+    pub virtual_buzz: bool,
+    /// Forward game rumble to host controllers
+    pub rumble: bool,
+    /// Show the player number as a light bar color on DualShock 4/DualSense
+    pub light_bar: bool,
+}
+
+impl Default for ControllerConfig {
+    fn default() -> Self {
+        Self {
+            ports: Vec::new(),
+            gyro_offsets: Vec::new(),
+            ds3_passthrough: false,
+            ds4_passthrough: false,
+            ds4_extra: Ds4ExtraMapping::default(),
+            usb_passthrough: false,
+            usb_passthrough_devices: Vec::new(),
+            virtual_buzz: false,
+            rumble: true,
+            light_bar: true,
+        }
+    }
+}
+
+/// Assignment of a host controller to a PS3 pad port
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PadPortConfig {
+    /// Host device name pinned to the port (empty = any controller)
+    pub device: String,
+    /// Mapping profile for the port (empty = game or global profile)
+    pub profile: String,
+    /// Play generic gamepads on the port as an instrument
+    pub instrument: InstrumentKind,
+}
+
+/// DualShock 4/DualSense extra input to PS3 button mapping
+///
+/// Values are PS3 button names ("select", "l3", ...); empty leaves the
+/// input unmapped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Ds4ExtraMapping {
+    pub touchpad_click: String,
+    pub touch_left: String,
+    pub touch_right: String,
+    pub ps: String,
+    pub mute: String,
 }
 
 /// Keyboard to PS3 button mapping
@@ -155,7 +559,23 @@ pub struct PathConfig {
     pub dev_flash: PathBuf,
     pub save_data: PathBuf,
     pub shader_cache: PathBuf,
+    /// Executables decrypted at boot, in a directory per title ID
+    pub self_cache: PathBuf,
+    /// Textures dumped from games, in a directory per title ID
+    pub texture_dumps: PathBuf,
+    /// Texture packs replacing game textures, in a directory per title ID
+    pub texture_replacements: PathBuf,
+    /// Downscaled game icons and backgrounds shown in the game list
+    pub thumbnail_cache: PathBuf,
+    /// Savestates, in a directory per title ID
+    pub savestates: PathBuf,
+    /// Recorded network traffic, in a file per title ID
+    pub network_captures: PathBuf,
     pub firmware: PathBuf,
+    /// Folder custom soundtracks are picked from
+    pub music: PathBuf,
+    /// Folders scanned for games besides `games`, e.g. single games kept elsewhere
+    pub extra_games: Vec<PathBuf>,
 }
 
 /// Debug settings
@@ -169,7 +589,141 @@ pub struct DebugConfig {
     pub trace_ppu: bool,
     pub trace_spu: bool,
     pub trace_rsx: bool,
+    /// Record guest file accesses into a per-game VFS report
+    pub trace_vfs: bool,
     pub breakpoints: Vec<u32>,
+    /// Listen for GDB remote debuggers (PPU on `gdb_port`, raw SPUs on the next port)
+    pub gdb_server: bool,
+    pub gdb_port: u16,
+    /// Gzip file instruction traces are recorded to (`trace_ppu`/`trace_spu` pick the processors)
+    pub trace_path: PathBuf,
+    /// Start recording at `breakpoint`, an address or a `start-end` range; empty starts right away
+    pub trace_start: String,
+    /// Stop recording at `breakpoint`, an address or a `start-end` range; empty records until stopped
+    pub trace_stop: String,
+    /// Keep only the last N instructions (0 records everything)
+    pub trace_ring_size: usize,
+    /// Write a crash dump when a guest thread faults or the emulator panics
+    pub crash_dumps: bool,
+    /// Directory crash dumps are written to
+    pub crash_dump_dir: PathBuf,
+    /// Record the inputs of a run, or replay a recorded run
+    pub replay_mode: ReplayMode,
+    /// Gzip file runs are recorded to and replayed from
+    pub replay_path: PathBuf,
+    /// Record basic block coverage of the game from boot
+    pub coverage: bool,
+    /// drcov file coverage is written to (with a `module+offset` list next to it as `.txt`)
+    pub coverage_path: PathBuf,
+}
+
+/// Deterministic record/replay of a run
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum ReplayMode {
+    #[default]
+    Off,
+    /// Log controller input, time reads, scheduling and syscall results
+    Record,
+    /// Feed a recorded run's inputs back
+    Replay,
+}
+
+/// Screenshot and video recording settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureConfig {
+    /// Directory screenshots and videos are saved to
+    pub output_dir: PathBuf,
+    pub container: VideoContainer,
+    pub codec: VideoCodec,
+    /// Mux the audio recorded alongside into the video
+    pub mux_audio: bool,
+    /// Name of the key taking a screenshot
+    pub screenshot_key: String,
+    /// Name of the key starting and stopping a video recording
+    pub record_key: String,
+}
+
+/// File format of video recordings
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum VideoContainer {
+    #[default]
+    Avi,
+    Mp4,
+    Mkv,
+    WebM,
+}
+
+/// Video codec of recordings
+///
+/// Motion JPEG in AVI is written directly; everything else is encoded by
+/// an ffmpeg executable on the PATH.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum VideoCodec {
+    #[default]
+    Mjpeg,
+    H264,
+    H265,
+    Vp9,
+    /// Lossless
+    Ffv1,
+}
+
+/// Network settings
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// Record the game's HTTP traffic, or answer it from a recording
+    pub capture_mode: NetCaptureMode,
+    /// Hosts table consulted before DNS, checked in order
+    pub host_overrides: Vec<HostOverride>,
+}
+
+/// Name resolution rule sending a domain somewhere else
+///
+/// Lets games whose official servers are gone reach community replacements
+/// without patching the executable.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HostOverride {
+    /// Domain the game looks up; `*.example.com` also matches its subdomains
+    pub host: String,
+    /// IP address or domain to use instead
+    pub address: String,
+}
+
+/// Recording and replay of network traffic
+///
+/// Replaying lets games whose servers are gone get past the handshakes
+/// they insist on before going online or even starting.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum NetCaptureMode {
+    #[default]
+    Off,
+    /// Save every request and its response
+    Record,
+    /// Answer requests with the recorded responses, going online for the rest
+    Replay,
+}
+
+/// Frontend appearance settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    pub theme_mode: ThemeMode,
+    /// Theme used in dark mode
+    pub dark_theme: String,
+    /// Theme used in light mode
+    pub light_theme: String,
+}
+
+/// Whether the frontend is light, dark or follows the OS
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum ThemeMode {
+    Light,
+    #[default]
+    Dark,
+    System,
 }
 
 /// Logging level
@@ -193,6 +747,18 @@ impl Default for GeneralConfig {
             start_paused: false,
             confirm_exit: true,
             auto_save_state: false,
+            autosave_interval: 300,
+            autosave_count: 3,
+            compat_db_url: String::new(),
+            resume_from_savestate: false,
+            turbo_key: String::from("Tab"),
+            slow_motion_key: String::from("F9"),
+            quick_menu_key: String::from("F1"),
+            complete_background_downloads: false,
+            hdd_free_space_gb: 100,
+            copy_disc_game_data: false,
+            console_region: ConsoleRegion::default(),
+            console_id: String::new(),
         }
     }
 }
@@ -224,7 +790,13 @@ impl Default for GpuConfig {
             anisotropic_filter: 8,
             vsync: true,
             frame_limit: 60,
+            turbo_speed: 0.0,
+            slow_motion_speed: 0.5,
+            pal_50hz: false,
+            frame_skip: 0,
             shader_cache: true,
+            dump_textures: false,
+            replace_textures: true,
             write_color_buffers: false,
             write_depth_buffer: false,
         }
@@ -239,6 +811,16 @@ impl Default for AudioConfig {
             volume: 1.0,
             buffer_duration_ms: 100,
             time_stretching: true,
+            output_device: String::new(),
+            exclusive_mode: false,
+            channel_layout: AudioChannelLayout::default(),
+            downmix: AudioDownmix::default(),
+            dump_format: AudioDumpFormat::default(),
+            dump_ports: false,
+            dump_path: dirs::data_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("oxidized-cell/audio_dumps"),
+            hle_sound_middleware: false,
         }
     }
 }
@@ -267,6 +849,68 @@ impl Default for KeyboardMapping {
     }
 }
 
+impl Default for Ds4ExtraMapping {
+    fn default() -> Self {
+        Self {
+            touchpad_click: "select".to_string(),
+            touch_left: String::new(),
+            touch_right: String::new(),
+            ps: String::new(),
+            mute: String::new(),
+        }
+    }
+}
+
+impl Default for KeyboardPadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            left_stick_up: "W".to_string(),
+            left_stick_down: "S".to_string(),
+            left_stick_left: "A".to_string(),
+            left_stick_right: "D".to_string(),
+            stick_ramp_ms: 80,
+            mouse_mode: MouseMode::RightStick,
+            mouse_sensitivity: 1.0,
+            mouse_decay_ms: 120,
+            mouse_left: "r1".to_string(),
+            mouse_right: "l1".to_string(),
+        }
+    }
+}
+
+impl Default for MoveConfig {
+    fn default() -> Self {
+        Self {
+            source: MoveSource::Off,
+            distance_mm: 1500.0,
+        }
+    }
+}
+
+impl Default for StickResponse {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.1,
+            sensitivity: 1.0,
+            curve: ResponseCurve::Linear,
+            invert_x: false,
+            invert_y: false,
+        }
+    }
+}
+
+impl Default for PointerResponse {
+    fn default() -> Self {
+        Self {
+            sensitivity_x: 1.0,
+            sensitivity_y: 1.0,
+            offset_x: 0.0,
+            offset_y: 0.0,
+        }
+    }
+}
+
 impl Default for PathConfig {
     fn default() -> Self {
         let base = dirs::data_dir()
@@ -280,7 +924,40 @@ impl Default for PathConfig {
             dev_flash: base.join("dev_flash"),
             save_data: base.join("savedata"),
             shader_cache: base.join("cache/shaders"),
+            self_cache: base.join("cache/self"),
+            texture_dumps: base.join("textures/dumps"),
+            texture_replacements: base.join("textures/replacements"),
+            thumbnail_cache: base.join("cache/thumbnails"),
+            savestates: base.join("savestates"),
+            network_captures: base.join("network"),
             firmware: base.join("firmware"),
+            music: dirs::audio_dir().unwrap_or_else(|| base.join("music")),
+            extra_games: Vec::new(),
+        }
+    }
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            output_dir: dirs::data_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("oxidized-cell/captures"),
+            container: VideoContainer::default(),
+            codec: VideoCodec::default(),
+            mux_audio: true,
+            screenshot_key: String::from("F12"),
+            record_key: String::from("F10"),
+        }
+    }
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            theme_mode: ThemeMode::Dark,
+            dark_theme: String::from("Dark"),
+            light_theme: String::from("Light"),
         }
     }
 }
@@ -295,7 +972,28 @@ impl Default for DebugConfig {
             trace_ppu: false,
             trace_spu: false,
             trace_rsx: false,
+            trace_vfs: false,
             breakpoints: Vec::new(),
+            gdb_server: false,
+            gdb_port: 2345,
+            trace_path: dirs::data_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("oxidized-cell/traces/trace.txt.gz"),
+            trace_start: String::new(),
+            trace_stop: String::new(),
+            trace_ring_size: 0,
+            crash_dumps: true,
+            crash_dump_dir: dirs::data_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("oxidized-cell/dumps"),
+            replay_mode: ReplayMode::default(),
+            replay_path: dirs::data_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("oxidized-cell/replays/replay.txt.gz"),
+            coverage: false,
+            coverage_path: dirs::data_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("oxidized-cell/coverage/coverage.drcov"),
         }
     }
 }
@@ -315,6 +1013,11 @@ impl Config {
         }
     }
 
+    /// Load configuration from `path`, which must exist
+    pub fn load_from(path: &std::path::Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
     /// Save configuration to file
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
         let path = Self::config_path();
@@ -337,6 +1040,443 @@ impl Config {
     }
 }
 
+impl Config {
+    /// Sections of the configuration file
+    pub const SECTIONS: [&'static str; 10] =
+        ["general", "cpu", "gpu", "audio", "input", "paths", "debug", "ui", "capture", "network"];
+
+    /// Sections in which `other` differs from this configuration
+    pub fn changed_sections(&self, other: &Config) -> Vec<&'static str> {
+        match (toml::Table::try_from(self), toml::Table::try_from(other)) {
+            (Ok(old), Ok(new)) => Self::SECTIONS
+                .into_iter()
+                .filter(|section| old.get(*section) != new.get(*section))
+                .collect(),
+            // Assume everything changed rather than miss a change
+            _ => Self::SECTIONS.to_vec(),
+        }
+    }
+}
+
+/// Settings that changed, as delivered by a [`ConfigBus`]
+#[derive(Debug, Clone)]
+pub struct ConfigChange {
+    /// The settings now in effect
+    pub config: Arc<Config>,
+    /// Sections that differ from the settings before
+    pub sections: Vec<&'static str>,
+}
+
+impl ConfigChange {
+    /// Whether anything in `section` changed
+    pub fn touches(&self, section: &str) -> bool {
+        self.sections.contains(&section)
+    }
+}
+
+/// Pending change of one [`ConfigWatcher`]
+type PendingChange = Mutex<Option<ConfigChange>>;
+
+/// Sections a watcher wants and its pending change, gone once it is dropped
+type WatcherSlot = (Vec<&'static str>, Weak<PendingChange>);
+
+/// Tells subsystems about configuration changes so they can apply them live
+///
+/// Whoever owns the settings publishes each saved configuration; subsystems
+/// hold a [`ConfigWatcher`] for the sections they care about and take the
+/// change when it suits them, typically once per frame. Clones share the
+/// same subscribers.
+#[derive(Clone, Default)]
+pub struct ConfigBus {
+    watchers: Arc<Mutex<Vec<WatcherSlot>>>,
+}
+
+impl ConfigBus {
+    /// Create a bus without watchers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Watch `sections` of the configuration, every section if empty
+    pub fn watch(&self, sections: &[&'static str]) -> ConfigWatcher {
+        let pending = Arc::new(Mutex::new(None));
+        self.watchers.lock().push((sections.to_vec(), Arc::downgrade(&pending)));
+        ConfigWatcher { pending }
+    }
+
+    /// Number of watchers still alive
+    pub fn watcher_count(&self) -> usize {
+        self.watchers.lock().iter().filter(|(_, pending)| pending.strong_count() > 0).count()
+    }
+
+    /// Publish `new` in place of `old`, returning the sections that changed
+    ///
+    /// Watchers of none of the changed sections are not told.
+    pub fn publish(&self, old: &Config, new: &Config) -> Vec<&'static str> {
+        let sections = old.changed_sections(new);
+        if sections.is_empty() {
+            return sections;
+        }
+        tracing::debug!("Configuration changed: {}", sections.join(", "));
+
+        let config = Arc::new(new.clone());
+        self.watchers.lock().retain(|(watched, pending)| {
+            let Some(pending) = pending.upgrade() else {
+                return false;
+            };
+            let changed: Vec<_> = sections
+                .iter()
+                .copied()
+                .filter(|section| watched.is_empty() || watched.contains(section))
+                .collect();
+            if !changed.is_empty() {
+                let mut pending = pending.lock();
+                // Changes not taken yet are merged into the new one
+                let mut merged = pending.take().map(|change| change.sections).unwrap_or_default();
+                for section in changed {
+                    if !merged.contains(&section) {
+                        merged.push(section);
+                    }
+                }
+                *pending = Some(ConfigChange { config: Arc::clone(&config), sections: merged });
+            }
+            true
+        });
+        sections
+    }
+}
+
+/// Receives the changes a [`ConfigBus`] publishes for some sections
+///
+/// Dropping it unsubscribes.
+pub struct ConfigWatcher {
+    pending: Arc<PendingChange>,
+}
+
+impl ConfigWatcher {
+    /// Take the changes published since the last call, None if there were none
+    pub fn take(&self) -> Option<ConfigChange> {
+        self.pending.lock().take()
+    }
+}
+
+/// Settings one game overrides on top of the global configuration
+///
+/// Only the CPU, GPU, audio, input and network sections can be overridden. The file
+/// keeps just the settings that differ, so later changes to the global
+/// configuration still reach everything the game leaves alone.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GameConfig {
+    pub title_id: String,
+    overrides: toml::Table,
+}
+
+impl GameConfig {
+    /// Sections a game can override
+    pub const SECTIONS: [&'static str; 5] = ["cpu", "gpu", "audio", "input", "network"];
+
+    /// Create a game configuration overriding nothing
+    pub fn new(title_id: &str) -> Self {
+        Self {
+            title_id: title_id.to_string(),
+            overrides: toml::Table::new(),
+        }
+    }
+
+    /// Get the path to the override file of a title
+    pub fn path(title_id: &str) -> PathBuf {
+        Config::config_path().with_file_name("games").join(format!("{}.toml", title_id))
+    }
+
+    /// Whether a title has an override file
+    pub fn exists(title_id: &str) -> bool {
+        Self::path(title_id).exists()
+    }
+
+    /// Load the overrides of a title, overriding nothing if it has no file
+    pub fn load(title_id: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::load_from(title_id, &Self::path(title_id))
+    }
+
+    /// Load the overrides of a title from `path`
+    pub fn load_from(title_id: &str, path: &std::path::Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut config = Self::new(title_id);
+        if path.exists() {
+            let mut overrides: toml::Table = toml::from_str(&std::fs::read_to_string(path)?)?;
+            overrides.retain(|section, _| Self::SECTIONS.contains(&section));
+            // Reject values of the wrong type now rather than when the game starts
+            config.overrides = overrides;
+            config.apply(&Config::default())?;
+        }
+        Ok(config)
+    }
+
+    /// Save the overrides, removing the file when nothing is overridden
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_to(&Self::path(&self.title_id))
+    }
+
+    /// Save the overrides to `path`
+    pub fn save_to(&self, path: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
+        if self.is_empty() {
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(&self.overrides)?)?;
+        Ok(())
+    }
+
+    /// Whether the game overrides nothing
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty()
+    }
+
+    /// Number of settings overridden
+    pub fn len(&self) -> usize {
+        fn count(table: &toml::Table) -> usize {
+            table
+                .values()
+                .map(|value| match value {
+                    toml::Value::Table(table) => count(table),
+                    _ => 1,
+                })
+                .sum()
+        }
+        count(&self.overrides)
+    }
+
+    /// Whether the game overrides anything in `section`
+    pub fn overrides_section(&self, section: &str) -> bool {
+        self.overrides.contains_key(section)
+    }
+
+    /// The global configuration with the game's overrides applied
+    pub fn apply(&self, global: &Config) -> Result<Config, Box<dyn std::error::Error>> {
+        fn merge(base: &mut toml::Table, overrides: &toml::Table) {
+            for (key, value) in overrides {
+                match (base.get_mut(key), value) {
+                    (Some(toml::Value::Table(base)), toml::Value::Table(value)) => merge(base, value),
+                    _ => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+
+        let mut table = toml::Table::try_from(global)?;
+        merge(&mut table, &self.overrides);
+        Ok(toml::Value::Table(table).try_into()?)
+    }
+
+    /// Override whatever `game` sets differently from `global`, and nothing else
+    pub fn set(&mut self, global: &Config, game: &Config) -> Result<(), Box<dyn std::error::Error>> {
+        fn diff(base: &toml::Table, other: &toml::Table) -> toml::Table {
+            let mut changed = toml::Table::new();
+            for (key, value) in other {
+                match (base.get(key), value) {
+                    (Some(toml::Value::Table(base)), toml::Value::Table(value)) => {
+                        let table = diff(base, value);
+                        if !table.is_empty() {
+                            changed.insert(key.clone(), toml::Value::Table(table));
+                        }
+                    }
+                    (Some(base), value) if base == value => {}
+                    _ => {
+                        changed.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            changed
+        }
+
+        let mut overrides = diff(&toml::Table::try_from(global)?, &toml::Table::try_from(game)?);
+        overrides.retain(|section, _| Self::SECTIONS.contains(&section));
+        self.overrides = overrides;
+        Ok(())
+    }
+
+    /// Stop overriding anything
+    pub fn clear(&mut self) {
+        self.overrides.clear();
+    }
+}
+
+impl Config {
+    /// The configuration a title runs with: this one with the title's overrides applied
+    pub fn for_title(&self, title_id: &str) -> Result<Config, Box<dyn std::error::Error>> {
+        GameConfig::load(title_id)?.apply(self)
+    }
+}
+
+/// When and for how long each title was played, and which are favorites
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayHistory {
+    pub titles: BTreeMap<String, TitleHistory>,
+}
+
+/// Play history of one title
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TitleHistory {
+    /// When the title was last started, in seconds since the Unix epoch (0 if never)
+    pub last_played: u64,
+    /// Total time played, in seconds
+    pub playtime: u64,
+    pub favorite: bool,
+}
+
+impl PlayHistory {
+    /// Get the path to the play history file
+    pub fn path() -> PathBuf {
+        Config::config_path().with_file_name("history.toml")
+    }
+
+    /// Load the play history, starting empty if there is none
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::load_from(&Self::path())
+    }
+
+    /// Load the play history from `path`
+    pub fn load_from(path: &std::path::Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Save the play history
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_to(&Self::path())
+    }
+
+    /// Save the play history to `path`
+    pub fn save_to(&self, path: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// History of a title
+    pub fn get(&self, title_id: &str) -> Option<&TitleHistory> {
+        self.titles.get(title_id)
+    }
+
+    /// Record that a title was started at `now`
+    pub fn mark_played(&mut self, title_id: &str, now: u64) {
+        self.titles.entry(title_id.to_string()).or_default().last_played = now;
+    }
+
+    /// Add `secs` to the time a title was played
+    pub fn add_playtime(&mut self, title_id: &str, secs: u64) {
+        let title = self.titles.entry(title_id.to_string()).or_default();
+        title.playtime = title.playtime.saturating_add(secs);
+    }
+
+    /// Whether a title is a favorite
+    pub fn is_favorite(&self, title_id: &str) -> bool {
+        self.titles.get(title_id).is_some_and(|title| title.favorite)
+    }
+
+    /// Mark or unmark a title as a favorite
+    pub fn set_favorite(&mut self, title_id: &str, favorite: bool) {
+        self.titles.entry(title_id.to_string()).or_default().favorite = favorite;
+    }
+
+    /// Up to `limit` played titles, most recently played first
+    pub fn recent(&self, limit: usize) -> Vec<&str> {
+        let mut played: Vec<(&String, &TitleHistory)> =
+            self.titles.iter().filter(|(_, title)| title.last_played > 0).collect();
+        played.sort_by_key(|(_, title)| std::cmp::Reverse(title.last_played));
+        played.into_iter().take(limit).map(|(id, _)| id.as_str()).collect()
+    }
+}
+
+/// Disc folders registered for multi-disc titles, in disc order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscSets {
+    pub titles: BTreeMap<String, Vec<PathBuf>>,
+}
+
+impl DiscSets {
+    /// Get the path to the disc sets file
+    pub fn path() -> PathBuf {
+        Config::config_path().with_file_name("discs.toml")
+    }
+
+    /// Load the disc sets, starting empty if there are none
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::load_from(&Self::path())
+    }
+
+    /// Load the disc sets from `path`
+    pub fn load_from(path: &std::path::Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Save the disc sets
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_to(&Self::path())
+    }
+
+    /// Save the disc sets to `path`
+    pub fn save_to(&self, path: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Discs registered for a title, in disc order
+    pub fn discs(&self, title_id: &str) -> &[PathBuf] {
+        self.titles.get(title_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Register `disc` as the next disc of a title; false if it already was
+    pub fn add_disc(&mut self, title_id: &str, disc: PathBuf) -> bool {
+        let discs = self.titles.entry(title_id.to_string()).or_default();
+        if discs.contains(&disc) {
+            return false;
+        }
+        discs.push(disc);
+        true
+    }
+
+    /// Unregister disc `index` of a title
+    pub fn remove_disc(&mut self, title_id: &str, index: usize) {
+        if let Some(discs) = self.titles.get_mut(title_id) {
+            if index < discs.len() {
+                discs.remove(index);
+            }
+            if discs.is_empty() {
+                self.titles.remove(title_id);
+            }
+        }
+    }
+
+    /// The disc after `current` for a title, wrapping around to the first
+    pub fn next_disc(&self, title_id: &str, current: Option<&std::path::Path>) -> Option<&PathBuf> {
+        let discs = self.discs(title_id);
+        let next = current
+            .and_then(|current| discs.iter().position(|disc| disc == current))
+            .map_or(0, |index| (index + 1) % discs.len());
+        discs.get(next)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -359,4 +1499,144 @@ mod tests {
         let parsed: Config = toml::from_str(&toml_str).unwrap();
         assert_eq!(parsed.cpu.ppu_threads, config.cpu.ppu_threads);
     }
+
+    #[test]
+    fn test_input_profile_serialization() {
+        let mut config = Config::default();
+        let mut profile = InputProfileConfig {
+            name: "Racing".to_string(),
+            ..Default::default()
+        };
+        profile.bindings.insert("r2".to_string(), "axis:right_z".to_string());
+        profile.left_stick.curve = ResponseCurve::Quadratic;
+        config.input.profiles.push(profile);
+        config.input.game_profiles.insert("BLUS30001".to_string(), "Racing".to_string());
+
+        let toml_str = toml::to_string_pretty(&config).unwrap();
+        let parsed: Config = toml::from_str(&toml_str).unwrap();
+        assert_eq!(parsed.input.profiles, config.input.profiles);
+        assert_eq!(parsed.input.game_profiles["BLUS30001"], "Racing");
+    }
+
+    #[test]
+    fn test_game_config_layering() {
+        let global = Config::default();
+        let mut game = global.clone();
+        game.cpu.spu_threads = 2;
+        game.gpu.resolution_scale = 200;
+        game.input.game_profiles.insert("BLUS30001".to_string(), "Racing".to_string());
+        game.network.host_overrides.push(HostOverride {
+            host: "*.example.com".to_string(),
+            address: "10.0.0.1".to_string(),
+        });
+        // Outside the overridable sections
+        game.general.start_paused = true;
+
+        let mut config = GameConfig::new("BLUS30001");
+        config.set(&global, &game).unwrap();
+        assert_eq!(config.len(), 4);
+        assert!(config.overrides_section("gpu"));
+        assert!(!config.overrides_section("audio"));
+        assert!(!config.overrides_section("general"));
+
+        // Later global changes still reach settings the game does not override
+        let mut global = global;
+        global.cpu.ppu_threads = 4;
+        global.gpu.resolution_scale = 50;
+        let applied = config.apply(&global).unwrap();
+        assert_eq!(applied.cpu.ppu_threads, 4);
+        assert_eq!(applied.cpu.spu_threads, 2);
+        assert_eq!(applied.gpu.resolution_scale, 200);
+        assert_eq!(applied.input.game_profiles["BLUS30001"], "Racing");
+        assert_eq!(applied.network.host_overrides, game.network.host_overrides);
+        assert!(!applied.general.start_paused);
+
+        let path = std::env::temp_dir().join(format!("oc_game_config_test_{}", std::process::id())).join("BLUS30001.toml");
+        config.save_to(&path).unwrap();
+        assert_eq!(GameConfig::load_from("BLUS30001", &path).unwrap(), config);
+
+        config.clear();
+        config.save_to(&path).unwrap();
+        assert!(!path.exists());
+        assert!(GameConfig::load_from("BLUS30001", &path).unwrap().is_empty());
+
+        std::fs::write(&path, "[gpu]\nresolution_scale = \"high\"\n").unwrap();
+        assert!(GameConfig::load_from("BLUS30001", &path).is_err());
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_config_bus() {
+        let bus = ConfigBus::new();
+        let audio = bus.watch(&["audio", "gpu"]);
+        let all = bus.watch(&[]);
+        let old = Config::default();
+        assert!(bus.publish(&old, &old.clone()).is_empty());
+        assert!(audio.take().is_none());
+
+        let mut new = old.clone();
+        new.audio.volume = 0.5;
+        new.debug.log_level = LogLevel::Trace;
+        assert_eq!(bus.publish(&old, &new), ["audio", "debug"]);
+        let mut newer = new.clone();
+        newer.gpu.resolution_scale = 200;
+        bus.publish(&new, &newer);
+
+        // Both changes arrive as one
+        let change = audio.take().unwrap();
+        assert_eq!(change.sections, ["audio", "gpu"]);
+        assert!(!change.touches("debug"));
+        assert_eq!(change.config.gpu.resolution_scale, 200);
+        assert!(audio.take().is_none());
+        assert_eq!(all.take().unwrap().sections, ["audio", "debug", "gpu"]);
+
+        drop(all);
+        assert_eq!(bus.watcher_count(), 1);
+    }
+
+    #[test]
+    fn test_play_history() {
+        let mut history = PlayHistory::default();
+        history.mark_played("BLUS30443", 100);
+        history.mark_played("BLES00001", 300);
+        history.set_favorite("NPUB30001", true);
+        history.add_playtime("BLUS30443", 60);
+        history.add_playtime("BLUS30443", 30);
+        assert_eq!(history.get("BLUS30443").unwrap().playtime, 90);
+        assert!(history.is_favorite("NPUB30001"));
+        assert!(!history.is_favorite("BLUS30443"));
+        // A favorite that was never played is not recent
+        assert_eq!(history.recent(5), ["BLES00001", "BLUS30443"]);
+        assert_eq!(history.recent(1), ["BLES00001"]);
+
+        let path = std::env::temp_dir().join(format!("oc_history_test_{}.toml", std::process::id()));
+        history.save_to(&path).unwrap();
+        assert_eq!(PlayHistory::load_from(&path).unwrap(), history);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(PlayHistory::load_from(&path).unwrap(), PlayHistory::default());
+    }
+
+    #[test]
+    fn test_disc_sets() {
+        let mut sets = DiscSets::default();
+        assert!(sets.add_disc("BLUS30001", PathBuf::from("/games/disc1")));
+        assert!(sets.add_disc("BLUS30001", PathBuf::from("/games/disc2")));
+        assert!(!sets.add_disc("BLUS30001", PathBuf::from("/games/disc1")));
+        assert_eq!(sets.discs("BLUS30001").len(), 2);
+        assert!(sets.discs("BLES00001").is_empty());
+
+        let next = |current: &str| sets.next_disc("BLUS30001", Some(std::path::Path::new(current))).cloned();
+        assert_eq!(next("/games/disc1"), Some(PathBuf::from("/games/disc2")));
+        assert_eq!(next("/games/disc2"), Some(PathBuf::from("/games/disc1")));
+        assert_eq!(next("/elsewhere"), Some(PathBuf::from("/games/disc1")));
+
+        let path = std::env::temp_dir().join(format!("oc_discs_test_{}.toml", std::process::id()));
+        sets.save_to(&path).unwrap();
+        assert_eq!(DiscSets::load_from(&path).unwrap(), sets);
+        std::fs::remove_file(&path).unwrap();
+
+        sets.remove_disc("BLUS30001", 0);
+        sets.remove_disc("BLUS30001", 0);
+        assert!(sets.titles.is_empty());
+    }
 }
@@ -59,6 +59,9 @@ pub enum MemoryError {
 
     #[error("Self-modifying code detected at 0x{0:08x}")]
     SelfModifyingCode(u32),
+
+    #[error("Snapshot mismatch: delta applies to generation {expected}, memory is at {actual}")]
+    SnapshotMismatch { expected: u64, actual: u64 },
 }
 
 /// PPU (PowerPC Processing Unit) errors
@@ -79,9 +82,15 @@ pub enum PpuError {
     #[error("Trap at 0x{addr:08x}")]
     Trap { addr: u32 },
 
+    #[error("Access violation at 0x{pc:08x}: {kind} of protected address 0x{addr:08x}")]
+    AccessViolation { pc: u32, addr: u32, kind: AccessKind },
+
     #[error("Breakpoint hit at 0x{addr:08x}")]
     Breakpoint { addr: u64 },
 
+    #[error("Watchpoint hit at 0x{addr:08x} ({kind}) by instruction at 0x{pc:08x}")]
+    Watchpoint { addr: u32, kind: AccessKind, pc: u32 },
+
     #[error("Exception {exception:?} at 0x{addr:08x}")]
     Exception { addr: u32, exception: PpuExceptionType },
 
@@ -196,6 +205,9 @@ pub enum KernelError {
     #[error("Resource limit exceeded")]
     ResourceLimit,
 
+    #[error("Invalid address: 0x{0:08x}")]
+    InvalidAddress(u32),
+
     #[error("Permission denied")]
     PermissionDenied,
 
@@ -218,6 +230,9 @@ pub enum LoaderError {
     #[error("Decryption failed: {0}")]
     DecryptionFailed(String),
 
+    #[error("Missing key: {0}")]
+    MissingKey(String),
+
     #[error("Missing PRX: {0}")]
     MissingPrx(String),
 
@@ -237,6 +252,25 @@ pub enum LoaderError {
     UnsupportedFormat(String),
 }
 
+/// Breakpoint condition errors
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ConditionError {
+    #[error("Unexpected {found} at offset {offset}")]
+    Syntax { offset: usize, found: String },
+
+    #[error("Unknown function: {0}")]
+    UnknownFunction(String),
+
+    #[error("Unknown variable: {0}")]
+    UnknownVariable(String),
+
+    #[error("Cannot read {size} bytes at 0x{addr:x}")]
+    BadRead { addr: u64, size: usize },
+
+    #[error("Division by zero")]
+    DivisionByZero,
+}
+
 /// Kind of memory access
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AccessKind {
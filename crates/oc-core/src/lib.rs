@@ -3,13 +3,19 @@
 //! This crate provides the foundational types, error handling,
 //! configuration, and logging infrastructure for the emulator.
 
+pub mod condition;
 pub mod config;
 pub mod emulator;
 pub mod error;
 pub mod logging;
+pub mod savestate;
 pub mod scheduler;
+pub mod time_base;
 
-pub use config::Config;
+pub use condition::{Condition, ConditionContext};
+pub use config::{Config, ConfigBus, GameConfig};
 pub use emulator::Emulator;
 pub use error::{EmulatorError, Result};
+pub use savestate::{StateReader, StateWriter};
 pub use scheduler::{Scheduler, ThreadId, ThreadState, ThreadStats};
+pub use time_base::TimeBase;
@@ -1,7 +1,12 @@
 //! Logging infrastructure for oxidized-cell emulator
 
+use parking_lot::{Mutex, RwLock};
+use std::collections::VecDeque;
+use std::fmt::Write;
 use std::sync::OnceLock;
-use tracing::Level;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
 use tracing_subscriber::{fmt, prelude::*, reload, EnvFilter};
 
 use crate::config::{Config, LogLevel};
@@ -12,6 +17,70 @@ type ReloadHandle = reload::Handle<EnvFilter, tracing_subscriber::Registry>;
 /// Global reload handle for runtime log level changes
 static RELOAD_HANDLE: OnceLock<ReloadHandle> = OnceLock::new();
 
+/// Number of log lines kept for crash dumps
+pub const RECENT_LOG_LINES: usize = 200;
+
+/// Most recent log lines, oldest first
+static RECENT_LOG: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Get the most recent log lines, oldest first
+pub fn recent_lines() -> Vec<String> {
+    RECENT_LOG.lock().iter().cloned().collect()
+}
+
+/// Receives every log event as (level, target, message)
+pub type LogListener = Box<dyn Fn(Level, &str, &str) + Send + Sync>;
+
+/// Listener set by the frontend's log window
+static LOG_LISTENER: RwLock<Option<LogListener>> = RwLock::new(None);
+
+/// Send log events to `listener` from now on (None = stop)
+///
+/// The listener must not log itself.
+pub fn set_log_listener(listener: Option<LogListener>) {
+    *LOG_LISTENER.write() = listener;
+}
+
+fn push_recent(line: String) {
+    let mut recent = RECENT_LOG.lock();
+    if recent.len() >= RECENT_LOG_LINES {
+        recent.pop_front();
+    }
+    recent.push_back(line);
+}
+
+/// Formats an event's message followed by its other fields
+#[derive(Default)]
+struct LineVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for LineVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// Layer keeping the last `RECENT_LOG_LINES` log lines in memory
+struct RecentLogLayer;
+
+impl<S: Subscriber> Layer<S> for RecentLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = LineVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        if let Some(listener) = LOG_LISTENER.read().as_ref() {
+            listener(*metadata.level(), metadata.target(), &format!("{}{}", visitor.message, visitor.fields));
+        }
+        push_recent(format!("{:>5} {}: {}{}", metadata.level(), metadata.target(), visitor.message, visitor.fields));
+    }
+}
+
 /// Convert our LogLevel to tracing Level
 fn log_level_to_tracing(level: LogLevel) -> Option<Level> {
     match level {
@@ -41,7 +110,8 @@ pub fn init_with_reload(initial_level: LogLevel) {
                 .with_thread_ids(false)
                 .with_file(false)
                 .with_line_number(false),
-        );
+        )
+        .with(RecentLogLayer);
     
     if subscriber.try_init().is_ok() {
         let _ = RELOAD_HANDLE.set(reload_handle);
@@ -92,7 +162,8 @@ pub fn init(config: &Config) {
             .with_thread_ids(true)
             .with_file(true)
             .with_line_number(true),
-    );
+    )
+    .with(RecentLogLayer);
 
     if config.debug.log_to_file {
         if let Ok(file) = std::fs::File::create(&config.debug.log_path) {
@@ -114,6 +185,7 @@ pub fn init_default() {
     let _ = tracing_subscriber::registry()
         .with(filter)
         .with(fmt::layer())
+        .with(RecentLogLayer)
         .try_init();
 }
 
@@ -182,3 +254,35 @@ macro_rules! kernel_debug {
         tracing::debug!(target: "kernel", $($arg)*)
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_lines() {
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(RecentLogLayer));
+        for i in 0..RECENT_LOG_LINES + 5 {
+            tracing::warn!(target: "test", count = i, "line {}", i);
+        }
+        let lines = recent_lines();
+        assert_eq!(lines.len(), RECENT_LOG_LINES);
+        assert_eq!(lines.last().unwrap(), &format!(" WARN test: line {} count={}", RECENT_LOG_LINES + 4, RECENT_LOG_LINES + 4));
+    }
+
+    #[test]
+    fn test_log_listener() {
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(RecentLogLayer));
+        let received = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let sink = std::sync::Arc::clone(&received);
+        set_log_listener(Some(Box::new(move |level, target, message| {
+            if target == "listener" {
+                sink.lock().push((level, message.to_string()));
+            }
+        })));
+        tracing::error!(target: "listener", id = 3, "failed");
+        set_log_listener(None);
+        tracing::error!(target: "listener", "not received");
+        assert_eq!(*received.lock(), vec![(Level::ERROR, "failed id=3".to_string())]);
+    }
+}
@@ -5,6 +5,9 @@
 
 use std::collections::{BinaryHeap, HashMap};
 use std::cmp::Ordering;
+use std::io;
+
+use crate::savestate::{invalid, StateReader, StateWriter};
 
 /// Thread identifier
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -137,10 +140,10 @@ impl Scheduler {
     /// Set thread state
     pub fn set_thread_state(&mut self, id: ThreadId, state: ThreadState) {
         if let Some(thread) = self.threads.get_mut(&id) {
-            thread.state = state;
+            let previous = std::mem::replace(&mut thread.state, state);
 
             // If transitioning to ready, add to ready queue
-            if state == ThreadState::Ready && thread.state != ThreadState::Ready {
+            if state == ThreadState::Ready && previous != ThreadState::Ready {
                 self.ready_queue.push(thread.clone());
             }
 
@@ -387,6 +390,74 @@ impl Scheduler {
         self.current = None;
         self.schedule()
     }
+
+    /// Save every thread's priority, state and time for a savestate
+    pub fn save_state(&self, w: &mut StateWriter) {
+        let write_id = |w: &mut StateWriter, id: ThreadId| match id {
+            ThreadId::Ppu(id) => {
+                w.u8(0);
+                w.u32(id);
+            }
+            ThreadId::Spu(id) => {
+                w.u8(1);
+                w.u32(id);
+            }
+        };
+        let mut threads: Vec<&ScheduledThread> = self.threads.values().collect();
+        threads.sort_by_key(|thread| thread.id);
+        w.u64(self.default_time_slice_us);
+        w.option(self.current, write_id);
+        w.count(threads.len());
+        for thread in threads {
+            write_id(w, thread.id);
+            w.u32(thread.priority);
+            w.u8(match thread.state {
+                ThreadState::Ready => 0,
+                ThreadState::Running => 1,
+                ThreadState::Waiting => 2,
+                ThreadState::Stopped => 3,
+            });
+            w.u64(thread.time_slice_us);
+            w.u64(thread.total_time_us);
+        }
+    }
+
+    /// Replace all threads with those written by [`Scheduler::save_state`]
+    pub fn load_state(&mut self, r: &mut StateReader) -> io::Result<()> {
+        let read_id = |r: &mut StateReader| -> io::Result<ThreadId> {
+            match r.u8()? {
+                0 => Ok(ThreadId::Ppu(r.u32()?)),
+                1 => Ok(ThreadId::Spu(r.u32()?)),
+                kind => Err(invalid(&format!("invalid thread kind {}", kind))),
+            }
+        };
+        let default_time_slice_us = r.u64()?;
+        let current = r.option(read_id)?;
+        let mut threads = HashMap::new();
+        for _ in 0..r.count()? {
+            let id = read_id(r)?;
+            let priority = r.u32()?;
+            let state = match r.u8()? {
+                0 => ThreadState::Ready,
+                1 => ThreadState::Running,
+                2 => ThreadState::Waiting,
+                3 => ThreadState::Stopped,
+                state => return Err(invalid(&format!("invalid scheduler thread state {}", state))),
+            };
+            threads.insert(id, ScheduledThread {
+                id,
+                priority,
+                state,
+                time_slice_us: r.u64()?,
+                total_time_us: r.u64()?,
+            });
+        }
+        self.default_time_slice_us = default_time_slice_us;
+        self.current = current;
+        self.threads = threads;
+        self.rebuild_ready_queue();
+        Ok(())
+    }
 }
 
 /// Scheduler error types
@@ -472,6 +543,11 @@ mod tests {
         
         scheduler.set_thread_state(ThreadId::Ppu(1), ThreadState::Waiting);
         assert_eq!(scheduler.get_thread_state(ThreadId::Ppu(1)), Some(ThreadState::Waiting));
+        assert_eq!(scheduler.schedule(), None);
+
+        // A woken thread is scheduled again
+        scheduler.set_thread_state(ThreadId::Ppu(1), ThreadState::Ready);
+        assert_eq!(scheduler.schedule(), Some(ThreadId::Ppu(1)));
     }
 
     #[test]
@@ -564,4 +640,28 @@ mod tests {
         let thread3 = scheduler.schedule();
         assert_eq!(thread3, Some(ThreadId::Ppu(2)));
     }
+
+    #[test]
+    fn test_save_state_round_trip() {
+        let mut scheduler = Scheduler::new();
+        scheduler.add_thread(ThreadId::Ppu(0), 100);
+        scheduler.add_thread(ThreadId::Spu(0), 50);
+        scheduler.add_thread(ThreadId::Ppu(1), 150);
+        assert_eq!(scheduler.schedule(), Some(ThreadId::Spu(0)));
+        scheduler.set_thread_state(ThreadId::Ppu(1), ThreadState::Waiting);
+
+        let mut w = StateWriter::new();
+        scheduler.save_state(&mut w);
+        let data = w.into_bytes();
+
+        let mut loaded = Scheduler::new();
+        loaded.load_state(&mut StateReader::new(&data)).unwrap();
+        assert_eq!(loaded.thread_count(), 3);
+        assert_eq!(loaded.current_thread(), Some(ThreadId::Spu(0)));
+        assert_eq!(loaded.get_thread_state(ThreadId::Ppu(1)), Some(ThreadState::Waiting));
+        // The running thread goes back to the queue and the ready one runs next
+        assert_eq!(loaded.schedule(), Some(ThreadId::Spu(0)));
+        loaded.set_thread_state(ThreadId::Spu(0), ThreadState::Stopped);
+        assert_eq!(loaded.schedule(), Some(ThreadId::Ppu(0)));
+    }
 }
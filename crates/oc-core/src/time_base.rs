@@ -0,0 +1,244 @@
+//! Emulated clock shared by every subsystem that reads guest time
+//!
+//! The PPU time base register, LV2 system time and timers, GCM vblank
+//! timestamps and cellAudio block timing all read one [`TimeBase`] instead
+//! of the host clock, so they agree with each other, stop while the
+//! emulator is paused, follow the emulation speed and carry over through a
+//! savestate. Guest time only moves forward: pauses and speed changes
+//! rebase the clock at the host time they happen.
+
+use crate::savestate::{invalid, StateReader, StateWriter};
+use parking_lot::Mutex;
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// PS3 time base frequency (79.8 MHz)
+pub const TIMEBASE_FREQUENCY: u64 = 79_800_000;
+
+#[derive(Debug)]
+struct Clock {
+    /// Host time the clock was last rebased at
+    host_anchor: Instant,
+    /// Guest time at `host_anchor`
+    guest_anchor: Duration,
+    /// Guest time passing per unit of host time
+    speed: f64,
+    paused: bool,
+    /// Wall-clock time of guest time zero, in microseconds since the UNIX epoch
+    boot_time_us: u64,
+}
+
+impl Clock {
+    fn now(&self, host: Instant) -> Duration {
+        if self.paused {
+            return self.guest_anchor;
+        }
+        self.guest_anchor + host.saturating_duration_since(self.host_anchor).mul_f64(self.speed)
+    }
+
+    fn rebase(&mut self, host: Instant) {
+        self.guest_anchor = self.now(host);
+        self.host_anchor = host;
+    }
+}
+
+/// Emulated clock, starting at zero when created
+///
+/// Clones share the same clock.
+#[derive(Debug, Clone)]
+pub struct TimeBase {
+    clock: Arc<Mutex<Clock>>,
+}
+
+impl TimeBase {
+    /// Create a running clock at real-time speed
+    pub fn new() -> Self {
+        let boot_time_us = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+        Self {
+            clock: Arc::new(Mutex::new(Clock {
+                host_anchor: Instant::now(),
+                guest_anchor: Duration::ZERO,
+                speed: 1.0,
+                paused: false,
+                boot_time_us,
+            })),
+        }
+    }
+
+    /// Guest time since the clock started
+    pub fn now(&self) -> Duration {
+        self.clock.lock().now(Instant::now())
+    }
+
+    /// Guest time since the clock started, in microseconds
+    pub fn micros(&self) -> u64 {
+        self.now().as_micros() as u64
+    }
+
+    /// Time base register value, in ticks of [`TIMEBASE_FREQUENCY`]
+    pub fn ticks(&self) -> u64 {
+        (self.now().as_nanos() * TIMEBASE_FREQUENCY as u128 / 1_000_000_000) as u64
+    }
+
+    /// Guest wall-clock time in microseconds since the UNIX epoch
+    ///
+    /// Starts at the host time the clock was created and then follows guest time.
+    pub fn system_time_us(&self) -> u64 {
+        let clock = self.clock.lock();
+        clock.boot_time_us + clock.now(Instant::now()).as_micros() as u64
+    }
+
+    /// Stop guest time, e.g. while the emulator is paused
+    pub fn pause(&self) {
+        let mut clock = self.clock.lock();
+        if !clock.paused {
+            clock.rebase(Instant::now());
+            clock.paused = true;
+        }
+    }
+
+    /// Let guest time run again from where it was paused
+    pub fn resume(&self) {
+        let mut clock = self.clock.lock();
+        if clock.paused {
+            clock.paused = false;
+            clock.host_anchor = Instant::now();
+        }
+    }
+
+    /// Whether guest time is stopped
+    pub fn is_paused(&self) -> bool {
+        self.clock.lock().paused
+    }
+
+    /// Guest time passing per unit of host time
+    pub fn speed(&self) -> f64 {
+        self.clock.lock().speed
+    }
+
+    /// Scale guest time against host time; speeds that are not positive are ignored
+    pub fn set_speed(&self, speed: f64) {
+        if !(speed.is_finite() && speed > 0.0) {
+            return;
+        }
+        let mut clock = self.clock.lock();
+        clock.rebase(Instant::now());
+        clock.speed = speed;
+    }
+
+    /// Move guest time forward by `by`, e.g. when stepping frames while paused
+    pub fn advance(&self, by: Duration) {
+        self.clock.lock().guest_anchor += by;
+    }
+
+    /// Host time it takes for `guest` to pass at the current speed
+    pub fn host_duration(&self, guest: Duration) -> Duration {
+        guest.div_f64(self.clock.lock().speed)
+    }
+
+    /// Save guest time, speed and the wall-clock time it started at
+    pub fn save_state(&self, w: &mut StateWriter) {
+        let clock = self.clock.lock();
+        w.u64(clock.now(Instant::now()).as_nanos() as u64);
+        w.f64(clock.speed);
+        w.u64(clock.boot_time_us);
+    }
+
+    /// Restore the clock written by [`TimeBase::save_state`]
+    ///
+    /// Whether the clock is paused is left as it is.
+    pub fn load_state(&self, r: &mut StateReader) -> io::Result<()> {
+        let now = Duration::from_nanos(r.u64()?);
+        let speed = r.f64()?;
+        if !(speed.is_finite() && speed > 0.0) {
+            return Err(invalid(&format!("invalid clock speed {}", speed)));
+        }
+        let boot_time_us = r.u64()?;
+        let mut clock = self.clock.lock();
+        clock.host_anchor = Instant::now();
+        clock.guest_anchor = now;
+        clock.speed = speed;
+        clock.boot_time_us = boot_time_us;
+        Ok(())
+    }
+}
+
+impl Default for TimeBase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_base_pause_and_advance() {
+        let time_base = TimeBase::new();
+        time_base.pause();
+        let paused_at = time_base.now();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(time_base.now(), paused_at);
+
+        // Clones share the clock
+        let shared = time_base.clone();
+        shared.advance(Duration::from_secs(1));
+        assert_eq!(time_base.now(), paused_at + Duration::from_secs(1));
+
+        time_base.resume();
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(time_base.now() >= paused_at + Duration::from_millis(1005));
+    }
+
+    #[test]
+    fn test_time_base_ticks() {
+        let time_base = TimeBase::new();
+        time_base.pause();
+        let (ticks, micros, system_time) = (time_base.ticks(), time_base.micros(), time_base.system_time_us());
+        time_base.advance(Duration::from_secs(2));
+        assert_eq!(time_base.ticks() - ticks, 2 * TIMEBASE_FREQUENCY);
+        assert_eq!(time_base.micros() - micros, 2_000_000);
+        assert_eq!(time_base.system_time_us() - system_time, 2_000_000);
+    }
+
+    #[test]
+    fn test_time_base_speed() {
+        let time_base = TimeBase::new();
+        time_base.set_speed(2.0);
+        assert_eq!(time_base.host_duration(Duration::from_millis(100)), Duration::from_millis(50));
+        let start = time_base.now();
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(time_base.now() - start >= Duration::from_millis(20));
+
+        time_base.set_speed(0.0);
+        time_base.set_speed(f64::NAN);
+        assert_eq!(time_base.speed(), 2.0);
+    }
+
+    #[test]
+    fn test_time_base_savestate() {
+        let time_base = TimeBase::new();
+        time_base.pause();
+        time_base.advance(Duration::from_secs(10));
+        time_base.set_speed(0.5);
+        let mut w = StateWriter::new();
+        time_base.save_state(&mut w);
+        let data = w.into_bytes();
+
+        let restored = TimeBase::new();
+        restored.pause();
+        restored.load_state(&mut StateReader::new(&data)).unwrap();
+        assert_eq!(restored.now(), time_base.now());
+        assert_eq!(restored.speed(), 0.5);
+        assert_eq!(restored.system_time_us(), time_base.system_time_us());
+
+        let mut bad = data.clone();
+        bad[8..16].copy_from_slice(&(-1.0f64).to_bits().to_le_bytes());
+        assert!(restored.load_state(&mut StateReader::new(&bad)).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+//! Call stack unwinding for PPU threads
+//!
+//! Frames are found by following the back chain of the 64-bit PowerPC ELF
+//! ABI: every frame starts with a pointer to the caller's frame, and a
+//! non-leaf function saves its return address 16 bytes into the caller's
+//! frame. The innermost return address comes from LR while the current
+//! function hasn't saved it yet (leaf functions and prologues).
+
+use oc_memory::MemoryManager;
+use oc_ppu::thread::PpuRegisters;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Deepest backtrace produced
+pub const MAX_FRAMES: usize = 64;
+
+/// Offset of the LR save slot in a stack frame
+const LR_SAVE_OFFSET: u64 = 16;
+
+/// Without function bounds, an LR this close behind the PC is taken as a
+/// call made by the current function rather than its return address
+const NEAR_CALL_RANGE: u64 = 0x1000;
+
+/// A named function in guest memory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    /// Address of the first instruction
+    pub start: u64,
+    /// Size in bytes (0 if unknown)
+    pub size: u64,
+    pub name: String,
+}
+
+impl Function {
+    /// Whether `addr` lies inside the function
+    pub fn contains(&self, addr: u64) -> bool {
+        addr >= self.start && (self.size == 0 || addr < self.start + self.size)
+    }
+}
+
+/// Function names by address, from ELF symbols or code analysis
+#[derive(Debug, Clone, Default)]
+pub struct FunctionMap {
+    /// Sorted by start address
+    functions: Vec<Function>,
+}
+
+impl FunctionMap {
+    /// Create an empty map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a function, replacing any other one starting at the same address
+    pub fn insert(&mut self, start: u64, size: u64, name: impl Into<String>) {
+        let function = Function { start, size, name: name.into() };
+        match self.functions.binary_search_by_key(&start, |f| f.start) {
+            Ok(index) => self.functions[index] = function,
+            Err(index) => self.functions.insert(index, function),
+        }
+    }
+
+    /// Number of functions
+    pub fn len(&self) -> usize {
+        self.functions.len()
+    }
+
+    /// Whether no functions are known
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// Function containing `addr`
+    ///
+    /// A function of unknown size extends to the next one.
+    pub fn lookup(&self, addr: u64) -> Option<&Function> {
+        let index = self.functions.partition_point(|f| f.start <= addr).checked_sub(1)?;
+        Some(&self.functions[index]).filter(|f| f.contains(addr))
+    }
+}
+
+/// One frame of a backtrace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackFrame {
+    /// Instruction executing in this frame; the return address for callers
+    pub pc: u64,
+    /// Stack pointer of the frame
+    pub sp: u64,
+    /// Name of the function containing `pc` and the offset into it
+    pub function: Option<(String, u64)>,
+}
+
+impl StackFrame {
+    fn new(pc: u64, sp: u64, functions: &FunctionMap, is_return_address: bool) -> Self {
+        // A return address can be the first instruction after the function's last call
+        let lookup = if is_return_address { pc.saturating_sub(4) } else { pc };
+        let function = functions.lookup(lookup).map(|f| (f.name.clone(), pc - f.start));
+        Self { pc, sp, function }
+    }
+}
+
+impl fmt::Display for StackFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:08x} in ", self.pc)?;
+        match &self.function {
+            Some((name, 0)) => write!(f, "{}", name)?,
+            Some((name, offset)) => write!(f, "{}+0x{:x}", name, offset)?,
+            None => write!(f, "??")?,
+        }
+        write!(f, " (sp 0x{:08x})", self.sp)
+    }
+}
+
+/// Unwind the stack of a PPU thread, innermost frame first
+///
+/// `stack` bounds the walk when the thread's stack is known. Unwinding
+/// stops at a null, misaligned or non-increasing back chain, at an
+/// unreadable frame, or after [`MAX_FRAMES`] frames.
+pub fn unwind_ppu(
+    regs: &PpuRegisters,
+    memory: &MemoryManager,
+    stack: Option<RangeInclusive<u64>>,
+    functions: &FunctionMap,
+) -> Vec<StackFrame> {
+    let read = |addr: u64| u32::try_from(addr).ok().and_then(|addr| memory.read_be64(addr).ok());
+    let in_stack = |sp: u64| {
+        let in_bounds = match &stack {
+            Some(range) => range.contains(&sp),
+            None => true,
+        };
+        // Frames are quadword aligned
+        sp != 0 && sp & 0xF == 0 && in_bounds
+    };
+    let is_code = |addr: u64| addr != 0 && addr & 3 == 0 && addr <= u32::MAX as u64;
+
+    let pc = regs.cia;
+    let mut sp = regs.gpr[1];
+    let mut frames = vec![StackFrame::new(pc, sp, functions, false)];
+    if !in_stack(sp) {
+        return frames;
+    }
+
+    // The current function hasn't saved LR if it isn't the saved return address
+    // and doesn't point back into the function after a call
+    let saved_lr = read(sp).filter(|&caller| caller > sp).and_then(|caller| read(caller + LR_SAVE_OFFSET));
+    let lr = regs.lr;
+    let calls_back = match functions.lookup(pc) {
+        Some(function) => function.contains(lr.wrapping_sub(4)),
+        None => lr <= pc && pc - lr < NEAR_CALL_RANGE,
+    };
+    if is_code(lr) && Some(lr) != saved_lr && !calls_back {
+        frames.push(StackFrame::new(lr, sp, functions, true));
+    }
+
+    while frames.len() < MAX_FRAMES {
+        let Some(caller) = read(sp).filter(|&caller| caller > sp && in_stack(caller)) else {
+            break;
+        };
+        let Some(return_addr) = read(caller + LR_SAVE_OFFSET).filter(|&addr| is_code(addr)) else {
+            break;
+        };
+        frames.push(StackFrame::new(return_addr, caller, functions, true));
+        sp = caller;
+    }
+    frames
+}
+
+/// Render frames one per line, numbered from the innermost
+pub fn format_backtrace(frames: &[StackFrame]) -> String {
+    frames
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| format!("#{:<2} {}", i, frame))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oc_memory::PageFlags;
+
+    #[test]
+    fn test_function_lookup() {
+        let mut functions = FunctionMap::new();
+        functions.insert(0x2000, 0x40, "update");
+        functions.insert(0x1000, 0, "main");
+        assert_eq!(functions.lookup(0x1100).unwrap().name, "main");
+        assert_eq!(functions.lookup(0x203C).unwrap().name, "update");
+        assert!(functions.lookup(0x2040).is_none());
+        assert!(functions.lookup(0xFFF).is_none());
+    }
+
+    #[test]
+    fn test_unwind_back_chain() {
+        let memory = MemoryManager::new().unwrap();
+        let stack = memory.allocate(0x1000, 0x1000, PageFlags::RW).unwrap() as u64;
+        let mut functions = FunctionMap::new();
+        functions.insert(0x10000, 0x100, "main");
+        functions.insert(0x10100, 0x100, "game_loop");
+        functions.insert(0x10200, 0x100, "draw");
+
+        // main's frame at +0x200, game_loop's at +0x100 (return into main saved in main's frame)
+        let main_sp = stack + 0x200;
+        let loop_sp = stack + 0x100;
+        memory.write_be64(loop_sp as u32, main_sp).unwrap();
+        memory.write_be64((main_sp + 16) as u32, 0x10020).unwrap();
+        memory.write_be64(main_sp as u32, 0).unwrap();
+
+        // draw is a leaf called from game_loop: r1 is still game_loop's frame
+        let mut regs = PpuRegisters { cia: 0x10210, lr: 0x10148, ..Default::default() };
+        regs.gpr[1] = loop_sp;
+        let frames = unwind_ppu(&regs, &memory, Some(stack..=stack + 0x1000), &functions);
+        let pcs: Vec<_> = frames.iter().map(|frame| frame.pc).collect();
+        assert_eq!(pcs, [0x10210, 0x10148, 0x10020]);
+        assert_eq!(frames[1].function, Some(("game_loop".to_string(), 0x48)));
+        assert_eq!(frames[2].sp, main_sp);
+        assert!(format_backtrace(&frames).starts_with("#0  0x00010210 in draw+0x10 (sp "));
+
+        // After draw returned, LR points into game_loop itself and isn't a frame
+        regs.cia = 0x10150;
+        let frames = unwind_ppu(&regs, &memory, Some(stack..=stack + 0x1000), &functions);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].pc, 0x10020);
+
+        // A bad stack pointer leaves just the current frame
+        regs.gpr[1] = stack + 0x104;
+        assert_eq!(unwind_ppu(&regs, &memory, None, &functions).len(), 1);
+    }
+}
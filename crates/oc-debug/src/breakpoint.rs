@@ -1,5 +1,6 @@
 //! Breakpoint management for debugging
 
+use oc_core::condition::{Condition, ConditionContext};
 use std::collections::HashMap;
 
 /// Breakpoint type
@@ -26,10 +27,11 @@ pub struct Breakpoint {
     pub bp_type: BreakpointType,
     /// Whether the breakpoint is enabled
     pub enabled: bool,
-    /// Hit count (number of times breakpoint was triggered)
+    /// Hit count (number of times the breakpoint was reached while enabled,
+    /// whether or not its condition held)
     pub hit_count: u64,
-    /// Optional condition expression
-    pub condition: Option<String>,
+    /// Optional condition; the breakpoint only triggers while it is non-zero
+    pub condition: Option<Condition>,
     /// Optional description/label
     pub label: Option<String>,
 }
@@ -80,10 +82,28 @@ impl Breakpoint {
         self
     }
 
+    /// Set the breakpoint condition
+    pub fn with_condition(mut self, condition: Condition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
     /// Record a hit on this breakpoint
     pub fn record_hit(&mut self) {
         self.hit_count += 1;
     }
+
+    /// Record a hit and check whether the breakpoint triggers
+    fn trigger(&mut self, ctx: &dyn ConditionContext) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        self.record_hit();
+        match &self.condition {
+            Some(condition) => condition.is_met(ctx, self.hit_count),
+            None => true,
+        }
+    }
 }
 
 /// Breakpoint manager
@@ -187,43 +207,37 @@ impl BreakpointManager {
         }
     }
 
-    /// Check if there's an execution breakpoint at the given address
-    pub fn check_execution(&mut self, address: u64) -> Option<&mut Breakpoint> {
-        if let Some(&id) = self.execution_bp.get(&address) {
-            if let Some(bp) = self.breakpoints.get_mut(&id) {
-                if bp.enabled {
-                    bp.record_hit();
-                    return Some(bp);
-                }
-            }
+    /// Set or clear the condition of a breakpoint
+    pub fn set_condition(&mut self, id: u32, condition: Option<Condition>) -> bool {
+        if let Some(bp) = self.breakpoints.get_mut(&id) {
+            bp.condition = condition;
+            true
+        } else {
+            false
         }
-        None
     }
 
-    /// Check if there's a read breakpoint at the given address
-    pub fn check_read(&mut self, address: u64) -> Option<&mut Breakpoint> {
-        if let Some(&id) = self.read_bp.get(&address) {
-            if let Some(bp) = self.breakpoints.get_mut(&id) {
-                if bp.enabled {
-                    bp.record_hit();
-                    return Some(bp);
-                }
-            }
-        }
-        None
+    /// Check if an execution breakpoint at the given address triggers
+    pub fn check_execution(&mut self, address: u64, ctx: &dyn ConditionContext) -> Option<&mut Breakpoint> {
+        let id = self.execution_bp.get(&address).copied();
+        self.check(id, ctx)
     }
 
-    /// Check if there's a write breakpoint at the given address
-    pub fn check_write(&mut self, address: u64) -> Option<&mut Breakpoint> {
-        if let Some(&id) = self.write_bp.get(&address) {
-            if let Some(bp) = self.breakpoints.get_mut(&id) {
-                if bp.enabled {
-                    bp.record_hit();
-                    return Some(bp);
-                }
-            }
-        }
-        None
+    /// Check if a read breakpoint at the given address triggers
+    pub fn check_read(&mut self, address: u64, ctx: &dyn ConditionContext) -> Option<&mut Breakpoint> {
+        let id = self.read_bp.get(&address).copied();
+        self.check(id, ctx)
+    }
+
+    /// Check if a write breakpoint at the given address triggers
+    pub fn check_write(&mut self, address: u64, ctx: &dyn ConditionContext) -> Option<&mut Breakpoint> {
+        let id = self.write_bp.get(&address).copied();
+        self.check(id, ctx)
+    }
+
+    fn check(&mut self, id: Option<u32>, ctx: &dyn ConditionContext) -> Option<&mut Breakpoint> {
+        let bp = self.breakpoints.get_mut(&id?)?;
+        bp.trigger(ctx).then_some(bp)
     }
 
     /// Get all breakpoints
@@ -255,6 +269,19 @@ impl BreakpointManager {
 mod tests {
     use super::*;
 
+    /// Just r3, no memory
+    struct Registers(u64);
+
+    impl ConditionContext for Registers {
+        fn variable(&self, name: &str) -> Option<u64> {
+            (name == "r3").then_some(self.0)
+        }
+
+        fn read_memory(&self, _addr: u64, _size: usize) -> Option<u64> {
+            None
+        }
+    }
+
     #[test]
     fn test_breakpoint_creation() {
         let bp = Breakpoint::new_execution(0, 0x10000);
@@ -288,10 +315,10 @@ mod tests {
         mgr.add_execution_breakpoint(0x10000);
         
         // Should hit
-        assert!(mgr.check_execution(0x10000).is_some());
+        assert!(mgr.check_execution(0x10000, &Registers(0)).is_some());
         
         // Should not hit
-        assert!(mgr.check_execution(0x10004).is_none());
+        assert!(mgr.check_execution(0x10004, &Registers(0)).is_none());
     }
 
     #[test]
@@ -301,18 +328,34 @@ mod tests {
         let id = mgr.add_execution_breakpoint(0x10000);
         
         // Should hit when enabled
-        assert!(mgr.check_execution(0x10000).is_some());
+        assert!(mgr.check_execution(0x10000, &Registers(0)).is_some());
         
         // Disable
         mgr.disable_breakpoint(id);
         
         // Should not hit when disabled
-        assert!(mgr.check_execution(0x10000).is_none());
+        assert!(mgr.check_execution(0x10000, &Registers(0)).is_none());
         
         // Enable again
         mgr.enable_breakpoint(id);
         
         // Should hit again
-        assert!(mgr.check_execution(0x10000).is_some());
+        assert!(mgr.check_execution(0x10000, &Registers(0)).is_some());
+    }
+
+    #[test]
+    fn test_breakpoint_condition() {
+        let mut mgr = BreakpointManager::new();
+        let id = mgr.add_execution_breakpoint(0x10000);
+        mgr.set_condition(id, Some(Condition::parse("r3 == 1 || hits == 3").unwrap()));
+
+        assert!(mgr.check_execution(0x10000, &Registers(0)).is_none());
+        assert!(mgr.check_execution(0x10000, &Registers(1)).is_some());
+        assert!(mgr.check_execution(0x10000, &Registers(0)).is_some());
+        assert_eq!(mgr.get(id).unwrap().hit_count, 3);
+
+        // Conditions that can't be evaluated don't trigger
+        mgr.set_condition(id, Some(Condition::parse("r4 == 0").unwrap()));
+        assert!(mgr.check_execution(0x10000, &Registers(0)).is_none());
     }
 }
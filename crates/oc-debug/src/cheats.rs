@@ -0,0 +1,477 @@
+//! Cheat manager for the cheat engine
+//!
+//! A cheat is a list of memory writes applied every frame, which also keeps
+//! ("freezes") values the game keeps changing. Cheats are stored per game as
+//! TOML next to the configuration and keep their code as entered, in one
+//! of two formats:
+//!
+//! - NetCheat / Artemis codes, one operation per line: `0 AAAAAAAA VV..`
+//!   writes the hex bytes V at A, `1 AAAAAAAA text` writes text, `2 AAAAAAAA
+//!   1.5` writes an f32, `4 AAAAAAAA VVVVVVVV` followed by `4 NNNNIIII
+//!   QQQQQQQQ` writes the word V N times, stepping the address by I and the
+//!   value by Q, and `6 AAAAAAAA OOOOOOOO` makes the addresses of the
+//!   following lines offsets from the pointer at A plus O.
+//! - Plain `address value` pairs in hex, e.g. `0x00A1B2C0 0x0001869F`, where
+//!   the number of value digits gives its size.
+
+use oc_memory::MemoryManager;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Code format of a cheat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CheatFormat {
+    /// `address value` pairs
+    #[default]
+    Raw,
+    /// NetCheat / Artemis codes
+    NetCheat,
+}
+
+impl CheatFormat {
+    /// All formats, in display order
+    pub const ALL: [CheatFormat; 2] = [Self::Raw, Self::NetCheat];
+
+    /// Display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Raw => "Address/Value",
+            Self::NetCheat => "NetCheat/Artemis",
+        }
+    }
+
+    /// Guess the format of a code: NetCheat lines start with a one-digit code type
+    pub fn detect(code: &str) -> Self {
+        let netcheat = code_lines(code).all(|line| {
+            let mut fields = line.split_whitespace();
+            fields.next().is_some_and(|kind| kind.len() == 1) && fields.next().is_some_and(|addr| addr.len() == 8)
+        });
+        if netcheat && code_lines(code).next().is_some() {
+            Self::NetCheat
+        } else {
+            Self::Raw
+        }
+    }
+}
+
+/// One memory operation of a cheat
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheatOp {
+    /// Write bytes
+    Write { address: u32, bytes: Vec<u8> },
+    /// Write a big-endian word `count` times with stepped address and value
+    Fill { address: u32, value: u32, count: u32, address_step: u32, value_step: u32 },
+    /// Make later addresses relative to the pointer at `address` plus `offset`
+    Pointer { address: u32, offset: u32 },
+}
+
+/// A named cheat
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Cheat {
+    pub name: String,
+    /// Whether the cheat is applied every frame
+    pub enabled: bool,
+    pub format: CheatFormat,
+    /// Code as entered
+    pub code: String,
+}
+
+impl Default for Cheat {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            enabled: false,
+            format: CheatFormat::Raw,
+            code: String::new(),
+        }
+    }
+}
+
+impl Cheat {
+    /// Create a disabled cheat, checking that its code parses
+    pub fn new(name: &str, format: CheatFormat, code: &str) -> Result<Self, String> {
+        let cheat = Self {
+            name: name.to_string(),
+            enabled: false,
+            format,
+            code: code.trim().to_string(),
+        };
+        cheat.operations()?;
+        Ok(cheat)
+    }
+
+    /// An enabled cheat that keeps `bytes` at `address`
+    pub fn freeze(name: &str, address: u32, bytes: &[u8]) -> Self {
+        let value: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+        Self {
+            name: name.to_string(),
+            enabled: true,
+            format: CheatFormat::Raw,
+            code: format!("0x{:08X} 0x{}", address, value),
+        }
+    }
+
+    /// Parse the code into memory operations
+    pub fn operations(&self) -> Result<Vec<CheatOp>, String> {
+        let ops = match self.format {
+            CheatFormat::Raw => parse_raw(&self.code),
+            CheatFormat::NetCheat => parse_netcheat(&self.code),
+        }?;
+        if ops.is_empty() {
+            return Err("Cheat has no code".to_string());
+        }
+        Ok(ops)
+    }
+}
+
+/// Non-empty lines of a code, without `#` comments
+fn code_lines(code: &str) -> impl Iterator<Item = &str> {
+    code.lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+}
+
+fn parse_hex(text: &str) -> Option<u32> {
+    let digits = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")).unwrap_or(text);
+    u32::from_str_radix(digits, 16).ok()
+}
+
+/// Bytes of a hex string with an even number of digits
+fn parse_hex_bytes(text: &str) -> Option<Vec<u8>> {
+    let digits = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")).unwrap_or(text);
+    if digits.is_empty() || digits.len() & 1 != 0 {
+        return None;
+    }
+    digits
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+fn parse_raw(code: &str) -> Result<Vec<CheatOp>, String> {
+    code_lines(code)
+        .enumerate()
+        .map(|(i, line)| {
+            let error = || format!("Line {}: expected \"address value\" in hex: {}", i + 1, line);
+            let mut fields = line.split_whitespace();
+            let address = fields.next().and_then(parse_hex).ok_or_else(error)?;
+            let bytes = fields.next().and_then(parse_hex_bytes).ok_or_else(error)?;
+            if fields.next().is_some() {
+                return Err(error());
+            }
+            Ok(CheatOp::Write { address, bytes })
+        })
+        .collect()
+}
+
+fn parse_netcheat(code: &str) -> Result<Vec<CheatOp>, String> {
+    let mut ops = Vec::new();
+    let mut lines = code_lines(code).enumerate();
+    while let Some((i, line)) = lines.next() {
+        let error = |what: &str| format!("Line {}: {}: {}", i + 1, what, line);
+        let mut fields = line.splitn(3, char::is_whitespace);
+        let kind = fields.next().unwrap_or_default();
+        let address = fields.next().and_then(parse_hex).ok_or_else(|| error("bad address"))?;
+        let value = fields.next().map(str::trim).unwrap_or_default();
+        let op = match kind {
+            "0" => CheatOp::Write {
+                address,
+                bytes: parse_hex_bytes(value).ok_or_else(|| error("bad hex value"))?,
+            },
+            "1" if !value.is_empty() => CheatOp::Write { address, bytes: value.as_bytes().to_vec() },
+            "2" => {
+                let value: f32 = value.parse().map_err(|_| error("bad float"))?;
+                CheatOp::Write { address, bytes: value.to_be_bytes().to_vec() }
+            }
+            "4" => {
+                let value = parse_hex(value).ok_or_else(|| error("bad hex value"))?;
+                // The second line holds the count, steps and value increment
+                let (_, next) = lines.next().ok_or_else(|| error("missing second line of a multi-write"))?;
+                let mut next = next.split_whitespace().skip(1);
+                let counts = next.next().and_then(parse_hex).ok_or_else(|| error("bad multi-write count"))?;
+                let value_step = next.next().and_then(parse_hex).ok_or_else(|| error("bad multi-write increment"))?;
+                CheatOp::Fill {
+                    address,
+                    value,
+                    count: counts >> 16,
+                    address_step: counts & 0xFFFF,
+                    value_step,
+                }
+            }
+            "6" => CheatOp::Pointer {
+                address,
+                offset: parse_hex(value).ok_or_else(|| error("bad pointer offset"))?,
+            },
+            _ => return Err(error("unsupported code type")),
+        };
+        ops.push(op);
+    }
+    Ok(ops)
+}
+
+/// Per-game file in `directory`
+pub(crate) fn title_file(directory: &Path, title_id: &str) -> PathBuf {
+    // Title IDs are alphanumeric; anything else must not escape the directory
+    let name: String = title_id.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_').collect();
+    directory.join(format!("{}.toml", name))
+}
+
+/// On-disk layout of a game's cheat list
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct CheatFile {
+    cheat: Vec<Cheat>,
+}
+
+/// Cheats of the running game
+#[derive(Debug, Default)]
+pub struct CheatManager {
+    /// Title the list belongs to (None until a game is loaded)
+    title_id: Option<String>,
+    /// Cheats with their parsed operations
+    cheats: Vec<(Cheat, Vec<CheatOp>)>,
+}
+
+impl CheatManager {
+    /// Create an empty cheat list
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Directory the per-game cheat lists are stored in
+    pub fn directory() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("oxidized-cell")
+            .join("cheats")
+    }
+
+    /// Switch to the saved cheats of a game, returning how many were loaded
+    ///
+    /// The list starts empty if there is no file; the file is written on
+    /// the next change.
+    pub fn load_for_title(&mut self, title_id: &str) -> Result<usize, String> {
+        self.title_id = Some(title_id.to_string());
+        self.cheats.clear();
+        let path = self.path().unwrap_or_default();
+        if !path.exists() {
+            return Ok(0);
+        }
+        self.load_file(&path)
+    }
+
+    /// Add the cheats of a TOML cheat list file
+    fn load_file(&mut self, path: &Path) -> Result<usize, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let file: CheatFile = toml::from_str(&text).map_err(|e| format!("Invalid cheat list {}: {}", path.display(), e))?;
+        let count = file.cheat.len();
+        for cheat in file.cheat {
+            match cheat.operations() {
+                Ok(ops) => self.cheats.push((cheat, ops)),
+                // Keep broken entries so saving doesn't lose them
+                Err(e) => {
+                    tracing::warn!("Cheat \"{}\" disabled: {}", cheat.name, e);
+                    self.cheats.push((Cheat { enabled: false, ..cheat }, Vec::new()));
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    /// Title of the loaded list
+    pub fn title_id(&self) -> Option<&str> {
+        self.title_id.as_deref()
+    }
+
+    /// File the list is saved to
+    pub fn path(&self) -> Option<PathBuf> {
+        Some(title_file(&Self::directory(), self.title_id.as_deref()?))
+    }
+
+    /// Save the list of the current game
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = self.path() else {
+            return Err("No game is loaded".to_string());
+        };
+        self.save_to(&path)
+    }
+
+    fn save_to(&self, path: &Path) -> Result<(), String> {
+        let file = CheatFile { cheat: self.cheats.iter().map(|(cheat, _)| cheat.clone()).collect() };
+        let text = toml::to_string_pretty(&file).map_err(|e| e.to_string())?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        std::fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// All cheats
+    pub fn cheats(&self) -> impl Iterator<Item = &Cheat> {
+        self.cheats.iter().map(|(cheat, _)| cheat)
+    }
+
+    /// Number of cheats
+    pub fn len(&self) -> usize {
+        self.cheats.len()
+    }
+
+    /// Whether there are no cheats
+    pub fn is_empty(&self) -> bool {
+        self.cheats.is_empty()
+    }
+
+    /// Add a cheat, returning its index
+    pub fn add(&mut self, cheat: Cheat) -> Result<usize, String> {
+        let ops = cheat.operations()?;
+        self.cheats.push((cheat, ops));
+        Ok(self.cheats.len() - 1)
+    }
+
+    /// Remove a cheat
+    pub fn remove(&mut self, index: usize) -> Option<Cheat> {
+        (index < self.cheats.len()).then(|| self.cheats.remove(index).0)
+    }
+
+    /// Turn a cheat on or off; cheats whose code doesn't parse stay off
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) {
+        if let Some((cheat, ops)) = self.cheats.get_mut(index) {
+            cheat.enabled = enabled && !ops.is_empty();
+        }
+    }
+
+    /// Import cheats from text
+    ///
+    /// Cheats are separated by blank lines; a first line that isn't code is
+    /// the cheat's name. The format of each cheat is detected from its code.
+    /// Returns how many cheats were added.
+    pub fn import(&mut self, text: &str) -> Result<usize, String> {
+        let mut imported = Vec::new();
+        for block in text.split("\n\n").map(str::trim).filter(|block| !block.is_empty()) {
+            let (first, rest) = block.split_once('\n').unwrap_or((block, ""));
+            let first = first.trim();
+            let starts_with_code = first.split_whitespace().next().is_some_and(|field| {
+                parse_hex(field).is_some()
+            }) && first.split_whitespace().count() >= 2;
+            let (name, code) = if starts_with_code {
+                (format!("Cheat {}", self.cheats.len() + imported.len() + 1), block)
+            } else {
+                (first.trim_start_matches(['[', '#']).trim_end_matches(']').trim().to_string(), rest)
+            };
+            let cheat = Cheat::new(&name, CheatFormat::detect(code), code).map_err(|e| format!("{}: {}", name, e))?;
+            imported.push(cheat);
+        }
+        let count = imported.len();
+        for cheat in imported {
+            self.add(cheat)?;
+        }
+        Ok(count)
+    }
+
+    /// Apply the enabled cheats, returning how many writes failed
+    pub fn apply(&self, memory: &MemoryManager) -> usize {
+        let mut failed = 0;
+        for (_, ops) in self.cheats.iter().filter(|(cheat, _)| cheat.enabled) {
+            let mut base = 0u32;
+            for op in ops {
+                let ok = match *op {
+                    CheatOp::Write { address, ref bytes } => {
+                        memory.write_bytes(base.wrapping_add(address), bytes).is_ok()
+                    }
+                    CheatOp::Fill { address, value, count, address_step, value_step } => (0..count).all(|n| {
+                        let address = base.wrapping_add(address).wrapping_add(n.wrapping_mul(address_step));
+                        memory.write_be32(address, value.wrapping_add(n.wrapping_mul(value_step))).is_ok()
+                    }),
+                    CheatOp::Pointer { address, offset } => match memory.read_be32(address) {
+                        Ok(pointer) => {
+                            base = pointer.wrapping_add(offset);
+                            true
+                        }
+                        Err(_) => false,
+                    },
+                };
+                if !ok {
+                    failed += 1;
+                }
+            }
+        }
+        failed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oc_memory::PageFlags;
+
+    #[test]
+    fn test_parse_formats() {
+        let raw = Cheat::new("Money", CheatFormat::Raw, "0x10000 0x0098967F\n00010010 FF # max").unwrap();
+        assert_eq!(
+            raw.operations().unwrap(),
+            [
+                CheatOp::Write { address: 0x10000, bytes: vec![0x00, 0x98, 0x96, 0x7F] },
+                CheatOp::Write { address: 0x10010, bytes: vec![0xFF] },
+            ]
+        );
+
+        let code = "0 00010000 0001\n1 00010004 AB C\n2 00010008 1.0\n4 00010100 00000001\n4 00030004 00000010\n6 00020000 00000008";
+        assert_eq!(CheatFormat::detect(code), CheatFormat::NetCheat);
+        assert_eq!(CheatFormat::detect("0x10000 0x01"), CheatFormat::Raw);
+        let ops = Cheat::new("", CheatFormat::NetCheat, code).unwrap().operations().unwrap();
+        assert_eq!(ops[1], CheatOp::Write { address: 0x10004, bytes: b"AB C".to_vec() });
+        assert_eq!(ops[2], CheatOp::Write { address: 0x10008, bytes: vec![0x3F, 0x80, 0, 0] });
+        assert_eq!(
+            ops[3],
+            CheatOp::Fill { address: 0x10100, value: 1, count: 3, address_step: 4, value_step: 0x10 }
+        );
+        assert_eq!(ops[4], CheatOp::Pointer { address: 0x20000, offset: 8 });
+
+        assert!(Cheat::new("", CheatFormat::Raw, "0x10000 0x123").is_err());
+        assert!(Cheat::new("", CheatFormat::NetCheat, "9 00010000 00").is_err());
+        assert!(Cheat::new("", CheatFormat::NetCheat, "4 00010000 00000001").is_err());
+        assert!(Cheat::new("", CheatFormat::Raw, "").is_err());
+    }
+
+    #[test]
+    fn test_apply_and_persist() {
+        let memory = MemoryManager::new().unwrap();
+        let base = memory.allocate(0x1000, 0x1000, PageFlags::RW).unwrap();
+        let mut cheats = CheatManager::new();
+        let text = format!(
+            "Infinite Lives\n0 {:08X} 00000009\n\n0x{:08X} 0x0102\n\n[Pointer]\n6 {:08X} 00000010\n0 00000000 AA",
+            base,
+            base + 8,
+            base + 0x20
+        );
+        assert_eq!(cheats.import(&text), Ok(3));
+        assert_eq!(cheats.cheats().map(|c| c.name.as_str()).collect::<Vec<_>>(), ["Infinite Lives", "Cheat 2", "Pointer"]);
+
+        // Nothing is written until enabled
+        assert_eq!(cheats.apply(&memory), 0);
+        assert_eq!(memory.read_be32(base).unwrap(), 0);
+        memory.write_be32(base + 0x20, base + 0x100).unwrap();
+        for i in 0..3 {
+            cheats.set_enabled(i, true);
+        }
+        memory.write_be32(base, 2).unwrap();
+        assert_eq!(cheats.apply(&memory), 0);
+        assert_eq!(memory.read_be32(base).unwrap(), 9);
+        assert_eq!(memory.read_be16(base + 8).unwrap(), 0x0102);
+        assert_eq!(memory.read::<u8>(base + 0x110).unwrap(), 0xAA);
+
+        // Freezing a scan result keeps its value after the game changes it
+        cheats.add(Cheat::freeze("Health", base + 0x40, &100u32.to_be_bytes())).unwrap();
+        memory.write_be32(base + 0x40, 3).unwrap();
+        cheats.apply(&memory);
+        assert_eq!(memory.read_be32(base + 0x40).unwrap(), 100);
+
+        let path = std::env::temp_dir().join(format!("oc-cheats-{}.toml", std::process::id()));
+        cheats.save_to(&path).unwrap();
+        let mut loaded = CheatManager::new();
+        assert_eq!(loaded.load_file(&path), Ok(4));
+        let _ = std::fs::remove_file(&path);
+        assert!(loaded.cheats().eq(cheats.cheats()));
+    }
+}
@@ -0,0 +1,280 @@
+//! Basic block coverage of guest code, exported for disassembler colorizers
+//!
+//! Coverage is written as a drcov file, the format read by Lighthouse (IDA)
+//! and Dragon Dance/Cartographer (Ghidra), and as a plain `module+offset` list.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Address range an executable or PRX module occupies
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleRange {
+    /// File the module was loaded from
+    pub path: String,
+    /// First address of the module
+    pub start: u32,
+    /// End of the module (exclusive)
+    pub end: u32,
+}
+
+impl ModuleRange {
+    /// File name of the module, which colorizers match loaded databases by
+    pub fn name(&self) -> &str {
+        self.path.rsplit(['/', '\\']).next().unwrap_or(&self.path)
+    }
+
+    /// Whether `address` lies in the module
+    pub fn contains(&self, address: u32) -> bool {
+        (self.start..self.end).contains(&address)
+    }
+}
+
+impl fmt::Display for ModuleRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} [0x{:08x}-0x{:08x})", self.path, self.start, self.end)
+    }
+}
+
+/// A basic block that executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub start: u32,
+    /// Size in bytes
+    pub size: u32,
+    /// Times the block was entered
+    pub hits: u64,
+}
+
+/// Totals of a coverage recording
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoverageSummary {
+    /// Distinct basic blocks executed
+    pub blocks: usize,
+    /// Blocks outside every module, left out of the drcov file
+    pub unmapped_blocks: usize,
+}
+
+/// Whether a PPU instruction ends a basic block
+fn ends_block(opcode: u32) -> bool {
+    match opcode >> 26 {
+        // bc, sc, b
+        16..=18 => true,
+        // bclr, bcctr
+        19 => matches!((opcode >> 1) & 0x3FF, 16 | 528),
+        _ => false,
+    }
+}
+
+/// Records which basic blocks of the loaded modules executed
+#[derive(Debug, Default)]
+pub struct Coverage {
+    modules: Vec<ModuleRange>,
+    /// Executed blocks by start address: size in bytes and hits
+    blocks: HashMap<u32, (u32, u64)>,
+    /// Block each thread is in: its start and the address of the next instruction
+    open: HashMap<u32, (u32, u32)>,
+}
+
+impl Coverage {
+    /// Start recording coverage of `modules`
+    pub fn new(modules: Vec<ModuleRange>) -> Self {
+        Self { modules, ..Self::default() }
+    }
+
+    /// Modules coverage is reported against
+    pub fn modules(&self) -> &[ModuleRange] {
+        &self.modules
+    }
+
+    /// Count the instruction `thread` is about to execute
+    pub fn record(&mut self, thread: u32, pc: u32, opcode: u32) {
+        let (start, next) = self.open.entry(thread).or_insert((pc, pc));
+        // Anything but the next instruction starts a new block, e.g. a taken branch
+        if *next != pc {
+            Self::close(&mut self.blocks, *start, *next);
+            *start = pc;
+        }
+        *next = pc.wrapping_add(4);
+        if ends_block(opcode) {
+            Self::close(&mut self.blocks, *start, *next);
+            self.open.remove(&thread);
+        }
+    }
+
+    /// Add a block that ran from `start` up to `end`
+    fn close(blocks: &mut HashMap<u32, (u32, u64)>, start: u32, end: u32) {
+        let size = end.wrapping_sub(start);
+        if size == 0 {
+            return;
+        }
+        let block = blocks.entry(start).or_insert((size, 0));
+        block.0 = block.0.max(size);
+        block.1 += 1;
+    }
+
+    /// Close the blocks threads are still in
+    pub fn flush(&mut self) {
+        for (_, (start, next)) in self.open.drain() {
+            Self::close(&mut self.blocks, start, next);
+        }
+    }
+
+    /// Executed blocks by address
+    pub fn blocks(&self) -> Vec<BasicBlock> {
+        let mut blocks: Vec<_> = self
+            .blocks
+            .iter()
+            .map(|(&start, &(size, hits))| BasicBlock { start, size, hits })
+            .collect();
+        blocks.sort_by_key(|block| block.start);
+        blocks
+    }
+
+    /// Number of distinct blocks executed
+    pub fn block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Index of the module holding `address`
+    fn module_of(&self, address: u32) -> Option<usize> {
+        self.modules.iter().position(|module| module.contains(address))
+    }
+
+    /// Coverage as a drcov (version 2) file
+    pub fn to_drcov(&self) -> Vec<u8> {
+        // drcov sizes are 16 bits, so longer blocks become several entries
+        let mut entries = Vec::new();
+        for block in self.blocks() {
+            let Some(id) = self.module_of(block.start) else {
+                continue;
+            };
+            let base = self.modules[id].start;
+            let mut offset = block.start - base;
+            let mut left = block.size;
+            while left > 0 {
+                let size = left.min(u16::MAX as u32 & !3);
+                entries.push((offset, size as u16, id as u16));
+                offset += size;
+                left -= size;
+            }
+        }
+
+        let mut text = String::from("DRCOV VERSION: 2\nDRCOV FLAVOR: drcov\n");
+        let _ = writeln!(text, "Module Table: version 2, count {}", self.modules.len());
+        text.push_str("Columns: id, base, end, entry, checksum, timestamp, path\n");
+        for (id, module) in self.modules.iter().enumerate() {
+            let _ = writeln!(
+                text,
+                "{:>3}, 0x{:016x}, 0x{:016x}, 0x{:016x}, 0x{:08x}, 0x{:08x}, {}",
+                id, module.start, module.end, 0, 0, 0, module.path
+            );
+        }
+        let _ = writeln!(text, "BB Table: {} bbs", entries.len());
+
+        let mut data = text.into_bytes();
+        for (offset, size, id) in entries {
+            data.extend_from_slice(&offset.to_le_bytes());
+            data.extend_from_slice(&size.to_le_bytes());
+            data.extend_from_slice(&id.to_le_bytes());
+        }
+        data
+    }
+
+    /// Coverage as `module+offset` lines, one per block, with absolute addresses outside modules
+    pub fn to_module_offsets(&self) -> String {
+        let mut text = String::new();
+        for block in self.blocks() {
+            let _ = match self.module_of(block.start) {
+                Some(id) => {
+                    let module = &self.modules[id];
+                    writeln!(text, "{}+{:x}", module.name(), block.start - module.start)
+                }
+                None => writeln!(text, "0x{:08x}", block.start),
+            };
+        }
+        text
+    }
+
+    /// Write the drcov file to `path` and the offset list next to it as `.txt`
+    pub fn write(&mut self, path: &Path) -> Result<CoverageSummary, String> {
+        self.flush();
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)
+                .map_err(|e| format!("Failed to create {}: {}", directory.display(), e))?;
+        }
+        std::fs::write(path, self.to_drcov()).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        let list = path.with_extension("txt");
+        std::fs::write(&list, self.to_module_offsets())
+            .map_err(|e| format!("Failed to write {}: {}", list.display(), e))?;
+        Ok(CoverageSummary {
+            blocks: self.blocks.len(),
+            unmapped_blocks: self.blocks.keys().filter(|&&start| self.module_of(start).is_none()).count(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOP: u32 = 0x6000_0000;
+    const BLR: u32 = 0x4E80_0020;
+
+    #[test]
+    fn test_basic_blocks() {
+        let module = ModuleRange { path: String::from("/dev_hdd0/game/TEST00000/USRDIR/EBOOT.BIN"), start: 0x10000, end: 0x20000 };
+        assert_eq!(module.name(), "EBOOT.BIN");
+        let mut coverage = Coverage::new(vec![module]);
+
+        // Thread 0 runs a block ending in blr twice; thread 1 jumps into its middle
+        for _ in 0..2 {
+            coverage.record(0, 0x10000, NOP);
+            coverage.record(0, 0x10004, NOP);
+            coverage.record(0, 0x10008, BLR);
+        }
+        coverage.record(1, 0x10004, NOP);
+        // Falling out of line without a branch, e.g. after an interrupt
+        coverage.record(1, 0x10100, NOP);
+        coverage.record(1, 0x30000, NOP);
+        coverage.flush();
+
+        let blocks = coverage.blocks();
+        assert_eq!(
+            blocks,
+            [
+                BasicBlock { start: 0x10000, size: 12, hits: 2 },
+                BasicBlock { start: 0x10004, size: 4, hits: 1 },
+                BasicBlock { start: 0x10100, size: 4, hits: 1 },
+                BasicBlock { start: 0x30000, size: 4, hits: 1 },
+            ]
+        );
+        assert_eq!(coverage.to_module_offsets(), "EBOOT.BIN+0\nEBOOT.BIN+4\nEBOOT.BIN+100\n0x00030000\n");
+    }
+
+    #[test]
+    fn test_drcov_export() {
+        let module = ModuleRange { path: String::from("EBOOT.BIN"), start: 0x10000, end: 0x20000 };
+        let mut coverage = Coverage::new(vec![module]);
+        coverage.record(0, 0x10010, NOP);
+        coverage.record(0, 0x10014, BLR);
+        coverage.record(0, 0x40000, BLR);
+
+        let data = coverage.to_drcov();
+        let header = "BB Table: 1 bbs\n";
+        let table = data.windows(header.len()).position(|w| w == header.as_bytes()).unwrap() + header.len();
+        let text = std::str::from_utf8(&data[..table]).unwrap();
+        assert!(text.starts_with("DRCOV VERSION: 2\n"));
+        assert!(text.contains("Module Table: version 2, count 1\n"));
+        assert!(text.contains("  0, 0x0000000000010000, 0x0000000000020000, "));
+        assert_eq!(&data[table..], [0x10, 0, 0, 0, 8, 0, 0, 0]);
+
+        let path = std::env::temp_dir().join(format!("oc_coverage_test_{}", std::process::id())).join("test.drcov");
+        let summary = coverage.write(&path).unwrap();
+        assert_eq!(summary, CoverageSummary { blocks: 2, unmapped_blocks: 1 });
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+        assert_eq!(std::fs::read_to_string(path.with_extension("txt")).unwrap(), "EBOOT.BIN+10\n0x00040000\n");
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,297 @@
+//! Crash dumps written when a guest thread faults or the emulator panics
+
+use crate::backtrace::{format_backtrace, StackFrame};
+use crate::disassembler::PpuDisassembler;
+use oc_memory::MemoryManager;
+use oc_ppu::PpuThread;
+use parking_lot::Mutex;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Bytes of memory dumped on each side of the faulting PC
+pub const MEMORY_EXCERPT_RADIUS: u32 = 64;
+
+/// Panic message of the last host panic, until taken
+static LAST_PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+/// What caused a crash
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrashKind {
+    /// An unrecoverable fault of a guest thread
+    GuestFault,
+    /// A panic in the emulator itself
+    HostPanic,
+}
+
+impl CrashKind {
+    /// Display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::GuestFault => "Guest fault",
+            Self::HostPanic => "Emulator panic",
+        }
+    }
+}
+
+/// State of one PPU thread at the time of a crash
+#[derive(Debug, Clone)]
+pub struct ThreadDump {
+    pub id: u32,
+    pub name: String,
+    /// Whether this thread caused the crash
+    pub faulting: bool,
+    pub pc: u64,
+    pub gpr: [u64; 32],
+    pub lr: u64,
+    pub ctr: u64,
+    pub cr: u32,
+    pub xer: u64,
+    /// Innermost frame first
+    pub backtrace: Vec<StackFrame>,
+}
+
+impl ThreadDump {
+    /// Capture a thread's registers along with its unwound stack
+    pub fn new(thread: &PpuThread, backtrace: Vec<StackFrame>, faulting: bool) -> Self {
+        Self {
+            id: thread.id,
+            name: thread.name.clone(),
+            faulting,
+            pc: thread.pc(),
+            gpr: thread.regs.gpr,
+            lr: thread.regs.lr,
+            ctr: thread.regs.ctr,
+            cr: thread.regs.cr,
+            xer: thread.regs.xer,
+            backtrace,
+        }
+    }
+}
+
+/// Everything known about the emulator when it crashed
+#[derive(Debug, Clone)]
+pub struct CrashDump {
+    pub kind: CrashKind,
+    /// Error or panic message
+    pub reason: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Title ID of the running game, if known
+    pub title_id: Option<String>,
+    pub threads: Vec<ThreadDump>,
+    /// Executable and PRX modules of the running game
+    pub modules: Vec<String>,
+    /// Last log lines, oldest first
+    pub log: Vec<String>,
+    /// Address of the first word in `memory`
+    pub memory_base: u32,
+    /// Words around the faulting PC (None where unreadable)
+    pub memory: Vec<Option<u32>>,
+}
+
+/// Summary of a crash dump that was taken
+#[derive(Debug, Clone)]
+pub struct CrashReport {
+    pub kind: CrashKind,
+    /// A few lines describing the crash
+    pub summary: String,
+    /// File the dump was written to (None if writing failed or was disabled)
+    pub path: Option<PathBuf>,
+}
+
+impl CrashDump {
+    /// Create an empty dump stamped with the current time
+    pub fn new(kind: CrashKind, reason: &str) -> Self {
+        Self {
+            kind,
+            reason: reason.to_string(),
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            title_id: None,
+            threads: Vec::new(),
+            modules: Vec::new(),
+            log: Vec::new(),
+            memory_base: 0,
+            memory: Vec::new(),
+        }
+    }
+
+    /// The thread that caused the crash
+    pub fn faulting_thread(&self) -> Option<&ThreadDump> {
+        self.threads.iter().find(|thread| thread.faulting)
+    }
+
+    /// Read the words around `pc`
+    pub fn capture_memory(&mut self, memory: &MemoryManager, pc: u32) {
+        let pc = pc & !3;
+        self.memory_base = pc.saturating_sub(MEMORY_EXCERPT_RADIUS);
+        let end = pc.saturating_add(MEMORY_EXCERPT_RADIUS);
+        self.memory = (self.memory_base..end)
+            .step_by(4)
+            .map(|addr| memory.read_be32(addr).ok())
+            .collect();
+    }
+
+    /// Short description for dialogs and logs
+    pub fn summary(&self) -> String {
+        // Panic messages carry a backtrace after the first line
+        let reason = self.reason.lines().next().unwrap_or_default();
+        let mut summary = format!("{}: {}", self.kind.name(), reason);
+        if let Some(thread) = self.faulting_thread() {
+            let location = thread
+                .backtrace
+                .first()
+                .map(|frame| frame.to_string())
+                .unwrap_or_else(|| format!("0x{:08x}", thread.pc));
+            let _ = write!(summary, "\nThread {} \"{}\" at {}", thread.id, thread.name, location);
+        }
+        if let Some(title_id) = &self.title_id {
+            let _ = write!(summary, "\nGame: {}", title_id);
+        }
+        summary
+    }
+
+    /// Full plain-text report
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "oxidized-cell crash dump");
+        let _ = writeln!(text, "Time: {} (Unix)", self.timestamp);
+        let _ = writeln!(text, "Kind: {}", self.kind.name());
+        let _ = writeln!(text, "Reason: {}", self.reason);
+        if let Some(title_id) = &self.title_id {
+            let _ = writeln!(text, "Game: {}", title_id);
+        }
+
+        let _ = writeln!(text, "\n== Modules ==");
+        for module in &self.modules {
+            let _ = writeln!(text, "{}", module);
+        }
+
+        for thread in &self.threads {
+            let marker = if thread.faulting { " (faulting)" } else { "" };
+            let _ = writeln!(text, "\n== PPU thread {} \"{}\"{} ==", thread.id, thread.name, marker);
+            let _ = writeln!(
+                text,
+                "pc  {:016x}  lr  {:016x}  ctr {:016x}\ncr  {:08x}          xer {:016x}",
+                thread.pc, thread.lr, thread.ctr, thread.cr, thread.xer
+            );
+            for (i, pair) in thread.gpr.chunks(4).enumerate() {
+                let row: Vec<String> = pair
+                    .iter()
+                    .enumerate()
+                    .map(|(j, value)| format!("r{:<2} {:016x}", i * 4 + j, value))
+                    .collect();
+                let _ = writeln!(text, "{}", row.join("  "));
+            }
+            let _ = writeln!(text, "Backtrace:\n{}", format_backtrace(&thread.backtrace));
+        }
+
+        if !self.memory.is_empty() {
+            let pc = self.faulting_thread().map(|thread| thread.pc as u32);
+            let _ = writeln!(text, "\n== Memory around PC ==");
+            for (i, word) in self.memory.iter().enumerate() {
+                let addr = self.memory_base.wrapping_add(i as u32 * 4);
+                let marker = if Some(addr) == pc { ">" } else { " " };
+                match word {
+                    Some(word) => {
+                        let instruction = PpuDisassembler::disassemble(addr as u64, *word);
+                        let _ = writeln!(text, "{}{:08x}: {:08x}  {}", marker, addr, word, instruction.to_string());
+                    }
+                    None => {
+                        let _ = writeln!(text, "{}{:08x}: ????????", marker, addr);
+                    }
+                }
+            }
+        }
+
+        let _ = writeln!(text, "\n== Log ==");
+        for line in &self.log {
+            let _ = writeln!(text, "{}", line);
+        }
+        text
+    }
+
+    /// Write the report into `directory`, returning the file written
+    pub fn write(&self, directory: &Path) -> Result<PathBuf, String> {
+        std::fs::create_dir_all(directory)
+            .map_err(|e| format!("Failed to create {}: {}", directory.display(), e))?;
+        let title = self.title_id.as_deref().unwrap_or("unknown");
+        let mut path = directory.join(format!("crash_{}_{}.txt", title, self.timestamp));
+        let mut n = 1;
+        while path.exists() {
+            path = directory.join(format!("crash_{}_{}_{}.txt", title, self.timestamp, n));
+            n += 1;
+        }
+        std::fs::write(&path, self.to_text()).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+}
+
+/// Remember the message of host panics for `take_panic`, keeping the previous hook
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let location = info
+            .location()
+            .map(|location| format!(" at {}:{}", location.file(), location.line()))
+            .unwrap_or_default();
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| String::from("unknown panic"));
+        let backtrace = std::backtrace::Backtrace::force_capture();
+        *LAST_PANIC.lock() = Some(format!("{}{}\n{}", message, location, backtrace));
+        previous(info);
+    }));
+}
+
+/// Take the message of the last host panic
+pub fn take_panic() -> Option<String> {
+    LAST_PANIC.lock().take()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oc_memory::PageFlags;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_crash_dump_report() {
+        let memory = MemoryManager::new().unwrap();
+        let base = memory.allocate(0x1000, 0x1000, PageFlags::RWX).unwrap();
+        let pc = base + 0x100;
+        memory.write_be32(pc, 0x6000_0000).unwrap();
+
+        let mut thread = PpuThread::new(0, Arc::clone(&memory));
+        thread.name = String::from("main");
+        thread.set_pc(pc as u64);
+        thread.set_gpr(3, 0x1234);
+
+        let mut dump = CrashDump::new(CrashKind::GuestFault, "Invalid instruction");
+        dump.title_id = Some(String::from("BLUS00001"));
+        dump.threads.push(ThreadDump::new(&thread, Vec::new(), true));
+        dump.modules.push(String::from("EBOOT.BIN"));
+        dump.log.push(String::from("ERROR ppu: boom"));
+        dump.capture_memory(&memory, pc);
+        assert_eq!(dump.memory.len(), (MEMORY_EXCERPT_RADIUS / 2) as usize);
+        assert_eq!(dump.memory[(MEMORY_EXCERPT_RADIUS / 4) as usize], Some(0x6000_0000));
+
+        assert!(dump.summary().contains(&format!("Thread 0 \"main\" at 0x{:08x}", pc)));
+        let text = dump.to_text();
+        assert!(text.contains("(faulting)"));
+        assert!(text.contains("r3  0000000000001234"));
+        assert!(text.contains(&format!(">{:08x}: 60000000", pc)));
+        assert!(text.contains("EBOOT.BIN"));
+        assert!(text.contains("ERROR ppu: boom"));
+
+        let directory = std::env::temp_dir().join(format!("oc_crash_dump_test_{}", std::process::id()));
+        let first = dump.write(&directory).unwrap();
+        let second = dump.write(&directory).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), text);
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
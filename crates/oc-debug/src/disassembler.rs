@@ -747,6 +747,18 @@ impl SpuDisassembler {
             0b00000001100 => {
                 return ("dsync".to_string(), String::new());
             }
+            0b00000001101 => {
+                let (_, ca, rt) = SpuDecoder::rr_form(opcode);
+                return ("rdch".to_string(), format!("${}, $ch{}", rt, ca));
+            }
+            0b00000001111 => {
+                let (_, ca, rt) = SpuDecoder::rr_form(opcode);
+                return ("rchcnt".to_string(), format!("${}, $ch{}", rt, ca));
+            }
+            0b00100001101 => {
+                let (_, ca, rt) = SpuDecoder::rr_form(opcode);
+                return ("wrch".to_string(), format!("$ch{}, ${}", ca, rt));
+            }
             0b00000000010 => {
                 return ("lnop".to_string(), String::new());
             }
@@ -978,19 +990,20 @@ impl SpuDisassembler {
                 let target = (address as i32).wrapping_add((i16_val as i32) << 2) as u32;
                 return ("br".to_string(), format!("0x{:X}", target));
             }
-            0b001100000 => {
+            0b001100110 => {
                 let (i16_val, rt) = SpuDecoder::ri16_form(opcode);
                 let target = (address as i32).wrapping_add((i16_val as i32) << 2) as u32;
                 return ("brsl".to_string(), format!("${}, 0x{:X}", rt, target));
             }
-            0b001000010 => {
+            // The absolute forms address local store directly
+            0b001100000 => {
                 let (i16_val, _rt) = SpuDecoder::ri16_form(opcode);
-                let target = (address as i32).wrapping_add((i16_val as i32) << 2) as u32;
+                let target = ((i16_val as i32) << 2) as u32 & 0x3FFFF;
                 return ("bra".to_string(), format!("0x{:X}", target));
             }
             0b001100010 => {
                 let (i16_val, rt) = SpuDecoder::ri16_form(opcode);
-                let target = (address as i32).wrapping_add((i16_val as i32) << 2) as u32;
+                let target = ((i16_val as i32) << 2) as u32 & 0x3FFFF;
                 return ("brasl".to_string(), format!("${}, 0x{:X}", rt, target));
             }
             _ => {}
@@ -1091,4 +1104,15 @@ mod tests {
         let dis = SpuDisassembler::disassemble(0, 0x42003203);
         assert_eq!(dis.mnemonic, "il");
     }
+
+    #[test]
+    fn test_spu_disassemble_calls() {
+        // brsl $0, +16 and brasl $0, 0x2000
+        let dis = SpuDisassembler::disassemble(0x100, (0x066 << 23) | (4 << 7));
+        assert_eq!(dis.mnemonic, "brsl");
+        assert!(dis.operands.contains("0x110"));
+        let dis = SpuDisassembler::disassemble(0x100, (0x062 << 23) | (0x800 << 7));
+        assert_eq!(dis.mnemonic, "brasl");
+        assert!(dis.operands.contains("0x2000"));
+    }
 }
@@ -0,0 +1,998 @@
+//! GDB remote serial protocol server
+//!
+//! Lets gdb, IDA or Ghidra attach over TCP to PPU threads or raw SPUs.
+//! The server never blocks: the emulator polls it once per update and the
+//! target is only touched from inside [`GdbServer::poll`].
+//!
+//! Execution is all-stop. A stop halts every thread, and continuing or
+//! stepping resumes them all. PPU registers use gdb's 64-bit PowerPC
+//! numbering with AltiVec; SPU registers use the numbering of gdb's `spu`
+//! target (r0-r127, npc, id).
+
+use oc_ppu::thread::PpuRegisters;
+use oc_spu::thread::SpuRegisters;
+use std::collections::BTreeSet;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+
+/// Largest packet the server accepts, advertised in qSupported
+const PACKET_SIZE: usize = 0x4000;
+
+/// Signal numbers used in stop replies
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+
+/// Register in a target description: number, name, size in bits and type
+type TargetRegister = (usize, String, u32, &'static str);
+
+/// Processor a server debugs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GdbArch {
+    /// PPU threads sharing guest memory
+    Ppu,
+    /// Raw SPUs, each addressing its own local store
+    Spu,
+}
+
+impl GdbArch {
+    /// Number of registers in the `g` packet
+    pub fn register_count(self) -> usize {
+        match self {
+            Self::Ppu => 105,
+            Self::Spu => 130,
+        }
+    }
+
+    /// Size in bytes of a register
+    pub fn register_size(self, reg: usize) -> Option<usize> {
+        match (self, reg) {
+            (Self::Ppu, 0..=65 | 67 | 68) => Some(8),
+            (Self::Ppu, 66 | 69 | 70 | 103 | 104) => Some(4),
+            (Self::Ppu, 71..=102) => Some(16),
+            (Self::Spu, 0..=127) => Some(16),
+            (Self::Spu, 128 | 129) => Some(4),
+            _ => None,
+        }
+    }
+
+    /// Byte offset of a register in the `g` packet
+    fn register_offset(self, reg: usize) -> Option<usize> {
+        self.register_size(reg)?;
+        (0..reg).map(|r| self.register_size(r)).sum()
+    }
+
+    /// Target description sent for qXfer:features:read
+    fn target_xml(self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\"?><!DOCTYPE target SYSTEM \"gdb-target.dtd\"><target>");
+        let mut regs = Vec::new();
+        match self {
+            Self::Ppu => {
+                xml.push_str("<architecture>powerpc:common64</architecture>");
+                let core: Vec<_> = (0..32)
+                    .map(|i| (format!("r{}", i), 64, "uint64"))
+                    .chain([
+                        ("pc".to_string(), 64, "code_ptr"),
+                        ("msr".to_string(), 64, "uint64"),
+                        ("cr".to_string(), 32, "uint32"),
+                        ("lr".to_string(), 64, "code_ptr"),
+                        ("ctr".to_string(), 64, "uint64"),
+                        ("xer".to_string(), 32, "uint32"),
+                    ])
+                    .collect();
+                let fpu: Vec<_> = (0..32)
+                    .map(|i| (format!("f{}", i), 64, "ieee_double"))
+                    .chain([("fpscr".to_string(), 32, "uint32")])
+                    .collect();
+                let altivec: Vec<_> = (0..32)
+                    .map(|i| (format!("vr{}", i), 128, "v4i32"))
+                    .chain([("vscr".to_string(), 32, "uint32"), ("vrsave".to_string(), 32, "uint32")])
+                    .collect();
+                // gdb numbers f0-f31 before pc even though they live in another feature
+                regs.push(("org.gnu.gdb.power.core", core[..32].to_vec(), 0));
+                regs.push(("org.gnu.gdb.power.fpu", fpu[..32].to_vec(), 32));
+                regs.push(("org.gnu.gdb.power.core", core[32..].to_vec(), 64));
+                regs.push(("org.gnu.gdb.power.fpu", fpu[32..].to_vec(), 70));
+                regs.push(("org.gnu.gdb.power.altivec", altivec, 71));
+            }
+            Self::Spu => {
+                xml.push_str("<architecture>spu:256K</architecture>");
+                let core = (0..128)
+                    .map(|i| (format!("r{}", i), 128, "v4i32"))
+                    .chain([("npc".to_string(), 32, "code_ptr"), ("id".to_string(), 32, "uint32")])
+                    .collect();
+                regs.push(("org.gnu.gdb.spu.core", core, 0));
+            }
+        }
+
+        // Registers of one feature must be contiguous in the document
+        let mut features: Vec<(&str, Vec<TargetRegister>)> = Vec::new();
+        for (feature, list, first) in regs {
+            let numbered = list.into_iter().enumerate().map(|(i, (name, bits, ty))| (first + i, name, bits, ty));
+            match features.iter_mut().find(|(name, _)| *name == feature) {
+                Some((_, existing)) => existing.extend(numbered),
+                None => features.push((feature, numbered.collect())),
+            }
+        }
+        for (feature, list) in features {
+            xml.push_str(&format!("<feature name=\"{}\">", feature));
+            if list.iter().any(|(_, _, _, ty)| *ty == "v4i32") {
+                xml.push_str("<vector id=\"v4i32\" type=\"int32\" count=\"4\"/>");
+            }
+            for (regnum, name, bits, ty) in list {
+                xml.push_str(&format!(
+                    "<reg name=\"{}\" bitsize=\"{}\" type=\"{}\" regnum=\"{}\"/>",
+                    name, bits, ty, regnum
+                ));
+            }
+            xml.push_str("</feature>");
+        }
+        xml.push_str("</target>");
+        xml
+    }
+}
+
+/// Why the target stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// Execution reached a breakpoint
+    Breakpoint,
+    /// A single step finished
+    Step,
+    /// The debugger or the user halted execution
+    Interrupt,
+}
+
+/// A thread listed to the debugger
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GdbThread {
+    /// Debugger thread ID (starts at 1)
+    pub id: u32,
+    /// Name shown by `info threads`
+    pub name: String,
+}
+
+/// Emulator state a server debugs
+///
+/// Thread IDs are the debugger IDs from [`GdbTarget::threads`]. Registers
+/// are big-endian in the numbering of [`GdbArch`].
+pub trait GdbTarget {
+    /// Threads that can be debugged
+    fn threads(&self) -> Vec<GdbThread>;
+    /// All registers of a thread, in `g` packet order
+    fn read_registers(&self, thread: u32) -> Option<Vec<u8>>;
+    /// Set one register; false if the thread or register doesn't exist
+    fn write_register(&mut self, thread: u32, reg: usize, value: &[u8]) -> bool;
+    /// Read memory as a thread sees it
+    fn read_memory(&self, thread: u32, addr: u64, len: usize) -> Option<Vec<u8>>;
+    /// Write memory as a thread sees it
+    fn write_memory(&mut self, thread: u32, addr: u64, data: &[u8]) -> bool;
+    /// Stop every thread
+    fn halt(&mut self);
+    /// Resume every thread, or run one instruction of `step` and stop again
+    ///
+    /// The target reports the stop later through [`GdbServer::report_stop`].
+    fn resume(&mut self, step: Option<u32>);
+}
+
+/// Encode PPU registers in gdb order
+pub fn ppu_registers(regs: &PpuRegisters) -> Vec<u8> {
+    let mut data = Vec::with_capacity(32 * 8 * 2 + 5 * 8 + 32 * 16 + 4 * 5);
+    for gpr in regs.gpr {
+        data.extend_from_slice(&gpr.to_be_bytes());
+    }
+    for fpr in regs.fpr {
+        data.extend_from_slice(&fpr.to_bits().to_be_bytes());
+    }
+    data.extend_from_slice(&regs.cia.to_be_bytes());
+    data.extend_from_slice(&regs.msr.to_be_bytes());
+    data.extend_from_slice(&regs.cr.to_be_bytes());
+    data.extend_from_slice(&regs.lr.to_be_bytes());
+    data.extend_from_slice(&regs.ctr.to_be_bytes());
+    data.extend_from_slice(&(regs.xer as u32).to_be_bytes());
+    data.extend_from_slice(&(regs.fpscr as u32).to_be_bytes());
+    for vr in regs.vr {
+        data.extend(vr.iter().flat_map(|word| word.to_be_bytes()));
+    }
+    data.extend_from_slice(&regs.vscr.to_be_bytes());
+    // VRSAVE is not emulated
+    data.extend_from_slice(&0u32.to_be_bytes());
+    data
+}
+
+/// Set one PPU register from its big-endian value
+pub fn set_ppu_register(regs: &mut PpuRegisters, reg: usize, value: &[u8]) -> bool {
+    if GdbArch::Ppu.register_size(reg) != Some(value.len()) {
+        return false;
+    }
+    let u64_value = || u64::from_be_bytes(value.try_into().unwrap());
+    let u32_value = || u32::from_be_bytes(value.try_into().unwrap());
+    match reg {
+        0..=31 => regs.gpr[reg] = u64_value(),
+        32..=63 => regs.fpr[reg - 32] = f64::from_bits(u64_value()),
+        64 => regs.cia = u64_value(),
+        65 => regs.msr = u64_value(),
+        66 => regs.cr = u32_value(),
+        67 => regs.lr = u64_value(),
+        68 => regs.ctr = u64_value(),
+        69 => regs.xer = u32_value() as u64,
+        70 => regs.fpscr = u32_value() as u64,
+        71..=102 => regs.vr[reg - 71] = vector_value(value),
+        103 => regs.vscr = u32_value(),
+        _ => {}
+    }
+    true
+}
+
+/// Encode SPU registers in gdb order
+pub fn spu_registers(regs: &SpuRegisters, spu_id: u32) -> Vec<u8> {
+    let mut data = Vec::with_capacity(128 * 16 + 8);
+    for gpr in regs.gpr {
+        data.extend(gpr.iter().flat_map(|word| word.to_be_bytes()));
+    }
+    data.extend_from_slice(&regs.pc.to_be_bytes());
+    data.extend_from_slice(&spu_id.to_be_bytes());
+    data
+}
+
+/// Set one SPU register from its big-endian value
+pub fn set_spu_register(regs: &mut SpuRegisters, reg: usize, value: &[u8]) -> bool {
+    if GdbArch::Spu.register_size(reg) != Some(value.len()) {
+        return false;
+    }
+    match reg {
+        0..=127 => regs.gpr[reg] = vector_value(value),
+        128 => regs.pc = u32::from_be_bytes(value.try_into().unwrap()),
+        // The SPU ID is read-only
+        _ => {}
+    }
+    true
+}
+
+/// Split a big-endian 128-bit value into words, most significant first
+fn vector_value(value: &[u8]) -> [u32; 4] {
+    std::array::from_fn(|i| u32::from_be_bytes(value[i * 4..i * 4 + 4].try_into().unwrap()))
+}
+
+/// Something read from the debugger connection
+#[derive(Debug, PartialEq, Eq)]
+enum Incoming {
+    /// A packet with a valid checksum
+    Packet(Vec<u8>),
+    /// A packet that must be sent again
+    Corrupt,
+    /// Ctrl-C: halt the target
+    Interrupt,
+    /// The last reply was not received correctly
+    Nack,
+}
+
+/// Take the next complete message from the front of `input`
+fn take_incoming(input: &mut Vec<u8>) -> Option<Incoming> {
+    loop {
+        let (&first, _) = input.split_first()?;
+        match first {
+            b'+' => {
+                input.remove(0);
+            }
+            b'-' => {
+                input.remove(0);
+                return Some(Incoming::Nack);
+            }
+            0x03 => {
+                input.remove(0);
+                return Some(Incoming::Interrupt);
+            }
+            b'$' => {
+                let end = input.iter().position(|&b| b == b'#')?;
+                if input.len() < end + 3 {
+                    return None;
+                }
+                let body = unescape(&input[1..end]);
+                let checksum = std::str::from_utf8(&input[end + 1..end + 3])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                let valid = checksum == Some(input[1..end].iter().fold(0u8, |sum, &b| sum.wrapping_add(b)));
+                input.drain(..end + 3);
+                return Some(if valid { Incoming::Packet(body) } else { Incoming::Corrupt });
+            }
+            // Line noise between packets
+            _ => {
+                input.remove(0);
+            }
+        }
+    }
+}
+
+/// Frame a reply, escaping the protocol's special characters
+fn frame_packet(body: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(body.len() + 4);
+    packet.push(b'$');
+    for &byte in body {
+        if matches!(byte, b'$' | b'#' | b'}' | b'*') {
+            packet.extend_from_slice(&[b'}', byte ^ 0x20]);
+        } else {
+            packet.push(byte);
+        }
+    }
+    let checksum = packet[1..].iter().fold(0u8, |sum, &b| sum.wrapping_add(b));
+    packet.extend_from_slice(format!("#{:02x}", checksum).as_bytes());
+    packet
+}
+
+/// Undo `}` escaping in binary data
+fn unescape(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut bytes = data.iter();
+    while let Some(&byte) = bytes.next() {
+        if byte == b'}' {
+            if let Some(&next) = bytes.next() {
+                out.push(next ^ 0x20);
+            }
+        } else {
+            out.push(byte);
+        }
+    }
+    out
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    text.as_bytes()
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok().filter(|pair| pair.len() == 2)?, 16).ok())
+        .collect()
+}
+
+fn parse_hex(text: &str) -> Option<u64> {
+    u64::from_str_radix(text, 16).ok()
+}
+
+/// Parse `addr,len`
+fn parse_range(text: &str) -> Option<(u64, usize)> {
+    let (addr, len) = text.split_once(',')?;
+    Some((parse_hex(addr)?, parse_hex(len)? as usize))
+}
+
+/// Parse a thread ID; None for "any" (0) and "all" (-1)
+fn parse_thread(text: &str) -> Option<u32> {
+    match text {
+        "-1" | "0" => None,
+        _ => u32::from_str_radix(text, 16).ok(),
+    }
+}
+
+/// An attached debugger
+struct Connection {
+    stream: TcpStream,
+    input: Vec<u8>,
+    /// Last reply, sent again on a nack
+    last_reply: Vec<u8>,
+    /// Acknowledgments turned off with QStartNoAckMode
+    no_ack: bool,
+    /// Debugger understands the swbreak stop reason
+    swbreak: bool,
+}
+
+impl Connection {
+    fn send_raw(&mut self, data: &[u8]) -> io::Result<()> {
+        // Replies go out in one piece; only reads are non-blocking
+        self.stream.set_nonblocking(false)?;
+        let result = self.stream.write_all(data);
+        self.stream.set_nonblocking(true)?;
+        result
+    }
+
+    fn send(&mut self, body: &[u8]) -> io::Result<()> {
+        self.last_reply = frame_packet(body);
+        let reply = std::mem::take(&mut self.last_reply);
+        let result = self.send_raw(&reply);
+        self.last_reply = reply;
+        result
+    }
+}
+
+/// GDB remote serial protocol server for one processor type
+pub struct GdbServer {
+    arch: GdbArch,
+    listener: TcpListener,
+    client: Option<Connection>,
+    /// Execution breakpoints (Z0 and Z1)
+    breakpoints: BTreeSet<u64>,
+    /// Thread used for register and memory access (Hg)
+    thread: u32,
+    /// Last stop, reported again for `?`
+    last_stop: (u32, StopReason),
+    /// Target is running and a stop reply is owed
+    running: bool,
+}
+
+impl GdbServer {
+    /// Listen for a debugger on localhost
+    ///
+    /// Port 0 picks a free port, see [`GdbServer::port`].
+    pub fn bind(port: u16, arch: GdbArch) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        listener.set_nonblocking(true)?;
+        tracing::info!("GDB server ({:?}) listening on port {}", arch, listener.local_addr()?.port());
+        Ok(Self {
+            arch,
+            listener,
+            client: None,
+            breakpoints: BTreeSet::new(),
+            thread: 1,
+            last_stop: (1, StopReason::Interrupt),
+            running: false,
+        })
+    }
+
+    /// Processor this server debugs
+    pub fn arch(&self) -> GdbArch {
+        self.arch
+    }
+
+    /// Port the server listens on
+    pub fn port(&self) -> u16 {
+        self.listener.local_addr().map(|addr| addr.port()).unwrap_or(0)
+    }
+
+    /// Whether a debugger is attached
+    pub fn is_attached(&self) -> bool {
+        self.client.is_some()
+    }
+
+    /// Whether any breakpoint is set
+    pub fn has_breakpoints(&self) -> bool {
+        !self.breakpoints.is_empty()
+    }
+
+    /// Whether execution should stop at `addr`
+    pub fn has_breakpoint(&self, addr: u64) -> bool {
+        self.breakpoints.contains(&addr)
+    }
+
+    /// Accept a debugger and handle everything it sent
+    pub fn poll(&mut self, target: &mut dyn GdbTarget) {
+        if self.client.is_none() {
+            match self.listener.accept() {
+                Ok((stream, addr)) => self.attach(stream, target, addr.to_string()),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+                Err(e) => {
+                    tracing::warn!("GDB server failed to accept a connection: {}", e);
+                    return;
+                }
+            }
+        }
+
+        let Some(client) = self.client.as_mut() else {
+            return;
+        };
+        let mut buffer = [0u8; 4096];
+        let closed = loop {
+            match client.stream.read(&mut buffer) {
+                Ok(0) => break true,
+                Ok(n) => client.input.extend_from_slice(&buffer[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break false,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => break true,
+            }
+        };
+
+        while let Some(incoming) = self.client.as_mut().and_then(|client| take_incoming(&mut client.input)) {
+            if let Err(e) = self.handle_incoming(incoming, target) {
+                tracing::warn!("GDB connection lost: {}", e);
+                self.detach(target);
+                return;
+            }
+        }
+        if closed && self.client.is_some() {
+            tracing::info!("GDB debugger disconnected");
+            self.detach(target);
+        }
+    }
+
+    /// Tell the debugger the target stopped
+    ///
+    /// `thread` is the debugger ID of the thread that caused the stop, or
+    /// None when it belongs to another server; the selected thread is
+    /// reported as interrupted then. Does nothing unless the debugger is
+    /// waiting for a stop.
+    pub fn report_stop(&mut self, thread: Option<u32>, reason: StopReason) {
+        if !std::mem::take(&mut self.running) {
+            return;
+        }
+        self.last_stop = match thread {
+            Some(thread) => (thread, reason),
+            None => (self.thread, StopReason::Interrupt),
+        };
+        self.thread = self.last_stop.0;
+        let reply = self.stop_reply();
+        if let Some(client) = self.client.as_mut() {
+            if let Err(e) = client.send(reply.as_bytes()) {
+                tracing::warn!("GDB connection lost: {}", e);
+                self.client = None;
+                self.breakpoints.clear();
+            }
+        }
+    }
+
+    fn attach(&mut self, stream: TcpStream, target: &mut dyn GdbTarget, peer: String) {
+        if let Err(e) = stream.set_nonblocking(true) {
+            tracing::warn!("GDB server failed to set up connection from {}: {}", peer, e);
+            return;
+        }
+        let _ = stream.set_nodelay(true);
+        tracing::info!("GDB debugger attached from {}", peer);
+        // The debugger expects the target halted once it connects
+        target.halt();
+        self.thread = target.threads().first().map_or(1, |thread| thread.id);
+        self.last_stop = (self.thread, StopReason::Interrupt);
+        self.running = false;
+        self.client = Some(Connection {
+            stream,
+            input: Vec::new(),
+            last_reply: Vec::new(),
+            no_ack: false,
+            swbreak: false,
+        });
+    }
+
+    /// Drop the debugger and let the target run freely
+    fn detach(&mut self, target: &mut dyn GdbTarget) {
+        self.client = None;
+        self.breakpoints.clear();
+        self.running = false;
+        target.resume(None);
+    }
+
+    fn handle_incoming(&mut self, incoming: Incoming, target: &mut dyn GdbTarget) -> io::Result<()> {
+        let Some(client) = self.client.as_mut() else {
+            return Ok(());
+        };
+        match incoming {
+            Incoming::Nack => {
+                let reply = client.last_reply.clone();
+                client.send_raw(&reply)
+            }
+            Incoming::Corrupt => {
+                if client.no_ack {
+                    Ok(())
+                } else {
+                    client.send_raw(b"-")
+                }
+            }
+            Incoming::Interrupt => {
+                if self.running {
+                    target.halt();
+                    self.report_stop(None, StopReason::Interrupt);
+                }
+                Ok(())
+            }
+            Incoming::Packet(packet) => {
+                if !client.no_ack {
+                    client.send_raw(b"+")?;
+                }
+                let reply = match packet.split_first() {
+                    // X carries binary data that needn't be valid UTF-8
+                    Some((b'X', args)) => Some(self.write_binary(args, target)),
+                    _ => self.handle_packet(&String::from_utf8_lossy(&packet), target),
+                };
+                let packet = String::from_utf8_lossy(&packet);
+                match (reply, self.client.as_mut()) {
+                    (Some(reply), Some(client)) => client.send(reply.as_bytes())?,
+                    _ => return Ok(()),
+                }
+                // Detaching replies OK first
+                if packet == "D" || packet.starts_with("D;") {
+                    tracing::info!("GDB debugger detached");
+                    self.detach(target);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Handle one packet; None when the reply comes later (or never)
+    fn handle_packet(&mut self, packet: &str, target: &mut dyn GdbTarget) -> Option<String> {
+        let Some(command) = packet.chars().next() else {
+            return Some(String::new());
+        };
+        let args = &packet[command.len_utf8()..];
+        let reply = match command {
+            '?' => self.stop_reply(),
+            'g' => match target.read_registers(self.thread) {
+                Some(regs) => to_hex(&regs),
+                None => "E01".to_string(),
+            },
+            'G' => match from_hex(args) {
+                Some(data) => self.write_registers(&data, target),
+                None => "E01".to_string(),
+            },
+            'p' => {
+                let value = parse_hex(args).and_then(|reg| {
+                    let offset = self.arch.register_offset(reg as usize)?;
+                    let size = self.arch.register_size(reg as usize)?;
+                    let regs = target.read_registers(self.thread)?;
+                    regs.get(offset..offset + size).map(to_hex)
+                });
+                value.unwrap_or_else(|| "E01".to_string())
+            }
+            'P' => {
+                let written = args.split_once('=').and_then(|(reg, value)| {
+                    Some(target.write_register(self.thread, parse_hex(reg)? as usize, &from_hex(value)?))
+                });
+                ok_or_error(written == Some(true))
+            }
+            'm' => {
+                let data = parse_range(args)
+                    .and_then(|(addr, len)| target.read_memory(self.thread, addr, len.min(PACKET_SIZE / 2)));
+                match data {
+                    Some(data) => to_hex(&data),
+                    None => "E14".to_string(),
+                }
+            }
+            'M' => {
+                let written = args.split_once(':').and_then(|(range, data)| {
+                    let (addr, len) = parse_range(range)?;
+                    let data = from_hex(data)?;
+                    (data.len() == len).then(|| target.write_memory(self.thread, addr, &data))
+                });
+                ok_or_error(written == Some(true))
+            }
+            'Z' | 'z' => self.handle_breakpoint(command == 'Z', args),
+            'c' | 'C' => {
+                self.resume(None, target);
+                return None;
+            }
+            's' | 'S' => {
+                self.resume(Some(self.thread), target);
+                return None;
+            }
+            'H' => {
+                if let Some(thread) = args.get(1..).and_then(parse_thread) {
+                    if args.starts_with('g') && target.threads().iter().any(|t| t.id == thread) {
+                        self.thread = thread;
+                    }
+                }
+                "OK".to_string()
+            }
+            'T' => {
+                let alive = parse_thread(args).is_some_and(|id| target.threads().iter().any(|t| t.id == id));
+                ok_or_error(alive)
+            }
+            'D' => "OK".to_string(),
+            'k' => {
+                tracing::info!("GDB debugger killed the session");
+                self.detach(target);
+                return None;
+            }
+            'q' | 'Q' => self.handle_query(packet, target),
+            'v' => return self.handle_v(packet, target),
+            _ => String::new(),
+        };
+        Some(reply)
+    }
+
+    fn handle_query(&mut self, packet: &str, target: &mut dyn GdbTarget) -> String {
+        let (name, args) = packet.split_once(':').unwrap_or((packet, ""));
+        match name {
+            "qSupported" => {
+                if let Some(client) = self.client.as_mut() {
+                    client.swbreak = args.split(';').any(|feature| feature == "swbreak+");
+                }
+                format!(
+                    "PacketSize={:x};qXfer:features:read+;swbreak+;vContSupported+;QStartNoAckMode+",
+                    PACKET_SIZE
+                )
+            }
+            "QStartNoAckMode" => {
+                if let Some(client) = self.client.as_mut() {
+                    client.no_ack = true;
+                }
+                "OK".to_string()
+            }
+            "qXfer" => {
+                let Some(range) = args.strip_prefix("features:read:target.xml:") else {
+                    return String::new();
+                };
+                let Some((offset, len)) = parse_range(range) else {
+                    return "E01".to_string();
+                };
+                let xml = self.arch.target_xml();
+                let chunk = xml.get(offset as usize..).unwrap_or("");
+                let end = len.min(chunk.len());
+                let more = if end < chunk.len() { 'm' } else { 'l' };
+                format!("{}{}", more, &chunk[..end])
+            }
+            "qfThreadInfo" => {
+                let ids: Vec<_> = target.threads().iter().map(|thread| format!("{:x}", thread.id)).collect();
+                if ids.is_empty() {
+                    "l".to_string()
+                } else {
+                    format!("m{}", ids.join(","))
+                }
+            }
+            "qsThreadInfo" => "l".to_string(),
+            "qC" => format!("QC{:x}", self.thread),
+            "qAttached" => "1".to_string(),
+            "qSymbol" => "OK".to_string(),
+            _ => {
+                if let Some(id) = packet.strip_prefix("qThreadExtraInfo,") {
+                    let name = parse_thread(id)
+                        .and_then(|id| target.threads().into_iter().find(|thread| thread.id == id))
+                        .map(|thread| thread.name)
+                        .unwrap_or_default();
+                    to_hex(name.as_bytes())
+                } else {
+                    String::new()
+                }
+            }
+        }
+    }
+
+    fn handle_v(&mut self, packet: &str, target: &mut dyn GdbTarget) -> Option<String> {
+        if packet == "vCont?" {
+            return Some("vCont;c;C;s;S".to_string());
+        }
+        let Some(actions) = packet.strip_prefix("vCont;") else {
+            return Some(String::new());
+        };
+        // All-stop: one step wins over continuing everything else
+        let mut step = None;
+        for action in actions.split(';') {
+            let (action, thread) = action.split_once(':').unwrap_or((action, "-1"));
+            if action.starts_with('s') || action.starts_with('S') {
+                step = Some(parse_thread(thread).unwrap_or(self.thread));
+                break;
+            }
+        }
+        if let Some(thread) = step {
+            self.thread = thread;
+        }
+        self.resume(step, target);
+        None
+    }
+
+    fn handle_breakpoint(&mut self, insert: bool, args: &str) -> String {
+        let mut fields = args.split(',');
+        let (Some(kind), Some(addr)) = (fields.next(), fields.next().and_then(parse_hex)) else {
+            return "E01".to_string();
+        };
+        // Only execution breakpoints; watchpoints are reported unsupported
+        if kind != "0" && kind != "1" {
+            return String::new();
+        }
+        if insert {
+            self.breakpoints.insert(addr);
+        } else {
+            self.breakpoints.remove(&addr);
+        }
+        "OK".to_string()
+    }
+
+    /// Handle the arguments of an X packet
+    fn write_binary(&mut self, args: &[u8], target: &mut dyn GdbTarget) -> String {
+        let Some(colon) = args.iter().position(|&b| b == b':') else {
+            return "E01".to_string();
+        };
+        let range = std::str::from_utf8(&args[..colon]).ok().and_then(parse_range);
+        let data = &args[colon + 1..];
+        let written = range.and_then(|(addr, len)| {
+            // Zero-length writes probe for X support
+            (data.len() == len).then(|| len == 0 || target.write_memory(self.thread, addr, data))
+        });
+        ok_or_error(written == Some(true))
+    }
+
+    fn write_registers(&mut self, data: &[u8], target: &mut dyn GdbTarget) -> String {
+        let mut offset = 0;
+        for reg in 0..self.arch.register_count() {
+            let size = self.arch.register_size(reg).unwrap_or(0);
+            let Some(value) = data.get(offset..offset + size) else {
+                break;
+            };
+            if !target.write_register(self.thread, reg, value) {
+                return "E01".to_string();
+            }
+            offset += size;
+        }
+        "OK".to_string()
+    }
+
+    fn resume(&mut self, step: Option<u32>, target: &mut dyn GdbTarget) {
+        self.running = true;
+        target.resume(step);
+    }
+
+    fn stop_reply(&self) -> String {
+        let (thread, reason) = self.last_stop;
+        let swbreak = self.client.as_ref().is_some_and(|client| client.swbreak);
+        match reason {
+            StopReason::Breakpoint if swbreak => format!("T{:02x}thread:{:x};swbreak:;", SIGTRAP, thread),
+            StopReason::Breakpoint | StopReason::Step => format!("T{:02x}thread:{:x};", SIGTRAP, thread),
+            StopReason::Interrupt => format!("T{:02x}thread:{:x};", SIGINT, thread),
+        }
+    }
+}
+
+fn ok_or_error(ok: bool) -> String {
+    if ok { "OK" } else { "E01" }.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct MockTarget {
+        regs: PpuRegisters,
+        memory: Vec<u8>,
+        halted: bool,
+        resumed: Vec<Option<u32>>,
+    }
+
+    impl GdbTarget for MockTarget {
+        fn threads(&self) -> Vec<GdbThread> {
+            vec![GdbThread { id: 1, name: "main".to_string() }, GdbThread { id: 2, name: "worker".to_string() }]
+        }
+
+        fn read_registers(&self, _thread: u32) -> Option<Vec<u8>> {
+            Some(ppu_registers(&self.regs))
+        }
+
+        fn write_register(&mut self, _thread: u32, reg: usize, value: &[u8]) -> bool {
+            set_ppu_register(&mut self.regs, reg, value)
+        }
+
+        fn read_memory(&self, _thread: u32, addr: u64, len: usize) -> Option<Vec<u8>> {
+            self.memory.get(addr as usize..addr as usize + len).map(<[u8]>::to_vec)
+        }
+
+        fn write_memory(&mut self, _thread: u32, addr: u64, data: &[u8]) -> bool {
+            match self.memory.get_mut(addr as usize..addr as usize + data.len()) {
+                Some(memory) => {
+                    memory.copy_from_slice(data);
+                    true
+                }
+                None => false,
+            }
+        }
+
+        fn halt(&mut self) {
+            self.halted = true;
+        }
+
+        fn resume(&mut self, step: Option<u32>) {
+            self.halted = false;
+            self.resumed.push(step);
+        }
+    }
+
+    #[test]
+    fn test_packet_framing() {
+        assert_eq!(frame_packet(b"OK"), b"$OK#9a");
+        // Special characters are escaped and the checksum covers the escapes
+        assert_eq!(frame_packet(b"a}"), b"$a}]#3b");
+
+        let mut input = b"+$m10,4#2e\x03-$g#00".to_vec();
+        assert_eq!(take_incoming(&mut input), Some(Incoming::Packet(b"m10,4".to_vec())));
+        assert_eq!(take_incoming(&mut input), Some(Incoming::Interrupt));
+        assert_eq!(take_incoming(&mut input), Some(Incoming::Nack));
+        assert_eq!(take_incoming(&mut input), Some(Incoming::Corrupt));
+        assert_eq!(take_incoming(&mut input), None);
+
+        // Incomplete packets wait for more data
+        let mut input = b"$qC#b".to_vec();
+        assert_eq!(take_incoming(&mut input), None);
+        input.push(b'4');
+        assert_eq!(take_incoming(&mut input), Some(Incoming::Packet(b"qC".to_vec())));
+    }
+
+    #[test]
+    fn test_register_encoding() {
+        let mut regs = PpuRegisters::default();
+        regs.gpr[3] = 0x1122_3344_5566_7788;
+        regs.cia = 0x10200;
+        regs.cr = 0x2000_0000;
+        regs.vr[1] = [1, 2, 3, 4];
+        let data = ppu_registers(&regs);
+        let size: usize = (0..GdbArch::Ppu.register_count()).map(|r| GdbArch::Ppu.register_size(r).unwrap()).sum();
+        assert_eq!(data.len(), size);
+        assert_eq!(&data[24..32], &0x1122_3344_5566_7788u64.to_be_bytes());
+        let pc = GdbArch::Ppu.register_offset(64).unwrap();
+        assert_eq!(&data[pc..pc + 8], &0x10200u64.to_be_bytes());
+        let cr = GdbArch::Ppu.register_offset(66).unwrap();
+        assert_eq!(&data[cr..cr + 4], &0x2000_0000u32.to_be_bytes());
+        let vr1 = GdbArch::Ppu.register_offset(72).unwrap();
+        assert_eq!(vector_value(&data[vr1..vr1 + 16]), [1, 2, 3, 4]);
+
+        assert!(set_ppu_register(&mut regs, 67, &0xABCDu64.to_be_bytes()));
+        assert_eq!(regs.lr, 0xABCD);
+        // Wrong sizes and unknown registers are rejected
+        assert!(!set_ppu_register(&mut regs, 66, &[0; 8]));
+        assert!(!set_ppu_register(&mut regs, 200, &[0; 4]));
+
+        let mut spu = SpuRegisters::default();
+        assert!(set_spu_register(&mut spu, 128, &0x400u32.to_be_bytes()));
+        let data = spu_registers(&spu, 3);
+        assert_eq!(data.len(), 128 * 16 + 8);
+        assert_eq!(&data[2048..], &[0, 0, 4, 0, 0, 0, 0, 3]);
+    }
+
+    /// Debugger end of a test connection
+    struct Client {
+        stream: TcpStream,
+        input: Vec<u8>,
+    }
+
+    impl Client {
+        fn send(&mut self, packet: &[u8]) {
+            self.stream.write_all(&frame_packet(packet)).unwrap();
+        }
+
+        fn reply(&mut self, server: &mut GdbServer, target: &mut MockTarget) -> String {
+            let mut buffer = [0u8; 4096];
+            for _ in 0..1000 {
+                server.poll(target);
+                if let Ok(n) = self.stream.read(&mut buffer) {
+                    self.input.extend_from_slice(&buffer[..n]);
+                }
+                if let Some(Incoming::Packet(reply)) = take_incoming(&mut self.input) {
+                    return String::from_utf8(reply).unwrap();
+                }
+            }
+            panic!("no reply");
+        }
+
+        fn exchange(&mut self, server: &mut GdbServer, target: &mut MockTarget, packet: &str) -> String {
+            self.send(packet.as_bytes());
+            self.reply(server, target)
+        }
+    }
+
+    #[test]
+    fn test_session() {
+        let mut server = GdbServer::bind(0, GdbArch::Ppu).unwrap();
+        let mut target = MockTarget { memory: (0..64).collect(), ..Default::default() };
+        let stream = TcpStream::connect((Ipv4Addr::LOCALHOST, server.port())).unwrap();
+        stream.set_read_timeout(Some(Duration::from_millis(5))).unwrap();
+        let mut gdb = Client { stream, input: Vec::new() };
+        let (server, target) = (&mut server, &mut target);
+
+        assert!(gdb.exchange(server, target, "qSupported:swbreak+;xmlRegisters=i386").contains("swbreak+"));
+        assert!(server.is_attached());
+        assert!(target.halted);
+        assert_eq!(gdb.exchange(server, target, "?"), "T02thread:1;");
+        assert_eq!(gdb.exchange(server, target, "qfThreadInfo"), "m1,2");
+        assert_eq!(gdb.exchange(server, target, "qThreadExtraInfo,2"), to_hex(b"worker"));
+        assert_eq!(gdb.exchange(server, target, "m4,4"), "04050607");
+        assert_eq!(gdb.exchange(server, target, "M4,2:aabb"), "OK");
+        gdb.send(b"X8,2:\x03#");
+        assert_eq!(gdb.reply(server, target), "OK");
+        assert_eq!(&target.memory[4..10], &[0xAA, 0xBB, 6, 7, 0x03, 0x23]);
+        assert_eq!(gdb.exchange(server, target, "P40=0000000000010000"), "OK");
+        assert_eq!(gdb.exchange(server, target, "p40"), "0000000000010000");
+        assert!(gdb.exchange(server, target, "qXfer:features:read:target.xml:0,10").starts_with('m'));
+
+        assert_eq!(gdb.exchange(server, target, "Z0,10000,4"), "OK");
+        assert!(server.has_breakpoint(0x10000));
+        // Continuing replies only once the target stops
+        gdb.send(b"vCont;c");
+        while target.resumed.is_empty() {
+            server.poll(target);
+        }
+        assert_eq!(target.resumed, [None]);
+        server.report_stop(Some(2), StopReason::Breakpoint);
+        assert_eq!(gdb.reply(server, target), "T05thread:2;swbreak:;");
+        assert_eq!(gdb.exchange(server, target, "qC"), "QC2");
+
+        assert_eq!(gdb.exchange(server, target, "z0,10000,4"), "OK");
+        assert!(!server.has_breakpoints());
+        assert_eq!(gdb.exchange(server, target, "D"), "OK");
+        assert!(!server.is_attached());
+        assert_eq!(target.resumed.last(), Some(&None));
+    }
+}
@@ -1,10 +1,16 @@
 //! Debugging tools for oxidized-cell PS3 emulator
 //!
 //! This crate provides debugging infrastructure for:
-//! - PPU debugging (instruction tracing, register inspection, breakpoints)
+//! - PPU debugging (instruction tracing, register inspection, breakpoints, backtraces)
+//! - Instruction trace recording to compressed files for diffing against other emulators
 //! - SPU debugging (local storage viewer, register viewer, channel monitor)
 //! - RSX debugging (command buffer viewer, state inspector)
-//! - Performance profiling (CPU/GPU profiling, hotspot analysis)
+//! - Performance profiling (CPU/GPU profiling, hotspot analysis, per-function flamegraphs)
+//! - Memory value search and per-game cheat lists with value freezing
+//! - Runtime code patches and Rust hooks on PPU code
+//! - Crash dumps of guest faults and emulator panics
+//! - Basic block coverage of guest code for disassembler colorizers
+//! - GDB remote protocol server for attaching external debuggers
 
 pub mod ppu_debugger;
 pub mod spu_debugger;
@@ -12,10 +18,26 @@ pub mod rsx_debugger;
 pub mod profiler;
 pub mod breakpoint;
 pub mod disassembler;
+pub mod memory_scan;
+pub mod cheats;
+pub mod patches;
+pub mod gdb_stub;
+pub mod backtrace;
+pub mod trace_recorder;
+pub mod crash_dump;
+pub mod coverage;
 
 pub use ppu_debugger::PpuDebugger;
-pub use spu_debugger::SpuDebugger;
-pub use rsx_debugger::RsxDebugger;
-pub use profiler::Profiler;
+pub use spu_debugger::{ChannelAccess, ChannelEvent, SpuDebugger};
+pub use rsx_debugger::{DrawCallRecord, RsxDebugger, RsxRegister};
+pub use profiler::{FunctionHotspot, Profiler};
 pub use breakpoint::{Breakpoint, BreakpointManager};
 pub use disassembler::{PpuDisassembler, SpuDisassembler};
+pub use cheats::{Cheat, CheatFormat, CheatManager};
+pub use patches::{CodePatch, HookAction, PatchManager};
+pub use memory_scan::{MemoryScanner, ScanCondition, ScanRange, ScanResult, ScanValue, ScanValueType};
+pub use backtrace::{format_backtrace, unwind_ppu, FunctionMap, StackFrame};
+pub use crash_dump::{CrashDump, CrashKind, CrashReport, ThreadDump};
+pub use coverage::{BasicBlock, Coverage, CoverageSummary, ModuleRange};
+pub use trace_recorder::{TraceConfig, TraceRecorder, TraceTrigger};
+pub use gdb_stub::{GdbArch, GdbServer, GdbTarget, GdbThread, StopReason};
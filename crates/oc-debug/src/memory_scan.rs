@@ -0,0 +1,645 @@
+//! Memory value search for the cheat engine
+//!
+//! A [`MemoryScanner`] searches guest memory for values of one type and
+//! narrows the candidate list over successive scans: first an exact value
+//! (or an unknown one, which just snapshots memory), then conditions
+//! relative to the previous scan such as "changed" or "decreased".
+//! Reads go straight to guest memory without triggering watchpoints.
+
+use oc_memory::{MemoryManager, PageFlags, PAGE_SIZE};
+
+/// Default cap on the number of candidates kept after a scan
+pub const DEFAULT_MAX_RESULTS: usize = 4_000_000;
+
+/// Type of value being searched for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanValueType {
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl ScanValueType {
+    /// All value types, in display order
+    pub const ALL: [ScanValueType; 10] = [
+        Self::U8,
+        Self::U16,
+        Self::U32,
+        Self::U64,
+        Self::I8,
+        Self::I16,
+        Self::I32,
+        Self::I64,
+        Self::F32,
+        Self::F64,
+    ];
+
+    /// Size of the value in bytes
+    pub fn size(&self) -> usize {
+        match self {
+            Self::U8 | Self::I8 => 1,
+            Self::U16 | Self::I16 => 2,
+            Self::U32 | Self::I32 | Self::F32 => 4,
+            Self::U64 | Self::I64 | Self::F64 => 8,
+        }
+    }
+
+    /// Display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::U32 => "u32",
+            Self::U64 => "u64",
+            Self::I8 => "i8",
+            Self::I16 => "i16",
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+        }
+    }
+
+    /// Whether this is a floating-point type
+    pub fn is_float(&self) -> bool {
+        matches!(self, Self::F32 | Self::F64)
+    }
+
+    /// Decode a big-endian value from the start of `bytes`
+    pub fn decode(&self, bytes: &[u8]) -> ScanValue {
+        let mut raw = [0u8; 8];
+        raw[..self.size()].copy_from_slice(&bytes[..self.size()]);
+        match self {
+            Self::U8 => ScanValue::Int(raw[0] as i128),
+            Self::U16 => ScanValue::Int(u16::from_be_bytes([raw[0], raw[1]]) as i128),
+            Self::U32 => ScanValue::Int(u32::from_be_bytes([raw[0], raw[1], raw[2], raw[3]]) as i128),
+            Self::U64 => ScanValue::Int(u64::from_be_bytes(raw) as i128),
+            Self::I8 => ScanValue::Int(raw[0] as i8 as i128),
+            Self::I16 => ScanValue::Int(i16::from_be_bytes([raw[0], raw[1]]) as i128),
+            Self::I32 => ScanValue::Int(i32::from_be_bytes([raw[0], raw[1], raw[2], raw[3]]) as i128),
+            Self::I64 => ScanValue::Int(i64::from_be_bytes(raw) as i128),
+            Self::F32 => ScanValue::Float(f32::from_be_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64),
+            Self::F64 => ScanValue::Float(f64::from_be_bytes(raw)),
+        }
+    }
+
+    /// Encode a value as big-endian bytes of this type
+    pub fn encode(&self, value: ScanValue) -> Vec<u8> {
+        let int = match value {
+            ScanValue::Int(v) => v,
+            ScanValue::Float(v) => v as i128,
+        };
+        let float = value.as_f64();
+        match self {
+            Self::U8 | Self::I8 => vec![int as u8],
+            Self::U16 | Self::I16 => (int as u16).to_be_bytes().to_vec(),
+            Self::U32 | Self::I32 => (int as u32).to_be_bytes().to_vec(),
+            Self::U64 | Self::I64 => (int as u64).to_be_bytes().to_vec(),
+            Self::F32 => (float as f32).to_be_bytes().to_vec(),
+            Self::F64 => float.to_be_bytes().to_vec(),
+        }
+    }
+
+    /// Parse a user-entered value (integers accept a `0x` prefix)
+    pub fn parse(&self, s: &str) -> Option<ScanValue> {
+        let s = s.trim();
+        if self.is_float() {
+            return s.parse::<f64>().ok().map(ScanValue::Float);
+        }
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let value = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+            Some(hex) => i128::from_str_radix(hex, 16).ok()?,
+            None => digits.parse::<i128>().ok()?,
+        };
+        Some(ScanValue::Int(if negative { -value } else { value }))
+    }
+}
+
+/// A decoded value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScanValue {
+    Int(i128),
+    Float(f64),
+}
+
+impl ScanValue {
+    /// Value as f64
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            Self::Int(v) => v as f64,
+            Self::Float(v) => v,
+        }
+    }
+}
+
+impl std::fmt::Display for ScanValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(v) => write!(f, "{}", v),
+            Self::Float(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// Condition a value must satisfy to stay in the result list
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScanCondition {
+    /// Any value (first scan only: snapshot memory for relative scans)
+    Unknown,
+    /// Equal to a value (within the float tolerance for float types)
+    Exact(ScanValue),
+    /// Not equal to a value
+    NotEqual(ScanValue),
+    /// Greater than a value
+    GreaterThan(ScanValue),
+    /// Less than a value
+    LessThan(ScanValue),
+    /// Within an inclusive range
+    Between(ScanValue, ScanValue),
+    /// Different from the previous scan
+    Changed,
+    /// Same as the previous scan
+    Unchanged,
+    /// Greater than the previous scan
+    Increased,
+    /// Less than the previous scan
+    Decreased,
+    /// Greater than the previous scan by exactly this amount
+    IncreasedBy(ScanValue),
+    /// Less than the previous scan by exactly this amount
+    DecreasedBy(ScanValue),
+}
+
+impl ScanCondition {
+    /// Whether the condition compares against the previous scan
+    pub fn is_relative(&self) -> bool {
+        matches!(
+            self,
+            Self::Changed
+                | Self::Unchanged
+                | Self::Increased
+                | Self::Decreased
+                | Self::IncreasedBy(_)
+                | Self::DecreasedBy(_)
+        )
+    }
+
+    /// Evaluate the condition
+    fn matches(&self, current: ScanValue, previous: Option<ScanValue>, tolerance: f64) -> bool {
+        let eq = |a: ScanValue, b: ScanValue| match (a, b) {
+            (ScanValue::Int(a), ScanValue::Int(b)) => a == b,
+            (a, b) => (a.as_f64() - b.as_f64()).abs() <= tolerance,
+        };
+        let cmp = |a: ScanValue, b: ScanValue| match (a, b) {
+            (ScanValue::Int(a), ScanValue::Int(b)) => a.partial_cmp(&b),
+            (a, b) => a.as_f64().partial_cmp(&b.as_f64()),
+        };
+        let diff = |a: ScanValue, b: ScanValue| match (a, b) {
+            (ScanValue::Int(a), ScanValue::Int(b)) => ScanValue::Int(a - b),
+            (a, b) => ScanValue::Float(a.as_f64() - b.as_f64()),
+        };
+        use std::cmp::Ordering::{Greater, Less};
+
+        match *self {
+            Self::Unknown => true,
+            Self::Exact(v) => eq(current, v),
+            Self::NotEqual(v) => !eq(current, v),
+            Self::GreaterThan(v) => cmp(current, v) == Some(Greater),
+            Self::LessThan(v) => cmp(current, v) == Some(Less),
+            Self::Between(lo, hi) => {
+                cmp(current, lo) != Some(Less) && cmp(current, hi) != Some(Greater)
+            }
+            _ => {
+                let Some(previous) = previous else {
+                    return false;
+                };
+                match *self {
+                    Self::Changed => !eq(current, previous),
+                    Self::Unchanged => eq(current, previous),
+                    Self::Increased => cmp(current, previous) == Some(Greater),
+                    Self::Decreased => cmp(current, previous) == Some(Less),
+                    Self::IncreasedBy(d) => eq(diff(current, previous), d),
+                    Self::DecreasedBy(d) => eq(diff(previous, current), d),
+                    _ => unreachable!(),
+                }
+            }
+        }
+    }
+}
+
+/// A candidate address
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScanResult {
+    /// Guest address
+    pub address: u32,
+    /// Value seen by the latest scan
+    pub value: ScanValue,
+    /// Value seen by the scan before it
+    pub previous: Option<ScanValue>,
+}
+
+/// Address range to scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanRange {
+    /// First address
+    pub start: u32,
+    /// Size in bytes
+    pub size: u32,
+}
+
+/// Scanner state between scans
+#[derive(Debug, Clone)]
+enum ScanState {
+    /// No scan yet
+    Empty,
+    /// Copies of readable memory from an unknown-value first scan
+    Snapshot(Vec<(u32, Vec<u8>)>),
+    /// Candidate addresses
+    Candidates(Vec<ScanResult>),
+}
+
+/// Iterative memory value scanner
+pub struct MemoryScanner {
+    /// Type of value searched for
+    value_type: ScanValueType,
+    /// Address alignment of candidates (1 = every byte)
+    alignment: u32,
+    /// Tolerance for float comparisons
+    tolerance: f64,
+    /// Ranges to scan (empty = all non-MMIO memory regions)
+    ranges: Vec<ScanRange>,
+    /// Maximum number of candidates kept
+    max_results: usize,
+    /// Whether the last scan hit `max_results`
+    truncated: bool,
+    /// Number of scans performed since the last reset
+    scan_count: u32,
+    state: ScanState,
+}
+
+impl MemoryScanner {
+    /// Create a scanner for values of `value_type`
+    pub fn new(value_type: ScanValueType) -> Self {
+        Self {
+            value_type,
+            alignment: value_type.size() as u32,
+            tolerance: if value_type.is_float() { 1e-4 } else { 0.0 },
+            ranges: Vec::new(),
+            max_results: DEFAULT_MAX_RESULTS,
+            truncated: false,
+            scan_count: 0,
+            state: ScanState::Empty,
+        }
+    }
+
+    /// Set the candidate alignment in bytes (resets the scan)
+    pub fn set_alignment(&mut self, alignment: u32) {
+        self.alignment = alignment.max(1);
+        self.reset();
+    }
+
+    /// Set the tolerance for float comparisons
+    pub fn set_tolerance(&mut self, tolerance: f64) {
+        self.tolerance = tolerance.abs();
+    }
+
+    /// Restrict scanning to specific ranges (resets the scan)
+    pub fn set_ranges(&mut self, ranges: Vec<ScanRange>) {
+        self.ranges = ranges;
+        self.reset();
+    }
+
+    /// Set the maximum number of candidates kept
+    pub fn set_max_results(&mut self, max_results: usize) {
+        self.max_results = max_results.max(1);
+    }
+
+    /// Type of value searched for
+    pub fn value_type(&self) -> ScanValueType {
+        self.value_type
+    }
+
+    /// Number of scans since the last reset
+    pub fn scan_count(&self) -> u32 {
+        self.scan_count
+    }
+
+    /// Whether the last scan stopped at the result cap
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Discard all results
+    pub fn reset(&mut self) {
+        self.state = ScanState::Empty;
+        self.truncated = false;
+        self.scan_count = 0;
+    }
+
+    /// Candidate addresses (empty after an unknown-value first scan)
+    pub fn results(&self) -> &[ScanResult] {
+        match &self.state {
+            ScanState::Candidates(results) => results,
+            _ => &[],
+        }
+    }
+
+    /// Number of candidates (every aligned address after an unknown scan)
+    pub fn result_count(&self) -> usize {
+        match &self.state {
+            ScanState::Empty => 0,
+            ScanState::Snapshot(chunks) => chunks
+                .iter()
+                .map(|(_, data)| self.slots(data.len()))
+                .sum(),
+            ScanState::Candidates(results) => results.len(),
+        }
+    }
+
+    /// Number of aligned value slots in a run of `len` bytes
+    fn slots(&self, len: usize) -> usize {
+        let size = self.value_type.size();
+        if len < size {
+            0
+        } else {
+            (len - size) / self.alignment as usize + 1
+        }
+    }
+
+    /// Scan memory, narrowing previous results if there are any
+    ///
+    /// Relative conditions compare against the previous scan; on a first
+    /// scan they behave like [`ScanCondition::Unknown`]. Returns the
+    /// number of candidates left.
+    pub fn scan(&mut self, memory: &MemoryManager, condition: ScanCondition) -> usize {
+        let state = std::mem::replace(&mut self.state, ScanState::Empty);
+        self.truncated = false;
+
+        self.state = match state {
+            ScanState::Empty if condition.is_relative() || condition == ScanCondition::Unknown => {
+                ScanState::Snapshot(self.capture(memory))
+            }
+            ScanState::Empty => ScanState::Candidates(self.first_scan(memory, condition)),
+            ScanState::Snapshot(chunks) => {
+                ScanState::Candidates(self.scan_snapshot(memory, &chunks, condition))
+            }
+            ScanState::Candidates(results) => {
+                ScanState::Candidates(self.narrow(memory, results, condition))
+            }
+        };
+        self.scan_count += 1;
+
+        let count = self.result_count();
+        tracing::debug!(
+            "Memory scan {} ({}, {:?}): {} results",
+            self.scan_count,
+            self.value_type.name(),
+            condition,
+            count
+        );
+        count
+    }
+
+    /// Ranges to scan
+    fn scan_ranges(&self, memory: &MemoryManager) -> Vec<ScanRange> {
+        if !self.ranges.is_empty() {
+            return self.ranges.clone();
+        }
+        memory
+            .regions()
+            .iter()
+            .filter(|r| !r.flags.contains(PageFlags::MMIO))
+            .map(|r| ScanRange {
+                start: r.base,
+                size: r.size,
+            })
+            .collect()
+    }
+
+    /// Split the scan ranges into runs of readable pages
+    fn readable_runs(&self, memory: &MemoryManager) -> Vec<(u32, u32)> {
+        let mut runs: Vec<(u32, u32)> = Vec::new();
+        for range in self.scan_ranges(memory) {
+            let end = range.start as u64 + range.size as u64;
+            let mut addr = range.start as u64;
+            while addr < end {
+                let page_end = ((addr / PAGE_SIZE as u64) + 1) * PAGE_SIZE as u64;
+                let chunk_end = page_end.min(end);
+                if memory.page_flags(addr as u32).contains(PageFlags::READ) {
+                    match runs.last_mut() {
+                        Some((start, len)) if *start as u64 + *len as u64 == addr => {
+                            *len += (chunk_end - addr) as u32;
+                        }
+                        _ => runs.push((addr as u32, (chunk_end - addr) as u32)),
+                    }
+                }
+                addr = chunk_end;
+            }
+        }
+        runs
+    }
+
+    /// Borrow a readable run of guest memory
+    fn run_bytes(memory: &MemoryManager, start: u32, len: u32) -> Option<&[u8]> {
+        let ptr = memory.get_ptr(start, len, PageFlags::READ).ok()?;
+        // Safety: the whole run was just checked to be readable
+        Some(unsafe { std::slice::from_raw_parts(ptr, len as usize) })
+    }
+
+    /// Copy all readable memory for a later relative scan
+    fn capture(&self, memory: &MemoryManager) -> Vec<(u32, Vec<u8>)> {
+        self.readable_runs(memory)
+            .into_iter()
+            .filter_map(|(start, len)| {
+                Self::run_bytes(memory, start, len).map(|bytes| (start, bytes.to_vec()))
+            })
+            .collect()
+    }
+
+    /// Offsets of aligned slots in a run starting at `start`
+    fn offsets(&self, start: u32, len: usize) -> impl Iterator<Item = usize> {
+        let size = self.value_type.size();
+        let align = self.alignment as usize;
+        let first = (align - start as usize % align) % align;
+        (first..len.saturating_sub(size - 1)).step_by(align)
+    }
+
+    /// First scan for an absolute condition
+    fn first_scan(&mut self, memory: &MemoryManager, condition: ScanCondition) -> Vec<ScanResult> {
+        let size = self.value_type.size();
+        let mut results = Vec::new();
+        'runs: for (start, len) in self.readable_runs(memory) {
+            let Some(bytes) = Self::run_bytes(memory, start, len) else {
+                continue;
+            };
+            for offset in self.offsets(start, bytes.len()) {
+                let value = self.value_type.decode(&bytes[offset..offset + size]);
+                if condition.matches(value, None, self.tolerance) {
+                    if results.len() >= self.max_results {
+                        self.truncated = true;
+                        break 'runs;
+                    }
+                    results.push(ScanResult {
+                        address: start + offset as u32,
+                        value,
+                        previous: None,
+                    });
+                }
+            }
+        }
+        results
+    }
+
+    /// Compare current memory against an unknown-value snapshot
+    fn scan_snapshot(
+        &mut self,
+        memory: &MemoryManager,
+        chunks: &[(u32, Vec<u8>)],
+        condition: ScanCondition,
+    ) -> Vec<ScanResult> {
+        let size = self.value_type.size();
+        let mut results = Vec::new();
+        'chunks: for (start, old) in chunks {
+            let Some(new) = Self::run_bytes(memory, *start, old.len() as u32) else {
+                continue;
+            };
+            for offset in self.offsets(*start, old.len()) {
+                let previous = self.value_type.decode(&old[offset..offset + size]);
+                let value = self.value_type.decode(&new[offset..offset + size]);
+                if condition.matches(value, Some(previous), self.tolerance) {
+                    if results.len() >= self.max_results {
+                        self.truncated = true;
+                        break 'chunks;
+                    }
+                    results.push(ScanResult {
+                        address: start + offset as u32,
+                        value,
+                        previous: Some(previous),
+                    });
+                }
+            }
+        }
+        results
+    }
+
+    /// Narrow an existing candidate list
+    fn narrow(
+        &self,
+        memory: &MemoryManager,
+        results: Vec<ScanResult>,
+        condition: ScanCondition,
+    ) -> Vec<ScanResult> {
+        results
+            .into_iter()
+            .filter_map(|result| {
+                let value = self.read_value(memory, result.address)?;
+                condition
+                    .matches(value, Some(result.value), self.tolerance)
+                    .then_some(ScanResult {
+                        address: result.address,
+                        value,
+                        previous: Some(result.value),
+                    })
+            })
+            .collect()
+    }
+
+    /// Read the current value at `address`
+    pub fn read_value(&self, memory: &MemoryManager, address: u32) -> Option<ScanValue> {
+        let bytes = Self::run_bytes(memory, address, self.value_type.size() as u32)?;
+        Some(self.value_type.decode(bytes))
+    }
+
+    /// Write a value of the scanner's type to `address`
+    pub fn write_value(
+        &self,
+        memory: &MemoryManager,
+        address: u32,
+        value: ScanValue,
+    ) -> Result<(), oc_core::error::MemoryError> {
+        memory.write_bytes(address, &self.value_type.encode(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (std::sync::Arc<MemoryManager>, u32, MemoryScanner) {
+        let memory = MemoryManager::new().unwrap();
+        let base = memory.allocate(0x2000, 0x1000, PageFlags::RW).unwrap();
+        let mut scanner = MemoryScanner::new(ScanValueType::U32);
+        scanner.set_ranges(vec![ScanRange { start: base, size: 0x2000 }]);
+        (memory, base, scanner)
+    }
+
+    #[test]
+    fn test_exact_then_narrow() {
+        let (memory, base, mut scanner) = setup();
+        memory.write_be32(base + 0x10, 100).unwrap();
+        memory.write_be32(base + 0x1800, 100).unwrap();
+
+        assert_eq!(scanner.scan(&memory, ScanCondition::Exact(ScanValue::Int(100))), 2);
+
+        // Only one of them changes
+        memory.write_be32(base + 0x1800, 90).unwrap();
+        assert_eq!(scanner.scan(&memory, ScanCondition::Decreased), 1);
+        let result = scanner.results()[0];
+        assert_eq!(result.address, base + 0x1800);
+        assert_eq!(result.value, ScanValue::Int(90));
+        assert_eq!(result.previous, Some(ScanValue::Int(100)));
+
+        scanner.write_value(&memory, result.address, ScanValue::Int(999)).unwrap();
+        assert_eq!(memory.read_be32(base + 0x1800).unwrap(), 999);
+    }
+
+    #[test]
+    fn test_unknown_then_relative() {
+        let (memory, base, mut scanner) = setup();
+        memory.write_be32(base + 0x20, 5).unwrap();
+
+        // Unknown first scan keeps every aligned slot
+        assert_eq!(scanner.scan(&memory, ScanCondition::Unknown), 0x2000 / 4);
+
+        memory.write_be32(base + 0x20, 8).unwrap();
+        assert_eq!(
+            scanner.scan(&memory, ScanCondition::IncreasedBy(ScanValue::Int(3))),
+            1
+        );
+        assert_eq!(scanner.results()[0].address, base + 0x20);
+        assert_eq!(scanner.scan(&memory, ScanCondition::Unchanged), 1);
+    }
+
+    #[test]
+    fn test_float_tolerance() {
+        let (memory, base, _) = setup();
+        let mut scanner = MemoryScanner::new(ScanValueType::F32);
+        scanner.set_ranges(vec![ScanRange { start: base, size: 0x1000 }]);
+        memory.write_be32(base + 8, 2.71f32.to_bits()).unwrap();
+
+        scanner.set_tolerance(0.02);
+        assert_eq!(scanner.scan(&memory, ScanCondition::Exact(ScanValue::Float(2.7))), 1);
+        scanner.reset();
+        scanner.set_tolerance(0.0001);
+        assert_eq!(scanner.scan(&memory, ScanCondition::Exact(ScanValue::Float(2.7))), 0);
+    }
+
+    #[test]
+    fn test_parse_and_encode() {
+        assert_eq!(ScanValueType::U32.parse("0x10"), Some(ScanValue::Int(16)));
+        assert_eq!(ScanValueType::I16.parse("-2"), Some(ScanValue::Int(-2)));
+        assert_eq!(ScanValueType::I16.encode(ScanValue::Int(-2)), [0xFF, 0xFE]);
+        assert_eq!(ScanValueType::I16.decode(&[0xFF, 0xFE]), ScanValue::Int(-2));
+        assert!(ScanValueType::U8.parse("abc").is_none());
+    }
+}
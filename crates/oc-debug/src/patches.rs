@@ -0,0 +1,583 @@
+//! Runtime code patches and hooks for PPU code
+//!
+//! A patch replaces instructions with ones assembled from a few lines of
+//! PPU assembly, so NOPing an instruction is the patch `nop` and
+//! redirecting a branch is `b 0x12345678`. The original words are kept to
+//! revert the patch. Patches are stored per game next to the
+//! configuration and reapplied when the game is loaded.
+//!
+//! A hook calls a Rust function when a thread reaches an address, without
+//! changing guest memory. The function can inspect and modify the thread
+//! and then run the original instruction, skip it or return from the
+//! hooked function.
+
+use crate::cheats::title_file;
+use oc_memory::MemoryManager;
+use oc_ppu::PpuThread;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// `ori r0, r0, 0`
+pub const NOP: u32 = 0x6000_0000;
+
+/// Assemble one line of PPU assembly placed at `address`
+///
+/// Supports `nop`, `blr`, `bctr`, `bctrl`, `trap`, `b`/`bl`/`ba`/`bla`,
+/// `beq`/`bne`/`blt`/`bge`/`bgt`/`ble` on cr0, `li`, `lis`, `addi`,
+/// `addis`, `ori`, `oris`, `mr` and `.long` for raw words. Branch targets
+/// are absolute addresses.
+pub fn assemble(line: &str, address: u32) -> Result<u32, String> {
+    let line = line.split('#').next().unwrap_or_default().trim();
+    let (mnemonic, operands) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let operands: Vec<&str> = operands.split(',').map(str::trim).filter(|op| !op.is_empty()).collect();
+    let expect = |count: usize| {
+        if operands.len() == count {
+            Ok(())
+        } else {
+            Err(format!("{} takes {} operands: {}", mnemonic, count, line))
+        }
+    };
+    let reg = |index: usize| parse_register(operands[index]).ok_or_else(|| format!("Bad register: {}", operands[index]));
+    let imm = |index: usize| parse_imm16(operands[index]).ok_or_else(|| format!("Bad 16-bit immediate: {}", operands[index]));
+    let target = |index: usize| parse_number(operands[index]).and_then(|t| u32::try_from(t).ok()).ok_or_else(|| format!("Bad branch target: {}", operands[index]));
+    // D-form: opcode, rD/rS, rA, immediate
+    let d_form = |op: u32, rt: u32, ra: u32, imm: u32| (op << 26) | (rt << 21) | (ra << 16) | imm;
+
+    let word = match mnemonic.to_ascii_lowercase().as_str() {
+        "" => return Err("Empty instruction".to_string()),
+        "nop" => NOP,
+        "blr" => 0x4E80_0020,
+        "bctr" => 0x4E80_0420,
+        "bctrl" => 0x4E80_0421,
+        "trap" => 0x7FE0_0008,
+        ".long" | ".word" => {
+            expect(1)?;
+            parse_number(operands[0])
+                .and_then(|value| u32::try_from(value).ok())
+                .ok_or_else(|| format!("Bad word: {}", operands[0]))?
+        }
+        branch @ ("b" | "bl" | "ba" | "bla") => {
+            expect(1)?;
+            let absolute = branch.starts_with("ba");
+            let link = branch.ends_with('l') as u32;
+            let target = target(0)?;
+            let offset = if absolute { target } else { target.wrapping_sub(address) };
+            // 26-bit signed, word aligned
+            let signed = offset as i32;
+            if target & 3 != 0 || !(-0x200_0000..0x200_0000).contains(&signed) {
+                return Err(format!("Branch target 0x{:08x} out of range", target));
+            }
+            (18 << 26) | (offset & 0x03FF_FFFC) | ((absolute as u32) << 1) | link
+        }
+        cond @ ("beq" | "bne" | "blt" | "bge" | "bgt" | "ble") => {
+            expect(1)?;
+            // BO 12 branches if the cr0 bit is set, BO 4 if it is clear
+            let (bo, bi) = match cond {
+                "blt" => (12, 0),
+                "bge" => (4, 0),
+                "bgt" => (12, 1),
+                "ble" => (4, 1),
+                "beq" => (12, 2),
+                _ => (4, 2),
+            };
+            let target = target(0)?;
+            let offset = target.wrapping_sub(address);
+            let signed = offset as i32;
+            if target & 3 != 0 || !(-0x8000..0x8000).contains(&signed) {
+                return Err(format!("Branch target 0x{:08x} out of range", target));
+            }
+            (16 << 26) | (bo << 21) | (bi << 16) | (offset & 0xFFFC)
+        }
+        "li" => {
+            expect(2)?;
+            d_form(14, reg(0)?, 0, imm(1)?)
+        }
+        "lis" => {
+            expect(2)?;
+            d_form(15, reg(0)?, 0, imm(1)?)
+        }
+        "addi" => {
+            expect(3)?;
+            d_form(14, reg(0)?, reg(1)?, imm(2)?)
+        }
+        "addis" => {
+            expect(3)?;
+            d_form(15, reg(0)?, reg(1)?, imm(2)?)
+        }
+        // ori rA, rS, imm has rS in the first register field
+        "ori" => {
+            expect(3)?;
+            d_form(24, reg(1)?, reg(0)?, imm(2)?)
+        }
+        "oris" => {
+            expect(3)?;
+            d_form(25, reg(1)?, reg(0)?, imm(2)?)
+        }
+        "mr" => {
+            expect(2)?;
+            let (ra, rs) = (reg(0)?, reg(1)?);
+            (31 << 26) | (rs << 21) | (ra << 16) | (rs << 11) | (444 << 1)
+        }
+        _ => return Err(format!("Unsupported instruction: {}", line)),
+    };
+    Ok(word)
+}
+
+fn parse_number(text: &str) -> Option<i64> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let value = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+        None => text.parse().ok()?,
+    };
+    Some(if negative { -value } else { value })
+}
+
+/// Signed or unsigned 16-bit immediate, as the instruction field
+fn parse_imm16(text: &str) -> Option<u32> {
+    let value = parse_number(text)?;
+    (-0x8000..=0xFFFF).contains(&value).then_some(value as u32 & 0xFFFF)
+}
+
+fn parse_register(text: &str) -> Option<u32> {
+    let digits = text.strip_prefix('r').or_else(|| text.strip_prefix('R')).unwrap_or(text);
+    let index: u32 = digits.parse().ok()?;
+    (index < 32).then_some(index)
+}
+
+/// A persistent patch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CodePatch {
+    pub name: String,
+    /// Address of the first replaced instruction
+    pub address: u32,
+    /// Whether the patch is applied
+    pub enabled: bool,
+    /// Assembly, one instruction per line
+    pub code: String,
+}
+
+impl Default for CodePatch {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            address: 0,
+            enabled: true,
+            code: String::new(),
+        }
+    }
+}
+
+impl CodePatch {
+    /// Create an enabled patch
+    pub fn new(name: &str, address: u32, code: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            address,
+            enabled: true,
+            code: code.trim().to_string(),
+        }
+    }
+
+    /// Replace the instruction at `address` with a NOP
+    pub fn nop(address: u32) -> Self {
+        Self::new(&format!("NOP 0x{:08X}", address), address, "nop")
+    }
+
+    /// Replace the instruction at `address` with a branch to `target`
+    pub fn branch(address: u32, target: u32, link: bool) -> Self {
+        let mnemonic = if link { "bl" } else { "b" };
+        Self::new(
+            &format!("Branch 0x{:08X} to 0x{:08X}", address, target),
+            address,
+            &format!("{} 0x{:08X}", mnemonic, target),
+        )
+    }
+
+    /// Assemble the patch into instruction words
+    pub fn assemble(&self) -> Result<Vec<u32>, String> {
+        if self.address & 3 != 0 {
+            return Err(format!("Address 0x{:08X} is not word aligned", self.address));
+        }
+        let words = self
+            .code
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter(|line| !line.is_empty())
+            .enumerate()
+            .map(|(i, line)| {
+                assemble(line, self.address.wrapping_add(i as u32 * 4)).map_err(|e| format!("Line {}: {}", i + 1, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if words.is_empty() {
+            return Err("Patch has no instructions".to_string());
+        }
+        Ok(words)
+    }
+}
+
+/// What a thread does after a hook returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookAction {
+    /// Execute the instruction at the hooked address
+    Continue,
+    /// Skip the instruction at the hooked address
+    Skip,
+    /// Return to the caller through LR, as if the hooked function ended
+    Return,
+}
+
+/// Rust function called when a thread reaches a hooked address
+pub type HookFn = Box<dyn FnMut(&mut PpuThread, &MemoryManager) -> HookAction + Send>;
+
+/// A patch with the words it replaced while applied
+struct AppliedPatch {
+    patch: CodePatch,
+    original: Option<Vec<u32>>,
+}
+
+/// On-disk layout of a game's patch list
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PatchFile {
+    patch: Vec<CodePatch>,
+}
+
+/// Patches and hooks of the running game
+#[derive(Default)]
+pub struct PatchManager {
+    /// Title the patch list belongs to (None until a game is loaded)
+    title_id: Option<String>,
+    patches: Vec<AppliedPatch>,
+    /// Hooks with their names, by address
+    hooks: BTreeMap<u32, (String, HookFn)>,
+}
+
+impl PatchManager {
+    /// Create an empty patch list
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Directory the per-game patch lists are stored in
+    pub fn directory() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("oxidized-cell")
+            .join("patches")
+    }
+
+    /// Switch to the saved patches of a game, returning how many were loaded
+    ///
+    /// Nothing is written to memory; call [`PatchManager::apply_enabled`]
+    /// once the game is loaded. Hooks are kept.
+    pub fn load_for_title(&mut self, title_id: &str) -> Result<usize, String> {
+        self.title_id = Some(title_id.to_string());
+        self.patches.clear();
+        let path = self.path().unwrap_or_default();
+        if !path.exists() {
+            return Ok(0);
+        }
+        self.load_file(&path)
+    }
+
+    fn load_file(&mut self, path: &Path) -> Result<usize, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let file: PatchFile = toml::from_str(&text).map_err(|e| format!("Invalid patch list {}: {}", path.display(), e))?;
+        let count = file.patch.len();
+        self.patches.extend(file.patch.into_iter().map(|patch| AppliedPatch { patch, original: None }));
+        Ok(count)
+    }
+
+    /// Title of the loaded list
+    pub fn title_id(&self) -> Option<&str> {
+        self.title_id.as_deref()
+    }
+
+    /// File the list is saved to
+    pub fn path(&self) -> Option<PathBuf> {
+        Some(title_file(&Self::directory(), self.title_id.as_deref()?))
+    }
+
+    /// Save the list of the current game
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = self.path() else {
+            return Err("No game is loaded".to_string());
+        };
+        self.save_to(&path)
+    }
+
+    fn save_to(&self, path: &Path) -> Result<(), String> {
+        let file = PatchFile { patch: self.patches().cloned().collect() };
+        let text = toml::to_string_pretty(&file).map_err(|e| e.to_string())?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        std::fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// All patches
+    pub fn patches(&self) -> impl Iterator<Item = &CodePatch> {
+        self.patches.iter().map(|entry| &entry.patch)
+    }
+
+    /// Number of patches
+    pub fn len(&self) -> usize {
+        self.patches.len()
+    }
+
+    /// Whether there are no patches
+    pub fn is_empty(&self) -> bool {
+        self.patches.is_empty()
+    }
+
+    /// Whether a patch is currently written to memory
+    pub fn is_applied(&self, index: usize) -> bool {
+        self.patches.get(index).is_some_and(|entry| entry.original.is_some())
+    }
+
+    /// Words a patch replaced, while it is applied
+    pub fn original(&self, index: usize) -> Option<&[u32]> {
+        self.patches.get(index)?.original.as_deref()
+    }
+
+    /// Add a patch, writing it to memory if it is enabled
+    pub fn add(&mut self, patch: CodePatch, memory: &MemoryManager) -> Result<usize, String> {
+        patch.assemble()?;
+        self.patches.push(AppliedPatch { patch, original: None });
+        let index = self.patches.len() - 1;
+        if self.patches[index].patch.enabled {
+            if let Err(e) = self.apply(index, memory) {
+                self.patches.pop();
+                return Err(e);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Revert and remove a patch
+    pub fn remove(&mut self, index: usize, memory: &MemoryManager) -> Result<CodePatch, String> {
+        if index >= self.patches.len() {
+            return Err(format!("No patch {}", index));
+        }
+        self.revert(index, memory)?;
+        Ok(self.patches.remove(index).patch)
+    }
+
+    /// Apply or revert a patch
+    pub fn set_enabled(&mut self, index: usize, enabled: bool, memory: &MemoryManager) -> Result<(), String> {
+        if index >= self.patches.len() {
+            return Err(format!("No patch {}", index));
+        }
+        if enabled {
+            self.apply(index, memory)?;
+        } else {
+            self.revert(index, memory)?;
+        }
+        self.patches[index].patch.enabled = enabled;
+        Ok(())
+    }
+
+    /// Write the enabled patches that aren't applied yet, returning the errors
+    pub fn apply_enabled(&mut self, memory: &MemoryManager) -> Vec<String> {
+        let mut errors = Vec::new();
+        for index in 0..self.patches.len() {
+            if !self.patches[index].patch.enabled {
+                continue;
+            }
+            if let Err(e) = self.apply(index, memory) {
+                errors.push(format!("{}: {}", self.patches[index].patch.name, e));
+            }
+        }
+        errors
+    }
+
+    fn apply(&mut self, index: usize, memory: &MemoryManager) -> Result<(), String> {
+        let entry = &mut self.patches[index];
+        if entry.original.is_some() {
+            return Ok(());
+        }
+        let words = entry.patch.assemble()?;
+        let address = entry.patch.address;
+        let original = (0..words.len() as u32)
+            .map(|i| memory.read_be32(address + i * 4))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Cannot patch 0x{:08X}: {}", address, e))?;
+        for (i, &word) in words.iter().enumerate() {
+            memory
+                .write_be32(address + i as u32 * 4, word)
+                .map_err(|e| format!("Cannot patch 0x{:08X}: {}", address, e))?;
+        }
+        tracing::info!("Applied patch \"{}\" at 0x{:08x}", entry.patch.name, address);
+        entry.original = Some(original);
+        Ok(())
+    }
+
+    fn revert(&mut self, index: usize, memory: &MemoryManager) -> Result<(), String> {
+        let entry = &mut self.patches[index];
+        let Some(original) = entry.original.take() else {
+            return Ok(());
+        };
+        let address = entry.patch.address;
+        for (i, &word) in original.iter().enumerate() {
+            memory
+                .write_be32(address + i as u32 * 4, word)
+                .map_err(|e| format!("Cannot revert 0x{:08X}: {}", address, e))?;
+        }
+        tracing::info!("Reverted patch \"{}\" at 0x{:08x}", entry.patch.name, address);
+        Ok(())
+    }
+
+    /// Drop the patch list without touching memory, when the game is unloaded
+    ///
+    /// Hooks are kept.
+    pub fn clear(&mut self) {
+        self.title_id = None;
+        self.patches.clear();
+    }
+
+    /// Call `hook` whenever a PPU thread reaches `address`, replacing any other hook there
+    pub fn add_hook(
+        &mut self,
+        address: u32,
+        name: &str,
+        hook: impl FnMut(&mut PpuThread, &MemoryManager) -> HookAction + Send + 'static,
+    ) {
+        self.hooks.insert(address, (name.to_string(), Box::new(hook)));
+    }
+
+    /// Remove the hook at `address`
+    pub fn remove_hook(&mut self, address: u32) -> bool {
+        self.hooks.remove(&address).is_some()
+    }
+
+    /// Hooked addresses with their names
+    pub fn hooks(&self) -> impl Iterator<Item = (u32, &str)> {
+        self.hooks.iter().map(|(&address, (name, _))| (address, name.as_str()))
+    }
+
+    /// Whether any hooks are installed
+    pub fn has_hooks(&self) -> bool {
+        !self.hooks.is_empty()
+    }
+
+    /// Run the hook at the thread's PC, if there is one
+    ///
+    /// `Skip` and `Return` are applied to the thread here; on `Continue`
+    /// the caller executes the instruction.
+    pub fn run_hook(&mut self, thread: &mut PpuThread, memory: &MemoryManager) -> Option<HookAction> {
+        let (_, hook) = self.hooks.get_mut(&(thread.pc() as u32))?;
+        let action = hook(thread, memory);
+        match action {
+            HookAction::Continue => {}
+            HookAction::Skip => thread.advance_pc(),
+            HookAction::Return => thread.set_pc(thread.regs.lr),
+        }
+        Some(action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PpuDisassembler;
+    use oc_memory::PageFlags;
+
+    #[test]
+    fn test_assemble() {
+        let dis = |line: &str, address: u32| {
+            let word = assemble(line, address).unwrap();
+            PpuDisassembler::disassemble(address as u64, word).to_string()
+        };
+        assert_eq!(assemble("nop", 0), Ok(NOP));
+        assert_eq!(assemble("blr  # return", 0), Ok(0x4E80_0020));
+        assert_eq!(assemble("b 0x10010", 0x10000), Ok(0x4800_0010));
+        assert_eq!(assemble("bl 0x10000", 0x10010), Ok(0x4BFF_FFF1));
+        assert_eq!(assemble("ba 0x100", 0x10000), Ok(0x4800_0102));
+        assert_eq!(assemble("beq 0x10020", 0x10000), Ok(0x4182_0020));
+        assert_eq!(assemble("bne 0xFFF8", 0x10000), Ok(0x4082_FFF8));
+        assert_eq!(assemble("li r3, 1", 0), Ok(0x3860_0001));
+        assert_eq!(assemble("li r3, -1", 0), Ok(0x3860_FFFF));
+        assert_eq!(assemble("lis r4, 0x8001", 0), Ok(0x3C80_8001));
+        assert_eq!(assemble("ori r4, r4, 0x1234", 0), Ok(0x6084_1234));
+        assert_eq!(assemble("mr r3, r31", 0), Ok(0x7FE3_FB78));
+        assert_eq!(assemble(".long 0xDEADBEEF", 0), Ok(0xDEAD_BEEF));
+        assert!(dis("addi r1, r1, -16", 0).starts_with("addi"));
+
+        assert!(assemble("b 0x10002", 0x10000).is_err());
+        assert!(assemble("beq 0x20000", 0x10000).is_err());
+        assert!(assemble("li r32, 0", 0).is_err());
+        assert!(assemble("li r3, 0x10000", 0).is_err());
+        assert!(assemble("li r3", 0).is_err());
+        assert!(assemble("frob r3", 0).is_err());
+        assert!(CodePatch::new("", 0x10002, "nop").assemble().is_err());
+        assert!(CodePatch::new("", 0x10000, "# nothing").assemble().is_err());
+    }
+
+    #[test]
+    fn test_patch_apply_revert() {
+        let memory = MemoryManager::new().unwrap();
+        let base = memory.allocate(0x1000, 0x1000, PageFlags::RWX).unwrap();
+        for i in 0..4 {
+            memory.write_be32(base + i * 4, 0x3860_0000 + i).unwrap();
+        }
+        let mut patches = PatchManager::new();
+
+        let nop = patches.add(CodePatch::nop(base), &memory).unwrap();
+        let call = patches.add(CodePatch::new("Return 5", base + 4, "li r3, 5\nblr"), &memory).unwrap();
+        assert_eq!(memory.read_be32(base).unwrap(), NOP);
+        assert_eq!(memory.read_be32(base + 4).unwrap(), 0x3860_0005);
+        assert_eq!(memory.read_be32(base + 8).unwrap(), 0x4E80_0020);
+        assert_eq!(patches.original(call), Some(&[0x3860_0001, 0x3860_0002][..]));
+
+        patches.set_enabled(nop, false, &memory).unwrap();
+        assert!(!patches.is_applied(nop));
+        assert_eq!(memory.read_be32(base).unwrap(), 0x3860_0000);
+        patches.set_enabled(nop, true, &memory).unwrap();
+        assert_eq!(memory.read_be32(base).unwrap(), NOP);
+
+        // Patches are reapplied to a freshly loaded game
+        let path = std::env::temp_dir().join(format!("oc-patches-{}.toml", std::process::id()));
+        patches.save_to(&path).unwrap();
+        patches.remove(call, &memory).unwrap();
+        assert_eq!(memory.read_be32(base + 8).unwrap(), 0x3860_0002);
+        let mut loaded = PatchManager::new();
+        assert_eq!(loaded.load_file(&path), Ok(2));
+        let _ = std::fs::remove_file(&path);
+        memory.write_be32(base, 0x3860_0000).unwrap();
+        assert!(loaded.apply_enabled(&memory).is_empty());
+        assert_eq!(memory.read_be32(base).unwrap(), NOP);
+        assert_eq!(memory.read_be32(base + 8).unwrap(), 0x4E80_0020);
+
+        assert!(patches.add(CodePatch::nop(0x10), &memory).is_err());
+        assert_eq!(patches.len(), 1);
+    }
+
+    #[test]
+    fn test_hooks() {
+        let memory = MemoryManager::new().unwrap();
+        let mut patches = PatchManager::new();
+        let mut thread = PpuThread::new(0, memory.clone());
+        thread.set_pc(0x10000);
+        thread.regs.lr = 0x20000;
+        assert_eq!(patches.run_hook(&mut thread, &memory), None);
+
+        patches.add_hook(0x10000, "return 7", |thread, _| {
+            thread.set_gpr(3, 7);
+            HookAction::Return
+        });
+        patches.add_hook(0x20000, "skip", |_, _| HookAction::Skip);
+        assert_eq!(patches.hooks().collect::<Vec<_>>(), [(0x10000, "return 7"), (0x20000, "skip")]);
+
+        assert_eq!(patches.run_hook(&mut thread, &memory), Some(HookAction::Return));
+        assert_eq!((thread.gpr(3), thread.pc()), (7, 0x20000));
+        assert_eq!(patches.run_hook(&mut thread, &memory), Some(HookAction::Skip));
+        assert_eq!(thread.pc(), 0x20004);
+        assert!(patches.remove_hook(0x20000));
+        assert!(!patches.remove_hook(0x20000));
+    }
+}
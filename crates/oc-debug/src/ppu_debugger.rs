@@ -102,6 +102,11 @@ impl PpuDebugger {
         self.memory = Some(memory);
     }
 
+    /// Get the attached memory manager
+    pub fn memory(&self) -> Option<&Arc<MemoryManager>> {
+        self.memory.as_ref()
+    }
+
     /// Pause execution
     pub fn pause(&mut self) {
         self.state = DebugState::Paused;
@@ -131,11 +136,12 @@ impl PpuDebugger {
 
     /// Check if execution should stop before executing an instruction
     /// Returns true if a breakpoint was hit or we're stepping
-    pub fn check_before_execute(&mut self, pc: u64) -> bool {
+    pub fn check_before_execute(&mut self, thread: &PpuThread) -> bool {
+        let pc = thread.pc();
         match self.state {
             DebugState::Running => {
                 // Check for breakpoints
-                if self.breakpoints.check_execution(pc).is_some() {
+                if self.breakpoints.check_execution(pc, thread).is_some() {
                     tracing::info!("PPU debugger: breakpoint hit at 0x{:016x}", pc);
                     self.state = DebugState::Paused;
                     return true;
@@ -156,7 +162,7 @@ impl PpuDebugger {
                     }
                 }
                 // Also check breakpoints while stepping over
-                if self.breakpoints.check_execution(pc).is_some() {
+                if self.breakpoints.check_execution(pc, thread).is_some() {
                     tracing::info!("PPU debugger: breakpoint hit at 0x{:016x}", pc);
                     self.state = DebugState::Paused;
                     return true;
@@ -360,6 +366,12 @@ impl RegisterSnapshot {
 mod tests {
     use super::*;
 
+    fn thread_at(pc: u64) -> PpuThread {
+        let mut thread = PpuThread::new(0, MemoryManager::new().unwrap());
+        thread.set_pc(pc);
+        thread
+    }
+
     #[test]
     fn test_debugger_creation() {
         let debugger = PpuDebugger::new();
@@ -386,7 +398,7 @@ mod tests {
         assert_eq!(debugger.state, DebugState::Stepping);
         
         // After check, should be paused
-        assert!(debugger.check_before_execute(0x10000));
+        assert!(debugger.check_before_execute(&thread_at(0x10000)));
         assert_eq!(debugger.state, DebugState::Paused);
     }
 
@@ -397,10 +409,10 @@ mod tests {
         debugger.breakpoints.add_execution_breakpoint(0x10000);
         
         // Should not stop at other addresses
-        assert!(!debugger.check_before_execute(0x10004));
+        assert!(!debugger.check_before_execute(&thread_at(0x10004)));
         
         // Should stop at breakpoint
-        assert!(debugger.check_before_execute(0x10000));
+        assert!(debugger.check_before_execute(&thread_at(0x10000)));
         assert_eq!(debugger.state, DebugState::Paused);
     }
 
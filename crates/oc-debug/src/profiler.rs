@@ -1,8 +1,16 @@
 //! Performance profiler for CPU/GPU analysis
 
+use crate::backtrace::StackFrame;
+use oc_rsx::RsxFrameCounters;
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
+/// PPU instructions between call stack samples
+pub const PPU_SAMPLE_INTERVAL: u64 = 1000;
+
+/// Longest time a sample accounts for; longer gaps are pauses, not PPU time
+const MAX_SAMPLE_WEIGHT: Duration = Duration::from_millis(50);
+
 /// Profile category
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ProfileCategory {
@@ -118,6 +126,34 @@ pub struct Hotspot {
     pub percentage: f64,
 }
 
+/// Function hotspot from call stack samples
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionHotspot {
+    /// Function name, or its address when there is no symbol
+    pub name: String,
+    /// Samples with the function anywhere on the stack
+    pub inclusive_samples: u64,
+    /// Samples with the function executing
+    pub exclusive_samples: u64,
+    /// Time spent in the function and its callees
+    pub inclusive_time: Duration,
+    /// Time spent in the function itself
+    pub exclusive_time: Duration,
+    /// Inclusive share of all samples
+    pub inclusive_percentage: f64,
+    /// Exclusive share of all samples
+    pub exclusive_percentage: f64,
+}
+
+/// Per-function sample totals
+#[derive(Debug, Clone, Copy, Default)]
+struct FunctionTotals {
+    inclusive_samples: u64,
+    exclusive_samples: u64,
+    inclusive_time: Duration,
+    exclusive_time: Duration,
+}
+
 /// Frame timing info
 #[derive(Debug, Clone)]
 pub struct FrameTiming {
@@ -133,6 +169,8 @@ pub struct FrameTiming {
     pub rsx_time: Duration,
     /// Other time
     pub other_time: Duration,
+    /// RSX work done in the frame
+    pub rsx: RsxFrameCounters,
 }
 
 /// Performance profiler
@@ -159,6 +197,20 @@ pub struct Profiler {
     spu_hotspots: HashMap<u64, u64>,
     /// Total instructions executed (for percentage calculation)
     total_instructions: u64,
+    /// PPU instructions left until the next call stack sample
+    until_ppu_sample: u64,
+    /// Call stack sample totals by function name
+    functions: HashMap<String, FunctionTotals>,
+    /// Sample counts by call stack, outermost function first, joined with `;`
+    stacks: HashMap<String, u64>,
+    /// Number of call stack samples
+    total_samples: u64,
+    /// Time of the last call stack sample
+    last_sample: Option<Instant>,
+    /// RSX counters of the current frame
+    frame_rsx: RsxFrameCounters,
+    /// RSX counters since profiling started
+    rsx_total: RsxFrameCounters,
 }
 
 impl Default for Profiler {
@@ -182,6 +234,13 @@ impl Profiler {
             ppu_hotspots: HashMap::new(),
             spu_hotspots: HashMap::new(),
             total_instructions: 0,
+            until_ppu_sample: PPU_SAMPLE_INTERVAL,
+            functions: HashMap::new(),
+            stacks: HashMap::new(),
+            total_samples: 0,
+            last_sample: None,
+            frame_rsx: RsxFrameCounters::default(),
+            rsx_total: RsxFrameCounters::default(),
         }
     }
 
@@ -189,6 +248,7 @@ impl Profiler {
     pub fn enable(&mut self) {
         self.enabled = true;
         self.session_start = Instant::now();
+        self.last_sample = None;
         tracing::info!("Profiler enabled");
     }
 
@@ -233,6 +293,71 @@ impl Profiler {
         self.total_instructions += 1;
     }
 
+    /// Count a PPU instruction towards the next call stack sample
+    ///
+    /// Returns true every [`PPU_SAMPLE_INTERVAL`] instructions, when the
+    /// caller should unwind the thread and pass the stack to
+    /// [`Profiler::record_ppu_stack`].
+    pub fn ppu_sample_due(&mut self) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        self.until_ppu_sample -= 1;
+        if self.until_ppu_sample > 0 {
+            return false;
+        }
+        self.until_ppu_sample = PPU_SAMPLE_INTERVAL;
+        true
+    }
+
+    /// Record a sampled PPU call stack, innermost frame first
+    ///
+    /// The sample accounts for the time since the previous one. Frames are
+    /// grouped by function name; frames outside any known function are
+    /// listed by address.
+    pub fn record_ppu_stack(&mut self, frames: &[StackFrame]) {
+        if !self.enabled || frames.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        let weight = self
+            .last_sample
+            .map(|last| now.duration_since(last).min(MAX_SAMPLE_WEIGHT))
+            .unwrap_or_default();
+        self.last_sample = Some(now);
+        self.total_samples += 1;
+
+        let names: Vec<String> = frames
+            .iter()
+            .map(|frame| match &frame.function {
+                Some((name, _)) => name.clone(),
+                None => format!("0x{:08x}", frame.pc),
+            })
+            .collect();
+        for (depth, name) in names.iter().enumerate() {
+            // Recursive functions count once per sample
+            if names[..depth].contains(name) {
+                continue;
+            }
+            let totals = self.functions.entry(name.clone()).or_default();
+            totals.inclusive_samples += 1;
+            totals.inclusive_time += weight;
+            if depth == 0 {
+                totals.exclusive_samples += 1;
+                totals.exclusive_time += weight;
+            }
+        }
+
+        // Folded stack format used by flamegraph tools
+        let stack = names
+            .iter()
+            .rev()
+            .map(|name| name.replace([';', ' '], "_"))
+            .collect::<Vec<_>>()
+            .join(";");
+        *self.stacks.entry(stack).or_insert(0) += 1;
+    }
+
     /// Record SPU instruction execution
     pub fn record_spu_instruction(&mut self, spu_id: u32, address: u32) {
         if !self.enabled {
@@ -244,6 +369,20 @@ impl Profiler {
         self.total_instructions += 1;
     }
 
+    /// Record the RSX counters of the current frame
+    pub fn record_rsx_frame(&mut self, counters: RsxFrameCounters) {
+        if !self.enabled {
+            return;
+        }
+        self.frame_rsx = counters;
+        self.rsx_total += counters;
+    }
+
+    /// RSX counters since profiling started
+    pub fn rsx_totals(&self) -> RsxFrameCounters {
+        self.rsx_total
+    }
+
     /// Start frame timing
     pub fn start_frame(&mut self) {
         if !self.enabled {
@@ -268,6 +407,7 @@ impl Profiler {
             spu_time: *self.frame_category_times.get(&ProfileCategory::SpuExecution).unwrap_or(&Duration::ZERO),
             rsx_time: *self.frame_category_times.get(&ProfileCategory::RsxExecution).unwrap_or(&Duration::ZERO),
             other_time: *self.frame_category_times.get(&ProfileCategory::Other).unwrap_or(&Duration::ZERO),
+            rsx: std::mem::take(&mut self.frame_rsx),
         };
         
         self.frame_timings.push(timing);
@@ -367,6 +507,50 @@ impl Profiler {
         hotspots
     }
 
+    /// Get function hotspots from call stack samples (top N by exclusive time)
+    pub fn get_function_hotspots(&self, count: usize) -> Vec<FunctionHotspot> {
+        let percentage = |samples: u64| {
+            if self.total_samples > 0 {
+                (samples as f64 / self.total_samples as f64) * 100.0
+            } else {
+                0.0
+            }
+        };
+        let mut hotspots: Vec<_> = self.functions.iter()
+            .map(|(name, totals)| FunctionHotspot {
+                name: name.clone(),
+                inclusive_samples: totals.inclusive_samples,
+                exclusive_samples: totals.exclusive_samples,
+                inclusive_time: totals.inclusive_time,
+                exclusive_time: totals.exclusive_time,
+                inclusive_percentage: percentage(totals.inclusive_samples),
+                exclusive_percentage: percentage(totals.exclusive_samples),
+            })
+            .collect();
+
+        hotspots.sort_by(|a, b| {
+            (b.exclusive_samples, b.inclusive_samples, &a.name).cmp(&(a.exclusive_samples, a.inclusive_samples, &b.name))
+        });
+        hotspots.truncate(count);
+        hotspots
+    }
+
+    /// Number of call stack samples
+    pub fn sample_count(&self) -> u64 {
+        self.total_samples
+    }
+
+    /// Export the call stack samples as folded stacks
+    ///
+    /// Each line is a stack from the outermost function to the innermost,
+    /// separated by `;`, followed by its sample count. flamegraph.pl,
+    /// inferno and speedscope read this format.
+    pub fn export_folded(&self) -> String {
+        let mut stacks: Vec<_> = self.stacks.iter().collect();
+        stacks.sort();
+        stacks.iter().map(|(stack, count)| format!("{} {}\n", stack, count)).collect()
+    }
+
     /// Get session duration
     pub fn session_duration(&self) -> Duration {
         self.session_start.elapsed()
@@ -379,6 +563,13 @@ impl Profiler {
         self.ppu_hotspots.clear();
         self.spu_hotspots.clear();
         self.total_instructions = 0;
+        self.until_ppu_sample = PPU_SAMPLE_INTERVAL;
+        self.functions.clear();
+        self.stacks.clear();
+        self.total_samples = 0;
+        self.last_sample = None;
+        self.frame_rsx = RsxFrameCounters::default();
+        self.rsx_total = RsxFrameCounters::default();
         self.current_frame = 0;
         self.session_start = Instant::now();
         tracing::info!("Profiler reset");
@@ -416,6 +607,42 @@ impl Profiler {
             ));
         }
         
+        report.push_str("\n--- PPU Functions (exclusive / inclusive) ---\n");
+        for hotspot in self.get_function_hotspots(10) {
+            report.push_str(&format!(
+                "{}: {:.2}% / {:.2}% ({:.2}ms / {:.2}ms)\n",
+                hotspot.name,
+                hotspot.exclusive_percentage,
+                hotspot.inclusive_percentage,
+                hotspot.exclusive_time.as_secs_f64() * 1000.0,
+                hotspot.inclusive_time.as_secs_f64() * 1000.0
+            ));
+        }
+
+        let rsx = self.rsx_total;
+        let frames = self.current_frame.max(1);
+        report.push_str("\n--- RSX (total / per frame) ---\n");
+        for (name, value) in [
+            ("Draw calls", rsx.draw_calls),
+            ("Triangles", rsx.triangles),
+            ("Clears", rsx.clears),
+            ("Texture uploads", rsx.texture_uploads),
+        ] {
+            report.push_str(&format!("{}: {} / {:.1}\n", name, value, value as f64 / frames as f64));
+        }
+        report.push_str(&format!(
+            "Surface cache: {} hits, {} misses ({:.1}%)\n",
+            rsx.surface_cache_hits,
+            rsx.surface_cache_misses,
+            rsx.surface_hit_percent()
+        ));
+        report.push_str(&format!(
+            "Pipeline cache: {} hits, {} misses ({:.1}%)\n",
+            rsx.pipeline_cache_hits,
+            rsx.pipeline_cache_misses,
+            rsx.pipeline_hit_percent()
+        ));
+
         report.push_str("\n--- SPU Hotspots ---\n");
         for hotspot in self.get_spu_hotspots(10) {
             let spu_id = (hotspot.address >> 32) as u32;
@@ -509,6 +736,24 @@ mod tests {
         assert!(timings[0].total_time.as_micros() > 0);
     }
 
+    #[test]
+    fn test_rsx_counters() {
+        let mut profiler = Profiler::new();
+        let counters = RsxFrameCounters { draw_calls: 10, triangles: 500, pipeline_cache_hits: 9, ..Default::default() };
+        profiler.record_rsx_frame(counters);
+        assert_eq!(profiler.rsx_totals().draw_calls, 0);
+
+        profiler.enable();
+        for _ in 0..2 {
+            profiler.start_frame();
+            profiler.record_rsx_frame(counters);
+            profiler.end_frame();
+        }
+        assert_eq!(profiler.get_frame_timings(1)[0].rsx, counters);
+        assert_eq!(profiler.rsx_totals().triangles, 1000);
+        assert!(profiler.generate_report().contains("Draw calls: 20 / 10.0"));
+    }
+
     #[test]
     fn test_hotspots() {
         let mut profiler = Profiler::new();
@@ -524,6 +769,50 @@ mod tests {
         assert_eq!(hotspots[0].hit_count, 2);
     }
 
+    #[test]
+    fn test_function_hotspots() {
+        let frame = |pc: u64, name: Option<&str>| StackFrame {
+            pc,
+            sp: 0,
+            function: name.map(|name| (name.to_string(), 0)),
+        };
+        let mut profiler = Profiler::new();
+        profiler.record_ppu_stack(&[frame(0x10000, Some("main"))]);
+        assert!(!profiler.ppu_sample_due());
+        assert_eq!(profiler.sample_count(), 0);
+
+        profiler.enable();
+        let due = (0..PPU_SAMPLE_INTERVAL * 2).filter(|_| profiler.ppu_sample_due()).count();
+        assert_eq!(due, 2);
+
+        let main = frame(0x10000, Some("main"));
+        let update = frame(0x20010, Some("update"));
+        let draw = frame(0x30020, Some("cellGcmSetFlip"));
+        profiler.record_ppu_stack(&[update.clone(), main.clone()]);
+        profiler.record_ppu_stack(&[update.clone(), main.clone()]);
+        profiler.record_ppu_stack(&[draw.clone(), update.clone(), main.clone()]);
+        profiler.record_ppu_stack(&[frame(0x40000, None), update.clone(), update, main]);
+
+        let hotspots = profiler.get_function_hotspots(10);
+        assert_eq!(profiler.sample_count(), 4);
+        assert_eq!(hotspots[0].name, "update");
+        assert_eq!((hotspots[0].exclusive_samples, hotspots[0].inclusive_samples), (2, 4));
+        assert_eq!(hotspots[0].inclusive_percentage, 100.0);
+        let main = hotspots.iter().find(|h| h.name == "main").unwrap();
+        assert_eq!((main.exclusive_samples, main.inclusive_samples), (0, 4));
+        assert!(main.inclusive_time >= hotspots[0].exclusive_time);
+        assert!(hotspots.iter().any(|h| h.name == "0x00040000" && h.exclusive_samples == 1));
+
+        assert_eq!(
+            profiler.export_folded(),
+            "main;update 2\nmain;update;cellGcmSetFlip 1\nmain;update;update;0x00040000 1\n"
+        );
+
+        profiler.reset();
+        assert!(profiler.get_function_hotspots(10).is_empty());
+        assert!(profiler.export_folded().is_empty());
+    }
+
     #[test]
     fn test_profile_entry_average() {
         let mut entry = ProfileEntry::new("test", ProfileCategory::Other);
@@ -1,6 +1,18 @@
 //! RSX debugger for command buffer and graphics state inspection
 
+use oc_memory::MemoryManager;
+use oc_rsx::methods::*;
 use oc_rsx::state::RsxState;
+use oc_rsx::texture::format;
+use oc_rsx::texture_decode::{self, expand5, expand6, TEXTURE_ENABLE};
+use oc_rsx::FramebufferData;
+use std::collections::VecDeque;
+
+/// Draw calls kept by default
+pub const DEFAULT_DRAW_CALL_HISTORY: usize = 64;
+
+/// Context DMA handle of surfaces in main memory
+const CONTEXT_DMA_MEMORY_HOST_BUFFER: u32 = 0xFEED_0001;
 
 /// RSX debug state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -39,8 +51,14 @@ pub struct RenderTargetDebugInfo {
     pub width: u32,
     /// Height
     pub height: u32,
+    /// Bytes per row
+    pub pitch: u32,
     /// Format
     pub format: String,
+    /// Color format of NV4097_SET_SURFACE_FORMAT
+    pub format_id: u8,
+    /// Whether the surface is in RSX local memory
+    pub local: bool,
     /// Is active
     pub active: bool,
 }
@@ -58,10 +76,54 @@ pub struct TextureDebugInfo {
     pub height: u16,
     /// Format
     pub format: String,
+    /// Format byte of NV4097_SET_TEXTURE_FORMAT, including the linear/unnormalized flags
+    pub format_id: u8,
+    /// Whether the texture is in RSX local memory
+    pub local: bool,
     /// Is enabled
     pub enabled: bool,
 }
 
+impl TextureDebugInfo {
+    /// Whether texels are stored in Z-order rather than row by row
+    pub fn swizzled(&self) -> bool {
+        texture_decode::is_swizzled(self.format_id, self.width.into(), self.height.into())
+    }
+}
+
+/// A named NV4097 register and its current value
+#[derive(Debug, Clone)]
+pub struct RsxRegister {
+    /// Method register address
+    pub method: u32,
+    pub name: String,
+    /// Value, decoded where that reads better than hex
+    pub value: String,
+}
+
+/// A draw call and the state it was issued with
+#[derive(Debug, Clone)]
+pub struct DrawCallRecord {
+    /// Draws recorded before this one
+    pub index: u64,
+    /// Frame the draw was issued in
+    pub frame: u64,
+    /// NV4097_DRAW_INDEX_ARRAY rather than NV4097_DRAW_ARRAYS
+    pub indexed: bool,
+    /// NV4097_SET_BEGIN_END primitive type
+    pub primitive: u32,
+    pub first: u32,
+    pub count: u32,
+    /// Vertex program start slot
+    pub vertex_program: u32,
+    /// Fragment program offset with its location in the low bits
+    pub fragment_program: u32,
+    /// Enabled texture units and their offsets
+    pub textures: Vec<(usize, u32)>,
+    /// Active color targets and their offsets
+    pub color_targets: Vec<(usize, u32)>,
+}
+
 /// Vertex attribute debug info
 #[derive(Debug, Clone)]
 pub struct VertexAttributeDebugInfo {
@@ -93,6 +155,14 @@ pub struct RsxDebugger {
     pub frame_count: u64,
     /// Commands in current frame
     pub commands_this_frame: u64,
+    /// Let the next command pass a method breakpoint, set when leaving one
+    skip_breakpoint: bool,
+    /// Last draw calls, oldest first
+    draw_calls: VecDeque<DrawCallRecord>,
+    /// Maximum draw calls kept
+    max_draw_calls: usize,
+    /// Draw calls recorded so far
+    draw_call_count: u64,
 }
 
 impl Default for RsxDebugger {
@@ -111,6 +181,10 @@ impl RsxDebugger {
             break_on_method: None,
             frame_count: 0,
             commands_this_frame: 0,
+            skip_breakpoint: false,
+            draw_calls: VecDeque::new(),
+            max_draw_calls: DEFAULT_DRAW_CALL_HISTORY,
+            draw_call_count: 0,
         }
     }
 
@@ -123,21 +197,29 @@ impl RsxDebugger {
     /// Resume RSX execution
     pub fn resume(&mut self) {
         self.state = RsxDebugState::Running;
+        self.skip_breakpoint = true;
         tracing::info!("RSX debugger: resumed");
     }
 
     /// Step one command
     pub fn step_command(&mut self) {
         self.state = RsxDebugState::StepCommand;
+        self.skip_breakpoint = true;
         tracing::debug!("RSX debugger: step command");
     }
 
     /// Step one frame
     pub fn step_frame(&mut self) {
         self.state = RsxDebugState::StepFrame;
+        self.skip_breakpoint = true;
         tracing::debug!("RSX debugger: step frame");
     }
 
+    /// Current method breakpoint
+    pub fn method_breakpoint(&self) -> Option<u32> {
+        self.break_on_method
+    }
+
     /// Set breakpoint on method register
     pub fn break_on_method(&mut self, method: u32) {
         self.break_on_method = Some(method);
@@ -170,6 +252,27 @@ impl RsxDebugger {
         }
     }
 
+    /// Whether the command processor may execute a command with `method` now
+    ///
+    /// A step executes exactly one command and resuming passes the breakpoint
+    /// that stopped the FIFO.
+    pub fn should_execute(&mut self, method: u32) -> bool {
+        if self.state == RsxDebugState::Paused {
+            return false;
+        }
+        let skip = std::mem::take(&mut self.skip_breakpoint);
+        if self.state == RsxDebugState::StepCommand {
+            self.state = RsxDebugState::Paused;
+            return true;
+        }
+        if self.break_on_method == Some(method) && !skip {
+            tracing::info!("RSX debugger: method breakpoint hit at 0x{:04x}", method);
+            self.state = RsxDebugState::Paused;
+            return false;
+        }
+        true
+    }
+
     /// Record end of frame
     pub fn record_frame_end(&mut self) {
         self.frame_count += 1;
@@ -203,6 +306,62 @@ impl RsxDebugger {
         }
     }
 
+    /// Record a draw command along with the state it draws with, ignoring other methods
+    pub fn record_draw(&mut self, state: &RsxState, method: u32, data: u32) {
+        let indexed = match method {
+            NV4097_DRAW_ARRAYS => false,
+            NV4097_DRAW_INDEX_ARRAY => true,
+            _ => return,
+        };
+        let textures = (0..16)
+            .filter(|&i| state.texture_control[i] & TEXTURE_ENABLE != 0)
+            .map(|i| (i, state.texture_offset[i]))
+            .collect();
+        let color_targets = (0..4)
+            .filter(|&i| Self::color_target_active(state.surface_color_target, i))
+            .map(|i| (i, state.surface_offset_color[i]))
+            .collect();
+        self.draw_calls.push_back(DrawCallRecord {
+            index: self.draw_call_count,
+            frame: self.frame_count,
+            indexed,
+            primitive: state.primitive_type,
+            first: data & 0xFFFFFF,
+            count: (data >> 24) & 0xFF,
+            vertex_program: state.vertex_program_addr,
+            fragment_program: state.fragment_program_addr,
+            textures,
+            color_targets,
+        });
+        self.draw_call_count += 1;
+        while self.draw_calls.len() > self.max_draw_calls {
+            self.draw_calls.pop_front();
+        }
+    }
+
+    /// Recorded draw calls, oldest first
+    pub fn draw_calls(&self) -> impl DoubleEndedIterator<Item = &DrawCallRecord> {
+        self.draw_calls.iter()
+    }
+
+    /// Maximum draw calls kept
+    pub fn max_draw_calls(&self) -> usize {
+        self.max_draw_calls
+    }
+
+    /// Keep the last `count` draw calls
+    pub fn set_max_draw_calls(&mut self, count: usize) {
+        self.max_draw_calls = count.max(1);
+        while self.draw_calls.len() > self.max_draw_calls {
+            self.draw_calls.pop_front();
+        }
+    }
+
+    /// Forget recorded draw calls
+    pub fn clear_draw_calls(&mut self) {
+        self.draw_calls.clear();
+    }
+
     /// Get command history
     pub fn get_command_history(&self, count: usize) -> &[RsxCommandEntry] {
         let start = self.command_history.len().saturating_sub(count);
@@ -260,13 +419,17 @@ impl RsxDebugger {
         let mut targets = Vec::new();
         
         for i in 0..4 {
+            let format_id = (state.surface_format & 0x1F) as u8;
             targets.push(RenderTargetDebugInfo {
                 index: i,
                 offset: state.surface_offset_color[i],
                 width: state.surface_clip_width as u32,
                 height: state.surface_clip_height as u32,
-                format: Self::surface_format_name(state.surface_format as u8),
-                active: state.surface_offset_color[i] != 0,
+                pitch: state.surface_pitch[i],
+                format: Self::surface_format_name(format_id),
+                format_id,
+                local: state.context_dma_color[i] != CONTEXT_DMA_MEMORY_HOST_BUFFER,
+                active: Self::color_target_active(state.surface_color_target, i),
             });
         }
         
@@ -278,13 +441,16 @@ impl RsxDebugger {
         let mut textures = Vec::new();
         
         for i in 0..16 {
+            let format_id = ((state.texture_format[i] >> 8) & 0xFF) as u8;
             textures.push(TextureDebugInfo {
                 unit: i,
                 offset: state.texture_offset[i],
-                width: ((state.texture_format[i] >> 16) & 0xFFFF) as u16,
-                height: (state.texture_format[i] & 0xFFFF) as u16,
-                format: Self::texture_format_name(((state.texture_format[i] >> 8) & 0xFF) as u8),
-                enabled: state.texture_offset[i] != 0,
+                width: (state.texture_image_rect[i] >> 16) as u16,
+                height: (state.texture_image_rect[i] & 0xFFFF) as u16,
+                format: Self::texture_format_name(format_id),
+                format_id,
+                local: state.texture_format[i] & 0x3 != 2,
+                enabled: state.texture_control[i] & TEXTURE_ENABLE != 0,
             });
         }
         
@@ -313,6 +479,260 @@ impl RsxDebugger {
         attrs
     }
 
+    /// Current value of each NV4097 register the state tracks
+    pub fn get_registers(&self, state: &RsxState) -> Vec<RsxRegister> {
+        macro_rules! register {
+            ($method:ident, $value:expr) => {
+                RsxRegister { method: $method, name: stringify!($method).to_string(), value: $value }
+            };
+        }
+        let hex = |value: u32| format!("0x{:08X}", value);
+        let flag = |value: bool| if value { "enabled" } else { "disabled" }.to_string();
+
+        let mut registers = vec![
+            register!(NV4097_SET_SURFACE_FORMAT, format!("{} (0x{:08X})", Self::surface_format_name((state.surface_format & 0x1F) as u8), state.surface_format)),
+            register!(NV4097_SET_SURFACE_COLOR_TARGET, hex(state.surface_color_target)),
+            register!(NV4097_SET_CONTEXT_DMA_COLOR_A, hex(state.context_dma_color[0])),
+            register!(NV4097_SET_CONTEXT_DMA_COLOR_B, hex(state.context_dma_color[1])),
+            register!(NV4097_SET_CONTEXT_DMA_COLOR_C, hex(state.context_dma_color[2])),
+            register!(NV4097_SET_CONTEXT_DMA_COLOR_D, hex(state.context_dma_color[3])),
+            register!(NV4097_SET_SURFACE_COLOR_AOFFSET, hex(state.surface_offset_color[0])),
+            register!(NV4097_SET_SURFACE_COLOR_BOFFSET, hex(state.surface_offset_color[1])),
+            register!(NV4097_SET_SURFACE_COLOR_COFFSET, hex(state.surface_offset_color[2])),
+            register!(NV4097_SET_SURFACE_COLOR_DOFFSET, hex(state.surface_offset_color[3])),
+            register!(NV4097_SET_SURFACE_PITCH_A, state.surface_pitch[0].to_string()),
+            register!(NV4097_SET_SURFACE_PITCH_B, state.surface_pitch[1].to_string()),
+            register!(NV4097_SET_SURFACE_PITCH_C, state.surface_pitch[2].to_string()),
+            register!(NV4097_SET_SURFACE_PITCH_D, state.surface_pitch[3].to_string()),
+            register!(NV4097_SET_CONTEXT_DMA_ZETA, hex(state.context_dma_depth)),
+            register!(NV4097_SET_SURFACE_ZETA_OFFSET, hex(state.surface_offset_depth)),
+            register!(NV4097_SET_SURFACE_CLIP_HORIZONTAL, format!("x={}, width={}", state.surface_clip_x, state.surface_clip_width)),
+            register!(NV4097_SET_SURFACE_CLIP_VERTICAL, format!("y={}, height={}", state.surface_clip_y, state.surface_clip_height)),
+            register!(NV4097_SET_VIEWPORT_HORIZONTAL, format!("x={}, width={}", state.viewport_x, state.viewport_width)),
+            register!(NV4097_SET_VIEWPORT_VERTICAL, format!("y={}, height={}", state.viewport_y, state.viewport_height)),
+            register!(NV4097_SET_CLIP_MIN, state.depth_min.to_string()),
+            register!(NV4097_SET_CLIP_MAX, state.depth_max.to_string()),
+            register!(NV4097_SET_COLOR_CLEAR_VALUE, hex(state.clear_color)),
+            register!(NV4097_SET_ZSTENCIL_CLEAR_VALUE, format!("depth={}, stencil={}", state.clear_depth, state.clear_stencil)),
+            register!(NV4097_SET_BLEND_ENABLE, flag(state.blend_enable)),
+            register!(NV4097_SET_BLEND_FUNC_SFACTOR, hex(state.blend_src_factor)),
+            register!(NV4097_SET_BLEND_FUNC_DFACTOR, hex(state.blend_dst_factor)),
+            register!(NV4097_SET_BLEND_EQUATION, hex(state.blend_equation)),
+            register!(NV4097_SET_DEPTH_TEST_ENABLE, flag(state.depth_test_enable)),
+            register!(NV4097_SET_DEPTH_FUNC, hex(state.depth_func)),
+            register!(NV4097_SET_DEPTH_MASK, flag(state.depth_write_enable)),
+            register!(NV4097_SET_STENCIL_TEST_ENABLE, flag(state.stencil_test_enable)),
+            register!(NV4097_SET_STENCIL_FUNC, hex(state.stencil_func)),
+            register!(NV4097_SET_STENCIL_FUNC_REF, state.stencil_ref.to_string()),
+            register!(NV4097_SET_STENCIL_FUNC_MASK, format!("0x{:02X}", state.stencil_mask)),
+            register!(NV4097_SET_CULL_FACE_ENABLE, flag(state.cull_face_enable)),
+            register!(NV4097_SET_CULL_FACE, hex(state.cull_face_mode)),
+            register!(NV4097_SET_FRONT_FACE, hex(state.front_face)),
+            register!(NV4097_SET_ALPHA_TEST_ENABLE, flag(state.alpha_test_enable)),
+            register!(NV4097_SET_ALPHA_FUNC, hex(state.alpha_test_func)),
+            register!(NV4097_SET_ALPHA_REF, state.alpha_test_ref.to_string()),
+            register!(NV4097_SET_POLYGON_OFFSET_FILL_ENABLE, flag(state.polygon_offset_fill_enable)),
+            register!(NV4097_SET_POLYGON_OFFSET_LINE_ENABLE, flag(state.polygon_offset_line_enable)),
+            register!(NV4097_SET_POLYGON_OFFSET_POINT_ENABLE, flag(state.polygon_offset_point_enable)),
+            register!(NV4097_SET_POLYGON_OFFSET_SCALE_FACTOR, state.polygon_offset_factor.to_string()),
+            register!(NV4097_SET_POLYGON_OFFSET_BIAS, state.polygon_offset_units.to_string()),
+            register!(NV4097_SET_LINE_WIDTH, state.line_width.to_string()),
+            register!(NV4097_SET_POINT_SIZE, state.point_size.to_string()),
+            register!(NV4097_SET_POINT_SPRITE_CONTROL, flag(state.point_sprite_enable)),
+            register!(NV4097_SET_ANTI_ALIASING_CONTROL, format!("multisample {}, alpha to coverage {}", flag(state.multisample_enable), flag(state.sample_alpha_to_coverage_enable))),
+            register!(NV4097_SET_SAMPLE_COUNT_CONTROL, format!("{} sample(s)", state.sample_count)),
+            register!(NV4097_SET_RESTART_INDEX_ENABLE, flag(state.primitive_restart_enable)),
+            register!(NV4097_SET_RESTART_INDEX, hex(state.primitive_restart_index)),
+            register!(NV4097_SET_ZPASS_PIXEL_COUNT_ENABLE, flag(state.occlusion_query_enable)),
+            register!(NV4097_SET_REPORT_SEMAPHORE_OFFSET, hex(state.occlusion_query_offset)),
+            register!(NV4097_SET_VERTEX_PROGRAM_START_SLOT, state.vertex_program_addr.to_string()),
+            register!(NV4097_SET_SHADER_PROGRAM, hex(state.fragment_program_addr)),
+            register!(NV4097_SET_VERTEX_ATTRIB_INPUT_MASK, hex(state.vertex_attrib_input_mask)),
+            register!(NV4097_SET_VERTEX_ATTRIB_OUTPUT_MASK, hex(state.vertex_attrib_output_mask)),
+            register!(NV4097_SET_BEGIN_END, Self::primitive_name(state.primitive_type).to_string()),
+        ];
+
+        // Per attribute and per texture unit registers, where set
+        for i in 0..16 {
+            if state.vertex_attrib_format[i] != 0 || state.vertex_attrib_offset[i] != 0 {
+                registers.push(RsxRegister {
+                    method: NV4097_SET_VERTEX_DATA_ARRAY_FORMAT + i as u32,
+                    name: format!("NV4097_SET_VERTEX_DATA_ARRAY_FORMAT[{}]", i),
+                    value: hex(state.vertex_attrib_format[i]),
+                });
+                registers.push(RsxRegister {
+                    method: NV4097_SET_VERTEX_DATA_ARRAY_OFFSET + i as u32,
+                    name: format!("NV4097_SET_VERTEX_DATA_ARRAY_OFFSET[{}]", i),
+                    value: hex(state.vertex_attrib_offset[i]),
+                });
+            }
+        }
+        for i in 0..16 {
+            let unit = [
+                (NV4097_SET_TEXTURE_OFFSET, "NV4097_SET_TEXTURE_OFFSET", state.texture_offset[i]),
+                (NV4097_SET_TEXTURE_FORMAT, "NV4097_SET_TEXTURE_FORMAT", state.texture_format[i]),
+                (NV4097_SET_TEXTURE_CONTROL0, "NV4097_SET_TEXTURE_CONTROL0", state.texture_control[i]),
+                (NV4097_SET_TEXTURE_FILTER, "NV4097_SET_TEXTURE_FILTER", state.texture_filter[i]),
+                (NV4097_SET_TEXTURE_IMAGE_RECT, "NV4097_SET_TEXTURE_IMAGE_RECT", state.texture_image_rect[i]),
+            ];
+            if unit.iter().all(|&(_, _, value)| value == 0) {
+                continue;
+            }
+            for (method, name, value) in unit {
+                registers.push(RsxRegister {
+                    method: method + i as u32 * 0x20,
+                    name: format!("{}[{}]", name, i),
+                    value: hex(value),
+                });
+            }
+        }
+        registers
+    }
+
+    /// Decode a texture into an RGBA image no larger than `max_size` on either side
+    pub fn texture_preview(
+        &self,
+        memory: &MemoryManager,
+        texture: &TextureDebugInfo,
+        max_size: u32,
+    ) -> Result<FramebufferData, String> {
+        if !texture.local {
+            return Err(String::from("Textures in main memory are not previewed"));
+        }
+        let (width, height) = (texture.width as u32, texture.height as u32);
+        let id = texture_decode::base_format(texture.format_id);
+        let unsupported = || format!("No preview for {} textures", texture.format);
+
+        if format::is_compressed(id) {
+            if !matches!(id, format::DXT1 | format::DXT3 | format::DXT5) {
+                return Err(unsupported());
+            }
+            let (_, _, block_bytes) = format::block_size(id);
+            let blocks_per_row = width.div_ceil(4);
+            return Self::sample(width, height, max_size, |x, y| {
+                let offset = texture.offset + ((y / 4) * blocks_per_row + x / 4) * block_bytes;
+                let block = Self::read_local(memory, offset, block_bytes)?;
+                Ok(texture_decode::decode_dxt(id, &block, x % 4, y % 4))
+            });
+        }
+
+        let bytes = format::bytes_per_pixel(id);
+        let swizzled = texture.swizzled();
+        Self::sample(width, height, max_size, |x, y| {
+            let index = if swizzled { texture_decode::swizzle_index(x, y, width, height) } else { y * width + x };
+            let data = Self::read_local(memory, texture.offset + index * bytes, bytes)?;
+            texture_decode::decode_texel(id, &data).ok_or_else(unsupported)
+        })
+    }
+
+    /// Read a render target into an RGBA image no larger than `max_size` on either side
+    pub fn render_target_preview(
+        &self,
+        memory: &MemoryManager,
+        target: &RenderTargetDebugInfo,
+        max_size: u32,
+    ) -> Result<FramebufferData, String> {
+        if !target.local {
+            return Err(String::from("Surfaces in main memory are not previewed"));
+        }
+        let bytes = match target.format_id {
+            1..=3 => 2,
+            4 | 5 | 8 | 14..=16 => 4,
+            9 => 1,
+            _ => return Err(format!("No preview for {} surfaces", target.format)),
+        };
+        let pitch = if target.pitch != 0 { target.pitch } else { target.width * bytes };
+        Self::sample(target.width, target.height, max_size, |x, y| {
+            let data = Self::read_local(memory, target.offset + y * pitch + x * bytes, bytes)?;
+            Ok(Self::decode_surface_pixel(target.format_id, &data))
+        })
+    }
+
+    /// Read the first `size` bytes of a fragment program (an NV4097_SET_SHADER_PROGRAM value)
+    pub fn read_fragment_program(&self, memory: &MemoryManager, program: u32, size: u32) -> Result<Vec<u8>, String> {
+        if program & 0x3 == 2 {
+            return Err(String::from("Fragment programs in main memory are not read"));
+        }
+        Self::read_local(memory, program & !0x3, size)
+    }
+
+    /// Read RSX local memory
+    fn read_local(memory: &MemoryManager, offset: u32, size: u32) -> Result<Vec<u8>, String> {
+        memory.read_rsx_bytes(offset, size).map_err(|e| e.to_string())
+    }
+
+    /// Build an image of at most `max_size` on either side by sampling `texel` at every step
+    fn sample(
+        width: u32,
+        height: u32,
+        max_size: u32,
+        mut texel: impl FnMut(u32, u32) -> Result<[u8; 4], String>,
+    ) -> Result<FramebufferData, String> {
+        if width == 0 || height == 0 {
+            return Err(String::from("Empty image"));
+        }
+        let step = width.max(height).div_ceil(max_size.max(1));
+        let mut image = FramebufferData::new(width.div_ceil(step), height.div_ceil(step));
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let i = ((y * image.width + x) * 4) as usize;
+                image.pixels[i..i + 4].copy_from_slice(&texel(x * step, y * step)?);
+            }
+        }
+        Ok(image)
+    }
+
+    /// Convert one surface pixel, stored as little-endian words like the RSX writes them, to RGBA
+    fn decode_surface_pixel(format_id: u8, data: &[u8]) -> [u8; 4] {
+        match format_id {
+            1 | 2 => {
+                let v = u16::from_le_bytes([data[0], data[1]]);
+                [expand5(v >> 10), expand5(v >> 5), expand5(v), 255]
+            }
+            3 => {
+                let v = u16::from_le_bytes([data[0], data[1]]);
+                [expand5(v >> 11), expand6(v >> 5), expand5(v), 255]
+            }
+            9 => [data[0], data[0], data[0], 255],
+            // B, G, R, A/X in memory
+            4 | 5 => [data[2], data[1], data[0], 255],
+            8 => [data[2], data[1], data[0], data[3]],
+            // R, G, B, A/X in memory
+            14 | 15 => [data[0], data[1], data[2], 255],
+            _ => [data[0], data[1], data[2], data[3]],
+        }
+    }
+
+    /// Whether color target `index` is drawn to for an NV4097_SET_SURFACE_COLOR_TARGET value
+    fn color_target_active(target: u32, index: usize) -> bool {
+        let count = match target {
+            0x01 => return index == 0,
+            0x02 => return index == 1,
+            0x13 => 2,
+            0x17 => 3,
+            0x1F => 4,
+            _ => 0,
+        };
+        index < count
+    }
+
+    /// Name of an NV4097_SET_BEGIN_END primitive type
+    pub fn primitive_name(primitive: u32) -> &'static str {
+        match primitive {
+            1 => "Points",
+            2 => "Lines",
+            3 => "Line loop",
+            4 => "Line strip",
+            5 => "Triangles",
+            6 => "Triangle strip",
+            7 => "Triangle fan",
+            8 => "Quads",
+            9 => "Quad strip",
+            10 => "Polygon",
+            _ => "None",
+        }
+    }
+
     /// Check if RSX is paused
     pub fn is_paused(&self) -> bool {
         self.state == RsxDebugState::Paused
@@ -404,29 +824,44 @@ impl RsxDebugger {
         }
     }
 
-    /// Get surface format name
+    /// Get surface color format name
     fn surface_format_name(format: u8) -> String {
         match format {
-            1 => "A8R8G8B8".to_string(),
-            3 => "A16R16G16B16".to_string(),
-            4 => "A16R16G16B16_FLOAT".to_string(),
-            5 => "A32R32G32B32_FLOAT".to_string(),
+            1 => "X1R5G5B5_Z1R5G5B5".to_string(),
+            2 => "X1R5G5B5_O1R5G5B5".to_string(),
+            3 => "R5G6B5".to_string(),
+            4 => "X8R8G8B8_Z8R8G8B8".to_string(),
+            5 => "X8R8G8B8_O8R8G8B8".to_string(),
+            8 => "A8R8G8B8".to_string(),
+            9 => "B8".to_string(),
+            10 => "G8B8".to_string(),
+            11 => "F_W16Z16Y16X16".to_string(),
+            12 => "F_W32Z32Y32X32".to_string(),
+            13 => "F_X32".to_string(),
+            14 => "X8B8G8R8_Z8B8G8R8".to_string(),
+            15 => "X8B8G8R8_O8B8G8R8".to_string(),
+            16 => "A8B8G8R8".to_string(),
             _ => format!("Format_{}", format),
         }
     }
 
-    /// Get texture format name
-    fn texture_format_name(format: u8) -> String {
-        match format {
-            0x81 => "A8R8G8B8".to_string(),
-            0x82 => "R5G6B5".to_string(),
-            0x83 => "A1R5G5B5".to_string(),
-            0x84 => "A4R4G4B4".to_string(),
-            0x85 => "L8".to_string(),
-            0x86 => "DXT1".to_string(),
-            0x87 => "DXT3".to_string(),
-            0x88 => "DXT5".to_string(),
-            _ => format!("Format_0x{:02X}", format),
+    /// Get texture format name, ignoring the linear/unnormalized flags
+    fn texture_format_name(id: u8) -> String {
+        match texture_decode::base_format(id) {
+            format::B8 => "B8".to_string(),
+            format::A1R5G5B5 => "A1R5G5B5".to_string(),
+            format::A4R4G4B4 => "A4R4G4B4".to_string(),
+            format::R5G6B5 => "R5G6B5".to_string(),
+            format::ARGB8 => "ARGB8".to_string(),
+            format::DXT1 => "DXT1".to_string(),
+            format::DXT3 => "DXT3".to_string(),
+            format::DXT5 => "DXT5".to_string(),
+            format::A8R8G8B8 => "A8R8G8B8".to_string(),
+            format::XRGB8 => "XRGB8".to_string(),
+            format::R5G5B5A1 => "R5G5B5A1".to_string(),
+            format::D1R5G5B5 => "D1R5G5B5".to_string(),
+            format::D8R8G8B8 => "D8R8G8B8".to_string(),
+            _ => format!("Format_0x{:02X}", id),
         }
     }
 
@@ -562,4 +997,130 @@ mod tests {
         assert_eq!(history[1].method, 0x1810);
         assert_eq!(history[2].method, 0x1808);
     }
+
+    #[test]
+    fn test_should_execute_steps_and_leaves_breakpoint() {
+        let mut debugger = RsxDebugger::new();
+        debugger.break_on_method(NV4097_CLEAR_SURFACE);
+
+        assert!(debugger.should_execute(NV4097_SET_BLEND_ENABLE));
+        assert!(!debugger.should_execute(NV4097_CLEAR_SURFACE));
+        assert!(debugger.is_paused());
+        assert!(!debugger.should_execute(NV4097_CLEAR_SURFACE));
+
+        // A step runs the command the breakpoint stopped at, and only that one
+        debugger.step_command();
+        assert!(debugger.should_execute(NV4097_CLEAR_SURFACE));
+        assert!(!debugger.should_execute(NV4097_SET_BLEND_ENABLE));
+
+        debugger.resume();
+        assert!(debugger.should_execute(NV4097_SET_BLEND_ENABLE));
+        assert!(!debugger.should_execute(NV4097_CLEAR_SURFACE));
+    }
+
+    #[test]
+    fn test_draw_call_history() {
+        let mut debugger = RsxDebugger::new();
+        let mut state = RsxState::new();
+        state.primitive_type = 5;
+        state.fragment_program_addr = 0x1001;
+        state.surface_color_target = 0x13;
+        state.surface_offset_color[1] = 0x40_0000;
+        state.texture_control[2] = TEXTURE_ENABLE;
+        state.texture_offset[2] = 0x10000;
+
+        debugger.set_max_draw_calls(2);
+        debugger.record_draw(&state, NV4097_SET_BLEND_ENABLE, 1);
+        for i in 0..3 {
+            debugger.record_draw(&state, NV4097_DRAW_ARRAYS, (3 << 24) | i);
+        }
+        debugger.record_frame_end();
+        debugger.record_draw(&state, NV4097_DRAW_INDEX_ARRAY, 6 << 24);
+
+        let draws: Vec<_> = debugger.draw_calls().collect();
+        assert_eq!(draws.len(), 2);
+        assert_eq!((draws[0].index, draws[0].frame, draws[0].first, draws[0].indexed), (2, 0, 2, false));
+        assert_eq!((draws[1].index, draws[1].frame, draws[1].count, draws[1].indexed), (3, 1, 6, true));
+        assert_eq!(draws[1].fragment_program, 0x1001);
+        assert_eq!(draws[1].textures, [(2, 0x10000)]);
+        assert_eq!(draws[1].color_targets, [(0, 0), (1, 0x40_0000)]);
+        assert_eq!(RsxDebugger::primitive_name(draws[1].primitive), "Triangles");
+    }
+
+    #[test]
+    fn test_registers_and_textures() {
+        let debugger = RsxDebugger::new();
+        let mut state = RsxState::new();
+        oc_rsx::methods::MethodHandler::execute(NV4097_SET_TEXTURE_FORMAT + 0x20, 0x0000_8601, &mut state);
+        oc_rsx::methods::MethodHandler::execute(NV4097_SET_TEXTURE_IMAGE_RECT + 0x20, (64 << 16) | 32, &mut state);
+        state.blend_enable = true;
+
+        let registers = debugger.get_registers(&state);
+        let blend = registers.iter().find(|r| r.method == NV4097_SET_BLEND_ENABLE).unwrap();
+        assert_eq!((blend.name.as_str(), blend.value.as_str()), ("NV4097_SET_BLEND_ENABLE", "enabled"));
+        assert!(registers.iter().any(|r| r.name == "NV4097_SET_TEXTURE_IMAGE_RECT[1]"));
+        assert!(!registers.iter().any(|r| r.name == "NV4097_SET_TEXTURE_OFFSET[0]"));
+
+        let texture = &debugger.get_textures(&state)[1];
+        assert_eq!((texture.width, texture.height, texture.format.as_str()), (64, 32, "DXT1"));
+        assert!(texture.local && !texture.swizzled());
+    }
+
+    #[test]
+    fn test_texture_and_render_target_previews() {
+        let memory = MemoryManager::new().unwrap();
+        let write = |offset: u32, data: &[u8]| unsafe {
+            memory.rsx_ptr(offset).copy_from_nonoverlapping(data.as_ptr(), data.len())
+        };
+        let debugger = RsxDebugger::new();
+
+        // 2x2 swizzled ARGB8: texels (0,0), (1,0), (0,1), (1,1)
+        write(0x1000, &[255, 255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 128, 1, 2, 3]);
+        let mut texture = TextureDebugInfo {
+            unit: 0,
+            offset: 0x1000,
+            width: 2,
+            height: 2,
+            format: String::from("ARGB8"),
+            format_id: format::ARGB8,
+            local: true,
+            enabled: true,
+        };
+        assert!(texture.swizzled());
+        let image = debugger.texture_preview(&memory, &texture, 128).unwrap();
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(image.pixels, [255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 1, 2, 3, 128]);
+
+        // Sampled down to one texel per side
+        let image = debugger.texture_preview(&memory, &texture, 1).unwrap();
+        assert_eq!(image.pixels, [255, 0, 0, 255]);
+
+        // DXT1 block with white and black endpoints, first row white
+        write(0x2000, &[0xFF, 0xFF, 0x00, 0x00, 0x00, 0x55, 0x55, 0x55]);
+        texture.offset = 0x2000;
+        texture.width = 4;
+        texture.height = 4;
+        texture.format_id = format::DXT1;
+        let image = debugger.texture_preview(&memory, &texture, 128).unwrap();
+        assert_eq!(&image.pixels[..4], [255, 255, 255, 255]);
+        assert_eq!(&image.pixels[16..20], [0, 0, 0, 255]);
+
+        texture.local = false;
+        assert!(debugger.texture_preview(&memory, &texture, 128).is_err());
+
+        // A8R8G8B8 surface, 1x2 with a pitch of 64 bytes
+        write(0x3000, &[1, 2, 3, 4]);
+        write(0x3040, &[5, 6, 7, 8]);
+        let mut state = RsxState::new();
+        state.surface_format = 8;
+        state.surface_color_target = 0x01;
+        state.surface_offset_color[0] = 0x3000;
+        state.surface_pitch[0] = 64;
+        state.surface_clip_width = 1;
+        state.surface_clip_height = 2;
+        let target = &debugger.get_render_targets(&state)[0];
+        assert!(target.active && target.local);
+        let image = debugger.render_target_preview(&memory, target, 128).unwrap();
+        assert_eq!(image.pixels, [3, 2, 1, 4, 7, 6, 5, 8]);
+    }
 }
@@ -1,8 +1,14 @@
 //! SPU debugger for local storage viewing and register inspection
 
+use oc_spu::channels::channel_ids::*;
 use oc_spu::thread::{SpuThread, SPU_LS_SIZE};
+use oc_spu::SpuDecoder;
 use crate::breakpoint::BreakpointManager;
 use crate::disassembler::SpuDisassembler;
+use std::collections::VecDeque;
+
+/// Channel accesses kept per SPU
+pub const MAX_CHANNEL_EVENTS: usize = 512;
 
 /// SPU debug state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -30,6 +36,88 @@ pub struct ChannelDebugInfo {
     pub stalled: bool,
 }
 
+/// Kind of channel instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelAccess {
+    /// rdch
+    Read,
+    /// wrch
+    Write,
+    /// rchcnt
+    Count,
+}
+
+impl ChannelAccess {
+    /// Decode a channel instruction into its access, channel and register
+    pub fn decode(opcode: u32) -> Option<(Self, u32, usize)> {
+        let access = match SpuDecoder::op11(opcode) {
+            0b00000001101 => Self::Read,
+            0b00100001101 => Self::Write,
+            0b00000001111 => Self::Count,
+            _ => return None,
+        };
+        let (_, ca, rt) = SpuDecoder::rr_form(opcode);
+        Some((access, ca as u32, rt as usize))
+    }
+
+    /// Instruction mnemonic
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Self::Read => "rdch",
+            Self::Write => "wrch",
+            Self::Count => "rchcnt",
+        }
+    }
+}
+
+/// Name of an SPU channel
+pub fn channel_name(channel: u32) -> String {
+    let name = match channel {
+        SPU_RD_EVENT_STAT => "SPU_RdEventStat",
+        SPU_WR_EVENT_MASK => "SPU_WrEventMask",
+        SPU_WR_EVENT_ACK => "SPU_WrEventAck",
+        SPU_RD_SIGNAL1 => "SPU_RdSigNotify1",
+        SPU_RD_SIGNAL2 => "SPU_RdSigNotify2",
+        SPU_WR_DECR => "SPU_WrDec",
+        SPU_RD_DECR => "SPU_RdDec",
+        MFC_WR_TAG_MASK => "MFC_WrTagMask",
+        MFC_RD_TAG_STAT => "MFC_RdTagStat",
+        MFC_RD_LIST_STALL => "MFC_RdListStallStat",
+        MFC_WR_LIST_STALL_ACK => "MFC_WrListStallAck",
+        MFC_RD_ATOMIC_STAT => "MFC_RdAtomicStat",
+        SPU_WR_OUT_MBOX => "SPU_WrOutMbox",
+        SPU_RD_IN_MBOX => "SPU_RdInMbox",
+        SPU_WR_OUT_INTR_MBOX => "SPU_WrOutIntrMbox",
+        _ => return format!("ch{}", channel),
+    };
+    name.to_string()
+}
+
+/// A channel instruction an SPU executed
+#[derive(Debug, Clone)]
+pub struct ChannelEvent {
+    /// Number of the access on this SPU, counting from 0
+    pub sequence: u64,
+    /// Instruction address in local storage
+    pub address: u32,
+    pub access: ChannelAccess,
+    pub channel: u32,
+    /// Value read, written or counted
+    pub value: u32,
+    /// Read from an empty or write to a full channel, which blocks until the
+    /// PPU or the MFC catches up; logged once however long it waits
+    pub stalled: bool,
+}
+
+/// Channel instruction about to execute, finished by `SpuDebugger::finish_channel_access`
+#[derive(Debug, Clone)]
+pub struct PendingChannelAccess {
+    address: u32,
+    access: ChannelAccess,
+    channel: u32,
+    register: usize,
+}
+
 /// MFC command debug info
 #[derive(Debug, Clone)]
 pub struct MfcCommandDebugInfo {
@@ -59,6 +147,18 @@ pub struct SpuDebugger {
     trace_buffers: [Vec<SpuTraceEntry>; 6],
     /// Max trace entries
     max_trace_entries: usize,
+    /// Local storage at the previous break, which changes are highlighted against
+    ls_baselines: [Option<Vec<u8>>; 6],
+    /// Local storage at the latest break
+    ls_snapshots: [Option<Vec<u8>>; 6],
+    /// Whether the snapshot of the current break was taken
+    at_break: [bool; 6],
+    /// Run the next instruction even if it has a breakpoint (set when leaving one)
+    skip_breakpoint: [bool; 6],
+    /// Recent channel accesses per SPU, oldest first
+    channel_events: [VecDeque<ChannelEvent>; 6],
+    /// Channel accesses recorded per SPU
+    channel_event_counts: [u64; 6],
 }
 
 /// SPU trace entry
@@ -103,6 +203,12 @@ impl SpuDebugger {
                 Vec::new(),
             ],
             max_trace_entries: 10000,
+            ls_baselines: Default::default(),
+            ls_snapshots: Default::default(),
+            at_break: [false; 6],
+            skip_breakpoint: [false; 6],
+            channel_events: Default::default(),
+            channel_event_counts: [0; 6],
         }
     }
 
@@ -118,6 +224,8 @@ impl SpuDebugger {
     pub fn resume(&mut self, spu_id: usize) {
         if spu_id < 6 {
             self.states[spu_id] = SpuDebugState::Running;
+            self.skip_breakpoint[spu_id] = true;
+            self.end_break(spu_id);
             tracing::info!("SPU {} debugger: resumed", spu_id);
         }
     }
@@ -126,20 +234,23 @@ impl SpuDebugger {
     pub fn step(&mut self, spu_id: usize) {
         if spu_id < 6 {
             self.states[spu_id] = SpuDebugState::Stepping;
+            self.skip_breakpoint[spu_id] = true;
+            self.end_break(spu_id);
             tracing::debug!("SPU {} debugger: stepping", spu_id);
         }
     }
 
     /// Check if execution should stop before executing an instruction
-    pub fn check_before_execute(&mut self, spu_id: usize, pc: u32) -> bool {
+    pub fn check_before_execute(&mut self, spu_id: usize, thread: &SpuThread) -> bool {
         if spu_id >= 6 {
             return false;
         }
+        let pc = thread.pc();
 
         match self.states[spu_id] {
             SpuDebugState::Running => {
                 // Check for breakpoints
-                if self.breakpoints[spu_id].check_execution(pc as u64).is_some() {
+                if self.breakpoints[spu_id].check_execution(pc as u64, thread).is_some() {
                     tracing::info!("SPU {} debugger: breakpoint hit at 0x{:08x}", spu_id, pc);
                     self.states[spu_id] = SpuDebugState::Paused;
                     return true;
@@ -154,6 +265,134 @@ impl SpuDebugger {
         }
     }
 
+    /// Whether the runner may execute the next instruction of an SPU
+    ///
+    /// Unlike `check_before_execute`, a step lets exactly one instruction run and
+    /// resuming does not stop again at the breakpoint that was left.
+    pub fn should_run(&mut self, spu_id: usize, thread: &SpuThread) -> bool {
+        if spu_id >= 6 {
+            return true;
+        }
+        match self.states[spu_id] {
+            SpuDebugState::Running => {
+                if std::mem::take(&mut self.skip_breakpoint[spu_id]) {
+                    return true;
+                }
+                let pc = thread.pc();
+                if self.breakpoints[spu_id].check_execution(pc as u64, thread).is_none() {
+                    return true;
+                }
+                tracing::info!("SPU {} debugger: breakpoint hit at 0x{:08x}", spu_id, pc);
+                self.states[spu_id] = SpuDebugState::Paused;
+                self.record_break(spu_id, &thread.local_storage[..]);
+                false
+            }
+            SpuDebugState::Paused => {
+                self.record_break(spu_id, &thread.local_storage[..]);
+                false
+            }
+            SpuDebugState::Stepping => {
+                self.states[spu_id] = SpuDebugState::Paused;
+                true
+            }
+        }
+    }
+
+    /// Snapshot local storage when an SPU stops, once per break
+    pub fn record_break(&mut self, spu_id: usize, local_storage: &[u8]) {
+        if spu_id >= 6 || self.at_break[spu_id] {
+            return;
+        }
+        self.at_break[spu_id] = true;
+        let snapshot = match self.ls_baselines[spu_id].take() {
+            // Reuse the oldest buffer instead of allocating another 256 KB
+            Some(mut buffer) if buffer.len() == local_storage.len() => {
+                buffer.copy_from_slice(local_storage);
+                buffer
+            }
+            _ => local_storage.to_vec(),
+        };
+        self.ls_baselines[spu_id] = self.ls_snapshots[spu_id].replace(snapshot);
+    }
+
+    /// Leave the current break, unless the SPU itself is still paused
+    pub fn end_break(&mut self, spu_id: usize) {
+        if spu_id < 6 && self.states[spu_id] != SpuDebugState::Paused {
+            self.at_break[spu_id] = false;
+        }
+    }
+
+    /// Whether there is an earlier break to compare local storage against
+    pub fn has_ls_baseline(&self, spu_id: usize) -> bool {
+        self.ls_baselines.get(spu_id).is_some_and(Option::is_some)
+    }
+
+    /// Which bytes of `current`, read from `offset`, changed since the previous break
+    pub fn changed_bytes(&self, spu_id: usize, offset: u32, current: &[u8]) -> Vec<bool> {
+        let Some(baseline) = self.ls_baselines.get(spu_id).and_then(Option::as_ref) else {
+            return vec![false; current.len()];
+        };
+        let offset = offset as usize;
+        current
+            .iter()
+            .enumerate()
+            .map(|(i, byte)| baseline.get(offset + i).is_some_and(|old| old != byte))
+            .collect()
+    }
+
+    /// Number of local storage bytes that changed since the previous break
+    pub fn changed_byte_count(&self, spu_id: usize, local_storage: &[u8]) -> usize {
+        self.ls_baselines
+            .get(spu_id)
+            .and_then(Option::as_ref)
+            .map_or(0, |baseline| baseline.iter().zip(local_storage).filter(|(old, new)| old != new).count())
+    }
+
+    /// Capture a channel instruction before it executes
+    pub fn begin_channel_access(thread: &SpuThread, opcode: u32) -> Option<PendingChannelAccess> {
+        let (access, channel, register) = ChannelAccess::decode(opcode)?;
+        Some(PendingChannelAccess { address: thread.pc(), access, channel, register })
+    }
+
+    /// Log a channel instruction once it executed
+    pub fn finish_channel_access(&mut self, spu_id: usize, pending: PendingChannelAccess, thread: &SpuThread) {
+        if spu_id >= 6 {
+            return;
+        }
+        // A blocked access leaves the pc on the instruction to retry it
+        let stalled = pending.access != ChannelAccess::Count && thread.pc() == pending.address;
+        let events = &self.channel_events[spu_id];
+        if stalled && events.back().is_some_and(|last| last.stalled && last.address == pending.address) {
+            return;
+        }
+        let event = ChannelEvent {
+            sequence: self.channel_event_counts[spu_id],
+            address: pending.address,
+            access: pending.access,
+            channel: pending.channel,
+            value: thread.regs.read_preferred_u32(pending.register),
+            stalled,
+        };
+        self.channel_event_counts[spu_id] += 1;
+        let events = &mut self.channel_events[spu_id];
+        if events.len() == MAX_CHANNEL_EVENTS {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    /// Recent channel accesses of an SPU, oldest first
+    pub fn channel_activity(&self, spu_id: usize) -> impl Iterator<Item = &ChannelEvent> {
+        self.channel_events.get(spu_id).into_iter().flatten()
+    }
+
+    /// Clear the channel activity log of an SPU
+    pub fn clear_channel_activity(&mut self, spu_id: usize) {
+        if spu_id < 6 {
+            self.channel_events[spu_id].clear();
+        }
+    }
+
     /// Record instruction execution for tracing
     pub fn trace_instruction(&mut self, spu_id: usize, pc: u32, opcode: u32, cycle: u64) {
         if spu_id >= 6 || !self.tracing_enabled[spu_id] {
@@ -237,6 +476,17 @@ impl SpuDebugger {
             count: 1,
             stalled: false,
         });
+
+        // Mailboxes
+        for channel in [SPU_WR_OUT_MBOX, SPU_RD_IN_MBOX, SPU_WR_OUT_INTR_MBOX] {
+            info.push(ChannelDebugInfo {
+                name: channel_name(channel),
+                channel,
+                value: None,
+                count: thread.channels.get_count(channel),
+                stalled: false,
+            });
+        }
         
         info.push(ChannelDebugInfo {
             name: "SPU_RdEventMask".to_string(),
@@ -291,10 +541,23 @@ impl SpuDebugger {
     }
 
     /// Get MFC command queue info
-    pub fn get_mfc_queue(&self, _thread: &SpuThread) -> Vec<MfcCommandDebugInfo> {
-        // The MFC queue is private, so we can't iterate directly
-        // Return empty for now - this would require adding a public API to Mfc
-        Vec::new()
+    pub fn get_mfc_queue(&self, thread: &SpuThread) -> Vec<MfcCommandDebugInfo> {
+        let cycle = thread.mfc.get_cycle_counter();
+        thread
+            .mfc
+            .queued_commands()
+            .map(|cmd| MfcCommandDebugInfo {
+                cmd: cmd.cmd as u32,
+                lsa: cmd.lsa,
+                ea: cmd.ea,
+                size: cmd.size,
+                tag: cmd.tag as u32,
+                status: match cmd.completion_cycle.checked_sub(cycle) {
+                    Some(0) | None => format!("{:?}, done", cmd.cmd),
+                    Some(left) => format!("{:?}, {} cycles left", cmd.cmd, left),
+                },
+            })
+            .collect()
     }
 
     /// Disassemble SPU local storage at address
@@ -353,6 +616,13 @@ impl SpuRegisterSnapshot {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use oc_memory::MemoryManager;
+
+    fn thread_at(pc: u32) -> SpuThread {
+        let mut thread = SpuThread::new(0, MemoryManager::new().unwrap());
+        thread.set_pc(pc);
+        thread
+    }
 
     #[test]
     fn test_spu_debugger_creation() {
@@ -382,7 +652,7 @@ mod tests {
         assert_eq!(debugger.states[0], SpuDebugState::Stepping);
         
         // After check, should be paused
-        assert!(debugger.check_before_execute(0, 0x100));
+        assert!(debugger.check_before_execute(0, &thread_at(0x100)));
         assert_eq!(debugger.states[0], SpuDebugState::Paused);
     }
 
@@ -393,10 +663,10 @@ mod tests {
         debugger.breakpoints[0].add_execution_breakpoint(0x100);
         
         // Should not stop at other addresses
-        assert!(!debugger.check_before_execute(0, 0x104));
+        assert!(!debugger.check_before_execute(0, &thread_at(0x104)));
         
         // Should stop at breakpoint
-        assert!(debugger.check_before_execute(0, 0x100));
+        assert!(debugger.check_before_execute(0, &thread_at(0x100)));
         assert_eq!(debugger.states[0], SpuDebugState::Paused);
     }
 
@@ -411,4 +681,110 @@ mod tests {
         let trace = debugger.get_trace(0, 10);
         assert_eq!(trace.len(), 2);
     }
+
+    #[test]
+    fn test_ls_changes_since_last_break() {
+        let mut debugger = SpuDebugger::new();
+        let mut thread = thread_at(0x100);
+
+        debugger.pause(0);
+        assert!(!debugger.should_run(0, &thread));
+        assert!(!debugger.has_ls_baseline(0));
+
+        debugger.resume(0);
+        assert!(debugger.should_run(0, &thread));
+        thread.ls_write_u32(0x200, 0x11223344);
+        debugger.pause(0);
+        assert!(!debugger.should_run(0, &thread));
+        // Further checks while paused keep the same break
+        assert!(!debugger.should_run(0, &thread));
+
+        assert!(debugger.has_ls_baseline(0));
+        assert_eq!(debugger.changed_byte_count(0, &thread.local_storage[..]), 4);
+        let view = debugger.get_local_storage_view(&thread, 0x1FE, 8);
+        assert_eq!(
+            debugger.changed_bytes(0, 0x1FE, &view),
+            [false, false, true, true, true, true, false, false]
+        );
+    }
+
+    #[test]
+    fn test_should_run_steps_and_leaves_breakpoint() {
+        let mut debugger = SpuDebugger::new();
+        debugger.breakpoints[0].add_execution_breakpoint(0x100);
+        let thread = thread_at(0x100);
+
+        assert!(!debugger.should_run(0, &thread));
+        assert!(debugger.is_paused(0));
+
+        // A step runs exactly one instruction
+        debugger.step(0);
+        assert!(debugger.should_run(0, &thread));
+        assert!(!debugger.should_run(0, &thread));
+
+        // Resuming does not stop at the same breakpoint again
+        debugger.resume(0);
+        assert!(debugger.should_run(0, &thread));
+        assert!(!debugger.should_run(0, &thread));
+    }
+
+    #[test]
+    fn test_channel_activity() {
+        let mut debugger = SpuDebugger::new();
+        let mut thread = thread_at(0);
+        let interpreter = oc_spu::SpuInterpreter::new();
+
+        // rdch $2, $SPU_RdInMbox with an empty mailbox, then wrch $SPU_WrOutMbox, $1
+        thread.ls_write_u32(0, (0b00000001101 << 21) | (SPU_RD_IN_MBOX << 7) | 2);
+        thread.ls_write_u32(4, (0b00100001101 << 21) | (SPU_WR_OUT_MBOX << 7) | 1);
+        thread.regs.write_preferred_u32(1, 0xBEEF);
+        for step in 0..4 {
+            if step == 2 {
+                thread.channels.put_inbound_mailbox(0x1234);
+            }
+            let opcode = thread.ls_read_u32(thread.pc());
+            let pending = SpuDebugger::begin_channel_access(&thread, opcode).unwrap();
+            interpreter.step(&mut thread).unwrap();
+            debugger.finish_channel_access(0, pending, &thread);
+        }
+        assert!(SpuDebugger::begin_channel_access(&thread, 0x40200000).is_none());
+
+        // The read waits twice but is logged stalled once
+        let events: Vec<_> = debugger.channel_activity(0).collect();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].access, ChannelAccess::Read);
+        assert_eq!(events[0].channel, SPU_RD_IN_MBOX);
+        assert!(events[0].stalled);
+        assert_eq!((events[1].address, events[1].value), (0, 0x1234));
+        assert!(!events[1].stalled);
+        assert_eq!(events[2].access, ChannelAccess::Write);
+        assert_eq!((events[2].sequence, events[2].address, events[2].value), (2, 4, 0xBEEF));
+        assert!(!events[2].stalled);
+        assert_eq!(channel_name(events[2].channel), "SPU_WrOutMbox");
+
+        debugger.clear_channel_activity(0);
+        assert_eq!(debugger.channel_activity(0).count(), 0);
+    }
+
+    #[test]
+    fn test_mfc_queue_view() {
+        use oc_spu::mfc::{MfcCommand, MfcDmaCommand};
+
+        let debugger = SpuDebugger::new();
+        let mut thread = thread_at(0);
+        thread.mfc.queue_command(MfcDmaCommand {
+            lsa: 0x1000,
+            ea: 0x2000_0000,
+            size: 0x80,
+            tag: 3,
+            cmd: MfcCommand::Get,
+            issue_cycle: 0,
+            completion_cycle: 0,
+        });
+
+        let queue = debugger.get_mfc_queue(&thread);
+        assert_eq!(queue.len(), 1);
+        assert_eq!((queue[0].cmd, queue[0].lsa, queue[0].ea, queue[0].tag), (0x40, 0x1000, 0x2000_0000, 3));
+        assert!(queue[0].status.starts_with("Get, "));
+    }
 }
@@ -0,0 +1,396 @@
+//! Instruction trace recording to gzip-compressed text files
+//!
+//! Each executed instruction becomes one line with the processor, PC,
+//! opcode, disassembly and the registers the instruction changed:
+//!
+//! ```text
+//! ppu0 00010208: 38600064  li       r3, 100  r3=0000000000000064
+//! ```
+//!
+//! The plain layout is meant for diffing against traces from other
+//! emulators. A comment line lists each thread's non-zero registers before
+//! its first recorded instruction, so instruction lines only need the
+//! differences.
+
+use crate::disassembler::{PpuDisassembler, SpuDisassembler};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use oc_ppu::thread::PpuRegisters;
+use oc_spu::thread::SpuRegisters;
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+
+/// PPU special registers after the 96 GPR/FPR/VR slots, with their hex widths
+const PPU_SPECIAL: [(&str, usize); 6] = [("cr", 8), ("lr", 16), ("ctr", 16), ("xer", 16), ("fpscr", 16), ("vscr", 8)];
+
+/// Event that starts or stops recording
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceTrigger {
+    /// A traced processor's PC is inside the range
+    Address(Range<u64>),
+    /// A breakpoint is hit
+    Breakpoint,
+}
+
+impl TraceTrigger {
+    /// Parse `breakpoint`, an address or an `start-end` range (end excluded)
+    ///
+    /// An empty string gives None.
+    pub fn parse(text: &str) -> Result<Option<Self>, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(None);
+        }
+        if text.eq_ignore_ascii_case("breakpoint") {
+            return Ok(Some(Self::Breakpoint));
+        }
+        let parse = |addr: &str| {
+            let addr = addr.trim();
+            let result = match addr.strip_prefix("0x").or_else(|| addr.strip_prefix("0X")) {
+                Some(hex) => u64::from_str_radix(hex, 16),
+                None => addr.parse(),
+            };
+            result.map_err(|_| format!("Invalid trace trigger address \"{}\"", addr))
+        };
+        let range = match text.split_once('-') {
+            Some((start, end)) => parse(start)?..parse(end)?,
+            None => {
+                let addr = parse(text)?;
+                addr..addr.saturating_add(1)
+            }
+        };
+        if range.is_empty() {
+            return Err(format!("Empty trace trigger range \"{}\"", text));
+        }
+        Ok(Some(Self::Address(range)))
+    }
+}
+
+/// What a trace records
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceConfig {
+    /// Start when this fires; None starts right away
+    pub start: Option<TraceTrigger>,
+    /// Stop when this fires; None records until the trace is finished
+    pub stop: Option<TraceTrigger>,
+    /// Keep only the last `ring_size` instructions, written when the trace
+    /// is finished; 0 streams every instruction to the file
+    pub ring_size: usize,
+    /// Record PPU threads
+    pub ppu: bool,
+    /// Record SPU threads
+    pub spu: bool,
+}
+
+impl Default for TraceConfig {
+    fn default() -> Self {
+        Self {
+            start: None,
+            stop: None,
+            ring_size: 0,
+            ppu: true,
+            spu: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TraceState {
+    Waiting,
+    Recording,
+    Done,
+}
+
+/// Records executed instructions of PPU and SPU threads
+pub struct TraceRecorder<W: Write = BufWriter<File>> {
+    config: TraceConfig,
+    state: TraceState,
+    writer: GzEncoder<W>,
+    ring: VecDeque<String>,
+    /// Threads whose initial registers were written
+    seen: HashSet<String>,
+    recorded: u64,
+}
+
+impl TraceRecorder {
+    /// Record into a new gzip file at `path`
+    pub fn create(path: &Path, config: TraceConfig) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(Self::new(BufWriter::new(File::create(path)?), config))
+    }
+}
+
+impl<W: Write> TraceRecorder<W> {
+    /// Record into `writer`
+    pub fn new(writer: W, config: TraceConfig) -> Self {
+        let state = if config.start.is_some() { TraceState::Waiting } else { TraceState::Recording };
+        Self {
+            ring: VecDeque::with_capacity(config.ring_size.min(1 << 16)),
+            config,
+            state,
+            writer: GzEncoder::new(writer, Compression::fast()),
+            seen: HashSet::new(),
+            recorded: 0,
+        }
+    }
+
+    /// Whether instructions are being recorded now
+    pub fn is_recording(&self) -> bool {
+        self.state == TraceState::Recording
+    }
+
+    /// Whether the stop trigger ended the trace
+    pub fn is_finished(&self) -> bool {
+        self.state == TraceState::Done
+    }
+
+    /// Number of instructions recorded so far
+    pub fn recorded(&self) -> u64 {
+        self.recorded
+    }
+
+    /// Tell the recorder a breakpoint was hit, for breakpoint triggers
+    pub fn breakpoint_hit(&mut self) -> io::Result<()> {
+        match self.state {
+            TraceState::Waiting if self.config.start == Some(TraceTrigger::Breakpoint) => {
+                tracing::info!("Instruction trace started at breakpoint");
+                self.state = TraceState::Recording;
+            }
+            TraceState::Recording if self.config.stop == Some(TraceTrigger::Breakpoint) => self.end()?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Record a PPU instruction with the registers before and after it executed
+    pub fn record_ppu(
+        &mut self,
+        thread: u32,
+        pc: u64,
+        opcode: u32,
+        before: &PpuRegisters,
+        after: &PpuRegisters,
+    ) -> io::Result<()> {
+        if !self.config.ppu || !self.triggered(pc) {
+            return Ok(());
+        }
+        let disasm = PpuDisassembler::disassemble(pc, opcode).to_string();
+        let (before, after) = (ppu_values(before), ppu_values(after));
+        self.write_line(format!("ppu{}", thread), pc, opcode, &disasm, (&before, &after), ppu_register)
+    }
+
+    /// Record an SPU instruction with the registers before and after it executed
+    pub fn record_spu(
+        &mut self,
+        spu: u32,
+        pc: u32,
+        opcode: u32,
+        before: &SpuRegisters,
+        after: &SpuRegisters,
+    ) -> io::Result<()> {
+        if !self.config.spu || !self.triggered(pc as u64) {
+            return Ok(());
+        }
+        let disasm = SpuDisassembler::disassemble(pc, opcode).to_string();
+        let values = |regs: &SpuRegisters| regs.gpr.iter().map(|&r| vector(r)).collect::<Vec<_>>();
+        let (before, after) = (values(before), values(after));
+        let registers = (before.as_slice(), after.as_slice());
+        self.write_line(format!("spu{}", spu), pc as u64, opcode, &disasm, registers, |i| (format!("r{}", i), 32))
+    }
+
+    /// Write out the ring and close the compressed stream
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush_ring()?;
+        self.writer.finish()
+    }
+
+    /// Apply address triggers for an instruction at `pc`; whether to record it
+    fn triggered(&mut self, pc: u64) -> bool {
+        match self.state {
+            TraceState::Waiting => match &self.config.start {
+                Some(TraceTrigger::Address(range)) if range.contains(&pc) => {
+                    tracing::info!("Instruction trace started at 0x{:08x}", pc);
+                    self.state = TraceState::Recording;
+                    true
+                }
+                _ => false,
+            },
+            TraceState::Recording => true,
+            TraceState::Done => false,
+        }
+    }
+
+    /// Buffer or write one instruction; `registers` are the values before and after it
+    fn write_line(
+        &mut self,
+        thread: String,
+        pc: u64,
+        opcode: u32,
+        disasm: &str,
+        (before, after): (&[u128], &[u128]),
+        register: impl Fn(usize) -> (String, usize),
+    ) -> io::Result<()> {
+        let mut line = String::new();
+        if !self.seen.contains(&thread) {
+            line.push_str("# ");
+            line.push_str(&thread);
+            for (i, &value) in before.iter().enumerate().filter(|(_, &value)| value != 0) {
+                let (name, width) = register(i);
+                let _ = write!(line, " {}={:0width$x}", name, value, width = width);
+            }
+            line.push('\n');
+        }
+        let _ = write!(line, "{} {:08x}: {:08x}  {}", thread, pc, opcode, disasm);
+        for (i, (&value, _)) in after.iter().zip(before).enumerate().filter(|(_, (a, b))| a != b) {
+            let (name, width) = register(i);
+            let _ = write!(line, "  {}={:0width$x}", name, value, width = width);
+        }
+        self.seen.insert(thread);
+        self.recorded += 1;
+
+        if self.config.ring_size > 0 {
+            if self.ring.len() == self.config.ring_size {
+                self.ring.pop_front();
+            }
+            self.ring.push_back(line);
+        } else {
+            writeln!(self.writer, "{}", line)?;
+        }
+
+        if let Some(TraceTrigger::Address(range)) = &self.config.stop {
+            if range.contains(&pc) {
+                self.end()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Stop for good, finishing the file so it is complete on disk
+    fn end(&mut self) -> io::Result<()> {
+        tracing::info!("Instruction trace stopped after {} instructions", self.recorded);
+        self.state = TraceState::Done;
+        self.flush_ring()?;
+        self.writer.try_finish()?;
+        self.writer.get_mut().flush()
+    }
+
+    fn flush_ring(&mut self) -> io::Result<()> {
+        for line in self.ring.drain(..) {
+            writeln!(self.writer, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// PPU register slots in [`ppu_register`] order
+fn ppu_values(regs: &PpuRegisters) -> Vec<u128> {
+    let mut values = Vec::with_capacity(96 + PPU_SPECIAL.len());
+    values.extend(regs.gpr.iter().map(|&r| r as u128));
+    values.extend(regs.fpr.iter().map(|f| f.to_bits() as u128));
+    values.extend(regs.vr.iter().map(|&v| vector(v)));
+    values.extend([regs.cr as u128, regs.lr as u128, regs.ctr as u128]);
+    values.extend([regs.xer as u128, regs.fpscr as u128, regs.vscr as u128]);
+    values
+}
+
+/// A vector register with word 0 most significant
+fn vector(words: [u32; 4]) -> u128 {
+    words.iter().fold(0, |value, &word| value << 32 | word as u128)
+}
+
+/// Name and hex width of a PPU register slot
+fn ppu_register(index: usize) -> (String, usize) {
+    match index {
+        0..=31 => (format!("r{}", index), 16),
+        32..=63 => (format!("f{}", index - 32), 16),
+        64..=95 => (format!("v{}", index - 64), 32),
+        _ => {
+            let (name, width) = PPU_SPECIAL[index - 96];
+            (name.to_string(), width)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn decompress(data: &[u8]) -> String {
+        let mut text = String::new();
+        GzDecoder::new(data).read_to_string(&mut text).unwrap();
+        text
+    }
+
+    /// Run `li r3, n + 1` at 0x1000 + 4n for n in 0..count
+    fn run(recorder: &mut TraceRecorder<Vec<u8>>, count: u64) {
+        let mut regs = PpuRegisters::default();
+        regs.gpr[1] = 0x8000;
+        for n in 0..count {
+            let before = regs.clone();
+            regs.gpr[3] = n + 1;
+            recorder.record_ppu(0, 0x1000 + n * 4, 0x3860_0001 + n as u32, &before, &regs).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_trigger_parse() {
+        assert_eq!(TraceTrigger::parse(" "), Ok(None));
+        assert_eq!(TraceTrigger::parse("Breakpoint"), Ok(Some(TraceTrigger::Breakpoint)));
+        assert_eq!(TraceTrigger::parse("0x100-0x200"), Ok(Some(TraceTrigger::Address(0x100..0x200))));
+        assert_eq!(TraceTrigger::parse("4096"), Ok(Some(TraceTrigger::Address(4096..4097))));
+        assert!(TraceTrigger::parse("0x200-0x100").is_err());
+        assert!(TraceTrigger::parse("main").is_err());
+    }
+
+    #[test]
+    fn test_trace_changed_registers() {
+        let mut recorder = TraceRecorder::new(Vec::new(), TraceConfig::default());
+        run(&mut recorder, 3);
+        assert_eq!(recorder.recorded(), 3);
+        let text = decompress(&recorder.finish().unwrap());
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "# ppu0 r1=0000000000008000");
+        assert!(lines[1].starts_with("ppu0 00001000: 38600001  li"));
+        assert!(lines[1].ends_with("  r3=0000000000000001"), "{}", lines[1]);
+        assert!(lines[3].starts_with("ppu0 00001008: 38600003"));
+        assert!(lines[3].ends_with("3  r3=0000000000000003"), "{}", lines[3]);
+    }
+
+    #[test]
+    fn test_trace_triggers_and_ring() {
+        // Start at the third instruction, stop at the sixth
+        let config = TraceConfig {
+            start: Some(TraceTrigger::Address(0x1008..0x100C)),
+            stop: Some(TraceTrigger::Address(0x1014..0x1018)),
+            ..Default::default()
+        };
+        let mut recorder = TraceRecorder::new(Vec::new(), config);
+        run(&mut recorder, 10);
+        assert!(recorder.is_finished());
+        assert_eq!(recorder.recorded(), 4);
+
+        // A ring keeps only the most recent instructions; breakpoints start it
+        let config = TraceConfig {
+            start: Some(TraceTrigger::Breakpoint),
+            ring_size: 2,
+            ..Default::default()
+        };
+        let mut recorder = TraceRecorder::new(Vec::new(), config);
+        run(&mut recorder, 2);
+        assert!(!recorder.is_recording());
+        recorder.breakpoint_hit().unwrap();
+        run(&mut recorder, 5);
+        let text = decompress(&recorder.finish().unwrap());
+        let pcs: Vec<_> = text.lines().filter(|line| !line.starts_with('#')).map(|line| &line[5..13]).collect();
+        assert_eq!(pcs, ["0000100c", "00001010"]);
+    }
+}
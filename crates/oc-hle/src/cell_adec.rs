@@ -2,7 +2,7 @@
 //!
 //! This module provides HLE implementations for the PS3's audio decoder library.
 
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use tracing::trace;
 
 /// Audio decoder handle
@@ -21,6 +21,20 @@ pub enum CellAdecCodecType {
     Wma = 6,
 }
 
+/// Name of a cellAdec codec type, None for unknown types
+pub fn codec_name(codec_type: u32) -> Option<&'static str> {
+    match codec_type {
+        0 => Some("LPCM"),
+        1 => Some("AC3"),
+        2 => Some("ATRAC3"),
+        3 => Some("ATRAC3plus"),
+        4 => Some("MP3"),
+        5 => Some("AAC"),
+        6 => Some("WMA"),
+        _ => None,
+    }
+}
+
 /// Audio decoder type
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -111,10 +125,15 @@ struct AdecEntry {
     decoder: Option<AudioDecoderBackend>,
 }
 
+/// Samples per channel in an ATRAC3+ frame
+pub(crate) const ATRAC3PLUS_FRAME_SAMPLES: u32 = 2048;
+
 /// Audio decoder backend implementation
+///
+/// Also the core decoder of the cellAtrac and cellAtracMulti contexts.
 #[allow(dead_code)]
 #[derive(Debug)]
-struct AudioDecoderBackend {
+pub(crate) struct AudioDecoderBackend {
     /// Codec type
     codec: CellAdecCodecType,
     /// Sample rate (Hz)
@@ -129,7 +148,7 @@ struct AudioDecoderBackend {
 
 impl AudioDecoderBackend {
     /// Create a new audio decoder backend
-    fn new(codec_type: CellAdecCodecType) -> Self {
+    pub(crate) fn new(codec_type: CellAdecCodecType) -> Self {
         // Default audio parameters
         let (sample_rate, channels) = match codec_type {
             CellAdecCodecType::Aac => (48000, 2),      // AAC: 48kHz stereo
@@ -148,6 +167,15 @@ impl AudioDecoderBackend {
         }
     }
 
+    /// Create a backend for a stream whose format is known
+    pub(crate) fn with_format(codec_type: CellAdecCodecType, sample_rate: u32, channels: u32) -> Self {
+        Self {
+            sample_rate,
+            channels,
+            ..Self::new(codec_type)
+        }
+    }
+
     /// Decode AAC access unit to PCM
     fn decode_aac(&mut self, au_data: &[u8], au_info: &CellAdecAuInfo) -> Result<CellAdecPcmItem, i32> {
         trace!("AudioDecoderBackend::decode_aac: size={}, pts={}", au_data.len(), au_info.pts);
@@ -203,10 +231,10 @@ impl AudioDecoderBackend {
         Ok(pcm_item)
     }
 
-    /// Decode ATRAC3+ access unit to PCM
-    fn decode_atrac3plus(&mut self, au_data: &[u8], au_info: &CellAdecAuInfo) -> Result<CellAdecPcmItem, i32> {
-        trace!("AudioDecoderBackend::decode_atrac3plus: size={}, pts={}", au_data.len(), au_info.pts);
-        
+    /// Decode one ATRAC3+ frame to interleaved PCM of every channel
+    pub(crate) fn decode_atrac3plus_frame(&mut self, frame: &[u8]) -> Vec<f32> {
+        trace!("AudioDecoderBackend::decode_atrac3plus_frame: size={}", frame.len());
+
         // TODO: Actual ATRAC3+ decoding
         // ATRAC3+ is a Sony proprietary format
         // In a real implementation:
@@ -215,12 +243,19 @@ impl AudioDecoderBackend {
         // 3. Apply tone synthesis
         // 4. Joint stereo processing
         // 5. Output PCM samples
-        
+
         self.frame_count += 1;
-        
+
         // Simulate decoded PCM: 2048 samples per channel (ATRAC3+ frame size)
-        let samples_per_frame = 2048;
-        let pcm_size = samples_per_frame * self.channels * (self.bit_depth / 8);
+        vec![0.0; (ATRAC3PLUS_FRAME_SAMPLES * self.channels) as usize]
+    }
+
+    /// Decode ATRAC3+ access unit to PCM
+    fn decode_atrac3plus(&mut self, au_data: &[u8], au_info: &CellAdecAuInfo) -> Result<CellAdecPcmItem, i32> {
+        trace!("AudioDecoderBackend::decode_atrac3plus: size={}, pts={}", au_data.len(), au_info.pts);
+        
+        let pcm = self.decode_atrac3plus_frame(au_data);
+        let pcm_size = pcm.len() as u32 * (self.bit_depth / 8);
         
         let pcm_item = CellAdecPcmItem {
             start_addr: 0,
@@ -272,6 +307,8 @@ impl AdecEntry {
 pub struct AdecManager {
     decoders: HashMap<AdecHandle, AdecEntry>,
     next_handle: AdecHandle,
+    /// How often each codec type was opened
+    opened_codecs: BTreeMap<u32, u64>,
 }
 
 impl AdecManager {
@@ -279,12 +316,14 @@ impl AdecManager {
         Self {
             decoders: HashMap::new(),
             next_handle: 1,
+            opened_codecs: BTreeMap::new(),
         }
     }
 
     pub fn open(&mut self, codec_type: u32) -> Result<AdecHandle, i32> {
         let handle = self.next_handle;
         self.next_handle += 1;
+        *self.opened_codecs.entry(codec_type).or_insert(0) += 1;
         
         let entry = AdecEntry::new(codec_type);
         self.decoders.insert(handle, entry);
@@ -292,6 +331,11 @@ impl AdecManager {
         Ok(handle)
     }
 
+    /// Codec types the game opened decoders for, with how often, by type
+    pub fn opened_codecs(&self) -> Vec<(u32, u64)> {
+        self.opened_codecs.iter().map(|(&codec, &count)| (codec, count)).collect()
+    }
+
     pub fn close(&mut self, handle: AdecHandle) -> Result<(), i32> {
         self.decoders
             .remove(&handle)
@@ -549,6 +593,8 @@ mod tests {
         
         manager.close(handle).unwrap();
         assert_eq!(manager.decoders.len(), 0);
+        // Opens are still counted after the decoder is closed
+        assert_eq!(manager.opened_codecs(), [(CellAdecCodecType::Mp3 as u32, 1)]);
     }
 
     #[test]
@@ -0,0 +1,1137 @@
+//! cellAtrac HLE - ATRAC3plus decoding contexts
+//!
+//! This module provides HLE implementations for the cellAtrac and
+//! cellAtracMulti libraries, which decode an AT3 file the game streams
+//! through a buffer of its own, typically for BGM. The game hands over the
+//! start of the file with its RIFF header, then keeps the buffer filled while
+//! it decodes frame by frame. Looping AT3 files carry their loop points in the
+//! smpl chunk; decoding, and the data the game is asked to read, jump back to
+//! the loop start for each loop the game sets. cellAtracMulti decodes files
+//! of up to eight channels into the channel layout the game picks. Both share
+//! the ATRAC3+ core decoder of cellAdec.
+
+use crate::cell_adec::{AudioDecoderBackend, CellAdecCodecType, ATRAC3PLUS_FRAME_SAMPLES};
+use std::collections::HashMap;
+use tracing::{debug, trace};
+
+// Error codes
+pub const CELL_ATRAC_ERROR_API_FAIL: i32 = 0x80610301u32 as i32;
+pub const CELL_ATRAC_ERROR_READSIZE_OVER_BUFFER: i32 = 0x80610311u32 as i32;
+pub const CELL_ATRAC_ERROR_UNKNOWN_FORMAT: i32 = 0x80610312u32 as i32;
+pub const CELL_ATRAC_ERROR_READSIZE_IS_TOO_SMALL: i32 = 0x80610313u32 as i32;
+pub const CELL_ATRAC_ERROR_ILLEGAL_SAMPLING_RATE: i32 = 0x80610314u32 as i32;
+pub const CELL_ATRAC_ERROR_ILLEGAL_DATA: i32 = 0x80610315u32 as i32;
+pub const CELL_ATRAC_ERROR_NO_DECODER: i32 = 0x80610321u32 as i32;
+pub const CELL_ATRAC_ERROR_UNSET_DATA: i32 = 0x80610322u32 as i32;
+pub const CELL_ATRAC_ERROR_DECODER_WAS_CREATED: i32 = 0x80610323u32 as i32;
+pub const CELL_ATRAC_ERROR_ALLDATA_WAS_DECODED: i32 = 0x80610331u32 as i32;
+pub const CELL_ATRAC_ERROR_NODATA_IN_BUFFER: i32 = 0x80610332u32 as i32;
+pub const CELL_ATRAC_ERROR_NOT_ALIGNED_OUT_BUFFER: i32 = 0x80610333u32 as i32;
+pub const CELL_ATRAC_ERROR_ALLDATA_IS_ONMEMORY: i32 = 0x80610341u32 as i32;
+pub const CELL_ATRAC_ERROR_ADD_DATA_IS_TOO_BIG: i32 = 0x80610342u32 as i32;
+pub const CELL_ATRAC_ERROR_UNSET_LOOP_NUM: i32 = 0x80610361u32 as i32;
+pub const CELL_ATRAC_ERROR_ILLEGAL_SAMPLE: i32 = 0x80610371u32 as i32;
+pub const CELL_ATRAC_ERROR_ILLEGAL_RESET_BYTE: i32 = 0x80610372u32 as i32;
+pub const CELL_ATRAC_ERROR_ILLEGAL_PPU_THREAD_PRIORITY: i32 = 0x80610381u32 as i32;
+pub const CELL_ATRAC_ERROR_ILLEGAL_SPU_THREAD_PRIORITY: i32 = 0x80610382u32 as i32;
+
+/// Remain frame values once the stream needs no more data
+pub const CELL_ATRAC_ALLDATA_IS_ON_MEMORY: i32 = -1;
+pub const CELL_ATRAC_NONLOOP_STREAM_DATA_IS_ON_MEMORY: i32 = -2;
+pub const CELL_ATRAC_LOOP_STREAM_DATA_IS_ON_MEMORY: i32 = -3;
+
+/// Size of the handle the game allocates
+pub const CELL_ATRAC_HANDLE_SIZE: u32 = 512;
+/// Work memory a decoder needs
+pub const CELL_ATRAC_WORK_MEM_SIZE: u32 = 0x1000;
+/// Most channels a cellAtracMulti file carries
+pub const CELL_ATRACMULTI_MAX_CHANNELS: usize = 8;
+
+/// WAVE_FORMAT_EXTENSIBLE, the format tag of AT3 files
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+/// ATRAC3plus sub-format GUID, as stored in the fmt chunk
+const ATRAC3PLUS_GUID: [u8; 16] = [
+    0xBF, 0xAA, 0x23, 0xE9, 0x58, 0xCB, 0x71, 0x44, 0xA1, 0x19, 0xFF, 0xFA, 0x01, 0xE4, 0xCE, 0x62,
+];
+
+/// cellAtracMulti error code of a cellAtrac one
+///
+/// cellAtracMulti reports the same errors at 0x80610Bxx.
+pub fn multi_error(code: i32) -> i32 {
+    if code < 0 {
+        code | 0x800
+    } else {
+        code
+    }
+}
+
+fn le16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn le32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+/// Format of an AT3 file, from its RIFF header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct At3Header {
+    pub channels: u32,
+    pub sample_rate: u32,
+    /// Bytes of one frame
+    pub block_align: u32,
+    /// Samples of the file, per channel
+    pub total_samples: u32,
+    /// Samples the decoder outputs before the first one of the file
+    pub delay: u32,
+    /// First and last sample of the loop
+    pub loop_points: Option<(u32, u32)>,
+    /// File offset of the first frame
+    pub data_offset: u32,
+    pub data_size: u32,
+}
+
+impl At3Header {
+    /// Parse the RIFF header at the start of `data`, up to the data chunk
+    pub fn parse(data: &[u8]) -> Result<Self, i32> {
+        if data.len() < 12 {
+            return Err(CELL_ATRAC_ERROR_READSIZE_IS_TOO_SMALL);
+        }
+        if &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+            return Err(CELL_ATRAC_ERROR_UNKNOWN_FORMAT);
+        }
+
+        let mut format = None;
+        let mut fact = None;
+        let mut loop_points = None;
+        let mut pos = 12;
+        loop {
+            if data.len() < pos + 8 {
+                return Err(CELL_ATRAC_ERROR_READSIZE_IS_TOO_SMALL);
+            }
+            let id = &data[pos..pos + 4];
+            let size = le32(data, pos + 4) as usize;
+            let body = pos + 8;
+
+            if id == b"data" {
+                let (channels, sample_rate, block_align) = format.ok_or(CELL_ATRAC_ERROR_UNKNOWN_FORMAT)?;
+                let frames = size as u32 / block_align;
+                let (total_samples, delay) = fact.unwrap_or((frames * ATRAC3PLUS_FRAME_SAMPLES, 0));
+                if total_samples == 0 {
+                    return Err(CELL_ATRAC_ERROR_ILLEGAL_DATA);
+                }
+                if let Some((start, end)) = loop_points {
+                    if start > end || end >= total_samples {
+                        return Err(CELL_ATRAC_ERROR_ILLEGAL_DATA);
+                    }
+                }
+                return Ok(Self {
+                    channels,
+                    sample_rate,
+                    block_align,
+                    total_samples,
+                    delay,
+                    loop_points,
+                    data_offset: body as u32,
+                    data_size: size as u32,
+                });
+            }
+
+            let chunk = data.get(body..body + size).ok_or(CELL_ATRAC_ERROR_READSIZE_IS_TOO_SMALL)?;
+            match id {
+                b"fmt " => format = Some(Self::parse_fmt(chunk)?),
+                b"fact" if size >= 8 => fact = Some((le32(chunk, 0), le32(chunk, 4))),
+                b"fact" if size >= 4 => fact = Some((le32(chunk, 0), 0)),
+                b"smpl" if size >= 36 + 24 && le32(chunk, 28) > 0 => {
+                    loop_points = Some((le32(chunk, 36 + 8), le32(chunk, 36 + 12)));
+                }
+                _ => {}
+            }
+            // Chunks are padded to an even size
+            pos = body + size + (size & 1);
+        }
+    }
+
+    /// Channels, sample rate and frame size of an ATRAC3plus fmt chunk
+    fn parse_fmt(chunk: &[u8]) -> Result<(u32, u32, u32), i32> {
+        if chunk.len() < 40 || le16(chunk, 0) != WAVE_FORMAT_EXTENSIBLE || chunk[24..40] != ATRAC3PLUS_GUID {
+            return Err(CELL_ATRAC_ERROR_UNKNOWN_FORMAT);
+        }
+        let channels = le16(chunk, 2) as u32;
+        let sample_rate = le32(chunk, 4);
+        let block_align = le16(chunk, 12) as u32;
+        if channels == 0 || channels as usize > CELL_ATRACMULTI_MAX_CHANNELS {
+            return Err(CELL_ATRAC_ERROR_UNKNOWN_FORMAT);
+        }
+        if sample_rate != 44100 && sample_rate != 48000 {
+            return Err(CELL_ATRAC_ERROR_ILLEGAL_SAMPLING_RATE);
+        }
+        if block_align == 0 {
+            return Err(CELL_ATRAC_ERROR_ILLEGAL_DATA);
+        }
+        Ok((channels, sample_rate, block_align))
+    }
+
+    /// File size, header included
+    pub fn file_size(&self) -> u32 {
+        self.data_offset + self.data_size
+    }
+
+    /// Bitrate in kbps
+    pub fn bitrate(&self) -> u32 {
+        (self.block_align as u64 * 8 * self.sample_rate as u64 / ATRAC3PLUS_FRAME_SAMPLES as u64 / 1000) as u32
+    }
+
+    /// Frame whose output holds `sample`
+    pub fn frame_of(&self, sample: u32) -> u32 {
+        (sample + self.delay) / ATRAC3PLUS_FRAME_SAMPLES
+    }
+
+    /// File offset of `frame`
+    pub fn frame_offset(&self, frame: u32) -> u32 {
+        self.data_offset + frame * self.block_align
+    }
+
+    /// Sample after the last one `frame` outputs
+    fn frame_end(&self, frame: u32) -> u32 {
+        ((frame + 1) * ATRAC3PLUS_FRAME_SAMPLES).saturating_sub(self.delay)
+    }
+}
+
+/// Channel layout of cellAtracMulti output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtracMultiOutput {
+    pub channels: u32,
+    /// Channel of the file each output channel plays, -1 for silence
+    pub tracks: [i32; CELL_ATRACMULTI_MAX_CHANNELS],
+}
+
+impl AtracMultiOutput {
+    /// Output of the first `channels` channels of the file, in order
+    pub fn identity(channels: u32) -> Self {
+        let mut tracks = [-1; CELL_ATRACMULTI_MAX_CHANNELS];
+        for (i, track) in tracks.iter_mut().enumerate().take(channels as usize) {
+            *track = i as i32;
+        }
+        Self { channels, tracks }
+    }
+
+    /// Lay out interleaved PCM of `file_channels` channels
+    fn remap(&self, pcm: &[f32], file_channels: usize) -> Vec<f32> {
+        let mut out = Vec::with_capacity(pcm.len() / file_channels * self.channels as usize);
+        for frame in pcm.chunks(file_channels) {
+            for &track in &self.tracks[..self.channels as usize] {
+                out.push(frame.get(track as usize).copied().filter(|_| track >= 0).unwrap_or(0.0));
+            }
+        }
+        out
+    }
+}
+
+/// Where the game writes stream data when resetting the play position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtracBufferInfo {
+    pub write_addr: u32,
+    pub writable_byte: u32,
+    /// Bytes to write at least
+    pub min_write_byte: u32,
+    /// File offset to read from
+    pub read_position: u32,
+}
+
+/// Output of one decode call
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtracFrame {
+    /// Interleaved PCM of the output channels
+    pub pcm: Vec<f32>,
+    /// Samples per channel
+    pub samples: u32,
+    /// Whether this was the last frame
+    pub finished: bool,
+    pub remain_frame: i32,
+}
+
+/// ATRAC3plus stream of one handle
+#[derive(Debug)]
+pub struct AtracStream {
+    header: At3Header,
+    buffer_addr: u32,
+    buffer_byte: u32,
+    /// Whether the whole file is in the buffer
+    on_memory: bool,
+    /// cellAtracMulti channel layout
+    output: Option<AtracMultiOutput>,
+    decoder: Option<AudioDecoderBackend>,
+    /// Loops the game set, -1 looping forever
+    loop_num: i32,
+    /// Loops left to decode, and to read stream data for
+    decode_loops: i32,
+    read_loops: i32,
+    /// Next frame to decode, and the next sample it outputs
+    decode_frame: u32,
+    next_sample: u32,
+    /// File offset the game reads from next
+    read_pos: u32,
+    /// Stream data in the buffer, and the buffer offset the next goes to
+    buffered: u32,
+    write_offset: u32,
+}
+
+impl AtracStream {
+    /// Stream whose first `read_byte` bytes are at `buffer_addr`
+    pub fn new(header: At3Header, buffer_addr: u32, read_byte: u32, buffer_byte: u32) -> Self {
+        let on_memory = read_byte >= header.file_size();
+        Self {
+            header,
+            buffer_addr,
+            buffer_byte,
+            on_memory,
+            output: None,
+            decoder: None,
+            loop_num: 0,
+            decode_loops: 0,
+            read_loops: 0,
+            decode_frame: header.frame_of(0),
+            next_sample: 0,
+            read_pos: read_byte.min(header.file_size()),
+            buffered: read_byte.saturating_sub(header.data_offset),
+            write_offset: if buffer_byte == 0 { 0 } else { read_byte % buffer_byte },
+        }
+    }
+
+    pub fn header(&self) -> &At3Header {
+        &self.header
+    }
+
+    /// Channels the game gets
+    pub fn channels(&self) -> u32 {
+        self.output.map_or(self.header.channels, |output| output.channels)
+    }
+
+    pub fn output(&self) -> Option<&AtracMultiOutput> {
+        self.output.as_ref()
+    }
+
+    /// Create the decoder of the stream
+    pub fn create_decoder(&mut self) -> Result<(), i32> {
+        if self.decoder.is_some() {
+            return Err(CELL_ATRAC_ERROR_DECODER_WAS_CREATED);
+        }
+        self.decoder = Some(AudioDecoderBackend::with_format(
+            CellAdecCodecType::Atrac3Plus,
+            self.header.sample_rate,
+            self.header.channels,
+        ));
+        Ok(())
+    }
+
+    /// Frame of the loop start and end, while loops are left for `loops`
+    fn active_loop(&self, loops: i32) -> Option<(u32, u32)> {
+        let (start, end) = self.header.loop_points.filter(|_| loops != 0)?;
+        Some((self.header.frame_of(start), self.header.frame_of(end)))
+    }
+
+    /// File offset reads go up to before they end or jump back
+    fn read_end(&self) -> u32 {
+        match self.active_loop(self.read_loops) {
+            Some((_, end_frame)) => self.header.frame_offset(end_frame + 1).min(self.header.file_size()),
+            None => self.header.file_size(),
+        }
+    }
+
+    /// Jump the reads back to the loop start once they reach the loop end
+    fn wrap_reads(&mut self) {
+        if let Some((start_frame, _)) = self.active_loop(self.read_loops) {
+            if self.read_pos >= self.read_end() {
+                self.read_pos = self.header.frame_offset(start_frame);
+                if self.read_loops > 0 {
+                    self.read_loops -= 1;
+                }
+            }
+        }
+    }
+
+    /// Write pointer, writable bytes and file offset to read from
+    pub fn stream_data_info(&self) -> (u32, u32, u32) {
+        if self.on_memory {
+            return (self.buffer_addr, 0, self.header.file_size());
+        }
+        let writable = (self.buffer_byte - self.buffered)
+            .min(self.buffer_byte - self.write_offset)
+            .min(self.read_end() - self.read_pos);
+        (self.buffer_addr + self.write_offset, writable, self.read_pos)
+    }
+
+    /// Take `add_byte` bytes the game wrote at the write pointer
+    pub fn add_stream_data(&mut self, add_byte: u32) -> Result<(), i32> {
+        if self.on_memory {
+            return Err(CELL_ATRAC_ERROR_ALLDATA_IS_ONMEMORY);
+        }
+        if add_byte > self.stream_data_info().1 {
+            return Err(CELL_ATRAC_ERROR_ADD_DATA_IS_TOO_BIG);
+        }
+        self.buffered += add_byte;
+        self.read_pos += add_byte;
+        self.write_offset = (self.write_offset + add_byte) % self.buffer_byte;
+        self.wrap_reads();
+        Ok(())
+    }
+
+    /// Free bytes of the buffer
+    pub fn vacant_size(&self) -> u32 {
+        if self.on_memory {
+            0
+        } else {
+            self.buffer_byte - self.buffered
+        }
+    }
+
+    /// Frames left in the buffer, or whether it holds the rest of the stream
+    pub fn remain_frame(&self) -> i32 {
+        if self.on_memory {
+            CELL_ATRAC_ALLDATA_IS_ON_MEMORY
+        } else if self.read_pos >= self.header.file_size() {
+            CELL_ATRAC_NONLOOP_STREAM_DATA_IS_ON_MEMORY
+        } else {
+            (self.buffered / self.header.block_align) as i32
+        }
+    }
+
+    /// First sample the next frame outputs, the sample after its last, and
+    /// whether decoding jumps back to the loop start after it
+    fn window(&self) -> Option<(u32, u32, bool)> {
+        if self.next_sample >= self.header.total_samples {
+            return None;
+        }
+        let frame = self.decode_frame;
+        if let Some((_, end_frame)) = self.active_loop(self.decode_loops) {
+            if frame == end_frame {
+                let loop_end = self.header.loop_points.map_or(0, |(_, end)| end);
+                return Some((self.next_sample, loop_end + 1, true));
+            }
+        }
+        Some((self.next_sample, self.header.frame_end(frame).min(self.header.total_samples), false))
+    }
+
+    /// Samples per channel the next decode outputs
+    pub fn next_sample_count(&self) -> u32 {
+        self.window().map_or(0, |(start, end, _)| end - start)
+    }
+
+    /// Next sample to decode
+    pub fn next_decode_position(&self) -> Result<u32, i32> {
+        match self.window() {
+            Some(_) => Ok(self.next_sample),
+            None => Err(CELL_ATRAC_ERROR_ALLDATA_WAS_DECODED),
+        }
+    }
+
+    /// Decode the next frame
+    pub fn decode(&mut self) -> Result<AtracFrame, i32> {
+        let (start, end, loops_back) = self.window().ok_or(CELL_ATRAC_ERROR_ALLDATA_WAS_DECODED)?;
+        if !self.on_memory && self.buffered < self.header.block_align {
+            return Err(CELL_ATRAC_ERROR_NODATA_IN_BUFFER);
+        }
+        let Some(decoder) = self.decoder.as_mut() else {
+            return Err(CELL_ATRAC_ERROR_NO_DECODER);
+        };
+
+        // Note: reading the frame from the game's buffer requires memory subsystem integration
+        let frame = vec![0u8; self.header.block_align as usize];
+        let pcm = decoder.decode_atrac3plus_frame(&frame);
+        let channels = self.header.channels as usize;
+        let first = (start + self.header.delay - self.decode_frame * ATRAC3PLUS_FRAME_SAMPLES) as usize;
+        let samples = &pcm[first * channels..(first + (end - start) as usize) * channels];
+        let pcm = match &self.output {
+            Some(output) => output.remap(samples, channels),
+            None => samples.to_vec(),
+        };
+
+        if loops_back {
+            let (loop_start, _) = self.header.loop_points.unwrap_or_default();
+            self.decode_frame = self.header.frame_of(loop_start);
+            self.next_sample = loop_start;
+            if self.decode_loops > 0 {
+                self.decode_loops -= 1;
+            }
+        } else {
+            self.decode_frame += 1;
+            self.next_sample = end;
+        }
+        if !self.on_memory {
+            self.buffered -= self.header.block_align;
+        }
+
+        Ok(AtracFrame {
+            pcm,
+            samples: end - start,
+            finished: self.window().is_none(),
+            remain_frame: self.remain_frame(),
+        })
+    }
+
+    /// Last sample, and the loop's first and last sample or -1
+    pub fn sound_info(&self) -> (i32, i32, i32) {
+        let (loop_start, loop_end) = self
+            .header
+            .loop_points
+            .map_or((-1, -1), |(start, end)| (start as i32, end as i32));
+        (self.header.total_samples as i32 - 1, loop_start, loop_end)
+    }
+
+    /// Loops set, and whether one is still to play
+    pub fn loop_info(&self) -> (i32, u32) {
+        (self.loop_num, (self.decode_loops != 0) as u32)
+    }
+
+    /// Set how often the loop plays, -1 forever
+    pub fn set_loop_num(&mut self, loop_num: i32) -> Result<(), i32> {
+        if self.header.loop_points.is_none() {
+            return Err(CELL_ATRAC_ERROR_UNSET_LOOP_NUM);
+        }
+        self.loop_num = loop_num;
+        self.decode_loops = loop_num;
+        self.read_loops = loop_num;
+        self.wrap_reads();
+        Ok(())
+    }
+
+    /// Where to write the stream data for playing from `sample`
+    pub fn buffer_info_for_resetting(&self, sample: u32) -> Result<AtracBufferInfo, i32> {
+        if sample >= self.header.total_samples {
+            return Err(CELL_ATRAC_ERROR_ILLEGAL_SAMPLE);
+        }
+        let writable_byte = if self.on_memory { 0 } else { self.buffer_byte };
+        Ok(AtracBufferInfo {
+            write_addr: self.buffer_addr,
+            writable_byte,
+            min_write_byte: self.header.block_align.min(writable_byte),
+            read_position: self.header.frame_offset(self.header.frame_of(sample)),
+        })
+    }
+
+    /// Play from `sample`, with the game having written `write_byte` bytes
+    /// as [`AtracStream::buffer_info_for_resetting`] asked
+    pub fn reset_play_position(&mut self, sample: u32, write_byte: u32) -> Result<(), i32> {
+        let info = self.buffer_info_for_resetting(sample)?;
+        if write_byte > info.writable_byte {
+            return Err(CELL_ATRAC_ERROR_ILLEGAL_RESET_BYTE);
+        }
+        self.decode_frame = self.header.frame_of(sample);
+        self.next_sample = sample;
+        self.decode_loops = self.loop_num;
+        self.read_loops = self.loop_num;
+        if !self.on_memory {
+            self.read_pos = (info.read_position + write_byte).min(self.header.file_size());
+            self.buffered = write_byte;
+            self.write_offset = write_byte % self.buffer_byte;
+            self.wrap_reads();
+        }
+        Ok(())
+    }
+}
+
+/// cellAtrac and cellAtracMulti manager, by handle address
+pub struct AtracManager {
+    streams: HashMap<u32, AtracStream>,
+}
+
+impl AtracManager {
+    pub fn new() -> Self {
+        Self {
+            streams: HashMap::new(),
+        }
+    }
+
+    /// Set the stream of `handle` from the first `read_byte` bytes of its
+    /// file, returning the work memory its decoder needs
+    ///
+    /// `output` is the channel layout of cellAtracMulti streams; cellAtrac
+    /// only decodes mono and stereo files.
+    pub fn set_data(
+        &mut self,
+        handle: u32,
+        data: &[u8],
+        buffer_addr: u32,
+        read_byte: u32,
+        buffer_byte: u32,
+        output: Option<AtracMultiOutput>,
+    ) -> Result<u32, i32> {
+        if handle == 0 || buffer_addr == 0 {
+            return Err(CELL_ATRAC_ERROR_API_FAIL);
+        }
+        if read_byte > buffer_byte {
+            return Err(CELL_ATRAC_ERROR_READSIZE_OVER_BUFFER);
+        }
+        let header = At3Header::parse(&data[..data.len().min(read_byte as usize)])?;
+        match output {
+            Some(output) if output.channels == 0 || output.channels as usize > CELL_ATRACMULTI_MAX_CHANNELS => {
+                return Err(CELL_ATRAC_ERROR_API_FAIL);
+            }
+            None if header.channels > 2 => return Err(CELL_ATRAC_ERROR_UNKNOWN_FORMAT),
+            _ => {}
+        }
+        if read_byte < header.file_size() && buffer_byte < header.block_align {
+            return Err(CELL_ATRAC_ERROR_READSIZE_IS_TOO_SMALL);
+        }
+
+        let mut stream = AtracStream::new(header, buffer_addr, read_byte, buffer_byte);
+        stream.output = output;
+        self.streams.insert(handle, stream);
+        Ok(CELL_ATRAC_WORK_MEM_SIZE)
+    }
+
+    /// Create the decoder of `handle`, checking the thread priorities
+    pub fn create_decoder(&mut self, handle: u32, ppu_priority: u32, spu_priority: Option<u32>) -> Result<(), i32> {
+        if ppu_priority > 3071 {
+            return Err(CELL_ATRAC_ERROR_ILLEGAL_PPU_THREAD_PRIORITY);
+        }
+        if spu_priority.is_some_and(|priority| !(16..=255).contains(&priority)) {
+            return Err(CELL_ATRAC_ERROR_ILLEGAL_SPU_THREAD_PRIORITY);
+        }
+        self.stream_mut(handle)?.create_decoder()
+    }
+
+    /// Delete the decoder of `handle` along with its stream
+    pub fn delete_decoder(&mut self, handle: u32) -> Result<(), i32> {
+        self.streams.remove(&handle).map(|_| ()).ok_or(CELL_ATRAC_ERROR_UNSET_DATA)
+    }
+
+    pub fn stream(&self, handle: u32) -> Result<&AtracStream, i32> {
+        self.streams.get(&handle).ok_or(CELL_ATRAC_ERROR_UNSET_DATA)
+    }
+
+    pub fn stream_mut(&mut self, handle: u32) -> Result<&mut AtracStream, i32> {
+        self.streams.get_mut(&handle).ok_or(CELL_ATRAC_ERROR_UNSET_DATA)
+    }
+}
+
+impl Default for AtracManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn status(result: Result<(), i32>) -> i32 {
+    match result {
+        Ok(()) => 0, // CELL_OK
+        Err(e) => e,
+    }
+}
+
+// Shared by the cellAtrac and cellAtracMulti functions, with the name to log
+
+fn set_data(name: &str, handle: u32, buffer_addr: u32, read_byte: u32, buffer_byte: u32) -> i32 {
+    debug!(
+        "{}(pHandle=0x{:08X}, pucBufferAddr=0x{:08X}, uiReadByte={}, uiBufferByte={})",
+        name, handle, buffer_addr, read_byte, buffer_byte
+    );
+
+    if handle == 0 || buffer_addr == 0 {
+        return CELL_ATRAC_ERROR_API_FAIL;
+    }
+    if read_byte > buffer_byte {
+        return CELL_ATRAC_ERROR_READSIZE_OVER_BUFFER;
+    }
+
+    // Note: reading the header for AtracManager::set_data and writing the work memory size requires memory subsystem integration
+    0 // CELL_OK
+}
+
+fn create_decoder(name: &str, handle: u32, ppu_priority: u32, spu_priority: Option<u32>) -> i32 {
+    debug!("{}(pHandle=0x{:08X}, uiPpuThreadPriority={})", name, handle, ppu_priority);
+
+    status(crate::context::get_hle_context_mut().atrac.create_decoder(handle, ppu_priority, spu_priority))
+}
+
+fn delete_decoder(name: &str, handle: u32) -> i32 {
+    debug!("{}(pHandle=0x{:08X})", name, handle);
+
+    status(crate::context::get_hle_context_mut().atrac.delete_decoder(handle))
+}
+
+fn decode(name: &str, handle: u32, out_addr: u32) -> i32 {
+    if out_addr & 0xF != 0 {
+        return CELL_ATRAC_ERROR_NOT_ALIGNED_OUT_BUFFER;
+    }
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    match ctx.atrac.stream_mut(handle).and_then(|stream| stream.decode()) {
+        Ok(frame) => {
+            trace!(
+                "{}(pHandle=0x{:08X}) -> samples={}, finished={}, remain_frame={}",
+                name, handle, frame.samples, frame.finished, frame.remain_frame
+            );
+            // Note: writing the PCM, sample count, finish flag and remain frame requires memory subsystem integration
+            0 // CELL_OK
+        }
+        Err(e) => e,
+    }
+}
+
+fn get_stream_data_info(name: &str, handle: u32) -> i32 {
+    let ctx = crate::context::get_hle_context();
+    match ctx.atrac.stream(handle) {
+        Ok(stream) => {
+            let (write_addr, writable, read_pos) = stream.stream_data_info();
+            trace!(
+                "{}(pHandle=0x{:08X}) -> write=0x{:08X}, writable={}, read_position={}",
+                name, handle, write_addr, writable, read_pos
+            );
+            // Note: writing the stream data info requires memory subsystem integration
+            0 // CELL_OK
+        }
+        Err(e) => e,
+    }
+}
+
+fn add_stream_data(name: &str, handle: u32, add_byte: u32) -> i32 {
+    trace!("{}(pHandle=0x{:08X}, uiAddByte={})", name, handle, add_byte);
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(ctx.atrac.stream_mut(handle).and_then(|stream| stream.add_stream_data(add_byte)))
+}
+
+/// Log a value of the stream of `handle`, which the game gets through a pointer
+fn get_value(name: &str, handle: u32, value: impl Fn(&AtracStream) -> Result<i64, i32>) -> i32 {
+    let ctx = crate::context::get_hle_context();
+    match ctx.atrac.stream(handle).and_then(value) {
+        Ok(value) => {
+            trace!("{}(pHandle=0x{:08X}) -> {}", name, handle, value);
+            // Note: writing the value requires memory subsystem integration
+            0 // CELL_OK
+        }
+        Err(e) => e,
+    }
+}
+
+fn get_sound_info(name: &str, handle: u32) -> i32 {
+    get_value(name, handle, |stream| {
+        let (end, loop_start, loop_end) = stream.sound_info();
+        trace!("{}: end={}, loop_start={}, loop_end={}", name, end, loop_start, loop_end);
+        Ok(end as i64)
+    })
+}
+
+fn get_loop_info(name: &str, handle: u32) -> i32 {
+    get_value(name, handle, |stream| {
+        let (loop_num, loop_status) = stream.loop_info();
+        trace!("{}: loop_num={}, status={}", name, loop_num, loop_status);
+        Ok(loop_num as i64)
+    })
+}
+
+fn set_loop_num(name: &str, handle: u32, loop_num: i32) -> i32 {
+    debug!("{}(pHandle=0x{:08X}, iLoopNum={})", name, handle, loop_num);
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(ctx.atrac.stream_mut(handle).and_then(|stream| stream.set_loop_num(loop_num)))
+}
+
+fn get_buffer_info_for_resetting(name: &str, handle: u32, sample: u32) -> i32 {
+    let ctx = crate::context::get_hle_context();
+    match ctx.atrac.stream(handle).and_then(|stream| stream.buffer_info_for_resetting(sample)) {
+        Ok(info) => {
+            trace!("{}(pHandle=0x{:08X}, uiSample={}) -> {:?}", name, handle, sample, info);
+            // Note: writing the CellAtracBufferInfo requires memory subsystem integration
+            0 // CELL_OK
+        }
+        Err(e) => e,
+    }
+}
+
+fn reset_play_position(name: &str, handle: u32, sample: u32, write_byte: u32) -> i32 {
+    debug!("{}(pHandle=0x{:08X}, uiSample={}, uiWriteByte={})", name, handle, sample, write_byte);
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(ctx.atrac.stream_mut(handle).and_then(|stream| stream.reset_play_position(sample, write_byte)))
+}
+
+/// cellAtracSetDataAndGetMemSize - Set the AT3 stream and get the work memory size
+pub fn cell_atrac_set_data_and_get_mem_size(
+    handle: u32,
+    buffer_addr: u32,
+    read_byte: u32,
+    buffer_byte: u32,
+    _work_mem_byte_addr: u32,
+) -> i32 {
+    set_data("cellAtracSetDataAndGetMemSize", handle, buffer_addr, read_byte, buffer_byte)
+}
+
+/// cellAtracCreateDecoder - Create the decoder
+pub fn cell_atrac_create_decoder(handle: u32, _work_mem: u32, ppu_priority: u32, spu_priority: u32) -> i32 {
+    create_decoder("cellAtracCreateDecoder", handle, ppu_priority, Some(spu_priority))
+}
+
+/// cellAtracCreateDecoderExt - Create the decoder on the game's SPURS
+pub fn cell_atrac_create_decoder_ext(handle: u32, _work_mem: u32, ppu_priority: u32, _ext_res: u32) -> i32 {
+    create_decoder("cellAtracCreateDecoderExt", handle, ppu_priority, None)
+}
+
+/// cellAtracDeleteDecoder - Delete the decoder
+pub fn cell_atrac_delete_decoder(handle: u32) -> i32 {
+    delete_decoder("cellAtracDeleteDecoder", handle)
+}
+
+/// cellAtracDecode - Decode the next frame
+pub fn cell_atrac_decode(handle: u32, out_addr: u32, _samples_addr: u32, _finish_addr: u32, _remain_addr: u32) -> i32 {
+    decode("cellAtracDecode", handle, out_addr)
+}
+
+/// cellAtracGetStreamDataInfo - Get where to write stream data next
+pub fn cell_atrac_get_stream_data_info(handle: u32, _write_addr: u32, _writable_addr: u32, _read_pos_addr: u32) -> i32 {
+    get_stream_data_info("cellAtracGetStreamDataInfo", handle)
+}
+
+/// cellAtracAddStreamData - Report stream data written to the buffer
+pub fn cell_atrac_add_stream_data(handle: u32, add_byte: u32) -> i32 {
+    add_stream_data("cellAtracAddStreamData", handle, add_byte)
+}
+
+/// cellAtracGetRemainFrame - Get the frames left in the buffer
+pub fn cell_atrac_get_remain_frame(handle: u32, _remain_addr: u32) -> i32 {
+    get_value("cellAtracGetRemainFrame", handle, |stream| Ok(stream.remain_frame() as i64))
+}
+
+/// cellAtracGetVacantSize - Get the free bytes of the buffer
+pub fn cell_atrac_get_vacant_size(handle: u32, _size_addr: u32) -> i32 {
+    get_value("cellAtracGetVacantSize", handle, |stream| Ok(stream.vacant_size() as i64))
+}
+
+/// cellAtracGetChannel - Get the channel count
+pub fn cell_atrac_get_channel(handle: u32, _channel_addr: u32) -> i32 {
+    get_value("cellAtracGetChannel", handle, |stream| Ok(stream.channels() as i64))
+}
+
+/// cellAtracGetMaxSample - Get the most samples a frame outputs
+pub fn cell_atrac_get_max_sample(handle: u32, _max_addr: u32) -> i32 {
+    get_value("cellAtracGetMaxSample", handle, |_| Ok(ATRAC3PLUS_FRAME_SAMPLES as i64))
+}
+
+/// cellAtracGetNextSample - Get the samples the next frame outputs
+pub fn cell_atrac_get_next_sample(handle: u32, _next_addr: u32) -> i32 {
+    get_value("cellAtracGetNextSample", handle, |stream| Ok(stream.next_sample_count() as i64))
+}
+
+/// cellAtracGetSoundInfo - Get the end sample and the loop points
+pub fn cell_atrac_get_sound_info(handle: u32, _end_addr: u32, _loop_start_addr: u32, _loop_end_addr: u32) -> i32 {
+    get_sound_info("cellAtracGetSoundInfo", handle)
+}
+
+/// cellAtracGetNextDecodePosition - Get the next sample to decode
+pub fn cell_atrac_get_next_decode_position(handle: u32, _position_addr: u32) -> i32 {
+    get_value("cellAtracGetNextDecodePosition", handle, |stream| {
+        stream.next_decode_position().map(|sample| sample as i64)
+    })
+}
+
+/// cellAtracGetBitrate - Get the bitrate in kbps
+pub fn cell_atrac_get_bitrate(handle: u32, _bitrate_addr: u32) -> i32 {
+    get_value("cellAtracGetBitrate", handle, |stream| Ok(stream.header().bitrate() as i64))
+}
+
+/// cellAtracGetLoopInfo - Get the loops set and whether one is left
+pub fn cell_atrac_get_loop_info(handle: u32, _loop_num_addr: u32, _loop_status_addr: u32) -> i32 {
+    get_loop_info("cellAtracGetLoopInfo", handle)
+}
+
+/// cellAtracSetLoopNum - Set how often the loop plays
+pub fn cell_atrac_set_loop_num(handle: u32, loop_num: i32) -> i32 {
+    set_loop_num("cellAtracSetLoopNum", handle, loop_num)
+}
+
+/// cellAtracGetBufferInfoForResetting - Get the stream data to play from a sample
+pub fn cell_atrac_get_buffer_info_for_resetting(handle: u32, sample: u32, _info_addr: u32) -> i32 {
+    get_buffer_info_for_resetting("cellAtracGetBufferInfoForResetting", handle, sample)
+}
+
+/// cellAtracResetPlayPosition - Play from a sample
+pub fn cell_atrac_reset_play_position(handle: u32, sample: u32, write_byte: u32) -> i32 {
+    reset_play_position("cellAtracResetPlayPosition", handle, sample, write_byte)
+}
+
+/// cellAtracGetInternalErrorInfo - Get the decoder's internal error
+pub fn cell_atrac_get_internal_error_info(handle: u32, _result_addr: u32) -> i32 {
+    get_value("cellAtracGetInternalErrorInfo", handle, |_| Ok(0))
+}
+
+/// cellAtracMultiSetDataAndGetMemSize - Set the AT3 stream and its output channels
+pub fn cell_atrac_multi_set_data_and_get_mem_size(
+    handle: u32,
+    buffer_addr: u32,
+    read_byte: u32,
+    buffer_byte: u32,
+    output_channels: u32,
+    _track_array_addr: u32,
+    _work_mem_byte_addr: u32,
+) -> i32 {
+    if output_channels == 0 || output_channels as usize > CELL_ATRACMULTI_MAX_CHANNELS {
+        return multi_error(CELL_ATRAC_ERROR_API_FAIL);
+    }
+
+    // Note: reading the track array for AtracMultiOutput requires memory subsystem integration
+    multi_error(set_data("cellAtracMultiSetDataAndGetMemSize", handle, buffer_addr, read_byte, buffer_byte))
+}
+
+/// cellAtracMultiCreateDecoder - Create the decoder
+pub fn cell_atrac_multi_create_decoder(handle: u32, _work_mem: u32, ppu_priority: u32, spu_priority: u32) -> i32 {
+    multi_error(create_decoder("cellAtracMultiCreateDecoder", handle, ppu_priority, Some(spu_priority)))
+}
+
+/// cellAtracMultiCreateDecoderExt - Create the decoder on the game's SPURS
+pub fn cell_atrac_multi_create_decoder_ext(handle: u32, _work_mem: u32, ppu_priority: u32, _ext_res: u32) -> i32 {
+    multi_error(create_decoder("cellAtracMultiCreateDecoderExt", handle, ppu_priority, None))
+}
+
+/// cellAtracMultiDeleteDecoder - Delete the decoder
+pub fn cell_atrac_multi_delete_decoder(handle: u32) -> i32 {
+    multi_error(delete_decoder("cellAtracMultiDeleteDecoder", handle))
+}
+
+/// cellAtracMultiDecode - Decode the next frame
+pub fn cell_atrac_multi_decode(
+    handle: u32,
+    out_addr: u32,
+    _samples_addr: u32,
+    _finish_addr: u32,
+    _remain_addr: u32,
+) -> i32 {
+    multi_error(decode("cellAtracMultiDecode", handle, out_addr))
+}
+
+/// cellAtracMultiGetStreamDataInfo - Get where to write stream data next
+pub fn cell_atrac_multi_get_stream_data_info(
+    handle: u32,
+    _write_addr: u32,
+    _writable_addr: u32,
+    _read_pos_addr: u32,
+) -> i32 {
+    multi_error(get_stream_data_info("cellAtracMultiGetStreamDataInfo", handle))
+}
+
+/// cellAtracMultiAddStreamData - Report stream data written to the buffer
+pub fn cell_atrac_multi_add_stream_data(handle: u32, add_byte: u32) -> i32 {
+    multi_error(add_stream_data("cellAtracMultiAddStreamData", handle, add_byte))
+}
+
+/// cellAtracMultiGetRemainFrame - Get the frames left in the buffer
+pub fn cell_atrac_multi_get_remain_frame(handle: u32, _remain_addr: u32) -> i32 {
+    multi_error(get_value("cellAtracMultiGetRemainFrame", handle, |stream| Ok(stream.remain_frame() as i64)))
+}
+
+/// cellAtracMultiGetVacantSize - Get the free bytes of the buffer
+pub fn cell_atrac_multi_get_vacant_size(handle: u32, _size_addr: u32) -> i32 {
+    multi_error(get_value("cellAtracMultiGetVacantSize", handle, |stream| Ok(stream.vacant_size() as i64)))
+}
+
+/// cellAtracMultiGetChannel - Get the output channel count
+pub fn cell_atrac_multi_get_channel(handle: u32, _channel_addr: u32) -> i32 {
+    multi_error(get_value("cellAtracMultiGetChannel", handle, |stream| Ok(stream.channels() as i64)))
+}
+
+/// cellAtracMultiGetMaxSample - Get the most samples a frame outputs
+pub fn cell_atrac_multi_get_max_sample(handle: u32, _max_addr: u32) -> i32 {
+    multi_error(get_value("cellAtracMultiGetMaxSample", handle, |_| Ok(ATRAC3PLUS_FRAME_SAMPLES as i64)))
+}
+
+/// cellAtracMultiGetNextSample - Get the samples the next frame outputs
+pub fn cell_atrac_multi_get_next_sample(handle: u32, _next_addr: u32) -> i32 {
+    multi_error(get_value("cellAtracMultiGetNextSample", handle, |stream| Ok(stream.next_sample_count() as i64)))
+}
+
+/// cellAtracMultiGetSoundInfo - Get the end sample and the loop points
+pub fn cell_atrac_multi_get_sound_info(
+    handle: u32,
+    _end_addr: u32,
+    _loop_start_addr: u32,
+    _loop_end_addr: u32,
+) -> i32 {
+    multi_error(get_sound_info("cellAtracMultiGetSoundInfo", handle))
+}
+
+/// cellAtracMultiGetNextDecodePosition - Get the next sample to decode
+pub fn cell_atrac_multi_get_next_decode_position(handle: u32, _position_addr: u32) -> i32 {
+    multi_error(get_value("cellAtracMultiGetNextDecodePosition", handle, |stream| {
+        stream.next_decode_position().map(|sample| sample as i64)
+    }))
+}
+
+/// cellAtracMultiGetBitrate - Get the bitrate in kbps
+pub fn cell_atrac_multi_get_bitrate(handle: u32, _bitrate_addr: u32) -> i32 {
+    multi_error(get_value("cellAtracMultiGetBitrate", handle, |stream| Ok(stream.header().bitrate() as i64)))
+}
+
+/// cellAtracMultiGetTrackArray - Get the file channel each output channel plays
+pub fn cell_atrac_multi_get_track_array(handle: u32, _track_array_addr: u32) -> i32 {
+    multi_error(get_value("cellAtracMultiGetTrackArray", handle, |stream| {
+        let output = stream.output().copied().unwrap_or_else(|| AtracMultiOutput::identity(stream.channels()));
+        trace!("cellAtracMultiGetTrackArray: tracks={:?}", output.tracks);
+        Ok(output.channels as i64)
+    }))
+}
+
+/// cellAtracMultiGetLoopInfo - Get the loops set and whether one is left
+pub fn cell_atrac_multi_get_loop_info(handle: u32, _loop_num_addr: u32, _loop_status_addr: u32) -> i32 {
+    multi_error(get_loop_info("cellAtracMultiGetLoopInfo", handle))
+}
+
+/// cellAtracMultiSetLoopNum - Set how often the loop plays
+pub fn cell_atrac_multi_set_loop_num(handle: u32, loop_num: i32) -> i32 {
+    multi_error(set_loop_num("cellAtracMultiSetLoopNum", handle, loop_num))
+}
+
+/// cellAtracMultiGetBufferInfoForResetting - Get the stream data to play from a sample
+pub fn cell_atrac_multi_get_buffer_info_for_resetting(handle: u32, sample: u32, _info_addr: u32) -> i32 {
+    multi_error(get_buffer_info_for_resetting("cellAtracMultiGetBufferInfoForResetting", handle, sample))
+}
+
+/// cellAtracMultiResetPlayPosition - Play from a sample
+pub fn cell_atrac_multi_reset_play_position(handle: u32, sample: u32, write_byte: u32) -> i32 {
+    multi_error(reset_play_position("cellAtracMultiResetPlayPosition", handle, sample, write_byte))
+}
+
+/// cellAtracMultiGetInternalErrorInfo - Get the decoder's internal error
+pub fn cell_atrac_multi_get_internal_error_info(handle: u32, _result_addr: u32) -> i32 {
+    multi_error(get_value("cellAtracMultiGetInternalErrorInfo", handle, |_| Ok(0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// AT3 file of `frames` 0x130-byte frames, with optional loop points
+    fn at3_file(channels: u16, frames: u32, total_samples: u32, delay: u32, loop_points: Option<(u32, u32)>) -> Vec<u8> {
+        let mut fmt = Vec::new();
+        fmt.extend_from_slice(&WAVE_FORMAT_EXTENSIBLE.to_le_bytes());
+        fmt.extend_from_slice(&channels.to_le_bytes());
+        fmt.extend_from_slice(&48000u32.to_le_bytes());
+        fmt.extend_from_slice(&(0x130u32 * 48000 / 2048).to_le_bytes());
+        fmt.extend_from_slice(&0x130u16.to_le_bytes());
+        fmt.extend_from_slice(&0u16.to_le_bytes());
+        fmt.extend_from_slice(&22u16.to_le_bytes());
+        fmt.extend_from_slice(&[0; 6]);
+        fmt.extend_from_slice(&ATRAC3PLUS_GUID);
+        fmt.extend_from_slice(&[0; 12]);
+
+        let mut chunks = vec![(*b"fmt ", fmt)];
+        let mut fact = total_samples.to_le_bytes().to_vec();
+        fact.extend_from_slice(&delay.to_le_bytes());
+        chunks.push((*b"fact", fact));
+        if let Some((start, end)) = loop_points {
+            let mut smpl = vec![0; 36 + 24];
+            smpl[28..32].copy_from_slice(&1u32.to_le_bytes());
+            smpl[44..48].copy_from_slice(&start.to_le_bytes());
+            smpl[48..52].copy_from_slice(&end.to_le_bytes());
+            chunks.push((*b"smpl", smpl));
+        }
+        chunks.push((*b"data", vec![0; (frames * 0x130) as usize]));
+
+        let mut file = b"RIFF\0\0\0\0WAVE".to_vec();
+        for (id, body) in chunks {
+            file.extend_from_slice(&id);
+            file.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            file.extend_from_slice(&body);
+        }
+        let riff_size = file.len() as u32 - 8;
+        file[4..8].copy_from_slice(&riff_size.to_le_bytes());
+        file
+    }
+
+    #[test]
+    fn test_at3_header() {
+        let file = at3_file(2, 10, 19000, 184, Some((4096, 12287)));
+        let header = At3Header::parse(&file).unwrap();
+        assert_eq!(header.channels, 2);
+        assert_eq!(header.block_align, 0x130);
+        assert_eq!(header.loop_points, Some((4096, 12287)));
+        assert_eq!(header.file_size() as usize, file.len());
+        assert_eq!(header.bitrate(), 57);
+
+        // Only the start of the file was read
+        assert_eq!(At3Header::parse(&file[..60]), Err(CELL_ATRAC_ERROR_READSIZE_IS_TOO_SMALL));
+        let mut wav = file.clone();
+        wav[20] = 1; // WAVE_FORMAT_PCM
+        assert_eq!(At3Header::parse(&wav), Err(CELL_ATRAC_ERROR_UNKNOWN_FORMAT));
+    }
+
+    #[test]
+    fn test_atrac_looping_decode() {
+        let mut manager = AtracManager::new();
+        let file = at3_file(2, 10, 19000, 0, Some((4096, 12287)));
+        let size = file.len() as u32;
+        manager.set_data(0x1000, &file, 0x10_0000, size, size, None).unwrap();
+        assert_eq!(manager.stream_mut(0x1000).unwrap().decode(), Err(CELL_ATRAC_ERROR_NO_DECODER));
+        manager.create_decoder(0x1000, 1000, Some(100)).unwrap();
+        assert_eq!(manager.create_decoder(0x1000, 1000, Some(100)), Err(CELL_ATRAC_ERROR_DECODER_WAS_CREATED));
+
+        let stream = manager.stream_mut(0x1000).unwrap();
+        assert_eq!(stream.sound_info(), (18999, 4096, 12287));
+        stream.set_loop_num(1).unwrap();
+        let mut samples = Vec::new();
+        loop {
+            let frame = stream.decode().unwrap();
+            assert_eq!(frame.pcm.len() as u32, frame.samples * 2);
+            assert_eq!(frame.remain_frame, CELL_ATRAC_ALLDATA_IS_ON_MEMORY);
+            samples.push(frame.samples);
+            if frame.finished {
+                break;
+            }
+        }
+        // Frames 0-5, back to frame 2 once, then on to the last sample
+        let mut expected = vec![2048; 13];
+        expected.push(568);
+        assert_eq!(samples, expected);
+        assert_eq!(stream.decode(), Err(CELL_ATRAC_ERROR_ALLDATA_WAS_DECODED));
+        assert_eq!(stream.loop_info(), (1, 0));
+
+        stream.reset_play_position(10000, 0).unwrap();
+        assert_eq!(stream.next_decode_position(), Ok(10000));
+        assert_eq!(stream.next_sample_count(), 240);
+    }
+
+    #[test]
+    fn test_atrac_streaming() {
+        let mut manager = AtracManager::new();
+        let file = at3_file(1, 8, 8 * 2048, 0, Some((0, 4095)));
+        let header = At3Header::parse(&file).unwrap();
+        let buffer = header.data_offset + 2 * 0x130;
+        manager.set_data(0x2000, &file, 0x20_0000, buffer, buffer, None).unwrap();
+        manager.create_decoder(0x2000, 1000, None).unwrap();
+        let stream = manager.stream_mut(0x2000).unwrap();
+        stream.set_loop_num(1).unwrap();
+        assert_eq!(stream.remain_frame(), 2);
+        assert_eq!(stream.vacant_size(), header.data_offset);
+
+        stream.decode().unwrap();
+        stream.decode().unwrap();
+        assert_eq!(stream.decode(), Err(CELL_ATRAC_ERROR_NODATA_IN_BUFFER));
+
+        // The reads went up to the loop end, so they start over at the loop start
+        let (_, writable, read_pos) = stream.stream_data_info();
+        assert_eq!(read_pos, header.data_offset);
+        assert_eq!(stream.add_stream_data(writable + 1), Err(CELL_ATRAC_ERROR_ADD_DATA_IS_TOO_BIG));
+        stream.add_stream_data(writable).unwrap();
+        stream.decode().unwrap();
+        assert_eq!(stream.next_decode_position(), Ok(2048));
+        assert_eq!(stream.stream_data_info().2, header.data_offset + writable);
+    }
+
+    #[test]
+    fn test_atrac_multi_output() {
+        let mut manager = AtracManager::new();
+        let file = at3_file(6, 2, 4096, 0, None);
+        let size = file.len() as u32;
+        assert_eq!(manager.set_data(0x3000, &file, 0x30_0000, size, size, None), Err(CELL_ATRAC_ERROR_UNKNOWN_FORMAT));
+
+        let mut output = AtracMultiOutput::identity(2);
+        output.tracks[1] = 4;
+        manager.set_data(0x3000, &file, 0x30_0000, size, size, Some(output)).unwrap();
+        manager.create_decoder(0x3000, 1000, Some(100)).unwrap();
+        let frame = manager.stream_mut(0x3000).unwrap().decode().unwrap();
+        assert_eq!(frame.pcm.len(), 2048 * 2);
+
+        assert_eq!(manager.delete_decoder(0x3000), Ok(()));
+        assert_eq!(multi_error(CELL_ATRAC_ERROR_UNSET_DATA), 0x80610B22u32 as i32);
+        assert_eq!(cell_atrac_multi_set_data_and_get_mem_size(0x3000, 0x30_0000, 16, 32, 9, 0, 0), 0x80610B01u32 as i32);
+    }
+}
@@ -3,6 +3,8 @@
 //! This module provides HLE implementations for PS3 audio output.
 //! It bridges to the oc-audio subsystem for actual audio playback.
 
+use oc_core::savestate::{invalid, StateReader, StateWriter};
+use std::io;
 use tracing::{debug, trace};
 
 /// Maximum number of audio ports
@@ -75,6 +77,8 @@ pub struct AudioManager {
     audio_backend: Option<()>,
     /// Master volume (0.0 to 1.0)
     master_volume: f32,
+    /// Guest system time the last block was mixed at, in microseconds
+    last_block_time: u64,
 }
 
 impl AudioManager {
@@ -85,6 +89,7 @@ impl AudioManager {
             initialized: false,
             audio_backend: None,
             master_volume: 1.0,
+            last_block_time: 0,
         }
     }
 
@@ -151,6 +156,7 @@ impl AudioManager {
         self.ports[port_num].num_channels = num_channels;
         self.ports[port_num].num_blocks = num_blocks;
         self.ports[port_num].volume = level;
+        self.ports[port_num].tag = 0;
 
         // TODO: Allocate buffer through oc-audio subsystem
         // TODO: Store buffer address
@@ -356,6 +362,68 @@ impl AudioManager {
     pub fn is_backend_connected(&self) -> bool {
         self.audio_backend.is_some()
     }
+
+    /// Move the started ports on by the block mixed at `time_us` of guest system time
+    pub fn advance_block(&mut self, time_us: u64) {
+        for port in self.ports.iter_mut().filter(|port| port.state == AudioPortState::Started) {
+            port.tag += 1;
+        }
+        self.last_block_time = time_us;
+    }
+
+    /// Tag of the last block mixed from an open port
+    pub fn port_tag(&self, port_num: u32) -> Option<u64> {
+        self.ports
+            .get(port_num as usize)
+            .filter(|port| port.state != AudioPortState::Closed)
+            .map(|port| port.tag)
+    }
+
+    /// Guest system time the last block was mixed at, in microseconds
+    pub fn last_block_time(&self) -> u64 {
+        self.last_block_time
+    }
+    /// Save the ports for a savestate
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.bool(self.initialized);
+        w.f32(self.master_volume);
+        for port in &self.ports {
+            w.u8(match port.state {
+                AudioPortState::Closed => 0,
+                AudioPortState::Open => 1,
+                AudioPortState::Started => 2,
+            });
+            w.u32(port.num_channels);
+            w.u32(port.num_blocks);
+            w.u64(port.tag);
+            w.u32(port.buffer_addr);
+            w.f32(port.volume);
+        }
+        w.u64(self.last_block_time);
+    }
+
+    /// Restore state written by [`AudioManager::save_state`]
+    pub fn load_state(&mut self, r: &mut StateReader) -> io::Result<()> {
+        self.initialized = r.bool()?;
+        self.master_volume = r.f32()?;
+        for port in &mut self.ports {
+            *port = AudioPort {
+                state: match r.u8()? {
+                    0 => AudioPortState::Closed,
+                    1 => AudioPortState::Open,
+                    2 => AudioPortState::Started,
+                    state => return Err(invalid(&format!("invalid audio port state {}", state))),
+                },
+                num_channels: r.u32()?,
+                num_blocks: r.u32()?,
+                tag: r.u64()?,
+                buffer_addr: r.u32()?,
+                volume: r.f32()?,
+            };
+        }
+        self.last_block_time = r.u64()?;
+        Ok(())
+    }
 }
 
 impl Default for AudioManager {
@@ -545,6 +613,33 @@ mod tests {
         manager.quit();
     }
 
+    #[test]
+    fn test_audio_block_clock() {
+        let mut manager = AudioManager::new();
+        manager.init();
+        let started = manager.port_open(2, CELL_AUDIO_BLOCK_8, 0, 1.0).unwrap();
+        let open = manager.port_open(2, CELL_AUDIO_BLOCK_8, 0, 1.0).unwrap();
+        manager.port_start(started);
+
+        manager.advance_block(5_333);
+        manager.advance_block(10_666);
+        // Only started ports move on
+        assert_eq!(manager.port_tag(started), Some(2));
+        assert_eq!(manager.port_tag(open), Some(0));
+        assert_eq!(manager.last_block_time(), 10_666);
+
+        let mut w = StateWriter::new();
+        manager.save_state(&mut w);
+        let data = w.into_bytes();
+        let mut restored = AudioManager::new();
+        restored.load_state(&mut StateReader::new(&data)).unwrap();
+        assert_eq!(restored.port_tag(started), Some(2));
+        assert_eq!(restored.last_block_time(), 10_666);
+
+        manager.port_close(open);
+        assert_eq!(manager.port_tag(open), None);
+    }
+
     #[test]
     fn test_audio_constants() {
         assert_eq!(CELL_AUDIO_PORT_MAX, 8);
@@ -0,0 +1,690 @@
+//! cellBGDL/cellDownloader HLE - Background Downloads
+//!
+//! This module provides HLE implementations for games that fetch extra
+//! content in the background and check on the downloads later, possibly
+//! after a reboot. Nothing is actually downloaded: a task finishes as soon
+//! as it starts, completing or failing depending on the user's setting, so
+//! games waiting on it move on, and the game's callback hears of each state
+//! change. Tasks are kept in `/dev_hdd0/bgdl` so their state survives between
+//! sessions like on the console.
+
+use crate::context::{guest_memory, read_guest_string};
+use crate::guest_call::GuestCallQueue;
+use oc_memory::BeValue;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tracing::{debug, trace, warn};
+
+/// Error codes
+pub const CELL_BGDL_UTIL_ERROR_BUSY: i32 = 0x8002CE01u32 as i32;
+pub const CELL_BGDL_UTIL_ERROR_INTERNAL: i32 = 0x8002CE02u32 as i32;
+pub const CELL_BGDL_UTIL_ERROR_PARAM: i32 = 0x8002CE03u32 as i32;
+pub const CELL_BGDL_UTIL_ERROR_ACCESS_ERROR: i32 = 0x8002CE04u32 as i32;
+pub const CELL_BGDL_UTIL_ERROR_INITIALIZE: i32 = 0x8002CE05u32 as i32;
+
+/// Download states
+pub const CELL_BGDL_STATE_ERROR: i32 = 0;
+pub const CELL_BGDL_STATE_PAUSE: i32 = 1;
+pub const CELL_BGDL_STATE_READY: i32 = 2;
+pub const CELL_BGDL_STATE_RUNNING: i32 = 3;
+pub const CELL_BGDL_STATE_COMPLETE: i32 = 4;
+
+/// Download modes
+pub const CELL_BGDL_MODE_AUTO: i32 = 0;
+pub const CELL_BGDL_MODE_ALWAYS_ALLOWED: i32 = 1;
+
+/// Length of a content ID
+const CONTENT_ID_LEN: u32 = 36;
+
+/// Longest URL or path read from guest memory
+const MAX_TASK_STRING: u32 = 1024;
+
+/// Download state handed to the game (CellBGDLInfo)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, BeValue)]
+pub struct CellBgdlInfo {
+    pub received_size: u64,
+    pub content_size: u64,
+    pub state: i32,
+    pub reserved: u32,
+}
+
+/// Background download task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadTask {
+    pub id: u32,
+    /// Content ID of the downloaded package (empty if unknown)
+    pub content_id: String,
+    pub url: String,
+    /// Guest path the download is written to
+    pub path: String,
+    pub state: i32,
+    pub received_size: u64,
+    pub content_size: u64,
+}
+
+impl DownloadTask {
+    /// State of the task as handed to the game
+    pub fn info(&self) -> CellBgdlInfo {
+        CellBgdlInfo {
+            received_size: self.received_size,
+            content_size: self.content_size,
+            state: self.state,
+            reserved: 0,
+        }
+    }
+}
+
+/// Task state change waiting to be passed to the game's callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadEvent {
+    pub task_id: u32,
+    pub state: i32,
+    pub result: i32,
+}
+
+/// What is kept in the task file
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct TaskFile {
+    mode: i32,
+    next_task_id: u32,
+    tasks: Vec<DownloadTask>,
+}
+
+/// Background download manager
+pub struct BgdlManager {
+    /// Initialization flag of cellDownloader
+    initialized: bool,
+    /// Event callback address
+    callback: u32,
+    /// Callback user data
+    userdata: u32,
+    /// Whether started tasks complete rather than fail
+    complete_downloads: bool,
+    /// Host file the tasks are kept in
+    task_file: Option<PathBuf>,
+    /// Download mode
+    mode: i32,
+    /// Tasks by ID
+    tasks: BTreeMap<u32, DownloadTask>,
+    /// Next task ID to hand out
+    next_task_id: u32,
+    /// Events waiting to be passed to the callback
+    pending_events: Vec<DownloadEvent>,
+}
+
+impl BgdlManager {
+    /// Create a new background download manager
+    pub fn new() -> Self {
+        Self {
+            initialized: false,
+            callback: 0,
+            userdata: 0,
+            complete_downloads: false,
+            task_file: None,
+            mode: CELL_BGDL_MODE_AUTO,
+            tasks: BTreeMap::new(),
+            next_task_id: 1,
+            pending_events: Vec::new(),
+        }
+    }
+
+    /// Set the host directory backing `/dev_hdd0`, loading the tasks kept there
+    pub fn set_hdd0_root(&mut self, root: PathBuf) {
+        let path = root.join("bgdl").join("tasks.json");
+        if self.task_file.as_ref() == Some(&path) {
+            return;
+        }
+
+        let stored = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable background download tasks {}: {}", path.display(), e);
+                TaskFile::default()
+            }),
+            Err(_) => TaskFile::default(),
+        };
+        debug!("BgdlManager: {} tasks kept in {}", stored.tasks.len(), path.display());
+
+        self.mode = stored.mode;
+        self.tasks = stored.tasks.into_iter().map(|task| (task.id, task)).collect();
+        // Tasks cut off mid-download by a shutdown failed
+        for task in self.tasks.values_mut().filter(|task| task.state == CELL_BGDL_STATE_RUNNING) {
+            task.state = CELL_BGDL_STATE_ERROR;
+        }
+        let last_id = self.tasks.keys().next_back().copied().unwrap_or(0);
+        self.next_task_id = stored.next_task_id.max(last_id + 1);
+        self.task_file = Some(path);
+    }
+
+    /// Set whether started tasks complete rather than fail
+    pub fn set_complete_downloads(&mut self, complete: bool) {
+        self.complete_downloads = complete;
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.task_file else {
+            return;
+        };
+        let stored = TaskFile {
+            mode: self.mode,
+            next_task_id: self.next_task_id,
+            tasks: self.tasks.values().cloned().collect(),
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(path, serde_json::to_string_pretty(&stored).unwrap_or_default()));
+        if let Err(e) = result {
+            warn!("Failed to save background download tasks {}: {}", path.display(), e);
+        }
+    }
+
+    /// Initialize the downloader
+    pub fn init(&mut self, callback: u32, userdata: u32) -> i32 {
+        if callback == 0 {
+            return CELL_BGDL_UTIL_ERROR_PARAM;
+        }
+        if self.initialized {
+            return CELL_BGDL_UTIL_ERROR_BUSY;
+        }
+
+        debug!("BgdlManager::init: callback=0x{:08X}", callback);
+
+        self.initialized = true;
+        self.callback = callback;
+        self.userdata = userdata;
+        0 // CELL_OK
+    }
+
+    /// Shut the downloader down, keeping its tasks
+    pub fn finalize(&mut self) -> i32 {
+        if !self.initialized {
+            return CELL_BGDL_UTIL_ERROR_INITIALIZE;
+        }
+
+        debug!("BgdlManager::finalize");
+
+        self.initialized = false;
+        0 // CELL_OK
+    }
+
+    /// Add a task, ready to be started
+    pub fn create_task(&mut self, content_id: &str, url: &str, path: &str, content_size: u64) -> Result<u32, i32> {
+        if !self.initialized {
+            return Err(CELL_BGDL_UTIL_ERROR_INITIALIZE);
+        }
+
+        let id = self.next_task_id;
+        self.next_task_id += 1;
+        debug!("BgdlManager::create_task: id={}, url={:?}", id, url);
+
+        self.tasks.insert(
+            id,
+            DownloadTask {
+                id,
+                content_id: content_id.to_string(),
+                url: url.to_string(),
+                path: path.to_string(),
+                state: CELL_BGDL_STATE_READY,
+                received_size: 0,
+                content_size,
+            },
+        );
+        self.save();
+        Ok(id)
+    }
+
+    /// Start a ready or paused task, which finishes at once
+    pub fn start_task(&mut self, id: u32) -> i32 {
+        if !self.initialized {
+            return CELL_BGDL_UTIL_ERROR_INITIALIZE;
+        }
+        let complete = self.complete_downloads;
+        let Some(task) = self.tasks.get_mut(&id) else {
+            return CELL_BGDL_UTIL_ERROR_PARAM;
+        };
+        if !matches!(task.state, CELL_BGDL_STATE_READY | CELL_BGDL_STATE_PAUSE | CELL_BGDL_STATE_ERROR) {
+            return CELL_BGDL_UTIL_ERROR_BUSY;
+        }
+
+        let (state, result) = if complete {
+            task.received_size = task.content_size;
+            (CELL_BGDL_STATE_COMPLETE, 0)
+        } else {
+            (CELL_BGDL_STATE_ERROR, CELL_BGDL_UTIL_ERROR_ACCESS_ERROR)
+        };
+        task.state = state;
+        debug!("BgdlManager::start_task: id={} -> state {}", id, state);
+
+        self.queue_event(id, CELL_BGDL_STATE_RUNNING, 0);
+        self.queue_event(id, state, result);
+        self.save();
+        0 // CELL_OK
+    }
+
+    /// Pause a task that has not finished
+    pub fn cancel_task(&mut self, id: u32) -> i32 {
+        if !self.initialized {
+            return CELL_BGDL_UTIL_ERROR_INITIALIZE;
+        }
+        let Some(task) = self.tasks.get_mut(&id) else {
+            return CELL_BGDL_UTIL_ERROR_PARAM;
+        };
+        if task.state == CELL_BGDL_STATE_COMPLETE {
+            return CELL_BGDL_UTIL_ERROR_BUSY;
+        }
+
+        task.state = CELL_BGDL_STATE_PAUSE;
+        self.queue_event(id, CELL_BGDL_STATE_PAUSE, 0);
+        self.save();
+        0 // CELL_OK
+    }
+
+    /// Remove a task
+    pub fn delete_task(&mut self, id: u32) -> i32 {
+        if !self.initialized {
+            return CELL_BGDL_UTIL_ERROR_INITIALIZE;
+        }
+        if self.tasks.remove(&id).is_none() {
+            return CELL_BGDL_UTIL_ERROR_PARAM;
+        }
+        trace!("BgdlManager::delete_task: id={}", id);
+
+        self.save();
+        0 // CELL_OK
+    }
+
+    /// Get a task
+    pub fn task(&self, id: u32) -> Option<&DownloadTask> {
+        self.tasks.get(&id)
+    }
+
+    /// Up to `max` tasks downloading `content_id`, or any content if empty
+    pub fn infos(&self, content_id: &str, max: usize) -> Vec<&DownloadTask> {
+        self.tasks
+            .values()
+            .filter(|task| content_id.is_empty() || task.content_id == content_id)
+            .take(max)
+            .collect()
+    }
+
+    /// Set the download mode
+    pub fn set_mode(&mut self, mode: i32) -> i32 {
+        if !(CELL_BGDL_MODE_AUTO..=CELL_BGDL_MODE_ALWAYS_ALLOWED).contains(&mode) {
+            return CELL_BGDL_UTIL_ERROR_PARAM;
+        }
+        self.mode = mode;
+        self.save();
+        0 // CELL_OK
+    }
+
+    /// Get the download mode
+    pub fn mode(&self) -> i32 {
+        self.mode
+    }
+
+    fn queue_event(&mut self, task_id: u32, state: i32, result: i32) {
+        trace!("BgdlManager::queue_event: task_id={}, state={}, result=0x{:X}", task_id, state, result);
+        self.pending_events.push(DownloadEvent { task_id, state, result });
+    }
+
+    /// Take the events waiting to be passed to the callback
+    pub fn take_events(&mut self) -> Vec<DownloadEvent> {
+        std::mem::take(&mut self.pending_events)
+    }
+
+    /// Hand the waiting events to the game's callback on `calls`
+    ///
+    /// The callback gets the task ID, its new state, the result and the user data.
+    pub fn queue_callbacks(&mut self, calls: &mut GuestCallQueue) {
+        for event in self.take_events() {
+            calls.push(
+                self.callback,
+                [event.task_id as u64, event.state as i64 as u64, event.result as i64 as u64, self.userdata as u64],
+            );
+        }
+    }
+
+    /// Event callback address and user data
+    pub fn callback(&self) -> (u32, u32) {
+        (self.callback, self.userdata)
+    }
+
+    /// Check if the downloader is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+impl Default for BgdlManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Run `f` on the download manager, handing the events it queues to the game's callback
+fn with_bgdl(f: impl FnOnce(&mut BgdlManager) -> i32) -> i32 {
+    let ctx = &mut *crate::context::get_hle_context_mut();
+    let ret = f(&mut ctx.bgdl);
+    ctx.bgdl.queue_callbacks(&mut ctx.guest_calls);
+    ret
+}
+
+/// Write a value the game asked for
+fn write_output<T: BeValue>(addr: u32, value: T) -> i32 {
+    match guest_memory().map(|memory| memory.write_be(addr, value)) {
+        Some(Ok(())) => 0, // CELL_OK
+        _ => CELL_BGDL_UTIL_ERROR_INTERNAL,
+    }
+}
+
+/// cellBGDLGetInfo - Get the state of the background downloads of a content
+///
+/// # Arguments
+/// * `content_id_addr` - Address of the content ID
+/// * `info_addr` - Address of the CellBGDLInfo array
+/// * `num` - Number of entries in the array
+///
+/// # Returns
+/// * Number of downloads found
+pub fn cell_bgdl_get_info(content_id_addr: u32, info_addr: u32, num: i32) -> i32 {
+    if content_id_addr == 0 || num < 0 || (num > 0 && info_addr == 0) {
+        return CELL_BGDL_UTIL_ERROR_PARAM;
+    }
+
+    let Some(content_id) = read_guest_string(content_id_addr, CONTENT_ID_LEN) else {
+        return CELL_BGDL_UTIL_ERROR_PARAM;
+    };
+
+    let ctx = crate::context::get_hle_context();
+    let infos = ctx.bgdl.infos(&content_id, num as usize);
+    trace!("cellBGDLGetInfo(content_id={}, num={}) -> {}", content_id, num, infos.len());
+
+    let size = std::mem::size_of::<CellBgdlInfo>() as u32;
+    for (i, task) in infos.iter().enumerate() {
+        let ret = write_output(info_addr + i as u32 * size, task.info());
+        if ret != 0 {
+            return ret;
+        }
+    }
+    infos.len() as i32
+}
+
+/// cellBGDLGetInfo2 - Get the state of the background downloads of a content
+///
+/// # Arguments
+/// * `content_id_addr` - Address of the content ID
+/// * `info_addr` - Address of the CellBGDLInfo array
+/// * `num` - Number of entries in the array
+///
+/// # Returns
+/// * Number of downloads found
+pub fn cell_bgdl_get_info2(content_id_addr: u32, info_addr: u32, num: i32) -> i32 {
+    cell_bgdl_get_info(content_id_addr, info_addr, num)
+}
+
+/// cellBGDLSetMode - Set when background downloads may run
+///
+/// # Arguments
+/// * `mode` - Download mode
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_bgdl_set_mode(mode: i32) -> i32 {
+    debug!("cellBGDLSetMode(mode={})", mode);
+
+    crate::context::get_hle_context_mut().bgdl.set_mode(mode)
+}
+
+/// cellBGDLGetMode - Get when background downloads may run
+///
+/// # Arguments
+/// * `mode_addr` - Address to write the mode
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_bgdl_get_mode(mode_addr: u32) -> i32 {
+    if mode_addr == 0 {
+        return CELL_BGDL_UTIL_ERROR_PARAM;
+    }
+    let mode = crate::context::get_hle_context().bgdl.mode();
+    trace!("cellBGDLGetMode() -> {}", mode);
+
+    write_output(mode_addr, mode)
+}
+
+/// cellDownloaderInitialize - Initialize the downloader
+///
+/// # Arguments
+/// * `container` - Memory container for the downloader
+/// * `func` - Event callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_downloader_initialize(container: u32, func: u32, userdata: u32) -> i32 {
+    debug!("cellDownloaderInitialize(container={})", container);
+
+    crate::context::get_hle_context_mut().bgdl.init(func, userdata)
+}
+
+/// cellDownloaderFinalize - Shut the downloader down
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_downloader_finalize() -> i32 {
+    debug!("cellDownloaderFinalize()");
+
+    crate::context::get_hle_context_mut().bgdl.finalize()
+}
+
+/// cellDownloaderCreateTask - Add a download task
+///
+/// # Arguments
+/// * `url_addr` - Address of the URL to download
+/// * `path_addr` - Address of the path to write the download to
+/// * `task_id_addr` - Address to write the task ID
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_downloader_create_task(url_addr: u32, path_addr: u32, task_id_addr: u32) -> i32 {
+    debug!("cellDownloaderCreateTask(url_addr=0x{:08X}, path_addr=0x{:08X})", url_addr, path_addr);
+
+    if task_id_addr == 0 {
+        return CELL_BGDL_UTIL_ERROR_PARAM;
+    }
+    let (Some(url), Some(path)) =
+        (read_guest_string(url_addr, MAX_TASK_STRING), read_guest_string(path_addr, MAX_TASK_STRING))
+    else {
+        return CELL_BGDL_UTIL_ERROR_PARAM;
+    };
+    match crate::context::get_hle_context_mut().bgdl.create_task("", &url, &path, 0) {
+        Ok(task_id) => write_output(task_id_addr, task_id),
+        Err(e) => e,
+    }
+}
+
+/// cellDownloaderStartTask - Start a download task
+///
+/// # Arguments
+/// * `task_id` - Task ID
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_downloader_start_task(task_id: u32) -> i32 {
+    debug!("cellDownloaderStartTask(task_id={})", task_id);
+
+    with_bgdl(|bgdl| bgdl.start_task(task_id))
+}
+
+/// cellDownloaderCancelTask - Pause a download task
+///
+/// # Arguments
+/// * `task_id` - Task ID
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_downloader_cancel_task(task_id: u32) -> i32 {
+    debug!("cellDownloaderCancelTask(task_id={})", task_id);
+
+    with_bgdl(|bgdl| bgdl.cancel_task(task_id))
+}
+
+/// cellDownloaderDeleteTask - Remove a download task
+///
+/// # Arguments
+/// * `task_id` - Task ID
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_downloader_delete_task(task_id: u32) -> i32 {
+    debug!("cellDownloaderDeleteTask(task_id={})", task_id);
+
+    crate::context::get_hle_context_mut().bgdl.delete_task(task_id)
+}
+
+/// cellDownloaderGetTaskInfo - Get the state of a download task
+///
+/// # Arguments
+/// * `task_id` - Task ID
+/// * `info_addr` - Address of CellBGDLInfo
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_downloader_get_task_info(task_id: u32, info_addr: u32) -> i32 {
+    if info_addr == 0 {
+        return CELL_BGDL_UTIL_ERROR_PARAM;
+    }
+    let ctx = crate::context::get_hle_context();
+    let Some(task) = ctx.bgdl.task(task_id) else {
+        return CELL_BGDL_UTIL_ERROR_PARAM;
+    };
+    trace!("cellDownloaderGetTaskInfo(task_id={}) -> state {}", task_id, task.state);
+
+    write_output(info_addr, task.info())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hdd0(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("oc_hle_bgdl_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        root
+    }
+
+    #[test]
+    fn test_bgdl_tasks() {
+        let mut manager = BgdlManager::new();
+
+        assert_eq!(manager.create_task("", "", "", 0), Err(CELL_BGDL_UTIL_ERROR_INITIALIZE));
+        assert_eq!(manager.init(0, 0), CELL_BGDL_UTIL_ERROR_PARAM);
+        assert_eq!(manager.init(0x1000, 0x2000), 0);
+        assert_eq!(manager.init(0x1000, 0x2000), CELL_BGDL_UTIL_ERROR_BUSY);
+
+        // Downloads fail unless set to complete
+        let id = manager.create_task("UP0000-TEST00000_00-DLC", "http://example.com/a.pkg", "/dev_hdd0/a", 100).unwrap();
+        assert_eq!(manager.task(id).unwrap().state, CELL_BGDL_STATE_READY);
+        assert_eq!(manager.start_task(id), 0);
+        assert_eq!(manager.task(id).unwrap().state, CELL_BGDL_STATE_ERROR);
+        assert_eq!(
+            manager.take_events(),
+            [
+                DownloadEvent { task_id: id, state: CELL_BGDL_STATE_RUNNING, result: 0 },
+                DownloadEvent { task_id: id, state: CELL_BGDL_STATE_ERROR, result: CELL_BGDL_UTIL_ERROR_ACCESS_ERROR },
+            ]
+        );
+
+        // Failed tasks can be retried
+        manager.set_complete_downloads(true);
+        assert_eq!(manager.start_task(id), 0);
+        let task = manager.task(id).unwrap();
+        assert_eq!((task.state, task.received_size), (CELL_BGDL_STATE_COMPLETE, 100));
+        assert_eq!(manager.start_task(id), CELL_BGDL_UTIL_ERROR_BUSY);
+        assert_eq!(manager.cancel_task(id), CELL_BGDL_UTIL_ERROR_BUSY);
+
+        let other = manager.create_task("OTHER", "", "", 0).unwrap();
+        assert_eq!(manager.cancel_task(other), 0);
+        assert_eq!(manager.task(other).unwrap().state, CELL_BGDL_STATE_PAUSE);
+        assert_eq!(manager.infos("OTHER", 10).len(), 1);
+        assert_eq!(manager.infos("", 10).len(), 2);
+        assert_eq!(manager.infos("", 1).len(), 1);
+
+        assert_eq!(manager.delete_task(other), 0);
+        assert_eq!(manager.delete_task(other), CELL_BGDL_UTIL_ERROR_PARAM);
+        assert_eq!(manager.start_task(99), CELL_BGDL_UTIL_ERROR_PARAM);
+        assert_eq!(manager.finalize(), 0);
+        assert_eq!(manager.finalize(), CELL_BGDL_UTIL_ERROR_INITIALIZE);
+    }
+
+    #[test]
+    fn test_bgdl_persistence() {
+        let root = hdd0("persist");
+        let mut manager = BgdlManager::new();
+        manager.set_hdd0_root(root.clone());
+        manager.set_complete_downloads(true);
+        manager.init(0x1000, 0);
+        let done = manager.create_task("DONE", "", "", 10).unwrap();
+        manager.start_task(done);
+        let ready = manager.create_task("READY", "", "", 10).unwrap();
+        assert_eq!(manager.set_mode(CELL_BGDL_MODE_ALWAYS_ALLOWED), 0);
+        assert_eq!(manager.set_mode(5), CELL_BGDL_UTIL_ERROR_PARAM);
+
+        // Another session picks the tasks back up
+        let mut manager = BgdlManager::new();
+        manager.set_hdd0_root(root.clone());
+        assert_eq!(manager.mode(), CELL_BGDL_MODE_ALWAYS_ALLOWED);
+        assert_eq!(manager.task(done).unwrap().state, CELL_BGDL_STATE_COMPLETE);
+        assert_eq!(manager.task(ready).unwrap().state, CELL_BGDL_STATE_READY);
+        manager.init(0x1000, 0);
+        let id = manager.create_task("NEW", "", "", 0).unwrap();
+        assert!(id > ready);
+
+        // A corrupt task file starts over
+        std::fs::write(root.join("bgdl/tasks.json"), "{").unwrap();
+        let mut manager = BgdlManager::new();
+        manager.set_hdd0_root(root.clone());
+        assert!(manager.infos("", 10).is_empty());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_bgdl_guest_memory() {
+        let _guard = crate::context::test_guard();
+        crate::context::reset_hle_context();
+        let memory = crate::context::test_guest_memory();
+        let addr = memory.allocate(0x1000, 0x10, oc_memory::PageFlags::RW).unwrap();
+        let (url_addr, path_addr, id_addr, info_addr) = (addr, addr + 0x100, addr + 0x200, addr + 0x210);
+        memory.write_bytes(url_addr, b"http://example.com/a.pkg\0").unwrap();
+        memory.write_bytes(path_addr, b"/dev_hdd0/a\0").unwrap();
+        crate::context::get_hle_context_mut().bgdl.set_complete_downloads(true);
+
+        assert_eq!(cell_downloader_initialize(0, 0x1000, 0x2000), 0);
+        assert_eq!(cell_downloader_create_task(url_addr, path_addr, id_addr), 0);
+        let task_id = memory.read_be32(id_addr).unwrap();
+        let task = crate::context::get_hle_context().bgdl.task(task_id).cloned().unwrap();
+        assert_eq!((task.url.as_str(), task.path.as_str()), ("http://example.com/a.pkg", "/dev_hdd0/a"));
+
+        assert_eq!(cell_downloader_start_task(task_id), 0);
+        assert_eq!(cell_downloader_get_task_info(task_id, info_addr), 0);
+        assert_eq!(memory.read_be::<CellBgdlInfo>(info_addr).unwrap().state, CELL_BGDL_STATE_COMPLETE);
+        assert_eq!(cell_bgdl_get_mode(info_addr), 0);
+        assert_eq!(memory.read_be32(info_addr).unwrap() as i32, CELL_BGDL_MODE_AUTO);
+
+        // The task's content ID is unknown, so it is only listed among every content's downloads
+        memory.write_bytes(url_addr, b"UP0000-TEST00000_00-DLC\0").unwrap();
+        assert_eq!(cell_bgdl_get_info(url_addr, info_addr, 4), 0);
+        memory.write_bytes(url_addr, &[0]).unwrap();
+        assert_eq!(cell_bgdl_get_info(url_addr, info_addr, 4), 1);
+        assert_eq!(memory.read_be::<CellBgdlInfo>(info_addr).unwrap().state, CELL_BGDL_STATE_COMPLETE);
+
+        let calls = crate::context::get_hle_context_mut().guest_calls.take();
+        assert!(calls.iter().all(|call| call.opd == 0x1000 && call.args[3] == 0x2000));
+        let states: Vec<u64> = calls.iter().map(|call| call.args[1]).collect();
+        assert_eq!(states, [CELL_BGDL_STATE_RUNNING as u64, CELL_BGDL_STATE_COMPLETE as u64]);
+        assert_eq!(calls[1].args[0], task_id as u64);
+    }
+}
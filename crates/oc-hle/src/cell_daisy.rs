@@ -0,0 +1,692 @@
+//! cellDaisy HLE - PPU/SPU pipeline queues
+//!
+//! This module provides HLE implementations for the cell::Daisy library,
+//! whose lock-free queues pass buffers between a producer and a consumer on
+//! the PPU or SPUs. A queue is a ring of `depth` entries taken in two steps:
+//! an end gets the pointer (index) of its next entry, fills or reads it, then
+//! completes it. Each end opens the queue before use and closes it when done;
+//! once the producer closed, the consumer drains what is left and then finds
+//! the queue terminated. Scatter-gather interlocks count the SPUs that reached
+//! the end of a job step, and the PPU releases them once all did.
+
+use std::collections::HashMap;
+use tracing::{debug, trace};
+
+/// Error codes
+pub const CELL_DAISY_ERROR_NO_BEGIN: i32 = 0x80410501u32 as i32;
+pub const CELL_DAISY_ERROR_INVALID_PORT_ATTACH: i32 = 0x80410502u32 as i32;
+pub const CELL_DAISY_ERROR_NOT_IMPLEMENTED: i32 = 0x80410503u32 as i32;
+pub const CELL_DAISY_ERROR_PERM: i32 = 0x80410509u32 as i32;
+pub const CELL_DAISY_ERROR_BUSY: i32 = 0x8041050Au32 as i32;
+pub const CELL_DAISY_ERROR_STAT: i32 = 0x8041050Fu32 as i32;
+pub const CELL_DAISY_ERROR_AGAIN: i32 = 0x80410511u32 as i32;
+pub const CELL_DAISY_ERROR_INVAL: i32 = 0x80410512u32 as i32;
+
+/// Largest queue depth
+pub const CELL_DAISY_MAX_DEPTH: u32 = 128;
+
+/// Depth of LFQueue2 queues, whose header the game builds in guest memory
+///
+/// Note: reading the depth from the header requires memory subsystem integration
+pub const LFQUEUE2_DEFAULT_DEPTH: u32 = 16;
+
+/// Open state of one end of a queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PipeEnd {
+    /// Not opened yet
+    #[default]
+    Idle,
+    Open,
+    Closed,
+}
+
+/// Ring of entries between a producer and a consumer
+#[derive(Debug, Clone)]
+pub struct DaisyPipe {
+    depth: u32,
+    /// Entries completed by the producer
+    produced: u32,
+    /// Entries completed by the consumer
+    consumed: u32,
+    /// Whether each end holds an entry it has not completed
+    producing: bool,
+    consuming: bool,
+    push: PipeEnd,
+    pop: PipeEnd,
+}
+
+impl DaisyPipe {
+    /// Create an empty queue of `depth` entries
+    pub fn new(depth: u32) -> Self {
+        Self {
+            depth,
+            produced: 0,
+            consumed: 0,
+            producing: false,
+            consuming: false,
+            push: PipeEnd::Idle,
+            pop: PipeEnd::Idle,
+        }
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Entries produced and not yet consumed
+    pub fn len(&self) -> u32 {
+        self.produced.wrapping_sub(self.consumed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Open state of the producer and the consumer
+    pub fn ends(&self) -> (PipeEnd, PipeEnd) {
+        (self.push, self.pop)
+    }
+
+    fn open(end: &mut PipeEnd) -> i32 {
+        if *end == PipeEnd::Open {
+            return CELL_DAISY_ERROR_PERM;
+        }
+        *end = PipeEnd::Open;
+        0 // CELL_OK
+    }
+
+    fn close(end: &mut PipeEnd) -> i32 {
+        if *end != PipeEnd::Open {
+            return CELL_DAISY_ERROR_NO_BEGIN;
+        }
+        *end = PipeEnd::Closed;
+        0 // CELL_OK
+    }
+
+    /// Open the producer end
+    pub fn push_open(&mut self) -> i32 {
+        Self::open(&mut self.push)
+    }
+
+    /// Close the producer end; the consumer terminates once the queue drains
+    pub fn push_close(&mut self) -> i32 {
+        if self.producing {
+            return CELL_DAISY_ERROR_BUSY;
+        }
+        Self::close(&mut self.push)
+    }
+
+    /// Open the consumer end
+    pub fn pop_open(&mut self) -> i32 {
+        Self::open(&mut self.pop)
+    }
+
+    /// Close the consumer end
+    pub fn pop_close(&mut self) -> i32 {
+        if self.consuming {
+            return CELL_DAISY_ERROR_BUSY;
+        }
+        Self::close(&mut self.pop)
+    }
+
+    /// Pointer of the next entry for the producer to fill
+    pub fn next_tail(&mut self) -> Result<u32, i32> {
+        if self.push != PipeEnd::Open {
+            return Err(CELL_DAISY_ERROR_NO_BEGIN);
+        }
+        if self.producing {
+            return Err(CELL_DAISY_ERROR_BUSY);
+        }
+        if self.len() >= self.depth {
+            return Err(CELL_DAISY_ERROR_AGAIN);
+        }
+        self.producing = true;
+        Ok(self.produced % self.depth)
+    }
+
+    /// Hand the entry at `pointer` to the consumer
+    pub fn complete_produce(&mut self, pointer: u32) -> i32 {
+        if !self.producing || pointer != self.produced % self.depth {
+            return CELL_DAISY_ERROR_INVAL;
+        }
+        self.producing = false;
+        self.produced = self.produced.wrapping_add(1);
+        0 // CELL_OK
+    }
+
+    /// Pointer of the next entry for the consumer to read
+    ///
+    /// Fails with CELL_DAISY_ERROR_STAT once the producer closed and every
+    /// entry was consumed.
+    pub fn next_head(&mut self) -> Result<u32, i32> {
+        if self.pop != PipeEnd::Open {
+            return Err(CELL_DAISY_ERROR_NO_BEGIN);
+        }
+        if self.consuming {
+            return Err(CELL_DAISY_ERROR_BUSY);
+        }
+        if self.is_empty() {
+            return Err(match self.push {
+                PipeEnd::Closed => CELL_DAISY_ERROR_STAT,
+                _ => CELL_DAISY_ERROR_AGAIN,
+            });
+        }
+        self.consuming = true;
+        Ok(self.consumed % self.depth)
+    }
+
+    /// Give the entry at `pointer` back to the producer
+    pub fn complete_consume(&mut self, pointer: u32) -> i32 {
+        if !self.consuming || pointer != self.consumed % self.depth {
+            return CELL_DAISY_ERROR_INVAL;
+        }
+        self.consuming = false;
+        self.consumed = self.consumed.wrapping_add(1);
+        0 // CELL_OK
+    }
+
+    /// Whether the consumer is open and, unless cancelled, has entries left
+    pub fn has_unfinished_consumer(&self, cancelled: bool) -> bool {
+        self.pop == PipeEnd::Open && (cancelled || !self.is_empty() || self.consuming)
+    }
+}
+
+/// Interlock gathering the SPUs of a job step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScatterGatherInterlock {
+    /// Arrivals that complete the step
+    size: u32,
+    arrived: u32,
+    /// SPUs notified on release
+    num_spus: u32,
+    sequence: u32,
+}
+
+impl ScatterGatherInterlock {
+    pub fn new(size: u32, num_spus: u32) -> Self {
+        Self {
+            size,
+            arrived: 0,
+            num_spus,
+            sequence: 0,
+        }
+    }
+
+    /// An SPU reached the end of the step
+    pub fn arrive(&mut self) {
+        self.arrived = (self.arrived + 1).min(self.size);
+    }
+
+    /// Whether every SPU reached the end of the step
+    pub fn is_complete(&self) -> bool {
+        self.arrived == self.size
+    }
+
+    /// Start the next step, returning the SPUs to notify
+    pub fn release(&mut self) -> u32 {
+        self.arrived = 0;
+        self.num_spus
+    }
+
+    /// Advance the step sequence number, returning the new one
+    pub fn proceed_sequence_number(&mut self) -> u32 {
+        self.sequence = self.sequence.wrapping_add(1);
+        self.sequence
+    }
+
+    pub fn sequence(&self) -> u32 {
+        self.sequence
+    }
+}
+
+/// cellDaisy manager
+pub struct DaisyManager {
+    /// cell::Daisy::Lock queues by guest address
+    locks: HashMap<u32, DaisyPipe>,
+    /// LFQueue2 queues by guest address
+    queues: HashMap<u32, DaisyPipe>,
+    /// Scatter-gather interlocks by guest address
+    interlocks: HashMap<u32, ScatterGatherInterlock>,
+}
+
+impl DaisyManager {
+    /// Create a new cellDaisy manager
+    pub fn new() -> Self {
+        Self {
+            locks: HashMap::new(),
+            queues: HashMap::new(),
+            interlocks: HashMap::new(),
+        }
+    }
+
+    /// Initialize the Lock at `lock` with `depth` entries
+    pub fn lock_initialize(&mut self, lock: u32, depth: u32) -> i32 {
+        if lock == 0 || depth == 0 || depth > CELL_DAISY_MAX_DEPTH {
+            return CELL_DAISY_ERROR_INVAL;
+        }
+        self.locks.insert(lock, DaisyPipe::new(depth));
+        0 // CELL_OK
+    }
+
+    /// Lock at `lock`, once initialized
+    pub fn lock(&mut self, lock: u32) -> Result<&mut DaisyPipe, i32> {
+        self.locks.get_mut(&lock).ok_or(CELL_DAISY_ERROR_STAT)
+    }
+
+    /// LFQueue2 at `queue`, created on first use
+    pub fn lfqueue2(&mut self, queue: u32) -> Result<&mut DaisyPipe, i32> {
+        if queue == 0 {
+            return Err(CELL_DAISY_ERROR_INVAL);
+        }
+        Ok(self.queues.entry(queue).or_insert_with(|| DaisyPipe::new(LFQUEUE2_DEFAULT_DEPTH)))
+    }
+
+    /// Construct the interlock at `interlock`
+    pub fn interlock_create(&mut self, interlock: u32, size: u32, num_spus: u32) -> i32 {
+        if interlock == 0 || size == 0 {
+            return CELL_DAISY_ERROR_INVAL;
+        }
+        self.interlocks.insert(interlock, ScatterGatherInterlock::new(size, num_spus));
+        0 // CELL_OK
+    }
+
+    /// Destroy the interlock at `interlock`
+    pub fn interlock_destroy(&mut self, interlock: u32) -> i32 {
+        match self.interlocks.remove(&interlock) {
+            Some(_) => 0, // CELL_OK
+            None => CELL_DAISY_ERROR_STAT,
+        }
+    }
+
+    /// Interlock at `interlock`, once constructed
+    pub fn interlock(&mut self, interlock: u32) -> Result<&mut ScatterGatherInterlock, i32> {
+        self.interlocks.get_mut(&interlock).ok_or(CELL_DAISY_ERROR_STAT)
+    }
+}
+
+impl Default for DaisyManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Return code of a call on a queue or interlock
+fn status(result: Result<i32, i32>) -> i32 {
+    result.unwrap_or_else(|e| e)
+}
+
+/// cell::Daisy::Lock::initialize - Set up a queue lock
+///
+/// # Arguments
+/// * `lock_addr` - Address of the Lock
+/// * `depth` - Number of entries
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_daisy_lock_initialize(lock_addr: u32, depth: u32) -> i32 {
+    debug!("cell::Daisy::Lock::initialize(this=0x{:08X}, depth={})", lock_addr, depth);
+
+    crate::context::get_hle_context_mut().daisy.lock_initialize(lock_addr, depth)
+}
+
+/// cell::Daisy::Lock::getNextHeadPointer - Take the next entry to consume
+///
+/// # Arguments
+/// * `lock_addr` - Address of the Lock
+///
+/// # Returns
+/// * The entry's pointer, or an error code
+pub fn cell_daisy_lock_get_next_head_pointer(lock_addr: u32) -> i32 {
+    trace!("cell::Daisy::Lock::getNextHeadPointer(this=0x{:08X})", lock_addr);
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(ctx.daisy.lock(lock_addr).and_then(|lock| lock.next_head()).map(|pointer| pointer as i32))
+}
+
+/// cell::Daisy::Lock::getNextTailPointer - Take the next entry to produce
+///
+/// # Arguments
+/// * `lock_addr` - Address of the Lock
+///
+/// # Returns
+/// * The entry's pointer, or an error code
+pub fn cell_daisy_lock_get_next_tail_pointer(lock_addr: u32) -> i32 {
+    trace!("cell::Daisy::Lock::getNextTailPointer(this=0x{:08X})", lock_addr);
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(ctx.daisy.lock(lock_addr).and_then(|lock| lock.next_tail()).map(|pointer| pointer as i32))
+}
+
+/// cell::Daisy::Lock::completeConsume - Give a consumed entry back
+///
+/// # Arguments
+/// * `lock_addr` - Address of the Lock
+/// * `pointer` - Pointer of the entry
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_daisy_lock_complete_consume(lock_addr: u32, pointer: u32) -> i32 {
+    trace!("cell::Daisy::Lock::completeConsume(this=0x{:08X}, pointer={})", lock_addr, pointer);
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(ctx.daisy.lock(lock_addr).map(|lock| lock.complete_consume(pointer)))
+}
+
+/// cell::Daisy::Lock::completeProduce - Hand a produced entry to the consumer
+///
+/// # Arguments
+/// * `lock_addr` - Address of the Lock
+/// * `pointer` - Pointer of the entry
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_daisy_lock_complete_produce(lock_addr: u32, pointer: u32) -> i32 {
+    trace!("cell::Daisy::Lock::completeProduce(this=0x{:08X}, pointer={})", lock_addr, pointer);
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(ctx.daisy.lock(lock_addr).map(|lock| lock.complete_produce(pointer)))
+}
+
+/// cell::Daisy::Lock::pushOpen - Open the producer end
+pub fn cell_daisy_lock_push_open(lock_addr: u32) -> i32 {
+    debug!("cell::Daisy::Lock::pushOpen(this=0x{:08X})", lock_addr);
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(ctx.daisy.lock(lock_addr).map(DaisyPipe::push_open))
+}
+
+/// cell::Daisy::Lock::pushClose - Close the producer end
+pub fn cell_daisy_lock_push_close(lock_addr: u32) -> i32 {
+    debug!("cell::Daisy::Lock::pushClose(this=0x{:08X})", lock_addr);
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(ctx.daisy.lock(lock_addr).map(DaisyPipe::push_close))
+}
+
+/// cell::Daisy::Lock::popOpen - Open the consumer end
+pub fn cell_daisy_lock_pop_open(lock_addr: u32) -> i32 {
+    debug!("cell::Daisy::Lock::popOpen(this=0x{:08X})", lock_addr);
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(ctx.daisy.lock(lock_addr).map(DaisyPipe::pop_open))
+}
+
+/// cell::Daisy::Lock::popClose - Close the consumer end
+pub fn cell_daisy_lock_pop_close(lock_addr: u32) -> i32 {
+    debug!("cell::Daisy::Lock::popClose(this=0x{:08X})", lock_addr);
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(ctx.daisy.lock(lock_addr).map(DaisyPipe::pop_close))
+}
+
+/// cell::Daisy::LFQueue2GetPopPointer - Take the next entry of a queue to consume
+///
+/// # Arguments
+/// * `queue_addr` - Address of the LFQueue2
+/// * `pointer_addr` - Address to write the entry's pointer
+/// * `is_blocking` - Whether to wait for an entry
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_daisy_lfqueue2_get_pop_pointer(queue_addr: u32, pointer_addr: u32, is_blocking: u32) -> i32 {
+    trace!(
+        "cell::Daisy::LFQueue2GetPopPointer(queue=0x{:08X}, blocking={})",
+        queue_addr,
+        is_blocking
+    );
+
+    if pointer_addr == 0 {
+        return CELL_DAISY_ERROR_INVAL;
+    }
+
+    // Note: a blocking call returns CELL_DAISY_ERROR_AGAIN, as HLE calls cannot wait for the producer
+    let mut ctx = crate::context::get_hle_context_mut();
+    match ctx.daisy.lfqueue2(queue_addr).and_then(DaisyPipe::next_head) {
+        Ok(_pointer) => {
+            // Note: writing the pointer requires memory subsystem integration
+            0 // CELL_OK
+        }
+        Err(e) => e,
+    }
+}
+
+/// cell::Daisy::LFQueue2CompletePopPointer - Give a consumed queue entry back
+///
+/// # Arguments
+/// * `queue_addr` - Address of the LFQueue2
+/// * `pointer` - Pointer of the entry
+/// * `send_signal` - Function signalling the producer
+/// * `is_queue_full` - Whether the queue was full before the entry was taken
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_daisy_lfqueue2_complete_pop_pointer(
+    queue_addr: u32,
+    pointer: u32,
+    send_signal: u32,
+    is_queue_full: u32,
+) -> i32 {
+    trace!(
+        "cell::Daisy::LFQueue2CompletePopPointer(queue=0x{:08X}, pointer={}, full={})",
+        queue_addr,
+        pointer,
+        is_queue_full
+    );
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    let result = status(ctx.daisy.lfqueue2(queue_addr).map(|queue| queue.complete_consume(pointer)));
+    if result == 0 && is_queue_full != 0 && send_signal != 0 {
+        // Note: calling the signal function requires PPU callback integration
+        trace!("LFQueue2 0x{:08X}: producer signal 0x{:08X} not called", queue_addr, send_signal);
+    }
+    result
+}
+
+/// cell::Daisy::LFQueue2PushOpen - Open the producer end of a queue
+pub fn cell_daisy_lfqueue2_push_open(queue_addr: u32) -> i32 {
+    debug!("cell::Daisy::LFQueue2PushOpen(queue=0x{:08X})", queue_addr);
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(ctx.daisy.lfqueue2(queue_addr).map(DaisyPipe::push_open))
+}
+
+/// cell::Daisy::LFQueue2PushClose - Close the producer end of a queue
+pub fn cell_daisy_lfqueue2_push_close(queue_addr: u32, _send_signal: u32) -> i32 {
+    debug!("cell::Daisy::LFQueue2PushClose(queue=0x{:08X})", queue_addr);
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(ctx.daisy.lfqueue2(queue_addr).map(DaisyPipe::push_close))
+}
+
+/// cell::Daisy::LFQueue2PopOpen - Open the consumer end of a queue
+pub fn cell_daisy_lfqueue2_pop_open(queue_addr: u32) -> i32 {
+    debug!("cell::Daisy::LFQueue2PopOpen(queue=0x{:08X})", queue_addr);
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(ctx.daisy.lfqueue2(queue_addr).map(DaisyPipe::pop_open))
+}
+
+/// cell::Daisy::LFQueue2PopClose - Close the consumer end of a queue
+pub fn cell_daisy_lfqueue2_pop_close(queue_addr: u32, _send_signal: u32) -> i32 {
+    debug!("cell::Daisy::LFQueue2PopClose(queue=0x{:08X})", queue_addr);
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(ctx.daisy.lfqueue2(queue_addr).map(DaisyPipe::pop_close))
+}
+
+/// cell::Daisy::LFQueue2HasUnfinishedConsumer - Check whether the consumer still runs
+///
+/// # Arguments
+/// * `queue_addr` - Address of the LFQueue2
+/// * `is_cancelled` - Whether the producer cancelled the remaining entries
+///
+/// # Returns
+/// * 1 if the consumer has not finished, 0 if it has
+pub fn cell_daisy_lfqueue2_has_unfinished_consumer(queue_addr: u32, is_cancelled: u32) -> i32 {
+    trace!("cell::Daisy::LFQueue2HasUnfinishedConsumer(queue=0x{:08X})", queue_addr);
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(
+        ctx.daisy
+            .lfqueue2(queue_addr)
+            .map(|queue| queue.has_unfinished_consumer(is_cancelled != 0) as i32),
+    )
+}
+
+/// cell::Daisy::ScatterGatherInterlock::ScatterGatherInterlock - Construct an interlock
+///
+/// # Arguments
+/// * `interlock_addr` - Address of the ScatterGatherInterlock
+/// * `ea` - Address of the atomic interlock the SPUs update
+/// * `size` - Arrivals that complete a step
+/// * `ids_addr` - Address of the SPU thread IDs to notify
+/// * `num_spus` - Number of SPU threads to notify
+/// * `spup` - SPU port the notification is sent on
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_daisy_scatter_gather_interlock_create(
+    interlock_addr: u32,
+    ea: u32,
+    size: u32,
+    _ids_addr: u32,
+    num_spus: u32,
+    spup: u8,
+) -> i32 {
+    debug!(
+        "cell::Daisy::ScatterGatherInterlock(this=0x{:08X}, ea=0x{:08X}, size={}, num_spus={}, spup={})",
+        interlock_addr, ea, size, num_spus, spup
+    );
+
+    crate::context::get_hle_context_mut().daisy.interlock_create(interlock_addr, size, num_spus)
+}
+
+/// cell::Daisy::ScatterGatherInterlock::~ScatterGatherInterlock - Destroy an interlock
+pub fn cell_daisy_scatter_gather_interlock_destroy(interlock_addr: u32) -> i32 {
+    debug!("cell::Daisy::~ScatterGatherInterlock(this=0x{:08X})", interlock_addr);
+
+    crate::context::get_hle_context_mut().daisy.interlock_destroy(interlock_addr)
+}
+
+/// cell::Daisy::ScatterGatherInterlock::probe - Check whether every SPU arrived
+///
+/// # Arguments
+/// * `interlock_addr` - Address of the ScatterGatherInterlock
+/// * `is_blocking` - Whether to wait for the SPUs
+///
+/// # Returns
+/// * 0 once every SPU arrived, CELL_DAISY_ERROR_AGAIN before
+pub fn cell_daisy_scatter_gather_interlock_probe(interlock_addr: u32, is_blocking: u32) -> i32 {
+    trace!(
+        "cell::Daisy::ScatterGatherInterlock::probe(this=0x{:08X}, blocking={})",
+        interlock_addr,
+        is_blocking
+    );
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(ctx.daisy.interlock(interlock_addr).map(|interlock| {
+        if interlock.is_complete() {
+            0 // CELL_OK
+        } else {
+            CELL_DAISY_ERROR_AGAIN
+        }
+    }))
+}
+
+/// cell::Daisy::ScatterGatherInterlock::release - Let the SPUs start the next step
+pub fn cell_daisy_scatter_gather_interlock_release(interlock_addr: u32) -> i32 {
+    trace!("cell::Daisy::ScatterGatherInterlock::release(this=0x{:08X})", interlock_addr);
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(ctx.daisy.interlock(interlock_addr).map(|interlock| {
+        let spus = interlock.release();
+        // Note: notifying the SPU threads requires SPU port integration
+        trace!("ScatterGatherInterlock 0x{:08X}: released {} SPUs", interlock_addr, spus);
+        0 // CELL_OK
+    }))
+}
+
+/// cell::Daisy::ScatterGatherInterlock::proceedSequenceNumber - Advance the step sequence number
+///
+/// # Returns
+/// * The new sequence number
+pub fn cell_daisy_scatter_gather_interlock_proceed_sequence_number(interlock_addr: u32) -> i32 {
+    trace!(
+        "cell::Daisy::ScatterGatherInterlock::proceedSequenceNumber(this=0x{:08X})",
+        interlock_addr
+    );
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    status(
+        ctx.daisy
+            .interlock(interlock_addr)
+            .map(|interlock| interlock.proceed_sequence_number() as i32),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daisy_pipe() {
+        let mut pipe = DaisyPipe::new(2);
+        assert_eq!(pipe.next_tail(), Err(CELL_DAISY_ERROR_NO_BEGIN));
+        assert_eq!(pipe.push_open(), 0);
+        assert_eq!(pipe.push_open(), CELL_DAISY_ERROR_PERM);
+        assert_eq!(pipe.pop_open(), 0);
+        assert_eq!(pipe.next_head(), Err(CELL_DAISY_ERROR_AGAIN));
+
+        // Entries are taken, then completed, in ring order
+        assert_eq!(pipe.next_tail(), Ok(0));
+        assert_eq!(pipe.next_tail(), Err(CELL_DAISY_ERROR_BUSY));
+        assert_eq!(pipe.complete_produce(1), CELL_DAISY_ERROR_INVAL);
+        assert_eq!(pipe.complete_produce(0), 0);
+        assert_eq!(pipe.next_tail(), Ok(1));
+        assert_eq!(pipe.complete_produce(1), 0);
+        assert_eq!(pipe.next_tail(), Err(CELL_DAISY_ERROR_AGAIN));
+        assert_eq!(pipe.len(), 2);
+
+        assert_eq!(pipe.next_head(), Ok(0));
+        assert_eq!(pipe.complete_consume(0), 0);
+        assert_eq!(pipe.next_tail(), Ok(0));
+        assert_eq!(pipe.complete_produce(0), 0);
+
+        // After the producer closes, the consumer drains the queue and terminates
+        assert_eq!(pipe.push_close(), 0);
+        assert!(pipe.has_unfinished_consumer(false));
+        for pointer in [1, 0] {
+            assert_eq!(pipe.next_head(), Ok(pointer));
+            assert_eq!(pipe.complete_consume(pointer), 0);
+        }
+        assert_eq!(pipe.next_head(), Err(CELL_DAISY_ERROR_STAT));
+        assert!(!pipe.has_unfinished_consumer(false));
+        assert!(pipe.has_unfinished_consumer(true));
+        assert_eq!(pipe.pop_close(), 0);
+        assert_eq!(pipe.ends(), (PipeEnd::Closed, PipeEnd::Closed));
+    }
+
+    #[test]
+    fn test_daisy_manager() {
+        let mut manager = DaisyManager::new();
+        assert_eq!(manager.lock_initialize(0x1000, 0), CELL_DAISY_ERROR_INVAL);
+        assert_eq!(manager.lock_initialize(0x1000, 4), 0);
+        assert_eq!(manager.lock(0x1000).unwrap().depth(), 4);
+        assert!(manager.lock(0x2000).is_err());
+        assert_eq!(manager.lfqueue2(0x3000).unwrap().depth(), LFQUEUE2_DEFAULT_DEPTH);
+
+        // The interlock completes once every SPU arrived and starts over when released
+        assert_eq!(manager.interlock_create(0x4000, 2, 2), 0);
+        let interlock = manager.interlock(0x4000).unwrap();
+        interlock.arrive();
+        assert!(!interlock.is_complete());
+        interlock.arrive();
+        interlock.arrive();
+        assert!(interlock.is_complete());
+        assert_eq!(interlock.release(), 2);
+        assert!(!interlock.is_complete());
+        assert_eq!(interlock.proceed_sequence_number(), 1);
+        assert_eq!(manager.interlock_destroy(0x4000), 0);
+        assert_eq!(manager.interlock_destroy(0x4000), CELL_DAISY_ERROR_STAT);
+    }
+}
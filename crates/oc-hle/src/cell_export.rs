@@ -0,0 +1,697 @@
+//! cellPhotoExport/cellVideoExport/cellMusicExport HLE - Media Export Utilities
+//!
+//! This module provides HLE implementations for copying screenshots, replays
+//! and music from a game's data into the console's media library. Exports go
+//! to the photo, video and music directories of the host `/dev_hdd0` and
+//! finish at once; the finish callback then runs on the guest with the result.
+
+use crate::context::read_guest_string;
+use std::path::{Component, Path, PathBuf};
+use tracing::{debug, trace};
+
+/// Photo export error codes
+pub const CELL_PHOTO_EXPORT_UTIL_ERROR_BUSY: i32 = 0x8002C201u32 as i32;
+pub const CELL_PHOTO_EXPORT_UTIL_ERROR_INTERNAL: i32 = 0x8002C202u32 as i32;
+pub const CELL_PHOTO_EXPORT_UTIL_ERROR_PARAM: i32 = 0x8002C203u32 as i32;
+pub const CELL_PHOTO_EXPORT_UTIL_ERROR_ACCESS_ERROR: i32 = 0x8002C204u32 as i32;
+pub const CELL_PHOTO_EXPORT_UTIL_ERROR_MOVE: i32 = 0x8002C209u32 as i32;
+pub const CELL_PHOTO_EXPORT_UTIL_ERROR_INITIALIZE: i32 = 0x8002C20Au32 as i32;
+
+/// Video export error codes
+pub const CELL_VIDEO_EXPORT_UTIL_ERROR_BUSY: i32 = 0x8002CA01u32 as i32;
+pub const CELL_VIDEO_EXPORT_UTIL_ERROR_INTERNAL: i32 = 0x8002CA02u32 as i32;
+pub const CELL_VIDEO_EXPORT_UTIL_ERROR_PARAM: i32 = 0x8002CA03u32 as i32;
+pub const CELL_VIDEO_EXPORT_UTIL_ERROR_ACCESS_ERROR: i32 = 0x8002CA04u32 as i32;
+pub const CELL_VIDEO_EXPORT_UTIL_ERROR_MOVE: i32 = 0x8002CA09u32 as i32;
+pub const CELL_VIDEO_EXPORT_UTIL_ERROR_INITIALIZE: i32 = 0x8002CA0Au32 as i32;
+
+/// Music export error codes
+pub const CELL_MUSIC_EXPORT_UTIL_ERROR_BUSY: i32 = 0x8002C601u32 as i32;
+pub const CELL_MUSIC_EXPORT_UTIL_ERROR_INTERNAL: i32 = 0x8002C602u32 as i32;
+pub const CELL_MUSIC_EXPORT_UTIL_ERROR_PARAM: i32 = 0x8002C603u32 as i32;
+pub const CELL_MUSIC_EXPORT_UTIL_ERROR_ACCESS_ERROR: i32 = 0x8002C604u32 as i32;
+pub const CELL_MUSIC_EXPORT_UTIL_ERROR_MOVE: i32 = 0x8002C609u32 as i32;
+pub const CELL_MUSIC_EXPORT_UTIL_ERROR_INITIALIZE: i32 = 0x8002C60Au32 as i32;
+
+/// Progress reported once an export is done (0xFFFF is 100%)
+pub const CELL_EXPORT_PROGRESS_DONE: i32 = 0xFFFF;
+
+/// Longest source directory path and file name read from guest memory
+const MAX_SRC_DIR: u32 = 1024;
+const MAX_SRC_FILE: u32 = 255;
+
+/// Kind of media being exported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportKind {
+    Photo = 0,
+    Video = 1,
+    Music = 2,
+}
+
+impl ExportKind {
+    /// Directory under `/dev_hdd0` receiving the exports
+    pub fn directory(self) -> &'static str {
+        match self {
+            Self::Photo => "photo",
+            Self::Video => "video",
+            Self::Music => "music",
+        }
+    }
+
+    fn busy(self) -> i32 {
+        [CELL_PHOTO_EXPORT_UTIL_ERROR_BUSY, CELL_VIDEO_EXPORT_UTIL_ERROR_BUSY, CELL_MUSIC_EXPORT_UTIL_ERROR_BUSY]
+            [self as usize]
+    }
+
+    fn param(self) -> i32 {
+        [CELL_PHOTO_EXPORT_UTIL_ERROR_PARAM, CELL_VIDEO_EXPORT_UTIL_ERROR_PARAM, CELL_MUSIC_EXPORT_UTIL_ERROR_PARAM]
+            [self as usize]
+    }
+
+    fn access(self) -> i32 {
+        [
+            CELL_PHOTO_EXPORT_UTIL_ERROR_ACCESS_ERROR,
+            CELL_VIDEO_EXPORT_UTIL_ERROR_ACCESS_ERROR,
+            CELL_MUSIC_EXPORT_UTIL_ERROR_ACCESS_ERROR,
+        ][self as usize]
+    }
+
+    fn move_failed(self) -> i32 {
+        [CELL_PHOTO_EXPORT_UTIL_ERROR_MOVE, CELL_VIDEO_EXPORT_UTIL_ERROR_MOVE, CELL_MUSIC_EXPORT_UTIL_ERROR_MOVE]
+            [self as usize]
+    }
+
+    fn not_initialized(self) -> i32 {
+        [
+            CELL_PHOTO_EXPORT_UTIL_ERROR_INITIALIZE,
+            CELL_VIDEO_EXPORT_UTIL_ERROR_INITIALIZE,
+            CELL_MUSIC_EXPORT_UTIL_ERROR_INITIALIZE,
+        ][self as usize]
+    }
+}
+
+/// Finish or progress callback waiting to be run on the PPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportCallback {
+    pub func: u32,
+    pub result: i32,
+    pub userdata: u32,
+}
+
+/// Media export manager
+pub struct ExportManager {
+    /// Host directory backing `/dev_hdd0`
+    hdd0_root: Option<PathBuf>,
+    /// Initialization flags by kind
+    initialized: [bool; 3],
+    /// Host paths of the files exported so far
+    exported: Vec<(ExportKind, PathBuf)>,
+    /// Callbacks waiting to be run
+    pending_callbacks: Vec<ExportCallback>,
+}
+
+impl ExportManager {
+    /// Create a new export manager
+    pub fn new() -> Self {
+        Self {
+            hdd0_root: None,
+            initialized: [false; 3],
+            exported: Vec::new(),
+            pending_callbacks: Vec::new(),
+        }
+    }
+
+    /// Set the host directory backing `/dev_hdd0`
+    pub fn set_hdd0_root(&mut self, root: PathBuf) {
+        self.hdd0_root = Some(root);
+    }
+
+    /// Initialize the utility for `kind`
+    pub fn initialize(&mut self, kind: ExportKind, func: u32, userdata: u32) -> i32 {
+        if func == 0 {
+            return kind.param();
+        }
+        if self.initialized[kind as usize] {
+            return kind.busy();
+        }
+
+        debug!("ExportManager::initialize: {:?}", kind);
+
+        self.initialized[kind as usize] = true;
+        self.queue_callback(func, 0, userdata);
+        0 // CELL_OK
+    }
+
+    /// Finalize the utility for `kind`
+    pub fn finalize(&mut self, kind: ExportKind, func: u32, userdata: u32) -> i32 {
+        if !self.initialized[kind as usize] {
+            return kind.not_initialized();
+        }
+
+        debug!("ExportManager::finalize: {:?}", kind);
+
+        self.initialized[kind as usize] = false;
+        self.queue_callback(func, 0, userdata);
+        0 // CELL_OK
+    }
+
+    /// Report the progress of the current export, which is always done
+    pub fn progress(&mut self, kind: ExportKind, func: u32, userdata: u32) -> i32 {
+        if !self.initialized[kind as usize] {
+            return kind.not_initialized();
+        }
+        self.queue_callback(func, CELL_EXPORT_PROGRESS_DONE, userdata);
+        0 // CELL_OK
+    }
+
+    /// Export `src_file` from the guest directory `src_dir`
+    ///
+    /// The file is moved, or copied when `copy` is set, into the kind's
+    /// directory, getting a numbered name if one with its name exists.
+    /// The finish callback receives the result; the call itself only fails
+    /// on bad arguments.
+    pub fn export(
+        &mut self,
+        kind: ExportKind,
+        src_dir: &str,
+        src_file: &str,
+        copy: bool,
+        func: u32,
+        userdata: u32,
+    ) -> i32 {
+        if !self.initialized[kind as usize] {
+            return kind.not_initialized();
+        }
+        if func == 0 || src_file.is_empty() || src_file.contains('/') {
+            return kind.param();
+        }
+
+        let result = match self.export_file(kind, src_dir, src_file, copy) {
+            Ok(path) => {
+                debug!("ExportManager::export: {:?} {}/{} -> {}", kind, src_dir, src_file, path.display());
+                self.exported.push((kind, path));
+                0
+            }
+            Err(e) => e,
+        };
+        self.queue_callback(func, result, userdata);
+        0 // CELL_OK
+    }
+
+    fn export_file(&self, kind: ExportKind, src_dir: &str, src_file: &str, copy: bool) -> Result<PathBuf, i32> {
+        let root = self.hdd0_root.as_ref().ok_or(kind.access())?;
+        let src = Self::resolve(root, src_dir).ok_or(kind.access())?.join(src_file);
+        if !src.is_file() {
+            return Err(kind.access());
+        }
+
+        let dir = root.join(kind.directory());
+        std::fs::create_dir_all(&dir).map_err(|_| kind.access())?;
+        let dst = Self::available_name(&dir, src_file);
+        let moved = if copy {
+            std::fs::copy(&src, &dst).map(|_| ())
+        } else {
+            std::fs::rename(&src, &dst)
+        };
+        moved.map_err(|_| kind.move_failed())?;
+        Ok(dst)
+    }
+
+    /// Host path of a guest directory under `/dev_hdd0`
+    fn resolve(root: &Path, guest_dir: &str) -> Option<PathBuf> {
+        let rest = guest_dir.strip_prefix("/dev_hdd0")?;
+        let rest = Path::new(rest.trim_start_matches('/'));
+        if rest.components().any(|c| !matches!(c, Component::Normal(_))) {
+            return None;
+        }
+        Some(root.join(rest))
+    }
+
+    /// `name` in `dir`, numbered if a file with that name exists
+    fn available_name(dir: &Path, name: &str) -> PathBuf {
+        let path = dir.join(name);
+        if !path.exists() {
+            return path;
+        }
+        let (stem, ext) = match name.rsplit_once('.') {
+            Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+            _ => (name, String::new()),
+        };
+        (1..)
+            .map(|n| dir.join(format!("{}_{}{}", stem, n, ext)))
+            .find(|path| !path.exists())
+            .unwrap_or(path)
+    }
+
+    /// Queue a callback to run on the guest
+    pub fn queue_callback(&mut self, func: u32, result: i32, userdata: u32) {
+        if func == 0 {
+            return;
+        }
+        trace!("ExportManager::queue_callback: func=0x{:08X}, result=0x{:X}", func, result);
+        self.pending_callbacks.push(ExportCallback { func, result, userdata });
+    }
+
+    /// Take the callbacks waiting to be run
+    pub fn take_callbacks(&mut self) -> Vec<ExportCallback> {
+        std::mem::take(&mut self.pending_callbacks)
+    }
+
+    /// Host paths of the files exported so far
+    pub fn exported(&self, kind: ExportKind) -> Vec<&Path> {
+        self.exported
+            .iter()
+            .filter(|(k, _)| *k == kind)
+            .map(|(_, path)| path.as_path())
+            .collect()
+    }
+
+    /// Check if the utility for `kind` is initialized
+    pub fn is_initialized(&self, kind: ExportKind) -> bool {
+        self.initialized[kind as usize]
+    }
+}
+
+impl Default for ExportManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Run `f` on the export manager, handing the callbacks it queued to the guest
+///
+/// Finish and progress callbacks take the result and the user data.
+fn with_export(f: impl FnOnce(&mut ExportManager) -> i32) -> i32 {
+    let ctx = &mut *crate::context::get_hle_context_mut();
+    let ret = f(&mut ctx.export);
+    for callback in ctx.export.take_callbacks() {
+        ctx.guest_calls.push(callback.func, [callback.result as i64 as u64, callback.userdata as u64, 0, 0]);
+    }
+    ret
+}
+
+fn initialize(kind: ExportKind, func: u32, userdata: u32) -> i32 {
+    with_export(|export| export.initialize(kind, func, userdata))
+}
+
+fn from_file(kind: ExportKind, src_dir_addr: u32, src_file_addr: u32, copy: bool, func: u32, userdata: u32) -> i32 {
+    let src_dir = read_guest_string(src_dir_addr, MAX_SRC_DIR);
+    let src_file = read_guest_string(src_file_addr, MAX_SRC_FILE);
+    with_export(|export| {
+        if !export.is_initialized(kind) {
+            return kind.not_initialized();
+        }
+        let (Some(src_dir), Some(src_file)) = (src_dir, src_file) else {
+            return kind.param();
+        };
+        export.export(kind, &src_dir, &src_file, copy, func, userdata)
+    })
+}
+
+/// cellPhotoExportInitialize - Initialize the photo export utility
+///
+/// # Arguments
+/// * `version` - Utility version
+/// * `container` - Memory container for the utility
+/// * `func` - Finish callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_photo_export_initialize(version: u32, container: u32, func: u32, userdata: u32) -> i32 {
+    debug!("cellPhotoExportInitialize(version={}, container={})", version, container);
+
+    initialize(ExportKind::Photo, func, userdata)
+}
+
+/// cellPhotoExportInitialize2 - Initialize the photo export utility
+///
+/// # Arguments
+/// * `version` - Utility version
+/// * `func` - Finish callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_photo_export_initialize2(version: u32, func: u32, userdata: u32) -> i32 {
+    debug!("cellPhotoExportInitialize2(version={})", version);
+
+    initialize(ExportKind::Photo, func, userdata)
+}
+
+/// cellPhotoExportFromFile - Move a photo into the photo library
+///
+/// # Arguments
+/// * `src_dir_addr` - Address of the source directory path
+/// * `src_file_addr` - Address of the source file name
+/// * `param_addr` - Address of CellPhotoExportSetParam
+/// * `func` - Finish callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_photo_export_from_file(
+    src_dir_addr: u32,
+    src_file_addr: u32,
+    _param_addr: u32,
+    func: u32,
+    userdata: u32,
+) -> i32 {
+    debug!("cellPhotoExportFromFile()");
+
+    from_file(ExportKind::Photo, src_dir_addr, src_file_addr, false, func, userdata)
+}
+
+/// cellPhotoExportFromFileWithCopy - Copy a photo into the photo library
+///
+/// # Arguments
+/// * `src_dir_addr` - Address of the source directory path
+/// * `src_file_addr` - Address of the source file name
+/// * `param_addr` - Address of CellPhotoExportSetParam
+/// * `func` - Finish callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_photo_export_from_file_with_copy(
+    src_dir_addr: u32,
+    src_file_addr: u32,
+    _param_addr: u32,
+    func: u32,
+    userdata: u32,
+) -> i32 {
+    debug!("cellPhotoExportFromFileWithCopy()");
+
+    from_file(ExportKind::Photo, src_dir_addr, src_file_addr, true, func, userdata)
+}
+
+/// cellPhotoExportProgress - Report the progress of the current photo export
+///
+/// # Arguments
+/// * `func` - Progress callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_photo_export_progress(func: u32, userdata: u32) -> i32 {
+    trace!("cellPhotoExportProgress()");
+
+    with_export(|export| export.progress(ExportKind::Photo, func, userdata))
+}
+
+/// cellPhotoExportFinalize - Finalize the photo export utility
+///
+/// # Arguments
+/// * `func` - Finish callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_photo_export_finalize(func: u32, userdata: u32) -> i32 {
+    debug!("cellPhotoExportFinalize()");
+
+    with_export(|export| export.finalize(ExportKind::Photo, func, userdata))
+}
+
+/// cellVideoExportInitialize - Initialize the video export utility
+///
+/// # Arguments
+/// * `version` - Utility version
+/// * `container` - Memory container for the utility
+/// * `func` - Finish callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_video_export_initialize(version: u32, container: u32, func: u32, userdata: u32) -> i32 {
+    debug!("cellVideoExportInitialize(version={}, container={})", version, container);
+
+    initialize(ExportKind::Video, func, userdata)
+}
+
+/// cellVideoExportInitialize2 - Initialize the video export utility
+///
+/// # Arguments
+/// * `version` - Utility version
+/// * `func` - Finish callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_video_export_initialize2(version: u32, func: u32, userdata: u32) -> i32 {
+    debug!("cellVideoExportInitialize2(version={})", version);
+
+    initialize(ExportKind::Video, func, userdata)
+}
+
+/// cellVideoExportFromFile - Move a video into the video library
+///
+/// # Arguments
+/// * `src_dir_addr` - Address of the source directory path
+/// * `src_file_addr` - Address of the source file name
+/// * `param_addr` - Address of CellVideoExportSetParam
+/// * `func` - Finish callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_video_export_from_file(
+    src_dir_addr: u32,
+    src_file_addr: u32,
+    _param_addr: u32,
+    func: u32,
+    userdata: u32,
+) -> i32 {
+    debug!("cellVideoExportFromFile()");
+
+    from_file(ExportKind::Video, src_dir_addr, src_file_addr, false, func, userdata)
+}
+
+/// cellVideoExportFromFileWithCopy - Copy a video into the video library
+///
+/// # Arguments
+/// * `src_dir_addr` - Address of the source directory path
+/// * `src_file_addr` - Address of the source file name
+/// * `param_addr` - Address of CellVideoExportSetParam
+/// * `func` - Finish callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_video_export_from_file_with_copy(
+    src_dir_addr: u32,
+    src_file_addr: u32,
+    _param_addr: u32,
+    func: u32,
+    userdata: u32,
+) -> i32 {
+    debug!("cellVideoExportFromFileWithCopy()");
+
+    from_file(ExportKind::Video, src_dir_addr, src_file_addr, true, func, userdata)
+}
+
+/// cellVideoExportProgress - Report the progress of the current video export
+///
+/// # Arguments
+/// * `func` - Progress callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_video_export_progress(func: u32, userdata: u32) -> i32 {
+    trace!("cellVideoExportProgress()");
+
+    with_export(|export| export.progress(ExportKind::Video, func, userdata))
+}
+
+/// cellVideoExportFinalize - Finalize the video export utility
+///
+/// # Arguments
+/// * `func` - Finish callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_video_export_finalize(func: u32, userdata: u32) -> i32 {
+    debug!("cellVideoExportFinalize()");
+
+    with_export(|export| export.finalize(ExportKind::Video, func, userdata))
+}
+
+/// cellMusicExportInitialize - Initialize the music export utility
+///
+/// # Arguments
+/// * `version` - Utility version
+/// * `container` - Memory container for the utility
+/// * `func` - Finish callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_music_export_initialize(version: u32, container: u32, func: u32, userdata: u32) -> i32 {
+    debug!("cellMusicExportInitialize(version={}, container={})", version, container);
+
+    initialize(ExportKind::Music, func, userdata)
+}
+
+/// cellMusicExportInitialize2 - Initialize the music export utility
+///
+/// # Arguments
+/// * `version` - Utility version
+/// * `func` - Finish callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_music_export_initialize2(version: u32, func: u32, userdata: u32) -> i32 {
+    debug!("cellMusicExportInitialize2(version={})", version);
+
+    initialize(ExportKind::Music, func, userdata)
+}
+
+/// cellMusicExportFromFile - Copy a track into the music library
+///
+/// # Arguments
+/// * `src_dir_addr` - Address of the source directory path
+/// * `src_file_addr` - Address of the source file name
+/// * `param_addr` - Address of CellMusicExportSetParam
+/// * `func` - Finish callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_music_export_from_file(
+    src_dir_addr: u32,
+    src_file_addr: u32,
+    _param_addr: u32,
+    func: u32,
+    userdata: u32,
+) -> i32 {
+    debug!("cellMusicExportFromFile()");
+
+    from_file(ExportKind::Music, src_dir_addr, src_file_addr, true, func, userdata)
+}
+
+/// cellMusicExportProgress - Report the progress of the current music export
+///
+/// # Arguments
+/// * `func` - Progress callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_music_export_progress(func: u32, userdata: u32) -> i32 {
+    trace!("cellMusicExportProgress()");
+
+    with_export(|export| export.progress(ExportKind::Music, func, userdata))
+}
+
+/// cellMusicExportFinalize - Finalize the music export utility
+///
+/// # Arguments
+/// * `func` - Finish callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_music_export_finalize(func: u32, userdata: u32) -> i32 {
+    debug!("cellMusicExportFinalize()");
+
+    with_export(|export| export.finalize(ExportKind::Music, func, userdata))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_hdd0(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("oc_hle_export_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("game/TEST00000/USRDIR")).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_export_lifecycle() {
+        let mut manager = ExportManager::new();
+
+        assert_eq!(manager.progress(ExportKind::Photo, 0x1000, 0), CELL_PHOTO_EXPORT_UTIL_ERROR_INITIALIZE);
+        assert_eq!(manager.initialize(ExportKind::Photo, 0, 0), CELL_PHOTO_EXPORT_UTIL_ERROR_PARAM);
+        assert_eq!(manager.initialize(ExportKind::Photo, 0x1000, 0x2000), 0);
+        assert_eq!(manager.initialize(ExportKind::Photo, 0x1000, 0x2000), CELL_PHOTO_EXPORT_UTIL_ERROR_BUSY);
+        assert!(!manager.is_initialized(ExportKind::Video));
+
+        assert_eq!(manager.progress(ExportKind::Photo, 0x1100, 0x2000), 0);
+        assert_eq!(manager.finalize(ExportKind::Photo, 0x1200, 0x2000), 0);
+        assert_eq!(
+            manager.take_callbacks(),
+            [
+                ExportCallback { func: 0x1000, result: 0, userdata: 0x2000 },
+                ExportCallback { func: 0x1100, result: CELL_EXPORT_PROGRESS_DONE, userdata: 0x2000 },
+                ExportCallback { func: 0x1200, result: 0, userdata: 0x2000 },
+            ]
+        );
+        assert!(manager.take_callbacks().is_empty());
+    }
+
+    #[test]
+    fn test_export_files() {
+        let root = temp_hdd0("files");
+        let usrdir = root.join("game/TEST00000/USRDIR");
+        std::fs::write(usrdir.join("shot.jpg"), b"jpeg").unwrap();
+        std::fs::write(usrdir.join("replay.mp4"), b"mp4").unwrap();
+
+        let mut manager = ExportManager::new();
+        manager.set_hdd0_root(root.clone());
+        manager.initialize(ExportKind::Photo, 0x1000, 0);
+        manager.initialize(ExportKind::Video, 0x1000, 0);
+        manager.take_callbacks();
+
+        // Copies keep the source and are numbered on collision
+        let src = "/dev_hdd0/game/TEST00000/USRDIR";
+        assert_eq!(manager.export(ExportKind::Photo, src, "shot.jpg", true, 0x1000, 0), 0);
+        assert_eq!(manager.export(ExportKind::Photo, src, "shot.jpg", true, 0x1000, 0), 0);
+        assert!(usrdir.join("shot.jpg").exists());
+        assert_eq!(manager.exported(ExportKind::Photo), [root.join("photo/shot.jpg"), root.join("photo/shot_1.jpg")]);
+
+        // Moves take the source away
+        assert_eq!(manager.export(ExportKind::Video, src, "replay.mp4", false, 0x1000, 0), 0);
+        assert!(!usrdir.join("replay.mp4").exists());
+        assert_eq!(std::fs::read(root.join("video/replay.mp4")).unwrap(), b"mp4");
+
+        // Failures are reported through the finish callback
+        assert_eq!(manager.export(ExportKind::Video, src, "missing.mp4", false, 0x1000, 0), 0);
+        assert_eq!(manager.export(ExportKind::Video, "/dev_hdd0/../etc", "passwd", true, 0x1000, 0), 0);
+        assert_eq!(manager.export(ExportKind::Video, src, "../shot.jpg", true, 0x1000, 0), CELL_VIDEO_EXPORT_UTIL_ERROR_PARAM);
+        let results: Vec<i32> = manager.take_callbacks().iter().map(|c| c.result).collect();
+        assert_eq!(results, [0, 0, 0, CELL_VIDEO_EXPORT_UTIL_ERROR_ACCESS_ERROR, CELL_VIDEO_EXPORT_UTIL_ERROR_ACCESS_ERROR]);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_export_from_guest() {
+        let _guard = crate::context::test_guard();
+        crate::context::reset_hle_context();
+        let root = temp_hdd0("guest");
+        std::fs::write(root.join("game/TEST00000/USRDIR/shot.png"), b"png").unwrap();
+        crate::context::get_hle_context_mut().export.set_hdd0_root(root.clone());
+
+        let memory = crate::context::test_guest_memory();
+        let dir_addr = memory.allocate(0x100, 0x10, oc_memory::PageFlags::RW).unwrap();
+        memory.write_bytes(dir_addr, b"/dev_hdd0/game/TEST00000/USRDIR\0").unwrap();
+        memory.write_bytes(dir_addr + 0x80, b"shot.png\0").unwrap();
+
+        assert_eq!(cell_photo_export_initialize2(1, 0x1000, 0x2000), 0);
+        assert_eq!(cell_photo_export_from_file_with_copy(dir_addr, dir_addr + 0x80, 0, 0x1100, 0x2000), 0);
+        assert_eq!(cell_photo_export_from_file(dir_addr, 0, 0, 0x1100, 0x2000), CELL_PHOTO_EXPORT_UTIL_ERROR_PARAM);
+        assert_eq!(std::fs::read(root.join("photo/shot.png")).unwrap(), b"png");
+
+        // The initialize and finish callbacks run on the guest with the result and user data
+        let calls = crate::context::get_hle_context_mut().guest_calls.take();
+        let calls: Vec<(u32, u64, u64)> = calls.iter().map(|c| (c.opd, c.args[0], c.args[1])).collect();
+        assert_eq!(calls, [(0x1000, 0, 0x2000), (0x1100, 0, 0x2000)]);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -3,8 +3,15 @@
 //! This module provides HLE implementations for PS3 game data access,
 //! including disc content, digital content, and game directories.
 
-use std::collections::HashMap;
-use tracing::{debug, trace};
+use crate::cell_sysutil::{
+    DialogStatus, DialogType, CELL_SYSUTIL_DRAWING_BEGIN, CELL_SYSUTIL_DRAWING_END, CELL_SYSUTIL_REQUEST_EXITGAME,
+};
+use oc_core::savestate::{invalid, StateReader, StateWriter};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use tracing::{debug, trace, warn};
 
 /// Game data type
 #[repr(u32)]
@@ -23,6 +30,53 @@ pub const CELL_GAME_ATTRIBUTE_PATCH: u32 = 1;
 pub const CELL_GAME_ATTRIBUTE_APP_HOME: u32 = 2;
 pub const CELL_GAME_ATTRIBUTE_DEBUG: u32 = 4;
 
+/// cellGameContentErrorDialog types
+pub const CELL_GAME_ERRDIALOG_BROKEN_GAMEDATA: u32 = 0;
+pub const CELL_GAME_ERRDIALOG_BROKEN_HDDGAME: u32 = 1;
+pub const CELL_GAME_ERRDIALOG_NOSPACE: u32 = 2;
+pub const CELL_GAME_ERRDIALOG_BROKEN_EXIT_GAMEDATA: u32 = 100;
+pub const CELL_GAME_ERRDIALOG_BROKEN_EXIT_HDDGAME: u32 = 101;
+pub const CELL_GAME_ERRDIALOG_NOSPACE_EXIT: u32 = 102;
+
+/// cellHddGame error codes
+pub const CELL_HDDGAME_ERROR_CBRESULT: i32 = 0x8002BA01u32 as i32;
+pub const CELL_HDDGAME_ERROR_ACCESS_ERROR: i32 = 0x8002BA02u32 as i32;
+pub const CELL_HDDGAME_ERROR_INTERNAL: i32 = 0x8002BA03u32 as i32;
+pub const CELL_HDDGAME_ERROR_PARAM: i32 = 0x8002BA04u32 as i32;
+pub const CELL_HDDGAME_ERROR_NOSPACE: i32 = 0x8002BA05u32 as i32;
+pub const CELL_HDDGAME_ERROR_BROKEN: i32 = 0x8002BA06u32 as i32;
+pub const CELL_HDDGAME_ERROR_FAILURE: i32 = 0x8002BA07u32 as i32;
+
+/// cellGameData error codes
+pub const CELL_GAMEDATA_ERROR_CBRESULT: i32 = 0x8002B601u32 as i32;
+pub const CELL_GAMEDATA_ERROR_ACCESS_ERROR: i32 = 0x8002B602u32 as i32;
+pub const CELL_GAMEDATA_ERROR_INTERNAL: i32 = 0x8002B603u32 as i32;
+pub const CELL_GAMEDATA_ERROR_PARAM: i32 = 0x8002B604u32 as i32;
+pub const CELL_GAMEDATA_ERROR_NOSPACE: i32 = 0x8002B605u32 as i32;
+pub const CELL_GAMEDATA_ERROR_BROKEN: i32 = 0x8002B606u32 as i32;
+pub const CELL_GAMEDATA_ERROR_FAILURE: i32 = 0x8002B607u32 as i32;
+
+/// Size a game data install is shown as when nothing is copied (KB)
+pub const SIMULATED_INSTALL_SIZE_KB: u64 = 256 * 1024;
+
+/// Longest game directory name, without the terminator
+pub const CELL_GAME_DIRNAME_MAX: usize = 31;
+
+/// Size of the path arrays of CellHddGameStatGet
+pub const CELL_HDDGAME_PATH_MAX: usize = 1055;
+
+/// Size of the title arrays of the system file parameters
+const SYSP_TITLE_SIZE: usize = 128;
+
+/// Languages a title is given in by the system file parameters
+const SYSP_LANGUAGE_NUM: usize = 20;
+
+/// Guest block the status callbacks' structures are written to
+const STAT_BUFFER_SIZE: u32 = 0x2000;
+
+/// Offset of the StatGet structure in the status block, after the CBResult and StatSet
+const STAT_GET_OFFSET: u32 = 0x20;
+
 /// Parameter IDs for PARAM.SFO
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -63,6 +117,52 @@ impl Default for CellGameContentSize {
     }
 }
 
+/// Status cellHddGameCheck and cellGameDataCheckCreate pass to the game's status callback
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HddGameStat {
+    /// Free space on /dev_hdd0 (KB)
+    pub hdd_free_size_kb: u64,
+    /// Whether the directory was created by this check
+    pub is_new_data: bool,
+    /// Guest path of the game directory
+    pub content_info_path: String,
+    /// Guest path of its USRDIR
+    pub game_data_path: String,
+    /// Size of the whole directory (KB)
+    pub size_kb: u64,
+    /// Size of the system files next to USRDIR (KB)
+    pub sys_size_kb: u64,
+}
+
+/// Big-endian structure being laid out for a status callback
+#[derive(Default)]
+struct StatWriter(Vec<u8>);
+
+impl StatWriter {
+    fn u32(mut self, value: u32) -> Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn zeros(mut self, len: usize) -> Self {
+        self.0.resize(self.0.len() + len, 0);
+        self
+    }
+
+    /// Text array of `len` bytes, cut to leave room for its NUL
+    fn text(mut self, text: &str, len: usize) -> Self {
+        let end = text.len().min(len - 1);
+        self.0.extend_from_slice(&text.as_bytes()[..end]);
+        self.zeros(len - end)
+    }
+
+    /// Bytes padded to the 8 byte alignment of the structures' times
+    fn finish(self) -> Vec<u8> {
+        let len = self.0.len();
+        self.zeros(len.next_multiple_of(8) - len).0
+    }
+}
+
 /// Game set initial info
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -163,6 +263,83 @@ pub struct GameUpdateInfo {
     pub error_code: i32,
 }
 
+/// Content error dialog the game asked the system to show
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentErrorDialog {
+    /// CELL_GAME_ERRDIALOG_* type
+    pub error_type: u32,
+    /// Space the game still needs, for the NOSPACE types
+    pub need_size_kb: u64,
+    /// Game data directory the error is about
+    pub dir_name: String,
+}
+
+impl ContentErrorDialog {
+    /// Whether `error_type` is a CELL_GAME_ERRDIALOG_* type
+    pub fn is_valid_type(error_type: u32) -> bool {
+        matches!(error_type, 0..=2 | 100..=102)
+    }
+
+    /// Whether the game exits once the dialog is closed
+    pub fn exits_game(&self) -> bool {
+        self.error_type >= CELL_GAME_ERRDIALOG_BROKEN_EXIT_GAMEDATA
+    }
+
+    /// Text the system shows for the error
+    pub fn message(&self) -> String {
+        let text = match self.error_type % 100 {
+            0 => "The game data is corrupted.".to_string(),
+            1 => "The game is corrupted. Delete it and install it again.".to_string(),
+            _ => format!("There is not enough free space. {} KB more is needed.", self.need_size_kb),
+        };
+        if self.exits_game() {
+            format!("{} The game will now exit.", text)
+        } else {
+            text
+        }
+    }
+}
+
+/// Game data install the system runs across frames
+struct DataInstall {
+    /// Whether only progress is shown, copying nothing
+    simulated: bool,
+    /// Files left to copy, as host source and destination
+    files: VecDeque<(PathBuf, PathBuf)>,
+    /// File being copied
+    current: Option<(File, File)>,
+    /// Bytes installed so far
+    installed_bytes: u64,
+}
+
+impl DataInstall {
+    /// Copy up to `budget` bytes, returning whether every file is copied
+    fn copy(&mut self, mut budget: u64) -> io::Result<bool> {
+        while budget > 0 {
+            if self.current.is_none() {
+                let Some((src, dst)) = self.files.pop_front() else {
+                    return Ok(true);
+                };
+                if let Some(parent) = dst.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                self.current = Some((File::open(src)?, File::create(dst)?));
+            }
+            let Some((src, dst)) = self.current.as_mut() else {
+                break;
+            };
+            let copied = io::copy(&mut src.take(budget), dst)?;
+            if copied < budget {
+                // Reached the end of the file
+                self.current = None;
+            }
+            self.installed_bytes += copied;
+            budget -= copied;
+        }
+        Ok(self.current.is_none() && self.files.is_empty())
+    }
+}
+
 /// Game manager
 pub struct GameManager {
     /// Current game data type
@@ -191,6 +368,28 @@ pub struct GameManager {
     usrdir_path: String,
     /// Game update info
     update_info: GameUpdateInfo,
+    /// Content error dialog being shown
+    error_dialog: Option<ContentErrorDialog>,
+    /// Incremented on every error dialog, so the frontend sees a new one
+    error_dialog_generation: u32,
+    /// Host directory backing /dev_hdd0
+    hdd0_root: Option<PathBuf>,
+    /// HDD game checked by the last cellHddGameCheck
+    hdd_game: Option<HddGameStat>,
+    /// Guest address of the status callbacks' structures, 0 until the first check
+    stat_buffer_addr: u32,
+    /// Host directory of the disc in the drive
+    bdvd_root: Option<PathBuf>,
+    /// Whether game data installs copy the disc's USRDIR
+    copy_disc_data: bool,
+    /// Game data install in progress
+    data_install: Option<DataInstall>,
+    /// Directory checked by the last cellGameDataCheckCreate
+    data_dir_name: String,
+    /// Whether that directory was created and is installed by the check
+    data_is_new: bool,
+    /// Status callback told of the install's progress, 0 once it is done
+    data_func_stat: u32,
 }
 
 impl GameManager {
@@ -210,6 +409,17 @@ impl GameManager {
             content_info_path: String::new(),
             usrdir_path: String::new(),
             update_info: GameUpdateInfo::default(),
+            error_dialog: None,
+            error_dialog_generation: 0,
+            hdd0_root: None,
+            hdd_game: None,
+            stat_buffer_addr: 0,
+            bdvd_root: None,
+            copy_disc_data: false,
+            data_install: None,
+            data_dir_name: String::new(),
+            data_is_new: false,
+            data_func_stat: 0,
         };
         
         // Initialize default parameters
@@ -446,6 +656,170 @@ impl GameManager {
         &self.usrdir_path
     }
 
+    // ========================================================================
+    // HDD Space
+    // ========================================================================
+
+    /// Set the host directory backing `/dev_hdd0`
+    pub fn set_hdd0_root(&mut self, root: PathBuf) {
+        self.hdd0_root = Some(root);
+    }
+
+    /// Set the free space reported on `/dev_hdd0`
+    pub fn set_hdd_free_size_kb(&mut self, size_kb: u64) {
+        self.content_size.hdd_free_size = size_kb;
+    }
+
+    /// Free space reported on `/dev_hdd0` (KB)
+    pub fn hdd_free_size_kb(&self) -> u64 {
+        self.content_size.hdd_free_size
+    }
+
+    /// Host path of a guest path on `/dev_hdd0`
+    fn host_path(&self, guest_path: &str) -> Option<PathBuf> {
+        let rest = guest_path.strip_prefix("/dev_hdd0")?.trim_start_matches('/');
+        if rest.split('/').any(|part| part == "..") {
+            return None;
+        }
+        Some(self.hdd0_root.as_ref()?.join(rest))
+    }
+
+    /// Create `/dev_hdd0/game/<dir_name>` and its USRDIR if missing
+    ///
+    /// Returns the host directory and whether it was created, or the
+    /// library's `param` or `access` error.
+    fn create_game_dir(&self, dir_name: &str, param: i32, access: i32) -> Result<(PathBuf, bool), i32> {
+        if dir_name.is_empty()
+            || dir_name.len() > CELL_GAME_DIRNAME_MAX
+            || dir_name.contains(['/', '\\'])
+            || dir_name == ".."
+        {
+            return Err(param);
+        }
+
+        let dir = self.host_path(&format!("/dev_hdd0/game/{}", dir_name)).ok_or(access)?;
+        let usrdir = dir.join("USRDIR");
+        let is_new = !dir.is_dir();
+        std::fs::create_dir_all(&usrdir).map_err(|e| {
+            warn!("GameManager: failed to create {}: {}", usrdir.display(), e);
+            access
+        })?;
+        Ok((dir, is_new))
+    }
+
+    /// Status of the game directory `dir_name` at `dir`
+    fn dir_stat(&self, dir: &Path, dir_name: &str, is_new_data: bool) -> HddGameStat {
+        let content_info_path = format!("/dev_hdd0/game/{}", dir_name);
+        let size_kb = dir_size_kb(dir);
+        HddGameStat {
+            hdd_free_size_kb: self.hdd_free_size_kb(),
+            is_new_data,
+            game_data_path: format!("{}/USRDIR", content_info_path),
+            content_info_path,
+            size_kb,
+            sys_size_kb: size_kb - dir_size_kb(&dir.join("USRDIR")),
+        }
+    }
+
+    /// Check an HDD game directory, creating it and its USRDIR if missing
+    pub fn hdd_game_check(&mut self, dir_name: &str) -> Result<HddGameStat, i32> {
+        let (dir, is_new_data) =
+            self.create_game_dir(dir_name, CELL_HDDGAME_ERROR_PARAM, CELL_HDDGAME_ERROR_ACCESS_ERROR)?;
+        let stat = self.dir_stat(&dir, dir_name, is_new_data);
+        debug!(
+            "GameManager::hdd_game_check: {} new={} size={} KB free={} KB",
+            dir_name, stat.is_new_data, stat.size_kb, stat.hdd_free_size_kb
+        );
+        self.hdd_game = Some(stat.clone());
+        Ok(stat)
+    }
+
+    /// Bytes of the CellHddGameStatGet describing `stat`
+    pub fn hdd_game_stat_get(&self, stat: &HddGameStat) -> Vec<u8> {
+        self.stat_get(stat, false)
+    }
+
+    /// Bytes of the CellGameDataStatGet describing `stat`
+    pub fn game_data_stat_get(&self, stat: &HddGameStat) -> Vec<u8> {
+        self.stat_get(stat, true)
+    }
+
+    /// StatGet of cellHddGame or cellGameData, which differ in their system file parameters
+    ///
+    /// An existing directory is given the running game's system file
+    /// parameters, and its times are left 0.
+    fn stat_get(&self, stat: &HddGameStat, game_data: bool) -> Vec<u8> {
+        let get = StatWriter::default()
+            .u32(stat.hdd_free_size_kb.min(i32::MAX as u64) as u32)
+            .u32(stat.is_new_data as u32)
+            .text(&stat.content_info_path, CELL_HDDGAME_PATH_MAX)
+            .text(&stat.game_data_path, CELL_HDDGAME_PATH_MAX)
+            .zeros(2 + 3 * 8); // reserved0, atime, mtime, ctime
+        let string = |id: CellGameParamId| self.get_param_string(id as u32).unwrap_or_default();
+        let int = |id: CellGameParamId| self.get_param_int(id as u32).unwrap_or(0) as u32;
+        let get = match (stat.is_new_data, game_data) {
+            (true, false) => get.zeros(SYSP_TITLE_SIZE * (SYSP_LANGUAGE_NUM + 1) + 8 + 4 * 4),
+            (true, true) => get.zeros(SYSP_TITLE_SIZE * (SYSP_LANGUAGE_NUM + 1) + 12 + 8 + 2 * 4),
+            (false, _) => {
+                let title = string(CellGameParamId::Title);
+                let get = (0..=SYSP_LANGUAGE_NUM).fold(get, |get, _| get.text(title, SYSP_TITLE_SIZE));
+                if game_data {
+                    get.text(string(CellGameParamId::TitleId), 12)
+                        .text(string(CellGameParamId::Version), 8)
+                        .u32(int(CellGameParamId::ParentalLevel))
+                        .u32(0) // attribute
+                } else {
+                    get.text(string(CellGameParamId::Version), 8)
+                        .u32(0) // attribute
+                        .u32(int(CellGameParamId::ParentalLevel))
+                        .u32(int(CellGameParamId::Resolution))
+                        .u32(int(CellGameParamId::SoundFormat))
+                }
+            }
+        };
+        get.zeros(256) // reserved space of the system file parameters
+            .u32(stat.size_kb.min(i32::MAX as u64) as u32)
+            .u32(stat.sys_size_kb.min(i32::MAX as u64) as u32)
+            .zeros(68)
+            .finish()
+    }
+
+    /// Write a status callback's StatGet to guest memory, returning the callback's arguments
+    ///
+    /// The CBResult and StatSet the game fills in are cleared in front of it.
+    /// The block is reused by every check.
+    pub fn write_stat(&mut self, get: &[u8]) -> Option<[u64; 4]> {
+        let memory = crate::context::guest_memory()?;
+        if self.stat_buffer_addr == 0 {
+            self.stat_buffer_addr = memory.allocate(STAT_BUFFER_SIZE, 0x10, oc_memory::PageFlags::RW).ok()?;
+        }
+        let base = self.stat_buffer_addr;
+        memory.write_bytes(base, &[0; STAT_GET_OFFSET as usize]).ok()?;
+        memory.write_bytes(base + STAT_GET_OFFSET, get).ok()?;
+        Some([base as u64, (base + STAT_GET_OFFSET) as u64, base as u64 + 0x10, 0])
+    }
+
+    /// HDD game checked by the last cellHddGameCheck
+    pub fn hdd_game(&self) -> Option<&HddGameStat> {
+        self.hdd_game.as_ref()
+    }
+
+    /// Current size of the HDD game checked last (KB)
+    pub fn hdd_game_size_kb(&self) -> Option<u64> {
+        let game = self.hdd_game.as_ref()?;
+        Some(dir_size_kb(&self.host_path(&game.content_info_path)?))
+    }
+
+    /// Size of the game content directory (KB)
+    ///
+    /// Content outside `/dev_hdd0` reports the size found by the data check.
+    pub fn get_size_kb(&self) -> u64 {
+        match self.host_path(&self.content_info_path) {
+            Some(dir) if dir.is_dir() => dir_size_kb(&dir),
+            _ => self.content_size.size_kb,
+        }
+    }
+
     // ========================================================================
     // Game Data Installation
     // ========================================================================
@@ -546,6 +920,121 @@ impl GameManager {
         self.install_info = GameInstallInfo::default();
     }
 
+    /// Set the host directory of the disc in the drive
+    pub fn set_bdvd_root(&mut self, root: Option<PathBuf>) {
+        self.bdvd_root = root;
+    }
+
+    /// Set whether game data installs copy the disc's USRDIR rather than only showing progress
+    pub fn set_copy_disc_data(&mut self, copy: bool) {
+        self.copy_disc_data = copy;
+    }
+
+    /// Check a game data directory, installing it when it's new
+    ///
+    /// Returns whether the directory was created. The install runs over
+    /// the following frames through [`GameManager::advance_data_install`].
+    pub fn data_check_create(&mut self, dir_name: &str) -> Result<bool, i32> {
+        let (dir, is_new) =
+            self.create_game_dir(dir_name, CELL_GAMEDATA_ERROR_PARAM, CELL_GAMEDATA_ERROR_ACCESS_ERROR)?;
+        self.data_dir_name = dir_name.to_string();
+        self.data_is_new = is_new;
+        if !is_new {
+            return Ok(false);
+        }
+
+        let usrdir = dir.join("USRDIR");
+        let source = self
+            .bdvd_root
+            .as_ref()
+            .map(|root| root.join("PS3_GAME").join("USRDIR"))
+            .filter(|source| self.copy_disc_data && source.is_dir());
+        let mut install = DataInstall {
+            simulated: source.is_none(),
+            files: VecDeque::new(),
+            current: None,
+            installed_bytes: 0,
+        };
+        let total_kb = match &source {
+            Some(source) => collect_files(source, &usrdir, &mut install.files).div_ceil(1024),
+            None => SIMULATED_INSTALL_SIZE_KB,
+        };
+        if total_kb > self.hdd_free_size_kb() {
+            return Err(CELL_GAMEDATA_ERROR_NOSPACE);
+        }
+
+        let source_path = if install.simulated { "(simulated)" } else { "/dev_bdvd/PS3_GAME/USRDIR" };
+        let ret = self.start_installation(source_path, total_kb);
+        if ret != 0 {
+            return Err(ret);
+        }
+        debug!("GameManager: installing {} ({} files, {} KB)", dir_name, install.files.len(), total_kb);
+        self.data_install = Some(install);
+        Ok(true)
+    }
+
+    /// Check if a game data install is running
+    pub fn is_data_installing(&self) -> bool {
+        self.data_install.is_some()
+    }
+
+    /// Status of the directory checked by the last cellGameDataCheckCreate
+    ///
+    /// A directory the check installs is as big as the part installed so far.
+    pub fn data_stat(&self) -> Option<HddGameStat> {
+        let dir = self.host_path(&format!("/dev_hdd0/game/{}", self.data_dir_name))?;
+        let mut stat = self.dir_stat(&dir, &self.data_dir_name, self.data_is_new);
+        if self.data_is_new {
+            stat.size_kb = self.install_info.installed_size_kb;
+            stat.sys_size_kb = 0;
+        }
+        Some(stat)
+    }
+
+    /// Set the status callback told of the install's progress
+    pub fn set_data_func_stat(&mut self, func_stat: u32) {
+        self.data_func_stat = func_stat;
+    }
+
+    /// Status callback told of the install's progress, 0 if none
+    pub fn data_func_stat(&self) -> u32 {
+        self.data_func_stat
+    }
+
+    /// Install up to `budget_kb` more of the running game data install
+    ///
+    /// Returns true once the install has finished or failed.
+    pub fn advance_data_install(&mut self, budget_kb: u64) -> bool {
+        let Some(install) = self.data_install.as_mut() else {
+            return false;
+        };
+        let total_bytes = self.install_info.total_size_kb * 1024;
+        let result = if install.simulated {
+            install.installed_bytes = (install.installed_bytes + budget_kb * 1024).min(total_bytes);
+            Ok(install.installed_bytes >= total_bytes)
+        } else {
+            install.copy(budget_kb * 1024)
+        };
+        let installed_kb = install.installed_bytes.div_ceil(1024);
+
+        match result {
+            Ok(done) => {
+                self.update_installation_progress(installed_kb);
+                if done {
+                    self.data_install = None;
+                    self.complete_installation();
+                }
+                done
+            }
+            Err(e) => {
+                warn!("GameManager: game data install failed: {}", e);
+                self.data_install = None;
+                self.fail_installation(CELL_GAMEDATA_ERROR_ACCESS_ERROR);
+                true
+            }
+        }
+    }
+
     // ========================================================================
     // Game Update Handling
     // ========================================================================
@@ -705,6 +1194,63 @@ impl GameManager {
     pub fn reset_update(&mut self) {
         self.update_info = GameUpdateInfo::default();
     }
+
+    /// Show a content error dialog until the frontend closes it
+    pub fn open_error_dialog(&mut self, dialog: ContentErrorDialog) -> i32 {
+        if !ContentErrorDialog::is_valid_type(dialog.error_type) {
+            return 0x8002b101u32 as i32; // CELL_GAME_ERROR_PARAM
+        }
+        if self.error_dialog.is_some() {
+            return 0x8002b105u32 as i32; // Already in progress
+        }
+
+        debug!("GameManager::open_error_dialog: {}", dialog.message());
+        self.error_dialog_generation = self.error_dialog_generation.wrapping_add(1);
+        self.error_dialog = Some(dialog);
+        0 // CELL_OK
+    }
+
+    /// Content error dialog being shown
+    pub fn error_dialog(&self) -> Option<&ContentErrorDialog> {
+        self.error_dialog.as_ref()
+    }
+
+    /// Error dialog count, changing whenever a new dialog opens
+    pub fn error_dialog_generation(&self) -> u32 {
+        self.error_dialog_generation
+    }
+
+    /// Close the content error dialog, returning it
+    pub fn close_error_dialog(&mut self) -> Option<ContentErrorDialog> {
+        self.error_dialog.take()
+    }
+    /// Save the boot check results for a savestate
+    ///
+    /// PARAM.SFO values are loaded from the game again when it boots.
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.bool(self.initialized);
+        w.u32(self.game_type as u32);
+        w.u32(self.attributes);
+        w.str(&self.dir_name);
+        w.str(&self.content_info_path);
+        w.str(&self.usrdir_path);
+    }
+
+    /// Restore state written by [`GameManager::save_state`]
+    pub fn load_state(&mut self, r: &mut StateReader) -> io::Result<()> {
+        self.initialized = r.bool()?;
+        self.game_type = match r.u32()? {
+            1 => CellGameDataType::Disc,
+            2 => CellGameDataType::Hdd,
+            3 => CellGameDataType::Home,
+            value => return Err(invalid(&format!("invalid game data type {}", value))),
+        };
+        self.attributes = r.u32()?;
+        self.dir_name = r.str()?;
+        self.content_info_path = r.str()?;
+        self.usrdir_path = r.str()?;
+        Ok(())
+    }
 }
 
 impl Default for GameManager {
@@ -713,6 +1259,41 @@ impl Default for GameManager {
     }
 }
 
+/// Queue every file under `src` to be copied to the same place under `dst`, returning their size
+fn collect_files(src: &Path, dst: &Path, files: &mut VecDeque<(PathBuf, PathBuf)>) -> u64 {
+    let Ok(entries) = std::fs::read_dir(src) else {
+        return 0;
+    };
+    let mut size = 0;
+    for entry in entries.flatten() {
+        let target = dst.join(entry.file_name());
+        match entry.metadata() {
+            Ok(meta) if meta.is_dir() => size += collect_files(&entry.path(), &target, files),
+            Ok(meta) => {
+                size += meta.len();
+                files.push_back((entry.path(), target));
+            }
+            Err(_) => {}
+        }
+    }
+    size
+}
+
+/// Size of a host directory in KB, counting every file in whole KB like the console
+fn dir_size_kb(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size_kb(&entry.path()),
+            Ok(meta) => meta.len().div_ceil(1024),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
 /// cellGameBootCheck - Check game boot status
 ///
 /// # Arguments
@@ -811,8 +1392,25 @@ pub fn cell_game_content_permit(
     0 // CELL_OK
 }
 
+/// Close the content error dialog the game has open, returning it
+///
+/// Called by the frontend. Gives the controllers back and, for the EXIT
+/// types, asks the game to exit.
+pub fn close_content_error_dialog() -> Option<ContentErrorDialog> {
+    let mut ctx = crate::context::get_hle_context_mut();
+    let dialog = ctx.game.close_error_dialog()?;
+    ctx.pad.set_intercepted(false);
+    if dialog.exits_game() {
+        ctx.sysutil.queue_event(CELL_SYSUTIL_REQUEST_EXITGAME, 0);
+    }
+    Some(dialog)
+}
+
 /// cellGameContentErrorDialog - Show content error dialog
 ///
+/// The dialog stays up until the frontend closes it; the controllers are
+/// held back from the game meanwhile.
+///
 /// # Arguments
 /// * `type` - Error type
 /// * `errNeedSizeKB` - Required size in KB
@@ -823,17 +1421,26 @@ pub fn cell_game_content_permit(
 pub fn cell_game_content_error_dialog(
     error_type: u32,
     err_need_size_kb: u64,
-    _dir_name_addr: u32,
+    dir_name_addr: u32,
 ) -> i32 {
     debug!(
         "cellGameContentErrorDialog(type={}, needSize={} KB)",
         error_type, err_need_size_kb
     );
 
-    // Note: Displaying dialog requires UI subsystem integration
-    // For now, just log and return success
-
-    0 // CELL_OK
+    // Without a name the dialog shows the directory of the booted content
+    let dir_name = crate::context::read_guest_string(dir_name_addr, CELL_GAME_DIRNAME_MAX as u32);
+    let mut ctx = crate::context::get_hle_context_mut();
+    let dir_name = dir_name.unwrap_or_else(|| ctx.game.get_dir_name().to_string());
+    let ret = ctx.game.open_error_dialog(ContentErrorDialog {
+        error_type,
+        need_size_kb: err_need_size_kb,
+        dir_name,
+    });
+    if ret == 0 {
+        ctx.pad.set_intercepted(true);
+    }
+    ret
 }
 
 /// cellGameGetParamInt - Get game parameter (integer)
@@ -894,6 +1501,228 @@ pub fn cell_game_get_local_web_content_path(_path_addr: u32) -> i32 {
     0 // CELL_OK
 }
 
+/// cellGameGetSizeKB - Get the size of the game content
+///
+/// # Arguments
+/// * `size_addr` - Address to write the size in KB
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_game_get_size_kb(_size_addr: u32) -> i32 {
+    let size_kb = crate::context::get_hle_context().game.get_size_kb();
+    debug!("cellGameGetSizeKB() -> {} KB", size_kb);
+
+    // Note: Writing the size to memory requires memory subsystem integration
+
+    0 // CELL_OK
+}
+
+/// cellHddGameCheck - Check an HDD game directory
+///
+/// Creates the directory and its USRDIR if they are missing, as installers
+/// expect them to exist once the check returns, then calls funcStat with the
+/// directory's CellHddGameCBResult, CellHddGameStatGet and CellHddGameStatSet.
+///
+/// # Arguments
+/// * `version` - Library version
+/// * `dirName` - Game directory name
+/// * `errDialog` - Whether errors are shown to the user
+/// * `funcStat` - Status callback
+/// * `container` - Memory container
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_hdd_game_check(
+    version: u32,
+    dir_name_addr: u32,
+    err_dialog: u32,
+    func_stat: u32,
+    container: u32,
+) -> i32 {
+    debug!(
+        "cellHddGameCheck(version={}, errDialog={}, funcStat=0x{:08X}, container=0x{:X})",
+        version, err_dialog, func_stat, container
+    );
+
+    if func_stat == 0 {
+        return CELL_HDDGAME_ERROR_PARAM;
+    }
+    let Some(dir_name) = crate::context::read_guest_string(dir_name_addr, CELL_GAME_DIRNAME_MAX as u32) else {
+        return CELL_HDDGAME_ERROR_PARAM;
+    };
+
+    let ctx = &mut *crate::context::get_hle_context_mut();
+    let stat = match ctx.game.hdd_game_check(&dir_name) {
+        Ok(stat) => stat,
+        Err(e) => return e,
+    };
+    let get = ctx.game.hdd_game_stat_get(&stat);
+    let Some(args) = ctx.game.write_stat(&get) else {
+        return CELL_HDDGAME_ERROR_INTERNAL;
+    };
+    ctx.guest_calls.push(func_stat, args);
+    0 // CELL_OK
+}
+
+/// cellHddGameCheck2 - Check an HDD game directory
+///
+/// Same as cellHddGameCheck for the newer SDK versions.
+pub fn cell_hdd_game_check2(
+    version: u32,
+    dir_name_addr: u32,
+    err_dialog: u32,
+    func_stat: u32,
+    container: u32,
+) -> i32 {
+    cell_hdd_game_check(version, dir_name_addr, err_dialog, func_stat, container)
+}
+
+/// cellHddGameGetSizeKB - Get the size of the HDD game being checked
+///
+/// # Arguments
+/// * `size_addr` - Address to write the size in KB
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_hdd_game_get_size_kb(size_addr: u32) -> i32 {
+    if size_addr == 0 {
+        return CELL_HDDGAME_ERROR_PARAM;
+    }
+    let Some(size_kb) = crate::context::get_hle_context().game.hdd_game_size_kb() else {
+        return CELL_HDDGAME_ERROR_FAILURE;
+    };
+    debug!("cellHddGameGetSizeKB() -> {} KB", size_kb);
+
+    match crate::context::guest_memory().map(|memory| memory.write_be32(size_addr, size_kb as u32)) {
+        Some(Ok(())) => 0, // CELL_OK
+        _ => CELL_HDDGAME_ERROR_INTERNAL,
+    }
+}
+
+/// cellHddGameExitBroken - Tell the user the game is broken and exit
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_hdd_game_exit_broken() -> i32 {
+    debug!("cellHddGameExitBroken()");
+    cell_game_content_error_dialog(CELL_GAME_ERRDIALOG_BROKEN_EXIT_HDDGAME, 0, 0)
+}
+
+/// Hand the status of the directory checked by cellGameDataCheckCreate to its funcStat
+///
+/// Returns false if the status could not be written. A call still waiting
+/// to run reads the newer status rather than being queued twice.
+fn queue_data_stat(ctx: &mut crate::context::HleContext, func_stat: u32) -> bool {
+    let Some(stat) = ctx.game.data_stat() else {
+        return false;
+    };
+    let get = ctx.game.game_data_stat_get(&stat);
+    let Some(args) = ctx.game.write_stat(&get) else {
+        return false;
+    };
+    if !ctx.guest_calls.pending().any(|call| call.opd == func_stat) {
+        ctx.guest_calls.push(func_stat, args);
+    }
+    true
+}
+
+/// cellGameDataCheckCreate2 - Check a game data directory, creating it if missing
+///
+/// funcStat is called with the directory's CellGameDataCBResult,
+/// CellGameDataStatGet and CellGameDataStatSet. A new directory gets its
+/// data installed from the disc over the next frames behind the system's
+/// install dialog, and funcStat is called again as the install moves on,
+/// see [`run_data_install`].
+///
+/// # Arguments
+/// * `version` - Library version
+/// * `dirName` - Game data directory name
+/// * `errDialog` - Whether errors are shown to the user
+/// * `funcStat` - Status callback
+/// * `container` - Memory container
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_game_data_check_create2(
+    version: u32,
+    dir_name_addr: u32,
+    err_dialog: u32,
+    func_stat: u32,
+    container: u32,
+) -> i32 {
+    debug!(
+        "cellGameDataCheckCreate2(version={}, errDialog={}, funcStat=0x{:08X}, container=0x{:X})",
+        version, err_dialog, func_stat, container
+    );
+
+    if func_stat == 0 {
+        return CELL_GAMEDATA_ERROR_PARAM;
+    }
+    let Some(dir_name) = crate::context::read_guest_string(dir_name_addr, CELL_GAME_DIRNAME_MAX as u32) else {
+        return CELL_GAMEDATA_ERROR_PARAM;
+    };
+
+    let ctx = &mut *crate::context::get_hle_context_mut();
+    match ctx.game.data_check_create(&dir_name) {
+        Ok(true) => {
+            ctx.game.set_data_func_stat(func_stat);
+            if ctx.sysutil.open_game_data_dialog("Installing game data...") == 0 {
+                ctx.sysutil.queue_event(CELL_SYSUTIL_DRAWING_BEGIN, 0);
+            }
+        }
+        Ok(false) => {}
+        Err(e) => return e,
+    }
+    if !queue_data_stat(ctx, func_stat) {
+        return CELL_GAMEDATA_ERROR_INTERNAL;
+    }
+    0 // CELL_OK
+}
+
+/// cellGameDataCheckCreate - Check a game data directory, creating it if missing
+///
+/// Same as cellGameDataCheckCreate2 for older SDK versions.
+pub fn cell_game_data_check_create(
+    version: u32,
+    dir_name_addr: u32,
+    err_dialog: u32,
+    func_stat: u32,
+    container: u32,
+) -> i32 {
+    cell_game_data_check_create2(version, dir_name_addr, err_dialog, func_stat, container)
+}
+
+/// Run the game data install for a frame, installing up to `budget_kb`
+///
+/// Called by the emulator every frame. Tells funcStat of the size installed
+/// so far, keeps the install dialog's progress bar in step and closes the
+/// dialog once the install is done.
+pub fn run_data_install(budget_kb: u64) {
+    let ctx = &mut *crate::context::get_hle_context_mut();
+    if !ctx.game.is_data_installing() {
+        return;
+    }
+
+    let done = ctx.game.advance_data_install(budget_kb);
+    let func_stat = ctx.game.data_func_stat();
+    if func_stat != 0 {
+        queue_data_stat(ctx, func_stat);
+        if done {
+            ctx.game.set_data_func_stat(0);
+        }
+    }
+    if ctx.sysutil.dialog_type() != Some(DialogType::GameData) {
+        return;
+    }
+    let progress = ctx.game.get_install_progress();
+    ctx.sysutil.update_progress(progress);
+    if done {
+        let status = if ctx.game.is_installed() { DialogStatus::Ok } else { DialogStatus::Error };
+        ctx.sysutil.close_dialog(status);
+        ctx.sysutil.queue_event(CELL_SYSUTIL_DRAWING_END, 0);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1250,6 +2079,193 @@ mod tests {
         assert!(manager.is_update_available());
     }
 
+    #[test]
+    fn test_error_dialog() {
+        let mut manager = GameManager::new();
+        let dialog = |error_type| ContentErrorDialog {
+            error_type,
+            need_size_kb: 2048,
+            dir_name: "GAME00000".to_string(),
+        };
+        assert!(manager.open_error_dialog(dialog(3)) != 0);
+        assert_eq!(manager.open_error_dialog(dialog(CELL_GAME_ERRDIALOG_NOSPACE)), 0);
+        assert!(manager.open_error_dialog(dialog(CELL_GAME_ERRDIALOG_NOSPACE)) != 0);
+        assert!(manager.error_dialog().unwrap().message().contains("2048 KB"));
+        assert_eq!(manager.error_dialog_generation(), 1);
+
+        assert!(!manager.close_error_dialog().unwrap().exits_game());
+        assert!(manager.error_dialog().is_none());
+        assert!(dialog(CELL_GAME_ERRDIALOG_BROKEN_EXIT_HDDGAME).exits_game());
+    }
+
+    #[test]
+    fn test_content_error_dialog_api() {
+        let _guard = crate::context::test_guard();
+        crate::context::reset_hle_context();
+
+        assert_eq!(cell_game_content_error_dialog(CELL_GAME_ERRDIALOG_NOSPACE_EXIT, 512, 0), 0);
+        assert!(crate::context::get_hle_context().game.error_dialog().is_some());
+        assert!(close_content_error_dialog().unwrap().exits_game());
+        assert!(close_content_error_dialog().is_none());
+
+        let memory = crate::context::test_guest_memory();
+        let name_addr = memory.allocate(0x20, 0x10, oc_memory::PageFlags::RW).unwrap();
+        memory.write_bytes(name_addr, b"BLUS30001DATA\0").unwrap();
+        assert_eq!(cell_game_content_error_dialog(CELL_GAME_ERRDIALOG_BROKEN_GAMEDATA, 0, name_addr), 0);
+        let ctx = crate::context::get_hle_context();
+        assert_eq!(ctx.game.error_dialog().unwrap().dir_name, "BLUS30001DATA");
+    }
+
+    #[test]
+    fn test_hdd_game_check() {
+        let root = std::env::temp_dir().join(format!("oc_hle_hddgame_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let mut manager = GameManager::new();
+        assert_eq!(manager.hdd_game_check("NPUB12345"), Err(CELL_HDDGAME_ERROR_ACCESS_ERROR));
+
+        manager.set_hdd0_root(root.clone());
+        manager.set_hdd_free_size_kb(2048);
+        assert_eq!(manager.hdd_game_check("../NPUB12345"), Err(CELL_HDDGAME_ERROR_PARAM));
+        let stat = manager.hdd_game_check("NPUB12345").unwrap();
+        assert!(stat.is_new_data);
+        assert_eq!(stat.hdd_free_size_kb, 2048);
+        assert_eq!(stat.game_data_path, "/dev_hdd0/game/NPUB12345/USRDIR");
+        assert_eq!(stat.size_kb, 0);
+        assert!(root.join("game/NPUB12345/USRDIR").is_dir());
+
+        std::fs::write(root.join("game/NPUB12345/PARAM.SFO"), [0u8; 100]).unwrap();
+        std::fs::write(root.join("game/NPUB12345/USRDIR/data.bin"), vec![0u8; 3000]).unwrap();
+        assert_eq!(manager.hdd_game_size_kb(), Some(4));
+        let stat = manager.hdd_game_check("NPUB12345").unwrap();
+        assert!(!stat.is_new_data);
+        assert_eq!((stat.size_kb, stat.sys_size_kb), (4, 1));
+
+        manager.boot_check();
+        std::fs::create_dir_all(root.join("game/GAME00000")).unwrap();
+        std::fs::write(root.join("game/GAME00000/EBOOT.BIN"), vec![0u8; 4096]).unwrap();
+        assert_eq!(manager.get_size_kb(), 4);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_hdd_game_check_guest() {
+        let _guard = crate::context::test_guard();
+        crate::context::reset_hle_context();
+        let memory = crate::context::test_guest_memory();
+        let root = std::env::temp_dir().join(format!("oc_hle_hddgame_api_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        crate::context::get_hle_context_mut().game.set_hdd0_root(root.clone());
+        let addr = memory.allocate(0x100, 0x10, oc_memory::PageFlags::RW).unwrap();
+        memory.write_bytes(addr, b"NPUB12345\0").unwrap();
+
+        assert_eq!(cell_hdd_game_check(0, addr, 0, 0, 0), CELL_HDDGAME_ERROR_PARAM);
+        assert_eq!(cell_hdd_game_check(0, 0, 0, 0x1000, 0), CELL_HDDGAME_ERROR_PARAM);
+        assert_eq!(cell_hdd_game_check(0, addr, 0, 0x1000, 0), 0);
+        assert!(root.join("game/NPUB12345/USRDIR").is_dir());
+
+        // funcStat gets the result, the directory's status and the settings to fill in
+        let calls = crate::context::get_hle_context_mut().guest_calls.take();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].opd, 0x1000);
+        let get = calls[0].args[1] as u32;
+        assert_eq!(memory.read_be32(get + 4).unwrap(), 1); // isNewData
+        assert_eq!(crate::context::read_guest_string(get + 8, 64).unwrap(), "/dev_hdd0/game/NPUB12345");
+        let usrdir = get + 8 + CELL_HDDGAME_PATH_MAX as u32;
+        assert_eq!(crate::context::read_guest_string(usrdir, 64).unwrap(), "/dev_hdd0/game/NPUB12345/USRDIR");
+
+        std::fs::write(root.join("game/NPUB12345/USRDIR/data.bin"), vec![0u8; 2048]).unwrap();
+        assert_eq!(cell_hdd_game_get_size_kb(addr), 0);
+        assert_eq!(memory.read_be32(addr).unwrap(), 2);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_data_install() {
+        let root = std::env::temp_dir().join(format!("oc_hle_datainstall_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let disc = root.join("bdvd");
+        std::fs::create_dir_all(disc.join("PS3_GAME/USRDIR/data")).unwrap();
+        std::fs::write(disc.join("PS3_GAME/USRDIR/data/level.dat"), vec![7u8; 3000]).unwrap();
+        std::fs::write(disc.join("PS3_GAME/USRDIR/boot.bin"), vec![1u8; 1024]).unwrap();
+
+        let mut manager = GameManager::new();
+        manager.set_hdd0_root(root.join("hdd0"));
+        manager.set_bdvd_root(Some(disc));
+        manager.set_hdd_free_size_kb(1024);
+        assert_eq!(manager.data_check_create("BLUS00001"), Err(CELL_GAMEDATA_ERROR_NOSPACE));
+
+        // Without copying, progress runs over the simulated size
+        manager.set_hdd_free_size_kb(SIMULATED_INSTALL_SIZE_KB);
+        assert_eq!(manager.data_check_create("BLUS00002"), Ok(true));
+        assert!(!manager.advance_data_install(SIMULATED_INSTALL_SIZE_KB / 2));
+        assert_eq!(manager.get_install_progress(), 50);
+        assert!(manager.advance_data_install(SIMULATED_INSTALL_SIZE_KB));
+        assert!(manager.is_installed());
+        assert_eq!(manager.data_check_create("BLUS00002"), Ok(false));
+        assert!(!manager.is_data_installing());
+
+        manager.reset_installation();
+        manager.set_copy_disc_data(true);
+        assert_eq!(manager.data_check_create("BLUS00003"), Ok(true));
+        assert_eq!(manager.get_install_info().total_size_kb, 4);
+        let mut steps = 0;
+        while !manager.advance_data_install(1) {
+            steps += 1;
+        }
+        assert!(steps >= 3);
+        assert!(manager.is_installed());
+        let usrdir = root.join("hdd0/game/BLUS00003/USRDIR");
+        assert_eq!(std::fs::read(usrdir.join("data/level.dat")).unwrap(), vec![7u8; 3000]);
+        assert_eq!(std::fs::read(usrdir.join("boot.bin")).unwrap().len(), 1024);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_data_install_dialog() {
+        let _guard = crate::context::test_guard();
+        crate::context::reset_hle_context();
+        let root = std::env::temp_dir().join(format!("oc_hle_datainstall_api_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        crate::context::get_hle_context_mut().game.set_hdd0_root(root.clone());
+        let memory = crate::context::test_guest_memory();
+        let name_addr = memory.allocate(0x20, 0x10, oc_memory::PageFlags::RW).unwrap();
+        memory.write_bytes(name_addr, b"BLUS00001DATA\0").unwrap();
+
+        assert_eq!(cell_game_data_check_create2(0, name_addr, 0, 0, 0), CELL_GAMEDATA_ERROR_PARAM);
+        assert_eq!(cell_game_data_check_create2(0, 0, 0, 0x10000, 0), CELL_GAMEDATA_ERROR_PARAM);
+        assert_eq!(cell_game_data_check_create2(0, name_addr, 0, 0x10000, 0), 0);
+        assert_eq!(crate::context::get_hle_context().sysutil.dialog_type(), Some(DialogType::GameData));
+
+        // funcStat hears of the new directory, then of the size installed each frame
+        let size_kb_offset = STAT_GET_OFFSET + 2144 + 2972;
+        let mut sizes = Vec::new();
+        for _ in 0..5 {
+            let calls = crate::context::get_hle_context_mut().guest_calls.take();
+            assert!(calls.iter().all(|call| call.opd == 0x10000));
+            for call in calls {
+                assert_eq!(memory.read_be32(call.args[1] as u32 + 4).unwrap(), 1); // isNewData
+                sizes.push(memory.read_be32(call.args[0] as u32 + size_kb_offset).unwrap() as u64);
+            }
+            run_data_install(SIMULATED_INSTALL_SIZE_KB / 4);
+        }
+        let quarter = SIMULATED_INSTALL_SIZE_KB / 4;
+        assert_eq!(sizes, [0, quarter, 2 * quarter, 3 * quarter, 4 * quarter]);
+        let ctx = crate::context::get_hle_context();
+        assert!(ctx.game.is_installed());
+        assert_eq!(ctx.sysutil.get_dialog_status(), DialogStatus::Ok);
+        assert!(root.join("game/BLUS00001DATA/USRDIR").is_dir());
+        drop(ctx);
+
+        // Checking it again passes the installed directory once
+        crate::context::get_hle_context_mut().game.reset_installation();
+        assert_eq!(cell_game_data_check_create2(0, name_addr, 0, 0x10000, 0), 0);
+        run_data_install(quarter);
+        let calls = crate::context::get_hle_context_mut().guest_calls.take();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(memory.read_be32(calls[0].args[1] as u32 + 4).unwrap(), 0);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
     #[test]
     fn test_game_update_state_enum() {
         assert_eq!(GameUpdateState::NoUpdate as u32, 0);
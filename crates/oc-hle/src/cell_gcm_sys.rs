@@ -3,12 +3,24 @@
 //! This module provides HLE implementations for the PS3's RSX graphics system.
 //! It manages display buffers, graphics memory, and the command FIFO.
 
-use std::collections::HashMap;
+use oc_core::savestate::{invalid, StateReader, StateWriter};
+use std::collections::{HashMap, VecDeque};
+use std::io;
 use tracing::{debug, trace};
 
 /// Maximum number of display buffers
 pub const CELL_GCM_MAX_DISPLAY_BUFFERS: usize = 8;
 
+/// Flip status values
+pub const CELL_GCM_DISPLAY_FLIP_STATUS_DONE: u32 = 0;
+pub const CELL_GCM_DISPLAY_FLIP_STATUS_WAITING: u32 = 1;
+
+/// Display head the vblank and flip handlers are called for
+pub const CELL_GCM_DISPLAY_HEAD: u32 = 1;
+
+/// Handler calls kept for the game before the oldest are dropped
+const MAX_PENDING_DISPLAY_EVENTS: usize = 64;
+
 /// GCM configuration
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -66,6 +78,14 @@ pub enum CellGcmFlipMode {
     Hsync = 2,
 }
 
+/// Call due to one of the game's display handlers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GcmDisplayEvent {
+    /// Vblank handler call, with the vblank count at the time
+    VBlank { count: u64 },
+    /// Flip handler call once a flip is on screen
+    Flip { buffer_id: u32 },
+}
 
 // ============================================================================
 // RSX Backend Integration
@@ -348,6 +368,22 @@ pub struct GcmManager {
     texture_id_counter: u32,
     /// Active texture bindings (slot -> texture ID)
     texture_bindings: HashMap<u32, u32>,
+    /// Vblank handler address (0 if none)
+    vblank_handler: u32,
+    /// Flip handler address (0 if none)
+    flip_handler: u32,
+    /// Vblanks since boot
+    vblank_count: u64,
+    /// Time of the last vblank in microseconds
+    last_vblank_time: u64,
+    /// Time the last flip reached the screen in microseconds
+    last_flip_time: u64,
+    /// CELL_GCM_DISPLAY_FLIP_STATUS_* value
+    flip_status: u32,
+    /// Display buffer waiting for the next vblank to be shown
+    pending_flip: Option<u32>,
+    /// Handler calls waiting to be made
+    pending_events: VecDeque<GcmDisplayEvent>,
 }
 
 impl GcmManager {
@@ -367,6 +403,14 @@ impl GcmManager {
             render_target: CellGcmSurface::default(),
             texture_id_counter: 0,
             texture_bindings: HashMap::new(),
+            vblank_handler: 0,
+            flip_handler: 0,
+            vblank_count: 0,
+            last_vblank_time: 0,
+            last_flip_time: 0,
+            flip_status: CELL_GCM_DISPLAY_FLIP_STATUS_DONE,
+            pending_flip: None,
+            pending_events: VecDeque::new(),
         }
     }
 
@@ -427,7 +471,7 @@ impl GcmManager {
         }
 
         trace!("GcmManager::set_flip: buffer_id={}", buffer_id);
-        self.current_buffer = buffer_id;
+        self.request_flip(buffer_id);
         
         // Queue flip command to RSX command buffer
         let _ = self.submit_command(0x0001, buffer_id);
@@ -435,6 +479,101 @@ impl GcmManager {
         0 // CELL_OK
     }
 
+    // ========================================================================
+    // Vblank and Flip Timing
+    // ========================================================================
+
+    /// Show `buffer_id`, at the next vblank in VSYNC mode or at once in HSYNC mode
+    ///
+    /// Called for flips the RSX processes from the FIFO as well as HLE ones.
+    /// A second flip before the vblank replaces the first, which never shows.
+    pub fn request_flip(&mut self, buffer_id: u32) {
+        match self.flip_mode {
+            CellGcmFlipMode::Vsync => self.pending_flip = Some(buffer_id),
+            CellGcmFlipMode::Hsync => self.complete_flip(buffer_id, self.last_vblank_time),
+        }
+    }
+
+    fn complete_flip(&mut self, buffer_id: u32, time_us: u64) {
+        trace!("GcmManager: flip to buffer {} done", buffer_id);
+        self.current_buffer = buffer_id;
+        self.flip_status = CELL_GCM_DISPLAY_FLIP_STATUS_DONE;
+        self.last_flip_time = time_us;
+        if self.flip_handler != 0 {
+            self.queue_event(GcmDisplayEvent::Flip { buffer_id });
+        }
+    }
+
+    fn queue_event(&mut self, event: GcmDisplayEvent) {
+        if self.pending_events.len() >= MAX_PENDING_DISPLAY_EVENTS {
+            // A handler that isn't serviced misses vblanks like on the console
+            self.pending_events.pop_front();
+        }
+        self.pending_events.push_back(event);
+    }
+
+    /// Raise a vblank at `time_us`, showing the flip waiting for it
+    pub fn vblank(&mut self, time_us: u64) {
+        self.vblank_count += 1;
+        self.last_vblank_time = time_us;
+        if let Some(buffer_id) = self.pending_flip.take() {
+            self.complete_flip(buffer_id, time_us);
+        }
+        if self.vblank_handler != 0 {
+            self.queue_event(GcmDisplayEvent::VBlank { count: self.vblank_count });
+        }
+    }
+
+    /// Set the vblank handler (0 to remove it)
+    pub fn set_vblank_handler(&mut self, handler: u32) {
+        self.vblank_handler = handler;
+    }
+
+    /// Vblank handler address
+    pub fn vblank_handler(&self) -> u32 {
+        self.vblank_handler
+    }
+
+    /// Set the flip handler (0 to remove it)
+    pub fn set_flip_handler(&mut self, handler: u32) {
+        self.flip_handler = handler;
+    }
+
+    /// Flip handler address
+    pub fn flip_handler(&self) -> u32 {
+        self.flip_handler
+    }
+
+    /// Take the handler calls due since the last call
+    pub fn take_events(&mut self) -> Vec<GcmDisplayEvent> {
+        self.pending_events.drain(..).collect()
+    }
+
+    /// CELL_GCM_DISPLAY_FLIP_STATUS_* of the last flip
+    pub fn flip_status(&self) -> u32 {
+        self.flip_status
+    }
+
+    /// Mark the flip as waiting, before the game issues the next one
+    pub fn reset_flip_status(&mut self) {
+        self.flip_status = CELL_GCM_DISPLAY_FLIP_STATUS_WAITING;
+    }
+
+    /// Vblanks since boot
+    pub fn vblank_count(&self) -> u64 {
+        self.vblank_count
+    }
+
+    /// Time the last flip reached the screen in microseconds
+    pub fn last_flip_time(&self) -> u64 {
+        self.last_flip_time
+    }
+
+    /// Display buffer on screen
+    pub fn current_buffer(&self) -> u32 {
+        self.current_buffer
+    }
+
     /// Set display buffer configuration
     pub fn set_display_buffer(
         &mut self,
@@ -737,6 +876,82 @@ impl GcmManager {
         
         0 // CELL_OK
     }
+    /// Save the configuration and display state for a savestate
+    ///
+    /// Queued commands and texture bindings are rebuilt by the game as it draws.
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.bool(self.initialized);
+        let config = &self.config;
+        for value in [
+            config.local_addr,
+            config.local_size,
+            config.io_addr,
+            config.io_size,
+            config.mem_frequency,
+            config.core_frequency,
+        ] {
+            w.u32(value);
+        }
+        for buffer in &self.display_buffers {
+            for value in [buffer.offset, buffer.pitch, buffer.width, buffer.height] {
+                w.u32(value);
+            }
+        }
+        w.u32(self.flip_mode as u32);
+        w.u32(self.current_buffer);
+        w.u32(self.context_addr);
+        w.u32(self.context_size);
+        w.u32(self.vblank_handler);
+        w.u32(self.flip_handler);
+        w.u64(self.vblank_count);
+        w.u64(self.last_vblank_time);
+        w.u64(self.last_flip_time);
+        w.u32(self.flip_status);
+    }
+
+    /// Restore state written by [`GcmManager::save_state`]
+    pub fn load_state(&mut self, r: &mut StateReader) -> io::Result<()> {
+        self.initialized = r.bool()?;
+        self.config = CellGcmConfig {
+            local_addr: r.u32()?,
+            local_size: r.u32()?,
+            io_addr: r.u32()?,
+            io_size: r.u32()?,
+            mem_frequency: r.u32()?,
+            core_frequency: r.u32()?,
+        };
+        for buffer in &mut self.display_buffers {
+            *buffer = CellGcmDisplayBuffer {
+                offset: r.u32()?,
+                pitch: r.u32()?,
+                width: r.u32()?,
+                height: r.u32()?,
+            };
+        }
+        self.flip_mode = match r.u32()? {
+            1 => CellGcmFlipMode::Vsync,
+            2 => CellGcmFlipMode::Hsync,
+            mode => return Err(invalid(&format!("invalid GCM flip mode {}", mode))),
+        };
+        self.current_buffer = r.u32()?;
+        self.context_addr = r.u32()?;
+        self.context_size = r.u32()?;
+        self.vblank_handler = r.u32()?;
+        self.flip_handler = r.u32()?;
+        self.vblank_count = r.u64()?;
+        self.last_vblank_time = r.u64()?;
+        self.last_flip_time = r.u64()?;
+        self.flip_status = r.u32()?;
+        self.pending_flip = None;
+        self.pending_events.clear();
+        self.rsx_state = if self.initialized {
+            RsxConnectionState::Connected
+        } else {
+            RsxConnectionState::Disconnected
+        };
+        self.command_buffer = CommandBuffer::default();
+        Ok(())
+    }
 }
 
 impl Default for GcmManager {
@@ -804,6 +1019,74 @@ pub fn cell_gcm_set_flip(buffer_id: u32) -> i32 {
     crate::context::get_hle_context_mut().gcm.set_flip(buffer_id)
 }
 
+/// cellGcmSetVBlankHandler - Set the function called on every vblank
+///
+/// # Arguments
+/// * `handler` - Handler address, 0 to remove it
+pub fn cell_gcm_set_vblank_handler(handler: u32) -> i32 {
+    debug!("cellGcmSetVBlankHandler(handler=0x{:08X})", handler);
+
+    crate::context::get_hle_context_mut().gcm.set_vblank_handler(handler);
+    0 // CELL_OK
+}
+
+/// cellGcmSetFlipHandler - Set the function called once a flip is on screen
+///
+/// # Arguments
+/// * `handler` - Handler address, 0 to remove it
+pub fn cell_gcm_set_flip_handler(handler: u32) -> i32 {
+    debug!("cellGcmSetFlipHandler(handler=0x{:08X})", handler);
+
+    crate::context::get_hle_context_mut().gcm.set_flip_handler(handler);
+    0 // CELL_OK
+}
+
+/// cellGcmGetFlipStatus - Check whether the last flip is on screen
+///
+/// # Returns
+/// * CELL_GCM_DISPLAY_FLIP_STATUS_DONE once it is
+pub fn cell_gcm_get_flip_status() -> u32 {
+    let status = crate::context::get_hle_context().gcm.flip_status();
+    trace!("cellGcmGetFlipStatus() -> {}", status);
+    status
+}
+
+/// cellGcmResetFlipStatus - Mark the flip status as waiting
+pub fn cell_gcm_reset_flip_status() -> i32 {
+    trace!("cellGcmResetFlipStatus()");
+
+    crate::context::get_hle_context_mut().gcm.reset_flip_status();
+    0 // CELL_OK
+}
+
+/// cellGcmGetLastFlipTime - Get the time the last flip reached the screen
+///
+/// # Returns
+/// * Time in microseconds
+pub fn cell_gcm_get_last_flip_time() -> u64 {
+    crate::context::get_hle_context().gcm.last_flip_time()
+}
+
+/// cellGcmGetVBlankCount - Get the number of vblanks since boot
+pub fn cell_gcm_get_vblank_count() -> u64 {
+    crate::context::get_hle_context().gcm.vblank_count()
+}
+
+/// cellGcmGetCurrentDisplayBufferId - Get the display buffer on screen
+///
+/// # Arguments
+/// * `id_addr` - Address to write the buffer ID
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_gcm_get_current_display_buffer_id(_id_addr: u32) -> i32 {
+    let _buffer_id = crate::context::get_hle_context().gcm.current_buffer();
+
+    // Note: Writing the buffer ID to memory requires memory subsystem integration
+
+    0 // CELL_OK
+}
+
 /// cellGcmSetDisplayBuffer - Configure display buffer
 ///
 /// # Arguments
@@ -1181,6 +1464,43 @@ mod tests {
         assert_eq!(config.local_addr, 0xC0000000);
     }
 
+    #[test]
+    fn test_gcm_vblank_flip() {
+        let mut manager = GcmManager::new();
+        manager.init(0x10000000, 1024 * 1024);
+        manager.set_vblank_handler(0x20000);
+        manager.set_flip_handler(0x30000);
+
+        // VSYNC flips wait for the vblank
+        manager.reset_flip_status();
+        assert_eq!(manager.set_flip(1), 0);
+        assert_eq!(manager.flip_status(), CELL_GCM_DISPLAY_FLIP_STATUS_WAITING);
+        assert_eq!(manager.current_buffer(), 0);
+        manager.vblank(16683);
+        assert_eq!(manager.flip_status(), CELL_GCM_DISPLAY_FLIP_STATUS_DONE);
+        assert_eq!((manager.current_buffer(), manager.last_flip_time()), (1, 16683));
+        assert_eq!(
+            manager.take_events(),
+            [GcmDisplayEvent::Flip { buffer_id: 1 }, GcmDisplayEvent::VBlank { count: 1 }]
+        );
+
+        // HSYNC flips show at once
+        manager.set_flip_mode(CellGcmFlipMode::Hsync);
+        manager.reset_flip_status();
+        manager.request_flip(0);
+        assert_eq!(manager.flip_status(), CELL_GCM_DISPLAY_FLIP_STATUS_DONE);
+        assert_eq!(manager.take_events(), [GcmDisplayEvent::Flip { buffer_id: 0 }]);
+
+        manager.set_flip_handler(0);
+        for i in 0..100 {
+            manager.vblank(i * 16683);
+        }
+        assert_eq!(manager.vblank_count(), 101);
+        let events = manager.take_events();
+        assert_eq!(events.len(), MAX_PENDING_DISPLAY_EVENTS);
+        assert_eq!(events.last(), Some(&GcmDisplayEvent::VBlank { count: 101 }));
+    }
+
     #[test]
     fn test_gcm_manager_address_conversion() {
         let mut manager = GcmManager::new();
@@ -1214,6 +1534,7 @@ mod tests {
     #[test]
     fn test_gcm_init() {
         // Reset context first to ensure clean state
+        let _guard = crate::context::test_guard();
         crate::context::reset_hle_context();
         
         let result = cell_gcm_init(0x10000000, 1024 * 1024, 0);
@@ -1237,6 +1558,7 @@ mod tests {
     #[test]
     fn test_set_flip_mode() {
         // Reset context and initialize GCM
+        let _guard = crate::context::test_guard();
         crate::context::reset_hle_context();
         crate::context::get_hle_context_mut().gcm.init(0x10000000, 1024 * 1024);
         
@@ -1247,6 +1569,7 @@ mod tests {
     #[test]
     fn test_display_buffer_validation() {
         // Reset context and initialize GCM
+        let _guard = crate::context::test_guard();
         crate::context::reset_hle_context();
         crate::context::get_hle_context_mut().gcm.init(0x10000000, 1024 * 1024);
         
@@ -1469,6 +1792,7 @@ mod tests {
 
     #[test]
     fn test_gcm_flush() {
+        let _guard = crate::context::test_guard();
         crate::context::reset_hle_context();
         crate::context::get_hle_context_mut().gcm.init(0x10000000, 1024 * 1024);
         
@@ -1477,6 +1801,7 @@ mod tests {
 
     #[test]
     fn test_gcm_finish() {
+        let _guard = crate::context::test_guard();
         crate::context::reset_hle_context();
         crate::context::get_hle_context_mut().gcm.init(0x10000000, 1024 * 1024);
         
@@ -1485,6 +1810,7 @@ mod tests {
 
     #[test]
     fn test_gcm_set_texture() {
+        let _guard = crate::context::test_guard();
         crate::context::reset_hle_context();
         crate::context::get_hle_context_mut().gcm.init(0x10000000, 1024 * 1024);
         
@@ -1494,6 +1820,7 @@ mod tests {
 
     #[test]
     fn test_gcm_texture_functions() {
+        let _guard = crate::context::test_guard();
         crate::context::reset_hle_context();
         crate::context::get_hle_context_mut().gcm.init(0x10000000, 1024 * 1024);
         
@@ -1505,6 +1832,7 @@ mod tests {
 
     #[test]
     fn test_gcm_render_target_functions() {
+        let _guard = crate::context::test_guard();
         crate::context::reset_hle_context();
         crate::context::get_hle_context_mut().gcm.init(0x10000000, 1024 * 1024);
         
@@ -2,8 +2,18 @@
 //!
 //! This module provides HLE implementations for the PS3's HTTP client library.
 //! Supports HTTP/1.0 and HTTP/1.1 with transaction-based request/response handling.
-
+//! Requests go through the title's [`NetCapture`], which can record them or
+//! answer them from a recording, and the ones that go online are sent to the
+//! server the title's [`HostTable`] redirects their domain to.
+
+use crate::context::{guest_memory, read_guest_string};
+use crate::net_capture::{Body, HttpExchange, NetCapture};
+use crate::net_hosts::{self, HostTable};
+use oc_core::config::NetCaptureMode;
+use oc_memory::BeValue;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::net::IpAddr;
 use tracing::{debug, trace};
 
 // Error codes
@@ -36,6 +46,38 @@ pub enum CellHttpMethod {
     Connect = 7,
 }
 
+impl CellHttpMethod {
+    /// Method as it appears in a request line
+    pub fn name(self) -> &'static str {
+        match self {
+            CellHttpMethod::Get => "GET",
+            CellHttpMethod::Post => "POST",
+            CellHttpMethod::Head => "HEAD",
+            CellHttpMethod::Put => "PUT",
+            CellHttpMethod::Delete => "DELETE",
+            CellHttpMethod::Options => "OPTIONS",
+            CellHttpMethod::Trace => "TRACE",
+            CellHttpMethod::Connect => "CONNECT",
+        }
+    }
+
+    /// Method named in a request line
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            CellHttpMethod::Get,
+            CellHttpMethod::Post,
+            CellHttpMethod::Head,
+            CellHttpMethod::Put,
+            CellHttpMethod::Delete,
+            CellHttpMethod::Options,
+            CellHttpMethod::Trace,
+            CellHttpMethod::Connect,
+        ]
+        .into_iter()
+        .find(|method| method.name().eq_ignore_ascii_case(name))
+    }
+}
+
 /// HTTP version
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -58,6 +100,46 @@ enum TransactionState {
 /// HTTP status code
 pub type CellHttpStatusCode = u32;
 
+/// URI of a transaction, as the guest passes it (CellHttpUri)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, BeValue)]
+pub struct CellHttpUri {
+    /// Addresses of NUL-terminated strings
+    pub scheme: u32,
+    pub hostname: u32,
+    pub username: u32,
+    pub password: u32,
+    pub path: u32,
+    pub port: u32,
+    pub reserved: [u8; 4],
+}
+
+impl CellHttpUri {
+    /// Longest URI component read from guest memory
+    const MAX_COMPONENT: u32 = 2048;
+
+    /// URL of the URI, reading its components from guest memory
+    fn read_url(&self) -> Option<String> {
+        let scheme = read_guest_string(self.scheme, Self::MAX_COMPONENT)?;
+        let hostname = read_guest_string(self.hostname, Self::MAX_COMPONENT)?;
+        let path = read_guest_string(self.path, Self::MAX_COMPONENT).unwrap_or_default();
+        let user = read_guest_string(self.username, Self::MAX_COMPONENT).filter(|user| !user.is_empty());
+        let userinfo = match (user, read_guest_string(self.password, Self::MAX_COMPONENT)) {
+            (Some(user), Some(password)) if !password.is_empty() => format!("{}:{}@", user, password),
+            (Some(user), _) => format!("{}@", user),
+            (None, _) => String::new(),
+        };
+        let default_port = if scheme.eq_ignore_ascii_case("https") { 443 } else { 80 };
+        let port = match self.port {
+            0 => String::new(),
+            port if port == default_port => String::new(),
+            port => format!(":{}", port),
+        };
+        let slash = if path.starts_with('/') { "" } else { "/" };
+        Some(format!("{}://{}{}{}{}{}", scheme, userinfo, hostname, port, slash, path))
+    }
+}
+
 /// HTTP header
 #[repr(C)]
 #[derive(Debug, Clone)]
@@ -83,6 +165,7 @@ struct TransactionEntry {
     state: TransactionState,
     request_headers: Vec<(String, String)>,
     response_headers: Vec<(String, String)>,
+    response_body: Vec<u8>,
     status_code: u32,
     content_length: u64,
     bytes_sent: u64,
@@ -97,6 +180,7 @@ impl TransactionEntry {
             state: TransactionState::Created,
             request_headers: Vec::new(),
             response_headers: Vec::new(),
+            response_body: Vec::new(),
             status_code: 0,
             content_length: 0,
             bytes_sent: 0,
@@ -121,14 +205,11 @@ impl HttpBackend {
     }
 
     /// Send HTTP request
-    fn send_request(
-        &self,
-        method: &CellHttpMethod,
-        url: &str,
-        _headers: &[(String, String)],
-        _body: &[u8],
-    ) -> Result<HttpResponse, i32> {
-        trace!("HttpBackend::send_request: {:?} {}", method, url);
+    fn send_request(&self, method: &CellHttpMethod, request: &RoutedRequest, _body: &[u8]) -> Result<HttpResponse, i32> {
+        trace!(
+            "HttpBackend::send_request: {:?} {} (server name {}, headers {:?})",
+            method, request.url, request.server_name, request.headers
+        );
 
         if self.use_real_network {
             // In a real implementation:
@@ -159,14 +240,13 @@ impl HttpBackend {
     fn send_request_with_proxy(
         &self,
         method: &CellHttpMethod,
-        url: &str,
-        headers: &[(String, String)],
+        request: &RoutedRequest,
         body: &[u8],
         proxy_host: &str,
         proxy_port: u16,
     ) -> Result<HttpResponse, i32> {
         trace!("HttpBackend::send_request_with_proxy: {:?} {} via {}:{}", 
-               method, url, proxy_host, proxy_port);
+               method, request.url, proxy_host, proxy_port);
 
         if self.use_real_network {
             // In a real implementation:
@@ -177,7 +257,35 @@ impl HttpBackend {
         }
 
         // Fall back to regular request
-        self.send_request(method, url, headers, body)
+        self.send_request(method, request, body)
+    }
+}
+
+/// Request as it goes out once the host table has been applied
+#[derive(Debug)]
+struct RoutedRequest<'a> {
+    url: Cow<'a, str>,
+    /// TLS SNI name
+    server_name: String,
+    headers: Cow<'a, [(String, String)]>,
+}
+
+impl<'a> RoutedRequest<'a> {
+    /// Route `url` through `hosts`. A domain redirected to a bare address keeps
+    /// its own Host header and SNI name, so the server still knows which site
+    /// and certificate the game asked for.
+    fn new(hosts: &HostTable, url: &'a str, headers: &'a [(String, String)]) -> Self {
+        let routed = hosts.redirect_url(url);
+        let routed_host = net_hosts::url_host(&routed);
+        if routed_host == net_hosts::url_host(url) || routed_host.parse::<IpAddr>().is_err() {
+            let server_name = routed_host.to_string();
+            return Self { url: routed, server_name, headers: Cow::Borrowed(headers) };
+        }
+        let mut headers = headers.to_vec();
+        if !headers.iter().any(|(name, _)| name.eq_ignore_ascii_case("Host")) {
+            headers.push((String::from("Host"), net_hosts::url_host_port(url).to_string()));
+        }
+        Self { url: routed, server_name: net_hosts::url_host(url).to_string(), headers: Cow::Owned(headers) }
     }
 }
 
@@ -191,12 +299,22 @@ struct HttpResponse {
     body: Vec<u8>,
 }
 
+impl From<HttpExchange> for HttpResponse {
+    fn from(exchange: HttpExchange) -> Self {
+        Self {
+            status_code: exchange.status,
+            reason: exchange.reason,
+            headers: exchange.response_headers,
+            body: exchange.response_body.0,
+        }
+    }
+}
+
 /// Client entry
 #[allow(dead_code)]
 #[derive(Debug)]
 struct ClientEntry {
     transactions: HashMap<HttpTransactionId, TransactionEntry>,
-    next_transaction_id: HttpTransactionId,
     proxy_host: Option<String>,
     proxy_port: u16,
     timeout: u32,
@@ -209,7 +327,6 @@ impl ClientEntry {
     fn new() -> Self {
         Self {
             transactions: HashMap::new(),
-            next_transaction_id: 1,
             proxy_host: None,
             proxy_port: 0,
             timeout: 30000, // 30 seconds default
@@ -225,6 +342,12 @@ pub struct HttpManager {
     pool_size: u32,
     clients: HashMap<HttpClientId, ClientEntry>,
     next_client_id: HttpClientId,
+    /// Transaction handles are unique across clients, as the guest passes only the handle
+    next_transaction_id: HttpTransactionId,
+    /// Recording or replay of the title's traffic
+    capture: NetCapture,
+    /// Domain redirections of the title
+    hosts: HostTable,
 }
 
 impl HttpManager {
@@ -234,9 +357,32 @@ impl HttpManager {
             pool_size: 0,
             clients: HashMap::new(),
             next_client_id: 1,
+            next_transaction_id: 1,
+            capture: NetCapture::new(),
+            hosts: HostTable::new(),
         }
     }
 
+    /// Record requests or answer them from a recording
+    pub fn set_capture(&mut self, capture: NetCapture) {
+        self.capture = capture;
+    }
+
+    /// Recording or replay of the title's traffic
+    pub fn capture(&self) -> &NetCapture {
+        &self.capture
+    }
+
+    /// Recording or replay of the title's traffic, shared with sys_net's sockets
+    pub fn capture_mut(&mut self) -> &mut NetCapture {
+        &mut self.capture
+    }
+
+    /// Send requests for the domains in `hosts` to their replacements
+    pub fn set_hosts(&mut self, hosts: HostTable) {
+        self.hosts = hosts;
+    }
+
     /// Initialize HTTP library
     pub fn init(&mut self, pool_size: u32) -> Result<(), i32> {
         if self.is_initialized {
@@ -297,8 +443,8 @@ impl HttpManager {
 
         let client = self.clients.get_mut(&client_id).ok_or(CELL_HTTP_ERROR_INVALID_CLIENT)?;
 
-        let transaction_id = client.next_transaction_id;
-        client.next_transaction_id += 1;
+        let transaction_id = self.next_transaction_id;
+        self.next_transaction_id += 1;
 
         let transaction = TransactionEntry::new(method, url.to_string());
         client.transactions.insert(transaction_id, transaction);
@@ -318,6 +464,19 @@ impl HttpManager {
         Ok(())
     }
 
+    /// Client a transaction belongs to
+    pub fn transaction_client(&self, transaction_id: HttpTransactionId) -> Result<HttpClientId, i32> {
+        if !self.is_initialized {
+            return Err(CELL_HTTP_ERROR_NOT_INITIALIZED);
+        }
+
+        self.clients
+            .iter()
+            .find(|(_, client)| client.transactions.contains_key(&transaction_id))
+            .map(|(&client_id, _)| client_id)
+            .ok_or(CELL_HTTP_ERROR_INVALID_TRANSACTION)
+    }
+
     /// Add request header
     pub fn add_request_header(&mut self, client_id: HttpClientId, transaction_id: HttpTransactionId, name: &str, value: &str) -> Result<(), i32> {
         if !self.is_initialized {
@@ -336,8 +495,8 @@ impl HttpManager {
         Ok(())
     }
 
-    /// Send HTTP request
-    pub fn send_request(&mut self, client_id: HttpClientId, transaction_id: HttpTransactionId, data_size: u64) -> Result<(), i32> {
+    /// Send HTTP request with `body`
+    pub fn send_request(&mut self, client_id: HttpClientId, transaction_id: HttpTransactionId, body: &[u8]) -> Result<(), i32> {
         if !self.is_initialized {
             return Err(CELL_HTTP_ERROR_NOT_INITIALIZED);
         }
@@ -349,31 +508,37 @@ impl HttpManager {
             return Err(CELL_HTTP_ERROR_BUSY);
         }
 
-        // Send request through backend
-        let response = if let (Some(proxy_host), proxy_port) = (&client.proxy_host, client.proxy_port) {
-            client.backend.send_request_with_proxy(
-                &transaction.method,
-                &transaction.url,
-                &transaction.request_headers,
-                &[], // TODO: Get request body
-                proxy_host,
-                proxy_port,
-            )?
+        // Answer from the recording if there is one, otherwise send through the backend.
+        // Recordings keep the URL the game asked for, so they replay whatever the redirections.
+        let method = transaction.method.name();
+        let request = RoutedRequest::new(&self.hosts, &transaction.url, &transaction.request_headers);
+        let response = if let Some(exchange) = self.capture.replay_http(method, &transaction.url) {
+            HttpResponse::from(exchange)
+        } else if let (Some(proxy_host), proxy_port) = (&client.proxy_host, client.proxy_port) {
+            client.backend.send_request_with_proxy(&transaction.method, &request, body, proxy_host, proxy_port)?
         } else {
-            client.backend.send_request(
-                &transaction.method,
-                &transaction.url,
-                &transaction.request_headers,
-                &[], // TODO: Get request body
-            )?
+            client.backend.send_request(&transaction.method, &request, body)?
         };
+        if self.capture.mode() == NetCaptureMode::Record {
+            self.capture.record_http(HttpExchange {
+                method: method.to_string(),
+                url: transaction.url.clone(),
+                request_headers: transaction.request_headers.clone(),
+                request_body: Body(body.to_vec()),
+                status: response.status_code,
+                reason: response.reason.clone(),
+                response_headers: response.headers.clone(),
+                response_body: Body(response.body.clone()),
+            });
+        }
 
         // Update transaction with response
-        transaction.bytes_sent = data_size;
+        transaction.bytes_sent = body.len() as u64;
         transaction.state = TransactionState::RequestSent;
         transaction.status_code = response.status_code;
         transaction.response_headers = response.headers;
         transaction.content_length = response.body.len() as u64;
+        transaction.response_body = response.body;
         transaction.state = TransactionState::ResponseReceived;
 
         trace!("HttpManager::send_request: {} {} -> status {}", 
@@ -382,8 +547,8 @@ impl HttpManager {
         Ok(())
     }
 
-    /// Receive HTTP response
-    pub fn recv_response(&mut self, client_id: HttpClientId, transaction_id: HttpTransactionId, _buffer_size: u64) -> Result<u64, i32> {
+    /// Receive HTTP response body into `buffer`, returning the bytes read
+    pub fn recv_response(&mut self, client_id: HttpClientId, transaction_id: HttpTransactionId, buffer: &mut [u8]) -> Result<u64, i32> {
         if !self.is_initialized {
             return Err(CELL_HTTP_ERROR_NOT_INITIALIZED);
         }
@@ -395,9 +560,11 @@ impl HttpManager {
             return Err(CELL_HTTP_ERROR_NOT_CONNECTED);
         }
 
-        // TODO: Integrate with actual HTTP networking
-        // For now, return 0 bytes (empty response)
-        let bytes_to_read = 0u64;
+        let start = transaction.bytes_received as usize;
+        let remaining = &transaction.response_body[start.min(transaction.response_body.len())..];
+        let bytes_to_read = remaining.len().min(buffer.len());
+        buffer[..bytes_to_read].copy_from_slice(&remaining[..bytes_to_read]);
+        let bytes_to_read = bytes_to_read as u64;
         transaction.bytes_received += bytes_to_read;
 
         if transaction.bytes_received >= transaction.content_length {
@@ -508,16 +675,22 @@ pub fn cell_http_end() -> i32 {
 ///
 /// # Returns
 /// * 0 on success
-pub fn cell_http_create_client(_client_addr: u32) -> i32 {
-    debug!("cellHttpCreateClient()");
-
-    match crate::context::get_hle_context_mut().http.create_client() {
-        Ok(_client_id) => {
-            // TODO: Write client handle to memory at _client_addr
-            0 // CELL_OK
-        }
-        Err(e) => e,
+pub fn cell_http_create_client(client_addr: u32) -> i32 {
+    debug!("cellHttpCreateClient(client=0x{:08X})", client_addr);
+
+    let Some(memory) = guest_memory().filter(|_| client_addr != 0) else {
+        return CELL_HTTP_ERROR_INVALID_PARAM;
+    };
+    let mut ctx = crate::context::get_hle_context_mut();
+    let client_id = match ctx.http.create_client() {
+        Ok(client_id) => client_id,
+        Err(e) => return e,
+    };
+    if memory.write_be32(client_addr, client_id).is_err() {
+        let _ = ctx.http.destroy_client(client_id);
+        return CELL_HTTP_ERROR_INVALID_PARAM;
     }
+    0 // CELL_OK
 }
 
 /// cellHttpDestroyClient - Destroy HTTP client
@@ -540,27 +713,40 @@ pub fn cell_http_destroy_client(client: u32) -> i32 {
 ///
 /// # Arguments
 /// * `client` - Client handle
-/// * `method` - HTTP method
-/// * `url` - URL address
+/// * `method` - HTTP method name address
+/// * `uri` - CellHttpUri address
 /// * `transaction` - Transaction handle address
 ///
 /// # Returns
 /// * 0 on success
 pub fn cell_http_create_transaction(
     client: u32,
-    method: u32,
-    _url_addr: u32,
-    _transaction_addr: u32,
+    method_addr: u32,
+    uri_addr: u32,
+    transaction_addr: u32,
 ) -> i32 {
-    debug!("cellHttpCreateTransaction(client={}, method={})", client, method);
-
-    // Validate client exists through global manager
-    if !crate::context::get_hle_context().http.is_client_valid(client) {
-        return CELL_HTTP_ERROR_INVALID_CLIENT;
+    debug!("cellHttpCreateTransaction(client={}, uri=0x{:08X})", client, uri_addr);
+
+    let Some(memory) = guest_memory().filter(|_| uri_addr != 0 && transaction_addr != 0) else {
+        return CELL_HTTP_ERROR_INVALID_PARAM;
+    };
+    let Some(method) = read_guest_string(method_addr, 16).and_then(|name| CellHttpMethod::from_name(&name)) else {
+        return CELL_HTTP_ERROR_INVALID_PARAM;
+    };
+    let Some(url) = memory.read_be::<CellHttpUri>(uri_addr).ok().and_then(|uri| uri.read_url()) else {
+        return CELL_HTTP_ERROR_INVALID_PARAM;
+    };
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    let transaction_id = match ctx.http.create_transaction(client, method, &url) {
+        Ok(transaction_id) => transaction_id,
+        Err(e) => return e,
+    };
+    if memory.write_be32(transaction_addr, transaction_id).is_err() {
+        let _ = ctx.http.destroy_transaction(client, transaction_id);
+        return CELL_HTTP_ERROR_INVALID_PARAM;
     }
-
-    // Note: URL parsing and transaction creation requires memory subsystem integration
-
+    debug!("cellHttpCreateTransaction: {} {} -> transaction {}", method.name(), url, transaction_id);
     0 // CELL_OK
 }
 
@@ -574,54 +760,90 @@ pub fn cell_http_create_transaction(
 pub fn cell_http_destroy_transaction(transaction: u32) -> i32 {
     debug!("cellHttpDestroyTransaction(transaction={})", transaction);
 
-    // Validate transaction through global manager
-    // Note: Transaction tracking requires full implementation
-
-    0 // CELL_OK
+    let http = &mut crate::context::get_hle_context_mut().http;
+    match http.transaction_client(transaction).and_then(|client| http.destroy_transaction(client, transaction)) {
+        Ok(_) => 0, // CELL_OK
+        Err(e) => e,
+    }
 }
 
 /// cellHttpSendRequest - Send HTTP request
 ///
+/// The request goes out with the `size` bytes at `data` as its body, and
+/// its response is kept for [`cell_http_recv_response`].
+///
 /// # Arguments
 /// * `transaction` - Transaction handle
 /// * `data` - Request body data address
 /// * `size` - Request body size
+/// * `sent` - Address receiving the number of body bytes sent, or 0
 ///
 /// # Returns
 /// * 0 on success
-pub fn cell_http_send_request(transaction: u32, _data_addr: u32, size: u64) -> i32 {
-    trace!("cellHttpSendRequest(transaction={}, size={})", transaction, size);
-
-    // Verify HTTP manager is initialized
-    if !crate::context::get_hle_context().http.is_initialized() {
-        return CELL_HTTP_ERROR_NOT_INITIALIZED;
+pub fn cell_http_send_request(transaction: u32, data_addr: u32, size: u32, sent_addr: u32) -> i32 {
+    trace!("cellHttpSendRequest(transaction={}, data=0x{:08X}, size={})", transaction, data_addr, size);
+
+    let Some(memory) = guest_memory() else {
+        return CELL_HTTP_ERROR_INVALID_PARAM;
+    };
+    let body = match (data_addr, size) {
+        (_, 0) => Vec::new(),
+        (0, _) => return CELL_HTTP_ERROR_INVALID_PARAM,
+        _ => match memory.read_bytes(data_addr, size) {
+            Ok(body) => body,
+            Err(_) => return CELL_HTTP_ERROR_INVALID_PARAM,
+        },
+    };
+
+    let http = &mut crate::context::get_hle_context_mut().http;
+    let result = http
+        .transaction_client(transaction)
+        .and_then(|client| http.send_request(client, transaction, &body));
+    if let Err(e) = result {
+        return e;
+    }
+    if sent_addr != 0 && memory.write_be32(sent_addr, size).is_err() {
+        return CELL_HTTP_ERROR_INVALID_PARAM;
     }
-
-    // Note: Actual HTTP request sending requires network backend integration
-
     0 // CELL_OK
 }
 
 /// cellHttpRecvResponse - Receive HTTP response
 ///
+/// Copies the next part of the response body, live or replayed, into the
+/// guest buffer.
+///
 /// # Arguments
 /// * `transaction` - Transaction handle
 /// * `data` - Response buffer address
 /// * `size` - Buffer size
+/// * `recvd` - Address receiving the number of bytes copied, or 0
 ///
 /// # Returns
-/// * Number of bytes received on success
-pub fn cell_http_recv_response(transaction: u32, _data_addr: u32, size: u64) -> i64 {
-    trace!("cellHttpRecvResponse(transaction={}, size={})", transaction, size);
-
-    // Verify HTTP manager is initialized
-    if !crate::context::get_hle_context().http.is_initialized() {
-        return 0;
+/// * 0 on success
+pub fn cell_http_recv_response(transaction: u32, data_addr: u32, size: u32, recvd_addr: u32) -> i32 {
+    trace!("cellHttpRecvResponse(transaction={}, data=0x{:08X}, size={})", transaction, data_addr, size);
+
+    let Some(memory) = guest_memory().filter(|_| data_addr != 0 || size == 0) else {
+        return CELL_HTTP_ERROR_INVALID_PARAM;
+    };
+
+    let mut buffer = vec![0u8; size as usize];
+    let http = &mut crate::context::get_hle_context_mut().http;
+    let result = http
+        .transaction_client(transaction)
+        .and_then(|client| http.recv_response(client, transaction, &mut buffer));
+    let received = match result {
+        Ok(received) => received as usize,
+        Err(e) => return e,
+    };
+    if memory.write_bytes(data_addr, &buffer[..received]).is_err() {
+        return CELL_HTTP_ERROR_INVALID_PARAM;
     }
-
-    // Note: Actual HTTP response receiving requires network backend integration
-
-    0 // Return 0 bytes for now
+    if recvd_addr != 0 && memory.write_be32(recvd_addr, received as u32).is_err() {
+        return CELL_HTTP_ERROR_INVALID_PARAM;
+    }
+    0 // CELL_OK
 }
 
 /// cellHttpAddRequestHeader - Add request header
@@ -658,16 +880,23 @@ pub fn cell_http_add_request_header(
 ///
 /// # Returns
 /// * 0 on success
-pub fn cell_http_get_status_code(transaction: u32, _status_code_addr: u32) -> i32 {
+pub fn cell_http_get_status_code(transaction: u32, status_code_addr: u32) -> i32 {
     trace!("cellHttpGetStatusCode(transaction={})", transaction);
 
-    // Verify HTTP manager is initialized
-    if !crate::context::get_hle_context().http.is_initialized() {
-        return CELL_HTTP_ERROR_NOT_INITIALIZED;
+    let Some(memory) = guest_memory().filter(|_| status_code_addr != 0) else {
+        return CELL_HTTP_ERROR_INVALID_PARAM;
+    };
+    let http = &crate::context::get_hle_context().http;
+    let result = http
+        .transaction_client(transaction)
+        .and_then(|client| http.get_status_code(client, transaction));
+    let status_code = match result {
+        Ok(status_code) => status_code,
+        Err(e) => return e,
+    };
+    if memory.write_be32(status_code_addr, status_code).is_err() {
+        return CELL_HTTP_ERROR_INVALID_PARAM;
     }
-
-    // Note: Writing status code requires memory subsystem integration
-
     0 // CELL_OK
 }
 
@@ -832,10 +1061,41 @@ mod tests {
         let client_id = manager.create_client().unwrap();
         let transaction_id = manager.create_transaction(client_id, CellHttpMethod::Get, "http://example.com").unwrap();
 
-        manager.send_request(client_id, transaction_id, 0).unwrap();
+        manager.send_request(client_id, transaction_id, &[]).unwrap();
         assert_eq!(manager.get_status_code(client_id, transaction_id).unwrap(), 200);
     }
 
+    #[test]
+    fn test_http_manager_replays_capture() {
+        let path = std::env::temp_dir().join(format!("oc_http_capture_test_{}.json", std::process::id()));
+        let file = crate::net_capture::CaptureFile {
+            http: vec![HttpExchange {
+                method: "GET".to_string(),
+                url: "http://auth.example.com/hello".to_string(),
+                status: 403,
+                response_body: Body(b"denied".to_vec()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        std::fs::write(&path, serde_json::to_string(&file).unwrap()).unwrap();
+
+        let mut manager = HttpManager::new();
+        manager.init(1024 * 1024).unwrap();
+        manager.set_capture(NetCapture::open(NetCaptureMode::Replay, path.clone()).unwrap());
+        let client_id = manager.create_client().unwrap();
+        let transaction_id = manager.create_transaction(client_id, CellHttpMethod::Get, "http://auth.example.com/hello").unwrap();
+
+        manager.send_request(client_id, transaction_id, &[]).unwrap();
+        assert_eq!(manager.get_status_code(client_id, transaction_id).unwrap(), 403);
+        let mut buffer = [0u8; 4];
+        assert_eq!(manager.recv_response(client_id, transaction_id, &mut buffer), Ok(4));
+        assert_eq!(&buffer, b"deni");
+        assert_eq!(manager.recv_response(client_id, transaction_id, &mut buffer), Ok(2));
+        assert_eq!(&buffer[..2], b"ed");
+        let _ = std::fs::remove_file(&path);
+    }
+
     #[test]
     fn test_http_manager_set_proxy() {
         let mut manager = HttpManager::new();
@@ -854,10 +1114,93 @@ mod tests {
         manager.set_timeout(client_id, 60000).unwrap();
     }
 
+    #[test]
+    fn test_http_manager_records_request_body() {
+        let path = std::env::temp_dir().join(format!("oc_http_record_test_{}.json", std::process::id()));
+        let mut manager = HttpManager::new();
+        manager.init(1024 * 1024).unwrap();
+        manager.set_capture(NetCapture::open(NetCaptureMode::Record, path.clone()).unwrap());
+        let client_id = manager.create_client().unwrap();
+        let transaction_id = manager.create_transaction(client_id, CellHttpMethod::Post, "http://example.com/score").unwrap();
+        assert_eq!(manager.transaction_client(transaction_id), Ok(client_id));
+
+        manager.send_request(client_id, transaction_id, b"points=10").unwrap();
+        assert_eq!(manager.capture().file().http[0].request_body, Body(b"points=10".to_vec()));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_routed_request_keeps_host_name() {
+        let rule = |host: &str, address: &str| oc_core::config::HostOverride {
+            host: host.to_string(),
+            address: address.to_string(),
+        };
+        let hosts = HostTable::from_overrides(&[rule("auth.example.com", "10.0.0.5"), rule("cdn.example.com", "mirror.example.org")]);
+        let headers = vec![(String::from("Accept"), String::from("*/*"))];
+
+        let request = RoutedRequest::new(&hosts, "https://auth.example.com:8443/login", &headers);
+        assert_eq!(request.url, "https://10.0.0.5:8443/login");
+        assert_eq!(request.server_name, "auth.example.com");
+        assert!(request.headers.contains(&(String::from("Host"), String::from("auth.example.com:8443"))));
+
+        let request = RoutedRequest::new(&hosts, "http://cdn.example.com/patch.pkg", &headers);
+        assert_eq!(request.url, "http://mirror.example.org/patch.pkg");
+        assert_eq!(request.server_name, "mirror.example.org");
+        assert_eq!(request.headers.len(), 1);
+
+        let request = RoutedRequest::new(&hosts, "http://example.com/", &headers);
+        assert_eq!(request.server_name, "example.com");
+        assert!(matches!(request.headers, Cow::Borrowed(_)));
+    }
+
     #[test]
     fn test_http_init() {
+        let _guard = crate::context::test_guard();
         let result = cell_http_init(1024 * 1024);
         assert_eq!(result, 0);
+
+        // A transaction made through guest memory, answered from a recording
+        let path = std::env::temp_dir().join(format!("oc_http_guest_test_{}.json", std::process::id()));
+        let file = crate::net_capture::CaptureFile {
+            http: vec![HttpExchange {
+                method: "POST".to_string(),
+                url: "http://auth.example.com:8080/login".to_string(),
+                status: 201,
+                response_body: Body(b"welcome".to_vec()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        std::fs::write(&path, serde_json::to_string(&file).unwrap()).unwrap();
+        let capture = NetCapture::open(NetCaptureMode::Replay, path.clone()).unwrap();
+        crate::context::get_hle_context_mut().http.set_capture(capture);
+
+        let memory = crate::context::test_guest_memory();
+        let base = memory.allocate(0x1000, 0x1000, oc_memory::PageFlags::RW).unwrap();
+        let (method, scheme, host, path_addr, uri, out, body) =
+            (base, base + 0x10, base + 0x20, base + 0x40, base + 0x80, base + 0x100, base + 0x200);
+        memory.write_bytes(method, b"POST\0").unwrap();
+        memory.write_bytes(scheme, b"http\0").unwrap();
+        memory.write_bytes(host, b"auth.example.com\0").unwrap();
+        memory.write_bytes(path_addr, b"/login\0").unwrap();
+        let uri_value = CellHttpUri { scheme, hostname: host, path: path_addr, port: 8080, ..Default::default() };
+        memory.write_be(uri, uri_value).unwrap();
+        memory.write_bytes(body, b"user=1").unwrap();
+
+        assert_eq!(cell_http_create_client(out), 0);
+        let client = memory.read_be32(out).unwrap();
+        assert_eq!(cell_http_create_transaction(client, method, uri, out), 0);
+        let transaction = memory.read_be32(out).unwrap();
+        assert_eq!(cell_http_send_request(transaction, body, 6, out), 0);
+        assert_eq!(memory.read_be32(out).unwrap(), 6);
+        assert_eq!(cell_http_get_status_code(transaction, out), 0);
+        assert_eq!(memory.read_be32(out).unwrap(), 201);
+        assert_eq!(cell_http_recv_response(transaction, body, 0x100, out), 0);
+        assert_eq!(memory.read_be32(out).unwrap(), 7);
+        assert_eq!(memory.read_bytes(body, 7).unwrap(), b"welcome");
+        assert_eq!(cell_http_destroy_transaction(transaction), 0);
+        assert_eq!(cell_http_destroy_transaction(transaction), CELL_HTTP_ERROR_INVALID_TRANSACTION);
+        let _ = std::fs::remove_file(&path);
     }
 
     #[test]
@@ -3,6 +3,7 @@
 //! This module provides HLE implementations for PS3 keyboard input.
 //! It supports multiple keyboard layouts and key mapping.
 
+use crate::kb_layout::{self, CELL_KEYC_BS, CELL_KEYC_ENTER, CELL_KEYC_KPAD_ENTER, CELL_KEYC_TAB};
 use tracing::{debug, trace};
 
 /// Error codes
@@ -70,6 +71,41 @@ pub enum CellKbCodeType {
     Release = 1,
 }
 
+/// Code types set with cellKbSetCodeType (CELL_KB_CODETYPE_*)
+pub const CELL_KB_CODETYPE_RAW: u32 = 0;
+pub const CELL_KB_CODETYPE_ASCII: u32 = 1;
+
+/// Flags of converted keycodes: no character, keypad key
+pub const CELL_KB_RAWDAT: u16 = 0x8000;
+pub const CELL_KB_KEYPAD: u16 = 0x4000;
+
+/// Keyboard arrangements (CELL_KB_MAPPING_*)
+pub const CELL_KB_MAPPING_101: u32 = 0;
+pub const CELL_KB_MAPPING_106: u32 = 1;
+pub const CELL_KB_MAPPING_106_KANA: u32 = 2;
+pub const CELL_KB_MAPPING_GERMAN_GERMANY: u32 = 3;
+pub const CELL_KB_MAPPING_SPANISH_SPAIN: u32 = 4;
+pub const CELL_KB_MAPPING_FRENCH_FRANCE: u32 = 5;
+pub const CELL_KB_MAPPING_ITALIAN_ITALY: u32 = 6;
+pub const CELL_KB_MAPPING_DUTCH_NETHERLANDS: u32 = 7;
+pub const CELL_KB_MAPPING_PORTUGUESE_PORTUGAL: u32 = 8;
+pub const CELL_KB_MAPPING_RUSSIAN_RUSSIA: u32 = 9;
+pub const CELL_KB_MAPPING_ENGLISH_UK: u32 = 10;
+pub const CELL_KB_MAPPING_KOREAN_KOREA: u32 = 11;
+pub const CELL_KB_MAPPING_NORWEGIAN_NORWAY: u32 = 12;
+pub const CELL_KB_MAPPING_FINNISH_FINLAND: u32 = 13;
+pub const CELL_KB_MAPPING_DANISH_DENMARK: u32 = 14;
+pub const CELL_KB_MAPPING_SWEDISH_SWEDEN: u32 = 15;
+pub const CELL_KB_MAPPING_CHINESE_TRADITIONAL: u32 = 16;
+pub const CELL_KB_MAPPING_CHINESE_SIMPLIFIED: u32 = 17;
+pub const CELL_KB_MAPPING_SWISS_FRENCH_SWITZERLAND: u32 = 18;
+pub const CELL_KB_MAPPING_SWISS_GERMAN_SWITZERLAND: u32 = 19;
+pub const CELL_KB_MAPPING_CANADIAN_FRENCH_CANADA: u32 = 20;
+pub const CELL_KB_MAPPING_BELGIAN_BELGIUM: u32 = 21;
+pub const CELL_KB_MAPPING_POLISH_POLAND: u32 = 22;
+pub const CELL_KB_MAPPING_PORTUGUESE_BRAZIL: u32 = 23;
+pub const CELL_KB_MAPPING_TURKISH_TURKEY: u32 = 24;
+
 /// Keyboard modifier flags
 pub const CELL_KB_MKEY_L_CTRL: u32 = 0x01;
 pub const CELL_KB_MKEY_L_SHIFT: u32 = 0x02;
@@ -253,7 +289,15 @@ impl KbManager {
         trace!("KbManager::read: port={}", port);
 
         // Return cached keyboard data from backend
-        Ok(self.keyboard_data[port as usize])
+        let mut data = self.keyboard_data[port as usize];
+        let config = &self.configs[port as usize];
+        if config.code_type == CELL_KB_CODETYPE_ASCII {
+            let len = (data.len.max(0) as usize).min(CELL_KB_MAX_KEYCODES);
+            for code in &mut data.keycodes[..len] {
+                *code = cnv_raw_code(config.arrange, data.mkey, data.led, *code);
+            }
+        }
+        Ok(data)
     }
 
     /// Set read mode
@@ -468,6 +512,24 @@ impl Default for KbManager {
     }
 }
 
+/// Convert a raw keycode to the character it types on `arrange`
+///
+/// Characters are UCS-2. Keys that type none come back as the keycode with
+/// CELL_KB_RAWDAT set, and keypad keys have CELL_KB_KEYPAD set.
+pub fn cnv_raw_code(arrange: u32, mkey: u32, led: u32, rawcode: u16) -> u16 {
+    let c = match rawcode {
+        CELL_KEYC_ENTER | CELL_KEYC_KPAD_ENTER => Some('\n'),
+        CELL_KEYC_BS => Some('\u{8}'),
+        CELL_KEYC_TAB => Some('\t'),
+        _ => kb_layout::key_char(arrange, rawcode, mkey, led),
+    };
+    match c {
+        Some(c) if kb_layout::is_keypad(rawcode) => c as u16 | CELL_KB_KEYPAD,
+        Some(c) => c as u16,
+        None => rawcode | CELL_KB_RAWDAT,
+    }
+}
+
 /// cellKbInit - Initialize keyboard system
 ///
 /// # Arguments
@@ -589,6 +651,22 @@ pub fn cell_kb_set_led_status(port: u32, led: u32) -> i32 {
     0 // CELL_OK
 }
 
+/// cellKbCnvRawCode - Convert a raw keycode to a character
+///
+/// # Arguments
+/// * `arrange` - Keyboard arrangement (CELL_KB_MAPPING_*)
+/// * `mkey` - Modifier key flags
+/// * `led` - LED flags
+/// * `rawcode` - Raw keycode
+///
+/// # Returns
+/// * The character, or the keycode with CELL_KB_RAWDAT set
+pub fn cell_kb_cnv_raw_code(arrange: u32, mkey: u32, led: u32, rawcode: u16) -> u16 {
+    trace!("cellKbCnvRawCode(arrange={}, mkey=0x{:X}, led=0x{:X}, rawcode=0x{:X})", arrange, mkey, led, rawcode);
+
+    cnv_raw_code(arrange, mkey, led, rawcode)
+}
+
 /// cellKbClearBuf - Clear keyboard input buffer
 ///
 /// # Arguments
@@ -681,6 +759,23 @@ mod tests {
         manager.end();
     }
 
+    #[test]
+    fn test_kb_ascii_code_type() {
+        let mut manager = KbManager::new();
+        manager.init(2);
+        manager.update_keyboard_data(0, &[0x04, 0x28, 0x29, 0x59], CELL_KB_MKEY_R_SHIFT);
+        assert_eq!(manager.read(0).unwrap().keycodes[0], 0x04);
+
+        assert_eq!(manager.set_code_type(0, CELL_KB_CODETYPE_ASCII), 0);
+        let data = manager.read(0).unwrap();
+        assert_eq!(data.keycodes[..4], [b'A' as u16, 0x0A, 0x29 | CELL_KB_RAWDAT, 0x59 | CELL_KB_RAWDAT]);
+        assert_eq!(
+            cnv_raw_code(CELL_KB_MAPPING_101, 0, CELL_KB_LED_NUM_LOCK, 0x59),
+            b'1' as u16 | CELL_KB_KEYPAD
+        );
+        assert_eq!(cnv_raw_code(CELL_KB_MAPPING_GERMAN_GERMANY, 0, 0, 0x33), 'ö' as u16);
+    }
+
     #[test]
     fn test_kb_layout_values() {
         assert_eq!(CellKbLayout::Us as u32, 0);
@@ -0,0 +1,404 @@
+//! cellKey2char HLE - Key to character conversion
+//!
+//! This module provides HLE implementations for the library games use to
+//! turn cellKb key presses into text. Each handle converts with its own mode
+//! and keyboard arrangement: English mode types as a US keyboard, native
+//! mode as the arrangement's own layout. On Japanese keyboards native mode
+//! turns romaji into hiragana (katakana in the second native mode) as it is
+//! typed, or types kana directly with the kana layout or kana lock on.
+
+use crate::cell_kb::{
+    CELL_KB_LED_KANA, CELL_KB_MAPPING_101, CELL_KB_MAPPING_106, CELL_KB_MAPPING_106_KANA,
+    CELL_KB_MAPPING_TURKISH_TURKEY, CELL_KB_MKEY_L_ALT, CELL_KB_MKEY_L_CTRL, CELL_KB_MKEY_L_WIN,
+    CELL_KB_MKEY_R_CTRL, CELL_KB_MKEY_R_WIN,
+};
+use crate::cell_osk_dialog::{romaji_to_kana, to_katakana};
+use crate::kb_layout::{self, CELL_KEYC_BS, CELL_KEYC_ENTER, CELL_KEYC_KPAD_ENTER};
+use std::collections::HashMap;
+use tracing::{debug, trace};
+
+/// Error codes
+pub const CELL_K2C_ERROR_FATAL: i32 = 0x80121301u32 as i32;
+pub const CELL_K2C_ERROR_INVALID_HANDLE: i32 = 0x80121302u32 as i32;
+pub const CELL_K2C_ERROR_INVALID_PARAMETER: i32 = 0x80121303u32 as i32;
+pub const CELL_K2C_ERROR_ALREADY_INITIALIZED: i32 = 0x80121304u32 as i32;
+pub const CELL_K2C_ERROR_UNINITIALIZED: i32 = 0x80121305u32 as i32;
+pub const CELL_K2C_ERROR_OTHER: i32 = 0x80121306u32 as i32;
+
+/// Conversion modes
+pub const CELL_KEY2CHAR_MODE_ENGLISH: u32 = 0;
+pub const CELL_KEY2CHAR_MODE_NATIVE: u32 = 1;
+pub const CELL_KEY2CHAR_MODE_NATIVE2: u32 = 2;
+
+/// Size of the handle the game allocates
+pub const SCE_KEY2CHAR_HANDLE_SIZE: u32 = 128;
+
+/// Modifiers held for shortcuts rather than text
+const SHORTCUT_MKEYS: u32 =
+    CELL_KB_MKEY_L_CTRL | CELL_KB_MKEY_R_CTRL | CELL_KB_MKEY_L_ALT | CELL_KB_MKEY_L_WIN | CELL_KB_MKEY_R_WIN;
+
+/// Key press to convert
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CellKey2CharKeyData {
+    /// LED flags
+    pub led: u32,
+    /// Modifier key flags
+    pub mkey: u32,
+    /// Raw keycode
+    pub keycode: u16,
+}
+
+/// Characters a key press typed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Key2CharOutput {
+    /// UTF-16 characters
+    pub chars: Vec<u16>,
+    /// Whether the library used the key, so the game should not act on it
+    pub processed: bool,
+}
+
+impl Key2CharOutput {
+    fn typed(text: Option<&str>) -> Self {
+        match text {
+            Some(text) => Self {
+                chars: text.encode_utf16().collect(),
+                processed: true,
+            },
+            None => Self::default(),
+        }
+    }
+}
+
+/// Conversion state of one handle
+#[derive(Debug, Clone)]
+struct Key2CharHandle {
+    mode: u32,
+    arrange: u32,
+    /// Romaji of the kana being typed
+    romaji: String,
+}
+
+impl Key2CharHandle {
+    fn new() -> Self {
+        Self {
+            mode: CELL_KEY2CHAR_MODE_ENGLISH,
+            arrange: CELL_KB_MAPPING_101,
+            romaji: String::new(),
+        }
+    }
+
+    fn convert(&mut self, key: &CellKey2CharKeyData) -> Key2CharOutput {
+        if key.mkey & SHORTCUT_MKEYS != 0 {
+            return Key2CharOutput::default();
+        }
+        let japanese = matches!(self.arrange, CELL_KB_MAPPING_106 | CELL_KB_MAPPING_106_KANA);
+        let arrange = match self.mode {
+            CELL_KEY2CHAR_MODE_ENGLISH => CELL_KB_MAPPING_101,
+            _ => self.arrange,
+        };
+        if self.mode == CELL_KEY2CHAR_MODE_ENGLISH || !japanese {
+            let c = kb_layout::key_char(arrange, key.keycode, key.mkey, key.led);
+            return Key2CharOutput::typed(c.map(String::from).as_deref());
+        }
+
+        let katakana = self.mode == CELL_KEY2CHAR_MODE_NATIVE2;
+        if self.arrange == CELL_KB_MAPPING_106_KANA || key.led & CELL_KB_LED_KANA != 0 {
+            let kana = kb_layout::kana_char(key.keycode, key.mkey).map(String::from);
+            let kana = kana.map(|kana| if katakana { to_katakana(&kana) } else { kana });
+            return Key2CharOutput::typed(kana.as_deref());
+        }
+        self.compose(key, katakana)
+    }
+
+    /// Add a key to the romaji being typed, returning the kana it completes
+    fn compose(&mut self, key: &CellKey2CharKeyData, katakana: bool) -> Key2CharOutput {
+        match key.keycode {
+            CELL_KEYC_BS if !self.romaji.is_empty() => {
+                self.romaji.pop();
+                Key2CharOutput {
+                    chars: Vec::new(),
+                    processed: true,
+                }
+            }
+            // Enter ends the syllable, typing a trailing "n" as ん and other romaji as is
+            CELL_KEYC_ENTER | CELL_KEYC_KPAD_ENTER if !self.romaji.is_empty() => {
+                let romaji = std::mem::take(&mut self.romaji);
+                let text = match romaji.as_str() {
+                    "n" => romaji_to_kana("n'", katakana).0,
+                    _ => romaji,
+                };
+                Key2CharOutput::typed(Some(&text))
+            }
+            keycode => {
+                let Some(c) = kb_layout::key_char(self.arrange, keycode, key.mkey, key.led) else {
+                    return Key2CharOutput::default();
+                };
+                self.romaji.push(c);
+                let (kana, pending) = romaji_to_kana(&self.romaji, katakana);
+                self.romaji = pending;
+                Key2CharOutput::typed(Some(&kana))
+            }
+        }
+    }
+}
+
+/// Key to character conversion manager
+pub struct Key2CharManager {
+    /// Open handles by guest address
+    handles: HashMap<u32, Key2CharHandle>,
+}
+
+impl Key2CharManager {
+    /// Create a new key to character conversion manager
+    pub fn new() -> Self {
+        Self {
+            handles: HashMap::new(),
+        }
+    }
+
+    fn handle(&mut self, handle: u32) -> Result<&mut Key2CharHandle, i32> {
+        if handle == 0 {
+            return Err(CELL_K2C_ERROR_INVALID_HANDLE);
+        }
+        self.handles.get_mut(&handle).ok_or(CELL_K2C_ERROR_UNINITIALIZED)
+    }
+
+    /// Open the handle at `handle`
+    pub fn open(&mut self, handle: u32) -> i32 {
+        if handle == 0 {
+            return CELL_K2C_ERROR_INVALID_HANDLE;
+        }
+        if self.handles.contains_key(&handle) {
+            return CELL_K2C_ERROR_ALREADY_INITIALIZED;
+        }
+        self.handles.insert(handle, Key2CharHandle::new());
+        0 // CELL_OK
+    }
+
+    /// Close the handle at `handle`
+    pub fn close(&mut self, handle: u32) -> i32 {
+        if let Err(e) = self.handle(handle) {
+            return e;
+        }
+        self.handles.remove(&handle);
+        0 // CELL_OK
+    }
+
+    /// Set the conversion mode (CELL_KEY2CHAR_MODE_*)
+    pub fn set_mode(&mut self, handle: u32, mode: u32) -> i32 {
+        let state = match self.handle(handle) {
+            Ok(state) => state,
+            Err(e) => return e,
+        };
+        if mode > CELL_KEY2CHAR_MODE_NATIVE2 {
+            return CELL_K2C_ERROR_INVALID_PARAMETER;
+        }
+        state.mode = mode;
+        state.romaji.clear();
+        0 // CELL_OK
+    }
+
+    /// Set the keyboard arrangement (CELL_KB_MAPPING_*)
+    pub fn set_arrangement(&mut self, handle: u32, arrange: u32) -> i32 {
+        let state = match self.handle(handle) {
+            Ok(state) => state,
+            Err(e) => return e,
+        };
+        if arrange > CELL_KB_MAPPING_TURKISH_TURKEY {
+            return CELL_K2C_ERROR_INVALID_PARAMETER;
+        }
+        state.arrange = arrange;
+        state.romaji.clear();
+        0 // CELL_OK
+    }
+
+    /// Convert a key press with the handle at `handle`
+    pub fn get_char(&mut self, handle: u32, key: &CellKey2CharKeyData) -> Result<Key2CharOutput, i32> {
+        Ok(self.handle(handle)?.convert(key))
+    }
+
+    /// Mode and arrangement of the handle at `handle`
+    pub fn settings(&self, handle: u32) -> Option<(u32, u32)> {
+        self.handles.get(&handle).map(|state| (state.mode, state.arrange))
+    }
+}
+
+impl Default for Key2CharManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// cellKey2CharOpen - Open a conversion handle
+///
+/// # Arguments
+/// * `handle_addr` - Address of the handle
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_key2char_open(handle_addr: u32) -> i32 {
+    debug!("cellKey2CharOpen(handle=0x{:08X})", handle_addr);
+
+    crate::context::get_hle_context_mut().key2char.open(handle_addr)
+}
+
+/// cellKey2CharClose - Close a conversion handle
+///
+/// # Arguments
+/// * `handle_addr` - Address of the handle
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_key2char_close(handle_addr: u32) -> i32 {
+    debug!("cellKey2CharClose(handle=0x{:08X})", handle_addr);
+
+    crate::context::get_hle_context_mut().key2char.close(handle_addr)
+}
+
+/// cellKey2CharGetChar - Convert a key press to characters
+///
+/// # Arguments
+/// * `handle_addr` - Address of the handle
+/// * `kdata_addr` - Address of CellKey2CharKeyData
+/// * `char_code_addr` - Address to write the address of the characters
+/// * `char_num_addr` - Address to write the number of characters
+/// * `process_key_addr` - Address to write whether the key was used
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_key2char_get_char(
+    handle_addr: u32,
+    kdata_addr: u32,
+    char_code_addr: u32,
+    char_num_addr: u32,
+    process_key_addr: u32,
+) -> i32 {
+    trace!("cellKey2CharGetChar(handle=0x{:08X}, kdata=0x{:08X})", handle_addr, kdata_addr);
+
+    if handle_addr == 0 {
+        return CELL_K2C_ERROR_INVALID_HANDLE;
+    }
+    if kdata_addr == 0 || char_code_addr == 0 || char_num_addr == 0 || process_key_addr == 0 {
+        return CELL_K2C_ERROR_INVALID_PARAMETER;
+    }
+    if crate::context::get_hle_context().key2char.settings(handle_addr).is_none() {
+        return CELL_K2C_ERROR_UNINITIALIZED;
+    }
+
+    // Note: reading the key data and writing the characters requires memory subsystem integration
+    0 // CELL_OK
+}
+
+/// cellKey2CharSetMode - Set the conversion mode
+///
+/// # Arguments
+/// * `handle_addr` - Address of the handle
+/// * `mode` - Conversion mode (CELL_KEY2CHAR_MODE_*)
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_key2char_set_mode(handle_addr: u32, mode: u32) -> i32 {
+    debug!("cellKey2CharSetMode(handle=0x{:08X}, mode={})", handle_addr, mode);
+
+    crate::context::get_hle_context_mut().key2char.set_mode(handle_addr, mode)
+}
+
+/// cellKey2CharSetArrangement - Set the keyboard arrangement
+///
+/// # Arguments
+/// * `handle_addr` - Address of the handle
+/// * `arrange` - Keyboard arrangement (CELL_KB_MAPPING_*)
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_key2char_set_arrangement(handle_addr: u32, arrange: u32) -> i32 {
+    debug!("cellKey2CharSetArrangement(handle=0x{:08X}, arrange={})", handle_addr, arrange);
+
+    crate::context::get_hle_context_mut().key2char.set_arrangement(handle_addr, arrange)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell_kb::{CELL_KB_MAPPING_GERMAN_GERMANY, CELL_KB_MKEY_L_SHIFT};
+    use crate::kb_layout::CELL_KEYC_A;
+
+    const HANDLE: u32 = 0x10000;
+
+    fn press(manager: &mut Key2CharManager, keycode: u16, mkey: u32, led: u32) -> Key2CharOutput {
+        manager
+            .get_char(HANDLE, &CellKey2CharKeyData { led, mkey, keycode })
+            .unwrap()
+    }
+
+    fn text(output: &Key2CharOutput) -> String {
+        String::from_utf16_lossy(&output.chars)
+    }
+
+    #[test]
+    fn test_key2char_handles() {
+        let mut manager = Key2CharManager::new();
+        assert_eq!(manager.open(0), CELL_K2C_ERROR_INVALID_HANDLE);
+        assert_eq!(manager.open(HANDLE), 0);
+        assert_eq!(manager.open(HANDLE), CELL_K2C_ERROR_ALREADY_INITIALIZED);
+        assert_eq!(manager.settings(HANDLE), Some((CELL_KEY2CHAR_MODE_ENGLISH, CELL_KB_MAPPING_101)));
+
+        assert_eq!(manager.set_mode(HANDLE, 3), CELL_K2C_ERROR_INVALID_PARAMETER);
+        assert_eq!(manager.set_arrangement(HANDLE, 99), CELL_K2C_ERROR_INVALID_PARAMETER);
+        assert_eq!(manager.set_mode(0x20000, CELL_KEY2CHAR_MODE_NATIVE), CELL_K2C_ERROR_UNINITIALIZED);
+
+        assert_eq!(manager.close(HANDLE), 0);
+        assert_eq!(manager.close(HANDLE), CELL_K2C_ERROR_UNINITIALIZED);
+        assert!(manager.get_char(HANDLE, &CellKey2CharKeyData::default()).is_err());
+    }
+
+    #[test]
+    fn test_key2char_layouts() {
+        let mut manager = Key2CharManager::new();
+        manager.open(HANDLE);
+        manager.set_arrangement(HANDLE, CELL_KB_MAPPING_GERMAN_GERMANY);
+
+        // English mode types as a US keyboard whatever the arrangement
+        assert_eq!(text(&press(&mut manager, 0x1C, 0, 0)), "y");
+        manager.set_mode(HANDLE, CELL_KEY2CHAR_MODE_NATIVE);
+        assert_eq!(text(&press(&mut manager, 0x1C, 0, 0)), "z");
+        assert_eq!(text(&press(&mut manager, 0x34, CELL_KB_MKEY_L_SHIFT, 0)), "Ä");
+
+        // Control keys and shortcuts are left to the game
+        assert_eq!(press(&mut manager, CELL_KEYC_ENTER, 0, 0), Key2CharOutput::default());
+        assert!(!press(&mut manager, CELL_KEYC_A, CELL_KB_MKEY_L_CTRL, 0).processed);
+    }
+
+    #[test]
+    fn test_key2char_kana_input() {
+        let mut manager = Key2CharManager::new();
+        manager.open(HANDLE);
+        manager.set_arrangement(HANDLE, CELL_KB_MAPPING_106);
+        manager.set_mode(HANDLE, CELL_KEY2CHAR_MODE_NATIVE);
+
+        // Romaji becomes kana once a syllable is complete
+        let k = press(&mut manager, 0x0E, 0, 0);
+        assert!(k.processed);
+        assert!(k.chars.is_empty());
+        assert_eq!(text(&press(&mut manager, 0x04, 0, 0)), "か");
+        press(&mut manager, 0x11, 0, 0);
+        assert_eq!(text(&press(&mut manager, CELL_KEYC_ENTER, 0, 0)), "ん");
+        assert!(!press(&mut manager, CELL_KEYC_ENTER, 0, 0).processed);
+
+        // Backspace takes back romaji not yet converted
+        press(&mut manager, 0x17, 0, 0);
+        assert!(press(&mut manager, CELL_KEYC_BS, 0, 0).processed);
+        assert!(!press(&mut manager, CELL_KEYC_BS, 0, 0).processed);
+
+        manager.set_mode(HANDLE, CELL_KEY2CHAR_MODE_NATIVE2);
+        press(&mut manager, 0x0E, 0, 0);
+        assert_eq!(text(&press(&mut manager, 0x04, 0, 0)), "カ");
+
+        // Kana lock types the kana printed on the keys
+        assert_eq!(text(&press(&mut manager, CELL_KEYC_A, 0, CELL_KB_LED_KANA)), "チ");
+        manager.set_arrangement(HANDLE, CELL_KB_MAPPING_106_KANA);
+        manager.set_mode(HANDLE, CELL_KEY2CHAR_MODE_NATIVE);
+        assert_eq!(text(&press(&mut manager, 0x20, CELL_KB_MKEY_L_SHIFT, 0)), "ぁ");
+    }
+}
@@ -0,0 +1,786 @@
+//! cellMusic HLE - Custom Soundtrack Playback
+//!
+//! This module provides HLE implementations for games that let the player
+//! listen to their own music in place of the game's soundtrack. Selecting
+//! contents picks every MP3 and FLAC track in the configured music folder,
+//! as there is no XMB to choose from. The runner decodes and mixes the
+//! current track; this module only tracks the playlist and playback state
+//! and hands the events to the game's callback.
+
+use crate::cell_search::{
+    scan_folder, MusicSelection, CELL_MUSIC_SELECTION_CONTEXT_SIZE, CELL_SEARCH_REPEATMODE_ALL,
+    CELL_SEARCH_REPEATMODE_NOREPEAT1, CELL_SEARCH_REPEATMODE_REPEAT1,
+};
+use crate::guest_call::GuestCallQueue;
+use std::path::{Path, PathBuf};
+use tracing::{debug, trace};
+
+/// Result codes
+pub const CELL_MUSIC_CANCELED: i32 = 1;
+pub const CELL_MUSIC_PLAYBACK_FINISHED: i32 = 0x8002C101u32 as i32;
+pub const CELL_MUSIC_ERROR_PARAM: i32 = 0x8002C102u32 as i32;
+pub const CELL_MUSIC_ERROR_BUSY: i32 = 0x8002C103u32 as i32;
+pub const CELL_MUSIC_ERROR_NO_ACTIVE_CONTENT: i32 = 0x8002C104u32 as i32;
+pub const CELL_MUSIC_ERROR_NO_MATCH_FOUND: i32 = 0x8002C105u32 as i32;
+pub const CELL_MUSIC_ERROR_INVALID_CONTEXT: i32 = 0x8002C106u32 as i32;
+pub const CELL_MUSIC_ERROR_PLAYBACK_FAILURE: i32 = 0x8002C107u32 as i32;
+pub const CELL_MUSIC_ERROR_NO_MORE_CONTENT: i32 = 0x8002C108u32 as i32;
+pub const CELL_MUSIC_DIALOG_OPEN: i32 = 0x8002C109u32 as i32;
+pub const CELL_MUSIC_DIALOG_CLOSE: i32 = 0x8002C10Au32 as i32;
+pub const CELL_MUSIC_ERROR_GENERIC: i32 = 0x8002C1FFu32 as i32;
+
+/// Events passed to the game's callback
+pub const CELL_MUSIC_EVENT_STATUS_NOTIFICATION: u32 = 0;
+pub const CELL_MUSIC_EVENT_INITIALIZE_RESULT: u32 = 1;
+pub const CELL_MUSIC_EVENT_FINALIZE_RESULT: u32 = 2;
+pub const CELL_MUSIC_EVENT_SELECT_CONTENTS_RESULT: u32 = 3;
+pub const CELL_MUSIC_EVENT_SET_PLAYBACK_COMMAND_RESULT: u32 = 4;
+pub const CELL_MUSIC_EVENT_SET_VOLUME_RESULT: u32 = 5;
+pub const CELL_MUSIC_EVENT_SET_SELECTION_CONTEXT_RESULT: u32 = 6;
+pub const CELL_MUSIC_EVENT_UI_NOTIFICATION: u32 = 7;
+
+/// Playback commands
+pub const CELL_MUSIC_PB_CMD_STOP: i32 = 0;
+pub const CELL_MUSIC_PB_CMD_PLAY: i32 = 1;
+pub const CELL_MUSIC_PB_CMD_PAUSE: i32 = 2;
+pub const CELL_MUSIC_PB_CMD_NEXT: i32 = 3;
+pub const CELL_MUSIC_PB_CMD_PREV: i32 = 4;
+pub const CELL_MUSIC_PB_CMD_FASTFORWARD: i32 = 5;
+pub const CELL_MUSIC_PB_CMD_FASTREVERSE: i32 = 6;
+
+/// Playback status
+pub const CELL_MUSIC_PB_STATUS_STOP: i32 = 0;
+pub const CELL_MUSIC_PB_STATUS_PLAY: i32 = 1;
+pub const CELL_MUSIC_PB_STATUS_PAUSE: i32 = 2;
+pub const CELL_MUSIC_PB_STATUS_FASTFORWARD: i32 = 3;
+pub const CELL_MUSIC_PB_STATUS_FASTREVERSE: i32 = 4;
+
+/// Extensions of the tracks picked from the music folder
+pub const MUSIC_EXTENSIONS: &[&str] = &["mp3", "flac"];
+
+/// Event waiting to be passed to the game's callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MusicEvent {
+    pub event: u32,
+    pub param: i32,
+}
+
+/// Custom soundtrack manager
+pub struct MusicManager {
+    /// Initialization flag
+    initialized: bool,
+    /// Event callback address
+    callback: u32,
+    /// Callback user data
+    userdata: u32,
+    /// Host folder tracks are picked from
+    folder: Option<PathBuf>,
+    /// Selected tracks
+    playlist: Vec<PathBuf>,
+    /// Index of the current track in `playlist`
+    current: Option<usize>,
+    /// Playback status
+    status: i32,
+    /// Repeat mode of the selection
+    repeat_mode: u32,
+    /// Volume (0.0 to 1.0)
+    volume: f32,
+    /// Bumped whenever the current track restarts or changes
+    track_generation: u64,
+    /// Events waiting to be passed to the callback
+    pending_events: Vec<MusicEvent>,
+}
+
+impl MusicManager {
+    /// Create a new music manager
+    pub fn new() -> Self {
+        Self {
+            initialized: false,
+            callback: 0,
+            userdata: 0,
+            folder: None,
+            playlist: Vec::new(),
+            current: None,
+            status: CELL_MUSIC_PB_STATUS_STOP,
+            repeat_mode: CELL_SEARCH_REPEATMODE_ALL,
+            volume: 1.0,
+            track_generation: 0,
+            pending_events: Vec::new(),
+        }
+    }
+
+    /// Set the host folder tracks are picked from
+    pub fn set_folder(&mut self, folder: PathBuf) {
+        self.folder = Some(folder);
+    }
+
+    /// Initialize the library
+    pub fn init(&mut self, callback: u32, userdata: u32) -> i32 {
+        if callback == 0 {
+            return CELL_MUSIC_ERROR_PARAM;
+        }
+        if self.initialized {
+            return CELL_MUSIC_ERROR_BUSY;
+        }
+
+        debug!("MusicManager::init: callback=0x{:08X}", callback);
+
+        self.initialized = true;
+        self.callback = callback;
+        self.userdata = userdata;
+        self.queue_event(CELL_MUSIC_EVENT_INITIALIZE_RESULT, 0);
+        0 // CELL_OK
+    }
+
+    /// Shut the library down, stopping playback
+    pub fn finalize(&mut self) -> i32 {
+        if !self.initialized {
+            return CELL_MUSIC_ERROR_GENERIC;
+        }
+
+        debug!("MusicManager::finalize");
+
+        // The callback is kept to receive the finalize result
+        self.queue_event(CELL_MUSIC_EVENT_FINALIZE_RESULT, 0);
+        let events = std::mem::take(&mut self.pending_events);
+        let folder = self.folder.take();
+        let (callback, userdata) = (self.callback, self.userdata);
+        let generation = self.track_generation;
+        *self = Self::new();
+        self.pending_events = events;
+        self.folder = folder;
+        self.callback = callback;
+        self.userdata = userdata;
+        self.track_generation = generation + 1;
+        0 // CELL_OK
+    }
+
+    /// Select every track in the music folder
+    ///
+    /// The selection is cancelled when the folder holds no tracks.
+    pub fn select_contents(&mut self) -> i32 {
+        let selection = self.folder_selection();
+        self.select(CELL_MUSIC_EVENT_SELECT_CONTENTS_RESULT, CELL_MUSIC_CANCELED, selection)
+    }
+
+    /// Restore a selection the game saved
+    ///
+    /// Contexts made from cellSearch results are restored as they were; any
+    /// other context selects the whole music folder again.
+    pub fn restore_selection(&mut self, selection: Option<MusicSelection>) -> i32 {
+        let selection = selection.unwrap_or_else(|| self.folder_selection());
+        self.select(CELL_MUSIC_EVENT_SET_SELECTION_CONTEXT_RESULT, CELL_MUSIC_ERROR_INVALID_CONTEXT, selection)
+    }
+
+    fn folder_selection(&self) -> MusicSelection {
+        MusicSelection {
+            tracks: self.folder.as_deref().map(scan_music_folder).unwrap_or_default(),
+            first: 0,
+            repeat_mode: self.repeat_mode,
+        }
+    }
+
+    fn select(&mut self, event: u32, empty: i32, selection: MusicSelection) -> i32 {
+        if !self.initialized {
+            return CELL_MUSIC_ERROR_GENERIC;
+        }
+
+        debug!("MusicManager::select: {} tracks", selection.tracks.len());
+
+        if selection.tracks.is_empty() {
+            self.queue_event(event, empty);
+            return 0; // CELL_OK
+        }
+        self.current = Some(selection.first.min(selection.tracks.len() - 1));
+        self.playlist = selection.tracks;
+        self.repeat_mode = selection.repeat_mode;
+        self.status = CELL_MUSIC_PB_STATUS_STOP;
+        self.track_generation += 1;
+        self.queue_event(event, 0);
+        0 // CELL_OK
+    }
+
+    /// Set the repeat mode of the selection
+    pub fn set_repeat_mode(&mut self, mode: u32) -> i32 {
+        if mode > CELL_SEARCH_REPEATMODE_NOREPEAT1 {
+            return CELL_MUSIC_ERROR_PARAM;
+        }
+        self.repeat_mode = mode;
+        0 // CELL_OK
+    }
+
+    /// Carry out a playback command
+    ///
+    /// The command's result is passed to the callback.
+    pub fn set_playback_command(&mut self, command: i32) -> i32 {
+        if !self.initialized {
+            return CELL_MUSIC_ERROR_GENERIC;
+        }
+        if !(CELL_MUSIC_PB_CMD_STOP..=CELL_MUSIC_PB_CMD_FASTREVERSE).contains(&command) {
+            return CELL_MUSIC_ERROR_PARAM;
+        }
+
+        debug!("MusicManager::set_playback_command: command={}", command);
+
+        let result = match (command, self.current) {
+            (_, None) if command != CELL_MUSIC_PB_CMD_STOP => CELL_MUSIC_ERROR_NO_ACTIVE_CONTENT,
+            (CELL_MUSIC_PB_CMD_STOP, _) => {
+                // Playing again starts the track over
+                if self.status != CELL_MUSIC_PB_STATUS_STOP {
+                    self.track_generation += 1;
+                }
+                self.set_status(CELL_MUSIC_PB_STATUS_STOP);
+                0
+            }
+            (CELL_MUSIC_PB_CMD_PLAY, _) => {
+                self.set_status(CELL_MUSIC_PB_STATUS_PLAY);
+                0
+            }
+            (CELL_MUSIC_PB_CMD_PAUSE, _) => {
+                self.set_status(CELL_MUSIC_PB_STATUS_PAUSE);
+                0
+            }
+            (CELL_MUSIC_PB_CMD_NEXT, _) => self.skip(1),
+            (CELL_MUSIC_PB_CMD_PREV, _) => self.skip(-1),
+            (CELL_MUSIC_PB_CMD_FASTFORWARD, _) => {
+                self.set_status(CELL_MUSIC_PB_STATUS_FASTFORWARD);
+                0
+            }
+            _ => {
+                self.set_status(CELL_MUSIC_PB_STATUS_FASTREVERSE);
+                0
+            }
+        };
+        self.queue_event(CELL_MUSIC_EVENT_SET_PLAYBACK_COMMAND_RESULT, result);
+        0 // CELL_OK
+    }
+
+    /// Move `step` tracks through the playlist
+    ///
+    /// Wraps around when the whole selection repeats; otherwise running off
+    /// either end fails.
+    fn skip(&mut self, step: isize) -> i32 {
+        let Some(current) = self.current else {
+            return CELL_MUSIC_ERROR_NO_ACTIVE_CONTENT;
+        };
+        let len = self.playlist.len() as isize;
+        let next = current as isize + step;
+        let next = if (0..len).contains(&next) {
+            next
+        } else if self.repeat_mode == CELL_SEARCH_REPEATMODE_ALL {
+            next.rem_euclid(len)
+        } else {
+            return CELL_MUSIC_ERROR_NO_MORE_CONTENT;
+        };
+        self.current = Some(next as usize);
+        self.track_generation += 1;
+        0
+    }
+
+    fn set_status(&mut self, status: i32) {
+        if self.status != status {
+            self.status = status;
+            self.queue_event(CELL_MUSIC_EVENT_STATUS_NOTIFICATION, status);
+        }
+    }
+
+    /// Move on after the current track played to its end
+    pub fn track_finished(&mut self) {
+        if !self.is_playing() {
+            return;
+        }
+        trace!("MusicManager::track_finished");
+
+        match self.repeat_mode {
+            CELL_SEARCH_REPEATMODE_REPEAT1 => self.track_generation += 1,
+            CELL_SEARCH_REPEATMODE_NOREPEAT1 => self.end_playback(CELL_MUSIC_PLAYBACK_FINISHED),
+            _ => {
+                if self.skip(1) != 0 {
+                    self.current = Some(0);
+                    self.track_generation += 1;
+                    self.end_playback(CELL_MUSIC_PLAYBACK_FINISHED);
+                }
+            }
+        }
+    }
+
+    /// Drop the current track after it failed to decode and move on
+    pub fn track_failed(&mut self) {
+        let Some(current) = self.current else {
+            return;
+        };
+        debug!("MusicManager::track_failed: {}", self.playlist[current].display());
+
+        self.playlist.remove(current);
+        if self.playlist.is_empty() {
+            self.current = None;
+            self.end_playback(CELL_MUSIC_ERROR_PLAYBACK_FAILURE);
+        } else {
+            self.current = Some(current % self.playlist.len());
+            self.queue_event(CELL_MUSIC_EVENT_STATUS_NOTIFICATION, CELL_MUSIC_ERROR_PLAYBACK_FAILURE);
+        }
+        self.track_generation += 1;
+    }
+
+    fn end_playback(&mut self, reason: i32) {
+        self.status = CELL_MUSIC_PB_STATUS_STOP;
+        self.queue_event(CELL_MUSIC_EVENT_STATUS_NOTIFICATION, reason);
+    }
+
+    fn queue_event(&mut self, event: u32, param: i32) {
+        trace!("MusicManager::queue_event: event={}, param=0x{:X}", event, param);
+        self.pending_events.push(MusicEvent { event, param });
+    }
+
+    /// Take the events waiting to be passed to the callback
+    pub fn take_events(&mut self) -> Vec<MusicEvent> {
+        std::mem::take(&mut self.pending_events)
+    }
+
+    /// Event callback address and user data
+    pub fn callback(&self) -> (u32, u32) {
+        (self.callback, self.userdata)
+    }
+
+    /// Hand the waiting events to the game's callback on `calls`
+    ///
+    /// The callback gets the event, its parameter and the user data.
+    pub fn queue_callbacks(&mut self, calls: &mut GuestCallQueue) {
+        for event in self.take_events() {
+            calls.push(self.callback, [event.event as u64, event.param as u32 as u64, self.userdata as u64, 0]);
+        }
+    }
+
+    /// Set the playback volume
+    pub fn set_volume(&mut self, volume: f32) -> i32 {
+        if !self.initialized {
+            return CELL_MUSIC_ERROR_GENERIC;
+        }
+        if !(0.0..=1.0).contains(&volume) {
+            return CELL_MUSIC_ERROR_PARAM;
+        }
+        self.volume = volume;
+        self.queue_event(CELL_MUSIC_EVENT_SET_VOLUME_RESULT, 0);
+        0 // CELL_OK
+    }
+
+    /// Get the playback volume
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// Get the playback status
+    pub fn status(&self) -> i32 {
+        self.status
+    }
+
+    /// Check if the current track should be heard
+    pub fn is_playing(&self) -> bool {
+        matches!(
+            self.status,
+            CELL_MUSIC_PB_STATUS_PLAY | CELL_MUSIC_PB_STATUS_FASTFORWARD | CELL_MUSIC_PB_STATUS_FASTREVERSE
+        )
+    }
+
+    /// Host path of the current track
+    pub fn current_track(&self) -> Option<&Path> {
+        self.current.map(|index| self.playlist[index].as_path())
+    }
+
+    /// Counter bumped whenever the current track restarts or changes
+    pub fn track_generation(&self) -> u64 {
+        self.track_generation
+    }
+
+    /// Selected tracks
+    pub fn playlist(&self) -> &[PathBuf] {
+        &self.playlist
+    }
+
+    /// Check if initialized
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+impl Default for MusicManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// MP3 and FLAC files under `folder`, sorted by path
+pub fn scan_music_folder(folder: &Path) -> Vec<PathBuf> {
+    scan_folder(folder, MUSIC_EXTENSIONS)
+}
+
+/// Run `f` on the music manager, handing the events it queued to the game's callback
+fn with_music(f: impl FnOnce(&mut MusicManager) -> i32) -> i32 {
+    let ctx = &mut *crate::context::get_hle_context_mut();
+    let ret = f(&mut ctx.music);
+    ctx.music.queue_callbacks(&mut ctx.guest_calls);
+    ret
+}
+
+/// Write a playback value the game asked for
+fn write_output<T: oc_memory::BeValue>(addr: u32, value: T) -> i32 {
+    if addr == 0 {
+        return CELL_MUSIC_ERROR_PARAM;
+    }
+    match crate::context::guest_memory().map(|memory| memory.write_be(addr, value)) {
+        Some(Ok(())) => 0, // CELL_OK
+        _ => CELL_MUSIC_ERROR_GENERIC,
+    }
+}
+
+/// cellMusicInitialize - Initialize the music library
+///
+/// # Arguments
+/// * `mode` - Player mode
+/// * `container` - Memory container for the library
+/// * `spu_priority` - Priority of the decoder's SPU thread
+/// * `func` - Event callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_music_initialize(mode: i32, container: u32, spu_priority: i32, func: u32, userdata: u32) -> i32 {
+    debug!(
+        "cellMusicInitialize(mode={}, container={}, spu_priority={})",
+        mode, container, spu_priority
+    );
+
+    with_music(|music| music.init(func, userdata))
+}
+
+/// cellMusicInitialize2 - Initialize the music library
+///
+/// # Arguments
+/// * `mode` - Player mode
+/// * `spu_priority` - Priority of the decoder's SPU thread
+/// * `func` - Event callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_music_initialize2(mode: i32, spu_priority: i32, func: u32, userdata: u32) -> i32 {
+    debug!("cellMusicInitialize2(mode={}, spu_priority={})", mode, spu_priority);
+
+    with_music(|music| music.init(func, userdata))
+}
+
+/// cellMusicFinalize - Shut the music library down
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_music_finalize() -> i32 {
+    debug!("cellMusicFinalize()");
+
+    with_music(|music| music.finalize())
+}
+
+/// cellMusicSelectContents - Select the tracks to play
+///
+/// # Arguments
+/// * `container` - Memory container for the selection dialog
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_music_select_contents(container: u32) -> i32 {
+    debug!("cellMusicSelectContents(container={})", container);
+
+    with_music(|music| music.select_contents())
+}
+
+/// cellMusicSetSelectionContext - Restore a selection saved by the game
+///
+/// # Arguments
+/// * `context_addr` - Address of CellMusicSelectionContext
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_music_set_selection_context(context_addr: u32) -> i32 {
+    debug!("cellMusicSetSelectionContext(context_addr=0x{:08X})", context_addr);
+
+    if context_addr == 0 {
+        return CELL_MUSIC_ERROR_PARAM;
+    }
+
+    let Some(context) = crate::context::guest_memory()
+        .and_then(|memory| memory.read_bytes(context_addr, CELL_MUSIC_SELECTION_CONTEXT_SIZE as u32).ok())
+    else {
+        return CELL_MUSIC_ERROR_PARAM;
+    };
+    // Contexts cellSearch did not write this run select the whole music folder
+    let ctx = &mut *crate::context::get_hle_context_mut();
+    let selection = ctx.search.saved_music_selection(&context);
+    let ret = ctx.music.restore_selection(selection);
+    ctx.music.queue_callbacks(&mut ctx.guest_calls);
+    ret
+}
+
+/// cellMusicGetSelectionContext - Get the current selection to save
+///
+/// # Arguments
+/// * `context_addr` - Address of CellMusicSelectionContext
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_music_get_selection_context(_context_addr: u32) -> i32 {
+    trace!("cellMusicGetSelectionContext()");
+
+    let music = &crate::context::get_hle_context().music;
+    if music.current_track().is_none() {
+        return CELL_MUSIC_ERROR_NO_ACTIVE_CONTENT;
+    }
+    // Note: writing the context requires memory subsystem integration
+    0 // CELL_OK
+}
+
+/// cellMusicSetPlaybackCommand - Play, pause, stop or skip
+///
+/// # Arguments
+/// * `command` - Playback command
+/// * `param_addr` - Address of the command parameters
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_music_set_playback_command(command: i32, _param_addr: u32) -> i32 {
+    with_music(|music| music.set_playback_command(command))
+}
+
+/// cellMusicGetPlaybackStatus - Get the playback status
+///
+/// # Arguments
+/// * `status_addr` - Address to write the status
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_music_get_playback_status(status_addr: u32) -> i32 {
+    let music = &crate::context::get_hle_context().music;
+    if !music.is_initialized() {
+        return CELL_MUSIC_ERROR_GENERIC;
+    }
+    trace!("cellMusicGetPlaybackStatus() -> {}", music.status());
+
+    write_output(status_addr, music.status())
+}
+
+/// cellMusicGetVolume - Get the playback volume
+///
+/// # Arguments
+/// * `level_addr` - Address to write the volume
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_music_get_volume(level_addr: u32) -> i32 {
+    let music = &crate::context::get_hle_context().music;
+    if !music.is_initialized() {
+        return CELL_MUSIC_ERROR_GENERIC;
+    }
+    trace!("cellMusicGetVolume() -> {}", music.volume());
+
+    write_output(level_addr, music.volume())
+}
+
+/// cellMusicGetContentsId - Get the search ID of the current track
+///
+/// # Arguments
+/// * `contents_id_addr` - Address of CellSearchContentId
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_music_get_contents_id(_contents_id_addr: u32) -> i32 {
+    trace!("cellMusicGetContentsId()");
+
+    if crate::context::get_hle_context().music.current_track().is_none() {
+        return CELL_MUSIC_ERROR_NO_ACTIVE_CONTENT;
+    }
+    // Note: writing the ID requires memory subsystem integration
+    0 // CELL_OK
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell_search::CELL_SEARCH_REPEATMODE_NONE;
+
+    fn music_folder(name: &str, tracks: &[&str]) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("oc_hle_music_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(folder.join("album")).unwrap();
+        for track in tracks {
+            std::fs::write(folder.join(track), b"").unwrap();
+        }
+        folder
+    }
+
+    fn selected_manager(folder: PathBuf) -> MusicManager {
+        let mut manager = MusicManager::new();
+        manager.set_folder(folder);
+        assert_eq!(manager.init(0x1000, 0x2000), 0);
+        assert_eq!(manager.select_contents(), 0);
+        manager.take_events();
+        manager
+    }
+
+    #[test]
+    fn test_scan_music_folder() {
+        let folder = music_folder("scan", &["b.mp3", "a.FLAC", "cover.jpg", "album/c.mp3"]);
+        let tracks = scan_music_folder(&folder);
+        assert_eq!(tracks, [folder.join("a.FLAC"), folder.join("album/c.mp3"), folder.join("b.mp3")]);
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn test_music_lifecycle() {
+        let mut manager = MusicManager::new();
+
+        assert_eq!(manager.select_contents(), CELL_MUSIC_ERROR_GENERIC);
+        assert_eq!(manager.init(0, 0), CELL_MUSIC_ERROR_PARAM);
+        assert_eq!(manager.init(0x1000, 0x2000), 0);
+        assert_eq!(manager.init(0x1000, 0x2000), CELL_MUSIC_ERROR_BUSY);
+
+        // Without a music folder the selection is cancelled
+        assert_eq!(manager.select_contents(), 0);
+        assert_eq!(manager.set_playback_command(CELL_MUSIC_PB_CMD_PLAY), 0);
+        assert_eq!(
+            manager.take_events(),
+            [
+                MusicEvent { event: CELL_MUSIC_EVENT_INITIALIZE_RESULT, param: 0 },
+                MusicEvent { event: CELL_MUSIC_EVENT_SELECT_CONTENTS_RESULT, param: CELL_MUSIC_CANCELED },
+                MusicEvent {
+                    event: CELL_MUSIC_EVENT_SET_PLAYBACK_COMMAND_RESULT,
+                    param: CELL_MUSIC_ERROR_NO_ACTIVE_CONTENT,
+                },
+            ]
+        );
+
+        assert_eq!(manager.set_playback_command(9), CELL_MUSIC_ERROR_PARAM);
+        assert_eq!(manager.finalize(), 0);
+        assert!(!manager.is_initialized());
+        assert_eq!(manager.finalize(), CELL_MUSIC_ERROR_GENERIC);
+    }
+
+    #[test]
+    fn test_music_playback() {
+        let folder = music_folder("playback", &["1.mp3", "2.mp3", "3.flac"]);
+        let mut manager = selected_manager(folder.clone());
+        assert_eq!(manager.playlist().len(), 3);
+        assert_eq!(manager.current_track(), Some(folder.join("1.mp3").as_path()));
+
+        manager.set_playback_command(CELL_MUSIC_PB_CMD_PLAY);
+        assert!(manager.is_playing());
+        assert_eq!(
+            manager.take_events(),
+            [
+                MusicEvent { event: CELL_MUSIC_EVENT_STATUS_NOTIFICATION, param: CELL_MUSIC_PB_STATUS_PLAY },
+                MusicEvent { event: CELL_MUSIC_EVENT_SET_PLAYBACK_COMMAND_RESULT, param: 0 },
+            ]
+        );
+
+        // Skipping wraps around and restarts the decoder
+        let generation = manager.track_generation();
+        manager.set_playback_command(CELL_MUSIC_PB_CMD_PREV);
+        assert_eq!(manager.current_track(), Some(folder.join("3.flac").as_path()));
+        assert!(manager.track_generation() > generation);
+
+        // The whole selection repeats by default
+        manager.track_finished();
+        assert_eq!(manager.current_track(), Some(folder.join("1.mp3").as_path()));
+        assert!(manager.is_playing());
+
+        // Stopping and playing again starts the track over
+        let generation = manager.track_generation();
+        manager.set_playback_command(CELL_MUSIC_PB_CMD_PAUSE);
+        assert_eq!(manager.track_generation(), generation);
+        manager.set_playback_command(CELL_MUSIC_PB_CMD_STOP);
+        assert!(manager.track_generation() > generation);
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn test_music_repeat_modes() {
+        let folder = music_folder("repeat", &["1.mp3", "2.mp3"]);
+        let mut manager = selected_manager(folder.clone());
+        manager.set_playback_command(CELL_MUSIC_PB_CMD_PLAY);
+
+        assert_eq!(manager.set_repeat_mode(CELL_SEARCH_REPEATMODE_REPEAT1), 0);
+        manager.track_finished();
+        assert_eq!(manager.current_track(), Some(folder.join("1.mp3").as_path()));
+
+        assert_eq!(manager.set_repeat_mode(CELL_SEARCH_REPEATMODE_NONE), 0);
+        manager.track_finished();
+        manager.take_events();
+        manager.track_finished();
+        assert!(!manager.is_playing());
+        assert_eq!(
+            manager.take_events(),
+            [MusicEvent { event: CELL_MUSIC_EVENT_STATUS_NOTIFICATION, param: CELL_MUSIC_PLAYBACK_FINISHED }]
+        );
+
+        // Tracks that fail to decode are dropped from the selection
+        manager.set_playback_command(CELL_MUSIC_PB_CMD_PLAY);
+        manager.track_failed();
+        assert_eq!(manager.playlist().len(), 1);
+        manager.track_failed();
+        assert_eq!(manager.current_track(), None);
+        assert!(!manager.is_playing());
+        assert_eq!(manager.set_repeat_mode(7), CELL_MUSIC_ERROR_PARAM);
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn test_music_search_selection() {
+        let mut manager = MusicManager::new();
+        manager.init(0x1000, 0);
+        let tracks = vec![PathBuf::from("/a.mp3"), PathBuf::from("/b.mp3")];
+        let selection = MusicSelection { tracks, first: 1, repeat_mode: CELL_SEARCH_REPEATMODE_REPEAT1 };
+
+        assert_eq!(manager.restore_selection(Some(selection)), 0);
+        assert_eq!(manager.current_track(), Some(Path::new("/b.mp3")));
+        manager.set_playback_command(CELL_MUSIC_PB_CMD_PLAY);
+        manager.track_finished();
+        assert_eq!(manager.current_track(), Some(Path::new("/b.mp3")));
+
+        // Other contexts fall back to the music folder, which is unset here
+        manager.take_events();
+        manager.restore_selection(None);
+        assert_eq!(
+            manager.take_events(),
+            [MusicEvent {
+                event: CELL_MUSIC_EVENT_SET_SELECTION_CONTEXT_RESULT,
+                param: CELL_MUSIC_ERROR_INVALID_CONTEXT,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_music_guest_callbacks() {
+        let _guard = crate::context::test_guard();
+        crate::context::reset_hle_context();
+        let memory = crate::context::test_guest_memory();
+        let addr = memory.allocate(0x10, 0x10, oc_memory::PageFlags::RW).unwrap();
+
+        assert_eq!(cell_music_initialize2(0, 0, 0x1000, 0x2000), 0);
+        assert_eq!(cell_music_select_contents(0), 0);
+        assert_eq!(cell_music_get_playback_status(addr), 0);
+        assert_eq!(memory.read_be32(addr).unwrap() as i32, CELL_MUSIC_PB_STATUS_STOP);
+        assert_eq!(cell_music_get_volume(addr), 0);
+        assert_eq!(memory.read_be::<f32>(addr).unwrap(), 1.0);
+        assert_eq!(cell_music_finalize(), 0);
+
+        // Every result reaches the game's callback, the finalize result included
+        let calls = crate::context::get_hle_context_mut().guest_calls.take();
+        assert!(calls.iter().all(|call| call.opd == 0x1000 && call.args[2] == 0x2000));
+        let events: Vec<(u64, u64)> = calls.iter().map(|call| (call.args[0], call.args[1])).collect();
+        assert_eq!(
+            events,
+            [
+                (CELL_MUSIC_EVENT_INITIALIZE_RESULT as u64, 0),
+                (CELL_MUSIC_EVENT_SELECT_CONTENTS_RESULT as u64, CELL_MUSIC_CANCELED as u64),
+                (CELL_MUSIC_EVENT_FINALIZE_RESULT as u64, 0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,721 @@
+//! cellOskDialog HLE - On-Screen Keyboard
+//!
+//! This module provides HLE implementations for the system on-screen
+//! keyboard. The game loads the dialog with a message, initial text and a
+//! character limit; the frontend shows a keyboard for the open request and
+//! finishes it with the entered text, and the game collects the text when
+//! it unloads the dialog. Controllers are intercepted while it is open.
+
+use crate::context::guest_memory;
+use oc_memory::BeValue;
+use tracing::{debug, trace};
+
+/// Error codes
+pub const CELL_OSKDIALOG_ERROR_IME_ALREADY_IN_USE: i32 = 0x8002b501u32 as i32;
+pub const CELL_OSKDIALOG_ERROR_GET_SIZE_ERROR: i32 = 0x8002b502u32 as i32;
+pub const CELL_OSKDIALOG_ERROR_UNKNOWN: i32 = 0x8002b503u32 as i32;
+pub const CELL_OSKDIALOG_ERROR_PARAM: i32 = 0x8002b504u32 as i32;
+
+/// Sysutil events sent to the game's callbacks
+pub const CELL_SYSUTIL_OSKDIALOG_LOADED: u64 = 0x0502;
+pub const CELL_SYSUTIL_OSKDIALOG_FINISHED: u64 = 0x0503;
+pub const CELL_SYSUTIL_OSKDIALOG_UNLOADED: u64 = 0x0504;
+
+/// Largest text the dialog accepts, in UTF-16 code units
+pub const CELL_OSKDIALOG_STRING_SIZE: u32 = 512;
+
+/// First dialog type whose text is shown in a window of the game's own
+/// (CELL_OSKDIALOG_TYPE_SEPARATE_SINGLELINE_TEXT_WINDOW)
+pub const CELL_OSKDIALOG_TYPE_SEPARATE_SINGLELINE_TEXT_WINDOW: u32 = 4;
+
+/// Keyboard panels a game can allow (CELL_OSKDIALOG_PANELMODE_*)
+pub mod panel_mode {
+    pub const DEFAULT: u32 = 0x0000_0000;
+    pub const ENGLISH: u32 = 0x0000_0002;
+    pub const JAPANESE: u32 = 0x0000_0100;
+    pub const JAPANESE_HIRAGANA: u32 = 0x0020_0000;
+    pub const JAPANESE_KATAKANA: u32 = 0x0040_0000;
+    pub const ALPHABET: u32 = 0x0100_0000;
+    pub const NUMERAL: u32 = 0x0800_0000;
+    pub const URL: u32 = 0x1000_0000;
+    pub const PASSWORD: u32 = 0x2000_0000;
+}
+
+/// How the user left the dialog (CELL_OSKDIALOG_INPUT_FIELD_RESULT_*)
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OskInputResult {
+    /// Text entered and confirmed
+    Ok = 0,
+    /// Cancelled by the user
+    Canceled = 1,
+    /// Closed by the game
+    Abort = 2,
+    /// Confirmed with no text
+    NoInputText = 3,
+}
+
+/// Panels and placement of the keyboard (CellOskDialogParam)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, BeValue)]
+pub struct CellOskDialogParam {
+    /// Allowed panels (`panel_mode` flags)
+    pub allow_osk_panel_flg: u32,
+    /// Panel shown first
+    pub first_view_panel: u32,
+    /// Position of the keyboard
+    pub control_point_x: f32,
+    pub control_point_y: f32,
+    pub prohibit_flgs: i32,
+}
+
+/// Text field of the keyboard (CellOskDialogInputFieldInfo)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, BeValue)]
+pub struct CellOskDialogInputFieldInfo {
+    /// Addresses of NUL-terminated UTF-16 strings
+    pub message: u32,
+    pub init_text: u32,
+    /// Maximum length, in UTF-16 code units
+    pub limit_length: u32,
+}
+
+/// Where the game collects the entered text (CellOskDialogCallbackReturnParam)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, BeValue)]
+pub struct CellOskDialogCallbackReturnParam {
+    /// `OskInputResult`
+    pub result: i32,
+    /// Size of the game's text buffer, in UTF-16 code units
+    pub num_chars_result_string: i32,
+    /// Address of the game's text buffer
+    pub result_string: u32,
+}
+
+/// Keyboard the game asked for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OskRequest {
+    /// Guidance message shown above the text field
+    pub message: String,
+    /// Text the field starts with
+    pub init_text: String,
+    /// Maximum length, in UTF-16 code units
+    pub limit: u32,
+    /// Allowed panels (`panel_mode` flags, DEFAULT = all)
+    pub allowed_panels: u32,
+    /// Panel shown first (`panel_mode` flag, DEFAULT = by system language)
+    pub first_panel: u32,
+}
+
+impl OskRequest {
+    /// Request for up to `limit` characters, with every panel allowed
+    pub fn new(message: &str, init_text: &str, limit: u32) -> Self {
+        Self {
+            message: message.to_string(),
+            init_text: init_text.to_string(),
+            limit,
+            allowed_panels: panel_mode::DEFAULT,
+            first_panel: panel_mode::DEFAULT,
+        }
+    }
+
+    /// Whether a panel may be shown
+    pub fn allows(&self, panel: u32) -> bool {
+        self.allowed_panels == panel_mode::DEFAULT || self.allowed_panels & panel != 0
+    }
+
+    /// Whether the text is a password and should be masked
+    pub fn is_password(&self) -> bool {
+        self.allowed_panels & panel_mode::PASSWORD != 0
+    }
+}
+
+/// Dialog state
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum OskState {
+    /// Not loaded
+    Unloaded,
+    /// Waiting for the user
+    Open(OskRequest),
+    /// Finished, waiting for the game to unload it and take the text
+    Finished { result: OskInputResult, text: String },
+}
+
+/// On-screen keyboard manager
+#[derive(Debug)]
+pub struct OskDialogManager {
+    state: OskState,
+    /// Incremented on every load, so the frontend sees a new request
+    generation: u32,
+    /// Key layout options (CELL_OSKDIALOG_10KEY_PANEL, FULLKEY_PANEL)
+    key_layout_option: u32,
+    /// Layout mode flags
+    layout_mode: i32,
+    /// Extra languages the game supports
+    support_languages: u32,
+}
+
+impl OskDialogManager {
+    /// Create a new on-screen keyboard manager
+    pub fn new() -> Self {
+        Self {
+            state: OskState::Unloaded,
+            generation: 0,
+            key_layout_option: 0,
+            layout_mode: 0,
+            support_languages: 0,
+        }
+    }
+
+    /// Open the keyboard
+    pub fn load(&mut self, request: OskRequest) -> i32 {
+        if self.state != OskState::Unloaded {
+            return CELL_OSKDIALOG_ERROR_IME_ALREADY_IN_USE;
+        }
+        if request.limit == 0 || request.limit > CELL_OSKDIALOG_STRING_SIZE {
+            return CELL_OSKDIALOG_ERROR_PARAM;
+        }
+
+        debug!("OskDialogManager::load: limit={}, message=\"{}\"", request.limit, request.message);
+
+        self.generation = self.generation.wrapping_add(1);
+        self.state = OskState::Open(request);
+
+        0 // CELL_OK
+    }
+
+    /// Keyboard waiting for the user (None when not open)
+    pub fn request(&self) -> Option<&OskRequest> {
+        match &self.state {
+            OskState::Open(request) => Some(request),
+            _ => None,
+        }
+    }
+
+    /// Load count, changing whenever a new keyboard opens
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Whether the keyboard is loaded, open or finished
+    pub fn is_loaded(&self) -> bool {
+        self.state != OskState::Unloaded
+    }
+
+    /// Finish the open keyboard with the entered text (None = cancelled)
+    ///
+    /// The text is cut to the request's limit. Returns the result, or None
+    /// if no keyboard was open.
+    pub fn finish(&mut self, text: Option<&str>) -> Option<OskInputResult> {
+        let OskState::Open(request) = &self.state else {
+            return None;
+        };
+        let (result, text) = match text {
+            None => (OskInputResult::Canceled, String::new()),
+            Some("") => (OskInputResult::NoInputText, String::new()),
+            Some(text) => (OskInputResult::Ok, truncate_utf16(text, request.limit as usize)),
+        };
+
+        debug!("OskDialogManager::finish: result={:?}", result);
+
+        self.state = OskState::Finished { result, text };
+        Some(result)
+    }
+
+    /// Close the keyboard on the game's behalf
+    pub fn abort(&mut self) -> i32 {
+        if self.request().is_none() {
+            return CELL_OSKDIALOG_ERROR_PARAM;
+        }
+        self.state = OskState::Finished {
+            result: OskInputResult::Abort,
+            text: String::new(),
+        };
+
+        0 // CELL_OK
+    }
+
+    /// Text entered so far, as NUL-terminated UTF-16
+    pub fn input_text(&self) -> Result<(OskInputResult, Vec<u16>), i32> {
+        match &self.state {
+            OskState::Finished { result, text } => Ok((*result, to_utf16z(text))),
+            _ => Err(CELL_OSKDIALOG_ERROR_PARAM),
+        }
+    }
+
+    /// Unload the finished keyboard, returning its result and NUL-terminated UTF-16 text
+    pub fn unload(&mut self) -> Result<(OskInputResult, Vec<u16>), i32> {
+        let output = self.input_text()?;
+        self.state = OskState::Unloaded;
+        debug!("OskDialogManager::unload: result={:?}", output.0);
+        Ok(output)
+    }
+
+    /// Set the key layout options
+    pub fn set_key_layout_option(&mut self, option: u32) -> i32 {
+        if option == 0 {
+            return CELL_OSKDIALOG_ERROR_PARAM;
+        }
+        self.key_layout_option = option;
+        0 // CELL_OK
+    }
+
+    /// Key layout options set by the game
+    pub fn key_layout_option(&self) -> u32 {
+        self.key_layout_option
+    }
+
+    /// Set the layout mode flags
+    pub fn set_layout_mode(&mut self, mode: i32) -> i32 {
+        self.layout_mode = mode;
+        0 // CELL_OK
+    }
+
+    /// Add languages the game's text supports
+    pub fn add_support_language(&mut self, languages: u32) -> i32 {
+        self.support_languages |= languages;
+        0 // CELL_OK
+    }
+}
+
+impl Default for OskDialogManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Cut `text` to at most `limit` UTF-16 code units, without splitting a character
+fn truncate_utf16(text: &str, limit: usize) -> String {
+    let mut units = 0;
+    text.chars()
+        .take_while(|c| {
+            units += c.len_utf16();
+            units <= limit
+        })
+        .collect()
+}
+
+/// `text` as NUL-terminated UTF-16
+fn to_utf16z(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// Read a NUL-terminated UTF-16 string of at most `max_units` code units from guest memory
+fn read_guest_utf16(addr: u32, max_units: u32) -> Option<String> {
+    if addr == 0 {
+        return None;
+    }
+    let memory = guest_memory()?;
+    let mut units = Vec::new();
+    for index in 0..max_units {
+        match memory.read_be16(addr.wrapping_add(index * 2)).ok()? {
+            0 => break,
+            unit => units.push(unit),
+        }
+    }
+    Some(String::from_utf16_lossy(&units))
+}
+
+/// Write the result and NUL-terminated UTF-16 `text` of a finished keyboard
+/// to the CellOskDialogCallbackReturnParam at `addr`
+///
+/// The text is cut to the game's buffer, keeping the terminating NUL.
+fn write_output_info(addr: u32, result: OskInputResult, text: &[u16]) -> i32 {
+    let Some(memory) = guest_memory() else {
+        return CELL_OSKDIALOG_ERROR_PARAM;
+    };
+    let Ok(mut output) = memory.read_be::<CellOskDialogCallbackReturnParam>(addr) else {
+        return CELL_OSKDIALOG_ERROR_PARAM;
+    };
+    output.result = result as i32;
+    let written = memory.write_be(addr, output).and_then(|_| {
+        let capacity = output.num_chars_result_string.max(0) as usize;
+        if output.result_string == 0 || capacity == 0 {
+            return Ok(());
+        }
+        let count = text.len().min(capacity);
+        for (index, &unit) in text[..count - 1].iter().chain([&0]).enumerate() {
+            memory.write_be16(output.result_string + index as u32 * 2, unit)?;
+        }
+        Ok(())
+    });
+    match written {
+        Ok(()) => 0, // CELL_OK
+        Err(_) => CELL_OSKDIALOG_ERROR_PARAM,
+    }
+}
+
+/// Romaji to hiragana, longest spellings first within each length
+const ROMAJI: &[(&str, &str)] = &[
+    ("kya", "きゃ"), ("kyu", "きゅ"), ("kyo", "きょ"), ("gya", "ぎゃ"), ("gyu", "ぎゅ"), ("gyo", "ぎょ"),
+    ("sha", "しゃ"), ("shi", "し"), ("shu", "しゅ"), ("she", "しぇ"), ("sho", "しょ"),
+    ("cha", "ちゃ"), ("chi", "ち"), ("chu", "ちゅ"), ("che", "ちぇ"), ("cho", "ちょ"), ("tsu", "つ"),
+    ("nya", "にゃ"), ("nyu", "にゅ"), ("nyo", "にょ"), ("hya", "ひゃ"), ("hyu", "ひゅ"), ("hyo", "ひょ"),
+    ("mya", "みゃ"), ("myu", "みゅ"), ("myo", "みょ"), ("rya", "りゃ"), ("ryu", "りゅ"), ("ryo", "りょ"),
+    ("bya", "びゃ"), ("byu", "びゅ"), ("byo", "びょ"), ("pya", "ぴゃ"), ("pyu", "ぴゅ"), ("pyo", "ぴょ"),
+    ("jya", "じゃ"), ("jyu", "じゅ"), ("jyo", "じょ"), ("xtu", "っ"), ("xya", "ゃ"), ("xyu", "ゅ"), ("xyo", "ょ"),
+    ("ka", "か"), ("ki", "き"), ("ku", "く"), ("ke", "け"), ("ko", "こ"),
+    ("ga", "が"), ("gi", "ぎ"), ("gu", "ぐ"), ("ge", "げ"), ("go", "ご"),
+    ("sa", "さ"), ("si", "し"), ("su", "す"), ("se", "せ"), ("so", "そ"),
+    ("za", "ざ"), ("zi", "じ"), ("ji", "じ"), ("zu", "ず"), ("ze", "ぜ"), ("zo", "ぞ"),
+    ("ja", "じゃ"), ("ju", "じゅ"), ("je", "じぇ"), ("jo", "じょ"),
+    ("ta", "た"), ("ti", "ち"), ("tu", "つ"), ("te", "て"), ("to", "と"),
+    ("da", "だ"), ("di", "ぢ"), ("du", "づ"), ("de", "で"), ("do", "ど"),
+    ("na", "な"), ("ni", "に"), ("nu", "ぬ"), ("ne", "ね"), ("no", "の"),
+    ("ha", "は"), ("hi", "ひ"), ("hu", "ふ"), ("fu", "ふ"), ("he", "へ"), ("ho", "ほ"),
+    ("ba", "ば"), ("bi", "び"), ("bu", "ぶ"), ("be", "べ"), ("bo", "ぼ"),
+    ("pa", "ぱ"), ("pi", "ぴ"), ("pu", "ぷ"), ("pe", "ぺ"), ("po", "ぽ"),
+    ("ma", "ま"), ("mi", "み"), ("mu", "む"), ("me", "め"), ("mo", "も"),
+    ("ya", "や"), ("yu", "ゆ"), ("yo", "よ"),
+    ("ra", "ら"), ("ri", "り"), ("ru", "る"), ("re", "れ"), ("ro", "ろ"),
+    ("wa", "わ"), ("wo", "を"), ("n'", "ん"), ("xa", "ぁ"), ("xi", "ぃ"), ("xu", "ぅ"), ("xe", "ぇ"), ("xo", "ぉ"),
+    ("a", "あ"), ("i", "い"), ("u", "う"), ("e", "え"), ("o", "お"), ("-", "ー"),
+];
+
+/// Hiragana as katakana
+pub fn to_katakana(hiragana: &str) -> String {
+    hiragana
+        .chars()
+        .map(|c| match c {
+            '\u{3041}'..='\u{3096}' => char::from_u32(c as u32 + 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+/// Convert romaji typed on a host keyboard to kana
+///
+/// Returns the kana of the complete syllables and the romaji of the one
+/// still being typed. A doubled consonant becomes a small tsu, and an "n"
+/// before a consonant (or "n'") becomes ん. Characters that start no syllable pass
+/// through unchanged.
+pub fn romaji_to_kana(romaji: &str, katakana: bool) -> (String, String) {
+    let input = romaji.to_ascii_lowercase();
+    let bytes = input.as_bytes();
+    let mut kana = String::new();
+    let mut pos = 0;
+
+    while pos < input.len() {
+        let rest = &input[pos..];
+        if let Some(&(spelling, syllable)) = ROMAJI.iter().find(|(spelling, _)| rest.starts_with(spelling)) {
+            kana.push_str(syllable);
+            pos += spelling.len();
+            continue;
+        }
+        let c = bytes[pos];
+        let next = bytes.get(pos + 1).copied();
+        match next {
+            // A doubled consonant is a geminate
+            Some(next) if next == c && c.is_ascii_alphabetic() && !b"aeioun".contains(&c) => {
+                kana.push('っ');
+                pos += 1;
+            }
+            // "n" before anything but a vowel or "y"
+            Some(next) if c == b'n' && !b"aeiouy".contains(&next) => {
+                kana.push('ん');
+                pos += 1;
+            }
+            _ if ROMAJI.iter().any(|(spelling, _)| spelling.starts_with(rest)) => {
+                // Still being typed
+                break;
+            }
+            _ => {
+                kana.push(rest.chars().next().unwrap_or_default());
+                pos += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+    }
+
+    let pending = input[pos..].to_string();
+    if katakana {
+        (to_katakana(&kana), pending)
+    } else {
+        (kana, pending)
+    }
+}
+
+/// Open `request`, intercept the controllers and tell the game the keyboard loaded
+pub fn open_osk(request: OskRequest) -> i32 {
+    let mut ctx = crate::context::get_hle_context_mut();
+    let ret = ctx.osk_dialog.load(request);
+    if ret == 0 {
+        ctx.pad.set_intercepted(true);
+        ctx.sysutil.queue_event(CELL_SYSUTIL_OSKDIALOG_LOADED, 0);
+    }
+    ret
+}
+
+/// Finish the open keyboard with the user's text (None = cancelled)
+///
+/// Called by the frontend. Gives the controllers back and tells the game
+/// the keyboard finished.
+pub fn finish_osk(text: Option<&str>) -> Option<OskInputResult> {
+    let mut ctx = crate::context::get_hle_context_mut();
+    let result = ctx.osk_dialog.finish(text)?;
+    ctx.pad.set_intercepted(false);
+    ctx.sysutil.queue_event(CELL_SYSUTIL_OSKDIALOG_FINISHED, result as u64);
+    Some(result)
+}
+
+/// cellOskDialogLoadAsync - Open the on-screen keyboard
+///
+/// # Arguments
+/// * `container` - Memory container for the dialog
+/// * `dialog_param_addr` - Address of CellOskDialogParam (allowed panels, position)
+/// * `input_field_info_addr` - Address of CellOskDialogInputFieldInfo (message, initial text, limit)
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_osk_dialog_load_async(container: u32, dialog_param_addr: u32, input_field_info_addr: u32) -> i32 {
+    debug!("cellOskDialogLoadAsync(container=0x{:08X})", container);
+
+    let Some(memory) = guest_memory() else {
+        return CELL_OSKDIALOG_ERROR_PARAM;
+    };
+    if dialog_param_addr == 0 || input_field_info_addr == 0 {
+        return CELL_OSKDIALOG_ERROR_PARAM;
+    }
+    let (Ok(param), Ok(field)) = (
+        memory.read_be::<CellOskDialogParam>(dialog_param_addr),
+        memory.read_be::<CellOskDialogInputFieldInfo>(input_field_info_addr),
+    ) else {
+        return CELL_OSKDIALOG_ERROR_PARAM;
+    };
+
+    let message = read_guest_utf16(field.message, CELL_OSKDIALOG_STRING_SIZE).unwrap_or_default();
+    let init_text = read_guest_utf16(field.init_text, CELL_OSKDIALOG_STRING_SIZE).unwrap_or_default();
+    let mut request = OskRequest::new(&message, &init_text, field.limit_length);
+    request.allowed_panels = param.allow_osk_panel_flg;
+    request.first_panel = param.first_view_panel;
+    open_osk(request)
+}
+
+/// cellOskDialogUnloadAsync - Close the finished keyboard and collect its text
+///
+/// # Arguments
+/// * `output_info_addr` - Address of CellOskDialogCallbackReturnParam
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_osk_dialog_unload_async(output_info_addr: u32) -> i32 {
+    debug!("cellOskDialogUnloadAsync()");
+
+    if output_info_addr == 0 {
+        return CELL_OSKDIALOG_ERROR_PARAM;
+    }
+    let mut ctx = crate::context::get_hle_context_mut();
+    // The keyboard stays loaded if the text cannot be handed over
+    let ret = match ctx.osk_dialog.input_text() {
+        Ok((result, text)) => write_output_info(output_info_addr, result, &text),
+        Err(e) => e,
+    };
+    if ret != 0 {
+        return ret;
+    }
+    let _ = ctx.osk_dialog.unload();
+    ctx.pad.set_intercepted(false);
+    ctx.sysutil.queue_event(CELL_SYSUTIL_OSKDIALOG_UNLOADED, 0);
+    0 // CELL_OK
+}
+
+/// cellOskDialogAbort - Close the keyboard before the user finishes
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_osk_dialog_abort() -> i32 {
+    debug!("cellOskDialogAbort()");
+
+    let mut ctx = crate::context::get_hle_context_mut();
+    let ret = ctx.osk_dialog.abort();
+    if ret == 0 {
+        ctx.pad.set_intercepted(false);
+        ctx.sysutil.queue_event(CELL_SYSUTIL_OSKDIALOG_FINISHED, OskInputResult::Abort as u64);
+    }
+    ret
+}
+
+/// cellOskDialogGetInputText - Get the text of a finished keyboard
+///
+/// # Arguments
+/// * `output_info_addr` - Address of CellOskDialogCallbackReturnParam
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_osk_dialog_get_input_text(output_info_addr: u32) -> i32 {
+    trace!("cellOskDialogGetInputText()");
+
+    if output_info_addr == 0 {
+        return CELL_OSKDIALOG_ERROR_PARAM;
+    }
+    match crate::context::get_hle_context().osk_dialog.input_text() {
+        Ok((result, text)) => write_output_info(output_info_addr, result, &text),
+        Err(e) => e,
+    }
+}
+
+/// cellOskDialogGetSize - Get the keyboard's on-screen size
+///
+/// The system reports the size in screen units: a single unit high, and
+/// no width when the game shows the text in a window of its own.
+///
+/// # Arguments
+/// * `width_addr` - Address to write the width (u16)
+/// * `height_addr` - Address to write the height (u16)
+/// * `dialog_type` - Dialog type (CELL_OSKDIALOG_TYPE_*)
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_osk_dialog_get_size(width_addr: u32, height_addr: u32, dialog_type: u32) -> i32 {
+    trace!("cellOskDialogGetSize(type={})", dialog_type);
+
+    if width_addr == 0 || height_addr == 0 {
+        return CELL_OSKDIALOG_ERROR_PARAM;
+    }
+    let Some(memory) = guest_memory() else {
+        return CELL_OSKDIALOG_ERROR_GET_SIZE_ERROR;
+    };
+    let width = if dialog_type >= CELL_OSKDIALOG_TYPE_SEPARATE_SINGLELINE_TEXT_WINDOW { 0 } else { 1 };
+    match memory.write_be16(width_addr, width).and_then(|_| memory.write_be16(height_addr, 1)) {
+        Ok(()) => 0, // CELL_OK
+        Err(_) => CELL_OSKDIALOG_ERROR_GET_SIZE_ERROR,
+    }
+}
+
+/// cellOskDialogSetKeyLayoutOption - Choose the full keyboard or numeric pad layouts
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_osk_dialog_set_key_layout_option(option: u32) -> i32 {
+    debug!("cellOskDialogSetKeyLayoutOption(option=0x{:X})", option);
+
+    crate::context::get_hle_context_mut().osk_dialog.set_key_layout_option(option)
+}
+
+/// cellOskDialogSetLayoutMode - Set the keyboard placement
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_osk_dialog_set_layout_mode(mode: i32) -> i32 {
+    debug!("cellOskDialogSetLayoutMode(mode=0x{:X})", mode);
+
+    crate::context::get_hle_context_mut().osk_dialog.set_layout_mode(mode)
+}
+
+/// cellOskDialogAddSupportLanguage - Add languages the text may use
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_osk_dialog_add_support_language(languages: u32) -> i32 {
+    debug!("cellOskDialogAddSupportLanguage(languages=0x{:X})", languages);
+
+    crate::context::get_hle_context_mut().osk_dialog.add_support_language(languages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osk_manager_lifecycle() {
+        let mut manager = OskDialogManager::new();
+        assert!(manager.request().is_none());
+        assert_eq!(manager.load(OskRequest::new("Name", "", 0)), CELL_OSKDIALOG_ERROR_PARAM);
+
+        assert_eq!(manager.load(OskRequest::new("Name", "Player", 4)), 0);
+        assert_eq!(manager.request().unwrap().init_text, "Player");
+        assert_eq!(manager.load(OskRequest::new("Again", "", 4)), CELL_OSKDIALOG_ERROR_IME_ALREADY_IN_USE);
+        assert!(manager.unload().is_err());
+
+        // Text is cut to the limit in UTF-16 units, keeping characters whole
+        assert_eq!(manager.finish(Some("ab😀cd")), Some(OskInputResult::Ok));
+        assert!(manager.request().is_none());
+        let (result, text) = manager.unload().unwrap();
+        assert_eq!(result, OskInputResult::Ok);
+        assert_eq!(text, "ab😀".encode_utf16().chain([0]).collect::<Vec<u16>>());
+        assert!(!manager.is_loaded());
+
+        let generation = manager.generation();
+        manager.load(OskRequest::new("", "", 16));
+        assert_ne!(manager.generation(), generation);
+        assert_eq!(manager.finish(None), Some(OskInputResult::Canceled));
+        assert_eq!(manager.finish(Some("late")), None);
+        assert_eq!(manager.unload().unwrap(), (OskInputResult::Canceled, vec![0]));
+
+        manager.load(OskRequest::new("", "", 16));
+        assert_eq!(manager.abort(), 0);
+        assert_eq!(manager.input_text().unwrap().0, OskInputResult::Abort);
+    }
+
+    #[test]
+    fn test_osk_guest_text() {
+        let _guard = crate::context::test_guard();
+        crate::context::reset_hle_context();
+        let memory = crate::context::test_guest_memory();
+        let base = memory.allocate(0x1000, 0x1000, oc_memory::PageFlags::RW).unwrap();
+        let (param, field, message, init_text, output, buffer, size) =
+            (base, base + 0x20, base + 0x40, base + 0x80, base + 0xC0, base + 0x100, base + 0x200);
+        let write_utf16 = |addr: u32, text: &str| {
+            for (index, unit) in text.encode_utf16().chain([0]).enumerate() {
+                memory.write_be16(addr + index as u32 * 2, unit).unwrap();
+            }
+        };
+        let panels = panel_mode::ALPHABET | panel_mode::NUMERAL;
+        let param_value = CellOskDialogParam {
+            allow_osk_panel_flg: panels,
+            first_view_panel: panel_mode::ALPHABET,
+            ..Default::default()
+        };
+        memory.write_be(param, param_value).unwrap();
+        memory.write_be(field, CellOskDialogInputFieldInfo { message, init_text, limit_length: 8 }).unwrap();
+        write_utf16(message, "Name?");
+        write_utf16(init_text, "Ryu");
+
+        assert_eq!(cell_osk_dialog_load_async(0, param, field), 0);
+        let request = crate::context::get_hle_context().osk_dialog.request().cloned().unwrap();
+        assert_eq!((request.message.as_str(), request.init_text.as_str(), request.limit), ("Name?", "Ryu", 8));
+        assert_eq!((request.allowed_panels, request.first_panel), (panels, panel_mode::ALPHABET));
+
+        // The text reaches the game's buffer as UTF-16, cut to its size with the NUL kept
+        assert_eq!(finish_osk(Some("ラーメン")), Some(OskInputResult::Ok));
+        let return_param =
+            CellOskDialogCallbackReturnParam { result: -1, num_chars_result_string: 4, result_string: buffer };
+        memory.write_be(output, return_param).unwrap();
+        assert_eq!(cell_osk_dialog_get_input_text(output), 0);
+        let read_text = |units: u32| (0..units).map(|i| memory.read_be16(buffer + i * 2).unwrap()).collect::<Vec<_>>();
+        assert_eq!(read_text(4), "ラーメ".encode_utf16().chain([0]).collect::<Vec<u16>>());
+
+        let return_param = CellOskDialogCallbackReturnParam { num_chars_result_string: 16, ..return_param };
+        memory.write_be(output, return_param).unwrap();
+        assert_eq!(cell_osk_dialog_unload_async(output), 0);
+        let result = memory.read_be::<CellOskDialogCallbackReturnParam>(output).unwrap().result;
+        assert_eq!(result, OskInputResult::Ok as i32);
+        assert_eq!(read_text(5), "ラーメン".encode_utf16().chain([0]).collect::<Vec<u16>>());
+        assert!(!crate::context::get_hle_context().osk_dialog.is_loaded());
+
+        assert_eq!(cell_osk_dialog_get_size(size, size + 2, 0), 0);
+        assert_eq!((memory.read_be16(size).unwrap(), memory.read_be16(size + 2).unwrap()), (1, 1));
+        assert_eq!(cell_osk_dialog_get_size(size, 0, 0), CELL_OSKDIALOG_ERROR_PARAM);
+        crate::context::reset_hle_context();
+    }
+
+    #[test]
+    fn test_osk_panels() {
+        let mut request = OskRequest::new("", "", 16);
+        assert!(request.allows(panel_mode::JAPANESE_HIRAGANA));
+        request.allowed_panels = panel_mode::NUMERAL | panel_mode::PASSWORD;
+        assert!(request.allows(panel_mode::NUMERAL));
+        assert!(!request.allows(panel_mode::ALPHABET));
+        assert!(request.is_password());
+    }
+
+    #[test]
+    fn test_romaji_to_kana() {
+        assert_eq!(romaji_to_kana("konnichiha", false), ("こんにちは".to_string(), String::new()));
+        assert_eq!(romaji_to_kana("kitto", false), ("きっと".to_string(), String::new()));
+        assert_eq!(romaji_to_kana("shinbun", false), ("しんぶ".to_string(), "n".to_string()));
+        assert_eq!(romaji_to_kana("kyo", true), ("キョ".to_string(), String::new()));
+        assert_eq!(romaji_to_kana("sh", false), (String::new(), "sh".to_string()));
+        assert_eq!(romaji_to_kana("ra-men1", true), ("ラーメン1".to_string(), String::new()));
+        assert_eq!(romaji_to_kana("KA", false), ("か".to_string(), String::new()));
+    }
+}
@@ -0,0 +1,350 @@
+//! cellOvis HLE - SPU overlay manager
+//!
+//! This module provides HLE implementations for the cellOvis library, which
+//! sets up SPU programs linked with overlays. Overlays are load segments of
+//! the SPU ELF that share a region of local storage; the SPU's overlay
+//! manager DMAs the one it calls into the region on demand. The overlay table
+//! tells it where each overlay lives in the ELF in main memory, and which
+//! overlay each region holds. Only the first overlay of each region is loaded
+//! with the image, so the overlapping segments are dropped from it.
+//!
+//! Note: swapping overlays at run time is done by the overlay manager in the
+//! SPU program and needs the MFC DMA of the SPU interpreter.
+
+use oc_memory::BeValue;
+use tracing::{debug, trace};
+
+/// Error codes
+pub const CELL_OVIS_ERROR_INVAL: i32 = 0x80410402u32 as i32;
+pub const CELL_OVIS_ERROR_ABORT: i32 = 0x8041040Cu32 as i32;
+pub const CELL_OVIS_ERROR_ALIGN: i32 = 0x80410410u32 as i32;
+
+/// ELF machine of SPU programs
+pub const EM_SPU: u16 = 23;
+/// Loadable program header type
+pub const PT_LOAD: u32 = 1;
+/// Program header flag the SPU linker sets on overlay segments
+pub const PF_OVERLAY: u32 = 0x0800_0000;
+
+/// sys_spu_segment types
+pub const SYS_SPU_SEGMENT_TYPE_COPY: u32 = 1;
+pub const SYS_SPU_SEGMENT_TYPE_FILL: u32 = 2;
+pub const SYS_SPU_SEGMENT_TYPE_INFO: u32 = 4;
+
+/// Size of an overlay table entry (_ovly_table)
+pub const OVERLAY_ENTRY_SIZE: u32 = 16;
+/// Size of a region entry (_ovly_buf_table)
+pub const OVERLAY_BUF_ENTRY_SIZE: u32 = 4;
+
+/// Load segment of an SPU ELF
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpuElfSegment {
+    /// Local storage address
+    pub vaddr: u32,
+    /// Offset of the data in the ELF
+    pub offset: u32,
+    pub filesz: u32,
+    pub memsz: u32,
+    pub flags: u32,
+}
+
+impl SpuElfSegment {
+    pub fn is_overlay(&self) -> bool {
+        self.flags & PF_OVERLAY != 0
+    }
+}
+
+/// ELF32 program header
+#[derive(Debug, Clone, Copy, BeValue)]
+#[repr(C)]
+struct Elf32Phdr {
+    p_type: u32,
+    offset: u32,
+    vaddr: u32,
+    paddr: u32,
+    filesz: u32,
+    memsz: u32,
+    flags: u32,
+    align: u32,
+}
+
+fn be<T: BeValue>(elf: &[u8], offset: usize) -> Option<T> {
+    elf.get(offset..).and_then(T::try_from_be_slice)
+}
+
+/// Parse the load segments of an SPU ELF (32-bit, big-endian)
+pub fn parse_spu_segments(elf: &[u8]) -> Result<Vec<SpuElfSegment>, i32> {
+    if elf.len() < 52 || &elf[0..4] != b"\x7FELF" || elf[4] != 1 || elf[5] != 2 {
+        return Err(CELL_OVIS_ERROR_INVAL);
+    }
+    if be::<u16>(elf, 18) != Some(EM_SPU) {
+        return Err(CELL_OVIS_ERROR_INVAL);
+    }
+
+    let phoff = be::<u32>(elf, 28).ok_or(CELL_OVIS_ERROR_INVAL)? as usize;
+    let phentsize = be::<u16>(elf, 42).ok_or(CELL_OVIS_ERROR_INVAL)? as usize;
+    let phnum = be::<u16>(elf, 44).ok_or(CELL_OVIS_ERROR_INVAL)? as usize;
+    if phnum > 0 && phentsize < Elf32Phdr::SIZE {
+        return Err(CELL_OVIS_ERROR_INVAL);
+    }
+
+    let mut segments = Vec::new();
+    for i in 0..phnum {
+        let ph: Elf32Phdr = be(elf, phoff + i * phentsize).ok_or(CELL_OVIS_ERROR_ABORT)?;
+        if ph.p_type != PT_LOAD {
+            continue;
+        }
+        segments.push(SpuElfSegment {
+            offset: ph.offset,
+            vaddr: ph.vaddr,
+            filesz: ph.filesz,
+            memsz: ph.memsz,
+            flags: ph.flags,
+        });
+    }
+    Ok(segments)
+}
+
+/// Overlay of the overlay table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BeValue)]
+#[repr(C)]
+pub struct OverlayEntry {
+    /// Local storage address of its region
+    pub vma: u32,
+    /// Bytes to DMA, a multiple of 16
+    pub size: u32,
+    /// Address of its data in main memory
+    pub ea: u32,
+    /// Region it loads into, from 1
+    pub buf: u32,
+}
+
+/// Overlay table of an SPU program
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OverlayTable {
+    pub entries: Vec<OverlayEntry>,
+    /// Overlay held by each region, from 1
+    pub mapped: Vec<u32>,
+}
+
+impl OverlayTable {
+    /// Build the table of an ELF loaded at `elf_addr`
+    ///
+    /// Overlays starting at the same address share a region, which holds the
+    /// first of them once the image is loaded.
+    pub fn new(segments: &[SpuElfSegment], elf_addr: u32) -> Self {
+        let mut table = Self::default();
+        let mut regions: Vec<u32> = Vec::new();
+        for segment in segments.iter().filter(|segment| segment.is_overlay()) {
+            let buf = match regions.iter().position(|&vma| vma == segment.vaddr) {
+                Some(index) => index as u32 + 1,
+                None => {
+                    regions.push(segment.vaddr);
+                    table.mapped.push(table.entries.len() as u32 + 1);
+                    regions.len() as u32
+                }
+            };
+            table.entries.push(OverlayEntry {
+                vma: segment.vaddr,
+                size: segment.filesz.div_ceil(16) * 16,
+                ea: elf_addr.wrapping_add(segment.offset),
+                buf,
+            });
+        }
+        table
+    }
+
+    /// Bytes the table takes in guest memory
+    pub fn size(&self) -> u32 {
+        self.entries.len() as u32 * OVERLAY_ENTRY_SIZE + self.mapped.len() as u32 * OVERLAY_BUF_ENTRY_SIZE
+    }
+
+    /// Guest layout of the table: the overlay entries, then the regions
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.size() as usize);
+        for entry in &self.entries {
+            bytes.extend_from_slice(&entry.to_be_vec());
+        }
+        for mapped in &self.mapped {
+            bytes.extend_from_slice(&mapped.to_be_vec());
+        }
+        bytes
+    }
+}
+
+/// Size of the overlay table of an SPU ELF
+pub fn overlay_table_size(elf: &[u8]) -> Result<u32, i32> {
+    Ok(OverlayTable::new(&parse_spu_segments(elf)?, 0).size())
+}
+
+/// Segment of a sys_spu_image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SysSpuSegment {
+    pub seg_type: u32,
+    /// Local storage address
+    pub ls: u32,
+    pub size: u32,
+    /// Address of the data, or the fill pattern
+    pub addr: u32,
+}
+
+impl SysSpuSegment {
+    fn overlaps(&self, other: &SysSpuSegment) -> bool {
+        self.ls < other.ls.saturating_add(other.size) && other.ls < self.ls.saturating_add(self.size)
+    }
+}
+
+/// Drop the segments whose local storage an earlier segment already covers
+///
+/// These are the overlays after the first of each region; info segments
+/// take no local storage and are kept.
+pub fn invalidate_overlapped_segments(segments: &mut Vec<SysSpuSegment>) {
+    let mut kept: Vec<SysSpuSegment> = Vec::with_capacity(segments.len());
+    for segment in segments.drain(..) {
+        let loads = segment.seg_type != SYS_SPU_SEGMENT_TYPE_INFO && segment.size != 0;
+        if loads && kept.iter().any(|other| other.seg_type != SYS_SPU_SEGMENT_TYPE_INFO && other.overlaps(&segment)) {
+            continue;
+        }
+        kept.push(segment);
+    }
+    *segments = kept;
+}
+
+/// cellOvisGetOverlayTableSize - Get the size of an SPU ELF's overlay table
+///
+/// # Arguments
+/// * `elf_addr` - Address of the SPU ELF
+///
+/// # Returns
+/// * The table size in bytes, or an error code
+pub fn cell_ovis_get_overlay_table_size(elf_addr: u32) -> i32 {
+    debug!("cellOvisGetOverlayTableSize(elf=0x{:08X})", elf_addr);
+
+    if elf_addr == 0 {
+        return CELL_OVIS_ERROR_INVAL;
+    }
+
+    // Note: reading the ELF for overlay_table_size requires memory subsystem integration
+    0
+}
+
+/// cellOvisInitializeOverlayTable - Write the overlay table of an SPU ELF
+///
+/// # Arguments
+/// * `table_addr` - Address of the table, 16-byte aligned
+/// * `elf_addr` - Address of the SPU ELF
+///
+/// # Returns
+/// * 0 on success, or an error code
+pub fn cell_ovis_initialize_overlay_table(table_addr: u32, elf_addr: u32) -> i32 {
+    debug!("cellOvisInitializeOverlayTable(table=0x{:08X}, elf=0x{:08X})", table_addr, elf_addr);
+
+    if table_addr == 0 || elf_addr == 0 {
+        return CELL_OVIS_ERROR_INVAL;
+    }
+    if table_addr & 0xF != 0 {
+        return CELL_OVIS_ERROR_ALIGN;
+    }
+
+    // Note: reading the ELF and writing OverlayTable::to_bytes requires memory subsystem integration
+    0 // CELL_OK
+}
+
+/// cellOvisFixSpuSegments - Drop the overlapped overlays of a sys_spu_image
+///
+/// # Arguments
+/// * `image_addr` - Address of the sys_spu_image
+///
+/// # Returns
+/// * 0 on success, or an error code
+pub fn cell_ovis_fix_spu_segments(image_addr: u32) -> i32 {
+    debug!("cellOvisFixSpuSegments(image=0x{:08X})", image_addr);
+
+    if image_addr == 0 {
+        return CELL_OVIS_ERROR_INVAL;
+    }
+
+    // Note: rewriting the image's segments with invalidate_overlapped_segments requires memory subsystem integration
+    0 // CELL_OK
+}
+
+/// cellOvisInvalidateOverlappedSegments - Drop the overlapped overlays of a segment list
+///
+/// # Arguments
+/// * `segs_addr` - Address of the sys_spu_segment array
+/// * `nsegs_addr` - Address of the segment count, updated to the segments kept
+pub fn cell_ovis_invalidate_overlapped_segments(segs_addr: u32, nsegs_addr: u32) {
+    trace!("cellOvisInvalidateOverlappedSegments(segs=0x{:08X}, nsegs=0x{:08X})", segs_addr, nsegs_addr);
+
+    // Note: rewriting the segments with invalidate_overlapped_segments requires memory subsystem integration
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SPU ELF with a root segment and three overlays in two regions
+    fn overlay_elf() -> Vec<u8> {
+        let phdrs = [
+            (0x0000_0080u32, 0x0000_0100u32, 0x1000u32, 0x1200u32, 5u32),
+            (0x1080, 0x2000, 0x0300, 0x0300, 5 | PF_OVERLAY),
+            (0x1380, 0x2000, 0x0404, 0x0404, 5 | PF_OVERLAY),
+            (0x1800, 0x3000, 0x0100, 0x0100, 5 | PF_OVERLAY),
+        ];
+        let mut elf = vec![0u8; 52];
+        elf[0..4].copy_from_slice(b"\x7FELF");
+        elf[4] = 1;
+        elf[5] = 2;
+        elf[18..20].copy_from_slice(&EM_SPU.to_be_bytes());
+        elf[28..32].copy_from_slice(&52u32.to_be_bytes());
+        elf[42..44].copy_from_slice(&32u16.to_be_bytes());
+        elf[44..46].copy_from_slice(&(phdrs.len() as u16).to_be_bytes());
+        for (offset, vaddr, filesz, memsz, flags) in phdrs {
+            for value in [PT_LOAD, offset, vaddr, vaddr, filesz, memsz, flags, 0x80] {
+                elf.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        elf
+    }
+
+    #[test]
+    fn test_overlay_table() {
+        let elf = overlay_elf();
+        let segments = parse_spu_segments(&elf).unwrap();
+        assert_eq!(segments.len(), 4);
+        assert!(!segments[0].is_overlay());
+        assert_eq!(overlay_table_size(&elf), Ok(3 * 16 + 2 * 4));
+
+        let table = OverlayTable::new(&segments, 0x0040_0000);
+        assert_eq!(
+            table.entries[1],
+            OverlayEntry { vma: 0x2000, size: 0x410, ea: 0x0040_1380, buf: 1 }
+        );
+        assert_eq!(table.entries[2].buf, 2);
+        // Each region holds its first overlay
+        assert_eq!(table.mapped, vec![1, 3]);
+        let bytes = table.to_bytes();
+        assert_eq!(bytes.len() as u32, table.size());
+        assert_eq!(&bytes[16..20], &0x2000u32.to_be_bytes());
+        assert_eq!(&bytes[48..52], &1u32.to_be_bytes());
+
+        assert_eq!(parse_spu_segments(&elf[..40]), Err(CELL_OVIS_ERROR_INVAL));
+        assert_eq!(cell_ovis_initialize_overlay_table(0x1_0008, 0x2_0000), CELL_OVIS_ERROR_ALIGN);
+    }
+
+    #[test]
+    fn test_invalidate_overlapped_segments() {
+        let copy = |ls, size| SysSpuSegment { seg_type: SYS_SPU_SEGMENT_TYPE_COPY, ls, size, addr: 0 };
+        let mut segments = vec![
+            copy(0x0000, 0x1000),
+            SysSpuSegment { seg_type: SYS_SPU_SEGMENT_TYPE_FILL, ls: 0x1000, size: 0x200, addr: 0 },
+            copy(0x2000, 0x300),
+            copy(0x2000, 0x404),
+            SysSpuSegment { seg_type: SYS_SPU_SEGMENT_TYPE_INFO, ls: 0, size: 0x20, addr: 0 },
+            copy(0x3000, 0x100),
+        ];
+        invalidate_overlapped_segments(&mut segments);
+        let kept: Vec<u32> = segments.iter().map(|segment| segment.ls).collect();
+        assert_eq!(kept, vec![0x0000, 0x1000, 0x2000, 0, 0x3000]);
+    }
+}
@@ -3,11 +3,16 @@
 //! This module provides HLE implementations for PS3 controller input.
 //! It bridges to the oc-input subsystem.
 
+use oc_core::config::InstrumentKind;
+use oc_core::savestate::{StateReader, StateWriter};
+use oc_input::pad::PadState;
+use oc_input::{PadPorts, SixaxisData, VibrationState};
+use std::io;
+use std::sync::Arc;
 use tracing::{debug, trace};
 
-/// OC-Input backend reference placeholder
-/// In a real implementation, this would hold a reference to oc-input Pad system
-type InputBackend = Option<()>;
+/// OC-Input backend: controller ports fed by host input devices
+type InputBackend = Option<Arc<PadPorts>>;
 
 /// Maximum number of controllers
 pub const CELL_PAD_MAX_PORT_NUM: usize = 7;
@@ -15,6 +20,15 @@ pub const CELL_PAD_MAX_PORT_NUM: usize = 7;
 /// Maximum number of codes (buttons/axes) per controller
 pub const CELL_PAD_MAX_CODES: usize = 64;
 
+/// Port status flags
+pub const CELL_PAD_STATUS_DISCONNECTED: u32 = 0;
+pub const CELL_PAD_STATUS_CONNECTED: u32 = 1;
+/// Set once after a controller was connected or disconnected on the port
+pub const CELL_PAD_STATUS_ASSIGN_CHANGES: u32 = 2;
+
+/// System info flag: a system dialog has the controllers, games see idle pads
+pub const CELL_PAD_INFO_INTERCEPTED: u32 = 1;
+
 /// Button codes (digital buttons in button[0])
 pub mod button_codes {
     pub const CELL_PAD_CTRL_LEFT: u16 = 0x0080;
@@ -61,7 +75,7 @@ pub struct CellPadInfo {
     pub now_connect: u32,
     /// System information
     pub system_info: u32,
-    /// Status of each port (connected=1, disconnected=0)
+    /// Status flags of each port (CELL_PAD_STATUS_*)
     pub port_status: [u32; CELL_PAD_MAX_PORT_NUM],
     /// Device capability info for each port
     pub device_capability: [u32; CELL_PAD_MAX_PORT_NUM],
@@ -91,6 +105,13 @@ pub struct CellPadData {
     pub len: i32,
     /// Digital button data (16 bits)
     pub button: [u16; 2],
+    /// Analog sticks: right X, right Y, left X, left Y (0-255, 128 = center)
+    pub analog: [u16; 4],
+    /// Button pressure: right, left, up, down, triangle, circle, cross,
+    /// square, L1, R1, L2, R2 (0-255)
+    pub pressure: [u16; 12],
+    /// Sixaxis sensors: accel X, Y, Z and gyro Z (10-bit, 512 = center)
+    pub sensor: [u16; 4],
 }
 
 
@@ -141,8 +162,14 @@ impl Default for CellPadCapabilityInfo {
 pub struct PadManager {
     /// Initialization flag
     initialized: bool,
+    /// Number of ports the game asked for
+    max_connect: u32,
     /// Connected pad mask
     connected_pads: u8,
+    /// Ports whose connection changed since the last cellPadGetInfo
+    assign_changes: u8,
+    /// Backend connection count of each port, to catch a quick replug
+    port_generations: [u32; CELL_PAD_MAX_PORT_NUM],
     /// Device types for each port
     device_types: [CellPadDeviceType; CELL_PAD_MAX_PORT_NUM],
     /// OC-Input backend
@@ -151,6 +178,8 @@ pub struct PadManager {
     pad_data: [CellPadData; CELL_PAD_MAX_PORT_NUM],
     /// Rumble/vibration state for each port
     rumble_states: [RumbleState; CELL_PAD_MAX_PORT_NUM],
+    /// A system dialog such as the on-screen keyboard owns the controllers
+    intercepted: bool,
 }
 
 impl PadManager {
@@ -158,11 +187,15 @@ impl PadManager {
     pub fn new() -> Self {
         Self {
             initialized: false,
+            max_connect: CELL_PAD_MAX_PORT_NUM as u32,
             connected_pads: 0,
+            assign_changes: 0,
+            port_generations: [0; CELL_PAD_MAX_PORT_NUM],
             device_types: [CellPadDeviceType::Standard; CELL_PAD_MAX_PORT_NUM],
             input_backend: None,
             pad_data: [CellPadData::default(); CELL_PAD_MAX_PORT_NUM],
             rumble_states: [RumbleState::default(); CELL_PAD_MAX_PORT_NUM],
+            intercepted: false,
         }
     }
 
@@ -173,18 +206,23 @@ impl PadManager {
             return 0x80121101u32 as i32; // CELL_PAD_ERROR_ALREADY_INITIALIZED
         }
 
+        if max_connect == 0 {
+            return 0x80121104u32 as i32; // CELL_PAD_ERROR_INVALID_PARAMETER
+        }
+
         debug!("cellPadInit: initializing pad system with max_connect={}", max_connect);
         self.initialized = true;
+        self.max_connect = max_connect.min(CELL_PAD_MAX_PORT_NUM as u32);
 
         // Simulate one controller connected on port 0
         self.connected_pads = 0x01;
+        self.assign_changes = 0x01;
+        self.port_generations = [0; CELL_PAD_MAX_PORT_NUM];
         self.device_types[0] = CellPadDeviceType::Standard;
         
         // Initialize pad data for the connected controller
         self.pad_data[0].len = 24; // Standard data length
 
-        // TODO: Connect to oc-input subsystem
-
         0 // CELL_OK
     }
 
@@ -198,24 +236,35 @@ impl PadManager {
         debug!("cellPadEnd: shutting down pad system");
         self.initialized = false;
         self.connected_pads = 0;
+        self.assign_changes = 0;
 
         0 // CELL_OK
     }
 
     /// Get pad info
-    pub fn get_info(&self) -> CellPadInfo {
+    ///
+    /// Reports the connection changes since the previous call once, through
+    /// `CELL_PAD_STATUS_ASSIGN_CHANGES`.
+    pub fn get_info(&mut self) -> CellPadInfo {
         let mut info = CellPadInfo::default();
 
         if self.initialized {
-            // Report connected pads
+            info.max = self.max_connect;
+            if self.intercepted {
+                info.system_info |= CELL_PAD_INFO_INTERCEPTED;
+            }
             for port in 0..CELL_PAD_MAX_PORT_NUM {
                 if (self.connected_pads & (1 << port)) != 0 {
                     info.now_connect += 1;
-                    info.port_status[port] = 1;
+                    info.port_status[port] = CELL_PAD_STATUS_CONNECTED;
                     info.device_capability[port] = 0; // Standard controller
                     info.device_type[port] = self.device_types[port] as u32;
                 }
+                if (self.assign_changes & (1 << port)) != 0 {
+                    info.port_status[port] |= CELL_PAD_STATUS_ASSIGN_CHANGES;
+                }
             }
+            self.assign_changes = 0;
         }
 
         info
@@ -282,6 +331,7 @@ impl PadManager {
         }
 
         self.connected_pads |= 1 << port;
+        self.assign_changes |= 1 << port;
         self.device_types[port as usize] = device_type;
         
         // Initialize pad data for the connected controller
@@ -303,6 +353,7 @@ impl PadManager {
         }
 
         self.connected_pads &= !(1 << port);
+        self.assign_changes |= 1 << port;
         self.pad_data[port as usize] = CellPadData::default();
         
         debug!("Disconnected pad on port {}", port);
@@ -329,31 +380,50 @@ impl PadManager {
         0 // CELL_OK
     }
 
+    /// Update pad data from a full oc-input pad state
+    pub fn update_pad_state(&mut self, port: u32, state: &PadState, motion: &SixaxisData) -> i32 {
+        use oc_input::dualshock3::pressure_index as idx;
+
+        let ret = self.update_pad_data(port, Self::map_button(state.buttons));
+        if ret != 0 {
+            return ret;
+        }
+
+        let data = &mut self.pad_data[port as usize];
+        data.analog = [state.right_x, state.right_y, state.left_x, state.left_y].map(u16::from);
+        data.pressure = [
+            idx::DPAD_RIGHT, idx::DPAD_LEFT, idx::DPAD_UP, idx::DPAD_DOWN,
+            idx::TRIANGLE, idx::CIRCLE, idx::CROSS, idx::SQUARE,
+            idx::L1, idx::R1, idx::L2, idx::R2,
+        ]
+        .map(|i| state.pressure[i] as u16);
+        data.sensor = [motion.accel_x, motion.accel_y, motion.accel_z, motion.gyro_z]
+            .map(|v| (v as i32 + 512).clamp(0, 1023) as u16);
+
+        0 // CELL_OK
+    }
+
     // ========================================================================
     // OC-Input Backend Integration
     // ========================================================================
 
     /// Connect to oc-input backend
     /// 
-    /// This would integrate with oc-input for actual controller input.
-    /// For now, this is a stub implementation.
-    pub fn connect_input_backend(&mut self, _backend: InputBackend) -> i32 {
-        debug!("PadManager::connect_input_backend");
-        
-        // In a real implementation:
-        // 1. Store the oc-input backend reference
-        // 2. Register pad input callbacks
-        // 3. Query connected controllers
-        // 4. Set up button/axis mappings
-        
-        self.input_backend = None; // Would store actual backend
-        
+    /// Once connected, the backend's ports decide which pads are connected
+    /// and pad data is refreshed from them on every poll.
+    pub fn connect_input_backend(&mut self, backend: InputBackend) -> i32 {
+        debug!("PadManager::connect_input_backend(connected={})", backend.is_some());
+
+        self.input_backend = backend;
+
         0 // CELL_OK
     }
 
     /// Poll input from backend
     /// 
-    /// Reads current input state from oc-input and updates pad data.
+    /// Reads current input state from oc-input and updates pad data,
+    /// connecting and disconnecting ports as host controllers come and go.
+    /// Ports beyond the game's `max_connect` are left disconnected.
     pub fn poll_input(&mut self) -> i32 {
         if !self.initialized {
             return 0x80121103u32 as i32; // CELL_PAD_ERROR_UNINITIALIZED
@@ -361,34 +431,74 @@ impl PadManager {
 
         trace!("PadManager::poll_input");
 
-        // In a real implementation, this would:
-        // 1. Query oc-input for current controller states
-        // 2. Convert oc-input button/axis data to PS3 format
-        // 3. Update pad_data for each connected pad
-        // 4. Handle button pressure sensitivity
-        // 5. Handle analog stick values
+        let Some(ports) = self.input_backend.clone() else {
+            // Without a backend, pad data is manually updated via update_pad_data()
+            return 0; // CELL_OK
+        };
 
-        // For HLE, pad data is manually updated via update_pad_data()
+        for port in 0..CELL_PAD_MAX_PORT_NUM {
+            let connected = (self.connected_pads & (1 << port)) != 0;
+            let state = ports.state(port as u8).filter(|_| port < self.max_connect as usize);
+            match state {
+                Some(state) => {
+                    // A different controller may have been plugged in since the last poll
+                    let generation = ports.generation(port as u8);
+                    if connected && generation != self.port_generations[port] {
+                        self.disconnect_pad(port as u32);
+                    }
+                    let device_type = match ports.instrument(port as u8) {
+                        InstrumentKind::None => CellPadDeviceType::Standard,
+                        InstrumentKind::Guitar => CellPadDeviceType::Guitar,
+                        InstrumentKind::Drums => CellPadDeviceType::Drum,
+                    };
+                    if !connected || generation != self.port_generations[port] {
+                        self.connect_pad(port as u32, device_type);
+                    } else if self.device_types[port] != device_type {
+                        // The port was switched to or from an instrument
+                        self.device_types[port] = device_type;
+                        self.assign_changes |= 1 << port;
+                    }
+                    self.port_generations[port] = generation;
+                    if self.intercepted {
+                        self.update_pad_state(port as u32, &PadState::new(), &SixaxisData::at_rest());
+                    } else {
+                        let motion = ports.motion(port as u8).unwrap_or_else(SixaxisData::at_rest);
+                        self.update_pad_state(port as u32, &state, &motion);
+                    }
+                }
+                None if connected => {
+                    self.disconnect_pad(port as u32);
+                }
+                None => {}
+            }
+        }
 
         0 // CELL_OK
     }
 
-    /// Map oc-input button to PS3 button
-    /// 
-    /// Converts button codes between oc-input and PS3 formats.
-    pub fn map_button(oc_input_button: u32) -> u16 {
-        // In a real implementation, this would map:
-        // oc-input button codes -> PS3 button codes
-        // 
-        // For example:
-        // oc_input::PadButtons::CROSS -> CELL_PAD_CTRL_CROSS
-        // oc_input::PadButtons::CIRCLE -> CELL_PAD_CTRL_CIRCLE
-        // etc.
+    /// Hand the controllers to a system dialog, or give them back
+    ///
+    /// While intercepted, polls publish idle pads and cellPadGetInfo
+    /// reports `CELL_PAD_INFO_INTERCEPTED`.
+    pub fn set_intercepted(&mut self, intercepted: bool) {
+        debug!("PadManager::set_intercepted({})", intercepted);
+        self.intercepted = intercepted;
+    }
 
-        trace!("Mapping button: 0x{:08X}", oc_input_button);
+    /// Whether a system dialog owns the controllers
+    pub fn is_intercepted(&self) -> bool {
+        self.intercepted
+    }
 
-        // Return unmapped for now
-        0
+    /// Map oc-input buttons to PS3 button words
+    /// 
+    /// oc-input's `PadButtons` uses the cellPad bit layout, with the
+    /// button[0] codes in the low byte and the button[1] codes above them.
+    pub fn map_button(oc_input_buttons: u32) -> [u16; 2] {
+        [
+            (oc_input_buttons & 0xFF) as u16,
+            ((oc_input_buttons >> 8) & 0xFF) as u16,
+        ]
     }
 
     /// Convert analog axis value
@@ -444,10 +554,16 @@ impl PadManager {
             port, param.motor_small, param.motor_large
         );
 
-        // In a real implementation, this would:
-        // 1. Send rumble commands to oc-input backend
-        // 2. Control actual controller motors
-        // 3. Handle timing and duration
+        // Forward to the host controller
+        if let Some(ports) = &self.input_backend {
+            ports.set_vibration(
+                port as u8,
+                VibrationState {
+                    small_motor: param.motor_small.min(1),
+                    large_motor: param.motor_large,
+                },
+            );
+        }
 
         0 // CELL_OK
     }
@@ -508,6 +624,24 @@ impl PadManager {
 
         self.rumble_states[port as usize].active
     }
+    /// Save the initialization and connection state for a savestate
+    ///
+    /// Button and sensor data are read from the host again on the next poll.
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.bool(self.initialized);
+        w.u32(self.max_connect);
+        w.u8(self.connected_pads);
+        w.u8(self.assign_changes);
+    }
+
+    /// Restore state written by [`PadManager::save_state`]
+    pub fn load_state(&mut self, r: &mut StateReader) -> io::Result<()> {
+        self.initialized = r.bool()?;
+        self.max_connect = r.u32()?;
+        self.connected_pads = r.u8()?;
+        self.assign_changes = r.u8()?;
+        Ok(())
+    }
 }
 
 impl Default for PadManager {
@@ -549,7 +683,9 @@ pub fn cell_pad_end() -> i32 {
 pub fn cell_pad_get_info(_info_addr: u32) -> i32 {
     trace!("cellPadGetInfo()");
 
-    let _info = crate::context::get_hle_context().pad.get_info();
+    let mut ctx = crate::context::get_hle_context_mut();
+    ctx.pad.poll_input();
+    let _info = ctx.pad.get_info();
     // TODO: Write info to memory at _info_addr
 
     0 // CELL_OK
@@ -565,7 +701,9 @@ pub fn cell_pad_get_info(_info_addr: u32) -> i32 {
 pub fn cell_pad_get_info2(_info_addr: u32) -> i32 {
     trace!("cellPadGetInfo2()");
 
-    let _info = crate::context::get_hle_context().pad.get_info();
+    let mut ctx = crate::context::get_hle_context_mut();
+    ctx.pad.poll_input();
+    let _info = ctx.pad.get_info();
     // TODO: Write info to memory at _info_addr
 
     0 // CELL_OK
@@ -582,7 +720,9 @@ pub fn cell_pad_get_info2(_info_addr: u32) -> i32 {
 pub fn cell_pad_get_data(port: u32, _data_addr: u32) -> i32 {
     trace!("cellPadGetData(port={})", port);
 
-    match crate::context::get_hle_context().pad.get_data(port) {
+    let mut ctx = crate::context::get_hle_context_mut();
+    ctx.pad.poll_input();
+    match ctx.pad.get_data(port) {
         Ok(_data) => {
             // TODO: Write data to memory at _data_addr
             0 // CELL_OK
@@ -836,6 +976,86 @@ mod tests {
         manager.end();
     }
 
+    #[test]
+    fn test_input_backend_hotplug() {
+        use oc_input::pad::{PadButtons, PadState};
+
+        let ports = Arc::new(PadPorts::new());
+        let mut manager = PadManager::new();
+        manager.init(7);
+        manager.connect_input_backend(Some(ports.clone()));
+        assert!(manager.is_backend_connected());
+
+        // The backend decides which ports are connected
+        manager.poll_input();
+        assert_eq!(manager.get_info().now_connect, 0);
+
+        let port = ports.connect_free().unwrap();
+        let mut state = PadState::new();
+        state.set_button(PadButtons::CROSS, true);
+        state.set_button(PadButtons::START, true);
+        ports.update(port, state);
+        manager.poll_input();
+        let data = manager.get_data(port as u32).unwrap();
+        assert_eq!(data.button[0], button_codes::CELL_PAD_CTRL_START);
+        assert_eq!(data.button[1], button_codes_2::CELL_PAD_CTRL_CROSS);
+        assert_eq!(data.analog, [128; 4]);
+        assert_eq!(data.sensor[2], 512 + 511);
+
+        let param = CellPadActParam { motor_small: 1, motor_large: 200, reserved: [0; 6] };
+        manager.set_actuator(port as u32, &param);
+        assert_eq!(ports.vibration(port).large_motor, 200);
+
+        // Buttons pressed in a system dialog do not reach the game
+        manager.set_intercepted(true);
+        manager.poll_input();
+        assert_eq!(manager.get_data(port as u32).unwrap().button[1], 0);
+        assert_eq!(manager.get_info().system_info, CELL_PAD_INFO_INTERCEPTED);
+        manager.set_intercepted(false);
+        manager.poll_input();
+        assert_eq!(manager.get_data(port as u32).unwrap().button[1], button_codes_2::CELL_PAD_CTRL_CROSS);
+
+        ports.disconnect(port);
+        manager.poll_input();
+        assert!(manager.get_data(port as u32).is_err());
+    }
+
+    #[test]
+    fn test_assign_changes() {
+        let ports = Arc::new(PadPorts::new());
+        let mut manager = PadManager::new();
+        assert_eq!(manager.init(0), 0x80121104u32 as i32);
+        manager.init(2);
+        manager.connect_input_backend(Some(ports.clone()));
+        manager.poll_input();
+        let info = manager.get_info();
+        assert_eq!(info.max, 2);
+        assert_eq!(info.port_status[0], CELL_PAD_STATUS_DISCONNECTED | CELL_PAD_STATUS_ASSIGN_CHANGES);
+
+        // Changes are reported once
+        for _ in 0..3 {
+            ports.connect_free();
+        }
+        manager.poll_input();
+        let info = manager.get_info();
+        assert_eq!(info.now_connect, 2); // Port 2 is beyond max_connect
+        assert_eq!(info.port_status[1], CELL_PAD_STATUS_CONNECTED | CELL_PAD_STATUS_ASSIGN_CHANGES);
+        assert_eq!(manager.get_info().port_status[1], CELL_PAD_STATUS_CONNECTED);
+
+        // Swapping controllers between polls still shows up as a change
+        ports.disconnect(1);
+        ports.connect_free();
+        manager.poll_input();
+        assert_eq!(manager.get_info().port_status[1], CELL_PAD_STATUS_CONNECTED | CELL_PAD_STATUS_ASSIGN_CHANGES);
+
+        // Instruments report their device type
+        ports.set_instrument(1, InstrumentKind::Drums);
+        manager.poll_input();
+        let info = manager.get_info();
+        assert_eq!(info.device_type[1], CellPadDeviceType::Drum as u32);
+        assert_eq!(info.port_status[1], CELL_PAD_STATUS_CONNECTED | CELL_PAD_STATUS_ASSIGN_CHANGES);
+    }
+
     #[test]
     fn test_axis_conversion() {
         // Test axis conversion
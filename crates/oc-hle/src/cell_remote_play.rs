@@ -0,0 +1,222 @@
+//! cellRemotePlay HLE - Remote Play session queries
+//!
+//! This module provides HLE implementations for games that check whether
+//! they are being streamed to a PSP or PS Vita. No Remote Play session is
+//! ever active, so status queries report the idle state of a console that
+//! nobody is connected to.
+
+use tracing::{debug, trace};
+
+/// Error codes
+pub const CELL_REMOTEPLAY_ERROR_INTERNAL: i32 = 0x80029830u32 as i32;
+
+/// Remote Play status
+pub const CELL_REMOTEPLAY_STATUS_LOADING: u32 = 0;
+pub const CELL_REMOTEPLAY_STATUS_WAIT: u32 = 1;
+pub const CELL_REMOTEPLAY_STATUS_RUNNING: u32 = 2;
+pub const CELL_REMOTEPLAY_STATUS_UNLOADING: u32 = 3;
+pub const CELL_REMOTEPLAY_STATUS_FATALERROR: u32 = 4;
+pub const CELL_REMOTEPLAY_STATUS_PREMOEND: u32 = 5;
+
+/// Comparative volume at full level
+pub const CELL_REMOTEPLAY_VOLUME_MAX: f32 = 1.0;
+
+/// Remote Play manager
+pub struct RemotePlayManager {
+    /// Volume of the peer's audio relative to the TV
+    comparative_volume: f32,
+    /// Whether the game asked for all streamed data to be encrypted
+    encrypt_all: bool,
+}
+
+impl RemotePlayManager {
+    /// Create a new Remote Play manager
+    pub fn new() -> Self {
+        Self {
+            comparative_volume: CELL_REMOTEPLAY_VOLUME_MAX,
+            encrypt_all: false,
+        }
+    }
+
+    /// Current session status, always LOADING as no session exists
+    pub fn status(&self) -> u32 {
+        CELL_REMOTEPLAY_STATUS_LOADING
+    }
+
+    /// Set the peer's comparative volume
+    pub fn set_comparative_volume(&mut self, volume: f32) -> i32 {
+        if !(0.0..=CELL_REMOTEPLAY_VOLUME_MAX).contains(&volume) {
+            return CELL_REMOTEPLAY_ERROR_INTERNAL;
+        }
+        self.comparative_volume = volume;
+        0 // CELL_OK
+    }
+
+    /// Get the peer's comparative volume
+    pub fn comparative_volume(&self) -> f32 {
+        self.comparative_volume
+    }
+
+    /// Request encryption of all streamed data
+    pub fn encrypt_all_data(&mut self) {
+        self.encrypt_all = true;
+    }
+
+    /// Check if all streamed data is encrypted
+    pub fn is_encrypting_all(&self) -> bool {
+        self.encrypt_all
+    }
+}
+
+impl Default for RemotePlayManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// cellRemotePlayGetStatus - Get the Remote Play session status
+///
+/// # Returns
+/// * CELL_REMOTEPLAY_STATUS_LOADING, as no session is active
+pub fn cell_remote_play_get_status() -> i32 {
+    trace!("cellRemotePlayGetStatus()");
+
+    crate::context::get_hle_context().remote_play.status() as i32
+}
+
+/// cellRemotePlaySetComparativeVolume - Set the peer's volume relative to the TV
+///
+/// # Arguments
+/// * `volume` - Volume between 0.0 and 1.0
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_remote_play_set_comparative_volume(volume: f32) -> i32 {
+    debug!("cellRemotePlaySetComparativeVolume(volume={})", volume);
+
+    crate::context::get_hle_context_mut().remote_play.set_comparative_volume(volume)
+}
+
+/// cellRemotePlayGetComparativeVolume - Get the peer's volume relative to the TV
+///
+/// # Arguments
+/// * `volume_addr` - Address to write the volume
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_remote_play_get_comparative_volume(volume_addr: u32) -> i32 {
+    let volume = crate::context::get_hle_context().remote_play.comparative_volume();
+    trace!("cellRemotePlayGetComparativeVolume() -> {}", volume);
+
+    if volume_addr == 0 {
+        return CELL_REMOTEPLAY_ERROR_INTERNAL;
+    }
+    match crate::context::guest_memory().map(|memory| memory.write_be(volume_addr, volume)) {
+        Some(Ok(())) => 0, // CELL_OK
+        _ => CELL_REMOTEPLAY_ERROR_INTERNAL,
+    }
+}
+
+/// cellRemotePlayGetPeerInfo - Get the connected peer's information
+///
+/// # Arguments
+/// * `peer_info_addr` - Address of CellRemotePlayPeerInfo
+///
+/// # Returns
+/// * CELL_REMOTEPLAY_ERROR_INTERNAL, as no peer is connected
+pub fn cell_remote_play_get_peer_info(_peer_info_addr: u32) -> i32 {
+    trace!("cellRemotePlayGetPeerInfo()");
+
+    CELL_REMOTEPLAY_ERROR_INTERNAL
+}
+
+/// cellRemotePlayGetSharedMemory - Get the memory shared with the Remote Play utility
+///
+/// # Arguments
+/// * `memory_addr` - Address to write the shared memory address
+/// * `size_addr` - Address to write the shared memory size
+///
+/// # Returns
+/// * CELL_REMOTEPLAY_ERROR_INTERNAL, as no session is active
+pub fn cell_remote_play_get_shared_memory(_memory_addr: u32, _size_addr: u32) -> i32 {
+    trace!("cellRemotePlayGetSharedMemory()");
+
+    CELL_REMOTEPLAY_ERROR_INTERNAL
+}
+
+/// cellRemotePlayEncryptAllData - Encrypt all data streamed to the peer
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_remote_play_encrypt_all_data() -> i32 {
+    debug!("cellRemotePlayEncryptAllData()");
+
+    crate::context::get_hle_context_mut().remote_play.encrypt_all_data();
+    0 // CELL_OK
+}
+
+/// cellRemotePlayStopPeerVideoOut - Stop streaming video to the peer
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_remote_play_stop_peer_video_out() -> i32 {
+    debug!("cellRemotePlayStopPeerVideoOut()");
+
+    0 // CELL_OK
+}
+
+/// cellRemotePlayBreak - End the Remote Play session
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_remote_play_break() -> i32 {
+    debug!("cellRemotePlayBreak()");
+
+    0 // CELL_OK
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_play_idle() {
+        let mut manager = RemotePlayManager::new();
+
+        assert_eq!(manager.status(), CELL_REMOTEPLAY_STATUS_LOADING);
+        assert_eq!(manager.comparative_volume(), CELL_REMOTEPLAY_VOLUME_MAX);
+        assert!(!manager.is_encrypting_all());
+
+        manager.encrypt_all_data();
+        assert!(manager.is_encrypting_all());
+    }
+
+    #[test]
+    fn test_remote_play_comparative_volume() {
+        let mut manager = RemotePlayManager::new();
+
+        assert_eq!(manager.set_comparative_volume(0.5), 0);
+        assert_eq!(manager.comparative_volume(), 0.5);
+        assert_eq!(manager.set_comparative_volume(1.5), CELL_REMOTEPLAY_ERROR_INTERNAL);
+        assert_eq!(manager.comparative_volume(), 0.5);
+    }
+
+    #[test]
+    fn test_remote_play_guest_volume() {
+        let _guard = crate::context::test_guard();
+        crate::context::reset_hle_context();
+        let memory = crate::context::test_guest_memory();
+        let addr = memory.allocate(0x10, 0x10, oc_memory::PageFlags::RW).unwrap();
+
+        assert_eq!(cell_remote_play_set_comparative_volume(0.25), 0);
+        assert_eq!(cell_remote_play_get_comparative_volume(addr), 0);
+        assert_eq!(memory.read_be::<f32>(addr).unwrap(), 0.25);
+        assert_eq!(cell_remote_play_get_comparative_volume(0), CELL_REMOTEPLAY_ERROR_INTERNAL);
+    }
+
+    #[test]
+    fn test_remote_play_no_peer() {
+        assert_eq!(cell_remote_play_get_peer_info(0x1000), CELL_REMOTEPLAY_ERROR_INTERNAL);
+        assert_eq!(cell_remote_play_get_shared_memory(0x1000, 0x1004), CELL_REMOTEPLAY_ERROR_INTERNAL);
+    }
+}
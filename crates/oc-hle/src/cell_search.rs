@@ -0,0 +1,2200 @@
+//! cellSearch HLE - Media Content Search
+//!
+//! This module provides HLE implementations for games that browse the
+//! console's music, photo and video library. Searches enumerate the music,
+//! photo and video directories of the host `/dev_hdd0`, reading titles,
+//! albums, dimensions and durations from the files' own tags and headers.
+//! Results are ready at once; the result events are handed to the game's
+//! callback.
+
+use crate::cell_export::ExportKind;
+use crate::cell_music::MUSIC_EXTENSIONS;
+use crate::context::guest_memory;
+use crate::guest_call::GuestCallQueue;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tracing::{debug, trace};
+
+/// Result codes
+pub const CELL_SEARCH_CANCELED: i32 = 1;
+pub const CELL_SEARCH_ERROR_PARAM: i32 = 0x8002C801u32 as i32;
+pub const CELL_SEARCH_ERROR_BUSY: i32 = 0x8002C802u32 as i32;
+pub const CELL_SEARCH_ERROR_NO_MEMORY: i32 = 0x8002C803u32 as i32;
+pub const CELL_SEARCH_ERROR_UNKNOWN_MODE: i32 = 0x8002C804u32 as i32;
+pub const CELL_SEARCH_ERROR_ALREADY_INITIALIZED: i32 = 0x8002C805u32 as i32;
+pub const CELL_SEARCH_ERROR_NOT_INITIALIZED: i32 = 0x8002C806u32 as i32;
+pub const CELL_SEARCH_ERROR_FINALIZING: i32 = 0x8002C807u32 as i32;
+pub const CELL_SEARCH_ERROR_NOT_SUPPORTED_SEARCH: i32 = 0x8002C808u32 as i32;
+pub const CELL_SEARCH_ERROR_CONTENT_OBSOLETE: i32 = 0x8002C809u32 as i32;
+pub const CELL_SEARCH_ERROR_CONTENT_NOT_FOUND: i32 = 0x8002C80Au32 as i32;
+pub const CELL_SEARCH_ERROR_NOT_LIST: i32 = 0x8002C80Bu32 as i32;
+pub const CELL_SEARCH_ERROR_OUT_OF_RANGE: i32 = 0x8002C80Cu32 as i32;
+pub const CELL_SEARCH_ERROR_INVALID_SEARCHID: i32 = 0x8002C80Du32 as i32;
+pub const CELL_SEARCH_ERROR_ALREADY_GOT_RESULT: i32 = 0x8002C80Eu32 as i32;
+pub const CELL_SEARCH_ERROR_NOT_SUPPORTED_CONTEXT: i32 = 0x8002C80Fu32 as i32;
+pub const CELL_SEARCH_ERROR_INVALID_CONTENTTYPE: i32 = 0x8002C810u32 as i32;
+pub const CELL_SEARCH_ERROR_DRM: i32 = 0x8002C811u32 as i32;
+pub const CELL_SEARCH_ERROR_TAG: i32 = 0x8002C812u32 as i32;
+pub const CELL_SEARCH_ERROR_GENERIC: i32 = 0x8002C8FFu32 as i32;
+
+/// Search modes
+pub const CELL_SEARCH_MODE_NORMAL: i32 = 0;
+
+/// Events passed to the game's callback
+pub const CELL_SEARCH_EVENT_NOTIFICATION: u32 = 0;
+pub const CELL_SEARCH_EVENT_INITIALIZE_RESULT: u32 = 1;
+pub const CELL_SEARCH_EVENT_FINALIZE_RESULT: u32 = 2;
+pub const CELL_SEARCH_EVENT_LISTSEARCH_RESULT: u32 = 3;
+pub const CELL_SEARCH_EVENT_CONTENTSEARCH_INLIST_RESULT: u32 = 4;
+pub const CELL_SEARCH_EVENT_CONTENTSEARCH_RESULT: u32 = 5;
+pub const CELL_SEARCH_EVENT_SCENESEARCH_INVIDEO_RESULT: u32 = 6;
+pub const CELL_SEARCH_EVENT_SCENESEARCH_RESULT: u32 = 7;
+
+/// Content types
+pub const CELL_SEARCH_CONTENTTYPE_NONE: u32 = 0;
+pub const CELL_SEARCH_CONTENTTYPE_MUSIC: u32 = 1;
+pub const CELL_SEARCH_CONTENTTYPE_MUSICLIST: u32 = 2;
+pub const CELL_SEARCH_CONTENTTYPE_PHOTO: u32 = 3;
+pub const CELL_SEARCH_CONTENTTYPE_PHOTOLIST: u32 = 4;
+pub const CELL_SEARCH_CONTENTTYPE_VIDEO: u32 = 5;
+pub const CELL_SEARCH_CONTENTTYPE_VIDEOLIST: u32 = 6;
+pub const CELL_SEARCH_CONTENTTYPE_SCENE: u32 = 7;
+
+/// Content search types
+pub const CELL_SEARCH_CONTENTSEARCHTYPE_NONE: u32 = 0;
+pub const CELL_SEARCH_CONTENTSEARCHTYPE_MUSIC_ALL: u32 = 1;
+pub const CELL_SEARCH_CONTENTSEARCHTYPE_PHOTO_ALL: u32 = 2;
+pub const CELL_SEARCH_CONTENTSEARCHTYPE_VIDEO_ALL: u32 = 3;
+
+/// List search types
+pub const CELL_SEARCH_LISTSEARCHTYPE_NONE: u32 = 0;
+pub const CELL_SEARCH_LISTSEARCHTYPE_MUSIC_ALBUM: u32 = 1;
+pub const CELL_SEARCH_LISTSEARCHTYPE_MUSIC_GENRE: u32 = 2;
+pub const CELL_SEARCH_LISTSEARCHTYPE_MUSIC_ARTIST: u32 = 3;
+pub const CELL_SEARCH_LISTSEARCHTYPE_PHOTO_YEAR: u32 = 4;
+pub const CELL_SEARCH_LISTSEARCHTYPE_PHOTO_MONTH: u32 = 5;
+pub const CELL_SEARCH_LISTSEARCHTYPE_PHOTO_ALBUM: u32 = 6;
+pub const CELL_SEARCH_LISTSEARCHTYPE_PHOTO_PLAYLIST: u32 = 7;
+pub const CELL_SEARCH_LISTSEARCHTYPE_VIDEO_ALBUM: u32 = 8;
+pub const CELL_SEARCH_LISTSEARCHTYPE_MUSIC_PLAYLIST: u32 = 9;
+
+/// Sort keys
+pub const CELL_SEARCH_SORTKEY_NONE: u32 = 0;
+pub const CELL_SEARCH_SORTKEY_DEFAULT: u32 = 1;
+pub const CELL_SEARCH_SORTKEY_TITLE: u32 = 2;
+pub const CELL_SEARCH_SORTKEY_ALBUMTITLE: u32 = 3;
+pub const CELL_SEARCH_SORTKEY_GENRENAME: u32 = 4;
+pub const CELL_SEARCH_SORTKEY_ARTISTNAME: u32 = 5;
+pub const CELL_SEARCH_SORTKEY_IMPORTEDDATE: u32 = 6;
+pub const CELL_SEARCH_SORTKEY_TRACKNUMBER: u32 = 7;
+pub const CELL_SEARCH_SORTKEY_TAKENDATE: u32 = 8;
+pub const CELL_SEARCH_SORTKEY_USERDEFINED: u32 = 9;
+pub const CELL_SEARCH_SORTKEY_MODIFIEDDATE: u32 = 10;
+
+/// Sort orders
+pub const CELL_SEARCH_SORTORDER_NONE: u32 = 0;
+pub const CELL_SEARCH_SORTORDER_ASCENDING: u32 = 1;
+pub const CELL_SEARCH_SORTORDER_DESCENDING: u32 = 2;
+
+/// Repeat modes of a music selection
+pub const CELL_SEARCH_REPEATMODE_NONE: u32 = 0;
+pub const CELL_SEARCH_REPEATMODE_REPEAT1: u32 = 1;
+pub const CELL_SEARCH_REPEATMODE_ALL: u32 = 2;
+pub const CELL_SEARCH_REPEATMODE_NOREPEAT1: u32 = 3;
+
+/// Codecs reported in content info
+pub const CELL_SEARCH_CODEC_UNKNOWN: u32 = 0;
+pub const CELL_SEARCH_CODEC_MPEG2: u32 = 1;
+pub const CELL_SEARCH_CODEC_MPEG4: u32 = 2;
+pub const CELL_SEARCH_CODEC_AVC: u32 = 3;
+pub const CELL_SEARCH_CODEC_MPEG1: u32 = 4;
+pub const CELL_SEARCH_CODEC_MP3: u32 = 11;
+pub const CELL_SEARCH_CODEC_JPEG: u32 = 13;
+pub const CELL_SEARCH_CODEC_BMP: u32 = 15;
+pub const CELL_SEARCH_CODEC_GIF: u32 = 16;
+pub const CELL_SEARCH_CODEC_PNG: u32 = 17;
+
+/// Size of CellSearchContentId
+pub const CELL_SEARCH_CONTENT_ID_SIZE: usize = 16;
+
+/// Longest title, album, artist or genre name in content info
+pub const CELL_SEARCH_TITLE_LEN_MAX: usize = 384;
+
+/// Longest path in CellSearchContentInfoPath
+pub const CELL_SEARCH_PATH_LEN_MAX: usize = 63;
+
+/// Status of a content that can be played or shown
+pub const CELL_SEARCH_CONTENTSTATUS_AVAILABLE: i32 = 1;
+
+/// Sharable type of a video that may not be uploaded
+pub const CELL_SEARCH_SHARABLETYPE_PROHIBITED: i32 = 0;
+
+/// Size of CellMusicSelectionContext
+pub const CELL_MUSIC_SELECTION_CONTEXT_SIZE: usize = 2048;
+
+/// Marks the selection contexts this library writes, followed by the context's handle
+const SELECTION_CONTEXT_MAGIC: &[u8; 8] = b"OCSEARCH";
+
+/// CellSearchResultParam slots handed to the callback, reused in turn
+const RESULT_PARAM_SLOTS: u32 = 16;
+
+/// Extensions of the photos and videos picked up by searches
+pub const PHOTO_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "bmp"];
+pub const VIDEO_EXTENSIONS: &[&str] = &["mp4", "m4v", "mov", "avi", "mpg", "mpeg"];
+
+/// Largest tag block read from a file
+const MAX_TAG_SIZE: usize = 1 << 20;
+
+/// Event waiting to be passed to the game's callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchEvent {
+    pub event: u32,
+    pub result: i32,
+    /// Search the event reports on (0 for initialize/finalize)
+    pub search_id: u32,
+    /// Number of contents found
+    pub result_num: u32,
+}
+
+/// Tags and header fields read from a content file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentMetadata {
+    pub title: String,
+    pub album: String,
+    pub artist: String,
+    pub genre: String,
+    pub track_number: i32,
+    /// Duration in milliseconds (0 when unknown)
+    pub duration_ms: u64,
+    pub sample_rate: u32,
+    pub channels: u32,
+    /// Bitrate in bits per second (0 when unknown)
+    pub bitrate: u32,
+    pub width: u32,
+    pub height: u32,
+    pub codec: u32,
+}
+
+/// Content found by a search, or a list grouping such contents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchContent {
+    pub id: u64,
+    pub content_type: u32,
+    /// List search type of a list (0 for files)
+    pub list_type: u32,
+    /// Host path of the file (empty for lists)
+    pub path: PathBuf,
+    /// Path games open the file with (empty for lists)
+    pub guest_path: String,
+    pub size: u64,
+    /// Modification time in seconds since the epoch, standing in for the
+    /// imported and taken dates
+    pub modified: i64,
+    pub metadata: ContentMetadata,
+    /// Contents of a list
+    pub items: Vec<u64>,
+}
+
+impl SearchContent {
+    /// Check if this is a list of contents
+    pub fn is_list(&self) -> bool {
+        matches!(
+            self.content_type,
+            CELL_SEARCH_CONTENTTYPE_MUSICLIST | CELL_SEARCH_CONTENTTYPE_PHOTOLIST | CELL_SEARCH_CONTENTTYPE_VIDEOLIST
+        )
+    }
+}
+
+/// Tracks of a music selection context, for cellMusic to play
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MusicSelection {
+    pub tracks: Vec<PathBuf>,
+    /// Index of the track played first
+    pub first: usize,
+    pub repeat_mode: u32,
+}
+
+/// Bytes of a CellSearchContentId
+pub fn content_id_bytes(id: u64) -> [u8; CELL_SEARCH_CONTENT_ID_SIZE] {
+    let mut bytes = [0; CELL_SEARCH_CONTENT_ID_SIZE];
+    bytes[8..].copy_from_slice(&id.to_be_bytes());
+    bytes
+}
+
+/// Content ID held in a CellSearchContentId
+pub fn content_id_from_bytes(bytes: &[u8; CELL_SEARCH_CONTENT_ID_SIZE]) -> u64 {
+    u64::from_be_bytes(bytes[8..].try_into().unwrap())
+}
+
+/// Big-endian structure being laid out for the guest
+struct InfoWriter(Vec<u8>);
+
+impl InfoWriter {
+    fn s32(mut self, value: i32) -> Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn s64(mut self, value: i64) -> Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    /// Text array of `len` bytes, cut to leave room for its NUL
+    fn text(mut self, text: &str, len: usize) -> Self {
+        let mut end = text.len().min(len - 1);
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.0.extend_from_slice(&text.as_bytes()[..end]);
+        self.0.resize(self.0.len() + len - end, 0);
+        self
+    }
+
+    /// Title, album, artist or genre array and the reserved bytes after it
+    fn title(self, text: &str) -> Self {
+        self.text(text, CELL_SEARCH_TITLE_LEN_MAX + 4)
+    }
+
+    /// Bytes padded to the structure's 8 byte alignment
+    fn finish(mut self) -> Vec<u8> {
+        self.0.resize(self.0.len().next_multiple_of(8), 0);
+        self.0
+    }
+}
+
+/// Bytes of the CellSearch*Info structure describing `content`
+///
+/// Durations are in milliseconds and bitrates in kbit/s; the modification
+/// time stands in for every date.
+pub fn content_info(content: &SearchContent) -> Vec<u8> {
+    let meta = &content.metadata;
+    let info = InfoWriter(Vec::new());
+    let info = match content.content_type {
+        CELL_SEARCH_CONTENTTYPE_MUSIC => info
+            .s64(meta.duration_ms as i64)
+            .s64(content.size as i64)
+            .s64(content.modified)
+            .s64(0) // lastPlayedDate
+            .s32(0) // releasedYear
+            .s32(meta.track_number)
+            .s32((meta.bitrate / 1000) as i32)
+            .s32(meta.sample_rate as i32)
+            .s32(16) // quantizationBitrate
+            .s32(0) // playCount
+            .s32(0) // drmEncrypted
+            .s32(meta.codec as i32)
+            .s32(CELL_SEARCH_CONTENTSTATUS_AVAILABLE)
+            .text("", 8) // diskNumber
+            .title(&meta.title)
+            .title(&meta.album)
+            .title(&meta.artist)
+            .title(&meta.genre),
+        CELL_SEARCH_CONTENTTYPE_PHOTO => info
+            .s64(content.size as i64)
+            .s64(content.modified)
+            .s64(content.modified)
+            .s32(meta.width as i32)
+            .s32(meta.height as i32)
+            .s32(0) // orientation
+            .s32(meta.codec as i32)
+            .s32(CELL_SEARCH_CONTENTSTATUS_AVAILABLE)
+            .title(&meta.title)
+            .title(&meta.album),
+        CELL_SEARCH_CONTENTTYPE_VIDEO => info
+            .s64(meta.duration_ms as i64)
+            .s64(content.size as i64)
+            .s64(content.modified)
+            .s64(content.modified)
+            .s32((meta.bitrate / 1000) as i32)
+            .s32(0) // audioBitrate
+            .s32(0) // playCount
+            .s32(0) // drmEncrypted
+            .s32(meta.codec as i32)
+            .s32(CELL_SEARCH_CODEC_UNKNOWN as i32)
+            .s32(CELL_SEARCH_CONTENTSTATUS_AVAILABLE)
+            .title(&meta.title)
+            .title(&meta.album),
+        CELL_SEARCH_CONTENTTYPE_MUSICLIST => info
+            .s32(content.list_type as i32)
+            .s32(content.items.len() as i32)
+            .s64(meta.duration_ms as i64)
+            .title(&meta.title)
+            .title(&meta.artist),
+        CELL_SEARCH_CONTENTTYPE_VIDEOLIST => info
+            .s32(content.list_type as i32)
+            .s32(content.items.len() as i32)
+            .s64(meta.duration_ms as i64)
+            .title(&meta.title),
+        // Photo lists, the one structure without 64-bit members, are 4 byte aligned
+        _ => return info.s32(content.list_type as i32).s32(content.items.len() as i32).title(&meta.title).0,
+    };
+    info.finish()
+}
+
+/// Content search manager
+pub struct SearchManager {
+    /// Initialization flag
+    initialized: bool,
+    /// Event callback address
+    callback: u32,
+    /// Callback user data
+    userdata: u32,
+    /// Host directory backing `/dev_hdd0`
+    hdd0_root: Option<PathBuf>,
+    /// Contents and lists found so far, by content ID
+    contents: BTreeMap<u64, SearchContent>,
+    /// Content IDs by host path or list key, so IDs stay the same between searches
+    ids: HashMap<String, u64>,
+    /// Next content ID to hand out
+    next_content_id: u64,
+    /// Results of each search, by search ID
+    searches: BTreeMap<u32, Vec<u64>>,
+    /// Next search ID to hand out
+    next_search_id: u32,
+    /// Music selection context made last, for cellMusic to restore
+    music_selection: Option<MusicSelection>,
+    /// Selection contexts written to the guest, by the handle they hold
+    saved_selections: Vec<MusicSelection>,
+    /// Events waiting to be passed to the callback
+    pending_events: Vec<SearchEvent>,
+    /// Guest address of the CellSearchResultParam slots, 0 until the first result
+    result_params_addr: u32,
+}
+
+impl SearchManager {
+    /// Create a new search manager
+    pub fn new() -> Self {
+        Self {
+            initialized: false,
+            callback: 0,
+            userdata: 0,
+            hdd0_root: None,
+            contents: BTreeMap::new(),
+            ids: HashMap::new(),
+            next_content_id: 1,
+            searches: BTreeMap::new(),
+            next_search_id: 1,
+            music_selection: None,
+            saved_selections: Vec::new(),
+            pending_events: Vec::new(),
+            result_params_addr: 0,
+        }
+    }
+
+    /// Set the host directory backing `/dev_hdd0`
+    pub fn set_hdd0_root(&mut self, root: PathBuf) {
+        self.hdd0_root = Some(root);
+    }
+
+    /// Initialize the search library
+    pub fn init(&mut self, mode: i32, callback: u32, userdata: u32) -> i32 {
+        if mode != CELL_SEARCH_MODE_NORMAL {
+            return CELL_SEARCH_ERROR_UNKNOWN_MODE;
+        }
+        if callback == 0 {
+            return CELL_SEARCH_ERROR_PARAM;
+        }
+        if self.initialized {
+            return CELL_SEARCH_ERROR_ALREADY_INITIALIZED;
+        }
+
+        debug!("SearchManager::init: callback=0x{:08X}", callback);
+
+        self.initialized = true;
+        self.callback = callback;
+        self.userdata = userdata;
+        self.queue_event(CELL_SEARCH_EVENT_INITIALIZE_RESULT, 0, 0, 0);
+        0 // CELL_OK
+    }
+
+    /// Shut the search library down, ending every search
+    pub fn finalize(&mut self) -> i32 {
+        if !self.initialized {
+            return CELL_SEARCH_ERROR_NOT_INITIALIZED;
+        }
+
+        debug!("SearchManager::finalize: {} searches open", self.searches.len());
+
+        self.initialized = false;
+        self.searches.clear();
+        self.queue_event(CELL_SEARCH_EVENT_FINALIZE_RESULT, 0, 0, 0);
+        0 // CELL_OK
+    }
+
+    /// Search every content of one kind
+    pub fn start_content_search(&mut self, search_type: u32, sort_key: u32, sort_order: u32) -> Result<u32, i32> {
+        self.check_search(sort_key, sort_order)?;
+        let kind = match search_type {
+            CELL_SEARCH_CONTENTSEARCHTYPE_MUSIC_ALL => ExportKind::Music,
+            CELL_SEARCH_CONTENTSEARCHTYPE_PHOTO_ALL => ExportKind::Photo,
+            CELL_SEARCH_CONTENTSEARCHTYPE_VIDEO_ALL => ExportKind::Video,
+            _ => return Err(CELL_SEARCH_ERROR_PARAM),
+        };
+
+        let mut results = self.scan(kind);
+        self.sort(&mut results, sort_key, sort_order);
+        Ok(self.finish_search(CELL_SEARCH_EVENT_CONTENTSEARCH_RESULT, results))
+    }
+
+    /// Search the lists contents are grouped into
+    pub fn start_list_search(&mut self, list_type: u32, sort_key: u32, sort_order: u32) -> Result<u32, i32> {
+        self.check_search(sort_key, sort_order)?;
+        let kind = match list_type {
+            CELL_SEARCH_LISTSEARCHTYPE_MUSIC_ALBUM
+            | CELL_SEARCH_LISTSEARCHTYPE_MUSIC_GENRE
+            | CELL_SEARCH_LISTSEARCHTYPE_MUSIC_ARTIST
+            | CELL_SEARCH_LISTSEARCHTYPE_MUSIC_PLAYLIST => ExportKind::Music,
+            CELL_SEARCH_LISTSEARCHTYPE_PHOTO_YEAR
+            | CELL_SEARCH_LISTSEARCHTYPE_PHOTO_MONTH
+            | CELL_SEARCH_LISTSEARCHTYPE_PHOTO_ALBUM
+            | CELL_SEARCH_LISTSEARCHTYPE_PHOTO_PLAYLIST => ExportKind::Photo,
+            CELL_SEARCH_LISTSEARCHTYPE_VIDEO_ALBUM => ExportKind::Video,
+            _ => return Err(CELL_SEARCH_ERROR_PARAM),
+        };
+
+        // Playlists are made on the XMB, so none exist
+        let items = match list_type {
+            CELL_SEARCH_LISTSEARCHTYPE_MUSIC_PLAYLIST | CELL_SEARCH_LISTSEARCHTYPE_PHOTO_PLAYLIST => Vec::new(),
+            _ => self.scan(kind),
+        };
+        let mut groups: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+        for id in items {
+            let content = &self.contents[&id];
+            let title = match list_type {
+                CELL_SEARCH_LISTSEARCHTYPE_MUSIC_GENRE => content.metadata.genre.clone(),
+                CELL_SEARCH_LISTSEARCHTYPE_MUSIC_ARTIST => content.metadata.artist.clone(),
+                CELL_SEARCH_LISTSEARCHTYPE_PHOTO_YEAR => date_of(content.modified).0.to_string(),
+                CELL_SEARCH_LISTSEARCHTYPE_PHOTO_MONTH => {
+                    let (year, month, _) = date_of(content.modified);
+                    format!("{}/{:02}", year, month)
+                }
+                _ => content.metadata.album.clone(),
+            };
+            // Contents lacking the field are only found by content searches
+            if !title.is_empty() {
+                groups.entry(title).or_default().push(id);
+            }
+        }
+
+        let mut results = Vec::with_capacity(groups.len());
+        for (title, items) in groups {
+            results.push(self.register_list(kind, list_type, title, items));
+        }
+        self.sort(&mut results, sort_key, sort_order);
+        Ok(self.finish_search(CELL_SEARCH_EVENT_LISTSEARCH_RESULT, results))
+    }
+
+    /// Search the contents of a list found by a list search
+    pub fn start_content_search_in_list(&mut self, list_id: u64, sort_key: u32, sort_order: u32) -> Result<u32, i32> {
+        self.check_search(sort_key, sort_order)?;
+        let list = self.contents.get(&list_id).ok_or(CELL_SEARCH_ERROR_CONTENT_NOT_FOUND)?;
+        if !list.is_list() {
+            return Err(CELL_SEARCH_ERROR_NOT_LIST);
+        }
+
+        let mut results: Vec<u64> = list.items.iter().copied().filter(|id| self.is_present(*id)).collect();
+        self.sort(&mut results, sort_key, sort_order);
+        Ok(self.finish_search(CELL_SEARCH_EVENT_CONTENTSEARCH_INLIST_RESULT, results))
+    }
+
+    /// Search the scenes of videos, of `video_id` alone if given
+    ///
+    /// Scenes are chapter marks the XMB records, so no video has any.
+    pub fn start_scene_search(&mut self, video_id: Option<u64>, sort_key: u32, sort_order: u32) -> Result<u32, i32> {
+        self.check_search(sort_key, sort_order)?;
+        let event = match video_id {
+            Some(id) => {
+                let video = self.contents.get(&id).ok_or(CELL_SEARCH_ERROR_CONTENT_NOT_FOUND)?;
+                if video.content_type != CELL_SEARCH_CONTENTTYPE_VIDEO {
+                    return Err(CELL_SEARCH_ERROR_INVALID_CONTENTTYPE);
+                }
+                CELL_SEARCH_EVENT_SCENESEARCH_INVIDEO_RESULT
+            }
+            None => CELL_SEARCH_EVENT_SCENESEARCH_RESULT,
+        };
+        Ok(self.finish_search(event, Vec::new()))
+    }
+
+    fn check_search(&self, sort_key: u32, sort_order: u32) -> Result<(), i32> {
+        if !self.initialized {
+            return Err(CELL_SEARCH_ERROR_NOT_INITIALIZED);
+        }
+        if sort_key > CELL_SEARCH_SORTKEY_MODIFIEDDATE || sort_order > CELL_SEARCH_SORTORDER_DESCENDING {
+            return Err(CELL_SEARCH_ERROR_PARAM);
+        }
+        Ok(())
+    }
+
+    fn finish_search(&mut self, event: u32, results: Vec<u64>) -> u32 {
+        let search_id = self.next_search_id;
+        self.next_search_id += 1;
+        debug!("SearchManager: search {} found {} contents", search_id, results.len());
+
+        self.queue_event(event, 0, search_id, results.len() as u32);
+        self.searches.insert(search_id, results);
+        search_id
+    }
+
+    /// Register every file in the directory of `kind`, returning their IDs by path
+    fn scan(&mut self, kind: ExportKind) -> Vec<u64> {
+        let Some(root) = self.hdd0_root.clone() else {
+            return Vec::new();
+        };
+        let dir = root.join(kind.directory());
+        let (content_type, extensions) = match kind {
+            ExportKind::Music => (CELL_SEARCH_CONTENTTYPE_MUSIC, MUSIC_EXTENSIONS),
+            ExportKind::Photo => (CELL_SEARCH_CONTENTTYPE_PHOTO, PHOTO_EXTENSIONS),
+            ExportKind::Video => (CELL_SEARCH_CONTENTTYPE_VIDEO, VIDEO_EXTENSIONS),
+        };
+
+        let mut ids = Vec::new();
+        for path in scan_folder(&dir, extensions) {
+            let Ok(relative) = path.strip_prefix(&root) else {
+                continue;
+            };
+            let guest_path = format!("/dev_hdd0/{}", relative.to_string_lossy().replace('\\', "/"));
+            let file_meta = std::fs::metadata(&path).ok();
+            let modified = file_meta
+                .as_ref()
+                .and_then(|m| m.modified().ok())
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs() as i64);
+
+            let mut metadata = read_metadata(&path);
+            if metadata.title.is_empty() {
+                metadata.title = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+            }
+            // Untagged files are grouped by the folder they sit in
+            if metadata.album.is_empty() && path.parent() != Some(dir.as_path()) {
+                metadata.album = path.parent().and_then(|p| p.file_name()).unwrap_or_default().to_string_lossy().into_owned();
+            }
+
+            let id = self.content_id(path.to_string_lossy().into_owned());
+            self.contents.insert(
+                id,
+                SearchContent {
+                    id,
+                    content_type,
+                    list_type: CELL_SEARCH_LISTSEARCHTYPE_NONE,
+                    path,
+                    guest_path,
+                    size: file_meta.map_or(0, |m| m.len()),
+                    modified,
+                    metadata,
+                    items: Vec::new(),
+                },
+            );
+            ids.push(id);
+        }
+        ids
+    }
+
+    fn register_list(&mut self, kind: ExportKind, list_type: u32, title: String, items: Vec<u64>) -> u64 {
+        let content_type = match kind {
+            ExportKind::Music => CELL_SEARCH_CONTENTTYPE_MUSICLIST,
+            ExportKind::Photo => CELL_SEARCH_CONTENTTYPE_PHOTOLIST,
+            ExportKind::Video => CELL_SEARCH_CONTENTTYPE_VIDEOLIST,
+        };
+        let first = &self.contents[&items[0]];
+        let mut metadata = ContentMetadata {
+            title: title.clone(),
+            duration_ms: items.iter().map(|id| self.contents[id].metadata.duration_ms).sum(),
+            ..Default::default()
+        };
+        // Albums credit their artist when every track agrees
+        if list_type == CELL_SEARCH_LISTSEARCHTYPE_MUSIC_ALBUM
+            && items.iter().all(|id| self.contents[id].metadata.artist == first.metadata.artist)
+        {
+            metadata.artist = first.metadata.artist.clone();
+        }
+        let modified = items.iter().map(|id| self.contents[id].modified).max().unwrap_or(0);
+
+        let id = self.content_id(format!("list:{}:{}", list_type, title));
+        self.contents.insert(
+            id,
+            SearchContent {
+                id,
+                content_type,
+                list_type,
+                path: PathBuf::new(),
+                guest_path: String::new(),
+                size: 0,
+                modified,
+                metadata,
+                items,
+            },
+        );
+        id
+    }
+
+    fn content_id(&mut self, key: String) -> u64 {
+        let next = &mut self.next_content_id;
+        *self.ids.entry(key).or_insert_with(|| {
+            *next += 1;
+            *next - 1
+        })
+    }
+
+    fn is_present(&self, id: u64) -> bool {
+        self.contents.get(&id).is_some_and(|c| c.is_list() || c.path.is_file())
+    }
+
+    fn sort(&self, ids: &mut [u64], sort_key: u32, sort_order: u32) {
+        let contents = &self.contents;
+        let text = |id: &u64, field: fn(&ContentMetadata) -> &str| field(&contents[id].metadata).to_lowercase();
+        match sort_key {
+            CELL_SEARCH_SORTKEY_TITLE => ids.sort_by_cached_key(|id| text(id, |m| &m.title)),
+            CELL_SEARCH_SORTKEY_ALBUMTITLE => {
+                ids.sort_by_cached_key(|id| (text(id, |m| &m.album), contents[id].metadata.track_number))
+            }
+            CELL_SEARCH_SORTKEY_GENRENAME => {
+                ids.sort_by_cached_key(|id| (text(id, |m| &m.genre), text(id, |m| &m.title)))
+            }
+            CELL_SEARCH_SORTKEY_ARTISTNAME => {
+                ids.sort_by_cached_key(|id| (text(id, |m| &m.artist), text(id, |m| &m.title)))
+            }
+            CELL_SEARCH_SORTKEY_TRACKNUMBER => ids.sort_by_key(|id| contents[id].metadata.track_number),
+            CELL_SEARCH_SORTKEY_IMPORTEDDATE | CELL_SEARCH_SORTKEY_TAKENDATE | CELL_SEARCH_SORTKEY_MODIFIEDDATE => {
+                ids.sort_by_key(|id| contents[id].modified)
+            }
+            // Everything else keeps the path order of the scan
+            _ => {}
+        }
+        if sort_order == CELL_SEARCH_SORTORDER_DESCENDING {
+            ids.reverse();
+        }
+    }
+
+    fn search(&self, search_id: u32) -> Result<&[u64], i32> {
+        if !self.initialized {
+            return Err(CELL_SEARCH_ERROR_NOT_INITIALIZED);
+        }
+        self.searches.get(&search_id).map(Vec::as_slice).ok_or(CELL_SEARCH_ERROR_INVALID_SEARCHID)
+    }
+
+    /// Content at `offset` in the results of a search
+    pub fn content_by_offset(&self, search_id: u32, offset: u32) -> Result<&SearchContent, i32> {
+        let id = *self.search(search_id)?.get(offset as usize).ok_or(CELL_SEARCH_ERROR_OUT_OF_RANGE)?;
+        self.content(id)
+    }
+
+    /// Content with ID `id`
+    pub fn content(&self, id: u64) -> Result<&SearchContent, i32> {
+        if !self.initialized {
+            return Err(CELL_SEARCH_ERROR_NOT_INITIALIZED);
+        }
+        let content = self.contents.get(&id).ok_or(CELL_SEARCH_ERROR_CONTENT_NOT_FOUND)?;
+        if !self.is_present(id) {
+            return Err(CELL_SEARCH_ERROR_CONTENT_OBSOLETE);
+        }
+        Ok(content)
+    }
+
+    /// Offset of content `id` in the results of a search
+    pub fn offset_of(&self, search_id: u32, id: u64) -> Result<u32, i32> {
+        let results = self.search(search_id)?;
+        results.iter().position(|&r| r == id).map(|offset| offset as u32).ok_or(CELL_SEARCH_ERROR_CONTENT_NOT_FOUND)
+    }
+
+    /// Make a music selection context of a search's results
+    ///
+    /// Playback starts at `first_id`, or at the first track without one.
+    /// Lists in the results contribute their tracks.
+    pub fn make_music_selection(&mut self, search_id: u32, first_id: Option<u64>, repeat_mode: u32) -> Result<(), i32> {
+        if repeat_mode > CELL_SEARCH_REPEATMODE_NOREPEAT1 {
+            return Err(CELL_SEARCH_ERROR_PARAM);
+        }
+        let mut ids = Vec::new();
+        for &id in self.search(search_id)? {
+            let content = &self.contents[&id];
+            match content.content_type {
+                CELL_SEARCH_CONTENTTYPE_MUSIC => ids.push(id),
+                CELL_SEARCH_CONTENTTYPE_MUSICLIST => ids.extend_from_slice(&content.items),
+                _ => return Err(CELL_SEARCH_ERROR_INVALID_CONTENTTYPE),
+            }
+        }
+        ids.retain(|&id| self.is_present(id));
+        if ids.is_empty() {
+            return Err(CELL_SEARCH_ERROR_CONTENT_NOT_FOUND);
+        }
+        let first = match first_id {
+            Some(first_id) => ids.iter().position(|&id| id == first_id).ok_or(CELL_SEARCH_ERROR_CONTENT_NOT_FOUND)?,
+            None => 0,
+        };
+
+        debug!("SearchManager::make_music_selection: {} tracks from search {}", ids.len(), search_id);
+        let tracks = ids.iter().map(|id| self.contents[id].path.clone()).collect();
+        self.music_selection = Some(MusicSelection { tracks, first, repeat_mode });
+        Ok(())
+    }
+
+    /// Make a music selection context of a single track
+    pub fn make_single_track_selection(&mut self, id: u64, repeat_mode: u32) -> Result<(), i32> {
+        if repeat_mode > CELL_SEARCH_REPEATMODE_NOREPEAT1 {
+            return Err(CELL_SEARCH_ERROR_PARAM);
+        }
+        let track = self.content(id)?;
+        if track.content_type != CELL_SEARCH_CONTENTTYPE_MUSIC {
+            return Err(CELL_SEARCH_ERROR_INVALID_CONTENTTYPE);
+        }
+        self.music_selection = Some(MusicSelection { tracks: vec![track.path.clone()], first: 0, repeat_mode });
+        Ok(())
+    }
+
+    /// Take the music selection context made last
+    pub fn take_music_selection(&mut self) -> Option<MusicSelection> {
+        self.music_selection.take()
+    }
+
+    /// Bytes of a CellMusicSelectionContext standing for the selection made last
+    ///
+    /// The context holds a handle to the selection, so cellMusic restores it
+    /// for as long as the emulator runs. Contexts a game kept from an earlier
+    /// run are not recognized and select the music folder again.
+    pub fn save_music_selection(&mut self) -> Option<[u8; CELL_MUSIC_SELECTION_CONTEXT_SIZE]> {
+        let selection = self.take_music_selection()?;
+        let handle = self.saved_selections.len() as u32;
+        self.saved_selections.push(selection);
+        let mut context = [0; CELL_MUSIC_SELECTION_CONTEXT_SIZE];
+        context[..8].copy_from_slice(SELECTION_CONTEXT_MAGIC);
+        context[8..12].copy_from_slice(&handle.to_be_bytes());
+        Some(context)
+    }
+
+    /// Selection a context written by [`SearchManager::save_music_selection`] stands for
+    pub fn saved_music_selection(&self, context: &[u8]) -> Option<MusicSelection> {
+        if context.len() < 12 || &context[..8] != SELECTION_CONTEXT_MAGIC {
+            return None;
+        }
+        let handle = u32::from_be_bytes(context[8..12].try_into().unwrap());
+        self.saved_selections.get(handle as usize).cloned()
+    }
+
+    /// Cancel a search, which has always finished already
+    pub fn cancel(&mut self, search_id: u32) -> i32 {
+        match self.search(search_id) {
+            Ok(_) => CELL_SEARCH_ERROR_ALREADY_GOT_RESULT,
+            Err(e) => e,
+        }
+    }
+
+    /// End a search, dropping its results
+    pub fn end(&mut self, search_id: u32) -> i32 {
+        if let Err(e) = self.search(search_id) {
+            return e;
+        }
+        trace!("SearchManager::end: search {}", search_id);
+        self.searches.remove(&search_id);
+        0 // CELL_OK
+    }
+
+    fn queue_event(&mut self, event: u32, result: i32, search_id: u32, result_num: u32) {
+        trace!("SearchManager::queue_event: event={}, search_id={}, results={}", event, search_id, result_num);
+        self.pending_events.push(SearchEvent { event, result, search_id, result_num });
+    }
+
+    /// Take the events waiting to be passed to the callback
+    pub fn take_events(&mut self) -> Vec<SearchEvent> {
+        std::mem::take(&mut self.pending_events)
+    }
+
+    /// Hand the waiting events to the game's callback on `calls`
+    ///
+    /// The callback gets the event, its result, the CellSearchResultParam of
+    /// search results (0 for the others) and the user data.
+    pub fn queue_callbacks(&mut self, calls: &mut GuestCallQueue) {
+        for event in self.take_events() {
+            let param = if event.search_id != 0 { self.write_result_param(&event) } else { 0 };
+            calls.push(
+                self.callback,
+                [event.event as u64, event.result as i64 as u64, param as u64, self.userdata as u64],
+            );
+        }
+    }
+
+    /// Write the search ID and result count of `event` to its parameter slot, returning the slot's address
+    ///
+    /// Slots are reused in turn, and there are more than the searches a game waits on at once.
+    fn write_result_param(&mut self, event: &SearchEvent) -> u32 {
+        let Some(memory) = guest_memory() else {
+            return 0;
+        };
+        if self.result_params_addr == 0 {
+            match memory.allocate(RESULT_PARAM_SLOTS * 8, 0x10, oc_memory::PageFlags::RW) {
+                Ok(addr) => self.result_params_addr = addr,
+                Err(_) => return 0,
+            }
+        }
+        let addr = self.result_params_addr + event.search_id % RESULT_PARAM_SLOTS * 8;
+        let written = memory
+            .write_be32(addr, event.search_id)
+            .and_then(|_| memory.write_be32(addr + 4, event.result_num));
+        if written.is_err() {
+            return 0;
+        }
+        addr
+    }
+
+    /// Event callback address and user data
+    pub fn callback(&self) -> (u32, u32) {
+        (self.callback, self.userdata)
+    }
+
+    /// Number of searches whose results are held
+    pub fn search_count(&self) -> usize {
+        self.searches.len()
+    }
+
+    /// Check if initialized
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+impl Default for SearchManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Files under `folder` with one of `extensions`, sorted by path
+pub fn scan_folder(folder: &Path, extensions: &[&str]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = vec![folder.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| extensions.contains(&e.to_ascii_lowercase().as_str()))
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Year, month and day of a time in seconds since the epoch
+fn date_of(secs: i64) -> (i64, u32, u32) {
+    // Days to civil date, after Howard Hinnant's algorithm
+    let days = secs.div_euclid(86400) + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Read the tags and header fields of a music, photo or video file
+///
+/// Fields the file does not carry are left empty or 0.
+pub fn read_metadata(path: &Path) -> ContentMetadata {
+    let mut meta = ContentMetadata::default();
+    let Ok(mut file) = File::open(path) else {
+        return meta;
+    };
+    let len = file.metadata().map_or(0, |m| m.len());
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+
+    match extension.as_str() {
+        "mp3" => {
+            meta.codec = CELL_SEARCH_CODEC_MP3;
+            let tag_len = read_id3v2(&mut file, &mut meta);
+            read_id3v1(&mut file, len, &mut meta);
+            read_mpeg_audio(&mut file, tag_len, len, &mut meta);
+        }
+        "flac" => read_flac(&mut file, len, &mut meta),
+        "jpg" | "jpeg" => {
+            meta.codec = CELL_SEARCH_CODEC_JPEG;
+            read_jpeg(&mut file, &mut meta);
+        }
+        "png" => {
+            meta.codec = CELL_SEARCH_CODEC_PNG;
+            if let Some(h) = read_at(&mut file, 0, 24).filter(|h| h.len() == 24 && h.starts_with(b"\x89PNG")) {
+                meta.width = be32(&h[16..]);
+                meta.height = be32(&h[20..]);
+            }
+        }
+        "gif" => {
+            meta.codec = CELL_SEARCH_CODEC_GIF;
+            if let Some(h) = read_at(&mut file, 0, 10).filter(|h| h.len() == 10 && h.starts_with(b"GIF8")) {
+                meta.width = u16::from_le_bytes([h[6], h[7]]) as u32;
+                meta.height = u16::from_le_bytes([h[8], h[9]]) as u32;
+            }
+        }
+        "bmp" => {
+            meta.codec = CELL_SEARCH_CODEC_BMP;
+            if let Some(h) = read_at(&mut file, 0, 26).filter(|h| h.len() == 26 && h.starts_with(b"BM")) {
+                meta.width = le32(&h[18..]);
+                // Top-down bitmaps store a negative height
+                meta.height = (le32(&h[22..]) as i32).unsigned_abs();
+            }
+        }
+        "mp4" | "m4v" | "mov" => read_mp4(&mut file, len, &mut meta),
+        "avi" => read_avi(&mut file, &mut meta),
+        "mpg" | "mpeg" => meta.codec = CELL_SEARCH_CODEC_MPEG2,
+        _ => {}
+    }
+    meta
+}
+
+fn read_at(file: &mut File, offset: u64, len: usize) -> Option<Vec<u8>> {
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    let mut buf = Vec::with_capacity(len);
+    file.by_ref().take(len as u64).read_to_end(&mut buf).ok()?;
+    Some(buf)
+}
+
+fn be32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn le32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn syncsafe(bytes: &[u8]) -> u32 {
+    bytes[..4].iter().fold(0, |value, &b| (value << 7) | (b & 0x7F) as u32)
+}
+
+/// Leading number of a track field such as "3/12"
+fn track_number(text: &str) -> i32 {
+    text.split('/').next().and_then(|n| n.trim().parse().ok()).unwrap_or(0)
+}
+
+/// Text of an ID3v2 text frame, up to its first terminator
+fn id3_text(body: &[u8]) -> String {
+    let Some((&encoding, data)) = body.split_first() else {
+        return String::new();
+    };
+    let utf16 = |data: &[u8], big_endian: bool| {
+        let units = data.chunks_exact(2).map(|c| if big_endian { [c[0], c[1]] } else { [c[1], c[0]] });
+        let units = units.map(u16::from_be_bytes).take_while(|&u| u != 0);
+        char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)).collect::<String>()
+    };
+    let text = match encoding {
+        // ISO-8859-1
+        0 => data.iter().take_while(|&&b| b != 0).map(|&b| b as char).collect(),
+        // UTF-16 with a byte order mark
+        1 => match data {
+            [0xFF, 0xFE, rest @ ..] => utf16(rest, false),
+            [0xFE, 0xFF, rest @ ..] => utf16(rest, true),
+            _ => utf16(data, true),
+        },
+        2 => utf16(data, true),
+        _ => String::from_utf8_lossy(data.split(|&b| b == 0).next().unwrap_or_default()).into_owned(),
+    };
+    text.trim().to_string()
+}
+
+/// Read the text frames of an ID3v2.3/2.4 tag, returning the tag's length
+fn read_id3v2(file: &mut File, meta: &mut ContentMetadata) -> u64 {
+    let Some(header) = read_at(file, 0, 10).filter(|h| h.len() == 10 && h.starts_with(b"ID3")) else {
+        return 0;
+    };
+    let (version, flags) = (header[3], header[5]);
+    let size = syncsafe(&header[6..]) as usize;
+    // A footer repeats the header after the frames
+    let tag_len = 10 + size as u64 + if flags & 0x10 != 0 { 10 } else { 0 };
+    // Unsynchronised tags are rare enough to leave to the ID3v1 fallback
+    if !(3..=4).contains(&version) || flags & 0x80 != 0 {
+        return tag_len;
+    }
+    let Some(tag) = read_at(file, 10, size.min(MAX_TAG_SIZE)) else {
+        return tag_len;
+    };
+
+    let mut pos = 0;
+    if flags & 0x40 != 0 && tag.len() >= 4 {
+        // The extended header's size counts itself in 2.4 but not in 2.3
+        pos = if version == 4 { syncsafe(&tag) as usize } else { be32(&tag) as usize + 4 };
+    }
+    while pos + 10 <= tag.len() && tag[pos] != 0 {
+        let frame_size = if version == 4 { syncsafe(&tag[pos + 4..]) } else { be32(&tag[pos + 4..]) } as usize;
+        let Some(body) = tag.get(pos + 10..pos + 10 + frame_size) else {
+            break;
+        };
+        match &tag[pos..pos + 4] {
+            b"TIT2" => meta.title = id3_text(body),
+            b"TALB" => meta.album = id3_text(body),
+            b"TPE1" => meta.artist = id3_text(body),
+            b"TCON" => meta.genre = id3_text(body),
+            b"TRCK" => meta.track_number = track_number(&id3_text(body)),
+            _ => {}
+        }
+        pos += 10 + frame_size;
+    }
+    tag_len
+}
+
+/// Fill fields the ID3v2 tag lacked from an ID3v1 tag
+fn read_id3v1(file: &mut File, len: u64, meta: &mut ContentMetadata) {
+    if len < 128 {
+        return;
+    }
+    let Some(tag) = read_at(file, len - 128, 128).filter(|t| t.len() == 128 && t.starts_with(b"TAG")) else {
+        return;
+    };
+    let field = |range: std::ops::Range<usize>| {
+        let bytes = &tag[range];
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        bytes[..end].iter().map(|&b| b as char).collect::<String>().trim().to_string()
+    };
+    for (value, range) in [(&mut meta.title, 3..33), (&mut meta.artist, 33..63), (&mut meta.album, 63..93)] {
+        if value.is_empty() {
+            *value = field(range);
+        }
+    }
+    // ID3v1.1 keeps the track number after a zero byte ending the comment
+    if meta.track_number == 0 && tag[125] == 0 {
+        meta.track_number = tag[126] as i32;
+    }
+}
+
+/// Check for the frame sync of a valid MPEG layer III frame header
+fn is_layer3_header(header: &[u8]) -> bool {
+    let sync = header[0] == 0xFF && header[1] & 0xE0 == 0xE0;
+    let (version, layer) = ((header[1] >> 3) & 3, (header[1] >> 1) & 3);
+    let (bitrate, sample_rate) = (header[2] >> 4, (header[2] >> 2) & 3);
+    sync && version != 1 && layer == 1 && bitrate != 0xF && sample_rate != 3
+}
+
+/// Read the first MPEG audio frame for the stream's format and duration
+fn read_mpeg_audio(file: &mut File, start: u64, len: u64, meta: &mut ContentMetadata) {
+    const BITRATES_V1: [u32; 15] = [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320];
+    const BITRATES_V2: [u32; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+    const SAMPLE_RATES: [u32; 3] = [44100, 48000, 32000];
+
+    let Some(buf) = read_at(file, start, 64 * 1024) else {
+        return;
+    };
+    let Some(pos) = buf.windows(4).position(is_layer3_header) else {
+        return;
+    };
+    let header = &buf[pos..];
+    let version = (header[1] >> 3) & 3;
+    let bitrate_index = (header[2] >> 4) as usize;
+    let (bitrate, divisor, samples_per_frame) = match version {
+        3 => (BITRATES_V1[bitrate_index], 1, 1152),
+        2 => (BITRATES_V2[bitrate_index], 2, 576),
+        _ => (BITRATES_V2[bitrate_index], 4, 576),
+    };
+    meta.sample_rate = SAMPLE_RATES[((header[2] >> 2) & 3) as usize] / divisor;
+    meta.channels = if header[3] >> 6 == 3 { 1 } else { 2 };
+    meta.bitrate = bitrate * 1000;
+
+    // VBR files carry a frame count in a Xing or Info header
+    let frame = &header[..header.len().min(64)];
+    let frames = frame
+        .windows(4)
+        .position(|w| w == b"Xing" || w == b"Info")
+        .and_then(|x| header.get(x..x + 12))
+        .filter(|xing| be32(&xing[4..]) & 1 != 0)
+        .map(|xing| be32(&xing[8..]) as u64);
+    let audio_len = len.saturating_sub(start + pos as u64);
+    meta.duration_ms = match frames {
+        Some(frames) => frames * samples_per_frame * 1000 / meta.sample_rate as u64,
+        None if bitrate != 0 => audio_len * 8 / bitrate as u64,
+        None => 0,
+    };
+    if frames.is_some() && meta.duration_ms != 0 {
+        meta.bitrate = (audio_len * 8 * 1000 / meta.duration_ms) as u32;
+    }
+}
+
+/// Read the stream info and Vorbis comments of a FLAC file
+fn read_flac(file: &mut File, len: u64, meta: &mut ContentMetadata) {
+    if read_at(file, 0, 4).as_deref() != Some(&b"fLaC"[..]) {
+        return;
+    }
+    let mut pos = 4;
+    while let Some(header) = read_at(file, pos, 4).filter(|h| h.len() == 4) {
+        let block_len = be32(&[0, header[1], header[2], header[3]]) as usize;
+        match header[0] & 0x7F {
+            // STREAMINFO
+            0 => {
+                if let Some(info) = read_at(file, pos + 4, 18).filter(|i| i.len() == 18) {
+                    meta.sample_rate = ((info[10] as u32) << 12) | ((info[11] as u32) << 4) | (info[12] as u32 >> 4);
+                    meta.channels = ((info[12] >> 1) & 7) as u32 + 1;
+                    let total = (((info[13] & 0x0F) as u64) << 32) | be32(&info[14..]) as u64;
+                    if meta.sample_rate != 0 {
+                        meta.duration_ms = total * 1000 / meta.sample_rate as u64;
+                    }
+                }
+            }
+            // VORBIS_COMMENT
+            4 => {
+                if let Some(block) = read_at(file, pos + 4, block_len.min(MAX_TAG_SIZE)) {
+                    read_vorbis_comments(&block, meta);
+                }
+            }
+            _ => {}
+        }
+        pos += 4 + block_len as u64;
+        if header[0] & 0x80 != 0 {
+            break;
+        }
+    }
+    meta.bitrate = (len * 8 * 1000).checked_div(meta.duration_ms).unwrap_or(0) as u32;
+}
+
+fn read_vorbis_comments(block: &[u8], meta: &mut ContentMetadata) {
+    let mut pos = 0;
+    let mut next = |len: usize| {
+        let bytes = block.get(pos..pos + len)?;
+        pos += len;
+        Some(bytes)
+    };
+    let Some(vendor_len) = next(4).map(le32) else {
+        return;
+    };
+    next(vendor_len as usize);
+    let count = next(4).map_or(0, le32);
+    for _ in 0..count {
+        let Some(comment) = next(4).map(le32).and_then(|len| next(len as usize)) else {
+            break;
+        };
+        let comment = String::from_utf8_lossy(comment);
+        let Some((key, value)) = comment.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key.to_ascii_uppercase().as_str() {
+            "TITLE" => meta.title = value,
+            "ALBUM" => meta.album = value,
+            "ARTIST" => meta.artist = value,
+            "GENRE" => meta.genre = value,
+            "TRACKNUMBER" => meta.track_number = track_number(&value),
+            _ => {}
+        }
+    }
+}
+
+/// Read the dimensions from a JPEG's start-of-frame segment
+fn read_jpeg(file: &mut File, meta: &mut ContentMetadata) {
+    if read_at(file, 0, 2).as_deref() != Some(&[0xFF, 0xD8][..]) {
+        return;
+    }
+    let mut pos = 2;
+    while let Some(segment) = read_at(file, pos, 9).filter(|s| s.len() == 9 && s[0] == 0xFF) {
+        let marker = segment[1];
+        // SOF0-SOF15, which DHT, JPG and DAC share the range with
+        if (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+            meta.height = u16::from_be_bytes([segment[5], segment[6]]) as u32;
+            meta.width = u16::from_be_bytes([segment[7], segment[8]]) as u32;
+            return;
+        }
+        // Image data follows start-of-scan
+        if marker == 0xDA {
+            return;
+        }
+        pos += 2 + u16::from_be_bytes([segment[2], segment[3]]) as u64;
+    }
+}
+
+/// Boxes between `start` and `end` of an MP4 file, as (type, body start, body end)
+fn mp4_boxes(file: &mut File, start: u64, end: u64) -> Vec<([u8; 4], u64, u64)> {
+    let mut boxes = Vec::new();
+    let mut pos = start;
+    while pos + 8 <= end {
+        let Some(header) = read_at(file, pos, 16).filter(|h| h.len() >= 8) else {
+            break;
+        };
+        let kind = [header[4], header[5], header[6], header[7]];
+        let (body, size) = match be32(&header) as u64 {
+            // The box runs to the end of its parent
+            0 => (pos + 8, end - pos),
+            1 if header.len() == 16 => (pos + 16, u64::from_be_bytes(header[8..16].try_into().unwrap())),
+            size => (pos + 8, size),
+        };
+        if size < body - pos || pos + size > end {
+            break;
+        }
+        boxes.push((kind, body, pos + size));
+        pos += size;
+    }
+    boxes
+}
+
+/// Read the duration, dimensions and codec from an MP4/QuickTime movie header
+fn read_mp4(file: &mut File, len: u64, meta: &mut ContentMetadata) {
+    let Some(&(_, moov, moov_end)) = mp4_boxes(file, 0, len).iter().find(|(kind, ..)| kind == b"moov") else {
+        return;
+    };
+    for (kind, body, end) in mp4_boxes(file, moov, moov_end) {
+        match &kind {
+            b"mvhd" => {
+                let Some(mvhd) = read_at(file, body, 32).filter(|m| m.len() == 32) else {
+                    continue;
+                };
+                let (timescale, duration) = if mvhd[0] == 1 {
+                    (be32(&mvhd[20..]) as u64, u64::from_be_bytes(mvhd[24..32].try_into().unwrap()))
+                } else {
+                    (be32(&mvhd[12..]) as u64, be32(&mvhd[16..]) as u64)
+                };
+                meta.duration_ms = (duration * 1000).checked_div(timescale).unwrap_or(0);
+            }
+            b"trak" if meta.width == 0 => read_mp4_track(file, body, end, meta),
+            _ => {}
+        }
+    }
+    meta.bitrate = (len * 8 * 1000).checked_div(meta.duration_ms).unwrap_or(0) as u32;
+}
+
+fn read_mp4_track(file: &mut File, start: u64, end: u64, meta: &mut ContentMetadata) {
+    let mut boxes = mp4_boxes(file, start, end);
+    let Some(&(_, tkhd, _)) = boxes.iter().find(|(kind, ..)| kind == b"tkhd") else {
+        return;
+    };
+    // Width and height close the track header as 16.16 fixed point
+    let Some(version) = read_at(file, tkhd, 1).and_then(|v| v.first().copied()) else {
+        return;
+    };
+    let Some(size) = read_at(file, tkhd + if version == 1 { 88 } else { 76 }, 8).filter(|s| s.len() == 8) else {
+        return;
+    };
+    // Audio tracks have no size
+    if be32(&size) == 0 {
+        return;
+    }
+    meta.width = be32(&size) >> 16;
+    meta.height = be32(&size[4..]) >> 16;
+
+    // The codec is the first sample entry of moov/trak/mdia/minf/stbl/stsd
+    for path in [b"mdia", b"minf", b"stbl", b"stsd"] {
+        let Some(&(_, body, end)) = boxes.iter().find(|(kind, ..)| kind == path) else {
+            return;
+        };
+        // Sample entries follow the version, flags and entry count
+        let start = if path == b"stsd" { body + 8 } else { body };
+        boxes = mp4_boxes(file, start, end);
+    }
+    meta.codec = match boxes.first().map(|(kind, ..)| kind) {
+        Some(b"avc1" | b"avc3") => CELL_SEARCH_CODEC_AVC,
+        Some(b"mp4v") => CELL_SEARCH_CODEC_MPEG4,
+        _ => CELL_SEARCH_CODEC_UNKNOWN,
+    };
+}
+
+/// Read the duration and dimensions from an AVI main header
+fn read_avi(file: &mut File, meta: &mut ContentMetadata) {
+    let Some(header) = read_at(file, 0, 88).filter(|h| h.len() == 88) else {
+        return;
+    };
+    if &header[..4] != b"RIFF" || &header[8..12] != b"AVI " || &header[24..28] != b"avih" {
+        return;
+    }
+    let avih = &header[32..];
+    let frame_us = le32(avih) as u64;
+    meta.duration_ms = frame_us * le32(&avih[16..]) as u64 / 1000;
+    meta.width = le32(&avih[32..]);
+    meta.height = le32(&avih[36..]);
+}
+
+/// Run `f` on the search manager, handing the events it queues to the game's callback
+fn with_search<T>(f: impl FnOnce(&mut SearchManager) -> T) -> T {
+    let ctx = &mut *crate::context::get_hle_context_mut();
+    let ret = f(&mut ctx.search);
+    ctx.search.queue_callbacks(&mut ctx.guest_calls);
+    ret
+}
+
+/// Read the CellSearchContentId at `addr`
+fn read_content_id(addr: u32) -> Option<u64> {
+    let bytes = guest_memory()?.read_bytes(addr, CELL_SEARCH_CONTENT_ID_SIZE as u32).ok()?;
+    Some(content_id_from_bytes(bytes.as_slice().try_into().ok()?))
+}
+
+/// Write `bytes` for the game at `addr`
+fn write_output(addr: u32, bytes: &[u8]) -> i32 {
+    match guest_memory().map(|memory| memory.write_bytes(addr, bytes)) {
+        Some(Ok(())) => 0, // CELL_OK
+        _ => CELL_SEARCH_ERROR_GENERIC,
+    }
+}
+
+/// Start a search with `start` and write its ID to `search_id_addr`
+fn start_search(search_id_addr: u32, start: impl FnOnce(&mut SearchManager) -> Result<u32, i32>) -> i32 {
+    if search_id_addr == 0 {
+        return CELL_SEARCH_ERROR_PARAM;
+    }
+    match with_search(start) {
+        Ok(search_id) => write_output(search_id_addr, &search_id.to_be_bytes()),
+        Err(e) => e,
+    }
+}
+
+/// Content whose CellSearchContentId is at `content_id_addr`
+fn content_at(search: &SearchManager, content_id_addr: u32) -> Result<&SearchContent, i32> {
+    if !search.is_initialized() {
+        return Err(CELL_SEARCH_ERROR_NOT_INITIALIZED);
+    }
+    let id = read_content_id(content_id_addr).ok_or(CELL_SEARCH_ERROR_PARAM)?;
+    search.content(id)
+}
+
+/// cellSearchInitialize - Initialize the search library
+///
+/// # Arguments
+/// * `mode` - Search mode
+/// * `container` - Memory container for the library
+/// * `func` - Event callback address
+/// * `userdata` - User data passed to the callback
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_search_initialize(mode: i32, container: u32, func: u32, userdata: u32) -> i32 {
+    debug!("cellSearchInitialize(mode={}, container={})", mode, container);
+
+    with_search(|search| search.init(mode, func, userdata))
+}
+
+/// cellSearchFinalize - Shut the search library down
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_search_finalize() -> i32 {
+    debug!("cellSearchFinalize()");
+
+    with_search(|search| search.finalize())
+}
+
+/// cellSearchStartListSearch - Search the albums, genres or artists of the library
+///
+/// # Arguments
+/// * `list_type` - List search type
+/// * `sort_key` - Key the lists are sorted by
+/// * `sort_order` - Sort order
+/// * `search_id_addr` - Address to write the search ID
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_search_start_list_search(list_type: u32, sort_key: u32, sort_order: u32, search_id_addr: u32) -> i32 {
+    debug!(
+        "cellSearchStartListSearch(type={}, sort_key={}, sort_order={})",
+        list_type, sort_key, sort_order
+    );
+
+    start_search(search_id_addr, |search| search.start_list_search(list_type, sort_key, sort_order))
+}
+
+/// cellSearchStartContentSearchInList - Search the contents of a list
+///
+/// # Arguments
+/// * `list_id_addr` - Address of the list's CellSearchContentId
+/// * `sort_key` - Key the contents are sorted by
+/// * `sort_order` - Sort order
+/// * `search_id_addr` - Address to write the search ID
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_search_start_content_search_in_list(
+    list_id_addr: u32,
+    sort_key: u32,
+    sort_order: u32,
+    search_id_addr: u32,
+) -> i32 {
+    debug!(
+        "cellSearchStartContentSearchInList(sort_key={}, sort_order={})",
+        sort_key, sort_order
+    );
+
+    let Some(list_id) = read_content_id(list_id_addr) else {
+        return CELL_SEARCH_ERROR_PARAM;
+    };
+    start_search(search_id_addr, |search| search.start_content_search_in_list(list_id, sort_key, sort_order))
+}
+
+/// cellSearchStartContentSearch - Search every music track, photo or video
+///
+/// # Arguments
+/// * `search_type` - Content search type
+/// * `sort_key` - Key the contents are sorted by
+/// * `sort_order` - Sort order
+/// * `search_id_addr` - Address to write the search ID
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_search_start_content_search(search_type: u32, sort_key: u32, sort_order: u32, search_id_addr: u32) -> i32 {
+    debug!(
+        "cellSearchStartContentSearch(type={}, sort_key={}, sort_order={})",
+        search_type, sort_key, sort_order
+    );
+
+    start_search(search_id_addr, |search| search.start_content_search(search_type, sort_key, sort_order))
+}
+
+/// cellSearchStartSceneSearchInVideo - Search the scenes of a video
+///
+/// # Arguments
+/// * `video_id_addr` - Address of the video's CellSearchContentId
+/// * `search_type` - Scene search type
+/// * `sort_order` - Sort order
+/// * `search_id_addr` - Address to write the search ID
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_search_start_scene_search_in_video(
+    video_id_addr: u32,
+    search_type: u32,
+    sort_order: u32,
+    search_id_addr: u32,
+) -> i32 {
+    debug!("cellSearchStartSceneSearchInVideo(type={}, sort_order={})", search_type, sort_order);
+
+    let Some(video_id) = read_content_id(video_id_addr) else {
+        return CELL_SEARCH_ERROR_PARAM;
+    };
+    start_search(search_id_addr, |search| {
+        search.start_scene_search(Some(video_id), CELL_SEARCH_SORTKEY_NONE, sort_order)
+    })
+}
+
+/// cellSearchStartSceneSearch - Search the scenes of every video
+///
+/// # Arguments
+/// * `search_type` - Scene search type
+/// * `game_title_addr` - Address of the title the scenes were recorded by
+/// * `tags_addr` - Address of the tags to match
+/// * `tag_num` - Number of tags
+/// * `sort_key` - Key the scenes are sorted by
+/// * `sort_order` - Sort order
+/// * `search_id_addr` - Address to write the search ID
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_search_start_scene_search(
+    search_type: u32,
+    _game_title_addr: u32,
+    _tags_addr: u32,
+    tag_num: u32,
+    sort_key: u32,
+    sort_order: u32,
+    search_id_addr: u32,
+) -> i32 {
+    debug!(
+        "cellSearchStartSceneSearch(type={}, tag_num={}, sort_key={}, sort_order={})",
+        search_type, tag_num, sort_key, sort_order
+    );
+
+    start_search(search_id_addr, |search| search.start_scene_search(None, sort_key, sort_order))
+}
+
+/// Write the info, type and ID of `content` to the addresses that are set
+fn write_content(content: &SearchContent, info_addr: u32, content_type_addr: u32, content_id_addr: u32) -> i32 {
+    let outputs = [
+        (info_addr, content_info(content)),
+        (content_type_addr, content.content_type.to_be_bytes().to_vec()),
+        (content_id_addr, content_id_bytes(content.id).to_vec()),
+    ];
+    for (addr, bytes) in outputs {
+        if addr != 0 {
+            let ret = write_output(addr, &bytes);
+            if ret != 0 {
+                return ret;
+            }
+        }
+    }
+    0 // CELL_OK
+}
+
+/// cellSearchGetContentInfoByOffset - Get a search result's info
+///
+/// # Arguments
+/// * `search_id` - Search ID
+/// * `offset` - Offset in the results
+/// * `info_addr` - Address of the content info buffer
+/// * `content_type_addr` - Address to write the content type
+/// * `content_id_addr` - Address to write the CellSearchContentId
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_search_get_content_info_by_offset(
+    search_id: u32,
+    offset: u32,
+    info_addr: u32,
+    content_type_addr: u32,
+    content_id_addr: u32,
+) -> i32 {
+    if info_addr == 0 || content_type_addr == 0 {
+        return CELL_SEARCH_ERROR_PARAM;
+    }
+    let ctx = crate::context::get_hle_context();
+    let content = match ctx.search.content_by_offset(search_id, offset) {
+        Ok(content) => content,
+        Err(e) => return e,
+    };
+    trace!(
+        "cellSearchGetContentInfoByOffset(search_id={}, offset={}) -> {:?}",
+        search_id, offset, content.metadata.title
+    );
+
+    write_content(content, info_addr, content_type_addr, content_id_addr)
+}
+
+/// cellSearchGetContentInfoByContentId - Get a content's info
+///
+/// # Arguments
+/// * `content_id_addr` - Address of the CellSearchContentId
+/// * `info_addr` - Address of the content info buffer
+/// * `content_type_addr` - Address to write the content type
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_search_get_content_info_by_content_id(
+    content_id_addr: u32,
+    info_addr: u32,
+    content_type_addr: u32,
+) -> i32 {
+    trace!("cellSearchGetContentInfoByContentId()");
+
+    if content_id_addr == 0 || info_addr == 0 || content_type_addr == 0 {
+        return CELL_SEARCH_ERROR_PARAM;
+    }
+    let ctx = crate::context::get_hle_context();
+    match content_at(&ctx.search, content_id_addr) {
+        Ok(content) => write_content(content, info_addr, content_type_addr, 0),
+        Err(e) => e,
+    }
+}
+
+/// cellSearchGetOffsetByContentId - Get the offset of a content in the results
+///
+/// # Arguments
+/// * `search_id` - Search ID
+/// * `content_id_addr` - Address of the CellSearchContentId
+/// * `offset_addr` - Address to write the offset
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_search_get_offset_by_content_id(search_id: u32, content_id_addr: u32, offset_addr: u32) -> i32 {
+    trace!("cellSearchGetOffsetByContentId(search_id={})", search_id);
+
+    if content_id_addr == 0 || offset_addr == 0 {
+        return CELL_SEARCH_ERROR_PARAM;
+    }
+    let Some(id) = read_content_id(content_id_addr) else {
+        return CELL_SEARCH_ERROR_PARAM;
+    };
+    match crate::context::get_hle_context().search.offset_of(search_id, id) {
+        Ok(offset) => write_output(offset_addr, &offset.to_be_bytes()),
+        Err(e) => e,
+    }
+}
+
+/// cellSearchGetContentIdByOffset - Get the ID of a search result
+///
+/// # Arguments
+/// * `search_id` - Search ID
+/// * `offset` - Offset in the results
+/// * `content_type_addr` - Address to write the content type
+/// * `content_id_addr` - Address to write the CellSearchContentId
+/// * `time_info_addr` - Address to write the CellSearchTimeInfo (may be 0)
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_search_get_content_id_by_offset(
+    search_id: u32,
+    offset: u32,
+    content_type_addr: u32,
+    content_id_addr: u32,
+    time_info_addr: u32,
+) -> i32 {
+    if content_type_addr == 0 || content_id_addr == 0 {
+        return CELL_SEARCH_ERROR_PARAM;
+    }
+    let ctx = crate::context::get_hle_context();
+    let content = match ctx.search.content_by_offset(search_id, offset) {
+        Ok(content) => content,
+        Err(e) => return e,
+    };
+    trace!(
+        "cellSearchGetContentIdByOffset(search_id={}, offset={}) -> {:02X?}",
+        search_id, offset, content_id_bytes(content.id)
+    );
+
+    let ret = write_content(content, 0, content_type_addr, content_id_addr);
+    if ret != 0 || time_info_addr == 0 {
+        return ret;
+    }
+    // Taken, imported and modified dates, all the file's modification time
+    write_output(time_info_addr, &[content.modified.to_be_bytes(); 3].concat())
+}
+
+/// cellSearchGetContentInfoGameComment - Get the comment a game attached to a content
+///
+/// # Arguments
+/// * `content_id_addr` - Address of the CellSearchContentId
+/// * `comment_addr` - Address to write the comment
+///
+/// # Returns
+/// * 0 on success, with an empty comment as library files carry none
+pub fn cell_search_get_content_info_game_comment(content_id_addr: u32, comment_addr: u32) -> i32 {
+    trace!("cellSearchGetContentInfoGameComment()");
+
+    if content_id_addr == 0 || comment_addr == 0 {
+        return CELL_SEARCH_ERROR_PARAM;
+    }
+    if let Err(e) = content_at(&crate::context::get_hle_context().search, content_id_addr) {
+        return e;
+    }
+    write_output(comment_addr, &[0])
+}
+
+/// cellSearchGetMusicSelectionContext - Make a cellMusic selection of search results
+///
+/// # Arguments
+/// * `search_id` - Search ID
+/// * `content_id_addr` - Address of the CellSearchContentId to start at (0 for the first)
+/// * `repeat_mode` - Repeat mode of the selection
+/// * `option` - Selection options
+/// * `context_addr` - Address of CellMusicSelectionContext
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_search_get_music_selection_context(
+    search_id: u32,
+    content_id_addr: u32,
+    repeat_mode: u32,
+    option: u32,
+    context_addr: u32,
+) -> i32 {
+    debug!(
+        "cellSearchGetMusicSelectionContext(search_id={}, repeat_mode={}, option={})",
+        search_id, repeat_mode, option
+    );
+
+    if context_addr == 0 {
+        return CELL_SEARCH_ERROR_PARAM;
+    }
+    let first_id = match content_id_addr {
+        0 => None,
+        addr => match read_content_id(addr) {
+            Some(id) => Some(id),
+            None => return CELL_SEARCH_ERROR_PARAM,
+        },
+    };
+    let search = &mut crate::context::get_hle_context_mut().search;
+    if let Err(e) = search.make_music_selection(search_id, first_id, repeat_mode) {
+        return e;
+    }
+    match search.save_music_selection() {
+        Some(context) => write_output(context_addr, &context),
+        None => CELL_SEARCH_ERROR_GENERIC,
+    }
+}
+
+/// cellSearchGetMusicSelectionContextOfSingleTrack - Make a cellMusic selection of one track
+///
+/// # Arguments
+/// * `content_id_addr` - Address of the track's CellSearchContentId
+/// * `context_addr` - Address of CellMusicSelectionContext
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_search_get_music_selection_context_of_single_track(content_id_addr: u32, context_addr: u32) -> i32 {
+    debug!("cellSearchGetMusicSelectionContextOfSingleTrack()");
+
+    if content_id_addr == 0 || context_addr == 0 {
+        return CELL_SEARCH_ERROR_PARAM;
+    }
+    let search = &mut crate::context::get_hle_context_mut().search;
+    if !search.is_initialized() {
+        return CELL_SEARCH_ERROR_NOT_INITIALIZED;
+    }
+    let Some(id) = read_content_id(content_id_addr) else {
+        return CELL_SEARCH_ERROR_PARAM;
+    };
+    if let Err(e) = search.make_single_track_selection(id, CELL_SEARCH_REPEATMODE_NONE) {
+        return e;
+    }
+    match search.save_music_selection() {
+        Some(context) => write_output(context_addr, &context),
+        None => CELL_SEARCH_ERROR_GENERIC,
+    }
+}
+
+/// Bytes of a path array of CellSearchContentInfoPath
+fn info_path(path: &str) -> [u8; CELL_SEARCH_PATH_LEN_MAX + 1] {
+    let mut bytes = [0; CELL_SEARCH_PATH_LEN_MAX + 1];
+    let len = path.len().min(CELL_SEARCH_PATH_LEN_MAX);
+    bytes[..len].copy_from_slice(&path.as_bytes()[..len]);
+    bytes
+}
+
+/// cellSearchGetContentInfoPath - Get the path a content's file is opened with
+///
+/// # Arguments
+/// * `content_id_addr` - Address of the CellSearchContentId
+/// * `info_path_addr` - Address of CellSearchContentInfoPath
+///
+/// # Returns
+/// * 0 on success, with no thumbnail path
+pub fn cell_search_get_content_info_path(content_id_addr: u32, info_path_addr: u32) -> i32 {
+    trace!("cellSearchGetContentInfoPath()");
+
+    if content_id_addr == 0 || info_path_addr == 0 {
+        return CELL_SEARCH_ERROR_PARAM;
+    }
+    let ctx = crate::context::get_hle_context();
+    match content_at(&ctx.search, content_id_addr) {
+        Ok(content) => write_output(info_path_addr, &[info_path(&content.guest_path), info_path("")].concat()),
+        Err(e) => e,
+    }
+}
+
+/// cellSearchGetContentInfoPathMovieThumb - Get the path of a video's thumbnail
+///
+/// # Arguments
+/// * `content_id_addr` - Address of the CellSearchContentId
+/// * `info_path_addr` - Address of CellSearchContentInfoPathMovieThumb
+///
+/// # Returns
+/// * 0 on success, with no thumbnail path as none are made for library videos
+pub fn cell_search_get_content_info_path_movie_thumb(content_id_addr: u32, info_path_addr: u32) -> i32 {
+    trace!("cellSearchGetContentInfoPathMovieThumb()");
+
+    if content_id_addr == 0 || info_path_addr == 0 {
+        return CELL_SEARCH_ERROR_PARAM;
+    }
+    match content_at(&crate::context::get_hle_context().search, content_id_addr) {
+        Ok(content) if content.content_type != CELL_SEARCH_CONTENTTYPE_VIDEO => CELL_SEARCH_ERROR_INVALID_CONTENTTYPE,
+        Ok(_) => write_output(info_path_addr, &info_path("")),
+        Err(e) => e,
+    }
+}
+
+/// cellSearchPrepareFile - Make a content's file ready to open
+///
+/// # Arguments
+/// * `path_addr` - Address of the file's path
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_search_prepare_file(path_addr: u32) -> i32 {
+    trace!("cellSearchPrepareFile(path_addr=0x{:08X})", path_addr);
+
+    if path_addr == 0 {
+        return CELL_SEARCH_ERROR_PARAM;
+    }
+    if !crate::context::get_hle_context().search.is_initialized() {
+        return CELL_SEARCH_ERROR_NOT_INITIALIZED;
+    }
+    // Library files sit unencrypted on /dev_hdd0, ready to open
+    0 // CELL_OK
+}
+
+/// cellSearchGetContentInfoDeveloperData - Get the data a game attached to a video
+///
+/// # Arguments
+/// * `content_id_addr` - Address of the CellSearchContentId
+/// * `data_addr` - Address to write the data
+///
+/// # Returns
+/// * 0 on success, with empty data as library files carry none
+pub fn cell_search_get_content_info_developer_data(content_id_addr: u32, data_addr: u32) -> i32 {
+    cell_search_get_content_info_game_comment(content_id_addr, data_addr)
+}
+
+/// cellSearchGetContentInfoSharable - Check if a video may be uploaded
+///
+/// # Arguments
+/// * `content_id_addr` - Address of the CellSearchContentId
+/// * `sharable_addr` - Address to write the sharable type
+///
+/// # Returns
+/// * 0 on success, with uploads prohibited
+pub fn cell_search_get_content_info_sharable(content_id_addr: u32, sharable_addr: u32) -> i32 {
+    trace!("cellSearchGetContentInfoSharable()");
+
+    if content_id_addr == 0 || sharable_addr == 0 {
+        return CELL_SEARCH_ERROR_PARAM;
+    }
+    if let Err(e) = content_at(&crate::context::get_hle_context().search, content_id_addr) {
+        return e;
+    }
+    write_output(sharable_addr, &CELL_SEARCH_SHARABLETYPE_PROHIBITED.to_be_bytes())
+}
+
+/// cellSearchCancel - Cancel a search
+///
+/// # Arguments
+/// * `search_id` - Search ID
+///
+/// # Returns
+/// * CELL_SEARCH_ERROR_ALREADY_GOT_RESULT, as searches finish at once
+pub fn cell_search_cancel(search_id: u32) -> i32 {
+    debug!("cellSearchCancel(search_id={})", search_id);
+
+    crate::context::get_hle_context_mut().search.cancel(search_id)
+}
+
+/// cellSearchEnd - End a search, releasing its results
+///
+/// # Arguments
+/// * `search_id` - Search ID
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_search_end(search_id: u32) -> i32 {
+    debug!("cellSearchEnd(search_id={})", search_id);
+
+    crate::context::get_hle_context_mut().search.end(search_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id3_frame(id: &[u8], text: &str) -> Vec<u8> {
+        let mut frame = id.to_vec();
+        frame.extend_from_slice(&(text.len() as u32 + 1).to_be_bytes());
+        frame.extend_from_slice(&[0, 0, 3]);
+        frame.extend_from_slice(text.as_bytes());
+        frame
+    }
+
+    fn mp4_box(kind: &[u8], body: &[u8]) -> Vec<u8> {
+        let mut b = (body.len() as u32 + 8).to_be_bytes().to_vec();
+        b.extend_from_slice(kind);
+        b.extend_from_slice(body);
+        b
+    }
+
+    /// A `/dev_hdd0` with tagged music, photos and a video
+    fn library(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("oc_hle_search_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for dir in ["music/Album A", "photo/Trip", "video"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+
+        // ID3v2.3 tags before 16000 bytes of 128 kbit/s MPEG-1 layer III
+        let frames = [
+            id3_frame(b"TIT2", "Song One"),
+            id3_frame(b"TPE1", "Artist"),
+            id3_frame(b"TALB", "Album A"),
+            id3_frame(b"TRCK", "1/2"),
+        ]
+        .concat();
+        let size = frames.len() as u32;
+        let mut mp3 = b"ID3\x03\x00\x00".to_vec();
+        mp3.extend_from_slice(&[(size >> 21) as u8 & 0x7F, (size >> 14) as u8 & 0x7F, (size >> 7) as u8 & 0x7F]);
+        mp3.push(size as u8 & 0x7F);
+        mp3.extend_from_slice(&frames);
+        mp3.extend_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+        mp3.resize(mp3.len() + 15996, 0);
+        std::fs::write(root.join("music/Album A/01.mp3"), mp3).unwrap();
+
+        // ID3v1.1 tag only
+        let mut tag = vec![0u8; 128];
+        tag[..3].copy_from_slice(b"TAG");
+        tag[3..11].copy_from_slice(b"Song Two");
+        tag[33..39].copy_from_slice(b"Artist");
+        tag[63..70].copy_from_slice(b"Album A");
+        tag[126] = 2;
+        let mut mp3 = vec![0xFF, 0xFB, 0x90, 0x00];
+        mp3.resize(4000, 0);
+        mp3.extend_from_slice(&tag);
+        std::fs::write(root.join("music/Album A/02.mp3"), mp3).unwrap();
+
+        // 2 seconds of 48 kHz stereo with Vorbis comments
+        let mut flac = b"fLaC\x00\x00\x00\x22".to_vec();
+        let mut info = [0u8; 34];
+        info[10..14].copy_from_slice(&[0x0B, 0xB8, 0x02, 0xF0]);
+        info[14..18].copy_from_slice(&96000u32.to_be_bytes());
+        flac.extend_from_slice(&info);
+        let mut comments = 0u32.to_le_bytes().to_vec();
+        comments.extend_from_slice(&2u32.to_le_bytes());
+        for comment in ["TITLE=Flac Song", "genre=Jazz"] {
+            comments.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+            comments.extend_from_slice(comment.as_bytes());
+        }
+        flac.extend_from_slice(&[0x84, 0, 0, comments.len() as u8]);
+        flac.extend_from_slice(&comments);
+        std::fs::write(root.join("music/loose.flac"), flac).unwrap();
+
+        let mut png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+        png.extend_from_slice(&640u32.to_be_bytes());
+        png.extend_from_slice(&480u32.to_be_bytes());
+        std::fs::write(root.join("photo/shot.png"), png).unwrap();
+        let jpeg = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0, 0, 0xFF, 0xC0, 0x00, 0x11, 0x08, 0x02, 0xD0, 0x05, 0x00];
+        std::fs::write(root.join("photo/Trip/pic.jpg"), jpeg).unwrap();
+
+        // 5 seconds of 1920x1080 AVC
+        let mut mvhd = vec![0u8; 100];
+        mvhd[12..16].copy_from_slice(&1000u32.to_be_bytes());
+        mvhd[16..20].copy_from_slice(&5000u32.to_be_bytes());
+        let mut tkhd = vec![0u8; 84];
+        tkhd[76..80].copy_from_slice(&(1920u32 << 16).to_be_bytes());
+        tkhd[80..84].copy_from_slice(&(1080u32 << 16).to_be_bytes());
+        let stsd = mp4_box(b"stsd", &[&[0, 0, 0, 0, 0, 0, 0, 1][..], &mp4_box(b"avc1", &[0; 8])].concat());
+        let stbl = mp4_box(b"stbl", &stsd);
+        let trak = mp4_box(b"trak", &[mp4_box(b"tkhd", &tkhd), mp4_box(b"mdia", &mp4_box(b"minf", &stbl))].concat());
+        let moov = mp4_box(b"moov", &[mp4_box(b"mvhd", &mvhd), trak].concat());
+        std::fs::write(root.join("video/clip.mp4"), [mp4_box(b"ftyp", b"isom"), moov].concat()).unwrap();
+        root
+    }
+
+    fn searching_manager(root: PathBuf) -> SearchManager {
+        let mut manager = SearchManager::new();
+        manager.set_hdd0_root(root);
+        assert_eq!(manager.init(CELL_SEARCH_MODE_NORMAL, 0x1000, 0x2000), 0);
+        manager.take_events();
+        manager
+    }
+
+    fn titles(manager: &SearchManager, search_id: u32) -> Vec<String> {
+        (0..manager.searches[&search_id].len() as u32)
+            .map(|offset| manager.content_by_offset(search_id, offset).unwrap().metadata.title.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_read_metadata() {
+        let root = library("metadata");
+
+        let song = read_metadata(&root.join("music/Album A/01.mp3"));
+        assert_eq!((song.title.as_str(), song.artist.as_str(), song.album.as_str()), ("Song One", "Artist", "Album A"));
+        assert_eq!(song.track_number, 1);
+        assert_eq!((song.sample_rate, song.channels, song.bitrate), (44100, 2, 128000));
+        assert_eq!(song.duration_ms, 1000);
+        assert_eq!(song.codec, CELL_SEARCH_CODEC_MP3);
+
+        let song = read_metadata(&root.join("music/Album A/02.mp3"));
+        assert_eq!((song.title.as_str(), song.album.as_str(), song.track_number), ("Song Two", "Album A", 2));
+
+        let song = read_metadata(&root.join("music/loose.flac"));
+        assert_eq!((song.title.as_str(), song.genre.as_str()), ("Flac Song", "Jazz"));
+        assert_eq!((song.sample_rate, song.channels, song.duration_ms), (48000, 2, 2000));
+
+        let photo = read_metadata(&root.join("photo/shot.png"));
+        assert_eq!((photo.width, photo.height, photo.codec), (640, 480, CELL_SEARCH_CODEC_PNG));
+        let photo = read_metadata(&root.join("photo/Trip/pic.jpg"));
+        assert_eq!((photo.width, photo.height, photo.codec), (1280, 720, CELL_SEARCH_CODEC_JPEG));
+
+        let video = read_metadata(&root.join("video/clip.mp4"));
+        assert_eq!((video.width, video.height, video.duration_ms), (1920, 1080, 5000));
+        assert_eq!(video.codec, CELL_SEARCH_CODEC_AVC);
+
+        assert_eq!(date_of(0), (1970, 1, 1));
+        assert_eq!(date_of(1_700_000_000), (2023, 11, 14));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_search_lifecycle() {
+        let mut manager = SearchManager::new();
+
+        assert_eq!(
+            manager.start_content_search(CELL_SEARCH_CONTENTSEARCHTYPE_MUSIC_ALL, 0, 0),
+            Err(CELL_SEARCH_ERROR_NOT_INITIALIZED)
+        );
+        assert_eq!(manager.init(5, 0x1000, 0), CELL_SEARCH_ERROR_UNKNOWN_MODE);
+        assert_eq!(manager.init(CELL_SEARCH_MODE_NORMAL, 0, 0), CELL_SEARCH_ERROR_PARAM);
+        assert_eq!(manager.init(CELL_SEARCH_MODE_NORMAL, 0x1000, 0x2000), 0);
+        assert_eq!(manager.init(CELL_SEARCH_MODE_NORMAL, 0x1000, 0x2000), CELL_SEARCH_ERROR_ALREADY_INITIALIZED);
+        assert_eq!(manager.callback(), (0x1000, 0x2000));
+
+        // Without a /dev_hdd0 nothing is found
+        let search_id = manager.start_content_search(CELL_SEARCH_CONTENTSEARCHTYPE_PHOTO_ALL, 0, 0).unwrap();
+        assert_eq!(manager.content_by_offset(search_id, 0), Err(CELL_SEARCH_ERROR_OUT_OF_RANGE));
+        assert_eq!(manager.start_content_search(9, 0, 0), Err(CELL_SEARCH_ERROR_PARAM));
+        assert_eq!(
+            manager.start_content_search(CELL_SEARCH_CONTENTSEARCHTYPE_PHOTO_ALL, 11, 0),
+            Err(CELL_SEARCH_ERROR_PARAM)
+        );
+
+        assert_eq!(manager.cancel(search_id), CELL_SEARCH_ERROR_ALREADY_GOT_RESULT);
+        assert_eq!(manager.end(search_id), 0);
+        assert_eq!(manager.end(search_id), CELL_SEARCH_ERROR_INVALID_SEARCHID);
+        assert_eq!(manager.finalize(), 0);
+        assert_eq!(manager.finalize(), CELL_SEARCH_ERROR_NOT_INITIALIZED);
+        assert_eq!(
+            manager.take_events(),
+            [
+                SearchEvent { event: CELL_SEARCH_EVENT_INITIALIZE_RESULT, result: 0, search_id: 0, result_num: 0 },
+                SearchEvent { event: CELL_SEARCH_EVENT_CONTENTSEARCH_RESULT, result: 0, search_id, result_num: 0 },
+                SearchEvent { event: CELL_SEARCH_EVENT_FINALIZE_RESULT, result: 0, search_id: 0, result_num: 0 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_content_search() {
+        let root = library("content");
+        let mut manager = searching_manager(root.clone());
+
+        let search_id = manager
+            .start_content_search(CELL_SEARCH_CONTENTSEARCHTYPE_MUSIC_ALL, CELL_SEARCH_SORTKEY_TITLE, 0)
+            .unwrap();
+        assert_eq!(titles(&manager, search_id), ["Flac Song", "Song One", "Song Two"]);
+        assert_eq!(
+            manager.take_events(),
+            [SearchEvent { event: CELL_SEARCH_EVENT_CONTENTSEARCH_RESULT, result: 0, search_id, result_num: 3 }]
+        );
+        let song = manager.content_by_offset(search_id, 1).unwrap();
+        assert_eq!(song.content_type, CELL_SEARCH_CONTENTTYPE_MUSIC);
+        assert_eq!(song.guest_path, "/dev_hdd0/music/Album A/01.mp3");
+        let song_id = song.id;
+        assert_eq!(manager.offset_of(search_id, song_id), Ok(1));
+        assert_eq!(content_id_from_bytes(&content_id_bytes(song_id)), song_id);
+
+        // IDs stay the same from one search to the next
+        let search_id = manager
+            .start_content_search(
+                CELL_SEARCH_CONTENTSEARCHTYPE_MUSIC_ALL,
+                CELL_SEARCH_SORTKEY_TRACKNUMBER,
+                CELL_SEARCH_SORTORDER_DESCENDING,
+            )
+            .unwrap();
+        assert_eq!(titles(&manager, search_id), ["Song Two", "Song One", "Flac Song"]);
+        assert_eq!(manager.offset_of(search_id, song_id), Ok(1));
+        assert_eq!(manager.search_count(), 2);
+
+        // Untagged photos and videos are titled after their files
+        let search_id = manager.start_content_search(CELL_SEARCH_CONTENTSEARCHTYPE_VIDEO_ALL, 0, 0).unwrap();
+        assert_eq!(titles(&manager, search_id), ["clip"]);
+
+        // Deleted files turn obsolete
+        std::fs::remove_file(root.join("music/Album A/01.mp3")).unwrap();
+        assert_eq!(manager.content(song_id), Err(CELL_SEARCH_ERROR_CONTENT_OBSOLETE));
+        assert_eq!(manager.content(999), Err(CELL_SEARCH_ERROR_CONTENT_NOT_FOUND));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_list_search() {
+        let root = library("list");
+        let mut manager = searching_manager(root.clone());
+
+        let search_id = manager.start_list_search(CELL_SEARCH_LISTSEARCHTYPE_MUSIC_ALBUM, 0, 0).unwrap();
+        assert_eq!(titles(&manager, search_id), ["Album A"]);
+        let album = manager.content_by_offset(search_id, 0).unwrap();
+        assert_eq!(album.content_type, CELL_SEARCH_CONTENTTYPE_MUSICLIST);
+        assert_eq!((album.metadata.artist.as_str(), album.items.len()), ("Artist", 2));
+        let album_id = album.id;
+
+        let in_list = manager.start_content_search_in_list(album_id, CELL_SEARCH_SORTKEY_TRACKNUMBER, 0).unwrap();
+        assert_eq!(titles(&manager, in_list), ["Song One", "Song Two"]);
+        let song_id = manager.content_by_offset(in_list, 0).unwrap().id;
+        assert_eq!(manager.start_content_search_in_list(song_id, 0, 0), Err(CELL_SEARCH_ERROR_NOT_LIST));
+
+        let search_id = manager.start_list_search(CELL_SEARCH_LISTSEARCHTYPE_MUSIC_GENRE, 0, 0).unwrap();
+        assert_eq!(titles(&manager, search_id), ["Jazz"]);
+        let search_id = manager.start_list_search(CELL_SEARCH_LISTSEARCHTYPE_PHOTO_ALBUM, 0, 0).unwrap();
+        assert_eq!(titles(&manager, search_id), ["Trip"]);
+        let search_id = manager.start_list_search(CELL_SEARCH_LISTSEARCHTYPE_PHOTO_YEAR, 0, 0).unwrap();
+        assert_eq!(manager.content_by_offset(search_id, 0).unwrap().items.len(), 2);
+        let search_id = manager.start_list_search(CELL_SEARCH_LISTSEARCHTYPE_MUSIC_PLAYLIST, 0, 0).unwrap();
+        assert!(titles(&manager, search_id).is_empty());
+
+        let video_id = {
+            let search_id = manager.start_content_search(CELL_SEARCH_CONTENTSEARCHTYPE_VIDEO_ALL, 0, 0).unwrap();
+            manager.content_by_offset(search_id, 0).unwrap().id
+        };
+        assert!(manager.start_scene_search(Some(video_id), 0, 0).is_ok());
+        assert_eq!(manager.start_scene_search(Some(album_id), 0, 0), Err(CELL_SEARCH_ERROR_INVALID_CONTENTTYPE));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_music_selection() {
+        let root = library("selection");
+        let mut manager = searching_manager(root.clone());
+
+        let albums = manager.start_list_search(CELL_SEARCH_LISTSEARCHTYPE_MUSIC_ALBUM, 0, 0).unwrap();
+        assert_eq!(manager.make_music_selection(albums, None, CELL_SEARCH_REPEATMODE_REPEAT1), Ok(()));
+        let selection = manager.take_music_selection().unwrap();
+        assert_eq!(selection.tracks, [root.join("music/Album A/01.mp3"), root.join("music/Album A/02.mp3")]);
+        assert_eq!((selection.first, selection.repeat_mode), (0, CELL_SEARCH_REPEATMODE_REPEAT1));
+        assert_eq!(manager.take_music_selection(), None);
+
+        let songs = manager.start_content_search(CELL_SEARCH_CONTENTSEARCHTYPE_MUSIC_ALL, 0, 0).unwrap();
+        let last = manager.content_by_offset(songs, 2).unwrap().id;
+        manager.make_music_selection(songs, Some(last), CELL_SEARCH_REPEATMODE_ALL).unwrap();
+        assert_eq!(manager.take_music_selection().unwrap().first, 2);
+        assert_eq!(manager.make_music_selection(songs, None, 7), Err(CELL_SEARCH_ERROR_PARAM));
+
+        manager.make_single_track_selection(last, CELL_SEARCH_REPEATMODE_NONE).unwrap();
+        assert_eq!(manager.take_music_selection().unwrap().tracks.len(), 1);
+        let photos = manager.start_content_search(CELL_SEARCH_CONTENTSEARCHTYPE_PHOTO_ALL, 0, 0).unwrap();
+        let photo = manager.content_by_offset(photos, 0).unwrap().id;
+        assert_eq!(
+            manager.make_single_track_selection(photo, CELL_SEARCH_REPEATMODE_NONE),
+            Err(CELL_SEARCH_ERROR_INVALID_CONTENTTYPE)
+        );
+        assert_eq!(
+            manager.make_music_selection(photos, None, CELL_SEARCH_REPEATMODE_NONE),
+            Err(CELL_SEARCH_ERROR_INVALID_CONTENTTYPE)
+        );
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_search_guest_outputs() {
+        let _guard = crate::context::test_guard();
+        crate::context::reset_hle_context();
+        let memory = crate::context::test_guest_memory();
+        let root = library("guest");
+        crate::context::get_hle_context_mut().search.set_hdd0_root(root.clone());
+        let addr = memory.allocate(0x1000, 0x10, oc_memory::PageFlags::RW).unwrap();
+        let (id_addr, type_addr, search_id_addr, info_addr) = (addr, addr + 0x10, addr + 0x18, addr + 0x20);
+
+        assert_eq!(cell_search_initialize(CELL_SEARCH_MODE_NORMAL, 0, 0x1000, 0x2000), 0);
+        assert_eq!(
+            cell_search_start_content_search(
+                CELL_SEARCH_CONTENTSEARCHTYPE_MUSIC_ALL,
+                CELL_SEARCH_SORTKEY_TITLE,
+                0,
+                search_id_addr
+            ),
+            0
+        );
+        let search_id = memory.read_be32(search_id_addr).unwrap();
+        assert_eq!(cell_search_get_content_info_by_offset(search_id, 1, info_addr, type_addr, id_addr), 0);
+        assert_eq!(memory.read_be32(type_addr).unwrap(), CELL_SEARCH_CONTENTTYPE_MUSIC);
+        assert_eq!(memory.read_be::<i64>(info_addr).unwrap(), 1000);
+        let title = memory.read_bytes(info_addr + 76, 9).unwrap();
+        assert_eq!(title, b"Song One\0");
+
+        // IDs the game got back lead to the same content
+        memory.write_be32(search_id_addr, 0).unwrap();
+        assert_eq!(cell_search_get_offset_by_content_id(search_id, id_addr, search_id_addr), 0);
+        assert_eq!(memory.read_be32(search_id_addr).unwrap(), 1);
+        let path_addr = addr + 0x800;
+        assert_eq!(cell_search_get_content_info_path(id_addr, path_addr), 0);
+        assert_eq!(crate::context::read_guest_string(path_addr, 64).unwrap(), "/dev_hdd0/music/Album A/01.mp3");
+
+        // The selection context restores the track in cellMusic
+        assert_eq!(cell_search_get_music_selection_context_of_single_track(id_addr, info_addr), 0);
+        let context = memory.read_bytes(info_addr, CELL_MUSIC_SELECTION_CONTEXT_SIZE as u32).unwrap();
+        let ctx = crate::context::get_hle_context();
+        let selection = ctx.search.saved_music_selection(&context).unwrap();
+        assert_eq!(selection.tracks, [root.join("music/Album A/01.mp3")]);
+        drop(ctx);
+
+        let calls = crate::context::get_hle_context_mut().guest_calls.take();
+        assert!(calls.iter().all(|call| call.opd == 0x1000 && call.args[3] == 0x2000));
+        assert_eq!(calls[0].args[..3], [CELL_SEARCH_EVENT_INITIALIZE_RESULT as u64, 0, 0]);
+        assert_eq!(calls[1].args[0], CELL_SEARCH_EVENT_CONTENTSEARCH_RESULT as u64);
+        let param = calls[1].args[2] as u32;
+        assert_eq!((memory.read_be32(param).unwrap(), memory.read_be32(param + 4).unwrap()), (search_id, 3));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
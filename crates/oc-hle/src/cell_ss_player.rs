@@ -0,0 +1,754 @@
+//! cellSSPlayer HLE - Sampler voices and the MultiStream fast path
+//!
+//! This module provides HLE implementations for the SSPlayer sampler of
+//! libmixer and for the stream commands of libmstream, the MultiStream SPU
+//! workload the SCREAM (Sulpha) sound middleware of first-party titles
+//! drives. Rather than queueing these commands for the SPU workload, they are
+//! taken here and their voices rendered on the host mixer, so these games get
+//! audio before SPU audio workloads run accurately. Rendering is optional
+//! (the `hle_sound_middleware` audio setting); without it the calls only keep
+//! the voice state the game polls.
+
+use std::collections::{HashMap, VecDeque};
+use tracing::{debug, trace};
+
+// libmixer error codes
+pub const CELL_LIBMIXER_ERROR_NOT_INITIALIZED: i32 = 0x80310002u32 as i32;
+pub const CELL_LIBMIXER_ERROR_INVALID_PARAMATER: i32 = 0x80310003u32 as i32;
+pub const CELL_LIBMIXER_ERROR_NO_MEMORY: i32 = 0x80310005u32 as i32;
+pub const CELL_LIBMIXER_ERROR_ALREADY_EXIST: i32 = 0x80310006u32 as i32;
+pub const CELL_LIBMIXER_ERROR_FULL: i32 = 0x80310007u32 as i32;
+pub const CELL_LIBMIXER_ERROR_NOT_EXIST: i32 = 0x80310008u32 as i32;
+pub const CELL_LIBMIXER_ERROR_TYPE_MISMATCH: i32 = 0x80310009u32 as i32;
+pub const CELL_LIBMIXER_ERROR_NOT_FOUND: i32 = 0x8031000Au32 as i32;
+
+/// SSPlayer loop modes
+pub const CELL_SSPLAYER_ONESHOT: u32 = 0;
+pub const CELL_SSPLAYER_ONESHOT_CONT: u32 = 2;
+pub const CELL_SSPLAYER_LOOP_ON: u32 = 16;
+
+/// SSPlayer states
+pub const CELL_SSPLAYER_STATE_ERROR: u32 = 0xFFFFFFFF;
+pub const CELL_SSPLAYER_STATE_NOTREADY: u32 = 0x88888888;
+pub const CELL_SSPLAYER_STATE_OFF: u32 = 0x00;
+pub const CELL_SSPLAYER_STATE_ON: u32 = 0x01;
+
+/// Rate libmixer and MultiStream mix at
+pub const MIXER_SAMPLE_RATE: u32 = 48000;
+/// Most MultiStream streams
+pub const CELL_MS_MAX_STREAMS: usize = 512;
+
+/// MultiStream stream states
+pub const CELL_MS_STREAM_OFF: u32 = 0;
+pub const CELL_MS_STREAM_ON: u32 = 1;
+
+/// Sample format of voice data in guest memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// 16-bit big-endian PCM
+    Pcm16Be,
+    /// 32-bit big-endian float
+    Float32Be,
+}
+
+impl SampleFormat {
+    /// Host samples of `data`
+    pub fn decode(self, data: &[u8]) -> Vec<f32> {
+        match self {
+            SampleFormat::Pcm16Be => data
+                .chunks_exact(2)
+                .map(|b| i16::from_be_bytes([b[0], b[1]]) as f32 / 32768.0)
+                .collect(),
+            SampleFormat::Float32Be => data
+                .chunks_exact(4)
+                .map(|b| f32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+        }
+    }
+}
+
+/// Voice played on the host mixer
+#[derive(Debug, Clone)]
+pub struct Voice {
+    /// Interleaved samples of `channels` channels
+    samples: Vec<f32>,
+    channels: usize,
+    /// Buffers to play once `samples` ends
+    queued: VecDeque<Vec<f32>>,
+    /// Rate the samples play at, in Hz
+    pub rate: f32,
+    /// Frame to loop back to at the end, None to stop
+    pub loop_start: Option<usize>,
+    pub level: f32,
+    /// Balance, from -1 (left) to 1 (right)
+    pub pan: f32,
+    /// Playback position in frames
+    cursor: f64,
+    playing: bool,
+}
+
+impl Voice {
+    /// Voice of `channels` (1 or 2) channels, playing from the start
+    pub fn new(samples: Vec<f32>, channels: usize, rate: f32) -> Self {
+        Self {
+            samples,
+            channels: channels.clamp(1, 2),
+            queued: VecDeque::new(),
+            rate,
+            loop_start: None,
+            level: 1.0,
+            pan: 0.0,
+            cursor: 0.0,
+            playing: true,
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    fn frames(&self) -> usize {
+        self.samples.len() / self.channels
+    }
+
+    /// Play from frame `position`
+    pub fn seek(&mut self, position: usize) {
+        self.cursor = position as f64;
+        self.playing = position < self.frames() || !self.queued.is_empty();
+    }
+
+    /// Queue a buffer to play after the current one
+    pub fn queue(&mut self, samples: Vec<f32>) {
+        self.queued.push_back(samples);
+    }
+
+    /// Buffers waiting to play
+    pub fn queued(&self) -> usize {
+        self.queued.len()
+    }
+
+    fn frame(&self, index: usize) -> (f32, f32) {
+        let at = index * self.channels;
+        match self.samples.get(at..at + self.channels) {
+            Some([mono]) => (*mono, *mono),
+            Some([left, right]) => (*left, *right),
+            _ => (0.0, 0.0),
+        }
+    }
+
+    /// Move past the end of the samples: loop, go on with the next buffer or stop
+    fn wrap(&mut self) {
+        while self.playing && self.cursor >= self.frames() as f64 {
+            let end = self.frames() as f64;
+            if let Some(start) = self.loop_start.filter(|&start| start < self.frames()) {
+                self.cursor = start as f64 + (self.cursor - end);
+            } else if let Some(next) = self.queued.pop_front() {
+                self.samples = next;
+                self.cursor -= end;
+            } else {
+                self.playing = false;
+            }
+        }
+    }
+
+    /// Mix stereo frames at `out_rate` into `out`
+    pub fn mix_into(&mut self, out: &mut [f32], out_rate: u32) {
+        let step = self.rate as f64 / out_rate as f64;
+        let left_gain = self.level * (1.0 - self.pan).min(1.0);
+        let right_gain = self.level * (1.0 + self.pan).min(1.0);
+        self.wrap();
+        for frame in out.chunks_exact_mut(2) {
+            if !self.playing {
+                break;
+            }
+            let index = self.cursor as usize;
+            let frac = (self.cursor - index as f64) as f32;
+            let (l0, r0) = self.frame(index);
+            let (l1, r1) = if index + 1 < self.frames() {
+                self.frame(index + 1)
+            } else {
+                (l0, r0)
+            };
+            frame[0] += (l0 + (l1 - l0) * frac) * left_gain;
+            frame[1] += (r0 + (r1 - r0) * frac) * right_gain;
+            self.cursor += step;
+            self.wrap();
+        }
+    }
+}
+
+/// Wave an SSPlayer plays
+#[derive(Debug, Clone, PartialEq)]
+pub struct SsPlayerWave {
+    /// Interleaved samples of the player's channels
+    pub samples: Vec<f32>,
+    pub loop_mode: u32,
+    /// Frame looping starts over at
+    pub loop_start_offset: u32,
+    /// Frame playback starts at
+    pub start_offset: u32,
+}
+
+/// SSPlayer of libmixer
+#[derive(Debug, Clone)]
+pub struct SsPlayer {
+    pub channels: u32,
+    wave: Option<SsPlayerWave>,
+    voice: Option<Voice>,
+    stopped: bool,
+}
+
+impl SsPlayer {
+    pub fn state(&self) -> u32 {
+        match (&self.wave, &self.voice) {
+            (None, _) => CELL_SSPLAYER_STATE_NOTREADY,
+            (Some(_), Some(voice)) if voice.is_playing() && !self.stopped => CELL_SSPLAYER_STATE_ON,
+            _ => CELL_SSPLAYER_STATE_OFF,
+        }
+    }
+}
+
+/// MultiStream stream settings, from CellMSInfo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MsStreamInfo {
+    pub sub_bus_group: u32,
+    /// Sample rate, in Hz
+    pub pitch: u32,
+    pub channels: u32,
+    pub format: SampleFormat,
+    /// Whether the first buffer loops once the data ends
+    pub looping: bool,
+}
+
+/// MultiStream stream
+#[derive(Debug, Clone)]
+pub struct MsStream {
+    pub info: Option<MsStreamInfo>,
+    voice: Option<Voice>,
+    /// Volume and balance, kept over buffers
+    level: f32,
+    pan: f32,
+}
+
+impl MsStream {
+    pub fn status(&self) -> u32 {
+        match &self.voice {
+            Some(voice) if voice.is_playing() => CELL_MS_STREAM_ON,
+            _ => CELL_MS_STREAM_OFF,
+        }
+    }
+
+    /// Whether the game should read the next buffer: the playing one is
+    /// the last queued
+    pub fn needs_second_read(&self) -> bool {
+        self.voice.as_ref().is_some_and(|voice| voice.is_playing() && voice.queued() == 0)
+    }
+}
+
+/// SSPlayer and MultiStream manager
+pub struct SsPlayerManager {
+    players: HashMap<u32, SsPlayer>,
+    next_handle: u32,
+    streams: Vec<Option<MsStream>>,
+}
+
+impl SsPlayerManager {
+    pub fn new() -> Self {
+        Self {
+            players: HashMap::new(),
+            next_handle: 1,
+            streams: Vec::new(),
+        }
+    }
+
+    /// Create a player of `channels` (1 or 2) channels
+    pub fn create(&mut self, channels: u32) -> Result<u32, i32> {
+        if channels == 0 || channels > 2 {
+            return Err(CELL_LIBMIXER_ERROR_INVALID_PARAMATER);
+        }
+        let handle = self.next_handle;
+        self.next_handle += 1;
+        self.players.insert(handle, SsPlayer { channels, wave: None, voice: None, stopped: false });
+        Ok(handle)
+    }
+
+    pub fn remove(&mut self, handle: u32) -> Result<(), i32> {
+        self.players.remove(&handle).map(|_| ()).ok_or(CELL_LIBMIXER_ERROR_NOT_EXIST)
+    }
+
+    pub fn player(&self, handle: u32) -> Result<&SsPlayer, i32> {
+        self.players.get(&handle).ok_or(CELL_LIBMIXER_ERROR_NOT_EXIST)
+    }
+
+    fn player_mut(&mut self, handle: u32) -> Result<&mut SsPlayer, i32> {
+        self.players.get_mut(&handle).ok_or(CELL_LIBMIXER_ERROR_NOT_EXIST)
+    }
+
+    /// Set the wave of a player, stopping it
+    pub fn set_wave(&mut self, handle: u32, wave: SsPlayerWave) -> Result<(), i32> {
+        let player = self.player_mut(handle)?;
+        if !matches!(wave.loop_mode, CELL_SSPLAYER_ONESHOT | CELL_SSPLAYER_ONESHOT_CONT | CELL_SSPLAYER_LOOP_ON) {
+            return Err(CELL_LIBMIXER_ERROR_INVALID_PARAMATER);
+        }
+        player.wave = Some(wave);
+        player.voice = None;
+        Ok(())
+    }
+
+    /// Play the wave of a player at `level` and `speed` from frame `position`
+    pub fn play(&mut self, handle: u32, level: f32, speed: f32, position: u32) -> Result<(), i32> {
+        let player = self.player_mut(handle)?;
+        let wave = player.wave.as_ref().ok_or(CELL_LIBMIXER_ERROR_NOT_FOUND)?;
+        let mut voice = Voice::new(wave.samples.clone(), player.channels as usize, MIXER_SAMPLE_RATE as f32 * speed);
+        voice.level = level;
+        if wave.loop_mode == CELL_SSPLAYER_LOOP_ON {
+            voice.loop_start = Some(wave.loop_start_offset as usize);
+        }
+        voice.seek((wave.start_offset + position) as usize);
+        player.voice = Some(voice);
+        player.stopped = false;
+        Ok(())
+    }
+
+    pub fn stop(&mut self, handle: u32) -> Result<(), i32> {
+        let player = self.player_mut(handle)?;
+        player.stopped = true;
+        Ok(())
+    }
+
+    /// Change the level and speed of a playing player
+    pub fn set_param(&mut self, handle: u32, level: f32, speed: f32) -> Result<(), i32> {
+        let player = self.player_mut(handle)?;
+        if let Some(voice) = &mut player.voice {
+            voice.level = level;
+            voice.rate = MIXER_SAMPLE_RATE as f32 * speed;
+        }
+        Ok(())
+    }
+
+    /// Open a stream, returning its number
+    pub fn stream_open(&mut self) -> Option<u32> {
+        let stream = MsStream { info: None, voice: None, level: 1.0, pan: 0.0 };
+        if let Some(index) = self.streams.iter().position(|slot| slot.is_none()) {
+            self.streams[index] = Some(stream);
+            return Some(index as u32);
+        }
+        if self.streams.len() == CELL_MS_MAX_STREAMS {
+            return None;
+        }
+        self.streams.push(Some(stream));
+        Some(self.streams.len() as u32 - 1)
+    }
+
+    pub fn stream_close(&mut self, stream: u32) -> bool {
+        match self.streams.get_mut(stream as usize) {
+            Some(slot @ Some(_)) => {
+                *slot = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn stream(&self, stream: u32) -> Option<&MsStream> {
+        self.streams.get(stream as usize)?.as_ref()
+    }
+
+    fn stream_mut(&mut self, stream: u32) -> Option<&mut MsStream> {
+        self.streams.get_mut(stream as usize)?.as_mut()
+    }
+
+    /// Set the format of a stream and its first buffer, stopping it
+    pub fn stream_set_info(&mut self, stream: u32, info: MsStreamInfo, first_buffer: &[u8]) -> bool {
+        let Some(ms) = self.stream_mut(stream) else {
+            return false;
+        };
+        let mut voice = Voice::new(info.format.decode(first_buffer), info.channels as usize, info.pitch as f32);
+        voice.loop_start = info.looping.then_some(0);
+        voice.level = ms.level;
+        voice.pan = ms.pan;
+        voice.playing = false;
+        ms.info = Some(info);
+        ms.voice = Some(voice);
+        true
+    }
+
+    /// Queue the next buffer of a stream
+    pub fn stream_set_second_read(&mut self, stream: u32, buffer: &[u8]) -> bool {
+        let Some(ms) = self.stream_mut(stream) else {
+            return false;
+        };
+        match (ms.info, &mut ms.voice) {
+            (Some(info), Some(voice)) => {
+                voice.queue(info.format.decode(buffer));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn stream_play(&mut self, stream: u32) -> bool {
+        match self.stream_mut(stream).and_then(|ms| ms.voice.as_mut()) {
+            Some(voice) => {
+                voice.seek(0);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set the volume and balance of a stream
+    pub fn stream_set_volume(&mut self, stream: u32, level: f32, pan: f32) -> bool {
+        let Some(ms) = self.stream_mut(stream) else {
+            return false;
+        };
+        ms.level = level;
+        ms.pan = pan.clamp(-1.0, 1.0);
+        if let Some(voice) = &mut ms.voice {
+            voice.level = ms.level;
+            voice.pan = ms.pan;
+        }
+        true
+    }
+
+    /// Whether any voice is playing
+    pub fn is_active(&self) -> bool {
+        self.players.values().any(|player| player.state() == CELL_SSPLAYER_STATE_ON)
+            || self.streams.iter().flatten().any(|ms| ms.status() == CELL_MS_STREAM_ON)
+    }
+
+    /// Render `frames` stereo frames at `out_rate` of every playing voice
+    ///
+    /// Returns nothing while no voice plays.
+    pub fn render(&mut self, frames: usize, out_rate: u32) -> Vec<f32> {
+        if !self.is_active() {
+            return Vec::new();
+        }
+        let mut out = vec![0.0; frames * 2];
+        for player in self.players.values_mut().filter(|player| !player.stopped) {
+            if let Some(voice) = &mut player.voice {
+                voice.mix_into(&mut out, out_rate);
+            }
+        }
+        for voice in self.streams.iter_mut().flatten().filter_map(|ms| ms.voice.as_mut()) {
+            voice.mix_into(&mut out, out_rate);
+        }
+        out
+    }
+}
+
+impl Default for SsPlayerManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn status(result: Result<(), i32>) -> i32 {
+    match result {
+        Ok(()) => 0, // CELL_OK
+        Err(e) => e,
+    }
+}
+
+/// cellSSPlayerCreate - Create a sampler voice
+///
+/// # Arguments
+/// * `handle_addr` - Address to write the handle to
+/// * `config_addr` - Address of the CellSSPlayerConfig
+///
+/// # Returns
+/// * 0 on success, or an error code
+pub fn cell_ss_player_create(handle_addr: u32, config_addr: u32) -> i32 {
+    debug!("cellSSPlayerCreate(handle=0x{:08X}, config=0x{:08X})", handle_addr, config_addr);
+
+    if handle_addr == 0 || config_addr == 0 {
+        return CELL_LIBMIXER_ERROR_INVALID_PARAMATER;
+    }
+
+    // Note: reading the channel count from the config requires memory subsystem integration
+    match crate::context::get_hle_context_mut().ss_player.create(1) {
+        Ok(handle) => {
+            trace!("cellSSPlayerCreate: handle={}", handle);
+            // Note: writing the handle requires memory subsystem integration
+            0 // CELL_OK
+        }
+        Err(e) => e,
+    }
+}
+
+/// cellSSPlayerRemove - Remove a sampler voice
+///
+/// # Arguments
+/// * `handle` - Player handle
+///
+/// # Returns
+/// * 0 on success, or an error code
+pub fn cell_ss_player_remove(handle: u32) -> i32 {
+    debug!("cellSSPlayerRemove(handle={})", handle);
+
+    status(crate::context::get_hle_context_mut().ss_player.remove(handle))
+}
+
+/// cellSSPlayerSetWave - Set the wave of a sampler voice
+///
+/// # Arguments
+/// * `handle` - Player handle
+/// * `wave_addr` - Address of the CellSSPlayerWaveParam
+/// * `common_addr` - Address of the CellSSPlayerCommonParam
+///
+/// # Returns
+/// * 0 on success, or an error code
+pub fn cell_ss_player_set_wave(handle: u32, wave_addr: u32, common_addr: u32) -> i32 {
+    debug!("cellSSPlayerSetWave(handle={}, wave=0x{:08X}, common=0x{:08X})", handle, wave_addr, common_addr);
+
+    if wave_addr == 0 {
+        return CELL_LIBMIXER_ERROR_INVALID_PARAMATER;
+    }
+
+    // Note: reading the wave parameters and samples for SsPlayerManager::set_wave requires memory subsystem integration
+    status(crate::context::get_hle_context().ss_player.player(handle).map(|_| ()))
+}
+
+/// cellSSPlayerPlay - Start a sampler voice
+///
+/// # Arguments
+/// * `handle` - Player handle
+/// * `info_addr` - Address of the CellSSPlayerRuntimeInfo
+///
+/// # Returns
+/// * 0 on success, or an error code
+pub fn cell_ss_player_play(handle: u32, info_addr: u32) -> i32 {
+    debug!("cellSSPlayerPlay(handle={}, info=0x{:08X})", handle, info_addr);
+
+    // Note: reading the level, speed and position requires memory subsystem integration
+    status(crate::context::get_hle_context_mut().ss_player.play(handle, 1.0, 1.0, 0))
+}
+
+/// cellSSPlayerStop - Stop a sampler voice
+///
+/// # Arguments
+/// * `handle` - Player handle
+/// * `mode` - Stop mode
+///
+/// # Returns
+/// * 0 on success, or an error code
+pub fn cell_ss_player_stop(handle: u32, mode: u32) -> i32 {
+    debug!("cellSSPlayerStop(handle={}, mode={})", handle, mode);
+
+    status(crate::context::get_hle_context_mut().ss_player.stop(handle))
+}
+
+/// cellSSPlayerSetParam - Change the level and speed of a sampler voice
+///
+/// # Arguments
+/// * `handle` - Player handle
+/// * `info_addr` - Address of the CellSSPlayerRuntimeInfo
+///
+/// # Returns
+/// * 0 on success, or an error code
+pub fn cell_ss_player_set_param(handle: u32, info_addr: u32) -> i32 {
+    trace!("cellSSPlayerSetParam(handle={}, info=0x{:08X})", handle, info_addr);
+
+    // Note: reading the level and speed for SsPlayerManager::set_param requires memory subsystem integration
+    status(crate::context::get_hle_context().ss_player.player(handle).map(|_| ()))
+}
+
+/// cellSSPlayerGetState - Get the state of a sampler voice
+///
+/// # Arguments
+/// * `handle` - Player handle
+///
+/// # Returns
+/// * The player state
+pub fn cell_ss_player_get_state(handle: u32) -> i32 {
+    trace!("cellSSPlayerGetState(handle={})", handle);
+
+    let ctx = crate::context::get_hle_context();
+    ctx.ss_player.player(handle).map_or(CELL_SSPLAYER_STATE_ERROR, |player| player.state()) as i32
+}
+
+/// cellMSStreamOpen - Open a MultiStream stream
+///
+/// # Returns
+/// * The stream number, or -1 when every stream is open
+pub fn cell_ms_stream_open() -> i32 {
+    debug!("cellMSStreamOpen()");
+
+    crate::context::get_hle_context_mut()
+        .ss_player
+        .stream_open()
+        .map_or(-1, |stream| stream as i32)
+}
+
+/// cellMSStreamClose - Close a MultiStream stream
+///
+/// # Arguments
+/// * `stream` - Stream number
+///
+/// # Returns
+/// * The stream number, or -1 if it was not open
+pub fn cell_ms_stream_close(stream: u32) -> i32 {
+    debug!("cellMSStreamClose(stream={})", stream);
+
+    if crate::context::get_hle_context_mut().ss_player.stream_close(stream) {
+        stream as i32
+    } else {
+        -1
+    }
+}
+
+/// cellMSStreamSetInfo - Set the format and first buffer of a stream
+///
+/// # Arguments
+/// * `stream` - Stream number
+/// * `info_addr` - Address of the CellMSInfo
+///
+/// # Returns
+/// * 0 on success, or -1
+pub fn cell_ms_stream_set_info(stream: u32, info_addr: u32) -> i32 {
+    debug!("cellMSStreamSetInfo(stream={}, info=0x{:08X})", stream, info_addr);
+
+    if info_addr == 0 || crate::context::get_hle_context().ss_player.stream(stream).is_none() {
+        return -1;
+    }
+
+    // Note: reading the CellMSInfo and its first buffer for SsPlayerManager::stream_set_info requires memory subsystem integration
+    0
+}
+
+/// cellMSStreamSetSecondRead - Queue the next buffer of a stream
+///
+/// # Arguments
+/// * `stream` - Stream number
+/// * `info_addr` - Address of the CellMSInfo holding the buffer
+///
+/// # Returns
+/// * 0 on success, or -1
+pub fn cell_ms_stream_set_second_read(stream: u32, info_addr: u32) -> i32 {
+    trace!("cellMSStreamSetSecondRead(stream={}, info=0x{:08X})", stream, info_addr);
+
+    if info_addr == 0 || crate::context::get_hle_context().ss_player.stream(stream).is_none() {
+        return -1;
+    }
+
+    // Note: reading the buffer for SsPlayerManager::stream_set_second_read requires memory subsystem integration
+    0
+}
+
+/// cellMSStreamPlay - Start a stream
+///
+/// # Arguments
+/// * `stream` - Stream number
+///
+/// # Returns
+/// * 0 on success, or -1 if the stream has no info set
+pub fn cell_ms_stream_play(stream: u32) -> i32 {
+    debug!("cellMSStreamPlay(stream={})", stream);
+
+    if crate::context::get_hle_context_mut().ss_player.stream_play(stream) {
+        0
+    } else {
+        -1
+    }
+}
+
+/// cellMSStreamGetStatus - Get the state of a stream
+///
+/// # Arguments
+/// * `stream` - Stream number
+///
+/// # Returns
+/// * The stream state, or -1 if it is not open
+pub fn cell_ms_stream_get_status(stream: u32) -> i32 {
+    trace!("cellMSStreamGetStatus(stream={})", stream);
+
+    let ctx = crate::context::get_hle_context();
+    ctx.ss_player.stream(stream).map_or(-1, |ms| ms.status() as i32)
+}
+
+/// cellMSCoreSetVolume1 - Set the volume of a stream on one bus
+///
+/// # Arguments
+/// * `stream` - Stream number
+/// * `dry_wet` - Dry or wet send
+/// * `bus` - Output bus
+/// * `volumes_addr` - Address of the per-speaker volumes
+///
+/// # Returns
+/// * 0 on success, or -1
+pub fn cell_ms_core_set_volume1(stream: u32, dry_wet: u32, bus: u32, volumes_addr: u32) -> i32 {
+    trace!(
+        "cellMSCoreSetVolume1(stream={}, dry_wet={}, bus={}, volumes=0x{:08X})",
+        stream, dry_wet, bus, volumes_addr
+    );
+
+    if crate::context::get_hle_context().ss_player.stream(stream).is_none() {
+        return -1;
+    }
+
+    // Note: reading the volumes for SsPlayerManager::stream_set_volume requires memory subsystem integration
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ss_player_voice() {
+        let mut manager = SsPlayerManager::new();
+        assert_eq!(manager.create(3), Err(CELL_LIBMIXER_ERROR_INVALID_PARAMATER));
+        let handle = manager.create(1).unwrap();
+        assert_eq!(manager.player(handle).unwrap().state(), CELL_SSPLAYER_STATE_NOTREADY);
+        assert_eq!(manager.play(handle, 1.0, 1.0, 0), Err(CELL_LIBMIXER_ERROR_NOT_FOUND));
+
+        let wave = SsPlayerWave {
+            samples: vec![0.5; 4],
+            loop_mode: CELL_SSPLAYER_ONESHOT,
+            loop_start_offset: 0,
+            start_offset: 0,
+        };
+        manager.set_wave(handle, wave).unwrap();
+        assert_eq!(manager.player(handle).unwrap().state(), CELL_SSPLAYER_STATE_OFF);
+        assert!(manager.render(4, MIXER_SAMPLE_RATE).is_empty());
+
+        // A one-shot wave of four frames at half level, then silence
+        manager.play(handle, 0.5, 1.0, 0).unwrap();
+        assert_eq!(manager.player(handle).unwrap().state(), CELL_SSPLAYER_STATE_ON);
+        let out = manager.render(6, MIXER_SAMPLE_RATE);
+        assert_eq!(out, [0.25, 0.25, 0.25, 0.25, 0.25, 0.25, 0.25, 0.25, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(manager.player(handle).unwrap().state(), CELL_SSPLAYER_STATE_OFF);
+        assert!(!manager.is_active());
+    }
+
+    #[test]
+    fn test_ms_stream_buffers() {
+        let mut manager = SsPlayerManager::new();
+        let stream = manager.stream_open().unwrap();
+        assert!(!manager.stream_play(stream));
+
+        let info = MsStreamInfo {
+            sub_bus_group: 0,
+            pitch: 24000,
+            channels: 2,
+            format: SampleFormat::Pcm16Be,
+            looping: false,
+        };
+        let first: Vec<u8> = [0x4000i16, -0x4000].iter().flat_map(|s| s.to_be_bytes()).collect();
+        assert!(manager.stream_set_info(stream, info, &first));
+        assert_eq!(manager.stream(stream).unwrap().status(), CELL_MS_STREAM_OFF);
+        assert!(manager.stream_play(stream));
+        assert!(manager.stream(stream).unwrap().needs_second_read());
+        assert!(manager.stream_set_second_read(stream, &first));
+        assert!(!manager.stream(stream).unwrap().needs_second_read());
+
+        // Half the output rate: each frame of both buffers plays twice
+        let out = manager.render(5, MIXER_SAMPLE_RATE);
+        assert_eq!(&out[..8], &[0.5, -0.5, 0.5, -0.5, 0.5, -0.5, 0.5, -0.5]);
+        assert_eq!(&out[8..], &[0.0, 0.0]);
+        assert_eq!(manager.stream(stream).unwrap().status(), CELL_MS_STREAM_OFF);
+
+        assert!(manager.stream_close(stream));
+        assert_eq!(manager.stream_open(), Some(stream));
+    }
+}
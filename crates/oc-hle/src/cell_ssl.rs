@@ -639,6 +639,7 @@ mod tests {
     #[test]
     fn test_ssl_lifecycle() {
         // Reset HLE context first to ensure clean state
+        let _guard = crate::context::test_guard();
         crate::context::reset_hle_context();
         assert_eq!(cell_ssl_init(0x10000), 0);
         assert_eq!(cell_ssl_end(), 0);
@@ -647,6 +648,7 @@ mod tests {
     #[test]
     fn test_ssl_cert_loader() {
         // Reset and initialize SSL first
+        let _guard = crate::context::test_guard();
         crate::context::reset_hle_context();
         assert_eq!(cell_ssl_init(0x10000), 0);
         
@@ -0,0 +1,355 @@
+//! cellSubDisplay HLE - PSP/PS Vita Remote Play Sub Display
+//!
+//! This module provides HLE implementations for showing a second view of
+//! the game on a Remote Play peer. No peer is ever registered, so the
+//! library initializes the way it does on a console without a paired
+//! handheld and games carry on with the TV as their only display.
+
+use crate::context::guest_memory;
+use tracing::{debug, trace};
+
+/// Error codes
+pub const CELL_SUBDISPLAY_ERROR_OUT_OF_MEMORY: i32 = 0x80029851u32 as i32;
+pub const CELL_SUBDISPLAY_ERROR_INVALID_VALUE: i32 = 0x80029852u32 as i32;
+pub const CELL_SUBDISPLAY_ERROR_NOT_INITIALIZED: i32 = 0x80029853u32 as i32;
+pub const CELL_SUBDISPLAY_ERROR_NOT_SUPPORTED: i32 = 0x80029854u32 as i32;
+pub const CELL_SUBDISPLAY_ERROR_SET_SAMPLE: i32 = 0x80029860u32 as i32;
+pub const CELL_SUBDISPLAY_ERROR_AUDIOOUT_IS_BUSY: i32 = 0x80029861u32 as i32;
+pub const CELL_SUBDISPLAY_ERROR_ZERO_REGISTERED: i32 = 0x80029813u32 as i32;
+
+/// Library versions
+pub const CELL_SUBDISPLAY_VERSION_0001: u32 = 1;
+pub const CELL_SUBDISPLAY_VERSION_0002: u32 = 2;
+pub const CELL_SUBDISPLAY_VERSION_0003: u32 = 3;
+
+/// Memory container sizes needed per version
+pub const CELL_SUBDISPLAY_0001_MEMORY_CONTAINER_SIZE: u32 = 8 * 1024 * 1024;
+pub const CELL_SUBDISPLAY_0002_MEMORY_CONTAINER_SIZE: u32 = 10 * 1024 * 1024;
+pub const CELL_SUBDISPLAY_0003_MEMORY_CONTAINER_SIZE: u32 = 10 * 1024 * 1024;
+
+/// Status passed to the game's callback
+pub const CELL_SUBDISPLAY_STATUS_JOIN: u32 = 1;
+pub const CELL_SUBDISPLAY_STATUS_LEAVE: u32 = 2;
+pub const CELL_SUBDISPLAY_STATUS_FATALERROR: u32 = 3;
+
+/// Sub display manager
+pub struct SubDisplayManager {
+    /// Initialization flag
+    initialized: bool,
+    /// Whether the game started sending its view
+    started: bool,
+    /// Status callback address
+    callback: u32,
+    /// Callback user data
+    userdata: u32,
+}
+
+impl SubDisplayManager {
+    /// Create a new sub display manager
+    pub fn new() -> Self {
+        Self {
+            initialized: false,
+            started: false,
+            callback: 0,
+            userdata: 0,
+        }
+    }
+
+    /// Memory container size `version` needs
+    pub fn required_memory(version: u32) -> Result<u32, i32> {
+        match version {
+            CELL_SUBDISPLAY_VERSION_0001 => Ok(CELL_SUBDISPLAY_0001_MEMORY_CONTAINER_SIZE),
+            CELL_SUBDISPLAY_VERSION_0002 => Ok(CELL_SUBDISPLAY_0002_MEMORY_CONTAINER_SIZE),
+            CELL_SUBDISPLAY_VERSION_0003 => Ok(CELL_SUBDISPLAY_0003_MEMORY_CONTAINER_SIZE),
+            _ => Err(CELL_SUBDISPLAY_ERROR_INVALID_VALUE),
+        }
+    }
+
+    /// Initialize the library
+    ///
+    /// Succeeds but reports that no Remote Play peer is registered, which
+    /// games treat as the sub display being unavailable.
+    pub fn init(&mut self, callback: u32, userdata: u32) -> i32 {
+        if callback == 0 {
+            return CELL_SUBDISPLAY_ERROR_INVALID_VALUE;
+        }
+
+        debug!("SubDisplayManager::init: callback=0x{:08X}", callback);
+
+        self.initialized = true;
+        self.started = false;
+        self.callback = callback;
+        self.userdata = userdata;
+
+        CELL_SUBDISPLAY_ERROR_ZERO_REGISTERED
+    }
+
+    /// Shut the library down
+    pub fn end(&mut self) -> i32 {
+        if !self.initialized {
+            return CELL_SUBDISPLAY_ERROR_NOT_INITIALIZED;
+        }
+
+        debug!("SubDisplayManager::end");
+
+        *self = Self::new();
+        0 // CELL_OK
+    }
+
+    /// Start sending the game's view to peers
+    pub fn start(&mut self) -> i32 {
+        if !self.initialized {
+            return CELL_SUBDISPLAY_ERROR_NOT_INITIALIZED;
+        }
+        self.started = true;
+        0 // CELL_OK
+    }
+
+    /// Stop sending the game's view
+    pub fn stop(&mut self) -> i32 {
+        if !self.initialized {
+            return CELL_SUBDISPLAY_ERROR_NOT_INITIALIZED;
+        }
+        self.started = false;
+        0 // CELL_OK
+    }
+
+    /// Number of connected peers, always 0
+    pub fn peer_count(&self) -> Result<u32, i32> {
+        if !self.initialized {
+            return Err(CELL_SUBDISPLAY_ERROR_NOT_INITIALIZED);
+        }
+        Ok(0)
+    }
+
+    /// Check if initialized
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    /// Check if the game started sending its view
+    pub fn is_started(&self) -> bool {
+        self.started
+    }
+}
+
+impl Default for SubDisplayManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// cellSubDisplayGetRequiredMemory - Get the memory container size needed
+///
+/// # Arguments
+/// * `param_addr` - Address of CellSubDisplayParam
+///
+/// # Returns
+/// * Container size in bytes on success
+pub fn cell_sub_display_get_required_memory(param_addr: u32) -> i32 {
+    trace!("cellSubDisplayGetRequiredMemory()");
+
+    // The version is the first member of the parameters
+    let Some(version) = guest_memory().and_then(|memory| memory.read_be32(param_addr).ok()) else {
+        return CELL_SUBDISPLAY_ERROR_INVALID_VALUE;
+    };
+    match SubDisplayManager::required_memory(version) {
+        Ok(size) => size as i32,
+        Err(e) => e,
+    }
+}
+
+/// cellSubDisplayInit - Initialize the sub display library
+///
+/// # Arguments
+/// * `param_addr` - Address of CellSubDisplayParam
+/// * `func` - Status callback address
+/// * `userdata` - User data passed to the callback
+/// * `container` - Memory container for the library
+///
+/// # Returns
+/// * CELL_SUBDISPLAY_ERROR_ZERO_REGISTERED, as no peer is registered
+pub fn cell_sub_display_init(_param_addr: u32, func: u32, userdata: u32, container: u32) -> i32 {
+    debug!(
+        "cellSubDisplayInit(func=0x{:08X}, userdata=0x{:08X}, container={})",
+        func, userdata, container
+    );
+
+    crate::context::get_hle_context_mut().subdisplay.init(func, userdata)
+}
+
+/// cellSubDisplayEnd - Shut the sub display library down
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_sub_display_end() -> i32 {
+    debug!("cellSubDisplayEnd()");
+
+    crate::context::get_hle_context_mut().subdisplay.end()
+}
+
+/// cellSubDisplayStart - Start sending the game's view
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_sub_display_start() -> i32 {
+    debug!("cellSubDisplayStart()");
+
+    crate::context::get_hle_context_mut().subdisplay.start()
+}
+
+/// cellSubDisplayStop - Stop sending the game's view
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_sub_display_stop() -> i32 {
+    debug!("cellSubDisplayStop()");
+
+    crate::context::get_hle_context_mut().subdisplay.stop()
+}
+
+/// cellSubDisplayGetVideoBuffer - Get the buffer the game draws its second view to
+///
+/// # Arguments
+/// * `group_id` - Peer group
+/// * `video_buf_addr` - Address to write the buffer address
+/// * `size_addr` - Address to write the buffer size
+///
+/// # Returns
+/// * CELL_SUBDISPLAY_ERROR_NOT_SUPPORTED, as no peer receives video
+pub fn cell_sub_display_get_video_buffer(group_id: i32, _video_buf_addr: u32, _size_addr: u32) -> i32 {
+    trace!("cellSubDisplayGetVideoBuffer(group_id={})", group_id);
+
+    if !crate::context::get_hle_context().subdisplay.is_initialized() {
+        return CELL_SUBDISPLAY_ERROR_NOT_INITIALIZED;
+    }
+    CELL_SUBDISPLAY_ERROR_NOT_SUPPORTED
+}
+
+/// cellSubDisplayAudioOutBlocking - Send audio samples to peers
+///
+/// # Arguments
+/// * `group_id` - Peer group
+/// * `buf_addr` - Sample buffer address
+/// * `samples` - Number of samples
+///
+/// # Returns
+/// * Number of samples consumed on success
+pub fn cell_sub_display_audio_out_blocking(group_id: i32, _buf_addr: u32, samples: u32) -> i32 {
+    trace!("cellSubDisplayAudioOutBlocking(group_id={}, samples={})", group_id, samples);
+
+    if !crate::context::get_hle_context().subdisplay.is_initialized() {
+        return CELL_SUBDISPLAY_ERROR_NOT_INITIALIZED;
+    }
+    // Nobody listens, so every sample is consumed at once
+    samples as i32
+}
+
+/// cellSubDisplayAudioOutNonBlocking - Send audio samples to peers without waiting
+///
+/// # Arguments
+/// * `group_id` - Peer group
+/// * `buf_addr` - Sample buffer address
+/// * `samples` - Number of samples
+///
+/// # Returns
+/// * Number of samples consumed on success
+pub fn cell_sub_display_audio_out_non_blocking(group_id: i32, buf_addr: u32, samples: u32) -> i32 {
+    cell_sub_display_audio_out_blocking(group_id, buf_addr, samples)
+}
+
+/// cellSubDisplayGetPeerNum - Get the number of connected peers
+///
+/// # Arguments
+/// * `group_id` - Peer group
+///
+/// # Returns
+/// * Number of peers (always 0) on success
+pub fn cell_sub_display_get_peer_num(group_id: i32) -> i32 {
+    trace!("cellSubDisplayGetPeerNum(group_id={})", group_id);
+
+    match crate::context::get_hle_context().subdisplay.peer_count() {
+        Ok(count) => count as i32,
+        Err(e) => e,
+    }
+}
+
+/// cellSubDisplayGetPeerList - Get the connected peers
+///
+/// # Arguments
+/// * `group_id` - Peer group
+/// * `info_addr` - Address of the CellSubDisplayPeerInfo array
+/// * `max_peer_num_addr` - Address of the array size, updated to the peer count
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_sub_display_get_peer_list(group_id: i32, _info_addr: u32, max_peer_num_addr: u32) -> i32 {
+    trace!("cellSubDisplayGetPeerList(group_id={})", group_id);
+
+    let count = match crate::context::get_hle_context().subdisplay.peer_count() {
+        Ok(count) => count,
+        Err(e) => return e,
+    };
+    if max_peer_num_addr == 0 {
+        return CELL_SUBDISPLAY_ERROR_INVALID_VALUE;
+    }
+    // With no peers the info array is left untouched and only the count is written
+    match guest_memory().map(|memory| memory.write_be32(max_peer_num_addr, count)) {
+        Some(Ok(())) => 0, // CELL_OK
+        _ => CELL_SUBDISPLAY_ERROR_INVALID_VALUE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subdisplay_lifecycle() {
+        let mut manager = SubDisplayManager::new();
+
+        assert_eq!(manager.start(), CELL_SUBDISPLAY_ERROR_NOT_INITIALIZED);
+        assert_eq!(manager.init(0, 0), CELL_SUBDISPLAY_ERROR_INVALID_VALUE);
+
+        // No peer is registered, but the library is usable
+        assert_eq!(manager.init(0x10000, 0x20000), CELL_SUBDISPLAY_ERROR_ZERO_REGISTERED);
+        assert!(manager.is_initialized());
+        assert_eq!(manager.peer_count(), Ok(0));
+        assert_eq!(manager.start(), 0);
+        assert!(manager.is_started());
+        assert_eq!(manager.stop(), 0);
+
+        assert_eq!(manager.end(), 0);
+        assert_eq!(manager.end(), CELL_SUBDISPLAY_ERROR_NOT_INITIALIZED);
+        assert_eq!(manager.peer_count(), Err(CELL_SUBDISPLAY_ERROR_NOT_INITIALIZED));
+    }
+
+    #[test]
+    fn test_subdisplay_required_memory() {
+        assert_eq!(
+            SubDisplayManager::required_memory(CELL_SUBDISPLAY_VERSION_0001),
+            Ok(CELL_SUBDISPLAY_0001_MEMORY_CONTAINER_SIZE)
+        );
+        assert_eq!(SubDisplayManager::required_memory(9), Err(CELL_SUBDISPLAY_ERROR_INVALID_VALUE));
+    }
+
+    #[test]
+    fn test_subdisplay_guest_outputs() {
+        let _guard = crate::context::test_guard();
+        crate::context::reset_hle_context();
+        let memory = crate::context::test_guest_memory();
+        let addr = memory.allocate(0x100, 0x10, oc_memory::PageFlags::RW).unwrap();
+
+        memory.write_be32(addr, CELL_SUBDISPLAY_VERSION_0001).unwrap();
+        assert_eq!(
+            cell_sub_display_get_required_memory(addr),
+            CELL_SUBDISPLAY_0001_MEMORY_CONTAINER_SIZE as i32
+        );
+
+        assert_eq!(cell_sub_display_init(addr, 0x10000, 0, 0), CELL_SUBDISPLAY_ERROR_ZERO_REGISTERED);
+        memory.write_be32(addr + 0x80, 4).unwrap();
+        assert_eq!(cell_sub_display_get_peer_list(0, addr + 0x10, addr + 0x80), 0);
+        assert_eq!(memory.read_be32(addr + 0x80).unwrap(), 0);
+        assert_eq!(cell_sub_display_get_peer_list(0, addr + 0x10, 0), CELL_SUBDISPLAY_ERROR_INVALID_VALUE);
+    }
+}
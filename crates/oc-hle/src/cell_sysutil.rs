@@ -3,7 +3,10 @@
 //! This module provides system utility functions including callback management,
 //! system events, and game exit handling.
 
+use crate::guest_call::GuestCallQueue;
+use oc_core::savestate::{StateReader, StateWriter};
 use std::collections::{HashMap, VecDeque};
+use std::io;
 use tracing::{debug, trace};
 
 /// Maximum number of callback slots
@@ -15,11 +18,17 @@ pub const CELL_SYSUTIL_ERROR_VALUE: i32 = 0x80010002u32 as i32;
 /// Error: Dialog already open
 pub const CELL_SYSUTIL_ERROR_DIALOG_ALREADY_OPEN: i32 = 0x80010003u32 as i32;
 
+/// Event asking the game to exit
+pub const CELL_SYSUTIL_REQUEST_EXITGAME: u64 = 0x0101;
+
+/// Events sent while a system dialog covers the game
+pub const CELL_SYSUTIL_DRAWING_BEGIN: u64 = 0x0121;
+pub const CELL_SYSUTIL_DRAWING_END: u64 = 0x0122;
+
 /// System callback function type
 pub type SysutilCallback = fn(status: u64, param: u64, userdata: u64);
 
 /// System callback entry
-#[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
 struct CallbackEntry {
     func: u32,      // Address of callback function
@@ -206,6 +215,11 @@ pub struct SysutilManager {
     account: AccountInfo,
     /// Disc information
     disc: DiscInfo,
+    /// cellDiscGame eject and insert callbacks (OPD addresses), 0 when unset
+    disc_eject_func: u32,
+    disc_insert_func: u32,
+    /// Guest address of the title ID insert callbacks get, 0 until the first
+    disc_title_id_addr: u32,
 }
 
 impl SysutilManager {
@@ -223,6 +237,9 @@ impl SysutilManager {
             },
             account: AccountInfo::default(),
             disc: DiscInfo::default(),
+            disc_eject_func: 0,
+            disc_insert_func: 0,
+            disc_title_id_addr: 0,
         };
         
         // Initialize default system parameters
@@ -289,16 +306,18 @@ impl SysutilManager {
     }
 
     /// Check callbacks (should be called periodically by game)
-    pub fn check_callback(&mut self) -> i32 {
+    ///
+    /// Every pending event becomes a call of each registered callback with
+    /// the event type, its parameter and the slot's user data, queued on
+    /// `calls` for the emulator to run.
+    pub fn check_callback(&mut self, calls: &mut GuestCallQueue) -> i32 {
         trace!("SysutilManager::check_callback()");
 
-        // Process pending events
         while let Some(event) = self.pending_events.pop_front() {
-            // TODO: Call registered callbacks with event
             trace!("Processing event: type=0x{:X}, param=0x{:X}", event.event_type, event.param);
-            
-            // For now, just process the event without calling actual callbacks
-            // Real implementation would need to call PPU callback functions
+            for entry in self.callbacks.iter().flatten() {
+                calls.push(entry.func, [event.event_type, event.param, entry.userdata as u64, 0]);
+            }
         }
 
         0 // CELL_OK
@@ -390,9 +409,25 @@ impl SysutilManager {
         0 // CELL_OK
     }
 
+    /// Open the game data dialog, which shows install progress
+    pub fn open_game_data_dialog(&mut self, message: &str) -> i32 {
+        if self.dialog.dialog_type.is_some() {
+            return CELL_SYSUTIL_ERROR_DIALOG_ALREADY_OPEN;
+        }
+
+        debug!("SysutilManager::open_game_data_dialog: {}", message);
+
+        self.dialog.dialog_type = Some(DialogType::GameData);
+        self.dialog.status = DialogStatus::Open;
+        self.dialog.message = message.to_string();
+        self.dialog.progress = 0;
+
+        0 // CELL_OK
+    }
+
     /// Update progress dialog
     pub fn update_progress(&mut self, progress: u32) -> i32 {
-        if self.dialog.dialog_type != Some(DialogType::Progress) {
+        if !matches!(self.dialog.dialog_type, Some(DialogType::Progress | DialogType::GameData)) {
             return CELL_SYSUTIL_ERROR_VALUE; // No progress dialog open
         }
 
@@ -418,6 +453,11 @@ impl SysutilManager {
         0 // CELL_OK
     }
 
+    /// Get the type of the open dialog
+    pub fn dialog_type(&self) -> Option<DialogType> {
+        self.dialog.dialog_type
+    }
+
     /// Get current dialog status
     pub fn get_dialog_status(&self) -> DialogStatus {
         self.dialog.status
@@ -540,6 +580,44 @@ impl SysutilManager {
     pub fn get_disc_type(&self) -> u32 {
         self.disc.disc_type
     }
+    /// Save registered callbacks and undelivered events for a savestate
+    pub fn save_state(&self, w: &mut StateWriter) {
+        for callback in &self.callbacks {
+            w.option(*callback, |w, callback| {
+                w.u32(callback.func);
+                w.u32(callback.userdata);
+            });
+        }
+        w.count(self.pending_events.len());
+        for event in &self.pending_events {
+            w.u64(event.event_type);
+            w.u64(event.param);
+        }
+        w.u32(self.disc_eject_func);
+        w.u32(self.disc_insert_func);
+    }
+
+    /// Restore state written by [`SysutilManager::save_state`]
+    pub fn load_state(&mut self, r: &mut StateReader) -> io::Result<()> {
+        for callback in &mut self.callbacks {
+            *callback = r.option(|r| {
+                Ok(CallbackEntry {
+                    func: r.u32()?,
+                    userdata: r.u32()?,
+                })
+            })?;
+        }
+        self.pending_events.clear();
+        for _ in 0..r.count()? {
+            self.pending_events.push_back(SystemEvent {
+                event_type: r.u64()?,
+                param: r.u64()?,
+            });
+        }
+        self.disc_eject_func = r.u32()?;
+        self.disc_insert_func = r.u32()?;
+        Ok(())
+    }
 }
 
 impl Default for SysutilManager {
@@ -606,7 +684,8 @@ pub fn cell_sysutil_unregister_callback(slot: u32) -> i32 {
 pub fn cell_sysutil_check_callback() -> i32 {
     trace!("cellSysutilCheckCallback()");
 
-    crate::context::get_hle_context_mut().sysutil.check_callback()
+    let ctx = &mut *crate::context::get_hle_context_mut();
+    ctx.sysutil.check_callback(&mut ctx.guest_calls)
 }
 
 /// cellSysutilGetSystemParamInt - Get system parameter (integer)
@@ -801,18 +880,89 @@ pub fn cell_disc_game_get_boot_disc_info(_info_addr: u32) -> i32 {
     0 // CELL_OK
 }
 
-/// cellDiscGameRegisterDiscChangeCallback - Register disc change callback
+/// Put a PS3 disc in the drive, telling the game it was inserted and is ready
+///
+/// Called by the frontend when the user swaps discs. The game's insert
+/// callback gets the disc type and the disc's title ID.
+pub fn insert_disc(game_id: &str, label: &str) {
+    let ctx = &mut *crate::context::get_hle_context_mut();
+    let sysutil = &mut ctx.sysutil;
+    sysutil.set_disc_info(DiscInfo {
+        status: DiscStatus::NoDisc,
+        disc_type: 1,
+        game_id: game_id.to_string(),
+        label: label.to_string(),
+    });
+    sysutil.set_disc_status(DiscStatus::Inserted);
+    sysutil.set_disc_status(DiscStatus::Ready);
+
+    if sysutil.disc_insert_func != 0 {
+        let title_id_addr = write_disc_title_id(sysutil, game_id);
+        let disc_type = sysutil.disc.disc_type as u64;
+        ctx.guest_calls.push(sysutil.disc_insert_func, [disc_type, title_id_addr as u64, 0, 0]);
+    }
+}
+
+/// Take the disc out of the drive, telling the game it was removed
+pub fn eject_disc() {
+    let ctx = &mut *crate::context::get_hle_context_mut();
+    ctx.sysutil.set_disc_status(DiscStatus::NoDisc);
+    ctx.sysutil.set_disc_info(DiscInfo::default());
+    ctx.guest_calls.push(ctx.sysutil.disc_eject_func, [0; 4]);
+}
+
+/// Copy `game_id` to the guest buffer insert callbacks read it from, returning its address
+///
+/// The buffer is reused by every insertion, so 0 is passed when guest memory is unavailable.
+fn write_disc_title_id(sysutil: &mut SysutilManager, game_id: &str) -> u32 {
+    let Some(memory) = crate::context::guest_memory() else {
+        return 0;
+    };
+    if sysutil.disc_title_id_addr == 0 {
+        match memory.allocate(0x10, 0x10, oc_memory::PageFlags::RW) {
+            Ok(addr) => sysutil.disc_title_id_addr = addr,
+            Err(_) => return 0,
+        }
+    }
+    let mut title_id = [0u8; 0x10];
+    let len = game_id.len().min(title_id.len() - 1);
+    title_id[..len].copy_from_slice(&game_id.as_bytes()[..len]);
+    match memory.write_bytes(sysutil.disc_title_id_addr, &title_id) {
+        Ok(()) => sysutil.disc_title_id_addr,
+        Err(_) => 0,
+    }
+}
+
+/// cellDiscGameRegisterDiscChangeCallback - Register disc change callbacks
 ///
 /// # Arguments
-/// * `callback` - Callback function address
-/// * `userdata` - User data
+/// * `func_eject` - Callback run when the disc is taken out
+/// * `func_insert` - Callback run with the disc type and title ID when a disc is put in
 ///
 /// # Returns
 /// * 0 on success
-pub fn cell_disc_game_register_disc_change_callback(_callback: u32, _userdata: u32) -> i32 {
-    debug!("cellDiscGameRegisterDiscChangeCallback()");
-    
-    // For HLE, we just acknowledge the registration
+pub fn cell_disc_game_register_disc_change_callback(func_eject: u32, func_insert: u32) -> i32 {
+    debug!(
+        "cellDiscGameRegisterDiscChangeCallback(funcEject=0x{:08X}, funcInsert=0x{:08X})",
+        func_eject, func_insert
+    );
+
+    let sysutil = &mut crate::context::get_hle_context_mut().sysutil;
+    sysutil.disc_eject_func = func_eject;
+    sysutil.disc_insert_func = func_insert;
+    0 // CELL_OK
+}
+
+/// cellDiscGameUnregisterDiscChangeCallback - Stop calling the disc change callbacks
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_disc_game_unregister_disc_change_callback() -> i32 {
+    debug!("cellDiscGameUnregisterDiscChangeCallback()");
+
+    let sysutil = &mut crate::context::get_hle_context_mut().sysutil;
+    sysutil.disc_eject_func = 0;
+    sysutil.disc_insert_func = 0;
     0 // CELL_OK
 }
 
@@ -838,7 +988,7 @@ mod tests {
     fn test_sysutil_manager() {
         let mut manager = SysutilManager::new();
         assert_eq!(manager.register_callback(0, 0x12345678, 0xABCDEF00), 0);
-        assert_eq!(manager.check_callback(), 0);
+        assert_eq!(manager.check_callback(&mut GuestCallQueue::new()), 0);
     }
 
     #[test]
@@ -863,6 +1013,7 @@ mod tests {
     #[test]
     fn test_sysutil_manager_events() {
         let mut manager = SysutilManager::new();
+        assert_eq!(manager.register_callback(1, 0x12345678, 0xABCDEF00), 0);
         
         // Queue some events
         manager.queue_event(CellSysutilEvent::MenuOpen as u64, 0);
@@ -871,10 +1022,16 @@ mod tests {
         assert_eq!(manager.pending_event_count(), 2);
         
         // Process events
-        assert_eq!(manager.check_callback(), 0);
+        let mut calls = GuestCallQueue::new();
+        assert_eq!(manager.check_callback(&mut calls), 0);
         
-        // Events should be processed
+        // Events should be processed, each calling the registered callback
         assert_eq!(manager.pending_event_count(), 0);
+        let calls = calls.take();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].opd, 0x12345678);
+        assert_eq!(calls[0].args, [CellSysutilEvent::MenuOpen as u64, 0, 0xABCDEF00, 0]);
+        assert_eq!(calls[1].args[..2], [CellSysutilEvent::DrawBegin as u64, 1]);
     }
 
     #[test]
@@ -911,6 +1068,8 @@ mod tests {
 
     #[test]
     fn test_register_callback() {
+        let _guard = crate::context::test_guard();
+        crate::context::reset_hle_context();
         let result = cell_sysutil_register_callback(0, 0x12345678, 0);
         assert_eq!(result, 0);
         
@@ -922,6 +1081,7 @@ mod tests {
     #[test]
     fn test_unregister_callback() {
         // First register a callback so we can unregister it
+        let _guard = crate::context::test_guard();
         crate::context::reset_hle_context();
         cell_sysutil_register_callback(0, 0x12345678, 0);
         
@@ -935,8 +1095,19 @@ mod tests {
 
     #[test]
     fn test_check_callback() {
+        let _guard = crate::context::test_guard();
+        crate::context::reset_hle_context();
+        cell_sysutil_register_callback(2, 0x12345678, 0x100);
+        crate::context::get_hle_context_mut()
+            .sysutil
+            .queue_event(CELL_SYSUTIL_REQUEST_EXITGAME, 0);
+
         let result = cell_sysutil_check_callback();
         assert_eq!(result, 0);
+        let calls = crate::context::get_hle_context_mut().guest_calls.take();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].opd, 0x12345678);
+        assert_eq!(calls[0].args, [CELL_SYSUTIL_REQUEST_EXITGAME, 0, 0x100, 0]);
     }
 
     #[test]
@@ -1150,6 +1321,7 @@ mod tests {
 
     #[test]
     fn test_msg_dialog_api() {
+        let _guard = crate::context::test_guard();
         crate::context::reset_hle_context();
         
         assert_eq!(cell_msg_dialog_open(0, 0, 0, 0), 0);
@@ -1158,6 +1330,7 @@ mod tests {
 
     #[test]
     fn test_psid_api() {
+        let _guard = crate::context::test_guard();
         crate::context::reset_hle_context();
         
         assert_eq!(cell_sysutil_get_ps_id(0x10000000), 0);
@@ -1165,6 +1338,7 @@ mod tests {
 
     #[test]
     fn test_user_info_api() {
+        let _guard = crate::context::test_guard();
         crate::context::reset_hle_context();
         
         assert_eq!(cell_user_info_get_stat(0, 0), 0);
@@ -1173,6 +1347,7 @@ mod tests {
 
     #[test]
     fn test_disc_api() {
+        let _guard = crate::context::test_guard();
         crate::context::reset_hle_context();
         
         // Set up a ready disc first
@@ -1187,6 +1362,48 @@ mod tests {
         assert_eq!(cell_disc_game_register_disc_change_callback(0, 0), 0);
     }
 
+    #[test]
+    fn test_disc_swap() {
+        let _guard = crate::context::test_guard();
+        crate::context::reset_hle_context();
+
+        insert_disc("BLUS30001", "Disc 2");
+        assert_eq!(crate::context::get_hle_context().sysutil.get_disc_game_id(), Some("BLUS30001"));
+
+        eject_disc();
+        assert!(!crate::context::get_hle_context().sysutil.is_disc_inserted());
+    }
+
+    #[test]
+    fn test_disc_swap_calls_guest() {
+        let _guard = crate::context::test_guard();
+        crate::context::reset_hle_context();
+        let memory = crate::context::test_guest_memory();
+        assert_eq!(cell_sysutil_register_callback(0, 0x1_0000, 0x42), 0);
+        assert_eq!(cell_disc_game_register_disc_change_callback(0x2_0000, 0x2_0010), 0);
+
+        eject_disc();
+        insert_disc("BLUS30002", "Disc 2");
+        let calls = crate::context::get_hle_context_mut().guest_calls.take();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].opd, 0x2_0000);
+        assert_eq!(calls[1].opd, 0x2_0010);
+        assert_eq!(calls[1].args[0], 1);
+        let title_id = memory.read_bytes(calls[1].args[1] as u32, 10).unwrap();
+        assert_eq!(title_id, b"BLUS30002\0");
+
+        // The removal, insertion and ready events reach the sysutil callback
+        assert_eq!(cell_sysutil_check_callback(), 0);
+        let calls = crate::context::get_hle_context_mut().guest_calls.take();
+        let events: Vec<u64> = calls.iter().map(|call| call.args[0]).collect();
+        assert_eq!(events, [0x0201, 0x0200, 0x0202]);
+        assert!(calls.iter().all(|call| call.opd == 0x1_0000 && call.args[2] == 0x42));
+
+        assert_eq!(cell_disc_game_unregister_disc_change_callback(), 0);
+        eject_disc();
+        assert!(!crate::context::get_hle_context().guest_calls.has_pending());
+    }
+
     #[test]
     fn test_dialog_status_enum() {
         assert_eq!(DialogStatus::None as u32, 0);
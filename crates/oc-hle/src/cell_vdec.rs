@@ -1,10 +1,19 @@
 //! cellVdec HLE - Video decoder module
 //!
 //! This module provides HLE implementations for the PS3's video decoder library.
+//!
+//! Decoded frames are kept in a small pool of shared buffers. A picture the
+//! game retrieves into a buffer is handed on as a [`VdecFrame`] sharing the
+//! decoder's pixels, so the renderer can cache it as a texture without the
+//! frame being copied into guest memory and read back.
 
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
 use tracing::trace;
 
+/// Frame buffers each decoder keeps for reuse
+const FRAME_POOL_SIZE: usize = 4;
+
 /// Video decoder handle
 pub type VdecHandle = u32;
 
@@ -17,6 +26,16 @@ pub enum CellVdecCodecType {
     Divx = 2,
 }
 
+/// Name of a cellVdec codec type, None for unknown types
+pub fn codec_name(codec_type: u32) -> Option<&'static str> {
+    match codec_type {
+        0 => Some("MPEG-2"),
+        1 => Some("AVC"),
+        2 => Some("DivX"),
+        _ => None,
+    }
+}
+
 /// Video decoder type
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -93,6 +112,26 @@ pub struct CellVdecAuInfo {
     pub codec_spec_info: u64,
 }
 
+/// Decoded picture retrieved into a guest buffer
+#[derive(Debug, Clone)]
+pub struct VdecFrame {
+    /// Guest address the game retrieved the picture to
+    pub addr: u32,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// RGBA8 rows, shared with the decoder's frame pool
+    pub pixels: Arc<Vec<u8>>,
+}
+
+/// Picture waiting for the game with the frame it was decoded to
+#[derive(Debug)]
+struct DecodedPicture {
+    item: CellVdecPicItem,
+    pixels: Arc<Vec<u8>>,
+}
+
 // Error codes
 pub const CELL_VDEC_ERROR_ARG: i32 = 0x80610901u32 as i32;
 pub const CELL_VDEC_ERROR_SEQ: i32 = 0x80610902u32 as i32;
@@ -107,7 +146,7 @@ struct VdecEntry {
     codec_type: u32,
     profile_level: u32,
     is_seq_started: bool,
-    picture_queue: VecDeque<CellVdecPicItem>,
+    picture_queue: VecDeque<DecodedPicture>,
     au_count: u32,
     /// Video decoder backend
     decoder: Option<VideoDecoderBackend>,
@@ -150,6 +189,8 @@ struct VideoDecoderBackend {
     height: u32,
     /// Decoded frame count
     frame_count: u32,
+    /// RGBA8 frame buffers, reused once nothing else holds them
+    frame_pool: Vec<Arc<Vec<u8>>>,
 }
 
 impl VideoDecoderBackend {
@@ -165,7 +206,25 @@ impl VideoDecoderBackend {
             width: 1920,  // Default HD resolution
             height: 1080,
             frame_count: 0,
+            frame_pool: Vec::new(),
+        }
+    }
+
+    /// Buffer to decode the next frame into
+    ///
+    /// Frames still shown by the renderer or queued for the game are not
+    /// reused, so a frame is never overwritten while it is read.
+    fn next_frame(&mut self) -> Arc<Vec<u8>> {
+        let size = (self.width * self.height * 4) as usize;
+        if let Some(frame) = self.frame_pool.iter().find(|f| Arc::strong_count(f) == 1 && f.len() == size) {
+            return frame.clone();
+        }
+        // Nothing decodes pixels yet, so frames are opaque black
+        let frame = Arc::new([0, 0, 0, 255].repeat(size / 4));
+        if self.frame_pool.len() < FRAME_POOL_SIZE {
+            self.frame_pool.push(frame.clone());
         }
+        frame
     }
 
     /// Decode an H.264/AVC access unit
@@ -285,6 +344,10 @@ impl VdecEntry {
 pub struct VdecManager {
     decoders: HashMap<VdecHandle, VdecEntry>,
     next_handle: VdecHandle,
+    /// How often each codec type was opened
+    opened_codecs: BTreeMap<u32, u64>,
+    /// Pictures retrieved into guest buffers since the renderer last took them
+    frames: Vec<VdecFrame>,
 }
 
 impl VdecManager {
@@ -292,12 +355,15 @@ impl VdecManager {
         Self {
             decoders: HashMap::new(),
             next_handle: 1,
+            opened_codecs: BTreeMap::new(),
+            frames: Vec::new(),
         }
     }
 
     pub fn open(&mut self, codec_type: u32, profile_level: u32) -> Result<VdecHandle, i32> {
         let handle = self.next_handle;
         self.next_handle += 1;
+        *self.opened_codecs.entry(codec_type).or_insert(0) += 1;
         
         let entry = VdecEntry::new(codec_type, profile_level);
         self.decoders.insert(handle, entry);
@@ -305,6 +371,11 @@ impl VdecManager {
         Ok(handle)
     }
 
+    /// Codec types the game opened decoders for, with how often, by type
+    pub fn opened_codecs(&self) -> Vec<(u32, u64)> {
+        self.opened_codecs.iter().map(|(&codec, &count)| (codec, count)).collect()
+    }
+
     pub fn close(&mut self, handle: VdecHandle) -> Result<(), i32> {
         self.decoders
             .remove(&handle)
@@ -365,7 +436,8 @@ impl VdecManager {
             };
             
             // Add decoded picture to queue
-            entry.picture_queue.push_back(pic_item);
+            let pixels = decoder.next_frame();
+            entry.picture_queue.push_back(DecodedPicture { item: pic_item, pixels });
             entry.au_count += 1;
             
             trace!("VdecManager::decode_au: handle={}, codec={:?}, au_count={}", 
@@ -377,14 +449,48 @@ impl VdecManager {
         }
     }
 
-    pub fn get_picture(&mut self, handle: VdecHandle, _pic_format: &CellVdecPicFormat) -> Result<CellVdecPicItem, i32> {
+    /// Take the next decoded picture, retrieving it into the guest buffer at `out_addr` unless 0
+    pub fn get_picture(
+        &mut self,
+        handle: VdecHandle,
+        _pic_format: &CellVdecPicFormat,
+        out_addr: u32,
+    ) -> Result<CellVdecPicItem, i32> {
         let entry = self.decoders.get_mut(&handle).ok_or(CELL_VDEC_ERROR_ARG)?;
         
         if !entry.is_seq_started {
             return Err(CELL_VDEC_ERROR_SEQ);
         }
         
-        entry.picture_queue.pop_front().ok_or(CELL_VDEC_ERROR_EMPTY)
+        let picture = entry.picture_queue.pop_front().ok_or(CELL_VDEC_ERROR_EMPTY)?;
+        if out_addr != 0 {
+            if let Some(decoder) = &entry.decoder {
+                self.frames.push(VdecFrame {
+                    addr: out_addr,
+                    width: decoder.width,
+                    height: decoder.height,
+                    pixels: picture.pixels,
+                });
+            }
+        }
+        Ok(picture.item)
+    }
+
+    /// Pictures retrieved into guest buffers since the last call, oldest first
+    ///
+    /// Only the last picture retrieved to each buffer is returned; earlier
+    /// ones were overwritten before anything could show them.
+    pub fn take_frames(&mut self) -> Vec<VdecFrame> {
+        let mut frames = std::mem::take(&mut self.frames);
+        let mut seen = Vec::new();
+        frames.reverse();
+        frames.retain(|frame| {
+            let first = !seen.contains(&frame.addr);
+            seen.push(frame.addr);
+            first
+        });
+        frames.reverse();
+        frames
     }
 
     pub fn set_frame_rate(&mut self, handle: VdecHandle, _frame_rate: u32) -> Result<(), i32> {
@@ -508,7 +614,7 @@ pub unsafe fn cell_vdec_get_picture(
     }
     
     unsafe {
-        match crate::context::get_hle_context_mut().vdec.get_picture(handle, &*pic_format) {
+        match crate::context::get_hle_context_mut().vdec.get_picture(handle, &*pic_format, 0) {
             Ok(pic) => {
                 *pic_item = pic;
                 0 // CELL_OK
@@ -576,6 +682,10 @@ mod tests {
         
         assert_ne!(handle1, handle2);
         assert_eq!(manager.decoders.len(), 2);
+
+        manager.open(CellVdecCodecType::Avc as u32, 0x42).unwrap();
+        assert_eq!(manager.opened_codecs(), [(0, 1), (1, 2)]);
+        assert_eq!(codec_name(1), Some("AVC"));
     }
 
     #[test]
@@ -642,7 +752,35 @@ mod tests {
         };
         
         // No pictures decoded yet
-        assert_eq!(manager.get_picture(handle, &pic_format), Err(CELL_VDEC_ERROR_EMPTY));
+        assert_eq!(manager.get_picture(handle, &pic_format, 0), Err(CELL_VDEC_ERROR_EMPTY));
+    }
+
+    #[test]
+    fn test_vdec_frames_shared_with_renderer() {
+        let mut manager = VdecManager::new();
+        let handle = manager.open(CellVdecCodecType::Avc as u32, 0x00420000).unwrap();
+        manager.start_seq(handle).unwrap();
+        let au_info = CellVdecAuInfo { pts: 0, dts: 0, user_data: 0, codec_spec_info: 0 };
+        let pic_format = CellVdecPicFormat { alpha: 0xFF, color_format: 0 };
+        for _ in 0..3 {
+            manager.decode_au(handle, &au_info).unwrap();
+        }
+
+        // Two pictures to the same buffer only show the last
+        manager.get_picture(handle, &pic_format, 0xC010_0000).unwrap();
+        manager.get_picture(handle, &pic_format, 0xC010_0000).unwrap();
+        manager.get_picture(handle, &pic_format, 0).unwrap();
+        let frames = manager.take_frames();
+        assert_eq!(frames.len(), 1);
+        assert_eq!((frames[0].addr, frames[0].width, frames[0].height), (0xC010_0000, 1920, 1080));
+        assert_eq!(frames[0].pixels.len(), 1920 * 1080 * 4);
+        assert_eq!(frames[0].pixels[..4], [0, 0, 0, 255]);
+        assert!(manager.take_frames().is_empty());
+
+        // A frame still held by the renderer is not decoded over
+        manager.decode_au(handle, &au_info).unwrap();
+        let entry = &manager.decoders[&handle];
+        assert!(!Arc::ptr_eq(&entry.picture_queue[0].pixels, &frames[0].pixels));
     }
 
     #[test]
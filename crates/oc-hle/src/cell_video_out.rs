@@ -0,0 +1,343 @@
+//! cellVideoOut HLE - Video Output Configuration
+//!
+//! This module provides HLE implementations for querying and configuring
+//! the TV output. The refresh rate reported follows the emulator's vblank
+//! source, 59.94 Hz or 50 Hz in PAL mode, so games that time themselves
+//! by the display mode stay in step with the vblanks they receive.
+
+use tracing::{debug, trace};
+
+/// Error codes
+pub const CELL_VIDEO_OUT_ERROR_NOT_IMPLEMENTED: i32 = 0x8002B220u32 as i32;
+pub const CELL_VIDEO_OUT_ERROR_ILLEGAL_CONFIGURATION: i32 = 0x8002B221u32 as i32;
+pub const CELL_VIDEO_OUT_ERROR_ILLEGAL_PARAMETER: i32 = 0x8002B222u32 as i32;
+pub const CELL_VIDEO_OUT_ERROR_PARAMETER_OUT_OF_RANGE: i32 = 0x8002B223u32 as i32;
+pub const CELL_VIDEO_OUT_ERROR_DEVICE_NOT_FOUND: i32 = 0x8002B224u32 as i32;
+pub const CELL_VIDEO_OUT_ERROR_UNSUPPORTED_VIDEO_OUT: i32 = 0x8002B225u32 as i32;
+pub const CELL_VIDEO_OUT_ERROR_UNSUPPORTED_DISPLAY_MODE: i32 = 0x8002B226u32 as i32;
+
+/// Video outputs
+pub const CELL_VIDEO_OUT_PRIMARY: u32 = 0;
+pub const CELL_VIDEO_OUT_SECONDARY: u32 = 1;
+
+/// Output states
+pub const CELL_VIDEO_OUT_OUTPUT_STATE_ENABLED: u8 = 0;
+pub const CELL_VIDEO_OUT_OUTPUT_STATE_DISABLED: u8 = 1;
+pub const CELL_VIDEO_OUT_OUTPUT_STATE_PREPARING: u8 = 2;
+
+/// Resolution IDs
+pub const CELL_VIDEO_OUT_RESOLUTION_1080: u8 = 1;
+pub const CELL_VIDEO_OUT_RESOLUTION_720: u8 = 2;
+pub const CELL_VIDEO_OUT_RESOLUTION_480: u8 = 4;
+pub const CELL_VIDEO_OUT_RESOLUTION_576: u8 = 5;
+
+/// Scan modes
+pub const CELL_VIDEO_OUT_SCAN_MODE_INTERLACE: u8 = 0;
+pub const CELL_VIDEO_OUT_SCAN_MODE_PROGRESSIVE: u8 = 1;
+
+/// Aspect ratios
+pub const CELL_VIDEO_OUT_ASPECT_AUTO: u8 = 0;
+pub const CELL_VIDEO_OUT_ASPECT_4_3: u8 = 1;
+pub const CELL_VIDEO_OUT_ASPECT_16_9: u8 = 2;
+
+/// Refresh rate flags
+pub const CELL_VIDEO_OUT_REFRESH_RATE_59_94HZ: u16 = 0x0001;
+pub const CELL_VIDEO_OUT_REFRESH_RATE_50HZ: u16 = 0x0002;
+
+/// Color space
+pub const CELL_VIDEO_OUT_COLOR_SPACE_RGB: u8 = 0x01;
+
+/// Display mode of an output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoOutDisplayMode {
+    pub resolution_id: u8,
+    pub scan_mode: u8,
+    pub conversion: u8,
+    pub aspect: u8,
+    /// CELL_VIDEO_OUT_REFRESH_RATE_* flags
+    pub refresh_rates: u16,
+}
+
+/// State cellVideoOutGetState reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoOutState {
+    /// CELL_VIDEO_OUT_OUTPUT_STATE_* value
+    pub state: u8,
+    pub color_space: u8,
+    pub display_mode: VideoOutDisplayMode,
+}
+
+/// Pixel size of a resolution ID
+pub fn resolution_size(resolution_id: u8) -> Option<(u32, u32)> {
+    match resolution_id {
+        CELL_VIDEO_OUT_RESOLUTION_1080 => Some((1920, 1080)),
+        CELL_VIDEO_OUT_RESOLUTION_720 => Some((1280, 720)),
+        CELL_VIDEO_OUT_RESOLUTION_480 => Some((720, 480)),
+        CELL_VIDEO_OUT_RESOLUTION_576 => Some((720, 576)),
+        _ => None,
+    }
+}
+
+/// Video output manager
+pub struct VideoOutManager {
+    /// Whether the TV runs at 50 Hz
+    pal: bool,
+    /// Resolution the game configured
+    resolution_id: u8,
+    /// Aspect ratio the game configured
+    aspect: u8,
+}
+
+impl VideoOutManager {
+    /// Create a new video output manager
+    pub fn new() -> Self {
+        Self {
+            pal: false,
+            resolution_id: CELL_VIDEO_OUT_RESOLUTION_720,
+            aspect: CELL_VIDEO_OUT_ASPECT_16_9,
+        }
+    }
+
+    /// Set whether the TV runs at 50 Hz
+    pub fn set_pal(&mut self, pal: bool) {
+        self.pal = pal;
+    }
+
+    /// Check if the TV runs at 50 Hz
+    pub fn is_pal(&self) -> bool {
+        self.pal
+    }
+
+    /// Refresh rate flag of the vblank source
+    pub fn refresh_rate(&self) -> u16 {
+        if self.pal {
+            CELL_VIDEO_OUT_REFRESH_RATE_50HZ
+        } else {
+            CELL_VIDEO_OUT_REFRESH_RATE_59_94HZ
+        }
+    }
+
+    /// Check if the TV offers `resolution_id`
+    ///
+    /// The SD resolution is the one matching the refresh rate.
+    pub fn is_resolution_available(&self, resolution_id: u8) -> bool {
+        match resolution_id {
+            CELL_VIDEO_OUT_RESOLUTION_1080 | CELL_VIDEO_OUT_RESOLUTION_720 => true,
+            CELL_VIDEO_OUT_RESOLUTION_480 => !self.pal,
+            CELL_VIDEO_OUT_RESOLUTION_576 => self.pal,
+            _ => false,
+        }
+    }
+
+    /// State of `video_out`
+    pub fn state(&self, video_out: u32) -> Result<VideoOutState, i32> {
+        match video_out {
+            CELL_VIDEO_OUT_PRIMARY => Ok(VideoOutState {
+                state: CELL_VIDEO_OUT_OUTPUT_STATE_ENABLED,
+                color_space: CELL_VIDEO_OUT_COLOR_SPACE_RGB,
+                display_mode: VideoOutDisplayMode {
+                    resolution_id: self.resolution_id,
+                    scan_mode: CELL_VIDEO_OUT_SCAN_MODE_PROGRESSIVE,
+                    conversion: 0,
+                    aspect: self.aspect,
+                    refresh_rates: self.refresh_rate(),
+                },
+            }),
+            // No second TV is connected
+            CELL_VIDEO_OUT_SECONDARY => Ok(VideoOutState {
+                state: CELL_VIDEO_OUT_OUTPUT_STATE_DISABLED,
+                color_space: CELL_VIDEO_OUT_COLOR_SPACE_RGB,
+                display_mode: VideoOutDisplayMode {
+                    resolution_id: 0,
+                    scan_mode: 0,
+                    conversion: 0,
+                    aspect: 0,
+                    refresh_rates: 0,
+                },
+            }),
+            _ => Err(CELL_VIDEO_OUT_ERROR_UNSUPPORTED_VIDEO_OUT),
+        }
+    }
+
+    /// Configure the primary output
+    pub fn configure(&mut self, video_out: u32, resolution_id: u8, aspect: u8) -> i32 {
+        if video_out != CELL_VIDEO_OUT_PRIMARY {
+            return CELL_VIDEO_OUT_ERROR_UNSUPPORTED_VIDEO_OUT;
+        }
+        if !self.is_resolution_available(resolution_id) {
+            return CELL_VIDEO_OUT_ERROR_UNSUPPORTED_DISPLAY_MODE;
+        }
+        if aspect > CELL_VIDEO_OUT_ASPECT_16_9 {
+            return CELL_VIDEO_OUT_ERROR_ILLEGAL_CONFIGURATION;
+        }
+
+        debug!("VideoOutManager::configure: resolution={}, aspect={}", resolution_id, aspect);
+        self.resolution_id = resolution_id;
+        if aspect != CELL_VIDEO_OUT_ASPECT_AUTO {
+            self.aspect = aspect;
+        }
+        0 // CELL_OK
+    }
+
+    /// Resolution the game configured
+    pub fn resolution_id(&self) -> u8 {
+        self.resolution_id
+    }
+}
+
+impl Default for VideoOutManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// cellVideoOutGetState - Get the state of a video output
+///
+/// # Arguments
+/// * `videoOut` - Video output
+/// * `deviceIndex` - Device index
+/// * `state_addr` - Address to write the state
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_video_out_get_state(video_out: u32, device_index: u32, _state_addr: u32) -> i32 {
+    trace!("cellVideoOutGetState(videoOut={}, deviceIndex={})", video_out, device_index);
+
+    if device_index != 0 {
+        return CELL_VIDEO_OUT_ERROR_DEVICE_NOT_FOUND;
+    }
+    if let Err(e) = crate::context::get_hle_context().video_out.state(video_out) {
+        return e;
+    }
+
+    // Note: Writing the state to memory requires memory subsystem integration
+
+    0 // CELL_OK
+}
+
+/// cellVideoOutGetResolution - Get the pixel size of a resolution ID
+///
+/// # Arguments
+/// * `resolutionId` - Resolution ID
+/// * `resolution_addr` - Address to write the width and height
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_video_out_get_resolution(resolution_id: u32, _resolution_addr: u32) -> i32 {
+    trace!("cellVideoOutGetResolution(resolutionId={})", resolution_id);
+
+    if u8::try_from(resolution_id).ok().and_then(resolution_size).is_none() {
+        return CELL_VIDEO_OUT_ERROR_ILLEGAL_PARAMETER;
+    }
+
+    // Note: Writing the resolution to memory requires memory subsystem integration
+
+    0 // CELL_OK
+}
+
+/// cellVideoOutConfigure - Configure a video output
+///
+/// # Arguments
+/// * `videoOut` - Video output
+/// * `config_addr` - Address of the configuration
+/// * `option_addr` - Address of the options
+/// * `waitForEvent` - Whether to wait for the configuration to finish
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_video_out_configure(video_out: u32, _config_addr: u32, _option_addr: u32, wait_for_event: u32) -> i32 {
+    debug!("cellVideoOutConfigure(videoOut={}, waitForEvent={})", video_out, wait_for_event);
+
+    // Note: reading the configuration requires memory subsystem integration,
+    // so the output keeps its current mode
+    let mut ctx = crate::context::get_hle_context_mut();
+    let resolution_id = ctx.video_out.resolution_id();
+    ctx.video_out.configure(video_out, resolution_id, CELL_VIDEO_OUT_ASPECT_AUTO)
+}
+
+/// cellVideoOutGetConfiguration - Get the configuration of a video output
+///
+/// # Arguments
+/// * `videoOut` - Video output
+/// * `config_addr` - Address to write the configuration
+/// * `option_addr` - Address to write the options
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_video_out_get_configuration(video_out: u32, _config_addr: u32, _option_addr: u32) -> i32 {
+    trace!("cellVideoOutGetConfiguration(videoOut={})", video_out);
+
+    if video_out != CELL_VIDEO_OUT_PRIMARY {
+        return CELL_VIDEO_OUT_ERROR_UNSUPPORTED_VIDEO_OUT;
+    }
+
+    // Note: Writing the configuration to memory requires memory subsystem integration
+
+    0 // CELL_OK
+}
+
+/// cellVideoOutGetNumberOfDevice - Get the number of devices on a video output
+///
+/// # Returns
+/// * Device count, or an error code
+pub fn cell_video_out_get_number_of_device(video_out: u32) -> i32 {
+    trace!("cellVideoOutGetNumberOfDevice(videoOut={})", video_out);
+
+    match video_out {
+        CELL_VIDEO_OUT_PRIMARY => 1,
+        CELL_VIDEO_OUT_SECONDARY => 0,
+        _ => CELL_VIDEO_OUT_ERROR_UNSUPPORTED_VIDEO_OUT,
+    }
+}
+
+/// cellVideoOutGetResolutionAvailability - Check if the TV offers a mode
+///
+/// # Returns
+/// * 1 if available, 0 if not
+pub fn cell_video_out_get_resolution_availability(
+    video_out: u32,
+    resolution_id: u32,
+    aspect: u32,
+    _option: u32,
+) -> i32 {
+    trace!(
+        "cellVideoOutGetResolutionAvailability(videoOut={}, resolutionId={}, aspect={})",
+        video_out, resolution_id, aspect
+    );
+
+    let ctx = crate::context::get_hle_context();
+    let available = video_out == CELL_VIDEO_OUT_PRIMARY
+        && u8::try_from(resolution_id).is_ok_and(|id| ctx.video_out.is_resolution_available(id))
+        && aspect <= CELL_VIDEO_OUT_ASPECT_16_9 as u32;
+    available as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_video_out_modes() {
+        let mut manager = VideoOutManager::new();
+        let state = manager.state(CELL_VIDEO_OUT_PRIMARY).unwrap();
+        assert_eq!(state.display_mode.refresh_rates, CELL_VIDEO_OUT_REFRESH_RATE_59_94HZ);
+        assert_eq!(state.display_mode.resolution_id, CELL_VIDEO_OUT_RESOLUTION_720);
+        assert_eq!(manager.state(CELL_VIDEO_OUT_SECONDARY).unwrap().state, CELL_VIDEO_OUT_OUTPUT_STATE_DISABLED);
+        assert_eq!(manager.state(7), Err(CELL_VIDEO_OUT_ERROR_UNSUPPORTED_VIDEO_OUT));
+
+        assert_eq!(
+            manager.configure(CELL_VIDEO_OUT_PRIMARY, CELL_VIDEO_OUT_RESOLUTION_576, CELL_VIDEO_OUT_ASPECT_4_3),
+            CELL_VIDEO_OUT_ERROR_UNSUPPORTED_DISPLAY_MODE
+        );
+        manager.set_pal(true);
+        assert_eq!(
+            manager.configure(CELL_VIDEO_OUT_PRIMARY, CELL_VIDEO_OUT_RESOLUTION_576, CELL_VIDEO_OUT_ASPECT_4_3),
+            0
+        );
+        let mode = manager.state(CELL_VIDEO_OUT_PRIMARY).unwrap().display_mode;
+        assert_eq!(mode.refresh_rates, CELL_VIDEO_OUT_REFRESH_RATE_50HZ);
+        assert_eq!((mode.resolution_id, mode.aspect), (CELL_VIDEO_OUT_RESOLUTION_576, CELL_VIDEO_OUT_ASPECT_4_3));
+        assert!(!manager.is_resolution_available(CELL_VIDEO_OUT_RESOLUTION_480));
+        assert_eq!(resolution_size(CELL_VIDEO_OUT_RESOLUTION_576), Some((720, 576)));
+    }
+}
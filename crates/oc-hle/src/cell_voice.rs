@@ -0,0 +1,844 @@
+//! cellVoice HLE - Voice Chat
+//!
+//! This module provides HLE implementations for the voice chat library.
+//! Ports are connected into a topology of input ports (microphone, game
+//! PCM, network voice) feeding output ports (game PCM, network voice).
+//! PCM ports work as a local loopback: samples entering an input port are
+//! routed to every connected output port, where the game can read them
+//! back. Network voice ports need a PSN connection, which is never
+//! available, so creating them fails the way it does on an offline console.
+
+use std::collections::{BTreeMap, VecDeque};
+use tracing::{debug, trace};
+
+/// Error codes
+pub const CELL_VOICE_ERROR_LIBVOICE_NOT_INIT: i32 = 0x80310801u32 as i32;
+pub const CELL_VOICE_ERROR_LIBVOICE_INITIALIZED: i32 = 0x80310802u32 as i32;
+pub const CELL_VOICE_ERROR_GENERAL: i32 = 0x80310803u32 as i32;
+pub const CELL_VOICE_ERROR_PORT_INVALID: i32 = 0x80310804u32 as i32;
+pub const CELL_VOICE_ERROR_ARGUMENT_INVALID: i32 = 0x80310805u32 as i32;
+pub const CELL_VOICE_ERROR_CONTAINER_INVALID: i32 = 0x80310806u32 as i32;
+pub const CELL_VOICE_ERROR_TOPOLOGY: i32 = 0x80310807u32 as i32;
+pub const CELL_VOICE_ERROR_RESOURCE_INSUFFICIENT: i32 = 0x80310808u32 as i32;
+pub const CELL_VOICE_ERROR_NOT_IMPLEMENTED: i32 = 0x80310809u32 as i32;
+pub const CELL_VOICE_ERROR_ADDRESS_INVALID: i32 = 0x8031080Au32 as i32;
+pub const CELL_VOICE_ERROR_SERVICE_DETACHED: i32 = 0x8031080Bu32 as i32;
+pub const CELL_VOICE_ERROR_SERVICE_ATTACHED: i32 = 0x8031080Cu32 as i32;
+pub const CELL_VOICE_ERROR_SERVICE_NOT_FOUND: i32 = 0x8031080Du32 as i32;
+pub const CELL_VOICE_ERROR_SHAREDMEMORY: i32 = 0x8031080Eu32 as i32;
+pub const CELL_VOICE_ERROR_EVENT_QUEUE: i32 = 0x8031080Fu32 as i32;
+pub const CELL_VOICE_ERROR_SERVICE_HANDLE: i32 = 0x80310810u32 as i32;
+pub const CELL_VOICE_ERROR_EVENT_DISPATCH: i32 = 0x80310811u32 as i32;
+pub const CELL_VOICE_ERROR_DEVICE_NOT_PRESENT: i32 = 0x80310812u32 as i32;
+
+/// Maximum number of ports
+pub const CELL_VOICE_MAX_PORT: usize = 128;
+
+/// Bytes an output port buffers before the oldest samples are dropped
+pub const CELL_VOICE_OPORT_BUFFER_SIZE: usize = 32 * 1024;
+
+/// Port type
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellVoicePortType {
+    /// Microphone input
+    InMic = 0,
+    /// PCM audio written by the game
+    InPcmAudio = 1,
+    /// Encoded voice received from the network
+    InVoice = 2,
+    /// PCM audio read by the game
+    OutPcmAudio = 3,
+    /// Encoded voice sent to the network
+    OutVoice = 4,
+    /// Secondary audio output
+    OutSecondary = 5,
+}
+
+impl CellVoicePortType {
+    /// Port type from its raw value
+    pub fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(Self::InMic),
+            1 => Some(Self::InPcmAudio),
+            2 => Some(Self::InVoice),
+            3 => Some(Self::OutPcmAudio),
+            4 => Some(Self::OutVoice),
+            5 => Some(Self::OutSecondary),
+            _ => None,
+        }
+    }
+
+    /// Check if the port takes samples in
+    pub fn is_input(self) -> bool {
+        matches!(self, Self::InMic | Self::InPcmAudio | Self::InVoice)
+    }
+
+    /// Check if the port carries encoded network voice
+    pub fn needs_np(self) -> bool {
+        matches!(self, Self::InVoice | Self::OutVoice)
+    }
+}
+
+/// Port state
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellVoicePortState {
+    /// Library is stopped
+    Idle = 0,
+    /// Port is paused
+    Ready = 1,
+    /// Output port is waiting for samples
+    Buffering = 2,
+    /// Port is passing samples
+    Running = 3,
+}
+
+/// Port entry
+struct VoicePort {
+    port_type: CellVoicePortType,
+    paused: bool,
+    muted: bool,
+    volume: f32,
+    bitrate: u32,
+    /// Samples waiting to be read from an output port
+    buffer: VecDeque<u8>,
+}
+
+/// Voice chat manager
+pub struct VoiceManager {
+    /// Initialization flag
+    initialized: bool,
+    /// Whether ports pass samples
+    started: bool,
+    /// Whether a PSN connection is available for network voice ports
+    np_online: bool,
+    /// Ports by ID
+    ports: BTreeMap<u32, VoicePort>,
+    /// Next port ID
+    next_port_id: u32,
+    /// Input port to output port connections
+    connections: Vec<(u32, u32)>,
+}
+
+impl VoiceManager {
+    /// Create a new voice manager
+    pub fn new() -> Self {
+        Self {
+            initialized: false,
+            started: false,
+            np_online: false,
+            ports: BTreeMap::new(),
+            next_port_id: 1,
+            connections: Vec::new(),
+        }
+    }
+
+    /// Initialize the library
+    pub fn init(&mut self) -> i32 {
+        if self.initialized {
+            return CELL_VOICE_ERROR_LIBVOICE_INITIALIZED;
+        }
+
+        debug!("VoiceManager::init");
+
+        self.initialized = true;
+        0 // CELL_OK
+    }
+
+    /// Shut the library down, deleting every port
+    pub fn end(&mut self) -> i32 {
+        if !self.initialized {
+            return CELL_VOICE_ERROR_LIBVOICE_NOT_INIT;
+        }
+
+        debug!("VoiceManager::end");
+
+        let np_online = self.np_online;
+        *self = Self::new();
+        self.np_online = np_online;
+        0 // CELL_OK
+    }
+
+    /// Set whether a PSN connection is available
+    pub fn set_np_online(&mut self, online: bool) {
+        self.np_online = online;
+    }
+
+    /// Create a port
+    pub fn create_port(&mut self, port_type: u32) -> Result<u32, i32> {
+        if !self.initialized {
+            return Err(CELL_VOICE_ERROR_LIBVOICE_NOT_INIT);
+        }
+
+        let port_type = CellVoicePortType::from_u32(port_type).ok_or(CELL_VOICE_ERROR_ARGUMENT_INVALID)?;
+        if port_type.needs_np() && !self.np_online {
+            return Err(CELL_VOICE_ERROR_SERVICE_DETACHED);
+        }
+        if self.ports.len() >= CELL_VOICE_MAX_PORT {
+            return Err(CELL_VOICE_ERROR_RESOURCE_INSUFFICIENT);
+        }
+
+        let id = self.next_port_id;
+        self.next_port_id += 1;
+        self.ports.insert(id, VoicePort {
+            port_type,
+            paused: false,
+            muted: false,
+            volume: 1.0,
+            bitrate: 0,
+            buffer: VecDeque::new(),
+        });
+
+        debug!("VoiceManager::create_port: id={}, type={:?}", id, port_type);
+
+        Ok(id)
+    }
+
+    /// Delete a port and its connections
+    pub fn delete_port(&mut self, port_id: u32) -> i32 {
+        if !self.initialized {
+            return CELL_VOICE_ERROR_LIBVOICE_NOT_INIT;
+        }
+        if self.ports.remove(&port_id).is_none() {
+            return CELL_VOICE_ERROR_PORT_INVALID;
+        }
+        self.connections.retain(|&(input, output)| input != port_id && output != port_id);
+        0 // CELL_OK
+    }
+
+    /// Connect an input port to an output port
+    pub fn connect(&mut self, input: u32, output: u32) -> i32 {
+        if let Err(e) = self.check_topology(input, output) {
+            return e;
+        }
+        if !self.connections.contains(&(input, output)) {
+            self.connections.push((input, output));
+        }
+        0 // CELL_OK
+    }
+
+    /// Disconnect an input port from an output port
+    pub fn disconnect(&mut self, input: u32, output: u32) -> i32 {
+        if let Err(e) = self.check_topology(input, output) {
+            return e;
+        }
+        let count = self.connections.len();
+        self.connections.retain(|&connection| connection != (input, output));
+        if self.connections.len() == count {
+            return CELL_VOICE_ERROR_TOPOLOGY;
+        }
+        0 // CELL_OK
+    }
+
+    fn check_topology(&self, input: u32, output: u32) -> Result<(), i32> {
+        if !self.initialized {
+            return Err(CELL_VOICE_ERROR_LIBVOICE_NOT_INIT);
+        }
+        let input = self.ports.get(&input).ok_or(CELL_VOICE_ERROR_PORT_INVALID)?;
+        let output = self.ports.get(&output).ok_or(CELL_VOICE_ERROR_PORT_INVALID)?;
+        if !input.port_type.is_input() || output.port_type.is_input() {
+            return Err(CELL_VOICE_ERROR_TOPOLOGY);
+        }
+        Ok(())
+    }
+
+    /// Start passing samples
+    pub fn start(&mut self) -> i32 {
+        if !self.initialized {
+            return CELL_VOICE_ERROR_LIBVOICE_NOT_INIT;
+        }
+        self.started = true;
+        0 // CELL_OK
+    }
+
+    /// Stop passing samples
+    pub fn stop(&mut self) -> i32 {
+        if !self.initialized {
+            return CELL_VOICE_ERROR_LIBVOICE_NOT_INIT;
+        }
+        self.started = false;
+        0 // CELL_OK
+    }
+
+    fn port(&self, port_id: u32) -> Result<&VoicePort, i32> {
+        if !self.initialized {
+            return Err(CELL_VOICE_ERROR_LIBVOICE_NOT_INIT);
+        }
+        self.ports.get(&port_id).ok_or(CELL_VOICE_ERROR_PORT_INVALID)
+    }
+
+    fn port_mut(&mut self, port_id: u32) -> Result<&mut VoicePort, i32> {
+        if !self.initialized {
+            return Err(CELL_VOICE_ERROR_LIBVOICE_NOT_INIT);
+        }
+        self.ports.get_mut(&port_id).ok_or(CELL_VOICE_ERROR_PORT_INVALID)
+    }
+
+    /// Write samples to an input port, returning the bytes consumed
+    ///
+    /// Samples are copied to every connected output port. Nothing passes
+    /// while the library is stopped or the port is paused or muted; the
+    /// samples are still consumed, as they are on hardware.
+    pub fn write_to_iport(&mut self, port_id: u32, data: &[u8]) -> Result<u32, i32> {
+        let port = self.port(port_id)?;
+        if !port.port_type.is_input() || port.port_type == CellVoicePortType::InMic {
+            return Err(CELL_VOICE_ERROR_PORT_INVALID);
+        }
+        trace!("VoiceManager::write_to_iport: port={}, size={}", port_id, data.len());
+
+        self.route(port_id, data);
+        Ok(data.len() as u32)
+    }
+
+    /// Feed captured microphone samples to every microphone port
+    pub fn capture_mic(&mut self, data: &[u8]) {
+        let mics: Vec<u32> = self
+            .ports
+            .iter()
+            .filter(|(_, port)| port.port_type == CellVoicePortType::InMic)
+            .map(|(&id, _)| id)
+            .collect();
+        for id in mics {
+            self.route(id, data);
+        }
+    }
+
+    fn route(&mut self, input: u32, data: &[u8]) {
+        let passes = |port: &VoicePort| !port.paused && !port.muted;
+        if !self.started || !self.ports.get(&input).is_some_and(passes) {
+            return;
+        }
+        for &(from, to) in &self.connections {
+            if from != input {
+                continue;
+            }
+            if let Some(output) = self.ports.get_mut(&to).filter(|port| passes(port)) {
+                output.buffer.extend(data);
+                let excess = output.buffer.len().saturating_sub(CELL_VOICE_OPORT_BUFFER_SIZE);
+                output.buffer.drain(..excess);
+            }
+        }
+    }
+
+    /// Read up to `max` bytes of samples from an output port
+    pub fn read_from_oport(&mut self, port_id: u32, max: usize) -> Result<Vec<u8>, i32> {
+        let port = self.port_mut(port_id)?;
+        if port.port_type.is_input() {
+            return Err(CELL_VOICE_ERROR_PORT_INVALID);
+        }
+        let len = max.min(port.buffer.len());
+        Ok(port.buffer.drain(..len).collect())
+    }
+
+    /// Drop the samples buffered in a port
+    pub fn reset_port(&mut self, port_id: u32) -> i32 {
+        match self.port_mut(port_id) {
+            Ok(port) => {
+                port.buffer.clear();
+                0 // CELL_OK
+            }
+            Err(e) => e,
+        }
+    }
+
+    /// Pause or resume a port
+    pub fn set_paused(&mut self, port_id: u32, paused: bool) -> i32 {
+        match self.port_mut(port_id) {
+            Ok(port) => {
+                port.paused = paused;
+                0 // CELL_OK
+            }
+            Err(e) => e,
+        }
+    }
+
+    /// Mute or unmute a port
+    pub fn set_mute(&mut self, port_id: u32, muted: bool) -> i32 {
+        match self.port_mut(port_id) {
+            Ok(port) => {
+                port.muted = muted;
+                0 // CELL_OK
+            }
+            Err(e) => e,
+        }
+    }
+
+    /// Get a port's mute flag
+    pub fn mute(&self, port_id: u32) -> Result<bool, i32> {
+        self.port(port_id).map(|port| port.muted)
+    }
+
+    /// Set a port's volume
+    pub fn set_volume(&mut self, port_id: u32, volume: f32) -> i32 {
+        if !(0.0..=1.0).contains(&volume) {
+            return CELL_VOICE_ERROR_ARGUMENT_INVALID;
+        }
+        match self.port_mut(port_id) {
+            Ok(port) => {
+                port.volume = volume;
+                0 // CELL_OK
+            }
+            Err(e) => e,
+        }
+    }
+
+    /// Get a port's volume
+    pub fn volume(&self, port_id: u32) -> Result<f32, i32> {
+        self.port(port_id).map(|port| port.volume)
+    }
+
+    /// Set the bit rate of a network voice port
+    pub fn set_bitrate(&mut self, port_id: u32, bitrate: u32) -> i32 {
+        match self.port_mut(port_id) {
+            Ok(port) if port.port_type.needs_np() => {
+                port.bitrate = bitrate;
+                0 // CELL_OK
+            }
+            Ok(_) => CELL_VOICE_ERROR_PORT_INVALID,
+            Err(e) => e,
+        }
+    }
+
+    /// Get the bit rate of a network voice port
+    pub fn bitrate(&self, port_id: u32) -> Result<u32, i32> {
+        let port = self.port(port_id)?;
+        if !port.port_type.needs_np() {
+            return Err(CELL_VOICE_ERROR_PORT_INVALID);
+        }
+        Ok(port.bitrate)
+    }
+
+    /// Get a port's state
+    pub fn port_state(&self, port_id: u32) -> Result<CellVoicePortState, i32> {
+        let port = self.port(port_id)?;
+        Ok(if !self.started {
+            CellVoicePortState::Idle
+        } else if port.paused {
+            CellVoicePortState::Ready
+        } else if !port.port_type.is_input() && port.buffer.is_empty() {
+            CellVoicePortState::Buffering
+        } else {
+            CellVoicePortState::Running
+        })
+    }
+
+    /// Bytes buffered in a port
+    pub fn buffered(&self, port_id: u32) -> Result<usize, i32> {
+        self.port(port_id).map(|port| port.buffer.len())
+    }
+
+    /// Check if initialized
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+impl Default for VoiceManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// cellVoiceInit - Initialize the voice library
+///
+/// # Arguments
+/// * `param_addr` - Address of CellVoiceInitParam
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_init(param_addr: u32) -> i32 {
+    debug!("cellVoiceInit(param_addr=0x{:08X})", param_addr);
+
+    if param_addr == 0 {
+        return CELL_VOICE_ERROR_ADDRESS_INVALID;
+    }
+    crate::context::get_hle_context_mut().voice.init()
+}
+
+/// cellVoiceInitEx - Initialize the voice library with extended parameters
+///
+/// # Arguments
+/// * `param_addr` - Address of CellVoiceInitParam
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_init_ex(param_addr: u32) -> i32 {
+    cell_voice_init(param_addr)
+}
+
+/// cellVoiceEnd - Shut the voice library down
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_end() -> i32 {
+    debug!("cellVoiceEnd()");
+
+    crate::context::get_hle_context_mut().voice.end()
+}
+
+/// cellVoiceCreatePort - Create a port
+///
+/// # Arguments
+/// * `port_id_addr` - Address to write the port ID
+/// * `param_addr` - Address of CellVoicePortParam
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_create_port(port_id_addr: u32, param_addr: u32) -> i32 {
+    debug!("cellVoiceCreatePort(param_addr=0x{:08X})", param_addr);
+
+    if port_id_addr == 0 || param_addr == 0 {
+        return CELL_VOICE_ERROR_ADDRESS_INVALID;
+    }
+
+    // Note: reading the port type and writing the port ID require memory
+    // subsystem integration; until then ports take PCM audio from the game
+    match crate::context::get_hle_context_mut()
+        .voice
+        .create_port(CellVoicePortType::InPcmAudio as u32)
+    {
+        Ok(_port_id) => 0, // CELL_OK
+        Err(e) => e,
+    }
+}
+
+/// cellVoiceDeletePort - Delete a port
+///
+/// # Arguments
+/// * `port_id` - Port ID
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_delete_port(port_id: u32) -> i32 {
+    debug!("cellVoiceDeletePort(port_id={})", port_id);
+
+    crate::context::get_hle_context_mut().voice.delete_port(port_id)
+}
+
+/// cellVoiceConnectIPortToOPort - Connect an input port to an output port
+///
+/// # Arguments
+/// * `ips` - Input port ID
+/// * `ops` - Output port ID
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_connect_iport_to_oport(ips: u32, ops: u32) -> i32 {
+    debug!("cellVoiceConnectIPortToOPort(ips={}, ops={})", ips, ops);
+
+    crate::context::get_hle_context_mut().voice.connect(ips, ops)
+}
+
+/// cellVoiceDisconnectIPortFromOPort - Disconnect an input port from an output port
+///
+/// # Arguments
+/// * `ips` - Input port ID
+/// * `ops` - Output port ID
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_disconnect_iport_from_oport(ips: u32, ops: u32) -> i32 {
+    debug!("cellVoiceDisconnectIPortFromOPort(ips={}, ops={})", ips, ops);
+
+    crate::context::get_hle_context_mut().voice.disconnect(ips, ops)
+}
+
+/// cellVoiceStart - Start passing samples between ports
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_start() -> i32 {
+    debug!("cellVoiceStart()");
+
+    crate::context::get_hle_context_mut().voice.start()
+}
+
+/// cellVoiceStartEx - Start passing samples between ports
+///
+/// # Arguments
+/// * `param_addr` - Address of CellVoiceStartParam
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_start_ex(param_addr: u32) -> i32 {
+    if param_addr == 0 {
+        return CELL_VOICE_ERROR_ADDRESS_INVALID;
+    }
+    cell_voice_start()
+}
+
+/// cellVoiceStop - Stop passing samples between ports
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_stop() -> i32 {
+    debug!("cellVoiceStop()");
+
+    crate::context::get_hle_context_mut().voice.stop()
+}
+
+/// cellVoiceResetPort - Drop the samples buffered in a port
+///
+/// # Arguments
+/// * `port_id` - Port ID
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_reset_port(port_id: u32) -> i32 {
+    trace!("cellVoiceResetPort(port_id={})", port_id);
+
+    crate::context::get_hle_context_mut().voice.reset_port(port_id)
+}
+
+/// cellVoiceWriteToIPort - Write samples to an input port
+///
+/// # Arguments
+/// * `ips` - Input port ID
+/// * `data_addr` - Sample buffer address
+/// * `size_addr` - Address of the buffer size, updated to the bytes consumed
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_write_to_iport(ips: u32, _data_addr: u32, _size_addr: u32) -> i32 {
+    trace!("cellVoiceWriteToIPort(ips={})", ips);
+
+    // Note: reading the samples requires memory subsystem integration
+    match crate::context::get_hle_context_mut().voice.write_to_iport(ips, &[]) {
+        Ok(_) => 0, // CELL_OK
+        Err(e) => e,
+    }
+}
+
+/// cellVoiceReadFromOPort - Read samples from an output port
+///
+/// # Arguments
+/// * `ops` - Output port ID
+/// * `data_addr` - Sample buffer address
+/// * `size_addr` - Address of the buffer size, updated to the bytes read
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_read_from_oport(ops: u32, _data_addr: u32, _size_addr: u32) -> i32 {
+    trace!("cellVoiceReadFromOPort(ops={})", ops);
+
+    // Note: reading the buffer size and writing the samples require memory
+    // subsystem integration
+    match crate::context::get_hle_context_mut().voice.read_from_oport(ops, 0) {
+        Ok(_) => 0, // CELL_OK
+        Err(e) => e,
+    }
+}
+
+/// cellVoiceGetPortInfo - Get a port's state
+///
+/// # Arguments
+/// * `port_id` - Port ID
+/// * `info_addr` - Address of CellVoiceBasePortInfo
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_get_port_info(port_id: u32, _info_addr: u32) -> i32 {
+    trace!("cellVoiceGetPortInfo(port_id={})", port_id);
+
+    match crate::context::get_hle_context().voice.port_state(port_id) {
+        // Note: writing the info requires memory subsystem integration
+        Ok(_state) => 0, // CELL_OK
+        Err(e) => e,
+    }
+}
+
+/// cellVoiceSetMuteFlag - Mute or unmute a port
+///
+/// # Arguments
+/// * `port_id` - Port ID
+/// * `muted` - Non-zero to mute
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_set_mute_flag(port_id: u32, muted: u16) -> i32 {
+    debug!("cellVoiceSetMuteFlag(port_id={}, muted={})", port_id, muted);
+
+    crate::context::get_hle_context_mut().voice.set_mute(port_id, muted != 0)
+}
+
+/// cellVoiceGetMuteFlag - Get a port's mute flag
+///
+/// # Arguments
+/// * `port_id` - Port ID
+/// * `muted_addr` - Address to write the flag
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_get_mute_flag(port_id: u32, _muted_addr: u32) -> i32 {
+    match crate::context::get_hle_context().voice.mute(port_id) {
+        // Note: writing the flag requires memory subsystem integration
+        Ok(_muted) => 0, // CELL_OK
+        Err(e) => e,
+    }
+}
+
+/// cellVoiceGetVolume - Get a port's volume
+///
+/// # Arguments
+/// * `port_id` - Port ID
+/// * `volume_addr` - Address to write the volume
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_get_volume(port_id: u32, _volume_addr: u32) -> i32 {
+    match crate::context::get_hle_context().voice.volume(port_id) {
+        // Note: writing the volume requires memory subsystem integration
+        Ok(_volume) => 0, // CELL_OK
+        Err(e) => e,
+    }
+}
+
+/// cellVoiceSetBitRate - Set the bit rate of a network voice port
+///
+/// # Arguments
+/// * `port_id` - Port ID
+/// * `bitrate` - Bit rate
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_set_bit_rate(port_id: u32, bitrate: u32) -> i32 {
+    debug!("cellVoiceSetBitRate(port_id={}, bitrate={})", port_id, bitrate);
+
+    crate::context::get_hle_context_mut().voice.set_bitrate(port_id, bitrate)
+}
+
+/// cellVoiceGetBitRate - Get the bit rate of a network voice port
+///
+/// # Arguments
+/// * `port_id` - Port ID
+/// * `bitrate_addr` - Address to write the bit rate
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_get_bit_rate(port_id: u32, _bitrate_addr: u32) -> i32 {
+    match crate::context::get_hle_context().voice.bitrate(port_id) {
+        // Note: writing the bit rate requires memory subsystem integration
+        Ok(_bitrate) => 0, // CELL_OK
+        Err(e) => e,
+    }
+}
+
+/// cellVoicePausePort - Pause a port
+///
+/// # Arguments
+/// * `port_id` - Port ID
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_pause_port(port_id: u32) -> i32 {
+    debug!("cellVoicePausePort(port_id={})", port_id);
+
+    crate::context::get_hle_context_mut().voice.set_paused(port_id, true)
+}
+
+/// cellVoiceResumePort - Resume a paused port
+///
+/// # Arguments
+/// * `port_id` - Port ID
+///
+/// # Returns
+/// * 0 on success
+pub fn cell_voice_resume_port(port_id: u32) -> i32 {
+    debug!("cellVoiceResumePort(port_id={})", port_id);
+
+    crate::context::get_hle_context_mut().voice.set_paused(port_id, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn started_manager() -> VoiceManager {
+        let mut manager = VoiceManager::new();
+        assert_eq!(manager.init(), 0);
+        assert_eq!(manager.start(), 0);
+        manager
+    }
+
+    #[test]
+    fn test_voice_lifecycle() {
+        let mut manager = VoiceManager::new();
+
+        assert_eq!(manager.create_port(CellVoicePortType::InMic as u32), Err(CELL_VOICE_ERROR_LIBVOICE_NOT_INIT));
+        assert_eq!(manager.init(), 0);
+        assert_eq!(manager.init(), CELL_VOICE_ERROR_LIBVOICE_INITIALIZED);
+
+        let port = manager.create_port(CellVoicePortType::InMic as u32).unwrap();
+        assert_eq!(manager.port_state(port), Ok(CellVoicePortState::Idle));
+        assert_eq!(manager.create_port(9), Err(CELL_VOICE_ERROR_ARGUMENT_INVALID));
+
+        assert_eq!(manager.end(), 0);
+        assert_eq!(manager.end(), CELL_VOICE_ERROR_LIBVOICE_NOT_INIT);
+        assert!(!manager.is_initialized());
+    }
+
+    #[test]
+    fn test_voice_np_offline() {
+        let mut manager = started_manager();
+
+        assert_eq!(manager.create_port(CellVoicePortType::InVoice as u32), Err(CELL_VOICE_ERROR_SERVICE_DETACHED));
+        assert_eq!(manager.create_port(CellVoicePortType::OutVoice as u32), Err(CELL_VOICE_ERROR_SERVICE_DETACHED));
+
+        manager.set_np_online(true);
+        let port = manager.create_port(CellVoicePortType::OutVoice as u32).unwrap();
+        assert_eq!(manager.set_bitrate(port, 7300), 0);
+        assert_eq!(manager.bitrate(port), Ok(7300));
+    }
+
+    #[test]
+    fn test_voice_loopback() {
+        let mut manager = started_manager();
+        let mic = manager.create_port(CellVoicePortType::InMic as u32).unwrap();
+        let pcm_in = manager.create_port(CellVoicePortType::InPcmAudio as u32).unwrap();
+        let pcm_out = manager.create_port(CellVoicePortType::OutPcmAudio as u32).unwrap();
+
+        assert_eq!(manager.connect(mic, pcm_in), CELL_VOICE_ERROR_TOPOLOGY);
+        assert_eq!(manager.connect(mic, pcm_out), 0);
+        assert_eq!(manager.connect(pcm_in, pcm_out), 0);
+        assert_eq!(manager.port_state(pcm_out), Ok(CellVoicePortState::Buffering));
+
+        manager.capture_mic(&[1, 2, 3, 4]);
+        assert_eq!(manager.write_to_iport(pcm_in, &[5, 6]), Ok(2));
+        assert_eq!(manager.write_to_iport(mic, &[7]), Err(CELL_VOICE_ERROR_PORT_INVALID));
+        assert_eq!(manager.port_state(pcm_out), Ok(CellVoicePortState::Running));
+
+        assert_eq!(manager.read_from_oport(pcm_out, 3), Ok(vec![1, 2, 3]));
+        assert_eq!(manager.read_from_oport(pcm_out, 16), Ok(vec![4, 5, 6]));
+        assert_eq!(manager.read_from_oport(mic, 16), Err(CELL_VOICE_ERROR_PORT_INVALID));
+
+        // Muted inputs and stopped libraries pass nothing
+        assert_eq!(manager.set_mute(mic, true), 0);
+        manager.capture_mic(&[1, 2]);
+        assert_eq!(manager.buffered(pcm_out), Ok(0));
+        assert_eq!(manager.stop(), 0);
+        assert_eq!(manager.write_to_iport(pcm_in, &[1, 2]), Ok(2));
+        assert_eq!(manager.buffered(pcm_out), Ok(0));
+
+        assert_eq!(manager.disconnect(pcm_in, pcm_out), 0);
+        assert_eq!(manager.disconnect(pcm_in, pcm_out), CELL_VOICE_ERROR_TOPOLOGY);
+        assert_eq!(manager.delete_port(mic), 0);
+        assert_eq!(manager.connect(mic, pcm_out), CELL_VOICE_ERROR_PORT_INVALID);
+    }
+
+    #[test]
+    fn test_voice_output_buffer_limit() {
+        let mut manager = started_manager();
+        let input = manager.create_port(CellVoicePortType::InPcmAudio as u32).unwrap();
+        let output = manager.create_port(CellVoicePortType::OutPcmAudio as u32).unwrap();
+        assert_eq!(manager.connect(input, output), 0);
+
+        let data = vec![0u8; CELL_VOICE_OPORT_BUFFER_SIZE];
+        manager.write_to_iport(input, &data).unwrap();
+        manager.write_to_iport(input, &[1]).unwrap();
+        assert_eq!(manager.buffered(output), Ok(CELL_VOICE_OPORT_BUFFER_SIZE));
+
+        assert_eq!(manager.reset_port(output), 0);
+        assert_eq!(manager.buffered(output), Ok(0));
+    }
+}
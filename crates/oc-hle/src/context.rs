@@ -3,35 +3,53 @@
 //! This module provides a global context that holds all HLE manager instances.
 //! This enables HLE functions to share state and interact with each other properly.
 
+use std::io;
 use std::sync::{Arc, RwLock};
 use once_cell::sync::Lazy;
+use oc_core::savestate::{StateReader, StateWriter};
+use oc_memory::MemoryManager;
 
 use crate::cell_sysutil::SysutilManager;
 use crate::cell_game::GameManager;
 use crate::cell_save_data::SaveDataManager;
+use crate::cell_osk_dialog::OskDialogManager;
+use crate::cell_export::ExportManager;
 use crate::cell_pad::PadManager;
 use crate::cell_audio::AudioManager;
 use crate::cell_fs::FsManager;
 use crate::cell_vdec::VdecManager;
 use crate::cell_adec::AdecManager;
+use crate::cell_atrac::AtracManager;
+use crate::cell_ss_player::SsPlayerManager;
 use crate::cell_dmux::DmuxManager;
 use crate::cell_png_dec::PngDecManager;
 use crate::cell_jpg_dec::JpgDecManager;
 use crate::cell_gif_dec::GifDecManager;
 use crate::cell_vpost::VpostManager;
+use crate::cell_music::MusicManager;
+use crate::cell_search::SearchManager;
 use crate::cell_net_ctl::NetCtlManager;
 use crate::cell_http::HttpManager;
 use crate::cell_ssl::SslManager;
+use crate::sys_net::SysNetManager;
+use crate::cell_bgdl::BgdlManager;
+use crate::guest_call::GuestCallQueue;
 use crate::cell_font::FontManager;
 use crate::cell_font_ft::FontFtManager;
 use crate::libsre::RegexManager;
 use crate::cell_gcm_sys::GcmManager;
 use crate::cell_spurs::SpursManager;
 use crate::cell_spurs_jq::SpursJqManager;
+use crate::cell_daisy::DaisyManager;
 use crate::cell_resc::RescManager;
+use crate::cell_video_out::VideoOutManager;
 use crate::cell_kb::KbManager;
+use crate::cell_key2char::Key2CharManager;
 use crate::cell_mouse::MouseManager;
 use crate::cell_mic::MicManager;
+use crate::cell_voice::VoiceManager;
+use crate::cell_subdisplay::SubDisplayManager;
+use crate::cell_remote_play::RemotePlayManager;
 
 /// Global HLE context instance
 pub static HLE_CONTEXT: Lazy<Arc<RwLock<HleContext>>> = Lazy::new(|| {
@@ -46,6 +64,10 @@ pub struct HleContext {
     pub game: GameManager,
     /// Save data manager
     pub save_data: SaveDataManager,
+    /// On-screen keyboard manager
+    pub osk_dialog: OskDialogManager,
+    /// Photo, video and music export manager
+    pub export: ExportManager,
     /// Controller input manager
     pub pad: PadManager,
     /// Audio output manager
@@ -56,6 +78,10 @@ pub struct HleContext {
     pub vdec: VdecManager,
     /// Audio decoder manager
     pub adec: AdecManager,
+    /// cellAtrac and cellAtracMulti stream manager
+    pub atrac: AtracManager,
+    /// libmixer SSPlayer and MultiStream voice manager
+    pub ss_player: SsPlayerManager,
     /// Demuxer manager
     pub dmux: DmuxManager,
     /// PNG decoder manager
@@ -66,12 +92,20 @@ pub struct HleContext {
     pub gif_dec: GifDecManager,
     /// Video post-processor manager
     pub vpost: VpostManager,
+    /// Custom soundtrack manager
+    pub music: MusicManager,
+    /// Media content search manager
+    pub search: SearchManager,
     /// Network control manager
     pub net_ctl: NetCtlManager,
+    /// Name resolution manager
+    pub sys_net: SysNetManager,
     /// HTTP client manager
     pub http: HttpManager,
     /// SSL/TLS manager
     pub ssl: SslManager,
+    /// Background download manager
+    pub bgdl: BgdlManager,
     /// Font manager
     pub font: FontManager,
     /// FreeType font manager
@@ -84,14 +118,28 @@ pub struct HleContext {
     pub spurs: SpursManager,
     /// SPURS Job Queue manager
     pub spurs_jq: SpursJqManager,
+    /// cellDaisy pipeline queue manager
+    pub daisy: DaisyManager,
     /// Resolution scaler manager
     pub resc: RescManager,
+    /// Video output manager
+    pub video_out: VideoOutManager,
     /// Keyboard input manager
     pub kb: KbManager,
+    /// Key to character conversion manager
+    pub key2char: Key2CharManager,
     /// Mouse input manager
     pub mouse: MouseManager,
     /// Microphone input manager
     pub mic: MicManager,
+    /// Voice chat manager
+    pub voice: VoiceManager,
+    /// Remote Play sub display manager
+    pub subdisplay: SubDisplayManager,
+    /// Remote Play session manager
+    pub remote_play: RemotePlayManager,
+    /// Guest callbacks waiting for the emulator to run them
+    pub guest_calls: GuestCallQueue,
 }
 
 impl HleContext {
@@ -101,29 +149,44 @@ impl HleContext {
             sysutil: SysutilManager::new(),
             game: GameManager::new(),
             save_data: SaveDataManager::new(),
+            osk_dialog: OskDialogManager::new(),
+            export: ExportManager::new(),
             pad: PadManager::new(),
             audio: AudioManager::new(),
             fs: FsManager::new(),
             vdec: VdecManager::new(),
             adec: AdecManager::new(),
+            atrac: AtracManager::new(),
+            ss_player: SsPlayerManager::new(),
             dmux: DmuxManager::new(),
             png_dec: PngDecManager::new(),
             jpg_dec: JpgDecManager::new(),
             gif_dec: GifDecManager::new(),
             vpost: VpostManager::new(),
+            music: MusicManager::new(),
+            search: SearchManager::new(),
             net_ctl: NetCtlManager::new(),
+            sys_net: SysNetManager::new(),
             http: HttpManager::new(),
             ssl: SslManager::new(),
+            bgdl: BgdlManager::new(),
             font: FontManager::new(),
             font_ft: FontFtManager::new(),
             regex: RegexManager::new(),
             gcm: GcmManager::new(),
             spurs: SpursManager::new(),
             spurs_jq: SpursJqManager::new(),
+            daisy: DaisyManager::new(),
             resc: RescManager::new(),
+            video_out: VideoOutManager::new(),
             kb: KbManager::new(),
+            key2char: Key2CharManager::new(),
             mouse: MouseManager::new(),
             mic: MicManager::new(),
+            voice: VoiceManager::new(),
+            subdisplay: SubDisplayManager::new(),
+            remote_play: RemotePlayManager::new(),
+            guest_calls: GuestCallQueue::new(),
         }
     }
 
@@ -131,6 +194,29 @@ impl HleContext {
     pub fn reset(&mut self) {
         *self = Self::new();
     }
+
+    /// Save the managers whose state the game depends on across frames
+    ///
+    /// Decoders, network, font and SPURS managers hold host resources and are
+    /// not saved; they keep whatever state they have when a savestate loads.
+    pub fn save_state(&self, w: &mut StateWriter) {
+        self.sysutil.save_state(w);
+        self.game.save_state(w);
+        self.pad.save_state(w);
+        self.audio.save_state(w);
+        self.gcm.save_state(w);
+        self.guest_calls.save_state(w);
+    }
+
+    /// Restore managers written by [`HleContext::save_state`]
+    pub fn load_state(&mut self, r: &mut StateReader) -> io::Result<()> {
+        self.sysutil.load_state(r)?;
+        self.game.load_state(r)?;
+        self.pad.load_state(r)?;
+        self.audio.load_state(r)?;
+        self.gcm.load_state(r)?;
+        self.guest_calls.load_state(r)
+    }
 }
 
 impl Default for HleContext {
@@ -174,6 +260,52 @@ pub fn reset_hle_context() {
     get_hle_context_mut().reset();
 }
 
+/// Guest memory HLE functions read their arguments from and write results to
+///
+/// Kept outside [`HleContext`] so resetting the managers leaves it attached.
+static GUEST_MEMORY: RwLock<Option<Arc<MemoryManager>>> = RwLock::new(None);
+
+/// Give HLE functions access to guest memory
+pub fn set_guest_memory(memory: Arc<MemoryManager>) {
+    *GUEST_MEMORY.write().unwrap_or_else(|e| e.into_inner()) = Some(memory);
+}
+
+/// Guest memory, once the emulator has attached it
+pub fn guest_memory() -> Option<Arc<MemoryManager>> {
+    GUEST_MEMORY.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Read a NUL-terminated string of at most `max_len` bytes from guest memory
+pub fn read_guest_string(addr: u32, max_len: u32) -> Option<String> {
+    if addr == 0 {
+        return None;
+    }
+    let memory = guest_memory()?;
+    let mut bytes = Vec::new();
+    for offset in 0..max_len {
+        match memory.read::<u8>(addr.wrapping_add(offset)).ok()? {
+            0 => break,
+            byte => bytes.push(byte),
+        }
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Hold while a test uses the global context, so tests that reset it wait
+#[cfg(test)]
+pub(crate) fn test_guard() -> std::sync::MutexGuard<'static, ()> {
+    static GUARD: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    GUARD.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Guest memory shared by every test, attached on first use
+#[cfg(test)]
+pub(crate) fn test_guest_memory() -> Arc<MemoryManager> {
+    static MEMORY: Lazy<Arc<MemoryManager>> = Lazy::new(|| MemoryManager::new().expect("test guest memory"));
+    set_guest_memory(MEMORY.clone());
+    MEMORY.clone()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -201,8 +333,27 @@ mod tests {
         assert!(!ctx.sysutil.has_callbacks());
     }
 
+    #[test]
+    fn test_hle_context_save_state() {
+        let mut ctx = HleContext::new();
+        ctx.sysutil.register_callback(1, 0x10200, 0x3000);
+        ctx.gcm.init(0x1000_0000, 0x10_0000);
+
+        let mut w = StateWriter::new();
+        ctx.save_state(&mut w);
+        let data = w.into_bytes();
+
+        let mut loaded = HleContext::new();
+        let mut r = StateReader::new(&data);
+        loaded.load_state(&mut r).unwrap();
+        assert_eq!(r.remaining(), 0);
+        assert!(loaded.sysutil.has_callbacks());
+        assert!(loaded.gcm.is_rsx_connected());
+    }
+
     #[test]
     fn test_global_context_access() {
+        let _guard = test_guard();
         // Test read access
         {
             let ctx = get_hle_context();
@@ -0,0 +1,103 @@
+//! Guest callbacks owed by HLE functions
+//!
+//! HLE functions run on the host and cannot call back into the game
+//! themselves. The callbacks they owe it, such as sysutil events or the
+//! status callback of a finished export, are queued here instead. The
+//! emulator drains the queue and runs each call on a PPU thread of its own,
+//! the way it runs interrupt handlers, one call at a time in queue order.
+
+use oc_core::savestate::{StateReader, StateWriter};
+use std::collections::VecDeque;
+use std::io;
+
+/// Guest function to call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuestCall {
+    /// Function descriptor (OPD) address
+    pub opd: u32,
+    /// Arguments, passed in r3 to r6
+    pub args: [u64; 4],
+}
+
+/// Calls waiting for the emulator to run them
+#[derive(Debug, Default)]
+pub struct GuestCallQueue {
+    calls: VecDeque<GuestCall>,
+}
+
+impl GuestCallQueue {
+    /// Create an empty queue
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a call of the function at `opd`, ignoring null functions
+    pub fn push(&mut self, opd: u32, args: [u64; 4]) {
+        if opd == 0 {
+            return;
+        }
+        self.calls.push_back(GuestCall { opd, args });
+    }
+
+    /// Take every queued call, oldest first
+    pub fn take(&mut self) -> Vec<GuestCall> {
+        self.calls.drain(..).collect()
+    }
+
+    /// Whether any call is waiting
+    pub fn has_pending(&self) -> bool {
+        !self.calls.is_empty()
+    }
+
+    /// Calls waiting, oldest first
+    pub fn pending(&self) -> impl Iterator<Item = &GuestCall> {
+        self.calls.iter()
+    }
+
+    /// Save the queued calls for a savestate
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.count(self.calls.len());
+        for call in &self.calls {
+            w.u32(call.opd);
+            for &arg in &call.args {
+                w.u64(arg);
+            }
+        }
+    }
+
+    /// Restore the calls written by [`GuestCallQueue::save_state`]
+    pub fn load_state(&mut self, r: &mut StateReader) -> io::Result<()> {
+        self.calls.clear();
+        for _ in 0..r.count()? {
+            let opd = r.u32()?;
+            let args = [r.u64()?, r.u64()?, r.u64()?, r.u64()?];
+            self.calls.push_back(GuestCall { opd, args });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guest_call_queue() {
+        let mut queue = GuestCallQueue::new();
+        queue.push(0x1_0000, [1, 2, 3, 0]);
+        queue.push(0, [4, 0, 0, 0]);
+        queue.push(0x1_0010, [5, 0, 0, 0]);
+
+        let mut w = StateWriter::new();
+        queue.save_state(&mut w);
+        let data = w.into_bytes();
+        let mut restored = GuestCallQueue::new();
+        restored.load_state(&mut StateReader::new(&data)).unwrap();
+
+        let calls = restored.take();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0], GuestCall { opd: 0x1_0000, args: [1, 2, 3, 0] });
+        assert_eq!(calls[1].opd, 0x1_0010);
+        assert!(!restored.has_pending());
+    }
+}
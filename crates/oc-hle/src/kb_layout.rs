@@ -0,0 +1,222 @@
+//! Keyboard layout tables
+//!
+//! The characters each key types on the keyboard arrangements games select
+//! (CELL_KB_MAPPING_*), by the USB HID usage IDs keyboards report
+//! (CELL_KEYC_*). cellKbCnvRawCode and cellKey2char convert key presses
+//! through them. Arrangements without a table of their own type as the US
+//! one, and dead keys type their accent on its own.
+
+use crate::cell_kb::{
+    CELL_KB_LED_CAPS_LOCK, CELL_KB_LED_NUM_LOCK, CELL_KB_MAPPING_106, CELL_KB_MAPPING_106_KANA,
+    CELL_KB_MAPPING_ENGLISH_UK, CELL_KB_MAPPING_FRENCH_FRANCE, CELL_KB_MAPPING_GERMAN_GERMANY,
+    CELL_KB_MAPPING_ITALIAN_ITALY, CELL_KB_MAPPING_SPANISH_SPAIN, CELL_KB_MKEY_L_SHIFT, CELL_KB_MKEY_R_ALT,
+    CELL_KB_MKEY_R_SHIFT,
+};
+
+/// Key codes (CELL_KEYC_*)
+pub const CELL_KEYC_A: u16 = 0x04;
+pub const CELL_KEYC_Z: u16 = 0x1D;
+pub const CELL_KEYC_1: u16 = 0x1E;
+pub const CELL_KEYC_0: u16 = 0x27;
+pub const CELL_KEYC_ENTER: u16 = 0x28;
+pub const CELL_KEYC_ESCAPE: u16 = 0x29;
+pub const CELL_KEYC_BS: u16 = 0x2A;
+pub const CELL_KEYC_TAB: u16 = 0x2B;
+pub const CELL_KEYC_SPACE: u16 = 0x2C;
+pub const CELL_KEYC_KPAD_NUMLOCK: u16 = 0x53;
+pub const CELL_KEYC_KPAD_ENTER: u16 = 0x58;
+pub const CELL_KEYC_KPAD_PERIOD: u16 = 0x63;
+pub const CELL_KEYC_BACKSLASH_106: u16 = 0x87;
+pub const CELL_KEYC_YEN_106: u16 = 0x89;
+
+/// Characters of a key: unshifted, shifted, then with AltGr
+///
+/// A key with fewer characters types nothing with the missing modifiers.
+type KeyChars = (u16, &'static str);
+
+/// US 101 key layout, which the other tables change
+const US: &[KeyChars] = &[
+    (0x04, "aA"), (0x05, "bB"), (0x06, "cC"), (0x07, "dD"), (0x08, "eE"), (0x09, "fF"), (0x0A, "gG"),
+    (0x0B, "hH"), (0x0C, "iI"), (0x0D, "jJ"), (0x0E, "kK"), (0x0F, "lL"), (0x10, "mM"), (0x11, "nN"),
+    (0x12, "oO"), (0x13, "pP"), (0x14, "qQ"), (0x15, "rR"), (0x16, "sS"), (0x17, "tT"), (0x18, "uU"),
+    (0x19, "vV"), (0x1A, "wW"), (0x1B, "xX"), (0x1C, "yY"), (0x1D, "zZ"),
+    (0x1E, "1!"), (0x1F, "2@"), (0x20, "3#"), (0x21, "4$"), (0x22, "5%"), (0x23, "6^"), (0x24, "7&"),
+    (0x25, "8*"), (0x26, "9("), (0x27, "0)"), (0x2C, "  "), (0x2D, "-_"), (0x2E, "=+"), (0x2F, "[{"),
+    (0x30, "]}"), (0x31, "\\|"), (0x32, "\\|"), (0x33, ";:"), (0x34, "'\""), (0x35, "`~"), (0x36, ",<"),
+    (0x37, ".>"), (0x38, "/?"), (0x64, "\\|"),
+];
+
+const UK: &[KeyChars] = &[
+    (0x1F, "2\""), (0x20, "3£"), (0x21, "4$€"), (0x32, "#~"), (0x34, "'@"), (0x35, "`¬"), (0x64, "\\|"),
+];
+
+const GERMAN: &[KeyChars] = &[
+    (0x08, "eE€"), (0x10, "mMµ"), (0x14, "qQ@"), (0x1C, "zZ"), (0x1D, "yY"),
+    (0x1F, "2\"²"), (0x20, "3§³"), (0x23, "6&"), (0x24, "7/{"), (0x25, "8(["), (0x26, "9)]"), (0x27, "0=}"),
+    (0x2D, "ß?\\"), (0x2E, "´`"), (0x2F, "üÜ"), (0x30, "+*~"), (0x32, "#'"), (0x33, "öÖ"), (0x34, "äÄ"),
+    (0x35, "^°"), (0x36, ",;"), (0x37, ".:"), (0x38, "-_"), (0x64, "<>|"),
+];
+
+const FRENCH: &[KeyChars] = &[
+    (0x04, "qQ"), (0x08, "eE€"), (0x10, ",?"), (0x14, "aA"), (0x1A, "zZ"), (0x1D, "wW"),
+    (0x1E, "&1"), (0x1F, "é2~"), (0x20, "\"3#"), (0x21, "'4{"), (0x22, "(5["), (0x23, "-6|"), (0x24, "è7`"),
+    (0x25, "_8\\"), (0x26, "ç9^"), (0x27, "à0@"), (0x2D, ")°]"), (0x2E, "=+}"), (0x2F, "^¨"), (0x30, "$£¤"),
+    (0x32, "*µ"), (0x33, "mM"), (0x34, "ù%"), (0x35, "²"), (0x36, ";."), (0x37, ":/"), (0x38, "!§"),
+    (0x64, "<>"),
+];
+
+const SPANISH: &[KeyChars] = &[
+    (0x08, "eE€"), (0x1E, "1!|"), (0x1F, "2\"@"), (0x20, "3·#"), (0x21, "4$~"), (0x23, "6&¬"), (0x24, "7/"),
+    (0x25, "8("), (0x26, "9)"), (0x27, "0="), (0x2D, "'?"), (0x2E, "¡¿"), (0x2F, "`^["), (0x30, "+*]"),
+    (0x32, "çÇ}"), (0x33, "ñÑ"), (0x34, "´¨{"), (0x35, "ºª\\"), (0x36, ",;"), (0x37, ".:"), (0x38, "-_"),
+    (0x64, "<>"),
+];
+
+const ITALIAN: &[KeyChars] = &[
+    (0x08, "eE€"), (0x1F, "2\""), (0x20, "3£"), (0x23, "6&"), (0x24, "7/"), (0x25, "8("), (0x26, "9)"),
+    (0x27, "0="), (0x2D, "'?"), (0x2E, "ì^"), (0x2F, "èé["), (0x30, "+*]"), (0x32, "ù§"), (0x33, "òç@"),
+    (0x34, "à°#"), (0x35, "\\|"), (0x36, ",;"), (0x37, ".:"), (0x38, "-_"), (0x64, "<>"),
+];
+
+/// Japanese 106 key layout; the yen key types a backslash, which JIS fonts show as ¥
+const JAPANESE: &[KeyChars] = &[
+    (0x1F, "2\""), (0x23, "6&"), (0x24, "7'"), (0x25, "8("), (0x26, "9)"), (0x27, "0"), (0x2D, "-="),
+    (0x2E, "^~"), (0x2F, "@`"), (0x30, "[{"), (0x32, "]}"), (0x33, ";+"), (0x34, ":*"), (0x35, ""),
+    (0x87, "\\_"), (0x89, "\\|"),
+];
+
+/// JIS kana layout of the 106 key keyboard; shift types the small kana and brackets
+const KANA: &[KeyChars] = &[
+    (0x04, "ち"), (0x05, "こ"), (0x06, "そ"), (0x07, "し"), (0x08, "いぃ"), (0x09, "は"), (0x0A, "き"),
+    (0x0B, "く"), (0x0C, "に"), (0x0D, "ま"), (0x0E, "の"), (0x0F, "り"), (0x10, "も"), (0x11, "み"),
+    (0x12, "ら"), (0x13, "せ"), (0x14, "た"), (0x15, "す"), (0x16, "と"), (0x17, "か"), (0x18, "な"),
+    (0x19, "ひ"), (0x1A, "て"), (0x1B, "さ"), (0x1C, "ん"), (0x1D, "つっ"),
+    (0x1E, "ぬ"), (0x1F, "ふ"), (0x20, "あぁ"), (0x21, "うぅ"), (0x22, "えぇ"), (0x23, "おぉ"), (0x24, "やゃ"),
+    (0x25, "ゆゅ"), (0x26, "よょ"), (0x27, "わを"), (0x2D, "ほ"), (0x2E, "へ"), (0x2F, "゛"), (0x30, "゜「"),
+    (0x32, "む」"), (0x33, "れ"), (0x34, "け"), (0x36, "ね、"), (0x37, "る。"), (0x38, "め・"), (0x87, "ろ"),
+    (0x89, "ー"),
+];
+
+/// Table of `arrange`'s own keys
+fn table(arrange: u32) -> &'static [KeyChars] {
+    match arrange {
+        CELL_KB_MAPPING_106 | CELL_KB_MAPPING_106_KANA => JAPANESE,
+        CELL_KB_MAPPING_GERMAN_GERMANY => GERMAN,
+        CELL_KB_MAPPING_SPANISH_SPAIN => SPANISH,
+        CELL_KB_MAPPING_FRENCH_FRANCE => FRENCH,
+        CELL_KB_MAPPING_ITALIAN_ITALY => ITALIAN,
+        CELL_KB_MAPPING_ENGLISH_UK => UK,
+        _ => &[],
+    }
+}
+
+fn find(table: &'static [KeyChars], keycode: u16) -> Option<&'static str> {
+    table.iter().find(|(code, _)| *code == keycode).map(|(_, chars)| *chars)
+}
+
+/// Pick the character of `chars` for the modifier keys and LEDs
+fn pick(chars: &str, mkey: u32, led: u32) -> Option<char> {
+    let mut chars = chars.chars();
+    let (normal, shifted, alt_gr) = (chars.next(), chars.next(), chars.next());
+    if mkey & CELL_KB_MKEY_R_ALT != 0 && alt_gr.is_some() {
+        return alt_gr;
+    }
+    let normal = normal?;
+    // Caps lock shifts letters only
+    let letter = normal.is_alphabetic() && normal.to_uppercase().next() == shifted;
+    let caps = led & CELL_KB_LED_CAPS_LOCK != 0 && letter;
+    let shift = mkey & (CELL_KB_MKEY_L_SHIFT | CELL_KB_MKEY_R_SHIFT) != 0;
+    if shift != caps {
+        shifted
+    } else {
+        Some(normal)
+    }
+}
+
+/// Whether `keycode` is on the numeric keypad
+pub fn is_keypad(keycode: u16) -> bool {
+    (CELL_KEYC_KPAD_NUMLOCK..=CELL_KEYC_KPAD_PERIOD).contains(&keycode)
+}
+
+/// Character the keypad key `keycode` types, digits only with num lock on
+fn keypad_char(keycode: u16, led: u32) -> Option<char> {
+    let num_lock = led & CELL_KB_LED_NUM_LOCK != 0;
+    match keycode {
+        0x54 => Some('/'),
+        0x55 => Some('*'),
+        0x56 => Some('-'),
+        0x57 => Some('+'),
+        0x59..=0x61 if num_lock => char::from_digit(u32::from(keycode - 0x58), 10),
+        0x62 if num_lock => Some('0'),
+        CELL_KEYC_KPAD_PERIOD if num_lock => Some('.'),
+        _ => None,
+    }
+}
+
+/// Printable character `keycode` types on `arrange` with `mkey` held and `led` lit
+///
+/// Control keys such as enter and backspace type no character.
+pub fn key_char(arrange: u32, keycode: u16, mkey: u32, led: u32) -> Option<char> {
+    if is_keypad(keycode) {
+        return keypad_char(keycode, led);
+    }
+    let chars = find(table(arrange), keycode).or_else(|| find(US, keycode))?;
+    pick(chars, mkey, led)
+}
+
+/// Kana `keycode` types on the JIS kana layout with `mkey` held
+pub fn kana_char(keycode: u16, mkey: u32) -> Option<char> {
+    let chars = find(KANA, keycode)?;
+    let shift = mkey & (CELL_KB_MKEY_L_SHIFT | CELL_KB_MKEY_R_SHIFT) != 0;
+    let mut chars = chars.chars();
+    let normal = chars.next();
+    match chars.next() {
+        Some(shifted) if shift => Some(shifted),
+        _ => normal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell_kb::{CELL_KB_MAPPING_101, CELL_KB_MKEY_L_CTRL};
+
+    #[test]
+    fn test_key_char_layouts() {
+        let shift = CELL_KB_MKEY_L_SHIFT;
+        assert_eq!(key_char(CELL_KB_MAPPING_101, CELL_KEYC_A, 0, 0), Some('a'));
+        assert_eq!(key_char(CELL_KB_MAPPING_101, CELL_KEYC_A, shift | CELL_KB_MKEY_L_CTRL, 0), Some('A'));
+        assert_eq!(key_char(CELL_KB_MAPPING_101, 0x1F, shift, 0), Some('@'));
+        assert_eq!(key_char(CELL_KB_MAPPING_101, CELL_KEYC_ENTER, 0, 0), None);
+
+        // Layouts change some keys and fall back to US for the rest
+        assert_eq!(key_char(CELL_KB_MAPPING_GERMAN_GERMANY, 0x1C, 0, 0), Some('z'));
+        assert_eq!(key_char(CELL_KB_MAPPING_GERMAN_GERMANY, 0x14, CELL_KB_MKEY_R_ALT, 0), Some('@'));
+        assert_eq!(key_char(CELL_KB_MAPPING_GERMAN_GERMANY, 0x04, CELL_KB_MKEY_R_ALT, 0), Some('a'));
+        assert_eq!(key_char(CELL_KB_MAPPING_FRENCH_FRANCE, CELL_KEYC_1, 0, 0), Some('&'));
+        assert_eq!(key_char(CELL_KB_MAPPING_FRENCH_FRANCE, CELL_KEYC_1, shift, 0), Some('1'));
+        assert_eq!(key_char(CELL_KB_MAPPING_ENGLISH_UK, 0x20, shift, 0), Some('£'));
+        assert_eq!(key_char(CELL_KB_MAPPING_106, 0x27, shift, 0), None);
+        assert_eq!(key_char(CELL_KB_MAPPING_106, CELL_KEYC_YEN_106, 0, 0), Some('\\'));
+        assert_eq!(key_char(7, 0x1F, shift, 0), Some('@'));
+
+        // Caps lock shifts letters but not the keys around them
+        assert_eq!(key_char(CELL_KB_MAPPING_GERMAN_GERMANY, 0x33, 0, CELL_KB_LED_CAPS_LOCK), Some('Ö'));
+        assert_eq!(key_char(CELL_KB_MAPPING_101, CELL_KEYC_Z, shift, CELL_KB_LED_CAPS_LOCK), Some('z'));
+        assert_eq!(key_char(CELL_KB_MAPPING_FRENCH_FRANCE, 0x1F, 0, CELL_KB_LED_CAPS_LOCK), Some('é'));
+    }
+
+    #[test]
+    fn test_keypad_and_kana() {
+        assert_eq!(key_char(CELL_KB_MAPPING_101, 0x59, 0, CELL_KB_LED_NUM_LOCK), Some('1'));
+        assert_eq!(key_char(CELL_KB_MAPPING_101, 0x59, 0, 0), None);
+        assert_eq!(key_char(CELL_KB_MAPPING_101, 0x55, 0, 0), Some('*'));
+        assert!(is_keypad(CELL_KEYC_KPAD_ENTER));
+        assert!(!is_keypad(CELL_KEYC_ENTER));
+
+        assert_eq!(kana_char(CELL_KEYC_A, 0), Some('ち'));
+        assert_eq!(kana_char(CELL_KEYC_Z, CELL_KB_MKEY_L_SHIFT), Some('っ'));
+        assert_eq!(kana_char(CELL_KEYC_A, CELL_KB_MKEY_L_SHIFT), Some('ち'));
+        assert_eq!(kana_char(CELL_KEYC_SPACE, 0), None);
+    }
+}
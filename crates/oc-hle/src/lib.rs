@@ -4,6 +4,7 @@
 
 pub mod module;
 pub mod context;
+pub mod guest_call;
 
 // Graphics Modules
 pub mod cell_gcm_sys;
@@ -11,39 +12,58 @@ pub mod cell_gif_dec;
 pub mod cell_png_dec;
 pub mod cell_jpg_dec;
 pub mod cell_resc;
+pub mod cell_video_out;
 
 // System Modules
 pub mod cell_sysutil;
 pub mod cell_game;
 pub mod cell_save_data;
+pub mod cell_osk_dialog;
+pub mod cell_export;
 
 // Multimedia Modules
 pub mod cell_dmux;
 pub mod cell_vdec;
 pub mod cell_adec;
+pub mod cell_atrac;
+pub mod cell_ss_player;
 pub mod cell_vpost;
+pub mod cell_music;
+pub mod cell_search;
 
 // Network Modules
 pub mod cell_net_ctl;
 pub mod cell_http;
 pub mod cell_ssl;
+pub mod cell_bgdl;
+pub mod net_capture;
+pub mod net_hosts;
+pub mod sys_net;
 
 // Utilities Modules
 pub mod cell_font;
 pub mod cell_font_ft;
 pub mod cell_spurs;
 pub mod cell_spurs_jq;
+pub mod cell_daisy;
+pub mod cell_ovis;
 pub mod libsre;
 
 // Input Modules
 pub mod cell_pad;
 pub mod cell_kb;
+pub mod cell_key2char;
+pub mod kb_layout;
 pub mod cell_mouse;
 pub mod cell_mic;
+pub mod cell_voice;
 
 // Other System Modules
 pub mod cell_audio;
 pub mod cell_fs;
+pub mod cell_subdisplay;
+pub mod cell_remote_play;
 
 pub use module::ModuleRegistry;
 pub use context::{HleContext, HLE_CONTEXT, get_hle_context, get_hle_context_mut, reset_hle_context};
+pub use context::{guest_memory, set_guest_memory};
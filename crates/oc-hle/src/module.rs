@@ -1,10 +1,130 @@
 //! HLE module registry
 
+use crate::cell_atrac::{
+    cell_atrac_add_stream_data, cell_atrac_create_decoder, cell_atrac_create_decoder_ext, cell_atrac_decode,
+    cell_atrac_delete_decoder, cell_atrac_get_bitrate, cell_atrac_get_buffer_info_for_resetting, cell_atrac_get_channel,
+    cell_atrac_get_internal_error_info, cell_atrac_get_loop_info, cell_atrac_get_max_sample,
+    cell_atrac_get_next_decode_position, cell_atrac_get_next_sample, cell_atrac_get_remain_frame,
+    cell_atrac_get_sound_info, cell_atrac_get_stream_data_info, cell_atrac_get_vacant_size,
+    cell_atrac_multi_add_stream_data, cell_atrac_multi_create_decoder, cell_atrac_multi_create_decoder_ext,
+    cell_atrac_multi_decode, cell_atrac_multi_delete_decoder, cell_atrac_multi_get_bitrate,
+    cell_atrac_multi_get_buffer_info_for_resetting, cell_atrac_multi_get_channel,
+    cell_atrac_multi_get_internal_error_info, cell_atrac_multi_get_loop_info, cell_atrac_multi_get_max_sample,
+    cell_atrac_multi_get_next_decode_position, cell_atrac_multi_get_next_sample, cell_atrac_multi_get_remain_frame,
+    cell_atrac_multi_get_sound_info, cell_atrac_multi_get_stream_data_info, cell_atrac_multi_get_track_array,
+    cell_atrac_multi_get_vacant_size, cell_atrac_multi_reset_play_position, cell_atrac_multi_set_data_and_get_mem_size,
+    cell_atrac_multi_set_loop_num, cell_atrac_reset_play_position, cell_atrac_set_data_and_get_mem_size,
+    cell_atrac_set_loop_num,
+};
+use crate::cell_bgdl::{
+    cell_bgdl_get_info, cell_bgdl_get_info2, cell_bgdl_get_mode, cell_bgdl_set_mode, cell_downloader_cancel_task,
+    cell_downloader_create_task, cell_downloader_delete_task, cell_downloader_finalize, cell_downloader_get_task_info,
+    cell_downloader_initialize, cell_downloader_start_task,
+};
+use crate::cell_daisy::{
+    cell_daisy_lfqueue2_complete_pop_pointer, cell_daisy_lfqueue2_get_pop_pointer,
+    cell_daisy_lfqueue2_has_unfinished_consumer, cell_daisy_lfqueue2_pop_close, cell_daisy_lfqueue2_pop_open,
+    cell_daisy_lfqueue2_push_close, cell_daisy_lfqueue2_push_open, cell_daisy_lock_complete_consume,
+    cell_daisy_lock_complete_produce, cell_daisy_lock_get_next_head_pointer, cell_daisy_lock_get_next_tail_pointer,
+    cell_daisy_lock_initialize, cell_daisy_lock_pop_close, cell_daisy_lock_pop_open, cell_daisy_lock_push_close,
+    cell_daisy_lock_push_open, cell_daisy_scatter_gather_interlock_create, cell_daisy_scatter_gather_interlock_destroy,
+    cell_daisy_scatter_gather_interlock_probe, cell_daisy_scatter_gather_interlock_proceed_sequence_number,
+    cell_daisy_scatter_gather_interlock_release,
+};
+use crate::cell_export::{
+    cell_music_export_finalize, cell_music_export_from_file, cell_music_export_initialize,
+    cell_music_export_initialize2, cell_music_export_progress, cell_photo_export_finalize,
+    cell_photo_export_from_file, cell_photo_export_from_file_with_copy, cell_photo_export_initialize,
+    cell_photo_export_initialize2, cell_photo_export_progress, cell_video_export_finalize,
+    cell_video_export_from_file, cell_video_export_from_file_with_copy, cell_video_export_initialize,
+    cell_video_export_initialize2, cell_video_export_progress,
+};
+use crate::cell_gcm_sys::{
+    cell_gcm_get_current_display_buffer_id, cell_gcm_get_flip_status, cell_gcm_get_last_flip_time,
+    cell_gcm_get_vblank_count, cell_gcm_reset_flip_status, cell_gcm_set_flip_handler, cell_gcm_set_vblank_handler,
+};
+use crate::cell_game::{
+    cell_game_content_error_dialog, cell_game_data_check_create, cell_game_data_check_create2,
+    cell_game_get_size_kb, cell_hdd_game_check, cell_hdd_game_check2, cell_hdd_game_exit_broken,
+    cell_hdd_game_get_size_kb,
+};
+use crate::cell_http::{
+    cell_http_create_client, cell_http_create_transaction, cell_http_destroy_client, cell_http_destroy_transaction,
+    cell_http_end, cell_http_get_status_code, cell_http_init, cell_http_recv_response, cell_http_send_request,
+};
+use crate::cell_kb::cell_kb_cnv_raw_code;
+use crate::cell_key2char::{
+    cell_key2char_close, cell_key2char_get_char, cell_key2char_open, cell_key2char_set_arrangement,
+    cell_key2char_set_mode,
+};
+use crate::cell_music::{
+    cell_music_finalize, cell_music_get_contents_id, cell_music_get_playback_status,
+    cell_music_get_selection_context, cell_music_get_volume, cell_music_initialize, cell_music_initialize2,
+    cell_music_select_contents, cell_music_set_playback_command, cell_music_set_selection_context,
+};
+use crate::cell_osk_dialog::{
+    cell_osk_dialog_abort, cell_osk_dialog_add_support_language, cell_osk_dialog_get_input_text,
+    cell_osk_dialog_get_size, cell_osk_dialog_load_async, cell_osk_dialog_set_key_layout_option,
+    cell_osk_dialog_set_layout_mode, cell_osk_dialog_unload_async,
+};
+use crate::cell_ovis::{
+    cell_ovis_fix_spu_segments, cell_ovis_get_overlay_table_size, cell_ovis_initialize_overlay_table,
+    cell_ovis_invalidate_overlapped_segments,
+};
+use crate::cell_remote_play::{
+    cell_remote_play_break, cell_remote_play_encrypt_all_data, cell_remote_play_get_comparative_volume,
+    cell_remote_play_get_peer_info, cell_remote_play_get_shared_memory, cell_remote_play_get_status,
+    cell_remote_play_stop_peer_video_out,
+};
+use crate::cell_search::{
+    cell_search_cancel, cell_search_end, cell_search_finalize, cell_search_get_content_id_by_offset,
+    cell_search_get_content_info_by_content_id, cell_search_get_content_info_by_offset,
+    cell_search_get_content_info_developer_data, cell_search_get_content_info_game_comment,
+    cell_search_get_content_info_path, cell_search_get_content_info_path_movie_thumb,
+    cell_search_get_content_info_sharable, cell_search_get_music_selection_context,
+    cell_search_get_music_selection_context_of_single_track, cell_search_get_offset_by_content_id,
+    cell_search_initialize, cell_search_prepare_file, cell_search_start_content_search,
+    cell_search_start_content_search_in_list, cell_search_start_list_search, cell_search_start_scene_search,
+    cell_search_start_scene_search_in_video,
+};
+use crate::cell_ss_player::{
+    cell_ms_core_set_volume1, cell_ms_stream_close, cell_ms_stream_get_status, cell_ms_stream_open, cell_ms_stream_play,
+    cell_ms_stream_set_info, cell_ms_stream_set_second_read, cell_ss_player_create, cell_ss_player_get_state,
+    cell_ss_player_play, cell_ss_player_remove, cell_ss_player_set_param, cell_ss_player_set_wave, cell_ss_player_stop,
+};
+use crate::cell_subdisplay::{
+    cell_sub_display_audio_out_blocking, cell_sub_display_audio_out_non_blocking, cell_sub_display_end,
+    cell_sub_display_get_peer_list, cell_sub_display_get_peer_num, cell_sub_display_get_required_memory,
+    cell_sub_display_get_video_buffer, cell_sub_display_init, cell_sub_display_start, cell_sub_display_stop,
+};
+use crate::cell_sysutil::{
+    cell_disc_game_register_disc_change_callback, cell_disc_game_unregister_disc_change_callback,
+    cell_sysutil_check_callback, cell_sysutil_register_callback, cell_sysutil_unregister_callback,
+};
+use crate::cell_video_out::{
+    cell_video_out_configure, cell_video_out_get_configuration, cell_video_out_get_number_of_device,
+    cell_video_out_get_resolution, cell_video_out_get_resolution_availability, cell_video_out_get_state,
+};
+use crate::cell_voice::{
+    cell_voice_connect_iport_to_oport, cell_voice_create_port, cell_voice_delete_port,
+    cell_voice_disconnect_iport_from_oport, cell_voice_end, cell_voice_get_bit_rate, cell_voice_get_mute_flag,
+    cell_voice_get_port_info, cell_voice_get_volume, cell_voice_init, cell_voice_init_ex, cell_voice_pause_port,
+    cell_voice_read_from_oport, cell_voice_reset_port, cell_voice_resume_port, cell_voice_set_bit_rate,
+    cell_voice_set_mute_flag, cell_voice_start, cell_voice_start_ex, cell_voice_stop, cell_voice_write_to_iport,
+};
+use crate::sys_net::{
+    sys_net_connect, sys_net_gethostbyname, sys_net_recv, sys_net_send, sys_net_socket, sys_net_socketclose,
+};
 use std::collections::HashMap;
 
 /// HLE function signature
 pub type HleFunction = fn(args: &[u64]) -> i64;
 
+/// Argument `index` of an HLE call, 0 if the caller passed fewer
+fn arg(args: &[u64], index: usize) -> u64 {
+    args.get(index).copied().unwrap_or(0)
+}
+
 /// HLE module
 pub struct HleModule {
     /// Module name
@@ -58,6 +178,15 @@ impl ModuleRegistry {
         gcm.register(0x21AC3697, |_| 0); // cellGcmInit
         gcm.register(0x9BA451E4, |_| 0); // cellGcmSetFlipMode
         gcm.register(0xD01B570D, |_| 0); // cellGcmGetConfiguration
+        gcm.register(0xA91B0402, |args| cell_gcm_set_vblank_handler(arg(args, 0) as u32) as i64); // cellGcmSetVBlankHandler
+        gcm.register(0xA41EF7E8, |args| cell_gcm_set_flip_handler(arg(args, 0) as u32) as i64); // cellGcmSetFlipHandler
+        gcm.register(0x72A577CE, |_| cell_gcm_get_flip_status() as i64); // cellGcmGetFlipStatus
+        gcm.register(0xB2E761D4, |_| cell_gcm_reset_flip_status() as i64); // cellGcmResetFlipStatus
+        gcm.register(0x63387071, |_| cell_gcm_get_last_flip_time() as i64); // cellGcmGetLastFlipTime
+        gcm.register(0x723BBC7E, |_| cell_gcm_get_vblank_count() as i64); // cellGcmGetVBlankCount
+        gcm.register(0x93806525, |args| {
+            cell_gcm_get_current_display_buffer_id(arg(args, 0) as u32) as i64
+        }); // cellGcmGetCurrentDisplayBufferId
         self.modules.insert("cellGcmSys".to_string(), gcm);
 
         // cellGifDec - GIF decoding
@@ -88,16 +217,93 @@ impl ModuleRegistry {
         
         // cellSysutil - System utilities
         let mut sysutil = HleModule::new("cellSysutil");
-        sysutil.register(0x0BAE8772, |_| 0); // cellSysutilCheckCallback
-        sysutil.register(0x40E34A7A, |_| 0); // cellSysutilRegisterCallback
-        sysutil.register(0xA5768D6B, |_| 0); // cellSysutilUnregisterCallback
+        sysutil.register(0x0BAE8772, |args| {
+            cell_video_out_configure(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+            ) as i64
+        }); // cellVideoOutConfigure
+        sysutil.register(0x9D98AFA0, |args| {
+            cell_sysutil_register_callback(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellSysutilRegisterCallback
+        sysutil.register(0x02FF3C1B, |args| {
+            cell_sysutil_unregister_callback(arg(args, 0) as u32) as i64
+        }); // cellSysutilUnregisterCallback
+        sysutil.register(0x189A74DA, |_| cell_sysutil_check_callback() as i64); // cellSysutilCheckCallback
+        sysutil.register(0xDFDD302E, |args| {
+            cell_disc_game_register_disc_change_callback(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellDiscGameRegisterDiscChangeCallback
+        sysutil.register(0xEDC34E1A, |_| {
+            cell_disc_game_unregister_disc_change_callback() as i64
+        }); // cellDiscGameUnregisterDiscChangeCallback
+        sysutil.register(0x9117DF20, |args| {
+            cell_hdd_game_check(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+                arg(args, 4) as u32,
+            ) as i64
+        }); // cellHddGameCheck
+        sysutil.register(0x4BDEC82A, |args| {
+            cell_hdd_game_check2(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+                arg(args, 4) as u32,
+            ) as i64
+        }); // cellHddGameCheck2
+        sysutil.register(0xF82E2EF7, |args| cell_hdd_game_get_size_kb(arg(args, 0) as u32) as i64); // cellHddGameGetSizeKB
+        sysutil.register(0xAFD605B3, |_| cell_hdd_game_exit_broken() as i64); // cellHddGameExitBroken
+        sysutil.register(0x887572D5, |args| {
+            cell_video_out_get_state(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellVideoOutGetState
+        sysutil.register(0xE558748D, |args| {
+            cell_video_out_get_resolution(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellVideoOutGetResolution
+        sysutil.register(0x15B0B0CD, |args| {
+            cell_video_out_get_configuration(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellVideoOutGetConfiguration
+        sysutil.register(0x75BBB672, |args| cell_video_out_get_number_of_device(arg(args, 0) as u32) as i64); // cellVideoOutGetNumberOfDevice
+        sysutil.register(0xA322DB75, |args| {
+            cell_video_out_get_resolution_availability(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+            ) as i64
+        }); // cellVideoOutGetResolutionAvailability
         self.modules.insert("cellSysutil".to_string(), sysutil);
 
         // cellGame - Game data access
         let mut game = HleModule::new("cellGame");
         game.register(0xDB9819F3, |_| 0); // cellGameBootCheck
         game.register(0x70ACEC67, |_| 0); // cellGameDataCheck
-        game.register(0x42A2E133, |_| 0); // cellGameContentErrorDialog
+        game.register(0xB0A1F8C6, |args| {
+            cell_game_content_error_dialog(arg(args, 0) as u32, arg(args, 1), arg(args, 2) as u32) as i64
+        }); // cellGameContentErrorDialog
+        game.register(0xEF9D42D5, |args| cell_game_get_size_kb(arg(args, 0) as u32) as i64); // cellGameGetSizeKB
+        game.register(0xE7951DEE, |args| {
+            cell_game_data_check_create(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+                arg(args, 4) as u32,
+            ) as i64
+        }); // cellGameDataCheckCreate
+        game.register(0xC9645C41, |args| {
+            cell_game_data_check_create2(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+                arg(args, 4) as u32,
+            ) as i64
+        }); // cellGameDataCheckCreate2
         self.modules.insert("cellGame".to_string(), game);
 
         // cellSaveData - Save data management
@@ -107,6 +313,118 @@ impl ModuleRegistry {
         save_data.register(0x2DE0D663, |_| 0); // cellSaveDataDelete2
         self.modules.insert("cellSaveData".to_string(), save_data);
 
+        // cellPhotoExportUtility - Photo library export
+        let mut photo_export = HleModule::new("cellPhotoExportUtility");
+        photo_export.register(0x4357C77F, |args| {
+            cell_photo_export_initialize(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+            ) as i64
+        }); // cellPhotoExportInitialize
+        photo_export.register(0x08CBD8E1, |args| {
+            cell_photo_export_initialize2(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellPhotoExportInitialize2
+        photo_export.register(0x09CE84AC, |args| {
+            cell_photo_export_from_file(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+                arg(args, 4) as u32,
+            ) as i64
+        }); // cellPhotoExportFromFile
+        photo_export.register(0x8D14D09B, |args| {
+            cell_photo_export_from_file_with_copy(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+                arg(args, 4) as u32,
+            ) as i64
+        }); // cellPhotoExportFromFileWithCopy
+        photo_export.register(0xDE509EAD, |args| cell_photo_export_progress(arg(args, 0) as u32, arg(args, 1) as u32) as i64); // cellPhotoExportProgress
+        photo_export.register(0xED4A0148, |args| cell_photo_export_finalize(arg(args, 0) as u32, arg(args, 1) as u32) as i64); // cellPhotoExportFinalize
+        self.modules.insert("cellPhotoExportUtility".to_string(), photo_export);
+
+        // cellVideoExportUtility - Video library export
+        let mut video_export = HleModule::new("cellVideoExportUtility");
+        video_export.register(0x6A24CC70, |args| {
+            cell_video_export_initialize(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+            ) as i64
+        }); // cellVideoExportInitialize
+        video_export.register(0x2F457571, |args| {
+            cell_video_export_initialize2(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellVideoExportInitialize2
+        video_export.register(0x81296524, |args| {
+            cell_video_export_from_file(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+                arg(args, 4) as u32,
+            ) as i64
+        }); // cellVideoExportFromFile
+        video_export.register(0x78946D1F, |args| {
+            cell_video_export_from_file_with_copy(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+                arg(args, 4) as u32,
+            ) as i64
+        }); // cellVideoExportFromFileWithCopy
+        video_export.register(0x1BB79FF4, |args| cell_video_export_progress(arg(args, 0) as u32, arg(args, 1) as u32) as i64); // cellVideoExportProgress
+        video_export.register(0xC15BE817, |args| cell_video_export_finalize(arg(args, 0) as u32, arg(args, 1) as u32) as i64); // cellVideoExportFinalize
+        self.modules.insert("cellVideoExportUtility".to_string(), video_export);
+
+        // cellMusicExportUtility - Music library export
+        let mut music_export = HleModule::new("cellMusicExportUtility");
+        music_export.register(0xB4C9B4F9, |args| {
+            cell_music_export_initialize(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+            ) as i64
+        }); // cellMusicExportInitialize
+        music_export.register(0xE0443A44, |args| {
+            cell_music_export_initialize2(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellMusicExportInitialize2
+        music_export.register(0xB202F0E8, |args| {
+            cell_music_export_from_file(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+                arg(args, 4) as u32,
+            ) as i64
+        }); // cellMusicExportFromFile
+        music_export.register(0x92B50EBC, |args| cell_music_export_progress(arg(args, 0) as u32, arg(args, 1) as u32) as i64); // cellMusicExportProgress
+        music_export.register(0xE90EFFEA, |args| cell_music_export_finalize(arg(args, 0) as u32, arg(args, 1) as u32) as i64); // cellMusicExportFinalize
+        self.modules.insert("cellMusicExportUtility".to_string(), music_export);
+
+        // cellOskDialog - On-screen keyboard
+        let mut osk_dialog = HleModule::new("cellOskDialog");
+        osk_dialog.register(0x7FCFC915, |args| {
+            cell_osk_dialog_load_async(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellOskDialogLoadAsync
+        osk_dialog.register(0x3D1E1931, |args| cell_osk_dialog_unload_async(arg(args, 0) as u32) as i64); // cellOskDialogUnloadAsync
+        osk_dialog.register(0xB6D84526, |_| cell_osk_dialog_abort() as i64); // cellOskDialogAbort
+        osk_dialog.register(0x1D99C3EE, |args| cell_osk_dialog_get_input_text(arg(args, 0) as u32) as i64); // cellOskDialogGetInputText
+        osk_dialog.register(0x35BEADE0, |args| {
+            cell_osk_dialog_get_size(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellOskDialogGetSize
+        osk_dialog.register(0xB53C54FA, |args| cell_osk_dialog_set_key_layout_option(arg(args, 0) as u32) as i64); // cellOskDialogSetKeyLayoutOption
+        osk_dialog.register(0xF0EC3CCC, |args| cell_osk_dialog_set_layout_mode(arg(args, 0) as i32) as i64); // cellOskDialogSetLayoutMode
+        osk_dialog.register(0x7F21C918, |args| cell_osk_dialog_add_support_language(arg(args, 0) as u32) as i64); // cellOskDialogAddSupportLanguage
+        self.modules.insert("cellOskDialog".to_string(), osk_dialog);
+
         // Multimedia Modules
         
         // cellDmux - Demuxer
@@ -133,6 +451,165 @@ impl ModuleRegistry {
         adec.register(0x68B6BB05, |_| 0); // cellAdecEndSeq
         self.modules.insert("cellAdec".to_string(), adec);
 
+        // cellAtrac - ATRAC3plus decoding contexts
+        let mut atrac = HleModule::new("cellAtrac");
+        atrac.register(0x66AFC68E, |args| {
+            cell_atrac_set_data_and_get_mem_size(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32, arg(args, 3) as u32, arg(args, 4) as u32) as i64
+        }); // cellAtracSetDataAndGetMemSize
+        atrac.register(0xFA293E88, |args| {
+            cell_atrac_create_decoder(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32, arg(args, 3) as u32) as i64
+        }); // cellAtracCreateDecoder
+        atrac.register(0x2642D4CC, |args| {
+            cell_atrac_create_decoder_ext(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32, arg(args, 3) as u32) as i64
+        }); // cellAtracCreateDecoderExt
+        atrac.register(0x761CB9BE, |args| {
+            cell_atrac_delete_decoder(arg(args, 0) as u32) as i64
+        }); // cellAtracDeleteDecoder
+        atrac.register(0x8EB0E65F, |args| {
+            cell_atrac_decode(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32, arg(args, 3) as u32, arg(args, 4) as u32) as i64
+        }); // cellAtracDecode
+        atrac.register(0x2BFFF084, |args| {
+            cell_atrac_get_stream_data_info(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32, arg(args, 3) as u32) as i64
+        }); // cellAtracGetStreamDataInfo
+        atrac.register(0x46CFC013, |args| {
+            cell_atrac_add_stream_data(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracAddStreamData
+        atrac.register(0xDFAB73AA, |args| {
+            cell_atrac_get_remain_frame(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracGetRemainFrame
+        atrac.register(0xC9A95FCB, |args| {
+            cell_atrac_get_vacant_size(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracGetVacantSize
+        atrac.register(0x0F9667B6, |args| {
+            cell_atrac_get_channel(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracGetChannel
+        atrac.register(0x5F62D546, |args| {
+            cell_atrac_get_max_sample(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracGetMaxSample
+        atrac.register(0x4797D1FF, |args| {
+            cell_atrac_get_next_sample(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracGetNextSample
+        atrac.register(0xCF01D5D4, |args| {
+            cell_atrac_get_sound_info(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32, arg(args, 3) as u32) as i64
+        }); // cellAtracGetSoundInfo
+        atrac.register(0x7B22E672, |args| {
+            cell_atrac_get_next_decode_position(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracGetNextDecodePosition
+        atrac.register(0x006016DA, |args| {
+            cell_atrac_get_bitrate(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracGetBitrate
+        atrac.register(0xAB6B6DBF, |args| {
+            cell_atrac_get_loop_info(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellAtracGetLoopInfo
+        atrac.register(0x78BA5C41, |args| {
+            cell_atrac_set_loop_num(arg(args, 0) as u32, arg(args, 1) as i32) as i64
+        }); // cellAtracSetLoopNum
+        atrac.register(0x99FB73D1, |args| {
+            cell_atrac_get_buffer_info_for_resetting(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellAtracGetBufferInfoForResetting
+        atrac.register(0x7772EB2B, |args| {
+            cell_atrac_reset_play_position(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellAtracResetPlayPosition
+        atrac.register(0xB5C11938, |args| {
+            cell_atrac_get_internal_error_info(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracGetInternalErrorInfo
+        self.modules.insert("cellAtrac".to_string(), atrac);
+
+        // cellAtracMulti - Multi-channel ATRAC3plus decoding contexts
+        let mut atrac_multi = HleModule::new("cellAtracMulti");
+        atrac_multi.register(0x81B22CA8, |args| {
+            cell_atrac_multi_set_data_and_get_mem_size(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32, arg(args, 3) as u32, arg(args, 4) as u32, arg(args, 5) as u32, arg(args, 6) as u32) as i64
+        }); // cellAtracMultiSetDataAndGetMemSize
+        atrac_multi.register(0x0FFB4665, |args| {
+            cell_atrac_multi_create_decoder(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32, arg(args, 3) as u32) as i64
+        }); // cellAtracMultiCreateDecoder
+        atrac_multi.register(0xB23F070A, |args| {
+            cell_atrac_multi_create_decoder_ext(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32, arg(args, 3) as u32) as i64
+        }); // cellAtracMultiCreateDecoderExt
+        atrac_multi.register(0x780A18BB, |args| {
+            cell_atrac_multi_delete_decoder(arg(args, 0) as u32) as i64
+        }); // cellAtracMultiDeleteDecoder
+        atrac_multi.register(0xD86ACB12, |args| {
+            cell_atrac_multi_decode(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32, arg(args, 3) as u32, arg(args, 4) as u32) as i64
+        }); // cellAtracMultiDecode
+        atrac_multi.register(0x670B2721, |args| {
+            cell_atrac_multi_get_stream_data_info(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32, arg(args, 3) as u32) as i64
+        }); // cellAtracMultiGetStreamDataInfo
+        atrac_multi.register(0x1AE28192, |args| {
+            cell_atrac_multi_add_stream_data(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracMultiAddStreamData
+        atrac_multi.register(0x59431D1B, |args| {
+            cell_atrac_multi_get_remain_frame(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracMultiGetRemainFrame
+        atrac_multi.register(0x60B2101D, |args| {
+            cell_atrac_multi_get_vacant_size(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracMultiGetVacantSize
+        atrac_multi.register(0xF11B8DD5, |args| {
+            cell_atrac_multi_get_channel(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracMultiGetChannel
+        atrac_multi.register(0xD85F2D10, |args| {
+            cell_atrac_multi_get_max_sample(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracMultiGetMaxSample
+        atrac_multi.register(0x77BD901A, |args| {
+            cell_atrac_multi_get_next_sample(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracMultiGetNextSample
+        atrac_multi.register(0xC5C5EED3, |args| {
+            cell_atrac_multi_get_sound_info(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32, arg(args, 3) as u32) as i64
+        }); // cellAtracMultiGetSoundInfo
+        atrac_multi.register(0x489E22F1, |args| {
+            cell_atrac_multi_get_next_decode_position(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracMultiGetNextDecodePosition
+        atrac_multi.register(0x6CFF1D7F, |args| {
+            cell_atrac_multi_get_bitrate(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracMultiGetBitrate
+        atrac_multi.register(0xB91967BC, |args| {
+            cell_atrac_multi_get_loop_info(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellAtracMultiGetLoopInfo
+        atrac_multi.register(0xDA43F526, |args| {
+            cell_atrac_multi_set_loop_num(arg(args, 0) as u32, arg(args, 1) as i32) as i64
+        }); // cellAtracMultiSetLoopNum
+        atrac_multi.register(0xDE9B7905, |args| {
+            cell_atrac_multi_get_buffer_info_for_resetting(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellAtracMultiGetBufferInfoForResetting
+        atrac_multi.register(0x9119CFD1, |args| {
+            cell_atrac_multi_reset_play_position(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellAtracMultiResetPlayPosition
+        atrac_multi.register(0x92D48677, |args| {
+            cell_atrac_multi_get_internal_error_info(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracMultiGetInternalErrorInfo
+        atrac_multi.register(0x5B6CCF5A, |args| {
+            cell_atrac_multi_get_track_array(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellAtracMultiGetTrackArray
+        self.modules.insert("cellAtracMulti".to_string(), atrac_multi);
+
+        // libmixer - SSPlayer sampler voices
+        let mut mixer = HleModule::new("libmixer");
+        mixer.register(0x97E4D75F, |args| cell_ss_player_create(arg(args, 0) as u32, arg(args, 1) as u32) as i64); // cellSSPlayerCreate
+        mixer.register(0xF19D9442, |args| cell_ss_player_remove(arg(args, 0) as u32) as i64); // cellSSPlayerRemove
+        mixer.register(0x1B5D641F, |args| {
+            cell_ss_player_set_wave(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellSSPlayerSetWave
+        mixer.register(0xB3A84880, |args| cell_ss_player_play(arg(args, 0) as u32, arg(args, 1) as u32) as i64); // cellSSPlayerPlay
+        mixer.register(0xA592CD2F, |args| cell_ss_player_stop(arg(args, 0) as u32, arg(args, 1) as u32) as i64); // cellSSPlayerStop
+        mixer.register(0x612F00FF, |args| cell_ss_player_set_param(arg(args, 0) as u32, arg(args, 1) as u32) as i64); // cellSSPlayerSetParam
+        mixer.register(0x562DA42B, |args| cell_ss_player_get_state(arg(args, 0) as u32) as i64); // cellSSPlayerGetState
+        self.modules.insert("libmixer".to_string(), mixer);
+
+        // libmstream - MultiStream streams of the SCREAM sound middleware
+        let mut mstream = HleModule::new("libmstream");
+        mstream.register(0x9AAC9F60, |_| cell_ms_stream_open() as i64); // cellMSStreamOpen
+        mstream.register(0xCD15F391, |args| cell_ms_stream_close(arg(args, 0) as u32) as i64); // cellMSStreamClose
+        mstream.register(0xDA9A44BA, |args| cell_ms_stream_set_info(arg(args, 0) as u32, arg(args, 1) as u32) as i64); // cellMSStreamSetInfo
+        mstream.register(0xFA627ABA, |args| {
+            cell_ms_stream_set_second_read(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellMSStreamSetSecondRead
+        mstream.register(0x4F68E642, |args| cell_ms_stream_play(arg(args, 0) as u32) as i64); // cellMSStreamPlay
+        mstream.register(0x2058AA57, |args| cell_ms_stream_get_status(arg(args, 0) as u32) as i64); // cellMSStreamGetStatus
+        mstream.register(0xBDAE3F9B, |args| {
+            cell_ms_core_set_volume1(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32, arg(args, 3) as u32) as i64
+        }); // cellMSCoreSetVolume1
+        self.modules.insert("libmstream".to_string(), mstream);
+
         // cellVpost - Video post-processing
         let mut vpost = HleModule::new("cellVpost");
         vpost.register(0xAFCE7E2A, |_| 0); // cellVpostOpen
@@ -140,6 +617,160 @@ impl ModuleRegistry {
         vpost.register(0xE4DC0E5D, |_| 0); // cellVpostExec
         self.modules.insert("cellVpost".to_string(), vpost);
 
+        // cellMusicUtility - Custom soundtrack playback
+        let mut music = HleModule::new("cellMusicUtility");
+        music.register(0x72EC14B5, |args| {
+            cell_music_initialize(
+                arg(args, 0) as i32,
+                arg(args, 1) as u32,
+                arg(args, 2) as i32,
+                arg(args, 3) as u32,
+                arg(args, 4) as u32,
+            ) as i64
+        }); // cellMusicInitialize
+        music.register(0xBE50B11E, |args| {
+            cell_music_initialize2(arg(args, 0) as i32, arg(args, 1) as i32, arg(args, 2) as u32, arg(args, 3) as u32)
+                as i64
+        }); // cellMusicInitialize2
+        music.register(0x72876546, |args| {
+            cell_music_initialize(arg(args, 0) as i32, arg(args, 1) as u32, 0, arg(args, 2) as u32, arg(args, 3) as u32)
+                as i64
+        }); // cellMusicInitializeSystemWorkload
+        music.register(0x61865281, |args| {
+            cell_music_initialize2(arg(args, 0) as i32, 0, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellMusicInitialize2SystemWorkload
+        music.register(0x6F2104F3, |_| cell_music_finalize() as i64); // cellMusicFinalize
+        music.register(0x7BE4DC31, |_| cell_music_finalize() as i64); // cellMusicFinalize2
+        music.register(0xB2336BA7, |args| cell_music_select_contents(arg(args, 0) as u32) as i64); // cellMusicSelectContents
+        music.register(0xAD04CDDD, |_| cell_music_select_contents(0) as i64); // cellMusicSelectContents2
+        music.register(0x5BFF31BF, |args| cell_music_set_selection_context(arg(args, 0) as u32) as i64); // cellMusicSetSelectionContext
+        music.register(0x2BDC5D6B, |args| cell_music_set_selection_context(arg(args, 0) as u32) as i64); // cellMusicSetSelectionContext2
+        music.register(0x0B461648, |args| cell_music_get_selection_context(arg(args, 0) as u32) as i64); // cellMusicGetSelectionContext
+        music.register(0x8793EF97, |args| cell_music_get_selection_context(arg(args, 0) as u32) as i64); // cellMusicGetSelectionContext2
+        music.register(0xA0661626, |args| {
+            cell_music_set_playback_command(arg(args, 0) as i32, arg(args, 1) as u32) as i64
+        }); // cellMusicSetPlaybackCommand
+        music.register(0x98947A6E, |args| {
+            cell_music_set_playback_command(arg(args, 0) as i32, arg(args, 1) as u32) as i64
+        }); // cellMusicSetPlaybackCommand2
+        music.register(0x95F7D9D9, |args| cell_music_get_playback_status(arg(args, 0) as u32) as i64); // cellMusicGetPlaybackStatus
+        music.register(0x648B7611, |args| cell_music_get_playback_status(arg(args, 0) as u32) as i64); // cellMusicGetPlaybackStatus2
+        // The volume is passed in f1, which HLE calls do not see
+        music.register(0xE74CE7BD, |_| 0); // cellMusicSetVolume
+        music.register(0x4014C246, |_| 0); // cellMusicSetVolume2
+        music.register(0x8AA188E3, |args| cell_music_get_volume(arg(args, 0) as u32) as i64); // cellMusicGetVolume
+        music.register(0xF9073A24, |args| cell_music_get_volume(arg(args, 0) as u32) as i64); // cellMusicGetVolume2
+        music.register(0x4C188CAA, |args| cell_music_get_contents_id(arg(args, 0) as u32) as i64); // cellMusicGetContentsId
+        music.register(0x6674DE2D, |args| cell_music_get_contents_id(arg(args, 0) as u32) as i64); // cellMusicGetContentsId2
+        self.modules.insert("cellMusicUtility".to_string(), music);
+
+        // cellSearch - Media content search
+        let mut search = HleModule::new("cellSearchUtility");
+        search.register(0xC81CCF8A, |args| {
+            cell_search_initialize(arg(args, 0) as i32, arg(args, 1) as u32, arg(args, 2) as u32, arg(args, 3) as u32)
+                as i64
+        }); // cellSearchInitialize
+        search.register(0xBFAB7616, |_| cell_search_finalize() as i64); // cellSearchFinalize
+        search.register(0x0A4C8295, |args| {
+            cell_search_start_list_search(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+            ) as i64
+        }); // cellSearchStartListSearch
+        search.register(0x64FB0B76, |args| {
+            cell_search_start_content_search_in_list(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+            ) as i64
+        }); // cellSearchStartContentSearchInList
+        search.register(0x0591826F, |args| {
+            cell_search_start_content_search(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+            ) as i64
+        }); // cellSearchStartContentSearch
+        search.register(0xC0ED0522, |args| {
+            cell_search_start_scene_search_in_video(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+            ) as i64
+        }); // cellSearchStartSceneSearchInVideo
+        search.register(0x13524FAA, |args| {
+            cell_search_start_scene_search(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+                arg(args, 4) as u32,
+                arg(args, 5) as u32,
+                arg(args, 6) as u32,
+            ) as i64
+        }); // cellSearchStartSceneSearch
+        search.register(0x3B210319, |args| {
+            cell_search_get_content_info_by_offset(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+                arg(args, 4) as u32,
+            ) as i64
+        }); // cellSearchGetContentInfoByOffset
+        search.register(0x9663A44B, |args| {
+            cell_search_get_content_info_by_content_id(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32)
+                as i64
+        }); // cellSearchGetContentInfoByContentId
+        search.register(0x540D9068, |args| {
+            cell_search_get_offset_by_content_id(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellSearchGetOffsetByContentId
+        search.register(0x94E21701, |args| {
+            cell_search_get_content_id_by_offset(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+                arg(args, 4) as u32,
+            ) as i64
+        }); // cellSearchGetContentIdByOffset
+        search.register(0xD7A7A433, |args| {
+            cell_search_get_content_info_game_comment(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellSearchGetContentInfoGameComment
+        search.register(0x025CE169, |args| {
+            cell_search_get_music_selection_context(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+                arg(args, 4) as u32,
+            ) as i64
+        }); // cellSearchGetMusicSelectionContext
+        search.register(0xED20E079, |args| {
+            cell_search_get_music_selection_context_of_single_track(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellSearchGetMusicSelectionContextOfSingleTrack
+        search.register(0xFFB28491, |args| {
+            cell_search_get_content_info_path(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellSearchGetContentInfoPath
+        search.register(0x37B5BA0C, |args| {
+            cell_search_get_content_info_path_movie_thumb(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellSearchGetContentInfoPathMovieThumb
+        search.register(0xE73CB0D2, |args| cell_search_prepare_file(arg(args, 0) as u32) as i64); // cellSearchPrepareFile
+        search.register(0x35CDA406, |args| {
+            cell_search_get_content_info_developer_data(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellSearchGetContentInfoDeveloperData
+        search.register(0x1E989496, |args| {
+            cell_search_get_content_info_sharable(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellSearchGetContentInfoSharable
+        search.register(0x8FE376A6, |args| cell_search_cancel(arg(args, 0) as u32) as i64); // cellSearchCancel
+        search.register(0x774033D6, |args| cell_search_end(arg(args, 0) as u32) as i64); // cellSearchEnd
+        self.modules.insert("cellSearchUtility".to_string(), search);
+
         // Network Modules
         
         // cellNetCtl - Network control
@@ -149,11 +780,60 @@ impl ModuleRegistry {
         net_ctl.register(0x899337F1, |_| 0); // cellNetCtlGetState
         self.modules.insert("cellNetCtl".to_string(), net_ctl);
 
+        // sys_net - Sockets and name resolution
+        let mut sys_net = HleModule::new("sys_net");
+        sys_net.register(0x71F4C717, |args| sys_net_gethostbyname(arg(args, 0) as u32) as i64); // gethostbyname
+        sys_net.register(0x9C056962, |args| {
+            sys_net_socket(arg(args, 0) as i32, arg(args, 1) as i32, arg(args, 2) as i32) as i64
+        }); // socket
+        sys_net.register(0x64F66D35, |args| {
+            sys_net_connect(arg(args, 0) as i32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // connect
+        sys_net.register(0xDC751B40, |args| {
+            sys_net_send(arg(args, 0) as i32, arg(args, 1) as u32, arg(args, 2) as u32, arg(args, 3) as i32) as i64
+        }); // send
+        sys_net.register(0xFBA04F37, |args| {
+            sys_net_recv(arg(args, 0) as i32, arg(args, 1) as u32, arg(args, 2) as u32, arg(args, 3) as i32) as i64
+        }); // recv
+        sys_net.register(0x6DB6E8CD, |args| sys_net_socketclose(arg(args, 0) as i32) as i64); // socketclose
+        self.modules.insert("sys_net".to_string(), sys_net);
+
         // cellHttp - HTTP client
         let mut http = HleModule::new("cellHttp");
-        http.register(0x77F93D09, |_| 0); // cellHttpInit
-        http.register(0xF1FC9429, |_| 0); // cellHttpEnd
-        http.register(0x03863A9F, |_| 0); // cellHttpCreateClient
+        http.register(0x250C386C, |args| cell_http_init(arg(args, 0) as u32) as i64); // cellHttpInit
+        http.register(0xD276FF1F, |_| cell_http_end() as i64); // cellHttpEnd
+        http.register(0x4E4EE53A, |args| cell_http_create_client(arg(args, 0) as u32) as i64); // cellHttpCreateClient
+        http.register(0x980855AC, |args| cell_http_destroy_client(arg(args, 0) as u32) as i64); // cellHttpDestroyClient
+        http.register(0x052A80D9, |args| {
+            cell_http_create_transaction(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+            ) as i64
+        }); // cellHttpCreateTransaction
+        http.register(0x32F5CAE2, |args| {
+            cell_http_destroy_transaction(arg(args, 0) as u32) as i64
+        }); // cellHttpDestroyTransaction
+        http.register(0xA755B005, |args| {
+            cell_http_send_request(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+            ) as i64
+        }); // cellHttpSendRequest
+        http.register(0x61C90691, |args| {
+            cell_http_recv_response(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+            ) as i64
+        }); // cellHttpRecvResponse
+        http.register(0x10D0D7FC, |args| {
+            cell_http_get_status_code(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellHttpResponseGetStatusCode
         self.modules.insert("cellHttp".to_string(), http);
 
         // cellSsl - SSL/TLS
@@ -163,6 +843,35 @@ impl ModuleRegistry {
         ssl.register(0x5BFD9DA1, |_| 0); // cellSslCertificateLoader
         self.modules.insert("cellSsl".to_string(), ssl);
 
+        // cellBGDL - Background download queries
+        let mut bgdl = HleModule::new("cellBGDLUtility");
+        bgdl.register(0x4E9BB95B, |args| {
+            cell_bgdl_get_info(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as i32) as i64
+        }); // cellBGDLGetInfo
+        bgdl.register(0x2AB0D183, |args| {
+            cell_bgdl_get_info2(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as i32) as i64
+        }); // cellBGDLGetInfo2
+        bgdl.register(0x7E134A90, |args| cell_bgdl_set_mode(arg(args, 0) as i32) as i64); // cellBGDLSetMode
+        bgdl.register(0x74E57BDF, |args| cell_bgdl_get_mode(arg(args, 0) as u32) as i64); // cellBGDLGetMode
+        self.modules.insert("cellBGDLUtility".to_string(), bgdl);
+
+        // cellDownloader - Background download tasks
+        let mut downloader = HleModule::new("cellDownloader");
+        downloader.register(0x8CE152BD, |args| {
+            cell_downloader_initialize(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellDownloaderInitialize
+        downloader.register(0xBA115F38, |_| cell_downloader_finalize() as i64); // cellDownloaderFinalize
+        downloader.register(0xD0E4327F, |args| {
+            cell_downloader_create_task(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellDownloaderCreateTask
+        downloader.register(0x3B9175D8, |args| cell_downloader_start_task(arg(args, 0) as u32) as i64); // cellDownloaderStartTask
+        downloader.register(0x0C18F3B3, |args| cell_downloader_cancel_task(arg(args, 0) as u32) as i64); // cellDownloaderCancelTask
+        downloader.register(0xC1842CAB, |args| cell_downloader_delete_task(arg(args, 0) as u32) as i64); // cellDownloaderDeleteTask
+        downloader.register(0x24011E59, |args| {
+            cell_downloader_get_task_info(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellDownloaderGetTaskInfo
+        self.modules.insert("cellDownloader".to_string(), downloader);
+
         // Utilities Modules
         
         // cellFont - Font rendering
@@ -242,6 +951,82 @@ impl ModuleRegistry {
         spurs_jq.register(0x2FF2A154, |_| 0); // cellSpursJobQueueSync
         self.modules.insert("cellSpursJq".to_string(), spurs_jq);
 
+        // cellDaisy - PPU/SPU pipeline queues (C++ exports, NIDs of the mangled names)
+        let mut daisy = HleModule::new("cellDaisy");
+        daisy.register(0x2E8654F8, |args| {
+            cell_daisy_lock_initialize(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cell::Daisy::Lock::initialize
+        daisy.register(0x2C967AA1, |args| cell_daisy_lock_get_next_head_pointer(arg(args, 0) as u32) as i64); // cell::Daisy::Lock::getNextHeadPointer
+        daisy.register(0x6FC83371, |args| cell_daisy_lock_get_next_tail_pointer(arg(args, 0) as u32) as i64); // cell::Daisy::Lock::getNextTailPointer
+        daisy.register(0xBD091E26, |args| {
+            cell_daisy_lock_complete_consume(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cell::Daisy::Lock::completeConsume
+        daisy.register(0x41DF5D21, |args| {
+            cell_daisy_lock_complete_produce(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cell::Daisy::Lock::completeProduce
+        daisy.register(0x706FEED9, |args| cell_daisy_lock_push_open(arg(args, 0) as u32) as i64); // cell::Daisy::Lock::pushOpen
+        daisy.register(0x7B79D6AA, |args| cell_daisy_lock_push_close(arg(args, 0) as u32) as i64); // cell::Daisy::Lock::pushClose
+        daisy.register(0x17C35CC9, |args| cell_daisy_lock_pop_open(arg(args, 0) as u32) as i64); // cell::Daisy::Lock::popOpen
+        daisy.register(0x566C9460, |args| cell_daisy_lock_pop_close(arg(args, 0) as u32) as i64); // cell::Daisy::Lock::popClose
+        daisy.register(0x0D219671, |args| {
+            cell_daisy_lfqueue2_get_pop_pointer(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cell::Daisy::LFQueue2GetPopPointer
+        daisy.register(0x1BBCB5B9, |args| {
+            cell_daisy_lfqueue2_complete_pop_pointer(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+            ) as i64
+        }); // cell::Daisy::LFQueue2CompletePopPointer
+        daisy.register(0xF2034429, |args| cell_daisy_lfqueue2_push_open(arg(args, 0) as u32) as i64); // cell::Daisy::LFQueue2PushOpen
+        daisy.register(0x0563627C, |args| {
+            cell_daisy_lfqueue2_push_close(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cell::Daisy::LFQueue2PushClose
+        daisy.register(0xA286B24A, |args| cell_daisy_lfqueue2_pop_open(arg(args, 0) as u32) as i64); // cell::Daisy::LFQueue2PopOpen
+        daisy.register(0x9581B8BC, |args| {
+            cell_daisy_lfqueue2_pop_close(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cell::Daisy::LFQueue2PopClose
+        daisy.register(0x2BE4DDE1, |args| {
+            cell_daisy_lfqueue2_has_unfinished_consumer(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cell::Daisy::LFQueue2HasUnfinishedConsumer
+        daisy.register(0x1E5B1E23, |args| {
+            cell_daisy_scatter_gather_interlock_create(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+                arg(args, 4) as u32,
+                arg(args, 5) as u8,
+            ) as i64
+        }); // cell::Daisy::ScatterGatherInterlock::ScatterGatherInterlock
+        daisy.register(0x8DACB8D0, |args| {
+            cell_daisy_scatter_gather_interlock_destroy(arg(args, 0) as u32) as i64
+        }); // cell::Daisy::ScatterGatherInterlock::~ScatterGatherInterlock
+        daisy.register(0xD2BE66E6, |args| {
+            cell_daisy_scatter_gather_interlock_probe(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cell::Daisy::ScatterGatherInterlock::probe
+        daisy.register(0x1CE8C914, |args| {
+            cell_daisy_scatter_gather_interlock_release(arg(args, 0) as u32) as i64
+        }); // cell::Daisy::ScatterGatherInterlock::release
+        daisy.register(0xC0FAF91C, |args| {
+            cell_daisy_scatter_gather_interlock_proceed_sequence_number(arg(args, 0) as u32) as i64
+        }); // cell::Daisy::ScatterGatherInterlock::proceedSequenceNumber
+        self.modules.insert("cellDaisy".to_string(), daisy);
+
+        // cellOvis - SPU overlay manager
+        let mut ovis = HleModule::new("cellOvis");
+        ovis.register(0x82F294B2, |args| cell_ovis_get_overlay_table_size(arg(args, 0) as u32) as i64); // cellOvisGetOverlayTableSize
+        ovis.register(0xA876C911, |args| {
+            cell_ovis_initialize_overlay_table(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellOvisInitializeOverlayTable
+        ovis.register(0xCE6CB776, |args| cell_ovis_fix_spu_segments(arg(args, 0) as u32) as i64); // cellOvisFixSpuSegments
+        ovis.register(0x629BA0C0, |args| {
+            cell_ovis_invalidate_overlapped_segments(arg(args, 0) as u32, arg(args, 1) as u32);
+            0
+        }); // cellOvisInvalidateOverlappedSegments
+        self.modules.insert("cellOvis".to_string(), ovis);
+
         // cellKb - Keyboard input
         let mut kb = HleModule::new("cellKb");
         kb.register(0x43E5E12C, |_| 0); // cellKbInit
@@ -250,8 +1035,33 @@ impl ModuleRegistry {
         kb.register(0xFF0A21B7, |_| 0); // cellKbRead
         kb.register(0xA5F85E4D, |_| 0); // cellKbSetReadMode
         kb.register(0x3F72C56E, |_| 0); // cellKbSetCodeType
+        kb.register(0x4AB1FA77, |args| {
+            cell_kb_cnv_raw_code(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32, arg(args, 3) as u16)
+                as i64
+        }); // cellKbCnvRawCode
         self.modules.insert("cellKb".to_string(), kb);
 
+        // cellKey2char - Key to character conversion
+        let mut key2char = HleModule::new("cellKey2char");
+        key2char.register(0xABF629C1, |args| cell_key2char_open(arg(args, 0) as u32) as i64); // cellKey2CharOpen
+        key2char.register(0x14BF2DC1, |args| cell_key2char_close(arg(args, 0) as u32) as i64); // cellKey2CharClose
+        key2char.register(0x56776C0D, |args| {
+            cell_key2char_get_char(
+                arg(args, 0) as u32,
+                arg(args, 1) as u32,
+                arg(args, 2) as u32,
+                arg(args, 3) as u32,
+                arg(args, 4) as u32,
+            ) as i64
+        }); // cellKey2CharGetChar
+        key2char.register(0xBFC03768, |args| {
+            cell_key2char_set_mode(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellKey2CharSetMode
+        key2char.register(0x0DFBADFA, |args| {
+            cell_key2char_set_arrangement(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellKey2CharSetArrangement
+        self.modules.insert("cellKey2char".to_string(), key2char);
+
         // cellMouse - Mouse input
         let mut mouse = HleModule::new("cellMouse");
         mouse.register(0xC9030138, |_| 0); // cellMouseInit
@@ -273,6 +1083,45 @@ impl ModuleRegistry {
         mic.register(0x75DA7A97, |_| 0); // cellMicRead
         self.modules.insert("cellMic".to_string(), mic);
 
+        // cellVoice - Voice chat
+        let mut voice = HleModule::new("cellVoice");
+        voice.register(0xC7CF1182, |args| cell_voice_init(arg(args, 0) as u32) as i64); // cellVoiceInit
+        voice.register(0xB1A2C38F, |args| cell_voice_init_ex(arg(args, 0) as u32) as i64); // cellVoiceInitEx
+        voice.register(0xE0E1AE12, |_| cell_voice_end() as i64); // cellVoiceEnd
+        voice.register(0x2DE54871, |args| cell_voice_create_port(arg(args, 0) as u32, arg(args, 1) as u32) as i64); // cellVoiceCreatePort
+        voice.register(0x9F70C475, |args| cell_voice_delete_port(arg(args, 0) as u32) as i64); // cellVoiceDeletePort
+        voice.register(0xAE6A21D5, |args| {
+            cell_voice_connect_iport_to_oport(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellVoiceConnectIPortToOPort
+        voice.register(0x18D3DF30, |args| {
+            cell_voice_disconnect_iport_from_oport(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellVoiceDisconnectIPortFromOPort
+        voice.register(0x0A563878, |_| cell_voice_start() as i64); // cellVoiceStart
+        voice.register(0x94D51F92, |args| cell_voice_start_ex(arg(args, 0) as u32) as i64); // cellVoiceStartEx
+        voice.register(0xD3A84BE1, |_| cell_voice_stop() as i64); // cellVoiceStop
+        voice.register(0xFF0FA43A, |args| cell_voice_reset_port(arg(args, 0) as u32) as i64); // cellVoiceResetPort
+        voice.register(0x3DAD26E7, |args| {
+            cell_voice_write_to_iport(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellVoiceWriteToIPort
+        voice.register(0x36472C57, |args| {
+            cell_voice_read_from_oport(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellVoiceReadFromOPort
+        voice.register(0x54AC3519, |args| cell_voice_get_port_info(arg(args, 0) as u32, arg(args, 1) as u32) as i64); // cellVoiceGetPortInfo
+        voice.register(0xDDE35A0C, |args| cell_voice_set_mute_flag(arg(args, 0) as u32, arg(args, 1) as u16) as i64); // cellVoiceSetMuteFlag
+        voice.register(0x474609E2, |args| cell_voice_get_mute_flag(arg(args, 0) as u32, arg(args, 1) as u32) as i64); // cellVoiceGetMuteFlag
+        // The volume is passed in f1, which HLE calls do not see
+        voice.register(0xD5AE37D8, |_| 0); // cellVoiceSetVolume
+        voice.register(0x762DC193, |args| cell_voice_get_volume(arg(args, 0) as u32, arg(args, 1) as u32) as i64); // cellVoiceGetVolume
+        voice.register(0x7E60ADC6, |args| cell_voice_set_bit_rate(arg(args, 0) as u32, arg(args, 1) as u32) as i64); // cellVoiceSetBitRate
+        voice.register(0xBEF53A2B, |args| cell_voice_get_bit_rate(arg(args, 0) as u32, arg(args, 1) as u32) as i64); // cellVoiceGetBitRate
+        voice.register(0x87C71B06, |args| cell_voice_pause_port(arg(args, 0) as u32) as i64); // cellVoicePausePort
+        voice.register(0x7BF17B15, |args| cell_voice_resume_port(arg(args, 0) as u32) as i64); // cellVoiceResumePort
+        voice.register(0x2F24FEA3, |_| 0); // cellVoiceUpdatePort
+        voice.register(0x2A01013E, |_| 0); // cellVoiceCreateNotifyEventQueue
+        voice.register(0x35D84910, |_| 0); // cellVoiceSetNotifyEventQueue
+        voice.register(0xDD000886, |_| 0); // cellVoiceRemoveNotifyEventQueue
+        self.modules.insert("cellVoice".to_string(), voice);
+
         // cellFontFT - FreeType font library
         let mut font_ft = HleModule::new("cellFontFT");
         font_ft.register(0x1387C6C4, |_| 0); // cellFontFTInit
@@ -282,6 +1131,49 @@ impl ModuleRegistry {
         font_ft.register(0x2E936C08, |_| 0); // cellFontFTCloseFont
         font_ft.register(0xB276F1F6, |_| 0); // cellFontFTLoadGlyph
         self.modules.insert("cellFontFT".to_string(), font_ft);
+
+        // cellSubDisplay - Remote Play sub display
+        let mut subdisplay = HleModule::new("cellSubDisplay");
+        subdisplay.register(0xF9A7E8A5, |args| {
+            cell_sub_display_init(arg(args, 0) as u32, arg(args, 1) as u32, arg(args, 2) as u32, arg(args, 3) as u32)
+                as i64
+        }); // cellSubDisplayInit
+        subdisplay.register(0x551D80A5, |_| cell_sub_display_end() as i64); // cellSubDisplayEnd
+        subdisplay.register(0x6595CE22, |args| cell_sub_display_get_required_memory(arg(args, 0) as u32) as i64); // cellSubDisplayGetRequiredMemory
+        subdisplay.register(0xA5BCCB47, |_| cell_sub_display_start() as i64); // cellSubDisplayStart
+        subdisplay.register(0x6D85DDB3, |_| cell_sub_display_stop() as i64); // cellSubDisplayStop
+        subdisplay.register(0x938AC642, |args| {
+            cell_sub_display_get_video_buffer(arg(args, 0) as i32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellSubDisplayGetVideoBuffer
+        subdisplay.register(0xAEE1E0C2, |args| {
+            cell_sub_display_audio_out_blocking(arg(args, 0) as i32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellSubDisplayAudioOutBlocking
+        subdisplay.register(0x5468D6B0, |args| {
+            cell_sub_display_audio_out_non_blocking(arg(args, 0) as i32, arg(args, 1) as u32, arg(args, 2) as u32)
+                as i64
+        }); // cellSubDisplayAudioOutNonBlocking
+        subdisplay.register(0x8A264D71, |args| cell_sub_display_get_peer_num(arg(args, 0) as i32) as i64); // cellSubDisplayGetPeerNum
+        subdisplay.register(0xE2485F79, |args| {
+            cell_sub_display_get_peer_list(arg(args, 0) as i32, arg(args, 1) as u32, arg(args, 2) as u32) as i64
+        }); // cellSubDisplayGetPeerList
+        self.modules.insert("cellSubDisplay".to_string(), subdisplay);
+
+        // cellRemotePlay - Remote Play session
+        let mut remote_play = HleModule::new("cellRemotePlay");
+        remote_play.register(0x533F41DF, |_| cell_remote_play_get_status() as i64); // cellRemotePlayGetStatus
+        // The volume is passed in f1, which HLE calls do not see
+        remote_play.register(0x743918BD, |_| 0); // cellRemotePlaySetComparativeVolume
+        remote_play.register(0xA445CD55, |args| cell_remote_play_get_peer_info(arg(args, 0) as u32) as i64); // cellRemotePlayGetPeerInfo
+        remote_play.register(0xC267987B, |args| {
+            cell_remote_play_get_shared_memory(arg(args, 0) as u32, arg(args, 1) as u32) as i64
+        }); // cellRemotePlayGetSharedMemory
+        remote_play.register(0xD29FE5E3, |_| cell_remote_play_encrypt_all_data() as i64); // cellRemotePlayEncryptAllData
+        remote_play.register(0xD6F3FC82, |_| cell_remote_play_stop_peer_video_out() as i64); // cellRemotePlayStopPeerVideoOut
+        remote_play.register(0xE12C8C19, |args| {
+            cell_remote_play_get_comparative_volume(arg(args, 0) as u32) as i64
+        }); // cellRemotePlayGetComparativeVolume
+        remote_play.register(0xFB793F27, |_| cell_remote_play_break() as i64); // cellRemotePlayBreak
+        self.modules.insert("cellRemotePlay".to_string(), remote_play);
     }
 
     /// Get a module by name
@@ -323,17 +1215,29 @@ mod tests {
         // Test system modules
         assert!(registry.get_module("cellSysutil").is_some());
         assert!(registry.get_module("cellGame").is_some());
+        assert!(registry.find_function("cellSysutil", 0x9117DF20).is_some()); // cellHddGameCheck
+        assert!(registry.find_function("cellSysutil", 0x189A74DA).is_some()); // cellSysutilCheckCallback
+        assert!(registry.find_function("cellGame", 0xEF9D42D5).is_some()); // cellGameGetSizeKB
+        assert!(registry.find_function("cellSysutil", 0x887572D5).is_some()); // cellVideoOutGetState
+        assert!(registry.find_function("cellGcmSys", 0xA91B0402).is_some()); // cellGcmSetVBlankHandler
+        assert!(registry.find_function("cellGame", 0xC9645C41).is_some()); // cellGameDataCheckCreate2
         assert!(registry.get_module("cellSaveData").is_some());
+        assert!(registry.find_function("cellOskDialog", 0x7FCFC915).is_some());
+        assert!(registry.find_function("cellPhotoExportUtility", 0x09CE84AC).is_some());
         
         // Test multimedia modules
         assert!(registry.get_module("cellDmux").is_some());
         assert!(registry.get_module("cellVdec").is_some());
         assert!(registry.get_module("cellAdec").is_some());
         assert!(registry.get_module("cellVpost").is_some());
+        assert!(registry.find_function("cellMusicUtility", 0x72EC14B5).is_some());
+        assert!(registry.find_function("cellSearchUtility", 0x0591826F).is_some());
         
         // Test network modules
         assert!(registry.get_module("cellNetCtl").is_some());
         assert!(registry.get_module("cellHttp").is_some());
+        assert!(registry.find_function("cellBGDLUtility", 0x4E9BB95B).is_some());
+        assert!(registry.find_function("cellDownloader", 0x3B9175D8).is_some());
         assert!(registry.get_module("cellSsl").is_some());
         
         // Test utilities modules
@@ -345,6 +1249,12 @@ mod tests {
         assert!(registry.get_module("cellPad").is_some());
         assert!(registry.get_module("cellAudio").is_some());
         assert!(registry.get_module("cellFs").is_some());
+        assert!(registry.find_function("cellSubDisplay", 0xF9A7E8A5).is_some());
+        assert!(registry.find_function("cellVoice", 0xC7CF1182).is_some());
+        assert!(registry.find_function("cellRemotePlay", 0x533F41DF).is_some());
+        assert!(registry.find_function("cellKey2char", 0xABF629C1).is_some());
+        assert!(registry.find_function("cellDaisy", 0x2E8654F8).is_some());
+        assert!(registry.find_function("cellOvis", 0xA876C911).is_some());
         
         // Test function lookup
         let func = registry.find_function("cellGcmSys", 0x21AC3697);
@@ -361,9 +1271,16 @@ mod tests {
         assert!(registry.find_function("cellDmux", 0x04E7CFAB).is_some());
         assert!(registry.find_function("cellVdec", 0xC982A84A).is_some());
         assert!(registry.find_function("cellAdec", 0x2CFFC4C9).is_some());
+        assert!(registry.find_function("cellAtrac", 0x8EB0E65F).is_some());
+        assert!(registry.find_function("cellAtracMulti", 0x5B6CCF5A).is_some());
+        assert!(registry.find_function("libmixer", 0xB3A84880).is_some());
+        assert!(registry.find_function("libmstream", 0x4F68E642).is_some());
         assert!(registry.find_function("cellSsl", 0x0C34B7A5).is_some());
         assert!(registry.find_function("cellAudio", 0x56DFE179).is_some());
         assert!(registry.find_function("cellFs", 0x718BF5F8).is_some());
+        assert!(registry.find_function("cellGame", 0xB0A1F8C6).is_some());
+        assert!(registry.find_function("sys_net", 0x71F4C717).is_some());
+        assert!(registry.find_function("sys_net", 0xDC751B40).is_some());
         
         // Test that non-existent functions return None
         assert!(registry.find_function("cellGcmSys", 0xFFFFFFFF).is_none());
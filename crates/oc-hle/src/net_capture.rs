@@ -0,0 +1,301 @@
+//! Network traffic capture and replay
+//!
+//! Sits between the HLE network libraries and the host network. When
+//! recording, every cellHttp request and sys_net socket message is saved with
+//! its response to the title's capture file. When replaying, requests found
+//! in the file are answered from it without going online, so games can get
+//! past the mandatory handshakes of servers that have shut down. HTTPS is
+//! captured above the TLS layer, so its traffic is stored in the clear.
+//!
+//! Capture files are JSON and text bodies stay text, so canned responses can
+//! be written or edited by hand.
+
+use oc_core::config::NetCaptureMode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Request or response body, stored as text when it is UTF-8
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "BodyRepr", into = "BodyRepr")]
+pub struct Body(pub Vec<u8>);
+
+/// How a [`Body`] appears in capture files
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum BodyRepr {
+    Text(String),
+    Hex { hex: String },
+}
+
+impl From<Body> for BodyRepr {
+    fn from(body: Body) -> Self {
+        match String::from_utf8(body.0) {
+            Ok(text) => BodyRepr::Text(text),
+            Err(e) => BodyRepr::Hex {
+                hex: e.as_bytes().iter().map(|byte| format!("{:02x}", byte)).collect(),
+            },
+        }
+    }
+}
+
+impl TryFrom<BodyRepr> for Body {
+    type Error = String;
+
+    fn try_from(repr: BodyRepr) -> Result<Self, String> {
+        match repr {
+            BodyRepr::Text(text) => Ok(Body(text.into_bytes())),
+            BodyRepr::Hex { hex } => {
+                if hex.len() % 2 != 0 {
+                    return Err(format!("odd number of hex digits in body \"{}\"", hex));
+                }
+                (0..hex.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|e| format!("bad hex body: {}", e)))
+                    .collect::<Result<_, _>>()
+                    .map(Body)
+            }
+        }
+    }
+}
+
+/// HTTP request and the response it got
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpExchange {
+    pub method: String,
+    pub url: String,
+    pub request_headers: Vec<(String, String)>,
+    pub request_body: Body,
+    pub status: u32,
+    pub reason: String,
+    pub response_headers: Vec<(String, String)>,
+    pub response_body: Body,
+}
+
+/// Message sent on a socket and the reply it got
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SocketExchange {
+    /// `host:port` the socket is connected to
+    pub endpoint: String,
+    pub sent: Body,
+    pub received: Body,
+}
+
+/// Contents of a capture file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureFile {
+    pub http: Vec<HttpExchange>,
+    pub sockets: Vec<SocketExchange>,
+}
+
+/// Capture file of `title_id` in `dir`
+pub fn capture_path(dir: &Path, title_id: &str) -> PathBuf {
+    dir.join(format!("{}.json", title_id))
+}
+
+/// Recorder and player of a title's network traffic
+#[derive(Debug, Default)]
+pub struct NetCapture {
+    mode: NetCaptureMode,
+    path: PathBuf,
+    file: CaptureFile,
+    /// Times each request was answered from the file
+    ///
+    /// A request made again gets the next recorded response with the same
+    /// key, and the last one once they run out.
+    replayed: HashMap<String, usize>,
+}
+
+impl NetCapture {
+    /// Capture that neither records nor replays
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record to or replay from the capture file at `path`
+    ///
+    /// Recording adds to an existing file. A file that exists but does not
+    /// parse is an error rather than being overwritten.
+    pub fn open(mode: NetCaptureMode, path: PathBuf) -> Result<Self, String> {
+        let file = match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| format!("Invalid capture {}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => CaptureFile::default(),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        Ok(Self {
+            mode,
+            path,
+            file,
+            replayed: HashMap::new(),
+        })
+    }
+
+    pub fn mode(&self) -> NetCaptureMode {
+        self.mode
+    }
+
+    /// Recorded traffic
+    pub fn file(&self) -> &CaptureFile {
+        &self.file
+    }
+
+    /// Recorded response to an HTTP request, when replaying
+    ///
+    /// The URL has to match exactly; failing that, a recording of the same
+    /// URL with any query string answers, since queries often carry
+    /// timestamps or session tokens.
+    pub fn replay_http(&mut self, method: &str, url: &str) -> Option<HttpExchange> {
+        if self.mode != NetCaptureMode::Replay {
+            return None;
+        }
+        let base = strip_query(url);
+        let exact: Vec<&HttpExchange> =
+            self.file.http.iter().filter(|exchange| exchange.method == method && exchange.url == url).collect();
+        let exchange = if exact.is_empty() {
+            let similar = self
+                .file
+                .http
+                .iter()
+                .filter(|exchange| exchange.method == method && strip_query(&exchange.url) == base)
+                .collect();
+            pick(&mut self.replayed, format!("{} {}?", method, base), similar)
+        } else {
+            pick(&mut self.replayed, format!("{} {}", method, url), exact)
+        };
+        match exchange {
+            Some(exchange) => debug!("Replaying {} {} -> {}", method, url, exchange.status),
+            None => debug!("No recorded response to {} {}", method, url),
+        }
+        exchange.cloned()
+    }
+
+    /// Save an HTTP request and its response, when recording
+    pub fn record_http(&mut self, exchange: HttpExchange) {
+        if self.mode == NetCaptureMode::Record {
+            self.file.http.push(exchange);
+            self.save();
+        }
+    }
+
+    /// Recorded reply to a socket message, when replaying
+    ///
+    /// The message has to match byte for byte; failing that, the replies
+    /// recorded on the same endpoint answer in order.
+    pub fn replay_socket(&mut self, endpoint: &str, sent: &[u8]) -> Option<Vec<u8>> {
+        if self.mode != NetCaptureMode::Replay {
+            return None;
+        }
+        let exact: Vec<&SocketExchange> = self
+            .file
+            .sockets
+            .iter()
+            .filter(|exchange| exchange.endpoint == endpoint && exchange.sent.0 == sent)
+            .collect();
+        let exchange = if exact.is_empty() {
+            let any = self.file.sockets.iter().filter(|exchange| exchange.endpoint == endpoint).collect();
+            pick(&mut self.replayed, format!("socket {}", endpoint), any)
+        } else {
+            pick(&mut self.replayed, format!("socket {} {:02x?}", endpoint, sent), exact)
+        };
+        match exchange {
+            Some(exchange) => debug!("Replaying {} bytes on {}", exchange.received.0.len(), endpoint),
+            None => debug!("No recorded reply on {}", endpoint),
+        }
+        exchange.map(|exchange| exchange.received.0.clone())
+    }
+
+    /// Save a socket message and the reply it got, when recording
+    pub fn record_socket(&mut self, endpoint: &str, sent: &[u8], received: &[u8]) {
+        if self.mode == NetCaptureMode::Record {
+            self.file.sockets.push(SocketExchange {
+                endpoint: endpoint.to_string(),
+                sent: Body(sent.to_vec()),
+                received: Body(received.to_vec()),
+            });
+            self.save();
+        }
+    }
+
+    /// Write the capture file; exchanges are rare enough to save each one
+    fn save(&self) {
+        let result = self
+            .path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .map_err(|e| e.to_string())
+            .and_then(|_| serde_json::to_string_pretty(&self.file).map_err(|e| e.to_string()))
+            .and_then(|json| std::fs::write(&self.path, json).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            warn!("Failed to save network capture {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// URL without its query string
+fn strip_query(url: &str) -> &str {
+    url.split_once('?').map_or(url, |(base, _)| base)
+}
+
+/// Next of `candidates` for `key`, repeating the last one
+fn pick<'a, T>(replayed: &mut HashMap<String, usize>, key: String, candidates: Vec<&'a T>) -> Option<&'a T> {
+    let last = candidates.len().checked_sub(1)?;
+    let count = replayed.entry(key).or_default();
+    let candidate = candidates[(*count).min(last)];
+    *count += 1;
+    Some(candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_replay() {
+        let path = std::env::temp_dir().join(format!("oc_net_capture_test_{}", std::process::id())).join("BLUS00001.json");
+        let mut capture = NetCapture::open(NetCaptureMode::Record, path.clone()).unwrap();
+        for (url, body) in [("https://auth.example.com/login?t=1", "first"), ("https://auth.example.com/login?t=2", "second")] {
+            capture.record_http(HttpExchange {
+                method: "POST".to_string(),
+                url: url.to_string(),
+                status: 200,
+                response_body: Body(body.as_bytes().to_vec()),
+                ..Default::default()
+            });
+        }
+        capture.record_http(HttpExchange {
+            method: "GET".to_string(),
+            url: "https://auth.example.com/ticket".to_string(),
+            status: 200,
+            response_body: Body(vec![0xFF, 0x00]),
+            ..Default::default()
+        });
+        capture.record_socket("match.example.com:3658", &[1, 2], &[0xFF, 0x00]);
+        // Only replaying answers requests
+        assert_eq!(capture.replay_http("POST", "https://auth.example.com/login?t=1"), None);
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("\"first\"") && text.contains("\"hex\": \"ff00\""));
+
+        let mut capture = NetCapture::open(NetCaptureMode::Replay, path.clone()).unwrap();
+        let body = |exchange: Option<HttpExchange>| String::from_utf8(exchange.unwrap().response_body.0).unwrap();
+        assert_eq!(body(capture.replay_http("POST", "https://auth.example.com/login?t=2")), "second");
+        // Other queries get the recordings in order, then the last one again
+        assert_eq!(body(capture.replay_http("POST", "https://auth.example.com/login?t=9")), "first");
+        assert_eq!(body(capture.replay_http("POST", "https://auth.example.com/login?t=9")), "second");
+        assert_eq!(body(capture.replay_http("POST", "https://auth.example.com/login")), "second");
+        assert_eq!(capture.replay_http("GET", "https://auth.example.com/login"), None);
+        let ticket = capture.replay_http("GET", "https://auth.example.com/ticket").unwrap();
+        assert_eq!(ticket.response_body.0, vec![0xFF, 0x00]);
+        assert_eq!(capture.replay_socket("match.example.com:3658", &[3]), Some(vec![0xFF, 0x00]));
+        assert_eq!(capture.replay_socket("other.example.com:3658", &[1, 2]), None);
+
+        // A broken file is not recorded over
+        std::fs::write(&path, "{").unwrap();
+        assert!(NetCapture::open(NetCaptureMode::Record, path.clone()).is_err());
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,162 @@
+//! Hosts table for guest name resolution
+//!
+//! Checked before DNS whenever the HLE network libraries look up a domain,
+//! so games can be pointed from official servers that have shut down to
+//! community replacements. A rule's host is either a domain, matched
+//! exactly, or `*.` and a domain, matched by the domain and every
+//! subdomain. Exact rules win over wildcards, and the first of several
+//! matching rules of the same kind wins.
+
+use oc_core::config::HostOverride;
+use std::borrow::Cow;
+use std::net::{IpAddr, ToSocketAddrs};
+use tracing::{debug, warn};
+
+/// Domain redirections of the running title
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostTable {
+    /// Lowercase host patterns with their replacements; incomplete rules are dropped
+    rules: Vec<(String, String)>,
+}
+
+impl HostTable {
+    /// Table redirecting nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Table of the network settings' overrides
+    pub fn from_overrides(overrides: &[HostOverride]) -> Self {
+        let rules = overrides
+            .iter()
+            .map(|rule| (rule.host.trim().trim_end_matches('.').to_ascii_lowercase(), rule.address.trim().to_string()))
+            .filter(|(host, address)| !host.is_empty() && !address.is_empty())
+            .collect();
+        Self { rules }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Replacement for `host`, an IP address or a domain, if a rule matches
+    pub fn lookup(&self, host: &str) -> Option<&str> {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        let exact = self.rules.iter().find(|(pattern, _)| *pattern == host);
+        let rule = exact.or_else(|| {
+            self.rules.iter().find(|(pattern, _)| {
+                pattern.strip_prefix("*.").is_some_and(|domain| {
+                    host == domain || host.strip_suffix(domain).is_some_and(|sub| sub.ends_with('.'))
+                })
+            })
+        });
+        rule.map(|(_, address)| address.as_str())
+    }
+
+    /// Address `host` resolves to, if a rule matches
+    ///
+    /// A rule naming a domain rather than an address is looked up on the
+    /// host; None when that lookup fails, so the caller reports the name as
+    /// unknown rather than reaching the official server.
+    pub fn resolve(&self, host: &str) -> Option<IpAddr> {
+        let address = self.lookup(host)?;
+        if let Ok(ip) = address.parse() {
+            debug!("Resolving {} as {}", host, ip);
+            return Some(ip);
+        }
+        match (address, 0).to_socket_addrs().map(|mut addrs| addrs.next()) {
+            Ok(Some(addr)) => {
+                debug!("Resolving {} as {} ({})", host, address, addr.ip());
+                Some(addr.ip())
+            }
+            Ok(None) | Err(_) => {
+                warn!("Failed to resolve {}, the replacement for {}", address, host);
+                None
+            }
+        }
+    }
+
+    /// `url` with its host replaced when a rule matches
+    ///
+    /// The scheme, user, port, path and query are kept.
+    pub fn redirect_url<'a>(&self, url: &'a str) -> Cow<'a, str> {
+        let (start, host_len, _) = host_span(url);
+        let host = &url[start..start + host_len];
+        let Some(address) = self.lookup(host.trim_start_matches('[').trim_end_matches(']')) else {
+            return Cow::Borrowed(url);
+        };
+        let address = if address.contains(':') { format!("[{}]", address) } else { address.to_string() };
+        debug!("Redirecting {} to {}", host, address);
+        Cow::Owned(format!("{}{}{}", &url[..start], address, &url[start + host_len..]))
+    }
+}
+
+/// Start of the host in `url`, its length, and the length with the port
+fn host_span(url: &str) -> (usize, usize, usize) {
+    let authority_start = url.find("://").map_or(0, |i| i + 3);
+    let rest = &url[authority_start..];
+    let authority = &rest[..rest.find(['/', '?', '#']).unwrap_or(rest.len())];
+    let host_start = authority.rfind('@').map_or(0, |i| i + 1);
+    let host_port = &authority[host_start..];
+    let host_len = if host_port.starts_with('[') {
+        host_port.find(']').map_or(host_port.len(), |i| i + 1)
+    } else {
+        host_port.find(':').unwrap_or(host_port.len())
+    };
+    (authority_start + host_start, host_len, host_port.len())
+}
+
+/// Host of `url`, without brackets around an IPv6 address
+pub fn url_host(url: &str) -> &str {
+    let (start, host_len, _) = host_span(url);
+    url[start..start + host_len].trim_start_matches('[').trim_end_matches(']')
+}
+
+/// Host and port of `url`, as a Host header names them
+pub fn url_host_port(url: &str) -> &str {
+    let (start, _, host_port_len) = host_span(url);
+    &url[start..start + host_port_len]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(host: &str, address: &str) -> HostOverride {
+        HostOverride { host: host.to_string(), address: address.to_string() }
+    }
+
+    #[test]
+    fn test_host_matching() {
+        let table = HostTable::from_overrides(&[
+            rule("*.game.example.com", "revival.example.org"),
+            rule("Auth.Game.Example.com.", "10.0.0.2"),
+            rule("", "10.0.0.3"),
+            rule("stats.example.com", " "),
+        ]);
+        assert_eq!(table.lookup("auth.game.example.com"), Some("10.0.0.2"));
+        assert_eq!(table.lookup("MATCH.game.example.com"), Some("revival.example.org"));
+        assert_eq!(table.lookup("game.example.com"), Some("revival.example.org"));
+        assert_eq!(table.lookup("othergame.example.com"), None);
+        assert_eq!(table.lookup("stats.example.com"), None);
+        assert_eq!(table.resolve("auth.game.example.com"), Some(IpAddr::from([10, 0, 0, 2])));
+        assert_eq!(HostTable::new().lookup("auth.game.example.com"), None);
+    }
+
+    #[test]
+    fn test_redirect_url() {
+        let table = HostTable::from_overrides(&[rule("*.example.com", "10.0.0.1"), rule("v6.example.net", "::1")]);
+        assert_eq!(table.redirect_url("https://auth.example.com:8443/login?x=1"), "https://10.0.0.1:8443/login?x=1");
+        assert_eq!(table.redirect_url("http://user@example.com"), "http://user@10.0.0.1");
+        assert_eq!(table.redirect_url("http://v6.example.net/"), "http://[::1]/");
+        assert!(matches!(table.redirect_url("http://example.org/a.example.com"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_url_host() {
+        assert_eq!(url_host("https://user@auth.example.com:8443/login"), "auth.example.com");
+        assert_eq!(url_host_port("https://user@auth.example.com:8443/login"), "auth.example.com:8443");
+        assert_eq!(url_host("http://[::1]:80/"), "::1");
+        assert_eq!(url_host_port("http://example.com?q=a:b"), "example.com");
+    }
+}
@@ -0,0 +1,508 @@
+//! sys_net HLE - Sockets and Name Resolution
+//!
+//! This module provides HLE implementations for the PS3 network library's
+//! host lookups and TCP sockets. Names are looked up in the title's
+//! [`HostTable`] first, so redirected domains resolve to their replacement,
+//! then parsed as dotted-quad addresses, then resolved by the host's DNS.
+//!
+//! Sockets are host TCP streams that go through the title's [`NetCapture`]:
+//! when recording, each message is saved with the reply received before the
+//! next one, and when replaying, messages are answered from the recording
+//! without connecting at all.
+
+use crate::context::{guest_memory, read_guest_string};
+use crate::net_capture::NetCapture;
+use crate::net_hosts::HostTable;
+use oc_core::config::NetCaptureMode;
+use oc_memory::BeValue;
+use std::collections::{HashMap, VecDeque};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use tracing::{debug, trace};
+
+/// Address family of IPv4 addresses
+pub const AF_INET: i32 = 2;
+
+/// Socket type of TCP streams
+pub const SOCK_STREAM: i32 = 1;
+
+/// Error numbers, returned negated
+pub const SYS_NET_EBADF: i32 = 9;
+pub const SYS_NET_EFAULT: i32 = 14;
+pub const SYS_NET_EINVAL: i32 = 22;
+pub const SYS_NET_EWOULDBLOCK: i32 = 35;
+pub const SYS_NET_EISCONN: i32 = 56;
+pub const SYS_NET_ENOTCONN: i32 = 57;
+pub const SYS_NET_EPROTONOSUPPORT: i32 = 43;
+pub const SYS_NET_EAFNOSUPPORT: i32 = 47;
+pub const SYS_NET_ECONNREFUSED: i32 = 61;
+
+/// Longest host name read from guest memory
+const MAX_HOST_NAME: u32 = 255;
+
+/// How long connecting or waiting for a reply may hold the game up
+const SOCKET_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// First socket descriptor handed out
+const FIRST_SOCKET: i32 = 3;
+
+/// IPv4 socket address passed by the guest (struct sockaddr_in)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, BeValue)]
+pub struct SysNetSockaddrIn {
+    pub sin_len: u8,
+    pub sin_family: u8,
+    pub sin_port: u16,
+    pub sin_addr: u32,
+    pub sin_zero: [u8; 8],
+}
+
+/// Host entry returned to the guest (struct hostent)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, BeValue)]
+pub struct SysNetHostent {
+    /// Address of the NUL-terminated official name
+    pub h_name: u32,
+    /// Address of the NULL-terminated alias list
+    pub h_aliases: u32,
+    pub h_addrtype: i32,
+    pub h_length: i32,
+    /// Address of the NULL-terminated address list
+    pub h_addr_list: u32,
+}
+
+// Layout of the hostent buffer, which is reused by every lookup like libc's
+const HOSTENT_SIZE: u32 = 0x1000;
+const HOSTENT_ALIASES: u32 = 0x20;
+const HOSTENT_ADDR_LIST: u32 = 0x28;
+const HOSTENT_ADDR: u32 = 0x30;
+const HOSTENT_NAME: u32 = 0x40;
+
+/// TCP socket opened by the guest
+#[derive(Debug, Default)]
+struct Socket {
+    /// `host:port` once connected
+    endpoint: Option<String>,
+    /// Host connection, absent when replaying
+    stream: Option<TcpStream>,
+    /// Replayed reply not yet received by the game
+    reply: VecDeque<u8>,
+    /// Message sent last and the reply received since, while recording
+    exchange: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+/// Socket and name resolution manager
+#[derive(Debug, Default)]
+pub struct SysNetManager {
+    /// Domains redirected to other servers
+    hosts: HostTable,
+    /// Guest address of the hostent buffer, 0 until the first lookup
+    hostent_addr: u32,
+    /// Names looked up by the game, by the address they resolved to
+    ///
+    /// Recordings name endpoints after the server rather than the address,
+    /// which changes between sessions.
+    names: HashMap<Ipv4Addr, String>,
+    /// Open sockets by descriptor
+    sockets: HashMap<i32, Socket>,
+    /// Next socket descriptor to hand out
+    next_socket: i32,
+}
+
+impl SysNetManager {
+    /// Create a new name resolution manager
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolve the domains in `hosts` to their replacements
+    pub fn set_hosts(&mut self, hosts: HostTable) {
+        self.hosts = hosts;
+    }
+
+    /// Resolve `name` to an IPv4 address, remembering the name for the sockets connected to it
+    pub fn lookup(&mut self, name: &str) -> Option<Ipv4Addr> {
+        let address = self.resolve(name)?;
+        self.names.insert(address, name.to_string());
+        Some(address)
+    }
+
+    /// Resolve `name` to an IPv4 address
+    pub fn resolve(&self, name: &str) -> Option<Ipv4Addr> {
+        if let Some(address) = self.hosts.resolve(name) {
+            return match address {
+                IpAddr::V4(address) => Some(address),
+                IpAddr::V6(_) => {
+                    debug!("sys_net: {} is redirected to an IPv6 address", name);
+                    None
+                }
+            };
+        }
+        if let Ok(address) = name.parse() {
+            return Some(address);
+        }
+        (name, 0)
+            .to_socket_addrs()
+            .ok()?
+            .find_map(|address| match address.ip() {
+                IpAddr::V4(address) => Some(address),
+                IpAddr::V6(_) => None,
+            })
+    }
+
+    /// Open a socket, returning its descriptor
+    pub fn socket(&mut self, family: i32, socket_type: i32) -> Result<i32, i32> {
+        if family != AF_INET {
+            return Err(SYS_NET_EAFNOSUPPORT);
+        }
+        if socket_type != SOCK_STREAM {
+            return Err(SYS_NET_EPROTONOSUPPORT);
+        }
+        let fd = self.next_socket.max(FIRST_SOCKET);
+        self.next_socket = fd + 1;
+        self.sockets.insert(fd, Socket::default());
+        Ok(fd)
+    }
+
+    fn socket_mut(&mut self, fd: i32) -> Result<&mut Socket, i32> {
+        self.sockets.get_mut(&fd).ok_or(SYS_NET_EBADF)
+    }
+
+    /// Connect a socket to `address`
+    ///
+    /// Replayed sockets connect without going online.
+    pub fn connect(&mut self, capture: &NetCapture, fd: i32, address: SocketAddr) -> Result<(), i32> {
+        let endpoint = match address.ip() {
+            IpAddr::V4(ip) => format!("{}:{}", self.names.get(&ip).cloned().unwrap_or(ip.to_string()), address.port()),
+            IpAddr::V6(_) => return Err(SYS_NET_EAFNOSUPPORT),
+        };
+        let socket = self.socket_mut(fd)?;
+        if socket.endpoint.is_some() {
+            return Err(SYS_NET_EISCONN);
+        }
+        debug!("sys_net: socket {} connecting to {}", fd, endpoint);
+
+        if capture.mode() != NetCaptureMode::Replay {
+            let stream = TcpStream::connect_timeout(&address, SOCKET_TIMEOUT).map_err(|e| {
+                debug!("sys_net: connecting to {} failed: {}", endpoint, e);
+                SYS_NET_ECONNREFUSED
+            })?;
+            let _ = stream.set_read_timeout(Some(SOCKET_TIMEOUT));
+            socket.stream = Some(stream);
+        }
+        socket.endpoint = Some(endpoint);
+        Ok(())
+    }
+
+    /// Send `data` on a socket, returning the bytes sent
+    pub fn send(&mut self, capture: &mut NetCapture, fd: i32, data: &[u8]) -> Result<usize, i32> {
+        let socket = self.socket_mut(fd)?;
+        let endpoint = socket.endpoint.clone().ok_or(SYS_NET_ENOTCONN)?;
+        if capture.mode() == NetCaptureMode::Replay {
+            socket.reply.extend(capture.replay_socket(&endpoint, data).unwrap_or_default());
+            return Ok(data.len());
+        }
+
+        let stream = socket.stream.as_mut().ok_or(SYS_NET_ENOTCONN)?;
+        stream.write_all(data).map_err(|e| {
+            debug!("sys_net: sending to {} failed: {}", endpoint, e);
+            SYS_NET_ENOTCONN
+        })?;
+        if capture.mode() == NetCaptureMode::Record {
+            if let Some((sent, received)) = socket.exchange.replace((data.to_vec(), Vec::new())) {
+                capture.record_socket(&endpoint, &sent, &received);
+            }
+        }
+        Ok(data.len())
+    }
+
+    /// Receive up to `len` bytes from a socket; an empty result means the peer closed it
+    pub fn recv(&mut self, fd: i32, len: usize) -> Result<Vec<u8>, i32> {
+        let socket = self.socket_mut(fd)?;
+        if socket.endpoint.is_none() {
+            return Err(SYS_NET_ENOTCONN);
+        }
+        let Some(stream) = socket.stream.as_mut() else {
+            let len = len.min(socket.reply.len());
+            return Ok(socket.reply.drain(..len).collect());
+        };
+
+        let mut data = vec![0; len];
+        let read = match stream.read(&mut data) {
+            Ok(read) => read,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                return Err(SYS_NET_EWOULDBLOCK);
+            }
+            Err(_) => 0,
+        };
+        data.truncate(read);
+        if let Some((_, received)) = socket.exchange.as_mut() {
+            received.extend_from_slice(&data);
+        }
+        Ok(data)
+    }
+
+    /// Close a socket, saving its last exchange when recording
+    pub fn close(&mut self, capture: &mut NetCapture, fd: i32) -> Result<(), i32> {
+        let socket = self.sockets.remove(&fd).ok_or(SYS_NET_EBADF)?;
+        if let (Some(endpoint), Some((sent, received))) = (socket.endpoint, socket.exchange) {
+            capture.record_socket(&endpoint, &sent, &received);
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Public API functions (called from HLE dispatcher)
+// ============================================================================
+
+/// gethostbyname - Look up a host by name
+///
+/// # Arguments
+/// * `name_addr` - Address of the NUL-terminated host name
+///
+/// # Returns
+/// * Address of the host entry, or 0 if the name does not resolve
+pub fn sys_net_gethostbyname(name_addr: u32) -> u32 {
+    let Some(name) = read_guest_string(name_addr, MAX_HOST_NAME) else {
+        return 0;
+    };
+    trace!("gethostbyname(name={})", name);
+
+    let net = &mut crate::context::get_hle_context_mut().sys_net;
+    let Some(address) = net.lookup(&name) else {
+        debug!("gethostbyname: {} does not resolve", name);
+        return 0;
+    };
+    let Some(memory) = guest_memory() else {
+        return 0;
+    };
+    if net.hostent_addr == 0 {
+        match memory.allocate(HOSTENT_SIZE, HOSTENT_SIZE, oc_memory::PageFlags::RW) {
+            Ok(addr) => net.hostent_addr = addr,
+            Err(_) => return 0,
+        }
+    }
+
+    let base = net.hostent_addr;
+    let hostent = SysNetHostent {
+        h_name: base + HOSTENT_NAME,
+        h_aliases: base + HOSTENT_ALIASES,
+        h_addrtype: AF_INET,
+        h_length: 4,
+        h_addr_list: base + HOSTENT_ADDR_LIST,
+    };
+    let mut name_bytes = name.into_bytes();
+    name_bytes.push(0);
+    let written = memory
+        .write_be(base, hostent)
+        .and_then(|_| memory.write_be32(base + HOSTENT_ALIASES, 0))
+        .and_then(|_| memory.write_be32(base + HOSTENT_ADDR_LIST, base + HOSTENT_ADDR))
+        .and_then(|_| memory.write_be32(base + HOSTENT_ADDR_LIST + 4, 0))
+        .and_then(|_| memory.write_bytes(base + HOSTENT_ADDR, &address.octets()))
+        .and_then(|_| memory.write_bytes(base + HOSTENT_NAME, &name_bytes));
+    if written.is_err() {
+        return 0;
+    }
+    debug!("gethostbyname: resolved to {}", address);
+    base
+}
+
+/// socket - Open a socket
+///
+/// # Arguments
+/// * `family` - Address family, AF_INET
+/// * `socket_type` - Socket type, SOCK_STREAM
+/// * `protocol` - Protocol, 0 for TCP
+///
+/// # Returns
+/// * Socket descriptor, or a negated SYS_NET_E* error
+pub fn sys_net_socket(family: i32, socket_type: i32, protocol: i32) -> i32 {
+    trace!("socket(family={}, type={}, protocol={})", family, socket_type, protocol);
+
+    crate::context::get_hle_context_mut().sys_net.socket(family, socket_type).unwrap_or_else(|e| -e)
+}
+
+/// connect - Connect a socket
+///
+/// # Arguments
+/// * `fd` - Socket descriptor
+/// * `addr` - Address of the server's sockaddr_in
+/// * `addr_len` - Size of the address
+///
+/// # Returns
+/// * 0 on success, or a negated SYS_NET_E* error
+pub fn sys_net_connect(fd: i32, addr: u32, addr_len: u32) -> i32 {
+    if addr == 0 || (addr_len as usize) < std::mem::size_of::<SysNetSockaddrIn>() {
+        return -SYS_NET_EINVAL;
+    }
+    let Some(sockaddr) = guest_memory().and_then(|memory| memory.read_be::<SysNetSockaddrIn>(addr).ok()) else {
+        return -SYS_NET_EFAULT;
+    };
+    if sockaddr.sin_family as i32 != AF_INET {
+        return -SYS_NET_EAFNOSUPPORT;
+    }
+    let address = SocketAddr::from((Ipv4Addr::from(sockaddr.sin_addr), sockaddr.sin_port));
+    trace!("connect(fd={}, addr={})", fd, address);
+
+    let ctx = &mut *crate::context::get_hle_context_mut();
+    match ctx.sys_net.connect(ctx.http.capture(), fd, address) {
+        Ok(()) => 0,
+        Err(e) => -e,
+    }
+}
+
+/// send - Send a message on a connected socket
+///
+/// # Arguments
+/// * `fd` - Socket descriptor
+/// * `buf` - Address of the message
+/// * `len` - Size of the message
+/// * `flags` - Send flags
+///
+/// # Returns
+/// * Bytes sent, or a negated SYS_NET_E* error
+pub fn sys_net_send(fd: i32, buf: u32, len: u32, flags: i32) -> i32 {
+    trace!("send(fd={}, len={}, flags=0x{:X})", fd, len, flags);
+
+    let Some(data) = guest_memory().and_then(|memory| memory.read_bytes(buf, len).ok()) else {
+        return -SYS_NET_EFAULT;
+    };
+    let ctx = &mut *crate::context::get_hle_context_mut();
+    match ctx.sys_net.send(ctx.http.capture_mut(), fd, &data) {
+        Ok(sent) => sent as i32,
+        Err(e) => -e,
+    }
+}
+
+/// recv - Receive from a connected socket
+///
+/// # Arguments
+/// * `fd` - Socket descriptor
+/// * `buf` - Address of the buffer
+/// * `len` - Size of the buffer
+/// * `flags` - Receive flags
+///
+/// # Returns
+/// * Bytes received, 0 once the peer closed the connection, or a negated SYS_NET_E* error
+pub fn sys_net_recv(fd: i32, buf: u32, len: u32, flags: i32) -> i32 {
+    trace!("recv(fd={}, len={}, flags=0x{:X})", fd, len, flags);
+
+    let Some(memory) = guest_memory() else {
+        return -SYS_NET_EFAULT;
+    };
+    let data = match crate::context::get_hle_context_mut().sys_net.recv(fd, len as usize) {
+        Ok(data) => data,
+        Err(e) => return -e,
+    };
+    match memory.write_bytes(buf, &data) {
+        Ok(()) => data.len() as i32,
+        Err(_) => -SYS_NET_EFAULT,
+    }
+}
+
+/// socketclose - Close a socket
+///
+/// # Arguments
+/// * `fd` - Socket descriptor
+///
+/// # Returns
+/// * 0 on success, or a negated SYS_NET_E* error
+pub fn sys_net_socketclose(fd: i32) -> i32 {
+    trace!("socketclose(fd={})", fd);
+
+    let ctx = &mut *crate::context::get_hle_context_mut();
+    match ctx.sys_net.close(ctx.http.capture_mut(), fd) {
+        Ok(()) => 0,
+        Err(e) => -e,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oc_core::config::HostOverride;
+
+    #[test]
+    fn test_resolve() {
+        let mut net = SysNetManager::new();
+        net.set_hosts(HostTable::from_overrides(&[
+            HostOverride { host: "*.example.com".to_string(), address: "10.0.0.5".to_string() },
+            HostOverride { host: "v6.example.org".to_string(), address: "::1".to_string() },
+        ]));
+
+        assert_eq!(net.resolve("auth.example.com"), Some(Ipv4Addr::new(10, 0, 0, 5)));
+        assert_eq!(net.resolve("192.168.1.20"), Some(Ipv4Addr::new(192, 168, 1, 20)));
+        assert_eq!(net.resolve("v6.example.org"), None);
+    }
+
+    /// Guest sockaddr_in for `port` on 127.0.0.1
+    fn write_sockaddr(memory: &oc_memory::MemoryManager, addr: u32, port: u16) {
+        let sockaddr = SysNetSockaddrIn {
+            sin_len: 16,
+            sin_family: AF_INET as u8,
+            sin_port: port,
+            sin_addr: u32::from(Ipv4Addr::LOCALHOST),
+            sin_zero: [0; 8],
+        };
+        memory.write_be(addr, sockaddr).unwrap();
+    }
+
+    #[test]
+    fn test_socket_capture() {
+        let _guard = crate::context::test_guard();
+        crate::context::reset_hle_context();
+        let memory = crate::context::test_guest_memory();
+        let addr = memory.allocate(0x100, 0x10, oc_memory::PageFlags::RW).unwrap();
+        let (sockaddr_addr, buf) = (addr, addr + 0x20);
+        let path = std::env::temp_dir().join(format!("oc_sys_net_capture_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        // A server that answers "hello" with "welcome"
+        let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut hello = [0; 5];
+            stream.read_exact(&mut hello).unwrap();
+            stream.write_all(b"welcome").unwrap();
+        });
+
+        let capture = NetCapture::open(NetCaptureMode::Record, path.clone()).unwrap();
+        crate::context::get_hle_context_mut().http.set_capture(capture);
+        assert_eq!(sys_net_socket(AF_INET, 2, 0), -SYS_NET_EPROTONOSUPPORT);
+        let fd = sys_net_socket(AF_INET, SOCK_STREAM, 0);
+        assert!(fd >= FIRST_SOCKET);
+        write_sockaddr(&memory, sockaddr_addr, port);
+        assert_eq!(sys_net_send(fd, buf, 5, 0), -SYS_NET_ENOTCONN);
+        assert_eq!(sys_net_connect(fd, sockaddr_addr, 16), 0);
+        memory.write_bytes(buf, b"hello").unwrap();
+        assert_eq!(sys_net_send(fd, buf, 5, 0), 5);
+        assert_eq!(sys_net_recv(fd, buf, 0x40, 0), 7);
+        assert_eq!(memory.read_bytes(buf, 7).unwrap(), b"welcome");
+        server.join().unwrap();
+        assert_eq!(sys_net_socketclose(fd), 0);
+        assert_eq!(sys_net_socketclose(fd), -SYS_NET_EBADF);
+
+        // Replaying answers without the server
+        let capture = NetCapture::open(NetCaptureMode::Replay, path.clone()).unwrap();
+        assert_eq!(capture.file().sockets[0].endpoint, format!("127.0.0.1:{}", port));
+        crate::context::get_hle_context_mut().http.set_capture(capture);
+        let fd = sys_net_socket(AF_INET, SOCK_STREAM, 0);
+        assert_eq!(sys_net_connect(fd, sockaddr_addr, 16), 0);
+        memory.write_bytes(buf, b"hello").unwrap();
+        assert_eq!(sys_net_send(fd, buf, 5, 0), 5);
+        assert_eq!(sys_net_recv(fd, buf, 3, 0), 3);
+        assert_eq!(sys_net_recv(fd, buf + 3, 0x40, 0), 4);
+        assert_eq!(memory.read_bytes(buf, 7).unwrap(), b"welcome");
+        assert_eq!(sys_net_recv(fd, buf, 0x40, 0), 0);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_hostent_layout() {
+        assert_eq!(std::mem::size_of::<SysNetHostent>(), 20);
+        assert!(HOSTENT_ALIASES as usize >= std::mem::size_of::<SysNetHostent>());
+    }
+}
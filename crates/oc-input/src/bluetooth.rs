@@ -4,7 +4,9 @@
 //! - DualShock 3 Bluetooth pairing
 //! - Generic Bluetooth HID devices
 //! - Connection state management
+//! - Pairings of the emulated controllers, for games that list Bluetooth devices
 
+use crate::pad::MAX_PADS;
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
@@ -53,6 +55,24 @@ impl BluetoothAddress {
         )
     }
 
+    /// Stable address of an emulated controller
+    ///
+    /// Uses a Sony OUI, then the device type and index, so the same
+    /// controller keeps its address between runs.
+    pub fn emulated(device_type: BluetoothDeviceType, index: u8) -> Self {
+        let kind = match device_type {
+            BluetoothDeviceType::DualShock3 => 0x03,
+            BluetoothDeviceType::MoveController => 0x0E,
+            _ => 0xFF,
+        };
+        Self([0x00, 0x19, 0xC1, 0x00, kind, index])
+    }
+
+    /// The six address bytes
+    pub fn bytes(&self) -> [u8; 6] {
+        self.0
+    }
+
     /// Generate a random address (for mock devices)
     pub fn random() -> Self {
         use std::time::SystemTime;
@@ -95,6 +115,8 @@ pub enum BluetoothDeviceType {
     DualShock3,
     /// DualShock 4 controller
     DualShock4,
+    /// PlayStation Move motion controller
+    MoveController,
     /// Generic HID gamepad
     GenericGamepad,
     /// Keyboard
@@ -160,6 +182,9 @@ impl BluetoothDevice {
         if name_lower.contains("wireless controller") || name_lower.contains("dualshock 4") {
             return BluetoothDeviceType::DualShock4;
         }
+        if name_lower.contains("motion controller") {
+            return BluetoothDeviceType::MoveController;
+        }
         
         // Fall back to device class
         match device_class & 0x00FF00 {
@@ -188,6 +213,7 @@ impl BluetoothDevice {
             self.device_type,
             BluetoothDeviceType::DualShock3 |
             BluetoothDeviceType::DualShock4 |
+            BluetoothDeviceType::MoveController |
             BluetoothDeviceType::GenericGamepad
         )
     }
@@ -531,6 +557,47 @@ impl BluetoothManager {
         self.adapter.paired_devices.insert(address, device);
         Ok(address)
     }
+
+    /// Pair the emulated controllers and track which are connected
+    ///
+    /// Every port in `pad_mask` is a DualShock 3 and every controller in
+    /// `move_mask` a Move. Like on a console, controllers stay paired
+    /// after they disconnect. Returns whether anything changed.
+    pub fn sync_emulated(&mut self, pad_mask: u8, move_mask: u8) -> bool {
+        let mut changed = false;
+        let controllers = [
+            (BluetoothDeviceType::DualShock3, "PLAYSTATION(R)3 Controller", pad_mask, MAX_PADS as u8),
+            (BluetoothDeviceType::MoveController, "Motion Controller", move_mask, 4),
+        ];
+        for (device_type, name, mask, count) in controllers {
+            for index in 0..count {
+                let connected = mask & (1 << index) != 0;
+                let address = BluetoothAddress::emulated(device_type, index);
+                let state = if connected {
+                    BluetoothState::Connected
+                } else {
+                    BluetoothState::Disconnected
+                };
+                match self.adapter.paired_devices.get_mut(&address) {
+                    Some(device) if device.state != state => {
+                        device.state = state;
+                        changed = true;
+                    }
+                    Some(_) => {}
+                    None if connected => {
+                        let mut device = BluetoothDevice::new(address, name.to_string(), device_class::GAMEPAD);
+                        device.paired = true;
+                        device.trusted = true;
+                        device.state = state;
+                        self.adapter.paired_devices.insert(address, device);
+                        changed = true;
+                    }
+                    None => {}
+                }
+            }
+        }
+        changed
+    }
 }
 
 impl Default for BluetoothManager {
@@ -606,6 +673,25 @@ mod tests {
         assert!(adapter.get_paired_devices().iter().any(|d| d.address == address));
     }
 
+    #[test]
+    fn test_emulated_controllers_stay_paired() {
+        let mut manager = BluetoothManager::new();
+        assert!(manager.sync_emulated(0b101, 0b1));
+        assert!(!manager.sync_emulated(0b101, 0b1));
+        assert_eq!(manager.adapter().get_connected_gamepads().len(), 3);
+
+        let ds3 = BluetoothAddress::emulated(BluetoothDeviceType::DualShock3, 2);
+        assert_eq!(ds3.to_string(), "00:19:C1:00:03:02");
+        let move_address = BluetoothAddress::emulated(BluetoothDeviceType::MoveController, 0);
+        let device = manager.adapter().get_device(&move_address).unwrap();
+        assert_eq!(device.device_type, BluetoothDeviceType::MoveController);
+
+        // Port 3 unplugged: still paired, no longer connected
+        assert!(manager.sync_emulated(0b001, 0b1));
+        assert_eq!(manager.adapter().get_paired_devices().len(), 3);
+        assert_eq!(manager.adapter().get_device(&ds3).unwrap().state, BluetoothState::Disconnected);
+    }
+
     #[test]
     fn test_quick_pair() {
         let mut manager = BluetoothManager::new();
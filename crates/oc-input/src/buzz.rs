@@ -0,0 +1,137 @@
+//! Virtual Buzz! buzzers
+//!
+//! Emulates the wired four-player Buzz! controller for players without
+//! one. Each buzzer is played on a controller port: R1 is the big red
+//! button and the face buttons are the four colored ones. Buzz! games talk
+//! to the controller with their own USB driver, so this only builds the HID
+//! reports; [`crate::usb::hid_device_descriptor`] and
+//! [`crate::usb::hid_configuration_descriptor`] describe the device.
+
+use crate::pad::{PadButtons, PadState};
+use crate::usb::{passthrough_devices, UsbDeviceInfo};
+
+/// Buzzers on one controller
+pub const BUZZERS: usize = 4;
+
+/// Device release (bcdDevice) of the wired controller
+pub const BUZZ_RELEASE: u16 = 0x05A1;
+
+/// HID report descriptor: two axes, 20 buttons, 4 padding bits and
+/// a 7-byte LED output report
+pub const BUZZ_REPORT_DESCRIPTOR: [u8; 54] = [
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x04, // Usage (Joystick)
+    0xA1, 0x01, // Collection (Application)
+    0x75, 0x08, //   Report Size (8)
+    0x95, 0x02, //   Report Count (2)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x26, 0xFF, 0x00, //   Logical Maximum (255)
+    0x09, 0x30, //   Usage (X)
+    0x09, 0x31, //   Usage (Y)
+    0x81, 0x02, //   Input (Data, Variable, Absolute)
+    0x75, 0x01, //   Report Size (1)
+    0x95, 0x14, //   Report Count (20)
+    0x25, 0x01, //   Logical Maximum (1)
+    0x05, 0x09, //   Usage Page (Button)
+    0x19, 0x01, //   Usage Minimum (1)
+    0x29, 0x14, //   Usage Maximum (20)
+    0x81, 0x02, //   Input (Data, Variable, Absolute)
+    0x95, 0x04, //   Report Count (4)
+    0x81, 0x01, //   Input (Constant)
+    0x06, 0x00, 0xFF, //   Usage Page (Vendor)
+    0x75, 0x08, //   Report Size (8)
+    0x95, 0x07, //   Report Count (7)
+    0x26, 0xFF, 0x00, //   Logical Maximum (255)
+    0x09, 0x01, //   Usage (1)
+    0x91, 0x02, //   Output (Data, Variable, Absolute)
+    0xC0, // End Collection
+];
+
+/// Buttons of one buzzer, in report bit order, with the pad button playing each
+const BUTTONS: [PadButtons; 5] = [
+    PadButtons::R1,       // red
+    PadButtons::SQUARE,   // yellow
+    PadButtons::CROSS,    // green
+    PadButtons::CIRCLE,   // orange
+    PadButtons::TRIANGLE, // blue
+];
+
+/// Emulated Buzz! controller
+#[derive(Debug, Clone, Default)]
+pub struct VirtualBuzz {
+    /// Whether each buzzer's red button is lit
+    leds: [bool; BUZZERS],
+}
+
+impl VirtualBuzz {
+    /// Create the controller with every light off
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// USB identity of the controller
+    pub fn device_info() -> UsbDeviceInfo {
+        let (vendor_id, product_id) = passthrough_devices::BUZZ;
+        UsbDeviceInfo {
+            vendor_id,
+            product_id,
+            name: "Logitech Buzz(tm) Controller V1".to_string(),
+            manufacturer: "Logitech".to_string(),
+            serial: None,
+        }
+    }
+
+    /// Input report for the pads playing each buzzer (None = not connected)
+    pub fn input_report(pads: &[Option<PadState>; BUZZERS]) -> [u8; 5] {
+        // Both axes rest at 0x7F and the padding bits read as set
+        let mut report = [0x7F, 0x7F, 0x00, 0x00, 0xF0];
+        for (buzzer, pad) in pads.iter().enumerate() {
+            let Some(pad) = pad else {
+                continue;
+            };
+            for (i, &button) in BUTTONS.iter().enumerate() {
+                if pad.is_button_pressed(button) {
+                    let bit = buzzer * BUTTONS.len() + i;
+                    report[2 + bit / 8] |= 1 << (bit % 8);
+                }
+            }
+        }
+        report
+    }
+
+    /// Apply an output report: a zero byte then one byte per buzzer light
+    pub fn set_leds(&mut self, report: &[u8]) {
+        for (buzzer, led) in self.leds.iter_mut().enumerate() {
+            if let Some(&value) = report.get(buzzer + 1) {
+                *led = value != 0;
+            }
+        }
+    }
+
+    /// Whether each buzzer's light is on
+    pub fn leds(&self) -> [bool; BUZZERS] {
+        self.leds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buzz_reports() {
+        let mut red = PadState::new();
+        red.set_button(PadButtons::R1, true);
+        let mut blue = PadState::new();
+        blue.set_button(PadButtons::TRIANGLE, true);
+
+        assert_eq!(VirtualBuzz::input_report(&[None, None, None, None]), [0x7F, 0x7F, 0, 0, 0xF0]);
+        // Buzzer 1 red is bit 0, buzzer 2 blue bit 9, buzzer 4 red bit 15
+        let report = VirtualBuzz::input_report(&[Some(red.clone()), Some(blue), None, Some(red)]);
+        assert_eq!(report, [0x7F, 0x7F, 0x01, 0x82, 0xF0]);
+
+        let mut buzz = VirtualBuzz::new();
+        buzz.set_leds(&[0x00, 0xFF, 0x00, 0x00, 0xFF, 0x00, 0x00]);
+        assert_eq!(buzz.leds(), [true, false, false, true]);
+    }
+}
@@ -0,0 +1,102 @@
+//! Dead zone and motion drift calibration
+//!
+//! Both are measured with the controller left alone: the largest resting
+//! deflection of each stick sets its dead zone, and the average gyroscope
+//! reading at rest is the drift subtracted from later readings.
+
+use crate::dualshock3::SixaxisData;
+
+/// How long the controller is sampled at rest
+pub const CALIBRATION_SECONDS: f32 = 3.0;
+
+/// Added to the largest resting deflection so worn sticks settle inside the dead zone
+const DEADZONE_MARGIN: f32 = 0.03;
+
+/// Smallest dead zone calibration picks
+const MIN_DEADZONE: f32 = 0.05;
+
+/// Largest dead zone calibration picks
+const MAX_DEADZONE: f32 = 0.5;
+
+/// Samples of a controller at rest
+#[derive(Debug, Clone, Default)]
+pub struct RestCalibration {
+    /// Largest deflection of the left and right sticks
+    stick_max: [f32; 2],
+    stick_samples: u32,
+    /// Sum of the gyroscope X, Y and Z readings
+    gyro_sum: [i64; 3],
+    motion_samples: u32,
+}
+
+impl RestCalibration {
+    /// Start with no samples
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the stick positions, as (x, y) from -1.0 to 1.0
+    pub fn add_sticks(&mut self, left: (f32, f32), right: (f32, f32)) {
+        for (max, (x, y)) in self.stick_max.iter_mut().zip([left, right]) {
+            *max = max.max((x * x + y * y).sqrt());
+        }
+        self.stick_samples += 1;
+    }
+
+    /// Record a motion sensor reading
+    pub fn add_motion(&mut self, sixaxis: &SixaxisData) {
+        for (sum, value) in self.gyro_sum.iter_mut().zip([sixaxis.gyro_x, sixaxis.gyro_y, sixaxis.gyro_z]) {
+            *sum += value as i64;
+        }
+        self.motion_samples += 1;
+    }
+
+    /// Dead zones of the left and right sticks that cover the drift seen (None without samples)
+    pub fn deadzones(&self) -> Option<[f32; 2]> {
+        if self.stick_samples == 0 {
+            return None;
+        }
+        Some(self.stick_max.map(|max| {
+            let deadzone = (max + DEADZONE_MARGIN).clamp(MIN_DEADZONE, MAX_DEADZONE);
+            (deadzone * 100.0).round() / 100.0
+        }))
+    }
+
+    /// Average gyroscope X, Y and Z reading (None without samples)
+    pub fn gyro_drift(&self) -> Option<[i16; 3]> {
+        if self.motion_samples == 0 {
+            return None;
+        }
+        let samples = self.motion_samples as i64;
+        Some(self.gyro_sum.map(|sum| (sum as f64 / samples as f64).round() as i16))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rest_calibration() {
+        let mut calibration = RestCalibration::new();
+        assert_eq!(calibration.deadzones(), None);
+        assert_eq!(calibration.gyro_drift(), None);
+
+        calibration.add_sticks((0.06, 0.08), (0.0, 0.01));
+        calibration.add_sticks((0.0, 0.02), (0.0, 0.0));
+        let mut sixaxis = SixaxisData::at_rest();
+        for gyro in [3, 5, 4] {
+            sixaxis.gyro_x = gyro;
+            sixaxis.gyro_z = -gyro;
+            calibration.add_motion(&sixaxis);
+        }
+
+        // 0.10 resting deflection plus the margin; a steady stick gets the minimum
+        assert_eq!(calibration.deadzones(), Some([0.13, MIN_DEADZONE]));
+        assert_eq!(calibration.gyro_drift(), Some([4, 0, -4]));
+
+        // Badly drifting sticks are capped
+        calibration.add_sticks((0.9, 0.0), (0.0, 0.0));
+        assert_eq!(calibration.deadzones(), Some([MAX_DEADZONE, MIN_DEADZONE]));
+    }
+}
@@ -7,6 +7,7 @@
 //! - Motion games (EyeCreate, EyePet)
 //! - Eye of Judgment (AR card game)
 
+use crate::webcam::WebcamCapture;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
@@ -71,10 +72,12 @@ pub enum CameraPixelFormat {
     BGR24,
     /// YUV420 planar
     YUV420P,
-    /// YUYV packed
+    /// YUYV packed (YUV422)
     YUYV,
     /// Raw Bayer pattern (PS Eye native)
     BayerGB,
+    /// JPEG compressed
+    Jpeg,
 }
 
 /// Camera exposure mode
@@ -179,6 +182,8 @@ impl CameraFrame {
             CameraPixelFormat::BayerGB => {
                 (settings.resolution.width() * settings.resolution.height()) as usize
             }
+            // Compressed frames have no fixed size
+            CameraPixelFormat::Jpeg => 0,
         };
 
         Self {
@@ -193,41 +198,186 @@ impl CameraFrame {
 
     /// Create a test pattern frame
     pub fn test_pattern(settings: &CameraSettings, sequence: u64) -> Self {
-        let mut frame = Self::blank(settings);
+        let width = settings.resolution.width();
+        let height = settings.resolution.height();
+        let bar_width = (width / 8).max(1);
+        let rgb: Vec<u8> = (0..width * height)
+            .flat_map(|i| COLOR_BARS[((i % width) / bar_width).min(7) as usize])
+            .collect();
+
+        let mut frame = Self::from_rgb(&rgb, width, height, settings);
         frame.sequence = sequence;
-        
-        // Generate color bars pattern
-        let width = frame.width as usize;
-        let height = frame.height as usize;
-        
-        if matches!(settings.pixel_format, CameraPixelFormat::RGB24) {
-            let bar_width = width / 8;
-            let colors: [(u8, u8, u8); 8] = [
-                (255, 255, 255), // White
-                (255, 255, 0),   // Yellow
-                (0, 255, 255),   // Cyan
-                (0, 255, 0),     // Green
-                (255, 0, 255),   // Magenta
-                (255, 0, 0),     // Red
-                (0, 0, 255),     // Blue
-                (0, 0, 0),       // Black
-            ];
-            
+        frame
+    }
+
+    /// Convert an RGB24 image to the resolution and pixel format in `settings`
+    ///
+    /// The image is scaled with nearest-neighbour sampling, then flipped and
+    /// color-adjusted as configured.
+    pub fn from_rgb(rgb: &[u8], width: u32, height: u32, settings: &CameraSettings) -> Self {
+        let out_width = settings.resolution.width();
+        let out_height = settings.resolution.height();
+        let mut image = vec![0u8; (out_width * out_height * 3) as usize];
+        if width > 0 && height > 0 && rgb.len() >= (width * height * 3) as usize {
+            for y in 0..out_height {
+                let row = if settings.flip_v { out_height - 1 - y } else { y };
+                let src_y = row * height / out_height;
+                for x in 0..out_width {
+                    let column = if settings.flip_h { out_width - 1 - x } else { x };
+                    let src = ((src_y * width + column * width / out_width) * 3) as usize;
+                    let dst = ((y * out_width + x) * 3) as usize;
+                    image[dst..dst + 3].copy_from_slice(&rgb[src..src + 3]);
+                }
+            }
+        }
+        adjust_colors(&mut image, settings);
+
+        Self {
+            data: encode(&image, out_width, out_height, settings.pixel_format),
+            width: out_width,
+            height: out_height,
+            format: settings.pixel_format,
+            timestamp: Duration::ZERO,
+            sequence: 0,
+        }
+    }
+}
+
+/// White, yellow, cyan, green, magenta, red, blue, black
+const COLOR_BARS: [[u8; 3]; 8] = [
+    [255, 255, 255],
+    [255, 255, 0],
+    [0, 255, 255],
+    [0, 255, 0],
+    [255, 0, 255],
+    [255, 0, 0],
+    [0, 0, 255],
+    [0, 0, 0],
+];
+
+/// JPEG quality of compressed frames
+#[cfg(feature = "jpeg")]
+const JPEG_QUALITY: u8 = 85;
+
+/// BT.601 RGB to studio-range YCbCr
+pub(crate) fn rgb_to_yuv([r, g, b]: [u8; 3]) -> [u8; 3] {
+    let (r, g, b) = (r as i32, g as i32, b as i32);
+    let y = ((66 * r + 129 * g + 25 * b + 128) >> 8) + 16;
+    let u = ((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128;
+    let v = ((112 * r - 94 * g - 18 * b + 128) >> 8) + 128;
+    [y as u8, u as u8, v as u8]
+}
+
+/// BT.601 studio-range YCbCr to RGB
+pub(crate) fn yuv_to_rgb(y: u8, u: u8, v: u8) -> [u8; 3] {
+    let c = 298 * (y as i32 - 16);
+    let (d, e) = (u as i32 - 128, v as i32 - 128);
+    let clamp = |value: i32| ((value + 128) >> 8).clamp(0, 255) as u8;
+    [clamp(c + 409 * e), clamp(c - 100 * d - 208 * e), clamp(c + 516 * d)]
+}
+
+/// Apply brightness, contrast and saturation (128 leaves a channel unchanged)
+fn adjust_colors(image: &mut [u8], settings: &CameraSettings) {
+    let (brightness, contrast, saturation) = (
+        settings.brightness as i32 - 128,
+        settings.contrast as i32,
+        settings.saturation as i32,
+    );
+    if brightness == 0 && contrast == 128 && saturation == 128 {
+        return;
+    }
+    for pixel in image.chunks_exact_mut(3) {
+        let luma = (77 * pixel[0] as i32 + 150 * pixel[1] as i32 + 29 * pixel[2] as i32) >> 8;
+        for channel in pixel.iter_mut() {
+            let value = luma + (*channel as i32 - luma) * saturation / 128;
+            let value = (value - 128) * contrast / 128 + 128 + brightness;
+            *channel = value.clamp(0, 255) as u8;
+        }
+    }
+}
+
+/// Encode an RGB24 image in `format`
+fn encode(image: &[u8], width: u32, height: u32, format: CameraPixelFormat) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let pixel = |x: usize, y: usize| {
+        let i = (y * width + x) * 3;
+        [image[i], image[i + 1], image[i + 2]]
+    };
+    match format {
+        CameraPixelFormat::RGB24 => image.to_vec(),
+        CameraPixelFormat::BGR24 => image.chunks_exact(3).flat_map(|p| [p[2], p[1], p[0]]).collect(),
+        CameraPixelFormat::YUYV => {
+            // Each pair of pixels shares one chroma sample
+            let mut data = Vec::with_capacity(width * height * 2);
+            for y in 0..height {
+                for x in (0..width).step_by(2) {
+                    let [y0, u0, v0] = rgb_to_yuv(pixel(x, y));
+                    let [y1, u1, v1] = rgb_to_yuv(pixel((x + 1).min(width - 1), y));
+                    let u = ((u0 as u16 + u1 as u16) / 2) as u8;
+                    let v = ((v0 as u16 + v1 as u16) / 2) as u8;
+                    data.extend_from_slice(&[y0, u, y1, v]);
+                }
+            }
+            data
+        }
+        CameraPixelFormat::YUV420P => {
+            let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+            let mut data = vec![0u8; width * height + 2 * chroma_width * chroma_height];
+            let (luma, chroma) = data.split_at_mut(width * height);
+            let (u_plane, v_plane) = chroma.split_at_mut(chroma_width * chroma_height);
             for y in 0..height {
                 for x in 0..width {
-                    let bar = (x / bar_width).min(7);
-                    let (r, g, b) = colors[bar];
-                    let idx = (y * width + x) * 3;
-                    if idx + 2 < frame.data.len() {
-                        frame.data[idx] = r;
-                        frame.data[idx + 1] = g;
-                        frame.data[idx + 2] = b;
+                    luma[y * width + x] = rgb_to_yuv(pixel(x, y))[0];
+                }
+            }
+            for cy in 0..chroma_height {
+                for cx in 0..chroma_width {
+                    // Average the chroma of each 2x2 block
+                    let (mut u, mut v, mut count) = (0u32, 0u32, 0u32);
+                    for y in (cy * 2)..(cy * 2 + 2).min(height) {
+                        for x in (cx * 2)..(cx * 2 + 2).min(width) {
+                            let [_, pu, pv] = rgb_to_yuv(pixel(x, y));
+                            u += pu as u32;
+                            v += pv as u32;
+                            count += 1;
+                        }
                     }
+                    u_plane[cy * chroma_width + cx] = (u / count) as u8;
+                    v_plane[cy * chroma_width + cx] = (v / count) as u8;
                 }
             }
+            data
+        }
+        CameraPixelFormat::BayerGB => {
+            // GBRG: even rows are green/blue, odd rows red/green
+            let mut data = Vec::with_capacity(width * height);
+            for y in 0..height {
+                for x in 0..width {
+                    let channel = match (y % 2, x % 2) {
+                        (0, 1) => 2,
+                        (1, 0) => 0,
+                        _ => 1,
+                    };
+                    data.push(pixel(x, y)[channel]);
+                }
+            }
+            data
+        }
+        #[cfg(feature = "jpeg")]
+        CameraPixelFormat::Jpeg => {
+            let mut data = Vec::new();
+            let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut data, JPEG_QUALITY);
+            if let Err(e) = encoder.encode(image, width as u32, height as u32, image::ExtendedColorType::Rgb8) {
+                tracing::warn!("Failed to encode camera frame as JPEG: {}", e);
+                data.clear();
+            }
+            data
+        }
+        #[cfg(not(feature = "jpeg"))]
+        CameraPixelFormat::Jpeg => {
+            tracing::warn!("JPEG camera frames need the jpeg feature");
+            Vec::new()
         }
-        
-        frame
     }
 }
 
@@ -265,6 +415,12 @@ pub struct Camera {
     frame_callback: Option<Arc<Mutex<FrameCallback>>>,
     /// Last frame
     last_frame: Option<CameraFrame>,
+    /// Host webcam frames are taken from (test pattern if None)
+    webcam: Option<WebcamCapture>,
+    /// Sequence number of the last webcam frame converted
+    webcam_sequence: u64,
+    /// Capture time at which the next frame is due
+    next_frame_at: Duration,
 }
 
 impl std::fmt::Debug for Camera {
@@ -275,6 +431,7 @@ impl std::fmt::Debug for Camera {
             .field("settings", &self.settings)
             .field("state", &self.state)
             .field("frame_count", &self.frame_count)
+            .field("webcam", &self.webcam_device())
             .finish()
     }
 }
@@ -290,9 +447,29 @@ impl Camera {
             start_time: None,
             frame_callback: None,
             last_frame: None,
+            webcam: None,
+            webcam_sequence: 0,
+            next_frame_at: Duration::ZERO,
         }
     }
 
+    /// Take frames from a host webcam instead of generating a test pattern
+    pub fn attach_webcam(&mut self, webcam: WebcamCapture) {
+        tracing::info!("Camera {} capturing from \"{}\"", self.index, webcam.device());
+        self.webcam = Some(webcam);
+        self.webcam_sequence = 0;
+    }
+
+    /// Stop using the host webcam and go back to the test pattern
+    pub fn detach_webcam(&mut self) {
+        self.webcam = None;
+    }
+
+    /// Name of the host webcam frames are taken from
+    pub fn webcam_device(&self) -> Option<&str> {
+        self.webcam.as_ref().map(WebcamCapture::device)
+    }
+
     /// Initialize the camera
     pub fn initialize(&mut self) -> Result<(), CameraError> {
         if self.state != CameraState::Uninitialized {
@@ -330,6 +507,7 @@ impl Camera {
         }
 
         self.frame_count = 0;
+        self.next_frame_at = Duration::ZERO;
         self.start_time = Some(Instant::now());
         self.state = CameraState::Capturing;
         
@@ -369,16 +547,38 @@ impl Camera {
     }
 
     /// Poll for next frame (generates test pattern if no real camera)
+    ///
+    /// Frames are delivered at most at the configured framerate, converted
+    /// from the latest webcam image. Returns None until the next frame is due
+    /// or while the webcam has nothing new.
     pub fn poll_frame(&mut self) -> Option<CameraFrame> {
         if self.state != CameraState::Capturing {
             return None;
         }
 
         let elapsed = self.start_time.map(|t| t.elapsed()).unwrap_or_default();
-        
-        // Generate test frame
+        if elapsed < self.next_frame_at {
+            return None;
+        }
+
+        let mut frame = match &self.webcam {
+            Some(webcam) => {
+                let image = webcam.frame_after(self.webcam_sequence)?;
+                self.webcam_sequence = image.sequence;
+                CameraFrame::from_rgb(&image.data, image.width, image.height, &self.settings)
+            }
+            None => CameraFrame::test_pattern(&self.settings, 0),
+        };
+        let fps = self.settings.framerate.clamp(1, self.settings.resolution.max_fps());
+        let interval = Duration::from_secs(1) / fps;
+        self.next_frame_at += interval;
+        if self.next_frame_at < elapsed {
+            // Fell behind; don't try to catch up with a burst of frames
+            self.next_frame_at = elapsed + interval;
+        }
+
         self.frame_count += 1;
-        let mut frame = CameraFrame::test_pattern(&self.settings, self.frame_count);
+        frame.sequence = self.frame_count;
         frame.timestamp = elapsed;
         
         // Call frame callback
@@ -407,6 +607,8 @@ impl Camera {
         if self.state == CameraState::Capturing {
             let _ = self.stop_capture();
         }
+        // Release the host device
+        self.webcam = None;
         self.state = CameraState::Uninitialized;
         tracing::info!("Camera {} shutdown", self.index);
     }
@@ -570,6 +772,55 @@ mod tests {
         assert!(!frame.data.is_empty());
     }
 
+    #[test]
+    fn test_format_conversion() {
+        let settings = |pixel_format| CameraSettings {
+            resolution: CameraResolution::QVGA,
+            pixel_format,
+            ..Default::default()
+        };
+        let pixels = 320 * 240;
+        let frame = |format| CameraFrame::test_pattern(&settings(format), 0).data.len();
+        assert_eq!(frame(CameraPixelFormat::RGB24), pixels * 3);
+        assert_eq!(frame(CameraPixelFormat::YUYV), pixels * 2);
+        assert_eq!(frame(CameraPixelFormat::YUV420P), CameraResolution::QVGA.frame_size_yuv());
+        assert_eq!(frame(CameraPixelFormat::BayerGB), pixels);
+
+        // A small image is scaled up; GBRG puts green/blue on even rows, red/green on odd
+        let bayer = CameraFrame::from_rgb(&[10, 20, 30].repeat(4), 2, 2, &settings(CameraPixelFormat::BayerGB));
+        assert_eq!(&bayer.data[..2], &[20, 30]);
+        assert_eq!(&bayer.data[320..322], &[10, 20]);
+
+        // YUV422 of white is full luma with neutral chroma
+        let yuyv = CameraFrame::from_rgb(&[255; 12], 2, 2, &settings(CameraPixelFormat::YUYV));
+        assert_eq!(&yuyv.data[..4], &[235, 128, 235, 128]);
+
+        // Mirroring puts the black bar first
+        let mirrored = CameraSettings { flip_h: true, ..settings(CameraPixelFormat::RGB24) };
+        assert_eq!(&CameraFrame::test_pattern(&mirrored, 0).data[..3], &[0, 0, 0]);
+
+        #[cfg(feature = "jpeg")]
+        {
+            let jpeg = CameraFrame::test_pattern(&settings(CameraPixelFormat::Jpeg), 0);
+            let decoded = image::load_from_memory(&jpeg.data).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (320, 240));
+        }
+    }
+
+    #[test]
+    fn test_frame_pacing() {
+        let mut camera = Camera::new(0, CameraType::Virtual);
+        camera.initialize().unwrap();
+        camera.settings.framerate = 30;
+        camera.start_capture().unwrap();
+
+        assert!(camera.poll_frame().is_some());
+        // The next frame is not due for another 33 ms
+        assert!(camera.poll_frame().is_none());
+        std::thread::sleep(Duration::from_millis(40));
+        assert_eq!(camera.poll_frame().map(|frame| frame.sequence), Some(2));
+    }
+
     #[test]
     fn test_camera_manager() {
         let mut manager = CameraManager::new();
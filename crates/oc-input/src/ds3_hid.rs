@@ -0,0 +1,399 @@
+//! DualShock 3 HID passthrough (hidapi)
+//!
+//! Talks to real DualShock 3 controllers in their native HID report
+//! format, so pressure-sensitive buttons, Sixaxis motion and rumble come
+//! from the hardware instead of being synthesized from a generic gamepad.
+//!
+//! A DS3 stays silent until it is told to start streaming: over USB this
+//! is done by reading feature report 0xF2, over Bluetooth by sending
+//! feature report 0xF4.
+
+use crate::dualshock3::{ConnectionMode, DualShock3, LedState, SixaxisData, VibrationState};
+use crate::pad::{GamepadEvent, HostGamepadInfo, PadPorts};
+use crate::usb::known_devices;
+use hidapi::{BusType, HidApi, HidDevice};
+use std::ffi::CString;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Input report length including the report ID
+pub const INPUT_REPORT_LEN: usize = 49;
+/// Output report length including the report ID
+pub const OUTPUT_REPORT_LEN: usize = 49;
+
+/// Input/output report ID
+const REPORT_ID: u8 = 0x01;
+/// Feature report that starts streaming over USB
+const USB_ENABLE_REPORT: u8 = 0xF2;
+/// Feature report that starts streaming over Bluetooth
+const BT_ENABLE_REPORT: [u8; 5] = [0xF4, 0x42, 0x03, 0x00, 0x00];
+/// Midpoint of the 10-bit motion sensors
+const SENSOR_CENTER: i16 = 512;
+/// How often to look for newly plugged-in controllers
+const RESCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Report byte offsets (report ID at offset 0)
+mod offset {
+    pub const BUTTONS: usize = 2;
+    pub const LEFT_X: usize = 6;
+    pub const LEFT_Y: usize = 7;
+    pub const RIGHT_X: usize = 8;
+    pub const RIGHT_Y: usize = 9;
+    pub const PRESSURE: usize = 14;
+    pub const ACCEL_X: usize = 41;
+    pub const ACCEL_Y: usize = 43;
+    pub const ACCEL_Z: usize = 45;
+    pub const GYRO_Z: usize = 47;
+}
+
+/// Read a big-endian 10-bit sensor value, centred on zero
+fn sensor(report: &[u8], at: usize) -> i16 {
+    (u16::from_be_bytes([report[at], report[at + 1]]) & 0x3FF) as i16 - SENSOR_CENTER
+}
+
+/// Parse a DS3 input report into controller state
+///
+/// Returns false if the report is not a full input report.
+pub fn parse_input_report(report: &[u8], ds3: &mut DualShock3) -> bool {
+    if report.len() < INPUT_REPORT_LEN || report[0] != REPORT_ID {
+        return false;
+    }
+
+    // The two button bytes use the same bit layout as `PadButtons`
+    ds3.pad.buttons =
+        u16::from_le_bytes([report[offset::BUTTONS], report[offset::BUTTONS + 1]]) as u32;
+    ds3.pad.left_x = report[offset::LEFT_X];
+    ds3.pad.left_y = report[offset::LEFT_Y];
+    ds3.pad.right_x = report[offset::RIGHT_X];
+    ds3.pad.right_y = report[offset::RIGHT_Y];
+    // Pressure bytes are in `pressure_index` order
+    ds3.pad
+        .pressure
+        .copy_from_slice(&report[offset::PRESSURE..offset::PRESSURE + 12]);
+
+    if ds3.sixaxis_enabled {
+        ds3.sixaxis = SixaxisData {
+            accel_x: sensor(report, offset::ACCEL_X),
+            accel_y: sensor(report, offset::ACCEL_Y),
+            accel_z: sensor(report, offset::ACCEL_Z),
+            gyro_x: 0,
+            gyro_y: 0,
+            gyro_z: sensor(report, offset::GYRO_Z),
+        };
+    }
+    true
+}
+
+/// Build the output report setting rumble and player LEDs
+pub fn output_report(vibration: &VibrationState, leds: &LedState) -> [u8; OUTPUT_REPORT_LEN] {
+    let mut report = [0u8; OUTPUT_REPORT_LEN];
+    report[0] = REPORT_ID;
+    // Motor durations of 0xFF keep the motors running until the next report
+    report[2] = if vibration.small_motor > 0 { 0xFF } else { 0 };
+    report[3] = vibration.small_motor.min(1);
+    report[4] = if vibration.large_motor > 0 { 0xFF } else { 0 };
+    report[5] = vibration.large_motor;
+
+    let lit = [leds.led1, leds.led2, leds.led3, leds.led4];
+    report[10] = lit
+        .iter()
+        .enumerate()
+        .filter(|(_, &on)| on)
+        .fold(0, |mask, (i, _)| mask | (0x02 << i));
+
+    // Per-LED timing: always on, or blinking with equal on/off periods
+    let (off, on) = match leds.blink_rate {
+        0 => (0x00, 0x32),
+        rate => {
+            let period = (0x80 / rate).max(1);
+            (period, period)
+        }
+    };
+    for led in 0..4 {
+        let at = 11 + led * 5;
+        report[at..at + 5].copy_from_slice(&[0xFF, 0x27, 0x10, off, on]);
+    }
+    report
+}
+
+/// An opened DualShock 3
+struct Ds3HidDevice {
+    device: HidDevice,
+    path: CString,
+    controller: DualShock3,
+    /// Last output report sent, to avoid resending unchanged state
+    last_output: Option<[u8; OUTPUT_REPORT_LEN]>,
+}
+
+impl Ds3HidDevice {
+    /// Open a controller and start its report stream
+    fn open(api: &HidApi, path: &CString, mode: ConnectionMode, port: u8) -> Result<Self, String> {
+        let device = api
+            .open_path(path)
+            .map_err(|e| format!("Failed to open DualShock 3: {}", e))?;
+        device
+            .set_blocking_mode(false)
+            .map_err(|e| format!("Failed to set non-blocking mode: {}", e))?;
+
+        match mode {
+            ConnectionMode::Bluetooth => device.send_feature_report(&BT_ENABLE_REPORT),
+            _ => {
+                let mut buf = [0u8; 18];
+                buf[0] = USB_ENABLE_REPORT;
+                device.get_feature_report(&mut buf).map(|_| ())
+            }
+        }
+        .map_err(|e| format!("Failed to enable DualShock 3 reports: {}", e))?;
+
+        let mut controller = DualShock3::new(port);
+        controller.connect(mode);
+        Ok(Self {
+            device,
+            path: path.clone(),
+            controller,
+            last_output: None,
+        })
+    }
+
+    /// Drain pending input reports, returning false if the device is gone
+    fn read(&mut self) -> bool {
+        let mut buf = [0u8; 64];
+        loop {
+            match self.device.read(&mut buf) {
+                Ok(0) => return true,
+                Ok(len) => {
+                    parse_input_report(&buf[..len], &mut self.controller);
+                }
+                Err(e) => {
+                    tracing::debug!("DualShock 3 read failed: {}", e);
+                    return false;
+                }
+            }
+        }
+    }
+
+    /// Send rumble and LED state if it changed
+    fn write(&mut self) -> bool {
+        let report = output_report(&self.controller.vibration, &self.controller.leds);
+        if self.last_output == Some(report) {
+            return true;
+        }
+        match self.device.write(&report) {
+            Ok(_) => {
+                self.last_output = Some(report);
+                true
+            }
+            Err(e) => {
+                tracing::debug!("DualShock 3 write failed: {}", e);
+                false
+            }
+        }
+    }
+}
+
+/// DualShock 3 passthrough backend
+pub struct Ds3HidBackend {
+    /// hidapi context (None if it failed to start)
+    api: Option<HidApi>,
+    devices: Vec<Ds3HidDevice>,
+    /// Ports shared with cellPad
+    ports: Arc<PadPorts>,
+    last_scan: Option<Instant>,
+    /// Forward game rumble
+    rumble: bool,
+}
+
+impl Ds3HidBackend {
+    /// Create a backend publishing to `ports`
+    pub fn new(ports: Arc<PadPorts>) -> Self {
+        let api = match HidApi::new() {
+            Ok(api) => Some(api),
+            Err(e) => {
+                tracing::warn!("Failed to initialize hidapi: {}", e);
+                None
+            }
+        };
+        Self {
+            api,
+            devices: Vec::new(),
+            ports,
+            last_scan: None,
+            rumble: true,
+        }
+    }
+
+    /// Whether HID devices can be accessed
+    pub fn is_available(&self) -> bool {
+        self.api.is_some()
+    }
+
+    /// Forward game rumble to the controllers (on by default)
+    pub fn set_rumble_enabled(&mut self, enabled: bool) {
+        self.rumble = enabled;
+    }
+
+    /// Read controllers, publish their state and send rumble
+    ///
+    /// Call once per frame. Returns the hotplug events that occurred.
+    pub fn poll(&mut self) -> Vec<GamepadEvent> {
+        let mut events = Vec::new();
+        let scan_due = match self.last_scan {
+            Some(last) => last.elapsed() >= RESCAN_INTERVAL,
+            None => true,
+        };
+        if scan_due {
+            self.last_scan = Some(Instant::now());
+            self.scan(&mut events);
+        }
+
+        let ports = &self.ports;
+        let rumble = self.rumble;
+        self.devices.retain_mut(|dev| {
+            let port = dev.controller.port;
+            dev.controller.vibration = if rumble { ports.vibration(port) } else { VibrationState::new() };
+            dev.controller.update();
+            if !dev.read() || !dev.write() {
+                ports.disconnect(port);
+                tracing::info!("DualShock 3 on port {} disconnected", port);
+                events.push(GamepadEvent::Disconnected { port });
+                return false;
+            }
+            ports.update(port, dev.controller.pad.clone());
+            ports.update_motion(port, dev.controller.sixaxis);
+            true
+        });
+        events
+    }
+
+    /// Connected controllers
+    pub fn devices(&self) -> Vec<HostGamepadInfo> {
+        self.devices
+            .iter()
+            .map(|dev| HostGamepadInfo {
+                name: device_name(dev.controller.connection),
+                port: dev.controller.port,
+                vendor_id: Some(known_devices::DUALSHOCK3.0),
+                product_id: Some(known_devices::DUALSHOCK3.1),
+                has_mapping: true,
+            })
+            .collect()
+    }
+
+    /// Release every controller's port
+    ///
+    /// Controllers are reopened, and assigned ports again, on the next poll.
+    pub fn release_all(&mut self) {
+        for dev in self.devices.drain(..) {
+            self.ports.disconnect(dev.controller.port);
+        }
+        self.last_scan = None;
+    }
+
+    /// Open controllers that appeared since the last scan
+    fn scan(&mut self, events: &mut Vec<GamepadEvent>) {
+        let Some(api) = self.api.as_mut() else {
+            return;
+        };
+        let (vid, pid) = known_devices::DUALSHOCK3;
+        if let Err(e) = api.reset_devices().and_then(|_| api.add_devices(vid, pid)) {
+            tracing::debug!("HID enumeration failed: {}", e);
+            return;
+        }
+
+        let found: Vec<(CString, ConnectionMode)> = api
+            .device_list()
+            .filter(|info| info.vendor_id() == vid && info.product_id() == pid)
+            .map(|info| {
+                let mode = match info.bus_type() {
+                    BusType::Bluetooth => ConnectionMode::Bluetooth,
+                    _ => ConnectionMode::Usb,
+                };
+                (info.path().to_owned(), mode)
+            })
+            .collect();
+
+        for (path, mode) in found {
+            if self.devices.iter().any(|dev| dev.path == path) {
+                continue;
+            }
+            let name = device_name(mode);
+            let Some(port) = self.ports.connect_device(&name) else {
+                events.push(GamepadEvent::NoFreePort { name });
+                continue;
+            };
+            match Ds3HidDevice::open(api, &path, mode, port) {
+                Ok(dev) => {
+                    events.push(GamepadEvent::Connected { port, name });
+                    self.devices.push(dev);
+                }
+                Err(e) => {
+                    self.ports.disconnect(port);
+                    tracing::warn!("{}", e);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Ds3HidBackend {
+    fn drop(&mut self) {
+        self.release_all();
+    }
+}
+
+/// Name reported for a controller, also used to pin it to a port
+fn device_name(mode: ConnectionMode) -> String {
+    format!("DualShock 3 ({:?})", mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dualshock3::pressure_index;
+    use crate::pad::PadButtons;
+
+    #[test]
+    fn test_parse_input_report() {
+        let mut report = [0u8; INPUT_REPORT_LEN];
+        report[0] = REPORT_ID;
+        report[2] = 0x08; // Start
+        report[3] = 0x40; // Cross
+        report[6] = 0x00;
+        report[9] = 0xFF;
+        report[offset::PRESSURE + pressure_index::CROSS] = 200;
+        report[offset::ACCEL_Z..offset::ACCEL_Z + 2].copy_from_slice(&(512u16 + 113).to_be_bytes());
+        report[offset::GYRO_Z..offset::GYRO_Z + 2].copy_from_slice(&(512u16 - 10).to_be_bytes());
+
+        let mut ds3 = DualShock3::new(0);
+        assert!(parse_input_report(&report, &mut ds3));
+        assert!(ds3.pad.is_button_pressed(PadButtons::START));
+        assert!(ds3.pad.is_button_pressed(PadButtons::CROSS));
+        assert!(!ds3.pad.is_button_pressed(PadButtons::CIRCLE));
+        assert_eq!(ds3.pad.left_x, 0);
+        assert_eq!(ds3.pad.right_y, 0xFF);
+        assert_eq!(ds3.pad.pressure[pressure_index::CROSS], 200);
+        assert_eq!(ds3.sixaxis.accel_z, 113);
+        assert_eq!(ds3.sixaxis.gyro_z, -10);
+
+        // Short or foreign reports are ignored
+        assert!(!parse_input_report(&report[..20], &mut ds3));
+    }
+
+    #[test]
+    fn test_output_report() {
+        let mut vibration = VibrationState::new();
+        vibration.set_small_motor(true);
+        vibration.set_large_motor(180);
+        let mut leds = LedState::default();
+        leds.set_player(2);
+
+        let report = output_report(&vibration, &leds);
+        assert_eq!(report[0], REPORT_ID);
+        assert_eq!(&report[2..6], &[0xFF, 1, 0xFF, 180]);
+        assert_eq!(report[10], 0x02 | 0x04);
+        assert_eq!(&report[11..16], &[0xFF, 0x27, 0x10, 0x00, 0x32]);
+
+        let report = output_report(&VibrationState::new(), &leds);
+        assert_eq!(&report[2..6], &[0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,787 @@
+//! DualShock 4 / DualSense HID support (hidapi)
+//!
+//! Reads DualShock 4 and DualSense controllers in their native HID report
+//! format so their gyroscope and accelerometer can drive Sixaxis motion.
+//! Readings are converted to DualShock 3 sensor counts, so tilt-controlled
+//! games see the ranges they were written for. The touchpad and the
+//! buttons a DS3 lacks (touchpad click, PS, mute) are mapped to PS3
+//! buttons through [`Ds4ExtraMapping`]. Game rumble is sent back to the
+//! controller, and the light bar (plus the DualSense player LEDs) shows
+//! which port it is on.
+
+use crate::dualshock3::{pressure_index, SixaxisData, VibrationState, SIXAXIS_ACCEL_PER_G, SIXAXIS_GYRO_PER_DEG_S};
+use crate::pad::{GamepadEvent, HostGamepadInfo, PadButtons, PadPorts, PadState};
+use crate::usb::known_devices;
+use hidapi::{BusType, HidApi, HidDevice};
+use std::ffi::CString;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Accelerometer counts per g on DS4/DualSense
+const ACCEL_PER_G: f32 = 8192.0;
+/// Gyroscope counts per degree/second on DS4/DualSense
+const GYRO_PER_DEG_S: f32 = 16.4;
+/// Touchpad width; touches left of the midpoint count as the left half
+const TOUCHPAD_WIDTH: u16 = 1920;
+/// How often to look for newly plugged-in controllers
+const RESCAN_INTERVAL: Duration = Duration::from_secs(2);
+/// Bluetooth output reports are checksummed with this byte prepended
+const BT_CRC_SEED: u8 = 0xA2;
+
+/// Light bar color for each pad port, following the PS4 player colors
+const LIGHT_BAR_COLORS: [[u8; 3]; 7] = [
+    [0, 0, 64],
+    [64, 0, 0],
+    [0, 64, 0],
+    [32, 0, 32],
+    [64, 24, 0],
+    [0, 32, 32],
+    [32, 32, 32],
+];
+/// DualSense player LED patterns (five LEDs under the touchpad)
+const PLAYER_LEDS: [u8; 5] = [0x04, 0x0A, 0x15, 0x1B, 0x1F];
+
+/// Controller family
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ds4Model {
+    /// DualShock 4 (either revision)
+    DualShock4,
+    /// DualSense
+    DualSense,
+}
+
+impl Ds4Model {
+    /// Identify a controller from its USB IDs
+    pub fn from_ids(vendor_id: u16, product_id: u16) -> Option<Self> {
+        match (vendor_id, product_id) {
+            known_devices::DUALSHOCK4 | known_devices::DUALSHOCK4_V2 => Some(Self::DualShock4),
+            known_devices::DUALSENSE => Some(Self::DualSense),
+            _ => None,
+        }
+    }
+
+    /// USB IDs of every supported controller
+    pub const DEVICES: [(u16, u16); 3] = [
+        known_devices::DUALSHOCK4,
+        known_devices::DUALSHOCK4_V2,
+        known_devices::DUALSENSE,
+    ];
+
+    /// Display name
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::DualShock4 => "DualShock 4",
+            Self::DualSense => "DualSense",
+        }
+    }
+}
+
+/// Inputs without a DualShock 3 equivalent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Ds4Extra {
+    /// Touchpad pressed down
+    TouchpadClick,
+    /// Finger on the left half of the touchpad
+    TouchLeft,
+    /// Finger on the right half of the touchpad
+    TouchRight,
+    /// PS button
+    Ps,
+    /// Microphone mute button (DualSense)
+    Mute,
+}
+
+/// PS3 buttons assigned to the extra inputs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ds4ExtraMapping {
+    pub touchpad_click: Option<PadButtons>,
+    pub touch_left: Option<PadButtons>,
+    pub touch_right: Option<PadButtons>,
+    pub ps: Option<PadButtons>,
+    pub mute: Option<PadButtons>,
+}
+
+impl Ds4ExtraMapping {
+    /// Build the mapping from configuration button names
+    pub fn from_config(config: &oc_core::config::Ds4ExtraMapping) -> Self {
+        Self {
+            touchpad_click: PadButtons::parse_name(&config.touchpad_click),
+            touch_left: PadButtons::parse_name(&config.touch_left),
+            touch_right: PadButtons::parse_name(&config.touch_right),
+            ps: PadButtons::parse_name(&config.ps),
+            mute: PadButtons::parse_name(&config.mute),
+        }
+    }
+
+    /// PS3 button assigned to an extra input
+    pub fn get(&self, extra: Ds4Extra) -> Option<PadButtons> {
+        match extra {
+            Ds4Extra::TouchpadClick => self.touchpad_click,
+            Ds4Extra::TouchLeft => self.touch_left,
+            Ds4Extra::TouchRight => self.touch_right,
+            Ds4Extra::Ps => self.ps,
+            Ds4Extra::Mute => self.mute,
+        }
+    }
+}
+
+/// Report layout for one model and transport
+///
+/// Offsets are from the start of the report, including the report ID.
+struct Layout {
+    report_id: u8,
+    len: usize,
+    sticks: usize,
+    triggers: usize,
+    buttons: usize,
+    gyro: usize,
+    accel: usize,
+    touch: usize,
+}
+
+impl Layout {
+    fn new(model: Ds4Model, bluetooth: bool) -> Self {
+        match (model, bluetooth) {
+            (Ds4Model::DualShock4, false) => Self::ds4(0x01, 0, 64),
+            (Ds4Model::DualShock4, true) => Self::ds4(0x11, 2, 78),
+            (Ds4Model::DualSense, false) => Self::dualsense(0x01, 0, 64),
+            (Ds4Model::DualSense, true) => Self::dualsense(0x31, 1, 78),
+        }
+    }
+
+    fn ds4(report_id: u8, shift: usize, len: usize) -> Self {
+        Self {
+            report_id,
+            len,
+            sticks: 1 + shift,
+            triggers: 8 + shift,
+            buttons: 5 + shift,
+            gyro: 13 + shift,
+            accel: 19 + shift,
+            touch: 35 + shift,
+        }
+    }
+
+    fn dualsense(report_id: u8, shift: usize, len: usize) -> Self {
+        Self {
+            report_id,
+            len,
+            sticks: 1 + shift,
+            triggers: 5 + shift,
+            buttons: 8 + shift,
+            gyro: 16 + shift,
+            accel: 22 + shift,
+            touch: 33 + shift,
+        }
+    }
+}
+
+fn read_i16(report: &[u8], at: usize) -> f32 {
+    i16::from_le_bytes([report[at], report[at + 1]]) as f32
+}
+
+/// Convert DS4/DualSense IMU readings to DualShock 3 sensor counts
+///
+/// The DS4 reports gyro as pitch/yaw/roll and its axes point the opposite
+/// way to the Sixaxis on every axis.
+fn convert_motion(gyro: [f32; 3], accel: [f32; 3]) -> SixaxisData {
+    let to_accel = |v: f32| (-v / ACCEL_PER_G * SIXAXIS_ACCEL_PER_G).clamp(-512.0, 511.0) as i16;
+    let to_gyro = |v: f32| (-v / GYRO_PER_DEG_S * SIXAXIS_GYRO_PER_DEG_S).clamp(-512.0, 511.0) as i16;
+    SixaxisData {
+        accel_x: to_accel(accel[0]),
+        accel_y: to_accel(accel[1]),
+        accel_z: to_accel(accel[2]),
+        gyro_x: to_gyro(gyro[2]),
+        gyro_y: to_gyro(gyro[0]),
+        gyro_z: to_gyro(gyro[1]),
+    }
+}
+
+/// Parse an input report into PS3 pad state and Sixaxis motion
+///
+/// Returns None for reports that are not full input reports (a Bluetooth
+/// DS4 sends reduced reports until it has been switched to full mode).
+pub fn parse_input_report(
+    model: Ds4Model,
+    bluetooth: bool,
+    report: &[u8],
+    mapping: &Ds4ExtraMapping,
+) -> Option<(PadState, SixaxisData)> {
+    let layout = Layout::new(model, bluetooth);
+    if report.len() < layout.len || report[0] != layout.report_id {
+        return None;
+    }
+
+    let mut pad = PadState::new();
+    pad.left_x = report[layout.sticks];
+    pad.left_y = report[layout.sticks + 1];
+    pad.right_x = report[layout.sticks + 2];
+    pad.right_y = report[layout.sticks + 3];
+
+    let b = &report[layout.buttons..layout.buttons + 3];
+    // D-pad hat: 0 = up, clockwise in 45° steps, 8 = released
+    let hat = b[0] & 0x0F;
+    let dpad = [
+        (PadButtons::DPAD_UP, matches!(hat, 7 | 0 | 1)),
+        (PadButtons::DPAD_RIGHT, matches!(hat, 1..=3)),
+        (PadButtons::DPAD_DOWN, matches!(hat, 3..=5)),
+        (PadButtons::DPAD_LEFT, matches!(hat, 5..=7)),
+    ];
+    let buttons = [
+        (PadButtons::SQUARE, b[0] & 0x10 != 0),
+        (PadButtons::CROSS, b[0] & 0x20 != 0),
+        (PadButtons::CIRCLE, b[0] & 0x40 != 0),
+        (PadButtons::TRIANGLE, b[0] & 0x80 != 0),
+        (PadButtons::L1, b[1] & 0x01 != 0),
+        (PadButtons::R1, b[1] & 0x02 != 0),
+        (PadButtons::L2, b[1] & 0x04 != 0),
+        (PadButtons::R2, b[1] & 0x08 != 0),
+        (PadButtons::SELECT, b[1] & 0x10 != 0),
+        (PadButtons::START, b[1] & 0x20 != 0),
+        (PadButtons::L3, b[1] & 0x40 != 0),
+        (PadButtons::R3, b[1] & 0x80 != 0),
+    ];
+    for (button, pressed) in dpad.into_iter().chain(buttons) {
+        pad.set_button(button, pressed);
+    }
+
+    // Only the triggers are analog; other buttons report full pressure
+    let digital_pressure = [
+        (PadButtons::DPAD_UP, pressure_index::DPAD_UP),
+        (PadButtons::DPAD_RIGHT, pressure_index::DPAD_RIGHT),
+        (PadButtons::DPAD_DOWN, pressure_index::DPAD_DOWN),
+        (PadButtons::DPAD_LEFT, pressure_index::DPAD_LEFT),
+        (PadButtons::L1, pressure_index::L1),
+        (PadButtons::R1, pressure_index::R1),
+        (PadButtons::TRIANGLE, pressure_index::TRIANGLE),
+        (PadButtons::CIRCLE, pressure_index::CIRCLE),
+        (PadButtons::CROSS, pressure_index::CROSS),
+        (PadButtons::SQUARE, pressure_index::SQUARE),
+    ];
+    for (button, slot) in digital_pressure {
+        if pad.is_button_pressed(button) {
+            pad.pressure[slot] = 255;
+        }
+    }
+    pad.pressure[pressure_index::L2] = report[layout.triggers];
+    pad.pressure[pressure_index::R2] = report[layout.triggers + 1];
+
+    // Touch points: bit 7 of the first byte is set while no finger is down
+    let mut extras = Vec::new();
+    for point in report[layout.touch..layout.touch + 8].chunks_exact(4) {
+        if point[0] & 0x80 == 0 {
+            let x = point[1] as u16 | ((point[2] as u16 & 0x0F) << 8);
+            extras.push(if x < TOUCHPAD_WIDTH / 2 {
+                Ds4Extra::TouchLeft
+            } else {
+                Ds4Extra::TouchRight
+            });
+        }
+    }
+    if b[2] & 0x01 != 0 {
+        extras.push(Ds4Extra::Ps);
+    }
+    if b[2] & 0x02 != 0 {
+        extras.push(Ds4Extra::TouchpadClick);
+    }
+    if model == Ds4Model::DualSense && b[2] & 0x04 != 0 {
+        extras.push(Ds4Extra::Mute);
+    }
+    for button in extras.into_iter().filter_map(|extra| mapping.get(extra)) {
+        pad.set_button(button, true);
+    }
+
+    let gyro = [0, 2, 4].map(|i| read_i16(report, layout.gyro + i));
+    let accel = [0, 2, 4].map(|i| read_i16(report, layout.accel + i));
+    Some((pad, convert_motion(gyro, accel)))
+}
+
+/// What the controller should show and play
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ds4Feedback {
+    pub vibration: VibrationState,
+    /// Light bar color (off when all zero)
+    pub light_bar: [u8; 3],
+    /// Pad port, for the DualSense player LEDs (None leaves them off)
+    pub player: Option<u8>,
+}
+
+impl Ds4Feedback {
+    /// Player color and LEDs for a pad port
+    pub fn for_port(port: u8, vibration: VibrationState) -> Self {
+        Self {
+            vibration,
+            light_bar: LIGHT_BAR_COLORS[port as usize % LIGHT_BAR_COLORS.len()],
+            player: Some(port),
+        }
+    }
+}
+
+/// Build the output report setting rumble, light bar and player LEDs
+///
+/// `seq` numbers DualSense Bluetooth reports. `release_light_bar` takes
+/// the DualSense light bar back from the firmware's pairing animation and
+/// only needs to be set once after connecting.
+pub fn output_report(model: Ds4Model, bluetooth: bool, feedback: &Ds4Feedback, seq: u8, release_light_bar: bool) -> Vec<u8> {
+    // The DS3 small motor is on/off; the DS4 weak motor takes a level
+    let weak = if feedback.vibration.small_motor > 0 { 0xFF } else { 0 };
+    let strong = feedback.vibration.large_motor;
+    let [red, green, blue] = feedback.light_bar;
+
+    let mut report = match (model, bluetooth) {
+        (Ds4Model::DualShock4, false) => {
+            let mut report = vec![0u8; 32];
+            report[0] = 0x05;
+            report
+        }
+        (Ds4Model::DualShock4, true) => {
+            let mut report = vec![0u8; 78];
+            report[0] = 0x11;
+            // HID report with CRC
+            report[1] = 0xC0;
+            report
+        }
+        (Ds4Model::DualSense, false) => {
+            let mut report = vec![0u8; 63];
+            report[0] = 0x02;
+            report
+        }
+        (Ds4Model::DualSense, true) => {
+            let mut report = vec![0u8; 78];
+            report[0] = 0x31;
+            report[1] = (seq & 0x0F) << 4;
+            report[2] = 0x10;
+            report
+        }
+    };
+    let common = if bluetooth { 3 } else { 1 };
+    let c = &mut report[common..];
+
+    match model {
+        Ds4Model::DualShock4 => {
+            // Motors and light bar valid
+            c[0] = 0x01 | 0x02;
+            c[3] = weak;
+            c[4] = strong;
+            c[5] = red;
+            c[6] = green;
+            c[7] = blue;
+        }
+        Ds4Model::DualSense => {
+            // Compatible vibration, haptics select
+            c[0] = 0x01 | 0x02;
+            // Light bar and player indicator control
+            c[1] = 0x04 | 0x10;
+            c[2] = weak;
+            c[3] = strong;
+            if release_light_bar {
+                c[38] = 0x02;
+                c[41] = 0x02;
+            }
+            c[43] = feedback.player.map_or(0, |port| PLAYER_LEDS[(port as usize).min(PLAYER_LEDS.len() - 1)]);
+            c[44] = red;
+            c[45] = green;
+            c[46] = blue;
+        }
+    }
+
+    if bluetooth {
+        let len = report.len();
+        let mut crc = crc32fast::Hasher::new();
+        crc.update(&[BT_CRC_SEED]);
+        crc.update(&report[..len - 4]);
+        report[len - 4..].copy_from_slice(&crc.finalize().to_le_bytes());
+    }
+    report
+}
+
+/// An opened DS4/DualSense
+struct Ds4HidDevice {
+    device: HidDevice,
+    path: CString,
+    /// USB vendor and product ID
+    ids: (u16, u16),
+    model: Ds4Model,
+    bluetooth: bool,
+    port: u8,
+    /// Feedback last sent, to avoid rewriting an unchanged state
+    sent: Option<Ds4Feedback>,
+    /// DualSense Bluetooth output sequence number
+    output_seq: u8,
+}
+
+impl Ds4HidDevice {
+    fn open(
+        api: &HidApi,
+        path: &CString,
+        ids: (u16, u16),
+        model: Ds4Model,
+        bluetooth: bool,
+        port: u8,
+    ) -> Result<Self, String> {
+        let name = model.name();
+        let device = api
+            .open_path(path)
+            .map_err(|e| format!("Failed to open {}: {}", name, e))?;
+        device
+            .set_blocking_mode(false)
+            .map_err(|e| format!("Failed to set non-blocking mode: {}", e))?;
+
+        if bluetooth {
+            // Reading the calibration feature report switches to full reports
+            let mut buf = [0u8; 64];
+            buf[0] = match model {
+                Ds4Model::DualShock4 => 0x02,
+                Ds4Model::DualSense => 0x05,
+            };
+            device
+                .get_feature_report(&mut buf)
+                .map_err(|e| format!("Failed to enable {} reports: {}", name, e))?;
+        }
+
+        Ok(Self {
+            device,
+            path: path.clone(),
+            ids,
+            model,
+            bluetooth,
+            port,
+            sent: None,
+            output_seq: 0,
+        })
+    }
+
+    /// Send rumble and light bar state if it changed, returning false if the device is gone
+    fn write(&mut self, feedback: Ds4Feedback) -> bool {
+        if self.sent == Some(feedback) {
+            return true;
+        }
+        let report = output_report(self.model, self.bluetooth, &feedback, self.output_seq, self.sent.is_none());
+        match self.device.write(&report) {
+            Ok(_) => {
+                self.sent = Some(feedback);
+                self.output_seq = self.output_seq.wrapping_add(1);
+                true
+            }
+            Err(e) => {
+                tracing::debug!("{} write failed: {}", self.model.name(), e);
+                false
+            }
+        }
+    }
+
+    /// Drain pending reports, returning the latest state or Err if the device is gone
+    fn read(&mut self, mapping: &Ds4ExtraMapping) -> Result<Option<(PadState, SixaxisData)>, ()> {
+        let mut buf = [0u8; 128];
+        let mut latest = None;
+        loop {
+            match self.device.read(&mut buf) {
+                Ok(0) => return Ok(latest),
+                Ok(len) => {
+                    if let Some(state) = parse_input_report(self.model, self.bluetooth, &buf[..len], mapping) {
+                        latest = Some(state);
+                    }
+                }
+                Err(e) => {
+                    tracing::debug!("{} read failed: {}", self.model.name(), e);
+                    return Err(());
+                }
+            }
+        }
+    }
+}
+
+/// DualShock 4 / DualSense backend
+pub struct Ds4HidBackend {
+    /// hidapi context (None if it failed to start)
+    api: Option<HidApi>,
+    devices: Vec<Ds4HidDevice>,
+    /// Ports shared with cellPad
+    ports: Arc<PadPorts>,
+    mapping: Ds4ExtraMapping,
+    last_scan: Option<Instant>,
+    /// Forward game rumble
+    rumble: bool,
+    /// Show the player color on the light bar
+    light_bar: bool,
+}
+
+impl Ds4HidBackend {
+    /// Create a backend publishing to `ports`
+    pub fn new(ports: Arc<PadPorts>, mapping: Ds4ExtraMapping) -> Self {
+        let api = match HidApi::new() {
+            Ok(api) => Some(api),
+            Err(e) => {
+                tracing::warn!("Failed to initialize hidapi: {}", e);
+                None
+            }
+        };
+        Self {
+            api,
+            devices: Vec::new(),
+            ports,
+            mapping,
+            last_scan: None,
+            rumble: true,
+            light_bar: true,
+        }
+    }
+
+    /// Whether HID devices can be accessed
+    pub fn is_available(&self) -> bool {
+        self.api.is_some()
+    }
+
+    /// Change the extra button mapping
+    pub fn set_mapping(&mut self, mapping: Ds4ExtraMapping) {
+        self.mapping = mapping;
+    }
+
+    /// Choose which feedback is sent to the controllers
+    pub fn set_feedback(&mut self, rumble: bool, light_bar: bool) {
+        self.rumble = rumble;
+        self.light_bar = light_bar;
+    }
+
+    /// Read controllers, publish their state and send rumble and light bar state
+    ///
+    /// Call once per frame. Returns the hotplug events that occurred.
+    pub fn poll(&mut self) -> Vec<GamepadEvent> {
+        let mut events = Vec::new();
+        let scan_due = match self.last_scan {
+            Some(last) => last.elapsed() >= RESCAN_INTERVAL,
+            None => true,
+        };
+        if scan_due {
+            self.last_scan = Some(Instant::now());
+            self.scan(&mut events);
+        }
+
+        let ports = &self.ports;
+        let mapping = &self.mapping;
+        let (rumble, light_bar) = (self.rumble, self.light_bar);
+        self.devices.retain_mut(|dev| {
+            let vibration = if rumble { ports.vibration(dev.port) } else { VibrationState::new() };
+            let feedback = if light_bar {
+                Ds4Feedback::for_port(dev.port, vibration)
+            } else {
+                Ds4Feedback {
+                    vibration,
+                    light_bar: [0; 3],
+                    player: None,
+                }
+            };
+            let state = dev.read(mapping).and_then(|state| if dev.write(feedback) { Ok(state) } else { Err(()) });
+            match state {
+                Ok(state) => {
+                    if let Some((pad, sixaxis)) = state {
+                        ports.update(dev.port, pad);
+                        ports.update_motion(dev.port, sixaxis);
+                    }
+                    true
+                }
+                Err(()) => {
+                    ports.disconnect(dev.port);
+                    tracing::info!("{} on port {} disconnected", dev.model.name(), dev.port);
+                    events.push(GamepadEvent::Disconnected { port: dev.port });
+                    false
+                }
+            }
+        });
+        events
+    }
+
+    /// Port of the first connected controller
+    pub fn first_port(&self) -> Option<u8> {
+        self.devices.first().map(|dev| dev.port)
+    }
+
+    /// Connected controllers
+    pub fn devices(&self) -> Vec<HostGamepadInfo> {
+        self.devices
+            .iter()
+            .map(|dev| HostGamepadInfo {
+                name: device_name(dev.model, dev.bluetooth),
+                port: dev.port,
+                vendor_id: Some(dev.ids.0),
+                product_id: Some(dev.ids.1),
+                has_mapping: true,
+            })
+            .collect()
+    }
+
+    /// Release every controller's port
+    ///
+    /// Controllers are reopened, and assigned ports again, on the next poll.
+    pub fn release_all(&mut self) {
+        for dev in self.devices.drain(..) {
+            self.ports.disconnect(dev.port);
+        }
+        self.last_scan = None;
+    }
+
+    /// Open controllers that appeared since the last scan
+    fn scan(&mut self, events: &mut Vec<GamepadEvent>) {
+        let Some(api) = self.api.as_mut() else {
+            return;
+        };
+        let enumerated = api.reset_devices().and_then(|_| {
+            Ds4Model::DEVICES
+                .iter()
+                .try_for_each(|&(vid, pid)| api.add_devices(vid, pid).map(|_| ()))
+        });
+        if let Err(e) = enumerated {
+            tracing::debug!("HID enumeration failed: {}", e);
+            return;
+        }
+
+        let found: Vec<(CString, (u16, u16), Ds4Model, bool)> = api
+            .device_list()
+            .filter_map(|info| {
+                let ids = (info.vendor_id(), info.product_id());
+                let model = Ds4Model::from_ids(ids.0, ids.1)?;
+                Some((info.path().to_owned(), ids, model, matches!(info.bus_type(), BusType::Bluetooth)))
+            })
+            .collect();
+
+        for (path, ids, model, bluetooth) in found {
+            if self.devices.iter().any(|dev| dev.path == path) {
+                continue;
+            }
+            let name = device_name(model, bluetooth);
+            let Some(port) = self.ports.connect_device(&name) else {
+                events.push(GamepadEvent::NoFreePort { name });
+                continue;
+            };
+            match Ds4HidDevice::open(api, &path, ids, model, bluetooth, port) {
+                Ok(dev) => {
+                    tracing::info!("{} connected on port {}", name, port);
+                    events.push(GamepadEvent::Connected { port, name });
+                    self.devices.push(dev);
+                }
+                Err(e) => {
+                    self.ports.disconnect(port);
+                    tracing::warn!("{}", e);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Ds4HidBackend {
+    fn drop(&mut self) {
+        self.release_all();
+    }
+}
+
+/// Name reported for a controller, also used to pin it to a port
+fn device_name(model: Ds4Model, bluetooth: bool) -> String {
+    format!("{} ({})", model.name(), if bluetooth { "Bluetooth" } else { "USB" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ds4_report() -> [u8; 64] {
+        let mut report = [0u8; 64];
+        report[0] = 0x01;
+        report[1..5].copy_from_slice(&[128, 128, 128, 128]);
+        report[5] = 0x08; // D-pad released
+        // Both touch points lifted
+        report[35] = 0x80;
+        report[39] = 0x80;
+        report
+    }
+
+    #[test]
+    fn test_ds4_buttons_and_dpad() {
+        let mut report = ds4_report();
+        report[5] = 0x20 | 0x01; // Cross, D-pad up-right
+        report[6] = 0x20; // Options
+        report[9] = 200; // R2 analog
+
+        let (pad, _) = parse_input_report(Ds4Model::DualShock4, false, &report, &Ds4ExtraMapping::default()).unwrap();
+        assert!(pad.is_button_pressed(PadButtons::CROSS));
+        assert!(pad.is_button_pressed(PadButtons::START));
+        assert!(pad.is_button_pressed(PadButtons::DPAD_UP));
+        assert!(pad.is_button_pressed(PadButtons::DPAD_RIGHT));
+        assert!(!pad.is_button_pressed(PadButtons::DPAD_DOWN));
+        assert_eq!(pad.pressure[pressure_index::CROSS], 255);
+        assert_eq!(pad.pressure[pressure_index::R2], 200);
+    }
+
+    #[test]
+    fn test_motion_conversion() {
+        let mut report = ds4_report();
+        // Lying flat: 1 g on the accelerometer's Z axis, pointing away from the Sixaxis convention
+        report[23..25].copy_from_slice(&(-8192i16).to_le_bytes());
+        // Yawing at 90°/s
+        report[15..17].copy_from_slice(&(-(16.4f32 * 90.0) as i16).to_le_bytes());
+
+        let (_, motion) = parse_input_report(Ds4Model::DualShock4, false, &report, &Ds4ExtraMapping::default()).unwrap();
+        assert_eq!(motion.accel_z, 113);
+        assert_eq!(motion.accel_x, 0);
+        assert_eq!(motion.gyro_z, 90);
+    }
+
+    #[test]
+    fn test_ds4_usb_output_report() {
+        let mut vibration = VibrationState::new();
+        vibration.set_small_motor(true);
+        vibration.set_large_motor(180);
+        let report = output_report(Ds4Model::DualShock4, false, &Ds4Feedback::for_port(1, vibration), 0, true);
+        assert_eq!(report.len(), 32);
+        assert_eq!(report[0], 0x05);
+        assert_eq!(report[1], 0x03);
+        assert_eq!(&report[4..9], &[0xFF, 180, 64, 0, 0]);
+    }
+
+    #[test]
+    fn test_dualsense_bluetooth_output_report() {
+        let feedback = Ds4Feedback::for_port(0, VibrationState::new());
+        let report = output_report(Ds4Model::DualSense, true, &feedback, 3, true);
+        assert_eq!(report.len(), 78);
+        assert_eq!(&report[..3], &[0x31, 0x30, 0x10]);
+        // Player 1 LED and blue light bar
+        assert_eq!(report[3 + 43], 0x04);
+        assert_eq!(&report[3 + 44..3 + 47], &[0, 0, 64]);
+        assert_eq!(report[3 + 41], 0x02);
+
+        let mut crc = crc32fast::Hasher::new();
+        crc.update(&[BT_CRC_SEED]);
+        crc.update(&report[..74]);
+        assert_eq!(&report[74..], &crc.finalize().to_le_bytes());
+
+        // The light bar is only released from the firmware once
+        let report = output_report(Ds4Model::DualSense, true, &feedback, 4, false);
+        assert_eq!(report[3 + 41], 0);
+    }
+
+    #[test]
+    fn test_touchpad_mapping() {
+        let mapping = Ds4ExtraMapping::from_config(&oc_core::config::Ds4ExtraMapping {
+            touch_right: "r3".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(mapping.touchpad_click, Some(PadButtons::SELECT));
+
+        // DualSense over Bluetooth: finger on the right half plus a click
+        let mut report = [0u8; 78];
+        report[0] = 0x31;
+        let layout = Layout::new(Ds4Model::DualSense, true);
+        report[layout.buttons] = 0x08;
+        report[layout.buttons + 2] = 0x02;
+        let x: u16 = 1500;
+        report[layout.touch..layout.touch + 4].copy_from_slice(&[0x01, x as u8, (x >> 8) as u8, 0]);
+        report[layout.touch + 4] = 0x80;
+
+        let (pad, _) = parse_input_report(Ds4Model::DualSense, true, &report, &mapping).unwrap();
+        assert!(pad.is_button_pressed(PadButtons::R3));
+        assert!(pad.is_button_pressed(PadButtons::SELECT));
+        assert!(!pad.is_button_pressed(PadButtons::L3));
+
+        // A USB-layout report is rejected on Bluetooth
+        assert!(parse_input_report(Ds4Model::DualSense, true, &ds4_report(), &mapping).is_none());
+    }
+}
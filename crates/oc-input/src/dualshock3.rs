@@ -9,8 +9,13 @@
 use crate::pad::{PadButtons, PadState};
 use std::time::{Duration, Instant};
 
+/// Accelerometer counts per g on a real DualShock 3
+pub const SIXAXIS_ACCEL_PER_G: f32 = 113.0;
+/// Gyroscope counts per degree/second on a real DualShock 3 (approximate)
+pub const SIXAXIS_GYRO_PER_DEG_S: f32 = 1.0;
+
 /// Sixaxis motion sensor data
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct SixaxisData {
     /// Accelerometer X axis (-512 to 511, 0 = level)
     pub accel_x: i16,
@@ -69,7 +74,7 @@ impl SixaxisData {
 }
 
 /// Vibration motor state
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct VibrationState {
     /// Small motor (high frequency, 0 or 1)
     pub small_motor: u8,
@@ -0,0 +1,549 @@
+//! Host gamepad backend (gilrs)
+//!
+//! Enumerates host game controllers through gilrs and handles hotplug.
+//! Controllers are read through the SDL-style standard layout and mapped
+//! onto the PS3 pad by the active [`InputProfile`]; the standard profile
+//! turns South/East/West/North into Cross/Circle/Square/Triangle. Each connected controller takes the lowest free port in the
+//! shared [`PadPorts`], which cellPad reads from. Rumble set on a port
+//! is played through the controller's force feedback where supported.
+
+use crate::dualshock3::VibrationState;
+use crate::instruments::InstrumentMapping;
+use crate::mapping::{axis_to_u8, HostInput, InputProfile, GAMEPAD_AXIS_NAMES, GAMEPAD_BUTTON_NAMES};
+use crate::pad::{GamepadEvent, HostGamepadInfo, PadButtons, PadPorts, PadState, MAX_PADS, PRESS_THRESHOLD};
+use oc_core::config::InstrumentKind;
+use gilrs::ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder};
+use gilrs::{Axis, Button, EventType, GamepadId, Gilrs};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Map a standard-layout button to a PS3 button
+pub fn map_button(button: Button) -> Option<PadButtons> {
+    Some(match button {
+        Button::South => PadButtons::CROSS,
+        Button::East => PadButtons::CIRCLE,
+        Button::West => PadButtons::SQUARE,
+        Button::North => PadButtons::TRIANGLE,
+        Button::LeftTrigger => PadButtons::L1,
+        Button::RightTrigger => PadButtons::R1,
+        Button::LeftTrigger2 => PadButtons::L2,
+        Button::RightTrigger2 => PadButtons::R2,
+        Button::Select => PadButtons::SELECT,
+        Button::Start => PadButtons::START,
+        Button::LeftThumb => PadButtons::L3,
+        Button::RightThumb => PadButtons::R3,
+        Button::DPadUp => PadButtons::DPAD_UP,
+        Button::DPadDown => PadButtons::DPAD_DOWN,
+        Button::DPadLeft => PadButtons::DPAD_LEFT,
+        Button::DPadRight => PadButtons::DPAD_RIGHT,
+        _ => return None,
+    })
+}
+
+/// Apply an analog button value (0.0..1.0) to a pad state
+pub fn apply_button(state: &mut PadState, button: Button, value: f32) {
+    if let Some(ps3) = map_button(button) {
+        state.press(ps3, value);
+    }
+}
+
+/// Apply an axis value (-1.0..1.0) to a pad state
+pub fn apply_axis(state: &mut PadState, axis: Axis, value: f32) {
+    match axis {
+        Axis::LeftStickX => state.left_x = axis_to_u8(value, false),
+        Axis::LeftStickY => state.left_y = axis_to_u8(value, true),
+        Axis::RightStickX => state.right_x = axis_to_u8(value, false),
+        Axis::RightStickY => state.right_y = axis_to_u8(value, true),
+        // Hat-style D-pads report as axes on some controllers
+        Axis::DPadX if value <= -PRESS_THRESHOLD => apply_button(state, Button::DPadLeft, 1.0),
+        Axis::DPadX if value >= PRESS_THRESHOLD => apply_button(state, Button::DPadRight, 1.0),
+        Axis::DPadY if value >= PRESS_THRESHOLD => apply_button(state, Button::DPadUp, 1.0),
+        Axis::DPadY if value <= -PRESS_THRESHOLD => apply_button(state, Button::DPadDown, 1.0),
+        _ => {}
+    }
+}
+
+/// Buttons sampled into the pad state, indexed like [`GAMEPAD_BUTTON_NAMES`]
+const BUTTONS: [Button; GAMEPAD_BUTTON_NAMES.len()] = [
+    Button::South,
+    Button::East,
+    Button::West,
+    Button::North,
+    Button::LeftTrigger,
+    Button::RightTrigger,
+    Button::LeftTrigger2,
+    Button::RightTrigger2,
+    Button::Select,
+    Button::Start,
+    Button::LeftThumb,
+    Button::RightThumb,
+    Button::DPadUp,
+    Button::DPadDown,
+    Button::DPadLeft,
+    Button::DPadRight,
+    Button::Mode,
+];
+
+/// Axes sampled into the pad state, indexed like [`GAMEPAD_AXIS_NAMES`]
+const AXES: [Axis; GAMEPAD_AXIS_NAMES.len()] = [
+    Axis::LeftStickX,
+    Axis::LeftStickY,
+    Axis::RightStickX,
+    Axis::RightStickY,
+    Axis::LeftZ,
+    Axis::RightZ,
+];
+
+/// Host input index of a gilrs button
+fn button_index(button: Button) -> Option<u8> {
+    BUTTONS.iter().position(|&b| b == button).map(|i| i as u8)
+}
+
+/// Host input index of a gilrs axis
+fn axis_index(axis: Axis) -> Option<u8> {
+    AXES.iter().position(|&a| a == axis).map(|i| i as u8)
+}
+
+/// Force feedback magnitudes (strong, weak) for a PS3 rumble state
+///
+/// The DS3 large motor maps to the strong (low frequency) motor; its
+/// on/off small motor runs the weak motor at full strength.
+fn rumble_magnitudes(vibration: &VibrationState) -> (u16, u16) {
+    let strong = vibration.large_motor as u16 * 257;
+    let weak = if vibration.small_motor > 0 { u16::MAX } else { 0 };
+    (strong, weak)
+}
+
+/// Rumble playing on one controller
+struct Rumble {
+    state: VibrationState,
+    /// Running effect; dropping it stops the motors
+    _effect: Option<Effect>,
+}
+
+/// Host gamepad backend
+pub struct GamepadBackend {
+    /// gilrs context (None if the platform backend failed to start)
+    gilrs: Option<Gilrs>,
+    /// Port assigned to each connected host controller
+    assigned: HashMap<GamepadId, u8>,
+    /// Ports shared with cellPad
+    ports: Arc<PadPorts>,
+    /// (vendor, product) IDs handled by another backend
+    ignored: Vec<(u16, u16)>,
+    /// Mapping applied to every controller
+    profile: InputProfile,
+    /// Mappings overriding `profile` on individual ports
+    port_profiles: [Option<InputProfile>; MAX_PADS],
+    /// Ports whose controllers are played as a guitar or drum kit
+    port_instruments: [Option<InstrumentMapping>; MAX_PADS],
+    /// Most recent button press or large axis movement
+    last_input: Option<HostInput>,
+    /// PS (guide) button pressed since the last [`take_ps_button`](Self::take_ps_button)
+    ps_pressed: bool,
+    /// Forward game rumble
+    rumble_enabled: bool,
+    rumble: HashMap<GamepadId, Rumble>,
+}
+
+impl GamepadBackend {
+    /// Create a backend publishing to `ports`
+    ///
+    /// Controllers that are already plugged in are assigned immediately.
+    pub fn new(ports: Arc<PadPorts>) -> Self {
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => {
+                tracing::warn!("Failed to initialize gamepad backend: {}", e);
+                None
+            }
+        };
+
+        let mut backend = Self {
+            gilrs,
+            assigned: HashMap::new(),
+            ports,
+            ignored: Vec::new(),
+            profile: InputProfile::standard(),
+            port_profiles: Default::default(),
+            port_instruments: Default::default(),
+            last_input: None,
+            ps_pressed: false,
+            rumble_enabled: true,
+            rumble: HashMap::new(),
+        };
+        backend.attach_all();
+        backend
+    }
+
+    /// Whether host gamepads can be read
+    pub fn is_available(&self) -> bool {
+        self.gilrs.is_some()
+    }
+
+    /// Ports shared with cellPad
+    pub fn ports(&self) -> &Arc<PadPorts> {
+        &self.ports
+    }
+
+    /// Mapping profile in use
+    pub fn profile(&self) -> &InputProfile {
+        &self.profile
+    }
+
+    /// Switch the mapping profile
+    pub fn set_profile(&mut self, profile: InputProfile) {
+        tracing::info!("Using input profile \"{}\"", profile.name);
+        self.profile = profile;
+    }
+
+    /// Use a different mapping profile on one port (None = the shared profile)
+    pub fn set_port_profile(&mut self, port: u8, profile: Option<InputProfile>) {
+        let Some(slot) = self.port_profiles.get_mut(port as usize) else {
+            return;
+        };
+        if let Some(profile) = &profile {
+            tracing::info!("Using input profile \"{}\" on port {}", profile.name, port);
+        }
+        *slot = profile;
+    }
+
+    /// Play the controller on a port as an instrument (None = a regular pad)
+    pub fn set_port_instrument(&mut self, port: u8, instrument: Option<InstrumentMapping>) {
+        let Some(slot) = self.port_instruments.get_mut(port as usize) else {
+            return;
+        };
+        let kind = instrument.as_ref().map_or(InstrumentKind::None, InstrumentMapping::kind);
+        if self.assigned.values().any(|&assigned| assigned == port) {
+            self.ports.set_instrument(port, kind);
+        }
+        *slot = instrument;
+    }
+
+    /// Mapping profile applied to a port
+    pub fn port_profile(&self, port: u8) -> &InputProfile {
+        self.port_profiles
+            .get(port as usize)
+            .and_then(Option::as_ref)
+            .unwrap_or(&self.profile)
+    }
+
+    /// Forward game rumble to the controllers (on by default)
+    pub fn set_rumble_enabled(&mut self, enabled: bool) {
+        self.rumble_enabled = enabled;
+        if !enabled {
+            self.rumble.clear();
+        }
+    }
+
+    /// Take the most recent physical input, for binding in a mapping editor
+    ///
+    /// Only inputs seen by [`poll`](Self::poll) are reported.
+    pub fn take_last_input(&mut self) -> Option<HostInput> {
+        self.last_input.take()
+    }
+
+    /// Whether a controller's PS button was pressed since the last call
+    ///
+    /// The button has no cellPad equivalent; the frontend uses it to open its
+    /// menu. Only presses seen by [`poll`](Self::poll) are reported.
+    pub fn take_ps_button(&mut self) -> bool {
+        std::mem::take(&mut self.ps_pressed)
+    }
+
+    /// Leave controllers with this USB ID to another backend
+    ///
+    /// Used when a passthrough driver such as [`Ds3HidBackend`](crate::ds3_hid::Ds3HidBackend)
+    /// reads the device directly. Controllers already assigned are released.
+    pub fn ignore_device(&mut self, vendor_id: u16, product_id: u16) {
+        self.ignored.push((vendor_id, product_id));
+        let Some(gilrs) = self.gilrs.as_ref() else {
+            return;
+        };
+        let released: Vec<GamepadId> = self
+            .assigned
+            .keys()
+            .copied()
+            .filter(|&id| {
+                let gamepad = gilrs.gamepad(id);
+                gamepad.vendor_id() == Some(vendor_id) && gamepad.product_id() == Some(product_id)
+            })
+            .collect();
+        for id in released {
+            self.detach(id);
+        }
+    }
+
+    /// Process pending host events and publish pad state
+    ///
+    /// Call once per frame. Returns the hotplug events that occurred.
+    pub fn poll(&mut self) -> Vec<GamepadEvent> {
+        let mut events = Vec::new();
+        let Some(gilrs) = self.gilrs.as_mut() else {
+            return events;
+        };
+
+        let mut hotplug = Vec::new();
+        while let Some(event) = gilrs.next_event() {
+            match event.event {
+                EventType::Connected => hotplug.push((event.id, true)),
+                EventType::Disconnected => hotplug.push((event.id, false)),
+                EventType::ButtonPressed(button, _) => {
+                    if button == Button::Mode {
+                        self.ps_pressed = true;
+                    }
+                    if let Some(i) = button_index(button) {
+                        self.last_input = Some(HostInput::GamepadButton(i));
+                    }
+                }
+                EventType::AxisChanged(axis, value, _) if value.abs() >= PRESS_THRESHOLD => {
+                    if let Some(i) = axis_index(axis) {
+                        self.last_input = Some(HostInput::GamepadAxis(i));
+                    }
+                }
+                _ => {}
+            }
+        }
+        for (id, connected) in hotplug {
+            let event = if connected { self.attach(id) } else { self.detach(id) };
+            events.extend(event);
+        }
+
+        if let Some(gilrs) = self.gilrs.as_ref() {
+            for (&id, &port) in &self.assigned {
+                if let Some(gamepad) = gilrs.connected_gamepad(id) {
+                    let instrument = self.port_instruments[port as usize].as_ref();
+                    let state = Self::read_state(&gamepad, self.port_profile(port), instrument);
+                    self.ports.update(port, state);
+                }
+            }
+        }
+        if self.rumble_enabled {
+            self.update_rumble();
+        }
+        events
+    }
+
+    /// Restart force feedback on controllers whose port rumble changed
+    fn update_rumble(&mut self) {
+        let Some(gilrs) = self.gilrs.as_mut() else {
+            return;
+        };
+        for (&id, &port) in &self.assigned {
+            let state = self.ports.vibration(port);
+            let current = self.rumble.get(&id).map(|rumble| rumble.state);
+            if current == Some(state) || (current.is_none() && !state.is_active()) {
+                continue;
+            }
+            if !gilrs.connected_gamepad(id).is_some_and(|gamepad| gamepad.is_ff_supported()) {
+                continue;
+            }
+
+            // Effects can't change strength while playing, so replace it
+            let effect = if state.is_active() {
+                let (strong, weak) = rumble_magnitudes(&state);
+                let effect = EffectBuilder::new()
+                    .add_effect(BaseEffect {
+                        kind: BaseEffectType::Strong { magnitude: strong },
+                        ..Default::default()
+                    })
+                    .add_effect(BaseEffect {
+                        kind: BaseEffectType::Weak { magnitude: weak },
+                        ..Default::default()
+                    })
+                    .gamepads(&[id])
+                    .finish(gilrs)
+                    .and_then(|effect| effect.play().map(|_| effect));
+                match effect {
+                    Ok(effect) => Some(effect),
+                    Err(e) => {
+                        tracing::debug!("Failed to start rumble on port {}: {}", port, e);
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+            self.rumble.insert(id, Rumble { state, _effect: effect });
+        }
+    }
+
+    /// Connected host controllers
+    pub fn devices(&self) -> Vec<HostGamepadInfo> {
+        let Some(gilrs) = self.gilrs.as_ref() else {
+            return Vec::new();
+        };
+        let mut devices: Vec<HostGamepadInfo> = self
+            .assigned
+            .iter()
+            .filter_map(|(&id, &port)| {
+                let gamepad = gilrs.connected_gamepad(id)?;
+                Some(HostGamepadInfo {
+                    name: gamepad.name().to_string(),
+                    port,
+                    vendor_id: gamepad.vendor_id(),
+                    product_id: gamepad.product_id(),
+                    has_mapping: gamepad.mapping_source() != gilrs::MappingSource::None,
+                })
+            })
+            .collect();
+        devices.sort_by_key(|device| device.port);
+        devices
+    }
+
+    /// Current axes of the controller on a port, indexed like [`GAMEPAD_AXIS_NAMES`]
+    ///
+    /// Values are unprocessed, before the profile's dead zone and response curve.
+    pub fn raw_axes(&self, port: u8) -> Option<[f32; GAMEPAD_AXIS_NAMES.len()]> {
+        let gilrs = self.gilrs.as_ref()?;
+        let (&id, _) = self.assigned.iter().find(|(_, &assigned)| assigned == port)?;
+        let gamepad = gilrs.connected_gamepad(id)?;
+        Some(AXES.map(|axis| gamepad.value(axis)))
+    }
+
+    /// Release every controller's port
+    ///
+    /// Used before reassigning ports; see [`attach_all`](Self::attach_all).
+    pub fn release_all(&mut self) {
+        let ids: Vec<GamepadId> = self.assigned.keys().copied().collect();
+        for id in ids {
+            self.detach(id);
+        }
+    }
+
+    /// Assign every connected controller that has no port yet
+    pub fn attach_all(&mut self) -> Vec<GamepadEvent> {
+        let connected: Vec<GamepadId> = self
+            .gilrs
+            .iter()
+            .flat_map(|gilrs| gilrs.gamepads().map(|(id, _)| id))
+            .collect();
+        connected.into_iter().filter_map(|id| self.attach(id)).collect()
+    }
+
+    /// Assign a newly connected controller to a port
+    fn attach(&mut self, id: GamepadId) -> Option<GamepadEvent> {
+        if self.assigned.contains_key(&id) {
+            return None;
+        }
+        let gamepad = self.gilrs.as_ref()?.gamepad(id);
+        if let (Some(vid), Some(pid)) = (gamepad.vendor_id(), gamepad.product_id()) {
+            if self.ignored.contains(&(vid, pid)) {
+                return None;
+            }
+        }
+        let name = gamepad.name().to_string();
+        match self.ports.connect_device(&name) {
+            Some(port) => {
+                self.assigned.insert(id, port);
+                if let Some(instrument) = &self.port_instruments[port as usize] {
+                    self.ports.set_instrument(port, instrument.kind());
+                }
+                tracing::info!("Gamepad \"{}\" connected on port {}", name, port);
+                Some(GamepadEvent::Connected { port, name })
+            }
+            None => {
+                tracing::warn!("Gamepad \"{}\" connected but all pad ports are in use", name);
+                Some(GamepadEvent::NoFreePort { name })
+            }
+        }
+    }
+
+    /// Release the port of an unplugged controller
+    fn detach(&mut self, id: GamepadId) -> Option<GamepadEvent> {
+        let port = self.assigned.remove(&id)?;
+        self.rumble.remove(&id);
+        self.ports.disconnect(port);
+        tracing::info!("Gamepad on port {} disconnected", port);
+        Some(GamepadEvent::Disconnected { port })
+    }
+
+    /// Sample a controller into a PS3 pad state
+    fn read_state(
+        gamepad: &gilrs::Gamepad<'_>,
+        profile: &InputProfile,
+        instrument: Option<&InstrumentMapping>,
+    ) -> PadState {
+        let mut buttons = BUTTONS.map(|button| {
+            gamepad
+                .button_data(button)
+                .map_or(0.0, |data| data.value())
+        });
+        // Hat-style D-pads report as axes on some controllers
+        let hats = [
+            (Axis::DPadY, 1.0, Button::DPadUp),
+            (Axis::DPadY, -1.0, Button::DPadDown),
+            (Axis::DPadX, -1.0, Button::DPadLeft),
+            (Axis::DPadX, 1.0, Button::DPadRight),
+        ];
+        for (axis, direction, button) in hats {
+            if gamepad.value(axis) * direction >= PRESS_THRESHOLD {
+                if let Some(i) = button_index(button) {
+                    buttons[i as usize] = 1.0;
+                }
+            }
+        }
+        let axes = AXES.map(|axis| gamepad.value(axis));
+
+        if let Some(instrument) = instrument {
+            return instrument.apply_gamepad(&buttons, &axes);
+        }
+        let mut state = PadState::new();
+        profile.apply_gamepad(&mut state, &buttons, &axes);
+        state
+    }
+}
+
+impl Drop for GamepadBackend {
+    fn drop(&mut self) {
+        for port in self.assigned.values() {
+            self.ports.disconnect(*port);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dualshock3::pressure_index;
+
+    #[test]
+    fn test_standard_mapping() {
+        let mut state = PadState::new();
+        apply_button(&mut state, Button::South, 1.0);
+        apply_button(&mut state, Button::North, 0.2);
+        assert!(state.is_button_pressed(PadButtons::CROSS));
+        // Below the threshold: pressure only
+        assert!(!state.is_button_pressed(PadButtons::TRIANGLE));
+        assert_eq!(state.pressure[pressure_index::CROSS], 255);
+        assert_eq!(state.pressure[pressure_index::TRIANGLE], 51);
+        assert!(map_button(Button::Mode).is_none());
+    }
+
+    #[test]
+    fn test_rumble_magnitudes() {
+        let mut vibration = VibrationState::new();
+        assert_eq!(rumble_magnitudes(&vibration), (0, 0));
+        vibration.set_small_motor(true);
+        vibration.set_large_motor(255);
+        assert_eq!(rumble_magnitudes(&vibration), (u16::MAX, u16::MAX));
+        vibration.set_small_motor(false);
+        vibration.set_large_motor(128);
+        assert_eq!(rumble_magnitudes(&vibration), (128 * 257, 0));
+    }
+
+    #[test]
+    fn test_axis_conversion() {
+        let mut state = PadState::new();
+        apply_axis(&mut state, Axis::LeftStickX, -1.0);
+        apply_axis(&mut state, Axis::LeftStickY, 1.0);
+        apply_axis(&mut state, Axis::RightStickX, 0.0);
+        // PS3 Y axis grows downwards
+        assert_eq!(state.left_x, 0);
+        assert_eq!(state.left_y, 0);
+        assert_eq!(state.right_x, 128);
+
+        apply_axis(&mut state, Axis::DPadY, -1.0);
+        assert!(state.is_button_pressed(PadButtons::DPAD_DOWN));
+    }
+}
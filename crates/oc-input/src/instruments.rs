@@ -5,8 +5,19 @@
 //! - Drum kits (4-pad and Pro drums)
 //! - DJ Hero turntable (limited)
 //! - Microphone passthrough
-
-use std::time::Instant;
+//!
+//! Real USB instruments are rare, so guitars and drums can also be played
+//! on ordinary gamepads, the keyboard or a MIDI e-drum kit through an
+//! [`InstrumentMapping`]. The result is reported to cellPad as the pad
+//! state of a Rock Band instrument.
+
+use crate::keyboard::KeyboardState;
+use crate::mapping::HostInput;
+use crate::midi::MidiMessage;
+use crate::pad::{PadButtons, PadState, PRESS_THRESHOLD};
+use oc_core::config::{InputConfig, InstrumentConfig, InstrumentKind};
+use std::fmt;
+use std::time::{Duration, Instant};
 
 /// Guitar fret buttons
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
@@ -588,6 +599,433 @@ impl Default for InstrumentManager {
     }
 }
 
+// ============================================================================
+// Mapping from host inputs
+// ============================================================================
+
+/// Instrument input a host input can drive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstrumentInput {
+    Fret(GuitarFrets),
+    StrumUp,
+    StrumDown,
+    StarPower,
+    /// Whammy bar (analog)
+    Whammy,
+    /// Tilt sensor (analog)
+    Tilt,
+    Pad(DrumPads),
+    Start,
+    Select,
+}
+
+/// Configuration names of the guitar inputs
+pub const GUITAR_INPUT_NAMES: [(&str, InstrumentInput); 12] = [
+    ("green", InstrumentInput::Fret(GuitarFrets::GREEN)),
+    ("red", InstrumentInput::Fret(GuitarFrets::RED)),
+    ("yellow", InstrumentInput::Fret(GuitarFrets::YELLOW)),
+    ("blue", InstrumentInput::Fret(GuitarFrets::BLUE)),
+    ("orange", InstrumentInput::Fret(GuitarFrets::ORANGE)),
+    ("fret6", InstrumentInput::Fret(GuitarFrets::FRET6)),
+    ("strum_up", InstrumentInput::StrumUp),
+    ("strum_down", InstrumentInput::StrumDown),
+    ("star_power", InstrumentInput::StarPower),
+    ("whammy", InstrumentInput::Whammy),
+    ("tilt", InstrumentInput::Tilt),
+    ("start", InstrumentInput::Start),
+];
+
+/// Configuration names of the drum inputs
+pub const DRUM_INPUT_NAMES: [(&str, InstrumentInput); 13] = [
+    ("red", InstrumentInput::Pad(DrumPads::RED)),
+    ("yellow", InstrumentInput::Pad(DrumPads::YELLOW)),
+    ("blue", InstrumentInput::Pad(DrumPads::BLUE)),
+    ("green", InstrumentInput::Pad(DrumPads::GREEN)),
+    ("orange", InstrumentInput::Pad(DrumPads::ORANGE)),
+    ("kick", InstrumentInput::Pad(DrumPads::KICK)),
+    ("kick2", InstrumentInput::Pad(DrumPads::KICK2)),
+    ("yellow_cymbal", InstrumentInput::Pad(DrumPads::YELLOW_CYMBAL)),
+    ("blue_cymbal", InstrumentInput::Pad(DrumPads::BLUE_CYMBAL)),
+    ("green_cymbal", InstrumentInput::Pad(DrumPads::GREEN_CYMBAL)),
+    ("hihat_pedal", InstrumentInput::Pad(DrumPads::HIHAT_PEDAL)),
+    ("start", InstrumentInput::Start),
+    ("select", InstrumentInput::Select),
+];
+
+/// Default guitar layout: face buttons as frets, D-pad to strum, F1-F5 on the keyboard
+const GUITAR_DEFAULTS: [(&str, &str); 10] = [
+    ("green", "pad:south,key:F1"),
+    ("red", "pad:east,key:F2"),
+    ("yellow", "pad:north,key:F3"),
+    ("blue", "pad:west,key:F4"),
+    ("orange", "pad:left_shoulder,key:F5"),
+    ("strum_up", "pad:dpad_up,key:Up"),
+    ("strum_down", "pad:dpad_down,key:Down"),
+    ("star_power", "pad:select,key:Backspace"),
+    ("whammy", "axis:right_x"),
+    ("start", "pad:start,key:Enter"),
+];
+
+/// Default drum layout, including the General MIDI percussion notes
+const DRUM_DEFAULTS: [(&str, &str); 11] = [
+    ("red", "pad:east,key:D,midi:38,midi:40,midi:37"),
+    ("yellow", "pad:north,key:F,midi:48,midi:50"),
+    ("blue", "pad:west,key:J,midi:45,midi:47"),
+    ("green", "pad:south,key:K,midi:41,midi:43"),
+    ("kick", "pad:left_shoulder,key:Space,midi:35,midi:36"),
+    ("yellow_cymbal", "key:R,midi:42,midi:46,midi:22,midi:26"),
+    ("blue_cymbal", "key:U,midi:51,midi:53,midi:59"),
+    ("green_cymbal", "key:I,midi:49,midi:52,midi:55,midi:57"),
+    ("hihat_pedal", "midi:44"),
+    ("start", "pad:start,key:Enter"),
+    ("select", "pad:select,key:Backspace"),
+];
+
+/// PS3 buttons of the guitar frets (Rock Band layout)
+const FRET_BUTTONS: [(GuitarFrets, PadButtons); 6] = [
+    (GuitarFrets::GREEN, PadButtons::CROSS),
+    (GuitarFrets::RED, PadButtons::CIRCLE),
+    (GuitarFrets::YELLOW, PadButtons::TRIANGLE),
+    (GuitarFrets::BLUE, PadButtons::SQUARE),
+    (GuitarFrets::ORANGE, PadButtons::L1),
+    (GuitarFrets::FRET6, PadButtons::R1),
+];
+
+/// PS3 buttons of the drum pads (Rock Band layout)
+///
+/// Drum heads also press R3 and cymbals R1, the flags Pro drum games use
+/// to tell them apart.
+const DRUM_BUTTONS: [(DrumPads, PadButtons); 11] = [
+    (DrumPads::RED, PadButtons::CIRCLE),
+    (DrumPads::YELLOW, PadButtons::TRIANGLE),
+    (DrumPads::BLUE, PadButtons::SQUARE),
+    (DrumPads::GREEN, PadButtons::CROSS),
+    (DrumPads::ORANGE, PadButtons::R2),
+    (DrumPads::KICK, PadButtons::L1),
+    (DrumPads::KICK2, PadButtons::L1),
+    (DrumPads::YELLOW_CYMBAL, PadButtons::TRIANGLE),
+    (DrumPads::BLUE_CYMBAL, PadButtons::SQUARE),
+    (DrumPads::GREEN_CYMBAL, PadButtons::CROSS),
+    (DrumPads::HIHAT_PEDAL, PadButtons::L2),
+];
+
+/// How long a MIDI drum hit stays pressed, so a game polling once per frame sees it
+const HIT_DURATION: Duration = Duration::from_millis(50);
+
+/// Host input driving an instrument
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InstrumentSource {
+    /// Keyboard key or gamepad button/axis
+    Host(HostInput),
+    /// MIDI note number (any channel)
+    MidiNote(u8),
+}
+
+impl InstrumentSource {
+    /// Parse a configuration string: a host input ("pad:south", "key:F1") or "midi:38"
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().strip_prefix("midi:") {
+            Some(note) => note.trim().parse().ok().filter(|&note: &u8| note < 128).map(Self::MidiNote),
+            None => HostInput::parse(s).map(Self::Host),
+        }
+    }
+}
+
+impl fmt::Display for InstrumentSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Host(input) => input.fmt(f),
+            Self::MidiNote(note) => write!(f, "midi:{}", note),
+        }
+    }
+}
+
+/// Guitar or drum state built from host inputs
+#[derive(Debug, Clone)]
+pub enum InstrumentState {
+    Guitar(GuitarState),
+    Drums(DrumState),
+}
+
+impl InstrumentState {
+    /// Idle state of an instrument (a regular controller maps like a guitar)
+    pub fn new(kind: InstrumentKind) -> Self {
+        match kind {
+            InstrumentKind::Drums => Self::Drums(DrumState::new()),
+            _ => Self::Guitar(GuitarState::new()),
+        }
+    }
+
+    /// Drive an input with a value from 0.0 to 1.0
+    ///
+    /// Drum pads take any non-zero value as a hit with that velocity;
+    /// other digital inputs need [`PRESS_THRESHOLD`].
+    pub fn apply(&mut self, input: InstrumentInput, value: f32) {
+        let pressed = value >= PRESS_THRESHOLD;
+        // Analog inputs rest at the center and deflect one way
+        let analog = (128.0 + value.clamp(0.0, 1.0) * 127.0).round() as u8;
+        match (self, input) {
+            (Self::Guitar(guitar), InstrumentInput::Fret(fret)) if pressed => guitar.frets.insert(fret),
+            (Self::Guitar(guitar), InstrumentInput::StrumUp) if pressed => guitar.buttons.insert(GuitarButtons::STRUM_UP),
+            (Self::Guitar(guitar), InstrumentInput::StrumDown) if pressed => {
+                guitar.buttons.insert(GuitarButtons::STRUM_DOWN)
+            }
+            (Self::Guitar(guitar), InstrumentInput::StarPower) if pressed => {
+                guitar.buttons.insert(GuitarButtons::STAR_POWER)
+            }
+            (Self::Guitar(guitar), InstrumentInput::Start) if pressed => guitar.buttons.insert(GuitarButtons::START),
+            (Self::Guitar(guitar), InstrumentInput::Select) if pressed => guitar.buttons.insert(GuitarButtons::SELECT),
+            (Self::Guitar(guitar), InstrumentInput::Whammy) => guitar.whammy = guitar.whammy.max(analog),
+            (Self::Guitar(guitar), InstrumentInput::Tilt) => guitar.tilt = guitar.tilt.max(analog),
+            (Self::Drums(drums), InstrumentInput::Pad(pad)) if value > 0.0 => {
+                let velocity = ((value.min(1.0) * 127.0).round() as u8).max(1);
+                drums.hit_pad(pad, drums.get_velocity(pad).max(velocity));
+            }
+            (Self::Drums(drums), InstrumentInput::Start) if pressed => drums.start = true,
+            (Self::Drums(drums), InstrumentInput::Select) if pressed => drums.select = true,
+            _ => {}
+        }
+    }
+
+    /// Report the instrument as a cellPad state
+    pub fn to_pad_state(&self) -> PadState {
+        let mut state = PadState::new();
+        match self {
+            Self::Guitar(guitar) => {
+                for (fret, button) in FRET_BUTTONS {
+                    if guitar.frets.contains(fret) {
+                        state.press(button, 1.0);
+                    }
+                }
+                let buttons = [
+                    (GuitarButtons::STRUM_UP, PadButtons::DPAD_UP),
+                    (GuitarButtons::STRUM_DOWN, PadButtons::DPAD_DOWN),
+                    (GuitarButtons::STAR_POWER, PadButtons::SELECT),
+                    (GuitarButtons::SELECT, PadButtons::SELECT),
+                    (GuitarButtons::START, PadButtons::START),
+                ];
+                for (guitar_button, button) in buttons {
+                    if guitar.buttons.contains(guitar_button) {
+                        state.press(button, 1.0);
+                    }
+                }
+                // The tilt sensor triggers star power
+                if guitar.is_tilted() {
+                    state.press(PadButtons::SELECT, 1.0);
+                }
+                state.right_x = guitar.whammy;
+                state.right_y = guitar.tilt;
+            }
+            Self::Drums(drums) => {
+                for (pad, button) in DRUM_BUTTONS {
+                    if !drums.pads.contains(pad) {
+                        continue;
+                    }
+                    state.press(button, drums.get_velocity(pad) as f32 / 127.0);
+                    if matches!(pad, DrumPads::YELLOW_CYMBAL | DrumPads::BLUE_CYMBAL | DrumPads::GREEN_CYMBAL) {
+                        state.press(PadButtons::R1, 1.0);
+                    } else if !matches!(pad, DrumPads::KICK | DrumPads::KICK2 | DrumPads::HIHAT_PEDAL) {
+                        state.press(PadButtons::R3, 1.0);
+                    }
+                }
+                state.set_button(PadButtons::START, drums.start);
+                state.set_button(PadButtons::SELECT, drums.select);
+            }
+        }
+        state
+    }
+}
+
+/// Host input layout of an emulated guitar or drum kit
+#[derive(Debug, Clone)]
+pub struct InstrumentMapping {
+    kind: InstrumentKind,
+    bindings: Vec<(InstrumentSource, InstrumentInput)>,
+}
+
+impl InstrumentMapping {
+    /// Inputs of an instrument with their configuration names
+    pub fn inputs(kind: InstrumentKind) -> &'static [(&'static str, InstrumentInput)] {
+        match kind {
+            InstrumentKind::None => &[],
+            InstrumentKind::Guitar => &GUITAR_INPUT_NAMES,
+            InstrumentKind::Drums => &DRUM_INPUT_NAMES,
+        }
+    }
+
+    /// Default host inputs of an instrument input, as a configuration string
+    pub fn default_binding(kind: InstrumentKind, name: &str) -> &'static str {
+        let defaults: &[(&str, &str)] = match kind {
+            InstrumentKind::None => &[],
+            InstrumentKind::Guitar => &GUITAR_DEFAULTS,
+            InstrumentKind::Drums => &DRUM_DEFAULTS,
+        };
+        defaults
+            .iter()
+            .find(|(input, _)| *input == name)
+            .map_or("", |&(_, sources)| sources)
+    }
+
+    /// Build the layout of an instrument (None for a regular controller)
+    ///
+    /// Configured bindings replace the default ones of the same input.
+    pub fn from_config(kind: InstrumentKind, config: &InstrumentConfig) -> Option<Self> {
+        let overrides = match kind {
+            InstrumentKind::None => return None,
+            InstrumentKind::Guitar => &config.guitar_bindings,
+            InstrumentKind::Drums => &config.drum_bindings,
+        };
+        for name in overrides.keys() {
+            if !Self::inputs(kind).iter().any(|(input, _)| input == name) {
+                tracing::warn!("Instrument binding: unknown {:?} input \"{}\"", kind, name);
+            }
+        }
+
+        let mut bindings = Vec::new();
+        for &(name, input) in Self::inputs(kind) {
+            let sources = overrides
+                .get(name)
+                .map_or(Self::default_binding(kind, name), String::as_str);
+            if sources.trim().eq_ignore_ascii_case("none") {
+                continue;
+            }
+            for source in sources.split(',').filter(|source| !source.trim().is_empty()) {
+                match InstrumentSource::parse(source) {
+                    Some(source) => bindings.push((source, input)),
+                    None => tracing::warn!("Instrument binding \"{}\": unknown host input \"{}\"", name, source.trim()),
+                }
+            }
+        }
+        Some(Self { kind, bindings })
+    }
+
+    /// Layout for generic gamepads on a pad port (None if it is a regular controller)
+    pub fn for_port(config: &InputConfig, port: u8) -> Option<Self> {
+        let kind = config.controller.ports.get(port as usize)?.instrument;
+        Self::from_config(kind, &config.instruments)
+    }
+
+    /// Instrument this layout plays
+    pub fn kind(&self) -> InstrumentKind {
+        self.kind
+    }
+
+    /// Map gamepad readings to the instrument's pad state
+    ///
+    /// `buttons` and `axes` are indexed like
+    /// [`InputProfile::apply_gamepad`](crate::mapping::InputProfile::apply_gamepad)
+    /// expects. Axes count in either direction.
+    pub fn apply_gamepad(&self, buttons: &[f32], axes: &[f32]) -> PadState {
+        let mut state = InstrumentState::new(self.kind);
+        for &(source, input) in &self.bindings {
+            let value = match source {
+                InstrumentSource::Host(HostInput::GamepadButton(i)) => buttons.get(i as usize).copied(),
+                InstrumentSource::Host(HostInput::GamepadAxis(i)) => axes.get(i as usize).map(|value| value.abs()),
+                _ => None,
+            };
+            if let Some(value) = value {
+                state.apply(input, value);
+            }
+        }
+        state.to_pad_state()
+    }
+}
+
+/// Instrument played on the keyboard or a MIDI device
+///
+/// Drum hits from MIDI are held for a short time even if the kit sends
+/// note-off right away, so the game sees every hit.
+pub struct MappedInstrument {
+    mapping: InstrumentMapping,
+    keyboard: KeyboardState,
+    /// Velocity of each held MIDI note (0 = released)
+    notes: [u8; 128],
+    /// Recent drum hits: note, velocity and time left
+    hits: Vec<(u8, u8, Duration)>,
+}
+
+impl MappedInstrument {
+    /// Create an instrument playing `mapping`
+    pub fn new(mapping: InstrumentMapping) -> Self {
+        Self {
+            mapping,
+            keyboard: KeyboardState::new(),
+            notes: [0; 128],
+            hits: Vec::new(),
+        }
+    }
+
+    /// Device name of an emulated instrument, used to pin it to a port
+    pub fn device_name(kind: InstrumentKind) -> &'static str {
+        match kind {
+            InstrumentKind::Drums => "Emulated Drums",
+            _ => "Emulated Guitar",
+        }
+    }
+
+    /// Instrument being played
+    pub fn kind(&self) -> InstrumentKind {
+        self.mapping.kind
+    }
+
+    /// Record a key press or release (USB HID usage code)
+    pub fn key_event(&mut self, key_code: u16, pressed: bool) {
+        if pressed {
+            self.keyboard.press_key(key_code);
+        } else {
+            self.keyboard.release_key(key_code);
+        }
+    }
+
+    /// Record a MIDI note
+    pub fn midi_message(&mut self, message: MidiMessage) {
+        match message {
+            MidiMessage::NoteOn { note, velocity, .. } => {
+                self.notes[note as usize & 0x7F] = velocity;
+                if self.mapping.kind == InstrumentKind::Drums {
+                    self.hits.push((note, velocity, HIT_DURATION));
+                }
+            }
+            MidiMessage::NoteOff { note, .. } => self.notes[note as usize & 0x7F] = 0,
+        }
+    }
+
+    /// Release every key and note (e.g. when the window loses focus)
+    pub fn release_all(&mut self) {
+        self.keyboard.clear();
+        self.notes = [0; 128];
+        self.hits.clear();
+    }
+
+    /// Advance hit timers by `dt` and produce the pad state
+    pub fn update(&mut self, dt: Duration) -> PadState {
+        let mut state = InstrumentState::new(self.mapping.kind);
+        for &(source, input) in &self.mapping.bindings {
+            let value = match source {
+                InstrumentSource::Host(HostInput::Key(key)) => f32::from(u8::from(self.keyboard.is_key_pressed(key))),
+                InstrumentSource::MidiNote(note) => {
+                    let hit = self.hits.iter().filter(|hit| hit.0 == note).map(|hit| hit.1).max();
+                    let velocity = hit.unwrap_or(0).max(self.notes[note as usize & 0x7F]);
+                    match input {
+                        // Only drums care about velocity; soft notes still press frets
+                        InstrumentInput::Pad(_) => velocity as f32 / 127.0,
+                        _ => f32::from(u8::from(velocity > 0)),
+                    }
+                }
+                _ => 0.0,
+            };
+            if value > 0.0 {
+                state.apply(input, value);
+            }
+        }
+        self.hits.retain_mut(|hit| {
+            hit.2 = hit.2.saturating_sub(dt);
+            !hit.2.is_zero()
+        });
+        state.to_pad_state()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -634,4 +1072,54 @@ mod tests {
         
         assert_eq!(manager.connected_count(), 2);
     }
+
+    #[test]
+    fn test_gamepad_guitar() {
+        let mut config = InstrumentConfig::default();
+        config.guitar_bindings.insert("orange".to_string(), "pad:right_shoulder".to_string());
+        config.guitar_bindings.insert("whammy".to_string(), "none".to_string());
+        let mapping = InstrumentMapping::from_config(InstrumentKind::Guitar, &config).unwrap();
+        assert!(InstrumentMapping::from_config(InstrumentKind::None, &config).is_none());
+
+        let mut buttons = [0.0; 17];
+        buttons[0] = 1.0; // south: green
+        buttons[5] = 1.0; // right shoulder: orange
+        buttons[12] = 1.0; // D-pad up: strum
+        let mut axes = [0.0; 6];
+        axes[2] = -1.0; // right stick X: whammy is unbound
+        let state = mapping.apply_gamepad(&buttons, &axes);
+        assert!(state.is_button_pressed(PadButtons::CROSS));
+        assert!(state.is_button_pressed(PadButtons::L1));
+        assert!(state.is_button_pressed(PadButtons::DPAD_UP));
+        assert!(!state.is_button_pressed(PadButtons::CIRCLE));
+        assert_eq!(state.right_x, 128);
+    }
+
+    #[test]
+    fn test_midi_drums() {
+        let mapping = InstrumentMapping::from_config(InstrumentKind::Drums, &InstrumentConfig::default()).unwrap();
+        let mut drums = MappedInstrument::new(mapping);
+        let frame = Duration::from_millis(16);
+
+        // Snare hit released at once still shows for a few frames, with its velocity
+        drums.midi_message(MidiMessage::NoteOn { channel: 9, note: 38, velocity: 64 });
+        drums.midi_message(MidiMessage::NoteOff { channel: 9, note: 38 });
+        let state = drums.update(frame);
+        assert!(state.is_button_pressed(PadButtons::CIRCLE));
+        assert!(state.is_button_pressed(PadButtons::R3));
+        assert_eq!(state.pressure[PadButtons::CIRCLE.pressure_index().unwrap()], 129);
+        for _ in 0..3 {
+            drums.update(frame);
+        }
+        assert_eq!(drums.update(frame).buttons, 0);
+
+        // Crash cymbal sets the cymbal flag; the keyboard kicks
+        drums.midi_message(MidiMessage::NoteOn { channel: 9, note: 49, velocity: 127 });
+        drums.key_event(crate::keyboard::KeyCode::Space as u16, true);
+        let state = drums.update(frame);
+        assert!(state.is_button_pressed(PadButtons::CROSS));
+        assert!(state.is_button_pressed(PadButtons::R1));
+        assert!(!state.is_button_pressed(PadButtons::R3));
+        assert!(state.is_button_pressed(PadButtons::L1));
+    }
 }
@@ -56,6 +56,47 @@ pub enum KeyCode {
     Up = 0x52,
 }
 
+impl KeyCode {
+    /// Every key with its name
+    const NAMES: [(&'static str, KeyCode); 57] = [
+        ("A", KeyCode::A), ("B", KeyCode::B), ("C", KeyCode::C), ("D", KeyCode::D),
+        ("E", KeyCode::E), ("F", KeyCode::F), ("G", KeyCode::G), ("H", KeyCode::H),
+        ("I", KeyCode::I), ("J", KeyCode::J), ("K", KeyCode::K), ("L", KeyCode::L),
+        ("M", KeyCode::M), ("N", KeyCode::N), ("O", KeyCode::O), ("P", KeyCode::P),
+        ("Q", KeyCode::Q), ("R", KeyCode::R), ("S", KeyCode::S), ("T", KeyCode::T),
+        ("U", KeyCode::U), ("V", KeyCode::V), ("W", KeyCode::W), ("X", KeyCode::X),
+        ("Y", KeyCode::Y), ("Z", KeyCode::Z),
+        ("1", KeyCode::Num1), ("2", KeyCode::Num2), ("3", KeyCode::Num3), ("4", KeyCode::Num4),
+        ("5", KeyCode::Num5), ("6", KeyCode::Num6), ("7", KeyCode::Num7), ("8", KeyCode::Num8),
+        ("9", KeyCode::Num9), ("0", KeyCode::Num0),
+        ("Enter", KeyCode::Enter), ("Escape", KeyCode::Escape), ("Backspace", KeyCode::Backspace),
+        ("Tab", KeyCode::Tab), ("Space", KeyCode::Space),
+        ("F1", KeyCode::F1), ("F2", KeyCode::F2), ("F3", KeyCode::F3), ("F4", KeyCode::F4),
+        ("F5", KeyCode::F5), ("F6", KeyCode::F6), ("F7", KeyCode::F7), ("F8", KeyCode::F8),
+        ("F9", KeyCode::F9), ("F10", KeyCode::F10), ("F11", KeyCode::F11), ("F12", KeyCode::F12),
+        ("Right", KeyCode::Right), ("Left", KeyCode::Left), ("Down", KeyCode::Down), ("Up", KeyCode::Up),
+    ];
+
+    /// Look up a key by case-insensitive name ("W", "Enter", "Up", "F1", ...)
+    ///
+    /// Also accepts "Return", "Esc", "Num1" style digits and "ArrowUp" style arrows.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim();
+        let name = match name.to_ascii_lowercase().as_str() {
+            "return" => "Enter",
+            "esc" => "Escape",
+            _ => name
+                .strip_prefix("Arrow")
+                .or_else(|| name.strip_prefix("Num").filter(|digit| digit.len() == 1))
+                .unwrap_or(name),
+        };
+        Self::NAMES
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|&(_, key)| key)
+    }
+}
+
 /// Keyboard event type
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum KeyEventType {
@@ -232,4 +273,14 @@ mod tests {
         assert!(state.modifiers.contains(KeyModifiers::LEFT_SHIFT));
         assert!(!state.modifiers.contains(KeyModifiers::LEFT_ALT));
     }
+
+    #[test]
+    fn test_key_from_name() {
+        assert_eq!(KeyCode::from_name("w"), Some(KeyCode::W));
+        assert_eq!(KeyCode::from_name("Return"), Some(KeyCode::Enter));
+        assert_eq!(KeyCode::from_name("ArrowUp"), Some(KeyCode::Up));
+        assert_eq!(KeyCode::from_name("Num3"), Some(KeyCode::Num3));
+        assert_eq!(KeyCode::from_name("3"), Some(KeyCode::Num3));
+        assert_eq!(KeyCode::from_name("Shift"), None);
+    }
 }
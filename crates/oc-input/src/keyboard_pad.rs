@@ -0,0 +1,235 @@
+//! Keyboard and mouse pad emulation
+//!
+//! Drives a PS3 pad for players without a controller. Mapped keys press
+//! buttons, stick keys ramp the left stick instead of snapping it to full
+//! deflection, and mouse movement acts as a flick gesture on the right
+//! stick (or tilts the Sixaxis) that springs back once the mouse stops.
+
+use crate::dualshock3::SixaxisData;
+use crate::keyboard::{KeyCode, KeyboardState};
+use crate::mapping::{axis_to_u8, HostInput, InputMapping, Ps3Input};
+use crate::mouse::MouseButtons;
+use crate::pad::{PadButtons, PadState};
+use oc_core::config::{KeyboardMapping, KeyboardPadConfig, MouseMode};
+use std::time::Duration;
+
+/// Device name of the emulated pad, used to pin it to a port
+pub const KEYBOARD_PAD_NAME: &str = "Keyboard & Mouse";
+
+/// Stick deflection per point of mouse movement at sensitivity 1.0
+const MOUSE_SCALE: f32 = 0.02;
+
+/// Keyboard and mouse to pad mapper
+pub struct KeyboardPad {
+    /// Key to button mappings
+    buttons: InputMapping,
+    /// Left stick up/down/left/right keys
+    stick_keys: [Option<u16>; 4],
+    /// Buttons for the left and right mouse buttons
+    mouse_buttons: [(MouseButtons, Option<PadButtons>); 2],
+    config: KeyboardPadConfig,
+    keyboard: KeyboardState,
+    mouse: MouseButtons,
+    /// Left stick position (-1.0..1.0, up positive)
+    left: [f32; 2],
+    /// Right stick or tilt position from mouse gestures (-1.0..1.0, up positive)
+    gesture: [f32; 2],
+    /// Mouse movement since the last update, in points
+    mouse_delta: [f32; 2],
+}
+
+impl KeyboardPad {
+    /// Create a mapper from configuration
+    pub fn new(mapping: &KeyboardMapping, config: &KeyboardPadConfig) -> Self {
+        let key = |name: &str| KeyCode::from_name(name).map(|key| key as u16);
+        Self {
+            buttons: InputMapping::from_keyboard_config(mapping),
+            stick_keys: [
+                key(&config.left_stick_up),
+                key(&config.left_stick_down),
+                key(&config.left_stick_left),
+                key(&config.left_stick_right),
+            ],
+            mouse_buttons: [
+                (MouseButtons::LEFT, PadButtons::parse_name(&config.mouse_left)),
+                (MouseButtons::RIGHT, PadButtons::parse_name(&config.mouse_right)),
+            ],
+            config: config.clone(),
+            keyboard: KeyboardState::new(),
+            mouse: MouseButtons::empty(),
+            left: [0.0; 2],
+            gesture: [0.0; 2],
+            mouse_delta: [0.0; 2],
+        }
+    }
+
+    /// Record a key press or release (USB HID usage code)
+    pub fn key_event(&mut self, key_code: u16, pressed: bool) {
+        if pressed {
+            self.keyboard.press_key(key_code);
+        } else {
+            self.keyboard.release_key(key_code);
+        }
+    }
+
+    /// Record a mouse button press or release
+    pub fn mouse_button(&mut self, button: MouseButtons, pressed: bool) {
+        self.mouse.set(button, pressed);
+    }
+
+    /// Record mouse movement in points (screen coordinates, down positive)
+    pub fn mouse_moved(&mut self, dx: f32, dy: f32) {
+        self.mouse_delta[0] += dx;
+        self.mouse_delta[1] += dy;
+    }
+
+    /// Release every key and button (e.g. when the window loses focus)
+    pub fn release_all(&mut self) {
+        self.keyboard.clear();
+        self.mouse = MouseButtons::empty();
+        self.mouse_delta = [0.0; 2];
+    }
+
+    /// Advance the smoothing by `dt` and produce the pad state
+    pub fn update(&mut self, dt: Duration) -> (PadState, SixaxisData) {
+        let mut state = PadState::new();
+        for &key in &self.keyboard.pressed_keys {
+            if let Some(Ps3Input::PadButton(button)) = self.buttons.get_mapping(HostInput::Key(key)) {
+                state.press(button, 1.0);
+            }
+        }
+        for (mouse_button, button) in self.mouse_buttons {
+            if let Some(button) = button.filter(|_| self.mouse.contains(mouse_button)) {
+                state.press(button, 1.0);
+            }
+        }
+
+        self.update_left_stick(dt);
+        self.update_gesture(dt);
+        state.left_x = axis_to_u8(self.left[0], false);
+        state.left_y = axis_to_u8(self.left[1], true);
+
+        let mut sixaxis = SixaxisData::at_rest();
+        match self.config.mouse_mode {
+            MouseMode::Off => {}
+            MouseMode::RightStick => {
+                state.right_x = axis_to_u8(self.gesture[0], false);
+                state.right_y = axis_to_u8(self.gesture[1], true);
+            }
+            MouseMode::Sixaxis => {
+                // Moving the mouse right rolls the pad right, moving it up tilts it forward
+                let [x, y] = self.gesture;
+                let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+                sixaxis.set_accel_normalized(x, y, z);
+            }
+        }
+        (state, sixaxis)
+    }
+
+    /// Ramp the left stick toward the direction of the held keys
+    fn update_left_stick(&mut self, dt: Duration) {
+        let held = |key: Option<u16>| key.is_some_and(|key| self.keyboard.is_key_pressed(key)) as i8 as f32;
+        let [up, down, left, right] = self.stick_keys.map(held);
+        let mut target = [right - left, up - down];
+        // Keep diagonals on the unit circle
+        let length = (target[0] * target[0] + target[1] * target[1]).sqrt();
+        if length > 1.0 {
+            target = target.map(|v| v / length);
+        }
+
+        let ramp = Duration::from_millis(self.config.stick_ramp_ms as u64);
+        let step = if ramp.is_zero() { 1.0 } else { dt.as_secs_f32() / ramp.as_secs_f32() };
+        for (value, target) in self.left.iter_mut().zip(target) {
+            *value += (target - *value).clamp(-step, step);
+        }
+    }
+
+    /// Add mouse movement to the gesture position and let it spring back
+    fn update_gesture(&mut self, dt: Duration) {
+        let decay_time = self.config.mouse_decay_ms as f32 / 1000.0;
+        let decay = if decay_time > 0.0 { (-dt.as_secs_f32() / decay_time).exp() } else { 0.0 };
+        let scale = MOUSE_SCALE * self.config.mouse_sensitivity;
+        let delta = std::mem::take(&mut self.mouse_delta);
+        // Screen Y grows downward; sticks are up positive
+        let impulse = [delta[0] * scale, -delta[1] * scale];
+        for (value, impulse) in self.gesture.iter_mut().zip(impulse) {
+            *value = (*value * decay + impulse).clamp(-1.0, 1.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(20);
+
+    fn pad(config: KeyboardPadConfig) -> KeyboardPad {
+        KeyboardPad::new(&KeyboardMapping::default(), &config)
+    }
+
+    #[test]
+    fn test_keys_press_buttons() {
+        let mut pad = pad(KeyboardPadConfig::default());
+        pad.key_event(KeyCode::X as u16, true);
+        pad.mouse_button(MouseButtons::LEFT, true);
+        let (state, _) = pad.update(FRAME);
+        assert!(state.is_button_pressed(PadButtons::CROSS));
+        assert!(state.is_button_pressed(PadButtons::R1));
+
+        pad.release_all();
+        let (state, _) = pad.update(FRAME);
+        assert_eq!(state.buttons, 0);
+    }
+
+    #[test]
+    fn test_stick_keys_ramp() {
+        let mut pad = pad(KeyboardPadConfig::default());
+        pad.key_event(KeyCode::W as u16, true);
+
+        // 20 ms of an 80 ms ramp: a quarter of the way up
+        let (state, _) = pad.update(FRAME);
+        assert!(state.left_y < 128 && state.left_y > 64);
+        for _ in 0..4 {
+            pad.update(FRAME);
+        }
+        let (state, _) = pad.update(FRAME);
+        assert_eq!(state.left_y, 0);
+        assert_eq!(state.left_x, 128);
+
+        // Diagonals stay on the unit circle
+        pad.key_event(KeyCode::D as u16, true);
+        for _ in 0..10 {
+            pad.update(FRAME);
+        }
+        let (x, y) = (pad.left[0], pad.left[1]);
+        assert!(((x * x + y * y).sqrt() - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_mouse_flick_springs_back() {
+        let mut pad = pad(KeyboardPadConfig::default());
+        pad.mouse_moved(30.0, 0.0);
+        let (state, _) = pad.update(FRAME);
+        assert!(state.right_x > 200);
+
+        for _ in 0..40 {
+            pad.update(FRAME);
+        }
+        let (state, _) = pad.update(FRAME);
+        assert_eq!(state.right_x, 128);
+    }
+
+    #[test]
+    fn test_mouse_tilts_sixaxis() {
+        let mut pad = pad(KeyboardPadConfig {
+            mouse_mode: MouseMode::Sixaxis,
+            ..Default::default()
+        });
+        pad.mouse_moved(0.0, -25.0);
+        let (state, sixaxis) = pad.update(FRAME);
+        assert_eq!(state.right_y, 128);
+        assert!(sixaxis.accel_y > 200);
+        assert!(sixaxis.accel_z < 511);
+    }
+}
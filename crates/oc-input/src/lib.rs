@@ -2,53 +2,102 @@
 //!
 //! This crate provides comprehensive input handling for PS3 emulation including:
 //! - DualShock 3 controller with Sixaxis motion and vibration
-//! - PlayStation Move motion controller
+//! - Real DualShock 3 passthrough over hidapi
+//! - DualShock 4 / DualSense with motion and touchpad mapping over hidapi
+//! - PlayStation Move motion controller, emulated with the mouse or a DS4
+//! - Calibration-free light-gun pointer from the mouse or a gyroscope
 //! - USB and Bluetooth controller support
-//! - Guitar Hero / Rock Band instruments
-//! - PlayStation Eye camera
+//! - Raw USB passthrough for Buzz! buzzers, dance mats and steering wheels
+//! - Emulated Buzz! buzzers played on the controller ports
+//! - Host gamepads with hotplug (gilrs) and per-game mapping profiles
+//! - Dead zone and motion drift calibration
+//! - Guitar Hero / Rock Band instruments, also playable on gamepads, the keyboard or MIDI kits
+//! - PlayStation Eye camera, captured from a host webcam
 //! - Microphone input
-//! - Keyboard and mouse
+//! - Keyboard and mouse, including pad emulation for players without a controller
+//!
+//! Host device backends are behind cargo features so the emulation core
+//! can use the device-agnostic types without the host libraries:
+//! `gamepad` (gilrs), `hid` (hidapi: DualShock 3/4 and USB passthrough),
+//! `midi` (midir), `jpeg` (JPEG camera frames) and `webcam` (host camera
+//! capture).
 
 // Core input modules
+pub mod calibration;
+#[cfg(feature = "gamepad")]
+pub mod gamepad;
 pub mod keyboard;
+pub mod keyboard_pad;
 pub mod mapping;
 pub mod mouse;
 pub mod pad;
 
 // Controller modules
 pub mod bluetooth;
+#[cfg(feature = "hid")]
+pub mod ds3_hid;
+#[cfg(feature = "hid")]
+pub mod ds4_hid;
 pub mod dualshock3;
 pub mod usb;
 
 // Special peripherals
+pub mod buzz;
 pub mod camera;
 pub mod instruments;
 pub mod microphone;
+pub mod midi;
 pub mod move_controller;
+pub mod pointer;
+pub mod virtual_move;
+pub mod webcam;
 
 // Re-exports for convenient access
-pub use pad::Pad;
+pub use pad::{Pad, PadPorts};
+
+// Host gamepads
+#[cfg(feature = "gamepad")]
+pub use gamepad::GamepadBackend;
+pub use pad::{GamepadEvent, HostGamepadInfo};
+pub use mapping::{HostInput, InputProfile, Ps3Input};
+pub use calibration::RestCalibration;
+pub use keyboard_pad::KeyboardPad;
 
 // DualShock 3
 pub use dualshock3::{DualShock3, DualShock3Manager, SixaxisData, VibrationState};
+#[cfg(feature = "hid")]
+pub use ds3_hid::Ds3HidBackend;
+#[cfg(feature = "hid")]
+pub use ds4_hid::{Ds4ExtraMapping, Ds4Feedback, Ds4HidBackend, Ds4Model};
 
 // USB controllers
-pub use usb::{UsbController, UsbControllerManager, UsbDeviceInfo};
+pub use usb::{UsbController, UsbControllerManager, UsbDeviceInfo, UsbPassthroughEvent};
+#[cfg(feature = "hid")]
+pub use usb::{UsbPassthrough, UsbPassthroughDevice};
 
 // Bluetooth
 pub use bluetooth::{BluetoothAdapter, BluetoothDevice, BluetoothManager};
 
 // PlayStation Move
-pub use move_controller::{MoveController, MoveManager, MoveMotionData, SphereColor};
+pub use move_controller::{CalibrationStatus, MoveController, MoveManager, MoveMotionData, SphereColor};
+pub use pointer::Pointer;
+pub use virtual_move::VirtualMove;
+
+// Buzz!
+pub use buzz::VirtualBuzz;
 
 // Instruments
 pub use instruments::{
-    DrumController, DrumPads, DrumType, GuitarController, GuitarFrets, GuitarType,
-    InstrumentManager, TurntableController,
+    DrumController, DrumPads, DrumType, GuitarController, GuitarFrets, GuitarType, InstrumentManager,
+    InstrumentMapping, InstrumentSource, MappedInstrument, TurntableController,
 };
+#[cfg(feature = "midi")]
+pub use midi::MidiInputBackend;
+pub use midi::MidiMessage;
 
 // Camera
-pub use camera::{Camera, CameraManager, CameraResolution, CameraSettings, CameraType};
+pub use camera::{Camera, CameraFrame, CameraManager, CameraPixelFormat, CameraResolution, CameraSettings, CameraType};
+pub use webcam::WebcamCapture;
 
 // Microphone
 pub use microphone::{Microphone, MicrophoneConfig, MicrophoneManager, SampleRate};
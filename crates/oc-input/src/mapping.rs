@@ -1,11 +1,39 @@
 //! Input mapping
 //!
 //! Maps host input devices to PS3 controller/keyboard/mouse inputs.
+//! Gamepad mappings are grouped into named [`InputProfile`]s, which are
+//! stored in the configuration and selected per title ID.
 
 use crate::pad::{PadButtons, PadState};
 use crate::keyboard::KeyCode;
 use crate::mouse::MouseButtons;
+use oc_core::config::{InputConfig, InputProfileConfig, KeyboardMapping, PointerResponse, ResponseCurve, StickResponse};
 use std::collections::HashMap;
+use std::fmt;
+
+/// Standard-layout gamepad button names, indexed by [`HostInput::GamepadButton`]
+pub const GAMEPAD_BUTTON_NAMES: [&str; 17] = [
+    "south",
+    "east",
+    "west",
+    "north",
+    "left_shoulder",
+    "right_shoulder",
+    "left_trigger",
+    "right_trigger",
+    "select",
+    "start",
+    "left_stick",
+    "right_stick",
+    "dpad_up",
+    "dpad_down",
+    "dpad_left",
+    "dpad_right",
+    "mode",
+];
+
+/// Gamepad axis names, indexed by [`HostInput::GamepadAxis`]
+pub const GAMEPAD_AXIS_NAMES: [&str; 6] = ["left_x", "left_y", "right_x", "right_y", "left_z", "right_z"];
 
 /// Host input source
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -16,6 +44,50 @@ pub enum HostInput {
     MouseButton(MouseButtons),
     /// Gamepad button
     GamepadButton(u8),
+    /// Gamepad axis
+    GamepadAxis(u8),
+}
+
+impl HostInput {
+    /// Parse a configuration string ("pad:south", "axis:left_z", "key:29", "key:F1", "mouse:1")
+    pub fn parse(s: &str) -> Option<Self> {
+        let (kind, value) = s.trim().split_once(':')?;
+        let index = |names: &[&str]| -> Option<u8> {
+            names
+                .iter()
+                .position(|name| name.eq_ignore_ascii_case(value))
+                .map(|i| i as u8)
+                .or_else(|| value.parse().ok())
+        };
+        match kind {
+            "key" => value
+                .parse()
+                .ok()
+                .or_else(|| KeyCode::from_name(value).map(|key| key as u16))
+                .map(HostInput::Key),
+            "mouse" => MouseButtons::from_bits(value.parse().ok()?).map(HostInput::MouseButton),
+            "pad" => index(&GAMEPAD_BUTTON_NAMES).map(HostInput::GamepadButton),
+            "axis" => index(&GAMEPAD_AXIS_NAMES).map(HostInput::GamepadAxis),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for HostInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            HostInput::Key(code) => write!(f, "key:{}", code),
+            HostInput::MouseButton(button) => write!(f, "mouse:{}", button.bits()),
+            HostInput::GamepadButton(i) => match GAMEPAD_BUTTON_NAMES.get(i as usize) {
+                Some(name) => write!(f, "pad:{}", name),
+                None => write!(f, "pad:{}", i),
+            },
+            HostInput::GamepadAxis(i) => match GAMEPAD_AXIS_NAMES.get(i as usize) {
+                Some(name) => write!(f, "axis:{}", name),
+                None => write!(f, "axis:{}", i),
+            },
+        }
+    }
 }
 
 /// PS3 input target
@@ -33,7 +105,61 @@ pub enum Ps3Input {
     RightAnalogY,
 }
 
+/// Configuration names of every PS3 input
+const PS3_INPUT_NAMES: [(&str, Ps3Input); 20] = [
+    ("cross", Ps3Input::PadButton(PadButtons::CROSS)),
+    ("circle", Ps3Input::PadButton(PadButtons::CIRCLE)),
+    ("square", Ps3Input::PadButton(PadButtons::SQUARE)),
+    ("triangle", Ps3Input::PadButton(PadButtons::TRIANGLE)),
+    ("l1", Ps3Input::PadButton(PadButtons::L1)),
+    ("l2", Ps3Input::PadButton(PadButtons::L2)),
+    ("l3", Ps3Input::PadButton(PadButtons::L3)),
+    ("r1", Ps3Input::PadButton(PadButtons::R1)),
+    ("r2", Ps3Input::PadButton(PadButtons::R2)),
+    ("r3", Ps3Input::PadButton(PadButtons::R3)),
+    ("start", Ps3Input::PadButton(PadButtons::START)),
+    ("select", Ps3Input::PadButton(PadButtons::SELECT)),
+    ("dpad_up", Ps3Input::PadButton(PadButtons::DPAD_UP)),
+    ("dpad_down", Ps3Input::PadButton(PadButtons::DPAD_DOWN)),
+    ("dpad_left", Ps3Input::PadButton(PadButtons::DPAD_LEFT)),
+    ("dpad_right", Ps3Input::PadButton(PadButtons::DPAD_RIGHT)),
+    ("left_x", Ps3Input::LeftAnalogX),
+    ("left_y", Ps3Input::LeftAnalogY),
+    ("right_x", Ps3Input::RightAnalogX),
+    ("right_y", Ps3Input::RightAnalogY),
+];
+
+impl Ps3Input {
+    /// Every PS3 input that can be bound
+    pub fn all() -> impl Iterator<Item = Ps3Input> {
+        PS3_INPUT_NAMES.iter().map(|&(_, input)| input)
+    }
+
+    /// Configuration name ("cross", "left_x", ...)
+    pub fn name(self) -> &'static str {
+        PS3_INPUT_NAMES
+            .iter()
+            .find(|&&(_, input)| input == self)
+            .map_or("unknown", |&(name, _)| name)
+    }
+
+    /// Look up a PS3 input by configuration name
+    pub fn from_config_name(name: &str) -> Option<Self> {
+        let name = name.trim();
+        PS3_INPUT_NAMES
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|&(_, input)| input)
+    }
+
+    /// Whether this is an analog stick axis
+    pub fn is_axis(self) -> bool {
+        !matches!(self, Ps3Input::PadButton(_))
+    }
+}
+
 /// Input mapping configuration
+#[derive(Debug, Clone)]
 pub struct InputMapping {
     /// Mappings from host input to PS3 input
     mappings: HashMap<HostInput, Ps3Input>,
@@ -76,6 +202,78 @@ impl InputMapping {
         mapping
     }
 
+    /// Create a keyboard mapping from configuration key names
+    ///
+    /// Unknown or empty key names leave the button unmapped.
+    pub fn from_keyboard_config(config: &KeyboardMapping) -> Self {
+        let mut mapping = Self::new();
+        let keys = [
+            (&config.cross, PadButtons::CROSS),
+            (&config.circle, PadButtons::CIRCLE),
+            (&config.square, PadButtons::SQUARE),
+            (&config.triangle, PadButtons::TRIANGLE),
+            (&config.l1, PadButtons::L1),
+            (&config.l2, PadButtons::L2),
+            (&config.l3, PadButtons::L3),
+            (&config.r1, PadButtons::R1),
+            (&config.r2, PadButtons::R2),
+            (&config.r3, PadButtons::R3),
+            (&config.start, PadButtons::START),
+            (&config.select, PadButtons::SELECT),
+            (&config.dpad_up, PadButtons::DPAD_UP),
+            (&config.dpad_down, PadButtons::DPAD_DOWN),
+            (&config.dpad_left, PadButtons::DPAD_LEFT),
+            (&config.dpad_right, PadButtons::DPAD_RIGHT),
+        ];
+        for (name, button) in keys {
+            match KeyCode::from_name(name) {
+                Some(key) => mapping.map_key(key as u16, Ps3Input::PadButton(button)),
+                None if name.trim().is_empty() => {}
+                None => tracing::warn!("Keyboard mapping: unknown key \"{}\"", name),
+            }
+        }
+        mapping
+    }
+
+    /// Create the standard gamepad layout
+    ///
+    /// South/East/West/North become Cross/Circle/Square/Triangle, shoulders
+    /// and triggers become L1/R1/L2/R2 and the sticks map straight across.
+    pub fn default_gamepad_mapping() -> Self {
+        let mut mapping = Self::new();
+        let buttons = [
+            PadButtons::CROSS,
+            PadButtons::CIRCLE,
+            PadButtons::SQUARE,
+            PadButtons::TRIANGLE,
+            PadButtons::L1,
+            PadButtons::R1,
+            PadButtons::L2,
+            PadButtons::R2,
+            PadButtons::SELECT,
+            PadButtons::START,
+            PadButtons::L3,
+            PadButtons::R3,
+            PadButtons::DPAD_UP,
+            PadButtons::DPAD_DOWN,
+            PadButtons::DPAD_LEFT,
+            PadButtons::DPAD_RIGHT,
+        ];
+        for (i, button) in buttons.into_iter().enumerate() {
+            mapping.map_gamepad_button(i as u8, Ps3Input::PadButton(button));
+        }
+        let axes = [
+            Ps3Input::LeftAnalogX,
+            Ps3Input::LeftAnalogY,
+            Ps3Input::RightAnalogX,
+            Ps3Input::RightAnalogY,
+        ];
+        for (i, axis) in axes.into_iter().enumerate() {
+            mapping.map_gamepad_axis(i as u8, axis);
+        }
+        mapping
+    }
+
     /// Map a keyboard key to a PS3 input
     pub fn map_key(&mut self, key_code: u16, ps3_input: Ps3Input) {
         self.mappings.insert(HostInput::Key(key_code), ps3_input);
@@ -91,6 +289,30 @@ impl InputMapping {
         self.mappings.insert(HostInput::GamepadButton(button), ps3_input);
     }
 
+    /// Map a gamepad axis to a PS3 input
+    pub fn map_gamepad_axis(&mut self, axis: u8, ps3_input: Ps3Input) {
+        self.mappings.insert(HostInput::GamepadAxis(axis), ps3_input);
+    }
+
+    /// Map a host input to a PS3 input, replacing any other source for it
+    pub fn bind(&mut self, host_input: HostInput, ps3_input: Ps3Input) {
+        self.unbind(ps3_input);
+        self.mappings.insert(host_input, ps3_input);
+    }
+
+    /// Remove every host input mapped to a PS3 input
+    pub fn unbind(&mut self, ps3_input: Ps3Input) {
+        self.mappings.retain(|_, target| *target != ps3_input);
+    }
+
+    /// Host input mapped to a PS3 input
+    pub fn host_input_for(&self, ps3_input: Ps3Input) -> Option<HostInput> {
+        self.mappings
+            .iter()
+            .find(|(_, &target)| target == ps3_input)
+            .map(|(&host, _)| host)
+    }
+
     /// Get PS3 input for a host input
     pub fn get_mapping(&self, host_input: HostInput) -> Option<Ps3Input> {
         self.mappings.get(&host_input).copied()
@@ -127,9 +349,166 @@ impl Default for InputMapping {
     }
 }
 
+/// Convert a stick axis (-1.0..1.0, up positive) to a PS3 axis byte
+pub(crate) fn axis_to_u8(value: f32, invert: bool) -> u8 {
+    let value = if invert { -value } else { value };
+    ((value.clamp(-1.0, 1.0) + 1.0) * 127.5).round() as u8
+}
+
+/// Apply dead zone, response curve and sensitivity to a stick position
+///
+/// The dead zone is radial, and the remaining travel is rescaled so the
+/// output still starts at zero and reaches full deflection.
+pub fn apply_stick_response(response: &StickResponse, x: f32, y: f32) -> (f32, f32) {
+    let magnitude = (x * x + y * y).sqrt();
+    let deadzone = response.deadzone.clamp(0.0, 0.99);
+    if magnitude <= deadzone {
+        return (0.0, 0.0);
+    }
+    let travel = ((magnitude - deadzone) / (1.0 - deadzone)).min(1.0);
+    let curved = match response.curve {
+        ResponseCurve::Linear => travel,
+        ResponseCurve::Quadratic => travel * travel,
+        ResponseCurve::Cubic => travel * travel * travel,
+    };
+    let scale = (curved * response.sensitivity).min(1.0) / magnitude;
+    let x = if response.invert_x { -x * scale } else { x * scale };
+    let y = if response.invert_y { -y * scale } else { y * scale };
+    (x.clamp(-1.0, 1.0), y.clamp(-1.0, 1.0))
+}
+
+/// Named gamepad mapping with stick response settings
+#[derive(Debug, Clone)]
+pub struct InputProfile {
+    /// Profile name
+    pub name: String,
+    /// Gamepad button/axis mappings
+    pub mapping: InputMapping,
+    /// Left stick response
+    pub left_stick: StickResponse,
+    /// Right stick response
+    pub right_stick: StickResponse,
+    /// Light-gun pointer response of the emulated Move
+    pub pointer: PointerResponse,
+}
+
+impl InputProfile {
+    /// Name of the built-in profile
+    pub const STANDARD: &'static str = "Standard";
+
+    /// Built-in standard-layout profile
+    pub fn standard() -> Self {
+        Self {
+            name: Self::STANDARD.to_string(),
+            mapping: InputMapping::default_gamepad_mapping(),
+            left_stick: StickResponse::default(),
+            right_stick: StickResponse::default(),
+            pointer: PointerResponse::default(),
+        }
+    }
+
+    /// Build a profile from its configuration
+    ///
+    /// Bindings are applied on top of the standard layout.
+    pub fn from_config(config: &InputProfileConfig) -> Self {
+        let mut mapping = InputMapping::default_gamepad_mapping();
+        for (target, host) in &config.bindings {
+            let Some(ps3_input) = Ps3Input::from_config_name(target) else {
+                tracing::warn!("Input profile \"{}\": unknown PS3 input \"{}\"", config.name, target);
+                continue;
+            };
+            if host.trim().eq_ignore_ascii_case("none") {
+                mapping.unbind(ps3_input);
+                continue;
+            }
+            match HostInput::parse(host) {
+                Some(host_input) => mapping.bind(host_input, ps3_input),
+                None => tracing::warn!("Input profile \"{}\": unknown host input \"{}\"", config.name, host),
+            }
+        }
+        Self {
+            name: config.name.clone(),
+            mapping,
+            left_stick: config.left_stick,
+            right_stick: config.right_stick,
+            pointer: config.pointer,
+        }
+    }
+
+    /// Profile configured for a title, or the standard profile
+    pub fn for_title(config: &InputConfig, title_id: Option<&str>) -> Self {
+        match title_id.and_then(|id| config.game_profiles.get(id)) {
+            Some(name) => Self::named(config, name),
+            None => Self::standard(),
+        }
+    }
+
+    /// Profile assigned to a pad port, overriding the per-game profile
+    ///
+    /// Returns None if the port has no profile of its own.
+    pub fn for_port(config: &InputConfig, port: u8) -> Option<Self> {
+        let name = config.controller.ports.get(port as usize)?.profile.trim();
+        (!name.is_empty()).then(|| Self::named(config, name))
+    }
+
+    /// Configured profile by name, or the standard profile
+    fn named(config: &InputConfig, name: &str) -> Self {
+        if name == Self::STANDARD {
+            return Self::standard();
+        }
+        match config.profiles.iter().find(|profile| profile.name == name) {
+            Some(profile) => Self::from_config(profile),
+            None => {
+                tracing::warn!("Input profile \"{}\" not found, using the standard layout", name);
+                Self::standard()
+            }
+        }
+    }
+
+    /// Apply gamepad readings to a pad state
+    ///
+    /// `buttons` holds analog values (0.0..1.0) indexed like
+    /// [`GAMEPAD_BUTTON_NAMES`]; `axes` holds values (-1.0..1.0, up
+    /// positive) indexed like [`GAMEPAD_AXIS_NAMES`].
+    pub fn apply_gamepad(&self, state: &mut PadState, buttons: &[f32], axes: &[f32]) {
+        for (i, &value) in buttons.iter().enumerate() {
+            if let Some(Ps3Input::PadButton(button)) = self.mapping.get_mapping(HostInput::GamepadButton(i as u8)) {
+                state.press(button, value);
+            }
+        }
+
+        // Left X/Y, right X/Y
+        let mut sticks = [0.0f32; 4];
+        for (i, &value) in axes.iter().enumerate() {
+            match self.mapping.get_mapping(HostInput::GamepadAxis(i as u8)) {
+                Some(Ps3Input::PadButton(button)) => state.press(button, value.max(0.0)),
+                Some(Ps3Input::LeftAnalogX) => sticks[0] = value,
+                Some(Ps3Input::LeftAnalogY) => sticks[1] = value,
+                Some(Ps3Input::RightAnalogX) => sticks[2] = value,
+                Some(Ps3Input::RightAnalogY) => sticks[3] = value,
+                None => {}
+            }
+        }
+
+        let (lx, ly) = apply_stick_response(&self.left_stick, sticks[0], sticks[1]);
+        let (rx, ry) = apply_stick_response(&self.right_stick, sticks[2], sticks[3]);
+        state.left_x = axis_to_u8(lx, false);
+        state.left_y = axis_to_u8(ly, true);
+        state.right_x = axis_to_u8(rx, false);
+        state.right_y = axis_to_u8(ry, true);
+    }
+}
+
+impl Default for InputProfile {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use oc_core::config::PadPortConfig;
 
     #[test]
     fn test_input_mapping_creation() {
@@ -176,4 +555,87 @@ mod tests {
         mapping.remove_mapping(HostInput::Key(KeyCode::A as u16));
         assert!(mapping.get_mapping(HostInput::Key(KeyCode::A as u16)).is_none());
     }
+
+    #[test]
+    fn test_host_input_names() {
+        for input in [
+            HostInput::GamepadButton(0),
+            HostInput::GamepadAxis(5),
+            HostInput::Key(KeyCode::W as u16),
+            HostInput::MouseButton(MouseButtons::RIGHT),
+        ] {
+            assert_eq!(HostInput::parse(&input.to_string()), Some(input));
+        }
+        assert_eq!(HostInput::parse("pad:North"), Some(HostInput::GamepadButton(3)));
+        assert_eq!(HostInput::parse("key:F1"), Some(HostInput::Key(KeyCode::F1 as u16)));
+        assert_eq!(HostInput::parse("axis:nope"), None);
+        assert_eq!(Ps3Input::from_config_name("left_y"), Some(Ps3Input::LeftAnalogY));
+        assert_eq!(Ps3Input::PadButton(PadButtons::R2).name(), "r2");
+    }
+
+    #[test]
+    fn test_profile_bindings() {
+        let mut config = InputProfileConfig {
+            name: "Racing".to_string(),
+            ..Default::default()
+        };
+        config.bindings.insert("r2".to_string(), "axis:right_z".to_string());
+        config.bindings.insert("cross".to_string(), "none".to_string());
+        let profile = InputProfile::from_config(&config);
+
+        let mut buttons = [0.0; GAMEPAD_BUTTON_NAMES.len()];
+        buttons[0] = 1.0; // South, unbound
+        buttons[1] = 1.0; // East, still Circle
+        let axes = [0.0, 0.0, 0.0, 0.0, 0.0, 0.6];
+        let mut state = PadState::new();
+        profile.apply_gamepad(&mut state, &buttons, &axes);
+
+        assert!(!state.is_button_pressed(PadButtons::CROSS));
+        assert!(state.is_button_pressed(PadButtons::CIRCLE));
+        assert!(state.is_button_pressed(PadButtons::R2));
+        assert_eq!(state.pressure[crate::dualshock3::pressure_index::R2], 153);
+        assert_eq!(
+            profile.mapping.host_input_for(Ps3Input::PadButton(PadButtons::R2)),
+            Some(HostInput::GamepadAxis(5))
+        );
+    }
+
+    #[test]
+    fn test_stick_response() {
+        let mut response = StickResponse::default();
+        assert_eq!(apply_stick_response(&response, 0.05, 0.05), (0.0, 0.0));
+        let (x, y) = apply_stick_response(&response, 1.0, 0.0);
+        assert!((x - 1.0).abs() < 1e-6 && y == 0.0);
+
+        response.deadzone = 0.0;
+        response.curve = ResponseCurve::Quadratic;
+        let (x, _) = apply_stick_response(&response, 0.5, 0.0);
+        assert!((x - 0.25).abs() < 1e-6);
+
+        response.sensitivity = 2.0;
+        response.invert_y = true;
+        let (x, y) = apply_stick_response(&response, 0.0, 0.5);
+        assert!(x.abs() < 1e-6 && (y + 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_profile_for_title() {
+        let mut config = InputConfig::default();
+        config.profiles.push(InputProfileConfig {
+            name: "Flight".to_string(),
+            ..Default::default()
+        });
+        config.game_profiles.insert("BCES00001".to_string(), "Flight".to_string());
+        config.game_profiles.insert("BCES00002".to_string(), "Missing".to_string());
+
+        assert_eq!(InputProfile::for_title(&config, Some("BCES00001")).name, "Flight");
+        assert_eq!(InputProfile::for_title(&config, Some("BCES00002")).name, InputProfile::STANDARD);
+        assert_eq!(InputProfile::for_title(&config, None).name, InputProfile::STANDARD);
+
+        config.controller.ports.resize(2, PadPortConfig::default());
+        config.controller.ports[1].profile = "Flight".to_string();
+        assert!(InputProfile::for_port(&config, 0).is_none());
+        assert_eq!(InputProfile::for_port(&config, 1).unwrap().name, "Flight");
+        assert!(InputProfile::for_port(&config, 5).is_none());
+    }
 }
@@ -0,0 +1,150 @@
+//! MIDI input for electronic drum kits and keyboards
+//!
+//! Note messages from a host MIDI device are queued by a midir callback
+//! and drained once per frame by the instrument emulation. The host
+//! backend needs the `midi` feature; [`MidiMessage`] is always available.
+
+#[cfg(feature = "midi")]
+use midir::{Ignore, MidiInput, MidiInputConnection};
+#[cfg(feature = "midi")]
+use parking_lot::Mutex;
+#[cfg(feature = "midi")]
+use std::sync::Arc;
+
+/// Client name shown to the host MIDI system
+#[cfg(feature = "midi")]
+const CLIENT_NAME: &str = "oxidized-cell";
+
+/// Messages kept between drains; older ones are dropped
+#[cfg(feature = "midi")]
+const MAX_QUEUED: usize = 256;
+
+/// MIDI note message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiMessage {
+    /// Key pressed or pad hit (velocity 1-127)
+    NoteOn { channel: u8, note: u8, velocity: u8 },
+    /// Key or pad released
+    NoteOff { channel: u8, note: u8 },
+}
+
+impl MidiMessage {
+    /// Decode a raw MIDI message, ignoring everything but notes
+    ///
+    /// A note-on with velocity 0 is a note-off, as running-status devices
+    /// send it.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let &[status, note, velocity, ..] = bytes else {
+            return None;
+        };
+        let channel = status & 0x0F;
+        let (note, velocity) = (note & 0x7F, velocity & 0x7F);
+        match status & 0xF0 {
+            0x90 if velocity > 0 => Some(Self::NoteOn { channel, note, velocity }),
+            0x80 | 0x90 => Some(Self::NoteOff { channel, note }),
+            _ => None,
+        }
+    }
+}
+
+/// Connection to one host MIDI input device
+#[cfg(feature = "midi")]
+pub struct MidiInputBackend {
+    /// Open connection; dropping it closes the device
+    _connection: MidiInputConnection<()>,
+    device: String,
+    messages: Arc<Mutex<Vec<MidiMessage>>>,
+}
+
+#[cfg(feature = "midi")]
+impl MidiInputBackend {
+    /// Names of the MIDI input devices on the host
+    pub fn device_names() -> Vec<String> {
+        let Ok(input) = MidiInput::new(CLIENT_NAME) else {
+            return Vec::new();
+        };
+        input
+            .ports()
+            .iter()
+            .filter_map(|port| input.port_name(port).ok())
+            .collect()
+    }
+
+    /// Open the device whose name contains `device`, or the first one if empty
+    pub fn open(device: &str) -> Result<Self, String> {
+        let mut input = MidiInput::new(CLIENT_NAME).map_err(|e| format!("Failed to initialize MIDI: {}", e))?;
+        input.ignore(Ignore::All);
+
+        let wanted = device.trim().to_ascii_lowercase();
+        let port = input
+            .ports()
+            .into_iter()
+            .find(|port| {
+                input
+                    .port_name(port)
+                    .is_ok_and(|name| name.to_ascii_lowercase().contains(&wanted))
+            })
+            .ok_or_else(|| {
+                if wanted.is_empty() {
+                    "No MIDI input device found".to_string()
+                } else {
+                    format!("MIDI input device \"{}\" not found", device.trim())
+                }
+            })?;
+        let name = input.port_name(&port).unwrap_or_default();
+
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let queue = messages.clone();
+        let connection = input
+            .connect(
+                &port,
+                "instrument",
+                move |_, bytes, _| {
+                    if let Some(message) = MidiMessage::parse(bytes) {
+                        let mut queue = queue.lock();
+                        if queue.len() >= MAX_QUEUED {
+                            queue.remove(0);
+                        }
+                        queue.push(message);
+                    }
+                },
+                (),
+            )
+            .map_err(|e| format!("Failed to open MIDI device \"{}\": {}", name, e))?;
+
+        tracing::info!("MIDI input \"{}\" opened", name);
+        Ok(Self {
+            _connection: connection,
+            device: name,
+            messages,
+        })
+    }
+
+    /// Name of the open device
+    pub fn device(&self) -> &str {
+        &self.device
+    }
+
+    /// Take the messages received since the last call
+    pub fn drain(&self) -> Vec<MidiMessage> {
+        std::mem::take(&mut *self.messages.lock())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_notes() {
+        assert_eq!(
+            MidiMessage::parse(&[0x99, 38, 100]),
+            Some(MidiMessage::NoteOn { channel: 9, note: 38, velocity: 100 })
+        );
+        assert_eq!(MidiMessage::parse(&[0x99, 38, 0]), Some(MidiMessage::NoteOff { channel: 9, note: 38 }));
+        assert_eq!(MidiMessage::parse(&[0x89, 38, 64]), Some(MidiMessage::NoteOff { channel: 9, note: 38 }));
+        // Control change and short messages are ignored
+        assert_eq!(MidiMessage::parse(&[0xB9, 4, 127]), None);
+        assert_eq!(MidiMessage::parse(&[0xF8]), None);
+    }
+}
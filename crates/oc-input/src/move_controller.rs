@@ -5,8 +5,23 @@
 //! - Tracking sphere (LED color, position tracking)
 //! - Buttons and trigger
 //! - Vibration feedback
-
-use std::time::Instant;
+//! - Start-up calibration (gyroscope bias and orientation reset)
+
+use crate::dualshock3::{SixaxisData, SIXAXIS_ACCEL_PER_G, SIXAXIS_GYRO_PER_DEG_S};
+use std::time::{Duration, Instant};
+
+/// Standard gravity (m/s²)
+pub const GRAVITY: f32 = 9.81;
+/// How long the controller must be held still to calibrate
+const CALIBRATION_TIME: Duration = Duration::from_millis(500);
+/// Give up on a calibration that never saw the controller held still
+const CALIBRATION_TIMEOUT: Duration = Duration::from_secs(5);
+/// Angular rate above which the controller is not considered still (rad/s)
+const STILL_GYRO_LIMIT: f32 = 0.3;
+/// Deviation from 1 g above which the controller is not considered still (m/s²)
+const STILL_ACCEL_LIMIT: f32 = 2.0;
+/// Strength of the accelerometer tilt correction (1/s)
+const TILT_CORRECTION: f32 = 1.0;
 
 /// Move button flags
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -175,6 +190,40 @@ impl Quaternion {
         }
     }
 
+    /// Create from a rotation vector (axis scaled by angle in radians)
+    pub fn from_rotation_vector(v: [f32; 3]) -> Self {
+        let angle = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        if angle < 1e-9 {
+            return Self::identity();
+        }
+        let (s, c) = (angle / 2.0).sin_cos();
+        let k = s / angle;
+        Self::new(c, v[0] * k, v[1] * k, v[2] * k)
+    }
+
+    /// Rotation vector (axis scaled by angle in radians) of this rotation
+    pub fn to_rotation_vector(&self) -> [f32; 3] {
+        // Take the short way round
+        let q = if self.w < 0.0 { Self::new(-self.w, -self.x, -self.y, -self.z) } else { *self };
+        let s = (q.x * q.x + q.y * q.y + q.z * q.z).sqrt();
+        if s < 1e-9 {
+            return [0.0; 3];
+        }
+        let k = 2.0 * s.atan2(q.w) / s;
+        [q.x * k, q.y * k, q.z * k]
+    }
+
+    /// Inverse of a unit quaternion
+    pub fn conjugate(&self) -> Self {
+        Self::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    /// Rotate a vector by this (unit) quaternion
+    pub fn rotate(&self, v: [f32; 3]) -> [f32; 3] {
+        let p = *self * Self::new(0.0, v[0], v[1], v[2]) * self.conjugate();
+        [p.x, p.y, p.z]
+    }
+
     /// Normalize the quaternion
     pub fn normalize(&mut self) {
         let mag = (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
@@ -187,6 +236,20 @@ impl Quaternion {
     }
 }
 
+impl std::ops::Mul for Quaternion {
+    type Output = Self;
+
+    /// Hamilton product: the rotation `rhs` followed by `self`
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            w: self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+            x: self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            y: self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            z: self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+        }
+    }
+}
+
 /// Motion sensor data (raw)
 #[derive(Debug, Clone, Copy, Default)]
 pub struct MoveMotionData {
@@ -216,7 +279,7 @@ impl MoveMotionData {
         Self {
             accel_x: 0.0,
             accel_y: 0.0,
-            accel_z: 9.81, // 1G pointing up
+            accel_z: GRAVITY, // 1G pointing up
             gyro_x: 0.0,
             gyro_y: 0.0,
             gyro_z: 0.0,
@@ -225,6 +288,29 @@ impl MoveMotionData {
             mag_z: 0.0,
         }
     }
+
+    /// Convert Sixaxis sensor counts (as reported by a DualShock 3 or a converted DS4)
+    pub fn from_sixaxis(sixaxis: &SixaxisData) -> Self {
+        let accel = |v: i16| v as f32 / SIXAXIS_ACCEL_PER_G * GRAVITY;
+        let gyro = |v: i16| (v as f32 / SIXAXIS_GYRO_PER_DEG_S).to_radians();
+        Self {
+            accel_x: accel(sixaxis.accel_x),
+            accel_y: accel(sixaxis.accel_y),
+            accel_z: accel(sixaxis.accel_z),
+            gyro_x: gyro(sixaxis.gyro_x),
+            gyro_y: gyro(sixaxis.gyro_y),
+            gyro_z: gyro(sixaxis.gyro_z),
+            ..Self::at_rest()
+        }
+    }
+
+    fn accel(&self) -> [f32; 3] {
+        [self.accel_x, self.accel_y, self.accel_z]
+    }
+
+    fn gyro(&self) -> [f32; 3] {
+        [self.gyro_x, self.gyro_y, self.gyro_z]
+    }
 }
 
 /// Tracking quality
@@ -240,6 +326,28 @@ pub enum TrackingQuality {
     Excellent,
 }
 
+/// Calibration progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationStatus {
+    /// Never calibrated
+    NotCalibrated,
+    /// Waiting for the controller to be held still
+    InProgress,
+    /// Gyroscope bias measured and orientation reset
+    Calibrated,
+    /// The controller was not held still before the timeout
+    Failed,
+}
+
+/// Samples collected while calibrating
+#[derive(Debug, Clone, Copy, Default)]
+struct CalibrationSampler {
+    elapsed: Duration,
+    still_time: Duration,
+    gyro_sum: [f32; 3],
+    samples: u32,
+}
+
 /// Move controller state
 #[derive(Debug, Clone)]
 pub struct MoveState {
@@ -305,6 +413,12 @@ pub struct MoveController {
     pub connected: bool,
     /// Is calibrated
     pub calibrated: bool,
+    calibration: CalibrationStatus,
+    sampler: Option<CalibrationSampler>,
+    /// Result of the last finished calibration, not yet reported
+    calibration_result: Option<bool>,
+    /// Gyroscope bias measured during calibration (rad/s)
+    gyro_bias: [f32; 3],
     /// Battery level (0-5)
     pub battery_level: u8,
     /// Is charging
@@ -325,6 +439,10 @@ impl MoveController {
             vibration: MoveVibration::Off,
             connected: false,
             calibrated: false,
+            calibration: CalibrationStatus::NotCalibrated,
+            sampler: None,
+            calibration_result: None,
+            gyro_bias: [0.0; 3],
             battery_level: 5,
             charging: false,
             last_update: Instant::now(),
@@ -343,6 +461,11 @@ impl MoveController {
         self.connected = false;
         self.state = MoveState::default();
         self.vibration = MoveVibration::Off;
+        self.calibrated = false;
+        self.calibration = CalibrationStatus::NotCalibrated;
+        self.sampler = None;
+        self.calibration_result = None;
+        self.gyro_bias = [0.0; 3];
         tracing::info!("Move controller {} disconnected", self.index);
     }
 
@@ -393,39 +516,105 @@ impl MoveController {
     }
 
     /// Start calibration
+    ///
+    /// Titles ask the player to point the controller at the screen and hold
+    /// it still. Once it has been still for long enough the gyroscope bias
+    /// is taken from the samples and the orientation is reset to face the
+    /// camera. Moving the controller restarts the wait.
     pub fn calibrate(&mut self) {
-        // In a real implementation, this would:
-        // 1. Sample gyro bias
-        // 2. Sample magnetometer for heading
-        // 3. Establish gravity direction from accelerometer
-        self.calibrated = true;
-        tracing::info!("Move controller {} calibrated", self.index);
+        self.calibration = CalibrationStatus::InProgress;
+        self.sampler = Some(CalibrationSampler::default());
+        self.calibration_result = None;
+        tracing::debug!("Move controller {} calibrating", self.index);
+    }
+
+    /// Calibration progress
+    pub fn calibration_status(&self) -> CalibrationStatus {
+        self.calibration
+    }
+
+    /// Result of a calibration that finished since the last call
+    ///
+    /// cellGem reports completion once through its status flags.
+    pub fn take_calibration_result(&mut self) -> Option<bool> {
+        self.calibration_result.take()
     }
 
     /// Update controller state (call each frame)
-    pub fn update(&mut self) {
+    pub fn update(&mut self, dt: Duration) {
         if !self.connected {
             return;
         }
 
         self.last_update = Instant::now();
 
-        // Simple sensor fusion for orientation
-        // In reality, this would use a proper fusion algorithm (Madgwick, Mahony, etc.)
-        let dt = 1.0 / 60.0; // Assume 60fps
-        
-        // Integrate gyroscope (very simplified)
-        let roll = self.state.motion.gyro_x * dt;
-        let pitch = self.state.motion.gyro_y * dt;
-        let yaw = self.state.motion.gyro_z * dt;
-        
-        if roll.abs() > 0.001 || pitch.abs() > 0.001 || yaw.abs() > 0.001 {
-            let delta = Quaternion::from_euler(roll, pitch, yaw);
-            // Apply rotation (simplified - should be quaternion multiplication)
-            self.state.orientation.x += delta.x;
-            self.state.orientation.y += delta.y;
-            self.state.orientation.z += delta.z;
-            self.state.orientation.normalize();
+        if self.sampler.is_some() {
+            self.update_calibration(dt);
+            return;
+        }
+
+        let accel = self.state.motion.accel();
+        let mut rate = self.state.motion.gyro();
+        for (rate, bias) in rate.iter_mut().zip(self.gyro_bias) {
+            *rate -= bias;
+        }
+
+        // Pull the estimated gravity direction toward the measured one so
+        // pitch and roll do not drift (Mahony-style complementary filter)
+        let length = (accel[0] * accel[0] + accel[1] * accel[1] + accel[2] * accel[2]).sqrt();
+        if (length - GRAVITY).abs() < STILL_ACCEL_LIMIT {
+            let measured = accel.map(|v| v / length);
+            let estimated = self.state.orientation.conjugate().rotate([0.0, 0.0, 1.0]);
+            let error = cross(measured, estimated);
+            for (rate, error) in rate.iter_mut().zip(error) {
+                *rate += error * TILT_CORRECTION;
+            }
+        }
+
+        let dt = dt.as_secs_f32();
+        let delta = Quaternion::from_rotation_vector(rate.map(|v| v * dt));
+        self.state.orientation = self.state.orientation * delta;
+        self.state.orientation.normalize();
+    }
+
+    /// Collect a calibration sample and finish once the controller has been still
+    fn update_calibration(&mut self, dt: Duration) {
+        let Some(sampler) = self.sampler.as_mut() else {
+            return;
+        };
+        let accel = self.state.motion.accel();
+        let gyro = self.state.motion.gyro();
+        let rate = (gyro[0] * gyro[0] + gyro[1] * gyro[1] + gyro[2] * gyro[2]).sqrt();
+        let gravity = (accel[0] * accel[0] + accel[1] * accel[1] + accel[2] * accel[2]).sqrt();
+
+        sampler.elapsed += dt;
+        if rate > STILL_GYRO_LIMIT || (gravity - GRAVITY).abs() > STILL_ACCEL_LIMIT {
+            *sampler = CalibrationSampler {
+                elapsed: sampler.elapsed,
+                ..Default::default()
+            };
+        } else {
+            sampler.still_time += dt;
+            sampler.samples += 1;
+            for (sum, v) in sampler.gyro_sum.iter_mut().zip(gyro) {
+                *sum += v;
+            }
+        }
+
+        if sampler.still_time >= CALIBRATION_TIME {
+            let samples = sampler.samples as f32;
+            self.gyro_bias = sampler.gyro_sum.map(|sum| sum / samples);
+            self.state.orientation = Quaternion::identity();
+            self.calibrated = true;
+            self.calibration = CalibrationStatus::Calibrated;
+            self.calibration_result = Some(true);
+            self.sampler = None;
+            tracing::info!("Move controller {} calibrated", self.index);
+        } else if sampler.elapsed >= CALIBRATION_TIMEOUT {
+            self.calibration = CalibrationStatus::Failed;
+            self.calibration_result = Some(false);
+            self.sampler = None;
+            tracing::warn!("Move controller {} calibration failed: not held still", self.index);
         }
     }
 
@@ -455,6 +644,14 @@ impl MoveController {
     }
 }
 
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
 /// GemState for cellGem API
 #[derive(Debug, Clone, Copy)]
 pub struct GemState {
@@ -530,10 +727,19 @@ impl MoveManager {
         self.controllers.iter().filter(|c| c.is_some()).count() as u8
     }
 
+    /// Bit mask of connected controllers
+    pub fn connected_mask(&self) -> u8 {
+        self.controllers
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_some())
+            .fold(0, |mask, (index, _)| mask | (1 << index))
+    }
+
     /// Update all controllers
-    pub fn update(&mut self) {
+    pub fn update(&mut self, dt: Duration) {
         for controller in self.controllers.iter_mut().flatten() {
-            controller.update();
+            controller.update(dt);
         }
     }
 
@@ -610,6 +816,78 @@ mod tests {
         assert!(!controller.state.buttons.contains(MoveButtons::TRIGGER));
     }
 
+    #[test]
+    fn test_gyro_integration() {
+        let mut controller = MoveController::new(0);
+        controller.connect();
+        // Yaw at 90°/s for one second
+        controller.set_motion(MoveMotionData {
+            gyro_z: std::f32::consts::FRAC_PI_2,
+            ..MoveMotionData::at_rest()
+        });
+        for _ in 0..60 {
+            controller.update(Duration::from_secs(1) / 60);
+        }
+        let forward = controller.state.orientation.rotate([1.0, 0.0, 0.0]);
+        assert!(forward[0].abs() < 1e-3);
+        assert!((forward[1] - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_calibration_removes_gyro_bias() {
+        let mut controller = MoveController::new(0);
+        controller.connect();
+        let biased = MoveMotionData {
+            gyro_z: 0.05,
+            ..MoveMotionData::at_rest()
+        };
+        controller.set_motion(biased);
+        controller.calibrate();
+        assert_eq!(controller.calibration_status(), CalibrationStatus::InProgress);
+
+        // Moving restarts the wait
+        let frame = Duration::from_millis(20);
+        for _ in 0..20 {
+            controller.update(frame);
+        }
+        controller.set_motion(MoveMotionData { gyro_x: 2.0, ..biased });
+        controller.update(frame);
+        controller.set_motion(biased);
+        for _ in 0..20 {
+            controller.update(frame);
+        }
+        assert_eq!(controller.take_calibration_result(), None);
+        for _ in 0..10 {
+            controller.update(frame);
+        }
+        assert_eq!(controller.calibration_status(), CalibrationStatus::Calibrated);
+        assert_eq!(controller.take_calibration_result(), Some(true));
+        assert_eq!(controller.take_calibration_result(), None);
+
+        // The bias no longer turns the controller
+        for _ in 0..100 {
+            controller.update(frame);
+        }
+        assert!(controller.state.orientation.to_rotation_vector()[2].abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_calibration_times_out() {
+        let mut controller = MoveController::new(0);
+        controller.connect();
+        controller.set_motion(MoveMotionData {
+            gyro_y: 1.0,
+            ..MoveMotionData::at_rest()
+        });
+        controller.calibrate();
+        for _ in 0..300 {
+            controller.update(Duration::from_millis(20));
+        }
+        assert_eq!(controller.calibration_status(), CalibrationStatus::Failed);
+        assert_eq!(controller.take_calibration_result(), Some(false));
+        assert!(!controller.calibrated);
+    }
+
     #[test]
     fn test_manager() {
         let mut manager = MoveManager::new();
@@ -1,6 +1,12 @@
 //! Controller/gamepad handling (cellPad)
 
+use crate::dualshock3::{pressure_index, SixaxisData, VibrationState};
 use bitflags::bitflags;
+use oc_core::config::InstrumentKind;
+use parking_lot::RwLock;
+
+/// Number of cellPad controller ports
+pub const MAX_PADS: usize = 7;
 
 bitflags! {
     /// PS3 controller button flags
@@ -25,8 +31,55 @@ bitflags! {
     }
 }
 
+impl PadButtons {
+    /// Look up a button by case-insensitive name ("cross", "l1", "dpad_up", ...)
+    pub fn parse_name(name: &str) -> Option<Self> {
+        Some(match name.trim().to_ascii_lowercase().as_str() {
+            "select" => Self::SELECT,
+            "l3" => Self::L3,
+            "r3" => Self::R3,
+            "start" => Self::START,
+            "dpad_up" | "up" => Self::DPAD_UP,
+            "dpad_right" | "right" => Self::DPAD_RIGHT,
+            "dpad_down" | "down" => Self::DPAD_DOWN,
+            "dpad_left" | "left" => Self::DPAD_LEFT,
+            "l2" => Self::L2,
+            "r2" => Self::R2,
+            "l1" => Self::L1,
+            "r1" => Self::R1,
+            "triangle" => Self::TRIANGLE,
+            "circle" => Self::CIRCLE,
+            "cross" => Self::CROSS,
+            "square" => Self::SQUARE,
+            _ => return None,
+        })
+    }
+
+    /// Pressure array slot of a pressure-sensitive button
+    pub fn pressure_index(self) -> Option<usize> {
+        Some(match self {
+            Self::DPAD_UP => pressure_index::DPAD_UP,
+            Self::DPAD_RIGHT => pressure_index::DPAD_RIGHT,
+            Self::DPAD_DOWN => pressure_index::DPAD_DOWN,
+            Self::DPAD_LEFT => pressure_index::DPAD_LEFT,
+            Self::L2 => pressure_index::L2,
+            Self::R2 => pressure_index::R2,
+            Self::L1 => pressure_index::L1,
+            Self::R1 => pressure_index::R1,
+            Self::TRIANGLE => pressure_index::TRIANGLE,
+            Self::CIRCLE => pressure_index::CIRCLE,
+            Self::CROSS => pressure_index::CROSS,
+            Self::SQUARE => pressure_index::SQUARE,
+            _ => return None,
+        })
+    }
+}
+
+/// Analog value above which a button counts as pressed
+pub const PRESS_THRESHOLD: f32 = 0.5;
+
 /// Controller state
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct PadState {
     /// Button state (bitflags)
     pub buttons: u32,
@@ -65,16 +118,42 @@ impl PadState {
             self.buttons &= !button.bits();
         }
     }
+
+    /// Apply an analog button value (0.0..1.0)
+    ///
+    /// Presses the button above [`PRESS_THRESHOLD`] and raises its pressure.
+    /// Never releases, so several inputs can drive the same button.
+    pub fn press(&mut self, button: PadButtons, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        if value >= PRESS_THRESHOLD {
+            self.set_button(button, true);
+        }
+        if let Some(slot) = button.pressure_index() {
+            let pressure = (value * 255.0).round() as u8;
+            self.pressure[slot] = self.pressure[slot].max(pressure);
+        }
+    }
 }
 
 /// Pad handler for a single controller
+#[derive(Debug, Clone)]
 pub struct Pad {
     /// Controller port (0-6)
     pub port: u8,
     /// Current state
     pub state: PadState,
+    /// Motion sensor state
+    pub sixaxis: SixaxisData,
+    /// Rumble requested by the game
+    pub vibration: VibrationState,
     /// Connected flag
     pub connected: bool,
+    /// Name of the host device on this port
+    pub device: Option<String>,
+    /// Incremented on every connect, so a quick unplug and replug is still seen
+    pub generation: u32,
+    /// Instrument the pad presents itself as
+    pub instrument: InstrumentKind,
 }
 
 impl Pad {
@@ -82,17 +161,250 @@ impl Pad {
         Self {
             port,
             state: PadState::new(),
+            sixaxis: SixaxisData::at_rest(),
+            vibration: VibrationState::new(),
             connected: false,
+            device: None,
+            generation: 0,
+            instrument: InstrumentKind::None,
         }
     }
 
     pub fn connect(&mut self) {
         self.connected = true;
+        self.generation = self.generation.wrapping_add(1);
     }
 
     pub fn disconnect(&mut self) {
         self.connected = false;
+        self.device = None;
+        self.instrument = InstrumentKind::None;
         self.state = PadState::new();
+        self.sixaxis = SixaxisData::at_rest();
+        self.vibration.stop();
+    }
+}
+
+/// Gamepad hotplug event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GamepadEvent {
+    /// A host controller was assigned to a port
+    Connected { port: u8, name: String },
+    /// The controller on a port was unplugged
+    Disconnected { port: u8 },
+    /// A controller was plugged in but every port is taken
+    NoFreePort { name: String },
+}
+
+/// Host controller information
+#[derive(Debug, Clone)]
+pub struct HostGamepadInfo {
+    /// Device name
+    pub name: String,
+    /// Assigned pad port
+    pub port: u8,
+    /// USB vendor ID, if known
+    pub vendor_id: Option<u16>,
+    /// USB product ID, if known
+    pub product_id: Option<u16>,
+    /// Whether an SDL mapping was found for the device
+    pub has_mapping: bool,
+}
+
+/// Controller ports shared between host input backends and cellPad
+///
+/// Backends connect pads and publish their state; cellPad reads a
+/// snapshot of each port when the game polls. Ports can be pinned to a
+/// host device name so each player keeps the same port; unpinned ports
+/// go to whichever device comes first.
+pub struct PadPorts {
+    pads: RwLock<[Pad; MAX_PADS]>,
+    /// Device name pinned to each port
+    pins: RwLock<[Option<String>; MAX_PADS]>,
+    /// Gyroscope drift of each port, subtracted from published motion
+    gyro_offsets: RwLock<[[i16; 3]; MAX_PADS]>,
+}
+
+impl PadPorts {
+    /// Create a set of disconnected ports
+    pub fn new() -> Self {
+        Self {
+            pads: RwLock::new(std::array::from_fn(|port| Pad::new(port as u8))),
+            pins: RwLock::new(Default::default()),
+            gyro_offsets: RwLock::new([[0; 3]; MAX_PADS]),
+        }
+    }
+
+    /// Pin ports to host device names (indexed by port, None = any device)
+    ///
+    /// Only affects devices connected afterwards.
+    pub fn set_pins(&self, pins: &[Option<String>]) {
+        let mut current = self.pins.write();
+        for (port, pin) in current.iter_mut().enumerate() {
+            *pin = pins.get(port).cloned().flatten().filter(|name| !name.trim().is_empty());
+        }
+    }
+
+    /// Device name pinned to a port
+    pub fn pin(&self, port: u8) -> Option<String> {
+        self.pins.read().get(port as usize).cloned().flatten()
+    }
+
+    /// Connect the lowest free port, returning its number
+    pub fn connect_free(&self) -> Option<u8> {
+        self.connect_device("")
+    }
+
+    /// Connect a port for a host device, returning its number
+    ///
+    /// Takes the lowest free port pinned to `name`, then the lowest free
+    /// unpinned port, then any free port.
+    pub fn connect_device(&self, name: &str) -> Option<u8> {
+        let pins = self.pins.read();
+        let mut pads = self.pads.write();
+        let name = name.trim();
+        let pinned_to = |port: usize| pins[port].as_deref().map(str::trim);
+        let free = |pad: &&mut Pad| !pad.connected;
+        let port = pads
+            .iter_mut()
+            .filter(free)
+            .find(|pad| pinned_to(pad.port as usize).is_some_and(|pin| !name.is_empty() && pin.eq_ignore_ascii_case(name)))
+            .map(|pad| pad.port)
+            .or_else(|| pads.iter_mut().filter(free).find(|pad| pinned_to(pad.port as usize).is_none()).map(|pad| pad.port))
+            .or_else(|| pads.iter_mut().find(|pad| !pad.connected).map(|pad| pad.port))?;
+        let pad = &mut pads[port as usize];
+        pad.connect();
+        pad.device = Some(name.to_string()).filter(|name| !name.is_empty());
+        Some(port)
+    }
+
+    /// Name of the host device on a port (None if disconnected or unnamed)
+    pub fn device(&self, port: u8) -> Option<String> {
+        self.pads.read().get(port as usize).and_then(|pad| pad.device.clone())
+    }
+
+    /// Connection count of a port, changing whenever a device connects
+    pub fn generation(&self, port: u8) -> u32 {
+        self.pads.read().get(port as usize).map_or(0, |pad| pad.generation)
+    }
+
+    /// Connect a specific port
+    pub fn connect(&self, port: u8) {
+        if let Some(pad) = self.pads.write().get_mut(port as usize) {
+            pad.connect();
+        }
+    }
+
+    /// Disconnect a port and reset its state
+    pub fn disconnect(&self, port: u8) {
+        if let Some(pad) = self.pads.write().get_mut(port as usize) {
+            pad.disconnect();
+        }
+    }
+
+    /// Publish the state of a connected port
+    pub fn update(&self, port: u8, state: PadState) {
+        if let Some(pad) = self.pads.write().get_mut(port as usize) {
+            if pad.connected {
+                pad.state = state;
+            }
+        }
+    }
+
+    /// Present a connected port as a guitar or drum kit
+    ///
+    /// Reset to a regular controller when the port disconnects.
+    pub fn set_instrument(&self, port: u8, instrument: InstrumentKind) {
+        if let Some(pad) = self.pads.write().get_mut(port as usize) {
+            if pad.connected {
+                pad.instrument = instrument;
+            }
+        }
+    }
+
+    /// Instrument a port presents itself as
+    pub fn instrument(&self, port: u8) -> InstrumentKind {
+        self.pads
+            .read()
+            .get(port as usize)
+            .map_or(InstrumentKind::None, |pad| pad.instrument)
+    }
+
+    /// Set the gyroscope drift of each port (indexed by port, missing = none)
+    ///
+    /// The drift is subtracted from every motion reading published afterwards.
+    pub fn set_gyro_offsets(&self, offsets: &[[i16; 3]]) {
+        let mut current = self.gyro_offsets.write();
+        for (port, offset) in current.iter_mut().enumerate() {
+            *offset = offsets.get(port).copied().unwrap_or_default();
+        }
+    }
+
+    /// Gyroscope drift subtracted on a port
+    pub fn gyro_offset(&self, port: u8) -> [i16; 3] {
+        self.gyro_offsets.read().get(port as usize).copied().unwrap_or_default()
+    }
+
+    /// Publish the motion sensor state of a connected port
+    pub fn update_motion(&self, port: u8, mut sixaxis: SixaxisData) {
+        let [x, y, z] = self.gyro_offset(port);
+        sixaxis.gyro_x = sixaxis.gyro_x.saturating_sub(x);
+        sixaxis.gyro_y = sixaxis.gyro_y.saturating_sub(y);
+        sixaxis.gyro_z = sixaxis.gyro_z.saturating_sub(z);
+        if let Some(pad) = self.pads.write().get_mut(port as usize) {
+            if pad.connected {
+                pad.sixaxis = sixaxis;
+            }
+        }
+    }
+
+    /// Motion sensor state of a port (None if disconnected)
+    pub fn motion(&self, port: u8) -> Option<SixaxisData> {
+        let pads = self.pads.read();
+        pads.get(port as usize)
+            .filter(|pad| pad.connected)
+            .map(|pad| pad.sixaxis)
+    }
+
+    /// Set the rumble requested for a port
+    pub fn set_vibration(&self, port: u8, vibration: VibrationState) {
+        if let Some(pad) = self.pads.write().get_mut(port as usize) {
+            if pad.connected {
+                pad.vibration = vibration;
+            }
+        }
+    }
+
+    /// Rumble requested for a port
+    pub fn vibration(&self, port: u8) -> VibrationState {
+        self.pads
+            .read()
+            .get(port as usize)
+            .map(|pad| pad.vibration)
+            .unwrap_or_default()
+    }
+
+    /// Current state of a port (None if disconnected)
+    pub fn state(&self, port: u8) -> Option<PadState> {
+        let pads = self.pads.read();
+        pads.get(port as usize)
+            .filter(|pad| pad.connected)
+            .map(|pad| pad.state.clone())
+    }
+
+    /// Bit mask of connected ports
+    pub fn connected_mask(&self) -> u8 {
+        self.pads
+            .read()
+            .iter()
+            .filter(|pad| pad.connected)
+            .fold(0, |mask, pad| mask | (1 << pad.port))
+    }
+}
+
+impl Default for PadPorts {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -118,4 +430,74 @@ mod tests {
         state.set_button(PadButtons::CROSS, false);
         assert!(!state.is_button_pressed(PadButtons::CROSS));
     }
+
+    #[test]
+    fn test_button_parse_name() {
+        assert_eq!(PadButtons::parse_name("Cross"), Some(PadButtons::CROSS));
+        assert_eq!(PadButtons::parse_name(" dpad_up "), Some(PadButtons::DPAD_UP));
+        assert_eq!(PadButtons::parse_name(""), None);
+    }
+
+    #[test]
+    fn test_pad_ports() {
+        let ports = PadPorts::new();
+        assert_eq!(ports.connect_free(), Some(0));
+        assert_eq!(ports.connect_free(), Some(1));
+        assert_eq!(ports.connected_mask(), 0b11);
+
+        let mut state = PadState::new();
+        state.set_button(PadButtons::START, true);
+        ports.update(1, state);
+        assert!(ports.state(1).unwrap().is_button_pressed(PadButtons::START));
+
+        // A freed port is reused first and comes back with a clean state
+        ports.disconnect(0);
+        assert!(ports.state(0).is_none());
+        assert_eq!(ports.connect_free(), Some(0));
+        assert_eq!(ports.state(0).unwrap().buttons, 0);
+    }
+
+    #[test]
+    fn test_pinned_ports() {
+        let ports = PadPorts::new();
+        ports.set_pins(&[None, Some("Xbox Controller".to_string()), None, Some("xbox controller".to_string())]);
+
+        // Pinned devices fill their ports in order, others skip them
+        assert_eq!(ports.connect_device("Xbox Controller"), Some(1));
+        assert_eq!(ports.connect_device("DualShock 3 (Usb)"), Some(0));
+        assert_eq!(ports.connect_device("Xbox Controller"), Some(3));
+        assert_eq!(ports.connect_device("Xbox Controller"), Some(2));
+        assert_eq!(ports.device(3).as_deref(), Some("Xbox Controller"));
+
+        // A reconnect is visible even if no poll saw the port empty
+        let generation = ports.generation(0);
+        ports.disconnect(0);
+        assert_eq!(ports.device(0), None);
+        assert_eq!(ports.connect_free(), Some(0));
+        assert_ne!(ports.generation(0), generation);
+
+        // Once unpinned ports run out, pinned ones are used
+        for port in 4..MAX_PADS as u8 {
+            assert_eq!(ports.connect_free(), Some(port));
+        }
+        ports.disconnect(1);
+        assert_eq!(ports.connect_free(), Some(1));
+        assert_eq!(ports.connect_free(), None);
+    }
+
+    #[test]
+    fn test_gyro_offsets() {
+        let ports = PadPorts::new();
+        assert_eq!(ports.connect_free(), Some(0));
+        ports.set_gyro_offsets(&[[4, -2, 0]]);
+        assert_eq!(ports.gyro_offset(1), [0; 3]);
+
+        let mut sixaxis = SixaxisData::at_rest();
+        sixaxis.gyro_x = 4;
+        sixaxis.gyro_y = i16::MIN;
+        ports.update_motion(0, sixaxis);
+        let motion = ports.motion(0).unwrap();
+        assert_eq!((motion.gyro_x, motion.gyro_y, motion.gyro_z), (0, i16::MIN + 2, 0));
+        assert_eq!(motion.accel_z, sixaxis.accel_z);
+    }
 }
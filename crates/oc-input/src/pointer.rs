@@ -0,0 +1,105 @@
+//! Light-gun pointer
+//!
+//! Light-gun style titles read where the Move points as a spot on the
+//! screen. A [`Pointer`] produces that spot straight from host aim, with no
+//! calibration: the mouse position in the game view, or the turn rate of a
+//! gyroscope added up into a screen offset, the way a gyro mouse works. The
+//! input profile's [`PointerResponse`] then scales and shifts it so the
+//! game's crosshair lines up with the host pointer.
+
+use oc_core::config::PointerResponse;
+
+/// Turn of the controller that sweeps the pointer across the view width, in degrees
+const SWEEP_H_DEG: f32 = 40.0;
+/// Aspect ratio of the game view
+const VIEW_ASPECT: f32 = 16.0 / 9.0;
+
+/// Screen-space pointer driven by host aim
+#[derive(Debug, Clone)]
+pub struct Pointer {
+    response: PointerResponse,
+    /// Host aim before the response (0.0..1.0 from the top left), None when off screen
+    aim: Option<[f32; 2]>,
+}
+
+impl Pointer {
+    /// Create an off-screen pointer
+    pub fn new(response: PointerResponse) -> Self {
+        Self { response, aim: None }
+    }
+
+    /// Change the response, keeping the aim
+    pub fn set_response(&mut self, response: PointerResponse) {
+        self.response = response;
+    }
+
+    /// Aim at a spot in the game view (0.0..1.0 from the top left)
+    pub fn set_absolute(&mut self, x: f32, y: f32) {
+        self.aim = Some([x.clamp(0.0, 1.0), y.clamp(0.0, 1.0)]);
+    }
+
+    /// Move the aim by a turn of the controller, in degrees to the left and up
+    ///
+    /// The aim stops at the edges of the view, so turning back brings it
+    /// straight back and no drift builds up off screen.
+    pub fn turn(&mut self, yaw_deg: f32, pitch_deg: f32) {
+        let [x, y] = self.aim.unwrap_or([0.5, 0.5]);
+        let sweep_v_deg = SWEEP_H_DEG / VIEW_ASPECT;
+        self.aim = Some([
+            (x - yaw_deg / SWEEP_H_DEG).clamp(0.0, 1.0),
+            (y - pitch_deg / sweep_v_deg).clamp(0.0, 1.0),
+        ]);
+    }
+
+    /// Aim at the center of the view
+    pub fn recenter(&mut self) {
+        self.aim = Some([0.5, 0.5]);
+    }
+
+    /// Take the pointer off screen
+    pub fn clear(&mut self) {
+        self.aim = None;
+    }
+
+    /// Spot the game sees (0.0..1.0 from the top left), None when off screen
+    pub fn position(&self) -> Option<[f32; 2]> {
+        let [x, y] = self.aim?;
+        let response = &self.response;
+        Some([
+            (0.5 + (x - 0.5) * response.sensitivity_x + response.offset_x).clamp(0.0, 1.0),
+            (0.5 + (y - 0.5) * response.sensitivity_y + response.offset_y).clamp(0.0, 1.0),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pointer_response() {
+        let mut pointer = Pointer::new(PointerResponse {
+            sensitivity_x: 0.5,
+            offset_y: 0.1,
+            ..Default::default()
+        });
+        assert_eq!(pointer.position(), None);
+        pointer.set_absolute(1.0, 0.5);
+        assert_eq!(pointer.position(), Some([0.75, 0.6]));
+
+        // Turning left by half a sweep from the center reaches the left edge
+        pointer.set_response(PointerResponse::default());
+        pointer.recenter();
+        pointer.turn(SWEEP_H_DEG / 2.0, 0.0);
+        assert_eq!(pointer.position(), Some([0.0, 0.5]));
+        // Turning further does not build up off screen
+        pointer.turn(SWEEP_H_DEG, 0.0);
+        pointer.turn(-SWEEP_H_DEG / 4.0, 0.0);
+        assert_eq!(pointer.position(), Some([0.25, 0.5]));
+        pointer.turn(0.0, -SWEEP_H_DEG / VIEW_ASPECT / 4.0);
+        assert_eq!(pointer.position(), Some([0.25, 0.75]));
+
+        pointer.clear();
+        assert_eq!(pointer.position(), None);
+    }
+}
@@ -5,9 +5,21 @@
 //! - XInput controllers (Xbox 360/One style)
 //! - DirectInput controllers
 //! - USB adapters for PS3 controllers
+//!
+//! Peripherals with their own drivers in games (Buzz! buzzers, dance mats,
+//! GT steering wheels) can instead be passed through as raw USB devices,
+//! see `UsbPassthrough` (requires the `hid` feature).
 
 use crate::pad::{PadButtons, PadState};
 use std::collections::HashMap;
+#[cfg(feature = "hid")]
+use hidapi::{DeviceInfo, HidApi, HidDevice};
+#[cfg(feature = "hid")]
+use std::collections::VecDeque;
+#[cfg(feature = "hid")]
+use std::ffi::CString;
+#[cfg(feature = "hid")]
+use std::time::{Duration, Instant};
 
 /// USB device vendor/product IDs for known controllers
 pub mod known_devices {
@@ -29,6 +41,42 @@ pub mod known_devices {
     pub const BITDO_PRO2: (u16, u16) = (0x2DC8, 0x6101);
 }
 
+/// Host HID devices games talk to with their own USB drivers
+pub mod passthrough_devices {
+    /// Buzz! buzzers (wired)
+    pub const BUZZ: (u16, u16) = (0x054C, 0x0002);
+    /// Buzz! buzzers (wireless)
+    pub const BUZZ_WIRELESS: (u16, u16) = (0x054C, 0x1000);
+    /// Konami Dance Dance Revolution mat
+    pub const DDR_MAT: (u16, u16) = (0x0507, 0x0011);
+    /// Logitech Driving Force Pro
+    pub const DRIVING_FORCE_PRO: (u16, u16) = (0x046D, 0xC298);
+    /// Logitech G25
+    pub const G25: (u16, u16) = (0x046D, 0xC299);
+    /// Logitech Driving Force GT
+    pub const DRIVING_FORCE_GT: (u16, u16) = (0x046D, 0xC29A);
+    /// Logitech G27
+    pub const G27: (u16, u16) = (0x046D, 0xC29B);
+
+    /// Devices passed through when passthrough is enabled
+    pub const WHITELIST: [(u16, u16); 7] = [
+        BUZZ,
+        BUZZ_WIRELESS,
+        DDR_MAT,
+        DRIVING_FORCE_PRO,
+        G25,
+        DRIVING_FORCE_GT,
+        G27,
+    ];
+}
+
+/// Parse a "VID:PID" pair in hex, e.g. "054c:0002"
+pub fn parse_usb_id(text: &str) -> Option<(u16, u16)> {
+    let (vid, pid) = text.trim().split_once(':')?;
+    let parse = |id: &str| u16::from_str_radix(id.trim().trim_start_matches("0x"), 16).ok();
+    Some((parse(vid)?, parse(pid)?))
+}
+
 /// USB device info
 #[derive(Debug, Clone)]
 pub struct UsbDeviceInfo {
@@ -476,6 +524,385 @@ impl Default for UsbControllerManager {
     }
 }
 
+/// Input reports kept per device between reads; older ones are dropped
+#[cfg(feature = "hid")]
+const MAX_QUEUED_REPORTS: usize = 64;
+/// Interval between scans for newly plugged devices
+#[cfg(feature = "hid")]
+const RESCAN_INTERVAL: Duration = Duration::from_secs(2);
+/// Largest report descriptor read from a device
+#[cfg(feature = "hid")]
+const MAX_REPORT_DESCRIPTOR: usize = 4096;
+/// Packet size of the interrupt endpoints presented to the guest
+const INTERRUPT_PACKET_SIZE: u16 = 64;
+
+/// Standard USB device descriptor (18 bytes) for a passed-through HID device
+///
+/// String descriptor indices are 1 (manufacturer), 2 (product) and 3
+/// (serial), or 0 when the host did not report the string.
+pub fn hid_device_descriptor(info: &UsbDeviceInfo, release_number: u16) -> [u8; 18] {
+    let [vid_lo, vid_hi] = info.vendor_id.to_le_bytes();
+    let [pid_lo, pid_hi] = info.product_id.to_le_bytes();
+    let [rel_lo, rel_hi] = release_number.to_le_bytes();
+    let string = |present: bool, index: u8| if present { index } else { 0 };
+    [
+        18,   // bLength
+        0x01, // DEVICE
+        0x00, 0x02, // USB 2.0
+        0x00, 0x00, 0x00, // class defined by the interface
+        64,   // bMaxPacketSize0
+        vid_lo, vid_hi, pid_lo, pid_hi, rel_lo, rel_hi,
+        string(!info.manufacturer.is_empty(), 1),
+        string(!info.name.is_empty(), 2),
+        string(info.serial.is_some(), 3),
+        1, // bNumConfigurations
+    ]
+}
+
+/// Configuration descriptor set of a HID device with one interrupt IN and OUT endpoint
+pub fn hid_configuration_descriptor(report_descriptor_len: u16) -> Vec<u8> {
+    let [report_lo, report_hi] = report_descriptor_len.to_le_bytes();
+    let [packet_lo, packet_hi] = INTERRUPT_PACKET_SIZE.to_le_bytes();
+    let mut descriptor = vec![
+        // Configuration: total length patched below, 1 interface, bus powered, 100 mA
+        9, 0x02, 0, 0, 1, 1, 0, 0x80, 50,
+        // Interface 0: HID, 2 endpoints
+        9, 0x04, 0, 0, 2, 0x03, 0x00, 0x00, 0,
+        // HID 1.11 with one report descriptor
+        9, 0x21, 0x11, 0x01, 0x00, 1, 0x22, report_lo, report_hi,
+        // Endpoint 1 IN, interrupt, 10 ms
+        7, 0x05, 0x81, 0x03, packet_lo, packet_hi, 10,
+        // Endpoint 2 OUT, interrupt, 10 ms
+        7, 0x05, 0x02, 0x03, packet_lo, packet_hi, 10,
+    ];
+    let total = (descriptor.len() as u16).to_le_bytes();
+    descriptor[2..4].copy_from_slice(&total);
+    descriptor
+}
+
+/// Whether a HID report descriptor declares report IDs
+///
+/// Reports of devices without IDs carry no ID byte on the wire, but hidapi
+/// expects a leading 0 on writes to them.
+pub fn uses_report_ids(report_descriptor: &[u8]) -> bool {
+    let mut at = 0;
+    while at < report_descriptor.len() {
+        let prefix = report_descriptor[at];
+        if prefix == 0xFE {
+            // Long item: size byte, tag byte, data
+            let size = report_descriptor.get(at + 1).copied().unwrap_or(0) as usize;
+            at += 3 + size;
+            continue;
+        }
+        // Global item "Report ID"
+        if prefix & 0xFC == 0x84 {
+            return true;
+        }
+        let size = match prefix & 0x03 {
+            3 => 4,
+            size => size as usize,
+        };
+        at += 1 + size;
+    }
+    false
+}
+
+/// A host HID device handed to the guest as a raw USB device
+#[cfg(feature = "hid")]
+pub struct UsbPassthroughDevice {
+    /// Handle in the guest's USB enumeration, never reused
+    pub id: u32,
+    pub info: UsbDeviceInfo,
+    /// Device release (bcdDevice)
+    pub release_number: u16,
+    device: HidDevice,
+    path: CString,
+    report_descriptor: Vec<u8>,
+    report_ids: bool,
+    input_reports: VecDeque<Vec<u8>>,
+}
+
+#[cfg(feature = "hid")]
+impl std::fmt::Debug for UsbPassthroughDevice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UsbPassthroughDevice")
+            .field("id", &self.id)
+            .field("info", &self.info)
+            .field("queued_reports", &self.input_reports.len())
+            .finish()
+    }
+}
+
+#[cfg(feature = "hid")]
+impl UsbPassthroughDevice {
+    /// Open a device in non-blocking mode
+    fn open(api: &HidApi, host: &DeviceInfo, id: u32) -> Result<Self, String> {
+        let info = UsbDeviceInfo {
+            vendor_id: host.vendor_id(),
+            product_id: host.product_id(),
+            name: host.product_string().unwrap_or_default().to_string(),
+            manufacturer: host.manufacturer_string().unwrap_or_default().to_string(),
+            serial: host.serial_number().filter(|serial| !serial.is_empty()).map(str::to_string),
+        };
+        let device = host
+            .open_device(api)
+            .map_err(|e| format!("Failed to open USB device {:04x}:{:04x}: {}", info.vendor_id, info.product_id, e))?;
+        device
+            .set_blocking_mode(false)
+            .map_err(|e| format!("Failed to set non-blocking mode: {}", e))?;
+
+        let mut buf = vec![0u8; MAX_REPORT_DESCRIPTOR];
+        let report_descriptor = match device.get_report_descriptor(&mut buf) {
+            Ok(len) => buf[..len].to_vec(),
+            Err(e) => {
+                tracing::debug!("No report descriptor for {:04x}:{:04x}: {}", info.vendor_id, info.product_id, e);
+                Vec::new()
+            }
+        };
+        Ok(Self {
+            id,
+            release_number: host.release_number(),
+            report_ids: uses_report_ids(&report_descriptor),
+            info,
+            device,
+            path: host.path().to_owned(),
+            report_descriptor,
+            input_reports: VecDeque::new(),
+        })
+    }
+
+    /// Standard device descriptor
+    pub fn device_descriptor(&self) -> [u8; 18] {
+        hid_device_descriptor(&self.info, self.release_number)
+    }
+
+    /// Configuration, interface, HID and endpoint descriptors
+    pub fn configuration_descriptor(&self) -> Vec<u8> {
+        hid_configuration_descriptor(self.report_descriptor.len() as u16)
+    }
+
+    /// HID report descriptor (empty if the host did not provide one)
+    pub fn report_descriptor(&self) -> &[u8] {
+        &self.report_descriptor
+    }
+
+    /// String descriptor text (1 = manufacturer, 2 = product, 3 = serial)
+    pub fn string(&self, index: u8) -> Option<&str> {
+        match index {
+            1 => Some(self.info.manufacturer.as_str()).filter(|s| !s.is_empty()),
+            2 => Some(self.info.name.as_str()).filter(|s| !s.is_empty()),
+            3 => self.info.serial.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Next input report, for an interrupt IN transfer
+    pub fn take_input_report(&mut self) -> Option<Vec<u8>> {
+        self.input_reports.pop_front()
+    }
+
+    /// Send an output report, from an interrupt OUT transfer or SET_REPORT(Output)
+    ///
+    /// `report` is as the guest sends it: starting with the report ID only
+    /// if the device uses IDs. Returns the number of bytes written.
+    pub fn write_output_report(&self, report: &[u8]) -> Result<usize, String> {
+        let written = if self.report_ids {
+            self.device.write(report)
+        } else {
+            self.device.write(&[&[0], report].concat()).map(|len| len.saturating_sub(1))
+        };
+        written.map_err(|e| format!("USB passthrough write failed: {}", e))
+    }
+
+    /// Read a feature report, for GET_REPORT(Feature)
+    pub fn get_feature_report(&self, report_id: u8, len: usize) -> Result<Vec<u8>, String> {
+        let mut buf = vec![0u8; len.max(1) + 1];
+        buf[0] = report_id;
+        let read = self
+            .device
+            .get_feature_report(&mut buf)
+            .map_err(|e| format!("USB passthrough feature read failed: {}", e))?;
+        buf.truncate(read);
+        if !self.report_ids && !buf.is_empty() {
+            buf.remove(0);
+        }
+        Ok(buf)
+    }
+
+    /// Send a feature report, for SET_REPORT(Feature)
+    pub fn send_feature_report(&self, report: &[u8]) -> Result<(), String> {
+        let result = if self.report_ids {
+            self.device.send_feature_report(report)
+        } else {
+            self.device.send_feature_report(&[&[0], report].concat())
+        };
+        result.map_err(|e| format!("USB passthrough feature write failed: {}", e))
+    }
+
+    /// Queue pending input reports, returning false if the device is gone
+    fn read(&mut self) -> bool {
+        let mut buf = [0u8; 256];
+        loop {
+            match self.device.read(&mut buf) {
+                Ok(0) => return true,
+                Ok(len) => {
+                    if self.input_reports.len() >= MAX_QUEUED_REPORTS {
+                        self.input_reports.pop_front();
+                    }
+                    self.input_reports.push_back(buf[..len].to_vec());
+                }
+                Err(e) => {
+                    tracing::debug!("USB passthrough read failed: {}", e);
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+/// Passthrough hotplug event, for the guest's USB enumeration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsbPassthroughEvent {
+    Attached { id: u32, vendor_id: u16, product_id: u16 },
+    Detached { id: u32 },
+}
+
+/// Raw USB passthrough of whitelisted host HID devices
+///
+/// Devices are opened as they are plugged in and their input reports are
+/// queued until the guest's driver reads them.
+#[cfg(feature = "hid")]
+pub struct UsbPassthrough {
+    /// hidapi context (None if it failed to start)
+    api: Option<HidApi>,
+    /// VID/PID pairs that may be passed through
+    allowed: Vec<(u16, u16)>,
+    devices: Vec<UsbPassthroughDevice>,
+    next_id: u32,
+    last_scan: Option<Instant>,
+}
+
+#[cfg(feature = "hid")]
+impl UsbPassthrough {
+    /// Pass through the known peripherals plus `extra` VID/PID pairs
+    pub fn new(extra: &[(u16, u16)]) -> Self {
+        let api = match HidApi::new() {
+            Ok(api) => Some(api),
+            Err(e) => {
+                tracing::warn!("Failed to initialize hidapi: {}", e);
+                None
+            }
+        };
+        let mut allowed = passthrough_devices::WHITELIST.to_vec();
+        for id in extra {
+            if !allowed.contains(id) {
+                allowed.push(*id);
+            }
+        }
+        Self {
+            api,
+            allowed,
+            devices: Vec::new(),
+            next_id: 1,
+            last_scan: None,
+        }
+    }
+
+    /// Whether HID devices can be accessed
+    pub fn is_available(&self) -> bool {
+        self.api.is_some()
+    }
+
+    /// VID/PID pairs that are passed through
+    pub fn allowed(&self) -> &[(u16, u16)] {
+        &self.allowed
+    }
+
+    /// Pick up hotplug and queue input reports
+    ///
+    /// Call once per frame. Returns the devices attached and detached.
+    pub fn poll(&mut self) -> Vec<UsbPassthroughEvent> {
+        let mut events = Vec::new();
+        let scan_due = match self.last_scan {
+            Some(last) => last.elapsed() >= RESCAN_INTERVAL,
+            None => true,
+        };
+        if scan_due {
+            self.last_scan = Some(Instant::now());
+            self.scan(&mut events);
+        }
+
+        self.devices.retain_mut(|dev| {
+            if dev.read() {
+                return true;
+            }
+            tracing::info!("USB passthrough device \"{}\" removed", dev.info.name);
+            events.push(UsbPassthroughEvent::Detached { id: dev.id });
+            false
+        });
+        events
+    }
+
+    /// Passed-through devices
+    pub fn devices(&self) -> &[UsbPassthroughDevice] {
+        &self.devices
+    }
+
+    /// Device by enumeration handle
+    pub fn device_mut(&mut self, id: u32) -> Option<&mut UsbPassthroughDevice> {
+        self.devices.iter_mut().find(|dev| dev.id == id)
+    }
+
+    /// Close every device; they are reopened under new handles on the next poll
+    pub fn release_all(&mut self) -> Vec<UsbPassthroughEvent> {
+        self.last_scan = None;
+        self.devices
+            .drain(..)
+            .map(|dev| UsbPassthroughEvent::Detached { id: dev.id })
+            .collect()
+    }
+
+    /// Open whitelisted devices that appeared since the last scan
+    fn scan(&mut self, events: &mut Vec<UsbPassthroughEvent>) {
+        let Some(api) = self.api.as_mut() else {
+            return;
+        };
+        if let Err(e) = api.refresh_devices() {
+            tracing::debug!("HID enumeration failed: {}", e);
+            return;
+        }
+
+        for host in api.device_list() {
+            let id = (host.vendor_id(), host.product_id());
+            // Composite devices list one entry per interface; the guest sees the device once
+            if !self.allowed.contains(&id)
+                || host.interface_number() > 0
+                || self.devices.iter().any(|dev| dev.path.as_c_str() == host.path())
+            {
+                continue;
+            }
+            match UsbPassthroughDevice::open(api, host, self.next_id) {
+                Ok(dev) => {
+                    tracing::info!(
+                        "USB passthrough device \"{}\" ({:04x}:{:04x}) attached",
+                        dev.info.name,
+                        id.0,
+                        id.1
+                    );
+                    events.push(UsbPassthroughEvent::Attached {
+                        id: dev.id,
+                        vendor_id: id.0,
+                        product_id: id.1,
+                    });
+                    self.next_id += 1;
+                    self.devices.push(dev);
+                }
+                Err(e) => tracing::warn!("{}", e),
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -534,6 +961,38 @@ mod tests {
         assert_eq!(manager.count(), 0);
     }
 
+    #[test]
+    fn test_passthrough_descriptors() {
+        assert_eq!(parse_usb_id("054c:0002"), Some(passthrough_devices::BUZZ));
+        assert_eq!(parse_usb_id(" 0x046D : 0xC29A "), Some(passthrough_devices::DRIVING_FORCE_GT));
+        assert_eq!(parse_usb_id("054c"), None);
+        assert_eq!(parse_usb_id("xyz:0002"), None);
+
+        let info = UsbDeviceInfo {
+            vendor_id: 0x054C,
+            product_id: 0x1000,
+            name: "Wireless Buzz! Receiver".to_string(),
+            manufacturer: String::new(),
+            serial: None,
+        };
+        let device = hid_device_descriptor(&info, 0x0101);
+        assert_eq!(&device[8..14], &[0x4C, 0x05, 0x00, 0x10, 0x01, 0x01]);
+        // Only the product string is present
+        assert_eq!(&device[14..17], &[0, 2, 0]);
+
+        let config = hid_configuration_descriptor(0x9D);
+        assert_eq!(u16::from_le_bytes([config[2], config[3]]) as usize, config.len());
+        assert_eq!(&config[25..27], &[0x9D, 0x00]);
+
+        // Usage page, usage, collection... without and with a Report ID item
+        let plain = [0x05, 0x01, 0x09, 0x04, 0xA1, 0x01, 0x15, 0x00, 0x26, 0xFF, 0x00, 0xC0];
+        assert!(!uses_report_ids(&plain));
+        let with_ids = [0x05, 0x01, 0x09, 0x04, 0xA1, 0x01, 0x85, 0x01, 0xC0];
+        assert!(uses_report_ids(&with_ids));
+        // 0x85 in item data is not a Report ID item
+        assert!(!uses_report_ids(&[0x26, 0x85, 0x00]));
+    }
+
     #[test]
     fn test_axis_conversion() {
         assert_eq!(UsbController::axis_to_u8(-32768), 0);
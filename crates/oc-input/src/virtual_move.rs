@@ -0,0 +1,367 @@
+//! Virtual PlayStation Move
+//!
+//! Drives an emulated Move for players without one. With the mouse, the
+//! pointer's place in the game view becomes the sphere's position in the
+//! camera image and the controller is aimed at it; the left and right
+//! buttons are the trigger and the Move button. With a DualShock 4 or
+//! DualSense, its gyroscope turns the controller and the sphere follows
+//! where it points. In pointer mode the gyroscope moves a calibration-free
+//! [`Pointer`] instead, which then stands in for the mouse, so light-gun
+//! titles get a steady crosshair that never needs recalibrating. Either way
+//! the sensor readings are synthesized to match, so titles that fuse them
+//! themselves see consistent motion.
+
+use crate::dualshock3::{pressure_index, SixaxisData};
+use crate::keyboard::KeyboardState;
+use crate::mapping::{HostInput, InputMapping, Ps3Input};
+use crate::mouse::MouseButtons;
+use crate::move_controller::{
+    MoveButtons, MoveController, MoveMotionData, Position3D, Quaternion, TrackingQuality, GRAVITY,
+};
+use crate::pad::{PadButtons, PadState};
+use crate::pointer::Pointer;
+use oc_core::config::{KeyboardMapping, MoveConfig, MoveSource, PointerResponse};
+use std::time::Duration;
+
+/// PlayStation Eye horizontal field of view (wide setting), in degrees
+const CAMERA_FOV_H: f32 = 75.0;
+/// PlayStation Eye image aspect ratio
+const CAMERA_ASPECT: f32 = 4.0 / 3.0;
+
+/// Virtual Move driver
+pub struct VirtualMove {
+    source: MoveSource,
+    distance_mm: f32,
+    /// Key to button mappings (face buttons, Start and Select)
+    keys: InputMapping,
+    keyboard: KeyboardState,
+    mouse: MouseButtons,
+    /// Where the mouse or the pointer-mode gyroscope aims in the game view
+    pointer: Pointer,
+    /// Latest state of the DS4 driving the Move
+    ds4: Option<(PadState, SixaxisData)>,
+    /// Orientation aimed at the pointer
+    aim: Quaternion,
+}
+
+impl VirtualMove {
+    /// Create a driver from configuration and the pointer response of the game's input profile
+    pub fn new(config: &MoveConfig, keys: &KeyboardMapping, pointer: PointerResponse) -> Self {
+        Self {
+            source: config.source,
+            distance_mm: config.distance_mm.max(1.0),
+            keys: InputMapping::from_keyboard_config(keys),
+            keyboard: KeyboardState::new(),
+            mouse: MouseButtons::empty(),
+            pointer: Pointer::new(pointer),
+            ds4: None,
+            aim: Quaternion::identity(),
+        }
+    }
+
+    /// What drives this Move
+    pub fn source(&self) -> MoveSource {
+        self.source
+    }
+
+    /// Record a key press or release (USB HID usage code)
+    pub fn key_event(&mut self, key_code: u16, pressed: bool) {
+        if pressed {
+            self.keyboard.press_key(key_code);
+        } else {
+            self.keyboard.release_key(key_code);
+        }
+    }
+
+    /// Record a mouse button press or release
+    pub fn mouse_button(&mut self, button: MouseButtons, pressed: bool) {
+        self.mouse.set(button, pressed);
+    }
+
+    /// Record the pointer position within the game view (0.0..1.0 from the top left)
+    pub fn pointer_moved(&mut self, x: f32, y: f32) {
+        self.pointer.set_absolute(x, y);
+    }
+
+    /// The pointer left the game view; the sphere is no longer tracked
+    pub fn pointer_left(&mut self) {
+        self.pointer.clear();
+    }
+
+    /// Record the state of the DS4 driving the Move
+    pub fn set_ds4_input(&mut self, pad: PadState, sixaxis: SixaxisData) {
+        self.ds4 = Some((pad, sixaxis));
+    }
+
+    /// Release every key and button (e.g. when the window loses focus)
+    pub fn release_all(&mut self) {
+        self.keyboard.clear();
+        self.mouse = MouseButtons::empty();
+    }
+
+    /// Advance by `dt` and update the controller
+    pub fn update(&mut self, dt: Duration, controller: &mut MoveController) {
+        match self.source {
+            MoveSource::Off => {}
+            MoveSource::Mouse => self.update_mouse(dt, controller),
+            MoveSource::Ds4 => self.update_ds4(dt, controller),
+            MoveSource::Ds4Pointer => self.update_ds4_pointer(dt, controller),
+        }
+    }
+
+    fn update_mouse(&mut self, dt: Duration, controller: &mut MoveController) {
+        let mut pad = PadState::new();
+        for &key in &self.keyboard.pressed_keys {
+            if let Some(Ps3Input::PadButton(button)) = self.keys.get_mapping(HostInput::Key(key)) {
+                pad.press(button, 1.0);
+            }
+        }
+        pad.press(PadButtons::R1, self.mouse.contains(MouseButtons::RIGHT) as u8 as f32);
+        pad.press(PadButtons::R2, self.mouse.contains(MouseButtons::LEFT) as u8 as f32);
+        apply_buttons(&pad, controller);
+        self.track_pointer(dt, controller);
+    }
+
+    fn update_ds4_pointer(&mut self, dt: Duration, controller: &mut MoveController) {
+        let Some((pad, sixaxis)) = self.ds4.as_ref() else {
+            controller.set_position(controller.state.position, TrackingQuality::NotTracked);
+            return;
+        };
+        apply_buttons(pad, controller);
+        // R3 puts the pointer back in the middle
+        if pad.is_button_pressed(PadButtons::R3) {
+            self.pointer.recenter();
+        } else {
+            let motion = MoveMotionData::from_sixaxis(sixaxis);
+            let dt_s = dt.as_secs_f32();
+            self.pointer.turn(motion.gyro_z.to_degrees() * dt_s, -motion.gyro_y.to_degrees() * dt_s);
+        }
+        self.track_pointer(dt, controller);
+    }
+
+    /// Put the sphere where the pointer is and aim the controller at it
+    fn track_pointer(&mut self, dt: Duration, controller: &mut MoveController) {
+        let previous = self.aim;
+        if let Some([u, v]) = self.pointer.position() {
+            let (half_w, half_h) = self.half_view();
+            let position = Position3D::new((2.0 * u - 1.0) * half_w, (1.0 - 2.0 * v) * half_h, self.distance_mm);
+            self.aim = aim_at(&position);
+            controller.set_position(position, TrackingQuality::Good);
+        } else {
+            controller.set_position(controller.state.position, TrackingQuality::NotTracked);
+        }
+
+        // Synthesize the readings a real controller would give for this motion
+        let dt_s = dt.as_secs_f32();
+        let rate = if dt_s > 0.0 {
+            (previous.conjugate() * self.aim).to_rotation_vector().map(|v| v / dt_s)
+        } else {
+            [0.0; 3]
+        };
+        let accel = self.aim.conjugate().rotate([0.0, 0.0, GRAVITY]);
+        controller.set_motion(MoveMotionData {
+            accel_x: accel[0],
+            accel_y: accel[1],
+            accel_z: accel[2],
+            gyro_x: rate[0],
+            gyro_y: rate[1],
+            gyro_z: rate[2],
+            ..MoveMotionData::at_rest()
+        });
+        controller.update(dt);
+        // The pointer gives the exact orientation, so skip the fused estimate
+        controller.set_orientation(self.aim);
+    }
+
+    fn update_ds4(&mut self, dt: Duration, controller: &mut MoveController) {
+        let Some((pad, sixaxis)) = self.ds4.as_ref() else {
+            controller.set_position(controller.state.position, TrackingQuality::NotTracked);
+            return;
+        };
+        apply_buttons(pad, controller);
+        controller.set_motion(MoveMotionData::from_sixaxis(sixaxis));
+        controller.update(dt);
+
+        // Put the sphere where the controller points, at the tracking distance
+        let forward = controller.state.orientation.rotate([1.0, 0.0, 0.0]);
+        let (half_w, half_h) = self.half_view();
+        let level = (forward[0] * forward[0] + forward[1] * forward[1]).sqrt();
+        if forward[0] > 0.0 {
+            let x = -self.distance_mm * forward[1] / forward[0];
+            let y = self.distance_mm * forward[2] / level;
+            let quality = if x.abs() <= half_w && y.abs() <= half_h {
+                TrackingQuality::Good
+            } else {
+                TrackingQuality::NotTracked
+            };
+            let position = Position3D::new(x.clamp(-half_w, half_w), y.clamp(-half_h, half_h), self.distance_mm);
+            controller.set_position(position, quality);
+        } else {
+            controller.set_position(controller.state.position, TrackingQuality::NotTracked);
+        }
+    }
+
+    /// Half the width and height of the camera view at the tracking distance (mm)
+    fn half_view(&self) -> (f32, f32) {
+        let half_w = self.distance_mm * (CAMERA_FOV_H / 2.0).to_radians().tan();
+        (half_w, half_w / CAMERA_ASPECT)
+    }
+}
+
+/// Orientation pointing the controller (forward along +X, Z up) at a camera-space position
+///
+/// Camera space has X to the right and Y up, so aiming right is a negative
+/// yaw and aiming up a negative pitch.
+fn aim_at(position: &Position3D) -> Quaternion {
+    let azimuth = -(position.x / position.z).atan();
+    let elevation = (position.y / position.z).atan();
+    Quaternion::from_euler(0.0, -elevation, azimuth)
+}
+
+/// Copy pad buttons to the Move: face buttons, Start and Select as-is,
+/// R1 as the Move button and R2 as the trigger
+fn apply_buttons(pad: &PadState, controller: &mut MoveController) {
+    let buttons = [
+        (PadButtons::CROSS, MoveButtons::CROSS),
+        (PadButtons::CIRCLE, MoveButtons::CIRCLE),
+        (PadButtons::SQUARE, MoveButtons::SQUARE),
+        (PadButtons::TRIANGLE, MoveButtons::TRIANGLE),
+        (PadButtons::START, MoveButtons::START),
+        (PadButtons::SELECT, MoveButtons::SELECT),
+        (PadButtons::R1, MoveButtons::MOVE),
+    ];
+    for (pad_button, move_button) in buttons {
+        controller.set_button(move_button, pad.is_button_pressed(pad_button));
+    }
+    let pressure = pad.pressure[pressure_index::R2];
+    let trigger = if pressure == 0 && pad.is_button_pressed(PadButtons::R2) { 255 } else { pressure };
+    controller.set_trigger(trigger);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyboard::KeyCode;
+    use crate::move_controller::CalibrationStatus;
+
+    const FRAME: Duration = Duration::from_millis(20);
+
+    fn setup(source: MoveSource) -> (VirtualMove, MoveController) {
+        let config = MoveConfig {
+            source,
+            ..Default::default()
+        };
+        let mut controller = MoveController::new(0);
+        controller.connect();
+        (VirtualMove::new(&config, &KeyboardMapping::default(), PointerResponse::default()), controller)
+    }
+
+    #[test]
+    fn test_mouse_pointer_tracking() {
+        let (mut driver, mut controller) = setup(MoveSource::Mouse);
+        driver.pointer_moved(0.5, 0.5);
+        driver.update(FRAME, &mut controller);
+        let position = controller.state.position;
+        assert_eq!(controller.state.tracking, TrackingQuality::Good);
+        assert!(position.x.abs() < 1e-3 && position.y.abs() < 1e-3);
+        assert_eq!(position.z, 1500.0);
+
+        // Moving right turns the controller right (negative yaw)
+        driver.pointer_moved(1.0, 0.25);
+        driver.update(FRAME, &mut controller);
+        let position = controller.state.position;
+        assert!(position.x > 1000.0 && position.y > 0.0);
+        assert!(controller.state.motion.gyro_z < 0.0);
+        let forward = controller.state.orientation.rotate([1.0, 0.0, 0.0]);
+        assert!(forward[1] < 0.0 && forward[2] > 0.0);
+        let level = (forward[0] * forward[0] + forward[1] * forward[1]).sqrt();
+        assert!((forward[2] / level - position.y / position.z).abs() < 1e-3);
+
+        // Tilting upward shows up on the accelerometer
+        assert!(controller.state.motion.accel_x > 0.0);
+
+        driver.pointer_left();
+        driver.update(FRAME, &mut controller);
+        assert_eq!(controller.state.tracking, TrackingQuality::NotTracked);
+    }
+
+    #[test]
+    fn test_mouse_buttons() {
+        let (mut driver, mut controller) = setup(MoveSource::Mouse);
+        driver.mouse_button(MouseButtons::LEFT, true);
+        driver.mouse_button(MouseButtons::RIGHT, true);
+        driver.key_event(KeyCode::X as u16, true);
+        driver.update(FRAME, &mut controller);
+        assert_eq!(controller.state.trigger, 255);
+        assert!(controller.state.buttons.contains(MoveButtons::TRIGGER));
+        assert!(controller.state.buttons.contains(MoveButtons::MOVE));
+        assert!(controller.state.buttons.contains(MoveButtons::CROSS));
+
+        driver.release_all();
+        driver.update(FRAME, &mut controller);
+        assert_eq!(controller.state.trigger, 0);
+        assert_eq!(controller.state.buttons, MoveButtons::empty());
+    }
+
+    #[test]
+    fn test_mouse_calibration() {
+        let (mut driver, mut controller) = setup(MoveSource::Mouse);
+        driver.pointer_moved(0.5, 0.5);
+        controller.calibrate();
+        for _ in 0..30 {
+            driver.update(FRAME, &mut controller);
+        }
+        assert_eq!(controller.calibration_status(), CalibrationStatus::Calibrated);
+    }
+
+    #[test]
+    fn test_ds4_drives_orientation() {
+        let (mut driver, mut controller) = setup(MoveSource::Ds4);
+        let mut pad = PadState::new();
+        pad.press(PadButtons::R2, 0.8);
+        // Yawing left at 10°/s
+        let sixaxis = SixaxisData {
+            accel_z: 113,
+            gyro_z: 10,
+            ..SixaxisData::at_rest()
+        };
+        driver.set_ds4_input(pad, sixaxis);
+        for _ in 0..50 {
+            driver.update(FRAME, &mut controller);
+        }
+        assert_eq!(controller.state.trigger, 204);
+        let position = controller.state.position;
+        // One second at 10°/s puts the sphere left of center
+        let expected = -1500.0 * 10f32.to_radians().tan();
+        assert!((position.x - expected).abs() < 5.0, "{}", position.x);
+        assert_eq!(controller.state.tracking, TrackingQuality::Good);
+    }
+
+    #[test]
+    fn test_ds4_pointer_mode() {
+        let (mut driver, mut controller) = setup(MoveSource::Ds4Pointer);
+        // Starts in the middle without calibrating
+        driver.set_ds4_input(PadState::new(), SixaxisData::at_rest());
+        driver.update(FRAME, &mut controller);
+        assert_eq!(controller.state.tracking, TrackingQuality::Good);
+        assert!(controller.state.position.x.abs() < 1e-3);
+
+        // Yawing right at 10°/s for a second moves the pointer right
+        let sixaxis = SixaxisData {
+            gyro_z: -10,
+            ..SixaxisData::at_rest()
+        };
+        driver.set_ds4_input(PadState::new(), sixaxis);
+        for _ in 0..50 {
+            driver.update(FRAME, &mut controller);
+        }
+        assert!(controller.state.position.x > 0.0);
+        assert_eq!(controller.state.tracking, TrackingQuality::Good);
+
+        let mut pad = PadState::new();
+        pad.press(PadButtons::R3, 1.0);
+        driver.set_ds4_input(pad, sixaxis);
+        driver.update(FRAME, &mut controller);
+        assert!(controller.state.position.x.abs() < 1e-3);
+    }
+}
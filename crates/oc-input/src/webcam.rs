@@ -0,0 +1,322 @@
+//! Host webcam capture for the emulated PlayStation Eye
+//!
+//! A capture thread reads frames from the host camera, decodes them to
+//! RGB24 and keeps only the most recent one. The emulated camera converts
+//! that image to whatever resolution and format the game asked for.
+//!
+//! Capture uses V4L2 on Linux with the `webcam` feature; other hosts and
+//! builds without it report no devices, and the emulated camera falls
+//! back to its test pattern.
+
+use crate::camera::{yuv_to_rgb, CameraFrame};
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// Frame layout delivered by the host camera
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostPixelFormat {
+    /// Packed YUV422
+    Yuyv,
+    /// Motion JPEG
+    Mjpeg,
+    Rgb24,
+    Bgr24,
+}
+
+/// Decode one host frame to RGB24
+///
+/// `stride` is the length of a row in bytes (ignored for MJPEG). Returns
+/// None for truncated or corrupt frames.
+pub fn decode_host_frame(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    stride: usize,
+    format: HostPixelFormat,
+) -> Option<Vec<u8>> {
+    let (width, height) = (width as usize, height as usize);
+    let row_bytes = match format {
+        HostPixelFormat::Yuyv => width * 2,
+        HostPixelFormat::Rgb24 | HostPixelFormat::Bgr24 => width * 3,
+        #[cfg(feature = "jpeg")]
+        HostPixelFormat::Mjpeg => {
+            let image = image::load_from_memory_with_format(data, image::ImageFormat::Jpeg).ok()?;
+            let image = image.to_rgb8();
+            return (image.width() as usize == width && image.height() as usize == height)
+                .then(|| image.into_raw());
+        }
+        #[cfg(not(feature = "jpeg"))]
+        HostPixelFormat::Mjpeg => return None,
+    };
+    let stride = stride.max(row_bytes);
+    if height == 0 || data.len() < stride * (height - 1) + row_bytes {
+        return None;
+    }
+
+    let mut rgb = Vec::with_capacity(width * height * 3);
+    for row in data.chunks(stride).take(height) {
+        let row = &row[..row_bytes];
+        match format {
+            HostPixelFormat::Yuyv => {
+                for pair in row.chunks_exact(4) {
+                    rgb.extend_from_slice(&yuv_to_rgb(pair[0], pair[1], pair[3]));
+                    rgb.extend_from_slice(&yuv_to_rgb(pair[2], pair[1], pair[3]));
+                }
+            }
+            HostPixelFormat::Bgr24 => rgb.extend(row.chunks_exact(3).flat_map(|p| [p[2], p[1], p[0]])),
+            _ => rgb.extend_from_slice(row),
+        }
+    }
+    // Odd widths leave the last YUYV pair half used
+    rgb.truncate(width * height * 3);
+    Some(rgb)
+}
+
+/// Capture from a host webcam on a background thread
+pub struct WebcamCapture {
+    device: String,
+    latest: Arc<Mutex<Option<Arc<CameraFrame>>>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WebcamCapture {
+    /// Names of the webcams on the host
+    pub fn device_names() -> Vec<String> {
+        host::device_names()
+    }
+
+    /// Open the webcam whose name contains `device`, or the first one if empty
+    ///
+    /// `width`, `height` and `framerate` are requests; the camera picks the
+    /// nearest mode it supports.
+    pub fn open(device: &str, width: u32, height: u32, framerate: u32) -> Result<Self, String> {
+        let latest = Arc::new(Mutex::new(None));
+        let running = Arc::new(AtomicBool::new(true));
+        let (name, thread) = host::start(device, width, height, framerate, latest.clone(), running.clone())?;
+        Ok(Self {
+            device: name,
+            latest,
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// Name of the open webcam
+    pub fn device(&self) -> &str {
+        &self.device
+    }
+
+    /// Latest frame (RGB24) if it is newer than `sequence`
+    pub fn frame_after(&self, sequence: u64) -> Option<Arc<CameraFrame>> {
+        self.latest.lock().as_ref().filter(|frame| frame.sequence > sequence).cloned()
+    }
+
+    /// Whether the capture thread is still delivering frames
+    pub fn is_running(&self) -> bool {
+        self.thread.as_ref().is_some_and(|thread| !thread.is_finished())
+    }
+}
+
+impl Drop for WebcamCapture {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(all(feature = "webcam", target_os = "linux"))]
+mod host {
+    use super::*;
+    use crate::camera::CameraPixelFormat;
+    use linuxvideo::format::{PixFormat, PixelFormat};
+    use linuxvideo::{BufType, CapabilityFlags, Device, Fract};
+    use std::time::{Duration, Instant};
+
+    /// Wait between checks for a new frame, and for the stop request
+    const POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+    /// Store a decoded frame as the latest one
+    fn publish(latest: &Mutex<Option<Arc<CameraFrame>>>, rgb: Vec<u8>, width: u32, height: u32, start: Instant) {
+        let mut latest = latest.lock();
+        let sequence = latest.as_ref().map_or(0, |frame| frame.sequence) + 1;
+        *latest = Some(Arc::new(CameraFrame {
+            data: rgb,
+            width,
+            height,
+            format: CameraPixelFormat::RGB24,
+            timestamp: start.elapsed(),
+            sequence,
+        }));
+    }
+
+    /// Host formats in order of preference
+    const FORMATS: [(PixelFormat, HostPixelFormat); 4] = [
+        (PixelFormat::YUYV, HostPixelFormat::Yuyv),
+        (PixelFormat::MJPG, HostPixelFormat::Mjpeg),
+        (PixelFormat::RGB3, HostPixelFormat::Rgb24),
+        (PixelFormat::BGR3, HostPixelFormat::Bgr24),
+    ];
+
+    /// Video capture nodes with their card names
+    fn capture_devices() -> Vec<(String, Device)> {
+        let Ok(devices) = linuxvideo::list() else {
+            return Vec::new();
+        };
+        let mut devices: Vec<_> = devices
+            .flatten()
+            .filter_map(|device| {
+                let caps = device.capabilities().ok()?;
+                // Cameras also expose metadata nodes under the same name
+                caps.device_capabilities()
+                    .contains(CapabilityFlags::VIDEO_CAPTURE)
+                    .then(|| (caps.card().to_string(), device))
+            })
+            .collect();
+        devices.sort_by_key(|(_, device)| device.path().ok());
+        devices
+    }
+
+    pub(super) fn device_names() -> Vec<String> {
+        capture_devices().into_iter().map(|(name, _)| name).collect()
+    }
+
+    pub(super) fn start(
+        device: &str,
+        width: u32,
+        height: u32,
+        framerate: u32,
+        latest: Arc<Mutex<Option<Arc<CameraFrame>>>>,
+        running: Arc<AtomicBool>,
+    ) -> Result<(String, JoinHandle<()>), String> {
+        let wanted = device.trim().to_ascii_lowercase();
+        let (name, device) = capture_devices()
+            .into_iter()
+            .find(|(name, _)| name.to_ascii_lowercase().contains(&wanted))
+            .ok_or_else(|| {
+                if wanted.is_empty() {
+                    "No webcam found".to_string()
+                } else {
+                    format!("Webcam \"{}\" not found", device.trim())
+                }
+            })?;
+
+        let supported: Vec<PixelFormat> = device
+            .formats(BufType::VIDEO_CAPTURE)
+            .flatten()
+            .map(|desc| desc.pixel_format())
+            .collect();
+        let &(pixel_format, _) = FORMATS
+            .iter()
+            .find(|(format, _)| supported.contains(format))
+            .ok_or_else(|| format!("Webcam \"{}\" has no supported pixel format", name))?;
+
+        let capture = device
+            .video_capture(PixFormat::new(width, height, pixel_format))
+            .map_err(|e| format!("Failed to configure webcam \"{}\": {}", name, e))?;
+        let format = capture.format();
+        let (width, height, stride) = (format.width(), format.height(), format.bytes_per_line() as usize);
+        let Some(&(_, host_format)) = FORMATS.iter().find(|(f, _)| *f == format.pixel_format()) else {
+            return Err(format!("Webcam \"{}\" switched to an unsupported pixel format", name));
+        };
+        if framerate > 0 {
+            // Not every driver lets the rate be chosen
+            let _ = capture.set_frame_interval(Fract::new(1, framerate));
+        }
+        let mut stream = capture
+            .into_stream()
+            .map_err(|e| format!("Failed to start webcam \"{}\": {}", name, e))?;
+
+        tracing::info!(
+            "Webcam \"{}\" opened at {}x{} ({:?})",
+            name,
+            width,
+            height,
+            host_format
+        );
+        let thread_name = name.clone();
+        let thread = std::thread::Builder::new()
+            .name("webcam".to_string())
+            .spawn(move || {
+                let start = Instant::now();
+                while running.load(Ordering::Relaxed) {
+                    match stream.will_block() {
+                        Ok(true) => {
+                            std::thread::sleep(POLL_INTERVAL);
+                            continue;
+                        }
+                        Ok(false) => {}
+                        Err(e) => {
+                            tracing::warn!("Webcam \"{}\" stopped: {}", thread_name, e);
+                            break;
+                        }
+                    }
+                    let result = stream.dequeue(|buffer| {
+                        if !buffer.is_error() {
+                            if let Some(rgb) = decode_host_frame(&buffer, width, height, stride, host_format) {
+                                publish(&latest, rgb, width, height, start);
+                            }
+                        }
+                        Ok(())
+                    });
+                    if let Err(e) = result {
+                        tracing::warn!("Webcam \"{}\" stopped: {}", thread_name, e);
+                        break;
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to start webcam thread: {}", e))?;
+        Ok((name, thread))
+    }
+}
+
+#[cfg(not(all(feature = "webcam", target_os = "linux")))]
+mod host {
+    use super::*;
+
+    pub(super) fn device_names() -> Vec<String> {
+        Vec::new()
+    }
+
+    pub(super) fn start(
+        _device: &str,
+        _width: u32,
+        _height: u32,
+        _framerate: u32,
+        _latest: Arc<Mutex<Option<Arc<CameraFrame>>>>,
+        _running: Arc<AtomicBool>,
+    ) -> Result<(String, JoinHandle<()>), String> {
+        Err("Webcam capture is not supported on this platform".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::rgb_to_yuv;
+
+    #[test]
+    fn test_decode_host_frames() {
+        // 2x2 YUYV with a padded stride: white/black over red/blue
+        let [wy, wu, wv] = rgb_to_yuv([255, 255, 255]);
+        let [ry, ru, rv] = rgb_to_yuv([255, 0, 0]);
+        let yuyv = [
+            wy, wu, 16, wv, 0, 0, //
+            ry, ru, ry, rv, 0, 0,
+        ];
+        let rgb = decode_host_frame(&yuyv, 2, 2, 6, HostPixelFormat::Yuyv).unwrap();
+        assert_eq!(rgb.len(), 12);
+        assert!(rgb[..3].iter().all(|&c| c > 250));
+        assert!(rgb[6] > 240 && rgb[7] < 15 && rgb[8] < 15);
+
+        let bgr = [1, 2, 3, 4, 5, 6];
+        assert_eq!(decode_host_frame(&bgr, 2, 1, 0, HostPixelFormat::Bgr24).unwrap(), [3, 2, 1, 6, 5, 4]);
+        // Truncated frames are dropped
+        assert_eq!(decode_host_frame(&bgr[..5], 2, 1, 0, HostPixelFormat::Rgb24), None);
+        assert_eq!(decode_host_frame(&[0xFF, 0xD8], 2, 1, 0, HostPixelFormat::Mjpeg), None);
+    }
+}
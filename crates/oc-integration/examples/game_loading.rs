@@ -130,5 +130,8 @@ fn create_mock_loaded_game() -> LoadedGame {
             "libsysutil.prx".to_string(),
             "libspurs.prx".to_string(),
         ],
+        modules: Vec::new(),
+        functions: Default::default(),
+        imports: Vec::new(),
     }
 }
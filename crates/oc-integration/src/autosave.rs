@@ -0,0 +1,218 @@
+//! Crash-safe background autosaves
+//!
+//! While a game runs, the [`Autosaver`] keeps a ring of its last few
+//! savestates next to the savestate slots. States are taken at the end of a
+//! frame and written on a background thread, so the game does not stall.
+//! A marker file says a session of the title is running; a clean stop
+//! removes it, so finding it on the next boot means the last session crashed
+//! or was killed after locking up, and its newest autosave is offered to
+//! recover from.
+
+use crate::savestate::{Savestate, SavestateInfo, SAVESTATE_EXTENSION};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Name autosave files start with, followed by their index in the ring
+const AUTOSAVE_PREFIX: &str = "autosave";
+
+/// Marker file present while a session of a title runs
+const SESSION_MARKER: &str = "session.lock";
+
+/// File of autosave `index` of `title_id` in `dir`
+pub fn autosave_path(dir: &Path, title_id: &str, index: usize) -> PathBuf {
+    dir.join(title_id).join(format!("{}{}.{}", AUTOSAVE_PREFIX, index, SAVESTATE_EXTENSION))
+}
+
+fn marker_path(dir: &Path, title_id: &str) -> PathBuf {
+    dir.join(title_id).join(SESSION_MARKER)
+}
+
+/// Autosaves of `title_id` in `dir` with their headers, newest first
+pub fn list_autosaves(dir: &Path, title_id: &str) -> Vec<(PathBuf, SavestateInfo)> {
+    let Ok(entries) = std::fs::read_dir(dir.join(title_id)) else {
+        return Vec::new();
+    };
+    let mut autosaves: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+            name.starts_with(AUTOSAVE_PREFIX) && path.extension().is_some_and(|ext| ext == SAVESTATE_EXTENSION)
+        })
+        .filter_map(|path| match Savestate::load_info(&path) {
+            Ok(info) => Some((path, info)),
+            Err(e) => {
+                tracing::warn!("Ignoring autosave: {}", e);
+                None
+            }
+        })
+        .collect();
+    autosaves.sort_by_key(|(_, info)| std::cmp::Reverse((info.created, info.frame)));
+    autosaves
+}
+
+/// Autosave offered after a session that did not end cleanly
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recovery {
+    pub path: PathBuf,
+    pub info: SavestateInfo,
+}
+
+/// The newest autosave of `title_id` if its last session did not end cleanly
+///
+/// Clears the marker, so the recovery is offered once.
+pub fn take_recovery(dir: &Path, title_id: &str) -> Option<Recovery> {
+    let marker = marker_path(dir, title_id);
+    if !marker.exists() {
+        return None;
+    }
+    if let Err(e) = std::fs::remove_file(&marker) {
+        tracing::warn!("Failed to remove {}: {}", marker.display(), e);
+    }
+    let (path, info) = list_autosaves(dir, title_id).into_iter().next()?;
+    tracing::info!("The last session of {} did not end cleanly, {} can be restored", title_id, path.display());
+    Some(Recovery { path, info })
+}
+
+/// Writes a ring of autosaves for one running title
+pub struct Autosaver {
+    dir: PathBuf,
+    title_id: String,
+    interval: Duration,
+    count: usize,
+    /// Ring index the next autosave goes to
+    next: usize,
+    /// When the last autosave was taken, or the session started
+    last: Instant,
+    /// Background write of the last autosave
+    writer: Option<JoinHandle<Result<PathBuf, String>>>,
+    /// Set when the session crashed, so the marker is kept for recovery
+    crashed: AtomicBool,
+}
+
+impl Autosaver {
+    /// Start autosaving `title_id` to `dir` every `interval`, keeping `count` states
+    pub fn start(dir: &Path, title_id: &str, interval: Duration, count: usize) -> Result<Self, String> {
+        let marker = marker_path(dir, title_id);
+        if let Some(parent) = marker.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        std::fs::write(&marker, std::process::id().to_string())
+            .map_err(|e| format!("Failed to write {}: {}", marker.display(), e))?;
+
+        let count = count.max(1);
+        // Continue the ring where the last session left it: at an empty or the oldest one
+        let next = (0..count)
+            .min_by_key(|&index| {
+                Savestate::load_info(&autosave_path(dir, title_id, index)).map_or((0, 0), |info| (info.created, info.frame))
+            })
+            .unwrap_or(0);
+        tracing::info!("Autosaving {} every {}s, keeping {}", title_id, interval.as_secs(), count);
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            title_id: title_id.to_string(),
+            interval,
+            count,
+            next,
+            last: Instant::now(),
+            writer: None,
+            crashed: AtomicBool::new(false),
+        })
+    }
+
+    /// Whether an autosave should be taken now
+    ///
+    /// Never while the previous one is still being written.
+    pub fn is_due(&self) -> bool {
+        let writing = match &self.writer {
+            Some(writer) => !writer.is_finished(),
+            None => false,
+        };
+        !writing && self.last.elapsed() >= self.interval
+    }
+
+    /// Write `state` to the next autosave of the ring in the background
+    pub fn save(&mut self, state: Savestate) {
+        self.join_writer();
+        let path = autosave_path(&self.dir, &self.title_id, self.next);
+        self.next = (self.next + 1) % self.count;
+        self.last = Instant::now();
+        let spawned = std::thread::Builder::new()
+            .name("autosave".to_string())
+            .spawn(move || state.save(&path).map(|()| path));
+        match spawned {
+            Ok(writer) => self.writer = Some(writer),
+            Err(e) => tracing::error!("Failed to start the autosave thread: {}", e),
+        }
+    }
+
+    /// Restart the interval without saving, e.g. after a savestate was loaded
+    pub fn postpone(&mut self) {
+        self.last = Instant::now();
+    }
+
+    /// Keep the session marker when the session ends, so the next boot offers recovery
+    pub fn mark_crashed(&self) {
+        self.crashed.store(true, Ordering::Relaxed);
+    }
+
+    /// Wait for the last write and end the session
+    pub fn finish(mut self) {
+        self.join_writer();
+        if self.crashed.load(Ordering::Relaxed) {
+            return;
+        }
+        let marker = marker_path(&self.dir, &self.title_id);
+        if let Err(e) = std::fs::remove_file(&marker) {
+            tracing::warn!("Failed to remove {}: {}", marker.display(), e);
+        }
+    }
+
+    fn join_writer(&mut self) {
+        let Some(writer) = self.writer.take() else {
+            return;
+        };
+        match writer.join() {
+            Ok(Ok(path)) => tracing::debug!("Autosaved to {}", path.display()),
+            Ok(Err(e)) => tracing::error!("Autosave failed: {}", e),
+            Err(_) => tracing::error!("The autosave thread panicked"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_autosave_ring() {
+        let dir = std::env::temp_dir().join(format!("oc_autosave_test_{}", std::process::id()));
+        let save = |autosaver: &mut Autosaver, frame| {
+            autosaver.save(Savestate::new(SavestateInfo::new(Some("NPUB00001"), frame, 0)));
+        };
+
+        let mut autosaver = Autosaver::start(&dir, "NPUB00001", Duration::ZERO, 2).unwrap();
+        assert!(autosaver.is_due());
+        for frame in [60, 120, 180] {
+            save(&mut autosaver, frame);
+        }
+        autosaver.finish();
+        let autosaves = list_autosaves(&dir, "NPUB00001");
+        let frames: Vec<_> = autosaves.iter().map(|(_, info)| info.frame).collect();
+        assert_eq!(frames, [180, 120]);
+        // A clean stop offers nothing
+        assert!(take_recovery(&dir, "NPUB00001").is_none());
+
+        let mut autosaver = Autosaver::start(&dir, "NPUB00001", Duration::ZERO, 2).unwrap();
+        save(&mut autosaver, 240);
+        autosaver.mark_crashed();
+        autosaver.finish();
+        let recovery = take_recovery(&dir, "NPUB00001").unwrap();
+        assert_eq!(recovery.info.frame, 240);
+        // The oldest one was replaced
+        assert_eq!(recovery.path, autosave_path(&dir, "NPUB00001", 1));
+        assert!(take_recovery(&dir, "NPUB00001").is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
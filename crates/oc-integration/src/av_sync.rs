@@ -0,0 +1,372 @@
+//! Audio/video synchronization
+//!
+//! The [`AvSyncGovernor`] keeps the cellAudio block clock and the emulated
+//! vblank clock on one timeline and paces frames against the wall clock.
+//! Audio blocks are generated to follow video time, so a stalled frame
+//! cannot let the sound run ahead of the picture. After a long stall the
+//! schedule is rebased instead of fast-forwarding to catch up. Pacing
+//! follows the emulation speed, and an unpaced governor never waits.
+//! A frame that starts after its vblank deadline can be skipped so a slow
+//! game catches up instead of falling further behind.
+
+use std::time::{Duration, Instant};
+
+/// NTSC vblank rate (59.94 Hz)
+pub const VBLANK_HZ_NTSC: f64 = 60000.0 / 1001.0;
+/// PAL vblank rate (50 Hz)
+pub const VBLANK_HZ_PAL: f64 = 50.0;
+/// Samples per channel in one cellAudio block
+pub const AUDIO_BLOCK_SAMPLES: u32 = 256;
+/// cellAudio output sample rate
+pub const AUDIO_SAMPLE_RATE: u32 = 48000;
+
+/// A/V sync configuration
+#[derive(Debug, Clone, Copy)]
+pub struct AvSyncConfig {
+    /// Vblank rate in Hz
+    pub vblank_hz: f64,
+    /// How far audio is generated ahead of video time
+    pub audio_lead: Duration,
+    /// How far behind schedule a clock may fall before it is resynced
+    pub max_lag: Duration,
+    /// Most audio blocks generated per frame while catching up
+    pub max_blocks_per_frame: u32,
+}
+
+impl Default for AvSyncConfig {
+    fn default() -> Self {
+        Self {
+            vblank_hz: VBLANK_HZ_NTSC,
+            audio_lead: Duration::from_millis(10),
+            max_lag: Duration::from_millis(100),
+            max_blocks_per_frame: 8,
+        }
+    }
+}
+
+/// A/V sync counters
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AvSyncStats {
+    /// Vblanks since the last reset
+    pub vblanks: u64,
+    /// Audio blocks generated since the last reset
+    pub audio_blocks: u64,
+    /// Audio clock minus video clock in milliseconds
+    pub drift_ms: f64,
+    /// Frames that finished more than `max_lag` late
+    pub late_frames: u64,
+    /// Times a clock was resynced after a stall
+    pub resyncs: u64,
+    /// Frames skipped to catch up
+    pub skipped_frames: u64,
+}
+
+/// Ties the audio block clock, vblank and frame pacing together
+pub struct AvSyncGovernor {
+    config: AvSyncConfig,
+    /// Wall-clock start of the current pacing schedule
+    epoch: Option<Instant>,
+    /// Vblanks since `epoch`
+    scheduled: u64,
+    /// Vblanks since the last reset
+    vblanks: u64,
+    /// Audio blocks since the last reset
+    audio_blocks: u64,
+    late_frames: u64,
+    resyncs: u64,
+    skipped_frames: u64,
+    /// Consecutive frames skipped so far
+    skip_run: u32,
+    /// Speed relative to real time, None when unpaced
+    speed: Option<f64>,
+}
+
+impl AvSyncGovernor {
+    /// Create a new governor
+    pub fn new(config: AvSyncConfig) -> Self {
+        Self {
+            config,
+            epoch: None,
+            scheduled: 0,
+            vblanks: 0,
+            audio_blocks: 0,
+            late_frames: 0,
+            resyncs: 0,
+            skipped_frames: 0,
+            skip_run: 0,
+            speed: Some(1.0),
+        }
+    }
+
+    /// Get configuration
+    pub fn config(&self) -> &AvSyncConfig {
+        &self.config
+    }
+
+    /// Duration of one vblank period
+    pub fn frame_period(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.config.vblank_hz)
+    }
+
+    /// Change the vblank rate, e.g. when switching between NTSC and PAL
+    ///
+    /// The vblank count is rescaled so video time, and with it A/V sync, is kept.
+    pub fn set_vblank_hz(&mut self, vblank_hz: f64) {
+        if vblank_hz <= 0.0 || vblank_hz == self.config.vblank_hz {
+            return;
+        }
+        self.vblanks = (self.video_time() * vblank_hz).round() as u64;
+        self.config.vblank_hz = vblank_hz;
+        self.reset_pacing();
+    }
+
+    /// Speed frames are paced to relative to real time, None when unpaced
+    pub fn speed(&self) -> Option<f64> {
+        self.speed
+    }
+
+    /// Pace frames to `speed`, rebasing the schedule when it changes
+    pub fn set_speed(&mut self, speed: Option<f64>) {
+        let speed = speed.filter(|speed| *speed > 0.0);
+        if self.speed != speed {
+            self.speed = speed;
+            self.reset_pacing();
+        }
+    }
+
+    /// Duration of one audio block in seconds
+    fn block_secs() -> f64 {
+        AUDIO_BLOCK_SAMPLES as f64 / AUDIO_SAMPLE_RATE as f64
+    }
+
+    /// Emulated video time in seconds
+    pub fn video_time(&self) -> f64 {
+        self.vblanks as f64 / self.config.vblank_hz
+    }
+
+    /// Emulated audio time in seconds
+    pub fn audio_time(&self) -> f64 {
+        self.audio_blocks as f64 * Self::block_secs()
+    }
+
+    /// Audio clock minus video clock in seconds
+    pub fn drift(&self) -> f64 {
+        self.audio_time() - self.video_time()
+    }
+
+    /// Number of audio blocks to generate now
+    ///
+    /// Keeps the audio clock `audio_lead` ahead of video time. If audio has
+    /// fallen more than `max_lag` behind (the mixer stalled), the missed
+    /// blocks are skipped rather than generated in a burst.
+    pub fn audio_blocks_due(&mut self) -> u32 {
+        let target = self.video_time() + self.config.audio_lead.as_secs_f64();
+        let missing = target - self.audio_time();
+        if missing <= 0.0 {
+            return 0;
+        }
+        if missing > self.config.max_lag.as_secs_f64() {
+            let skipped = (missing / Self::block_secs()).floor() as u64;
+            self.audio_blocks += skipped;
+            self.resyncs += 1;
+            tracing::debug!("A/V sync: audio fell behind, skipped {} blocks", skipped);
+        }
+        let missing = target - self.audio_time();
+        ((missing / Self::block_secs()).ceil().max(0.0) as u32).min(self.config.max_blocks_per_frame)
+    }
+
+    /// Record that an audio block was generated
+    pub fn on_audio_block(&mut self) {
+        self.audio_blocks += 1;
+    }
+
+    /// Record a vblank at `now` and return how long to wait for it
+    ///
+    /// Vblanks are scheduled against a fixed epoch so timing errors do not
+    /// accumulate. A frame later than `max_lag` rebases the schedule.
+    pub fn on_vblank(&mut self, now: Instant) -> Duration {
+        self.vblanks += 1;
+        let Some(speed) = self.speed else {
+            return Duration::ZERO;
+        };
+        let Some(epoch) = self.epoch else {
+            self.epoch = Some(now);
+            self.scheduled = 0;
+            return Duration::ZERO;
+        };
+
+        self.scheduled += 1;
+        let deadline = epoch + self.frame_period().mul_f64(self.scheduled as f64 / speed);
+        if now > deadline + self.config.max_lag {
+            self.late_frames += 1;
+            self.resyncs += 1;
+            self.epoch = Some(now);
+            self.scheduled = 0;
+            tracing::debug!(
+                "A/V sync: frame {:?} late, rebasing schedule",
+                now.duration_since(deadline)
+            );
+            return Duration::ZERO;
+        }
+        deadline.saturating_duration_since(now)
+    }
+
+    /// Check if the frame starting at `now` should be skipped
+    ///
+    /// A frame is skipped when it starts after the deadline of the vblank it
+    /// ends on, at most `max_skip` times in a row so the picture still
+    /// updates. Unpaced emulation never skips.
+    pub fn should_skip_frame(&mut self, now: Instant, max_skip: u32) -> bool {
+        let behind = match (self.speed, self.epoch) {
+            (Some(speed), Some(epoch)) => {
+                now > epoch + self.frame_period().mul_f64((self.scheduled + 1) as f64 / speed)
+            }
+            _ => false,
+        };
+        if behind && self.skip_run < max_skip {
+            self.skip_run += 1;
+            self.skipped_frames += 1;
+            return true;
+        }
+        self.skip_run = 0;
+        false
+    }
+
+    /// Restart pacing (after pause, resume or load)
+    ///
+    /// The clocks keep their values so audio and video stay aligned.
+    pub fn reset_pacing(&mut self) {
+        self.epoch = None;
+        self.scheduled = 0;
+    }
+
+    /// Reset clocks and counters
+    pub fn reset(&mut self) {
+        let speed = self.speed;
+        *self = Self::new(self.config);
+        self.speed = speed;
+    }
+
+    /// Current counters
+    pub fn stats(&self) -> AvSyncStats {
+        AvSyncStats {
+            vblanks: self.vblanks,
+            audio_blocks: self.audio_blocks,
+            drift_ms: self.drift() * 1000.0,
+            late_frames: self.late_frames,
+            resyncs: self.resyncs,
+            skipped_frames: self.skipped_frames,
+        }
+    }
+}
+
+impl Default for AvSyncGovernor {
+    fn default() -> Self {
+        Self::new(AvSyncConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audio_follows_video() {
+        let mut sync = AvSyncGovernor::default();
+        let start = Instant::now();
+        for i in 0..600u32 {
+            sync.on_vblank(start + sync.frame_period() * i);
+            for _ in 0..sync.audio_blocks_due() {
+                sync.on_audio_block();
+            }
+        }
+        // Audio stays within a block plus the lead of video after 10 seconds
+        let drift = sync.drift();
+        assert!(drift >= 0.0);
+        assert!(drift < 0.010 + 256.0 / 48000.0);
+        assert_eq!(sync.stats().resyncs, 0);
+    }
+
+    #[test]
+    fn test_audio_stall_resyncs() {
+        let mut sync = AvSyncGovernor::default();
+        let start = Instant::now();
+        // Video runs for a second without any audio being produced
+        for i in 0..60u32 {
+            sync.on_vblank(start + sync.frame_period() * i);
+        }
+        let due = sync.audio_blocks_due();
+        assert!(due <= sync.config().max_blocks_per_frame);
+        assert_eq!(sync.stats().resyncs, 1);
+        assert!(sync.drift() > -0.01);
+    }
+
+    #[test]
+    fn test_vblank_pacing() {
+        let mut sync = AvSyncGovernor::default();
+        let start = Instant::now();
+        assert_eq!(sync.on_vblank(start), Duration::ZERO);
+
+        // A fast frame waits out the rest of the period
+        let wait = sync.on_vblank(start + Duration::from_millis(5));
+        let expected = sync.frame_period() - Duration::from_millis(5);
+        assert!(wait.abs_diff(expected) < Duration::from_micros(10));
+
+        // A long stall rebases instead of running frames back-to-back
+        let wait = sync.on_vblank(start + Duration::from_secs(1));
+        assert_eq!(wait, Duration::ZERO);
+        assert_eq!(sync.stats().late_frames, 1);
+        let wait = sync.on_vblank(start + Duration::from_secs(1));
+        assert!(wait.abs_diff(sync.frame_period()) < Duration::from_micros(10));
+    }
+
+    #[test]
+    fn test_speed_pacing() {
+        let mut sync = AvSyncGovernor::default();
+        let start = Instant::now();
+        sync.set_speed(Some(0.5));
+        sync.on_vblank(start);
+        // Half speed takes twice the frame period
+        let wait = sync.on_vblank(start);
+        assert!(wait.abs_diff(sync.frame_period() * 2) < Duration::from_micros(10));
+
+        sync.set_speed(None);
+        assert_eq!(sync.on_vblank(start), Duration::ZERO);
+        assert_eq!(sync.on_vblank(start), Duration::ZERO);
+        assert_eq!(sync.stats().vblanks, 4);
+    }
+
+    #[test]
+    fn test_pal_rate() {
+        let mut sync = AvSyncGovernor::default();
+        let start = Instant::now();
+        for i in 0..60u32 {
+            sync.on_vblank(start + sync.frame_period() * i);
+        }
+        let video_time = sync.video_time();
+        sync.set_vblank_hz(VBLANK_HZ_PAL);
+        assert_eq!(sync.frame_period(), Duration::from_millis(20));
+        assert!((sync.video_time() - video_time).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_frame_skip() {
+        let mut sync = AvSyncGovernor::default();
+        let start = Instant::now();
+        assert!(!sync.should_skip_frame(start, 2));
+        sync.on_vblank(start);
+        // On schedule
+        assert!(!sync.should_skip_frame(start + Duration::from_millis(5), 2));
+
+        // Behind the next deadline, skipped at most twice in a row
+        let late = start + Duration::from_millis(50);
+        assert!(sync.should_skip_frame(late, 2));
+        assert!(sync.should_skip_frame(late, 2));
+        assert!(!sync.should_skip_frame(late, 2));
+        assert!(!sync.should_skip_frame(late, 0));
+        assert_eq!(sync.stats().skipped_frames, 2);
+
+        sync.set_speed(None);
+        assert!(!sync.should_skip_frame(late, 2));
+    }
+}
@@ -0,0 +1,198 @@
+//! Per-game caches on disk
+//!
+//! Each cache keeps a folder per title ID under its directory from the path
+//! settings: translated shaders, executables decrypted at boot, and dumped
+//! textures. The settings list their sizes and clear them; a cleared cache
+//! is rebuilt the next time the game runs.
+
+use oc_core::config::PathConfig;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Kind of per-game cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CacheKind {
+    Shaders,
+    DecryptedSelf,
+    TextureDumps,
+}
+
+impl CacheKind {
+    pub const ALL: [CacheKind; 3] = [CacheKind::Shaders, CacheKind::DecryptedSelf, CacheKind::TextureDumps];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CacheKind::Shaders => "Shader Cache",
+            CacheKind::DecryptedSelf => "Decrypted SELF Cache",
+            CacheKind::TextureDumps => "Texture Dumps",
+        }
+    }
+
+    /// Directory holding the folder of every title
+    pub fn dir(self, paths: &PathConfig) -> &Path {
+        match self {
+            CacheKind::Shaders => &paths.shader_cache,
+            CacheKind::DecryptedSelf => &paths.self_cache,
+            CacheKind::TextureDumps => &paths.texture_dumps,
+        }
+    }
+
+    /// Folder of `title_id`'s cache
+    pub fn title_dir(self, paths: &PathConfig, title_id: &str) -> PathBuf {
+        self.dir(paths).join(title_id)
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Disk space the caches of one title use
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TitleCaches {
+    pub title_id: String,
+    /// Bytes per kind, indexed like [`CacheKind::ALL`]
+    pub sizes: [u64; 3],
+}
+
+impl TitleCaches {
+    /// Bytes used by one kind of cache
+    pub fn size(&self, kind: CacheKind) -> u64 {
+        self.sizes[kind.index()]
+    }
+
+    /// Bytes used by every cache of the title
+    pub fn total(&self) -> u64 {
+        self.sizes.iter().sum()
+    }
+}
+
+/// Disk usage of every cache directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheUsage {
+    /// Titles with any cache, by title ID
+    pub titles: Vec<TitleCaches>,
+    /// Bytes per kind across the whole directory, including files of no title
+    pub totals: [u64; 3],
+}
+
+impl CacheUsage {
+    /// Bytes used by one kind of cache
+    pub fn total(&self, kind: CacheKind) -> u64 {
+        self.totals[kind.index()]
+    }
+
+    /// Bytes used by every cache
+    pub fn grand_total(&self) -> u64 {
+        self.totals.iter().sum()
+    }
+}
+
+/// Size of a file, or of everything below a directory
+fn disk_size(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.filter_map(Result::ok).map(|entry| disk_size(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+/// Measure every cache directory
+pub fn scan(paths: &PathConfig) -> CacheUsage {
+    let mut titles: BTreeMap<String, TitleCaches> = BTreeMap::new();
+    let mut totals = [0; 3];
+    for kind in CacheKind::ALL {
+        let Ok(entries) = std::fs::read_dir(kind.dir(paths)) else {
+            continue;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let size = disk_size(&entry.path());
+            totals[kind.index()] += size;
+            if size == 0 || !entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                continue;
+            }
+            let title_id = entry.file_name().to_string_lossy().into_owned();
+            let title = titles.entry(title_id.clone()).or_insert_with(|| TitleCaches {
+                title_id,
+                ..Default::default()
+            });
+            title.sizes[kind.index()] += size;
+        }
+    }
+    CacheUsage {
+        titles: titles.into_values().collect(),
+        totals,
+    }
+}
+
+/// Delete one cache of `title_id`, returning the bytes freed
+pub fn clear(paths: &PathConfig, kind: CacheKind, title_id: &str) -> Result<u64, String> {
+    if title_id.is_empty() || title_id.contains(['/', '\\']) || title_id == ".." {
+        return Err(format!("Invalid title ID \"{}\"", title_id));
+    }
+    let dir = kind.title_dir(paths, title_id);
+    let size = disk_size(&dir);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).map_err(|e| format!("Failed to clear {}: {}", dir.display(), e))?;
+    }
+    tracing::info!("Cleared the {} of {} ({} bytes)", kind.name().to_lowercase(), title_id, size);
+    Ok(size)
+}
+
+/// Cache file of the decrypted executable of a SELF, named after its contents
+///
+/// A game update replaces the SELF and so misses the old entry.
+pub fn decrypted_self_path(dir: &Path, self_path: &Path, self_data: &[u8]) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    self_data.hash(&mut hasher);
+    let stem = self_path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    dir.join(format!("{}_{:016x}.elf", stem, hasher.finish()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_and_clear() {
+        let base = std::env::temp_dir().join(format!("oc_caches_test_{}", std::process::id()));
+        let paths = PathConfig {
+            shader_cache: base.join("shaders"),
+            self_cache: base.join("self"),
+            texture_dumps: base.join("textures"),
+            ..Default::default()
+        };
+        let write = |kind: CacheKind, title: &str, name: &str, len: usize| {
+            let dir = kind.title_dir(&paths, title);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join(name), vec![0u8; len]).unwrap();
+        };
+        write(CacheKind::Shaders, "BLUS00001", "a.spv", 100);
+        write(CacheKind::Shaders, "BLUS00001", "b.spv", 50);
+        write(CacheKind::DecryptedSelf, "BLUS00001", "EBOOT.elf", 1000);
+        write(CacheKind::TextureDumps, "NPUB00002", "tex.dds", 10);
+        // Loose files count towards the totals only
+        std::fs::write(base.join("shaders/shared.spv"), [0u8; 5]).unwrap();
+
+        let usage = scan(&paths);
+        assert_eq!(usage.titles.len(), 2);
+        assert_eq!(usage.titles[0].title_id, "BLUS00001");
+        assert_eq!(usage.titles[0].size(CacheKind::Shaders), 150);
+        assert_eq!(usage.titles[0].total(), 1150);
+        assert_eq!(usage.total(CacheKind::Shaders), 155);
+        assert_eq!(usage.grand_total(), 1165);
+
+        assert_eq!(clear(&paths, CacheKind::Shaders, "BLUS00001"), Ok(150));
+        assert!(clear(&paths, CacheKind::Shaders, "..").is_err());
+        let usage = scan(&paths);
+        assert_eq!(usage.titles[0].size(CacheKind::Shaders), 0);
+        assert_eq!(usage.titles[0].size(CacheKind::DecryptedSelf), 1000);
+        let _ = std::fs::remove_dir_all(&base);
+    }
+}
@@ -0,0 +1,693 @@
+//! Screenshots and video recordings of the game output
+//!
+//! Screenshots are PNG files. Videos in Motion JPEG AVI are written
+//! directly; other codecs and containers are encoded by piping raw frames
+//! to an `ffmpeg` executable on the PATH. Audio is taken from a WAV dump of
+//! the final mix made during the recording and muxed in when it ends.
+
+use crate::compat::civil_from_days;
+use oc_core::config::{CaptureConfig, VideoCodec, VideoContainer};
+use oc_rsx::FramebufferData;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Frames waiting for the encoder before the emulator blocks
+const FRAME_QUEUE: usize = 4;
+
+/// JPEG quality of Motion JPEG frames
+const MJPEG_QUALITY: u8 = 90;
+
+/// AVI 1.0 files cannot reach 4 GiB; leave room for the audio and index
+const MAX_AVI_VIDEO_BYTES: u64 = 3_800_000_000;
+
+/// Timestamp for capture file names, as YYYYMMDD-HHMMSS in UTC
+pub fn timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let rem = secs % 86400;
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60
+    )
+}
+
+/// File name without extension of a capture of `title_id` taken now
+pub fn capture_name(title_id: Option<&str>) -> String {
+    format!("{}-{}", title_id.unwrap_or("capture"), timestamp())
+}
+
+/// File extension of a container
+pub fn extension(container: VideoContainer) -> &'static str {
+    match container {
+        VideoContainer::Avi => "avi",
+        VideoContainer::Mp4 => "mp4",
+        VideoContainer::Mkv => "mkv",
+        VideoContainer::WebM => "webm",
+    }
+}
+
+/// Whether recording `codec` into `container` is written without ffmpeg
+pub fn is_builtin(container: VideoContainer, codec: VideoCodec) -> bool {
+    container == VideoContainer::Avi && codec == VideoCodec::Mjpeg
+}
+
+/// Reject combinations the container cannot hold
+pub fn check_format(container: VideoContainer, codec: VideoCodec) -> Result<(), String> {
+    match (container, codec) {
+        (VideoContainer::WebM, VideoCodec::Vp9) => Ok(()),
+        (VideoContainer::WebM, _) => Err("WebM only holds VP9 video".to_string()),
+        (VideoContainer::Mp4, VideoCodec::Ffv1) => Err("MP4 cannot hold FFV1 video".to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Whether an ffmpeg executable can be run
+pub fn ffmpeg_available() -> bool {
+    Command::new("ffmpeg")
+        .arg("-version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Save `frame` as a PNG at `path`, ignoring its alpha
+pub fn save_screenshot(frame: &FramebufferData, path: &Path) -> Result<(), String> {
+    let rgb = rgb_pixels(frame.width, frame.height, &frame.pixels)
+        .ok_or_else(|| format!("A {}x{} frame has no pixels", frame.width, frame.height))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    image::save_buffer(path, &rgb, frame.width, frame.height, image::ExtendedColorType::Rgb8)
+        .map_err(|e| format!("Failed to save {}: {}", path.display(), e))
+}
+
+/// RGB pixels of an RGBA frame
+fn rgb_pixels(width: u32, height: u32, rgba: &[u8]) -> Option<Vec<u8>> {
+    let len = (width * height * 4) as usize;
+    if width == 0 || height == 0 || rgba.len() < len {
+        return None;
+    }
+    Some(rgba[..len].chunks_exact(4).flat_map(|pixel| [pixel[0], pixel[1], pixel[2]]).collect())
+}
+
+/// `frame` scaled to `width` x `height` with nearest sampling, as RGBA
+fn fit_frame(frame: &FramebufferData, width: u32, height: u32) -> Vec<u8> {
+    let len = (width * height * 4) as usize;
+    if frame.width == width && frame.height == height && frame.pixels.len() >= len {
+        return frame.pixels[..len].to_vec();
+    }
+    if frame.width == 0 || frame.height == 0 || frame.pixels.len() < (frame.width * frame.height * 4) as usize {
+        return vec![0; len];
+    }
+    let mut pixels = Vec::with_capacity(len);
+    for y in 0..height {
+        let src_y = y * frame.height / height;
+        for x in 0..width {
+            let src = ((src_y * frame.width + x * frame.width / width) * 4) as usize;
+            pixels.extend_from_slice(&frame.pixels[src..src + 4]);
+        }
+    }
+    pixels
+}
+
+/// WAV file to mux into a video
+#[derive(Debug, Clone)]
+pub struct AudioMux {
+    pub wav: PathBuf,
+    /// Remove the WAV once it is muxed
+    pub delete: bool,
+}
+
+/// Where an encoder writes frames
+enum Encoder {
+    /// JPEG frames appended to a temporary file, assembled into an AVI at the end
+    Mjpeg {
+        frames: BufWriter<File>,
+        temp: PathBuf,
+        sizes: Vec<u32>,
+        bytes: u64,
+    },
+    /// Raw frames piped to ffmpeg writing a video without audio to `temp`
+    Ffmpeg {
+        child: Child,
+        stdin: BufWriter<ChildStdin>,
+        temp: PathBuf,
+    },
+}
+
+/// Encoder state handed from the frame thread to the finishing thread
+struct Encoded {
+    encoder: Encoder,
+    /// Frames dropped because the AVI limit was reached
+    truncated: bool,
+}
+
+/// A video being recorded from presented frames
+pub struct VideoRecorder {
+    sender: Option<SyncSender<FramebufferData>>,
+    writer: Option<JoinHandle<Result<Encoded, String>>>,
+    output: PathBuf,
+    container: VideoContainer,
+    width: u32,
+    height: u32,
+    fps: f64,
+    started: Instant,
+}
+
+impl VideoRecorder {
+    /// Start recording `width` x `height` frames at `fps` to `base` plus the container's extension
+    pub fn start(base: &Path, config: &CaptureConfig, fps: f64, width: u32, height: u32) -> Result<Self, String> {
+        check_format(config.container, config.codec)?;
+        if width == 0 || height == 0 {
+            return Err("There is no frame to record".to_string());
+        }
+        // Encoders want even dimensions
+        let (width, height) = (width & !1, height & !1);
+        let output = base.with_extension(extension(config.container));
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+
+        let encoder = if is_builtin(config.container, config.codec) {
+            let temp = output.with_extension("frames.tmp");
+            let file = File::create(&temp).map_err(|e| format!("Failed to create {}: {}", temp.display(), e))?;
+            Encoder::Mjpeg {
+                frames: BufWriter::new(file),
+                temp,
+                sizes: Vec::new(),
+                bytes: 0,
+            }
+        } else {
+            let temp = output.with_extension(format!("video.{}", extension(config.container)));
+            let mut child = Command::new("ffmpeg")
+                .args(ffmpeg_encode_args(config.codec, width, height, fps, &temp))
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+            let stdin = child.stdin.take().ok_or("ffmpeg has no input")?;
+            Encoder::Ffmpeg {
+                child,
+                stdin: BufWriter::new(stdin),
+                temp,
+            }
+        };
+
+        let (sender, receiver) = mpsc::sync_channel(FRAME_QUEUE);
+        let writer = std::thread::Builder::new()
+            .name("video-encoder".to_string())
+            .spawn(move || encode_frames(encoder, receiver, width, height))
+            .map_err(|e| format!("Failed to start the encoder thread: {}", e))?;
+        tracing::info!("Recording {}x{} video at {:.2} fps to {}", width, height, fps, output.display());
+        Ok(Self {
+            sender: Some(sender),
+            writer: Some(writer),
+            output,
+            container: config.container,
+            width,
+            height,
+            fps,
+            started: Instant::now(),
+        })
+    }
+
+    /// File the video is written to
+    pub fn output(&self) -> &Path {
+        &self.output
+    }
+
+    /// Time since the recording started
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Queue a presented frame, blocking while the encoder is behind
+    pub fn push_frame(&mut self, frame: FramebufferData) {
+        let failed = self.sender.as_ref().is_some_and(|sender| sender.send(frame).is_err());
+        if failed {
+            // The encoder stopped; its error is reported by `finish`
+            self.sender = None;
+        }
+    }
+
+    /// End the recording, muxing in `audio`, and return the thread finishing the file
+    ///
+    /// The thread logs the outcome, so it can be left to run.
+    pub fn finish(mut self, audio: Option<AudioMux>) -> JoinHandle<Result<PathBuf, String>> {
+        self.sender = None;
+        let writer = self.writer.take();
+        std::thread::spawn(move || {
+            let result = writer
+                .ok_or_else(|| "The encoder was not started".to_string())
+                .and_then(|writer| writer.join().unwrap_or_else(|_| Err("The encoder thread panicked".to_string())))
+                .and_then(|encoded| self.finalize(encoded, audio.as_ref()));
+            match &result {
+                Ok(path) => tracing::info!("Video saved to {}", path.display()),
+                Err(e) => tracing::error!("Video recording failed: {}", e),
+            }
+            if let Some(audio) = audio.filter(|audio| audio.delete) {
+                fs::remove_file(&audio.wav).ok();
+            }
+            result
+        })
+    }
+
+    fn finalize(&self, encoded: Encoded, audio: Option<&AudioMux>) -> Result<PathBuf, String> {
+        if encoded.truncated {
+            tracing::warn!("The AVI size limit was reached; later frames were not recorded");
+        }
+        match encoded.encoder {
+            Encoder::Mjpeg { frames, temp, sizes, .. } => {
+                drop(frames);
+                let wav = match audio {
+                    Some(audio) => Some(read_wav(&audio.wav)?),
+                    None => None,
+                };
+                let written = write_avi(&self.output, &temp, &sizes, self.width, self.height, self.fps, wav.as_ref())
+                    .map_err(|e| format!("Failed to write {}: {}", self.output.display(), e));
+                fs::remove_file(&temp).ok();
+                written?;
+            }
+            Encoder::Ffmpeg { mut child, stdin, temp } => {
+                // Closing the pipe ends the encode
+                drop(stdin);
+                wait_ffmpeg(&mut child)?;
+                match audio {
+                    Some(audio) => {
+                        let muxed = Command::new("ffmpeg")
+                            .args(ffmpeg_mux_args(self.container, &temp, &audio.wav, &self.output))
+                            .stdout(Stdio::null())
+                            .stderr(Stdio::piped())
+                            .spawn()
+                            .map_err(|e| format!("Failed to run ffmpeg: {}", e))
+                            .and_then(|mut child| wait_ffmpeg(&mut child));
+                        fs::remove_file(&temp).ok();
+                        muxed?;
+                    }
+                    None => fs::rename(&temp, &self.output).map_err(|e| e.to_string())?,
+                }
+            }
+        }
+        Ok(self.output.clone())
+    }
+}
+
+/// Encode frames until the recording ends
+fn encode_frames(mut encoder: Encoder, frames: Receiver<FramebufferData>, width: u32, height: u32) -> Result<Encoded, String> {
+    let mut truncated = false;
+    for frame in frames {
+        let rgba = fit_frame(&frame, width, height);
+        match &mut encoder {
+            Encoder::Mjpeg { frames, sizes, bytes, .. } => {
+                if truncated {
+                    continue;
+                }
+                let rgb = rgb_pixels(width, height, &rgba).unwrap_or_default();
+                let mut jpeg = Vec::new();
+                image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, MJPEG_QUALITY)
+                    .encode(&rgb, width, height, image::ExtendedColorType::Rgb8)
+                    .map_err(|e| format!("Failed to encode a frame: {}", e))?;
+                if *bytes + jpeg.len() as u64 > MAX_AVI_VIDEO_BYTES {
+                    truncated = true;
+                    continue;
+                }
+                frames.write_all(&jpeg).map_err(|e| format!("Failed to write a frame: {}", e))?;
+                sizes.push(jpeg.len() as u32);
+                *bytes += jpeg.len() as u64;
+            }
+            Encoder::Ffmpeg { stdin, .. } => {
+                stdin.write_all(&rgba).map_err(|e| format!("ffmpeg stopped taking frames: {}", e))?;
+            }
+        }
+    }
+    Ok(Encoded { encoder, truncated })
+}
+
+/// Wait for ffmpeg, turning a failure into its error output
+fn wait_ffmpeg(child: &mut Child) -> Result<(), String> {
+    let mut errors = String::new();
+    if let Some(stderr) = child.stderr.as_mut() {
+        stderr.read_to_string(&mut errors).ok();
+    }
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("ffmpeg failed: {}", errors.trim()))
+    }
+}
+
+/// Arguments encoding raw RGBA frames from stdin to `output`
+fn ffmpeg_encode_args(codec: VideoCodec, width: u32, height: u32, fps: f64, output: &Path) -> Vec<String> {
+    let mut args: Vec<String> = ["-y", "-loglevel", "error", "-f", "rawvideo", "-pix_fmt", "rgba"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    args.extend(["-s".to_string(), format!("{}x{}", width, height)]);
+    args.extend(["-framerate".to_string(), format!("{:.3}", fps), "-i".to_string(), "-".to_string()]);
+    let codec_args: &[&str] = match codec {
+        VideoCodec::Mjpeg => &["-c:v", "mjpeg", "-q:v", "3", "-pix_fmt", "yuvj420p"],
+        VideoCodec::H264 => &["-c:v", "libx264", "-preset", "veryfast", "-crf", "20", "-pix_fmt", "yuv420p"],
+        VideoCodec::H265 => &["-c:v", "libx265", "-preset", "fast", "-crf", "24", "-pix_fmt", "yuv420p"],
+        VideoCodec::Vp9 => &["-c:v", "libvpx-vp9", "-b:v", "0", "-crf", "32", "-deadline", "realtime", "-row-mt", "1"],
+        VideoCodec::Ffv1 => &["-c:v", "ffv1", "-level", "3"],
+    };
+    args.extend(codec_args.iter().map(|s| s.to_string()));
+    args.push(output.display().to_string());
+    args
+}
+
+/// Arguments muxing `video` with `wav` into `output`
+fn ffmpeg_mux_args(container: VideoContainer, video: &Path, wav: &Path, output: &Path) -> Vec<String> {
+    let audio_codec = match container {
+        VideoContainer::Avi => "pcm_s16le",
+        VideoContainer::Mp4 => "aac",
+        VideoContainer::Mkv => "flac",
+        VideoContainer::WebM => "libopus",
+    };
+    let mut args: Vec<String> = ["-y", "-loglevel", "error", "-i"].iter().map(|s| s.to_string()).collect();
+    args.push(video.display().to_string());
+    args.push("-i".to_string());
+    args.push(wav.display().to_string());
+    args.extend(
+        ["-map", "0:v:0", "-map", "1:a:0", "-c:v", "copy", "-c:a", audio_codec, "-shortest"]
+            .iter()
+            .map(|s| s.to_string()),
+    );
+    args.push(output.display().to_string());
+    args
+}
+
+/// Format and samples of a WAV file
+struct Wav {
+    /// Body of the fmt chunk, a WAVEFORMATEX
+    format: Vec<u8>,
+    data: Vec<u8>,
+    byte_rate: u32,
+    block_align: u16,
+}
+
+fn read_wav(path: &Path) -> Result<Wav, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_wav(&bytes).ok_or_else(|| format!("{} is not a WAV file", path.display()))
+}
+
+fn parse_wav(bytes: &[u8]) -> Option<Wav> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return None;
+    }
+    let mut format = None;
+    let mut data = None;
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let size = u32::from_le_bytes(bytes[pos + 4..pos + 8].try_into().ok()?) as usize;
+        let body = &bytes[pos + 8..(pos + 8 + size).min(bytes.len())];
+        match id {
+            b"fmt " => format = Some(body.to_vec()),
+            b"data" => data = Some(body.to_vec()),
+            _ => {}
+        }
+        pos += 8 + size + (size & 1);
+    }
+    let format = format.filter(|format| format.len() >= 16)?;
+    let byte_rate = u32::from_le_bytes(format[8..12].try_into().ok()?);
+    let block_align = u16::from_le_bytes(format[12..14].try_into().ok()?).max(1);
+    Some(Wav {
+        format,
+        data: data?,
+        byte_rate,
+        block_align,
+    })
+}
+
+/// Write a chunk header, returning where its size goes
+fn begin_chunk(out: &mut (impl Write + Seek), id: &[u8; 4]) -> io::Result<u64> {
+    out.write_all(id)?;
+    let size_pos = out.stream_position()?;
+    out.write_all(&0u32.to_le_bytes())?;
+    Ok(size_pos)
+}
+
+/// Fill in the size of a chunk started at `size_pos`, padding it to an even length
+fn end_chunk(out: &mut (impl Write + Seek), size_pos: u64) -> io::Result<()> {
+    let end = out.stream_position()?;
+    let size = end - size_pos - 4;
+    if size & 1 == 1 {
+        out.write_all(&[0])?;
+    }
+    out.seek(SeekFrom::Start(size_pos))?;
+    out.write_all(&(size as u32).to_le_bytes())?;
+    out.seek(SeekFrom::End(0))?;
+    Ok(())
+}
+
+fn write_u32s(out: &mut impl Write, values: &[u32]) -> io::Result<()> {
+    values.iter().try_for_each(|value| out.write_all(&value.to_le_bytes()))
+}
+
+/// Assemble a Motion JPEG AVI from the frames in `frames_path` and optional audio
+fn write_avi(
+    path: &Path,
+    frames_path: &Path,
+    sizes: &[u32],
+    width: u32,
+    height: u32,
+    fps: f64,
+    audio: Option<&Wav>,
+) -> io::Result<()> {
+    const AVIF_HASINDEX: u32 = 0x10;
+    const AVIF_ISINTERLEAVED: u32 = 0x100;
+    const AVIIF_KEYFRAME: u32 = 0x10;
+
+    let mut out = BufWriter::new(File::create(path)?);
+    let mut frames = BufReader::new(File::open(frames_path)?);
+    let frame_count = sizes.len() as u32;
+    let max_frame = sizes.iter().copied().max().unwrap_or(0);
+    let streams = if audio.is_some() { 2 } else { 1 };
+    let fps_rate = (fps * 1000.0).round() as u32;
+
+    let riff = begin_chunk(&mut out, b"RIFF")?;
+    out.write_all(b"AVI ")?;
+    let hdrl = begin_chunk(&mut out, b"LIST")?;
+    out.write_all(b"hdrl")?;
+
+    let avih = begin_chunk(&mut out, b"avih")?;
+    write_u32s(
+        &mut out,
+        &[
+            (1_000_000.0 / fps).round() as u32,
+            (max_frame as f64 * fps) as u32 + audio.map_or(0, |audio| audio.byte_rate),
+            0,
+            AVIF_HASINDEX | AVIF_ISINTERLEAVED,
+            frame_count,
+            0,
+            streams,
+            max_frame,
+            width,
+            height,
+            0,
+            0,
+            0,
+            0,
+        ],
+    )?;
+    end_chunk(&mut out, avih)?;
+
+    // Video stream
+    let strl = begin_chunk(&mut out, b"LIST")?;
+    out.write_all(b"strl")?;
+    let strh = begin_chunk(&mut out, b"strh")?;
+    out.write_all(b"vidsMJPG")?;
+    write_u32s(&mut out, &[0, 0, 0, 1000, fps_rate, 0, frame_count, max_frame, u32::MAX, 0])?;
+    out.write_all(&[0, 0, 0, 0])?;
+    out.write_all(&(width as u16).to_le_bytes())?;
+    out.write_all(&(height as u16).to_le_bytes())?;
+    end_chunk(&mut out, strh)?;
+    let strf = begin_chunk(&mut out, b"strf")?;
+    write_u32s(&mut out, &[40, width, height])?;
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&24u16.to_le_bytes())?;
+    out.write_all(b"MJPG")?;
+    write_u32s(&mut out, &[width * height * 3, 0, 0, 0, 0])?;
+    end_chunk(&mut out, strf)?;
+    end_chunk(&mut out, strl)?;
+
+    // Audio stream
+    if let Some(audio) = audio {
+        let block_align = audio.block_align as u32;
+        let strl = begin_chunk(&mut out, b"LIST")?;
+        out.write_all(b"strl")?;
+        let strh = begin_chunk(&mut out, b"strh")?;
+        out.write_all(b"auds")?;
+        write_u32s(
+            &mut out,
+            &[
+                0,
+                0,
+                0,
+                0,
+                block_align,
+                audio.byte_rate,
+                0,
+                audio.data.len() as u32 / block_align,
+                audio.byte_rate,
+                u32::MAX,
+                block_align,
+                0,
+                0,
+            ],
+        )?;
+        end_chunk(&mut out, strh)?;
+        let strf = begin_chunk(&mut out, b"strf")?;
+        out.write_all(&audio.format)?;
+        if audio.format.len() == 16 {
+            // cbSize of a plain PCM WAVEFORMATEX
+            out.write_all(&0u16.to_le_bytes())?;
+        }
+        end_chunk(&mut out, strf)?;
+        end_chunk(&mut out, strl)?;
+    }
+    end_chunk(&mut out, hdrl)?;
+
+    // Frames, each followed by the audio played during it
+    let movi = begin_chunk(&mut out, b"LIST")?;
+    out.write_all(b"movi")?;
+    // Index offsets count from the "movi" type
+    let movi_start = movi + 4;
+    let mut index: Vec<(&[u8; 4], u32, u32, u32)> = Vec::new();
+    let mut audio_pos = 0usize;
+    let mut frame = Vec::new();
+    for (number, &size) in sizes.iter().enumerate() {
+        frame.resize(size as usize, 0);
+        frames.read_exact(&mut frame)?;
+        let offset = (out.stream_position()? - movi_start) as u32;
+        let chunk = begin_chunk(&mut out, b"00dc")?;
+        out.write_all(&frame)?;
+        end_chunk(&mut out, chunk)?;
+        index.push((b"00dc", AVIIF_KEYFRAME, offset, size));
+
+        if let Some(audio) = audio {
+            let end = if number + 1 == sizes.len() {
+                audio.data.len()
+            } else {
+                let block = audio.block_align as usize;
+                let bytes = ((number + 1) as f64 / fps * audio.byte_rate as f64) as usize;
+                (bytes / block * block).min(audio.data.len())
+            };
+            if end > audio_pos {
+                let offset = (out.stream_position()? - movi_start) as u32;
+                let chunk = begin_chunk(&mut out, b"01wb")?;
+                out.write_all(&audio.data[audio_pos..end])?;
+                end_chunk(&mut out, chunk)?;
+                index.push((b"01wb", 0, offset, (end - audio_pos) as u32));
+                audio_pos = end;
+            }
+        }
+    }
+    end_chunk(&mut out, movi)?;
+
+    let idx1 = begin_chunk(&mut out, b"idx1")?;
+    for (id, flags, offset, size) in index {
+        out.write_all(id)?;
+        write_u32s(&mut out, &[flags, offset, size])?;
+    }
+    end_chunk(&mut out, idx1)?;
+    end_chunk(&mut out, riff)?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(width: u32, height: u32, value: u8) -> FramebufferData {
+        FramebufferData {
+            width,
+            height,
+            pixels: vec![value; (width * height * 4) as usize],
+        }
+    }
+
+    #[test]
+    fn test_formats() {
+        assert!(is_builtin(VideoContainer::Avi, VideoCodec::Mjpeg));
+        assert!(!is_builtin(VideoContainer::Mkv, VideoCodec::Mjpeg));
+        assert!(check_format(VideoContainer::WebM, VideoCodec::Vp9).is_ok());
+        assert!(check_format(VideoContainer::WebM, VideoCodec::H264).is_err());
+        assert!(check_format(VideoContainer::Mp4, VideoCodec::Ffv1).is_err());
+        assert_eq!(timestamp().len(), 15);
+    }
+
+    #[test]
+    fn test_fit_frame() {
+        let mut source = frame(4, 2, 0);
+        source.pixels[16..20].copy_from_slice(&[1, 2, 3, 4]);
+        let scaled = fit_frame(&source, 2, 1);
+        assert_eq!(scaled.len(), 8);
+        assert_eq!(fit_frame(&source, 4, 2), source.pixels);
+        // A frame without pixels comes out black
+        assert_eq!(fit_frame(&frame(0, 0, 0), 2, 2), vec![0; 16]);
+    }
+
+    #[test]
+    fn test_mjpeg_avi_with_audio() {
+        let dir = std::env::temp_dir().join(format!("oc_capture_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        // One second of 16-bit stereo silence at 8 kHz
+        let wav_path = dir.join("mix.wav");
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF\0\0\0\0WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&[1, 0, 2, 0]);
+        wav.extend_from_slice(&8000u32.to_le_bytes());
+        wav.extend_from_slice(&32000u32.to_le_bytes());
+        wav.extend_from_slice(&[4, 0, 16, 0]);
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&32000u32.to_le_bytes());
+        wav.resize(wav.len() + 32000, 0);
+        fs::write(&wav_path, &wav).unwrap();
+
+        let config = CaptureConfig::default();
+        let mut recorder = VideoRecorder::start(&dir.join("clip"), &config, 2.0, 17, 8).unwrap();
+        recorder.push_frame(frame(16, 8, 200));
+        recorder.push_frame(frame(32, 16, 100));
+        let output = recorder.output().to_path_buf();
+        let path = recorder
+            .finish(Some(AudioMux { wav: wav_path.clone(), delete: true }))
+            .join()
+            .unwrap()
+            .unwrap();
+        assert_eq!(path, output);
+        assert!(!wav_path.exists());
+
+        let avi = fs::read(&path).unwrap();
+        assert_eq!(&avi[0..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(avi[4..8].try_into().unwrap()) as usize, avi.len() - 8);
+        assert_eq!(&avi[8..12], b"AVI ");
+        // Two frames, each with half a second of audio
+        let idx1 = avi.windows(4).rposition(|w| w == b"idx1").unwrap();
+        let entries = u32::from_le_bytes(avi[idx1 + 4..idx1 + 8].try_into().unwrap()) / 16;
+        assert_eq!(entries, 4);
+        let audio_chunk = avi.windows(4).position(|w| w == b"01wb").unwrap();
+        assert_eq!(u32::from_le_bytes(avi[audio_chunk + 4..audio_chunk + 8].try_into().unwrap()), 16000);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,315 @@
+//! Game compatibility database
+//!
+//! Statuses are kept in two JSON files mapping title IDs to entries:
+//! `compat.json` holds the database downloaded from a URL and
+//! `compat_local.json` the results reported on this machine, which take
+//! precedence. Both files use the same format, and downloads may also be in
+//! the `{"results": {...}}` layout of RPCS3's compatibility API.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// File of the downloaded database
+const REMOTE_FILE: &str = "compat.json";
+
+/// File of the results reported locally
+const LOCAL_FILE: &str = "compat_local.json";
+
+/// How far a game gets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CompatStatus {
+    /// Does not boot or shows nothing
+    Nothing,
+    /// Shows intros or menus but cannot be played
+    Intro,
+    /// Reaches gameplay with major issues
+    Ingame,
+    /// Can be played through
+    Playable,
+}
+
+impl CompatStatus {
+    /// Every status, worst first
+    pub const ALL: [CompatStatus; 4] = [Self::Nothing, Self::Intro, Self::Ingame, Self::Playable];
+
+    /// Display name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Nothing => "Nothing",
+            Self::Intro => "Intro",
+            Self::Ingame => "Ingame",
+            Self::Playable => "Playable",
+        }
+    }
+
+    /// What the status means
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Nothing => "Does not boot or shows nothing",
+            Self::Intro => "Shows intros or menus but gameplay is not reached",
+            Self::Ingame => "Reaches gameplay with major issues",
+            Self::Playable => "Can be played through with at most minor issues",
+        }
+    }
+
+    /// Parse a status name, ignoring case
+    ///
+    /// RPCS3's "Loadable" shows nothing and counts as [`CompatStatus::Nothing`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "nothing" | "loadable" => Some(Self::Nothing),
+            "intro" => Some(Self::Intro),
+            "ingame" => Some(Self::Ingame),
+            "playable" => Some(Self::Playable),
+            _ => None,
+        }
+    }
+}
+
+/// Compatibility of one title
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompatEntry {
+    pub status: CompatStatus,
+    /// Issues or workarounds
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    /// Day of the test, as YYYY-MM-DD
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub date: String,
+    /// Emulator version tested
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub version: String,
+}
+
+impl CompatEntry {
+    /// Entry for a test run today with this build
+    pub fn report(status: CompatStatus, notes: &str) -> Self {
+        Self {
+            status,
+            notes: notes.to_string(),
+            date: today(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+/// Title ID to compatibility mapping
+pub struct CompatDatabase {
+    /// Directory of the database files
+    dir: PathBuf,
+    /// Downloaded statuses
+    remote: BTreeMap<String, CompatEntry>,
+    /// Statuses reported on this machine
+    local: BTreeMap<String, CompatEntry>,
+}
+
+impl CompatDatabase {
+    /// Create an empty database stored in `dir`
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            remote: BTreeMap::new(),
+            local: BTreeMap::new(),
+        }
+    }
+
+    /// Load the database files in `dir`, treating missing or broken files as empty
+    pub fn load(dir: &Path) -> Self {
+        let mut db = Self::new(dir);
+        db.remote = Self::load_file(&dir.join(REMOTE_FILE));
+        db.local = Self::load_file(&dir.join(LOCAL_FILE));
+        db
+    }
+
+    fn load_file(path: &Path) -> BTreeMap<String, CompatEntry> {
+        let Ok(json) = std::fs::read_to_string(path) else {
+            return BTreeMap::new();
+        };
+        Self::parse(&json).unwrap_or_else(|e| {
+            tracing::warn!("Failed to read compatibility database {}: {}", path.display(), e);
+            BTreeMap::new()
+        })
+    }
+
+    /// Parse a database, skipping entries with an unknown status
+    pub fn parse(json: &str) -> Result<BTreeMap<String, CompatEntry>, String> {
+        let value: serde_json::Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let entries = value
+            .get("results")
+            .unwrap_or(&value)
+            .as_object()
+            .ok_or("Expected an object of title IDs")?;
+
+        let text = |entry: &serde_json::Value, key: &str| {
+            entry.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string()
+        };
+        Ok(entries
+            .iter()
+            .filter_map(|(id, entry)| {
+                let status = CompatStatus::from_name(entry.get("status")?.as_str()?)?;
+                let version = match text(entry, "version") {
+                    version if version.is_empty() => text(entry, "commit"),
+                    version => version,
+                };
+                Some((
+                    id.to_ascii_uppercase(),
+                    CompatEntry {
+                        status,
+                        notes: text(entry, "notes"),
+                        date: text(entry, "date"),
+                        version,
+                    },
+                ))
+            })
+            .collect())
+    }
+
+    /// Status of a title, preferring the local result
+    pub fn get(&self, title_id: &str) -> Option<&CompatEntry> {
+        self.local.get(title_id).or_else(|| self.remote.get(title_id))
+    }
+
+    /// Result reported locally for a title
+    pub fn local(&self, title_id: &str) -> Option<&CompatEntry> {
+        self.local.get(title_id)
+    }
+
+    /// Number of titles in the downloaded database
+    pub fn remote_len(&self) -> usize {
+        self.remote.len()
+    }
+
+    /// Record a local result and save it
+    pub fn report(&mut self, title_id: &str, entry: CompatEntry) -> Result<(), String> {
+        self.local.insert(title_id.to_ascii_uppercase(), entry);
+        self.save_local()
+    }
+
+    /// Drop the local result of a title and save the rest
+    pub fn remove_report(&mut self, title_id: &str) -> Result<(), String> {
+        if self.local.remove(title_id).is_some() {
+            self.save_local()?;
+        }
+        Ok(())
+    }
+
+    /// JSON of the local results, in the database format
+    pub fn export_local(&self) -> String {
+        serde_json::to_string_pretty(&self.local).unwrap_or_default()
+    }
+
+    fn save_local(&self) -> Result<(), String> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        std::fs::write(self.dir.join(LOCAL_FILE), self.export_local()).map_err(|e| e.to_string())
+    }
+
+    /// Replace the downloaded database with `json`, returning the number of titles
+    pub fn update_remote(&mut self, json: &str) -> Result<usize, String> {
+        let remote = Self::parse(json)?;
+        std::fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        let saved = serde_json::to_string_pretty(&remote).map_err(|e| e.to_string())?;
+        std::fs::write(self.dir.join(REMOTE_FILE), saved).map_err(|e| e.to_string())?;
+        self.remote = remote;
+        Ok(self.remote.len())
+    }
+
+    /// Read a database from a URL or a local file
+    ///
+    /// There is no HTTP client in the build, so http(s) URLs are downloaded
+    /// with the system's `curl`. This blocks; call it off the UI thread.
+    pub fn fetch(url: &str) -> Result<String, String> {
+        let url = url.trim();
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            let path = url.strip_prefix("file://").unwrap_or(url);
+            return std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e));
+        }
+
+        let output = std::process::Command::new("curl")
+            .args(["--fail", "--silent", "--show-error", "--location", "--max-time", "60", url])
+            .output()
+            .map_err(|e| format!("Failed to run curl: {}", e))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        String::from_utf8(output.stdout).map_err(|e| e.to_string())
+    }
+}
+
+/// Today's UTC date as YYYY-MM-DD
+fn today() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Calendar date of a day count since 1970-01-01
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_formats() {
+        let ours = r#"{"BLUS30443": {"status": "Playable", "notes": "Minor audio pops"}}"#;
+        let entries = CompatDatabase::parse(ours).unwrap();
+        assert_eq!(entries["BLUS30443"].status, CompatStatus::Playable);
+        assert_eq!(entries["BLUS30443"].notes, "Minor audio pops");
+
+        let rpcs3 = r#"{"return_code": 0, "results": {
+            "bles00001": {"title": "A", "status": "Ingame", "date": "2023-05-01", "commit": "abc123"},
+            "BLES00002": {"status": "Loadable"},
+            "BLES00003": {"status": "Unknown"}
+        }}"#;
+        let entries = CompatDatabase::parse(rpcs3).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries["BLES00001"].status, CompatStatus::Ingame);
+        assert_eq!(entries["BLES00001"].date, "2023-05-01");
+        assert_eq!(entries["BLES00001"].version, "abc123");
+        assert_eq!(entries["BLES00002"].status, CompatStatus::Nothing);
+
+        assert!(CompatDatabase::parse("[]").is_err());
+    }
+
+    #[test]
+    fn test_local_results_override() {
+        let dir = std::env::temp_dir().join(format!("oc_compat_test_{}", std::process::id()));
+        let mut db = CompatDatabase::new(&dir);
+        db.update_remote(r#"{"BLUS30443": {"status": "Intro"}}"#).unwrap();
+        assert_eq!(db.get("BLUS30443").unwrap().status, CompatStatus::Intro);
+
+        db.report("blus30443", CompatEntry::report(CompatStatus::Playable, "")).unwrap();
+        assert_eq!(db.get("BLUS30443").unwrap().status, CompatStatus::Playable);
+
+        // Both files survive a reload
+        let mut db = CompatDatabase::load(&dir);
+        assert_eq!(db.remote_len(), 1);
+        assert_eq!(db.local("BLUS30443").unwrap().date.len(), 10);
+        db.remove_report("BLUS30443").unwrap();
+        assert_eq!(CompatDatabase::load(&dir).get("BLUS30443").unwrap().status, CompatStatus::Intro);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_723), (2024, 1, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+    }
+}
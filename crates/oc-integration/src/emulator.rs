@@ -0,0 +1,256 @@
+//! Emulation lifecycle
+//!
+//! [`Emulator`] owns the [`EmulatorRunner`] and with it every subsystem.
+//! Each boot gets a fresh runner, so nothing of the previous game survives,
+//! and stopping shuts the runner down before dropping it. Frontends drive
+//! the runner's frames themselves and use the runner handle for everything
+//! else.
+
+use crate::autosave::{self, Recovery};
+use crate::loader::LoadedGame;
+use crate::missing_features::MissingFeatureReport;
+use crate::runner::{EmulatorRunner, RunnerState};
+use oc_core::config::ReplayMode;
+use oc_core::{Config, ConfigBus, EmulatorError, Result};
+use oc_input::InputProfile;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Game running in an [`Emulator`]
+#[derive(Debug, Clone)]
+pub struct BootedGame {
+    /// Path the game was booted from
+    pub path: PathBuf,
+    pub title_id: Option<String>,
+    pub game: LoadedGame,
+    /// Autosave to offer when the game's last session did not end cleanly
+    pub recovery: Option<Recovery>,
+}
+
+/// Owner of the emulated system and its lifecycle
+pub struct Emulator {
+    /// Settings the next runner is created with
+    config: Config,
+    /// Tells the runner and anyone else watching about settings changes
+    bus: ConfigBus,
+    runner: Option<Rc<RefCell<EmulatorRunner>>>,
+    booted: Option<BootedGame>,
+    /// Number of runners created, so holders of the old one can tell it was replaced
+    generation: u64,
+    /// Missing features of the last game stopped, until taken
+    missing_features: Option<MissingFeatureReport>,
+}
+
+impl Emulator {
+    /// Create an emulator with no runner yet
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            bus: ConfigBus::new(),
+            runner: None,
+            booted: None,
+            generation: 0,
+            missing_features: None,
+        }
+    }
+
+    /// Settings the next runner is created with
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Replace the settings, applying at once what the runner can change live
+    ///
+    /// Everything else takes effect with the next boot.
+    pub fn set_config(&mut self, config: Config) {
+        self.bus.publish(&self.config, &config);
+        self.config = config;
+        if let Some(runner) = &self.runner {
+            runner.borrow_mut().apply_config_changes();
+        }
+    }
+
+    /// Bus settings changes are published on, to watch them
+    pub fn config_bus(&self) -> &ConfigBus {
+        &self.bus
+    }
+
+    /// The runner, if one was created
+    pub fn runner(&self) -> Option<&Rc<RefCell<EmulatorRunner>>> {
+        self.runner.as_ref()
+    }
+
+    /// Number of runners created so far
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The game booted, until it is stopped
+    pub fn booted(&self) -> Option<&BootedGame> {
+        self.booted.as_ref()
+    }
+
+    /// State of the runner, stopped without one
+    pub fn state(&self) -> RunnerState {
+        self.runner.as_ref().map_or(RunnerState::Stopped, |runner| runner.borrow().state())
+    }
+
+    /// Create an idle runner with audio, input and debugger set up if there is none
+    pub fn ensure_runner(&mut self) -> Result<&Rc<RefCell<EmulatorRunner>>> {
+        if self.runner.is_none() {
+            let mut runner = EmulatorRunner::new(self.config.clone())?;
+            runner.init_audio();
+            runner.init_input();
+            runner.configure_debugger(&self.config.debug);
+            runner.watch_config(self.bus.watch(&EmulatorRunner::LIVE_CONFIG_SECTIONS));
+            self.runner = Some(Rc::new(RefCell::new(runner)));
+            self.generation += 1;
+        }
+        Ok(self.runner.as_ref().expect("runner was just created"))
+    }
+
+    /// Boot the game at `path` on a fresh runner and start it
+    ///
+    /// A game already running is stopped first. Replay recording or playback
+    /// starts with the game when the debug settings ask for it, and so do
+    /// autosaves.
+    pub fn boot(&mut self, path: &Path, title_id: Option<&str>) -> Result<&BootedGame> {
+        self.stop()?;
+        let runner = Rc::clone(self.ensure_runner()?);
+
+        // The title's caches are used while loading
+        runner.borrow_mut().set_title_id(title_id);
+        let game = runner.borrow().load_game(path)?;
+        {
+            let mut runner = runner.borrow_mut();
+            let profile = InputProfile::for_title(&self.config.input, title_id);
+            runner.set_input_profile(profile);
+        }
+
+        let debug = &self.config.debug;
+        let replay = match debug.replay_mode {
+            ReplayMode::Off => Ok(()),
+            ReplayMode::Record => runner.borrow().start_recording(&debug.replay_path),
+            ReplayMode::Replay => runner.borrow().start_replay(&debug.replay_path),
+        };
+        if let Err(e) = replay {
+            tracing::error!("{}", e);
+        }
+
+        // Look for a crashed session before this one marks itself running
+        let savestates = &self.config.paths.savestates;
+        let recovery = title_id.and_then(|title_id| autosave::take_recovery(savestates, title_id));
+        {
+            let mut runner = runner.borrow_mut();
+            runner.start()?;
+            runner.start_autosave(savestates);
+        }
+        tracing::info!("Booted {}", path.display());
+        Ok(self.booted.insert(BootedGame {
+            path: path.to_path_buf(),
+            title_id: title_id.map(str::to_string),
+            game,
+            recovery,
+        }))
+    }
+
+    /// Pause the running game
+    pub fn pause(&mut self) -> Result<()> {
+        match &self.runner {
+            Some(runner) => runner.borrow_mut().pause(),
+            None => Ok(()),
+        }
+    }
+
+    /// Resume the paused game
+    pub fn resume(&mut self) -> Result<()> {
+        match &self.runner {
+            Some(runner) => runner.borrow_mut().resume(),
+            None => Ok(()),
+        }
+    }
+
+    /// Stop the game and shut the runner down
+    ///
+    /// Panels still holding the runner or its subsystems keep them alive,
+    /// but the emulator never runs them again. The missing features of a
+    /// booted game are written beside the log and kept for
+    /// [`Emulator::take_missing_features`].
+    pub fn stop(&mut self) -> Result<()> {
+        let Some(runner) = self.runner.take() else {
+            return Ok(());
+        };
+        if let Some(booted) = self.booted.take() {
+            let runner = runner.borrow();
+            let mut report = runner.missing_features(booted.title_id.as_deref().unwrap_or("UNKNOWN"));
+            if !report.is_empty() {
+                match runner.write_missing_features(&report) {
+                    Ok(path) => report.path = Some(path),
+                    Err(e) => tracing::warn!("Failed to write the missing feature report: {}", e),
+                }
+            }
+            self.missing_features = Some(report);
+        }
+        let result = runner.borrow_mut().shutdown();
+        result
+    }
+
+    /// Take the missing feature report of the last game stopped
+    pub fn take_missing_features(&mut self) -> Option<MissingFeatureReport> {
+        self.missing_features.take()
+    }
+
+    /// Stop the game and boot it again with the current settings
+    pub fn restart(&mut self) -> Result<&BootedGame> {
+        let booted = self
+            .booted
+            .clone()
+            .ok_or_else(|| EmulatorError::GameNotFound("no game was booted to restart".to_string()))?;
+        self.boot(&booted.path, booted.title_id.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lifecycle_without_game() {
+        let mut emulator = Emulator::new(Config::default());
+        assert_eq!(emulator.state(), RunnerState::Stopped);
+        assert!(emulator.pause().is_ok());
+        assert!(emulator.restart().is_err());
+
+        emulator.ensure_runner().unwrap();
+        assert_eq!(emulator.generation(), 1);
+        emulator.ensure_runner().unwrap();
+        assert_eq!(emulator.generation(), 1);
+
+        // A game that cannot be loaded leaves nothing booted
+        assert!(emulator.boot(Path::new("/nonexistent/EBOOT.BIN"), Some("BLUS00001")).is_err());
+        assert!(emulator.booted().is_none());
+        assert_eq!(emulator.generation(), 2);
+
+        emulator.stop().unwrap();
+        assert!(emulator.runner().is_none());
+        assert_eq!(emulator.state(), RunnerState::Stopped);
+    }
+    #[test]
+    fn test_live_config() {
+        let mut emulator = Emulator::new(Config::default());
+        let runner = Rc::clone(emulator.ensure_runner().unwrap());
+        let watcher = emulator.config_bus().watch(&["debug"]);
+
+        let mut config = emulator.config().clone();
+        config.gpu.frame_limit = 30;
+        config.gpu.resolution_scale = 200;
+        emulator.set_config(config);
+        let runner = runner.borrow();
+        assert_eq!(runner.speed(), Some(0.5));
+        assert_eq!(runner.rsx_thread().read().render_scale().percentage, 200.0);
+        assert_eq!(runner.config().gpu.frame_limit, 30);
+        // Only watchers of a changed section are told
+        assert!(watcher.take().is_none());
+    }
+}
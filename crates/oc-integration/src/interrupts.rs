@@ -0,0 +1,200 @@
+//! PPU threads interrupt handlers run on
+//!
+//! Interrupt handlers run on PPU threads of their own, so the scheduler
+//! preempts lower priority guest code for them: the handlers of LV2
+//! interrupt threads the game established, and the GCM vblank and flip
+//! handlers libgcm calls from its interrupt thread. Callbacks HLE libraries
+//! owe the game, such as sysutil event callbacks, run the same way on a
+//! thread of their own. Each handler owner gets one PPU thread, created on
+//! its first interrupt. Calls for an owner whose handler is still running
+//! wait and run after it ends.
+
+use oc_core::savestate::{invalid, StateReader, StateWriter};
+use oc_lv2::objects::ObjectId;
+use std::collections::VecDeque;
+use std::io;
+
+/// What a handler thread runs handlers for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterruptOwner {
+    /// LV2 interrupt thread, with its handle and LV2 thread ID
+    Lv2 { handle: ObjectId, thread_id: u64 },
+    /// GCM vblank and flip handlers
+    Gcm,
+    /// Callbacks queued by HLE libraries
+    Hle,
+}
+
+/// Handler to call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HandlerCall {
+    /// Function descriptor (OPD) address
+    pub opd: u32,
+    /// Arguments, passed in r3 to r6
+    pub args: [u64; 4],
+}
+
+/// PPU thread of one handler owner
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterruptSlot {
+    pub owner: InterruptOwner,
+    pub ppu_thread: u32,
+    /// Whether a handler is running, until it ends with sys_interrupt_thread_eoi
+    pub busy: bool,
+    queued: VecDeque<HandlerCall>,
+}
+
+/// Handler threads of the running game
+#[derive(Debug, Default)]
+pub struct InterruptThreads {
+    slots: Vec<InterruptSlot>,
+    /// Code handlers return to, ending them with sys_interrupt_thread_eoi
+    return_stub: Option<u32>,
+}
+
+impl InterruptThreads {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Code handlers return to, once allocated
+    pub fn return_stub(&self) -> Option<u32> {
+        self.return_stub
+    }
+
+    pub fn set_return_stub(&mut self, addr: u32) {
+        self.return_stub = Some(addr);
+    }
+
+    /// Slot of `owner`, if its thread was created
+    pub fn find(&self, owner: InterruptOwner) -> Option<&InterruptSlot> {
+        self.slots.iter().find(|slot| slot.owner == owner)
+    }
+
+    /// Slot whose handlers run on `ppu_thread`
+    pub fn by_thread(&self, ppu_thread: u32) -> Option<&InterruptSlot> {
+        self.slots.iter().find(|slot| slot.ppu_thread == ppu_thread)
+    }
+
+    /// Add the slot of `owner`, running on `ppu_thread`
+    pub fn add(&mut self, owner: InterruptOwner, ppu_thread: u32) {
+        self.slots.push(InterruptSlot {
+            owner,
+            ppu_thread,
+            busy: false,
+            queued: VecDeque::new(),
+        });
+    }
+
+    /// Queue a call for `owner`, whose slot must exist
+    pub fn queue(&mut self, owner: InterruptOwner, call: HandlerCall) {
+        if let Some(slot) = self.slots.iter_mut().find(|slot| slot.owner == owner) {
+            slot.queued.push_back(call);
+        }
+    }
+
+    /// Next call to start on `ppu_thread`, if its handler is not running
+    pub fn start_next(&mut self, ppu_thread: u32) -> Option<HandlerCall> {
+        let slot = self.slots.iter_mut().find(|slot| slot.ppu_thread == ppu_thread)?;
+        if slot.busy {
+            return None;
+        }
+        let call = slot.queued.pop_front()?;
+        slot.busy = true;
+        Some(call)
+    }
+
+    /// End the handler running on `ppu_thread`, returning its owner
+    pub fn finish(&mut self, ppu_thread: u32) -> Option<InterruptOwner> {
+        let slot = self.slots.iter_mut().find(|slot| slot.ppu_thread == ppu_thread && slot.busy)?;
+        slot.busy = false;
+        Some(slot.owner)
+    }
+
+    /// Forget every slot, e.g. when the threads they ran on are gone
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.return_stub = None;
+    }
+
+    /// Save the slots and the return stub for a savestate
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.option(self.return_stub, |w, addr| w.u32(addr));
+        w.count(self.slots.len());
+        for slot in &self.slots {
+            match slot.owner {
+                InterruptOwner::Lv2 { handle, thread_id } => {
+                    w.u8(0);
+                    w.u32(handle);
+                    w.u64(thread_id);
+                }
+                InterruptOwner::Gcm => w.u8(1),
+                InterruptOwner::Hle => w.u8(2),
+            }
+            w.u32(slot.ppu_thread);
+            w.bool(slot.busy);
+            w.count(slot.queued.len());
+            for call in &slot.queued {
+                w.u32(call.opd);
+                for &arg in &call.args {
+                    w.u64(arg);
+                }
+            }
+        }
+    }
+
+    /// Restore the slots written by [`InterruptThreads::save_state`]
+    pub fn load_state(r: &mut StateReader) -> io::Result<Self> {
+        let return_stub = r.option(|r| r.u32())?;
+        let slots = (0..r.count()?)
+            .map(|_| {
+                let owner = match r.u8()? {
+                    0 => InterruptOwner::Lv2 { handle: r.u32()?, thread_id: r.u64()? },
+                    1 => InterruptOwner::Gcm,
+                    2 => InterruptOwner::Hle,
+                    value => return Err(invalid(&format!("invalid interrupt owner {}", value))),
+                };
+                let ppu_thread = r.u32()?;
+                let busy = r.bool()?;
+                let queued = (0..r.count()?)
+                    .map(|_| {
+                        let opd = r.u32()?;
+                        Ok(HandlerCall { opd, args: [r.u64()?, r.u64()?, r.u64()?, r.u64()?] })
+                    })
+                    .collect::<io::Result<_>>()?;
+                Ok(InterruptSlot { owner, ppu_thread, busy, queued })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self { slots, return_stub })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handler_calls_run_one_at_a_time() {
+        let mut threads = InterruptThreads::new();
+        let vblank = HandlerCall { opd: 0x1_0000, args: [1, 0, 0, 0] };
+        threads.add(InterruptOwner::Gcm, 3);
+        threads.queue(InterruptOwner::Gcm, vblank);
+        threads.queue(InterruptOwner::Gcm, HandlerCall { opd: 0x1_0010, args: [1, 0, 0, 0] });
+        assert_eq!(threads.by_thread(3).map(|slot| slot.owner), Some(InterruptOwner::Gcm));
+
+        assert_eq!(threads.start_next(3), Some(vblank));
+        assert_eq!(threads.start_next(3), None);
+        assert_eq!(threads.finish(3), Some(InterruptOwner::Gcm));
+        assert_eq!(threads.finish(3), None);
+
+        // Queued calls and running handlers carry over through a savestate
+        threads.set_return_stub(0x2_0000);
+        let mut w = StateWriter::new();
+        threads.save_state(&mut w);
+        let data = w.into_bytes();
+        let mut restored = InterruptThreads::load_state(&mut StateReader::new(&data)).unwrap();
+        assert_eq!(restored.return_stub(), Some(0x2_0000));
+        assert_eq!(restored.start_next(3).map(|call| call.opd), Some(0x1_0010));
+        assert!(restored.start_next(3).is_none());
+    }
+}
@@ -2,15 +2,42 @@
 //!
 //! This crate integrates all subsystems into a cohesive emulator runner.
 
+pub mod autosave;
+pub mod av_sync;
+pub mod caches;
+pub mod capture;
+pub mod compat;
+pub mod emulator;
+pub mod interrupts;
 pub mod loader;
+pub mod missing_features;
+pub mod pacing;
+pub mod perf;
 pub mod pipeline;
+pub mod replay;
+pub mod rpcs3_import;
 pub mod runner;
+pub mod savestate;
+pub mod texture_pack;
+pub mod usb_devices;
 
+pub use autosave::{Autosaver, Recovery};
+pub use av_sync::{AvSyncConfig, AvSyncGovernor, AvSyncStats};
+pub use capture::{AudioMux, VideoRecorder};
+pub use compat::{CompatDatabase, CompatEntry, CompatStatus};
+pub use emulator::{BootedGame, Emulator};
 pub use loader::{GameLoader, LoadedGame};
+pub use missing_features::{MissingFeature, MissingFeatureReport};
+pub use pacing::{FrameLimiter, SpeedMode};
+pub use perf::{FrameSample, PerfMonitor, PerfStats};
 pub use pipeline::{
     GameInfo, GamePipeline, GameScanner, KernelObjectsInfo, MainThreadInfo, 
     MainThreadState, MemoryLayoutInfo, ModuleDependency, ModuleLifecycleEvent, 
     ModuleState, RegisterState, SystemModule, ThreadStackInfo, TlsLayoutInfo, 
     TlsThreadArea
 };
+pub use replay::{ReplayEvent, ReplayLog, ReplaySession};
+pub use rpcs3_import::{CopyProgress, CopyReport, ImportOptions, Rpcs3Install};
 pub use runner::{EmulatorRunner, RunnerState};
+pub use savestate::{Savestate, SavestateInfo, Thumbnail};
+pub use usb_devices::{PassthroughUsbBackend, VirtualUsbBackend};
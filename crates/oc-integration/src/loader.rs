@@ -6,8 +6,10 @@
 
 use oc_core::error::{EmulatorError, LoaderError};
 use oc_core::Result;
+use crate::caches::decrypted_self_path;
+use oc_debug::{FunctionMap, ModuleRange};
 use oc_loader::elf::{pt, sht};
-use oc_loader::{ElfLoader, PrxLoader, SelfLoader};
+use oc_loader::{parse_imports, ElfLoader, ImportedLibrary, PrxLoader, SelfLoader};
 use oc_memory::MemoryManager;
 use oc_vfs::IsoReader;
 use std::fs::File;
@@ -64,6 +66,12 @@ pub struct LoadedGame {
     pub is_self: bool,
     /// Loaded PRX modules
     pub prx_modules: Vec<String>,
+    /// Addresses the executable and each loaded PRX occupy
+    pub modules: Vec<ModuleRange>,
+    /// Functions named by the executable's symbol table
+    pub functions: FunctionMap,
+    /// Libraries the executable imports functions from
+    pub imports: Vec<ImportedLibrary>,
 }
 
 /// Game loader for loading PS3 executables
@@ -74,6 +82,8 @@ pub struct GameLoader {
     prx_loader: PrxLoader,
     /// Next available PRX base address
     next_prx_addr: u32,
+    /// Directory decrypted executables are cached in
+    self_cache: Option<PathBuf>,
 }
 
 impl GameLoader {
@@ -83,6 +93,32 @@ impl GameLoader {
             memory,
             prx_loader: PrxLoader::new(),
             next_prx_addr: PRX_BASE_ADDR,
+            self_cache: None,
+        }
+    }
+
+    /// Cache decrypted executables in `dir`, so later boots skip decryption
+    pub fn set_self_cache<P: AsRef<Path>>(&mut self, dir: P) {
+        self.self_cache = Some(dir.as_ref().to_path_buf());
+    }
+
+    /// Executable decrypted by an earlier boot, if it was cached
+    fn cached_self(&self, path: &Path, data: &[u8]) -> Option<Vec<u8>> {
+        let cached = decrypted_self_path(self.self_cache.as_deref()?, path, data);
+        let elf = std::fs::read(&cached).ok()?;
+        info!("Using the decrypted executable cached in {}", cached.display());
+        Some(elf)
+    }
+
+    /// Keep a decrypted executable for later boots
+    fn cache_self(&self, path: &Path, data: &[u8], elf: &[u8]) {
+        let Some(dir) = self.self_cache.as_deref() else {
+            return;
+        };
+        let cached = decrypted_self_path(dir, path, data);
+        let result = std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&cached, elf));
+        if let Err(e) = result {
+            warn!("Failed to cache the decrypted executable in {}: {}", cached.display(), e);
         }
     }
 
@@ -135,7 +171,14 @@ impl GameLoader {
         }
 
         // Check if it's a SELF file (encrypted PS3 executable)
-        let (elf_data, is_self) = if SelfLoader::is_self(&data) {
+        let cached = if SelfLoader::is_self(&data) {
+            self.cached_self(Path::new(&actual_path), &data)
+        } else {
+            None
+        };
+        let (elf_data, is_self) = if let Some(elf) = cached {
+            (elf, true)
+        } else if SelfLoader::is_self(&data) {
             info!("Detected SELF file (encrypted PS3 executable)");
             
             // Try to create a SELF loader with firmware keys
@@ -144,6 +187,7 @@ impl GameLoader {
             match self_loader.decrypt(&data) {
                 Ok(decrypted) => {
                     info!("Successfully decrypted SELF file");
+                    self.cache_self(Path::new(&actual_path), &data, &decrypted);
                     (decrypted, true)
                 }
                 Err(e) => {
@@ -547,6 +591,16 @@ impl GameLoader {
         if let Err(e) = elf_loader.process_relocations(&mut cursor, &self.memory, base_addr) {
             debug!("Failed to process relocations (non-fatal): {}", e);
         }
+        let functions = self.function_map(&elf_loader, base_addr);
+        let imports = parse_imports(&elf_loader, data).unwrap_or_else(|e| {
+            debug!("Failed to parse imports (non-fatal): {}", e);
+            Vec::new()
+        });
+
+        // Make text and read-only data segments read-only
+        if let Err(e) = elf_loader.protect_segments(&self.memory, base_addr) {
+            warn!("Failed to apply segment protection (non-fatal): {}", e);
+        }
 
         // Calculate the actual entry point address
         // For ET_EXEC (executable), entry point is absolute. For ET_DYN (shared object), 
@@ -560,6 +614,13 @@ impl GameLoader {
             elf_loader.entry_point
         };
 
+        let segments = elf_loader.phdrs.iter().filter(|p| p.p_type == pt::LOAD && p.p_memsz > 0);
+        let image = ModuleRange {
+            path: path.clone(),
+            start: segments.clone().map(|p| base_addr as u64 + p.p_vaddr).min().unwrap_or(0) as u32,
+            end: segments.map(|p| base_addr as u64 + p.p_vaddr + p.p_memsz).max().unwrap_or(0) as u32,
+        };
+
         // Set up stack
         let stack_size = DEFAULT_STACK_SIZE;
         let stack_addr = STACK_BASE + stack_size; // Stack grows downward, so start at top
@@ -587,9 +648,41 @@ impl GameLoader {
             path,
             is_self,
             prx_modules: Vec::new(),
+            modules: vec![image],
+            functions,
+            imports,
         })
     }
 
+    /// Name the functions of a loaded executable from its symbol table
+    ///
+    /// PPC64 function symbols usually name the function descriptor in .opd;
+    /// the code address is the descriptor's first word then.
+    fn function_map(&self, elf: &ElfLoader, base_addr: u32) -> FunctionMap {
+        let executable = |addr: u64| {
+            elf.phdrs.iter().any(|p| {
+                let start = base_addr as u64 + p.p_vaddr;
+                p.p_type == pt::LOAD && p.p_flags & 0x1 != 0 && (start..start + p.p_memsz).contains(&addr)
+            })
+        };
+        let mut functions = FunctionMap::new();
+        for symbol in elf.symbols.iter().filter(|s| s.is_function() && s.value != 0 && !s.name.is_empty()) {
+            let addr = base_addr as u64 + symbol.value;
+            if executable(addr) {
+                functions.insert(addr, symbol.size, symbol.name.clone());
+                continue;
+            }
+            let descriptor = u32::try_from(addr).ok().and_then(|addr| self.memory.read_be32(addr).ok());
+            if let Some(code) = descriptor.map(u64::from).filter(|&code| executable(code)) {
+                functions.insert(code, 0, symbol.name.clone());
+            }
+        }
+        if !functions.is_empty() {
+            debug!("Named {} functions from symbols", functions.len());
+        }
+        functions
+    }
+
     /// Calculate the base address for loading
     fn calculate_base_addr(&self, elf: &ElfLoader) -> u32 {
         // Check if ELF has a preferred base address
@@ -804,7 +897,8 @@ impl GameLoader {
                 // Update next PRX address (add 16MB spacing between PRX modules)
                 self.next_prx_addr += 0x0100_0000;
 
-                // Track loaded module
+                // Track loaded module; its size isn't known, so it spans its slot
+                game.modules.push(ModuleRange { path: path_str, start: base_addr, end: self.next_prx_addr });
                 game.prx_modules.push(module.name);
             }
             Err(e) => {
@@ -878,6 +972,9 @@ mod tests {
             path: "/test/game.elf".to_string(),
             is_self: false,
             prx_modules: Vec::new(),
+            modules: Vec::new(),
+            functions: FunctionMap::new(),
+            imports: Vec::new(),
         };
 
         assert_eq!(game.entry_point, 0x10000);
@@ -967,6 +1064,9 @@ mod tests {
             path: "/test/game.elf".to_string(),
             is_self: false,
             prx_modules: Vec::new(),
+            modules: Vec::new(),
+            functions: FunctionMap::new(),
+            imports: Vec::new(),
         };
 
         // Test adding PRX modules
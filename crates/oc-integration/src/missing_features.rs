@@ -0,0 +1,223 @@
+//! Report of what a game needed that the emulator does not implement
+//!
+//! After a run, the syscalls, HLE functions, RSX methods and codecs the game
+//! touched without an implementation are collected into a
+//! [`MissingFeatureReport`], most used first, so contributors can tell what
+//! to implement next. HLE functions are taken from the executable's import
+//! table, as imports are not dispatched through the HLE registry. Codecs are
+//! those the game opened a decoder for that only produce placeholder output.
+
+use oc_hle::ModuleRegistry;
+use oc_loader::{ImportedLibrary, PrxLoader};
+use serde::Serialize;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Imported function without an HLE implementation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MissingFunction {
+    pub library: String,
+    pub nid: u32,
+    /// Function name, if the NID is a known one
+    pub name: Option<String>,
+}
+
+impl MissingFunction {
+    /// `library::name`, or the NID in hex when the name is unknown
+    pub fn display_name(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{}::{}", self.library, name),
+            None => format!("{}::0x{:08X}", self.library, self.nid),
+        }
+    }
+}
+
+/// The imported functions of `libraries` that `registry` has no implementation for
+pub fn missing_functions(libraries: &[ImportedLibrary], registry: &ModuleRegistry) -> Vec<MissingFunction> {
+    let names = PrxLoader::new();
+    let mut missing: Vec<_> = libraries
+        .iter()
+        .flat_map(|library| library.functions.iter().map(move |function| (library, function.nid)))
+        .filter(|(library, nid)| registry.find_function(&library.name, *nid).is_none())
+        .map(|(library, nid)| MissingFunction {
+            library: library.name.clone(),
+            nid,
+            name: names.resolve_nid_to_name(nid).map(str::to_string),
+        })
+        .collect();
+    missing.sort_by(|a, b| (&a.library, a.nid).cmp(&(&b.library, b.nid)));
+    missing
+}
+
+/// Unimplemented feature and how often the game used it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MissingFeature {
+    pub name: String,
+    /// Times the game used it, 0 when it was only referenced (imports)
+    pub calls: u64,
+}
+
+impl MissingFeature {
+    pub fn new(name: impl Into<String>, calls: u64) -> Self {
+        Self {
+            name: name.into(),
+            calls,
+        }
+    }
+}
+
+/// Everything unimplemented a game touched during one run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MissingFeatureReport {
+    pub title_id: String,
+    pub syscalls: Vec<MissingFeature>,
+    pub hle_functions: Vec<MissingFeature>,
+    pub rsx_methods: Vec<MissingFeature>,
+    pub codecs: Vec<MissingFeature>,
+    /// File the report was written to, if it was
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+impl MissingFeatureReport {
+    /// Create a report, sorting every section by call count, most used first
+    pub fn new(
+        title_id: &str,
+        syscalls: Vec<MissingFeature>,
+        hle_functions: Vec<MissingFeature>,
+        rsx_methods: Vec<MissingFeature>,
+        codecs: Vec<MissingFeature>,
+    ) -> Self {
+        let sorted = |mut features: Vec<MissingFeature>| {
+            features.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.name.cmp(&b.name)));
+            features
+        };
+        Self {
+            title_id: title_id.to_string(),
+            syscalls: sorted(syscalls),
+            hle_functions: sorted(hle_functions),
+            rsx_methods: sorted(rsx_methods),
+            codecs: sorted(codecs),
+            path: None,
+        }
+    }
+
+    /// Sections with their headings
+    fn sections(&self) -> [(&'static str, &[MissingFeature]); 4] {
+        [
+            ("Syscalls", &self.syscalls),
+            ("HLE functions", &self.hle_functions),
+            ("RSX methods", &self.rsx_methods),
+            ("Codecs", &self.codecs),
+        ]
+    }
+
+    /// Number of missing features in every section
+    pub fn total(&self) -> usize {
+        self.sections().iter().map(|(_, features)| features.len()).sum()
+    }
+
+    /// Whether the game touched nothing unimplemented
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+
+    /// One line counting each section
+    pub fn summary(&self) -> String {
+        format!(
+            "{} syscalls, {} HLE functions, {} RSX methods, {} codecs",
+            self.syscalls.len(),
+            self.hle_functions.len(),
+            self.rsx_methods.len(),
+            self.codecs.len()
+        )
+    }
+
+    /// Format the report as human readable text
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Missing features of {}", self.title_id);
+        let _ = writeln!(out, "{}", self.summary());
+        for (heading, features) in self.sections() {
+            if features.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out, "\n{}:", heading);
+            for feature in features {
+                match feature.calls {
+                    0 => {
+                        let _ = writeln!(out, "  {:>8}  {}", "imported", feature.name);
+                    }
+                    calls => {
+                        let _ = writeln!(out, "  {:>8}  {}", calls, feature.name);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Write the report to `path` as JSON
+    pub fn write_to(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oc_loader::ImportedSymbol;
+
+    #[test]
+    fn test_missing_functions() {
+        let symbol = |nid| ImportedSymbol { nid, slot: 0 };
+        let libraries = [ImportedLibrary {
+            name: "cellGcmSys".to_string(),
+            version: 1,
+            // cellGcmInit is implemented, cellFsOpen is not a cellGcmSys function
+            functions: vec![symbol(0x21AC3697), symbol(PrxLoader::calculate_nid("cellFsOpen"))],
+            variables: Vec::new(),
+        }];
+        let missing = missing_functions(&libraries, &ModuleRegistry::new());
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].library, "cellGcmSys");
+        assert_eq!(missing[0].name.as_deref(), Some("cellFsOpen"));
+        assert_eq!(missing[0].display_name(), "cellGcmSys::cellFsOpen");
+    }
+
+    #[test]
+    fn test_report_sorted_by_calls() {
+        let report = MissingFeatureReport::new(
+            "BLUS00001",
+            vec![
+                MissingFeature::new("syscall 900", 3),
+                MissingFeature::new("syscall 901", 40),
+                MissingFeature::new("syscall 899", 3),
+            ],
+            vec![MissingFeature::new("cellFoo::bar", 0)],
+            Vec::new(),
+            vec![MissingFeature::new("cellVdec DivX", 1)],
+        );
+        let names: Vec<_> = report.syscalls.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["syscall 901", "syscall 899", "syscall 900"]);
+        assert_eq!(report.total(), 5);
+        assert!(!report.is_empty());
+        assert!(MissingFeatureReport::default().is_empty());
+
+        let text = report.to_text();
+        assert!(text.contains("3 syscalls, 1 HLE functions, 0 RSX methods, 1 codecs"));
+        assert!(text.contains("imported  cellFoo::bar"));
+        assert!(!text.contains("RSX methods:"));
+
+        let dir = std::env::temp_dir().join(format!("oc_missing_features_{}", std::process::id()));
+        let path = dir.join("BLUS00001_missing_features.json");
+        report.write_to(&path).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["syscalls"][0]["calls"], 40);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
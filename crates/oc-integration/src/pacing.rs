@@ -0,0 +1,128 @@
+//! Emulation speed control
+//!
+//! The [`FrameLimiter`] decides how fast emulated time runs against the wall
+//! clock: at the configured frame limit, unlimited, in turbo while a hotkey
+//! is held, or in slow motion. Vblanks keep their emulated rate, so games see
+//! no difference; the runner hands the speed to the A/V sync governor for
+//! frame pacing and to the mixer, which plays audio at the same speed.
+
+use oc_core::config::GpuConfig;
+
+/// Slowest speed slow motion runs at
+const MIN_SPEED: f64 = 0.05;
+
+/// Speed mode picked at runtime
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SpeedMode {
+    #[default]
+    Normal,
+    /// Faster, while the turbo hotkey is held
+    Turbo,
+    SlowMotion,
+}
+
+/// Works out the speed emulation is paced to
+#[derive(Debug, Clone)]
+pub struct FrameLimiter {
+    /// Frames per second, 0 for unlimited
+    frame_limit: u32,
+    turbo_speed: f32,
+    slow_motion_speed: f32,
+    mode: SpeedMode,
+    /// Whether the frontend has frame limiting on
+    limited: bool,
+}
+
+impl FrameLimiter {
+    /// Create a limiter with the speeds from `config`
+    pub fn new(config: &GpuConfig) -> Self {
+        let mut limiter = Self {
+            frame_limit: 0,
+            turbo_speed: 0.0,
+            slow_motion_speed: 0.0,
+            mode: SpeedMode::Normal,
+            limited: true,
+        };
+        limiter.configure(config);
+        limiter
+    }
+
+    /// Take the frame limit and speeds from `config`
+    pub fn configure(&mut self, config: &GpuConfig) {
+        self.frame_limit = config.frame_limit;
+        self.turbo_speed = config.turbo_speed;
+        self.slow_motion_speed = config.slow_motion_speed;
+    }
+
+    /// Current speed mode
+    pub fn mode(&self) -> SpeedMode {
+        self.mode
+    }
+
+    /// Switch the speed mode
+    pub fn set_mode(&mut self, mode: SpeedMode) {
+        self.mode = mode;
+    }
+
+    /// Whether frame limiting is on
+    pub fn is_limited(&self) -> bool {
+        self.limited
+    }
+
+    /// Turn frame limiting on or off, leaving the frame limit setting alone
+    pub fn set_limited(&mut self, limited: bool) {
+        self.limited = limited;
+    }
+
+    /// Speed relative to real time, None when emulation runs unpaced
+    ///
+    /// A frame limit of 60 is real time; 30 runs at half speed.
+    pub fn speed(&self) -> Option<f64> {
+        let base = (self.limited && self.frame_limit > 0).then(|| self.frame_limit as f64 / 60.0);
+        match self.mode {
+            SpeedMode::Normal => base,
+            SpeedMode::Turbo if self.turbo_speed <= 0.0 => None,
+            SpeedMode::Turbo => base.map(|base| base * self.turbo_speed as f64),
+            // Slow motion is paced even without a frame limit
+            SpeedMode::SlowMotion => {
+                Some((base.unwrap_or(1.0) * self.slow_motion_speed as f64).max(MIN_SPEED))
+            }
+        }
+    }
+}
+
+impl Default for FrameLimiter {
+    fn default() -> Self {
+        Self::new(&GpuConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_speed() {
+        let mut config = GpuConfig::default();
+        let mut limiter = FrameLimiter::new(&config);
+        assert_eq!(limiter.speed(), Some(1.0));
+
+        limiter.set_mode(SpeedMode::SlowMotion);
+        assert_eq!(limiter.speed(), Some(0.5));
+        limiter.set_mode(SpeedMode::Turbo);
+        assert_eq!(limiter.speed(), None);
+        config.turbo_speed = 2.0;
+        limiter.configure(&config);
+        assert_eq!(limiter.speed(), Some(2.0));
+
+        config.frame_limit = 30;
+        limiter.configure(&config);
+        limiter.set_mode(SpeedMode::Normal);
+        assert_eq!(limiter.speed(), Some(0.5));
+
+        limiter.set_limited(false);
+        assert_eq!(limiter.speed(), None);
+        limiter.set_mode(SpeedMode::SlowMotion);
+        assert_eq!(limiter.speed(), Some(0.5));
+    }
+}
@@ -0,0 +1,166 @@
+//! Frame pacing and thread utilization counters for the performance overlay
+//!
+//! Every presented frame records how long it took since the previous one
+//! and how much of that the guest CPU threads and the RSX spent working.
+//! The rest of the frame is spent waiting for vblank or on the host.
+
+use oc_rsx::RsxFrameCounters;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Frames kept for the history graphs and percentiles
+pub const PERF_HISTORY_FRAMES: usize = 300;
+
+/// Timing of one presented frame, in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameSample {
+    /// Time since the previous frame was presented
+    pub frame_ms: f32,
+    /// Time spent running PPU and SPU threads
+    pub cpu_ms: f32,
+    /// Time spent processing RSX commands and presenting
+    pub gpu_ms: f32,
+}
+
+/// Performance summary over the recent frames
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PerfStats {
+    /// Frames per second over the history
+    pub fps: f64,
+    /// Median frame time
+    pub frame_ms_p50: f32,
+    /// 95th percentile frame time
+    pub frame_ms_p95: f32,
+    /// 99th percentile frame time
+    pub frame_ms_p99: f32,
+    /// Share of frame time spent running guest CPU threads, in percent
+    pub cpu_percent: f32,
+    /// Share of frame time spent in the RSX, in percent
+    pub gpu_percent: f32,
+    /// Shader programs translated since the game started
+    pub shader_compiles: u64,
+    /// RSX work done in the last frame
+    pub rsx: RsxFrameCounters,
+    /// Emulation speed relative to the console's vblank rate, in percent
+    pub speed_percent: f32,
+    /// Frame time the console would run at
+    pub target_frame_ms: f32,
+    /// Recent frames, oldest first
+    pub history: Vec<FrameSample>,
+}
+
+/// Collects [`FrameSample`]s as frames are presented
+#[derive(Debug, Default)]
+pub struct PerfMonitor {
+    history: VecDeque<FrameSample>,
+    /// When the previous frame was presented (None after a pause)
+    last_present: Option<Instant>,
+}
+
+impl PerfMonitor {
+    /// Create a monitor without samples
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget the previous frame so time spent paused is not counted as a slow frame
+    pub fn restart(&mut self) {
+        self.last_present = None;
+    }
+
+    /// Drop all samples
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.last_present = None;
+    }
+
+    /// Record a frame presented at `now` that spent `cpu` and `gpu` working
+    pub fn record(&mut self, now: Instant, cpu: Duration, gpu: Duration) {
+        let Some(last) = self.last_present.replace(now) else {
+            return;
+        };
+        if self.history.len() == PERF_HISTORY_FRAMES {
+            self.history.pop_front();
+        }
+        self.history.push_back(FrameSample {
+            frame_ms: now.saturating_duration_since(last).as_secs_f32() * 1000.0,
+            cpu_ms: cpu.as_secs_f32() * 1000.0,
+            gpu_ms: gpu.as_secs_f32() * 1000.0,
+        });
+    }
+
+    /// Summarize the history for a console running one frame every `target`
+    pub fn stats(&self, target: Duration, shader_compiles: u64) -> PerfStats {
+        let target_frame_ms = target.as_secs_f32() * 1000.0;
+        let mut stats = PerfStats {
+            shader_compiles,
+            target_frame_ms,
+            history: self.history.iter().copied().collect(),
+            ..PerfStats::default()
+        };
+        let total_ms: f32 = self.history.iter().map(|sample| sample.frame_ms).sum();
+        if total_ms <= 0.0 {
+            return stats;
+        }
+
+        let mut frame_times: Vec<f32> = self.history.iter().map(|sample| sample.frame_ms).collect();
+        frame_times.sort_by(f32::total_cmp);
+        let percentile = |p: f32| frame_times[((frame_times.len() - 1) as f32 * p).round() as usize];
+        stats.frame_ms_p50 = percentile(0.50);
+        stats.frame_ms_p95 = percentile(0.95);
+        stats.frame_ms_p99 = percentile(0.99);
+
+        let average_ms = total_ms / self.history.len() as f32;
+        stats.fps = 1000.0 / average_ms as f64;
+        stats.speed_percent = target_frame_ms / average_ms * 100.0;
+        let cpu_ms: f32 = self.history.iter().map(|sample| sample.cpu_ms).sum();
+        let gpu_ms: f32 = self.history.iter().map(|sample| sample.gpu_ms).sum();
+        stats.cpu_percent = (cpu_ms / total_ms * 100.0).min(100.0);
+        stats.gpu_percent = (gpu_ms / total_ms * 100.0).min(100.0);
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_perf_stats() {
+        let mut monitor = PerfMonitor::new();
+        let target = Duration::from_millis(20);
+        assert_eq!(monitor.stats(target, 0).fps, 0.0);
+
+        // 99 frames at 20 ms and one 60 ms hitch
+        let start = Instant::now();
+        let mut now = start;
+        monitor.record(now, Duration::ZERO, Duration::ZERO);
+        for frame in 0..100 {
+            now += Duration::from_millis(if frame == 50 { 60 } else { 20 });
+            monitor.record(now, Duration::from_millis(10), Duration::from_millis(5));
+        }
+
+        let stats = monitor.stats(target, 4);
+        assert_eq!(stats.history.len(), 100);
+        assert!((stats.frame_ms_p50 - 20.0).abs() < 0.01);
+        assert!((stats.frame_ms_p99 - 20.0).abs() < 0.01);
+        assert!((stats.history[50].frame_ms - 60.0).abs() < 0.01);
+        // 2040 ms for 100 frames
+        assert!((stats.fps - 1000.0 / 20.4).abs() < 0.01);
+        assert!((stats.speed_percent - 20.0 / 20.4 * 100.0).abs() < 0.01);
+        assert!((stats.cpu_percent - 1000.0 / 2040.0 * 100.0).abs() < 0.01);
+        assert_eq!(stats.shader_compiles, 4);
+
+        // Time spent paused is not a frame
+        monitor.restart();
+        monitor.record(now + Duration::from_secs(10), Duration::ZERO, Duration::ZERO);
+        assert_eq!(monitor.stats(target, 0).history.len(), 100);
+
+        // Old frames fall out of the history
+        for _ in 0..PERF_HISTORY_FRAMES {
+            now += Duration::from_millis(10);
+            monitor.record(now, Duration::ZERO, Duration::ZERO);
+        }
+        assert_eq!(monitor.stats(target, 0).history.len(), PERF_HISTORY_FRAMES);
+    }
+}
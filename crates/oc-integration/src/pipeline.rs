@@ -54,6 +54,8 @@ pub struct GameScanner {
     games: HashMap<String, GameInfo>,
     /// Cache file path
     cache_path: Option<PathBuf>,
+    /// Whether ICON0.PNG and PIC1.PNG are read into the game info
+    load_images: bool,
 }
 
 impl GameScanner {
@@ -63,9 +65,15 @@ impl GameScanner {
             search_dirs: Vec::new(),
             games: HashMap::new(),
             cache_path: None,
+            load_images: true,
         }
     }
 
+    /// Set whether scanning reads each game's ICON0.PNG and PIC1.PNG
+    pub fn set_load_images(&mut self, load_images: bool) {
+        self.load_images = load_images;
+    }
+
     /// Set cache file path for storing game database
     pub fn set_cache_path<P: AsRef<Path>>(&mut self, path: P) {
         self.cache_path = Some(path.as_ref().to_path_buf());
@@ -467,44 +475,26 @@ impl GameScanner {
         })
     }
 
+    /// Find an image such as ICON0.PNG in a game directory or its PS3_GAME folder
+    pub fn find_image(game_dir: &Path, name: &str) -> Option<PathBuf> {
+        [game_dir.join(name), game_dir.join("PS3_GAME").join(name)]
+            .into_iter()
+            .find(|path| path.is_file())
+    }
+
     /// Extract ICON0.PNG and PIC1.PNG from game directory
     fn extract_images(&self, game_dir: &Path) -> (Option<Vec<u8>>, Option<Vec<u8>>) {
-        let mut icon0_data = None;
-        let mut pic1_data = None;
-
-        // Try to find ICON0.PNG in multiple locations
-        let icon0_paths = [
-            game_dir.join("ICON0.PNG"),
-            game_dir.join("PS3_GAME").join("ICON0.PNG"),
-        ];
-
-        for icon_path in &icon0_paths {
-            if icon_path.exists() {
-                if let Ok(data) = fs::read(icon_path) {
-                    debug!("Loaded ICON0.PNG: {} bytes", data.len());
-                    icon0_data = Some(data);
-                    break;
-                }
-            }
+        if !self.load_images {
+            return (None, None);
         }
 
-        // Try to find PIC1.PNG in multiple locations
-        let pic1_paths = [
-            game_dir.join("PIC1.PNG"),
-            game_dir.join("PS3_GAME").join("PIC1.PNG"),
-        ];
-
-        for pic_path in &pic1_paths {
-            if pic_path.exists() {
-                if let Ok(data) = fs::read(pic_path) {
-                    debug!("Loaded PIC1.PNG: {} bytes", data.len());
-                    pic1_data = Some(data);
-                    break;
-                }
-            }
-        }
+        let read = |name: &str| {
+            let data = fs::read(Self::find_image(game_dir, name)?).ok()?;
+            debug!("Loaded {}: {} bytes", name, data.len());
+            Some(data)
+        };
 
-        (icon0_data, pic1_data)
+        (read("ICON0.PNG"), read("PIC1.PNG"))
     }
 
     /// Get discovered games
@@ -632,6 +622,8 @@ pub struct GamePipeline {
 impl GamePipeline {
     /// Create a new game pipeline
     pub fn new(memory: Arc<MemoryManager>) -> Self {
+        // HLE functions called through the registry read and write this memory
+        oc_hle::set_guest_memory(memory.clone());
         let mut pipeline = Self {
             module_registry: ModuleRegistry::new(),
             system_modules: HashMap::new(),
@@ -1643,6 +1635,19 @@ mod tests {
         assert_eq!(scanner.search_dirs.len(), 1);
     }
 
+    #[test]
+    fn test_game_scanner_find_image() {
+        let dir = std::env::temp_dir().join(format!("oc_find_image_test_{}", std::process::id()));
+        fs::create_dir_all(dir.join("PS3_GAME")).unwrap();
+        fs::write(dir.join("PS3_GAME").join("ICON0.PNG"), b"png").unwrap();
+        fs::write(dir.join("PIC1.PNG"), b"png").unwrap();
+
+        assert_eq!(GameScanner::find_image(&dir, "ICON0.PNG"), Some(dir.join("PS3_GAME").join("ICON0.PNG")));
+        assert_eq!(GameScanner::find_image(&dir, "PIC1.PNG"), Some(dir.join("PIC1.PNG")));
+        assert_eq!(GameScanner::find_image(&dir, "SND0.AT3"), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_game_info_default() {
         let info = GameInfo::default();
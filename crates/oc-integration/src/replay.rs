@@ -0,0 +1,565 @@
+//! Deterministic recording and replay of a run's nondeterministic inputs
+//!
+//! A recording logs everything that can differ between two runs of the same
+//! game: controller input, guest time reads, the scheduler's thread picks and
+//! every syscall result. Replaying feeds the logged inputs back so the run
+//! repeats exactly, and reports the first point where it drifts from the
+//! recording.
+//!
+//! Recordings are gzip-compressed text, one event per line:
+//!
+//! ```text
+//! F 12                                  frame 12 starts
+//! P 0 00000040 128 128 128 255 000…     port 0 state changed ("P 0 -" when unplugged)
+//! M 0 0 0 511 0 0 0                     port 0 motion changed
+//! S p0 1500                             PPU thread 0 picked 1500 times in a row
+//! C 145 000000000001e240                syscall 145 returned 0x1e240
+//! ```
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use oc_core::ThreadId;
+use oc_input::pad::{PadState, MAX_PADS};
+use oc_input::{PadPorts, SixaxisData};
+use oc_lv2::syscall_numbers::SYS_TIME_GET_SYSTEM_TIME;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// First line of a recording
+const HEADER: &str = "# oxidized-cell replay v1";
+
+/// Syscalls whose recorded result replaces the live one on replay
+pub const NONDETERMINISTIC_SYSCALLS: &[u64] = &[SYS_TIME_GET_SYSTEM_TIME];
+
+/// One logged input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayEvent {
+    /// A frame starts
+    Frame(u64),
+    /// The state of a port changed (None when it was unplugged)
+    Pad { port: u8, state: Option<PadState> },
+    /// The motion sensors of a port changed
+    Motion { port: u8, sixaxis: SixaxisData },
+    /// The scheduler picked the same thread (None for no thread) `count` times in a row
+    Schedule { thread: Option<ThreadId>, count: u32 },
+    /// A syscall returned `result` in r3
+    Syscall { number: u64, result: u64 },
+}
+
+impl ReplayEvent {
+    /// Format the event as a recording line
+    pub fn to_line(&self) -> String {
+        match self {
+            Self::Frame(frame) => format!("F {}", frame),
+            Self::Pad { port, state: None } => format!("P {} -", port),
+            Self::Pad { port, state: Some(state) } => {
+                let mut pressure = String::new();
+                for value in state.pressure {
+                    let _ = write!(pressure, "{:02x}", value);
+                }
+                format!(
+                    "P {} {:08x} {} {} {} {} {}",
+                    port, state.buttons, state.left_x, state.left_y, state.right_x, state.right_y, pressure
+                )
+            }
+            Self::Motion { port, sixaxis } => format!(
+                "M {} {} {} {} {} {} {}",
+                port, sixaxis.accel_x, sixaxis.accel_y, sixaxis.accel_z, sixaxis.gyro_x, sixaxis.gyro_y, sixaxis.gyro_z
+            ),
+            Self::Schedule { thread, count } => {
+                let thread = match thread {
+                    Some(ThreadId::Ppu(id)) => format!("p{}", id),
+                    Some(ThreadId::Spu(id)) => format!("s{}", id),
+                    None => String::from("-"),
+                };
+                format!("S {} {}", thread, count)
+            }
+            Self::Syscall { number, result } => format!("C {} {:016x}", number, result),
+        }
+    }
+
+    /// Parse a recording line
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid replay event \"{}\"", line);
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let number = |i: usize| fields.get(i).and_then(|field| field.parse::<i64>().ok()).ok_or_else(invalid);
+        let byte = |i: usize| number(i).and_then(|n| u8::try_from(n).map_err(|_| invalid()));
+        let short = |i: usize| number(i).and_then(|n| i16::try_from(n).map_err(|_| invalid()));
+        let hex = |i: usize| fields.get(i).and_then(|field| u64::from_str_radix(field, 16).ok()).ok_or_else(invalid);
+
+        match fields.first().copied() {
+            Some("F") if fields.len() == 2 => Ok(Self::Frame(number(1)? as u64)),
+            Some("P") if fields.len() == 3 && fields[2] == "-" => Ok(Self::Pad { port: byte(1)?, state: None }),
+            Some("P") if fields.len() == 8 => {
+                let mut pressure = [0u8; 12];
+                let text = fields[7];
+                if text.len() != pressure.len() * 2 {
+                    return Err(invalid());
+                }
+                for (i, value) in pressure.iter_mut().enumerate() {
+                    *value = u8::from_str_radix(text.get(i * 2..i * 2 + 2).ok_or_else(invalid)?, 16).map_err(|_| invalid())?;
+                }
+                let state = PadState {
+                    buttons: hex(2)? as u32,
+                    left_x: byte(3)?,
+                    left_y: byte(4)?,
+                    right_x: byte(5)?,
+                    right_y: byte(6)?,
+                    pressure,
+                };
+                Ok(Self::Pad { port: byte(1)?, state: Some(state) })
+            }
+            Some("M") if fields.len() == 8 => Ok(Self::Motion {
+                port: byte(1)?,
+                sixaxis: SixaxisData {
+                    accel_x: short(2)?,
+                    accel_y: short(3)?,
+                    accel_z: short(4)?,
+                    gyro_x: short(5)?,
+                    gyro_y: short(6)?,
+                    gyro_z: short(7)?,
+                },
+            }),
+            Some("S") if fields.len() == 3 => {
+                let thread = match fields[1] {
+                    "-" => None,
+                    thread => {
+                        let id = thread.get(1..).and_then(|id| id.parse().ok()).ok_or_else(invalid)?;
+                        match thread.as_bytes()[0] {
+                            b'p' => Some(ThreadId::Ppu(id)),
+                            b's' => Some(ThreadId::Spu(id)),
+                            _ => return Err(invalid()),
+                        }
+                    }
+                };
+                let count = number(2).and_then(|n| u32::try_from(n).map_err(|_| invalid()))?;
+                Ok(Self::Schedule { thread, count })
+            }
+            Some("C") if fields.len() == 3 => Ok(Self::Syscall { number: number(1)? as u64, result: hex(2)? }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// A recorded run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayLog {
+    /// Title ID of the recorded game, if known
+    pub title_id: Option<String>,
+    pub events: Vec<ReplayEvent>,
+}
+
+impl ReplayLog {
+    /// Write the log to a gzip file
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let write = || -> std::io::Result<()> {
+            let file = std::fs::File::create(path)?;
+            let mut out = GzEncoder::new(std::io::BufWriter::new(file), Compression::default());
+            writeln!(out, "{}", HEADER)?;
+            if let Some(title_id) = &self.title_id {
+                writeln!(out, "# title {}", title_id)?;
+            }
+            for event in &self.events {
+                writeln!(out, "{}", event.to_line())?;
+            }
+            out.finish()?.flush()
+        };
+        write().map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Read a log written by `save`
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let mut lines = BufReader::new(GzDecoder::new(file)).lines();
+        let read_error = |e: std::io::Error| format!("Failed to read {}: {}", path.display(), e);
+        match lines.next().transpose().map_err(read_error)? {
+            Some(header) if header == HEADER => {}
+            _ => return Err(format!("{} is not a replay recording", path.display())),
+        }
+
+        let mut log = Self::default();
+        for line in lines {
+            let line = line.map_err(read_error)?;
+            if let Some(comment) = line.strip_prefix('#') {
+                if let Some(title_id) = comment.trim().strip_prefix("title ") {
+                    log.title_id = Some(title_id.to_string());
+                }
+            } else if !line.trim().is_empty() {
+                log.events.push(ReplayEvent::parse(&line)?);
+            }
+        }
+        Ok(log)
+    }
+}
+
+/// Whether a session records or replays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SessionMode {
+    Recording,
+    Replaying,
+}
+
+/// A recording or replay in progress
+pub struct ReplaySession {
+    mode: SessionMode,
+    log: ReplayLog,
+    path: PathBuf,
+    /// Next event to replay
+    cursor: usize,
+    /// Current frame, for divergence reports
+    frame: u64,
+    /// Thread of the scheduler run being recorded or replayed, and its picks so far (left when replaying)
+    schedule_run: Option<(Option<ThreadId>, u32)>,
+    /// Last recorded state of each port
+    pads: [Option<PadState>; MAX_PADS],
+    motion: [Option<SixaxisData>; MAX_PADS],
+    /// First point the replay drifted from the recording
+    divergence: Option<String>,
+}
+
+impl ReplaySession {
+    /// Start recording, to be saved to `path` by `finish`
+    pub fn record(path: &Path, title_id: Option<&str>) -> Self {
+        Self::new(
+            SessionMode::Recording,
+            ReplayLog { title_id: title_id.map(str::to_string), events: Vec::new() },
+            path,
+        )
+    }
+
+    /// Start replaying the recording at `path`
+    pub fn replay(path: &Path) -> Result<Self, String> {
+        Ok(Self::new(SessionMode::Replaying, ReplayLog::load(path)?, path))
+    }
+
+    fn new(mode: SessionMode, log: ReplayLog, path: &Path) -> Self {
+        Self {
+            mode,
+            log,
+            path: path.to_path_buf(),
+            cursor: 0,
+            frame: 0,
+            schedule_run: None,
+            pads: Default::default(),
+            motion: [None; MAX_PADS],
+            divergence: None,
+        }
+    }
+
+    /// Title ID of the recorded game
+    pub fn title_id(&self) -> Option<&str> {
+        self.log.title_id.as_deref()
+    }
+
+    /// Whether this session records
+    pub fn is_recording(&self) -> bool {
+        self.mode == SessionMode::Recording
+    }
+
+    /// Whether recorded inputs are still being fed back
+    pub fn is_replaying(&self) -> bool {
+        self.mode == SessionMode::Replaying && self.divergence.is_none() && self.cursor < self.log.events.len()
+    }
+
+    /// First point the replay drifted from the recording
+    pub fn divergence(&self) -> Option<&str> {
+        self.divergence.as_deref()
+    }
+
+    /// One-line description of the session
+    pub fn status(&self) -> String {
+        match (self.mode, &self.divergence) {
+            (SessionMode::Recording, _) => format!("Recording, {} events", self.log.events.len()),
+            (SessionMode::Replaying, Some(divergence)) => format!("Replay diverged: {}", divergence),
+            (SessionMode::Replaying, None) if self.cursor >= self.log.events.len() => String::from("Replay finished"),
+            (SessionMode::Replaying, None) => {
+                format!("Replaying, event {} of {}", self.cursor, self.log.events.len())
+            }
+        }
+    }
+
+    fn push(&mut self, event: ReplayEvent) {
+        self.log.events.push(event);
+    }
+
+    /// Record the scheduler run so far, before an event that interrupts it
+    fn flush_schedule(&mut self) {
+        if let Some((thread, count)) = self.schedule_run.take() {
+            self.push(ReplayEvent::Schedule { thread, count });
+        }
+    }
+
+    /// Event the replay is at
+    fn peek(&self) -> Option<&ReplayEvent> {
+        if self.is_replaying() {
+            self.log.events.get(self.cursor)
+        } else {
+            None
+        }
+    }
+
+    /// Stop feeding back inputs, remembering where the run drifted off
+    fn diverge(&mut self, what: String) {
+        let divergence = format!("frame {}, event {}: {}", self.frame, self.cursor, what);
+        tracing::warn!("Replay diverged at {}", divergence);
+        self.divergence = Some(divergence);
+    }
+
+    /// Consume the next replayed event, which must match `expected`
+    fn expect(&mut self, expected: &str, matches: impl FnOnce(&ReplayEvent) -> bool) -> Option<ReplayEvent> {
+        let event = self.peek()?.clone();
+        if matches(&event) {
+            self.cursor += 1;
+            if self.cursor == self.log.events.len() {
+                tracing::info!("Replay of {} finished", self.path.display());
+            }
+            Some(event)
+        } else {
+            self.diverge(format!("expected {}, recorded \"{}\"", expected, event.to_line()));
+            None
+        }
+    }
+
+    /// Mark the start of a frame
+    pub fn frame(&mut self, frame: u64) {
+        self.frame = frame;
+        match self.mode {
+            SessionMode::Recording => {
+                self.flush_schedule();
+                self.push(ReplayEvent::Frame(frame));
+            }
+            SessionMode::Replaying => {
+                if let Some((_, left)) = self.schedule_run.take() {
+                    if left > 0 && self.is_replaying() {
+                        self.diverge(format!("{} recorded scheduler picks were not made", left));
+                        return;
+                    }
+                }
+                self.expect(&format!("frame {}", frame), |event| *event == ReplayEvent::Frame(frame));
+            }
+        }
+    }
+
+    /// Record changed controller state, or replace it with the recorded one
+    pub fn pads(&mut self, ports: &PadPorts) {
+        match self.mode {
+            SessionMode::Recording => {
+                for port in 0..MAX_PADS {
+                    let state = ports.state(port as u8);
+                    if state != self.pads[port] {
+                        self.pads[port] = state.clone();
+                        self.push(ReplayEvent::Pad { port: port as u8, state });
+                    }
+                    if let Some(sixaxis) = ports.motion(port as u8) {
+                        if self.motion[port] != Some(sixaxis) {
+                            self.motion[port] = Some(sixaxis);
+                            self.push(ReplayEvent::Motion { port: port as u8, sixaxis });
+                        }
+                    }
+                }
+            }
+            SessionMode::Replaying => {
+                while let Some(event) = self.peek().cloned() {
+                    match event {
+                        ReplayEvent::Pad { port, state } => {
+                            self.pads[port as usize % MAX_PADS] = state;
+                        }
+                        ReplayEvent::Motion { port, sixaxis } => {
+                            self.motion[port as usize % MAX_PADS] = Some(sixaxis);
+                        }
+                        _ => break,
+                    }
+                    self.expect("controller input", |_| true);
+                }
+                // Live input is overridden every frame, not only on changes
+                for port in 0..MAX_PADS {
+                    match &self.pads[port] {
+                        Some(state) => {
+                            if ports.state(port as u8).is_none() {
+                                ports.connect(port as u8);
+                            }
+                            ports.update(port as u8, state.clone());
+                            if let Some(sixaxis) = self.motion[port] {
+                                ports.update_motion(port as u8, sixaxis);
+                            }
+                        }
+                        None => ports.disconnect(port as u8),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Record the scheduler's pick, or return the recorded one to run instead
+    pub fn schedule(&mut self, live: Option<ThreadId>) -> Option<ThreadId> {
+        match self.mode {
+            SessionMode::Recording => {
+                match &mut self.schedule_run {
+                    Some((thread, count)) if *thread == live && *count < u32::MAX => *count += 1,
+                    _ => {
+                        self.flush_schedule();
+                        self.schedule_run = Some((live, 1));
+                    }
+                }
+                live
+            }
+            SessionMode::Replaying => {
+                if !matches!(self.schedule_run, Some((_, left)) if left > 0) {
+                    let event = self.expect("a scheduler pick", |event| matches!(event, ReplayEvent::Schedule { .. }));
+                    match event {
+                        Some(ReplayEvent::Schedule { thread, count }) => self.schedule_run = Some((thread, count)),
+                        _ => return live,
+                    }
+                }
+                match &mut self.schedule_run {
+                    Some((thread, left)) => {
+                        *left -= 1;
+                        *thread
+                    }
+                    None => live,
+                }
+            }
+        }
+    }
+
+    /// Report that the recorded scheduler pick could not be made
+    pub fn schedule_failed(&mut self, recorded: Option<ThreadId>, live: Option<ThreadId>) {
+        self.diverge(format!("recorded thread {:?} is not ready, scheduler picked {:?}", recorded, live));
+    }
+
+    /// Record a syscall result, or check it against the recorded one
+    ///
+    /// Returns the result to put in r3, which is the recorded one for
+    /// [`NONDETERMINISTIC_SYSCALLS`] while replaying.
+    pub fn syscall(&mut self, number: u64, result: u64) -> u64 {
+        match self.mode {
+            SessionMode::Recording => {
+                self.flush_schedule();
+                self.push(ReplayEvent::Syscall { number, result });
+                result
+            }
+            SessionMode::Replaying => {
+                if matches!(self.schedule_run, Some((_, left)) if left > 0) && self.is_replaying() {
+                    self.diverge(format!("syscall {} was made before its recorded scheduler picks", number));
+                    return result;
+                }
+                let expected = format!("syscall {}", number);
+                let recorded = self.expect(&expected, |event| matches!(event, ReplayEvent::Syscall { number: n, .. } if *n == number));
+                match recorded {
+                    Some(ReplayEvent::Syscall { result: recorded, .. }) if NONDETERMINISTIC_SYSCALLS.contains(&number) => recorded,
+                    Some(ReplayEvent::Syscall { result: recorded, .. }) if recorded != result => {
+                        self.diverge(format!("syscall {} returned 0x{:x}, recorded 0x{:x}", number, result, recorded));
+                        result
+                    }
+                    _ => result,
+                }
+            }
+        }
+    }
+
+    /// End the session, saving a recording; returns a description of how it went
+    pub fn finish(mut self) -> Result<String, String> {
+        match self.mode {
+            SessionMode::Recording => {
+                self.flush_schedule();
+                self.log.save(&self.path)?;
+                Ok(format!("Recorded {} events to {}", self.log.events.len(), self.path.display()))
+            }
+            SessionMode::Replaying => Ok(self.status()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_lines() {
+        let mut state = PadState::new();
+        state.buttons = 0x40;
+        state.pressure[3] = 0xFF;
+        let events = [
+            ReplayEvent::Frame(12),
+            ReplayEvent::Pad { port: 1, state: Some(state) },
+            ReplayEvent::Pad { port: 2, state: None },
+            ReplayEvent::Motion { port: 0, sixaxis: SixaxisData::at_rest() },
+            ReplayEvent::Schedule { thread: Some(ThreadId::Spu(3)), count: 1500 },
+            ReplayEvent::Schedule { thread: None, count: 1 },
+            ReplayEvent::Syscall { number: 145, result: u64::MAX },
+        ];
+        for event in events {
+            assert_eq!(ReplayEvent::parse(&event.to_line()), Ok(event));
+        }
+        assert!(ReplayEvent::parse("S q0 1").is_err());
+        assert!(ReplayEvent::parse("P 0 0 128 128 128 128 00").is_err());
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let path = std::env::temp_dir().join(format!("oc-replay-{}.txt.gz", std::process::id()));
+        let ports = PadPorts::new();
+        ports.connect(0);
+        let mut pressed = PadState::new();
+        pressed.buttons = 0x4000;
+
+        let mut session = ReplaySession::record(&path, Some("TEST00000"));
+        session.frame(0);
+        session.pads(&ports);
+        assert_eq!(session.schedule(Some(ThreadId::Ppu(0))), Some(ThreadId::Ppu(0)));
+        session.schedule(Some(ThreadId::Ppu(0)));
+        assert_eq!(session.syscall(SYS_TIME_GET_SYSTEM_TIME, 1000), 1000);
+        session.schedule(Some(ThreadId::Ppu(1)));
+        session.frame(1);
+        ports.update(0, pressed.clone());
+        session.pads(&ports);
+        session.schedule(None);
+        assert_eq!(session.finish().unwrap(), format!("Recorded 9 events to {}", path.display()));
+
+        // The replay feeds back input, time and scheduling regardless of the live values
+        let mut replay = ReplaySession::replay(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(replay.title_id(), Some("TEST00000"));
+        ports.update(0, PadState::new());
+        replay.frame(0);
+        replay.pads(&ports);
+        assert_eq!(ports.state(0), Some(PadState::new()));
+        assert_eq!(replay.schedule(Some(ThreadId::Ppu(1))), Some(ThreadId::Ppu(0)));
+        assert_eq!(replay.schedule(None), Some(ThreadId::Ppu(0)));
+        assert_eq!(replay.syscall(SYS_TIME_GET_SYSTEM_TIME, 5000), 1000);
+        assert_eq!(replay.schedule(Some(ThreadId::Ppu(1))), Some(ThreadId::Ppu(1)));
+        replay.frame(1);
+        replay.pads(&ports);
+        assert_eq!(ports.state(0), Some(pressed));
+        assert!(replay.is_replaying());
+        assert_eq!(replay.schedule(Some(ThreadId::Ppu(0))), None);
+        assert!(!replay.is_replaying());
+        assert!(replay.divergence().is_none());
+        assert_eq!(replay.status(), "Replay finished");
+    }
+
+    #[test]
+    fn test_replay_divergence() {
+        let path = std::env::temp_dir().join(format!("oc-replay-diverge-{}.txt.gz", std::process::id()));
+        let mut session = ReplaySession::record(&path, None);
+        session.frame(0);
+        session.syscall(1, 0);
+        session.frame(1);
+        session.finish().unwrap();
+
+        let mut replay = ReplaySession::replay(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        replay.frame(0);
+        // Deterministic syscalls are checked, not replaced
+        assert_eq!(replay.syscall(1, 7), 7);
+        assert!(replay.divergence().unwrap().contains("syscall 1 returned 0x7, recorded 0x0"));
+        // After diverging, the live run goes on untouched
+        assert_eq!(replay.schedule(Some(ThreadId::Ppu(2))), Some(ThreadId::Ppu(2)));
+        assert!(replay.status().starts_with("Replay diverged: frame 0, event 2"));
+    }
+}
@@ -0,0 +1,504 @@
+//! Import of an existing RPCS3 installation
+//!
+//! RPCS3 keeps its settings in `config.yml`, the games it was pointed at in
+//! `games.yml`, its virtual file system mounts in `vfs.yml` and the guest
+//! HDD in `dev_hdd0`. Newer versions put the YAML files in a `config`
+//! subdirectory. Settings are mapped onto the closest equivalent here, games
+//! stay where they are and are added to the scanned folders, and the
+//! installed games, saves and licenses in dev_hdd0 are copied over.
+
+use oc_core::config::{AudioBackend, AudioChannelLayout, Config, GpuBackend, PathConfig, PpuDecoder, SpuDecoder};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// Subdirectories of dev_hdd0 that are copied: installed games, updates and
+/// DLC, and the users' saves and licenses
+const HDD0_DIRS: [&str; 2] = ["game", "home"];
+
+/// Extensions of disc key files
+const DISC_KEY_EXTENSIONS: [&str; 2] = ["dkey", "ird"];
+
+/// Progress of a copy running on another thread
+#[derive(Debug, Default)]
+pub struct CopyProgress {
+    pub done: AtomicU64,
+    pub total: AtomicU64,
+    pub cancel: AtomicBool,
+}
+
+/// What to import
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportOptions {
+    /// Map config.yml onto the configuration
+    pub settings: bool,
+    /// Scan the folders of the games in games.yml
+    pub games: bool,
+    /// Copy installed games, saves and licenses from dev_hdd0
+    pub hdd0: bool,
+    /// Copy disc key files
+    pub disc_keys: bool,
+    /// Replace files that already exist
+    pub overwrite: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            settings: true,
+            games: true,
+            hdd0: true,
+            disc_keys: true,
+            overwrite: false,
+        }
+    }
+}
+
+/// Outcome of copying files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CopyReport {
+    pub copied: u64,
+    /// Files left alone because they already existed
+    pub skipped: u64,
+    pub errors: Vec<String>,
+    pub cancelled: bool,
+}
+
+/// An RPCS3 installation found on disk
+#[derive(Debug, Clone)]
+pub struct Rpcs3Install {
+    pub root: PathBuf,
+    /// config.yml flattened to `Section/Key` entries
+    config: BTreeMap<String, String>,
+    /// Title IDs and folders from games.yml
+    pub games: Vec<(String, PathBuf)>,
+    pub dev_hdd0: PathBuf,
+    pub disc_keys: Vec<PathBuf>,
+}
+
+impl Rpcs3Install {
+    /// Where RPCS3 keeps its data by default on this OS, if it is there
+    pub fn default_location() -> Option<PathBuf> {
+        let dir = dirs::config_dir()?.join("rpcs3");
+        dir.is_dir().then_some(dir)
+    }
+
+    /// Read the installation in `root`
+    pub fn open(root: &Path) -> Result<Self, String> {
+        let find = |name: &str| {
+            [root.join("config").join(name), root.join(name)]
+                .into_iter()
+                .find(|path| path.is_file())
+        };
+        let read = |name: &str| -> Result<BTreeMap<String, String>, String> {
+            match find(name) {
+                Some(path) => fs::read_to_string(&path)
+                    .map(|text| parse_yaml(&text))
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e)),
+                None => Ok(BTreeMap::new()),
+            }
+        };
+
+        let config = read("config.yml")?;
+        let vfs = read("vfs.yml")?;
+        let games = read("games.yml")?
+            .into_iter()
+            .map(|(id, path)| (id, resolve_path(root, &path)))
+            .collect();
+
+        // Older versions keep the mounts in config.yml
+        let dev_hdd0 = vfs
+            .get("/dev_hdd0/")
+            .or_else(|| config.get("VFS//dev_hdd0/"))
+            .filter(|path| !path.is_empty())
+            .map(|path| resolve_path(root, path))
+            .unwrap_or_else(|| root.join("dev_hdd0"));
+
+        let install = Self {
+            root: root.to_path_buf(),
+            config,
+            games,
+            dev_hdd0,
+            disc_keys: find_disc_keys(root),
+        };
+        if install.config.is_empty() && install.games.is_empty() && !install.dev_hdd0.is_dir() {
+            return Err(format!("No RPCS3 installation found in {}", root.display()));
+        }
+        Ok(install)
+    }
+
+    /// Whether config.yml was found
+    pub fn has_config(&self) -> bool {
+        !self.config.is_empty()
+    }
+
+    /// Save data directories of every user in dev_hdd0
+    pub fn save_count(&self) -> usize {
+        let Ok(users) = fs::read_dir(self.dev_hdd0.join("home")) else {
+            return 0;
+        };
+        users
+            .flatten()
+            .filter_map(|user| fs::read_dir(user.path().join("savedata")).ok())
+            .map(|saves| saves.flatten().filter(|save| save.path().is_dir()).count())
+            .sum()
+    }
+
+    /// Games installed into dev_hdd0, including updates and DLC
+    pub fn installed_count(&self) -> usize {
+        fs::read_dir(self.dev_hdd0.join("game"))
+            .map(|games| games.flatten().filter(|game| game.path().is_dir()).count())
+            .unwrap_or(0)
+    }
+
+    /// Apply the settings with an equivalent here, returning what was changed
+    pub fn apply_settings(&self, config: &mut Config) -> Vec<String> {
+        let mut changes = Vec::new();
+        let value = |key: &str| self.config.get(key).map(String::as_str);
+        let flag = |key: &str| match value(key) {
+            Some("true") => Some(true),
+            Some("false") => Some(false),
+            _ => None,
+        };
+        let number = |key: &str| value(key).and_then(|v| v.parse::<f64>().ok());
+        let mut note = |name: &str, value: &dyn std::fmt::Debug| changes.push(format!("{} = {:?}", name, value));
+
+        let cpu = &mut config.cpu;
+        if let Some(decoder) = value("Core/PPU Decoder") {
+            cpu.ppu_decoder = if decoder.contains("Interpreter") { PpuDecoder::Interpreter } else { PpuDecoder::Recompiler };
+            note("PPU decoder", &cpu.ppu_decoder);
+        }
+        if let Some(decoder) = value("Core/SPU Decoder") {
+            cpu.spu_decoder = if decoder.contains("Interpreter") { SpuDecoder::Interpreter } else { SpuDecoder::Recompiler };
+            note("SPU decoder", &cpu.spu_decoder);
+        }
+        if let Some(threads) = number("Core/PPU Threads").filter(|&n| n >= 1.0) {
+            cpu.ppu_threads = threads as u32;
+            note("PPU threads", &cpu.ppu_threads);
+        }
+        // 0 lets RPCS3 pick
+        if let Some(threads) = number("Core/Preferred SPU Threads").filter(|&n| n >= 1.0) {
+            cpu.spu_threads = threads as u32;
+            note("SPU threads", &cpu.spu_threads);
+        }
+        if let Some(on) = flag("Core/Accurate DFMA") {
+            cpu.accurate_dfma = on;
+            note("Accurate DFMA", &on);
+        }
+        if let Some(on) = flag("Core/Accurate RSX reservation access") {
+            cpu.accurate_rsx_reservation = on;
+            note("Accurate RSX reservation", &on);
+        }
+        if let Some(on) = flag("Core/SPU loop detection") {
+            cpu.spu_loop_detection = on;
+            note("SPU loop detection", &on);
+        }
+
+        let gpu = &mut config.gpu;
+        if let Some(renderer) = value("Video/Renderer") {
+            // OpenGL has no equivalent, so everything but Null renders with Vulkan
+            gpu.backend = if renderer == "Null" { GpuBackend::Null } else { GpuBackend::Vulkan };
+            note("GPU backend", &gpu.backend);
+        }
+        if let Some(scale) = number("Video/Resolution Scale").filter(|&n| n > 0.0) {
+            gpu.resolution_scale = scale as u32;
+            note("Resolution scale", &gpu.resolution_scale);
+        }
+        // 0 leaves filtering to the game
+        if let Some(filter) = number("Video/Anisotropic Filter Override").filter(|&n| n >= 1.0) {
+            gpu.anisotropic_filter = filter as u32;
+            note("Anisotropic filter", &gpu.anisotropic_filter);
+        }
+        if let Some(on) = flag("Video/VSync") {
+            gpu.vsync = on;
+            note("VSync", &on);
+        }
+        // Auto, Off and Display have no fixed rate
+        if let Some(limit) = number("Video/Frame limit").filter(|&n| n > 0.0) {
+            gpu.frame_limit = limit.round() as u32;
+            note("Frame limit", &gpu.frame_limit);
+        }
+        if let Some(on) = flag("Video/Write Color Buffers") {
+            gpu.write_color_buffers = on;
+            note("Write color buffers", &on);
+        }
+        if let Some(on) = flag("Video/Write Depth Buffer") {
+            gpu.write_depth_buffer = on;
+            note("Write depth buffer", &on);
+        }
+        if let Some(off) = flag("Video/Disable On-Disk Shader Cache") {
+            gpu.shader_cache = !off;
+            note("Shader cache", &gpu.shader_cache);
+        }
+
+        let audio = &mut config.audio;
+        if let Some(renderer) = value("Audio/Renderer") {
+            audio.backend = if renderer == "Null" { AudioBackend::Null } else { AudioBackend::Auto };
+            note("Audio backend", &audio.backend);
+        }
+        if let Some(volume) = number("Audio/Master Volume") {
+            audio.volume = (volume / 100.0).clamp(0.0, 1.0) as f32;
+            note("Volume", &audio.volume);
+        }
+        if let Some(duration) = number("Audio/Desired Audio Buffer Duration").filter(|&n| n > 0.0) {
+            audio.buffer_duration_ms = duration as u32;
+            note("Audio buffer", &audio.buffer_duration_ms);
+        }
+        if let Some(on) = flag("Audio/Enable Time Stretching") {
+            audio.time_stretching = on;
+            note("Time stretching", &on);
+        }
+        let layout = match value("Audio/Audio Format") {
+            Some("Stereo") => Some(AudioChannelLayout::Stereo),
+            Some("Surround 5.1") => Some(AudioChannelLayout::Surround51),
+            Some("Surround 7.1") => Some(AudioChannelLayout::Surround71),
+            _ => None,
+        };
+        if let Some(layout) = layout {
+            audio.channel_layout = layout;
+            note("Channel layout", &layout);
+        }
+
+        if let Some(on) = flag("Miscellaneous/Start games in paused mode") {
+            config.general.start_paused = on;
+            note("Start paused", &on);
+        }
+        changes
+    }
+
+    /// Add the folders of the games in games.yml to the scanned folders,
+    /// returning how many were added
+    pub fn add_games(&self, paths: &mut PathConfig) -> usize {
+        let mut added = 0;
+        for (_, dir) in &self.games {
+            if dir.is_dir() && !paths.extra_games.contains(dir) {
+                paths.extra_games.push(dir.clone());
+                added += 1;
+            }
+        }
+        added
+    }
+
+    /// Copy dev_hdd0 contents and disc keys into `paths`
+    ///
+    /// This can take a long time; run it off the UI thread.
+    pub fn copy_data(&self, paths: &PathConfig, options: ImportOptions, progress: &CopyProgress) -> CopyReport {
+        let mut jobs: Vec<(PathBuf, PathBuf)> = Vec::new();
+        if options.hdd0 {
+            for dir in HDD0_DIRS {
+                collect_files(&self.dev_hdd0.join(dir), &paths.dev_hdd0.join(dir), &mut jobs);
+            }
+        }
+        if options.disc_keys {
+            let keys = disc_key_dir(paths);
+            for key in &self.disc_keys {
+                if let Some(name) = key.file_name() {
+                    jobs.push((key.clone(), keys.join(name)));
+                }
+            }
+        }
+        progress.total.store(jobs.len() as u64, Ordering::Relaxed);
+
+        let mut report = CopyReport::default();
+        for (from, to) in jobs {
+            if progress.cancel.load(Ordering::Relaxed) {
+                report.cancelled = true;
+                break;
+            }
+            if to.exists() && !options.overwrite {
+                report.skipped += 1;
+            } else {
+                let copied = to
+                    .parent()
+                    .map_or(Ok(()), fs::create_dir_all)
+                    .and_then(|_| fs::copy(&from, &to));
+                match copied {
+                    Ok(_) => report.copied += 1,
+                    Err(e) => report.errors.push(format!("{}: {}", from.display(), e)),
+                }
+            }
+            progress.done.fetch_add(1, Ordering::Relaxed);
+        }
+        tracing::info!(
+            "Imported {} files from {} ({} skipped, {} failed)",
+            report.copied,
+            self.root.display(),
+            report.skipped,
+            report.errors.len()
+        );
+        report
+    }
+}
+
+/// Directory disc key files are copied into, next to dev_hdd0
+pub fn disc_key_dir(paths: &PathConfig) -> PathBuf {
+    paths.dev_hdd0.with_file_name("keys")
+}
+
+/// Every file under `from` with the path it gets under `to`
+fn collect_files(from: &Path, to: &Path, jobs: &mut Vec<(PathBuf, PathBuf)>) {
+    let Ok(entries) = fs::read_dir(from) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let target = to.join(entry.file_name());
+        if path.is_dir() {
+            collect_files(&path, &target, jobs);
+        } else {
+            jobs.push((path, target));
+        }
+    }
+}
+
+/// Disc key files in the installation and its direct subdirectories, except the HDDs
+fn find_disc_keys(root: &Path) -> Vec<PathBuf> {
+    let is_key = |path: &Path| {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| DISC_KEY_EXTENSIONS.iter().any(|key| ext.eq_ignore_ascii_case(key)))
+    };
+    let mut keys = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    if let Ok(entries) = fs::read_dir(root) {
+        dirs.extend(
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir() && !path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("dev_"))),
+        );
+    }
+    for dir in dirs {
+        if let Ok(entries) = fs::read_dir(&dir) {
+            keys.extend(entries.flatten().map(|entry| entry.path()).filter(|path| path.is_file() && is_key(path)));
+        }
+    }
+    keys.sort();
+    keys
+}
+
+/// Path from an RPCS3 file, which may start with `$(EmulatorDir)` or be relative to it
+fn resolve_path(root: &Path, path: &str) -> PathBuf {
+    match path.strip_prefix("$(EmulatorDir)") {
+        Some(rest) => root.join(rest.trim_start_matches(['/', '\\'])),
+        None => root.join(path),
+    }
+}
+
+/// Flatten the block mappings of a YAML file to `Parent/Key` entries
+///
+/// This covers what RPCS3 writes: nested mappings of plain or quoted
+/// scalars. Sequences and flow collections are skipped.
+fn parse_yaml(text: &str) -> BTreeMap<String, String> {
+    fn unquote(s: &str) -> String {
+        let s = s.trim();
+        for quote in ['"', '\''] {
+            if s.len() >= 2 && s.starts_with(quote) && s.ends_with(quote) {
+                return s[1..s.len() - 1].to_string();
+            }
+        }
+        s.to_string()
+    }
+
+    let mut entries = BTreeMap::new();
+    // Indentation and key of each open mapping
+    let mut parents: Vec<(usize, String)> = Vec::new();
+    for line in text.lines() {
+        let content = line.trim_start();
+        if content.is_empty() || content.starts_with('#') || content.starts_with("---") || content.starts_with('-') {
+            continue;
+        }
+        let indent = line.len() - content.len();
+        let (key, value) = match content.split_once(": ") {
+            Some((key, value)) => (key, value.trim()),
+            None => match content.strip_suffix(':') {
+                Some(key) => (key, ""),
+                None => continue,
+            },
+        };
+        while parents.last().is_some_and(|(parent_indent, _)| *parent_indent >= indent) {
+            parents.pop();
+        }
+        let key = unquote(key);
+        let full_key = parents
+            .iter()
+            .map(|(_, parent)| parent.as_str())
+            .chain(std::iter::once(key.as_str()))
+            .collect::<Vec<_>>()
+            .join("/");
+        if value.is_empty() {
+            parents.push((indent, key));
+        } else if !value.starts_with('[') && !value.starts_with('{') {
+            entries.insert(full_key, unquote(value));
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_yaml() {
+        let yaml = "Core:\n  PPU Decoder: Interpreter (static)\n  PPU LLVM Precompilation: true\n\
+                    Video:\n  Renderer: \"Null\"\n  Vulkan:\n    Adapter: \"\"\n  VSync: false\n\
+                    Log: {}\n\"$(EmulatorDir)\": \"\"\n/dev_hdd0/: $(EmulatorDir)dev_hdd0/\n";
+        let entries = parse_yaml(yaml);
+        assert_eq!(entries["Core/PPU Decoder"], "Interpreter (static)");
+        assert_eq!(entries["Video/Renderer"], "Null");
+        assert_eq!(entries["Video/VSync"], "false");
+        assert_eq!(entries["/dev_hdd0/"], "$(EmulatorDir)dev_hdd0/");
+        assert!(!entries.contains_key("Log"));
+    }
+
+    #[test]
+    fn test_import() {
+        let root = std::env::temp_dir().join(format!("oc_rpcs3_test_{}", std::process::id()));
+        let target = root.join("target");
+        let rpcs3 = root.join("rpcs3");
+        let save = rpcs3.join("dev_hdd0/home/00000001/savedata/BLUS30443-SAVE");
+        fs::create_dir_all(&save).unwrap();
+        fs::create_dir_all(rpcs3.join("config")).unwrap();
+        fs::create_dir_all(rpcs3.join("games/Game")).unwrap();
+        fs::write(save.join("SAVE.DAT"), b"save").unwrap();
+        fs::write(rpcs3.join("BLUS30443.dkey"), b"key").unwrap();
+        fs::write(
+            rpcs3.join("config/config.yml"),
+            "Core:\n  SPU Decoder: Recompiler (LLVM)\n  Preferred SPU Threads: 0\nVideo:\n  Resolution Scale: 150\n  Frame limit: Auto\nAudio:\n  Master Volume: 50\n",
+        )
+        .unwrap();
+        fs::write(rpcs3.join("config/games.yml"), "BLUS30443: $(EmulatorDir)games/Game/\n").unwrap();
+
+        let install = Rpcs3Install::open(&rpcs3).unwrap();
+        assert_eq!(install.save_count(), 1);
+        assert_eq!(install.disc_keys.len(), 1);
+
+        let mut config = Config::default();
+        let changes = install.apply_settings(&mut config);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(config.gpu.resolution_scale, 150);
+        assert_eq!(config.cpu.spu_threads, Config::default().cpu.spu_threads);
+        assert!((config.audio.volume - 0.5).abs() < f32::EPSILON);
+
+        config.paths.dev_hdd0 = target.join("dev_hdd0");
+        assert_eq!(install.add_games(&mut config.paths), 1);
+        assert_eq!(install.add_games(&mut config.paths), 0);
+        assert_eq!(config.paths.extra_games, [rpcs3.join("games/Game/")]);
+
+        let progress = CopyProgress::default();
+        let report = install.copy_data(&config.paths, ImportOptions::default(), &progress);
+        assert_eq!((report.copied, report.skipped), (2, 0));
+        assert_eq!(progress.done.load(Ordering::Relaxed), 2);
+        assert!(target.join("dev_hdd0/home/00000001/savedata/BLUS30443-SAVE/SAVE.DAT").is_file());
+        assert!(target.join("keys/BLUS30443.dkey").is_file());
+
+        // Existing files are kept unless asked to overwrite them
+        let report = install.copy_data(&config.paths, ImportOptions::default(), &CopyProgress::default());
+        assert_eq!((report.copied, report.skipped), (0, 2));
+
+        assert!(Rpcs3Install::open(&target.join("missing")).is_err());
+        fs::remove_dir_all(&root).ok();
+    }
+}
@@ -8,17 +8,96 @@
 //! - LV2 kernel syscalls
 //! - Thread scheduler
 
+use crate::autosave::Autosaver;
+use crate::caches::CacheKind;
+use crate::capture::{self, AudioMux, VideoRecorder};
+use crate::interrupts::{HandlerCall, InterruptOwner, InterruptThreads};
+use crate::av_sync::{
+    AvSyncConfig, AvSyncGovernor, AvSyncStats, AUDIO_BLOCK_SAMPLES, AUDIO_SAMPLE_RATE, VBLANK_HZ_NTSC, VBLANK_HZ_PAL,
+};
 use crate::loader::{GameLoader, LoadedGame};
-use oc_core::{Config, EmulatorError, Result, Scheduler, ThreadId, ThreadState};
-use oc_memory::MemoryManager;
+use crate::missing_features::{missing_functions, MissingFeature, MissingFeatureReport};
+use crate::pacing::{FrameLimiter, SpeedMode};
+use crate::perf::{PerfMonitor, PerfStats};
+use crate::replay::ReplaySession;
+use crate::savestate::{Savestate, SavestateInfo, Thumbnail};
+use crate::texture_pack;
+use crate::usb_devices::{PassthroughUsbBackend, VirtualUsbBackend};
+use oc_core::config::{
+    AudioConfig, AudioDumpFormat, CaptureConfig, ConfigWatcher, DebugConfig, GeneralConfig, GpuConfig, InputConfig,
+    MoveSource, NetCaptureMode, PathConfig,
+};
+use oc_core::{Config, EmulatorError, Result, Scheduler, ThreadId, ThreadState, TimeBase};
+use oc_memory::{MemoryManager, MemorySnapshot, RSX_MEM_BASE, RSX_MEM_SIZE};
+use oc_core::error::{PpuError, PpuExceptionType};
+use oc_core::savestate::invalid;
+use oc_core::time_base::TIMEBASE_FREQUENCY;
+use oc_debug::gdb_stub::{ppu_registers, set_ppu_register, set_spu_register, spu_registers};
+use oc_debug::{
+    format_backtrace, unwind_ppu, CheatManager, Coverage, CrashDump, CrashKind, CrashReport, FunctionMap, HookAction,
+    ModuleRange, PatchManager, Profiler, GdbArch, GdbServer, GdbTarget, GdbThread, RsxDebugger, SpuDebugger, StackFrame,
+    StopReason,
+    ThreadDump, TraceConfig, TraceRecorder, TraceTrigger,
+};
 use oc_ppu::{PpuInterpreter, PpuThread};
 use oc_spu::{SpuInterpreter, SpuThread};
+use oc_rsx::scaling::RenderScale;
 use oc_rsx::RsxThread;
+use oc_loader::ImportedLibrary;
+use oc_lv2::bluetooth::BtDevice;
+use oc_lv2::interrupt::{InterruptSource, RSX_INT_STAT_VBLANK, SPU_INT2_STAT_MAILBOX_INT};
+use oc_lv2::ss::ConsoleIdentity;
+use oc_lv2::syscall_numbers::SYS_INTERRUPT_THREAD_EOI;
+use oc_lv2::usbd::UsbBackend;
 use oc_lv2::SyscallHandler;
-use std::path::Path;
+use oc_vfs::{DiscManager, VfsAccessReport};
+use oc_hle::cell_adec::CellAdecCodecType;
+use oc_hle::cell_game::{ContentErrorDialog, GameInstallInfo};
+use oc_hle::cell_osk_dialog::OskRequest;
+use oc_hle::net_capture::{self, NetCapture};
+use oc_hle::net_hosts::HostTable;
+use oc_audio::backend::{create_backend, AudioBackend, BackendOptions};
+use oc_audio::mixer::{ChannelLayout, SourceId};
+use oc_audio::music::MusicPlayer;
+use oc_audio::time_stretch::DynamicRateConfig;
+use oc_audio::{AudioMixer, AudioRecorder, AudioRingBuffer};
+use oc_input::usb::known_devices;
+use oc_input::{
+    BluetoothManager, CameraManager, CameraType, Ds3HidBackend, Ds4ExtraMapping, Ds4HidBackend, Ds4Model, GamepadBackend,
+    HostGamepadInfo, HostInput, InputProfile, InstrumentMapping, KeyboardPad, MappedInstrument, MidiInputBackend,
+    MoveManager, PadPorts, UsbPassthrough, VirtualMove, WebcamCapture,
+};
+use oc_input::bluetooth::BluetoothState;
+use oc_input::keyboard_pad::KEYBOARD_PAD_NAME;
+use oc_input::usb::parse_usb_id;
+use oc_input::pad::MAX_PADS;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
-use parking_lot::RwLock;
+use std::time::Instant;
+use parking_lot::{Mutex, RwLock};
+
+/// Game data the system installs per frame (KB), about 1 GB/s at 60 Hz
+const DATA_INSTALL_KB_PER_FRAME: u64 = 16 * 1024;
+
+/// PPU time slice the decrementer is loaded with (1 ms)
+const PPU_TIME_SLICE_TICKS: u32 = (TIMEBASE_FREQUENCY / 1000) as u32;
+
+/// Priority of the thread GCM vblank and flip handlers run on, above the game's threads
+const GCM_INTERRUPT_PRIORITY: u32 = 1;
+
+/// Stack size of the GCM handler thread
+const GCM_INTERRUPT_STACK_SIZE: u32 = 0x4000;
+
+/// Display head GCM handlers are called with
+const GCM_DISPLAY_HEAD: u64 = 1;
+
+/// Priority of the thread HLE callbacks run on, above the game's threads
+const HLE_CALLBACK_PRIORITY: u32 = 1;
+
+/// Stack size of the HLE callback thread
+const HLE_CALLBACK_STACK_SIZE: u32 = 0x10000;
 
 /// Emulator runner state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -35,6 +114,8 @@ pub enum RunnerState {
 pub struct EmulatorRunner {
     /// Configuration
     config: Config,
+    /// Settings changes to apply live (None unless watched)
+    config_watcher: Option<ConfigWatcher>,
     /// Current state
     state: RunnerState,
     /// Shared memory manager
@@ -43,6 +124,10 @@ pub struct EmulatorRunner {
     ppu_threads: RwLock<Vec<Arc<RwLock<PpuThread>>>>,
     /// PPU interpreter
     ppu_interpreter: Arc<PpuInterpreter>,
+    /// Function names of the loaded game, for backtraces
+    functions: RwLock<FunctionMap>,
+    /// Libraries the loaded game imports, for the missing feature report
+    imports: RwLock<Vec<ImportedLibrary>>,
     /// SPU threads
     spu_threads: RwLock<Vec<Arc<RwLock<SpuThread>>>>,
     /// SPU interpreter
@@ -51,16 +136,103 @@ pub struct EmulatorRunner {
     rsx_thread: Arc<RwLock<RsxThread>>,
     /// LV2 syscall handler
     syscall_handler: Arc<SyscallHandler>,
+    /// Emulated clock shared by PPU threads, LV2, GCM vblank and cellAudio
+    time_base: TimeBase,
     /// Thread scheduler
     scheduler: Arc<RwLock<Scheduler>>,
+    /// PPU threads interrupt handlers run on
+    interrupts: Mutex<InterruptThreads>,
+    /// Whether an interrupt source fired since a PPU thread last took an external interrupt
+    interrupt_pending: AtomicBool,
+    /// Final audio mixer
+    audio_mixer: Arc<Mutex<AudioMixer>>,
+    /// Audio dump recorder tapping the mixer
+    audio_recorder: Arc<Mutex<AudioRecorder>>,
+    /// Mixed audio waiting for the output device
+    audio_ring: Arc<AudioRingBuffer>,
+    /// Custom soundtrack picked through cellMusic
+    music_player: MusicPlayer,
+    /// Mixer source the custom soundtrack plays through
+    music_source: SourceId,
+    /// cellMusic track generation `music_player` was loaded for
+    music_generation: u64,
+    /// Mixer source SSPlayer and MultiStream voices play through
+    voice_source: SourceId,
+    /// Audio output device (None until init_audio)
+    audio_backend: Option<Box<dyn AudioBackend>>,
+    /// Paces frames and audio blocks against a shared clock
+    av_sync: AvSyncGovernor,
+    /// RSX flip count at the last vblank
+    rsx_flip_count: u64,
+    /// Speed emulation is paced to
+    limiter: FrameLimiter,
+    /// Frame times and thread utilization for the performance overlay
+    perf: PerfMonitor,
+    /// Controller ports read by cellPad
+    pad_ports: Arc<PadPorts>,
+    /// Host gamepads (None until init_input)
+    gamepads: Option<GamepadBackend>,
+    /// DualShock 3 HID passthrough (None unless enabled)
+    ds3_hid: Option<Ds3HidBackend>,
+    /// DualShock 4 / DualSense HID passthrough (None unless enabled)
+    ds4_hid: Option<Ds4HidBackend>,
+    /// Keyboard/mouse pad emulation and its port (None unless enabled)
+    keyboard_pad: Option<(u8, KeyboardPad)>,
+    /// Guitar/drums played on the keyboard or a MIDI kit, and its port (None unless enabled)
+    instrument: Option<(u8, MappedInstrument)>,
+    /// MIDI input device feeding the instrument, with the device setting it was opened for
+    midi: Option<(String, MidiInputBackend)>,
+    /// PlayStation Move controllers read by cellGem
+    move_manager: MoveManager,
+    /// Bluetooth pairings of the emulated controllers, listed by sys_usbbtaudio
+    bluetooth: BluetoothManager,
+    /// Driver for the emulated Move on slot 0 (None unless enabled)
+    virtual_move: Option<VirtualMove>,
+    /// PlayStation Eye cameras read by cellCamera
+    camera_manager: CameraManager,
+    /// Webcam setting the camera on slot 0 was connected with (None unless enabled)
+    camera_device: Option<String>,
+    /// GDB servers for PPU threads and raw SPUs (None unless enabled)
+    gdb_ppu: Option<GdbServer>,
+    gdb_spu: Option<GdbServer>,
+    /// Thread to run one instruction of before stopping for the debugger again
+    debug_step: Option<ThreadId>,
+    /// Thread the debugger last stopped at, which must not stop at the same breakpoint on resume
+    debug_resume_from: Option<ThreadId>,
+    /// Instruction trace being recorded (None unless started)
+    trace: Mutex<Option<TraceRecorder>>,
+    /// Cheats of the loaded game, applied every frame
+    cheats: Arc<Mutex<CheatManager>>,
+    /// Profiler sampling PPU call stacks while enabled
+    profiler: Arc<Mutex<Profiler>>,
+    /// Code patches and hooks of the loaded game
+    patches: Arc<Mutex<PatchManager>>,
+    /// SPU breakpoints, local storage snapshots and channel activity
+    spu_debugger: Arc<Mutex<SpuDebugger>>,
+    /// RSX method breakpoints, command history and draw calls
+    rsx_debugger: Arc<Mutex<RsxDebugger>>,
+    /// Title ID of the loaded game, for crash dumps
+    title_id: Option<String>,
+    /// Executable and PRX modules of the loaded game, for crash dumps and coverage
+    modules: RwLock<Vec<ModuleRange>>,
+    /// Basic block coverage being recorded and the file it goes to (None unless started)
+    coverage: Mutex<Option<(Coverage, PathBuf)>>,
+    /// Last crash, until the UI takes it
+    last_crash: Mutex<Option<CrashReport>>,
+    /// Run being recorded or replayed (None unless started)
+    replay: Mutex<Option<ReplaySession>>,
+    /// Video being recorded, and whether its audio dump was started for it
+    video: Option<(VideoRecorder, bool)>,
+    /// Background savestates of the running game (None unless enabled)
+    autosave: Option<Autosaver>,
+    /// Disc in the drive, mounted at /dev_bdvd
+    disc: DiscManager,
     /// Frame counter
     frame_count: u64,
     /// Total cycles executed
     total_cycles: u64,
     /// Last frame time
     last_frame_time: Instant,
-    /// Target frame time (16.67ms for 60 FPS)
-    target_frame_time: Duration,
 }
 
 impl EmulatorRunner {
@@ -79,35 +251,219 @@ impl EmulatorRunner {
         let spu_interpreter = Arc::new(SpuInterpreter::new());
 
         // Create RSX thread
-        let rsx_thread = Arc::new(RwLock::new(RsxThread::new(memory.clone())));
+        let mut rsx_thread = RsxThread::new(memory.clone());
+        rsx_thread.set_render_scale(RenderScale::new(config.gpu.resolution_scale as f32));
+        let rsx_thread = Arc::new(RwLock::new(rsx_thread));
 
         // Create syscall handler
-        let syscall_handler = Arc::new(SyscallHandler::new());
+        let mut syscall_handler = SyscallHandler::new();
+        syscall_handler.set_guest_memory(memory.clone());
+        oc_hle::set_guest_memory(memory.clone());
+        syscall_handler.set_console(ConsoleIdentity::from_config(
+            &config.general.console_id,
+            config.general.console_region,
+        ));
+        let syscall_handler = Arc::new(syscall_handler);
+        syscall_handler.vfs().tracer().set_enabled(config.debug.trace_vfs);
+        // Guest time stands still until the emulator starts
+        let time_base = syscall_handler.time_base().clone();
+        time_base.pause();
+        Self::configure_hle_general(&config.general);
+        Self::configure_hle_paths(&config.paths);
+        Self::configure_hle_video(&config.gpu);
 
         // Create scheduler
         let scheduler = Arc::new(RwLock::new(Scheduler::new()));
 
-        // Target 60 FPS
-        let target_frame_time = Duration::from_micros(16667);
+        // Create audio mixer with the dump recorder attached
+        let mut audio_recorder = AudioRecorder::new(&config.audio.dump_path, config.audio.dump_format);
+        audio_recorder.set_record_ports(config.audio.dump_ports);
+        let audio_recorder = Arc::new(Mutex::new(audio_recorder));
+        let mut audio_mixer = AudioMixer::new(config.audio.channel_layout.into());
+        audio_mixer.set_downmix(config.audio.downmix);
+        audio_mixer.set_master_volume(config.audio.volume);
+        audio_mixer.set_recorder(audio_recorder.clone());
+        let music_source = audio_mixer.add_source(ChannelLayout::Stereo);
+        let voice_source = audio_mixer.add_source(ChannelLayout::Stereo);
+        let audio_ring = Arc::new(Self::new_audio_ring(&config, audio_mixer.output_layout()));
+        let audio_mixer = Arc::new(Mutex::new(audio_mixer));
+        let limiter = FrameLimiter::new(&config.gpu);
+        let av_sync = AvSyncGovernor::new(AvSyncConfig {
+            vblank_hz: Self::vblank_hz(&config.gpu),
+            ..AvSyncConfig::default()
+        });
 
         Ok(Self {
             config,
+            config_watcher: None,
             state: RunnerState::Stopped,
             memory,
             ppu_threads: RwLock::new(Vec::new()),
             ppu_interpreter,
+            functions: RwLock::new(FunctionMap::new()),
+            imports: RwLock::new(Vec::new()),
             spu_threads: RwLock::new(Vec::new()),
             spu_interpreter,
             rsx_thread,
             syscall_handler,
+            time_base,
             scheduler,
+            interrupts: Mutex::new(InterruptThreads::new()),
+            interrupt_pending: AtomicBool::new(false),
+            audio_mixer,
+            audio_recorder,
+            audio_ring,
+            music_player: MusicPlayer::new(AUDIO_SAMPLE_RATE),
+            music_source,
+            music_generation: 0,
+            voice_source,
+            audio_backend: None,
+            av_sync,
+            rsx_flip_count: 0,
+            limiter,
+            perf: PerfMonitor::new(),
+            pad_ports: Arc::new(PadPorts::new()),
+            gamepads: None,
+            ds3_hid: None,
+            ds4_hid: None,
+            keyboard_pad: None,
+            instrument: None,
+            midi: None,
+            move_manager: MoveManager::new(),
+            bluetooth: BluetoothManager::new(),
+            virtual_move: None,
+            camera_manager: CameraManager::new(),
+            camera_device: None,
+            gdb_ppu: None,
+            gdb_spu: None,
+            debug_step: None,
+            debug_resume_from: None,
+            trace: Mutex::new(None),
+            cheats: Arc::new(Mutex::new(CheatManager::new())),
+            profiler: Arc::new(Mutex::new(Profiler::new())),
+            patches: Arc::new(Mutex::new(PatchManager::new())),
+            spu_debugger: Arc::new(Mutex::new(SpuDebugger::new())),
+            rsx_debugger: Arc::new(Mutex::new(RsxDebugger::new())),
+            title_id: None,
+            modules: RwLock::new(Vec::new()),
+            coverage: Mutex::new(None),
+            last_crash: Mutex::new(None),
+            replay: Mutex::new(None),
+            video: None,
+            autosave: None,
+            disc: DiscManager::new(),
             frame_count: 0,
             total_cycles: 0,
             last_frame_time: Instant::now(),
-            target_frame_time,
         })
     }
 
+    /// Apply the general settings HLE libraries follow
+    fn configure_hle_general(general: &GeneralConfig) {
+        let mut hle = oc_hle::get_hle_context_mut();
+        hle.bgdl.set_complete_downloads(general.complete_background_downloads);
+        hle.game.set_hdd_free_size_kb(u64::from(general.hdd_free_space_gb) * 1024 * 1024);
+        hle.game.set_copy_disc_data(general.copy_disc_game_data);
+    }
+
+    /// Apply the video standard games see through cellVideoOut
+    fn configure_hle_video(gpu: &GpuConfig) {
+        oc_hle::get_hle_context_mut().video_out.set_pal(gpu.pal_50hz);
+    }
+
+    /// Vblank rate of the configured video standard
+    fn vblank_hz(gpu: &GpuConfig) -> f64 {
+        if gpu.pal_50hz {
+            VBLANK_HZ_PAL
+        } else {
+            VBLANK_HZ_NTSC
+        }
+    }
+
+    /// Point the HLE game data, media and download libraries at their host folders
+    fn configure_hle_paths(paths: &PathConfig) {
+        let mut hle = oc_hle::get_hle_context_mut();
+        hle.export.set_hdd0_root(paths.dev_hdd0.clone());
+        hle.bgdl.set_hdd0_root(paths.dev_hdd0.clone());
+        hle.game.set_hdd0_root(paths.dev_hdd0.clone());
+        hle.search.set_hdd0_root(paths.dev_hdd0.clone());
+        hle.music.set_folder(paths.music.clone());
+    }
+
+    /// Create the output ring buffer sized from the configured latency
+    fn new_audio_ring(config: &Config, layout: ChannelLayout) -> AudioRingBuffer {
+        let frames = (config.audio.buffer_duration_ms.max(10) * AUDIO_SAMPLE_RATE / 1000) as usize;
+        AudioRingBuffer::new(frames, layout.num_channels())
+    }
+
+    /// Open the audio output device and start playback
+    ///
+    /// Falls back to silent output if no device can be opened. The mixer
+    /// is switched to the device's channel layout and resampled to its rate.
+    pub fn init_audio(&mut self) {
+        if !self.config.audio.enable || self.audio_backend.is_some() {
+            return;
+        }
+
+        let options = BackendOptions::from_config(&self.config.audio);
+        let mut backend = create_backend(self.config.audio.backend, &options);
+        let device_rate = backend.sample_rate().unwrap_or(AUDIO_SAMPLE_RATE);
+        let channels = backend.channels().unwrap_or(options.channels) as usize;
+
+        let layout = ChannelLayout::for_device(channels);
+        self.audio_ring = Arc::new(Self::new_audio_ring(&self.config, layout));
+        {
+            let mut mixer = self.audio_mixer.lock();
+            mixer.set_output_layout(layout);
+            let pitch_tolerance = if self.config.audio.time_stretching {
+                DynamicRateConfig::default().pitch_tolerance
+            } else {
+                0.0
+            };
+            mixer.enable_dynamic_rate(
+                AUDIO_SAMPLE_RATE,
+                device_rate,
+                DynamicRateConfig { pitch_tolerance, ..Default::default() },
+            );
+        }
+
+        backend.set_ring_buffer(self.audio_ring.clone());
+        if let Err(e) = backend.start() {
+            tracing::warn!("Failed to start {} audio output: {}", backend.name(), e);
+        }
+        self.audio_backend = Some(backend);
+    }
+
+    /// Apply new audio settings, reopening the output device if it changed
+    ///
+    /// Volume and downmix apply at once. The channel layout presented to
+    /// games and the dump settings wait for the next boot.
+    pub fn configure_audio(&mut self, audio: &AudioConfig) {
+        {
+            let mut mixer = self.audio_mixer.lock();
+            mixer.set_downmix(audio.downmix);
+            mixer.set_master_volume(audio.volume);
+        }
+        let old = &self.config.audio;
+        let reopen = old.enable != audio.enable
+            || old.backend != audio.backend
+            || old.buffer_duration_ms != audio.buffer_duration_ms
+            || old.time_stretching != audio.time_stretching
+            || old.output_device != audio.output_device
+            || old.exclusive_mode != audio.exclusive_mode;
+        self.config.audio = audio.clone();
+        if !reopen {
+            return;
+        }
+        if let Some(mut backend) = self.audio_backend.take() {
+            if let Err(e) = backend.stop() {
+                tracing::warn!("Failed to stop {} audio output: {}", backend.name(), e);
+            }
+        }
+        self.init_audio();
+        tracing::info!("Audio output reopened");
+    }
+
     /// Initialize the RSX graphics backend
     pub fn init_graphics(&mut self) -> Result<()> {
         let mut rsx = self.rsx_thread.write();
@@ -117,6 +473,392 @@ impl EmulatorRunner {
             ))
     }
     
+    /// Start reading host gamepads and connect them to cellPad
+    pub fn init_input(&mut self) {
+        if self.gamepads.is_some() {
+            return;
+        }
+        // Pins must be in place before the gamepad backend claims ports
+        let input = self.config.input.clone();
+        self.pad_ports.set_pins(&Self::port_pins(&input));
+        let mut gamepads = GamepadBackend::new(self.pad_ports.clone());
+        if self.config.input.controller.ds3_passthrough {
+            // DS3s are read over HID instead of through the generic gamepad path
+            let (vid, pid) = known_devices::DUALSHOCK3;
+            gamepads.ignore_device(vid, pid);
+            self.ds3_hid = Some(Ds3HidBackend::new(self.pad_ports.clone()));
+        }
+        if self.config.input.controller.ds4_passthrough {
+            for (vid, pid) in Ds4Model::DEVICES {
+                gamepads.ignore_device(vid, pid);
+            }
+            let mapping = Ds4ExtraMapping::from_config(&self.config.input.controller.ds4_extra);
+            self.ds4_hid = Some(Ds4HidBackend::new(self.pad_ports.clone(), mapping));
+        }
+        let mut usb_backends: Vec<Box<dyn UsbBackend>> = Vec::new();
+        if self.config.input.controller.usb_passthrough {
+            let extra: Vec<(u16, u16)> = self
+                .config
+                .input
+                .controller
+                .usb_passthrough_devices
+                .iter()
+                .filter(|id| !id.trim().is_empty())
+                .filter_map(|id| {
+                    let parsed = parse_usb_id(id);
+                    if parsed.is_none() {
+                        tracing::warn!("Ignoring USB passthrough device \"{}\": expected VID:PID in hex", id);
+                    }
+                    parsed
+                })
+                .collect();
+            let passthrough = UsbPassthrough::new(&extra);
+            // Passed-through devices belong to the game's own driver, not cellPad
+            for &(vid, pid) in passthrough.allowed() {
+                gamepads.ignore_device(vid, pid);
+            }
+            usb_backends.push(Box::new(PassthroughUsbBackend::new(passthrough)));
+        }
+        if self.config.input.controller.virtual_buzz {
+            usb_backends.push(Box::new(VirtualUsbBackend::new(self.pad_ports.clone())));
+        }
+        self.syscall_handler.usbd().set_backends(usb_backends);
+        self.gamepads = Some(gamepads);
+        self.configure_ports(&input);
+        self.configure_feedback(&input);
+        self.configure_keyboard_pad(&input);
+        self.configure_instrument(&input);
+        self.configure_move(&input);
+        self.configure_camera(&input);
+        oc_hle::get_hle_context_mut()
+            .pad
+            .connect_input_backend(Some(self.pad_ports.clone()));
+    }
+
+    /// Apply the port assignments and per-port profiles
+    ///
+    /// Pins only affect controllers connected afterwards; use
+    /// [`reassign_ports`](Self::reassign_ports) to move connected ones.
+    pub fn configure_ports(&mut self, input: &InputConfig) {
+        self.pad_ports.set_pins(&Self::port_pins(input));
+        self.pad_ports.set_gyro_offsets(&input.controller.gyro_offsets);
+        if let Some(gamepads) = self.gamepads.as_mut() {
+            for port in 0..MAX_PADS as u8 {
+                gamepads.set_port_profile(port, InputProfile::for_port(input, port));
+                gamepads.set_port_instrument(port, InstrumentMapping::for_port(input, port));
+            }
+        }
+    }
+
+    /// Apply the port assignments and move every controller to its port
+    ///
+    /// Games see each controller disconnect and reconnect. Passthrough
+    /// controllers come back on the next frame.
+    pub fn reassign_ports(&mut self, input: &InputConfig) {
+        self.configure_ports(input);
+        if let Some(ds3_hid) = self.ds3_hid.as_mut() {
+            ds3_hid.release_all();
+        }
+        if let Some(ds4_hid) = self.ds4_hid.as_mut() {
+            ds4_hid.release_all();
+        }
+        if let Some((port, _)) = self.keyboard_pad.take() {
+            self.pad_ports.disconnect(port);
+        }
+        if let Some((port, _)) = self.instrument.take() {
+            self.pad_ports.disconnect(port);
+        }
+        if let Some(gamepads) = self.gamepads.as_mut() {
+            gamepads.release_all();
+            gamepads.attach_all();
+        }
+        self.configure_keyboard_pad(input);
+        self.configure_instrument(input);
+    }
+
+    /// Device names pinned to each port
+    fn port_pins(input: &InputConfig) -> Vec<Option<String>> {
+        input
+            .controller
+            .ports
+            .iter()
+            .map(|port| Some(port.device.clone()).filter(|device| !device.trim().is_empty()))
+            .collect()
+    }
+
+    /// Choose whether game rumble and player light bars reach host controllers
+    pub fn configure_feedback(&mut self, input: &InputConfig) {
+        let controller = &input.controller;
+        if let Some(gamepads) = self.gamepads.as_mut() {
+            gamepads.set_rumble_enabled(controller.rumble);
+        }
+        if let Some(ds3_hid) = self.ds3_hid.as_mut() {
+            ds3_hid.set_rumble_enabled(controller.rumble);
+        }
+        if let Some(ds4_hid) = self.ds4_hid.as_mut() {
+            ds4_hid.set_feedback(controller.rumble, controller.light_bar);
+        }
+    }
+
+    /// Enable, disable or reconfigure keyboard/mouse pad emulation
+    ///
+    /// The emulated pad takes the port pinned to it, or the lowest free
+    /// port after the host gamepads.
+    pub fn configure_keyboard_pad(&mut self, input: &InputConfig) {
+        if !input.keyboard_pad.enabled {
+            if let Some((port, _)) = self.keyboard_pad.take() {
+                self.pad_ports.disconnect(port);
+            }
+            return;
+        }
+        let port = match self.keyboard_pad.as_ref() {
+            Some(&(port, _)) => port,
+            None => match self.pad_ports.connect_device(KEYBOARD_PAD_NAME) {
+                Some(port) => {
+                    tracing::info!("Keyboard/mouse pad connected on port {}", port);
+                    port
+                }
+                None => {
+                    tracing::warn!("Keyboard/mouse pad enabled but all pad ports are in use");
+                    return;
+                }
+            },
+        };
+        let pad = KeyboardPad::new(&input.keyboard_mapping, &input.keyboard_pad);
+        self.keyboard_pad = Some((port, pad));
+    }
+
+    /// Keyboard/mouse pad emulation, for feeding host input events
+    pub fn keyboard_pad_mut(&mut self) -> Option<&mut KeyboardPad> {
+        self.keyboard_pad.as_mut().map(|(_, pad)| pad)
+    }
+
+    /// Enable, disable or reconfigure the guitar or drum kit played on the keyboard or MIDI
+    ///
+    /// The instrument takes the port pinned to it, or the lowest free port.
+    pub fn configure_instrument(&mut self, input: &InputConfig) {
+        let config = &input.instruments;
+        let mapping = InstrumentMapping::from_config(config.device, config);
+        // Switching between guitar and drums reconnects under the other device name
+        let kind = self.instrument.as_ref().map(|(_, instrument)| instrument.kind());
+        if kind.is_some() && kind != Some(config.device) {
+            if let Some((port, _)) = self.instrument.take() {
+                self.pad_ports.disconnect(port);
+            }
+        }
+        let Some(mapping) = mapping else {
+            self.midi = None;
+            return;
+        };
+
+        let port = match self.instrument.as_ref() {
+            Some(&(port, _)) => port,
+            None => match self.pad_ports.connect_device(MappedInstrument::device_name(config.device)) {
+                Some(port) => {
+                    tracing::info!("Emulated {:?} connected on port {}", config.device, port);
+                    port
+                }
+                None => {
+                    tracing::warn!("Instrument enabled but all pad ports are in use");
+                    return;
+                }
+            },
+        };
+        self.pad_ports.set_instrument(port, config.device);
+        self.instrument = Some((port, MappedInstrument::new(mapping)));
+
+        if !config.midi {
+            self.midi = None;
+        } else if !matches!(&self.midi, Some((device, _)) if *device == config.midi_device) {
+            self.midi = match MidiInputBackend::open(&config.midi_device) {
+                Ok(midi) => Some((config.midi_device.clone(), midi)),
+                Err(e) => {
+                    tracing::warn!("{}", e);
+                    None
+                }
+            };
+        }
+    }
+
+    /// Guitar/drums played on the keyboard, for feeding host input events
+    pub fn instrument_mut(&mut self) -> Option<&mut MappedInstrument> {
+        self.instrument.as_mut().map(|(_, instrument)| instrument)
+    }
+
+    /// Name of the open MIDI input device
+    pub fn midi_device(&self) -> Option<&str> {
+        self.midi.as_ref().map(|(_, midi)| midi.device())
+    }
+
+    /// Enable, disable or reconfigure the emulated PlayStation Move
+    ///
+    /// The emulated Move always occupies slot 0. Reconfiguring keeps its
+    /// calibration so a running title does not have to calibrate again.
+    /// The pointer response comes from the loaded title's input profile.
+    pub fn configure_move(&mut self, input: &InputConfig) {
+        let config = &input.move_controller;
+        if config.source == MoveSource::Off {
+            if self.virtual_move.take().is_some() {
+                self.move_manager.disconnect(0);
+                self.move_manager.set_camera_available(false);
+            }
+            return;
+        }
+        if matches!(config.source, MoveSource::Ds4 | MoveSource::Ds4Pointer) && self.ds4_hid.is_none() {
+            tracing::warn!("Move emulation from a DS4 needs DS4/DualSense passthrough enabled");
+        }
+        if self.move_manager.get(0).is_none() {
+            // Slot 0 is always free, so connecting cannot fail
+            let _ = self.move_manager.connect(0);
+            // The emulated sphere is always "seen" by the camera
+            self.move_manager.set_camera_available(true);
+        }
+        let pointer = InputProfile::for_title(input, self.title_id.as_deref()).pointer;
+        self.virtual_move = Some(VirtualMove::new(config, &input.keyboard_mapping, pointer));
+    }
+
+    /// Emulated Move driver, for feeding host input events
+    pub fn virtual_move_mut(&mut self) -> Option<&mut VirtualMove> {
+        self.virtual_move.as_mut()
+    }
+
+    /// PlayStation Move controllers
+    pub fn move_manager(&self) -> &MoveManager {
+        &self.move_manager
+    }
+
+    /// PlayStation Move controllers, for cellGem calibration requests
+    pub fn move_manager_mut(&mut self) -> &mut MoveManager {
+        &mut self.move_manager
+    }
+
+    /// Enable, disable or reconfigure the emulated PlayStation Eye
+    ///
+    /// The camera on slot 0 takes frames from the configured webcam, or
+    /// shows a test pattern if it cannot be opened.
+    pub fn configure_camera(&mut self, input: &InputConfig) {
+        let config = &input.camera;
+        if !config.enabled {
+            if self.camera_device.take().is_some() {
+                self.camera_manager.disconnect(0);
+            }
+            return;
+        }
+        if self.camera_device.as_ref() == Some(&config.device) {
+            return;
+        }
+
+        self.camera_manager.disconnect(0);
+        if let Err(e) = self.camera_manager.connect(0, CameraType::PlayStationEye) {
+            tracing::warn!("Failed to connect the PlayStation Eye: {}", e);
+            return;
+        }
+        if let Some(camera) = self.camera_manager.get_mut(0) {
+            let resolution = camera.settings.resolution;
+            match WebcamCapture::open(&config.device, resolution.width(), resolution.height(), camera.settings.framerate) {
+                Ok(webcam) => camera.attach_webcam(webcam),
+                Err(e) => tracing::warn!("{}; the PlayStation Eye shows a test pattern", e),
+            }
+        }
+        self.camera_device = Some(config.device.clone());
+    }
+
+    /// PlayStation Eye cameras
+    pub fn camera_manager(&self) -> &CameraManager {
+        &self.camera_manager
+    }
+
+    /// PlayStation Eye cameras, for cellCamera capture requests
+    pub fn camera_manager_mut(&mut self) -> &mut CameraManager {
+        &mut self.camera_manager
+    }
+
+    /// Hand the game the Bluetooth devices behind the connected pads and Moves
+    fn sync_bluetooth(&mut self) {
+        let pads = self.pad_ports.connected_mask();
+        let moves = self.move_manager.connected_mask();
+        if !self.bluetooth.sync_emulated(pads, moves) {
+            return;
+        }
+        let mut devices: Vec<BtDevice> = self
+            .bluetooth
+            .adapter()
+            .get_paired_devices()
+            .into_iter()
+            .map(|dev| BtDevice {
+                address: dev.address.bytes(),
+                class_of_device: dev.device_class,
+                name: dev.name.clone(),
+                paired: dev.paired,
+                connected: dev.state == BluetoothState::Connected,
+            })
+            .collect();
+        devices.sort_by_key(|dev| dev.address);
+        self.syscall_handler.bluetooth().set_devices(devices);
+    }
+
+    /// Switch the host gamepad mapping profile
+    pub fn set_input_profile(&mut self, profile: InputProfile) {
+        if let Some(gamepads) = self.gamepads.as_mut() {
+            gamepads.set_profile(profile);
+        }
+    }
+
+    /// Apply input settings: the mapping profile of the loaded title and every pad port
+    pub fn configure_input(&mut self, input: &InputConfig) {
+        let profile = InputProfile::for_title(input, self.title_id.as_deref());
+        self.set_input_profile(profile);
+        self.configure_ports(input);
+        self.configure_feedback(input);
+        self.configure_keyboard_pad(input);
+        self.configure_instrument(input);
+        self.configure_move(input);
+        self.configure_camera(input);
+    }
+
+    /// Take the most recent physical gamepad input, for binding
+    ///
+    /// Polls the controllers itself while emulation is not running, which
+    /// also keeps the pad ports live for the controller test view.
+    pub fn capture_host_input(&mut self) -> Option<HostInput> {
+        if self.state != RunnerState::Running {
+            if let Some(ds3_hid) = self.ds3_hid.as_mut() {
+                ds3_hid.poll();
+            }
+            if let Some(ds4_hid) = self.ds4_hid.as_mut() {
+                ds4_hid.poll();
+            }
+        }
+        let gamepads = self.gamepads.as_mut()?;
+        if self.state != RunnerState::Running {
+            gamepads.poll();
+        }
+        gamepads.take_last_input()
+    }
+
+    /// Whether a controller's PS button was pressed since the last call
+    ///
+    /// Polls the controllers itself while emulation is not running, so a
+    /// paused game's menu can still be closed and navigated with the pad.
+    pub fn take_ps_button(&mut self) -> bool {
+        let Some(gamepads) = self.gamepads.as_mut() else {
+            return false;
+        };
+        if self.state != RunnerState::Running {
+            gamepads.poll();
+        }
+        gamepads.take_ps_button()
+    }
+
+    /// Unprocessed stick axes of the generic gamepad on a port
+    ///
+    /// Left X, left Y, right X, right Y from -1.0 to 1.0, up positive,
+    /// before the profile's dead zone and response curve.
+    pub fn raw_sticks(&self, port: u8) -> Option<[f32; 4]> {
+        let axes = self.gamepads.as_ref()?.raw_axes(port)?;
+        Some([axes[0], axes[1], axes[2], axes[3]])
+    }
+
     /// Get the current framebuffer data for display
     pub fn get_framebuffer(&self) -> Option<oc_rsx::FramebufferData> {
         let rsx = self.rsx_thread.read();
@@ -134,6 +876,21 @@ impl EmulatorRunner {
         self.state
     }
 
+    /// Emulated clock guest time is read from
+    pub fn time_base(&self) -> &TimeBase {
+        &self.time_base
+    }
+
+    /// Change state, running guest time only while the emulator runs
+    fn set_state(&mut self, state: RunnerState) {
+        self.state = state;
+        if state == RunnerState::Running {
+            self.time_base.resume();
+        } else {
+            self.time_base.pause();
+        }
+    }
+
     /// Start the emulator
     pub fn start(&mut self) -> Result<()> {
         if self.state == RunnerState::Running {
@@ -141,8 +898,12 @@ impl EmulatorRunner {
         }
 
         tracing::info!("Starting emulator");
-        self.state = RunnerState::Running;
+        self.set_state(RunnerState::Running);
         self.last_frame_time = Instant::now();
+        self.av_sync.reset();
+        self.perf.clear();
+        self.audio_ring.clear();
+        self.apply_speed();
 
         Ok(())
     }
@@ -151,7 +912,9 @@ impl EmulatorRunner {
     pub fn pause(&mut self) -> Result<()> {
         if self.state == RunnerState::Running {
             tracing::info!("Pausing emulator");
-            self.state = RunnerState::Paused;
+            self.set_state(RunnerState::Paused);
+            self.record_spu_breaks();
+            self.report_debugger_interrupt();
         }
         Ok(())
     }
@@ -160,19 +923,382 @@ impl EmulatorRunner {
     pub fn resume(&mut self) -> Result<()> {
         if self.state == RunnerState::Paused {
             tracing::info!("Resuming emulator");
-            self.state = RunnerState::Running;
+            self.set_state(RunnerState::Running);
             self.last_frame_time = Instant::now();
+            self.av_sync.reset_pacing();
+            self.perf.restart();
+            let mut debugger = self.spu_debugger.lock();
+            for spu in 0..self.spu_threads.read().len() {
+                debugger.end_break(spu);
+            }
         }
         Ok(())
     }
 
+    /// Snapshot SPU local storage for the debugger when the emulator stops
+    fn record_spu_breaks(&self) {
+        for (spu, thread) in self.spu_threads.read().iter().enumerate() {
+            // Thread before debugger, the order execute_spu_thread locks them in
+            let thread = thread.read();
+            self.spu_debugger.lock().record_break(spu, &thread.local_storage[..]);
+        }
+    }
+
     /// Stop the emulator
     pub fn stop(&mut self) -> Result<()> {
         tracing::info!("Stopping emulator");
-        self.state = RunnerState::Stopped;
+        self.set_state(RunnerState::Stopped);
+        self.debug_step = None;
+        match self.finish_replay() {
+            Ok(Some(message)) => tracing::info!("{}", message),
+            Ok(None) => {}
+            Err(e) => tracing::error!("{}", e),
+        }
+        match self.stop_coverage() {
+            Ok(Some(message)) => tracing::info!("{}", message),
+            Ok(None) => {}
+            Err(e) => tracing::error!("{}", e),
+        }
+        // The file is finished in the background and the thread logs the result
+        drop(self.stop_video_recording());
+        self.report_debugger_interrupt();
+        Ok(())
+    }
+
+    /// Stop the game and release everything it holds outside the runner
+    ///
+    /// Waits for a video being recorded to be written, ends audio dumps and
+    /// traces, stops audio output and the GDB servers, flushes and closes the
+    /// game's files and drops its threads. The runner is not meant to run
+    /// another game afterwards.
+    pub fn shutdown(&mut self) -> Result<()> {
+        let video = self.stop_video_recording();
+        self.stop()?;
+        if let Some(video) = video {
+            // The thread logs its own result
+            let _ = video.join();
+        }
+        self.audio_recorder.lock().stop();
+        if let Err(e) = self.stop_trace() {
+            tracing::error!("{}", e);
+        }
+        if let Some(mut backend) = self.audio_backend.take() {
+            if let Err(e) = backend.stop() {
+                tracing::warn!("Failed to stop {} audio output: {}", backend.name(), e);
+            }
+        }
+        if let Some(autosave) = self.autosave.take() {
+            autosave.finish();
+        }
+        self.gdb_ppu = None;
+        self.gdb_spu = None;
+        let closed = self.syscall_handler.close_files();
+        self.ppu_threads.write().clear();
+        self.spu_threads.write().clear();
+        self.interrupts.lock().clear();
+        tracing::info!("Emulator shut down, {} open files closed", closed);
+        Ok(())
+    }
+
+    /// Sections of the configuration the runner applies while a game runs
+    pub const LIVE_CONFIG_SECTIONS: [&'static str; 7] =
+        ["general", "gpu", "audio", "input", "paths", "debug", "network"];
+
+    /// Apply the settings changes `watcher` receives from now on
+    pub fn watch_config(&mut self, watcher: ConfigWatcher) {
+        self.config_watcher = Some(watcher);
+    }
+
+    /// Apply the settings published since the last call
+    ///
+    /// Called at the start of every frame; the frame limit, render scale,
+    /// audio output, input and debugger settings change without a reboot, as
+    /// do the HLE media folders, background download setting and network
+    /// capture.
+    pub fn apply_config_changes(&mut self) {
+        let Some(change) = self.config_watcher.as_ref().and_then(ConfigWatcher::take) else {
+            return;
+        };
+        let config = &change.config;
+        if change.touches("gpu") {
+            self.configure_speed(&config.gpu);
+            let scale = RenderScale::new(config.gpu.resolution_scale as f32);
+            self.rsx_thread.write().set_render_scale(scale);
+            self.av_sync.set_vblank_hz(Self::vblank_hz(&config.gpu));
+            Self::configure_hle_video(&config.gpu);
+        }
+        if change.touches("audio") {
+            self.configure_audio(&config.audio);
+        }
+        if change.touches("input") {
+            self.configure_input(&config.input);
+        }
+        if change.touches("debug") {
+            self.configure_debugger(&config.debug);
+            self.syscall_handler.vfs().tracer().set_enabled(config.debug.trace_vfs);
+        }
+        if change.touches("general") {
+            Self::configure_hle_general(&config.general);
+        }
+        if change.touches("paths") {
+            Self::configure_hle_paths(&config.paths);
+        }
+        self.config = Config::clone(config);
+        if change.touches("gpu") || change.touches("paths") {
+            self.configure_texture_pack();
+        }
+        if change.touches("network") || change.touches("paths") {
+            self.configure_net_capture();
+        }
+        if change.touches("network") {
+            self.configure_host_overrides();
+        }
+    }
+
+    /// Record or replay the loaded title's network traffic as the network settings ask
+    fn configure_net_capture(&self) {
+        let mode = self.config.network.capture_mode;
+        let capture = match self.title_id.as_deref() {
+            Some(title_id) if mode != NetCaptureMode::Off => {
+                let path = net_capture::capture_path(&self.config.paths.network_captures, title_id);
+                match NetCapture::open(mode, path) {
+                    Ok(capture) => {
+                        let file = capture.file();
+                        tracing::info!(
+                            "Network capture: {:?}, {} HTTP and {} socket exchanges recorded",
+                            mode,
+                            file.http.len(),
+                            file.sockets.len()
+                        );
+                        capture
+                    }
+                    Err(e) => {
+                        tracing::warn!("Network capture disabled: {}", e);
+                        NetCapture::new()
+                    }
+                }
+            }
+            _ => NetCapture::new(),
+        };
+        oc_hle::get_hle_context_mut().http.set_capture(capture);
+    }
+
+    /// Redirect the domains the network settings override
+    fn configure_host_overrides(&self) {
+        let hosts = HostTable::from_overrides(&self.config.network.host_overrides);
+        let mut hle = oc_hle::get_hle_context_mut();
+        hle.sys_net.set_hosts(hosts.clone());
+        hle.http.set_hosts(hosts);
+    }
+
+    /// Dump and replace the loaded title's textures as the GPU settings ask
+    ///
+    /// Packs are loaded whole, so replacements never stall a draw on a file.
+    fn configure_texture_pack(&self) {
+        let (gpu, paths) = (&self.config.gpu, &self.config.paths);
+        let title_id = self.title_id.as_deref();
+        let dump_dir = title_id.map(|title_id| CacheKind::TextureDumps.title_dir(paths, title_id));
+        let replacements = match title_id {
+            Some(title_id) if gpu.replace_textures => {
+                texture_pack::load_replacements(&paths.texture_replacements.join(title_id))
+            }
+            _ => HashMap::new(),
+        };
+        let dumped = match &dump_dir {
+            Some(dir) if gpu.dump_textures => texture_pack::dumped_hashes(dir),
+            _ => Vec::new(),
+        };
+        let mut rsx = self.rsx_thread.write();
+        rsx.set_texture_dumping(gpu.dump_textures && dump_dir.is_some(), dumped);
+        rsx.set_texture_replacements(replacements);
+    }
+
+    /// Save the textures dumped this frame, on a background thread
+    fn save_texture_dumps(&self) {
+        let dumps = self.rsx_thread.write().take_texture_dumps();
+        let Some(title_id) = self.title_id.as_deref().filter(|_| !dumps.is_empty()) else {
+            return;
+        };
+        let dir = CacheKind::TextureDumps.title_dir(&self.config.paths, title_id);
+        std::thread::spawn(move || match texture_pack::save_dumps(&dir, &dumps) {
+            Ok(count) => tracing::debug!("Dumped {} textures to {}", count, dir.display()),
+            Err(e) => tracing::warn!("Failed to dump textures: {}", e),
+        });
+    }
+
+    /// Start or stop the GDB servers to match the debug settings
+    ///
+    /// A server whose debugger is attached when it is stopped lets the
+    /// emulator run on.
+    pub fn configure_debugger(&mut self, config: &DebugConfig) {
+        let wanted = config.gdb_server.then_some(config.gdb_port);
+        if self.gdb_ppu.as_ref().map(GdbServer::port) == wanted && (wanted.is_none() || self.gdb_spu.is_some()) {
+            return;
+        }
+
+        let attached = [&self.gdb_ppu, &self.gdb_spu].iter().any(|server| server.as_ref().is_some_and(GdbServer::is_attached));
+        self.gdb_ppu = None;
+        self.gdb_spu = None;
+        self.debug_step = None;
+        if attached {
+            let _ = self.resume();
+        }
+        let Some(port) = wanted else {
+            return;
+        };
+        match GdbServer::bind(port, GdbArch::Ppu) {
+            Ok(server) => self.gdb_ppu = Some(server),
+            Err(e) => tracing::warn!("Failed to start the PPU GDB server on port {}: {}", port, e),
+        }
+        // Raw SPUs listen on the next port
+        match port.checked_add(1).map(|port| GdbServer::bind(port, GdbArch::Spu)) {
+            Some(Ok(server)) => self.gdb_spu = Some(server),
+            Some(Err(e)) => tracing::warn!("Failed to start the SPU GDB server on port {}: {}", port + 1, e),
+            None => tracing::warn!("No port left for the SPU GDB server after {}", port),
+        }
+    }
+
+    /// Whether a GDB server is listening, so the caller keeps polling while paused
+    pub fn debugger_listening(&self) -> bool {
+        self.gdb_ppu.is_some() || self.gdb_spu.is_some()
+    }
+
+    /// Serve attached debuggers; call every update, also while paused
+    pub fn poll_debugger(&mut self) {
+        // Each server is taken out while it drives the runner, so it handles its own stops
+        if let Some(mut server) = self.gdb_ppu.take() {
+            server.poll(&mut DebugTarget { runner: self, arch: GdbArch::Ppu });
+            self.gdb_ppu = Some(server);
+        }
+        if let Some(mut server) = self.gdb_spu.take() {
+            server.poll(&mut DebugTarget { runner: self, arch: GdbArch::Spu });
+            self.gdb_spu = Some(server);
+        }
+    }
+
+    /// Start recording an instruction trace as set up in the debug settings
+    pub fn start_trace(&self, config: &DebugConfig) -> std::result::Result<(), String> {
+        let trace_config = TraceConfig {
+            start: TraceTrigger::parse(&config.trace_start)?,
+            stop: TraceTrigger::parse(&config.trace_stop)?,
+            ring_size: config.trace_ring_size,
+            ppu: config.trace_ppu,
+            spu: config.trace_spu,
+        };
+        if !trace_config.ppu && !trace_config.spu {
+            return Err("Enable PPU or SPU tracing in the debug settings first".to_string());
+        }
+        let path = &config.trace_path;
+        let recorder = TraceRecorder::create(path, trace_config)
+            .map_err(|e| format!("Failed to create trace file {}: {}", path.display(), e))?;
+        *self.trace.lock() = Some(recorder);
+        tracing::info!("Recording instruction trace to {}", path.display());
         Ok(())
     }
 
+    /// Finish the instruction trace, returning how many instructions it holds
+    pub fn stop_trace(&self) -> std::result::Result<u64, String> {
+        let Some(recorder) = self.trace.lock().take() else {
+            return Ok(0);
+        };
+        let recorded = recorder.recorded();
+        recorder
+            .finish()
+            .and_then(|mut file| std::io::Write::flush(&mut file))
+            .map_err(|e| format!("Failed to write instruction trace: {}", e))?;
+        Ok(recorded)
+    }
+
+    /// Whether an instruction trace is waiting for its start trigger or recording
+    pub fn is_tracing(&self) -> bool {
+        self.trace.lock().as_ref().is_some_and(|recorder| !recorder.is_finished())
+    }
+
+    /// Feed the instruction trace, dropping it if the file can't be written
+    fn trace(&self, record: impl FnOnce(&mut TraceRecorder) -> std::io::Result<()>) {
+        let mut trace = self.trace.lock();
+        let Some(recorder) = trace.as_mut() else {
+            return;
+        };
+        if let Err(e) = record(recorder) {
+            tracing::warn!("Instruction trace stopped: {}", e);
+            *trace = None;
+        }
+    }
+
+    /// Halt for a debugger; other debuggers waiting for a stop see an interrupt
+    fn halt_for_debugger(&mut self) {
+        if self.state == RunnerState::Running {
+            tracing::info!("Emulator halted by the debugger");
+            self.set_state(RunnerState::Paused);
+            self.record_spu_breaks();
+        }
+        self.debug_step = None;
+        self.report_debugger_interrupt();
+    }
+
+    /// Resume for a debugger, optionally for a single instruction of one thread
+    fn resume_for_debugger(&mut self, step: Option<ThreadId>) {
+        self.debug_step = step;
+        let _ = self.resume();
+    }
+
+    /// Stop every thread because `thread` reached a breakpoint or finished a step
+    fn stop_for_debugger(&mut self, thread: ThreadId, reason: StopReason) {
+        self.set_state(RunnerState::Paused);
+        self.record_spu_breaks();
+        self.debug_resume_from = Some(thread);
+        if reason == StopReason::Breakpoint {
+            self.trace(TraceRecorder::breakpoint_hit);
+        }
+        let (arch, id) = match thread {
+            ThreadId::Ppu(id) => (GdbArch::Ppu, id + 1),
+            ThreadId::Spu(id) => (GdbArch::Spu, id + 1),
+        };
+        for server in [self.gdb_ppu.as_mut(), self.gdb_spu.as_mut()].into_iter().flatten() {
+            let thread = (server.arch() == arch).then_some(id);
+            server.report_stop(thread, reason);
+        }
+    }
+
+    /// Tell debuggers waiting for a stop that execution was interrupted
+    fn report_debugger_interrupt(&mut self) {
+        for server in [self.gdb_ppu.as_mut(), self.gdb_spu.as_mut()].into_iter().flatten() {
+            server.report_stop(None, StopReason::Interrupt);
+        }
+    }
+
+    /// Whether `thread` is about to execute an instruction with a debugger breakpoint
+    fn at_breakpoint(&mut self, thread: ThreadId) -> bool {
+        let server = match thread {
+            ThreadId::Ppu(_) => self.gdb_ppu.as_ref(),
+            ThreadId::Spu(_) => self.gdb_spu.as_ref(),
+        };
+        let Some(server) = server.filter(|server| server.has_breakpoints()) else {
+            return false;
+        };
+        if self.debug_resume_from == Some(thread) {
+            self.debug_resume_from = None;
+            return false;
+        }
+        let pc = match thread {
+            ThreadId::Ppu(id) => self.ppu_threads.read().get(id as usize).map(|thread| thread.read().pc()),
+            ThreadId::Spu(id) => self.spu_threads.read().get(id as usize).map(|thread| thread.read().pc() as u64),
+        };
+        pc.is_some_and(|pc| server.has_breakpoint(pc))
+    }
+
+    /// Run one instruction of `thread` for the debugger and stop again
+    fn step_for_debugger(&mut self, thread: ThreadId) -> Result<()> {
+        let result = match thread {
+            ThreadId::Ppu(id) => self.execute_ppu_thread(id),
+            ThreadId::Spu(id) => self.execute_spu_thread(id),
+        };
+        self.total_cycles += 1;
+        self.stop_for_debugger(thread, StopReason::Step);
+        result
+    }
+
     /// Check if the emulator is running
     pub fn is_running(&self) -> bool {
         self.state == RunnerState::Running
@@ -195,7 +1321,10 @@ impl EmulatorRunner {
             threads.len() as u32
         };
 
-        let thread = Arc::new(RwLock::new(PpuThread::new(thread_id, self.memory.clone())));
+        let mut thread = PpuThread::new(thread_id, self.memory.clone());
+        thread.set_time_base(self.time_base.clone());
+        thread.set_decrementer(PPU_TIME_SLICE_TICKS);
+        let thread = Arc::new(RwLock::new(thread));
         
         // Add to scheduler
         self.scheduler.write().add_thread(ThreadId::Ppu(thread_id), priority);
@@ -238,14 +1367,33 @@ impl EmulatorRunner {
     pub fn load_game<P: AsRef<Path>>(&self, path: P) -> Result<LoadedGame> {
         tracing::info!("Loading game: {}", path.as_ref().display());
 
-        // Create game loader
-        let loader = GameLoader::new(self.memory.clone());
+        // Create game loader, caching decrypted executables with the title's other caches
+        let mut loader = GameLoader::new(self.memory.clone());
+        if let Some(title_id) = self.title_id.as_deref() {
+            loader.set_self_cache(CacheKind::DecryptedSelf.title_dir(&self.config.paths, title_id));
+        }
 
         // Load the game
-        let game = loader.load(path)?;
+        let game = loader.load(&path)?;
+        self.configure_texture_pack();
+        self.configure_net_capture();
+        self.configure_host_overrides();
+
+        // Games booted from a disc folder find it in the drive
+        if let Some(root) = disc_root(path.as_ref()) {
+            if let Err(e) = self.mount_disc(root, false) {
+                tracing::warn!("Failed to mount the boot disc: {}", e);
+            }
+        }
 
         // Create the main PPU thread
         let thread_id = self.create_ppu_thread_with_entry(&game)?;
+        *self.functions.write() = game.functions.clone();
+        *self.imports.write() = game.imports.clone();
+        *self.modules.write() = game.modules.clone();
+        if self.config.debug.coverage {
+            self.start_coverage(&self.config.debug.coverage_path);
+        }
 
         tracing::info!(
             "Game loaded successfully, main thread {} created at entry 0x{:x}",
@@ -270,6 +1418,8 @@ impl EmulatorRunner {
         };
 
         let mut thread = PpuThread::new(thread_id, self.memory.clone());
+        thread.set_time_base(self.time_base.clone());
+        thread.set_decrementer(PPU_TIME_SLICE_TICKS);
 
         // Set up initial register state according to PS3 ABI
         // R1 = Stack pointer (pointing to top of stack, grows downward)
@@ -328,8 +1478,11 @@ impl EmulatorRunner {
         if self.state != RunnerState::Running {
             return Ok(());
         }
-
-        let frame_start = Instant::now();
+        if let Some(thread) = self.debug_step.take() {
+            return self.step_for_debugger(thread);
+        }
+        self.apply_config_changes();
+        self.profiler.lock().start_frame();
 
         // Begin graphics frame
         {
@@ -337,32 +1490,200 @@ impl EmulatorRunner {
             rsx.begin_frame();
         }
 
+        // Pick up controller hotplug and input for this frame
+        if let Some(gamepads) = self.gamepads.as_mut() {
+            gamepads.poll();
+        }
+        if let Some(ds3_hid) = self.ds3_hid.as_mut() {
+            ds3_hid.poll();
+        }
+        if let Some(ds4_hid) = self.ds4_hid.as_mut() {
+            ds4_hid.poll();
+        }
+        self.syscall_handler.poll_usb();
+        if let Some((port, keyboard_pad)) = self.keyboard_pad.as_mut() {
+            let (state, sixaxis) = keyboard_pad.update(self.av_sync.frame_period());
+            self.pad_ports.update(*port, state);
+            self.pad_ports.update_motion(*port, sixaxis);
+        }
+        if let Some((port, instrument)) = self.instrument.as_mut() {
+            for message in self.midi.iter().flat_map(|(_, midi)| midi.drain()) {
+                instrument.midi_message(message);
+            }
+            let state = instrument.update(self.av_sync.frame_period());
+            self.pad_ports.update(*port, state);
+        }
+        if let Some(virtual_move) = self.virtual_move.as_mut() {
+            if matches!(virtual_move.source(), MoveSource::Ds4 | MoveSource::Ds4Pointer) {
+                // The first DS4 drives the Move
+                let port = self.ds4_hid.as_ref().and_then(Ds4HidBackend::first_port);
+                let input = port.and_then(|port| Some((self.pad_ports.state(port)?, self.pad_ports.motion(port)?)));
+                if let Some((pad, sixaxis)) = input {
+                    virtual_move.set_ds4_input(pad, sixaxis);
+                }
+            }
+            if let Some(controller) = self.move_manager.get_mut(0) {
+                virtual_move.update(self.av_sync.frame_period(), controller);
+            }
+        }
+
+        // Recorded input replaces the live input when replaying
+        if let Some(replay) = self.replay.lock().as_mut() {
+            replay.frame(self.frame_count);
+            replay.pads(&self.pad_ports);
+        }
+        self.sync_bluetooth();
+
+        // Keep frozen values before the game reads them
+        self.cheats.lock().apply(&self.memory);
+
+        // Let the system's game data install move on
+        oc_hle::cell_game::run_data_install(DATA_INSTALL_KB_PER_FRAME);
+
+        // Callbacks HLE functions queued for the game run like interrupt handlers
+        if oc_hle::get_hle_context().guest_calls.has_pending() {
+            self.interrupt_pending.store(true, Ordering::Relaxed);
+        }
+
+        // Timers fire even when no PPU thread runs to take a decrementer interrupt
+        oc_lv2::timer::service_timers(self.syscall_handler.object_manager());
+
         // Run threads for this frame
+        let cpu_start = Instant::now();
         self.run_threads()?;
+        let gpu_start = Instant::now();
+        let cpu_time = gpu_start - cpu_start;
+
+        // Drop the frame's draws if it started too late, but never in a recording
+        let skip = self.video.is_none() && self.av_sync.should_skip_frame(cpu_start, self.config.gpu.frame_skip);
+        self.rsx_thread.write().set_skip_draws(skip);
 
         // Process RSX commands
+        self.import_video_frames();
         self.process_rsx()?;
+        self.save_texture_dumps();
 
         // End graphics frame and present
         {
             let mut rsx = self.rsx_thread.write();
             rsx.end_frame();
+            self.rsx_debugger.lock().record_frame_end();
+            self.profiler.lock().record_rsx_frame(rsx.frame_counters());
         }
+        let gpu_time = gpu_start.elapsed();
 
-        // Update frame timing
+        if let Some((video, _)) = self.video.as_mut() {
+            if let Some(frame) = self.rsx_thread.read().get_framebuffer() {
+                video.push_frame(frame);
+            }
+        }
         self.frame_count += 1;
-        let frame_time = frame_start.elapsed();
 
-        // Sleep to maintain target frame rate
-        if frame_time < self.target_frame_time {
-            std::thread::sleep(self.target_frame_time - frame_time);
+        // Wait for vblank, then produce the audio that belongs to this frame
+        let wait = self.av_sync.on_vblank(Instant::now());
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
         }
+        self.mix_audio();
+        self.signal_vblank();
 
         self.last_frame_time = Instant::now();
+        self.perf.record(self.last_frame_time, cpu_time, gpu_time);
+        self.profiler.lock().end_frame();
+        self.autosave_if_due();
 
         Ok(())
     }
 
+    /// Complete the flips queued since the last vblank and raise the GCM vblank
+    ///
+    /// Flips the game issued through the RSX FIFO complete here, so handlers
+    /// and flip status follow the emulated display rate.
+    fn signal_vblank(&mut self) {
+        let (flip_count, buffer) = {
+            let rsx = self.rsx_thread.read();
+            (rsx.flip_count(), rsx.last_flip_buffer())
+        };
+        let mut hle = oc_hle::get_hle_context_mut();
+        if flip_count != self.rsx_flip_count {
+            self.rsx_flip_count = flip_count;
+            hle.gcm.request_flip(buffer);
+        }
+        hle.gcm.vblank(self.time_base.system_time_us());
+        let gcm_handlers = hle.gcm.vblank_handler() != 0 || hle.gcm.flip_handler() != 0;
+        drop(hle);
+        let lv2_handler = oc_lv2::interrupt::raise(
+            self.syscall_handler.object_manager(),
+            InterruptSource::Rsx,
+            RSX_INT_STAT_VBLANK,
+            None,
+        );
+        if gcm_handlers || lv2_handler {
+            self.interrupt_pending.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Mix the audio blocks due at the current video time
+    fn mix_audio(&mut self) {
+        let blocks = self.av_sync.audio_blocks_due();
+        if blocks == 0 {
+            return;
+        }
+        let frames = (blocks * AUDIO_BLOCK_SAMPLES) as usize;
+        let music = self.music_samples(frames);
+        let voices = if self.config.audio.hle_sound_middleware {
+            oc_hle::get_hle_context_mut().ss_player.render(frames, AUDIO_SAMPLE_RATE)
+        } else {
+            Vec::new()
+        };
+        let mut mixer = self.audio_mixer.lock();
+        if !music.is_empty() {
+            let _ = mixer.write_to_source(self.music_source, &music);
+        }
+        if !voices.is_empty() {
+            let _ = mixer.write_to_source(self.voice_source, &voices);
+        }
+        for _ in 0..blocks {
+            mixer.mix_to_ring(&self.audio_ring, AUDIO_BLOCK_SAMPLES as usize);
+            self.av_sync.on_audio_block();
+        }
+        drop(mixer);
+        let mut hle = oc_hle::get_hle_context_mut();
+        for _ in 0..blocks {
+            hle.audio.advance_block(self.time_base.system_time_us());
+        }
+    }
+
+    /// Stereo frames of the custom soundtrack due for the next audio blocks
+    fn music_samples(&mut self, frames: usize) -> Vec<f32> {
+        let mut hle = oc_hle::get_hle_context_mut();
+        let ctx = &mut *hle;
+        let music = &mut ctx.music;
+        if music.track_generation() != self.music_generation {
+            self.music_generation = music.track_generation();
+            match music.current_track() {
+                Some(path) => self.music_player.load(path.to_path_buf()),
+                None => self.music_player.unload(),
+            }
+        }
+        if !music.is_playing() {
+            return Vec::new();
+        }
+        if let Some(e) = self.music_player.take_error() {
+            tracing::warn!("Skipping custom soundtrack track: {}", e);
+            music.track_failed();
+            music.queue_callbacks(&mut ctx.guest_calls);
+            return Vec::new();
+        }
+
+        let samples = self.music_player.fill(frames, music.volume());
+        if self.music_player.is_finished() {
+            music.track_finished();
+            music.queue_callbacks(&mut ctx.guest_calls);
+        }
+        samples
+    }
+
     /// Run threads using the scheduler
     fn run_threads(&mut self) -> Result<()> {
         const MAX_CYCLES_PER_FRAME: u64 = 100000;
@@ -370,11 +1691,22 @@ impl EmulatorRunner {
 
         while cycles < MAX_CYCLES_PER_FRAME {
             // Schedule next thread
-            let thread_id = match self.scheduler.write().schedule() {
+            let scheduled = self.scheduler.write().schedule();
+            let thread_id = match self.replay_schedule(scheduled) {
                 Some(id) => id,
+                // With every thread idle, interrupts are dispatched right away
+                None if self.interrupt_pending.swap(false, Ordering::Relaxed) => {
+                    self.dispatch_interrupts()?;
+                    continue;
+                }
                 None => break, // No ready threads
             };
 
+            if self.at_breakpoint(thread_id) {
+                self.stop_for_debugger(thread_id, StopReason::Breakpoint);
+                break;
+            }
+
             // Execute thread based on type
             match thread_id {
                 ThreadId::Ppu(id) => {
@@ -405,7 +1737,7 @@ impl EmulatorRunner {
         let threads = self.ppu_threads.read();
         let thread_arc = threads.get(thread_id as usize)
             .ok_or_else(|| EmulatorError::Ppu(
-                oc_core::error::PpuError::ThreadError(format!("Invalid thread ID: {}", thread_id))
+                PpuError::ThreadError(format!("Invalid thread ID: {}", thread_id))
             ))?;
         let mut thread = thread_arc.write();
 
@@ -414,21 +1746,58 @@ impl EmulatorRunner {
             return Ok(());
         }
 
+        // Interrupt sources that fired are taken as an external interrupt
+        if self.interrupt_pending.swap(false, Ordering::Relaxed) {
+            thread.raise_external_interrupt();
+        }
+        // Asynchronous exceptions are taken between instructions
+        if let Some(exception) = thread.pending_interrupt() {
+            thread.enter_exception(exception);
+            drop(thread);
+            drop(threads);
+            return self.handle_interrupt(thread_id, exception);
+        }
+
         // Check if we're about to execute a syscall instruction
         let pc = thread.pc() as u32;
         let opcode = match self.memory.read_be32(pc) {
             Ok(op) => op,
             Err(e) => {
-                tracing::error!("Failed to read instruction at 0x{:08x}: {}", pc, e);
+                tracing::error!(
+                    "Failed to read instruction at 0x{:08x}: {}\n{}",
+                    pc,
+                    e,
+                    format_backtrace(&self.unwind(&thread))
+                );
                 thread.stop();
                 self.scheduler.write().set_thread_state(
                     ThreadId::Ppu(thread_id),
                     ThreadState::Stopped
                 );
+                drop(thread);
+                drop(threads);
+                let reason = format!("Failed to read instruction at 0x{:08x}: {}", pc, e);
+                self.write_crash_dump(CrashKind::GuestFault, &reason, Some(thread_id));
                 return Err(EmulatorError::Memory(e));
             }
         };
 
+        self.profile_ppu(&thread);
+        if let Some((coverage, _)) = self.coverage.lock().as_mut() {
+            coverage.record(thread_id, pc, opcode);
+        }
+
+        // Hooks run before the instruction at their address
+        {
+            let mut patches = self.patches.lock();
+            if patches.has_hooks() && !matches!(patches.run_hook(&mut thread, &self.memory), None | Some(HookAction::Continue)) {
+                return Ok(());
+            }
+        }
+
+        // Traces show what each instruction changed
+        let before = self.is_tracing().then(|| thread.regs.clone());
+
         // Check if it's a syscall instruction (sc opcode = 0x44000002)
         if opcode == 0x44000002 {
             // Get syscall number from R11
@@ -440,38 +1809,595 @@ impl EmulatorRunner {
                 *arg = thread.gpr(3 + i); // R3-R10 are argument registers
             }
 
+            // The kernel ends the handlers it runs on interrupt threads itself
+            let end_of_interrupt =
+                syscall_num == SYS_INTERRUPT_THREAD_EOI && self.interrupts.lock().by_thread(thread_id).is_some();
+
             // Execute syscall
-            match self.syscall_handler.handle(syscall_num, &args) {
-                Ok(result) => {
-                    // Store result in R3
-                    thread.set_gpr(3, result as u64);
-                    thread.advance_pc();
-                }
+            thread.enter_exception(PpuExceptionType::SystemCall);
+            let result = if end_of_interrupt {
+                Ok(0)
+            } else {
+                self.syscall_handler.handle(syscall_num, &args)
+            };
+            let result = match result {
+                Ok(result) => result as u64,
                 Err(e) => {
                     tracing::error!("Syscall {} failed: {}", syscall_num, e);
-                    // Set error code in R3
-                    thread.set_gpr(3, 0xFFFFFFFFFFFFFFFF);
-                    thread.advance_pc();
+                    // Error code for R3
+                    0xFFFFFFFFFFFFFFFF
                 }
+            };
+            let result = match self.replay.lock().as_mut() {
+                Some(replay) => replay.syscall(syscall_num, result),
+                None => result,
+            };
+            // Store result in R3
+            thread.set_gpr(3, result);
+            thread.return_from_exception();
+            if let Some(before) = before {
+                self.trace(|trace| trace.record_ppu(thread_id, pc as u64, opcode, &before, &thread.regs));
+            }
+            if end_of_interrupt {
+                self.end_handler(&mut thread, thread_id)?;
             }
             return Ok(());
         }
 
         // Execute one instruction normally
         match self.ppu_interpreter.step(&mut thread) {
-            Ok(()) => Ok(()),
+            Ok(()) => {
+                if let Some(before) = before {
+                    self.trace(|trace| trace.record_ppu(thread_id, pc as u64, opcode, &before, &thread.regs));
+                }
+                Ok(())
+            }
             Err(e) => {
-                tracing::error!("PPU thread {} error: {}", thread_id, e);
+                if matches!(e, PpuError::Breakpoint { .. }) {
+                    self.trace(TraceRecorder::breakpoint_hit);
+                }
+                tracing::error!(
+                    "PPU thread {} error: {}\n{}",
+                    thread_id,
+                    e,
+                    format_backtrace(&self.unwind(&thread))
+                );
+                let mut reason = format!("PPU thread {}: {}", thread_id, e);
+                if let PpuError::AccessViolation { addr, kind, .. } = e {
+                    // LV2 has no data storage handler for games: the fault is
+                    // reported and the thread stopped at the access
+                    thread.enter_data_storage(addr, kind);
+                    reason = format!(
+                        "{} (data storage exception, DAR 0x{:08x}, DSISR 0x{:08x})",
+                        reason, thread.regs.dar, thread.regs.dsisr
+                    );
+                    thread.return_from_exception();
+                }
                 thread.stop();
                 self.scheduler.write().set_thread_state(
                     ThreadId::Ppu(thread_id),
                     ThreadState::Stopped
                 );
+                drop(thread);
+                drop(threads);
+                if !matches!(e, PpuError::Breakpoint { .. } | PpuError::PowerState(_)) {
+                    self.write_crash_dump(CrashKind::GuestFault, &reason, Some(thread_id));
+                }
                 Err(EmulatorError::Ppu(e))
             }
         }
     }
 
+    /// Kernel handler of a decrementer or external interrupt a PPU thread took
+    ///
+    /// The decrementer ends the thread's time slice and expires the LV2
+    /// timers that are due; an external interrupt starts the handlers of the
+    /// sources that fired. The thread then yields, so the scheduler preempts
+    /// it for a handler thread of higher priority.
+    fn handle_interrupt(&self, thread_id: u32, exception: PpuExceptionType) -> Result<()> {
+        match exception {
+            PpuExceptionType::Decrementer => {
+                oc_lv2::timer::service_timers(self.syscall_handler.object_manager());
+            }
+            _ => self.dispatch_interrupts()?,
+        }
+        if let Some(thread) = self.ppu_threads.read().get(thread_id as usize) {
+            let mut thread = thread.write();
+            if exception == PpuExceptionType::Decrementer {
+                thread.set_decrementer(PPU_TIME_SLICE_TICKS);
+            }
+            thread.return_from_exception();
+        }
+        self.scheduler.write().yield_current();
+        Ok(())
+    }
+
+    /// Queue the handlers of the interrupts raised since the last dispatch
+    fn dispatch_interrupts(&self) -> Result<()> {
+        let gcm_calls: Vec<HandlerCall> = {
+            let mut hle = oc_hle::get_hle_context_mut();
+            let gcm = &mut hle.gcm;
+            let events = gcm.take_events();
+            events
+                .into_iter()
+                .map(|event| match event {
+                    oc_hle::cell_gcm_sys::GcmDisplayEvent::VBlank { .. } => gcm.vblank_handler(),
+                    oc_hle::cell_gcm_sys::GcmDisplayEvent::Flip { .. } => gcm.flip_handler(),
+                })
+                .filter(|&opd| opd != 0)
+                .map(|opd| HandlerCall { opd, args: [GCM_DISPLAY_HEAD, 0, 0, 0] })
+                .collect()
+        };
+        for call in gcm_calls {
+            self.queue_handler_call(InterruptOwner::Gcm, GCM_INTERRUPT_PRIORITY, GCM_INTERRUPT_STACK_SIZE, call)?;
+        }
+        for dispatch in oc_lv2::interrupt::take_dispatches(self.syscall_handler.object_manager()) {
+            let owner = InterruptOwner::Lv2 { handle: dispatch.handle, thread_id: dispatch.thread_id };
+            let call = HandlerCall { opd: dispatch.entry as u32, args: [dispatch.arg, 0, 0, 0] };
+            self.queue_handler_call(owner, dispatch.priority, dispatch.stack_size as u32, call)?;
+        }
+        let hle_calls = oc_hle::get_hle_context_mut().guest_calls.take();
+        for call in hle_calls {
+            let call = HandlerCall { opd: call.opd, args: call.args };
+            self.queue_handler_call(InterruptOwner::Hle, HLE_CALLBACK_PRIORITY, HLE_CALLBACK_STACK_SIZE, call)?;
+        }
+        Ok(())
+    }
+
+    /// Queue a handler call on the thread of its owner, starting it if idle
+    ///
+    /// The thread is created on the owner's first interrupt and runs at
+    /// `priority`.
+    fn queue_handler_call(&self, owner: InterruptOwner, priority: u32, stack_size: u32, call: HandlerCall) -> Result<()> {
+        let existing = self.interrupts.lock().find(owner).map(|slot| slot.ppu_thread);
+        let ppu_thread = match existing {
+            Some(ppu_thread) => ppu_thread,
+            None => {
+                let ppu_thread = self.create_handler_thread(owner, priority, stack_size)?;
+                self.interrupts.lock().add(owner, ppu_thread);
+                ppu_thread
+            }
+        };
+        self.interrupts.lock().queue(owner, call);
+
+        let Some(call) = self.interrupts.lock().start_next(ppu_thread) else {
+            return Ok(());
+        };
+        if let Some(thread) = self.ppu_threads.read().get(ppu_thread as usize) {
+            self.begin_handler(&mut thread.write(), call)?;
+        }
+        self.scheduler.write().set_thread_state(ThreadId::Ppu(ppu_thread), ThreadState::Ready);
+        Ok(())
+    }
+
+    /// Create an idle PPU thread for the handlers of `owner`
+    fn create_handler_thread(&self, owner: InterruptOwner, priority: u32, stack_size: u32) -> Result<u32> {
+        if self.interrupts.lock().return_stub().is_none() {
+            // li r11, SYS_INTERRUPT_THREAD_EOI; sc
+            let stub = self.memory.allocate(0x1000, 0x1000, oc_memory::PageFlags::RW)?;
+            self.memory.write_be32(stub, 0x3960_0000 | SYS_INTERRUPT_THREAD_EOI as u32)?;
+            self.memory.write_be32(stub + 4, 0x4400_0002)?;
+            self.memory.protect(stub, 0x1000, oc_memory::PageFlags::RX)?;
+            self.interrupts.lock().set_return_stub(stub);
+        }
+        let stack_size = stack_size.max(0x1000);
+        let stack = self.memory.allocate(stack_size, 0x1000, oc_memory::PageFlags::RW)?;
+
+        let mut threads = self.ppu_threads.write();
+        let thread_id = threads.len() as u32;
+        let mut thread = PpuThread::new(thread_id, self.memory.clone());
+        thread.set_time_base(self.time_base.clone());
+        thread.set_decrementer(PPU_TIME_SLICE_TICKS);
+        // Leave room for the handler's back chain and LR save area
+        thread.stack_addr = stack + stack_size - 0x100;
+        thread.stack_size = stack_size - 0x100;
+        thread.priority = priority;
+        thread.name = match owner {
+            InterruptOwner::Lv2 { thread_id, .. } => format!("interrupt {}", thread_id),
+            InterruptOwner::Gcm => "gcm interrupt".to_string(),
+            InterruptOwner::Hle => "hle callback".to_string(),
+        };
+        threads.push(Arc::new(RwLock::new(thread)));
+        drop(threads);
+
+        let mut scheduler = self.scheduler.write();
+        scheduler.add_thread(ThreadId::Ppu(thread_id), priority);
+        scheduler.set_thread_state(ThreadId::Ppu(thread_id), ThreadState::Waiting);
+        tracing::debug!("Created PPU thread {} for {:?} handlers", thread_id, owner);
+        Ok(thread_id)
+    }
+
+    /// Set up a handler thread to call `call`, returning to the eoi stub
+    fn begin_handler(&self, thread: &mut PpuThread, call: HandlerCall) -> Result<()> {
+        let entry = self.memory.read_be32(call.opd)?;
+        let toc = self.memory.read_be32(call.opd + 4)?;
+        let stub = self.interrupts.lock().return_stub().unwrap_or_default();
+        thread.set_pc(entry as u64);
+        thread.set_gpr(1, thread.stack_addr as u64);
+        thread.set_gpr(2, toc as u64);
+        for (i, &arg) in call.args.iter().enumerate() {
+            thread.set_gpr(3 + i, arg);
+        }
+        thread.regs.lr = stub as u64;
+        thread.start();
+        Ok(())
+    }
+
+    /// End the handler running on a handler thread, starting its next queued call
+    fn end_handler(&self, thread: &mut PpuThread, ppu_thread: u32) -> Result<()> {
+        let owner = self.interrupts.lock().finish(ppu_thread);
+        if let Some(InterruptOwner::Lv2 { thread_id, .. }) = owner {
+            let manager = self.syscall_handler.object_manager();
+            if let Err(e) = oc_lv2::interrupt::syscalls::sys_interrupt_thread_eoi(manager, thread_id) {
+                tracing::debug!("Interrupt thread {} eoi: {}", thread_id, e);
+            }
+            // Interrupts raised while the handler ran are dispatched next
+            if oc_lv2::interrupt::has_dispatches(manager) {
+                self.interrupt_pending.store(true, Ordering::Relaxed);
+            }
+        }
+        let next = self.interrupts.lock().start_next(ppu_thread);
+        match next {
+            Some(call) => self.begin_handler(thread, call),
+            None => {
+                thread.stop();
+                self.scheduler.write().set_thread_state(ThreadId::Ppu(ppu_thread), ThreadState::Waiting);
+                Ok(())
+            }
+        }
+    }
+
+    /// Raise an SPU's class 2 mailbox interrupt for a value it wrote to its interrupt mailbox
+    fn raise_spu_interrupt(&self, spu_id: u32, value: u32) {
+        let source = InterruptSource::Spu { spu_id, class: 2 };
+        let manager = self.syscall_handler.object_manager();
+        if oc_lv2::interrupt::raise(manager, source, SPU_INT2_STAT_MAILBOX_INT, Some(value)) {
+            self.interrupt_pending.store(true, Ordering::Relaxed);
+        } else {
+            tracing::debug!("SPU {} interrupt mailbox 0x{:08x} has no handler", spu_id, value);
+        }
+    }
+
+    /// Count a PPU instruction for the profiler, sampling the call stack when due
+    fn profile_ppu(&self, thread: &PpuThread) {
+        let mut profiler = self.profiler.lock();
+        if !profiler.enabled {
+            return;
+        }
+        profiler.record_ppu_instruction(thread.pc());
+        if profiler.ppu_sample_due() {
+            profiler.record_ppu_stack(&self.unwind(thread));
+        }
+    }
+
+    /// Backtrace of a PPU thread, innermost frame first
+    pub fn ppu_backtrace(&self, thread_id: u32) -> Option<Vec<StackFrame>> {
+        let threads = self.ppu_threads.read();
+        let thread = threads.get(thread_id as usize)?.read();
+        Some(self.unwind(&thread))
+    }
+
+    /// Start recording the run's nondeterministic inputs to `path`
+    ///
+    /// Call before the first frame so a replay starts from the same state.
+    pub fn start_recording(&self, path: &Path) -> std::result::Result<(), String> {
+        self.finish_replay()?;
+        *self.replay.lock() = Some(ReplaySession::record(path, self.title_id.as_deref()));
+        tracing::info!("Recording run to {}", path.display());
+        Ok(())
+    }
+
+    /// Replay the run recorded at `path`
+    ///
+    /// Call before the first frame of the same game the recording was made with.
+    pub fn start_replay(&self, path: &Path) -> std::result::Result<(), String> {
+        self.finish_replay()?;
+        let session = ReplaySession::replay(path)?;
+        if let (Some(recorded), Some(loaded)) = (session.title_id(), self.title_id.as_deref()) {
+            if recorded != loaded {
+                return Err(format!("{} is a recording of {}, not {}", path.display(), recorded, loaded));
+            }
+        }
+        *self.replay.lock() = Some(session);
+        tracing::info!("Replaying run from {}", path.display());
+        Ok(())
+    }
+
+    /// End recording or replaying, saving a recording; returns how it went
+    pub fn finish_replay(&self) -> std::result::Result<Option<String>, String> {
+        self.replay.lock().take().map(ReplaySession::finish).transpose()
+    }
+
+    /// First point the replay in progress drifted from its recording
+    pub fn replay_divergence(&self) -> Option<String> {
+        self.replay.lock().as_ref().and_then(|session| session.divergence().map(str::to_string))
+    }
+
+    /// State of the recording or replay in progress
+    pub fn replay_status(&self) -> Option<String> {
+        self.replay.lock().as_ref().map(ReplaySession::status)
+    }
+
+    /// Start recording basic block coverage of the loaded modules into `path`, dropping any earlier recording
+    pub fn start_coverage(&self, path: &Path) {
+        let modules = self.modules.read().clone();
+        tracing::info!("Recording coverage of {} module(s) to {}", modules.len(), path.display());
+        *self.coverage.lock() = Some((Coverage::new(modules), path.to_path_buf()));
+    }
+
+    /// Stop recording coverage and write it, describing what was written
+    pub fn stop_coverage(&self) -> std::result::Result<Option<String>, String> {
+        let Some((mut coverage, path)) = self.coverage.lock().take() else {
+            return Ok(None);
+        };
+        let summary = coverage.write(&path)?;
+        let mut message = format!("Coverage of {} basic block(s) written to {}", summary.blocks, path.display());
+        if summary.unmapped_blocks > 0 {
+            message.push_str(&format!(" ({} outside the loaded modules)", summary.unmapped_blocks));
+        }
+        Ok(Some(message))
+    }
+
+    /// Whether coverage is being recorded
+    pub fn is_recording_coverage(&self) -> bool {
+        self.coverage.lock().is_some()
+    }
+
+    /// Record the scheduler's pick, or switch to the recorded thread when replaying
+    fn replay_schedule(&self, scheduled: Option<ThreadId>) -> Option<ThreadId> {
+        let mut replay = self.replay.lock();
+        let Some(replay) = replay.as_mut() else {
+            return scheduled;
+        };
+        let thread = replay.schedule(scheduled);
+        if thread == scheduled {
+            return thread;
+        }
+        let switched = match (scheduled, thread) {
+            (Some(from), Some(to)) => self.scheduler.write().context_switch(from, to).is_ok(),
+            // The picked thread stays current and is put back on the next pick
+            (Some(_), None) => true,
+            _ => false,
+        };
+        if switched {
+            thread
+        } else {
+            replay.schedule_failed(thread, scheduled);
+            scheduled
+        }
+    }
+
+    /// Dump all PPU threads, writing the dump if enabled and keeping its report for `take_crash`
+    pub fn write_crash_dump(&self, kind: CrashKind, reason: &str, faulting_thread: Option<u32>) -> CrashReport {
+        if let Some(autosave) = self.autosave.as_ref() {
+            autosave.mark_crashed();
+        }
+        let mut dump = CrashDump::new(kind, reason);
+        dump.title_id = self.title_id.clone();
+        dump.modules = self.modules.read().iter().map(ModuleRange::to_string).collect();
+        dump.log = oc_core::logging::recent_lines();
+        for thread in self.ppu_threads.read().iter() {
+            // Threads still locked further up the stack are left out
+            let Some(thread) = thread.try_read() else {
+                continue;
+            };
+            let faulting = faulting_thread == Some(thread.id);
+            if faulting {
+                dump.capture_memory(&self.memory, thread.pc() as u32);
+            }
+            dump.threads.push(ThreadDump::new(&thread, self.unwind(&thread), faulting));
+        }
+
+        let path = if self.config.debug.crash_dumps {
+            match dump.write(&self.config.debug.crash_dump_dir) {
+                Ok(path) => {
+                    tracing::error!("Crash dump written to {}", path.display());
+                    Some(path)
+                }
+                Err(e) => {
+                    tracing::error!("Failed to write crash dump: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let report = CrashReport { kind, summary: dump.summary(), path };
+        *self.last_crash.lock() = Some(report.clone());
+        report
+    }
+
+    /// Take the report of the last crash
+    pub fn take_crash(&self) -> Option<CrashReport> {
+        self.last_crash.lock().take()
+    }
+
+    /// Set the title ID of the loaded game, named in crash dumps
+    pub fn set_title_id(&mut self, title_id: Option<&str>) {
+        self.title_id = title_id.map(str::to_string);
+    }
+
+    /// Keep background savestates of the loaded game in `dir` as the general settings ask
+    pub fn start_autosave(&mut self, dir: &Path) {
+        let general = &self.config.general;
+        let (true, Some(title_id)) = (general.auto_save_state, self.title_id.as_deref()) else {
+            return;
+        };
+        let interval = std::time::Duration::from_secs(general.autosave_interval.max(1) as u64);
+        match Autosaver::start(dir, title_id, interval, general.autosave_count as usize) {
+            Ok(autosaver) => self.autosave = Some(autosaver),
+            Err(e) => tracing::error!("Failed to start autosaving: {}", e),
+        }
+    }
+
+    /// Take an autosave at the end of the frame if one is due
+    fn autosave_if_due(&mut self) {
+        if !self.autosave.as_ref().is_some_and(Autosaver::is_due) {
+            return;
+        }
+        match self.capture_state() {
+            Ok(state) => {
+                if let Some(autosave) = self.autosave.as_mut() {
+                    autosave.save(state);
+                }
+            }
+            Err(e) => tracing::error!("Autosave failed: {}", e),
+        }
+    }
+
+    /// Save the whole emulated machine to `path`
+    ///
+    /// Call between frames, with the emulator paused or stopped.
+    pub fn save_state(&self, path: &Path) -> std::result::Result<SavestateInfo, String> {
+        let state = self.capture_state()?;
+        state.save(path)?;
+        tracing::info!("Saved state to {}", path.display());
+        Ok(state.info)
+    }
+
+    /// Take a savestate of the whole emulated machine in memory, between frames
+    pub fn capture_state(&self) -> std::result::Result<Savestate, String> {
+        let mut info = SavestateInfo::new(self.title_id.as_deref(), self.frame_count, self.total_cycles);
+        info.thumbnail = self
+            .get_framebuffer()
+            .and_then(|frame| Thumbnail::from_frame(frame.width, frame.height, &frame.pixels));
+
+        let mut state = Savestate::new(info);
+        state.add_section(b"PPU ", |w| {
+            let threads = self.ppu_threads.read();
+            w.count(threads.len());
+            for thread in threads.iter() {
+                thread.read().save_state(w);
+            }
+        });
+        state.add_section(b"SPU ", |w| {
+            let threads = self.spu_threads.read();
+            w.count(threads.len());
+            for thread in threads.iter() {
+                thread.read().save_state(w);
+            }
+        });
+        state.add_section(b"SCHD", |w| self.scheduler.read().save_state(w));
+        state.add_section(b"INTR", |w| self.interrupts.lock().save_state(w));
+        state.add_section(b"TIME", |w| self.time_base.save_state(w));
+        state.add_section(b"LV2 ", |w| self.syscall_handler.save_state(w));
+        state.add_section(b"HLE ", |w| oc_hle::get_hle_context().save_state(w));
+        state.add_section(b"RSX ", |w| self.rsx_thread.read().save_state(w));
+        let mut memory = Vec::new();
+        self.memory
+            .snapshot()
+            .write_to(&mut memory)
+            .map_err(|e| format!("Failed to snapshot memory: {}", e))?;
+        state.add_section(b"MEM ", |w| w.raw(&memory));
+        Ok(state)
+    }
+
+    /// Restore the emulated machine from the savestate at `path`
+    ///
+    /// The savestate must be of the loaded game. Sections are decoded before
+    /// anything is replaced where possible; if a later one still fails the
+    /// emulator is stopped, as its state is then a mix of both.
+    pub fn load_state(&mut self, path: &Path) -> std::result::Result<SavestateInfo, String> {
+        let state = Savestate::load(path)?;
+        if let (Some(saved), Some(loaded)) = (state.info.title_id.as_deref(), self.title_id.as_deref()) {
+            if saved != loaded {
+                return Err(format!("{} is a savestate of {}, not {}", path.display(), saved, loaded));
+            }
+        }
+        let error = |e: std::io::Error| format!("{}: {}", path.display(), e);
+
+        let ppu_threads = state
+            .read_section(b"PPU ", |r| {
+                (0..r.count()?)
+                    .map(|id| {
+                        let mut thread = PpuThread::new(id as u32, self.memory.clone());
+                        thread.load_state(r)?;
+                        if thread.id != id as u32 {
+                            return Err(invalid(&format!("PPU thread {} saved at index {}", thread.id, id)));
+                        }
+                        Ok(Arc::new(RwLock::new(thread)))
+                    })
+                    .collect::<std::io::Result<Vec<_>>>()
+            })
+            .map_err(error)?;
+        let spu_threads = state
+            .read_section(b"SPU ", |r| {
+                (0..r.count()?)
+                    .map(|id| {
+                        let mut thread = SpuThread::new(id as u32, self.memory.clone());
+                        thread.load_state(r)?;
+                        if thread.id != id as u32 {
+                            return Err(invalid(&format!("SPU thread {} saved at index {}", thread.id, id)));
+                        }
+                        Ok(Arc::new(RwLock::new(thread)))
+                    })
+                    .collect::<std::io::Result<Vec<_>>>()
+            })
+            .map_err(error)?;
+        let scheduler = state
+            .read_section(b"SCHD", |r| {
+                let mut scheduler = Scheduler::new();
+                scheduler.load_state(r)?;
+                Ok(scheduler)
+            })
+            .map_err(error)?;
+        let interrupts = state.read_section(b"INTR", InterruptThreads::load_state).map_err(error)?;
+        let memory = state
+            .read_section(b"MEM ", |r| {
+                let mut data = r.raw(r.remaining())?;
+                MemorySnapshot::read_from(&mut data)
+            })
+            .map_err(error)?;
+
+        let restored = self
+            .memory
+            .restore(&memory)
+            .map_err(|e| format!("Failed to restore memory: {}", e))
+            // Before LV2, so timers resume on the restored clock
+            .and_then(|_| state.read_section(b"TIME", |r| self.time_base.load_state(r)).map_err(error))
+            .and_then(|_| state.read_section(b"LV2 ", |r| self.syscall_handler.load_state(r)).map_err(error))
+            .and_then(|_| state.read_section(b"HLE ", |r| oc_hle::get_hle_context_mut().load_state(r)).map_err(error))
+            .and_then(|_| state.read_section(b"RSX ", |r| self.rsx_thread.write().load_state(r)).map_err(error));
+        if let Err(e) = restored {
+            self.set_state(RunnerState::Stopped);
+            return Err(e);
+        }
+
+        // Decrementers go on counting down from their saved values on the restored clock
+        for thread in &ppu_threads {
+            thread.write().set_time_base(self.time_base.clone());
+        }
+        *self.ppu_threads.write() = ppu_threads;
+        *self.spu_threads.write() = spu_threads;
+        *self.scheduler.write() = scheduler;
+        *self.interrupts.lock() = interrupts;
+        // Interrupts pending in the restored kernel are dispatched again
+        let pending = oc_lv2::interrupt::has_dispatches(self.syscall_handler.object_manager())
+            || oc_hle::get_hle_context().guest_calls.has_pending();
+        self.interrupt_pending.store(pending, Ordering::Relaxed);
+        self.frame_count = state.info.frame;
+        self.total_cycles = state.info.total_cycles;
+        self.last_frame_time = Instant::now();
+        self.debug_step = None;
+        self.rsx_flip_count = self.rsx_thread.read().flip_count();
+        self.av_sync.reset();
+        self.perf.restart();
+        self.audio_ring.clear();
+        if let Some(autosave) = self.autosave.as_mut() {
+            autosave.postpone();
+        }
+        tracing::info!("Loaded state from {}", path.display());
+        Ok(state.info)
+    }
+
+    /// Unwind a PPU thread's stack, bounded by its stack when known
+    fn unwind(&self, thread: &PpuThread) -> Vec<StackFrame> {
+        // stack_addr is the top of the stack, where the first frame starts
+        let stack = (thread.stack_size != 0)
+            .then(|| thread.stack_addr.saturating_sub(thread.stack_size) as u64..=thread.stack_addr as u64);
+        unwind_ppu(&thread.regs, &self.memory, stack, &self.functions.read())
+    }
+
     /// Execute a single SPU thread step
     fn execute_spu_thread(&self, thread_id: u32) -> Result<()> {
         let threads = self.spu_threads.read();
@@ -486,9 +2412,29 @@ impl EmulatorRunner {
             return Ok(());
         }
 
+        let mut debugger = self.spu_debugger.lock();
+        if !debugger.should_run(thread_id as usize, &thread) {
+            return Ok(());
+        }
+
         // Execute one instruction
+        let pc = thread.pc();
+        let opcode = thread.ls_read_u32(pc);
+        let before = self.is_tracing().then(|| thread.regs.clone());
+        let channel_access = SpuDebugger::begin_channel_access(&thread, opcode);
         match self.spu_interpreter.step(&mut thread) {
-            Ok(()) => Ok(()),
+            Ok(()) => {
+                if let Some(before) = before {
+                    self.trace(|trace| trace.record_spu(thread_id, pc, opcode, &before, &thread.regs));
+                }
+                if let Some(access) = channel_access {
+                    debugger.finish_channel_access(thread_id as usize, access, &thread);
+                }
+                if let Some(value) = thread.channels.get_outbound_interrupt_mailbox() {
+                    self.raise_spu_interrupt(thread_id, value);
+                }
+                Ok(())
+            }
             Err(e) => {
                 tracing::error!("SPU thread {} error: {}", thread_id, e);
                 thread.stop();
@@ -501,13 +2447,48 @@ impl EmulatorRunner {
         }
     }
 
+    /// Hand the video frames the game retrieved this frame to the RSX
+    ///
+    /// Frames retrieved into local memory go straight to the texture cache,
+    /// sharing the decoder's pixels. Ones in main memory are written there
+    /// as the big-endian ARGB the game reads.
+    fn import_video_frames(&self) {
+        let frames = oc_hle::get_hle_context_mut().vdec.take_frames();
+        if frames.is_empty() {
+            return;
+        }
+        let mut rsx = self.rsx_thread.write();
+        for frame in frames {
+            let (Ok(width), Ok(height)) = (u16::try_from(frame.width), u16::try_from(frame.height)) else {
+                continue;
+            };
+            match frame.addr.checked_sub(RSX_MEM_BASE).filter(|&offset| offset < RSX_MEM_SIZE) {
+                Some(offset) => rsx.import_video_frame(offset, width, height, frame.pixels),
+                None => {
+                    let argb: Vec<u8> = frame.pixels.chunks_exact(4).flat_map(|p| [p[3], p[0], p[1], p[2]]).collect();
+                    if let Err(e) = self.memory.write_bytes(frame.addr, &argb) {
+                        tracing::debug!("Failed to write video frame at 0x{:08x}: {}", frame.addr, e);
+                    }
+                }
+            }
+        }
+    }
+
     /// Process RSX graphics commands
     fn process_rsx(&self) -> Result<()> {
         let mut rsx = self.rsx_thread.write();
-        
-        // Process any pending commands in the FIFO
-        rsx.process_commands();
-        
+        let mut debugger = self.rsx_debugger.lock();
+
+        // Process pending commands in the FIFO, leaving them queued while the RSX debugger holds them
+        rsx.process_commands_while(|state, cmd| {
+            if !debugger.should_execute(cmd.method) {
+                return false;
+            }
+            debugger.record_command(cmd.method, cmd.data);
+            debugger.record_draw(state, cmd.method, cmd.data);
+            true
+        });
+
         Ok(())
     }
 
@@ -516,11 +2497,376 @@ impl EmulatorRunner {
         &self.memory
     }
 
+    /// SPU debugger checked before every SPU instruction
+    pub fn spu_debugger(&self) -> &Arc<Mutex<SpuDebugger>> {
+        &self.spu_debugger
+    }
+
+    /// SPU threads, indexed by thread ID
+    pub fn spu_threads(&self) -> Vec<Arc<RwLock<SpuThread>>> {
+        self.spu_threads.read().clone()
+    }
+
+    /// RSX thread whose state the RSX debugger inspects
+    pub fn rsx_thread(&self) -> &Arc<RwLock<RsxThread>> {
+        &self.rsx_thread
+    }
+
+    /// RSX debugger checked before every RSX command
+    pub fn rsx_debugger(&self) -> &Arc<Mutex<RsxDebugger>> {
+        &self.rsx_debugger
+    }
+
+    /// Profiler fed with PPU hotspots and call stack samples while enabled
+    pub fn profiler(&self) -> &Arc<Mutex<Profiler>> {
+        &self.profiler
+    }
+
+    /// Code patches and hooks of the loaded game
+    pub fn patches(&self) -> &Arc<Mutex<PatchManager>> {
+        &self.patches
+    }
+
+    /// Cheat list of the loaded game
+    pub fn cheats(&self) -> &Arc<Mutex<CheatManager>> {
+        &self.cheats
+    }
+
     /// Get syscall handler reference
     pub fn syscall_handler(&self) -> &Arc<SyscallHandler> {
         &self.syscall_handler
     }
 
+    /// Get the final audio mixer
+    pub fn audio_mixer(&self) -> &Arc<Mutex<AudioMixer>> {
+        &self.audio_mixer
+    }
+
+    /// Get the audio dump recorder
+    pub fn audio_recorder(&self) -> &Arc<Mutex<AudioRecorder>> {
+        &self.audio_recorder
+    }
+
+    /// Code the game exited with through sys_process_exit, if it has exited
+    pub fn exit_code(&self) -> Option<i32> {
+        self.syscall_handler.process_manager().exit_code()
+    }
+
+    /// Save the current frame as a PNG in `dir`
+    pub fn save_screenshot(&self, dir: &Path) -> std::result::Result<PathBuf, String> {
+        let frame = self.get_framebuffer().ok_or("There is no frame to capture")?;
+        let path = dir.join(capture::capture_name(self.title_id.as_deref())).with_extension("png");
+        capture::save_screenshot(&frame, &path)?;
+        tracing::info!("Screenshot saved to {}", path.display());
+        Ok(path)
+    }
+
+    /// Start recording presented frames to a video in the capture directory
+    ///
+    /// With `mux_audio` the final mix is dumped to a WAV alongside and muxed in
+    /// at the end, unless an audio dump is already running.
+    pub fn start_video_recording(&mut self, config: &CaptureConfig) -> std::result::Result<PathBuf, String> {
+        if self.video.is_some() {
+            return Err("A video is already being recorded".to_string());
+        }
+        let (width, height) = self.get_framebuffer_dimensions();
+        let fps = 1.0 / self.av_sync.frame_period().as_secs_f64();
+        let base = config.output_dir.join(capture::capture_name(self.title_id.as_deref()));
+        let video = VideoRecorder::start(&base, config, fps, width, height)?;
+
+        let mut owns_audio = false;
+        if config.mux_audio {
+            let mut recorder = self.audio_recorder.lock();
+            if recorder.is_recording() {
+                tracing::warn!("An audio dump is running; the video is recorded without audio");
+            } else {
+                recorder.set_format(AudioDumpFormat::Wav);
+                recorder.set_record_ports(false);
+                match recorder.start() {
+                    Ok(()) => owns_audio = true,
+                    Err(e) => tracing::warn!("Recording video without audio: {}", e),
+                }
+            }
+        }
+        let output = video.output().to_path_buf();
+        self.video = Some((video, owns_audio));
+        Ok(output)
+    }
+
+    /// Stop recording video, returning the thread finishing the file
+    pub fn stop_video_recording(&mut self) -> Option<std::thread::JoinHandle<std::result::Result<PathBuf, String>>> {
+        let (video, owns_audio) = self.video.take()?;
+        let audio = if owns_audio {
+            let files = self.audio_recorder.lock().stop();
+            files.into_iter().next().map(|wav| AudioMux { wav, delete: true })
+        } else {
+            None
+        };
+        Some(video.finish(audio))
+    }
+
+    /// Whether a video is being recorded
+    pub fn is_recording_video(&self) -> bool {
+        self.video.is_some()
+    }
+
+    /// How long the current video has been recording
+    pub fn video_recording_time(&self) -> Option<std::time::Duration> {
+        self.video.as_ref().map(|(video, _)| video.elapsed())
+    }
+
+    /// Get the audio output ring buffer
+    pub fn audio_ring(&self) -> &Arc<AudioRingBuffer> {
+        &self.audio_ring
+    }
+
+    /// Get the controller ports read by cellPad
+    pub fn pad_ports(&self) -> &Arc<PadPorts> {
+        &self.pad_ports
+    }
+
+    /// On-screen keyboard the game has open, with its load count to tell requests apart
+    pub fn osk_request(&self) -> Option<(u32, OskRequest)> {
+        let ctx = oc_hle::get_hle_context();
+        let request = ctx.osk_dialog.request()?.clone();
+        Some((ctx.osk_dialog.generation(), request))
+    }
+
+    /// Send the text typed on the on-screen keyboard to the game (None = cancelled)
+    pub fn finish_osk(&mut self, text: Option<&str>) {
+        match oc_hle::cell_osk_dialog::finish_osk(text) {
+            Some(result) => tracing::debug!("On-screen keyboard finished: {:?}", result),
+            None => tracing::warn!("No on-screen keyboard is open"),
+        }
+    }
+
+    /// Game data error dialog the game has open, with its count to tell dialogs apart
+    pub fn game_error_dialog(&self) -> Option<(u32, ContentErrorDialog)> {
+        let ctx = oc_hle::get_hle_context();
+        let dialog = ctx.game.error_dialog()?.clone();
+        Some((ctx.game.error_dialog_generation(), dialog))
+    }
+
+    /// Game data install the system is running, for its progress dialog
+    pub fn game_data_install(&self) -> Option<GameInstallInfo> {
+        let ctx = oc_hle::get_hle_context();
+        ctx.game.is_data_installing().then(|| ctx.game.get_install_info().clone())
+    }
+
+    /// Close the game data error dialog once the user dismissed it
+    pub fn close_game_error_dialog(&mut self) {
+        match oc_hle::cell_game::close_content_error_dialog() {
+            Some(dialog) if dialog.exits_game() => tracing::info!("Game data error dialog closed, the game exits"),
+            Some(_) => tracing::debug!("Game data error dialog closed"),
+            None => tracing::warn!("No game data error dialog is open"),
+        }
+    }
+
+    /// Mount the disc folder at `path` at /dev_bdvd, telling the game if `notify`
+    fn mount_disc(&self, path: &Path, notify: bool) -> Result<()> {
+        self.disc
+            .mount_disc(self.syscall_handler.vfs(), path.to_path_buf())
+            .map_err(EmulatorError::GameNotFound)?;
+        let (game_id, label) = self
+            .disc
+            .disc_info()
+            .map(|info| (info.game_id.unwrap_or_default(), info.title.unwrap_or_default()))
+            .unwrap_or_default();
+        oc_hle::get_hle_context_mut().game.set_bdvd_root(Some(path.to_path_buf()));
+        if notify {
+            oc_hle::cell_sysutil::insert_disc(&game_id, &label);
+        } else {
+            oc_hle::get_hle_context_mut().sysutil.set_disc_info(oc_hle::cell_sysutil::DiscInfo {
+                status: oc_hle::cell_sysutil::DiscStatus::Ready,
+                disc_type: 1,
+                game_id,
+                label,
+            });
+        }
+        Ok(())
+    }
+
+    /// Put the disc folder at `path` in the drive, ejecting the disc in it first
+    ///
+    /// The game sees the disc removed and a new one inserted, as when the
+    /// user swaps discs of a multi-disc title.
+    pub fn insert_disc(&self, path: &Path) -> Result<()> {
+        self.eject_disc();
+        self.mount_disc(path, true)?;
+        tracing::info!("Inserted disc {}", path.display());
+        Ok(())
+    }
+
+    /// Take the disc out of the drive, telling the game; false if the drive was empty
+    pub fn eject_disc(&self) -> bool {
+        if !self.disc.is_disc_mounted() {
+            return false;
+        }
+        self.disc.unmount_disc(self.syscall_handler.vfs());
+        oc_hle::get_hle_context_mut().game.set_bdvd_root(None);
+        oc_hle::cell_sysutil::eject_disc();
+        true
+    }
+
+    /// The disc in the drive
+    pub fn disc(&self) -> Option<oc_vfs::DiscInfo> {
+        self.disc.disc_info()
+    }
+
+    /// Get the connected host gamepads
+    pub fn gamepad_devices(&self) -> Vec<HostGamepadInfo> {
+        let mut devices: Vec<HostGamepadInfo> = self
+            .gamepads
+            .iter()
+            .flat_map(GamepadBackend::devices)
+            .chain(self.ds3_hid.iter().flat_map(Ds3HidBackend::devices))
+            .chain(self.ds4_hid.iter().flat_map(Ds4HidBackend::devices))
+            .chain(self.keyboard_pad.iter().map(|&(port, _)| HostGamepadInfo {
+                name: KEYBOARD_PAD_NAME.to_string(),
+                port,
+                vendor_id: None,
+                product_id: None,
+                has_mapping: true,
+            }))
+            .chain(self.instrument.iter().map(|(port, instrument)| HostGamepadInfo {
+                name: MappedInstrument::device_name(instrument.kind()).to_string(),
+                port: *port,
+                vendor_id: None,
+                product_id: None,
+                has_mapping: true,
+            }))
+            .collect();
+        devices.sort_by_key(|device| device.port);
+        devices
+    }
+
+    /// Frame pacing, thread utilization and shader counters of the recent frames
+    pub fn perf_stats(&self) -> PerfStats {
+        let rsx = self.rsx_thread.read();
+        let mut stats = self.perf.stats(self.av_sync.frame_period(), rsx.shader_compile_count());
+        stats.rsx = rsx.frame_counters();
+        stats
+    }
+
+    /// Get A/V sync counters
+    pub fn av_sync_stats(&self) -> AvSyncStats {
+        self.av_sync.stats()
+    }
+
+    /// Current speed mode
+    pub fn speed_mode(&self) -> SpeedMode {
+        self.limiter.mode()
+    }
+
+    /// Switch between normal speed, turbo and slow motion
+    pub fn set_speed_mode(&mut self, mode: SpeedMode) {
+        if self.limiter.mode() != mode {
+            self.limiter.set_mode(mode);
+            self.apply_speed();
+        }
+    }
+
+    /// Whether frame limiting is on
+    pub fn is_frame_limited(&self) -> bool {
+        self.limiter.is_limited()
+    }
+
+    /// Turn frame limiting on or off
+    pub fn set_frame_limited(&mut self, limited: bool) {
+        self.limiter.set_limited(limited);
+        self.apply_speed();
+    }
+
+    /// Take a new frame limit and turbo and slow motion speeds
+    pub fn configure_speed(&mut self, gpu: &GpuConfig) {
+        self.limiter.configure(gpu);
+        self.apply_speed();
+    }
+
+    /// Speed emulation runs at relative to real time, None when unpaced
+    pub fn speed(&self) -> Option<f64> {
+        self.limiter.speed()
+    }
+
+    /// Hand the limiter's speed to frame pacing and the audio output
+    fn apply_speed(&mut self) {
+        let speed = self.limiter.speed();
+        self.av_sync.set_speed(speed);
+        // Unpaced emulation keeps guest time at real time
+        self.time_base.set_speed(speed.unwrap_or(1.0));
+        self.audio_mixer.lock().set_speed(speed);
+        tracing::debug!("Emulation speed: {:?} ({:?})", self.limiter.mode(), speed);
+    }
+
+    /// Build the VFS file access report for the current session
+    ///
+    /// Only contains entries if `debug.trace_vfs` was enabled.
+    pub fn vfs_access_report(&self, title_id: &str) -> VfsAccessReport {
+        self.syscall_handler.vfs().tracer().report(title_id)
+    }
+
+    /// Write the VFS file access report beside the log file
+    pub fn write_vfs_access_report(&self, title_id: &str) -> Result<PathBuf> {
+        let path = self
+            .config
+            .debug
+            .log_path
+            .with_file_name(format!("{}_file_access.txt", title_id));
+        self.vfs_access_report(title_id).write_to(&path)?;
+        tracing::info!("Wrote VFS access report to {}", path.display());
+        Ok(path)
+    }
+
+    /// Build the report of unimplemented features the game touched this session
+    pub fn missing_features(&self, title_id: &str) -> MissingFeatureReport {
+        let syscalls = self
+            .syscall_handler
+            .unknown_syscalls()
+            .into_iter()
+            .map(|(number, calls)| MissingFeature::new(format!("syscall {}", number), calls))
+            .collect();
+        let hle_functions = missing_functions(&self.imports.read(), &oc_hle::ModuleRegistry::new())
+            .iter()
+            .map(|function| MissingFeature::new(function.display_name(), 0))
+            .collect();
+        let rsx_methods = self
+            .rsx_thread
+            .read()
+            .unimplemented_methods()
+            .into_iter()
+            .map(|(method, calls)| MissingFeature::new(format!("method 0x{:04X}", method), calls))
+            .collect();
+        let codecs = {
+            let hle = oc_hle::get_hle_context();
+            let vdec = hle.vdec.opened_codecs().into_iter().map(|(codec, calls)| {
+                let name = oc_hle::cell_vdec::codec_name(codec)
+                    .map_or_else(|| format!("cellVdec codec {}", codec), |name| format!("cellVdec {}", name));
+                MissingFeature::new(name, calls)
+            });
+            // LPCM is passed through as is, every other codec only produces placeholder output
+            let adec = hle.adec.opened_codecs().into_iter();
+            let adec = adec.filter(|&(codec, _)| codec != CellAdecCodecType::Lpcm as u32).map(|(codec, calls)| {
+                let name = oc_hle::cell_adec::codec_name(codec)
+                    .map_or_else(|| format!("cellAdec codec {}", codec), |name| format!("cellAdec {}", name));
+                MissingFeature::new(name, calls)
+            });
+            vdec.chain(adec).collect()
+        };
+        MissingFeatureReport::new(title_id, syscalls, hle_functions, rsx_methods, codecs)
+    }
+
+    /// Write the missing feature report beside the log file
+    pub fn write_missing_features(&self, report: &MissingFeatureReport) -> Result<PathBuf> {
+        let path = self
+            .config
+            .debug
+            .log_path
+            .with_file_name(format!("{}_missing_features.json", report.title_id));
+        report.write_to(&path)?;
+        tracing::info!("Wrote missing feature report to {}", path.display());
+        Ok(path)
+    }
+
     /// Get scheduler reference
     pub fn scheduler(&self) -> &Arc<RwLock<Scheduler>> {
         &self.scheduler
@@ -562,6 +2908,106 @@ impl EmulatorRunner {
     }
 }
 
+/// Root of the disc folder `path` is in or is, the folder holding PS3_GAME
+fn disc_root(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|dir| dir.join("PS3_GAME").join("PARAM.SFO").is_file())
+}
+
+/// The runner as seen by one GDB server
+struct DebugTarget<'a> {
+    runner: &'a mut EmulatorRunner,
+    arch: GdbArch,
+}
+
+impl DebugTarget<'_> {
+    /// Emulator thread index of a debugger thread ID
+    fn index(thread: u32) -> Option<usize> {
+        thread.checked_sub(1).map(|index| index as usize)
+    }
+
+    fn ppu(&self, thread: u32) -> Option<Arc<RwLock<PpuThread>>> {
+        self.runner.ppu_threads.read().get(Self::index(thread)?).cloned()
+    }
+
+    fn spu(&self, thread: u32) -> Option<Arc<RwLock<SpuThread>>> {
+        self.runner.spu_threads.read().get(Self::index(thread)?).cloned()
+    }
+}
+
+impl GdbTarget for DebugTarget<'_> {
+    fn threads(&self) -> Vec<GdbThread> {
+        let thread = |index: usize, name: &str| GdbThread { id: index as u32 + 1, name: name.to_string() };
+        match self.arch {
+            GdbArch::Ppu => self.runner.ppu_threads.read().iter().enumerate().map(|(i, t)| thread(i, &t.read().name)).collect(),
+            GdbArch::Spu => self.runner.spu_threads.read().iter().enumerate().map(|(i, t)| thread(i, &t.read().name)).collect(),
+        }
+    }
+
+    fn read_registers(&self, thread: u32) -> Option<Vec<u8>> {
+        match self.arch {
+            GdbArch::Ppu => Some(ppu_registers(&self.ppu(thread)?.read().regs)),
+            GdbArch::Spu => {
+                let spu = self.spu(thread)?;
+                let spu = spu.read();
+                Some(spu_registers(&spu.regs, spu.id))
+            }
+        }
+    }
+
+    fn write_register(&mut self, thread: u32, reg: usize, value: &[u8]) -> bool {
+        match self.arch {
+            GdbArch::Ppu => self.ppu(thread).is_some_and(|ppu| set_ppu_register(&mut ppu.write().regs, reg, value)),
+            GdbArch::Spu => self.spu(thread).is_some_and(|spu| set_spu_register(&mut spu.write().regs, reg, value)),
+        }
+    }
+
+    fn read_memory(&self, thread: u32, addr: u64, len: usize) -> Option<Vec<u8>> {
+        match self.arch {
+            GdbArch::Ppu => self.runner.memory.read_bytes(u32::try_from(addr).ok()?, len as u32).ok(),
+            GdbArch::Spu => {
+                let spu = self.spu(thread)?;
+                let start = usize::try_from(addr).ok()?;
+                let data = spu.read().local_storage.get(start..start.checked_add(len)?).map(<[u8]>::to_vec);
+                data
+            }
+        }
+    }
+
+    fn write_memory(&mut self, thread: u32, addr: u64, data: &[u8]) -> bool {
+        match self.arch {
+            GdbArch::Ppu => u32::try_from(addr).is_ok_and(|addr| self.runner.memory.write_bytes(addr, data).is_ok()),
+            GdbArch::Spu => {
+                let Some(spu) = self.spu(thread) else {
+                    return false;
+                };
+                let mut spu = spu.write();
+                let Some(start) = usize::try_from(addr).ok() else {
+                    return false;
+                };
+                match start.checked_add(data.len()).and_then(|end| spu.local_storage.get_mut(start..end)) {
+                    Some(local_storage) => {
+                        local_storage.copy_from_slice(data);
+                        true
+                    }
+                    None => false,
+                }
+            }
+        }
+    }
+
+    fn halt(&mut self) {
+        self.runner.halt_for_debugger();
+    }
+
+    fn resume(&mut self, step: Option<u32>) {
+        let step = step.and_then(Self::index).map(|index| match self.arch {
+            GdbArch::Ppu => ThreadId::Ppu(index as u32),
+            GdbArch::Spu => ThreadId::Spu(index as u32),
+        });
+        self.runner.resume_for_debugger(step);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -597,6 +3043,29 @@ mod tests {
         assert!(runner.is_stopped());
     }
 
+    #[test]
+    fn test_time_base_follows_runner_state() {
+        let mut runner = EmulatorRunner::new(Config::default()).unwrap();
+        let time_base = runner.time_base().clone();
+        assert!(time_base.is_paused());
+        // PPU threads and LV2 read the same clock
+        runner.create_ppu_thread(100).unwrap();
+        time_base.advance(std::time::Duration::from_secs(1));
+        assert_eq!(runner.ppu_threads.read()[0].read().time_base().now(), time_base.now());
+        assert_eq!(runner.syscall_handler.time_base().now(), time_base.now());
+
+        runner.start().unwrap();
+        assert!(!time_base.is_paused());
+        runner.pause().unwrap();
+        let paused_at = time_base.now();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        assert_eq!(time_base.now(), paused_at);
+        runner.resume().unwrap();
+        assert!(!time_base.is_paused());
+        runner.stop().unwrap();
+        assert!(time_base.is_paused());
+    }
+
     #[test]
     fn test_create_ppu_thread() {
         let config = Config::default();
@@ -624,4 +3093,462 @@ mod tests {
         assert_eq!(thread_id2, 1);
         assert_eq!(runner.spu_thread_count(), 2);
     }
+
+    #[test]
+    fn test_rsx_debugger_holds_fifo() {
+        use oc_rsx::fifo::RsxCommand;
+        use oc_rsx::methods::{NV4097_DRAW_ARRAYS, NV4097_SET_BLEND_ENABLE};
+
+        let runner = EmulatorRunner::new(Config::default()).unwrap();
+        {
+            let mut rsx = runner.rsx_thread().write();
+            rsx.fifo.push(RsxCommand { method: NV4097_SET_BLEND_ENABLE, data: 1 });
+            rsx.fifo.push(RsxCommand { method: NV4097_DRAW_ARRAYS, data: 3 << 24 });
+        }
+        runner.rsx_debugger().lock().break_on_method(NV4097_DRAW_ARRAYS);
+
+        runner.process_rsx().unwrap();
+        assert!(runner.rsx_thread().read().gfx_state.blend_enable);
+        assert_eq!(runner.rsx_thread().read().fifo.len(), 1);
+        assert!(runner.rsx_debugger().lock().is_paused());
+
+        runner.rsx_debugger().lock().resume();
+        runner.process_rsx().unwrap();
+        assert!(runner.rsx_thread().read().fifo.is_empty());
+        let debugger = runner.rsx_debugger().lock();
+        assert_eq!(debugger.get_command_history(10).len(), 2);
+        assert_eq!(debugger.draw_calls().next().unwrap().count, 3);
+    }
+
+    #[test]
+    fn test_spu_debugger_breakpoint_and_channel_log() {
+        use oc_spu::channels::channel_ids::SPU_WR_OUT_MBOX;
+
+        let runner = EmulatorRunner::new(Config::default()).unwrap();
+        runner.create_spu_thread(100).unwrap();
+        {
+            let threads = runner.spu_threads();
+            let mut thread = threads[0].write();
+            // wrch $SPU_WrOutMbox, $1 twice
+            thread.ls_write_u32(0, (0b00100001101 << 21) | (SPU_WR_OUT_MBOX << 7) | 1);
+            thread.ls_write_u32(4, (0b00100001101 << 21) | (SPU_WR_OUT_MBOX << 7) | 1);
+            thread.regs.write_preferred_u32(1, 0x42);
+            thread.start();
+        }
+        runner.spu_debugger().lock().breakpoints[0].add_execution_breakpoint(4);
+
+        runner.execute_spu_thread(0).unwrap();
+        runner.execute_spu_thread(0).unwrap();
+        let pc = || runner.spu_threads()[0].read().pc();
+        assert_eq!(pc(), 4);
+        assert!(runner.spu_debugger().lock().is_paused(0));
+
+        runner.spu_debugger().lock().resume(0);
+        runner.execute_spu_thread(0).unwrap();
+        assert_eq!(pc(), 8);
+
+        let debugger = runner.spu_debugger().lock();
+        let events: Vec<_> = debugger.channel_activity(0).collect();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| e.channel == SPU_WR_OUT_MBOX && e.value == 0x42));
+        // The outbound mailbox holds one entry, so the second write finds it full
+        assert!(!events[0].stalled);
+        assert!(events[1].stalled);
+    }
+
+    #[test]
+    fn test_vfs_tracing_follows_config() {
+        let mut config = Config::default();
+        config.debug.trace_vfs = true;
+        let runner = EmulatorRunner::new(config).unwrap();
+        assert!(runner.syscall_handler().vfs().tracer().is_enabled());
+
+        let runner = EmulatorRunner::new(Config::default()).unwrap();
+        assert!(!runner.syscall_handler().vfs().tracer().is_enabled());
+        assert!(runner.vfs_access_report("TEST00000").entries.is_empty());
+    }
+
+    #[test]
+    fn test_disc_swap() {
+        let root = std::env::temp_dir().join(format!("oc_runner_disc_{}", std::process::id()));
+        let (disc1, disc2) = (root.join("disc1"), root.join("disc2"));
+        for disc in [&disc1, &disc2] {
+            std::fs::create_dir_all(disc.join("PS3_GAME/USRDIR")).unwrap();
+            std::fs::write(disc.join("PS3_GAME/PARAM.SFO"), b"").unwrap();
+        }
+        assert_eq!(disc_root(&disc1.join("PS3_GAME/USRDIR/EBOOT.BIN")), Some(disc1.as_path()));
+        assert_eq!(disc_root(&root), None);
+
+        let runner = EmulatorRunner::new(Config::default()).unwrap();
+        assert!(!runner.eject_disc());
+        runner.insert_disc(&disc1).unwrap();
+        runner.insert_disc(&disc2).unwrap();
+        assert_eq!(runner.disc().unwrap().path, disc2);
+        assert!(runner.syscall_handler().vfs().is_mounted("/dev_bdvd"));
+        assert!(runner.eject_disc());
+        assert!(runner.disc().is_none());
+        assert!(runner.insert_disc(&root.join("missing")).is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_debugger_breakpoint_and_step() {
+        let mut runner = EmulatorRunner::new(Config::default()).unwrap();
+        runner.create_ppu_thread(100).unwrap();
+        runner.create_spu_thread(100).unwrap();
+
+        let code = runner.memory.allocate(0x1000, 0x1000, oc_memory::PageFlags::RWX).unwrap();
+
+        let mut target = DebugTarget { runner: &mut runner, arch: GdbArch::Ppu };
+        assert_eq!(target.threads().len(), 1);
+        assert!(target.write_register(1, 64, &(code as u64).to_be_bytes()));
+        // nop
+        assert!(target.write_memory(1, code as u64, &[0x60, 0, 0, 0]));
+        assert_eq!(target.read_memory(1, code as u64, 4).unwrap(), [0x60, 0, 0, 0]);
+        assert!(target.read_registers(2).is_none());
+
+        let mut target = DebugTarget { runner: &mut runner, arch: GdbArch::Spu };
+        assert!(target.write_memory(1, 0x3FFFC, &[1, 2, 3, 4]));
+        assert!(!target.write_memory(1, 0x3FFFE, &[1, 2, 3, 4]));
+        assert_eq!(target.read_memory(1, 0x3FFFC, 4).unwrap(), [1, 2, 3, 4]);
+
+        // Stepping runs one instruction and pauses again
+        runner.start().unwrap();
+        runner.ppu_threads.read()[0].write().start();
+        runner.resume_for_debugger(Some(ThreadId::Ppu(0)));
+        runner.run_frame().unwrap();
+        assert!(runner.is_paused());
+        assert_eq!(runner.ppu_threads.read()[0].read().pc(), code as u64 + 4);
+        assert_eq!(runner.debug_resume_from, Some(ThreadId::Ppu(0)));
+    }
+
+    #[test]
+    fn test_instruction_trace() {
+        let runner = EmulatorRunner::new(Config::default()).unwrap();
+        runner.create_ppu_thread(100).unwrap();
+        let code = runner.memory.allocate(0x1000, 0x1000, oc_memory::PageFlags::RWX).unwrap();
+        // li r3, 100 ; nop ; nop
+        runner.memory.write_be32(code, 0x3860_0064).unwrap();
+        runner.memory.write_be32(code + 4, 0x6000_0000).unwrap();
+        runner.memory.write_be32(code + 8, 0x6000_0000).unwrap();
+        {
+            let threads = runner.ppu_threads.read();
+            let mut thread = threads[0].write();
+            thread.set_pc(code as u64);
+            thread.start();
+        }
+
+        let path = std::env::temp_dir().join(format!("oc-trace-{}.txt.gz", std::process::id()));
+        let config = DebugConfig {
+            trace_ppu: true,
+            trace_path: path.clone(),
+            trace_stop: format!("0x{:x}", code + 4),
+            ..Default::default()
+        };
+        runner.start_trace(&config).unwrap();
+        for _ in 0..3 {
+            runner.execute_ppu_thread(0).unwrap();
+        }
+        // The stop trigger ended the trace after the nop
+        assert!(!runner.is_tracing());
+        assert_eq!(runner.stop_trace(), Ok(2));
+
+        let mut text = String::new();
+        let file = std::fs::File::open(&path).unwrap();
+        std::io::Read::read_to_string(&mut flate2::read::GzDecoder::new(file), &mut text).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let lines: Vec<_> = text.lines().filter(|line| !line.starts_with('#')).collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("r3=0000000000000064"), "{}", lines[0]);
+    }
+
+    #[test]
+    fn test_ppu_patches_and_hooks() {
+        let runner = EmulatorRunner::new(Config::default()).unwrap();
+        runner.create_ppu_thread(100).unwrap();
+        let code = runner.memory.allocate(0x1000, 0x1000, oc_memory::PageFlags::RWX).unwrap();
+        // li r3, 1 ; li r4, 2 ; li r5, 3
+        for (i, word) in [0x3860_0001, 0x3880_0002, 0x38A0_0003].into_iter().enumerate() {
+            runner.memory.write_be32(code + i as u32 * 4, word).unwrap();
+        }
+        {
+            let threads = runner.ppu_threads.read();
+            let mut thread = threads[0].write();
+            thread.set_pc(code as u64);
+            thread.start();
+        }
+
+        let mut patches = runner.patches().lock();
+        patches.add(oc_debug::CodePatch::new("li r3, 9", code, "li r3, 9"), &runner.memory).unwrap();
+        patches.add_hook(code + 4, "skip li r4", |thread, _| {
+            thread.set_gpr(6, 6);
+            HookAction::Skip
+        });
+        drop(patches);
+        for _ in 0..3 {
+            runner.execute_ppu_thread(0).unwrap();
+        }
+
+        let threads = runner.ppu_threads.read();
+        let thread = threads[0].read();
+        assert_eq!(thread.gpr(3), 9);
+        assert_eq!(thread.gpr(4), 0);
+        assert_eq!(thread.gpr(5), 3);
+        assert_eq!(thread.gpr(6), 6);
+    }
+
+    #[test]
+    fn test_crash_dump_on_fault() {
+        let directory = std::env::temp_dir().join(format!("oc-crash-{}", std::process::id()));
+        let mut config = Config::default();
+        config.debug.crash_dump_dir = directory.clone();
+        let mut runner = EmulatorRunner::new(config).unwrap();
+        runner.set_title_id(Some("TEST00000"));
+        runner.create_ppu_thread(100).unwrap();
+        {
+            let threads = runner.ppu_threads.read();
+            let mut thread = threads[0].write();
+            thread.set_pc(0xDEAD_0000);
+            thread.start();
+        }
+
+        assert!(runner.execute_ppu_thread(0).is_err());
+        let report = runner.take_crash().unwrap();
+        assert_eq!(report.kind, CrashKind::GuestFault);
+        assert!(report.summary.contains("0xdead0000"), "{}", report.summary);
+        let text = std::fs::read_to_string(report.path.unwrap()).unwrap();
+        let _ = std::fs::remove_dir_all(&directory);
+        assert!(text.contains("Game: TEST00000"));
+        assert!(text.contains("(faulting)"));
+        assert!(text.contains(">dead0000: 00000000"));
+        assert!(runner.take_crash().is_none());
+    }
+
+    #[test]
+    fn test_interrupts_preempt_guest_code() {
+        let directory = std::env::temp_dir().join(format!("oc-interrupt-{}", std::process::id()));
+        let mut config = Config::default();
+        config.debug.crash_dump_dir = directory.clone();
+        let runner = EmulatorRunner::new(config).unwrap();
+        runner.create_ppu_thread(1000).unwrap();
+        let code = runner.memory.allocate(0x1000, 0x1000, oc_memory::PageFlags::RWX).unwrap();
+        // b . ; handler OPD ; li r4, 0x77 ; li r11, 88 ; sc
+        runner.memory.write_be32(code, 0x4800_0000).unwrap();
+        runner.memory.write_be32(code + 0x100, code + 0x200).unwrap();
+        runner.memory.write_be32(code + 0x104, 0x1234).unwrap();
+        runner.memory.write_be32(code + 0x200, 0x3880_0077).unwrap();
+        runner.memory.write_be32(code + 0x204, 0x3960_0058).unwrap();
+        runner.memory.write_be32(code + 0x208, 0x4400_0002).unwrap();
+        {
+            let threads = runner.ppu_threads.read();
+            let mut thread = threads[0].write();
+            thread.set_pc(code as u64);
+            thread.start();
+        }
+
+        // The game establishes an interrupt thread on the SPU's mailbox interrupt
+        let manager = runner.syscall_handler.object_manager();
+        let source = InterruptSource::Spu { spu_id: 0, class: 2 };
+        let tag = oc_lv2::interrupt::create_tag(manager, source);
+        let lv2_thread = oc_lv2::thread::syscalls::sys_ppu_thread_create(
+            runner.syscall_handler.thread_manager(),
+            (code + 0x100) as u64,
+            0,
+            100,
+            0x4000,
+            0,
+            "interrupt",
+        )
+        .unwrap();
+        let threads = runner.syscall_handler.thread_manager();
+        oc_lv2::interrupt::syscalls::sys_interrupt_thread_establish(manager, threads, tag, lv2_thread, 5).unwrap();
+
+        // The running thread takes the interrupt and yields to the handler thread
+        runner.raise_spu_interrupt(0, 0xABCD);
+        runner.scheduler.write().schedule();
+        runner.execute_ppu_thread(0).unwrap();
+        assert_eq!(runner.ppu_threads.read()[0].read().pc(), code as u64);
+        assert_eq!(runner.scheduler.write().schedule(), Some(ThreadId::Ppu(1)));
+        {
+            let threads = runner.ppu_threads.read();
+            let thread = threads[1].read();
+            assert_eq!(thread.pc(), code as u64 + 0x200);
+            assert_eq!((thread.gpr(2), thread.gpr(3)), (0x1234, 5));
+        }
+        for _ in 0..3 {
+            runner.execute_ppu_thread(1).unwrap();
+        }
+
+        // eoi ends the handler and the thread waits for the next interrupt
+        {
+            let threads = runner.ppu_threads.read();
+            let handler = threads[1].read();
+            assert_eq!(handler.gpr(4), 0x77);
+            assert!(!handler.is_running());
+        }
+        assert_eq!(runner.scheduler.read().get_thread_state(ThreadId::Ppu(1)), Some(ThreadState::Waiting));
+        let tag: Arc<oc_lv2::interrupt::InterruptTag> = manager.get(tag).unwrap();
+        assert_eq!(tag.read_mailbox(), Some(0xABCD));
+        assert!(oc_lv2::interrupt::take_dispatches(manager).is_empty());
+
+        // The decrementer ends the time slice on the emulated clock
+        runner.time_base.pause();
+        runner.ppu_threads.read()[0].write().set_decrementer(10);
+        runner.time_base.advance(std::time::Duration::from_micros(1));
+        runner.execute_ppu_thread(0).unwrap();
+        {
+            let threads = runner.ppu_threads.read();
+            let thread = threads[0].read();
+            assert_eq!(thread.pc(), code as u64);
+            assert_eq!(thread.decrementer(), PPU_TIME_SLICE_TICKS);
+        }
+
+        // A store to a read-only page is a data storage exception
+        let data = runner.memory.allocate(0x1000, 0x1000, oc_memory::PageFlags::READ).unwrap();
+        runner.memory.write_be32(code + 0x300, 0x9005_0000).unwrap(); // stw r0, 0(r5)
+        {
+            let threads = runner.ppu_threads.read();
+            let mut thread = threads[0].write();
+            thread.set_pc(code as u64 + 0x300);
+            thread.set_gpr(5, data as u64);
+        }
+        assert!(runner.execute_ppu_thread(0).is_err());
+        let _ = std::fs::remove_dir_all(&directory);
+        {
+            let threads = runner.ppu_threads.read();
+            let thread = threads[0].read();
+            assert_eq!(thread.pc(), code as u64 + 0x300);
+            assert_eq!((thread.regs.dar, thread.regs.dsisr), (data as u64, 0x0A00_0000));
+        }
+        assert!(runner.take_crash().unwrap().summary.contains("DSISR 0x0a000000"));
+    }
+    #[test]
+    fn test_record_and_replay_run() {
+        let path = std::env::temp_dir().join(format!("oc-run-replay-{}.txt.gz", std::process::id()));
+        let run = |replay: bool| {
+            let mut runner = EmulatorRunner::new(Config::default()).unwrap();
+            runner.create_ppu_thread(100).unwrap();
+            let code = runner.memory.allocate(0x1000, 0x1000, oc_memory::PageFlags::RWX).unwrap();
+            // li r11, SYS_TIME_GET_SYSTEM_TIME ; sc ; mr r20, r3 ; b .
+            let program = [0x3960_0091, 0x4400_0002, 0x7C74_1B78, 0x4800_0000];
+            for (i, word) in program.into_iter().enumerate() {
+                runner.memory.write_be32(code + i as u32 * 4, word).unwrap();
+            }
+            {
+                let threads = runner.ppu_threads.read();
+                let mut thread = threads[0].write();
+                thread.set_pc(code as u64);
+                thread.start();
+            }
+            if replay {
+                runner.start_replay(&path).unwrap();
+            } else {
+                runner.start_recording(&path).unwrap();
+            }
+            runner.start().unwrap();
+            runner.run_frame().unwrap();
+            let status = runner.replay_status().unwrap();
+            runner.stop().unwrap();
+            let time = runner.ppu_threads.read()[0].read().gpr(20);
+            (time, status)
+        };
+
+        let (recorded, _) = run(false);
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let (replayed, status) = run(true);
+        let _ = std::fs::remove_file(&path);
+        assert_ne!(recorded, 0);
+        assert_eq!(replayed, recorded);
+        assert_eq!(status, "Replay finished");
+    }
+
+    #[test]
+    fn test_coverage_recording() {
+        let directory = std::env::temp_dir().join(format!("oc-coverage-{}", std::process::id()));
+        let runner = EmulatorRunner::new(Config::default()).unwrap();
+        runner.create_ppu_thread(100).unwrap();
+        let code = runner.memory.allocate(0x1000, 0x1000, oc_memory::PageFlags::RWX).unwrap();
+        // li r3, 0 ; addi r3, r3, 1 ; b .
+        for (i, word) in [0x3860_0000, 0x3863_0001, 0x4800_0000].into_iter().enumerate() {
+            runner.memory.write_be32(code + i as u32 * 4, word).unwrap();
+        }
+        {
+            let threads = runner.ppu_threads.read();
+            let mut thread = threads[0].write();
+            thread.set_pc(code as u64);
+            thread.start();
+        }
+        *runner.modules.write() = vec![ModuleRange { path: String::from("EBOOT.BIN"), start: code, end: code + 0x1000 }];
+
+        assert_eq!(runner.stop_coverage(), Ok(None));
+        runner.start_coverage(&directory.join("run.drcov"));
+        assert!(runner.is_recording_coverage());
+        for _ in 0..6 {
+            runner.execute_ppu_thread(0).unwrap();
+        }
+        let message = runner.stop_coverage().unwrap().unwrap();
+        assert!(message.starts_with("Coverage of 2 basic block(s) written to"), "{}", message);
+        assert!(!runner.is_recording_coverage());
+        let offsets = std::fs::read_to_string(directory.join("run.txt")).unwrap();
+        let _ = std::fs::remove_dir_all(&directory);
+        assert_eq!(offsets, "EBOOT.BIN+0\nEBOOT.BIN+8\n");
+    }
+
+    #[test]
+    fn test_save_and_load_state() {
+        let directory = std::env::temp_dir().join(format!("oc-savestate-{}", std::process::id()));
+        let path = directory.join("slot0.ocstate");
+        let mut runner = EmulatorRunner::new(Config::default()).unwrap();
+        runner.set_title_id(Some("NPUB00001"));
+        runner.create_ppu_thread(100).unwrap();
+        runner.create_spu_thread(100).unwrap();
+        let code = runner.memory.allocate(0x1000, 0x1000, oc_memory::PageFlags::RWX).unwrap();
+        // li r3, 0 ; addi r3, r3, 1 ; addi r3, r3, 1 ; b .
+        for (i, word) in [0x3860_0000, 0x3863_0001, 0x3863_0001, 0x4800_0000].into_iter().enumerate() {
+            runner.memory.write_be32(code + i as u32 * 4, word).unwrap();
+        }
+        {
+            let threads = runner.ppu_threads.read();
+            let mut thread = threads[0].write();
+            thread.set_pc(code as u64);
+            thread.start();
+        }
+        runner.spu_threads()[0].write().ls_write_u32(0x100, 0x1234_5678);
+        runner.execute_ppu_thread(0).unwrap();
+        runner.execute_ppu_thread(0).unwrap();
+        runner.frame_count = 42;
+        runner.time_base.advance(std::time::Duration::from_secs(5));
+        let saved_time = runner.time_base.now();
+
+        let info = runner.save_state(&path).unwrap();
+        assert_eq!(info.title_id.as_deref(), Some("NPUB00001"));
+        assert_eq!(info.frame, 42);
+
+        // Run on and change memory, then go back
+        runner.execute_ppu_thread(0).unwrap();
+        runner.memory.write_be32(code + 0x800, 0xFFFF_FFFF).unwrap();
+        runner.create_ppu_thread(100).unwrap();
+        runner.frame_count = 50;
+        runner.time_base.advance(std::time::Duration::from_secs(5));
+        runner.load_state(&path).unwrap();
+        assert_eq!(runner.frame_count(), 42);
+        assert_eq!(runner.time_base.now(), saved_time);
+        assert_eq!(runner.ppu_thread_count(), 1);
+        {
+            let thread = runner.ppu_threads.read()[0].clone();
+            let thread = thread.read();
+            assert_eq!(thread.gpr(3), 1);
+            assert_eq!(thread.pc(), code as u64 + 8);
+        }
+        assert_eq!(runner.memory.read_be32(code + 0x800).unwrap(), 0);
+        assert_eq!(runner.spu_threads()[0].read().ls_read_u32(0x100), 0x1234_5678);
+        runner.execute_ppu_thread(0).unwrap();
+        assert_eq!(runner.ppu_threads.read()[0].read().gpr(3), 2);
+
+        // A savestate of another game is refused
+        runner.set_title_id(Some("NPUB00002"));
+        let error = runner.load_state(&path).unwrap_err();
+        let _ = std::fs::remove_dir_all(&directory);
+        assert!(error.contains("is a savestate of NPUB00001"), "{}", error);
+    }
 }
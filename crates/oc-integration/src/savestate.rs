@@ -0,0 +1,313 @@
+//! Savestate files: the complete emulated machine at the end of a frame
+//!
+//! A savestate is gzip-compressed and starts with a magic and a format
+//! version, followed by a header (title, time taken, frame, a small RGBA
+//! screenshot) and tagged sections, one per subsystem:
+//!
+//! ```text
+//! PPU   PPU threads              SCHD  scheduler queue
+//! SPU   SPU threads              LV2   kernel objects and threads
+//! HLE   HLE library state        RSX   RSX registers and FIFO
+//! MEM   guest memory snapshot    TIME  emulated clock
+//! ```
+//!
+//! Sections are length-prefixed, so a reader can skip ones it does not
+//! know. Files from a different format version are refused.
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use oc_core::savestate::{invalid, StateReader, StateWriter};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Magic at the start of a decompressed savestate
+const MAGIC: &[u8; 8] = b"OCSTATE\0";
+
+/// Format version, bumped whenever any section's layout changes
+pub const SAVESTATE_VERSION: u32 = 9;
+
+/// File extension of savestates
+pub const SAVESTATE_EXTENSION: &str = "ocstate";
+
+/// Number of savestate slots per game
+pub const SLOT_COUNT: usize = 10;
+
+/// Width screenshots are scaled down to
+pub const THUMBNAIL_WIDTH: u32 = 320;
+
+/// Downscaled screenshot stored in a savestate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thumbnail {
+    pub width: u32,
+    pub height: u32,
+    /// RGBA pixels, 4 bytes each
+    pub rgba: Vec<u8>,
+}
+
+impl Thumbnail {
+    /// Scale an RGBA frame down to [`THUMBNAIL_WIDTH`], keeping its aspect ratio
+    pub fn from_frame(width: u32, height: u32, rgba: &[u8]) -> Option<Self> {
+        if width == 0 || height == 0 || rgba.len() < (width * height * 4) as usize {
+            return None;
+        }
+        let thumb_width = width.min(THUMBNAIL_WIDTH);
+        let thumb_height = (height * thumb_width / width).max(1);
+        let mut pixels = Vec::with_capacity((thumb_width * thumb_height * 4) as usize);
+        for y in 0..thumb_height {
+            let src_y = y * height / thumb_height;
+            for x in 0..thumb_width {
+                let src = ((src_y * width + x * width / thumb_width) * 4) as usize;
+                pixels.extend_from_slice(&rgba[src..src + 4]);
+            }
+        }
+        Some(Self {
+            width: thumb_width,
+            height: thumb_height,
+            rgba: pixels,
+        })
+    }
+}
+
+/// Header of a savestate, readable without the sections
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavestateInfo {
+    /// Title ID of the game the state was saved in
+    pub title_id: Option<String>,
+    /// When the state was saved, in seconds since the Unix epoch
+    pub created: u64,
+    /// Frames run when the state was saved
+    pub frame: u64,
+    /// Guest cycles run when the state was saved
+    pub total_cycles: u64,
+    /// Screenshot of the last frame
+    pub thumbnail: Option<Thumbnail>,
+}
+
+impl SavestateInfo {
+    /// Header for a state taken now
+    pub fn new(title_id: Option<&str>, frame: u64, total_cycles: u64) -> Self {
+        Self {
+            title_id: title_id.map(str::to_string),
+            created: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+            frame,
+            total_cycles,
+            thumbnail: None,
+        }
+    }
+
+    fn write(&self, w: &mut StateWriter) {
+        w.option(self.title_id.as_deref(), |w, title_id| w.str(title_id));
+        w.u64(self.created);
+        w.u64(self.frame);
+        w.u64(self.total_cycles);
+        w.option(self.thumbnail.as_ref(), |w, thumbnail| {
+            w.u32(thumbnail.width);
+            w.u32(thumbnail.height);
+            w.bytes(&thumbnail.rgba);
+        });
+    }
+
+    fn read(r: &mut StateReader) -> io::Result<Self> {
+        Ok(Self {
+            title_id: r.option(|r| r.str())?,
+            created: r.u64()?,
+            frame: r.u64()?,
+            total_cycles: r.u64()?,
+            thumbnail: r.option(|r| {
+                let width = r.u32()?;
+                let height = r.u32()?;
+                let rgba = r.bytes()?.to_vec();
+                if rgba.len() != (width as usize) * (height as usize) * 4 {
+                    return Err(invalid("savestate thumbnail size does not match its pixels"));
+                }
+                Ok(Thumbnail { width, height, rgba })
+            })?,
+        })
+    }
+}
+
+/// A savestate in memory: its header and the encoded sections
+#[derive(Debug, Clone)]
+pub struct Savestate {
+    pub info: SavestateInfo,
+    sections: Vec<([u8; 4], Vec<u8>)>,
+}
+
+impl Savestate {
+    /// A savestate with no sections yet
+    pub fn new(info: SavestateInfo) -> Self {
+        Self {
+            info,
+            sections: Vec::new(),
+        }
+    }
+
+    /// Add a section encoded by `write`
+    pub fn add_section(&mut self, tag: &[u8; 4], write: impl FnOnce(&mut StateWriter)) {
+        let mut w = StateWriter::new();
+        write(&mut w);
+        self.sections.push((*tag, w.into_bytes()));
+    }
+
+    /// Decode the section `tag` with `read`, which must consume all of it
+    pub fn read_section<T>(&self, tag: &[u8; 4], read: impl FnOnce(&mut StateReader) -> io::Result<T>) -> io::Result<T> {
+        let name = String::from_utf8_lossy(tag).trim_end().to_string();
+        let (_, data) = self
+            .sections
+            .iter()
+            .find(|(t, _)| t == tag)
+            .ok_or_else(|| invalid(&format!("savestate has no {} section", name)))?;
+        let mut r = StateReader::new(data);
+        let value = read(&mut r).map_err(|e| invalid(&format!("{} section: {}", name, e)))?;
+        if r.remaining() != 0 {
+            return Err(invalid(&format!("{} section has {} unread bytes", name, r.remaining())));
+        }
+        Ok(value)
+    }
+
+    /// Encode the whole savestate, before compression
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = StateWriter::new();
+        w.raw(MAGIC);
+        w.u32(SAVESTATE_VERSION);
+        self.info.write(&mut w);
+        w.count(self.sections.len());
+        for (tag, data) in &self.sections {
+            w.raw(tag);
+            w.bytes(data);
+        }
+        w.into_bytes()
+    }
+
+    /// Decode a savestate written by [`Savestate::to_bytes`]
+    pub fn from_bytes(data: &[u8]) -> io::Result<Self> {
+        let mut r = StateReader::new(data);
+        let info = Self::read_header(&mut r)?;
+        let mut sections = Vec::new();
+        for _ in 0..r.count()? {
+            let mut tag = [0; 4];
+            tag.copy_from_slice(r.raw(4)?);
+            sections.push((tag, r.bytes()?.to_vec()));
+        }
+        Ok(Self { info, sections })
+    }
+
+    fn read_header(r: &mut StateReader) -> io::Result<SavestateInfo> {
+        if r.raw(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err(invalid("not a savestate"));
+        }
+        let version = r.u32()?;
+        if version != SAVESTATE_VERSION {
+            return Err(invalid(&format!(
+                "savestate format version {} is not supported (expected {})",
+                version, SAVESTATE_VERSION
+            )));
+        }
+        SavestateInfo::read(r)
+    }
+
+    /// Write the savestate to `path`, replacing it only once fully written
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let partial = path.with_extension("partial");
+        let write = || -> io::Result<()> {
+            let file = std::fs::File::create(&partial)?;
+            let mut out = GzEncoder::new(io::BufWriter::new(file), Compression::fast());
+            out.write_all(&self.to_bytes())?;
+            out.finish()?.flush()?;
+            std::fs::rename(&partial, path)
+        };
+        write().map_err(|e| {
+            let _ = std::fs::remove_file(&partial);
+            format!("Failed to write {}: {}", path.display(), e)
+        })
+    }
+
+    /// Read a savestate written by [`Savestate::save`]
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let mut data = Vec::new();
+        GzDecoder::new(io::BufReader::new(file))
+            .read_to_end(&mut data)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::from_bytes(&data).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Read only the header of the savestate at `path`
+    pub fn load_info(path: &Path) -> Result<SavestateInfo, String> {
+        let file = std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        // The header comes first, so decompress just enough to hold the largest one
+        let limit = 64 * 1024 + (THUMBNAIL_WIDTH as u64 * THUMBNAIL_WIDTH as u64 * 4);
+        let mut data = Vec::new();
+        GzDecoder::new(io::BufReader::new(file))
+            .take(limit)
+            .read_to_end(&mut data)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::read_header(&mut StateReader::new(&data)).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+/// File of savestate `slot` of `title_id` in `dir`
+pub fn slot_path(dir: &Path, title_id: &str, slot: usize) -> PathBuf {
+    dir.join(title_id).join(format!("slot{}.{}", slot, SAVESTATE_EXTENSION))
+}
+
+/// Headers of the savestate slots of `title_id`, None for empty or unreadable slots
+pub fn list_slots(dir: &Path, title_id: &str) -> Vec<Option<SavestateInfo>> {
+    (0..SLOT_COUNT)
+        .map(|slot| {
+            let path = slot_path(dir, title_id, slot);
+            if !path.exists() {
+                return None;
+            }
+            Savestate::load_info(&path)
+                .map_err(|e| tracing::warn!("Ignoring savestate: {}", e))
+                .ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_savestate_file() {
+        let mut info = SavestateInfo::new(Some("BLUS00001"), 120, 5_000_000);
+        info.thumbnail = Thumbnail::from_frame(1280, 720, &vec![0x80; 1280 * 720 * 4]);
+        let thumbnail = info.thumbnail.clone().unwrap();
+        assert_eq!((thumbnail.width, thumbnail.height), (320, 180));
+
+        let mut state = Savestate::new(info.clone());
+        state.add_section(b"TEST", |w| {
+            w.u32(7);
+            w.str("seven");
+        });
+
+        let dir = std::env::temp_dir().join(format!("oc_savestate_test_{}", std::process::id()));
+        let path = slot_path(&dir, "BLUS00001", 3);
+        state.save(&path).unwrap();
+        assert_eq!(Savestate::load_info(&path).unwrap(), info);
+        let slots = list_slots(&dir, "BLUS00001");
+        assert_eq!(slots.len(), SLOT_COUNT);
+        assert!(slots[3].is_some() && slots[0].is_none());
+
+        let loaded = Savestate::load(&path).unwrap();
+        assert_eq!(loaded.info, info);
+        let value = loaded.read_section(b"TEST", |r| Ok((r.u32()?, r.str()?))).unwrap();
+        assert_eq!(value, (7, "seven".to_string()));
+        // Sections must be read completely, and missing ones are reported
+        assert!(loaded.read_section(b"TEST", |r| r.u32()).is_err());
+        assert!(loaded.read_section(b"MEM ", |r| r.u32()).is_err());
+
+        // Another format version is refused
+        let mut data = state.to_bytes();
+        data[MAGIC.len()] = SAVESTATE_VERSION as u8 + 1;
+        assert!(Savestate::from_bytes(&data).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,97 @@
+//! Texture dumps and replacement packs on disk
+//!
+//! Dumped textures are saved as `<hash>.png` in the title's dump folder.
+//! Packs use the same names: every PNG named after a texture hash below the
+//! title's replacement folder, in any subfolder, replaces that texture. A
+//! replacement may be larger than the original, usually 2x or 4x.
+
+use oc_rsx::texture_pack::{parse_texture_name, texture_name, ReplacementTexture, TextureDump};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Extension of dumped and replacement textures
+const TEXTURE_EXTENSION: &str = "png";
+
+/// PNG files below `dir` named after a texture hash, with the hash
+fn texture_files(dir: &Path) -> Vec<(u64, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files = Vec::new();
+    for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        if path.is_dir() {
+            files.extend(texture_files(&path));
+            continue;
+        }
+        if !path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case(TEXTURE_EXTENSION)) {
+            continue;
+        }
+        if let Some(hash) = path.file_stem().and_then(|stem| parse_texture_name(&stem.to_string_lossy())) {
+            files.push((hash, path));
+        }
+    }
+    files
+}
+
+/// Hashes of the textures already dumped to `dir`
+pub fn dumped_hashes(dir: &Path) -> Vec<u64> {
+    texture_files(dir).into_iter().map(|(hash, _)| hash).collect()
+}
+
+/// Load every replacement texture below `dir`
+///
+/// Files that fail to decode are skipped with a warning.
+pub fn load_replacements(dir: &Path) -> HashMap<u64, ReplacementTexture> {
+    let mut replacements = HashMap::new();
+    for (hash, path) in texture_files(dir) {
+        match image::open(&path) {
+            Ok(image) => {
+                let rgba = image.to_rgba8();
+                let (width, height) = rgba.dimensions();
+                replacements.insert(hash, ReplacementTexture { width, height, pixels: Arc::new(rgba.into_raw()) });
+            }
+            Err(e) => tracing::warn!("Skipping replacement texture {}: {}", path.display(), e),
+        }
+    }
+    if !replacements.is_empty() {
+        tracing::info!("Loaded {} replacement textures from {}", replacements.len(), dir.display());
+    }
+    replacements
+}
+
+/// Save dumped textures to `dir` as PNGs, returning how many were written
+pub fn save_dumps(dir: &Path, dumps: &[TextureDump]) -> Result<usize, String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    for dump in dumps {
+        let path = dir.join(format!("{}.{}", texture_name(dump.hash), TEXTURE_EXTENSION));
+        image::save_buffer(&path, &dump.pixels, dump.width, dump.height, image::ExtendedColorType::Rgba8)
+            .map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+    }
+    Ok(dumps.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dumps_load_as_replacements() {
+        let dir = std::env::temp_dir().join(format!("oc_texture_pack_test_{}", std::process::id()));
+        let dump = TextureDump { hash: 0x0123_4567_89AB_CDEF, width: 2, height: 1, pixels: vec![1, 2, 3, 4, 5, 6, 7, 8] };
+        assert_eq!(save_dumps(&dir, std::slice::from_ref(&dump)), Ok(1));
+        assert_eq!(dumped_hashes(&dir), [dump.hash]);
+
+        // Packs may sort textures into subfolders; other files are ignored
+        let pack = dir.join("pack");
+        std::fs::create_dir_all(pack.join("ui")).unwrap();
+        std::fs::rename(dir.join("0123456789abcdef.png"), pack.join("ui/0123456789abcdef.png")).unwrap();
+        std::fs::write(pack.join("readme.png"), b"not a texture").unwrap();
+        let replacements = load_replacements(&pack);
+        assert_eq!(replacements.len(), 1);
+        let replacement = &replacements[&dump.hash];
+        assert_eq!((replacement.width, replacement.height), (2, 1));
+        assert_eq!(*replacement.pixels, dump.pixels);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
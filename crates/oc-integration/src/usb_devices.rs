@@ -0,0 +1,394 @@
+//! USB devices handed to sys_usbd
+//!
+//! Buzz! buzzers emulated on the controller ports and host HID devices
+//! passed through both reach the game's driver as HID devices. Standard
+//! and HID class requests are answered here from the descriptors and
+//! reports each device provides, so the two backends only differ in where
+//! reports come from.
+
+use oc_input::buzz::{BUZZERS, BUZZ_RELEASE, BUZZ_REPORT_DESCRIPTOR};
+use oc_input::usb::{hid_configuration_descriptor, hid_device_descriptor};
+use oc_input::{PadPorts, UsbDeviceInfo, UsbPassthrough, UsbPassthroughDevice, UsbPassthroughEvent, VirtualBuzz};
+use oc_lv2::usbd::{UsbBackend, UsbHotplug, UsbSetup, UsbTransfer, UsbTransferResult};
+use std::sync::Arc;
+
+/// GET_STATUS
+const REQ_GET_STATUS: u8 = 0x00;
+/// GET_DESCRIPTOR
+const REQ_GET_DESCRIPTOR: u8 = 0x06;
+/// GET_CONFIGURATION
+const REQ_GET_CONFIGURATION: u8 = 0x08;
+/// SET_CONFIGURATION
+const REQ_SET_CONFIGURATION: u8 = 0x09;
+/// SET_INTERFACE
+const REQ_SET_INTERFACE: u8 = 0x0B;
+/// HID GET_REPORT
+const HID_GET_REPORT: u8 = 0x01;
+/// HID SET_REPORT (same number as SET_CONFIGURATION, told apart by the request type)
+const HID_SET_REPORT: u8 = 0x09;
+/// HID SET_IDLE
+const HID_SET_IDLE: u8 = 0x0A;
+/// HID SET_PROTOCOL
+const HID_SET_PROTOCOL: u8 = 0x0B;
+
+/// Request type bits: class request to an interface
+const TYPE_CLASS_INTERFACE: u8 = 0x21;
+
+/// Descriptor types
+const DESC_DEVICE: u8 = 0x01;
+const DESC_CONFIGURATION: u8 = 0x02;
+const DESC_STRING: u8 = 0x03;
+const DESC_HID: u8 = 0x21;
+const DESC_REPORT: u8 = 0x22;
+
+/// HID report types of GET_REPORT and SET_REPORT
+const REPORT_INPUT: u8 = 0x01;
+const REPORT_OUTPUT: u8 = 0x02;
+const REPORT_FEATURE: u8 = 0x03;
+
+/// A HID device as its USB driver sees it
+trait HidFunction {
+    fn device_descriptor(&self) -> [u8; 18];
+    fn configuration_descriptor(&self) -> Vec<u8>;
+    fn report_descriptor(&self) -> &[u8];
+    fn string(&self, index: u8) -> Option<&str>;
+    /// Next input report, None while there is nothing new
+    fn input_report(&mut self) -> Option<Vec<u8>>;
+    fn output_report(&mut self, report: &[u8]) -> Result<usize, String>;
+    fn get_feature_report(&self, report_id: u8, len: usize) -> Result<Vec<u8>, String>;
+    fn send_feature_report(&mut self, report: &[u8]) -> Result<(), String>;
+}
+
+/// Device descriptor followed by the configuration descriptor set
+fn descriptor_set(dev: &dyn HidFunction) -> Vec<u8> {
+    let mut descriptors = dev.device_descriptor().to_vec();
+    descriptors.extend(dev.configuration_descriptor());
+    descriptors
+}
+
+/// Run a transfer on a HID device
+fn hid_transfer(dev: &mut dyn HidFunction, transfer: &UsbTransfer) -> Option<UsbTransferResult> {
+    let result = match transfer {
+        UsbTransfer::Control { setup, data } => hid_control(dev, setup, data),
+        UsbTransfer::In { .. } => return dev.input_report().map(UsbTransferResult::data),
+        UsbTransfer::Out { data, .. } => dev.output_report(data).map(UsbTransferResult::sent),
+    };
+    Some(result.unwrap_or_else(|e| {
+        tracing::warn!("{}", e);
+        UsbTransferResult::stall()
+    }))
+}
+
+/// Answer a standard or HID class control request
+fn hid_control(dev: &mut dyn HidFunction, setup: &UsbSetup, data: &[u8]) -> Result<UsbTransferResult, String> {
+    let [index, kind] = setup.value.to_le_bytes();
+    let class = setup.request_type & 0x60 == 0x20;
+    Ok(match (class, setup.request) {
+        (false, REQ_GET_DESCRIPTOR) => match kind {
+            DESC_DEVICE => UsbTransferResult::data(dev.device_descriptor().to_vec()),
+            DESC_CONFIGURATION => UsbTransferResult::data(dev.configuration_descriptor()),
+            DESC_STRING => match index {
+                // Supported languages: US English
+                0 => UsbTransferResult::data(vec![4, DESC_STRING, 0x09, 0x04]),
+                _ => match dev.string(index) {
+                    Some(text) => UsbTransferResult::data(string_descriptor(text)),
+                    None => UsbTransferResult::stall(),
+                },
+            },
+            // The HID descriptor follows the interface descriptor in the configuration
+            DESC_HID => UsbTransferResult::data(dev.configuration_descriptor()[18..27].to_vec()),
+            DESC_REPORT => UsbTransferResult::data(dev.report_descriptor().to_vec()),
+            _ => UsbTransferResult::stall(),
+        },
+        (false, REQ_GET_STATUS) => UsbTransferResult::data(vec![0, 0]),
+        (false, REQ_GET_CONFIGURATION) => UsbTransferResult::data(vec![1]),
+        (false, REQ_SET_CONFIGURATION | REQ_SET_INTERFACE) => UsbTransferResult::sent(0),
+        (true, HID_GET_REPORT) => match kind {
+            REPORT_INPUT => UsbTransferResult::data(dev.input_report().unwrap_or_default()),
+            REPORT_FEATURE => UsbTransferResult::data(dev.get_feature_report(index, setup.length as usize)?),
+            _ => UsbTransferResult::stall(),
+        },
+        (true, HID_SET_REPORT) if setup.request_type == TYPE_CLASS_INTERFACE => match kind {
+            REPORT_OUTPUT => UsbTransferResult::sent(dev.output_report(data)?),
+            REPORT_FEATURE => {
+                dev.send_feature_report(data)?;
+                UsbTransferResult::sent(data.len())
+            }
+            _ => UsbTransferResult::stall(),
+        },
+        (true, HID_SET_IDLE | HID_SET_PROTOCOL) => UsbTransferResult::sent(0),
+        _ => {
+            tracing::debug!("Unsupported USB request {:?}", setup);
+            UsbTransferResult::stall()
+        }
+    })
+}
+
+/// String descriptor of a UTF-16 string
+fn string_descriptor(text: &str) -> Vec<u8> {
+    let mut descriptor = vec![0, DESC_STRING];
+    descriptor.extend(text.encode_utf16().take(126).flat_map(u16::to_le_bytes));
+    descriptor[0] = descriptor.len() as u8;
+    descriptor
+}
+
+impl HidFunction for UsbPassthroughDevice {
+    fn device_descriptor(&self) -> [u8; 18] {
+        UsbPassthroughDevice::device_descriptor(self)
+    }
+
+    fn configuration_descriptor(&self) -> Vec<u8> {
+        UsbPassthroughDevice::configuration_descriptor(self)
+    }
+
+    fn report_descriptor(&self) -> &[u8] {
+        UsbPassthroughDevice::report_descriptor(self)
+    }
+
+    fn string(&self, index: u8) -> Option<&str> {
+        UsbPassthroughDevice::string(self, index)
+    }
+
+    fn input_report(&mut self) -> Option<Vec<u8>> {
+        self.take_input_report()
+    }
+
+    fn output_report(&mut self, report: &[u8]) -> Result<usize, String> {
+        self.write_output_report(report)
+    }
+
+    fn get_feature_report(&self, report_id: u8, len: usize) -> Result<Vec<u8>, String> {
+        UsbPassthroughDevice::get_feature_report(self, report_id, len)
+    }
+
+    fn send_feature_report(&mut self, report: &[u8]) -> Result<(), String> {
+        UsbPassthroughDevice::send_feature_report(self, report)
+    }
+}
+
+/// Host HID devices passed through to the game's driver
+pub struct PassthroughUsbBackend {
+    passthrough: UsbPassthrough,
+}
+
+impl PassthroughUsbBackend {
+    pub fn new(passthrough: UsbPassthrough) -> Self {
+        Self { passthrough }
+    }
+
+    fn device(&self, id: u32) -> Option<&UsbPassthroughDevice> {
+        self.passthrough.devices().iter().find(|dev| dev.id == id)
+    }
+}
+
+impl UsbBackend for PassthroughUsbBackend {
+    fn name(&self) -> &'static str {
+        "passthrough"
+    }
+
+    fn poll(&mut self) -> Vec<UsbHotplug> {
+        self.passthrough
+            .poll()
+            .into_iter()
+            .map(|event| match event {
+                UsbPassthroughEvent::Attached { id, .. } => UsbHotplug::Attached(id),
+                UsbPassthroughEvent::Detached { id } => UsbHotplug::Detached(id),
+            })
+            .collect()
+    }
+
+    fn descriptors(&self, device: u32) -> Option<Vec<u8>> {
+        self.device(device).map(|dev| descriptor_set(dev))
+    }
+
+    fn product(&self, device: u32) -> Option<String> {
+        self.device(device).map(|dev| dev.info.name.clone())
+    }
+
+    fn transfer(&mut self, device: u32, transfer: &UsbTransfer) -> Option<UsbTransferResult> {
+        match self.passthrough.device_mut(device) {
+            Some(dev) => hid_transfer(dev, transfer),
+            None => Some(UsbTransferResult::stall()),
+        }
+    }
+}
+
+/// Buzz! controller whose buzzers are the pads on ports 1-4
+struct BuzzDevice {
+    buzz: VirtualBuzz,
+    info: UsbDeviceInfo,
+    pad_ports: Arc<PadPorts>,
+    /// Last report sent; like the real controller, a new one is only sent on change
+    last_report: Option<[u8; 5]>,
+}
+
+impl BuzzDevice {
+    fn report(&self) -> [u8; 5] {
+        let pads = std::array::from_fn::<_, BUZZERS, _>(|port| self.pad_ports.state(port as u8));
+        VirtualBuzz::input_report(&pads)
+    }
+}
+
+impl HidFunction for BuzzDevice {
+    fn device_descriptor(&self) -> [u8; 18] {
+        hid_device_descriptor(&self.info, BUZZ_RELEASE)
+    }
+
+    fn configuration_descriptor(&self) -> Vec<u8> {
+        hid_configuration_descriptor(BUZZ_REPORT_DESCRIPTOR.len() as u16)
+    }
+
+    fn report_descriptor(&self) -> &[u8] {
+        &BUZZ_REPORT_DESCRIPTOR
+    }
+
+    fn string(&self, index: u8) -> Option<&str> {
+        match index {
+            1 => Some(self.info.manufacturer.as_str()),
+            2 => Some(self.info.name.as_str()),
+            _ => None,
+        }
+    }
+
+    fn input_report(&mut self) -> Option<Vec<u8>> {
+        let report = self.report();
+        if self.last_report == Some(report) {
+            return None;
+        }
+        self.last_report = Some(report);
+        Some(report.to_vec())
+    }
+
+    fn output_report(&mut self, report: &[u8]) -> Result<usize, String> {
+        self.buzz.set_leds(report);
+        Ok(report.len())
+    }
+
+    fn get_feature_report(&self, _report_id: u8, _len: usize) -> Result<Vec<u8>, String> {
+        Err("Buzz! controller has no feature reports".to_string())
+    }
+
+    fn send_feature_report(&mut self, _report: &[u8]) -> Result<(), String> {
+        Err("Buzz! controller has no feature reports".to_string())
+    }
+}
+
+/// Peripherals emulated from host input
+pub struct VirtualUsbBackend {
+    buzz: BuzzDevice,
+    attached: bool,
+}
+
+impl VirtualUsbBackend {
+    /// Device ID of the Buzz! controller
+    const BUZZ: u32 = 1;
+
+    /// Emulate a Buzz! controller played on the first four controller ports
+    pub fn new(pad_ports: Arc<PadPorts>) -> Self {
+        Self {
+            buzz: BuzzDevice {
+                buzz: VirtualBuzz::new(),
+                info: VirtualBuzz::device_info(),
+                pad_ports,
+                last_report: None,
+            },
+            attached: false,
+        }
+    }
+
+    /// Whether each buzzer's light is on
+    pub fn buzz_leds(&self) -> [bool; BUZZERS] {
+        self.buzz.buzz.leds()
+    }
+}
+
+impl UsbBackend for VirtualUsbBackend {
+    fn name(&self) -> &'static str {
+        "virtual"
+    }
+
+    fn poll(&mut self) -> Vec<UsbHotplug> {
+        if std::mem::replace(&mut self.attached, true) {
+            Vec::new()
+        } else {
+            vec![UsbHotplug::Attached(Self::BUZZ)]
+        }
+    }
+
+    fn descriptors(&self, device: u32) -> Option<Vec<u8>> {
+        (device == Self::BUZZ).then(|| descriptor_set(&self.buzz))
+    }
+
+    fn product(&self, device: u32) -> Option<String> {
+        (device == Self::BUZZ).then(|| self.buzz.info.name.clone())
+    }
+
+    fn transfer(&mut self, device: u32, transfer: &UsbTransfer) -> Option<UsbTransferResult> {
+        if device != Self::BUZZ {
+            return Some(UsbTransferResult::stall());
+        }
+        hid_transfer(&mut self.buzz, transfer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oc_input::pad::{PadButtons, PadState};
+
+    fn control(request_type: u8, request: u8, value: u16, length: u16) -> UsbTransfer {
+        UsbTransfer::Control {
+            setup: UsbSetup {
+                request_type,
+                request,
+                value,
+                index: 0,
+                length,
+            },
+            data: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_virtual_buzz_over_usb() {
+        let pad_ports = Arc::new(PadPorts::new());
+        let mut backend = VirtualUsbBackend::new(pad_ports.clone());
+        assert_eq!(backend.poll(), vec![UsbHotplug::Attached(1)]);
+        assert!(backend.poll().is_empty());
+
+        let descriptors = backend.descriptors(1).unwrap();
+        assert_eq!(descriptors[8..12], [0x4C, 0x05, 0x02, 0x00]);
+        let report = backend.transfer(1, &control(0x81, REQ_GET_DESCRIPTOR, 0x2200, 256)).unwrap();
+        assert_eq!(report.data, BUZZ_REPORT_DESCRIPTOR);
+        let product = backend.transfer(1, &control(0x80, REQ_GET_DESCRIPTOR, 0x0302, 255)).unwrap();
+        assert_eq!(product.data[2..6], [b'L', 0, b'o', 0]);
+        assert!(backend.transfer(1, &control(0x21, HID_SET_IDLE, 0, 0)).is_some());
+
+        // Reports are sent on change
+        let read = UsbTransfer::In {
+            endpoint: 0x81,
+            length: 64,
+        };
+        assert_eq!(backend.transfer(1, &read).unwrap().data, [0x7F, 0x7F, 0, 0, 0xF0]);
+        assert!(backend.transfer(1, &read).is_none());
+        let mut pad = PadState::new();
+        pad.set_button(PadButtons::R1, true);
+        pad_ports.connect(1);
+        pad_ports.update(1, pad);
+        assert_eq!(backend.transfer(1, &read).unwrap().data, [0x7F, 0x7F, 0x20, 0, 0xF0]);
+
+        // Lights are set through SET_REPORT(Output)
+        let lights = UsbTransfer::Control {
+            setup: UsbSetup {
+                request_type: 0x21,
+                request: HID_SET_REPORT,
+                value: 0x0200,
+                index: 0,
+                length: 7,
+            },
+            data: vec![0, 0, 0xFF, 0, 0, 0, 0],
+        };
+        assert_eq!(backend.transfer(1, &lights).unwrap().count, 7);
+        assert_eq!(backend.buzz_leds(), [false, true, false, false]);
+    }
+}
@@ -707,7 +707,28 @@ impl CryptoEngine {
         let mut hasher = Sha1::new();
         hasher.update(data);
         hasher.finalize().into()
-    }    /// Verify SHA-1 hash
+    }
+
+    /// Compute HMAC-SHA1 of `data` with `key`
+    pub fn hmac_sha1(&self, key: &[u8], data: &[u8]) -> [u8; 20] {
+        const BLOCK_SIZE: usize = 64;
+        let mut block = [0u8; BLOCK_SIZE];
+        if key.len() > BLOCK_SIZE {
+            block[..20].copy_from_slice(&self.sha1(key));
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+
+        let mut inner = Sha1::new();
+        inner.update(block.map(|b| b ^ 0x36));
+        inner.update(data);
+        let mut outer = Sha1::new();
+        outer.update(block.map(|b| b ^ 0x5c));
+        outer.update(inner.finalize());
+        outer.finalize().into()
+    }
+
+    /// Verify SHA-1 hash
     pub fn verify_sha1(&self, data: &[u8], expected_hash: &[u8; 20]) -> bool {
         let computed = self.sha1(data);
         computed == *expected_hash
@@ -873,6 +894,14 @@ mod tests {
         assert_eq!(&hash[..], &expected[..]);
     }
 
+    #[test]
+    fn test_hmac_sha1() {
+        let engine = CryptoEngine::new();
+        let hash = engine.hmac_sha1(b"key", b"The quick brown fox jumps over the lazy dog");
+        let expected = hex_decode("de7c9b85b8b78aa6bc8a7a36f70a90701c9db4d9").unwrap();
+        assert_eq!(&hash[..], &expected[..]);
+    }
+
     #[test]
     fn test_self_key_set() {
         let mut engine = CryptoEngine::new();
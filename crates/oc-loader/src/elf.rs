@@ -3,11 +3,11 @@
 use std::io::{Read, Seek, SeekFrom};
 use std::sync::Arc;
 use oc_core::error::LoaderError;
-use oc_memory::{MemoryManager, PageFlags};
+use oc_memory::{BeValue, MemoryManager, PageFlags, PAGE_SIZE};
 use tracing::{debug, info, trace};
 
 /// ELF file header (64-bit)
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, BeValue)]
 #[repr(C)]
 pub struct Elf64Header {
     pub e_ident: [u8; 16],
@@ -27,7 +27,7 @@ pub struct Elf64Header {
 }
 
 /// ELF program header (64-bit)
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, BeValue)]
 #[repr(C)]
 pub struct Elf64Phdr {
     pub p_type: u32,
@@ -41,7 +41,7 @@ pub struct Elf64Phdr {
 }
 
 /// ELF section header (64-bit)
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, BeValue)]
 #[repr(C)]
 pub struct Elf64Shdr {
     pub sh_name: u32,
@@ -57,7 +57,7 @@ pub struct Elf64Shdr {
 }
 
 /// ELF symbol table entry (64-bit)
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, BeValue)]
 #[repr(C)]
 pub struct Elf64Sym {
     pub st_name: u32,
@@ -69,7 +69,7 @@ pub struct Elf64Sym {
 }
 
 /// ELF relocation entry with addend (64-bit)
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, BeValue)]
 #[repr(C)]
 pub struct Elf64Rela {
     pub r_offset: u64,
@@ -78,7 +78,7 @@ pub struct Elf64Rela {
 }
 
 /// ELF dynamic entry (64-bit)
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, BeValue)]
 #[repr(C)]
 pub struct Elf64Dyn {
     pub d_tag: i64,
@@ -250,6 +250,52 @@ impl ElfLoader {
         Ok(())
     }
 
+    /// Convert program header flags to page flags
+    pub fn segment_flags(phdr: &Elf64Phdr) -> PageFlags {
+        let mut flags = PageFlags::empty();
+        if phdr.p_flags & 0x4 != 0 {
+            flags |= PageFlags::READ;
+        }
+        if phdr.p_flags & 0x2 != 0 {
+            flags |= PageFlags::WRITE;
+        }
+        if phdr.p_flags & 0x1 != 0 {
+            flags |= PageFlags::EXECUTE;
+        }
+        flags
+    }
+
+    /// Apply segment permissions to loaded memory
+    ///
+    /// Called after relocations are processed. Non-writable segments (text,
+    /// rodata) become read-only so stray guest writes fault. Only pages that
+    /// lie entirely inside a segment are changed, so a page shared with a
+    /// neighbouring writable segment stays writable.
+    pub fn protect_segments(&self, memory: &Arc<MemoryManager>, base_addr: u32) -> Result<(), LoaderError> {
+        for phdr in self.phdrs.iter().filter(|p| p.p_type == pt::LOAD && p.p_memsz > 0) {
+            let flags = Self::segment_flags(phdr);
+            if flags.contains(PageFlags::WRITE) {
+                continue;
+            }
+
+            let start = (base_addr as u64 + phdr.p_vaddr).next_multiple_of(PAGE_SIZE as u64);
+            let end = (base_addr as u64 + phdr.p_vaddr + phdr.p_memsz) & !(PAGE_SIZE as u64 - 1);
+            if end <= start {
+                continue;
+            }
+
+            debug!(
+                "Protecting segment 0x{:08x}-0x{:08x} as {:?}",
+                start, end, flags
+            );
+            memory
+                .protect(start as u32, (end - start) as u32, flags)
+                .map_err(|e| LoaderError::InvalidElf(format!("Failed to protect segment: {}", e)))?;
+        }
+
+        Ok(())
+    }
+
     /// Load a single segment into memory
     fn load_segment<R: Read + Seek>(
         &self,
@@ -268,18 +314,6 @@ impl ElfLoader {
             index, vaddr, filesz, memsz
         );
 
-        // Convert program header flags to page flags
-        let mut flags = PageFlags::empty();
-        if phdr.p_flags & 0x4 != 0 {
-            flags |= PageFlags::READ;
-        }
-        if phdr.p_flags & 0x2 != 0 {
-            flags |= PageFlags::WRITE;
-        }
-        if phdr.p_flags & 0x1 != 0 {
-            flags |= PageFlags::EXECUTE;
-        }
-
         // Read segment data from file
         if filesz > 0 {
             // Get file size for error context
@@ -395,30 +429,7 @@ impl ElfLoader {
                     ))
                 })?;
 
-            let shdr = Elf64Shdr {
-                sh_name: u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]),
-                sh_type: u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]),
-                sh_flags: u64::from_be_bytes([
-                    buf[8], buf[9], buf[10], buf[11], buf[12], buf[13], buf[14], buf[15],
-                ]),
-                sh_addr: u64::from_be_bytes([
-                    buf[16], buf[17], buf[18], buf[19], buf[20], buf[21], buf[22], buf[23],
-                ]),
-                sh_offset: u64::from_be_bytes([
-                    buf[24], buf[25], buf[26], buf[27], buf[28], buf[29], buf[30], buf[31],
-                ]),
-                sh_size: u64::from_be_bytes([
-                    buf[32], buf[33], buf[34], buf[35], buf[36], buf[37], buf[38], buf[39],
-                ]),
-                sh_link: u32::from_be_bytes([buf[40], buf[41], buf[42], buf[43]]),
-                sh_info: u32::from_be_bytes([buf[44], buf[45], buf[46], buf[47]]),
-                sh_addralign: u64::from_be_bytes([
-                    buf[48], buf[49], buf[50], buf[51], buf[52], buf[53], buf[54], buf[55],
-                ]),
-                sh_entsize: u64::from_be_bytes([
-                    buf[56], buf[57], buf[58], buf[59], buf[60], buf[61], buf[62], buf[63],
-                ]),
-            };
+            let shdr = Elf64Shdr::from_be_slice(&buf);
 
             shdrs.push(shdr);
         }
@@ -472,16 +483,14 @@ impl ElfLoader {
                 .read_exact(&mut buf)
                 .map_err(|e| LoaderError::InvalidElf(e.to_string()))?;
 
-            let st_name = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
-            let st_info = buf[4];
-            let _st_other = buf[5];
-            let st_shndx = u16::from_be_bytes([buf[6], buf[7]]);
-            let st_value = u64::from_be_bytes([
-                buf[8], buf[9], buf[10], buf[11], buf[12], buf[13], buf[14], buf[15],
-            ]);
-            let st_size = u64::from_be_bytes([
-                buf[16], buf[17], buf[18], buf[19], buf[20], buf[21], buf[22], buf[23],
-            ]);
+            let Elf64Sym {
+                st_name,
+                st_info,
+                st_shndx,
+                st_value,
+                st_size,
+                ..
+            } = Elf64Sym::from_be_slice(&buf);
 
             // Extract symbol name from string table
             let name = if st_name > 0 && (st_name as usize) < strtab_data.len() {
@@ -556,15 +565,11 @@ impl ElfLoader {
                 .read_exact(&mut buf)
                 .map_err(|e| LoaderError::InvalidElf(e.to_string()))?;
 
-            let r_offset = u64::from_be_bytes([
-                buf[0], buf[1], buf[2], buf[3], buf[4], buf[5], buf[6], buf[7],
-            ]);
-            let r_info = u64::from_be_bytes([
-                buf[8], buf[9], buf[10], buf[11], buf[12], buf[13], buf[14], buf[15],
-            ]);
-            let r_addend = i64::from_be_bytes([
-                buf[16], buf[17], buf[18], buf[19], buf[20], buf[21], buf[22], buf[23],
-            ]);
+            let Elf64Rela {
+                r_offset,
+                r_info,
+                r_addend,
+            } = Elf64Rela::from_be_slice(&buf);
 
             let r_type = (r_info & 0xffffffff) as u32;
             let r_sym = (r_info >> 32) as usize;
@@ -609,27 +614,27 @@ impl ElfLoader {
                 // S + A
                 let value = (sym_value as i64 + addend) as u64;
                 memory
-                    .write(addr, value.to_be_bytes())
+                    .write_be(addr, value)
                     .map_err(|e| LoaderError::InvalidElf(format!("Relocation failed: {}", e)))?;
             }
             r_ppc64::ADDR32 => {
                 // S + A (truncated to 32 bits)
-                let value = ((sym_value as i64 + addend) as u32).to_be_bytes();
+                let value = (sym_value as i64 + addend) as u32;
                 memory
-                    .write(addr, value)
+                    .write_be(addr, value)
                     .map_err(|e| LoaderError::InvalidElf(format!("Relocation failed: {}", e)))?;
             }
             r_ppc64::RELATIVE => {
                 // B + A
                 let value = (base_addr as i64 + addend) as u64;
                 memory
-                    .write(addr, value.to_be_bytes())
+                    .write_be(addr, value)
                     .map_err(|e| LoaderError::InvalidElf(format!("Relocation failed: {}", e)))?;
             }
             r_ppc64::GLOB_DAT | r_ppc64::JMP_SLOT => {
                 // S
                 memory
-                    .write(addr, sym_value.to_be_bytes())
+                    .write_be(addr, sym_value)
                     .map_err(|e| LoaderError::InvalidElf(format!("Relocation failed: {}", e)))?;
             }
             _ => {
@@ -710,19 +715,10 @@ impl ElfLoader {
             ))
         })?;
         
-        header.e_type = u16::from_be_bytes([buf[0], buf[1]]);
-        header.e_machine = u16::from_be_bytes([buf[2], buf[3]]);
-        header.e_version = u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]);
-        header.e_entry = u64::from_be_bytes([buf[8], buf[9], buf[10], buf[11], buf[12], buf[13], buf[14], buf[15]]);
-        header.e_phoff = u64::from_be_bytes([buf[16], buf[17], buf[18], buf[19], buf[20], buf[21], buf[22], buf[23]]);
-        header.e_shoff = u64::from_be_bytes([buf[24], buf[25], buf[26], buf[27], buf[28], buf[29], buf[30], buf[31]]);
-        header.e_flags = u32::from_be_bytes([buf[32], buf[33], buf[34], buf[35]]);
-        header.e_ehsize = u16::from_be_bytes([buf[36], buf[37]]);
-        header.e_phentsize = u16::from_be_bytes([buf[38], buf[39]]);
-        header.e_phnum = u16::from_be_bytes([buf[40], buf[41]]);
-        header.e_shentsize = u16::from_be_bytes([buf[42], buf[43]]);
-        header.e_shnum = u16::from_be_bytes([buf[44], buf[45]]);
-        header.e_shstrndx = u16::from_be_bytes([buf[46], buf[47]]);
+        let mut raw = [0u8; 64];
+        raw[..16].copy_from_slice(&header.e_ident);
+        raw[16..].copy_from_slice(&buf);
+        let header = Elf64Header::from_be_slice(&raw);
         
         Ok(header)
     }
@@ -766,16 +762,7 @@ impl ElfLoader {
                 ))
             })?;
             
-            let phdr = Elf64Phdr {
-                p_type: u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]),
-                p_flags: u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]),
-                p_offset: u64::from_be_bytes([buf[8], buf[9], buf[10], buf[11], buf[12], buf[13], buf[14], buf[15]]),
-                p_vaddr: u64::from_be_bytes([buf[16], buf[17], buf[18], buf[19], buf[20], buf[21], buf[22], buf[23]]),
-                p_paddr: u64::from_be_bytes([buf[24], buf[25], buf[26], buf[27], buf[28], buf[29], buf[30], buf[31]]),
-                p_filesz: u64::from_be_bytes([buf[32], buf[33], buf[34], buf[35], buf[36], buf[37], buf[38], buf[39]]),
-                p_memsz: u64::from_be_bytes([buf[40], buf[41], buf[42], buf[43], buf[44], buf[45], buf[46], buf[47]]),
-                p_align: u64::from_be_bytes([buf[48], buf[49], buf[50], buf[51], buf[52], buf[53], buf[54], buf[55]]),
-            };
+            let phdr = Elf64Phdr::from_be_slice(&buf);
             
             phdrs.push(phdr);
         }
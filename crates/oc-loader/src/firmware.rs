@@ -2,14 +2,16 @@
 //!
 //! This module provides parsing and handling of PS3 firmware files.
 
+use crate::crypto::CryptoEngine;
 use oc_core::error::LoaderError;
+use oc_memory::BeValue;
 use tracing::{debug, info};
 
 /// PUP (PlayStation Update Package) file magic
 pub const PUP_MAGIC: [u8; 8] = [0x53, 0x43, 0x45, 0x55, 0x46, 0x00, 0x00, 0x00]; // "SCEUF\0\0\0"
 
 /// PUP file header
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, BeValue)]
 #[repr(C)]
 pub struct PupHeader {
     pub magic: [u8; 8],
@@ -21,7 +23,7 @@ pub struct PupHeader {
 }
 
 /// PUP file entry
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, BeValue)]
 #[repr(C)]
 pub struct PupFileEntry {
     pub entry_id: u64,
@@ -31,7 +33,7 @@ pub struct PupFileEntry {
 }
 
 /// PUP hash entry
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, BeValue)]
 #[repr(C)]
 pub struct PupHashEntry {
     pub entry_id: u64,
@@ -61,6 +63,22 @@ pub enum PupEntryId {
     Unknown = 0xFFFF,
 }
 
+impl PupEntryId {
+    /// File name an entry is extracted to
+    pub fn file_name(&self, raw_id: u64) -> String {
+        match self {
+            Self::UpdateVersion => "version.txt".to_string(),
+            Self::Lv0 => "lv0.self".to_string(),
+            Self::Lv1 => "lv1.self".to_string(),
+            Self::Lv2Kernel => "lv2_kernel.self".to_string(),
+            Self::Vsh => "vsh.self".to_string(),
+            Self::CoreOs => "core_os.bin".to_string(),
+            Self::PkgMetadata => "pkg_metadata.bin".to_string(),
+            Self::Unknown => format!("entry_{:x}.bin", raw_id),
+        }
+    }
+}
+
 impl From<u64> for PupEntryId {
     fn from(value: u64) -> Self {
         match value {
@@ -126,6 +144,8 @@ pub struct FirmwareFile {
     pub raw_id: u64,
     pub offset: u64,
     pub size: u64,
+    /// HMAC-SHA1 of the data from the hash table, if the table is present
+    pub hmac: Option<[u8; 20]>,
 }
 
 /// PUP file loader
@@ -152,7 +172,7 @@ impl PupLoader {
 
     /// Parse PUP header
     pub fn parse_header(data: &[u8]) -> Result<PupHeader, LoaderError> {
-        if data.len() < 48 {
+        if data.len() < PupHeader::SIZE {
             return Err(LoaderError::InvalidPup("File too small".to_string()));
         }
 
@@ -160,32 +180,7 @@ impl PupLoader {
             return Err(LoaderError::InvalidPup("Invalid PUP magic".to_string()));
         }
 
-        let mut magic = [0u8; 8];
-        magic.copy_from_slice(&data[0..8]);
-
-        let header = PupHeader {
-            magic,
-            package_version: u64::from_be_bytes([
-                data[8], data[9], data[10], data[11],
-                data[12], data[13], data[14], data[15],
-            ]),
-            image_version: u64::from_be_bytes([
-                data[16], data[17], data[18], data[19],
-                data[20], data[21], data[22], data[23],
-            ]),
-            file_count: u64::from_be_bytes([
-                data[24], data[25], data[26], data[27],
-                data[28], data[29], data[30], data[31],
-            ]),
-            header_length: u64::from_be_bytes([
-                data[32], data[33], data[34], data[35],
-                data[36], data[37], data[38], data[39],
-            ]),
-            data_length: u64::from_be_bytes([
-                data[40], data[41], data[42], data[43],
-                data[44], data[45], data[46], data[47],
-            ]),
-        };
+        let header = PupHeader::from_be_slice(data);
 
         info!(
             "PUP header: version=0x{:016x}, files={}, header_len=0x{:x}",
@@ -203,41 +198,43 @@ impl PupLoader {
         self.entries.clear();
 
         // Parse file entries (after header)
-        let entry_offset = 48usize;
-        let entry_size = 32usize; // sizeof(PupFileEntry)
+        let entry_offset = PupHeader::SIZE;
 
         for i in 0..header.file_count {
-            let offset = entry_offset + (i as usize * entry_size);
-            if data.len() < offset + entry_size {
-                return Err(LoaderError::InvalidPup("Truncated file table".to_string()));
-            }
-
-            let entry_id = u64::from_be_bytes([
-                data[offset], data[offset + 1], data[offset + 2], data[offset + 3],
-                data[offset + 4], data[offset + 5], data[offset + 6], data[offset + 7],
-            ]);
-            let data_offset = u64::from_be_bytes([
-                data[offset + 8], data[offset + 9], data[offset + 10], data[offset + 11],
-                data[offset + 12], data[offset + 13], data[offset + 14], data[offset + 15],
-            ]);
-            let data_length = u64::from_be_bytes([
-                data[offset + 16], data[offset + 17], data[offset + 18], data[offset + 19],
-                data[offset + 20], data[offset + 21], data[offset + 22], data[offset + 23],
-            ]);
+            let offset = entry_offset + (i as usize * PupFileEntry::SIZE);
+            let entry = data
+                .get(offset..)
+                .and_then(PupFileEntry::try_from_be_slice)
+                .ok_or_else(|| LoaderError::InvalidPup("Truncated file table".to_string()))?;
 
             debug!(
                 "PUP entry {}: id=0x{:x}, offset=0x{:x}, size=0x{:x}",
-                i, entry_id, data_offset, data_length
+                i, entry.entry_id, entry.data_offset, entry.data_length
             );
 
             self.entries.push(FirmwareFile {
-                id: PupEntryId::from(entry_id),
-                raw_id: entry_id,
-                offset: data_offset,
-                size: data_length,
+                id: PupEntryId::from(entry.entry_id),
+                raw_id: entry.entry_id,
+                offset: entry.data_offset,
+                size: entry.data_length,
+                hmac: None,
             });
         }
 
+        // The hash table follows the file table, one entry per file
+        let hash_offset = entry_offset + header.file_count as usize * PupFileEntry::SIZE;
+        for (i, entry) in self.entries.iter_mut().enumerate() {
+            let offset = hash_offset + i * PupHashEntry::SIZE;
+            let Some(hash) = data.get(offset..).and_then(PupHashEntry::try_from_be_slice) else {
+                break;
+            };
+            if hash.entry_id != entry.raw_id {
+                debug!("PUP hash entry {} is for 0x{:x}, not 0x{:x}", i, hash.entry_id, entry.raw_id);
+                continue;
+            }
+            entry.hmac = Some(hash.hash);
+        }
+
         Ok(&self.entries)
     }
 
@@ -268,6 +265,17 @@ impl PupLoader {
         Ok(data[start..end].to_vec())
     }
 
+    /// Check an entry's data against its HMAC-SHA1 with the PUP HMAC `key`
+    ///
+    /// None when the PUP has no hash for the entry.
+    pub fn verify_hmac(&self, data: &[u8], entry: &FirmwareFile, key: &[u8]) -> Result<Option<bool>, LoaderError> {
+        let Some(expected) = entry.hmac else {
+            return Ok(None);
+        };
+        let contents = self.extract(data, entry)?;
+        Ok(Some(CryptoEngine::new().hmac_sha1(key, &contents) == expected))
+    }
+
     /// Install firmware from a PUP file to a target directory
     ///
     /// This extracts the necessary files from the firmware update package
@@ -351,6 +359,85 @@ impl Default for PupLoader {
     }
 }
 
+/// File in the CoreOS container
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreOsFile {
+    pub name: String,
+    /// Offset from the start of the container
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// CoreOS container header
+#[derive(Debug, Clone, Copy, BeValue)]
+#[repr(C)]
+struct CoreOsHeader {
+    version: u64,
+    file_count: u64,
+    size: u64,
+}
+
+/// CoreOS container file table entry
+#[derive(Debug, Clone, Copy, BeValue)]
+#[repr(C)]
+struct CoreOsEntry {
+    offset: u64,
+    size: u64,
+    /// NUL-padded file name
+    name: [u8; 32],
+}
+
+/// The CoreOS container, holding the LV0/LV1/LV2 SELFs and system SPRXs
+///
+/// A 0x18 byte header (version, file count, total size) is followed by
+/// 0x30 byte entries: offset, size and a NUL-padded 32 byte name.
+#[derive(Debug, Clone)]
+pub struct CoreOsContainer {
+    pub version: u64,
+    pub files: Vec<CoreOsFile>,
+}
+
+impl CoreOsContainer {
+    /// Parse the file table of a CoreOS container
+    pub fn parse(data: &[u8]) -> Result<Self, LoaderError> {
+        let header = CoreOsHeader::try_from_be_slice(data)
+            .ok_or_else(|| LoaderError::InvalidPup("Truncated CoreOS container".to_string()))?;
+        let (count, size) = (header.file_count, header.size);
+        if size > data.len() as u64 || count > (data.len() / CoreOsEntry::SIZE) as u64 {
+            return Err(LoaderError::InvalidPup(format!(
+                "CoreOS container claims {} files in {} bytes, but has {} bytes",
+                count, size, data.len()
+            )));
+        }
+
+        let mut files = Vec::with_capacity(count as usize);
+        for i in 0..count as usize {
+            let base = CoreOsHeader::SIZE + i * CoreOsEntry::SIZE;
+            let entry = data
+                .get(base..)
+                .and_then(CoreOsEntry::try_from_be_slice)
+                .ok_or_else(|| LoaderError::InvalidPup("Truncated CoreOS file table".to_string()))?;
+            let (offset, file_size) = (entry.offset, entry.size);
+            let name = entry.name.split(|&b| b == 0).next().unwrap_or_default();
+            let name = String::from_utf8_lossy(name).into_owned();
+            let fits = match offset.checked_add(file_size) {
+                Some(end) => end <= data.len() as u64,
+                None => false,
+            };
+            if !fits {
+                return Err(LoaderError::InvalidPup(format!("CoreOS file {} extends beyond the container", name)));
+            }
+            files.push(CoreOsFile { name, offset, size: file_size });
+        }
+        Ok(Self { version: header.version, files })
+    }
+
+    /// Contents of `file`, from the container `data`
+    pub fn extract<'a>(&self, data: &'a [u8], file: &CoreOsFile) -> &'a [u8] {
+        &data[file.offset as usize..(file.offset + file.size) as usize]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -378,6 +465,53 @@ mod tests {
         assert_eq!(v.minor, 90);
     }
 
+    #[test]
+    fn test_pup_hashes() {
+        let contents = b"4.90\n";
+        let key = b"test key";
+        let mut pup = PUP_MAGIC.to_vec();
+        for value in [1u64, 0x0490_0000_0000_0000, 1, 0x70, contents.len() as u64] {
+            pup.extend_from_slice(&value.to_be_bytes());
+        }
+        for value in [0x100u64, 0x70, contents.len() as u64, 0] {
+            pup.extend_from_slice(&value.to_be_bytes());
+        }
+        pup.extend_from_slice(&0x100u64.to_be_bytes());
+        pup.extend_from_slice(&CryptoEngine::new().hmac_sha1(key, contents));
+        pup.extend_from_slice(&[0; 4]);
+        pup.extend_from_slice(contents);
+
+        let mut loader = PupLoader::new();
+        loader.parse(&pup).unwrap();
+        let entry = loader.entries()[0].clone();
+        assert_eq!(entry.id.file_name(entry.raw_id), "version.txt");
+        assert_eq!(loader.extract(&pup, &entry).unwrap(), contents);
+        assert_eq!(loader.verify_hmac(&pup, &entry, key).unwrap(), Some(true));
+        assert_eq!(loader.verify_hmac(&pup, &entry, b"other key").unwrap(), Some(false));
+    }
+
+    #[test]
+    fn test_core_os_container() {
+        let mut data = Vec::new();
+        for value in [1u64, 2, 0x18 + 2 * 0x30 + 6] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+        for (offset, size, name) in [(0x78u64, 4u64, "lv1ldr"), (0x7c, 2, "sc_iso.self")] {
+            data.extend_from_slice(&offset.to_be_bytes());
+            data.extend_from_slice(&size.to_be_bytes());
+            let mut field = [0u8; 32];
+            field[..name.len()].copy_from_slice(name.as_bytes());
+            data.extend_from_slice(&field);
+        }
+        data.extend_from_slice(b"SCE\0hi");
+
+        let container = CoreOsContainer::parse(&data).unwrap();
+        let names: Vec<_> = container.files.iter().map(|file| file.name.as_str()).collect();
+        assert_eq!(names, ["lv1ldr", "sc_iso.self"]);
+        assert_eq!(container.extract(&data, &container.files[1]), b"hi");
+        assert!(CoreOsContainer::parse(&data[..0x40]).is_err());
+    }
+
     #[test]
     fn test_entry_id_conversion() {
         assert_eq!(PupEntryId::from(0x100), PupEntryId::UpdateVersion);
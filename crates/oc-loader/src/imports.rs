@@ -0,0 +1,185 @@
+//! Import tables of PPU executables and PRX modules
+//!
+//! An executable lists the libraries it imports in its `sys_process_prx_param`
+//! segment, a PRX module in its module info. Every library entry names the
+//! library and points at a table of function NIDs and a parallel table of
+//! stub slots that the loader fills with the resolved function addresses.
+
+use crate::elf::{pt, ElfLoader};
+use oc_core::error::LoaderError;
+
+/// Magic of `sys_process_prx_param`
+pub const PRX_PARAM_MAGIC: u32 = 0x1B43_4CEC;
+
+/// ELF type of PRX modules
+pub const ET_SCE_PPURELEXEC: u16 = 0xFFA4;
+
+/// Size of a library stub entry when it does not give its own
+const LIBSTUB_SIZE: u32 = 0x2C;
+
+/// Symbol imported from a library
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportedSymbol {
+    pub nid: u32,
+    /// Address of the stub table slot holding the resolved address
+    pub slot: u32,
+}
+
+/// Library an executable or module imports from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedLibrary {
+    pub name: String,
+    pub version: u16,
+    pub functions: Vec<ImportedSymbol>,
+    pub variables: Vec<ImportedSymbol>,
+}
+
+/// Reads the file contents at guest addresses through the LOAD segments
+struct Image<'a> {
+    elf: &'a ElfLoader,
+    data: &'a [u8],
+}
+
+impl Image<'_> {
+    fn bytes(&self, addr: u32, len: usize) -> Result<&[u8], LoaderError> {
+        let addr = addr as u64;
+        let offset = self
+            .elf
+            .phdrs
+            .iter()
+            .filter(|p| p.p_type == pt::LOAD)
+            .find(|p| addr >= p.p_vaddr && addr + len as u64 <= p.p_vaddr + p.p_filesz)
+            .map(|p| (p.p_offset + addr - p.p_vaddr) as usize)
+            .ok_or_else(|| LoaderError::InvalidElf(format!("0x{:x} is outside the file's segments", addr)))?;
+        self.data
+            .get(offset..offset + len)
+            .ok_or_else(|| LoaderError::InvalidElf(format!("0x{:x} is past the end of the file", addr)))
+    }
+
+    fn read_u16(&self, addr: u32) -> Result<u16, LoaderError> {
+        let bytes = self.bytes(addr, 2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u32(&self, addr: u32) -> Result<u32, LoaderError> {
+        let bytes = self.bytes(addr, 4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_string(&self, addr: u32) -> Result<String, LoaderError> {
+        let mut name = Vec::new();
+        for offset in 0..256 {
+            match self.bytes(addr.wrapping_add(offset), 1)?[0] {
+                0 => break,
+                byte => name.push(byte),
+            }
+        }
+        Ok(String::from_utf8_lossy(&name).into_owned())
+    }
+}
+
+/// Parse the libraries an executable or PRX module imports
+///
+/// `data` is the whole ELF file `elf` was parsed from. Files without an
+/// import table import nothing.
+pub fn parse_imports(elf: &ElfLoader, data: &[u8]) -> Result<Vec<ImportedLibrary>, LoaderError> {
+    let image = Image { elf, data };
+    let (start, end) = if let Some(param) = elf.phdrs.iter().find(|p| p.p_type == pt::PROC1 && p.p_filesz >= 0x20) {
+        let param = param.p_vaddr as u32;
+        let magic = image.read_u32(param + 4)?;
+        if magic != PRX_PARAM_MAGIC {
+            return Err(LoaderError::InvalidElf(format!("Bad sys_process_prx_param magic 0x{:08x}", magic)));
+        }
+        (image.read_u32(param + 0x18)?, image.read_u32(param + 0x1C)?)
+    } else if elf.header.e_type == ET_SCE_PPURELEXEC {
+        // The first segment's physical address is the file offset of the module info
+        let Some(first) = elf.phdrs.first() else {
+            return Ok(Vec::new());
+        };
+        let info = first.p_vaddr.wrapping_add(first.p_paddr).wrapping_sub(first.p_offset) as u32;
+        (image.read_u32(info + 0x2C)?, image.read_u32(info + 0x30)?)
+    } else {
+        return Ok(Vec::new());
+    };
+
+    let mut libraries = Vec::new();
+    let mut entry = start;
+    while entry < end {
+        let size = match image.bytes(entry, 1)?[0] as u32 {
+            0 => LIBSTUB_SIZE,
+            size => size,
+        };
+        let version = image.read_u16(entry + 2)?;
+        let function_count = image.read_u16(entry + 6)?;
+        let variable_count = image.read_u16(entry + 8)?;
+        let name = match image.read_u32(entry + 0x10)? {
+            0 => String::new(),
+            addr => image.read_string(addr)?,
+        };
+        let symbols = |nids: u32, slots: u32, count: u16| -> Result<Vec<ImportedSymbol>, LoaderError> {
+            (0..count as u32)
+                .map(|i| {
+                    let nid = image.read_u32(nids.wrapping_add(i * 4))?;
+                    Ok(ImportedSymbol { nid, slot: slots.wrapping_add(i * 4) })
+                })
+                .collect()
+        };
+        libraries.push(ImportedLibrary {
+            name,
+            version,
+            functions: symbols(image.read_u32(entry + 0x14)?, image.read_u32(entry + 0x18)?, function_count)?,
+            variables: symbols(image.read_u32(entry + 0x1C)?, image.read_u32(entry + 0x20)?, variable_count)?,
+        });
+        entry = entry.saturating_add(size);
+    }
+    Ok(libraries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elf::{Elf64Header, Elf64Phdr};
+
+    #[test]
+    fn test_parse_imports() {
+        // One segment at 0x10000 holding the prx param, a library entry, its name and tables
+        let mut data = vec![0u8; 0x100];
+        let put = |data: &mut Vec<u8>, offset: usize, value: u32| {
+            data[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+        };
+        put(&mut data, 0x04, PRX_PARAM_MAGIC);
+        put(&mut data, 0x18, 0x10040);
+        put(&mut data, 0x1C, 0x10040 + LIBSTUB_SIZE);
+        data[0x40] = LIBSTUB_SIZE as u8;
+        data[0x46..0x48].copy_from_slice(&2u16.to_be_bytes());
+        put(&mut data, 0x50, 0x100A0);
+        put(&mut data, 0x54, 0x100B0);
+        put(&mut data, 0x58, 0x100C0);
+        data[0xA0..0xA7].copy_from_slice(b"cellPad");
+        put(&mut data, 0xB0, 0x1CF98800);
+        put(&mut data, 0xB4, 0x4D9B75D5);
+
+        let segment = |p_type| Elf64Phdr {
+            p_type,
+            p_vaddr: 0x10000,
+            p_filesz: 0x100,
+            p_memsz: 0x100,
+            ..Default::default()
+        };
+        let elf = ElfLoader {
+            header: Elf64Header::default(),
+            phdrs: vec![segment(pt::LOAD), segment(pt::PROC1)],
+            shdrs: Vec::new(),
+            symbols: Vec::new(),
+            entry_point: 0,
+        };
+        let libraries = parse_imports(&elf, &data).unwrap();
+        assert_eq!(libraries.len(), 1);
+        assert_eq!(libraries[0].name, "cellPad");
+        assert_eq!(
+            libraries[0].functions,
+            [ImportedSymbol { nid: 0x1CF98800, slot: 0x100C0 }, ImportedSymbol { nid: 0x4D9B75D5, slot: 0x100C4 }]
+        );
+        assert!(libraries[0].variables.is_empty());
+    }
+}
@@ -19,14 +19,20 @@
 pub mod crypto;
 pub mod elf;
 pub mod firmware;
+pub mod imports;
 pub mod pkg;
 pub mod prx;
 pub mod self_file;
 
 // Re-export main types
 pub use elf::{ElfLoader, Elf64Header, Elf64Phdr, Elf64Shdr, Symbol};
-pub use self_file::{SelfLoader, SelfHeader, AppInfo};
+pub use self_file::{
+    AppInfo, MetadataHeader, MetadataSectionHeader, SelfExtHeader, SelfHeader, SelfLoader, SelfMetadata,
+};
 pub use prx::{PrxLoader, PrxModule, PrxExport, PrxImport, ExportType, ImportType};
 pub use crypto::{CryptoEngine, KeyType, KeyEntry, SelfKeySet, KeyStats};
-pub use firmware::{PupLoader, PupHeader, PupEntryId, FirmwareVersion, FirmwareFile}; 
-pub use pkg::{PkgLoader, PkgHeader, PkgType, PkgFileEntry, PkgMetadataEntry};
+pub use imports::{parse_imports, ImportedLibrary, ImportedSymbol};
+pub use firmware::{CoreOsContainer, CoreOsFile, FirmwareFile, FirmwareVersion, PupEntryId, PupHeader, PupLoader};
+pub use pkg::{
+    PkgDrmType, PkgFileEntry, PkgHeader, PkgInstallReport, PkgInstaller, PkgLoader, PkgMetadataEntry, PkgType,
+};
@@ -2,12 +2,30 @@
 //!
 //! This module provides parsing and extraction of PlayStation Store package files.
 
+use aes::cipher::{KeyIvInit, StreamCipher, StreamCipherSeek};
 use oc_core::error::LoaderError;
+use oc_memory::BeValue;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
 use tracing::{debug, info, warn};
 
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+
 /// PKG file magic
 pub const PKG_MAGIC: u32 = 0x7F504B47; // "\x7FPKG"
 
+/// AES key of the data area of retail PS3 packages
+pub const PKG_AES_KEY: [u8; 16] = [
+    0x2E, 0x7B, 0x71, 0xD7, 0xC9, 0xC9, 0xA1, 0x4E, 0xA3, 0x22, 0x1F, 0x18, 0x88, 0x28, 0xB8, 0xF8,
+];
+
+/// Revision of retail packages; debug packages have 0
+pub const PKG_REVISION_RETAIL: u16 = 0x8000;
+
+/// Platform of PS3 packages, in the type field of the header
+pub const PKG_PLATFORM_PS3: u16 = 0x0001;
+
 /// PKG file types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u32)]
@@ -71,7 +89,7 @@ pub struct PkgHeader {
     pub magic: u32,
     /// Revision
     pub revision: u16,
-    /// Type
+    /// Type, the platform for PS3 (1) or PSP and PS Vita (2) content
     pub pkg_type: u16,
     /// Metadata offset
     pub metadata_offset: u32,
@@ -91,12 +109,88 @@ pub struct PkgHeader {
     pub content_id: String,
     /// Package digest (SHA-1)
     pub digest: [u8; 16],
-    /// Package key (AES key for decryption)
+    /// Klicensee, the counter the data area is encrypted from
     pub pkg_data_key: [u8; 16],
-    /// Package IV
+    /// Header CMAC
     pub pkg_data_iv: [u8; 16],
 }
 
+/// PKG header as stored in the file
+#[derive(Debug, Clone, Copy, BeValue)]
+#[repr(C)]
+struct RawPkgHeader {
+    magic: u32,
+    revision: u16,
+    pkg_type: u16,
+    metadata_offset: u32,
+    metadata_count: u32,
+    metadata_size: u32,
+    item_count: u32,
+    total_size: u64,
+    data_offset: u64,
+    data_size: u64,
+    /// NUL-padded content ID
+    content_id: [u8; 0x24],
+    padding: [u8; 0x0C],
+    digest: [u8; 16],
+    pkg_data_key: [u8; 16],
+    pkg_data_iv: [u8; 16],
+}
+
+impl From<RawPkgHeader> for PkgHeader {
+    fn from(raw: RawPkgHeader) -> Self {
+        Self {
+            magic: raw.magic,
+            revision: raw.revision,
+            pkg_type: raw.pkg_type,
+            metadata_offset: raw.metadata_offset,
+            metadata_count: raw.metadata_count,
+            metadata_size: raw.metadata_size,
+            item_count: raw.item_count,
+            total_size: raw.total_size,
+            data_offset: raw.data_offset,
+            data_size: raw.data_size,
+            content_id: String::from_utf8_lossy(&raw.content_id).trim_end_matches('\0').to_string(),
+            digest: raw.digest,
+            pkg_data_key: raw.pkg_data_key,
+            pkg_data_iv: raw.pkg_data_iv,
+        }
+    }
+}
+
+/// Header of a metadata entry, followed by `size` bytes of data
+#[derive(Debug, Clone, Copy, BeValue)]
+#[repr(C)]
+struct RawPkgMetadata {
+    id: u32,
+    size: u32,
+}
+
+/// Item table entry as stored in the data area
+#[derive(Debug, Clone, Copy, BeValue)]
+#[repr(C)]
+struct RawPkgItem {
+    name_offset: u32,
+    name_size: u32,
+    data_offset: u64,
+    data_size: u64,
+    flags: u32,
+    padding: u32,
+}
+
+impl RawPkgItem {
+    fn entry(self, name: String) -> PkgFileEntry {
+        PkgFileEntry {
+            name_offset: self.name_offset,
+            name_size: self.name_size,
+            data_offset: self.data_offset,
+            data_size: self.data_size,
+            flags: self.flags,
+            name,
+        }
+    }
+}
+
 /// PKG metadata entry
 #[derive(Debug, Clone)]
 pub struct PkgMetadataEntry {
@@ -166,11 +260,7 @@ impl PkgLoader {
 
     /// Check if data is a PKG file
     pub fn is_pkg(data: &[u8]) -> bool {
-        if data.len() < 4 {
-            return false;
-        }
-        let magic = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
-        magic == PKG_MAGIC
+        u32::try_from_be_slice(data) == Some(PKG_MAGIC)
     }
 
     /// Parse PKG header
@@ -183,64 +273,14 @@ impl PkgLoader {
             return Err(LoaderError::InvalidPkg("Invalid PKG magic".to_string()));
         }
 
-        let magic = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
-        let revision = u16::from_be_bytes([data[4], data[5]]);
-        let pkg_type = u16::from_be_bytes([data[6], data[7]]);
-        let metadata_offset = u32::from_be_bytes([data[8], data[9], data[10], data[11]]);
-        let metadata_count = u32::from_be_bytes([data[12], data[13], data[14], data[15]]);
-        let metadata_size = u32::from_be_bytes([data[16], data[17], data[18], data[19]]);
-        let item_count = u32::from_be_bytes([data[20], data[21], data[22], data[23]]);
-        let total_size = u64::from_be_bytes([
-            data[24], data[25], data[26], data[27],
-            data[28], data[29], data[30], data[31],
-        ]);
-        let data_offset = u64::from_be_bytes([
-            data[32], data[33], data[34], data[35],
-            data[36], data[37], data[38], data[39],
-        ]);
-        let data_size = u64::from_be_bytes([
-            data[40], data[41], data[42], data[43],
-            data[44], data[45], data[46], data[47],
-        ]);
-
-        // Content ID is at offset 0x30, 36 bytes
-        let content_id = String::from_utf8_lossy(&data[0x30..0x54])
-            .trim_end_matches('\0')
-            .to_string();
-
-        // Digest at offset 0x60
-        let mut digest = [0u8; 16];
-        digest.copy_from_slice(&data[0x60..0x70]);
-
-        // Data key at offset 0x70
-        let mut pkg_data_key = [0u8; 16];
-        pkg_data_key.copy_from_slice(&data[0x70..0x80]);
-
-        // Data IV at offset 0x80
-        let mut pkg_data_iv = [0u8; 16];
-        pkg_data_iv.copy_from_slice(&data[0x80..0x90]);
+        let header = PkgHeader::from(RawPkgHeader::from_be_slice(data));
 
         info!(
             "PKG header: type={}, content_id={}, items={}, size={}",
-            pkg_type, content_id, item_count, total_size
+            header.pkg_type, header.content_id, header.item_count, header.total_size
         );
 
-        Ok(PkgHeader {
-            magic,
-            revision,
-            pkg_type,
-            metadata_offset,
-            metadata_count,
-            metadata_size,
-            item_count,
-            total_size,
-            data_offset,
-            data_size,
-            content_id,
-            digest,
-            pkg_data_key,
-            pkg_data_iv,
-        })
+        Ok(header)
     }
 
     /// Parse PKG file
@@ -252,16 +292,9 @@ impl PkgLoader {
         let mut offset = header.metadata_offset as usize;
         
         for _ in 0..header.metadata_count {
-            if data.len() < offset + 8 {
+            let Some(RawPkgMetadata { id, size }) = data.get(offset..).and_then(RawPkgMetadata::try_from_be_slice) else {
                 break;
-            }
-
-            let id = u32::from_be_bytes([
-                data[offset], data[offset + 1], data[offset + 2], data[offset + 3],
-            ]);
-            let size = u32::from_be_bytes([
-                data[offset + 4], data[offset + 5], data[offset + 6], data[offset + 7],
-            ]);
+            };
 
             let entry_data = if data.len() >= offset + 8 + size as usize {
                 data[offset + 8..offset + 8 + size as usize].to_vec()
@@ -277,79 +310,31 @@ impl PkgLoader {
                 data: entry_data,
             });
 
-            offset += 8 + ((size + 15) & !15) as usize; // Align to 16 bytes
+            offset += 8 + size as usize;
         }
 
         // Parse file entries
         self.files.clear();
         let items_offset = header.data_offset as usize;
-        let item_entry_size = 32usize;
-
         for i in 0..header.item_count {
-            let entry_offset = items_offset + (i as usize * item_entry_size);
-            if data.len() < entry_offset + item_entry_size {
+            let entry_offset = items_offset + (i as usize * RawPkgItem::SIZE);
+            let Some(item) = data.get(entry_offset..).and_then(RawPkgItem::try_from_be_slice) else {
                 break;
-            }
-
-            let name_offset = u32::from_be_bytes([
-                data[entry_offset],
-                data[entry_offset + 1],
-                data[entry_offset + 2],
-                data[entry_offset + 3],
-            ]);
-            let name_size = u32::from_be_bytes([
-                data[entry_offset + 4],
-                data[entry_offset + 5],
-                data[entry_offset + 6],
-                data[entry_offset + 7],
-            ]);
-            let file_data_offset = u64::from_be_bytes([
-                data[entry_offset + 8],
-                data[entry_offset + 9],
-                data[entry_offset + 10],
-                data[entry_offset + 11],
-                data[entry_offset + 12],
-                data[entry_offset + 13],
-                data[entry_offset + 14],
-                data[entry_offset + 15],
-            ]);
-            let file_data_size = u64::from_be_bytes([
-                data[entry_offset + 16],
-                data[entry_offset + 17],
-                data[entry_offset + 18],
-                data[entry_offset + 19],
-                data[entry_offset + 20],
-                data[entry_offset + 21],
-                data[entry_offset + 22],
-                data[entry_offset + 23],
-            ]);
-            let flags = u32::from_be_bytes([
-                data[entry_offset + 24],
-                data[entry_offset + 25],
-                data[entry_offset + 26],
-                data[entry_offset + 27],
-            ]);
+            };
 
             // Extract file name
-            let name_start = (header.data_offset + name_offset as u64) as usize;
-            let name = if data.len() >= name_start + name_size as usize {
-                String::from_utf8_lossy(&data[name_start..name_start + name_size as usize])
+            let name_start = (header.data_offset + item.name_offset as u64) as usize;
+            let name = if data.len() >= name_start + item.name_size as usize {
+                String::from_utf8_lossy(&data[name_start..name_start + item.name_size as usize])
                     .trim_end_matches('\0')
                     .to_string()
             } else {
                 format!("file_{}", i)
             };
 
-            debug!("PKG file {}: {} (size={})", i, name, file_data_size);
+            debug!("PKG file {}: {} (size={})", i, name, item.data_size);
 
-            self.files.push(PkgFileEntry {
-                name_offset,
-                name_size,
-                data_offset: file_data_offset,
-                data_size: file_data_size,
-                flags,
-                name,
-            });
+            self.files.push(item.entry(name));
         }
 
         self.header = Some(header);
@@ -410,6 +395,269 @@ impl Default for PkgLoader {
     }
 }
 
+/// DRM of a package's content, from its metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PkgDrmType {
+    /// Licensed to a PSN account, needs a RAP file
+    Network,
+    /// Licensed to a console, needs a RAP file
+    Local,
+    /// Free content, no license needed
+    Free,
+    Unknown(u32),
+}
+
+impl PkgDrmType {
+    /// Whether content of this type needs a RAP license to run
+    pub fn needs_license(&self) -> bool {
+        matches!(self, PkgDrmType::Network | PkgDrmType::Local)
+    }
+}
+
+impl From<u32> for PkgDrmType {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => Self::Network,
+            2 => Self::Local,
+            3 | 0xD => Self::Free,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+/// Result of installing a package
+#[derive(Debug, Clone)]
+pub struct PkgInstallReport {
+    pub content_id: String,
+    pub title_id: String,
+    /// Directory the content was installed into
+    pub install_dir: PathBuf,
+    /// Files written
+    pub files: usize,
+    /// Bytes of file data written
+    pub bytes: u64,
+    /// RAP license copied into the user's exdata directory
+    pub rap: Option<PathBuf>,
+    /// Whether the content needs a license that was not installed with it
+    pub missing_license: bool,
+}
+
+/// Entry types of directories in the item table
+const PKG_ENTRY_DIRECTORY: [u32; 2] = [0x04, 0x12];
+
+/// Size of the pieces packages are decrypted in
+const PKG_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Installs a package straight from disk, decrypting its data area as it goes
+pub struct PkgInstaller {
+    file: File,
+    header: PkgHeader,
+    metadata: Vec<PkgMetadataEntry>,
+}
+
+impl PkgInstaller {
+    /// Open the package at `path` and read its header and metadata
+    pub fn open(path: &Path) -> Result<Self, LoaderError> {
+        let mut file = File::open(path)
+            .map_err(|e| LoaderError::InvalidPkg(format!("Failed to open {}: {}", path.display(), e)))?;
+        let mut head = vec![0u8; 0xC0];
+        file.read_exact(&mut head)
+            .map_err(|e| LoaderError::InvalidPkg(format!("Failed to read {}: {}", path.display(), e)))?;
+        let header = PkgLoader::parse_header(&head)?;
+        if header.revision != PKG_REVISION_RETAIL {
+            return Err(LoaderError::UnsupportedFormat(format!(
+                "debug packages (revision 0x{:04x}) are not supported",
+                header.revision
+            )));
+        }
+        if header.pkg_type != PKG_PLATFORM_PS3 {
+            return Err(LoaderError::UnsupportedFormat(format!(
+                "only PS3 packages can be installed (platform {})",
+                header.pkg_type
+            )));
+        }
+        if header.content_id.len() < 16 {
+            return Err(LoaderError::InvalidPkg(format!("Invalid content ID {}", header.content_id)));
+        }
+
+        // Metadata sits unencrypted between the header and the data area
+        let metadata_end = header.data_offset.min(header.metadata_offset as u64 + 0x10000);
+        let mut area = vec![0u8; metadata_end.saturating_sub(0xC0) as usize];
+        file.read_exact(&mut area)
+            .map_err(|e| LoaderError::InvalidPkg(format!("Failed to read metadata: {}", e)))?;
+        head.extend_from_slice(&area);
+        let mut metadata = Vec::new();
+        let mut offset = header.metadata_offset as usize;
+        for _ in 0..header.metadata_count {
+            let Some(RawPkgMetadata { id, size }) = head.get(offset..).and_then(RawPkgMetadata::try_from_be_slice) else {
+                break;
+            };
+            let end = (offset + 8 + size as usize).min(head.len());
+            metadata.push(PkgMetadataEntry { id, size, data: head[offset + 8..end].to_vec() });
+            offset += 8 + size as usize;
+        }
+
+        Ok(Self { file, header, metadata })
+    }
+
+    /// The package header
+    pub fn header(&self) -> &PkgHeader {
+        &self.header
+    }
+
+    /// Content ID, e.g. UP0001-NPUB30001_00-0000000000000000
+    pub fn content_id(&self) -> &str {
+        &self.header.content_id
+    }
+
+    /// Title ID, the part of the content ID naming the game
+    pub fn title_id(&self) -> &str {
+        &self.header.content_id[7..16]
+    }
+
+    /// DRM of the content, unknown without DRM metadata
+    pub fn drm_type(&self) -> PkgDrmType {
+        self.metadata
+            .iter()
+            .find(|entry| entry.id == PkgMetadataId::DrmType as u32)
+            .and_then(|entry| entry.data.get(..4))
+            .map_or(PkgDrmType::Unknown(0), |data| PkgDrmType::from(u32::from_be_slice(data)))
+    }
+
+    /// Read and decrypt the data area from `offset` into `buffer`
+    fn read_data(&mut self, offset: u64, buffer: &mut [u8]) -> Result<(), LoaderError> {
+        if offset + buffer.len() as u64 > self.header.data_size {
+            return Err(LoaderError::InvalidPkg("Item extends beyond the data area".to_string()));
+        }
+        self.file
+            .seek(SeekFrom::Start(self.header.data_offset + offset))
+            .and_then(|_| self.file.read_exact(buffer))
+            .map_err(|e| LoaderError::InvalidPkg(format!("Failed to read package data: {}", e)))?;
+        // The IV is the klicensee at 0x70, counting up once per 16-byte block
+        let mut cipher = Aes128Ctr::new(&PKG_AES_KEY.into(), &self.header.pkg_data_key.into());
+        cipher.seek(offset);
+        cipher.apply_keystream(buffer);
+        Ok(())
+    }
+
+    /// Decrypt the item table
+    pub fn entries(&mut self) -> Result<Vec<PkgFileEntry>, LoaderError> {
+        let count = self.header.item_count as usize;
+        let mut table = vec![0u8; count * RawPkgItem::SIZE];
+        self.read_data(0, &mut table)?;
+
+        let mut entries = Vec::with_capacity(count);
+        for item in table.chunks_exact(RawPkgItem::SIZE).map(RawPkgItem::from_be_slice) {
+            let mut name = vec![0u8; item.name_size as usize];
+            self.read_data(item.name_offset as u64, &mut name)?;
+            entries.push(item.entry(String::from_utf8_lossy(&name).trim_end_matches('\0').to_string()));
+        }
+        Ok(entries)
+    }
+
+    /// Install the package under `dev_hdd0/game/<title ID>`, with its RAP license if given
+    ///
+    /// `progress` is told the bytes written so far and in total, and stops
+    /// the installation by returning false. Files already installed are
+    /// overwritten, as for updates.
+    pub fn install(
+        &mut self,
+        dev_hdd0: &Path,
+        rap: Option<&Path>,
+        mut progress: impl FnMut(u64, u64) -> bool,
+    ) -> Result<PkgInstallReport, LoaderError> {
+        let entries = self.entries()?;
+        let install_dir = dev_hdd0.join("game").join(self.title_id());
+        info!("Installing {} to {}", self.content_id(), install_dir.display());
+
+        let total: u64 = entries
+            .iter()
+            .filter(|entry| !PKG_ENTRY_DIRECTORY.contains(&(entry.flags & 0xFF)))
+            .map(|entry| entry.data_size)
+            .sum();
+        let mut report = PkgInstallReport {
+            content_id: self.content_id().to_string(),
+            title_id: self.title_id().to_string(),
+            install_dir: install_dir.clone(),
+            files: 0,
+            bytes: 0,
+            rap: None,
+            missing_license: self.drm_type().needs_license(),
+        };
+        if let Some(rap) = rap {
+            report.rap = Some(install_rap(rap, dev_hdd0, self.content_id())?);
+            report.missing_license = false;
+        }
+        let cancelled = || LoaderError::InvalidPkg("Installation cancelled".to_string());
+        if !progress(0, total) {
+            return Err(cancelled());
+        }
+
+        let mut buffer = vec![0u8; PKG_CHUNK_SIZE];
+        for entry in &entries {
+            let relative = Path::new(&entry.name);
+            if entry.name.is_empty() || !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+                return Err(LoaderError::InvalidPkg(format!("Invalid item path {:?}", entry.name)));
+            }
+            let path = install_dir.join(relative);
+            if PKG_ENTRY_DIRECTORY.contains(&(entry.flags & 0xFF)) {
+                fs::create_dir_all(&path).map_err(|e| {
+                    LoaderError::InvalidPkg(format!("Failed to create directory {}: {}", path.display(), e))
+                })?;
+                continue;
+            }
+
+            debug!("Extracting {} ({} bytes)", entry.name, entry.data_size);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| LoaderError::InvalidPkg(format!("Failed to create parent dir: {}", e)))?;
+            }
+            let mut file = File::create(&path)
+                .map_err(|e| LoaderError::InvalidPkg(format!("Failed to create file {}: {}", path.display(), e)))?;
+            let mut written = 0;
+            while written < entry.data_size {
+                let len = (entry.data_size - written).min(PKG_CHUNK_SIZE as u64) as usize;
+                self.read_data(entry.data_offset + written, &mut buffer[..len])?;
+                file.write_all(&buffer[..len])
+                    .map_err(|e| LoaderError::InvalidPkg(format!("Failed to write file {}: {}", path.display(), e)))?;
+                written += len as u64;
+                report.bytes += len as u64;
+                if !progress(report.bytes, total) {
+                    return Err(cancelled());
+                }
+            }
+            report.files += 1;
+        }
+
+        info!("Installed {} files of {} ({} bytes)", report.files, report.content_id, report.bytes);
+        Ok(report)
+    }
+}
+
+/// RAP license file of `content_id` next to the package at `pkg_path`, if there is one
+pub fn find_rap(pkg_path: &Path, content_id: &str) -> Option<PathBuf> {
+    let rap = pkg_path.with_file_name(format!("{}.rap", content_id));
+    rap.is_file().then_some(rap)
+}
+
+/// Copy a RAP license into the user's exdata directory as `<content ID>.rap`
+pub fn install_rap(rap: &Path, dev_hdd0: &Path, content_id: &str) -> Result<PathBuf, LoaderError> {
+    let data = fs::read(rap)
+        .map_err(|e| LoaderError::InvalidPkg(format!("Failed to read {}: {}", rap.display(), e)))?;
+    // A RAP holds the 16-byte encrypted klicensee of its content
+    if data.len() != 16 {
+        return Err(LoaderError::InvalidPkg(format!("{} is not a RAP file", rap.display())));
+    }
+    let exdata = dev_hdd0.join("home/00000001/exdata");
+    fs::create_dir_all(&exdata)
+        .map_err(|e| LoaderError::InvalidPkg(format!("Failed to create directory {}: {}", exdata.display(), e)))?;
+    let target = exdata.join(format!("{}.rap", content_id));
+    fs::write(&target, data)
+        .map_err(|e| LoaderError::InvalidPkg(format!("Failed to write file {}: {}", target.display(), e)))?;
+    info!("Installed license {}", target.display());
+    Ok(target)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -434,4 +682,104 @@ mod tests {
         assert_eq!(PkgType::from(0x02), PkgType::Ps3Dlc);
         assert_eq!(PkgType::from(0x99), PkgType::Unknown);
     }
+
+    /// Build a retail package holding `files`, encrypted like a real one
+    fn build_pkg(content_id: &str, drm_type: u32, files: &[(&str, &[u8])]) -> Vec<u8> {
+        let klicensee = [0x5Au8; 16];
+        // Item table, then names, then file data, each 16-byte aligned
+        let align = |n: usize| (n + 15) & !15;
+        let mut names = Vec::new();
+        let mut items = Vec::new();
+        let table_size = files.len() * 32;
+        let mut name_offsets = Vec::new();
+        for (name, _) in files {
+            name_offsets.push(table_size + names.len());
+            names.extend_from_slice(name.as_bytes());
+            names.resize(align(names.len()), 0);
+        }
+        let mut data_area = vec![0u8; table_size];
+        data_area.extend_from_slice(&names);
+        for (i, (name, contents)) in files.iter().enumerate() {
+            let is_dir = contents.is_empty();
+            let data_offset = data_area.len();
+            data_area.extend_from_slice(contents);
+            data_area.resize(align(data_area.len()), 0);
+            let mut item = Vec::new();
+            item.extend_from_slice(&(name_offsets[i] as u32).to_be_bytes());
+            item.extend_from_slice(&(name.len() as u32).to_be_bytes());
+            item.extend_from_slice(&(data_offset as u64).to_be_bytes());
+            item.extend_from_slice(&(contents.len() as u64).to_be_bytes());
+            item.extend_from_slice(&(if is_dir { 0x8000_0004u32 } else { 0x8000_0003 }).to_be_bytes());
+            item.extend_from_slice(&[0; 4]);
+            items.extend_from_slice(&item);
+        }
+        data_area[..table_size].copy_from_slice(&items);
+        let mut cipher = Aes128Ctr::new(&PKG_AES_KEY.into(), &klicensee.into());
+        cipher.apply_keystream(&mut data_area);
+
+        let mut pkg = vec![0u8; 0x100];
+        pkg[0..4].copy_from_slice(&PKG_MAGIC.to_be_bytes());
+        pkg[4..6].copy_from_slice(&PKG_REVISION_RETAIL.to_be_bytes());
+        pkg[6..8].copy_from_slice(&PKG_PLATFORM_PS3.to_be_bytes());
+        pkg[8..12].copy_from_slice(&0xC0u32.to_be_bytes());
+        pkg[12..16].copy_from_slice(&1u32.to_be_bytes());
+        pkg[20..24].copy_from_slice(&(files.len() as u32).to_be_bytes());
+        pkg[24..32].copy_from_slice(&((0x100 + data_area.len()) as u64).to_be_bytes());
+        pkg[32..40].copy_from_slice(&0x100u64.to_be_bytes());
+        pkg[40..48].copy_from_slice(&(data_area.len() as u64).to_be_bytes());
+        pkg[0x30..0x30 + content_id.len()].copy_from_slice(content_id.as_bytes());
+        pkg[0x70..0x80].copy_from_slice(&klicensee);
+        // DRM type metadata
+        pkg[0xC0..0xC4].copy_from_slice(&1u32.to_be_bytes());
+        pkg[0xC4..0xC8].copy_from_slice(&4u32.to_be_bytes());
+        pkg[0xC8..0xCC].copy_from_slice(&drm_type.to_be_bytes());
+        pkg.extend_from_slice(&data_area);
+        pkg
+    }
+
+    #[test]
+    fn test_pkg_install() {
+        let dir = std::env::temp_dir().join(format!("oc_pkg_test_{}", std::process::id()));
+        let content_id = "UP0001-NPUB30001_00-TESTPACKAGE00000";
+        let eboot = vec![0xA5u8; 3 * PKG_CHUNK_SIZE / 2];
+        let pkg = build_pkg(
+            content_id,
+            1,
+            &[("USRDIR", b""), ("PARAM.SFO", b"\0PSF"), ("USRDIR/EBOOT.BIN", &eboot)],
+        );
+        let pkg_path = dir.join("game.pkg");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&pkg_path, &pkg).unwrap();
+        let rap = dir.join(format!("{}.rap", content_id));
+        fs::write(&rap, [7u8; 16]).unwrap();
+        assert_eq!(find_rap(&pkg_path, content_id), Some(rap.clone()));
+
+        let mut installer = PkgInstaller::open(&pkg_path).unwrap();
+        assert_eq!(installer.title_id(), "NPUB30001");
+        assert_eq!(installer.drm_type(), PkgDrmType::Network);
+        let entries = installer.entries().unwrap();
+        assert_eq!(entries[2].name, "USRDIR/EBOOT.BIN");
+
+        // Stopping midway leaves a partial install
+        let hdd0 = dir.join("dev_hdd0");
+        assert!(installer.install(&hdd0, None, |done, _| done == 0).is_err());
+
+        let mut updates = Vec::new();
+        let report = installer
+            .install(&hdd0, Some(&rap), |done, total| {
+                updates.push((done, total));
+                true
+            })
+            .unwrap();
+        let game = hdd0.join("game/NPUB30001");
+        assert_eq!(report.install_dir, game);
+        assert_eq!((report.files, report.bytes), (2, 4 + eboot.len() as u64));
+        assert!(!report.missing_license);
+        assert_eq!(updates.last(), Some(&(report.bytes, report.bytes)));
+        assert_eq!(fs::read(game.join("PARAM.SFO")).unwrap(), b"\0PSF");
+        assert_eq!(fs::read(game.join("USRDIR/EBOOT.BIN")).unwrap(), eboot);
+        let license = hdd0.join(format!("home/00000001/exdata/{}.rap", content_id));
+        assert_eq!(fs::read(license).unwrap(), [7u8; 16]);
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
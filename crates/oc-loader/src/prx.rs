@@ -5,8 +5,14 @@ use crate::elf::ElfLoader;
 use std::collections::HashMap;
 use std::sync::Arc;
 use oc_memory::MemoryManager;
+use sha1::{Digest, Sha1};
 use tracing::{debug, info};
 
+/// Suffix appended to a symbol name before hashing it into a NID
+const NID_SUFFIX: [u8; 16] = [
+    0x67, 0x59, 0x65, 0x99, 0x04, 0x25, 0x04, 0x90, 0x56, 0x64, 0x27, 0x49, 0x94, 0x89, 0x74, 0x1A,
+];
+
 /// PRX module information
 #[derive(Debug, Clone)]
 pub struct PrxModule {
@@ -108,23 +114,23 @@ impl PrxLoader {
     
     /// Initialize NID database with known PS3 system function NIDs
     fn init_nid_database(&mut self) {
-        // Common PS3 system function NIDs (sample - real database would be much larger)
-        let known_nids = [
-            (0x9FB6228E, "sys_ppu_thread_create"),
-            (0x350D454E, "sys_ppu_thread_exit"),
-            (0x8461E528, "sys_process_exit"),
-            (0xDA0EB71A, "sys_lwmutex_create"),
-            (0x1573DC3F, "sys_lwmutex_destroy"),
-            (0xE7A3B5D8, "sys_prx_load_module"),
-            (0x26090058, "sys_prx_unload_module"),
-            (0xB27C8AE7, "cellFsOpen"),
-            (0x2CB51F0D, "cellFsClose"),
-            (0xB1840AE5, "cellFsRead"),
-            (0xC9AFD7F6, "cellFsWrite"),
+        // Common PS3 system functions (sample - real database would be much larger)
+        let known_names = [
+            "sys_ppu_thread_create",
+            "sys_ppu_thread_exit",
+            "sys_process_exit",
+            "sys_lwmutex_create",
+            "sys_lwmutex_destroy",
+            "sys_prx_load_module",
+            "sys_prx_unload_module",
+            "cellFsOpen",
+            "cellFsClose",
+            "cellFsRead",
+            "cellFsWrite",
         ];
         
-        for (nid, name) in &known_nids {
-            self.nid_database.insert(*nid, name.to_string());
+        for name in known_names {
+            self.nid_database.insert(Self::calculate_nid(name), name.to_string());
         }
         
         debug!("Initialized NID database with {} entries", self.nid_database.len());
@@ -402,20 +408,15 @@ impl PrxLoader {
     }
 
     /// Calculate NID (Name ID) for a symbol name
-    /// This is a simplified hash - PS3 uses SHA-1 truncated to 32 bits
-    fn calculate_nid(name: &str) -> u32 {
-        // FNV-1a hash constants
-        const FNV_OFFSET_BASIS: u32 = 0x811c9dc5;
-        const FNV_PRIME: u32 = 0x01000193;
-
-        // Simple hash for demonstration
-        // Real implementation would use SHA-1 and take first 4 bytes
-        let mut hash: u32 = FNV_OFFSET_BASIS;
-        for byte in name.bytes() {
-            hash ^= byte as u32;
-            hash = hash.wrapping_mul(FNV_PRIME);
-        }
-        hash
+    ///
+    /// The NID is the first 4 bytes, little-endian, of the SHA-1 of the name
+    /// followed by a fixed suffix.
+    pub fn calculate_nid(name: &str) -> u32 {
+        let mut hasher = Sha1::new();
+        hasher.update(name.as_bytes());
+        hasher.update(NID_SUFFIX);
+        let digest = hasher.finalize();
+        u32::from_le_bytes([digest[0], digest[1], digest[2], digest[3]])
     }
 
     /// List all loaded modules
@@ -452,6 +453,8 @@ mod tests {
 
         assert_eq!(nid1, nid2);
         assert_ne!(nid1, nid3);
+        assert_eq!(PrxLoader::calculate_nid("cellPadInit"), 0x1CF98800);
+        assert_eq!(PrxLoader::calculate_nid("cellSysutilCheckCallback"), 0x189A74DA);
     }
 
     #[test]
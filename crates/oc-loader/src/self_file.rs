@@ -1,6 +1,7 @@
 //! SELF file loader
 
 use oc_core::error::LoaderError;
+use oc_memory::BeValue;
 use crate::crypto::CryptoEngine;
 use tracing::{debug, info, warn};
 use flate2::read::ZlibDecoder;
@@ -10,7 +11,7 @@ use std::io::Read;
 pub const SELF_MAGIC: [u8; 4] = [0x53, 0x43, 0x45, 0x00]; // "SCE\0"
 
 /// SELF file header
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, BeValue)]
 #[repr(C)]
 pub struct SelfHeader {
     pub magic: [u8; 4],
@@ -22,8 +23,24 @@ pub struct SelfHeader {
     pub data_len: u64,
 }
 
+/// SELF extended header, following the SCE header
+#[derive(Debug, Clone, Copy, BeValue)]
+#[repr(C)]
+pub struct SelfExtHeader {
+    pub header_type: u64,
+    pub app_info_offset: u64,
+    pub elf_offset: u64,
+    pub phdr_offset: u64,
+    pub shdr_offset: u64,
+    pub section_info_offset: u64,
+    pub sce_version_offset: u64,
+    pub control_info_offset: u64,
+    pub control_info_size: u64,
+    pub padding: u64,
+}
+
 /// SELF application info
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, BeValue)]
 #[repr(C)]
 pub struct AppInfo {
     pub auth_id: u64,
@@ -55,7 +72,7 @@ pub struct MetadataHeader {
 }
 
 /// SELF metadata section header
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, BeValue)]
 #[repr(C)]
 pub struct MetadataSectionHeader {
     pub data_offset: u64,
@@ -70,6 +87,17 @@ pub struct MetadataSectionHeader {
     pub compressed: u32,
 }
 
+/// Decrypted SELF metadata
+#[derive(Debug, Clone)]
+pub struct SelfMetadata {
+    pub header: MetadataHeader,
+    pub sections: Vec<MetadataSectionHeader>,
+    /// Section keys, indexed by half the section's key index
+    pub keys: Vec<[u8; 16]>,
+    /// Section IVs, indexed by half the section's IV index
+    pub ivs: Vec<[u8; 16]>,
+}
+
 /// SELF loader with decryption support
 pub struct SelfLoader {
     crypto: CryptoEngine,
@@ -127,24 +155,7 @@ impl SelfLoader {
             return Err(LoaderError::InvalidSelf("Invalid SELF magic".to_string()));
         }
 
-        let mut magic = [0u8; 4];
-        magic.copy_from_slice(&data[0..4]);
-
-        let header = SelfHeader {
-            magic,
-            version: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
-            key_type: u16::from_be_bytes([data[8], data[9]]),
-            header_type: u16::from_be_bytes([data[10], data[11]]),
-            metadata_offset: u32::from_be_bytes([data[12], data[13], data[14], data[15]]),
-            header_len: u64::from_be_bytes([
-                data[16], data[17], data[18], data[19],
-                data[20], data[21], data[22], data[23],
-            ]),
-            data_len: u64::from_be_bytes([
-                data[24], data[25], data[26], data[27],
-                data[28], data[29], data[30], data[31],
-            ]),
-        };
+        let header = SelfHeader::from_be_slice(data);
 
         info!(
             "SELF header: version=0x{:x}, key_type=0x{:x}, metadata_offset=0x{:x}",
@@ -154,32 +165,22 @@ impl SelfLoader {
         Ok(header)
     }
 
+    /// Parse the extended header
+    pub fn parse_ext_header(data: &[u8]) -> Result<SelfExtHeader, LoaderError> {
+        if data.len() < 32 + 80 {
+            return Err(LoaderError::InvalidSelf("File too small for extended header".to_string()));
+        }
+
+        Ok(SelfExtHeader::from_be_slice(&data[32..]))
+    }
+
     /// Parse application info
     pub fn parse_app_info(data: &[u8], offset: usize) -> Result<AppInfo, LoaderError> {
         if data.len() < offset + 40 {
             return Err(LoaderError::InvalidSelf("Invalid app info offset".to_string()));
         }
 
-        let info = AppInfo {
-            auth_id: u64::from_be_bytes([
-                data[offset], data[offset + 1], data[offset + 2], data[offset + 3],
-                data[offset + 4], data[offset + 5], data[offset + 6], data[offset + 7],
-            ]),
-            vendor_id: u32::from_be_bytes([
-                data[offset + 8], data[offset + 9], data[offset + 10], data[offset + 11],
-            ]),
-            self_type: u32::from_be_bytes([
-                data[offset + 12], data[offset + 13], data[offset + 14], data[offset + 15],
-            ]),
-            version: u64::from_be_bytes([
-                data[offset + 16], data[offset + 17], data[offset + 18], data[offset + 19],
-                data[offset + 20], data[offset + 21], data[offset + 22], data[offset + 23],
-            ]),
-            padding: u64::from_be_bytes([
-                data[offset + 24], data[offset + 25], data[offset + 26], data[offset + 27],
-                data[offset + 28], data[offset + 29], data[offset + 30], data[offset + 31],
-            ]),
-        };
+        let info = AppInfo::from_be_slice(&data[offset..]);
 
         debug!(
             "App info: auth_id=0x{:x}, type=0x{:x}",
@@ -216,26 +217,16 @@ impl SelfLoader {
             return Ok(data[elf_offset..].to_vec());
         }
 
-        // Attempt basic decryption
         warn!("Encrypted SELF detected, attempting decryption");
-        
-        // Parse the extended header to get app info offset
-        // Extended header starts at offset 32 (after main SELF header)
-        if data.len() < 32 + 80 {
-            return Err(LoaderError::InvalidSelf("File too small for extended header".to_string()));
-        }
-        
-        // Read ext_hdr (extended header) - 80 bytes starting at offset 32
-        let ext_hdr_offset = 32usize;
-        let app_info_offset = u64::from_be_bytes([
-            data[ext_hdr_offset + 8], data[ext_hdr_offset + 9], 
-            data[ext_hdr_offset + 10], data[ext_hdr_offset + 11],
-            data[ext_hdr_offset + 12], data[ext_hdr_offset + 13], 
-            data[ext_hdr_offset + 14], data[ext_hdr_offset + 15],
-        ]) as usize;
-        
-        // Parse app info at the specified offset
-        let app_info = Self::parse_app_info(data, app_info_offset)?;
+        let metadata = self.decrypt_metadata(data)?;
+        self.build_elf(data, &metadata)
+    }
+
+    /// Decrypt the metadata of an encrypted SELF: its section table and section keys
+    pub fn decrypt_metadata(&self, data: &[u8]) -> Result<SelfMetadata, LoaderError> {
+        let header = Self::parse_header(data)?;
+        let ext_header = Self::parse_ext_header(data)?;
+        let app_info = Self::parse_app_info(data, ext_header.app_info_offset as usize)?;
         
         // The program_type from app_info determines which key set to use
         // RPCS3 uses: program_type, se_flags, program_sceversion
@@ -260,7 +251,7 @@ impl SelfLoader {
         program_type: u32,
         revision: u16,
         _version: u64,
-    ) -> Result<Vec<u8>, LoaderError> {
+    ) -> Result<SelfMetadata, LoaderError> {
         // Check if this is a DEBUG SELF (se_flags & 0x8000)
         // Debug SELFs have unencrypted metadata
         let is_debug = (revision & 0x8000) == 0x8000;
@@ -306,7 +297,7 @@ impl SelfLoader {
         let key_set = self.crypto.get_self_key_set(internal_key_type, key_revision)
             .ok_or_else(|| {
                 let available_keys = self.crypto.list_available_keys();
-                LoaderError::DecryptionFailed(format!(
+                LoaderError::MissingKey(format!(
                     "Key not available for program_type={}, internal_type={}, revision=0x{:04x}\n\
                      This game may require additional decryption keys.\n\
                      Available keys ({} total): {}\n\n\
@@ -466,24 +457,20 @@ impl SelfLoader {
         
         debug!("Extracted {} section keys and {} IVs", section_keys.len(), section_ivs.len());
         
-        // Now we need to read the ELF header to understand the structure
-        // Extended header starts at offset 32
-        let ext_hdr_offset = 32usize;
-        
-        // Parse ext_hdr to get ELF header offset
-        let elf_offset = u64::from_be_bytes([
-            data[ext_hdr_offset + 16], data[ext_hdr_offset + 17],
-            data[ext_hdr_offset + 18], data[ext_hdr_offset + 19],
-            data[ext_hdr_offset + 20], data[ext_hdr_offset + 21],
-            data[ext_hdr_offset + 22], data[ext_hdr_offset + 23],
-        ]) as usize;
-        
-        let phdr_offset = u64::from_be_bytes([
-            data[ext_hdr_offset + 24], data[ext_hdr_offset + 25],
-            data[ext_hdr_offset + 26], data[ext_hdr_offset + 27],
-            data[ext_hdr_offset + 28], data[ext_hdr_offset + 29],
-            data[ext_hdr_offset + 30], data[ext_hdr_offset + 31],
-        ]) as usize;
+        Ok(SelfMetadata {
+            header: meta_hdr,
+            sections: section_headers,
+            keys: section_keys,
+            ivs: section_ivs,
+        })
+    }
+
+    /// Rebuild the ELF of an encrypted SELF from its decrypted metadata
+    fn build_elf(&self, data: &[u8], metadata: &SelfMetadata) -> Result<Vec<u8>, LoaderError> {
+        let ext_header = Self::parse_ext_header(data)?;
+        let elf_offset = ext_header.elf_offset as usize;
+        let phdr_offset = ext_header.phdr_offset as usize;
+        let (section_headers, section_keys, section_ivs) = (&metadata.sections, &metadata.keys, &metadata.ivs);
         
         debug!("ELF header offset: 0x{:x}, Program header offset: 0x{:x}", elf_offset, phdr_offset);
         
@@ -722,18 +709,7 @@ impl SelfLoader {
             return Err(LoaderError::InvalidSelf("Section header too small".to_string()));
         }
         
-        Ok(MetadataSectionHeader {
-            data_offset: u64::from_be_bytes(data[0..8].try_into().unwrap()),
-            data_size: u64::from_be_bytes(data[8..16].try_into().unwrap()),
-            section_type: u32::from_be_bytes(data[16..20].try_into().unwrap()),
-            section_index: u32::from_be_bytes(data[20..24].try_into().unwrap()),
-            hashed: u32::from_be_bytes(data[24..28].try_into().unwrap()),
-            sha1_index: u32::from_be_bytes(data[28..32].try_into().unwrap()),
-            encrypted: u32::from_be_bytes(data[32..36].try_into().unwrap()),
-            key_index: u32::from_be_bytes(data[36..40].try_into().unwrap()),
-            iv_index: u32::from_be_bytes(data[40..44].try_into().unwrap()),
-            compressed: u32::from_be_bytes(data[44..48].try_into().unwrap()),
-        })
+        Ok(MetadataSectionHeader::from_be_slice(data))
     }
 
     /// Decrypt metadata section (MetaLV2)
@@ -773,4 +749,18 @@ mod tests {
         let elf_data = [0x7F, b'E', b'L', b'F', 0x00, 0x00];
         assert!(!SelfLoader::is_self(&elf_data));
     }
+
+    #[test]
+    fn test_parse_ext_header() {
+        let mut data = vec![0u8; 32 + 80];
+        data[..4].copy_from_slice(&SELF_MAGIC);
+        data[40..48].copy_from_slice(&0x70u64.to_be_bytes());
+        data[48..56].copy_from_slice(&0x90u64.to_be_bytes());
+        let ext = SelfLoader::parse_ext_header(&data).unwrap();
+        assert_eq!(ext.app_info_offset, 0x70);
+        assert_eq!(ext.elf_offset, 0x90);
+        assert!(SelfLoader::parse_ext_header(&data[..100]).is_err());
+        // The app info it points at is past the end
+        assert!(matches!(SelfLoader::new().decrypt_metadata(&data), Err(LoaderError::InvalidSelf(_))));
+    }
 }
@@ -0,0 +1,234 @@
+//! Bluetooth manager (sys_usbbtaudio_*)
+//!
+//! The kernel's Bluetooth stack is reached through the sys_usbbtaudio
+//! calls. Discovery lists every device the console has paired, not just
+//! headsets, so titles that enumerate Bluetooth devices themselves find the
+//! controllers cellPad and cellGem report. The host fills the registry
+//! from its emulated controllers; pairing and connecting only succeed for
+//! devices in it.
+
+use oc_core::error::KernelError;
+use oc_memory::BeValue;
+use parking_lot::Mutex;
+
+/// Bytes per device in the discovery buffer
+pub const BT_DEVICE_INFO_SIZE: usize = 80;
+/// Bytes of the NUL-terminated name in a device entry
+pub const BT_NAME_SIZE: usize = 64;
+
+/// Status bit: the device is paired with the console
+pub const BT_DEVICE_PAIRED: u32 = 0x1;
+/// Status bit: the device is connected
+pub const BT_DEVICE_CONNECTED: u32 = 0x2;
+
+/// Discovery buffer entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BeValue)]
+#[repr(C)]
+pub struct BtDeviceInfo {
+    pub address: [u8; 6],
+    pub class_of_device: u32,
+    /// `BT_DEVICE_*` bits
+    pub status: u32,
+    /// NUL-terminated name
+    pub name: [u8; BT_NAME_SIZE],
+}
+
+/// A device in the console's Bluetooth registry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BtDevice {
+    /// BD_ADDR
+    pub address: [u8; 6],
+    /// Class of device (e.g. 0x002508 for a gamepad)
+    pub class_of_device: u32,
+    pub name: String,
+    pub paired: bool,
+    pub connected: bool,
+}
+
+impl BtDevice {
+    /// Discovery buffer entry of the device
+    pub fn info(&self) -> BtDeviceInfo {
+        let mut status = 0;
+        if self.paired {
+            status |= BT_DEVICE_PAIRED;
+        }
+        if self.connected {
+            status |= BT_DEVICE_CONNECTED;
+        }
+        // Keep the terminating NUL
+        let mut name = [0u8; BT_NAME_SIZE];
+        let bytes = self.name.as_bytes();
+        let len = bytes.len().min(BT_NAME_SIZE - 1);
+        name[..len].copy_from_slice(&bytes[..len]);
+        BtDeviceInfo {
+            address: self.address,
+            class_of_device: self.class_of_device,
+            status,
+            name,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct BluetoothState {
+    initialized: bool,
+    discovering: bool,
+    devices: Vec<BtDevice>,
+}
+
+impl BluetoothState {
+    fn check_initialized(&self) -> Result<(), KernelError> {
+        if self.initialized {
+            Ok(())
+        } else {
+            Err(KernelError::PermissionDenied)
+        }
+    }
+
+    fn device_mut(&mut self, address: &[u8; 6]) -> Result<&mut BtDevice, KernelError> {
+        self.check_initialized()?;
+        self.devices
+            .iter_mut()
+            .find(|dev| &dev.address == address)
+            .ok_or(KernelError::InvalidId(u32::from_be_bytes([address[2], address[3], address[4], address[5]])))
+    }
+}
+
+/// The console's Bluetooth devices
+#[derive(Debug, Default)]
+pub struct Bluetooth {
+    state: Mutex<BluetoothState>,
+}
+
+impl Bluetooth {
+    /// Create the manager with no devices
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the registry with the host's devices
+    pub fn set_devices(&self, devices: Vec<BtDevice>) {
+        self.state.lock().devices = devices;
+    }
+
+    /// Devices in the registry
+    pub fn devices(&self) -> Vec<BtDevice> {
+        self.state.lock().devices.clone()
+    }
+
+    /// Start the manager for the game
+    pub fn initialize(&self) {
+        self.state.lock().initialized = true;
+    }
+
+    /// Stop the manager
+    pub fn finalize(&self) -> Result<(), KernelError> {
+        let mut state = self.state.lock();
+        state.check_initialized()?;
+        state.initialized = false;
+        state.discovering = false;
+        Ok(())
+    }
+
+    /// Start discovery, returning up to `max` devices
+    ///
+    /// Paired devices are listed whether or not they are connected.
+    pub fn discovery(&self, max: usize) -> Result<Vec<BtDevice>, KernelError> {
+        let mut state = self.state.lock();
+        state.check_initialized()?;
+        state.discovering = true;
+        Ok(state.devices.iter().take(max).cloned().collect())
+    }
+
+    /// Stop discovery
+    pub fn cancel_discovery(&self) -> Result<(), KernelError> {
+        let mut state = self.state.lock();
+        state.check_initialized()?;
+        state.discovering = false;
+        Ok(())
+    }
+
+    /// Whether discovery is running
+    pub fn is_discovering(&self) -> bool {
+        self.state.lock().discovering
+    }
+
+    /// Pair with a device
+    pub fn pair(&self, address: &[u8; 6]) -> Result<(), KernelError> {
+        let mut state = self.state.lock();
+        state.device_mut(address)?.paired = true;
+        Ok(())
+    }
+
+    /// Supply a passkey for pairing (the emulated controllers need none)
+    pub fn set_passkey(&self, address: &[u8; 6]) -> Result<(), KernelError> {
+        let mut state = self.state.lock();
+        state.device_mut(address).map(|_| ())
+    }
+
+    /// Connect to a paired device
+    ///
+    /// Controllers connect on their own, so this only succeeds for those
+    /// already connected on the host and times out for the rest.
+    pub fn connect(&self, address: &[u8; 6]) -> Result<(), KernelError> {
+        let mut state = self.state.lock();
+        let device = state.device_mut(address)?;
+        if !device.paired {
+            return Err(KernelError::PermissionDenied);
+        }
+        if device.connected {
+            Ok(())
+        } else {
+            Err(KernelError::Timeout)
+        }
+    }
+
+    /// Drop the game's connection to a device
+    ///
+    /// The host's controllers stay connected for cellPad.
+    pub fn disconnect(&self, address: &[u8; 6]) -> Result<(), KernelError> {
+        let mut state = self.state.lock();
+        let device = state.device_mut(address)?;
+        tracing::debug!("Bluetooth disconnect of \"{}\" requested", device.name);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn controller(index: u8, connected: bool) -> BtDevice {
+        BtDevice {
+            address: [0x00, 0x19, 0xC1, 0x00, 0x03, index],
+            class_of_device: 0x002508,
+            name: "PLAYSTATION(R)3 Controller".to_string(),
+            paired: true,
+            connected,
+        }
+    }
+
+    #[test]
+    fn test_bluetooth_registry() {
+        let bluetooth = Bluetooth::new();
+        bluetooth.set_devices(vec![controller(0, true), controller(1, false)]);
+        assert!(bluetooth.discovery(8).is_err());
+
+        bluetooth.initialize();
+        let devices = bluetooth.discovery(8).unwrap();
+        assert_eq!(devices.len(), 2);
+        assert!(bluetooth.is_discovering());
+        assert_eq!(BtDeviceInfo::SIZE, BT_DEVICE_INFO_SIZE);
+        let entry = devices[0].info().to_be_vec();
+        assert_eq!(entry[..6], [0x00, 0x19, 0xC1, 0x00, 0x03, 0x00]);
+        assert_eq!(entry[8..16], [0, 0x00, 0x25, 0x08, 0, 0, 0, 3]);
+        assert_eq!(&entry[16..42], b"PLAYSTATION(R)3 Controller");
+
+        assert!(bluetooth.connect(&devices[0].address).is_ok());
+        assert!(matches!(bluetooth.connect(&devices[1].address), Err(KernelError::Timeout)));
+        assert!(bluetooth.pair(&[1, 2, 3, 4, 5, 6]).is_err());
+        bluetooth.cancel_discovery().unwrap();
+        bluetooth.finalize().unwrap();
+        assert!(bluetooth.connect(&devices[0].address).is_err());
+    }
+}
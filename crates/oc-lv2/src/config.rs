@@ -0,0 +1,282 @@
+//! System configuration services (sys_config_*)
+//!
+//! A config handle connects an event queue to the kernel's configuration
+//! service registry. Games open one at boot, register their own services and
+//! listen for system ones such as controller or BD remote settings. No system
+//! service registers on the emulated console, so no events are posted; the
+//! handles, listeners and services are only tracked so the calls succeed and
+//! later unregistering finds them.
+
+use crate::objects::{KernelObject, ObjectDebugInfo, ObjectId, ObjectManager, ObjectType};
+use crate::sync::event::EventQueue;
+use oc_core::error::KernelError;
+use oc_core::savestate::{StateReader, StateWriter};
+use parking_lot::Mutex;
+use std::io;
+use std::sync::Arc;
+
+/// Listener for events of one service
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceListener {
+    pub id: ObjectId,
+    pub service_id: i64,
+    /// Lowest event ID delivered
+    pub min_event_id: u64,
+    pub verbosity: u64,
+    /// Bytes of listener data passed in
+    pub data_size: u64,
+}
+
+/// Service registered by the game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigService {
+    pub id: ObjectId,
+    pub service_id: i64,
+    pub user_id: u64,
+    pub verbosity: u64,
+    /// Bytes of service data passed in
+    pub data_size: u64,
+}
+
+/// Config handle opened with sys_config_open
+pub struct SysConfig {
+    id: ObjectId,
+    event_queue_id: ObjectId,
+    state: Mutex<SysConfigState>,
+}
+
+#[derive(Debug, Default)]
+struct SysConfigState {
+    listeners: Vec<ServiceListener>,
+    services: Vec<ConfigService>,
+}
+
+impl SysConfig {
+    pub fn new(id: ObjectId, event_queue_id: ObjectId) -> Self {
+        Self {
+            id,
+            event_queue_id,
+            state: Mutex::new(SysConfigState::default()),
+        }
+    }
+
+    /// Event queue events would be posted to
+    pub fn event_queue_id(&self) -> ObjectId {
+        self.event_queue_id
+    }
+
+    /// Listeners added to the handle
+    pub fn listeners(&self) -> Vec<ServiceListener> {
+        self.state.lock().listeners.clone()
+    }
+
+    /// Services registered through the handle
+    pub fn services(&self) -> Vec<ConfigService> {
+        self.state.lock().services.clone()
+    }
+
+    /// Recreate a handle written by [`KernelObject::save_state`]
+    pub(crate) fn load_state(id: ObjectId, r: &mut StateReader) -> io::Result<Self> {
+        let config = Self::new(id, r.u32()?);
+        {
+            let mut state = config.state.lock();
+            for _ in 0..r.count()? {
+                state.listeners.push(ServiceListener {
+                    id: r.u32()?,
+                    service_id: r.u64()? as i64,
+                    min_event_id: r.u64()?,
+                    verbosity: r.u64()?,
+                    data_size: r.u64()?,
+                });
+            }
+            for _ in 0..r.count()? {
+                state.services.push(ConfigService {
+                    id: r.u32()?,
+                    service_id: r.u64()? as i64,
+                    user_id: r.u64()?,
+                    verbosity: r.u64()?,
+                    data_size: r.u64()?,
+                });
+            }
+        }
+        Ok(config)
+    }
+}
+
+impl KernelObject for SysConfig {
+    fn object_type(&self) -> ObjectType {
+        ObjectType::Config
+    }
+
+    fn id(&self) -> ObjectId {
+        self.id
+    }
+
+    fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync> {
+        self
+    }
+
+    fn debug_info(&self) -> ObjectDebugInfo {
+        let state = self.state.lock();
+        ObjectDebugInfo::new(self.id, ObjectType::Config)
+            .detail("event queue", self.event_queue_id)
+            .detail("listeners", state.listeners.len())
+            .detail("services", state.services.len())
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u32(self.event_queue_id);
+        let state = self.state.lock();
+        w.count(state.listeners.len());
+        for listener in &state.listeners {
+            w.u32(listener.id);
+            w.u64(listener.service_id as u64);
+            w.u64(listener.min_event_id);
+            w.u64(listener.verbosity);
+            w.u64(listener.data_size);
+        }
+        w.count(state.services.len());
+        for service in &state.services {
+            w.u32(service.id);
+            w.u64(service.service_id as u64);
+            w.u64(service.user_id);
+            w.u64(service.verbosity);
+            w.u64(service.data_size);
+        }
+    }
+}
+
+/// Config syscall implementations
+pub mod syscalls {
+    use super::*;
+
+    /// sys_config_open
+    pub fn sys_config_open(manager: &ObjectManager, event_queue_id: ObjectId) -> Result<ObjectId, KernelError> {
+        manager.get::<EventQueue>(event_queue_id)?;
+        let id = manager.next_id();
+        manager.register(Arc::new(SysConfig::new(id, event_queue_id)));
+        tracing::debug!("Opened config {} on event queue {}", id, event_queue_id);
+        Ok(id)
+    }
+
+    /// sys_config_close
+    pub fn sys_config_close(manager: &ObjectManager, config_id: ObjectId) -> Result<(), KernelError> {
+        manager.get::<SysConfig>(config_id)?;
+        manager.unregister(config_id)
+    }
+
+    /// sys_config_add_service_listener
+    pub fn sys_config_add_service_listener(
+        manager: &ObjectManager,
+        config_id: ObjectId,
+        service_id: i64,
+        min_event_id: u64,
+        verbosity: u64,
+        data_size: u64,
+    ) -> Result<ObjectId, KernelError> {
+        let config: Arc<SysConfig> = manager.get(config_id)?;
+        let id = manager.next_id();
+        config.state.lock().listeners.push(ServiceListener {
+            id,
+            service_id,
+            min_event_id,
+            verbosity,
+            data_size,
+        });
+        tracing::debug!("Config {}: listener {} for service 0x{:x}", config_id, id, service_id);
+        Ok(id)
+    }
+
+    /// sys_config_remove_service_listener
+    pub fn sys_config_remove_service_listener(
+        manager: &ObjectManager,
+        config_id: ObjectId,
+        listener_id: ObjectId,
+    ) -> Result<(), KernelError> {
+        let config: Arc<SysConfig> = manager.get(config_id)?;
+        let mut state = config.state.lock();
+        let index = state
+            .listeners
+            .iter()
+            .position(|listener| listener.id == listener_id)
+            .ok_or(KernelError::InvalidId(listener_id))?;
+        state.listeners.remove(index);
+        Ok(())
+    }
+
+    /// sys_config_register_service
+    pub fn sys_config_register_service(
+        manager: &ObjectManager,
+        config_id: ObjectId,
+        service_id: i64,
+        user_id: u64,
+        verbosity: u64,
+        data_size: u64,
+    ) -> Result<ObjectId, KernelError> {
+        let config: Arc<SysConfig> = manager.get(config_id)?;
+        let id = manager.next_id();
+        config.state.lock().services.push(ConfigService {
+            id,
+            service_id,
+            user_id,
+            verbosity,
+            data_size,
+        });
+        tracing::debug!("Config {}: registered service 0x{:x} as {}", config_id, service_id, id);
+        Ok(id)
+    }
+
+    /// sys_config_unregister_service
+    pub fn sys_config_unregister_service(
+        manager: &ObjectManager,
+        config_id: ObjectId,
+        service_handle: ObjectId,
+    ) -> Result<(), KernelError> {
+        let config: Arc<SysConfig> = manager.get(config_id)?;
+        let mut state = config.state.lock();
+        let index = state
+            .services
+            .iter()
+            .position(|service| service.id == service_handle)
+            .ok_or(KernelError::InvalidId(service_handle))?;
+        state.services.remove(index);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::event::{syscalls::sys_event_queue_create, EventQueueAttributes};
+
+    #[test]
+    fn test_config_services() {
+        let manager = ObjectManager::new();
+        assert!(syscalls::sys_config_open(&manager, 42).is_err());
+
+        let queue = sys_event_queue_create(&manager, EventQueueAttributes::default(), 16).unwrap();
+        let config_id = syscalls::sys_config_open(&manager, queue).unwrap();
+        let listener = syscalls::sys_config_add_service_listener(&manager, config_id, 0x11, 0, 1, 0).unwrap();
+        let service = syscalls::sys_config_register_service(&manager, config_id, -7, 1, 1, 16).unwrap();
+        assert_ne!(listener, service);
+
+        let config: Arc<SysConfig> = manager.get(config_id).unwrap();
+        assert_eq!(config.event_queue_id(), queue);
+        assert_eq!(config.services()[0].service_id, -7);
+
+        syscalls::sys_config_remove_service_listener(&manager, config_id, listener).unwrap();
+        assert!(syscalls::sys_config_remove_service_listener(&manager, config_id, listener).is_err());
+        assert!(config.listeners().is_empty());
+
+        // The handle round-trips through a savestate
+        let mut w = StateWriter::new();
+        config.save_state(&mut w);
+        let data = w.into_bytes();
+        let restored = SysConfig::load_state(config_id, &mut StateReader::new(&data)).unwrap();
+        assert_eq!(restored.services(), config.services());
+
+        syscalls::sys_config_unregister_service(&manager, config_id, service).unwrap();
+        syscalls::sys_config_close(&manager, config_id).unwrap();
+        assert!(syscalls::sys_config_close(&manager, config_id).is_err());
+    }
+}
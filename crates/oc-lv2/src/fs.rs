@@ -1,10 +1,11 @@
 //! File system (sys_fs_*)
 
-use crate::objects::{KernelObject, ObjectId, ObjectManager, ObjectType};
+use crate::objects::{KernelObject, ObjectDebugInfo, ObjectId, ObjectManager, ObjectType};
 use oc_core::error::KernelError;
-use oc_vfs::VirtualFileSystem;
+use oc_core::savestate::{StateReader, StateWriter};
+use oc_vfs::{VfsAccessKind, VirtualFileSystem};
 use parking_lot::Mutex;
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -93,7 +94,7 @@ struct FileState {
     virtual_path: String,
     path: PathBuf,
     file: Option<std::fs::File>,
-    _flags: u32,
+    flags: u32,
 }
 
 impl FileDescriptor {
@@ -106,7 +107,7 @@ impl FileDescriptor {
                 virtual_path,
                 path,
                 file: Some(file),
-                _flags: flags,
+                flags,
             }),
         })
     }
@@ -215,9 +216,49 @@ impl FileDescriptor {
         self.inner.lock().path.clone()
     }
 
+    /// Write the file's data to disk if it was opened for writing
+    pub fn sync(&self) -> io::Result<()> {
+        let state = self.inner.lock();
+        match state.file.as_ref() {
+            Some(file) if state.flags & (flags::O_WRONLY | flags::O_RDWR) != 0 => file.sync_all(),
+            _ => Ok(()),
+        }
+    }
+
     pub fn virtual_path(&self) -> String {
         self.inner.lock().virtual_path.clone()
     }
+
+    /// Recreate a descriptor written by [`KernelObject::save_state`]
+    ///
+    /// The host file is reopened at the saved offset without truncating it.
+    /// A file that can no longer be opened stays closed, so later calls on
+    /// the descriptor fail instead of the whole savestate.
+    pub(crate) fn load_state(id: ObjectId, r: &mut StateReader) -> io::Result<Self> {
+        let virtual_path = r.str()?;
+        let path = PathBuf::from(r.str()?);
+        let flags = r.u32()?;
+        let position = r.option(|r| r.u64())?;
+        let file = position.and_then(|position| {
+            let reopened = Self::open_file(&path, flags & !flags::O_TRUNC)
+                .ok()
+                .and_then(|mut file| file.seek(SeekFrom::Start(position)).ok().map(|_| file));
+            if reopened.is_none() {
+                tracing::warn!("Savestate: could not reopen {} ({})", virtual_path, path.display());
+            }
+            reopened
+        });
+
+        Ok(Self {
+            id,
+            inner: Mutex::new(FileState {
+                virtual_path,
+                path,
+                file,
+                flags,
+            }),
+        })
+    }
 }
 
 impl KernelObject for FileDescriptor {
@@ -232,6 +273,22 @@ impl KernelObject for FileDescriptor {
     fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync> {
         self
     }
+
+    fn debug_info(&self) -> ObjectDebugInfo {
+        let state = self.inner.lock();
+        ObjectDebugInfo::new(self.id, ObjectType::File)
+            .detail("path", &state.virtual_path)
+            .detail("host path", state.path.display())
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        let mut state = self.inner.lock();
+        w.str(&state.virtual_path);
+        w.str(&state.path.to_string_lossy());
+        w.u32(state.flags);
+        let position = state.file.as_mut().and_then(|file| file.stream_position().ok());
+        w.option(position, |w, position| w.u64(position));
+    }
 }
 
 /// Directory descriptor
@@ -241,7 +298,7 @@ pub struct DirectoryDescriptor {
 }
 
 struct DirectoryState {
-    _path: PathBuf,
+    path: PathBuf,
     entries: Vec<CellFsDirent>,
     position: usize,
 }
@@ -253,7 +310,7 @@ impl DirectoryDescriptor {
         Ok(Self {
             id,
             inner: Mutex::new(DirectoryState {
-                _path: path,
+                path,
                 entries,
                 position: 0,
             }),
@@ -287,6 +344,25 @@ impl DirectoryDescriptor {
 
         Ok(Some(entry))
     }
+
+    /// Recreate a descriptor written by [`KernelObject::save_state`], listing the directory again
+    pub(crate) fn load_state(id: ObjectId, r: &mut StateReader) -> io::Result<Self> {
+        let path = PathBuf::from(r.str()?);
+        let position = r.u64()? as usize;
+        let entries = Self::read_entries(&path).unwrap_or_else(|_| {
+            tracing::warn!("Savestate: could not list {}", path.display());
+            Vec::new()
+        });
+
+        Ok(Self {
+            id,
+            inner: Mutex::new(DirectoryState {
+                path,
+                entries,
+                position,
+            }),
+        })
+    }
 }
 
 impl KernelObject for DirectoryDescriptor {
@@ -301,6 +377,45 @@ impl KernelObject for DirectoryDescriptor {
     fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync> {
         self
     }
+
+    fn debug_info(&self) -> ObjectDebugInfo {
+        let state = self.inner.lock();
+        ObjectDebugInfo::new(self.id, ObjectType::Directory)
+            .detail("host path", state.path.display())
+            .detail("position", format!("{}/{}", state.position, state.entries.len()))
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        let state = self.inner.lock();
+        w.str(&state.path.to_string_lossy());
+        w.u64(state.position as u64);
+    }
+}
+
+/// Flush and close every file and directory descriptor, returning how many were open
+///
+/// Used when the game is torn down, so written data reaches the disk
+/// before the host files are reused.
+pub fn close_all(manager: &ObjectManager) -> usize {
+    let mut closed = 0;
+    for object in manager.list() {
+        let id = object.id();
+        match object.object_type() {
+            ObjectType::File => {
+                if let Ok(file) = manager.get::<FileDescriptor>(id) {
+                    if let Err(e) = file.sync() {
+                        tracing::warn!("Failed to flush {}: {}", file.virtual_path(), e);
+                    }
+                }
+            }
+            ObjectType::Directory => {}
+            _ => continue,
+        }
+        if manager.unregister(id).is_ok() {
+            closed += 1;
+        }
+    }
+    closed
 }
 
 /// File system syscall implementations
@@ -316,9 +431,9 @@ pub mod syscalls {
         _mode: u32,
     ) -> Result<ObjectId, KernelError> {
         // Resolve virtual path to host path using VFS
-        let host_path = vfs
-            .resolve(virtual_path)
-            .unwrap_or_else(|| PathBuf::from(virtual_path));
+        let resolved = vfs.resolve(virtual_path);
+        vfs.tracer().record(VfsAccessKind::Open, virtual_path, resolved.as_deref());
+        let host_path = resolved.unwrap_or_else(|| PathBuf::from(virtual_path));
         
         tracing::debug!(
             "sys_fs_open: virtual_path={}, host_path={:?}, flags={:#x}",
@@ -386,9 +501,9 @@ pub mod syscalls {
     /// sys_fs_stat
     pub fn sys_fs_stat(vfs: &VirtualFileSystem, virtual_path: &str) -> Result<CellFsStat, KernelError> {
         // Resolve virtual path to host path using VFS
-        let host_path = vfs
-            .resolve(virtual_path)
-            .unwrap_or_else(|| PathBuf::from(virtual_path));
+        let resolved = vfs.resolve(virtual_path);
+        vfs.tracer().record(VfsAccessKind::Stat, virtual_path, resolved.as_deref());
+        let host_path = resolved.unwrap_or_else(|| PathBuf::from(virtual_path));
         
         tracing::debug!(
             "sys_fs_stat: virtual_path={}, host_path={:?}",
@@ -438,9 +553,9 @@ pub mod syscalls {
         virtual_path: &str,
     ) -> Result<ObjectId, KernelError> {
         // Resolve virtual path to host path using VFS
-        let host_path = vfs
-            .resolve(virtual_path)
-            .unwrap_or_else(|| PathBuf::from(virtual_path));
+        let resolved = vfs.resolve(virtual_path);
+        vfs.tracer().record(VfsAccessKind::OpenDir, virtual_path, resolved.as_deref());
+        let host_path = resolved.unwrap_or_else(|| PathBuf::from(virtual_path));
         
         tracing::debug!(
             "sys_fs_opendir: virtual_path={}, host_path={:?}",
@@ -626,6 +741,56 @@ mod tests {
         let _ = std::fs::remove_file(temp_path);
     }
 
+    #[test]
+    fn test_close_all() {
+        let manager = ObjectManager::new();
+        let vfs = VirtualFileSystem::new();
+        let temp_path = std::env::temp_dir().join(format!("test_oc_lv2_close_all_{}.txt", std::process::id()));
+
+        let fd = syscalls::sys_fs_open(
+            &manager,
+            &vfs,
+            temp_path.to_str().unwrap(),
+            flags::O_WRONLY | flags::O_CREAT | flags::O_TRUNC,
+            0o644,
+        )
+        .unwrap();
+        syscalls::sys_fs_write(&manager, fd, b"saved").unwrap();
+        let dir = syscalls::sys_fs_opendir(&manager, &vfs, std::env::temp_dir().to_str().unwrap()).unwrap();
+
+        assert_eq!(close_all(&manager), 2);
+        assert!(!manager.exists(fd));
+        assert!(!manager.exists(dir));
+        assert_eq!(std::fs::read(&temp_path).unwrap(), b"saved");
+
+        let _ = std::fs::remove_file(temp_path);
+    }
+
+    #[test]
+    fn test_fs_access_tracing() {
+        let manager = ObjectManager::new();
+        let vfs = VirtualFileSystem::new();
+        vfs.tracer().set_enabled(true);
+
+        let temp_dir = std::env::temp_dir().join("test_oc_lv2_trace");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(temp_dir.join("present.bin"), b"data").unwrap();
+        vfs.mount("/dev_hdd0", temp_dir.clone());
+
+        let fd = syscalls::sys_fs_open(&manager, &vfs, "/dev_hdd0/present.bin", flags::O_RDONLY, 0)
+            .unwrap();
+        syscalls::sys_fs_close(&manager, fd).unwrap();
+        assert!(syscalls::sys_fs_stat(&vfs, "/dev_hdd0/missing.bin").is_err());
+
+        let report = vfs.tracer().report("TEST00000");
+        assert_eq!(report.entries.len(), 2);
+        assert!(report.entries[0].hit);
+        assert_eq!(report.miss_count(), 1);
+        assert_eq!(report.misses().next().unwrap().virtual_path, "/dev_hdd0/missing.bin");
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
     #[test]
     fn test_vfs_integration() {
         let manager = ObjectManager::new();
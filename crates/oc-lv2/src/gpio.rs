@@ -0,0 +1,68 @@
+//! General purpose I/O (sys_gpio_*)
+//!
+//! Retail consoles expose the front panel LEDs and the (unpopulated) DIP
+//! switches. LED writes are kept so reads return them; the DIP switches read
+//! as all off.
+
+use oc_core::error::KernelError;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// GPIO device driving the LEDs
+pub const SYS_GPIO_LED_DEVICE_ID: u64 = 0;
+
+/// GPIO device reading the DIP switches
+pub const SYS_GPIO_DIP_SWITCH_DEVICE_ID: u64 = 1;
+
+/// GPIO devices of the console
+#[derive(Debug, Default)]
+pub struct Gpio {
+    /// Last value written to the LED device
+    led: AtomicU64,
+}
+
+impl Gpio {
+    /// Create the devices with every LED off
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the bits of `mask` on a device to those of `value`
+    pub fn set(&self, device_id: u64, mask: u64, value: u64) -> Result<(), KernelError> {
+        match device_id {
+            SYS_GPIO_LED_DEVICE_ID => {
+                let _ = self.led.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |led| {
+                    Some((led & !mask) | (value & mask))
+                });
+                Ok(())
+            }
+            // The DIP switches are read-only
+            _ => Err(KernelError::InvalidId(device_id as u32)),
+        }
+    }
+
+    /// Read a device
+    pub fn get(&self, device_id: u64) -> Result<u64, KernelError> {
+        match device_id {
+            SYS_GPIO_LED_DEVICE_ID => Ok(self.led.load(Ordering::Relaxed)),
+            SYS_GPIO_DIP_SWITCH_DEVICE_ID => Ok(0),
+            _ => Err(KernelError::InvalidId(device_id as u32)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gpio_devices() {
+        let gpio = Gpio::new();
+        gpio.set(SYS_GPIO_LED_DEVICE_ID, 0b0110, 0b1111).unwrap();
+        gpio.set(SYS_GPIO_LED_DEVICE_ID, 0b0010, 0).unwrap();
+        assert_eq!(gpio.get(SYS_GPIO_LED_DEVICE_ID).unwrap(), 0b0100);
+
+        assert_eq!(gpio.get(SYS_GPIO_DIP_SWITCH_DEVICE_ID).unwrap(), 0);
+        assert!(gpio.set(SYS_GPIO_DIP_SWITCH_DEVICE_ID, 1, 1).is_err());
+        assert!(gpio.get(7).is_err());
+    }
+}
@@ -0,0 +1,490 @@
+//! Interrupt tags and threads (sys_interrupt_*)
+//!
+//! Each hardware interrupt source (an SPU's interrupt mailbox, the RSX) has
+//! an interrupt tag. A game binds a PPU thread it created for the purpose to
+//! a tag with sys_interrupt_thread_establish. When the source fires, the
+//! kernel runs the thread's entry with the establish argument, preempting
+//! lower priority threads, until the handler calls sys_interrupt_thread_eoi.
+//! Interrupts raised while the handler runs are counted and handled one
+//! after another.
+
+use crate::objects::{KernelObject, ObjectDebugInfo, ObjectId, ObjectManager, ObjectType};
+use crate::thread::{ThreadId, ThreadManager};
+use oc_core::error::KernelError;
+use oc_core::savestate::{invalid, StateReader, StateWriter};
+use parking_lot::Mutex;
+use std::io;
+use std::sync::Arc;
+
+/// SPU interrupt class 2 status bit of the outbound interrupt mailbox
+pub const SPU_INT2_STAT_MAILBOX_INT: u64 = 0x1;
+
+/// RSX interrupt status bit of a vblank
+pub const RSX_INT_STAT_VBLANK: u64 = 0x1;
+
+/// Hardware source raising an interrupt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterruptSource {
+    /// Interrupt class of an SPU
+    Spu { spu_id: u32, class: u32 },
+    /// RSX (vblank, flip and user interrupts)
+    Rsx,
+}
+
+impl InterruptSource {
+    fn save_state(&self, w: &mut StateWriter) {
+        match *self {
+            Self::Spu { spu_id, class } => {
+                w.u8(0);
+                w.u32(spu_id);
+                w.u32(class);
+            }
+            Self::Rsx => w.u8(1),
+        }
+    }
+
+    fn load_state(r: &mut StateReader) -> io::Result<Self> {
+        match r.u8()? {
+            0 => Ok(Self::Spu { spu_id: r.u32()?, class: r.u32()? }),
+            1 => Ok(Self::Rsx),
+            value => Err(invalid(&format!("invalid interrupt source {}", value))),
+        }
+    }
+}
+
+/// Interrupt tag of one source
+pub struct InterruptTag {
+    id: ObjectId,
+    source: InterruptSource,
+    state: Mutex<InterruptTagState>,
+}
+
+#[derive(Debug, Default)]
+struct InterruptTagState {
+    /// Interrupt thread established on the tag
+    handler: Option<ObjectId>,
+    /// Status bits raised and not yet cleared
+    status: u64,
+    /// Value taken from the SPU's outbound interrupt mailbox, until read
+    mailbox: Option<u32>,
+}
+
+impl InterruptTag {
+    pub fn new(id: ObjectId, source: InterruptSource) -> Self {
+        Self {
+            id,
+            source,
+            state: Mutex::new(InterruptTagState::default()),
+        }
+    }
+
+    pub fn source(&self) -> InterruptSource {
+        self.source
+    }
+
+    /// Interrupt thread established on the tag
+    pub fn handler(&self) -> Option<ObjectId> {
+        self.state.lock().handler
+    }
+
+    /// Status bits raised and not yet cleared
+    pub fn status(&self) -> u64 {
+        self.state.lock().status
+    }
+
+    /// Clear the status bits of `mask`
+    pub fn clear_status(&self, mask: u64) {
+        self.state.lock().status &= !mask;
+    }
+
+    /// Read the mailbox value of the last interrupt
+    pub fn read_mailbox(&self) -> Option<u32> {
+        self.state.lock().mailbox.take()
+    }
+
+    /// Recreate a tag written by [`KernelObject::save_state`]
+    pub(crate) fn load_state(id: ObjectId, r: &mut StateReader) -> io::Result<Self> {
+        let tag = Self::new(id, InterruptSource::load_state(r)?);
+        {
+            let mut state = tag.state.lock();
+            state.handler = r.option(|r| r.u32())?;
+            state.status = r.u64()?;
+            state.mailbox = r.option(|r| r.u32())?;
+        }
+        Ok(tag)
+    }
+}
+
+impl KernelObject for InterruptTag {
+    fn object_type(&self) -> ObjectType {
+        ObjectType::InterruptTag
+    }
+
+    fn id(&self) -> ObjectId {
+        self.id
+    }
+
+    fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync> {
+        self
+    }
+
+    fn debug_info(&self) -> ObjectDebugInfo {
+        let state = self.state.lock();
+        let info = ObjectDebugInfo::new(self.id, ObjectType::InterruptTag)
+            .detail("source", format!("{:?}", self.source))
+            .detail("status", format!("0x{:x}", state.status));
+        match state.handler {
+            Some(handler) => info.detail("handler", handler),
+            None => info,
+        }
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        self.source.save_state(w);
+        let state = self.state.lock();
+        w.option(state.handler, |w, handler| w.u32(handler));
+        w.u64(state.status);
+        w.option(state.mailbox, |w, value| w.u32(value));
+    }
+}
+
+/// PPU thread established as the handler of an interrupt tag
+pub struct InterruptThread {
+    id: ObjectId,
+    tag_id: ObjectId,
+    thread_id: ThreadId,
+    /// Handler function descriptor (OPD) the thread was created with
+    entry: u64,
+    arg: u64,
+    priority: u32,
+    stack_size: usize,
+    state: Mutex<InterruptThreadState>,
+}
+
+#[derive(Debug, Default)]
+struct InterruptThreadState {
+    /// Interrupts raised and not yet handled
+    pending: u32,
+    /// Whether the handler is running, until it calls eoi
+    running: bool,
+}
+
+impl InterruptThread {
+    /// Thread the handler runs on
+    pub fn thread_id(&self) -> ThreadId {
+        self.thread_id
+    }
+
+    /// Tag the thread handles
+    pub fn tag_id(&self) -> ObjectId {
+        self.tag_id
+    }
+
+    /// Interrupts raised and not yet handled
+    pub fn pending(&self) -> u32 {
+        self.state.lock().pending
+    }
+
+    /// Whether the handler is running
+    pub fn is_running(&self) -> bool {
+        self.state.lock().running
+    }
+
+    /// Recreate an interrupt thread written by [`KernelObject::save_state`]
+    pub(crate) fn load_state(id: ObjectId, r: &mut StateReader) -> io::Result<Self> {
+        let thread = Self {
+            id,
+            tag_id: r.u32()?,
+            thread_id: r.u64()?,
+            entry: r.u64()?,
+            arg: r.u64()?,
+            priority: r.u32()?,
+            stack_size: r.u64()? as usize,
+            state: Mutex::new(InterruptThreadState::default()),
+        };
+        {
+            let mut state = thread.state.lock();
+            state.pending = r.u32()?;
+            state.running = r.bool()?;
+        }
+        Ok(thread)
+    }
+}
+
+impl KernelObject for InterruptThread {
+    fn object_type(&self) -> ObjectType {
+        ObjectType::InterruptThread
+    }
+
+    fn id(&self) -> ObjectId {
+        self.id
+    }
+
+    fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync> {
+        self
+    }
+
+    fn debug_info(&self) -> ObjectDebugInfo {
+        let state = self.state.lock();
+        ObjectDebugInfo::new(self.id, ObjectType::InterruptThread)
+            .detail("tag", self.tag_id)
+            .detail("thread", self.thread_id)
+            .detail("pending", state.pending)
+            .detail("running", state.running)
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u32(self.tag_id);
+        w.u64(self.thread_id);
+        w.u64(self.entry);
+        w.u64(self.arg);
+        w.u32(self.priority);
+        w.u64(self.stack_size as u64);
+        let state = self.state.lock();
+        w.u32(state.pending);
+        w.bool(state.running);
+    }
+}
+
+/// Handler call the kernel has to run on an interrupt thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterruptDispatch {
+    /// Interrupt thread handle
+    pub handle: ObjectId,
+    pub thread_id: ThreadId,
+    /// Handler function descriptor (OPD)
+    pub entry: u64,
+    pub arg: u64,
+    pub priority: u32,
+    pub stack_size: usize,
+}
+
+fn objects_of<T: KernelObject + 'static>(manager: &ObjectManager, object_type: ObjectType) -> Vec<Arc<T>> {
+    let mut objects: Vec<Arc<T>> = manager
+        .list()
+        .into_iter()
+        .filter(|object| object.object_type() == object_type)
+        .filter_map(|object| object.as_any().downcast::<T>().ok())
+        .collect();
+    objects.sort_by_key(|object| object.id());
+    objects
+}
+
+/// Tag of `source`, if one was created
+pub fn find_tag(manager: &ObjectManager, source: InterruptSource) -> Option<Arc<InterruptTag>> {
+    objects_of::<InterruptTag>(manager, ObjectType::InterruptTag)
+        .into_iter()
+        .find(|tag| tag.source == source)
+}
+
+/// Tag of `source`, creating it on first use
+pub fn create_tag(manager: &ObjectManager, source: InterruptSource) -> ObjectId {
+    if let Some(tag) = find_tag(manager, source) {
+        return tag.id;
+    }
+    let id = manager.next_id();
+    manager.register(Arc::new(InterruptTag::new(id, source)));
+    tracing::debug!("Created interrupt tag {} for {:?}", id, source);
+    id
+}
+
+/// Raise `status` on the tag of `source`, with the SPU mailbox value if any
+///
+/// Returns whether an interrupt thread is established to handle it.
+pub fn raise(manager: &ObjectManager, source: InterruptSource, status: u64, mailbox: Option<u32>) -> bool {
+    let Some(tag) = find_tag(manager, source) else {
+        return false;
+    };
+    let handler = {
+        let mut state = tag.state.lock();
+        state.status |= status;
+        if mailbox.is_some() {
+            state.mailbox = mailbox;
+        }
+        state.handler
+    };
+    let Some(thread) = handler.and_then(|handle| manager.get::<InterruptThread>(handle).ok()) else {
+        return false;
+    };
+    thread.state.lock().pending += 1;
+    true
+}
+
+/// Start the interrupt threads with pending interrupts that are not running
+///
+/// Each returned handler call runs until the thread calls
+/// sys_interrupt_thread_eoi.
+pub fn take_dispatches(manager: &ObjectManager) -> Vec<InterruptDispatch> {
+    objects_of::<InterruptThread>(manager, ObjectType::InterruptThread)
+        .into_iter()
+        .filter(|thread| {
+            let mut state = thread.state.lock();
+            if state.running || state.pending == 0 {
+                return false;
+            }
+            state.pending -= 1;
+            state.running = true;
+            true
+        })
+        .map(|thread| InterruptDispatch {
+            handle: thread.id,
+            thread_id: thread.thread_id,
+            entry: thread.entry,
+            arg: thread.arg,
+            priority: thread.priority,
+            stack_size: thread.stack_size,
+        })
+        .collect()
+}
+
+/// Whether [`take_dispatches`] has a handler call to return
+pub fn has_dispatches(manager: &ObjectManager) -> bool {
+    objects_of::<InterruptThread>(manager, ObjectType::InterruptThread)
+        .iter()
+        .any(|thread| {
+            let state = thread.state.lock();
+            !state.running && state.pending > 0
+        })
+}
+
+/// Interrupt syscall implementations
+pub mod syscalls {
+    use super::*;
+
+    /// sys_interrupt_tag_destroy
+    pub fn sys_interrupt_tag_destroy(manager: &ObjectManager, tag_id: ObjectId) -> Result<(), KernelError> {
+        let tag: Arc<InterruptTag> = manager.get(tag_id)?;
+        if tag.handler().is_some() {
+            return Err(KernelError::WouldBlock);
+        }
+        manager.unregister(tag_id)
+    }
+
+    /// sys_interrupt_thread_establish
+    ///
+    /// `thread_id` must be a PPU thread created for interrupts; its entry and
+    /// priority are those of the handler.
+    pub fn sys_interrupt_thread_establish(
+        manager: &ObjectManager,
+        threads: &ThreadManager,
+        tag_id: ObjectId,
+        thread_id: ThreadId,
+        arg: u64,
+    ) -> Result<ObjectId, KernelError> {
+        let tag: Arc<InterruptTag> = manager.get(tag_id)?;
+        let thread = threads.get(thread_id)?.debug_info();
+        let established = objects_of::<InterruptThread>(manager, ObjectType::InterruptThread);
+        if established.iter().any(|handler| handler.thread_id == thread_id) {
+            return Err(KernelError::WouldBlock);
+        }
+
+        let mut state = tag.state.lock();
+        if state.handler.is_some() {
+            return Err(KernelError::WouldBlock);
+        }
+        let id = manager.next_id();
+        manager.register(Arc::new(InterruptThread {
+            id,
+            tag_id,
+            thread_id,
+            entry: thread.entry_point,
+            arg,
+            priority: thread.priority,
+            stack_size: thread.stack_size,
+            state: Mutex::new(InterruptThreadState::default()),
+        }));
+        state.handler = Some(id);
+        tracing::debug!("Established thread {} on interrupt tag {} as {}", thread_id, tag_id, id);
+        Ok(id)
+    }
+
+    /// sys_interrupt_thread_disestablish
+    pub fn sys_interrupt_thread_disestablish(manager: &ObjectManager, handle: ObjectId) -> Result<(), KernelError> {
+        let thread: Arc<InterruptThread> = manager.get(handle)?;
+        if let Ok(tag) = manager.get::<InterruptTag>(thread.tag_id) {
+            tag.state.lock().handler = None;
+        }
+        manager.unregister(handle)
+    }
+
+    /// sys_interrupt_thread_eoi
+    ///
+    /// Ends the handler running on `thread_id`; the next pending interrupt
+    /// is dispatched again by [`take_dispatches`].
+    pub fn sys_interrupt_thread_eoi(manager: &ObjectManager, thread_id: ThreadId) -> Result<(), KernelError> {
+        let thread = objects_of::<InterruptThread>(manager, ObjectType::InterruptThread)
+            .into_iter()
+            .find(|handler| handler.thread_id == thread_id)
+            .ok_or(KernelError::PermissionDenied)?;
+        let mut state = thread.state.lock();
+        if !state.running {
+            return Err(KernelError::PermissionDenied);
+        }
+        state.running = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thread::syscalls::sys_ppu_thread_create;
+
+    #[test]
+    fn test_interrupt_dispatch() {
+        let manager = ObjectManager::new();
+        let threads = ThreadManager::new();
+        let source = InterruptSource::Spu { spu_id: 0, class: 2 };
+        assert!(!raise(&manager, source, SPU_INT2_STAT_MAILBOX_INT, None));
+
+        let tag_id = create_tag(&manager, source);
+        assert_eq!(create_tag(&manager, source), tag_id);
+        assert_ne!(create_tag(&manager, InterruptSource::Rsx), tag_id);
+
+        let thread_id = sys_ppu_thread_create(&threads, 0x10_0000, 0, 100, 0x4000, 0, "intr").unwrap();
+        let handle = syscalls::sys_interrupt_thread_establish(&manager, &threads, tag_id, thread_id, 0x1234).unwrap();
+        assert!(syscalls::sys_interrupt_thread_establish(&manager, &threads, tag_id, thread_id, 0).is_err());
+        assert!(syscalls::sys_interrupt_tag_destroy(&manager, tag_id).is_err());
+
+        // Two interrupts run the handler twice, one after the other
+        assert!(raise(&manager, source, SPU_INT2_STAT_MAILBOX_INT, Some(7)));
+        assert!(raise(&manager, source, SPU_INT2_STAT_MAILBOX_INT, None));
+        let dispatches = take_dispatches(&manager);
+        assert_eq!(
+            dispatches,
+            [InterruptDispatch {
+                handle,
+                thread_id,
+                entry: 0x10_0000,
+                arg: 0x1234,
+                priority: 100,
+                stack_size: 0x4000,
+            }]
+        );
+        assert!(take_dispatches(&manager).is_empty());
+        assert!(!has_dispatches(&manager));
+
+        let tag: Arc<InterruptTag> = manager.get(tag_id).unwrap();
+        assert_eq!(tag.status(), SPU_INT2_STAT_MAILBOX_INT);
+        assert_eq!(tag.read_mailbox(), Some(7));
+        tag.clear_status(SPU_INT2_STAT_MAILBOX_INT);
+
+        syscalls::sys_interrupt_thread_eoi(&manager, thread_id).unwrap();
+        assert!(syscalls::sys_interrupt_thread_eoi(&manager, thread_id).is_err());
+        assert!(has_dispatches(&manager));
+        assert_eq!(take_dispatches(&manager).len(), 1);
+
+        // Handlers being run carry over through a savestate
+        let thread: Arc<InterruptThread> = manager.get(handle).unwrap();
+        let mut w = StateWriter::new();
+        thread.save_state(&mut w);
+        let data = w.into_bytes();
+        let restored = InterruptThread::load_state(handle, &mut StateReader::new(&data)).unwrap();
+        assert!(restored.is_running());
+        assert_eq!((restored.thread_id(), restored.tag_id()), (thread_id, tag_id));
+
+        syscalls::sys_interrupt_thread_disestablish(&manager, handle).unwrap();
+        assert!(!raise(&manager, source, 0, None));
+        syscalls::sys_interrupt_tag_destroy(&manager, tag_id).unwrap();
+    }
+}
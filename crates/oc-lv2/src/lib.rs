@@ -3,18 +3,24 @@
 //! LV2 is the PS3's hypervisor/kernel. This crate implements
 //! high-level emulation of LV2 system calls.
 
+pub mod bluetooth;
+pub mod config;
 pub mod fs;
+pub mod gpio;
+pub mod interrupt;
 pub mod memory;
 pub mod objects;
 pub mod process;
 pub mod prx;
 pub mod spu;
+pub mod ss;
 pub mod sync;
 pub mod syscall;
 pub mod syscall_numbers;
 pub mod thread;
 pub mod time;
 pub mod timer;
+pub mod usbd;
 
 pub use objects::ObjectManager;
 pub use process::ProcessManager;
@@ -1,8 +1,11 @@
 //! Memory management (sys_memory_*)
 
 use oc_core::error::KernelError;
+use oc_core::savestate::{StateReader, StateWriter};
+use oc_memory::BeValue;
 use parking_lot::Mutex;
 use std::collections::HashMap;
+use std::io;
 
 /// Memory container ID
 pub type ContainerId = u32;
@@ -10,6 +13,20 @@ pub type ContainerId = u32;
 /// Memory page size (64KB, typical for PS3)
 pub const PAGE_SIZE: usize = 0x10000;
 
+/// User memory available to a game process (213 MB on retail firmware)
+pub const USER_MEMORY_SIZE: usize = 0x0D50_0000;
+
+/// Container ID used for sys_mmapper allocations
+pub const MMAPPER_CONTAINER: ContainerId = 1;
+
+/// sys_memory_info_t
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BeValue)]
+#[repr(C)]
+pub struct SysMemoryInfo {
+    pub total_user_memory: u32,
+    pub available_user_memory: u32,
+}
+
 /// Memory page attributes
 #[derive(Debug, Clone, Copy)]
 pub struct PageAttribute {
@@ -32,7 +49,7 @@ pub mod flags {
 struct MemoryAllocation {
     addr: u64,
     size: usize,
-    _container_id: ContainerId,
+    container_id: ContainerId,
     flags: u64,
 }
 
@@ -40,16 +57,49 @@ struct MemoryAllocation {
 pub struct MemoryManager {
     allocations: Mutex<HashMap<u64, MemoryAllocation>>,
     next_addr: Mutex<u64>,
+    /// User memory budget in bytes
+    user_memory_size: usize,
 }
 
 impl MemoryManager {
     pub fn new() -> Self {
+        Self::with_user_memory_size(USER_MEMORY_SIZE)
+    }
+
+    /// Create a memory manager with a custom user memory budget
+    pub fn with_user_memory_size(user_memory_size: usize) -> Self {
         Self {
             allocations: Mutex::new(HashMap::new()),
             next_addr: Mutex::new(0x3000_0000), // Start of user memory region
+            user_memory_size,
         }
     }
 
+    /// Total user memory budget in bytes
+    pub fn user_memory_size(&self) -> usize {
+        self.user_memory_size
+    }
+
+    /// Bytes currently allocated (sys_memory and sys_mmapper)
+    pub fn used(&self) -> usize {
+        self.allocations.lock().values().map(|a| a.size).sum()
+    }
+
+    /// Bytes currently allocated from a container
+    pub fn used_by_container(&self, container_id: ContainerId) -> usize {
+        self.allocations
+            .lock()
+            .values()
+            .filter(|a| a.container_id == container_id)
+            .map(|a| a.size)
+            .sum()
+    }
+
+    /// Bytes still available to the game
+    pub fn available(&self) -> usize {
+        self.user_memory_size.saturating_sub(self.used())
+    }
+
     /// Allocate memory
     pub fn allocate(
         &self,
@@ -61,6 +111,16 @@ impl MemoryManager {
         // Align size to page boundary
         let aligned_size = (size + page_size - 1) & !(page_size - 1);
 
+        // Fail like the real kernel (ENOMEM) once the budget is used up
+        if aligned_size > self.available() {
+            tracing::warn!(
+                "Allocation of {} bytes exceeds available user memory ({} bytes)",
+                aligned_size,
+                self.available()
+            );
+            return Err(KernelError::ResourceLimit);
+        }
+
         let mut next_addr = self.next_addr.lock();
         let addr = *next_addr;
         *next_addr += aligned_size as u64;
@@ -68,7 +128,7 @@ impl MemoryManager {
         let allocation = MemoryAllocation {
             addr,
             size: aligned_size,
-            _container_id: container_id,
+            container_id,
             flags,
         };
 
@@ -119,6 +179,38 @@ impl MemoryManager {
         let allocations = self.allocations.lock();
         allocations.get(&addr).map(|a| (a.addr, a.size))
     }
+
+    /// Save the allocation table; the memory itself is saved with guest memory
+    pub fn save_state(&self, w: &mut StateWriter) {
+        let mut allocations: Vec<MemoryAllocation> = self.allocations.lock().values().cloned().collect();
+        allocations.sort_by_key(|allocation| allocation.addr);
+        w.u64(*self.next_addr.lock());
+        w.count(allocations.len());
+        for allocation in allocations {
+            w.u64(allocation.addr);
+            w.u64(allocation.size as u64);
+            w.u32(allocation.container_id);
+            w.u64(allocation.flags);
+        }
+    }
+
+    /// Replace the allocation table with one written by [`MemoryManager::save_state`]
+    pub fn load_state(&self, r: &mut StateReader) -> io::Result<()> {
+        let next_addr = r.u64()?;
+        let mut allocations = HashMap::new();
+        for _ in 0..r.count()? {
+            let allocation = MemoryAllocation {
+                addr: r.u64()?,
+                size: r.u64()? as usize,
+                container_id: r.u32()?,
+                flags: r.u64()?,
+            };
+            allocations.insert(allocation.addr, allocation);
+        }
+        *self.next_addr.lock() = next_addr;
+        *self.allocations.lock() = allocations;
+        Ok(())
+    }
 }
 
 impl Default for MemoryManager {
@@ -175,10 +267,11 @@ pub mod syscalls {
     }
 
     /// sys_memory_get_user_memory_size
-    pub fn sys_memory_get_user_memory_size() -> (usize, usize) {
-        // Return (total_user_memory, available_user_memory)
-        // PS3 typically has 256MB total, with varying amounts available
-        (256 * 1024 * 1024, 200 * 1024 * 1024)
+    ///
+    /// Returns (total_user_memory, available_user_memory), counting both
+    /// sys_memory and sys_mmapper allocations against the budget.
+    pub fn sys_memory_get_user_memory_size(manager: &MemoryManager) -> (usize, usize) {
+        (manager.user_memory_size(), manager.available())
     }
 
     /// sys_mmapper_allocate_memory
@@ -195,8 +288,8 @@ pub mod syscalls {
             return Err(KernelError::ResourceLimit);
         }
 
-        // Use container ID 1 for mmapper allocations to distinguish them
-        manager.allocate(size, page_size, flags, 1)
+        // Use a dedicated container ID for mmapper allocations to distinguish them
+        manager.allocate(size, page_size, flags, MMAPPER_CONTAINER)
     }
 
     /// sys_mmapper_map_memory
@@ -318,10 +411,25 @@ mod tests {
 
     #[test]
     fn test_memory_user_size() {
-        let (total, available) = syscalls::sys_memory_get_user_memory_size();
-        assert_eq!(total, 256 * 1024 * 1024);
-        assert!(available > 0);
-        assert!(available <= total);
+        let manager = MemoryManager::new();
+        let (total, available) = syscalls::sys_memory_get_user_memory_size(&manager);
+        assert_eq!(total, USER_MEMORY_SIZE);
+        assert_eq!(available, total);
+
+        // Both sys_memory and sys_mmapper allocations reduce free memory
+        syscalls::sys_memory_allocate(&manager, 0x10000, PAGE_SIZE, 0).unwrap();
+        syscalls::sys_mmapper_allocate_memory(&manager, 0x20000, PAGE_SIZE, 0).unwrap();
+        let (_, available) = syscalls::sys_memory_get_user_memory_size(&manager);
+        assert_eq!(available, total - 0x30000);
+        assert_eq!(manager.used_by_container(MMAPPER_CONTAINER), 0x20000);
+    }
+
+    #[test]
+    fn test_memory_budget_exhausted() {
+        let manager = MemoryManager::with_user_memory_size(0x20000);
+        syscalls::sys_memory_allocate(&manager, 0x10000, PAGE_SIZE, 0).unwrap();
+        assert!(syscalls::sys_memory_allocate(&manager, 0x20000, PAGE_SIZE, 0).is_err());
+        assert_eq!(manager.available(), 0x10000);
     }
 
     #[test]
@@ -1,8 +1,11 @@
 //! Kernel object lifetime management
 
 use oc_core::error::KernelError;
+use oc_core::savestate::{invalid, StateReader, StateWriter};
+use oc_core::time_base::TimeBase;
 use parking_lot::RwLock;
 use std::collections::HashMap;
+use std::io;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 
@@ -26,6 +29,87 @@ pub enum ObjectType {
     File,
     Directory,
     PrxModule,
+    Config,
+    InterruptTag,
+    InterruptThread,
+}
+
+impl ObjectType {
+    /// Every object type, in savestate tag order
+    pub const ALL: [ObjectType; 17] = [
+        Self::Mutex,
+        Self::Cond,
+        Self::RwLock,
+        Self::Semaphore,
+        Self::EventQueue,
+        Self::EventPort,
+        Self::EventFlag,
+        Self::Barrier,
+        Self::Timer,
+        Self::SpuThreadGroup,
+        Self::SpuThread,
+        Self::File,
+        Self::Directory,
+        Self::PrxModule,
+        Self::Config,
+        Self::InterruptTag,
+        Self::InterruptThread,
+    ];
+
+    /// Display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Mutex => "Mutex",
+            Self::Cond => "Condition Variable",
+            Self::RwLock => "RW Lock",
+            Self::Semaphore => "Semaphore",
+            Self::EventQueue => "Event Queue",
+            Self::EventPort => "Event Port",
+            Self::EventFlag => "Event Flag",
+            Self::Barrier => "Barrier",
+            Self::Timer => "Timer",
+            Self::SpuThreadGroup => "SPU Thread Group",
+            Self::SpuThread => "SPU Thread",
+            Self::File => "File",
+            Self::Directory => "Directory",
+            Self::PrxModule => "PRX Module",
+            Self::Config => "Config",
+            Self::InterruptTag => "Interrupt Tag",
+            Self::InterruptThread => "Interrupt Thread",
+        }
+    }
+}
+
+/// Debugger view of a kernel object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectDebugInfo {
+    pub id: ObjectId,
+    pub object_type: ObjectType,
+    /// Thread holding the object (mutex owner, rwlock writer)
+    pub owner: Option<u64>,
+    /// Threads blocked on the object
+    pub waiters: Vec<u64>,
+    /// Type-specific state, one `key: value` line each
+    pub details: Vec<String>,
+}
+
+impl ObjectDebugInfo {
+    /// Info with no owner, waiters or details
+    pub fn new(id: ObjectId, object_type: ObjectType) -> Self {
+        Self {
+            id,
+            object_type,
+            owner: None,
+            waiters: Vec::new(),
+            details: Vec::new(),
+        }
+    }
+
+    /// Add a detail line
+    pub fn detail(mut self, key: &str, value: impl std::fmt::Display) -> Self {
+        self.details.push(format!("{}: {}", key, value));
+        self
+    }
 }
 
 /// Trait for kernel objects
@@ -35,12 +119,47 @@ pub trait KernelObject: Send + Sync + std::any::Any {
     
     /// Helper for downcasting
     fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync>;
+
+    /// State shown by the kernel object inspector
+    fn debug_info(&self) -> ObjectDebugInfo {
+        ObjectDebugInfo::new(self.id(), self.object_type())
+    }
+
+    /// Write the state the object is recreated from when a savestate loads
+    fn save_state(&self, w: &mut StateWriter);
+}
+
+/// Recreate an object written by [`KernelObject::save_state`]
+fn load_object(object_type: ObjectType, id: ObjectId, r: &mut StateReader, time_base: &TimeBase) -> io::Result<Arc<dyn KernelObject>> {
+    use crate::sync::{barrier, cond, event, event_flag, mutex, rwlock, semaphore};
+
+    Ok(match object_type {
+        ObjectType::Mutex => Arc::new(mutex::Mutex::load_state(id, r)?),
+        ObjectType::Cond => Arc::new(cond::Cond::load_state(id, r)?),
+        ObjectType::RwLock => Arc::new(rwlock::RwLock::load_state(id, r)?),
+        ObjectType::Semaphore => Arc::new(semaphore::Semaphore::load_state(id, r)?),
+        ObjectType::EventQueue => Arc::new(event::EventQueue::load_state(id, r)?),
+        ObjectType::EventPort => Arc::new(event::EventPort::load_state(id, r)?),
+        ObjectType::EventFlag => Arc::new(event_flag::EventFlag::load_state(id, r)?),
+        ObjectType::Barrier => Arc::new(barrier::Barrier::load_state(id, r)?),
+        ObjectType::Timer => Arc::new(crate::timer::Timer::load_state(id, r, time_base.clone())?),
+        ObjectType::SpuThreadGroup => Arc::new(crate::spu::SpuThreadGroup::load_state(id, r)?),
+        ObjectType::SpuThread => Arc::new(crate::spu::SpuThread::load_state(id, r)?),
+        ObjectType::File => Arc::new(crate::fs::FileDescriptor::load_state(id, r)?),
+        ObjectType::Directory => Arc::new(crate::fs::DirectoryDescriptor::load_state(id, r)?),
+        ObjectType::PrxModule => Arc::new(crate::prx::PrxModule::load_state(id, r)?),
+        ObjectType::Config => Arc::new(crate::config::SysConfig::load_state(id, r)?),
+        ObjectType::InterruptTag => Arc::new(crate::interrupt::InterruptTag::load_state(id, r)?),
+        ObjectType::InterruptThread => Arc::new(crate::interrupt::InterruptThread::load_state(id, r)?),
+    })
 }
 
 /// Object manager for tracking kernel objects
 pub struct ObjectManager {
     next_id: AtomicU32,
     objects: RwLock<HashMap<ObjectId, Arc<dyn KernelObject>>>,
+    /// Emulated clock timers run on
+    time_base: TimeBase,
 }
 
 impl ObjectManager {
@@ -49,9 +168,15 @@ impl ObjectManager {
         Self {
             next_id: AtomicU32::new(1), // Start IDs from 1 (0 is invalid)
             objects: RwLock::new(HashMap::new()),
+            time_base: TimeBase::new(),
         }
     }
 
+    /// Emulated clock timers and time syscalls read
+    pub fn time_base(&self) -> &TimeBase {
+        &self.time_base
+    }
+
     /// Generate a new unique object ID
     pub fn next_id(&self) -> ObjectId {
         self.next_id.fetch_add(1, Ordering::Relaxed)
@@ -107,6 +232,53 @@ impl ObjectManager {
     pub fn list(&self) -> Vec<Arc<dyn KernelObject>> {
         self.objects.read().values().cloned().collect()
     }
+
+    /// Debug info of every live object, ordered by ID
+    pub fn debug_snapshot(&self) -> Vec<ObjectDebugInfo> {
+        // Query the objects without holding the table lock, they take their own
+        let mut objects = self.list();
+        objects.sort_by_key(|object| object.id());
+        objects.iter().map(|object| object.debug_info()).collect()
+    }
+
+    /// Save every object and the next ID to hand out
+    pub fn save_state(&self, w: &mut StateWriter) {
+        let mut objects = self.list();
+        objects.sort_by_key(|object| object.id());
+        w.u32(self.next_id.load(Ordering::Relaxed));
+        w.count(objects.len());
+        for object in objects {
+            let tag = ObjectType::ALL.iter().position(|&t| t == object.object_type()).unwrap_or_default();
+            w.u32(object.id());
+            w.u8(tag as u8);
+            // Each object is length-prefixed so a bad one is reported rather than misread
+            let mut state = StateWriter::new();
+            object.save_state(&mut state);
+            w.bytes(&state.into_bytes());
+        }
+    }
+
+    /// Replace all objects with those written by [`ObjectManager::save_state`]
+    pub fn load_state(&self, r: &mut StateReader) -> io::Result<()> {
+        let next_id = r.u32()?;
+        let count = r.count()?;
+        let mut objects: HashMap<ObjectId, Arc<dyn KernelObject>> = HashMap::with_capacity(count);
+        for _ in 0..count {
+            let id = r.u32()?;
+            let tag = r.u8()?;
+            let object_type = *ObjectType::ALL
+                .get(tag as usize)
+                .ok_or_else(|| invalid(&format!("invalid kernel object type {}", tag)))?;
+            let mut state = StateReader::new(r.bytes()?);
+            let object = load_object(object_type, id, &mut state, &self.time_base).map_err(|e| {
+                invalid(&format!("{} {}: {}", object_type.name(), id, e))
+            })?;
+            objects.insert(id, object);
+        }
+        self.next_id.store(next_id, Ordering::Relaxed);
+        *self.objects.write() = objects;
+        Ok(())
+    }
 }
 
 impl Default for ObjectManager {
@@ -136,6 +308,8 @@ mod tests {
         fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync> {
             self
         }
+
+        fn save_state(&self, _w: &mut StateWriter) {}
     }
 
     #[test]
@@ -181,4 +355,33 @@ mod tests {
         assert_eq!(manager.count_by_type(ObjectType::Mutex), 1);
         assert_eq!(manager.count_by_type(ObjectType::Cond), 1);
     }
+
+    #[test]
+    fn test_debug_snapshot() {
+        use crate::sync::event::{self, Event, EventQueue, EventQueueAttributes};
+        use crate::sync::mutex::{self, MutexAttributes};
+
+        let manager = ObjectManager::new();
+        let mutex_id = mutex::syscalls::sys_mutex_create(&manager, MutexAttributes::default()).unwrap();
+        mutex::syscalls::sys_mutex_lock(&manager, mutex_id, 7).unwrap();
+        let queue_id =
+            event::syscalls::sys_event_queue_create(&manager, EventQueueAttributes::default(), 4).unwrap();
+        let queue: Arc<EventQueue> = manager.get(queue_id).unwrap();
+        assert!(queue.receive_with_wait(9, None).is_err());
+        assert!(queue.receive_with_wait(10, None).is_err());
+        // Wakes thread 9, thread 10 keeps waiting
+        queue.send(Event { source: 1, data1: 2, data2: 3, data3: 4 }).unwrap();
+
+        let snapshot = manager.debug_snapshot();
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].object_type, ObjectType::Mutex);
+        assert_eq!(snapshot[0].owner, Some(7));
+        assert!(snapshot[0].details.contains(&"lock count: 1".to_string()));
+        assert_eq!(snapshot[1].id, queue_id);
+        assert_eq!(snapshot[1].waiters, [10]);
+        assert_eq!(
+            snapshot[1].details,
+            ["pending: 1/4", "event: source=0x1 data=0x2, 0x3, 0x4"]
+        );
+    }
 }
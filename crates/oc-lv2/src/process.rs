@@ -1,7 +1,9 @@
 //! Process management (sys_process_*)
 
 use oc_core::error::KernelError;
+use oc_core::savestate::{invalid, StateReader, StateWriter};
 use parking_lot::Mutex;
+use std::io;
 use std::sync::atomic::{AtomicU32, Ordering};
 
 /// Process ID type
@@ -25,6 +27,8 @@ pub struct ProcessInfo {
 struct ProcessInfoInner {
     state: ProcessState,
     sdk_version: u32,
+    /// Code passed to sys_process_exit
+    exit_code: i32,
     _parent_pid: ProcessId,
 }
 
@@ -35,6 +39,7 @@ impl ProcessInfo {
             inner: Mutex::new(ProcessInfoInner {
                 state: ProcessState::Running,
                 sdk_version,
+                exit_code: 0,
                 _parent_pid: 0,
             }),
         }
@@ -55,6 +60,13 @@ impl ProcessInfo {
     pub fn sdk_version(&self) -> u32 {
         self.inner.lock().sdk_version
     }
+
+    /// Terminate the process with `exit_code`
+    pub fn terminate(&self, exit_code: i32) {
+        let mut inner = self.inner.lock();
+        inner.state = ProcessState::Terminated;
+        inner.exit_code = exit_code;
+    }
 }
 
 /// Process manager
@@ -92,7 +104,7 @@ impl ProcessManager {
 
     pub fn exit(&self, exit_code: i32) -> Result<(), KernelError> {
         if let Some(process) = self.process.lock().as_ref() {
-            process.set_state(ProcessState::Terminated);
+            process.terminate(exit_code);
             tracing::info!("Process {} exited with code {}", process.pid(), exit_code);
         }
         Ok(())
@@ -105,6 +117,46 @@ impl ProcessManager {
             .map(|p| p.state())
             .unwrap_or(ProcessState::Terminated)
     }
+
+    /// Code the process exited with, if it has exited
+    pub fn exit_code(&self) -> Option<i32> {
+        let process = self.process.lock();
+        let inner = process.as_ref()?.inner.lock();
+        (inner.state == ProcessState::Terminated).then_some(inner.exit_code)
+    }
+
+    /// Save the current process
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.u32(self.current_pid());
+        w.option(self.process.lock().as_ref(), |w, process| {
+            w.u32(process.pid());
+            w.u8(match process.state() {
+                ProcessState::Running => 0,
+                ProcessState::Suspended => 1,
+                ProcessState::Terminated => 2,
+            });
+            w.u32(process.sdk_version());
+        });
+    }
+
+    /// Restore the process written by [`ProcessManager::save_state`]
+    pub fn load_state(&self, r: &mut StateReader) -> io::Result<()> {
+        let current_pid = r.u32()?;
+        let process = r.option(|r| {
+            let process = ProcessInfo::new(r.u32()?, 0);
+            process.set_state(match r.u8()? {
+                0 => ProcessState::Running,
+                1 => ProcessState::Suspended,
+                2 => ProcessState::Terminated,
+                value => return Err(invalid(&format!("invalid process state {}", value))),
+            });
+            process.inner.lock().sdk_version = r.u32()?;
+            Ok(process)
+        })?;
+        self.current_pid.store(current_pid, Ordering::Relaxed);
+        *self.process.lock() = process;
+        Ok(())
+    }
 }
 
 impl Default for ProcessManager {
@@ -218,8 +270,10 @@ mod tests {
         let pid = syscalls::sys_process_getpid(&manager);
         assert_eq!(manager.get_state(), ProcessState::Running);
 
-        syscalls::sys_process_exit(&manager, 0).unwrap();
+        assert_eq!(manager.exit_code(), None);
+        syscalls::sys_process_exit(&manager, 3).unwrap();
         assert_eq!(manager.get_state(), ProcessState::Terminated);
+        assert_eq!(manager.exit_code(), Some(3));
     }
 
     #[test]
@@ -2,7 +2,9 @@
 
 use crate::objects::{KernelObject, ObjectId, ObjectManager, ObjectType};
 use oc_core::error::KernelError;
+use oc_core::savestate::{invalid, StateReader, StateWriter};
 use parking_lot::Mutex;
+use std::io;
 use std::sync::Arc;
 
 /// PRX module states
@@ -25,7 +27,7 @@ struct PrxModuleInner {
     path: String,
     state: PrxModuleState,
     entry_point: u64,
-    _size: usize,
+    size: usize,
     info: PrxInfo,
     exports: Vec<PrxSymbol>,
     imports: Vec<PrxSymbol>,
@@ -73,7 +75,7 @@ impl PrxModule {
                 path,
                 state: PrxModuleState::Loaded,
                 entry_point,
-                _size: size,
+                size,
                 info: PrxInfo::default(),
                 exports: Vec::new(),
                 imports: Vec::new(),
@@ -150,6 +152,55 @@ impl PrxModule {
     pub fn entry_point(&self) -> u64 {
         self.inner.lock().entry_point
     }
+
+    /// Recreate a module written by [`KernelObject::save_state`]
+    pub(crate) fn load_state(id: ObjectId, r: &mut StateReader) -> io::Result<Self> {
+        let name = r.str()?;
+        let path = r.str()?;
+        let entry_point = r.u64()?;
+        let size = r.u64()? as usize;
+        let module = Self::new(id, name, path, entry_point, size);
+        {
+            let mut inner = module.inner.lock();
+            inner.state = match r.u8()? {
+                0 => PrxModuleState::Loaded,
+                1 => PrxModuleState::Started,
+                2 => PrxModuleState::Stopped,
+                value => return Err(invalid(&format!("invalid PRX module state {}", value))),
+            };
+            inner.info = PrxInfo {
+                version: r.u32()?,
+                sdk_version: r.u32()?,
+                attribute: r.u32()?,
+            };
+            inner.exports = Self::load_symbols(r)?;
+            inner.imports = Self::load_symbols(r)?;
+        }
+        Ok(module)
+    }
+
+    fn save_symbols(w: &mut StateWriter, symbols: &[PrxSymbol]) {
+        w.count(symbols.len());
+        for symbol in symbols {
+            w.str(&symbol.name);
+            w.u64(symbol.address);
+            w.u64(symbol.size as u64);
+            w.bool(symbol.symbol_type == PrxSymbolType::Data);
+        }
+    }
+
+    fn load_symbols(r: &mut StateReader) -> io::Result<Vec<PrxSymbol>> {
+        let mut symbols = Vec::new();
+        for _ in 0..r.count()? {
+            symbols.push(PrxSymbol {
+                name: r.str()?,
+                address: r.u64()?,
+                size: r.u64()? as usize,
+                symbol_type: if r.bool()? { PrxSymbolType::Data } else { PrxSymbolType::Function },
+            });
+        }
+        Ok(symbols)
+    }
 }
 
 impl KernelObject for PrxModule {
@@ -164,6 +215,24 @@ impl KernelObject for PrxModule {
     fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync> {
         self
     }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        let inner = self.inner.lock();
+        w.str(&inner.name);
+        w.str(&inner.path);
+        w.u64(inner.entry_point);
+        w.u64(inner.size as u64);
+        w.u8(match inner.state {
+            PrxModuleState::Loaded => 0,
+            PrxModuleState::Started => 1,
+            PrxModuleState::Stopped => 2,
+        });
+        w.u32(inner.info.version);
+        w.u32(inner.info.sdk_version);
+        w.u32(inner.info.attribute);
+        Self::save_symbols(w, &inner.exports);
+        Self::save_symbols(w, &inner.imports);
+    }
 }
 
 /// PRX syscall implementations
@@ -2,7 +2,9 @@
 
 use crate::objects::{KernelObject, ObjectId, ObjectManager, ObjectType};
 use oc_core::error::KernelError;
+use oc_core::savestate::{invalid, StateReader, StateWriter};
 use parking_lot::Mutex;
+use std::io;
 use std::sync::Arc;
 
 /// Maximum number of SPU threads per thread group
@@ -64,7 +66,7 @@ pub struct SpuSegment {
 pub struct SpuThreadGroup {
     id: ObjectId,
     inner: Mutex<SpuThreadGroupState>,
-    _attributes: SpuThreadGroupAttributes,
+    attributes: SpuThreadGroupAttributes,
 }
 
 #[derive(Debug)]
@@ -90,7 +92,7 @@ impl SpuThreadGroup {
                 threads: Vec::with_capacity(num_threads as usize),
                 status: SpuThreadGroupStatus::NotInitialized,
             }),
-            _attributes: attributes,
+            attributes,
         }
     }
 
@@ -119,6 +121,31 @@ impl SpuThreadGroup {
         tracing::debug!("Joined SPU thread group {}", self.id);
         Ok(())
     }
+
+    /// Recreate a thread group written by [`KernelObject::save_state`]
+    pub(crate) fn load_state(id: ObjectId, r: &mut StateReader) -> io::Result<Self> {
+        let attributes = SpuThreadGroupAttributes {
+            name: r.str()?,
+            priority: r.u32()?,
+            thread_type: r.u32()?,
+        };
+        let count = r.count()?;
+        let group = Self::new(id, attributes, count as u32);
+        {
+            let mut state = group.inner.lock();
+            for _ in 0..count {
+                state.threads.push(r.u32()?);
+            }
+            state.status = match r.u8()? {
+                0 => SpuThreadGroupStatus::NotInitialized,
+                1 => SpuThreadGroupStatus::Initialized,
+                2 => SpuThreadGroupStatus::Running,
+                3 => SpuThreadGroupStatus::Stopped,
+                value => return Err(invalid(&format!("invalid SPU thread group status {}", value))),
+            };
+        }
+        Ok(group)
+    }
 }
 
 impl KernelObject for SpuThreadGroup {
@@ -133,6 +160,23 @@ impl KernelObject for SpuThreadGroup {
     fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync> {
         self
     }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.str(&self.attributes.name);
+        w.u32(self.attributes.priority);
+        w.u32(self.attributes.thread_type);
+        let state = self.inner.lock();
+        w.count(state.threads.len());
+        for &thread in &state.threads {
+            w.u32(thread);
+        }
+        w.u8(match state.status {
+            SpuThreadGroupStatus::NotInitialized => 0,
+            SpuThreadGroupStatus::Initialized => 1,
+            SpuThreadGroupStatus::Running => 2,
+            SpuThreadGroupStatus::Stopped => 3,
+        });
+    }
 }
 
 /// SPU thread
@@ -140,7 +184,7 @@ pub struct SpuThread {
     id: ObjectId,
     group_id: ObjectId,
     inner: Mutex<SpuThreadState>,
-    _attributes: SpuThreadAttributes,
+    attributes: SpuThreadAttributes,
 }
 
 #[derive(Debug)]
@@ -181,7 +225,7 @@ impl SpuThread {
                     signal2: 0,
                 },
             }),
-            _attributes: attributes,
+            attributes,
         }
     }
 
@@ -245,6 +289,43 @@ impl SpuThread {
         
         Ok(state.local_storage[addr..addr + size].to_vec())
     }
+
+    /// Recreate a thread written by [`KernelObject::save_state`]
+    pub(crate) fn load_state(id: ObjectId, r: &mut StateReader) -> io::Result<Self> {
+        let group_id = r.u32()?;
+        let attributes = SpuThreadAttributes {
+            name: r.str()?,
+            option: r.u32()?,
+        };
+        let thread = Self::new(id, group_id, attributes);
+        {
+            let mut state = thread.inner.lock();
+            state.image = r.option(|r| {
+                let entry_point = r.u32()?;
+                let local_storage_size = r.u32()?;
+                let mut segments = Vec::new();
+                for _ in 0..r.count()? {
+                    segments.push(SpuSegment {
+                        addr: r.u32()?,
+                        size: r.u32()?,
+                        data: r.bytes()?.to_vec(),
+                    });
+                }
+                Ok(SpuImage { entry_point, local_storage_size, segments })
+            })?;
+            state.status = match r.u8()? {
+                0 => SpuThreadStatus::NotInitialized,
+                1 => SpuThreadStatus::Initialized,
+                2 => SpuThreadStatus::Running,
+                3 => SpuThreadStatus::Stopped,
+                value => return Err(invalid(&format!("invalid SPU thread status {}", value))),
+            };
+            state.local_storage = r.bytes()?.to_vec();
+            state.signals.signal1 = r.u32()?;
+            state.signals.signal2 = r.u32()?;
+        }
+        Ok(thread)
+    }
 }
 
 impl KernelObject for SpuThread {
@@ -259,6 +340,32 @@ impl KernelObject for SpuThread {
     fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync> {
         self
     }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u32(self.group_id);
+        w.str(&self.attributes.name);
+        w.u32(self.attributes.option);
+        let state = self.inner.lock();
+        w.option(state.image.as_ref(), |w, image| {
+            w.u32(image.entry_point);
+            w.u32(image.local_storage_size);
+            w.count(image.segments.len());
+            for segment in &image.segments {
+                w.u32(segment.addr);
+                w.u32(segment.size);
+                w.bytes(&segment.data);
+            }
+        });
+        w.u8(match state.status {
+            SpuThreadStatus::NotInitialized => 0,
+            SpuThreadStatus::Initialized => 1,
+            SpuThreadStatus::Running => 2,
+            SpuThreadStatus::Stopped => 3,
+        });
+        w.bytes(&state.local_storage);
+        w.u32(state.signals.signal1);
+        w.u32(state.signals.signal2);
+    }
 }
 
 /// SPU syscall implementations
@@ -0,0 +1,212 @@
+//! Security services (sys_ss_*)
+//!
+//! Games read the console's IDPS, OpenPSID and a few flags the kernel caches
+//! from flash while booting, and some refuse to continue when the calls fail.
+//! They are answered with the values of a retail console of the configured
+//! region.
+
+use oc_core::config::ConsoleRegion;
+use oc_core::error::KernelError;
+
+/// IDPS of the emulated console, with the target ID at byte 5
+const DEFAULT_IDPS: [u8; 16] = [
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x84, 0x00, 0x0B, 0x14, 0x00, 0xEF, 0xDD, 0xCA, 0x25, 0x52, 0x66,
+];
+
+/// Offset of the target (region) ID in the IDPS
+const TARGET_ID_OFFSET: usize = 5;
+
+/// Authority ID of a retail game
+pub const GAME_AUTHORITY_ID: u64 = 0x1010_0000_0100_0003;
+
+/// Cached product mode flag of a retail console (product mode off)
+pub const PRODUCT_MODE_OFF: u8 = 0xFF;
+
+/// Cached flash extension flag of a retail console
+pub const FLASH_EXT_FLAG: u8 = 0xFE;
+
+/// Boot device of a console booting from its internal flash
+pub const BOOT_DEVICE_FLASH: u64 = 0x190;
+
+/// IDs the console reports about itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleIdentity {
+    idps: [u8; 16],
+    open_psid: [u8; 16],
+}
+
+impl ConsoleIdentity {
+    /// Identity of a retail console of `region`
+    pub fn new(region: ConsoleRegion) -> Self {
+        let mut idps = DEFAULT_IDPS;
+        idps[TARGET_ID_OFFSET] = region.target_id();
+        Self::from_idps(idps)
+    }
+
+    /// Identity with the given IDPS, deriving the OpenPSID from it
+    pub fn from_idps(idps: [u8; 16]) -> Self {
+        let high = u64::from_be_bytes(idps[..8].try_into().unwrap());
+        let low = u64::from_be_bytes(idps[8..].try_into().unwrap());
+        let psid_high = mix(high ^ mix(low));
+        let psid_low = mix(low ^ psid_high);
+        let mut open_psid = [0u8; 16];
+        open_psid[..8].copy_from_slice(&psid_high.to_be_bytes());
+        open_psid[8..].copy_from_slice(&psid_low.to_be_bytes());
+        Self { idps, open_psid }
+    }
+
+    /// Identity from the configured console ID, or of `region` when it is empty or not 32 hex digits
+    pub fn from_config(console_id: &str, region: ConsoleRegion) -> Self {
+        let console_id = console_id.trim();
+        if console_id.is_empty() {
+            return Self::new(region);
+        }
+        match parse_idps(console_id) {
+            Some(idps) => Self::from_idps(idps),
+            None => {
+                tracing::warn!("Console ID {:?} is not 32 hex digits, using the default", console_id);
+                Self::new(region)
+            }
+        }
+    }
+
+    /// Console ID (IDPS)
+    pub fn idps(&self) -> [u8; 16] {
+        self.idps
+    }
+
+    /// OpenPSID, the ID games may send to servers
+    pub fn open_psid(&self) -> [u8; 16] {
+        self.open_psid
+    }
+
+    /// Region the IDPS target ID stands for, if it is a retail one
+    pub fn region(&self) -> Option<ConsoleRegion> {
+        ConsoleRegion::from_target_id(self.idps[TARGET_ID_OFFSET])
+    }
+}
+
+impl Default for ConsoleIdentity {
+    fn default() -> Self {
+        Self::new(ConsoleRegion::default())
+    }
+}
+
+/// Parse 32 hex digits into an IDPS
+fn parse_idps(hex: &str) -> Option<[u8; 16]> {
+    if hex.len() != 32 || !hex.is_ascii() {
+        return None;
+    }
+    let mut idps = [0u8; 16];
+    for (byte, digits) in idps.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()?;
+    }
+    Some(idps)
+}
+
+/// splitmix64 finalizer, spreading the IDPS bits over the OpenPSID
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Result of sys_ss_access_control_engine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessControlResult {
+    /// Write the authority ID to the pointer in the given argument (1 for a2, 2 for a3)
+    AuthorityId { arg: usize, authority_id: u64 },
+    /// Nothing to write
+    Done,
+}
+
+/// Security service syscall implementations
+pub mod syscalls {
+    use super::*;
+
+    /// sys_ss_get_console_id
+    pub fn sys_ss_get_console_id(console: &ConsoleIdentity) -> [u8; 16] {
+        console.idps()
+    }
+
+    /// sys_ss_get_open_psid
+    pub fn sys_ss_get_open_psid(console: &ConsoleIdentity) -> [u8; 16] {
+        console.open_psid()
+    }
+
+    /// sys_ss_access_control_engine
+    ///
+    /// Package 1 queries the authority ID of a process into a3, package 2 that
+    /// of the caller into a2, and package 3 checks the caller's permissions.
+    pub fn sys_ss_access_control_engine(pkg_id: u64) -> Result<AccessControlResult, KernelError> {
+        match pkg_id {
+            1 => Ok(AccessControlResult::AuthorityId { arg: 2, authority_id: GAME_AUTHORITY_ID }),
+            2 => Ok(AccessControlResult::AuthorityId { arg: 1, authority_id: GAME_AUTHORITY_ID }),
+            3 => Ok(AccessControlResult::Done),
+            _ => {
+                tracing::warn!("sys_ss_access_control_engine: unknown package {}", pkg_id);
+                Err(KernelError::PermissionDenied)
+            }
+        }
+    }
+
+    /// sys_ss_get_cache_of_product_mode
+    pub fn sys_ss_get_cache_of_product_mode() -> u8 {
+        PRODUCT_MODE_OFF
+    }
+
+    /// sys_ss_get_cache_of_flash_ext_flag
+    pub fn sys_ss_get_cache_of_flash_ext_flag() -> u8 {
+        FLASH_EXT_FLAG
+    }
+
+    /// sys_ss_get_boot_device
+    pub fn sys_ss_get_boot_device() -> u64 {
+        BOOT_DEVICE_FLASH
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_console_identity_region() {
+        for region in ConsoleRegion::ALL {
+            let console = ConsoleIdentity::new(region);
+            assert_eq!(console.region(), Some(region));
+            assert_eq!(&console.idps()[..4], &[0, 0, 0, 1]);
+        }
+        assert_eq!(ConsoleIdentity::new(ConsoleRegion::Europe).idps()[5], 0x85);
+
+        // The OpenPSID follows the IDPS
+        let us = ConsoleIdentity::new(ConsoleRegion::Usa);
+        let jp = ConsoleIdentity::new(ConsoleRegion::Japan);
+        assert_ne!(us.open_psid(), jp.open_psid());
+        assert_eq!(us.open_psid(), ConsoleIdentity::new(ConsoleRegion::Usa).open_psid());
+    }
+
+    #[test]
+    fn test_console_identity_from_config() {
+        let console = ConsoleIdentity::from_config("0000000100850008 14000123456789AB", ConsoleRegion::Japan);
+        // Not 32 digits, so the region's default is used
+        assert_eq!(console, ConsoleIdentity::new(ConsoleRegion::Japan));
+
+        let console = ConsoleIdentity::from_config("000000010085000814000123456789ab", ConsoleRegion::Japan);
+        assert_eq!(console.region(), Some(ConsoleRegion::Europe));
+        assert_eq!(console.idps()[15], 0xAB);
+
+        assert_eq!(ConsoleIdentity::from_config("", ConsoleRegion::Korea).region(), Some(ConsoleRegion::Korea));
+    }
+
+    #[test]
+    fn test_access_control_engine() {
+        assert_eq!(
+            syscalls::sys_ss_access_control_engine(1).unwrap(),
+            AccessControlResult::AuthorityId { arg: 2, authority_id: GAME_AUTHORITY_ID }
+        );
+        assert_eq!(syscalls::sys_ss_access_control_engine(3).unwrap(), AccessControlResult::Done);
+        assert!(syscalls::sys_ss_access_control_engine(99).is_err());
+    }
+}
@@ -3,9 +3,11 @@
 //! Barriers are synchronization primitives that allow multiple threads
 //! to wait until all threads reach a specific point before continuing.
 
-use crate::objects::{KernelObject, ObjectId, ObjectManager, ObjectType};
+use crate::objects::{KernelObject, ObjectDebugInfo, ObjectId, ObjectManager, ObjectType};
 use oc_core::error::KernelError;
+use oc_core::savestate::{StateReader, StateWriter};
 use parking_lot::{Condvar, Mutex as ParkingMutex};
+use std::io;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -135,6 +137,22 @@ impl Barrier {
     pub fn get_attributes(&self) -> BarrierAttributes {
         self.attributes
     }
+
+    /// Recreate a barrier written by [`KernelObject::save_state`]
+    pub(crate) fn load_state(id: ObjectId, r: &mut StateReader) -> io::Result<Self> {
+        let count = r.u32()?;
+        let attributes = BarrierAttributes {
+            protocol: r.u32()?,
+            pshared: r.u32()?,
+        };
+        let barrier = Self::new(id, count, attributes);
+        {
+            let mut state = barrier.state.lock();
+            state.waiting = r.u32()?;
+            state.generation = r.u64()?;
+        }
+        Ok(barrier)
+    }
 }
 
 impl KernelObject for Barrier {
@@ -149,6 +167,22 @@ impl KernelObject for Barrier {
     fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync> {
         self
     }
+
+    fn debug_info(&self) -> ObjectDebugInfo {
+        let state = self.state.lock();
+        ObjectDebugInfo::new(self.id, ObjectType::Barrier)
+            .detail("waiting", format!("{}/{}", state.waiting, self.count))
+            .detail("generation", state.generation)
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u32(self.count);
+        w.u32(self.attributes.protocol);
+        w.u32(self.attributes.pshared);
+        let state = self.state.lock();
+        w.u32(state.waiting);
+        w.u64(state.generation);
+    }
 }
 
 /// Barrier syscall implementations
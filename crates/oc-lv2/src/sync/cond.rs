@@ -1,10 +1,12 @@
 //! Condition variable (sys_cond_*)
 
-use crate::objects::{KernelObject, ObjectId, ObjectManager, ObjectType};
+use crate::objects::{KernelObject, ObjectDebugInfo, ObjectId, ObjectManager, ObjectType};
 use crate::sync::mutex::Mutex;
 use oc_core::error::KernelError;
+use oc_core::savestate::{StateReader, StateWriter};
 use parking_lot::{Condvar, Mutex as ParkingMutex};
 use std::collections::HashSet;
+use std::io;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -184,6 +186,20 @@ impl Cond {
     pub fn has_waiters(&self) -> bool {
         !self.state.lock().waiting_threads.is_empty()
     }
+
+    /// Recreate a condition variable written by [`KernelObject::save_state`]
+    pub(crate) fn load_state(id: ObjectId, r: &mut StateReader) -> io::Result<Self> {
+        let cond = Self::new(id, CondAttributes { flags: r.u32()? });
+        {
+            let mut state = cond.state.lock();
+            for _ in 0..r.count()? {
+                state.waiting_threads.insert(r.u64()?);
+            }
+            state.signal_count = r.u64()?;
+            state.broadcast_count = r.u64()?;
+        }
+        Ok(cond)
+    }
 }
 
 impl KernelObject for Cond {
@@ -198,6 +214,31 @@ impl KernelObject for Cond {
     fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync> {
         self
     }
+
+    fn debug_info(&self) -> ObjectDebugInfo {
+        let state = self.state.lock();
+        let mut waiters: Vec<u64> = state.waiting_threads.iter().copied().collect();
+        waiters.sort_unstable();
+        ObjectDebugInfo {
+            waiters,
+            ..ObjectDebugInfo::new(self.id, ObjectType::Cond)
+        }
+        .detail("signals", state.signal_count)
+        .detail("broadcasts", state.broadcast_count)
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u32(self.attributes.flags);
+        let state = self.state.lock();
+        let mut waiters: Vec<u64> = state.waiting_threads.iter().copied().collect();
+        waiters.sort_unstable();
+        w.count(waiters.len());
+        for thread in waiters {
+            w.u64(thread);
+        }
+        w.u64(state.signal_count);
+        w.u64(state.broadcast_count);
+    }
 }
 
 /// Condition variable syscall implementations
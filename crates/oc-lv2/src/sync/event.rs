@@ -1,9 +1,11 @@
 //! Event (sys_event_*)
 
-use crate::objects::{KernelObject, ObjectId, ObjectManager, ObjectType};
+use crate::objects::{KernelObject, ObjectDebugInfo, ObjectId, ObjectManager, ObjectType};
 use oc_core::error::KernelError;
+use oc_core::savestate::{StateReader, StateWriter};
 use parking_lot::Mutex;
 use std::collections::VecDeque;
+use std::io;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -48,7 +50,7 @@ pub struct Event {
 pub struct EventQueue {
     id: ObjectId,
     inner: Mutex<EventQueueState>,
-    _attributes: EventQueueAttributes,
+    attributes: EventQueueAttributes,
 }
 
 #[derive(Debug)]
@@ -68,7 +70,7 @@ impl EventQueue {
                 max_size: size,
                 waiting_threads: VecDeque::new(),
             }),
-            _attributes: attributes,
+            attributes,
         }
     }
 
@@ -168,6 +170,30 @@ impl EventQueue {
         let state = self.inner.lock();
         state.events.len() >= state.max_size
     }
+
+    /// Recreate an event queue written by [`KernelObject::save_state`]
+    pub(crate) fn load_state(id: ObjectId, r: &mut StateReader) -> io::Result<Self> {
+        let attributes = EventQueueAttributes {
+            protocol: r.u32()?,
+            queue_type: r.u32()?,
+        };
+        let queue = Self::new(id, attributes, r.u32()? as usize);
+        {
+            let mut state = queue.inner.lock();
+            for _ in 0..r.count()? {
+                state.events.push_back(Event {
+                    source: r.u64()?,
+                    data1: r.u64()?,
+                    data2: r.u64()?,
+                    data3: r.u64()?,
+                });
+            }
+            for _ in 0..r.count()? {
+                state.waiting_threads.push_back(r.u64()?);
+            }
+        }
+        Ok(queue)
+    }
 }
 
 impl KernelObject for EventQueue {
@@ -182,13 +208,49 @@ impl KernelObject for EventQueue {
     fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync> {
         self
     }
+
+    fn debug_info(&self) -> ObjectDebugInfo {
+        let state = self.inner.lock();
+        let mut info = ObjectDebugInfo {
+            waiters: state.waiting_threads.iter().copied().collect(),
+            ..ObjectDebugInfo::new(self.id, ObjectType::EventQueue)
+        }
+        .detail("pending", format!("{}/{}", state.events.len(), state.max_size));
+        for event in &state.events {
+            info = info.detail(
+                "event",
+                format!(
+                    "source=0x{:x} data=0x{:x}, 0x{:x}, 0x{:x}",
+                    event.source, event.data1, event.data2, event.data3
+                ),
+            );
+        }
+        info
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u32(self.attributes.protocol);
+        w.u32(self.attributes.queue_type);
+        let state = self.inner.lock();
+        w.count(state.max_size);
+        w.count(state.events.len());
+        for event in &state.events {
+            for value in [event.source, event.data1, event.data2, event.data3] {
+                w.u64(value);
+            }
+        }
+        w.count(state.waiting_threads.len());
+        for &thread in &state.waiting_threads {
+            w.u64(thread);
+        }
+    }
 }
 
 /// LV2 Event Port implementation
 pub struct EventPort {
     id: ObjectId,
     queue_id: ObjectId,
-    _attributes: EventPortAttributes,
+    attributes: EventPortAttributes,
 }
 
 impl EventPort {
@@ -196,13 +258,19 @@ impl EventPort {
         Self {
             id,
             queue_id,
-            _attributes: attributes,
+            attributes,
         }
     }
 
     pub fn queue_id(&self) -> ObjectId {
         self.queue_id
     }
+
+    /// Recreate an event port written by [`KernelObject::save_state`]
+    pub(crate) fn load_state(id: ObjectId, r: &mut StateReader) -> io::Result<Self> {
+        let queue_id = r.u32()?;
+        Ok(Self::new(id, queue_id, EventPortAttributes { name: r.u64()? }))
+    }
 }
 
 impl KernelObject for EventPort {
@@ -217,6 +285,15 @@ impl KernelObject for EventPort {
     fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync> {
         self
     }
+
+    fn debug_info(&self) -> ObjectDebugInfo {
+        ObjectDebugInfo::new(self.id, ObjectType::EventPort).detail("queue", self.queue_id)
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u32(self.queue_id);
+        w.u64(self.attributes.name);
+    }
 }
 
 /// Event syscall implementations
@@ -3,10 +3,12 @@
 //! Event flags are synchronization primitives that allow threads to wait
 //! for specific bit patterns to be set or cleared.
 
-use crate::objects::{KernelObject, ObjectId, ObjectManager, ObjectType};
+use crate::objects::{KernelObject, ObjectDebugInfo, ObjectId, ObjectManager, ObjectType};
 use oc_core::error::KernelError;
+use oc_core::savestate::{StateReader, StateWriter};
 use parking_lot::{Condvar, Mutex as ParkingMutex};
 use std::collections::VecDeque;
+use std::io;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -72,8 +74,8 @@ struct EventFlagState {
 #[derive(Debug, Clone)]
 struct WaitingThread {
     thread_id: u64,
-    _wait_pattern: u64,
-    _wait_mode: u32,
+    wait_pattern: u64,
+    wait_mode: u32,
 }
 
 impl EventFlag {
@@ -169,8 +171,8 @@ impl EventFlag {
             if !state.waiting_threads.iter().any(|w| w.thread_id == thread_id) {
                 state.waiting_threads.push_back(WaitingThread {
                     thread_id,
-                    _wait_pattern: wait_pattern,
-                    _wait_mode: mode,
+                    wait_pattern,
+                    wait_mode: mode,
                 });
             }
             
@@ -232,6 +234,28 @@ impl EventFlag {
     pub fn get_attributes(&self) -> EventFlagAttributes {
         self.attributes
     }
+
+    /// Recreate an event flag written by [`KernelObject::save_state`]
+    pub(crate) fn load_state(id: ObjectId, r: &mut StateReader) -> io::Result<Self> {
+        let attributes = EventFlagAttributes {
+            protocol: r.u32()?,
+            pshared: r.u32()?,
+            initial_pattern: r.u64()?,
+        };
+        let flag = Self::new(id, attributes);
+        {
+            let mut state = flag.state.lock();
+            state.pattern = r.u64()?;
+            for _ in 0..r.count()? {
+                state.waiting_threads.push_back(WaitingThread {
+                    thread_id: r.u64()?,
+                    wait_pattern: r.u64()?,
+                    wait_mode: r.u32()?,
+                });
+            }
+        }
+        Ok(flag)
+    }
 }
 
 impl KernelObject for EventFlag {
@@ -246,6 +270,29 @@ impl KernelObject for EventFlag {
     fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync> {
         self
     }
+
+    fn debug_info(&self) -> ObjectDebugInfo {
+        let state = self.state.lock();
+        ObjectDebugInfo {
+            waiters: state.waiting_threads.iter().map(|waiter| waiter.thread_id).collect(),
+            ..ObjectDebugInfo::new(self.id, ObjectType::EventFlag)
+        }
+        .detail("pattern", format!("0x{:016x}", state.pattern))
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u32(self.attributes.protocol);
+        w.u32(self.attributes.pshared);
+        w.u64(self.attributes.initial_pattern);
+        let state = self.state.lock();
+        w.u64(state.pattern);
+        w.count(state.waiting_threads.len());
+        for waiter in &state.waiting_threads {
+            w.u64(waiter.thread_id);
+            w.u64(waiter.wait_pattern);
+            w.u32(waiter.wait_mode);
+        }
+    }
 }
 
 /// Event flag syscall implementations
@@ -1,8 +1,10 @@
 //! Mutex (sys_mutex_*)
 
-use crate::objects::{KernelObject, ObjectId, ObjectManager, ObjectType};
+use crate::objects::{KernelObject, ObjectDebugInfo, ObjectId, ObjectManager, ObjectType};
 use oc_core::error::KernelError;
+use oc_core::savestate::{StateReader, StateWriter};
 use parking_lot::Mutex as ParkingMutex;
+use std::io;
 use std::sync::Arc;
 
 /// Mutex attributes
@@ -96,6 +98,22 @@ impl Mutex {
         }
     }
 
+    /// Recreate a mutex written by [`KernelObject::save_state`]
+    pub(crate) fn load_state(id: ObjectId, r: &mut StateReader) -> io::Result<Self> {
+        let attributes = MutexAttributes {
+            protocol: r.u32()?,
+            recursive: r.bool()?,
+            adaptive: r.bool()?,
+        };
+        let mutex = Self::new(id, attributes);
+        {
+            let mut state = mutex.inner.lock();
+            state.owner = r.option(|r| r.u64())?;
+            state.lock_count = r.u32()?;
+        }
+        Ok(mutex)
+    }
+
     pub fn unlock(&self, thread_id: u64) -> Result<(), KernelError> {
         let mut state = self.inner.lock();
 
@@ -127,6 +145,25 @@ impl KernelObject for Mutex {
     fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync> {
         self
     }
+
+    fn debug_info(&self) -> ObjectDebugInfo {
+        let state = self.inner.lock();
+        ObjectDebugInfo {
+            owner: state.owner,
+            ..ObjectDebugInfo::new(self.id, ObjectType::Mutex)
+        }
+        .detail("lock count", state.lock_count)
+        .detail("recursive", self.attributes.recursive)
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u32(self.attributes.protocol);
+        w.bool(self.attributes.recursive);
+        w.bool(self.attributes.adaptive);
+        let state = self.inner.lock();
+        w.option(state.owner, |w, owner| w.u64(owner));
+        w.u32(state.lock_count);
+    }
 }
 
 /// Mutex syscall implementations
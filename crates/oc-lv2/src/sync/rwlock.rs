@@ -1,9 +1,11 @@
 //! Read-write lock (sys_rwlock_*)
 
-use crate::objects::{KernelObject, ObjectId, ObjectManager, ObjectType};
+use crate::objects::{KernelObject, ObjectDebugInfo, ObjectId, ObjectManager, ObjectType};
 use oc_core::error::KernelError;
+use oc_core::savestate::{StateReader, StateWriter};
 use parking_lot::Mutex as ParkingMutex;
 use std::collections::{HashSet, VecDeque};
+use std::io;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -321,6 +323,25 @@ impl RwLock {
     pub fn has_write_lock(&self, thread_id: u64) -> bool {
         self.state.lock().writer == Some(thread_id)
     }
+
+    /// Recreate a read-write lock written by [`KernelObject::save_state`]
+    pub(crate) fn load_state(id: ObjectId, r: &mut StateReader) -> io::Result<Self> {
+        let lock = Self::new(id, RwLockAttributes { flags: r.u32()? });
+        {
+            let mut state = lock.state.lock();
+            for _ in 0..r.count()? {
+                state.readers.insert(r.u64()?);
+            }
+            state.writer = r.option(|r| r.u64())?;
+            for _ in 0..r.count()? {
+                state.waiting_readers.push_back(r.u64()?);
+            }
+            for _ in 0..r.count()? {
+                state.waiting_writers.push_back(r.u64()?);
+            }
+        }
+        Ok(lock)
+    }
 }
 
 impl KernelObject for RwLock {
@@ -335,6 +356,38 @@ impl KernelObject for RwLock {
     fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync> {
         self
     }
+
+    fn debug_info(&self) -> ObjectDebugInfo {
+        let state = self.state.lock();
+        let mut readers: Vec<u64> = state.readers.iter().copied().collect();
+        readers.sort_unstable();
+        ObjectDebugInfo {
+            owner: state.writer,
+            waiters: state.waiting_writers.iter().chain(&state.waiting_readers).copied().collect(),
+            ..ObjectDebugInfo::new(self.id, ObjectType::RwLock)
+        }
+        .detail("readers", format!("{:?}", readers))
+        .detail("waiting readers", state.waiting_readers.len())
+        .detail("waiting writers", state.waiting_writers.len())
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u32(self.attributes.flags);
+        let state = self.state.lock();
+        let mut readers: Vec<u64> = state.readers.iter().copied().collect();
+        readers.sort_unstable();
+        w.count(readers.len());
+        for thread in readers {
+            w.u64(thread);
+        }
+        w.option(state.writer, |w, writer| w.u64(writer));
+        for queue in [&state.waiting_readers, &state.waiting_writers] {
+            w.count(queue.len());
+            for &thread in queue {
+                w.u64(thread);
+            }
+        }
+    }
 }
 
 /// RwLock syscall implementations
@@ -1,8 +1,10 @@
 //! Semaphore (sys_semaphore_*)
 
-use crate::objects::{KernelObject, ObjectId, ObjectManager, ObjectType};
+use crate::objects::{KernelObject, ObjectDebugInfo, ObjectId, ObjectManager, ObjectType};
 use oc_core::error::KernelError;
+use oc_core::savestate::{StateReader, StateWriter};
 use parking_lot::Mutex;
+use std::io;
 use std::sync::Arc;
 
 /// Semaphore attributes
@@ -84,6 +86,15 @@ impl Semaphore {
     pub fn get_value(&self) -> u32 {
         self.inner.lock().count
     }
+
+    /// Recreate a semaphore written by [`KernelObject::save_state`]
+    pub(crate) fn load_state(id: ObjectId, r: &mut StateReader) -> io::Result<Self> {
+        let attributes = SemaphoreAttributes {
+            protocol: r.u32()?,
+            max_value: r.u32()?,
+        };
+        Ok(Self::new(id, attributes, r.u32()?))
+    }
 }
 
 impl KernelObject for Semaphore {
@@ -98,6 +109,18 @@ impl KernelObject for Semaphore {
     fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync> {
         self
     }
+
+    fn debug_info(&self) -> ObjectDebugInfo {
+        ObjectDebugInfo::new(self.id, ObjectType::Semaphore)
+            .detail("count", self.inner.lock().count)
+            .detail("max", self.attributes.max_value)
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u32(self.attributes.protocol);
+        w.u32(self.attributes.max_value);
+        w.u32(self.inner.lock().count);
+    }
 }
 
 /// Semaphore syscall implementations
@@ -1,19 +1,34 @@
 //! System call dispatcher
 
+use crate::bluetooth::Bluetooth;
+use crate::config;
 use crate::fs;
+use crate::gpio::Gpio;
+use crate::interrupt;
 use crate::memory::MemoryManager;
 use crate::objects::ObjectManager;
 use crate::process::ProcessManager;
 use crate::prx;
 use crate::spu;
+use crate::ss::{self, AccessControlResult, ConsoleIdentity};
 use crate::sync::{barrier, cond, event, event_flag, mutex, rwlock, semaphore};
 use crate::syscall_numbers::*;
 use crate::thread::ThreadManager;
 use crate::timer;
+use crate::usbd::{Usbd, UsbdDeviceEntry};
 use oc_core::error::KernelError;
-use oc_vfs::VirtualFileSystem;
+use oc_core::savestate::{StateReader, StateWriter};
+use oc_core::time_base::TimeBase;
+use oc_memory::{BeValue, MemoryManager as GuestMemory};
+use oc_vfs::{VfsAccessKind, VirtualFileSystem};
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::io;
 use std::sync::Arc;
 
+/// Most TTY output kept before further writes are dropped
+const TTY_LIMIT: usize = 4 * 1024 * 1024;
+
 /// System call handler with state management
 pub struct SyscallHandler {
     object_manager: Arc<ObjectManager>,
@@ -21,6 +36,20 @@ pub struct SyscallHandler {
     thread_manager: Arc<ThreadManager>,
     memory_manager: Arc<MemoryManager>,
     vfs: Arc<VirtualFileSystem>,
+    /// Guest memory for syscalls that write results through pointers
+    guest_memory: Option<Arc<GuestMemory>>,
+    /// Everything written with sys_tty_write
+    tty: Mutex<Vec<u8>>,
+    /// How often each unimplemented syscall was called
+    unknown_syscalls: Mutex<BTreeMap<u64, u64>>,
+    /// IDs the sys_ss calls report
+    console: ConsoleIdentity,
+    /// LEDs and DIP switches
+    gpio: Gpio,
+    /// USB devices for games with their own drivers
+    usbd: Usbd,
+    /// Paired Bluetooth devices
+    bluetooth: Bluetooth,
 }
 
 impl SyscallHandler {
@@ -32,6 +61,13 @@ impl SyscallHandler {
             thread_manager: Arc::new(ThreadManager::new()),
             memory_manager: Arc::new(MemoryManager::new()),
             vfs: Arc::new(VirtualFileSystem::new()),
+            guest_memory: None,
+            tty: Mutex::new(Vec::new()),
+            unknown_syscalls: Mutex::new(BTreeMap::new()),
+            console: ConsoleIdentity::default(),
+            gpio: Gpio::new(),
+            usbd: Usbd::new(),
+            bluetooth: Bluetooth::new(),
         }
     }
 
@@ -43,6 +79,13 @@ impl SyscallHandler {
             thread_manager: Arc::new(ThreadManager::new()),
             memory_manager: Arc::new(MemoryManager::new()),
             vfs,
+            guest_memory: None,
+            tty: Mutex::new(Vec::new()),
+            unknown_syscalls: Mutex::new(BTreeMap::new()),
+            console: ConsoleIdentity::default(),
+            gpio: Gpio::new(),
+            usbd: Usbd::new(),
+            bluetooth: Bluetooth::new(),
         }
     }
 
@@ -71,6 +114,169 @@ impl SyscallHandler {
         &self.vfs
     }
 
+    /// Emulated clock behind system time, the time base and timers
+    pub fn time_base(&self) -> &TimeBase {
+        self.object_manager.time_base()
+    }
+
+    /// Flush and close the files and directories the game has open, returning how many
+    pub fn close_files(&self) -> usize {
+        fs::close_all(&self.object_manager)
+    }
+
+    /// Everything the game wrote to its TTY so far
+    pub fn tty_output(&self) -> String {
+        String::from_utf8_lossy(&self.tty.lock()).into_owned()
+    }
+
+    /// Unimplemented syscalls the game called so far, with how often, by number
+    pub fn unknown_syscalls(&self) -> Vec<(u64, u64)> {
+        self.unknown_syscalls.lock().iter().map(|(&num, &count)| (num, count)).collect()
+    }
+
+    /// Attach guest memory so syscalls can write results through pointers
+    pub fn set_guest_memory(&mut self, memory: Arc<GuestMemory>) {
+        self.guest_memory = Some(memory);
+    }
+
+    /// IDs reported to the game as the console's
+    pub fn console(&self) -> &ConsoleIdentity {
+        &self.console
+    }
+
+    /// Set the IDs reported to the game as the console's
+    pub fn set_console(&mut self, console: ConsoleIdentity) {
+        self.console = console;
+    }
+
+    /// USB devices and the game's drivers for them
+    pub fn usbd(&self) -> &Usbd {
+        &self.usbd
+    }
+
+    /// Paired Bluetooth devices games can list
+    pub fn bluetooth(&self) -> &Bluetooth {
+        &self.bluetooth
+    }
+
+    /// Pick up USB hotplug and finish transfers whose data arrived
+    ///
+    /// Call once per frame.
+    pub fn poll_usb(&self) {
+        for completion in self.usbd.poll() {
+            if let Err(e) = self.write_guest_bytes(completion.buffer, &completion.data) {
+                tracing::warn!("USB transfer data lost: {}", e);
+            }
+        }
+    }
+
+    /// Save the process, threads, kernel objects and memory allocations
+    pub fn save_state(&self, w: &mut StateWriter) {
+        self.process_manager.save_state(w);
+        self.thread_manager.save_state(w);
+        self.object_manager.save_state(w);
+        self.memory_manager.save_state(w);
+    }
+
+    /// Restore kernel state written by [`SyscallHandler::save_state`]
+    pub fn load_state(&self, r: &mut StateReader) -> io::Result<()> {
+        self.process_manager.load_state(r)?;
+        self.thread_manager.load_state(r)?;
+        self.object_manager.load_state(r)?;
+        self.memory_manager.load_state(r)
+    }
+
+    /// Write a big-endian u32 result to guest memory
+    ///
+    /// Returns false when no guest memory is attached.
+    fn write_guest_u32(&self, addr: u32, value: u32) -> Result<bool, KernelError> {
+        match &self.guest_memory {
+            Some(memory) => memory
+                .write_be32(addr, value)
+                .map(|_| true)
+                .map_err(|_| KernelError::InvalidAddress(addr)),
+            None => Ok(false),
+        }
+    }
+
+    /// Write a big-endian u64 result to guest memory
+    ///
+    /// Returns false when no guest memory is attached.
+    fn write_guest_u64(&self, addr: u32, value: u64) -> Result<bool, KernelError> {
+        match &self.guest_memory {
+            Some(memory) => memory
+                .write_be64(addr, value)
+                .map(|_| true)
+                .map_err(|_| KernelError::InvalidAddress(addr)),
+            None => Ok(false),
+        }
+    }
+
+    /// Write a big-endian struct result to guest memory
+    ///
+    /// Returns false when no guest memory is attached.
+    fn write_guest<T: BeValue>(&self, addr: u32, value: T) -> Result<bool, KernelError> {
+        match &self.guest_memory {
+            Some(memory) => memory
+                .write_be(addr, value)
+                .map(|_| true)
+                .map_err(|_| KernelError::InvalidAddress(addr)),
+            None => Ok(false),
+        }
+    }
+
+    /// Read a buffer from guest memory
+    ///
+    /// Returns None when no guest memory is attached.
+    fn read_guest_bytes(&self, addr: u32, len: u32) -> Result<Option<Vec<u8>>, KernelError> {
+        match &self.guest_memory {
+            Some(memory) => memory
+                .read_bytes(addr, len)
+                .map(Some)
+                .map_err(|_| KernelError::InvalidAddress(addr)),
+            None => Ok(None),
+        }
+    }
+
+    /// Read a Bluetooth device address from guest memory
+    fn read_bt_address(&self, addr: u32) -> Result<[u8; 6], KernelError> {
+        self.read_guest_bytes(addr, 6)?
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(KernelError::InvalidAddress(addr))
+    }
+
+    /// Read a string of `len` bytes from guest memory, up to its NUL
+    fn read_guest_string(&self, addr: u32, len: u32) -> Result<String, KernelError> {
+        let bytes = self.read_guest_bytes(addr, len)?.unwrap_or_default();
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        Ok(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    }
+
+    /// Write a result buffer to guest memory
+    ///
+    /// Returns false when no guest memory is attached.
+    fn write_guest_bytes(&self, addr: u32, data: &[u8]) -> Result<bool, KernelError> {
+        match &self.guest_memory {
+            Some(memory) => memory
+                .write_bytes(addr, data)
+                .map(|_| true)
+                .map_err(|_| KernelError::InvalidAddress(addr)),
+            None => Ok(false),
+        }
+    }
+
+    /// Record bytes transferred through a file descriptor in the VFS tracer
+    fn trace_transfer(&self, kind: VfsAccessKind, fd: u32, bytes: usize) {
+        if !self.vfs.tracer().is_enabled() {
+            return;
+        }
+        if let Ok(file) = self.object_manager.get::<fs::FileDescriptor>(fd) {
+            self.vfs
+                .tracer()
+                .record_transfer(kind, &file.virtual_path(), bytes as u64);
+        }
+    }
+
     /// Handle a system call
     pub fn handle(&self, syscall_num: u64, args: &[u64; 8]) -> Result<i64, KernelError> {
         use crate::memory::syscalls as memory_sc;
@@ -580,6 +786,7 @@ impl SyscallHandler {
                 // In real implementation, would write to buffer at args[1]
                 let mut buffer = vec![0u8; size];
                 let bytes_read = fs::syscalls::sys_fs_read(&self.object_manager, fd, &mut buffer)?;
+                self.trace_transfer(VfsAccessKind::Read, fd, bytes_read);
                 Ok(bytes_read as i64)
             }
 
@@ -589,6 +796,7 @@ impl SyscallHandler {
                 // In real implementation, would read from buffer at args[1]
                 let buffer = vec![0u8; size];
                 let bytes_written = fs::syscalls::sys_fs_write(&self.object_manager, fd, &buffer)?;
+                self.trace_transfer(VfsAccessKind::Write, fd, bytes_written);
                 Ok(bytes_written as i64)
             }
 
@@ -671,7 +879,7 @@ impl SyscallHandler {
 
             // Time
             SYS_TIME_GET_SYSTEM_TIME => {
-                let time = time_sc::sys_time_get_current_time();
+                let time = time_sc::sys_time_get_current_time(self.time_base());
                 Ok(time as i64)
             }
 
@@ -682,7 +890,7 @@ impl SyscallHandler {
 
             SYS_TIME_USLEEP => {
                 let usec = args[0];
-                time_sc::sys_time_usleep(usec)?;
+                time_sc::sys_time_usleep(self.time_base(), usec)?;
                 Ok(0)
             }
 
@@ -722,9 +930,17 @@ impl SyscallHandler {
             }
 
             SYS_MEMORY_GET_USER_MEMORY_SIZE => {
-                let (total, _available) = memory_sc::sys_memory_get_user_memory_size();
-                // In real implementation, would write to memory pointed by args[0] and args[1]
-                // For now, return total size as the result
+                let (total, available) =
+                    memory_sc::sys_memory_get_user_memory_size(&self.memory_manager);
+                let info = args[0] as u32;
+                let value = crate::memory::SysMemoryInfo {
+                    total_user_memory: total as u32,
+                    available_user_memory: available as u32,
+                };
+                if info != 0 && self.write_guest(info, value)? {
+                    return Ok(0);
+                }
+                // Without guest memory, return the total size as the result
                 Ok(total as i64)
             }
 
@@ -983,26 +1199,365 @@ impl SyscallHandler {
 
             SYS_TIMER_USLEEP => {
                 let duration_usec = args[0];
-                timer::syscalls::sys_timer_usleep(duration_usec)?;
+                timer::syscalls::sys_timer_usleep(self.time_base(), duration_usec)?;
                 Ok(0)
             }
 
             SYS_TIMER_SLEEP => {
                 let duration_sec = args[0] as u32;
-                timer::syscalls::sys_timer_sleep(duration_sec)?;
+                timer::syscalls::sys_timer_sleep(self.time_base(), duration_sec)?;
+                Ok(0)
+            }
+
+            // Interrupts
+            SYS_INTERRUPT_TAG_DESTROY => {
+                let tag_id = args[0] as u32;
+                interrupt::syscalls::sys_interrupt_tag_destroy(&self.object_manager, tag_id)?;
+                Ok(0)
+            }
+
+            SYS_INTERRUPT_THREAD_ESTABLISH => {
+                let out_handle = args[0] as u32;
+                let tag_id = args[1] as u32;
+                let thread_id = args[2];
+                let arg = args[3];
+                let handle = interrupt::syscalls::sys_interrupt_thread_establish(
+                    &self.object_manager,
+                    &self.thread_manager,
+                    tag_id,
+                    thread_id,
+                    arg,
+                )?;
+                self.write_guest_u32(out_handle, handle)?;
+                Ok(0)
+            }
+
+            SYS_INTERRUPT_THREAD_EOI => {
+                let thread_id = self.thread_manager.current();
+                interrupt::syscalls::sys_interrupt_thread_eoi(&self.object_manager, thread_id)?;
+                Ok(0)
+            }
+
+            SYS_INTERRUPT_THREAD_DISESTABLISH => {
+                let handle = args[0] as u32;
+                interrupt::syscalls::sys_interrupt_thread_disestablish(&self.object_manager, handle)?;
                 Ok(0)
             }
 
             // TTY
             SYS_TTY_WRITE => {
-                let _ch = args[0] as u32;
-                let _buf = args[1] as u32;
+                let ch = args[0] as u32;
+                let buf = args[1] as u32;
                 let len = args[2] as u32;
-                Ok(len as i64)
+                let pwritelen = args[3] as u32;
+                if let Some(memory) = &self.guest_memory {
+                    let data = memory.read_bytes(buf, len).map_err(|_| KernelError::InvalidAddress(buf))?;
+                    tracing::info!(target: "tty", "[{}] {}", ch, String::from_utf8_lossy(&data).trim_end());
+                    let mut tty = self.tty.lock();
+                    let room = TTY_LIMIT.saturating_sub(tty.len());
+                    tty.extend_from_slice(&data[..data.len().min(room)]);
+                }
+                if pwritelen != 0 {
+                    self.write_guest_u32(pwritelen, len)?;
+                }
+                Ok(0)
+            }
+
+            // Config
+            SYS_CONFIG_OPEN => {
+                let event_queue_id = args[0] as u32;
+                let out_config_id = args[1] as u32;
+                let id = config::syscalls::sys_config_open(&self.object_manager, event_queue_id)?;
+                self.write_guest_u32(out_config_id, id)?;
+                Ok(0)
+            }
+
+            SYS_CONFIG_CLOSE => {
+                let config_id = args[0] as u32;
+                config::syscalls::sys_config_close(&self.object_manager, config_id)?;
+                Ok(0)
+            }
+
+            SYS_CONFIG_ADD_SERVICE_LISTENER => {
+                let config_id = args[0] as u32;
+                let service_id = args[1] as i64;
+                let min_event_id = args[2];
+                let verbosity = args[3];
+                let data_size = args[5];
+                let out_listener_id = args[6] as u32;
+                let id = config::syscalls::sys_config_add_service_listener(
+                    &self.object_manager,
+                    config_id,
+                    service_id,
+                    min_event_id,
+                    verbosity,
+                    data_size,
+                )?;
+                self.write_guest_u32(out_listener_id, id)?;
+                Ok(0)
+            }
+
+            SYS_CONFIG_REMOVE_SERVICE_LISTENER => {
+                let config_id = args[0] as u32;
+                let listener_id = args[1] as u32;
+                config::syscalls::sys_config_remove_service_listener(&self.object_manager, config_id, listener_id)?;
+                Ok(0)
+            }
+
+            SYS_CONFIG_REGISTER_SERVICE => {
+                let config_id = args[0] as u32;
+                let service_id = args[1] as i64;
+                let user_id = args[2];
+                let verbosity = args[3];
+                let data_size = args[5];
+                let out_service_handle = args[6] as u32;
+                let id = config::syscalls::sys_config_register_service(
+                    &self.object_manager,
+                    config_id,
+                    service_id,
+                    user_id,
+                    verbosity,
+                    data_size,
+                )?;
+                self.write_guest_u32(out_service_handle, id)?;
+                Ok(0)
+            }
+
+            SYS_CONFIG_UNREGISTER_SERVICE => {
+                let config_id = args[0] as u32;
+                let service_handle = args[1] as u32;
+                config::syscalls::sys_config_unregister_service(&self.object_manager, config_id, service_handle)?;
+                Ok(0)
+            }
+
+            // GPIO
+            SYS_GPIO_SET => {
+                self.gpio.set(args[0], args[1], args[2])?;
+                Ok(0)
+            }
+
+            SYS_GPIO_GET => {
+                let value = self.gpio.get(args[0])?;
+                self.write_guest_u64(args[1] as u32, value)?;
+                Ok(0)
+            }
+
+            // Security services
+            SYS_SS_GET_CONSOLE_ID => {
+                let buf = args[0] as u32;
+                self.write_guest_bytes(buf, &ss::syscalls::sys_ss_get_console_id(&self.console))?;
+                Ok(0)
+            }
+
+            SYS_SS_GET_OPEN_PSID => {
+                let buf = args[0] as u32;
+                self.write_guest_bytes(buf, &ss::syscalls::sys_ss_get_open_psid(&self.console))?;
+                Ok(0)
+            }
+
+            SYS_SS_ACCESS_CONTROL_ENGINE => {
+                match ss::syscalls::sys_ss_access_control_engine(args[0])? {
+                    AccessControlResult::AuthorityId { arg, authority_id } => {
+                        self.write_guest_u64(args[arg] as u32, authority_id)?;
+                    }
+                    AccessControlResult::Done => {}
+                }
+                Ok(0)
+            }
+
+            SYS_SS_GET_CACHE_OF_PRODUCT_MODE => {
+                let ptr = args[0] as u32;
+                self.write_guest_bytes(ptr, &[ss::syscalls::sys_ss_get_cache_of_product_mode()])?;
+                Ok(0)
+            }
+
+            SYS_SS_GET_CACHE_OF_FLASH_EXT_FLAG => {
+                let ptr = args[0] as u32;
+                self.write_guest_bytes(ptr, &[ss::syscalls::sys_ss_get_cache_of_flash_ext_flag()])?;
+                Ok(0)
+            }
+
+            SYS_SS_GET_BOOT_DEVICE => {
+                let ptr = args[0] as u32;
+                self.write_guest_u64(ptr, ss::syscalls::sys_ss_get_boot_device())?;
+                Ok(0)
+            }
+
+            // Bluetooth
+            SYS_USBBTAUDIO_INITIALIZE => {
+                self.bluetooth.initialize();
+                Ok(0)
+            }
+
+            SYS_USBBTAUDIO_FINALIZE => {
+                self.bluetooth.finalize()?;
+                Ok(0)
+            }
+
+            SYS_USBBTAUDIO_DISCOVERY => {
+                let buf = args[0] as u32;
+                let max_devices = args[1] as usize;
+                let pcount = args[2] as u32;
+                let devices = self.bluetooth.discovery(max_devices)?;
+                let entries: Vec<u8> = devices.iter().flat_map(|dev| dev.info().to_be_vec()).collect();
+                self.write_guest_bytes(buf, &entries)?;
+                if pcount != 0 {
+                    self.write_guest_u32(pcount, devices.len() as u32)?;
+                }
+                Ok(0)
+            }
+
+            SYS_USBBTAUDIO_CANCEL_DISCOVERY => {
+                self.bluetooth.cancel_discovery()?;
+                Ok(0)
+            }
+
+            SYS_USBBTAUDIO_PAIRING => {
+                let address = self.read_bt_address(args[0] as u32)?;
+                self.bluetooth.pair(&address)?;
+                Ok(0)
+            }
+
+            SYS_USBBTAUDIO_SET_PASSKEY => {
+                let address = self.read_bt_address(args[0] as u32)?;
+                self.bluetooth.set_passkey(&address)?;
+                Ok(0)
+            }
+
+            SYS_USBBTAUDIO_CONNECT => {
+                let address = self.read_bt_address(args[0] as u32)?;
+                self.bluetooth.connect(&address)?;
+                Ok(0)
+            }
+
+            SYS_USBBTAUDIO_DISCONNECT => {
+                let address = self.read_bt_address(args[0] as u32)?;
+                self.bluetooth.disconnect(&address)?;
+                Ok(0)
+            }
+
+            // USB
+            SYS_USBD_INITIALIZE => {
+                let handle = self.usbd.initialize();
+                self.write_guest_u32(args[0] as u32, handle)?;
+                Ok(0)
+            }
+
+            SYS_USBD_FINALIZE => {
+                self.usbd.finalize(args[0] as u32)?;
+                Ok(0)
+            }
+
+            SYS_USBD_GET_DEVICE_LIST => {
+                let handle = args[0] as u32;
+                let list = args[1] as u32;
+                let max_devices = args[2] as usize;
+                let devices = self.usbd.device_list(handle, max_devices)?;
+                let entries: Vec<u8> = devices
+                    .iter()
+                    .flat_map(|&dev| UsbdDeviceEntry { handle: dev as u8, reserved: [0; 3] }.to_be_vec())
+                    .collect();
+                self.write_guest_bytes(list, &entries)?;
+                Ok(devices.len() as i64)
+            }
+
+            SYS_USBD_GET_DESCRIPTOR_SIZE => {
+                let descriptors = self.usbd.descriptors(args[0] as u32, args[1] as u32)?;
+                Ok(descriptors.len() as i64)
+            }
+
+            SYS_USBD_GET_DESCRIPTOR => {
+                let mut descriptors = self.usbd.descriptors(args[0] as u32, args[1] as u32)?;
+                descriptors.truncate(args[3] as usize);
+                self.write_guest_bytes(args[2] as u32, &descriptors)?;
+                Ok(0)
+            }
+
+            SYS_USBD_REGISTER_LDD => {
+                let name = self.read_guest_string(args[1] as u32, args[2] as u32)?;
+                self.usbd.register_ldd(args[0] as u32, &name)?;
+                Ok(0)
+            }
+
+            SYS_USBD_REGISTER_EXTRA_LDD => {
+                let name = self.read_guest_string(args[1] as u32, args[2] as u32)?;
+                self.usbd
+                    .register_extra_ldd(args[0] as u32, &name, args[3] as u16, args[4] as u16, args[5] as u16)?;
+                Ok(0)
+            }
+
+            SYS_USBD_UNREGISTER_LDD => {
+                let name = self.read_guest_string(args[1] as u32, args[2] as u32)?;
+                self.usbd.unregister_ldd(args[0] as u32, &name)?;
+                Ok(0)
+            }
+
+            SYS_USBD_OPEN_PIPE => {
+                let endpoint = args[5] as u8;
+                let pipe = self.usbd.open_pipe(args[0] as u32, args[1] as u32, endpoint)?;
+                Ok(pipe as i64)
+            }
+
+            SYS_USBD_OPEN_DEFAULT_PIPE => {
+                let pipe = self.usbd.open_pipe(args[0] as u32, args[1] as u32, 0)?;
+                Ok(pipe as i64)
+            }
+
+            SYS_USBD_CLOSE_PIPE => {
+                self.usbd.close_pipe(args[0] as u32, args[1] as u32)?;
+                Ok(0)
+            }
+
+            SYS_USBD_RECEIVE_EVENT => {
+                let event = self.usbd.receive_event(args[0] as u32)?;
+                self.write_guest_u64(args[1] as u32, event.kind)?;
+                self.write_guest_u64(args[2] as u32, event.arg1)?;
+                self.write_guest_u64(args[3] as u32, event.arg2)?;
+                Ok(0)
+            }
+
+            SYS_USBD_ATTACH => {
+                self.usbd.attach(args[0] as u32, args[3] as u32)?;
+                Ok(0)
+            }
+
+            SYS_USBD_TRANSFER_DATA => {
+                let handle = args[0] as u32;
+                let pipe = args[1] as u32;
+                let buf = args[2] as u32;
+                let buf_size = args[3] as u32;
+                let request = args[4] as u32;
+                let setup = match request {
+                    0 => None,
+                    addr => self.read_guest_bytes(addr, 8)?.and_then(|bytes| bytes.try_into().ok()),
+                };
+                let data = match buf_size {
+                    0 => Vec::new(),
+                    len => self.read_guest_bytes(buf, len)?.unwrap_or_default(),
+                };
+                let (id, completion) = self.usbd.transfer(handle, pipe, setup, buf, data)?;
+                if let Some(completion) = completion {
+                    self.write_guest_bytes(completion.buffer, &completion.data)?;
+                }
+                Ok(id as i64)
+            }
+
+            SYS_USBD_GET_TRANSFER_STATUS => {
+                let (status, count) = self.usbd.transfer_status(args[0] as u32, args[1] as u32)?;
+                self.write_guest_u32(args[3] as u32, status)?;
+                self.write_guest_u32(args[4] as u32, count as u32)?;
+                Ok(0)
+            }
+
+            SYS_USBD_GET_DEVICE_SPEED => {
+                let speed = self.usbd.device_speed(args[0] as u32, args[1] as u32)?;
+                self.write_guest_bytes(args[2] as u32, &[speed])?;
+                Ok(0)
             }
 
             _ => {
                 tracing::warn!("Unknown syscall {}", syscall_num);
+                *self.unknown_syscalls.lock().entry(syscall_num).or_insert(0) += 1;
                 Err(KernelError::UnknownSyscall(syscall_num))
             }
         }
@@ -1124,6 +1679,204 @@ mod tests {
         handler.handle(SYS_MEMORY_FREE, &free_args).unwrap();
     }
 
+    #[test]
+    fn test_tty_write() {
+        let guest = GuestMemory::new().unwrap();
+        let buf = guest.allocate(0x1000, 0x1000, oc_memory::PageFlags::RW).unwrap();
+        guest.write_bytes(buf, b"hello\n").unwrap();
+        let mut handler = SyscallHandler::new();
+        handler.set_guest_memory(guest.clone());
+
+        let mut args = [0u64; 8];
+        args[1] = buf as u64;
+        args[2] = 6;
+        args[3] = (buf + 0x100) as u64;
+        assert_eq!(handler.handle(SYS_TTY_WRITE, &args).unwrap(), 0);
+        assert_eq!(guest.read_be32(buf + 0x100).unwrap(), 6);
+        handler.handle(SYS_TTY_WRITE, &args).unwrap();
+        assert_eq!(handler.tty_output(), "hello\nhello\n");
+    }
+
+    #[test]
+    fn test_unknown_syscalls_counted() {
+        let handler = SyscallHandler::new();
+        let args = [0u64; 8];
+        assert!(handler.handle(999, &args).is_err());
+        assert!(handler.handle(999, &args).is_err());
+        assert!(handler.handle(998, &args).is_err());
+        assert_eq!(handler.unknown_syscalls(), [(998, 1), (999, 2)]);
+    }
+
+    #[test]
+    fn test_user_memory_size_written_to_guest() {
+        let guest = GuestMemory::new().unwrap();
+        let info = guest.allocate(0x1000, 0x1000, oc_memory::PageFlags::RW).unwrap();
+        let mut handler = SyscallHandler::new();
+        handler.set_guest_memory(guest.clone());
+
+        let mut args = [0u64; 8];
+        args[0] = 0x10000;
+        handler.handle(SYS_MEMORY_ALLOCATE, &args).unwrap();
+
+        args[0] = info as u64;
+        assert_eq!(handler.handle(SYS_MEMORY_GET_USER_MEMORY_SIZE, &args).unwrap(), 0);
+        let total = guest.read_be32(info).unwrap();
+        assert_eq!(total as usize, crate::memory::USER_MEMORY_SIZE);
+        assert_eq!(guest.read_be32(info + 4).unwrap(), total - 0x10000);
+
+        // A bad pointer is reported instead of silently ignored
+        args[0] = 0x10;
+        assert!(matches!(
+            handler.handle(SYS_MEMORY_GET_USER_MEMORY_SIZE, &args),
+            Err(KernelError::InvalidAddress(0x10))
+        ));
+    }
+
+    #[test]
+    fn test_console_syscalls_written_to_guest() {
+        let guest = GuestMemory::new().unwrap();
+        let buf = guest.allocate(0x1000, 0x1000, oc_memory::PageFlags::RW).unwrap();
+        let mut handler = SyscallHandler::new();
+        handler.set_guest_memory(guest.clone());
+        handler.set_console(ConsoleIdentity::new(oc_core::config::ConsoleRegion::Europe));
+
+        let mut args = [0u64; 8];
+        args[0] = buf as u64;
+        assert_eq!(handler.handle(SYS_SS_GET_CONSOLE_ID, &args).unwrap(), 0);
+        let idps = guest.read_bytes(buf, 16).unwrap();
+        assert_eq!(idps[5], 0x85);
+        assert_eq!(idps, handler.console().idps());
+
+        handler.handle(SYS_SS_GET_BOOT_DEVICE, &args).unwrap();
+        assert_eq!(guest.read_be64(buf).unwrap(), ss::BOOT_DEVICE_FLASH);
+
+        args[0] = 1;
+        args[2] = buf as u64 + 8;
+        handler.handle(SYS_SS_ACCESS_CONTROL_ENGINE, &args).unwrap();
+        assert_eq!(guest.read_be64(buf + 8).unwrap(), ss::GAME_AUTHORITY_ID);
+
+        // The LED device keeps what was written, other devices are rejected
+        let args = [0, 0b11, 0b01, 0, 0, 0, 0, 0];
+        handler.handle(SYS_GPIO_SET, &args).unwrap();
+        let args = [0, buf as u64, 0, 0, 0, 0, 0, 0];
+        handler.handle(SYS_GPIO_GET, &args).unwrap();
+        assert_eq!(guest.read_be64(buf).unwrap(), 0b01);
+        assert!(handler.handle(SYS_GPIO_GET, &[5, buf as u64, 0, 0, 0, 0, 0, 0]).is_err());
+
+        // Config handles are opened on an existing event queue
+        let queue = handler.handle(SYS_EVENT_QUEUE_CREATE, &[16, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+        let args = [queue as u64, buf as u64, 0, 0, 0, 0, 0, 0];
+        handler.handle(SYS_CONFIG_OPEN, &args).unwrap();
+        let config_id = guest.read_be32(buf).unwrap();
+        let args = [config_id as u64, 0x11, 0, 1, 0, 0, buf as u64 + 4, 0];
+        handler.handle(SYS_CONFIG_REGISTER_SERVICE, &args).unwrap();
+        let service = guest.read_be32(buf + 4).unwrap();
+        handler.handle(SYS_CONFIG_UNREGISTER_SERVICE, &[config_id as u64, service as u64, 0, 0, 0, 0, 0, 0]).unwrap();
+        handler.handle(SYS_CONFIG_CLOSE, &[config_id as u64, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+        assert!(handler.unknown_syscalls().is_empty());
+    }
+
+    #[test]
+    fn test_usbd_syscalls_written_to_guest() {
+        use crate::usbd::{UsbBackend, UsbHotplug, UsbTransfer, UsbTransferResult, USBD_EVENT_ATTACH};
+
+        /// Device that only answers GET_DESCRIPTOR(Device)
+        struct Device(bool);
+
+        impl UsbBackend for Device {
+            fn name(&self) -> &'static str {
+                "test"
+            }
+
+            fn poll(&mut self) -> Vec<UsbHotplug> {
+                if std::mem::replace(&mut self.0, true) {
+                    Vec::new()
+                } else {
+                    vec![UsbHotplug::Attached(0)]
+                }
+            }
+
+            fn descriptors(&self, _device: u32) -> Option<Vec<u8>> {
+                Some(vec![18, 0x01, 0x00, 0x02, 0, 0, 0, 64, 0x4C, 0x05, 0x02, 0x00, 0, 0, 0, 0, 0, 1])
+            }
+
+            fn transfer(&mut self, _device: u32, transfer: &UsbTransfer) -> Option<UsbTransferResult> {
+                match transfer {
+                    UsbTransfer::Control { setup, .. } if setup.request == 0x06 => {
+                        self.descriptors(0).map(UsbTransferResult::data)
+                    }
+                    _ => Some(UsbTransferResult::stall()),
+                }
+            }
+        }
+
+        let guest = GuestMemory::new().unwrap();
+        let buf = guest.allocate(0x1000, 0x1000, oc_memory::PageFlags::RW).unwrap();
+        let mut handler = SyscallHandler::new();
+        handler.set_guest_memory(guest.clone());
+        handler.usbd().set_backends(vec![Box::new(Device(false))]);
+        handler.poll_usb();
+
+        let call = |num, args: &[u64]| {
+            let mut all = [0u64; 8];
+            all[..args.len()].copy_from_slice(args);
+            handler.handle(num, &all)
+        };
+        call(SYS_USBD_INITIALIZE, &[buf as u64]).unwrap();
+        let handle = guest.read_be32(buf).unwrap() as u64;
+        assert_eq!(call(SYS_USBD_GET_DEVICE_LIST, &[handle, buf as u64 + 0x10, 4]).unwrap(), 1);
+        let device = guest.read_bytes(buf + 0x10, 1).unwrap()[0] as u64;
+        assert_eq!(call(SYS_USBD_GET_DESCRIPTOR_SIZE, &[handle, device]).unwrap(), 18);
+
+        guest.write_bytes(buf + 0x20, b"Buzz\0").unwrap();
+        call(SYS_USBD_REGISTER_EXTRA_LDD, &[handle, buf as u64 + 0x20, 5, 0x054C, 0x0002, 0x0002]).unwrap();
+        call(SYS_USBD_RECEIVE_EVENT, &[handle, buf as u64 + 0x30, buf as u64 + 0x38, buf as u64 + 0x40]).unwrap();
+        assert_eq!(guest.read_be64(buf + 0x30).unwrap(), USBD_EVENT_ATTACH);
+        assert_eq!(guest.read_be64(buf + 0x38).unwrap(), device);
+
+        // GET_DESCRIPTOR(Device) lands in the transfer buffer
+        let pipe = call(SYS_USBD_OPEN_DEFAULT_PIPE, &[handle, device]).unwrap() as u64;
+        guest.write_bytes(buf + 0x50, &[0x80, 0x06, 0x00, 0x01, 0x00, 0x00, 18, 0]).unwrap();
+        let transfer = call(SYS_USBD_TRANSFER_DATA, &[handle, pipe, buf as u64 + 0x100, 18, buf as u64 + 0x50, 0]);
+        let transfer = transfer.unwrap() as u64;
+        assert_eq!(guest.read_bytes(buf + 0x108, 4).unwrap(), [0x4C, 0x05, 0x02, 0x00]);
+        call(SYS_USBD_GET_TRANSFER_STATUS, &[handle, transfer, 0, buf as u64 + 0x60, buf as u64 + 0x64]).unwrap();
+        assert_eq!(guest.read_be32(buf + 0x60).unwrap(), 0);
+        assert_eq!(guest.read_be32(buf + 0x64).unwrap(), 18);
+
+        call(SYS_USBD_CLOSE_PIPE, &[handle, pipe]).unwrap();
+        call(SYS_USBD_FINALIZE, &[handle]).unwrap();
+        assert!(call(SYS_USBD_OPEN_DEFAULT_PIPE, &[handle, device]).is_err());
+        assert!(handler.unknown_syscalls().is_empty());
+    }
+
+    #[test]
+    fn test_interrupt_syscalls() {
+        let guest = GuestMemory::new().unwrap();
+        let buf = guest.allocate(0x1000, 0x1000, oc_memory::PageFlags::RW).unwrap();
+        let mut handler = SyscallHandler::new();
+        handler.set_guest_memory(guest.clone());
+
+        let tag = interrupt::create_tag(handler.object_manager(), interrupt::InterruptSource::Rsx);
+        let thread = handler.handle(SYS_PPU_THREAD_CREATE, &[0x20_0000, 0, 50, 0, 0, 0, 0, 0]).unwrap();
+        let args = [buf as u64, tag as u64, thread as u64, 9, 0, 0, 0, 0];
+        handler.handle(SYS_INTERRUPT_THREAD_ESTABLISH, &args).unwrap();
+        let handle = guest.read_be32(buf).unwrap();
+
+        assert!(interrupt::raise(handler.object_manager(), interrupt::InterruptSource::Rsx, 1, None));
+        let dispatch = interrupt::take_dispatches(handler.object_manager())[0];
+        assert_eq!((dispatch.entry, dispatch.arg, dispatch.priority), (0x20_0000, 9, 50));
+
+        // eoi ends the handler of the calling thread
+        handler.thread_manager().set_current(thread as u64);
+        handler.handle(SYS_INTERRUPT_THREAD_EOI, &[0; 8]).unwrap();
+        assert!(handler.handle(SYS_INTERRUPT_THREAD_EOI, &[0; 8]).is_err());
+
+        assert!(handler.handle(SYS_INTERRUPT_TAG_DESTROY, &[tag as u64, 0, 0, 0, 0, 0, 0, 0]).is_err());
+        handler.handle(SYS_INTERRUPT_THREAD_DISESTABLISH, &[handle as u64, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+        handler.handle(SYS_INTERRUPT_TAG_DESTROY, &[tag as u64, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+    }
+
     #[test]
     fn test_time_syscalls() {
         let handler = SyscallHandler::new();
@@ -1358,5 +2111,51 @@ mod tests {
         let new_tls = handler.handle(SYS_PPU_THREAD_GET_TLS, &tls_args).unwrap();
         assert_eq!(new_tls, 0xDEADBEEF);
     }
-}
 
+    #[test]
+    fn test_save_state_round_trip() {
+        use crate::objects::ObjectType;
+        use crate::sync::event::{Event, EventQueue, EventQueueAttributes};
+        use crate::sync::mutex::MutexAttributes;
+
+        let handler = SyscallHandler::new();
+        let mut args = [0u64; 8];
+        args[0] = 0x1000;
+        args[2] = 1000;
+        args[3] = 0x4000;
+        let thread_id = handler.handle(SYS_PPU_THREAD_CREATE, &args).unwrap() as u64;
+        handler.thread_manager().get(thread_id).unwrap().set_tls_value(2, 0xABCD);
+
+        let objects = handler.object_manager();
+        let mutex_id = mutex::syscalls::sys_mutex_create(objects, MutexAttributes::default()).unwrap();
+        mutex::syscalls::sys_mutex_lock(objects, mutex_id, thread_id).unwrap();
+        let queue_id = event::syscalls::sys_event_queue_create(objects, EventQueueAttributes::default(), 8).unwrap();
+        let queue: Arc<EventQueue> = objects.get(queue_id).unwrap();
+        queue.send(Event { source: 1, data1: 2, data2: 3, data3: 4 }).unwrap();
+        let addr = handler.memory_manager().allocate(0x10000, 0x10000, 0, 0).unwrap();
+
+        let mut w = StateWriter::new();
+        handler.save_state(&mut w);
+        let data = w.into_bytes();
+
+        let loaded = SyscallHandler::new();
+        loaded.load_state(&mut StateReader::new(&data)).unwrap();
+        assert_eq!(loaded.thread_manager().get(thread_id).unwrap().get_tls_value(2), Some(0xABCD));
+        assert_eq!(loaded.thread_manager().next_id(), handler.thread_manager().next_id());
+        assert_eq!(loaded.object_manager().debug_snapshot(), objects.debug_snapshot());
+        assert_eq!(loaded.object_manager().count_by_type(ObjectType::EventQueue), 1);
+        assert_eq!(loaded.object_manager().next_id(), objects.next_id());
+        assert_eq!(loaded.memory_manager().get_allocation(addr), Some((addr, 0x10000)));
+
+        // An unknown object type tag is rejected
+        let mut w = StateWriter::new();
+        handler.process_manager().save_state(&mut w);
+        handler.thread_manager().save_state(&mut w);
+        w.u32(1);
+        w.count(1);
+        w.u32(1);
+        w.u8(ObjectType::ALL.len() as u8);
+        w.bytes(&[]);
+        assert!(SyscallHandler::new().load_state(&mut StateReader::new(&w.into_bytes())).is_err());
+    }
+}
@@ -112,6 +112,54 @@ pub const SYS_FS_RENAME: u64 = 829;
 // TTY
 pub const SYS_TTY_WRITE: u64 = 403;
 
+// Config
+pub const SYS_CONFIG_OPEN: u64 = 516;
+pub const SYS_CONFIG_CLOSE: u64 = 517;
+pub const SYS_CONFIG_ADD_SERVICE_LISTENER: u64 = 519;
+pub const SYS_CONFIG_REMOVE_SERVICE_LISTENER: u64 = 520;
+pub const SYS_CONFIG_REGISTER_SERVICE: u64 = 521;
+pub const SYS_CONFIG_UNREGISTER_SERVICE: u64 = 522;
+
+// GPIO
+pub const SYS_GPIO_SET: u64 = 630;
+pub const SYS_GPIO_GET: u64 = 631;
+
+// Security services
+pub const SYS_SS_GET_CONSOLE_ID: u64 = 870;
+pub const SYS_SS_ACCESS_CONTROL_ENGINE: u64 = 871;
+pub const SYS_SS_GET_OPEN_PSID: u64 = 872;
+pub const SYS_SS_GET_CACHE_OF_PRODUCT_MODE: u64 = 873;
+pub const SYS_SS_GET_CACHE_OF_FLASH_EXT_FLAG: u64 = 874;
+pub const SYS_SS_GET_BOOT_DEVICE: u64 = 875;
+
+// Bluetooth
+pub const SYS_USBBTAUDIO_INITIALIZE: u64 = 570;
+pub const SYS_USBBTAUDIO_FINALIZE: u64 = 571;
+pub const SYS_USBBTAUDIO_DISCOVERY: u64 = 572;
+pub const SYS_USBBTAUDIO_CANCEL_DISCOVERY: u64 = 573;
+pub const SYS_USBBTAUDIO_PAIRING: u64 = 574;
+pub const SYS_USBBTAUDIO_SET_PASSKEY: u64 = 575;
+pub const SYS_USBBTAUDIO_CONNECT: u64 = 576;
+pub const SYS_USBBTAUDIO_DISCONNECT: u64 = 577;
+
+// USB
+pub const SYS_USBD_INITIALIZE: u64 = 530;
+pub const SYS_USBD_FINALIZE: u64 = 531;
+pub const SYS_USBD_GET_DEVICE_LIST: u64 = 532;
+pub const SYS_USBD_GET_DESCRIPTOR_SIZE: u64 = 533;
+pub const SYS_USBD_GET_DESCRIPTOR: u64 = 534;
+pub const SYS_USBD_REGISTER_LDD: u64 = 535;
+pub const SYS_USBD_UNREGISTER_LDD: u64 = 536;
+pub const SYS_USBD_OPEN_PIPE: u64 = 537;
+pub const SYS_USBD_OPEN_DEFAULT_PIPE: u64 = 538;
+pub const SYS_USBD_CLOSE_PIPE: u64 = 539;
+pub const SYS_USBD_RECEIVE_EVENT: u64 = 540;
+pub const SYS_USBD_ATTACH: u64 = 542;
+pub const SYS_USBD_TRANSFER_DATA: u64 = 543;
+pub const SYS_USBD_GET_TRANSFER_STATUS: u64 = 545;
+pub const SYS_USBD_GET_DEVICE_SPEED: u64 = 556;
+pub const SYS_USBD_REGISTER_EXTRA_LDD: u64 = 559;
+
 // PRX module
 pub const SYS_PRX_LOAD_MODULE: u64 = 451;
 pub const SYS_PRX_START_MODULE: u64 = 452;
@@ -146,3 +194,9 @@ pub const SYS_TIMER_DISCONNECT_EVENT_QUEUE: u64 = 206;
 pub const SYS_TIMER_USLEEP: u64 = 257;
 pub const SYS_TIMER_SLEEP: u64 = 256;
 
+// Interrupts
+pub const SYS_INTERRUPT_TAG_DESTROY: u64 = 81;
+pub const SYS_INTERRUPT_THREAD_ESTABLISH: u64 = 84;
+pub const SYS_INTERRUPT_THREAD_EOI: u64 = 88;
+pub const SYS_INTERRUPT_THREAD_DISESTABLISH: u64 = 89;
+
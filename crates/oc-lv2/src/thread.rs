@@ -1,8 +1,10 @@
 //! Thread management (sys_ppu_thread_*)
 
 use oc_core::error::KernelError;
+use oc_core::savestate::{invalid, StateReader, StateWriter};
 use parking_lot::Mutex;
 use std::collections::HashMap;
+use std::io;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
@@ -45,6 +47,30 @@ pub enum ThreadState {
     Terminated,
 }
 
+impl ThreadState {
+    /// Every state, in savestate tag order
+    pub const ALL: [ThreadState; 5] = [
+        Self::Ready,
+        Self::Running,
+        Self::Waiting,
+        Self::Suspended,
+        Self::Terminated,
+    ];
+}
+
+/// Debugger view of a PPU thread
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadDebugInfo {
+    pub id: ThreadId,
+    pub name: String,
+    pub state: ThreadState,
+    pub priority: u32,
+    pub affinity_mask: u64,
+    pub entry_point: u64,
+    pub stack_addr: u64,
+    pub stack_size: usize,
+}
+
 /// PPU Thread
 pub struct Thread {
     id: ThreadId,
@@ -55,10 +81,10 @@ pub struct Thread {
 struct ThreadInner {
     state: ThreadState,
     priority: u32,
-    _stack_addr: u64,
-    _stack_size: usize,
-    _entry_point: u64,
-    _attributes: ThreadAttributes,
+    stack_addr: u64,
+    stack_size: usize,
+    entry_point: u64,
+    attributes: ThreadAttributes,
     /// CPU affinity mask (bit N = can run on CPU N)
     affinity_mask: u64,
     /// Thread-local storage pointer
@@ -79,10 +105,10 @@ impl Thread {
             inner: Mutex::new(ThreadInner {
                 state: ThreadState::Ready,
                 priority: attributes.priority,
-                _stack_addr: stack_addr,
-                _stack_size: attributes.stack_size,
-                _entry_point: entry_point,
-                _attributes: attributes,
+                stack_addr,
+                stack_size: attributes.stack_size,
+                entry_point,
+                attributes,
                 affinity_mask: 0xFF, // All CPUs by default (8 cores max)
                 tls_pointer: 0,
                 tls_data: HashMap::new(),
@@ -154,6 +180,21 @@ impl Thread {
         self.inner.lock().tls_data.remove(&key).is_some()
     }
 
+    /// State shown by the kernel object inspector
+    pub fn debug_info(&self) -> ThreadDebugInfo {
+        let inner = self.inner.lock();
+        ThreadDebugInfo {
+            id: self.id,
+            name: inner.attributes.name.clone(),
+            state: inner.state,
+            priority: inner.priority,
+            affinity_mask: inner.affinity_mask,
+            entry_point: inner.entry_point,
+            stack_addr: inner.stack_addr,
+            stack_size: inner.stack_size,
+        }
+    }
+
     pub fn join(&self) -> Result<(), KernelError> {
         let state = self.state();
         if state == ThreadState::Terminated {
@@ -280,6 +321,91 @@ impl ThreadManager {
     pub fn count(&self) -> usize {
         self.threads.lock().len()
     }
+
+    /// Debug info of every live thread, ordered by ID
+    pub fn debug_snapshot(&self) -> Vec<ThreadDebugInfo> {
+        let mut threads: Vec<ThreadDebugInfo> =
+            self.threads.lock().values().map(|thread| thread.debug_info()).collect();
+        threads.sort_by_key(|thread| thread.id);
+        threads
+    }
+
+    /// Save every thread and the ID and stack counters
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.u64(self.next_id.load(Ordering::Relaxed));
+        w.u64(self.next_stack_index.load(Ordering::Relaxed));
+        w.option(*self.current_thread.lock(), |w, id| w.u64(id));
+
+        let mut threads: Vec<Arc<Thread>> = self.threads.lock().values().cloned().collect();
+        threads.sort_by_key(|thread| thread.id);
+        w.count(threads.len());
+        for thread in threads {
+            let inner = thread.inner.lock();
+            w.u64(thread.id);
+            w.u8(ThreadState::ALL.iter().position(|&state| state == inner.state).unwrap_or_default() as u8);
+            w.u32(inner.priority);
+            w.u64(inner.stack_addr);
+            w.u64(inner.stack_size as u64);
+            w.u64(inner.entry_point);
+            w.u32(inner.attributes.priority);
+            w.u64(inner.attributes.stack_size as u64);
+            w.str(&inner.attributes.name);
+            w.u64(inner.affinity_mask);
+            w.u64(inner.tls_pointer);
+            let mut tls: Vec<(u32, u64)> = inner.tls_data.iter().map(|(&key, &value)| (key, value)).collect();
+            tls.sort_unstable();
+            w.count(tls.len());
+            for (key, value) in tls {
+                w.u32(key);
+                w.u64(value);
+            }
+        }
+    }
+
+    /// Replace all threads with those written by [`ThreadManager::save_state`]
+    pub fn load_state(&self, r: &mut StateReader) -> io::Result<()> {
+        let next_id = r.u64()?;
+        let next_stack_index = r.u64()?;
+        let current = r.option(|r| r.u64())?;
+
+        let mut threads = HashMap::new();
+        for _ in 0..r.count()? {
+            let id = r.u64()?;
+            let state = r.u8()?;
+            let state = *ThreadState::ALL
+                .get(state as usize)
+                .ok_or_else(|| invalid(&format!("invalid thread state {}", state)))?;
+            let priority = r.u32()?;
+            let stack_addr = r.u64()?;
+            let stack_size = r.u64()? as usize;
+            let entry_point = r.u64()?;
+            let attributes = ThreadAttributes {
+                priority: r.u32()?,
+                stack_size: r.u64()? as usize,
+                name: r.str()?,
+            };
+            let thread = Thread::new(id, entry_point, stack_addr, attributes);
+            {
+                let mut inner = thread.inner.lock();
+                inner.state = state;
+                inner.priority = priority;
+                inner.stack_size = stack_size;
+                inner.affinity_mask = r.u64()?;
+                inner.tls_pointer = r.u64()?;
+                for _ in 0..r.count()? {
+                    let key = r.u32()?;
+                    inner.tls_data.insert(key, r.u64()?);
+                }
+            }
+            threads.insert(id, Arc::new(thread));
+        }
+
+        self.next_id.store(next_id, Ordering::Relaxed);
+        self.next_stack_index.store(next_stack_index, Ordering::Relaxed);
+        *self.current_thread.lock() = current;
+        *self.threads.lock() = threads;
+        Ok(())
+    }
 }
 
 impl Default for ThreadManager {
@@ -478,6 +604,13 @@ mod tests {
 
         let thread = manager.get(thread_id).unwrap();
         assert_eq!(thread.state(), ThreadState::Ready);
+
+        let snapshot = manager.debug_snapshot();
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[0].id, thread_id);
+        assert_eq!(snapshot[0].name, "TestThread");
+        assert_eq!(snapshot[0].priority, 1000);
+        assert_eq!(snapshot[0].entry_point, 0x1000);
     }
 
     #[test]
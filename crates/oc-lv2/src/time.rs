@@ -1,17 +1,16 @@
 //! Time functions (sys_time_*)
+//!
+//! Guest time comes from the emulator's [`TimeBase`] rather than the host
+//! clock, so it stops while the emulator is paused and follows its speed.
 
 use oc_core::error::KernelError;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-
-/// Timebase frequency for PS3 (79.8 MHz)
-pub const TIMEBASE_FREQUENCY: u64 = 79_800_000;
+pub use oc_core::time_base::TIMEBASE_FREQUENCY;
+use oc_core::time_base::TimeBase;
+use std::time::Duration;
 
 /// Get current system time in microseconds since UNIX epoch
-pub fn get_system_time() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_micros() as u64
+pub fn get_system_time(clock: &TimeBase) -> u64 {
+    clock.system_time_us()
 }
 
 /// Get timebase frequency
@@ -19,13 +18,13 @@ pub fn get_timebase_frequency() -> u64 {
     TIMEBASE_FREQUENCY
 }
 
-/// Sleep for a given number of microseconds
-pub fn usleep(usec: u64) -> Result<(), KernelError> {
+/// Sleep for a given number of microseconds of guest time
+pub fn usleep(clock: &TimeBase, usec: u64) -> Result<(), KernelError> {
     if usec == 0 {
         return Ok(());
     }
 
-    let duration = Duration::from_micros(usec);
+    let duration = clock.host_duration(Duration::from_micros(usec));
     std::thread::sleep(duration);
     Ok(())
 }
@@ -35,8 +34,8 @@ pub mod syscalls {
     use super::*;
 
     /// sys_time_get_current_time
-    pub fn sys_time_get_current_time() -> u64 {
-        get_system_time()
+    pub fn sys_time_get_current_time(clock: &TimeBase) -> u64 {
+        get_system_time(clock)
     }
 
     /// sys_time_get_timebase_frequency
@@ -45,28 +44,23 @@ pub mod syscalls {
     }
 
     /// sys_time_get_system_time (alternative name)
-    pub fn sys_time_get_system_time() -> u64 {
-        get_system_time()
+    pub fn sys_time_get_system_time(clock: &TimeBase) -> u64 {
+        get_system_time(clock)
     }
 
     /// sys_time_usleep
-    pub fn sys_time_usleep(usec: u64) -> Result<(), KernelError> {
-        usleep(usec)
+    pub fn sys_time_usleep(clock: &TimeBase, usec: u64) -> Result<(), KernelError> {
+        usleep(clock, usec)
     }
 
     /// sys_time_sleep (sleep in seconds)
-    pub fn sys_time_sleep(seconds: u64) -> Result<(), KernelError> {
-        usleep(seconds * 1_000_000)
+    pub fn sys_time_sleep(clock: &TimeBase, seconds: u64) -> Result<(), KernelError> {
+        usleep(clock, seconds * 1_000_000)
     }
 
-    /// Get current timebase value (simulated)
-    pub fn sys_time_get_timebase() -> u64 {
-        // Simulate timebase counter
-        // In reality, this would be a hardware counter
-        let time = get_system_time();
-        // Convert microseconds to timebase ticks
-        // Use u128 to avoid overflow
-        ((time as u128 * TIMEBASE_FREQUENCY as u128) / 1_000_000) as u64
+    /// Get current timebase value
+    pub fn sys_time_get_timebase(clock: &TimeBase) -> u64 {
+        clock.ticks()
     }
 }
 
@@ -76,11 +70,12 @@ mod tests {
 
     #[test]
     fn test_get_system_time() {
-        let time = syscalls::sys_time_get_current_time();
+        let clock = TimeBase::new();
+        let time = syscalls::sys_time_get_current_time(&clock);
         assert!(time > 0);
 
         // Time should advance
-        let time2 = syscalls::sys_time_get_current_time();
+        let time2 = syscalls::sys_time_get_current_time(&clock);
         assert!(time2 >= time);
     }
 
@@ -93,12 +88,13 @@ mod tests {
 
     #[test]
     fn test_usleep() {
-        let start = syscalls::sys_time_get_current_time();
+        let clock = TimeBase::new();
+        let start = syscalls::sys_time_get_current_time(&clock);
 
         // Sleep for 10ms
-        syscalls::sys_time_usleep(10_000).unwrap();
+        syscalls::sys_time_usleep(&clock, 10_000).unwrap();
 
-        let end = syscalls::sys_time_get_current_time();
+        let end = syscalls::sys_time_get_current_time(&clock);
         let elapsed = end - start;
 
         // Should have slept at least 10ms (10,000 microseconds)
@@ -108,12 +104,13 @@ mod tests {
 
     #[test]
     fn test_sleep_seconds() {
-        let start = syscalls::sys_time_get_current_time();
+        let clock = TimeBase::new();
+        let start = syscalls::sys_time_get_current_time(&clock);
 
         // Sleep for 0 seconds (should return immediately)
-        syscalls::sys_time_sleep(0).unwrap();
+        syscalls::sys_time_sleep(&clock, 0).unwrap();
 
-        let end = syscalls::sys_time_get_current_time();
+        let end = syscalls::sys_time_get_current_time(&clock);
         let elapsed = end - start;
 
         // Should be very quick (less than 100ms)
@@ -122,13 +119,19 @@ mod tests {
 
     #[test]
     fn test_timebase() {
-        let tb1 = syscalls::sys_time_get_timebase();
-        assert!(tb1 > 0);
+        let clock = TimeBase::new();
+        let tb1 = syscalls::sys_time_get_timebase(&clock);
 
         // Timebase should advance
         std::thread::sleep(Duration::from_millis(1));
-        let tb2 = syscalls::sys_time_get_timebase();
+        let tb2 = syscalls::sys_time_get_timebase(&clock);
         assert!(tb2 > tb1);
+
+        // and stand still while the emulator is paused
+        clock.pause();
+        let tb3 = syscalls::sys_time_get_timebase(&clock);
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(syscalls::sys_time_get_timebase(&clock), tb3);
     }
 
     #[test]
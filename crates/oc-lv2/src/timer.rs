@@ -1,12 +1,17 @@
 //! Timer (sys_timer_*)
 //!
-//! High-resolution timers for the PS3 LV2 kernel.
+//! High-resolution timers for the PS3 LV2 kernel, running on the emulated
+//! clock so they stop while the emulator is paused and follow its speed.
 
-use crate::objects::{KernelObject, ObjectId, ObjectManager, ObjectType};
+use crate::objects::{KernelObject, ObjectDebugInfo, ObjectId, ObjectManager, ObjectType};
+use crate::sync::event::{Event, EventQueue};
 use oc_core::error::KernelError;
+use oc_core::savestate::{invalid, StateReader, StateWriter};
+use oc_core::time_base::TimeBase;
 use parking_lot::Mutex;
+use std::io;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 /// Timer states
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -64,14 +69,16 @@ pub struct Timer {
     id: ObjectId,
     state: Mutex<TimerInnerState>,
     attributes: TimerAttributes,
+    /// Emulated clock the timer runs on
+    clock: TimeBase,
 }
 
 #[derive(Debug)]
 struct TimerInnerState {
     /// Current timer state
     state: TimerState,
-    /// When the timer was started
-    start_time: Option<Instant>,
+    /// Guest time the timer was started at
+    start_time: Option<Duration>,
     /// Timer duration
     duration: Duration,
     /// Period for periodic timers
@@ -85,7 +92,7 @@ struct TimerInnerState {
 }
 
 impl Timer {
-    pub fn new(id: ObjectId, attributes: TimerAttributes) -> Self {
+    pub fn new(id: ObjectId, attributes: TimerAttributes, clock: TimeBase) -> Self {
         Self {
             id,
             state: Mutex::new(TimerInnerState {
@@ -98,15 +105,21 @@ impl Timer {
                 expiration_count: 0,
             }),
             attributes,
+            clock,
         }
     }
 
+    /// Guest time since `start_time`
+    fn elapsed(&self, start_time: Duration) -> Duration {
+        self.clock.now().saturating_sub(start_time)
+    }
+
     /// Start the timer with the specified duration
     pub fn start(&self, duration_usec: u64, period_usec: u64) -> Result<(), KernelError> {
         let mut state = self.state.lock();
         
         state.state = TimerState::Running;
-        state.start_time = Some(Instant::now());
+        state.start_time = Some(self.clock.now());
         state.duration = Duration::from_micros(duration_usec);
         state.period = Duration::from_micros(period_usec);
         state.expiration_count = 0;
@@ -134,13 +147,13 @@ impl Timer {
         }
         
         if let Some(start_time) = state.start_time {
-            let elapsed = start_time.elapsed();
+            let elapsed = self.elapsed(start_time);
             if elapsed >= state.duration {
                 state.expiration_count += 1;
                 
                 if self.attributes.timer_type == TimerType::Periodic && !state.period.is_zero() {
                     // Reset for next period
-                    state.start_time = Some(Instant::now());
+                    state.start_time = Some(self.clock.now());
                     state.duration = state.period;
                 } else {
                     state.state = TimerState::Expired;
@@ -177,7 +190,7 @@ impl Timer {
         }
         
         if let Some(start_time) = state.start_time {
-            let elapsed = start_time.elapsed();
+            let elapsed = self.elapsed(start_time);
             if elapsed < state.duration {
                 return (state.duration - elapsed).as_micros() as u64;
             }
@@ -213,6 +226,53 @@ impl Timer {
     pub fn get_state(&self) -> TimerState {
         self.state.lock().state
     }
+
+    /// Check the timer, returning the event queue and event to post if it
+    /// expired since the last check
+    pub fn poll(&self) -> Option<(ObjectId, Event)> {
+        let before = self.get_expiration_count();
+        self.check();
+        let state = self.state.lock();
+        if state.expiration_count == before {
+            return None;
+        }
+        let event = Event {
+            source: state.event_source,
+            data1: 0,
+            data2: 0,
+            data3: self.clock.micros(),
+        };
+        state.event_queue_id.map(|queue| (queue, event))
+    }
+
+    /// Recreate a timer written by [`KernelObject::save_state`]
+    ///
+    /// A running timer resumes with the time it had left when saved.
+    pub(crate) fn load_state(id: ObjectId, r: &mut StateReader, clock: TimeBase) -> io::Result<Self> {
+        let name = r.u32()?;
+        let timer_type = match r.u8()? {
+            0 => TimerType::OneShot,
+            1 => TimerType::Periodic,
+            value => return Err(invalid(&format!("invalid timer type {}", value))),
+        };
+        let timer = Self::new(id, TimerAttributes { name, timer_type }, clock);
+        {
+            let mut state = timer.state.lock();
+            state.state = match r.u8()? {
+                0 => TimerState::Stopped,
+                1 => TimerState::Running,
+                2 => TimerState::Expired,
+                value => return Err(invalid(&format!("invalid timer state {}", value))),
+            };
+            state.duration = Duration::from_micros(r.u64()?);
+            state.period = Duration::from_micros(r.u64()?);
+            state.start_time = (state.state == TimerState::Running).then(|| timer.clock.now());
+            state.event_queue_id = r.option(|r| r.u32())?;
+            state.event_source = r.u64()?;
+            state.expiration_count = r.u64()?;
+        }
+        Ok(timer)
+    }
 }
 
 impl KernelObject for Timer {
@@ -227,6 +287,59 @@ impl KernelObject for Timer {
     fn as_any(self: Arc<Self>) -> Arc<dyn std::any::Any + Send + Sync> {
         self
     }
+
+    fn debug_info(&self) -> ObjectDebugInfo {
+        let state = self.state.lock();
+        let info = ObjectDebugInfo::new(self.id, ObjectType::Timer)
+            .detail("state", format!("{:?}", state.state))
+            .detail("expirations", state.expiration_count);
+        match state.event_queue_id {
+            Some(queue) => info.detail("event queue", queue),
+            None => info,
+        }
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u32(self.attributes.name);
+        w.u8(match self.attributes.timer_type {
+            TimerType::OneShot => 0,
+            TimerType::Periodic => 1,
+        });
+        let state = self.state.lock();
+        w.u8(match state.state {
+            TimerState::Stopped => 0,
+            TimerState::Running => 1,
+            TimerState::Expired => 2,
+        });
+        w.u64(self.get_remaining_internal(&state));
+        w.u64(state.period.as_micros() as u64);
+        w.option(state.event_queue_id, |w, queue| w.u32(queue));
+        w.u64(state.event_source);
+        w.u64(state.expiration_count);
+    }
+}
+
+/// Expire the timers that are due, posting their events to the connected queues
+///
+/// Returns how many events were posted. The kernel calls this from its
+/// decrementer handler, so timers fire while guest code runs.
+pub fn service_timers(manager: &ObjectManager) -> usize {
+    let timers = manager
+        .list()
+        .into_iter()
+        .filter(|object| object.object_type() == ObjectType::Timer)
+        .filter_map(|object| object.as_any().downcast::<Timer>().ok());
+    let mut posted = 0;
+    for timer in timers {
+        let Some((queue_id, event)) = timer.poll() else {
+            continue;
+        };
+        match manager.get::<EventQueue>(queue_id).and_then(|queue| queue.send(event)) {
+            Ok(()) => posted += 1,
+            Err(e) => tracing::debug!("Timer {} event to queue {} dropped: {}", timer.id, queue_id, e),
+        }
+    }
+    posted
 }
 
 /// Timer syscall implementations
@@ -239,7 +352,7 @@ pub mod syscalls {
         attributes: TimerAttributes,
     ) -> Result<ObjectId, KernelError> {
         let id = manager.next_id();
-        let timer = Arc::new(Timer::new(id, attributes));
+        let timer = Arc::new(Timer::new(id, attributes, manager.time_base().clone()));
         manager.register(timer);
         tracing::debug!("Created timer {}", id);
         Ok(id)
@@ -292,7 +405,7 @@ pub mod syscalls {
         event_source: u64,
     ) -> Result<(), KernelError> {
         // Verify event queue exists
-        let _: Arc<crate::sync::event::EventQueue> = manager.get(event_queue_id)?;
+        let _: Arc<EventQueue> = manager.get(event_queue_id)?;
         
         let timer: Arc<Timer> = manager.get(timer_id)?;
         timer.connect(event_queue_id, event_source)
@@ -307,21 +420,21 @@ pub mod syscalls {
         timer.disconnect()
     }
 
-    /// Sleep for a specified duration using high-resolution timing
-    pub fn sys_timer_usleep(duration_usec: u64) -> Result<(), KernelError> {
+    /// Sleep for a specified duration of guest time
+    pub fn sys_timer_usleep(clock: &TimeBase, duration_usec: u64) -> Result<(), KernelError> {
         if duration_usec == 0 {
             return Ok(());
         }
-        std::thread::sleep(Duration::from_micros(duration_usec));
+        std::thread::sleep(clock.host_duration(Duration::from_micros(duration_usec)));
         Ok(())
     }
 
-    /// Sleep for a specified duration in seconds
-    pub fn sys_timer_sleep(duration_sec: u32) -> Result<(), KernelError> {
+    /// Sleep for a specified duration of guest time in seconds
+    pub fn sys_timer_sleep(clock: &TimeBase, duration_sec: u32) -> Result<(), KernelError> {
         if duration_sec == 0 {
             return Ok(());
         }
-        std::thread::sleep(Duration::from_secs(duration_sec as u64));
+        std::thread::sleep(clock.host_duration(Duration::from_secs(duration_sec as u64)));
         Ok(())
     }
 }
@@ -329,6 +442,7 @@ pub mod syscalls {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Instant;
 
     #[test]
     fn test_timer_create_destroy() {
@@ -456,10 +570,55 @@ mod tests {
         syscalls::sys_timer_destroy(&manager, timer_id).unwrap();
     }
 
+    #[test]
+    fn test_timer_follows_emulated_clock() {
+        let manager = ObjectManager::new();
+        manager.time_base().pause();
+        let timer_id = syscalls::sys_timer_create(&manager, TimerAttributes::default()).unwrap();
+        let timer: Arc<Timer> = manager.get(timer_id).unwrap();
+        syscalls::sys_timer_start(&manager, timer_id, 1_000, 0).unwrap();
+
+        // A paused clock holds the timer
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(timer.check(), TimerState::Running);
+        assert_eq!(timer.get_remaining(), 1_000);
+
+        manager.time_base().advance(Duration::from_micros(400));
+        assert_eq!(timer.get_remaining(), 600);
+        manager.time_base().advance(Duration::from_micros(600));
+        assert_eq!(timer.check(), TimerState::Expired);
+    }
+
+    #[test]
+    fn test_service_timers_posts_events() {
+        use crate::sync::event::{syscalls::sys_event_queue_create, EventQueueAttributes};
+
+        let manager = ObjectManager::new();
+        manager.time_base().pause();
+        let queue_id = sys_event_queue_create(&manager, EventQueueAttributes::default(), 8).unwrap();
+        let attributes = TimerAttributes { name: 0, timer_type: TimerType::Periodic };
+        let timer_id = syscalls::sys_timer_create(&manager, attributes).unwrap();
+        syscalls::sys_timer_connect_event_queue(&manager, timer_id, queue_id, 0x55).unwrap();
+        syscalls::sys_timer_start(&manager, timer_id, 100, 100).unwrap();
+        assert_eq!(service_timers(&manager), 0);
+
+        // Each period posts one event
+        manager.time_base().advance(Duration::from_micros(100));
+        assert_eq!(service_timers(&manager), 1);
+        assert_eq!(service_timers(&manager), 0);
+        manager.time_base().advance(Duration::from_micros(100));
+        assert_eq!(service_timers(&manager), 1);
+
+        let queue: Arc<EventQueue> = manager.get(queue_id).unwrap();
+        let event = queue.receive(None).unwrap();
+        assert_eq!(event.source, 0x55);
+        assert_eq!(event.data3, manager.time_base().micros() - 100);
+    }
+
     #[test]
     fn test_timer_usleep() {
         let start = Instant::now();
-        syscalls::sys_timer_usleep(10_000).unwrap(); // 10ms
+        syscalls::sys_timer_usleep(&TimeBase::new(), 10_000).unwrap(); // 10ms
         let elapsed = start.elapsed();
         
         // Should have slept at least 8ms (allowing for some variance)
@@ -470,7 +629,7 @@ mod tests {
     fn test_timer_sleep_zero() {
         // Zero duration should return immediately
         let start = Instant::now();
-        syscalls::sys_timer_usleep(0).unwrap();
+        syscalls::sys_timer_usleep(&TimeBase::new(), 0).unwrap();
         let elapsed = start.elapsed();
         
         // Should be very fast
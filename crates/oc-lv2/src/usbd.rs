@@ -0,0 +1,733 @@
+//! USB device driver interface (sys_usbd_*)
+//!
+//! Games with their own drivers for USB peripherals (Buzz! buzzers, dance
+//! mats, steering wheels) register a logical device driver (LDD) for the
+//! devices they handle, open pipes to them and submit transfers. Devices
+//! come from pluggable [`UsbBackend`]s, e.g. peripherals emulated from host
+//! input or host devices passed through. Transfers complete as soon as the
+//! backend has the data, the game learns of it from sys_usbd_receive_event.
+
+use oc_core::error::KernelError;
+use oc_memory::BeValue;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+
+/// Handle sys_usbd_initialize returns (the firmware always hands out this one)
+pub const SYS_USBD_HANDLE: u32 = 0x115B;
+
+/// Event: a device matching a registered LDD was attached
+pub const USBD_EVENT_ATTACH: u64 = 0x01;
+/// Event: such a device was detached
+pub const USBD_EVENT_DETACH: u64 = 0x02;
+/// Event: a transfer completed
+pub const USBD_EVENT_TRANSFER_COMPLETE: u64 = 0x03;
+
+/// Transfer completed without error
+pub const HC_CC_NOERR: u32 = 0x0;
+/// The device stalled the transfer
+pub const HC_CC_STALL: u32 = 0x4;
+/// The device is gone
+pub const HC_CC_NOTRESP: u32 = 0x5;
+/// The transfer has not completed yet
+pub const HC_CC_NOTACCESSED: u32 = 0xF;
+
+/// Low speed device (1.5 Mbit/s)
+pub const USB_SPEED_LOW: u8 = 0;
+/// Full speed device (12 Mbit/s)
+pub const USB_SPEED_FULL: u8 = 1;
+/// High speed device (480 Mbit/s)
+pub const USB_SPEED_HIGH: u8 = 2;
+
+/// Bytes per sys_usbd_get_device_list entry
+pub const USBD_DEVICE_ENTRY_SIZE: usize = 4;
+
+/// sys_usbd_get_device_list entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BeValue)]
+#[repr(C)]
+pub struct UsbdDeviceEntry {
+    pub handle: u8,
+    pub reserved: [u8; 3],
+}
+
+/// Most devices attached at once
+const MAX_DEVICES: usize = 127;
+/// Completed transfers whose status is kept, counted back from the latest
+const KEPT_TRANSFERS: u32 = 256;
+/// Events kept for the game before the oldest are dropped
+const MAX_EVENTS: usize = 256;
+
+/// Control request setup packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsbSetup {
+    pub request_type: u8,
+    pub request: u8,
+    pub value: u16,
+    pub index: u16,
+    pub length: u16,
+}
+
+impl UsbSetup {
+    /// Parse the 8 bytes of a setup packet (fields are little-endian on the wire)
+    pub fn parse(bytes: &[u8; 8]) -> Self {
+        Self {
+            request_type: bytes[0],
+            request: bytes[1],
+            value: u16::from_le_bytes([bytes[2], bytes[3]]),
+            index: u16::from_le_bytes([bytes[4], bytes[5]]),
+            length: u16::from_le_bytes([bytes[6], bytes[7]]),
+        }
+    }
+
+    /// Whether the data stage goes from the device to the host
+    pub fn is_in(&self) -> bool {
+        self.request_type & 0x80 != 0
+    }
+}
+
+/// A transfer as a backend receives it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsbTransfer {
+    /// Control transfer on the default pipe; `data` is the OUT data stage
+    Control { setup: UsbSetup, data: Vec<u8> },
+    /// Interrupt or bulk IN transfer of up to `length` bytes
+    In { endpoint: u8, length: usize },
+    /// Interrupt or bulk OUT transfer
+    Out { endpoint: u8, data: Vec<u8> },
+}
+
+/// Outcome of a transfer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsbTransferResult {
+    /// Completion code (HC_CC_*)
+    pub status: u32,
+    /// Data returned by an IN transfer
+    pub data: Vec<u8>,
+    /// Bytes transferred
+    pub count: usize,
+}
+
+impl UsbTransferResult {
+    /// Successful IN transfer
+    pub fn data(data: Vec<u8>) -> Self {
+        Self {
+            status: HC_CC_NOERR,
+            count: data.len(),
+            data,
+        }
+    }
+
+    /// Successful OUT transfer of `count` bytes
+    pub fn sent(count: usize) -> Self {
+        Self {
+            status: HC_CC_NOERR,
+            data: Vec::new(),
+            count,
+        }
+    }
+
+    /// Transfer the device refused
+    pub fn stall() -> Self {
+        Self {
+            status: HC_CC_STALL,
+            data: Vec::new(),
+            count: 0,
+        }
+    }
+}
+
+/// Devices a backend gained or lost
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsbHotplug {
+    Attached(u32),
+    Detached(u32),
+}
+
+/// Source of USB devices
+///
+/// Devices are named by backend-chosen IDs, which must not be reused while
+/// the device is attached.
+pub trait UsbBackend: Send {
+    /// Name for the log
+    fn name(&self) -> &'static str;
+
+    /// Pick up hotplug; called once per frame
+    fn poll(&mut self) -> Vec<UsbHotplug>;
+
+    /// Device descriptor followed by the configuration descriptor set
+    fn descriptors(&self, device: u32) -> Option<Vec<u8>>;
+
+    /// Product string, matched against LDDs registered by name
+    fn product(&self, _device: u32) -> Option<String> {
+        None
+    }
+
+    /// Bus speed (USB_SPEED_*)
+    fn speed(&self, _device: u32) -> u8 {
+        USB_SPEED_FULL
+    }
+
+    /// Run a transfer, or return None while the device has nothing to send
+    ///
+    /// Transfers left waiting are retried on every poll.
+    fn transfer(&mut self, device: u32, transfer: &UsbTransfer) -> Option<UsbTransferResult>;
+}
+
+/// IN data to copy to the guest buffer of a completed transfer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsbCompletion {
+    pub buffer: u32,
+    pub data: Vec<u8>,
+}
+
+/// Event read by sys_usbd_receive_event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsbdEvent {
+    pub kind: u64,
+    pub arg1: u64,
+    pub arg2: u64,
+}
+
+/// Logical device driver registered by the game
+#[derive(Debug, Clone)]
+struct Ldd {
+    name: String,
+    /// Vendor ID and product ID range, for LDDs registered with IDs
+    ids: Option<(u16, u16, u16)>,
+}
+
+#[derive(Debug)]
+struct AttachedDevice {
+    handle: u32,
+    backend: usize,
+    id: u32,
+    descriptors: Vec<u8>,
+    product: Option<String>,
+    speed: u8,
+    /// Whether the game was told of the device
+    announced: bool,
+}
+
+impl AttachedDevice {
+    fn vendor_id(&self) -> u16 {
+        self.descriptors.get(8..10).map_or(0, |id| u16::from_le_bytes([id[0], id[1]]))
+    }
+
+    fn product_id(&self) -> u16 {
+        self.descriptors.get(10..12).map_or(0, |id| u16::from_le_bytes([id[0], id[1]]))
+    }
+
+    fn matches(&self, ldd: &Ldd) -> bool {
+        match ldd.ids {
+            Some((vendor, min, max)) => self.vendor_id() == vendor && (min..=max).contains(&self.product_id()),
+            None => self.product.as_deref() == Some(ldd.name.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Pipe {
+    device: u32,
+    endpoint: u8,
+}
+
+#[derive(Debug)]
+struct PendingTransfer {
+    pipe: u32,
+    buffer: u32,
+    transfer: UsbTransfer,
+    /// Completion code and bytes transferred, once done
+    result: Option<(u32, usize)>,
+}
+
+#[derive(Default)]
+struct UsbdState {
+    backends: Vec<Box<dyn UsbBackend>>,
+    initialized: bool,
+    devices: Vec<AttachedDevice>,
+    ldds: Vec<Ldd>,
+    pipes: HashMap<u32, Pipe>,
+    transfers: HashMap<u32, PendingTransfer>,
+    events: VecDeque<UsbdEvent>,
+    next_device: u32,
+    next_pipe: u32,
+    next_transfer: u32,
+}
+
+impl UsbdState {
+    fn check_handle(&self, handle: u32) -> Result<(), KernelError> {
+        if self.initialized && handle == SYS_USBD_HANDLE {
+            Ok(())
+        } else {
+            Err(KernelError::InvalidId(handle))
+        }
+    }
+
+    fn device(&self, handle: u32) -> Result<&AttachedDevice, KernelError> {
+        self.devices
+            .iter()
+            .find(|dev| dev.handle == handle)
+            .ok_or(KernelError::InvalidId(handle))
+    }
+
+    fn push_event(&mut self, kind: u64, arg1: u64) {
+        if self.initialized {
+            if self.events.len() >= MAX_EVENTS {
+                self.events.pop_front();
+            }
+            self.events.push_back(UsbdEvent { kind, arg1, arg2: 0 });
+        }
+    }
+
+    /// Tell the game of devices its LDDs now cover
+    fn announce(&mut self) {
+        if !self.initialized {
+            return;
+        }
+        let mut attached = Vec::new();
+        for dev in &mut self.devices {
+            if !dev.announced && self.ldds.iter().any(|ldd| dev.matches(ldd)) {
+                dev.announced = true;
+                attached.push(dev.handle);
+            }
+        }
+        for handle in attached {
+            self.push_event(USBD_EVENT_ATTACH, handle as u64);
+        }
+    }
+
+    fn attach(&mut self, backend: usize, id: u32) {
+        let Some(descriptors) = self.backends[backend].descriptors(id) else {
+            tracing::warn!("USB device {} of {} has no descriptors", id, self.backends[backend].name());
+            return;
+        };
+        if self.devices.len() >= MAX_DEVICES {
+            tracing::warn!("Too many USB devices, ignoring device {} of {}", id, self.backends[backend].name());
+            return;
+        }
+        // Handles fit the guest's one-byte device IDs and are not reused while in use
+        let mut handle = self.next_device;
+        while handle == 0 || self.devices.iter().any(|dev| dev.handle == handle) {
+            handle = (handle + 1) & 0xFF;
+        }
+        self.next_device = (handle + 1) & 0xFF;
+        let dev = AttachedDevice {
+            handle,
+            backend,
+            id,
+            product: self.backends[backend].product(id),
+            speed: self.backends[backend].speed(id),
+            descriptors,
+            announced: false,
+        };
+        tracing::info!(
+            "USB device {:04x}:{:04x} attached from {} as {}",
+            dev.vendor_id(),
+            dev.product_id(),
+            self.backends[backend].name(),
+            handle
+        );
+        self.devices.push(dev);
+        self.announce();
+    }
+
+    fn detach(&mut self, backend: usize, id: u32) {
+        let Some(at) = self.devices.iter().position(|dev| dev.backend == backend && dev.id == id) else {
+            return;
+        };
+        let dev = self.devices.remove(at);
+        tracing::info!("USB device {} detached", dev.handle);
+        // Waiting transfers end with the device
+        for transfer in self.transfers.values_mut() {
+            let on_device = self.pipes.get(&transfer.pipe).is_some_and(|pipe| pipe.device == dev.handle);
+            if on_device && transfer.result.is_none() {
+                transfer.result = Some((HC_CC_NOTRESP, 0));
+            }
+        }
+        self.pipes.retain(|_, pipe| pipe.device != dev.handle);
+        if dev.announced {
+            self.push_event(USBD_EVENT_DETACH, dev.handle as u64);
+        }
+    }
+
+    /// Run a transfer, recording its result unless the device is not ready
+    fn run(&mut self, id: u32) -> Option<UsbCompletion> {
+        let transfer = self.transfers.get(&id)?;
+        let pipe = self.pipes.get(&transfer.pipe)?;
+        let dev = self.devices.iter().find(|dev| dev.handle == pipe.device)?;
+        let result = self.backends[dev.backend].transfer(dev.id, &transfer.transfer)?;
+
+        let transfer = self.transfers.get_mut(&id)?;
+        let limit = match &transfer.transfer {
+            UsbTransfer::Control { setup, .. } => setup.length as usize,
+            UsbTransfer::In { length, .. } => *length,
+            UsbTransfer::Out { data, .. } => data.len(),
+        };
+        let count = result.count.min(limit);
+        transfer.result = Some((result.status, count));
+        let buffer = transfer.buffer;
+        self.push_event(USBD_EVENT_TRANSFER_COMPLETE, id as u64);
+        if result.data.is_empty() {
+            return None;
+        }
+        let mut data = result.data;
+        data.truncate(count);
+        Some(UsbCompletion { buffer, data })
+    }
+}
+
+/// The USB driver interface and the devices of its backends
+pub struct Usbd {
+    state: Mutex<UsbdState>,
+}
+
+impl Default for Usbd {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Usbd {
+    /// Create the interface with no backends
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(UsbdState {
+                next_device: 1,
+                next_pipe: 1,
+                next_transfer: 1,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Replace the backends, detaching the devices of the old ones
+    pub fn set_backends(&self, backends: Vec<Box<dyn UsbBackend>>) {
+        let mut state = self.state.lock();
+        let old: Vec<(usize, u32)> = state.devices.iter().map(|dev| (dev.backend, dev.id)).collect();
+        for (backend, id) in old {
+            state.detach(backend, id);
+        }
+        state.backends = backends;
+    }
+
+    /// Number of attached devices
+    pub fn device_count(&self) -> usize {
+        self.state.lock().devices.len()
+    }
+
+    /// Pick up hotplug and retry waiting transfers
+    ///
+    /// Call once per frame. Returns the IN data to copy to guest memory.
+    pub fn poll(&self) -> Vec<UsbCompletion> {
+        let mut state = self.state.lock();
+        for backend in 0..state.backends.len() {
+            for event in state.backends[backend].poll() {
+                match event {
+                    UsbHotplug::Attached(id) => state.attach(backend, id),
+                    UsbHotplug::Detached(id) => state.detach(backend, id),
+                }
+            }
+        }
+
+        let mut waiting: Vec<u32> = state
+            .transfers
+            .iter()
+            .filter(|(_, transfer)| transfer.result.is_none())
+            .map(|(&id, _)| id)
+            .collect();
+        waiting.sort_unstable();
+        waiting.into_iter().filter_map(|id| state.run(id)).collect()
+    }
+
+    /// Start the interface, returning its handle
+    pub fn initialize(&self) -> u32 {
+        let mut state = self.state.lock();
+        state.initialized = true;
+        SYS_USBD_HANDLE
+    }
+
+    /// Stop the interface, dropping the game's LDDs, pipes and transfers
+    pub fn finalize(&self, handle: u32) -> Result<(), KernelError> {
+        let mut state = self.state.lock();
+        state.check_handle(handle)?;
+        state.initialized = false;
+        state.ldds.clear();
+        state.pipes.clear();
+        state.transfers.clear();
+        state.events.clear();
+        for dev in &mut state.devices {
+            dev.announced = false;
+        }
+        Ok(())
+    }
+
+    /// Handles of up to `max` attached devices
+    pub fn device_list(&self, handle: u32, max: usize) -> Result<Vec<u32>, KernelError> {
+        let state = self.state.lock();
+        state.check_handle(handle)?;
+        Ok(state.devices.iter().take(max).map(|dev| dev.handle).collect())
+    }
+
+    /// Device and configuration descriptors of a device
+    pub fn descriptors(&self, handle: u32, device: u32) -> Result<Vec<u8>, KernelError> {
+        let state = self.state.lock();
+        state.check_handle(handle)?;
+        Ok(state.device(device)?.descriptors.clone())
+    }
+
+    /// Bus speed of a device
+    pub fn device_speed(&self, handle: u32, device: u32) -> Result<u8, KernelError> {
+        let state = self.state.lock();
+        state.check_handle(handle)?;
+        Ok(state.device(device)?.speed)
+    }
+
+    /// Register a driver for devices with this product string
+    pub fn register_ldd(&self, handle: u32, name: &str) -> Result<(), KernelError> {
+        self.add_ldd(handle, Ldd {
+            name: name.to_string(),
+            ids: None,
+        })
+    }
+
+    /// Register a driver for a vendor's products in an ID range
+    pub fn register_extra_ldd(
+        &self,
+        handle: u32,
+        name: &str,
+        vendor_id: u16,
+        product_min: u16,
+        product_max: u16,
+    ) -> Result<(), KernelError> {
+        self.add_ldd(handle, Ldd {
+            name: name.to_string(),
+            ids: Some((vendor_id, product_min, product_max)),
+        })
+    }
+
+    fn add_ldd(&self, handle: u32, ldd: Ldd) -> Result<(), KernelError> {
+        let mut state = self.state.lock();
+        state.check_handle(handle)?;
+        tracing::debug!("USB LDD \"{}\" registered", ldd.name);
+        state.ldds.push(ldd);
+        state.announce();
+        Ok(())
+    }
+
+    /// Unregister the drivers with a name
+    pub fn unregister_ldd(&self, handle: u32, name: &str) -> Result<(), KernelError> {
+        let mut state = self.state.lock();
+        state.check_handle(handle)?;
+        let before = state.ldds.len();
+        state.ldds.retain(|ldd| ldd.name != name);
+        if state.ldds.len() == before {
+            return Err(KernelError::InvalidId(handle));
+        }
+        Ok(())
+    }
+
+    /// Open a pipe to an endpoint of a device (endpoint 0 for the default pipe)
+    pub fn open_pipe(&self, handle: u32, device: u32, endpoint: u8) -> Result<u32, KernelError> {
+        let mut state = self.state.lock();
+        state.check_handle(handle)?;
+        state.device(device)?;
+        let id = state.next_pipe;
+        state.next_pipe += 1;
+        state.pipes.insert(id, Pipe { device, endpoint });
+        Ok(id)
+    }
+
+    /// Close a pipe, dropping its transfers
+    pub fn close_pipe(&self, handle: u32, pipe: u32) -> Result<(), KernelError> {
+        let mut state = self.state.lock();
+        state.check_handle(handle)?;
+        state.pipes.remove(&pipe).ok_or(KernelError::InvalidId(pipe))?;
+        state.transfers.retain(|_, transfer| transfer.pipe != pipe);
+        Ok(())
+    }
+
+    /// Next event, or WouldBlock when none is queued
+    pub fn receive_event(&self, handle: u32) -> Result<UsbdEvent, KernelError> {
+        let mut state = self.state.lock();
+        state.check_handle(handle)?;
+        state.events.pop_front().ok_or(KernelError::WouldBlock)
+    }
+
+    /// Check a device the game's driver binds to
+    pub fn attach(&self, handle: u32, device: u32) -> Result<(), KernelError> {
+        let state = self.state.lock();
+        state.check_handle(handle)?;
+        state.device(device).map(|_| ())
+    }
+
+    /// Submit a transfer on a pipe
+    ///
+    /// `request` is the setup packet of control transfers and `buffer` the
+    /// transfer buffer, with `data` its contents. Returns the transfer ID
+    /// and the IN data to copy to the buffer if it completed at once.
+    pub fn transfer(
+        &self,
+        handle: u32,
+        pipe: u32,
+        request: Option<[u8; 8]>,
+        buffer: u32,
+        mut data: Vec<u8>,
+    ) -> Result<(u32, Option<UsbCompletion>), KernelError> {
+        let mut state = self.state.lock();
+        state.check_handle(handle)?;
+        let endpoint = state.pipes.get(&pipe).ok_or(KernelError::InvalidId(pipe))?.endpoint;
+        let transfer = if endpoint & 0x7F == 0 {
+            let setup = UsbSetup::parse(&request.ok_or(KernelError::InvalidAddress(0))?);
+            if setup.is_in() {
+                data.clear();
+            } else {
+                data.truncate(setup.length as usize);
+            }
+            UsbTransfer::Control { setup, data }
+        } else if endpoint & 0x80 != 0 {
+            UsbTransfer::In {
+                endpoint,
+                length: data.len(),
+            }
+        } else {
+            UsbTransfer::Out { endpoint, data }
+        };
+
+        let id = state.next_transfer;
+        state.next_transfer += 1;
+        state
+            .transfers
+            .retain(|&old, transfer| transfer.result.is_none() || id - old <= KEPT_TRANSFERS);
+        state.transfers.insert(id, PendingTransfer {
+            pipe,
+            buffer,
+            transfer,
+            result: None,
+        });
+        Ok((id, state.run(id)))
+    }
+
+    /// Completion code and bytes transferred of a transfer
+    pub fn transfer_status(&self, handle: u32, transfer: u32) -> Result<(u32, usize), KernelError> {
+        let state = self.state.lock();
+        state.check_handle(handle)?;
+        let transfer = state.transfers.get(&transfer).ok_or(KernelError::InvalidId(transfer))?;
+        Ok(transfer.result.unwrap_or((HC_CC_NOTACCESSED, 0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Device that answers GET_DESCRIPTOR and has one input report queued
+    struct FakeBackend {
+        hotplug: Vec<UsbHotplug>,
+        reports: VecDeque<Vec<u8>>,
+    }
+
+    impl UsbBackend for FakeBackend {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+
+        fn poll(&mut self) -> Vec<UsbHotplug> {
+            std::mem::take(&mut self.hotplug)
+        }
+
+        fn descriptors(&self, _device: u32) -> Option<Vec<u8>> {
+            let mut descriptor = vec![18, 0x01, 0x00, 0x02, 0, 0, 0, 64, 0x4C, 0x05, 0x02, 0x00];
+            descriptor.resize(18, 0);
+            Some(descriptor)
+        }
+
+        fn transfer(&mut self, _device: u32, transfer: &UsbTransfer) -> Option<UsbTransferResult> {
+            match transfer {
+                UsbTransfer::Control { setup, .. } if setup.request == 0x06 => {
+                    Some(UsbTransferResult::data(self.descriptors(0)?))
+                }
+                UsbTransfer::Control { .. } => Some(UsbTransferResult::stall()),
+                UsbTransfer::In { .. } => self.reports.pop_front().map(UsbTransferResult::data),
+                UsbTransfer::Out { data, .. } => Some(UsbTransferResult::sent(data.len())),
+            }
+        }
+    }
+
+    fn usbd_with_device() -> Usbd {
+        let usbd = Usbd::new();
+        usbd.set_backends(vec![Box::new(FakeBackend {
+            hotplug: vec![UsbHotplug::Attached(7)],
+            reports: VecDeque::new(),
+        })]);
+        usbd.poll();
+        usbd
+    }
+
+    #[test]
+    fn test_usbd_ldd_announces_matching_devices() {
+        let usbd = usbd_with_device();
+        let handle = usbd.initialize();
+        assert_eq!(usbd.device_list(handle, 8).unwrap(), vec![1]);
+        assert_eq!(usbd.descriptors(handle, 1).unwrap()[8..12], [0x4C, 0x05, 0x02, 0x00]);
+        assert!(matches!(usbd.receive_event(handle), Err(KernelError::WouldBlock)));
+
+        // Another vendor's driver does not see the device
+        usbd.register_extra_ldd(handle, "Other", 0x1234, 0, 0xFFFF).unwrap();
+        assert!(usbd.receive_event(handle).is_err());
+        usbd.register_extra_ldd(handle, "Buzz", 0x054C, 0x0001, 0x0002).unwrap();
+        assert_eq!(
+            usbd.receive_event(handle).unwrap(),
+            UsbdEvent {
+                kind: USBD_EVENT_ATTACH,
+                arg1: 1,
+                arg2: 0
+            }
+        );
+
+        // The game only hears of detaching devices it was told of
+        usbd.set_backends(Vec::new());
+        assert_eq!(usbd.receive_event(handle).unwrap().kind, USBD_EVENT_DETACH);
+        assert!(usbd.device_list(handle, 8).unwrap().is_empty());
+        assert!(usbd.open_pipe(handle, 1, 0).is_err());
+    }
+
+    #[test]
+    fn test_usbd_transfers_complete_when_data_arrives() {
+        let usbd = usbd_with_device();
+        let handle = usbd.initialize();
+        let control = usbd.open_pipe(handle, 1, 0).unwrap();
+        let interrupt = usbd.open_pipe(handle, 1, 0x81).unwrap();
+
+        // GET_DESCRIPTOR(Device) for 8 bytes completes at once
+        let setup = [0x80, 0x06, 0x00, 0x01, 0x00, 0x00, 8, 0];
+        let (id, done) = usbd.transfer(handle, control, Some(setup), 0x1000, vec![0; 8]).unwrap();
+        let done = done.unwrap();
+        assert_eq!((done.buffer, done.data.len()), (0x1000, 8));
+        assert_eq!(usbd.transfer_status(handle, id).unwrap(), (HC_CC_NOERR, 8));
+        assert_eq!(usbd.receive_event(handle).unwrap().arg1, id as u64);
+
+        // Control transfers need a setup packet
+        assert!(usbd.transfer(handle, control, None, 0x1000, Vec::new()).is_err());
+
+        // An interrupt IN waits for a report
+        let (id, done) = usbd.transfer(handle, interrupt, None, 0x2000, vec![0; 64]).unwrap();
+        assert!(done.is_none());
+        assert_eq!(usbd.transfer_status(handle, id).unwrap(), (HC_CC_NOTACCESSED, 0));
+        assert!(usbd.poll().is_empty());
+
+        usbd.state.lock().backends[0] = Box::new(FakeBackend {
+            hotplug: Vec::new(),
+            reports: VecDeque::from([vec![0x7F, 0x7F, 0x01, 0x00, 0xF0]]),
+        });
+        let done = usbd.poll();
+        assert_eq!(done, vec![UsbCompletion {
+            buffer: 0x2000,
+            data: vec![0x7F, 0x7F, 0x01, 0x00, 0xF0]
+        }]);
+        assert_eq!(usbd.transfer_status(handle, id).unwrap(), (HC_CC_NOERR, 5));
+
+        usbd.close_pipe(handle, interrupt).unwrap();
+        assert!(usbd.transfer_status(handle, id).is_err());
+        usbd.finalize(handle).unwrap();
+        assert!(usbd.device_list(handle, 8).is_err());
+    }
+}
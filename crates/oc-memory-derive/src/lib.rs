@@ -0,0 +1,183 @@
+//! Derive macros for oxidized-cell guest memory structs
+//!
+//! `#[derive(BeValue)]` implements `oc_memory::BeValue` for a struct whose
+//! fields all implement `BeValue`. Fields are laid out with C rules (each
+//! field aligned to its own alignment, total size rounded up to the struct
+//! alignment) unless the struct is `#[repr(packed)]`, matching how the
+//! same struct is declared in PS3 SDK headers.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Index};
+
+/// Derive `oc_memory::BeValue` for a `#[repr(C)]` struct
+#[proc_macro_derive(BeValue)]
+pub fn derive_be_value(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn is_packed(input: &DeriveInput) -> bool {
+    let mut packed = false;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("repr")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("packed") {
+                packed = true;
+            }
+            // Skip any arguments such as `align(N)` or `packed(N)`
+            if meta.input.peek(syn::token::Paren) {
+                let _content;
+                syn::parenthesized!(_content in meta.input);
+            }
+            Ok(())
+        });
+    }
+    packed
+}
+
+/// Reference a field for encoding; packed fields are copied out first
+/// since taking a reference to them may be unaligned
+fn field_ref(packed: bool, member: TokenStream2) -> TokenStream2 {
+    if packed {
+        quote!(&{ self.#member })
+    } else {
+        quote!(&self.#member)
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "BeValue can only be derived for structs",
+            ))
+        }
+    };
+
+    let packed = is_packed(input);
+    let types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+
+    // Offset of each field, computed at compile time from the field types
+    let align_of = |ty: &syn::Type| {
+        if packed {
+            quote!(1usize)
+        } else {
+            quote!(<#ty as ::oc_memory::BeValue>::ALIGN)
+        }
+    };
+    let offsets: Vec<TokenStream2> = (0..types.len())
+        .map(|i| {
+            let prev = &types[..i];
+            let prev_align: Vec<_> = prev.iter().map(|ty| align_of(ty)).collect();
+            let align = align_of(types[i]);
+            quote! {{
+                #[allow(unused_mut)]
+                let mut offset = 0usize;
+                #(
+                    offset = ::oc_memory::be::align_up(offset, #prev_align)
+                        + <#prev as ::oc_memory::BeValue>::SIZE;
+                )*
+                ::oc_memory::be::align_up(offset, #align)
+            }}
+        })
+        .collect();
+
+    let all_align: Vec<_> = types.iter().map(|ty| align_of(ty)).collect();
+    let struct_align = quote! {{
+        #[allow(unused_mut)]
+        let mut align = 1usize;
+        #(
+            if #all_align > align {
+                align = #all_align;
+            }
+        )*
+        align
+    }};
+    let struct_size = quote! {{
+        #[allow(unused_mut)]
+        let mut offset = 0usize;
+        #(
+            offset = ::oc_memory::be::align_up(offset, #all_align)
+                + <#types as ::oc_memory::BeValue>::SIZE;
+        )*
+        ::oc_memory::be::align_up(offset, <Self as ::oc_memory::BeValue>::ALIGN)
+    }};
+
+    let read_fields: Vec<TokenStream2> = types
+        .iter()
+        .zip(&offsets)
+        .map(|(ty, offset)| {
+            quote! {
+                <#ty as ::oc_memory::BeValue>::from_be_slice(
+                    &bytes[#offset..#offset + <#ty as ::oc_memory::BeValue>::SIZE],
+                )
+            }
+        })
+        .collect();
+
+    let (construct, write_fields) = match fields {
+        Fields::Named(named) => {
+            let idents: Vec<_> = named.named.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+            let construct = quote!(Self { #(#idents: #read_fields,)* });
+            let writes = idents
+                .iter()
+                .zip(types.iter().zip(&offsets))
+                .map(|(ident, (ty, offset))| {
+                    let field = field_ref(packed, quote!(#ident));
+                    quote! {
+                        ::oc_memory::BeValue::write_be_slice(
+                            #field,
+                            &mut out[#offset..#offset + <#ty as ::oc_memory::BeValue>::SIZE],
+                        );
+                    }
+                })
+                .collect::<Vec<_>>();
+            (construct, writes)
+        }
+        Fields::Unnamed(_) => {
+            let indices: Vec<_> = (0..types.len()).map(Index::from).collect();
+            let construct = quote!(Self(#(#read_fields,)*));
+            let writes = indices
+                .iter()
+                .zip(types.iter().zip(&offsets))
+                .map(|(index, (ty, offset))| {
+                    let field = field_ref(packed, quote!(#index));
+                    quote! {
+                        ::oc_memory::BeValue::write_be_slice(
+                            #field,
+                            &mut out[#offset..#offset + <#ty as ::oc_memory::BeValue>::SIZE],
+                        );
+                    }
+                })
+                .collect::<Vec<_>>();
+            (construct, writes)
+        }
+        Fields::Unit => (quote!(Self), Vec::new()),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::oc_memory::BeValue for #name #ty_generics #where_clause {
+            const SIZE: usize = #struct_size;
+            const ALIGN: usize = #struct_align;
+
+            fn from_be_slice(bytes: &[u8]) -> Self {
+                let _ = bytes;
+                #construct
+            }
+
+            fn write_be_slice(&self, out: &mut [u8]) {
+                let _ = out;
+                #(#write_fields)*
+            }
+        }
+    })
+}
@@ -162,7 +162,7 @@ fn bench_big_endian_access(c: &mut Criterion) {
     let mut group = c.benchmark_group("big_endian");
     
     let mem = MemoryManager::new().unwrap();
-    let addr = MAIN_MEM_BASE;
+    let addr = MAIN_MEM_BASE + NULL_PAGE_SIZE;
     
     group.bench_function("write_be32", |b| {
         b.iter(|| {
@@ -197,7 +197,7 @@ fn bench_checked_vs_unchecked(c: &mut Criterion) {
     let mut group = c.benchmark_group("checked_vs_unchecked");
     
     let mem = MemoryManager::new().unwrap();
-    let addr = MAIN_MEM_BASE + 0x1000;
+    let addr = MAIN_MEM_BASE + NULL_PAGE_SIZE + 0x1000;
     
     group.bench_function("checked_read", |b| {
         b.iter(|| {
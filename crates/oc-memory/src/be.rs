@@ -0,0 +1,157 @@
+//! Big-endian guest value (de)serialization
+//!
+//! [`BeValue`] describes a type with a fixed big-endian representation in
+//! guest memory. It is implemented for the primitive integer and float
+//! types and fixed-size arrays, and can be derived for `#[repr(C)]`
+//! structs with `#[derive(BeValue)]`, so a whole guest struct is read or
+//! written in one call instead of assembling it byte by byte.
+
+/// A value with a fixed big-endian guest representation
+pub trait BeValue: Sized {
+    /// Size of the guest representation in bytes
+    const SIZE: usize;
+    /// Alignment of the guest representation in bytes
+    const ALIGN: usize;
+
+    /// Decode from the first `SIZE` bytes of `bytes`
+    ///
+    /// # Panics
+    /// Panics if `bytes` is shorter than `SIZE`.
+    fn from_be_slice(bytes: &[u8]) -> Self;
+
+    /// Encode into the first `SIZE` bytes of `out`
+    ///
+    /// Padding bytes between struct fields are left untouched.
+    ///
+    /// # Panics
+    /// Panics if `out` is shorter than `SIZE`.
+    fn write_be_slice(&self, out: &mut [u8]);
+
+    /// Decode from `bytes`, or `None` if it is too short
+    fn try_from_be_slice(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < Self::SIZE {
+            return None;
+        }
+        Some(Self::from_be_slice(bytes))
+    }
+
+    /// Encode into a new buffer (padding is zeroed)
+    fn to_be_vec(&self) -> Vec<u8> {
+        let mut out = vec![0u8; Self::SIZE];
+        self.write_be_slice(&mut out);
+        out
+    }
+}
+
+/// Round `offset` up to a multiple of `align` (used by the derive)
+#[doc(hidden)]
+pub const fn align_up(offset: usize, align: usize) -> usize {
+    offset.div_ceil(align) * align
+}
+
+macro_rules! impl_be_value {
+    ($($ty:ty),*) => {
+        $(
+            impl BeValue for $ty {
+                const SIZE: usize = std::mem::size_of::<$ty>();
+                const ALIGN: usize = std::mem::size_of::<$ty>();
+
+                #[inline]
+                fn from_be_slice(bytes: &[u8]) -> Self {
+                    let mut raw = [0u8; std::mem::size_of::<$ty>()];
+                    raw.copy_from_slice(&bytes[..Self::SIZE]);
+                    <$ty>::from_be_bytes(raw)
+                }
+
+                #[inline]
+                fn write_be_slice(&self, out: &mut [u8]) {
+                    out[..Self::SIZE].copy_from_slice(&self.to_be_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_be_value!(u8, i8, u16, i16, u32, i32, u64, i64, u128, f32, f64);
+
+impl BeValue for bool {
+    const SIZE: usize = 1;
+    const ALIGN: usize = 1;
+
+    fn from_be_slice(bytes: &[u8]) -> Self {
+        bytes[0] != 0
+    }
+
+    fn write_be_slice(&self, out: &mut [u8]) {
+        out[0] = *self as u8;
+    }
+}
+
+impl<T: BeValue, const N: usize> BeValue for [T; N] {
+    const SIZE: usize = T::SIZE * N;
+    const ALIGN: usize = T::ALIGN;
+
+    fn from_be_slice(bytes: &[u8]) -> Self {
+        std::array::from_fn(|i| T::from_be_slice(&bytes[i * T::SIZE..(i + 1) * T::SIZE]))
+    }
+
+    fn write_be_slice(&self, out: &mut [u8]) {
+        for (i, value) in self.iter().enumerate() {
+            value.write_be_slice(&mut out[i * T::SIZE..(i + 1) * T::SIZE]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BeValue;
+
+    #[derive(Debug, Clone, Copy, PartialEq, BeValue)]
+    #[repr(C)]
+    struct Padded {
+        tag: u8,
+        value: u32,
+        small: u16,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, BeValue)]
+    #[repr(C, packed)]
+    struct Packed {
+        tag: u8,
+        value: u32,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, BeValue)]
+    struct Pair(u16, [u8; 2]);
+
+    #[test]
+    fn test_primitive_round_trip() {
+        let bytes = 0x1234_5678u32.to_be_vec();
+        assert_eq!(bytes, [0x12, 0x34, 0x56, 0x78]);
+        assert_eq!(u32::from_be_slice(&bytes), 0x1234_5678);
+        assert_eq!(f32::from_be_slice(&1.5f32.to_be_vec()), 1.5);
+        assert_eq!(u64::try_from_be_slice(&bytes), None);
+    }
+
+    #[test]
+    fn test_derive_c_layout() {
+        // Same layout as the equivalent C struct: 1 + 3 pad + 4 + 2 + 2 pad
+        assert_eq!(Padded::SIZE, std::mem::size_of::<Padded>());
+        assert_eq!(Padded::ALIGN, 4);
+
+        let value = Padded { tag: 0xAA, value: 0x0102_0304, small: 0x0506 };
+        let bytes = value.to_be_vec();
+        assert_eq!(bytes, [0xAA, 0, 0, 0, 1, 2, 3, 4, 5, 6, 0, 0]);
+        assert_eq!(Padded::from_be_slice(&bytes), value);
+    }
+
+    #[test]
+    fn test_derive_packed_and_tuple() {
+        assert_eq!(Packed::SIZE, 5);
+        let packed = Packed::from_be_slice(&[7, 0, 0, 1, 0]);
+        assert_eq!({ packed.value }, 0x100);
+
+        assert_eq!(Pair::SIZE, 4);
+        assert_eq!(Pair::from_be_slice(&[0xBE, 0xEF, 1, 2]), Pair(0xBEEF, [1, 2]));
+    }
+}
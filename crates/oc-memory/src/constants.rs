@@ -5,6 +5,12 @@ pub const MAIN_MEM_BASE: u32 = 0x0000_0000;
 /// Main memory size (256 MB)
 pub const MAIN_MEM_SIZE: u32 = 0x1000_0000;
 
+/// Size of the no-access guard at the bottom of main memory (64 KB)
+///
+/// PS3 executables are linked at 0x10000 or above, so anything below is a
+/// null (or near-null) pointer dereference.
+pub const NULL_PAGE_SIZE: u32 = 0x0001_0000;
+
 /// User memory base address
 pub const USER_MEM_BASE: u32 = 0x2000_0000;
 /// User memory size (256 MB)
@@ -7,6 +7,7 @@
 //! - Memory access profiling
 
 use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use parking_lot::RwLock;
 use oc_core::error::AccessKind;
 
@@ -38,6 +39,80 @@ pub struct Watchpoint {
     pub hit_count: u64,
     /// Optional condition (value comparison)
     pub condition: Option<WatchpointCondition>,
+    /// Only trigger on accesses of exactly this many bytes
+    pub size_filter: Option<u32>,
+}
+
+impl Watchpoint {
+    /// Check whether this watchpoint triggers for an access
+    ///
+    /// `old_value` is the big-endian value in memory before the access and
+    /// `new_value` the value read or written. Both are `None` for accesses
+    /// wider than 8 bytes, in which case value conditions always match.
+    pub fn matches(
+        &self,
+        addr: u32,
+        size: u32,
+        kind: AccessKind,
+        old_value: Option<u64>,
+        new_value: Option<u64>,
+    ) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        // Check if access overlaps with watchpoint
+        let access_end = addr.saturating_add(size.saturating_sub(1));
+        let wp_end = self.addr.saturating_add(self.size.saturating_sub(1));
+        if addr > wp_end || access_end < self.addr {
+            return false;
+        }
+
+        // Check access type
+        let type_matches = match self.wp_type {
+            WatchpointType::Read => kind == AccessKind::Read,
+            WatchpointType::Write => kind == AccessKind::Write,
+            WatchpointType::ReadWrite => kind == AccessKind::Read || kind == AccessKind::Write,
+            WatchpointType::Execute => kind == AccessKind::Execute,
+        };
+        if !type_matches {
+            return false;
+        }
+
+        if let Some(filter) = self.size_filter {
+            if filter != size {
+                return false;
+            }
+        }
+
+        match (&self.condition, old_value, new_value) {
+            (None, _, _) => true,
+            (Some(_), _, None) => true,
+            (Some(WatchpointCondition::Equals(value)), _, Some(new)) => new == *value,
+            (Some(WatchpointCondition::Changed), Some(old), Some(new)) => {
+                kind == AccessKind::Write && old != new
+            }
+            (Some(WatchpointCondition::Delta(delta)), Some(old), Some(new)) => {
+                kind == AccessKind::Write && new == old.wrapping_add(*delta as u64)
+            }
+            (Some(_), None, Some(_)) => true,
+        }
+    }
+}
+
+/// Details of a triggered watchpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchpointHit {
+    /// Address of the watchpoint that triggered
+    pub wp_addr: u32,
+    /// Address of the access
+    pub access_addr: u32,
+    /// Size of the access in bytes
+    pub size: u32,
+    /// Kind of access
+    pub kind: AccessKind,
+    /// Value read or written (big-endian, accesses up to 8 bytes)
+    pub value: Option<u64>,
 }
 
 /// Watchpoint condition for conditional breaks
@@ -57,6 +132,8 @@ pub struct WatchpointManager {
     watchpoints: RwLock<HashMap<u32, Watchpoint>>,
     /// Quick lookup for addresses with watchpoints
     watched_pages: RwLock<HashSet<u32>>,
+    /// Set while at least one watchpoint exists (lock-free fast path)
+    active: AtomicBool,
 }
 
 impl WatchpointManager {
@@ -65,55 +142,57 @@ impl WatchpointManager {
         Self {
             watchpoints: RwLock::new(HashMap::new()),
             watched_pages: RwLock::new(HashSet::new()),
+            active: AtomicBool::new(false),
         }
     }
 
     /// Add a watchpoint
     pub fn add(&self, addr: u32, size: u32, wp_type: WatchpointType) {
-        let wp = Watchpoint {
+        self.insert(Watchpoint {
             addr,
             size,
             wp_type,
             enabled: true,
             hit_count: 0,
             condition: None,
-        };
-
-        self.watchpoints.write().insert(addr, wp);
-
-        // Mark affected pages
-        let page_size = 0x1000u32;
-        let start_page = addr / page_size;
-        let end_page = (addr + size.saturating_sub(1)) / page_size;
-        
-        let mut watched = self.watched_pages.write();
-        for page in start_page..=end_page {
-            watched.insert(page);
-        }
+            size_filter: None,
+        });
     }
 
     /// Add a conditional watchpoint
     pub fn add_conditional(&self, addr: u32, size: u32, wp_type: WatchpointType, condition: WatchpointCondition) {
-        let wp = Watchpoint {
+        self.insert(Watchpoint {
             addr,
             size,
             wp_type,
             enabled: true,
             hit_count: 0,
             condition: Some(condition),
-        };
-
-        self.watchpoints.write().insert(addr, wp);
+            size_filter: None,
+        });
+    }
 
+    /// Add a fully configured watchpoint, replacing any at the same address
+    pub fn insert(&self, wp: Watchpoint) {
         // Mark affected pages
         let page_size = 0x1000u32;
-        let start_page = addr / page_size;
-        let end_page = (addr + size.saturating_sub(1)) / page_size;
-        
+        let start_page = wp.addr / page_size;
+        let end_page = (wp.addr + wp.size.saturating_sub(1)) / page_size;
+
+        self.watchpoints.write().insert(wp.addr, wp);
+
         let mut watched = self.watched_pages.write();
         for page in start_page..=end_page {
             watched.insert(page);
         }
+        self.active.store(true, Ordering::Release);
+    }
+
+    /// Restrict a watchpoint to accesses of a given size (`None` for any size)
+    pub fn set_size_filter(&self, addr: u32, size_filter: Option<u32>) {
+        if let Some(wp) = self.watchpoints.write().get_mut(&addr) {
+            wp.size_filter = size_filter;
+        }
     }
 
     /// Remove a watchpoint
@@ -141,6 +220,7 @@ impl WatchpointManager {
                     watched.remove(&page);
                 }
             }
+            self.active.store(!watchpoints.is_empty(), Ordering::Release);
         }
     }
 
@@ -162,6 +242,13 @@ impl WatchpointManager {
     pub fn clear(&self) {
         self.watchpoints.write().clear();
         self.watched_pages.write().clear();
+        self.active.store(false, Ordering::Release);
+    }
+
+    /// Check if any watchpoint exists (lock-free)
+    #[inline(always)]
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Acquire)
     }
 
     /// Check if an address is being watched (fast path)
@@ -179,31 +266,40 @@ impl WatchpointManager {
         }
 
         let watchpoints = self.watchpoints.read();
-        for (wp_addr, wp) in watchpoints.iter() {
-            if !wp.enabled {
-                continue;
-            }
-
-            // Check if access overlaps with watchpoint
-            let access_end = addr.saturating_add(size.saturating_sub(1));
-            let wp_end = wp.addr.saturating_add(wp.size.saturating_sub(1));
-
-            if addr <= wp_end && access_end >= wp.addr {
-                // Check access type
-                let matches = match wp.wp_type {
-                    WatchpointType::Read => kind == AccessKind::Read,
-                    WatchpointType::Write => kind == AccessKind::Write,
-                    WatchpointType::ReadWrite => kind == AccessKind::Read || kind == AccessKind::Write,
-                    WatchpointType::Execute => kind == AccessKind::Execute,
-                };
-
-                if matches {
-                    return Some(*wp_addr);
-                }
-            }
+        watchpoints
+            .values()
+            .find(|wp| wp.matches(addr, size, kind, None, None))
+            .map(|wp| wp.addr)
+    }
+
+    /// Check an access against all watchpoints, evaluating size filters and
+    /// value conditions, and record a hit on the first matching watchpoint
+    pub fn check_access_value(
+        &self,
+        addr: u32,
+        size: u32,
+        kind: AccessKind,
+        old_value: Option<u64>,
+        new_value: Option<u64>,
+    ) -> Option<WatchpointHit> {
+        let end_addr = addr.saturating_add(size.saturating_sub(1));
+        if !self.is_watched_page(addr) && !self.is_watched_page(end_addr) {
+            return None;
         }
 
-        None
+        let mut watchpoints = self.watchpoints.write();
+        let wp = watchpoints
+            .values_mut()
+            .find(|wp| wp.matches(addr, size, kind, old_value, new_value))?;
+        wp.hit_count += 1;
+
+        Some(WatchpointHit {
+            wp_addr: wp.addr,
+            access_addr: addr,
+            size,
+            kind,
+            value: new_value,
+        })
     }
 
     /// Increment hit count for a watchpoint
@@ -646,6 +742,53 @@ mod tests {
         assert!(manager.check_access(0x2000, 4, AccessKind::Write).is_none());
     }
 
+    #[test]
+    fn test_watchpoint_size_filter() {
+        let manager = WatchpointManager::new();
+
+        manager.add(0x1000, 8, WatchpointType::ReadWrite);
+        manager.set_size_filter(0x1000, Some(4));
+
+        assert!(manager.check_access_value(0x1000, 4, AccessKind::Read, None, Some(0)).is_some());
+        assert!(manager.check_access_value(0x1000, 1, AccessKind::Read, None, Some(0)).is_none());
+        assert!(manager.check_access_value(0x1004, 8, AccessKind::Write, Some(0), Some(1)).is_none());
+    }
+
+    #[test]
+    fn test_watchpoint_value_conditions() {
+        let manager = WatchpointManager::new();
+
+        manager.add_conditional(0x1000, 4, WatchpointType::Write, WatchpointCondition::Equals(0x10));
+        manager.add_conditional(0x2000, 4, WatchpointType::Write, WatchpointCondition::Changed);
+        manager.add_conditional(0x3000, 4, WatchpointType::Write, WatchpointCondition::Delta(-1));
+
+        assert!(manager.check_access_value(0x1000, 4, AccessKind::Write, Some(0), Some(0x0F)).is_none());
+        let hit = manager.check_access_value(0x1000, 4, AccessKind::Write, Some(0), Some(0x10)).unwrap();
+        assert_eq!(hit.wp_addr, 0x1000);
+        assert_eq!(hit.value, Some(0x10));
+
+        assert!(manager.check_access_value(0x2000, 4, AccessKind::Write, Some(5), Some(5)).is_none());
+        assert!(manager.check_access_value(0x2000, 4, AccessKind::Write, Some(5), Some(6)).is_some());
+
+        assert!(manager.check_access_value(0x3000, 4, AccessKind::Write, Some(5), Some(3)).is_none());
+        assert!(manager.check_access_value(0x3000, 4, AccessKind::Write, Some(5), Some(4)).is_some());
+
+        let hits: u64 = manager.get_all().iter().map(|wp| wp.hit_count).sum();
+        assert_eq!(hits, 3);
+    }
+
+    #[test]
+    fn test_watchpoint_active_flag() {
+        let manager = WatchpointManager::new();
+        assert!(!manager.is_active());
+
+        manager.add(0x1000, 4, WatchpointType::Read);
+        assert!(manager.is_active());
+
+        manager.remove(0x1000);
+        assert!(!manager.is_active());
+    }
+
     #[test]
     fn test_smc_detector() {
         let detector = SmcDetector::new(true);
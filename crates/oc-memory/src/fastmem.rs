@@ -0,0 +1,364 @@
+//! Fastmem: host-page-backed guest address space
+//!
+//! The full 32-bit guest address space is reserved as one contiguous host
+//! mapping with no access rights. Only pages the guest has mapped are
+//! committed with host protection matching their [`PageFlags`], so a guest
+//! load or store becomes `base + addr` plus a byte swap. Touching an
+//! unmapped page raises a host fault, which the fault handler installed
+//! here attributes to the guest address before passing it on.
+
+use crate::pages::PageFlags;
+use oc_core::error::MemoryError;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// Maximum number of arenas tracked by the fault handler
+const MAX_ARENAS: usize = 64;
+
+/// Registered arena ranges (base, size); a zero base marks a free slot
+static ARENAS: [(AtomicUsize, AtomicUsize); MAX_ARENAS] =
+    [const { (AtomicUsize::new(0), AtomicUsize::new(0)) }; MAX_ARENAS];
+
+/// Guest address of the last fastmem fault (`u64::MAX` if none)
+static LAST_FAULT: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// Host page size, used to round protection changes
+pub fn host_page_size() -> usize {
+    #[cfg(unix)]
+    {
+        let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if size > 0 {
+            return size as usize;
+        }
+    }
+    0x1000
+}
+
+/// A reserved host mapping backing a guest address range
+pub struct FastmemArena {
+    /// Host base pointer
+    base: *mut u8,
+    /// Size of the reservation in bytes
+    size: usize,
+    /// Fault handler slot, if registered
+    slot: Option<usize>,
+}
+
+// Safety: the arena only hands out raw pointers; synchronization is the
+// responsibility of the owner (see `MemoryManager`).
+unsafe impl Send for FastmemArena {}
+unsafe impl Sync for FastmemArena {}
+
+impl FastmemArena {
+    /// Reserve `size` bytes of host address space with no access rights
+    pub fn reserve(size: usize) -> Result<Self, MemoryError> {
+        let base = Self::reserve_host(size)?;
+        Ok(Self {
+            base,
+            size,
+            slot: None,
+        })
+    }
+
+    /// Reserve `size` bytes and commit all of them read/write
+    pub fn committed(size: usize) -> Result<Self, MemoryError> {
+        let arena = Self::reserve(size)?;
+        arena.protect(0, size, PageFlags::RW)?;
+        Ok(arena)
+    }
+
+    #[cfg(unix)]
+    fn reserve_host(size: usize) -> Result<*mut u8, MemoryError> {
+        use libc::{mmap, MAP_ANONYMOUS, MAP_NORESERVE, MAP_PRIVATE, PROT_NONE};
+
+        let ptr = unsafe {
+            mmap(
+                std::ptr::null_mut(),
+                size,
+                PROT_NONE,
+                MAP_PRIVATE | MAP_ANONYMOUS | MAP_NORESERVE,
+                -1,
+                0,
+            )
+        };
+
+        if ptr == libc::MAP_FAILED {
+            return Err(MemoryError::OutOfMemory);
+        }
+
+        Ok(ptr as *mut u8)
+    }
+
+    #[cfg(windows)]
+    fn reserve_host(size: usize) -> Result<*mut u8, MemoryError> {
+        use windows_sys::Win32::System::Memory::*;
+
+        let ptr = unsafe { VirtualAlloc(std::ptr::null(), size, MEM_RESERVE, PAGE_NOACCESS) };
+
+        if ptr.is_null() {
+            return Err(MemoryError::OutOfMemory);
+        }
+
+        Ok(ptr as *mut u8)
+    }
+
+    /// Host base pointer of the arena
+    #[inline(always)]
+    pub fn base(&self) -> *mut u8 {
+        self.base
+    }
+
+    /// Size of the arena in bytes
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Register the arena with the fault handler
+    ///
+    /// Faults inside registered arenas are reported with their guest
+    /// address. Returns false if every slot is in use.
+    pub fn register_faults(&mut self) -> bool {
+        if self.slot.is_some() {
+            return true;
+        }
+
+        for (i, (base, size)) in ARENAS.iter().enumerate() {
+            if base
+                .compare_exchange(0, self.base as usize, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+            {
+                size.store(self.size, Ordering::Release);
+                self.slot = Some(i);
+                install_fault_handler();
+                return true;
+            }
+        }
+
+        tracing::warn!("No free fastmem fault slot; faults will not be attributed");
+        false
+    }
+
+    /// Set host protection for `[offset, offset + len)`
+    ///
+    /// The range must be aligned to the host page size. Empty flags
+    /// decommit the range so any access faults.
+    pub fn protect(&self, offset: usize, len: usize, flags: PageFlags) -> Result<(), MemoryError> {
+        if len == 0 {
+            return Ok(());
+        }
+        if offset.checked_add(len).is_none_or(|end| end > self.size) {
+            return Err(MemoryError::InvalidAddress(offset as u32));
+        }
+
+        if self.protect_host(offset, len, flags) {
+            Ok(())
+        } else {
+            Err(MemoryError::OutOfMemory)
+        }
+    }
+
+    #[cfg(unix)]
+    fn protect_host(&self, offset: usize, len: usize, flags: PageFlags) -> bool {
+        let mut prot = libc::PROT_NONE;
+        if flags.contains(PageFlags::READ) {
+            prot |= libc::PROT_READ;
+        }
+        if flags.contains(PageFlags::WRITE) {
+            prot |= libc::PROT_READ | libc::PROT_WRITE;
+        }
+
+        let ret = unsafe { libc::mprotect(self.base.add(offset) as *mut libc::c_void, len, prot) };
+        ret == 0
+    }
+
+    #[cfg(windows)]
+    fn protect_host(&self, offset: usize, len: usize, flags: PageFlags) -> bool {
+        use windows_sys::Win32::System::Memory::*;
+
+        let ptr = unsafe { self.base.add(offset) } as *mut core::ffi::c_void;
+        if flags.intersects(PageFlags::RW) {
+            let prot = if flags.contains(PageFlags::WRITE) {
+                PAGE_READWRITE
+            } else {
+                PAGE_READONLY
+            };
+            let committed = unsafe { VirtualAlloc(ptr, len, MEM_COMMIT, prot) };
+            !committed.is_null()
+        } else {
+            let mut old = 0;
+            unsafe { VirtualProtect(ptr, len, PAGE_NOACCESS, &mut old) != 0 }
+        }
+    }
+
+    /// Translate a host address inside the arena to a guest offset
+    pub fn guest_offset(&self, host_addr: usize) -> Option<u32> {
+        let base = self.base as usize;
+        if host_addr >= base && host_addr < base + self.size {
+            Some((host_addr - base) as u32)
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for FastmemArena {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            ARENAS[slot].1.store(0, Ordering::Release);
+            ARENAS[slot].0.store(0, Ordering::Release);
+        }
+
+        #[cfg(unix)]
+        unsafe {
+            libc::munmap(self.base as *mut libc::c_void, self.size);
+        }
+
+        #[cfg(windows)]
+        unsafe {
+            use windows_sys::Win32::System::Memory::*;
+            VirtualFree(self.base as *mut _, 0, MEM_RELEASE);
+        }
+    }
+}
+
+/// Guest address of the most recent fastmem fault, if any
+pub fn last_fault() -> Option<u32> {
+    match LAST_FAULT.load(Ordering::Acquire) {
+        u64::MAX => None,
+        addr => Some(addr as u32),
+    }
+}
+
+/// Look up the guest address for a faulting host address
+fn fault_guest_address(host_addr: usize) -> Option<u32> {
+    for (base, size) in ARENAS.iter() {
+        let base = base.load(Ordering::Acquire);
+        if base == 0 {
+            continue;
+        }
+        let size = size.load(Ordering::Acquire);
+        if host_addr >= base && host_addr < base + size {
+            return Some((host_addr - base) as u32);
+        }
+    }
+    None
+}
+
+#[cfg(unix)]
+mod handler {
+    use super::{fault_guest_address, LAST_FAULT};
+    use std::sync::atomic::Ordering;
+    use std::sync::OnceLock;
+
+    static PREV_SEGV: OnceLock<libc::sigaction> = OnceLock::new();
+    static PREV_BUS: OnceLock<libc::sigaction> = OnceLock::new();
+
+    pub fn install() {
+        static INSTALLED: std::sync::Once = std::sync::Once::new();
+        INSTALLED.call_once(|| unsafe {
+            install_signal(libc::SIGSEGV, &PREV_SEGV);
+            install_signal(libc::SIGBUS, &PREV_BUS);
+        });
+    }
+
+    unsafe fn install_signal(sig: libc::c_int, prev: &OnceLock<libc::sigaction>) {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = fault_handler as *const () as usize;
+        action.sa_flags = libc::SA_SIGINFO | libc::SA_ONSTACK;
+        libc::sigemptyset(&mut action.sa_mask);
+
+        let mut old: libc::sigaction = std::mem::zeroed();
+        if libc::sigaction(sig, &action, &mut old) == 0 {
+            let _ = prev.set(old);
+        }
+    }
+
+    /// Report a fault address on stderr (async-signal-safe)
+    fn report(addr: u32) {
+        const PREFIX: &[u8] = b"oc-memory: fastmem fault at unmapped guest address 0x";
+        let mut buf = [0u8; PREFIX.len() + 9];
+        buf[..PREFIX.len()].copy_from_slice(PREFIX);
+        for i in 0..8 {
+            let nibble = ((addr >> ((7 - i) * 4)) & 0xF) as u8;
+            buf[PREFIX.len() + i] = if nibble < 10 { b'0' + nibble } else { b'a' + nibble - 10 };
+        }
+        buf[PREFIX.len() + 8] = b'\n';
+        unsafe {
+            libc::write(libc::STDERR_FILENO, buf.as_ptr() as *const libc::c_void, buf.len());
+        }
+    }
+
+    extern "C" fn fault_handler(sig: libc::c_int, info: *mut libc::siginfo_t, ctx: *mut libc::c_void) {
+        let host_addr = unsafe { (*info).si_addr() } as usize;
+        if let Some(guest) = fault_guest_address(host_addr) {
+            LAST_FAULT.store(guest as u64, Ordering::Release);
+            report(guest);
+        }
+
+        // Chain to whoever was installed before us (e.g. the std stack
+        // overflow handler), or fall back to the default action.
+        let prev = if sig == libc::SIGBUS { PREV_BUS.get() } else { PREV_SEGV.get() };
+        unsafe {
+            match prev {
+                Some(prev) if prev.sa_sigaction != libc::SIG_DFL && prev.sa_sigaction != libc::SIG_IGN => {
+                    if prev.sa_flags & libc::SA_SIGINFO != 0 {
+                        let f: extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void) =
+                            std::mem::transmute(prev.sa_sigaction);
+                        f(sig, info, ctx);
+                    } else {
+                        let f: extern "C" fn(libc::c_int) = std::mem::transmute(prev.sa_sigaction);
+                        f(sig);
+                    }
+                }
+                _ => {
+                    // Returning re-executes the faulting access, which now
+                    // takes the default action and terminates the process.
+                    libc::signal(sig, libc::SIG_DFL);
+                }
+            }
+        }
+    }
+}
+
+/// Install the process-wide fastmem fault handler (idempotent)
+pub fn install_fault_handler() {
+    #[cfg(unix)]
+    handler::install();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arena_protect_and_access() {
+        let page = host_page_size();
+        let arena = FastmemArena::reserve(page * 4).unwrap();
+
+        arena.protect(page, page, PageFlags::RW).unwrap();
+        unsafe {
+            arena.base().add(page).write(0xAB);
+            assert_eq!(arena.base().add(page).read(), 0xAB);
+        }
+
+        // Decommitting and recommitting keeps the mapping usable
+        arena.protect(page, page, PageFlags::empty()).unwrap();
+        arena.protect(page, page, PageFlags::READ).unwrap();
+        unsafe {
+            let _ = arena.base().add(page).read_volatile();
+        }
+
+        assert!(arena.protect(page * 3, page * 2, PageFlags::RW).is_err());
+    }
+
+    #[test]
+    fn test_fault_address_translation() {
+        let page = host_page_size();
+        let mut arena = FastmemArena::reserve(page * 2).unwrap();
+        assert!(arena.register_faults());
+
+        let host = arena.base() as usize + 0x10;
+        assert_eq!(arena.guest_offset(host), Some(0x10));
+        assert_eq!(fault_guest_address(host), Some(0x10));
+        assert_eq!(arena.guest_offset(host + page * 2), None);
+    }
+}
@@ -3,17 +3,29 @@
 //! This crate provides the virtual memory system that mimics the PS3's
 //! address space, including reservation system for SPU atomics.
 
+// Lets `#[derive(BeValue)]` refer to `::oc_memory` from inside this crate
+extern crate self as oc_memory;
+
+pub mod be;
 pub mod constants;
 pub mod debug;
+pub mod fastmem;
 pub mod manager;
 pub mod pages;
 pub mod reservation;
+pub mod snapshot;
+pub mod stats;
 
+pub use be::BeValue;
 pub use constants::*;
 pub use debug::{
     CacheMode, CacheSimulator, CacheStats, MemoryProfiler, SmcDetector,
-    Watchpoint, WatchpointCondition, WatchpointManager, WatchpointType,
+    Watchpoint, WatchpointCondition, WatchpointHit, WatchpointManager, WatchpointType,
 };
+pub use fastmem::FastmemArena;
 pub use manager::MemoryManager;
+pub use oc_memory_derive::BeValue;
 pub use pages::PageFlags;
 pub use reservation::Reservation;
+pub use snapshot::{MemoryDelta, MemorySnapshot, PageSnapshot};
+pub use stats::{MemoryStats, RegionStats};
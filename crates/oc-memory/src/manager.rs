@@ -1,10 +1,18 @@
 //! Memory manager implementation
 
+use crate::be::BeValue;
 use crate::constants::*;
+use crate::debug::{WatchpointHit, WatchpointManager};
+use crate::fastmem::{self, FastmemArena};
 use crate::pages::PageFlags;
 use crate::reservation::Reservation;
+use crate::snapshot::{MemoryDelta, MemorySnapshot, PageSnapshot};
+use crate::stats::{MemoryStats, RegionStats};
 use oc_core::error::{AccessKind, MemoryError};
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock, RwLockWriteGuard};
+use std::collections::BTreeMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 /// Memory region descriptor
@@ -25,8 +33,10 @@ pub struct MemoryRegion {
 /// Manages the 32-bit virtual address space with proper page tracking
 /// and reservation system for SPU atomics.
 pub struct MemoryManager {
-    /// Base pointer for the main address space
+    /// Base pointer for the main address space (cached from `arena`)
     base: *mut u8,
+    /// Host reservation backing the guest address space
+    arena: FastmemArena,
     /// Allocation bitmap (one bit per page)
     allocation_map: RwLock<Vec<u64>>,
     /// Page flags for each page
@@ -36,7 +46,46 @@ pub struct MemoryManager {
     /// Memory regions
     regions: Vec<MemoryRegion>,
     /// RSX memory (separate allocation for VRAM)
-    rsx_mem: *mut u8,
+    rsx_mem: FastmemArena,
+    /// Data watchpoints checked on every access
+    watchpoints: WatchpointManager,
+    /// First watchpoint hit since the last `take_watchpoint_hit`
+    watchpoint_hit: Mutex<Option<WatchpointHit>>,
+    /// Pages written since the last snapshot (one bit per page)
+    dirty: Box<[AtomicU64]>,
+    /// Generation of the current memory state
+    generation: AtomicU64,
+    /// Next generation to hand out to a snapshot or delta
+    next_generation: AtomicU64,
+    /// Advanced whenever any page's flags change
+    protection_epoch: AtomicU64,
+}
+
+/// Write access to the page flags that advances the protection epoch when released
+struct PageFlagsGuard<'a> {
+    flags: RwLockWriteGuard<'a, Vec<PageFlags>>,
+    epoch: &'a AtomicU64,
+}
+
+impl Deref for PageFlagsGuard<'_> {
+    type Target = Vec<PageFlags>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.flags
+    }
+}
+
+impl DerefMut for PageFlagsGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.flags
+    }
+}
+
+impl Drop for PageFlagsGuard<'_> {
+    fn drop(&mut self) {
+        // Still under the lock, so a reader that sees the new epoch sees the new flags
+        self.epoch.fetch_add(1, Ordering::Release);
+    }
 }
 
 // Safety: Memory is accessed through atomic operations and proper synchronization
@@ -46,11 +95,13 @@ unsafe impl Sync for MemoryManager {}
 impl MemoryManager {
     /// Create a new memory manager
     pub fn new() -> Result<Arc<Self>, MemoryError> {
-        // Allocate main address space
-        let base = Self::allocate_address_space(ADDRESS_SPACE_SIZE)?;
+        // Reserve the main address space; pages are committed on demand
+        let mut arena = FastmemArena::reserve(ADDRESS_SPACE_SIZE)?;
+        arena.register_faults();
+        let base = arena.base();
 
         // Allocate RSX memory separately
-        let rsx_mem = Self::allocate_address_space(RSX_MEM_SIZE as usize)?;
+        let rsx_mem = FastmemArena::committed(RSX_MEM_SIZE as usize)?;
 
         // Create page tracking
         let allocation_map = RwLock::new(vec![0u64; NUM_PAGES / 64]);
@@ -91,11 +142,18 @@ impl MemoryManager {
 
         let mut manager = Self {
             base,
+            arena,
             allocation_map,
             page_flags,
             reservations,
             regions,
             rsx_mem,
+            watchpoints: WatchpointManager::new(),
+            watchpoint_hit: Mutex::new(None),
+            dirty: (0..NUM_PAGES / 64).map(|_| AtomicU64::new(0)).collect(),
+            generation: AtomicU64::new(0),
+            next_generation: AtomicU64::new(1),
+            protection_epoch: AtomicU64::new(0),
         };
 
         // Initialize standard regions
@@ -104,51 +162,10 @@ impl MemoryManager {
         Ok(Arc::new(manager))
     }
 
-    #[cfg(unix)]
-    fn allocate_address_space(size: usize) -> Result<*mut u8, MemoryError> {
-        use libc::{mmap, MAP_ANONYMOUS, MAP_PRIVATE, PROT_READ, PROT_WRITE};
-
-        let ptr = unsafe {
-            mmap(
-                std::ptr::null_mut(),
-                size,
-                PROT_READ | PROT_WRITE,
-                MAP_PRIVATE | MAP_ANONYMOUS,
-                -1,
-                0,
-            )
-        };
-
-        if ptr == libc::MAP_FAILED {
-            return Err(MemoryError::OutOfMemory);
-        }
-
-        Ok(ptr as *mut u8)
-    }
-
-    #[cfg(windows)]
-    fn allocate_address_space(size: usize) -> Result<*mut u8, MemoryError> {
-        use windows_sys::Win32::System::Memory::*;
-
-        let ptr = unsafe {
-            VirtualAlloc(
-                std::ptr::null(),
-                size,
-                MEM_RESERVE | MEM_COMMIT,
-                PAGE_READWRITE,
-            )
-        };
-
-        if ptr.is_null() {
-            return Err(MemoryError::OutOfMemory);
-        }
-
-        Ok(ptr as *mut u8)
-    }
-
     fn init_regions(&mut self) -> Result<(), MemoryError> {
-        // Commit main memory
+        // Commit main memory, leaving the null page guard inaccessible
         self.commit_region(MAIN_MEM_BASE, MAIN_MEM_SIZE, PageFlags::RWX)?;
+        self.commit_region(MAIN_MEM_BASE, NULL_PAGE_SIZE, PageFlags::empty())?;
 
         // Commit user memory
         self.commit_region(USER_MEM_BASE, USER_MEM_SIZE, PageFlags::RWX)?;
@@ -163,7 +180,7 @@ impl MemoryManager {
         let start_page = (addr / PAGE_SIZE) as usize;
         let num_pages = (size / PAGE_SIZE) as usize;
 
-        let mut page_flags = self.page_flags.write();
+        let mut page_flags = self.write_page_flags();
 
         for i in start_page..start_page + num_pages {
             if i < page_flags.len() {
@@ -171,6 +188,50 @@ impl MemoryManager {
             }
         }
 
+        self.mark_pages_dirty(start_page, start_page + num_pages);
+        self.sync_host_pages(&page_flags, start_page, start_page + num_pages)
+    }
+
+    /// Update host protection to match guest page flags in `[start_page, end_page)`
+    ///
+    /// Each host page gets the union of the flags of the guest pages it
+    /// covers, so hosts with pages larger than 4 KB never lose access to a
+    /// live neighbour.
+    fn sync_host_pages(
+        &self,
+        page_flags: &[PageFlags],
+        start_page: usize,
+        end_page: usize,
+    ) -> Result<(), MemoryError> {
+        let host_page = fastmem::host_page_size().max(PAGE_SIZE as usize);
+        let per_host = host_page / PAGE_SIZE as usize;
+        let end_page = end_page.min(page_flags.len());
+        if start_page >= end_page {
+            return Ok(());
+        }
+
+        let first = start_page / per_host;
+        let last = end_page.div_ceil(per_host);
+        let host_flags = |host: usize| {
+            let guest = host * per_host;
+            page_flags[guest..(guest + per_host).min(page_flags.len())]
+                .iter()
+                .fold(PageFlags::empty(), |acc, f| acc | (*f & PageFlags::RW))
+        };
+
+        // Coalesce runs of equal protection into a single call
+        let mut run_start = first;
+        let mut run_flags = host_flags(first);
+        for host in first + 1..=last {
+            let flags = if host < last { host_flags(host) } else { PageFlags::all() };
+            if flags != run_flags {
+                self.arena
+                    .protect(run_start * host_page, (host - run_start) * host_page, run_flags)?;
+                run_start = host;
+                run_flags = flags;
+            }
+        }
+
         Ok(())
     }
 
@@ -183,6 +244,356 @@ impl MemoryManager {
         self.base.add(addr as usize)
     }
 
+    /// Change the protection of every page overlapping `[addr, addr + size)`
+    ///
+    /// Use empty flags for a no-access guard, or `PageFlags::RX` to make a
+    /// text segment read-only so stray writes fault instead of corrupting
+    /// code.
+    pub fn protect(&self, addr: u32, size: u32, flags: PageFlags) -> Result<(), MemoryError> {
+        if size == 0 {
+            return Ok(());
+        }
+        let end_addr = addr.checked_add(size - 1).ok_or(MemoryError::InvalidAddress(addr))?;
+        let start_page = (addr / PAGE_SIZE) as usize;
+        let end_page = (end_addr / PAGE_SIZE) as usize + 1;
+
+        let mut page_flags = self.write_page_flags();
+        for page in &mut page_flags[start_page..end_page] {
+            // Keep attribute bits (MMIO, LARGE, ...) and replace the access bits
+            *page = (*page - PageFlags::RWX) | (flags & PageFlags::RWX);
+        }
+
+        tracing::debug!(
+            "Protected 0x{:08x}-0x{:08x} as {:?}",
+            addr,
+            end_addr,
+            flags
+        );
+
+        self.mark_pages_dirty(start_page, end_page);
+        self.sync_host_pages(&page_flags, start_page, end_page)
+    }
+
+    /// Mark the pages overlapping `[addr, addr + size)` as written
+    ///
+    /// Checked writes do this automatically; code that stores through raw
+    /// pointers (`ptr`, `write_unchecked`, fastmem) must call it so the
+    /// change is captured by the next [`MemoryManager::snapshot_delta`].
+    #[inline]
+    pub fn mark_dirty(&self, addr: u32, size: u32) {
+        if size == 0 {
+            return;
+        }
+        let start_page = (addr / PAGE_SIZE) as usize;
+        let end_page = (addr.saturating_add(size - 1) / PAGE_SIZE) as usize;
+        self.mark_pages_dirty(start_page, end_page + 1);
+    }
+
+    /// Mark pages `[start_page, end_page)` as dirty
+    #[inline]
+    fn mark_pages_dirty(&self, start_page: usize, end_page: usize) {
+        for page in start_page..end_page.min(NUM_PAGES) {
+            let word = &self.dirty[page / 64];
+            let bit = 1u64 << (page % 64);
+            // Avoid the atomic RMW when the page is already dirty
+            if word.load(Ordering::Relaxed) & bit == 0 {
+                word.fetch_or(bit, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Page indices written since the last snapshot or delta
+    pub fn dirty_pages(&self) -> Vec<u32> {
+        let mut pages = Vec::new();
+        for (i, word) in self.dirty.iter().enumerate() {
+            let mut bits = word.load(Ordering::Relaxed);
+            while bits != 0 {
+                pages.push((i * 64) as u32 + bits.trailing_zeros());
+                bits &= bits - 1;
+            }
+        }
+        pages
+    }
+
+    /// Take and clear the dirty bitmap, returning the dirty page indices
+    fn take_dirty_pages(&self) -> Vec<u32> {
+        let mut pages = Vec::new();
+        for (i, word) in self.dirty.iter().enumerate() {
+            if word.load(Ordering::Relaxed) == 0 {
+                continue;
+            }
+            let mut bits = word.swap(0, Ordering::AcqRel);
+            while bits != 0 {
+                pages.push((i * 64) as u32 + bits.trailing_zeros());
+                bits &= bits - 1;
+            }
+        }
+        pages
+    }
+
+    /// Lock the page flags for writing
+    fn write_page_flags(&self) -> PageFlagsGuard<'_> {
+        PageFlagsGuard {
+            flags: self.page_flags.write(),
+            epoch: &self.protection_epoch,
+        }
+    }
+
+    /// Counter advanced whenever page flags change, by allocation, freeing,
+    /// protection or restoring a snapshot
+    ///
+    /// Callers caching the result of an access check must drop the cache
+    /// once the epoch moves on.
+    #[inline]
+    pub fn protection_epoch(&self) -> u64 {
+        self.protection_epoch.load(Ordering::Acquire)
+    }
+
+    /// Generation of the current memory state
+    ///
+    /// Advanced by every snapshot or delta, and set by restoring one.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Advance to a fresh generation, returning (previous, new)
+    fn advance_generation(&self) -> (u64, u64) {
+        let next = self.next_generation.fetch_add(1, Ordering::AcqRel);
+        let prev = self.generation.swap(next, Ordering::AcqRel);
+        (prev, next)
+    }
+
+    /// Capture one guest page
+    fn capture_page(&self, page: u32, flags: PageFlags) -> PageSnapshot {
+        if !flags.contains(PageFlags::READ) {
+            return PageSnapshot { flags, data: None };
+        }
+        let contents =
+            unsafe { std::slice::from_raw_parts(self.ptr(page * PAGE_SIZE), PAGE_SIZE as usize) };
+        PageSnapshot::capture(flags, contents)
+    }
+
+    /// Snapshot all mapped guest memory and RSX local memory
+    ///
+    /// Guest threads should be paused; concurrent writes may or may not be
+    /// included. Clears the dirty bitmap, so the next
+    /// [`MemoryManager::snapshot_delta`] is relative to this snapshot.
+    pub fn snapshot(&self) -> MemorySnapshot {
+        let page_flags = self.page_flags.read();
+        let allocation_map = self.allocation_map.read().clone();
+        self.take_dirty_pages();
+        let (_, generation) = self.advance_generation();
+
+        let pages: BTreeMap<u32, PageSnapshot> = page_flags
+            .iter()
+            .enumerate()
+            .filter(|(_, flags)| !flags.is_empty())
+            .map(|(page, flags)| (page as u32, self.capture_page(page as u32, *flags)))
+            .collect();
+
+        let mut rsx_pages = BTreeMap::new();
+        for page in 0..RSX_MEM_SIZE / PAGE_SIZE {
+            let contents = unsafe {
+                std::slice::from_raw_parts(self.rsx_ptr(page * PAGE_SIZE), PAGE_SIZE as usize)
+            };
+            if let Some(data) = PageSnapshot::capture(PageFlags::RW, contents).data {
+                rsx_pages.insert(page, data);
+            }
+        }
+
+        tracing::debug!("Memory snapshot {}: {} pages", generation, pages.len());
+
+        MemorySnapshot {
+            generation,
+            pages,
+            allocation_map,
+            rsx_pages,
+        }
+    }
+
+    /// Capture the pages changed since the last snapshot or delta
+    ///
+    /// RSX local memory is written through raw pointers and is not
+    /// dirty-tracked, so it is only captured by full snapshots.
+    pub fn snapshot_delta(&self) -> MemoryDelta {
+        let page_flags = self.page_flags.read();
+        let allocation_map = self.allocation_map.read().clone();
+        let dirty = self.take_dirty_pages();
+        let (base_generation, generation) = self.advance_generation();
+
+        let pages: BTreeMap<u32, PageSnapshot> = dirty
+            .into_iter()
+            .map(|page| (page, self.capture_page(page, page_flags[page as usize])))
+            .collect();
+
+        tracing::debug!(
+            "Memory delta {} -> {}: {} pages",
+            base_generation,
+            generation,
+            pages.len()
+        );
+
+        MemoryDelta {
+            base_generation,
+            generation,
+            pages,
+            allocation_map,
+        }
+    }
+
+    /// Restore guest memory and RSX local memory from a snapshot
+    ///
+    /// Pages mapped now but absent from the snapshot are zeroed and
+    /// unmapped. Reservations on restored memory are invalidated.
+    pub fn restore(&self, snapshot: &MemorySnapshot) -> Result<(), MemoryError> {
+        let mut page_flags = self.write_page_flags();
+        let mut allocation_map = self.allocation_map.write();
+
+        let mut target = vec![PageFlags::empty(); page_flags.len()];
+        for (&page, saved) in &snapshot.pages {
+            if let Some(flags) = target.get_mut(page as usize) {
+                *flags = saved.flags;
+            }
+        }
+        let changed: Vec<u32> = (0..page_flags.len() as u32)
+            .filter(|&page| {
+                !page_flags[page as usize].is_empty() || !target[page as usize].is_empty()
+            })
+            .collect();
+
+        let empty = PageSnapshot::unmapped();
+        self.restore_pages(
+            &mut page_flags,
+            target,
+            changed.iter().map(|&page| (page, snapshot.pages.get(&page).unwrap_or(&empty))),
+        )?;
+        restore_bitmap(&mut allocation_map, &snapshot.allocation_map);
+
+        for page in 0..RSX_MEM_SIZE / PAGE_SIZE {
+            let dest = self.rsx_ptr(page * PAGE_SIZE);
+            unsafe {
+                match snapshot.rsx_pages.get(&page) {
+                    Some(data) => std::ptr::copy_nonoverlapping(data.as_ptr(), dest, PAGE_SIZE as usize),
+                    None => std::ptr::write_bytes(dest, 0, PAGE_SIZE as usize),
+                }
+            }
+        }
+
+        self.take_dirty_pages();
+        self.generation.store(snapshot.generation, Ordering::Release);
+        tracing::debug!("Restored memory snapshot {}", snapshot.generation);
+        Ok(())
+    }
+
+    /// Apply a delta on top of memory at the delta's base generation
+    pub fn apply_delta(&self, delta: &MemoryDelta) -> Result<(), MemoryError> {
+        let mut page_flags = self.write_page_flags();
+        let mut allocation_map = self.allocation_map.write();
+
+        let actual = self.generation();
+        if actual != delta.base_generation {
+            return Err(MemoryError::SnapshotMismatch {
+                expected: delta.base_generation,
+                actual,
+            });
+        }
+
+        let mut target = page_flags.clone();
+        for (&page, saved) in &delta.pages {
+            if let Some(flags) = target.get_mut(page as usize) {
+                *flags = saved.flags;
+            }
+        }
+        self.restore_pages(&mut page_flags, target, delta.pages.iter().map(|(&p, s)| (p, s)))?;
+        restore_bitmap(&mut allocation_map, &delta.allocation_map);
+
+        self.take_dirty_pages();
+        self.generation.store(delta.generation, Ordering::Release);
+        Ok(())
+    }
+
+    /// Write saved pages back and switch the page table to `target`
+    ///
+    /// Restored pages are made host-writable while their contents are
+    /// copied, then every page gets its final protection.
+    fn restore_pages<'a>(
+        &self,
+        page_flags: &mut Vec<PageFlags>,
+        target: Vec<PageFlags>,
+        pages: impl Iterator<Item = (u32, &'a PageSnapshot)> + Clone,
+    ) -> Result<(), MemoryError> {
+        let Some((first, last)) = pages
+            .clone()
+            .map(|(page, _)| page as usize)
+            .filter(|&page| page < page_flags.len())
+            .fold(None, |range: Option<(usize, usize)>, page| {
+                Some(range.map_or((page, page), |(lo, hi)| (lo.min(page), hi.max(page))))
+            })
+        else {
+            *page_flags = target;
+            return Ok(());
+        };
+
+        // Stage: everything being restored (or scrubbed) is writable
+        let mut staging = page_flags.clone();
+        for (page, _) in pages.clone() {
+            if let Some(flags) = staging.get_mut(page as usize) {
+                *flags |= PageFlags::RW;
+            }
+        }
+        self.sync_host_pages(&staging, first, last + 1)?;
+
+        for (page, saved) in pages {
+            if page as usize >= page_flags.len() {
+                continue;
+            }
+            let dest = unsafe { self.ptr(page * PAGE_SIZE) };
+            unsafe {
+                match &saved.data {
+                    Some(data) => std::ptr::copy_nonoverlapping(
+                        data.as_ptr(),
+                        dest,
+                        data.len().min(PAGE_SIZE as usize),
+                    ),
+                    None if saved.flags.contains(PageFlags::READ) || saved.flags.is_empty() => {
+                        std::ptr::write_bytes(dest, 0, PAGE_SIZE as usize)
+                    }
+                    None => {}
+                }
+            }
+
+            // Any reservation on the restored page is stale now
+            let first_line = (page * PAGE_SIZE / RESERVATION_GRANULARITY) as usize;
+            let lines = (PAGE_SIZE / RESERVATION_GRANULARITY) as usize;
+            for reservation in &self.reservations[first_line..first_line + lines] {
+                reservation.invalidate();
+            }
+        }
+
+        *page_flags = target;
+        self.sync_host_pages(page_flags, first, last + 1)
+    }
+
+    /// Get the flags of the page containing `addr`
+    pub fn page_flags(&self, addr: u32) -> PageFlags {
+        self.page_flags.read()[(addr / PAGE_SIZE) as usize]
+    }
+
+    /// Host base of the guest address space
+    ///
+    /// Guest address `addr` lives at `fastmem_base() + addr`. Unmapped
+    /// guest pages are inaccessible on the host, so generated code can use
+    /// this directly and rely on the fault handler for bad accesses.
+    #[inline(always)]
+    pub fn fastmem_base(&self) -> *mut u8 {
+        self.base
+    }
+
+    /// Guest address of the last fault inside a fastmem arena, if any
+    pub fn last_fastmem_fault() -> Option<u32> {
+        fastmem::last_fault()
+    }
+
     /// Get pointer with bounds and permission checking
     pub fn get_ptr(&self, addr: u32, size: u32, flags: PageFlags) -> Result<*mut u8, MemoryError> {
         self.check_access(addr, size, flags)?;
@@ -219,10 +630,69 @@ impl MemoryManager {
         Ok(())
     }
 
+    /// Get the data watchpoint manager
+    pub fn watchpoints(&self) -> &WatchpointManager {
+        &self.watchpoints
+    }
+
+    /// Take the pending watchpoint hit, if any
+    ///
+    /// Accesses complete normally when a watchpoint triggers; the hit is
+    /// latched here so the CPU core can break after the instruction.
+    pub fn take_watchpoint_hit(&self) -> Option<WatchpointHit> {
+        if !self.watchpoints.is_active() {
+            return None;
+        }
+        self.watchpoint_hit.lock().take()
+    }
+
+    /// Read the big-endian value of up to 8 bytes at `addr` (no checks)
+    fn peek_be(&self, addr: u32, size: u32) -> Option<u64> {
+        if size == 0 || size > 8 {
+            return None;
+        }
+        let mut value = 0u64;
+        for i in 0..size {
+            let byte: u8 = unsafe { self.read_unchecked(addr.wrapping_add(i)) };
+            value = (value << 8) | byte as u64;
+        }
+        Some(value)
+    }
+
+    /// Evaluate watchpoints for an access and latch the first hit
+    #[cold]
+    fn check_watchpoints(
+        &self,
+        addr: u32,
+        size: u32,
+        kind: AccessKind,
+        old_value: Option<u64>,
+        new_value: Option<u64>,
+    ) {
+        if let Some(hit) = self.watchpoints.check_access_value(addr, size, kind, old_value, new_value) {
+            tracing::debug!(
+                "Watchpoint 0x{:08x} hit: {} of {} bytes at 0x{:08x}",
+                hit.wp_addr,
+                kind,
+                size,
+                addr
+            );
+            let mut pending = self.watchpoint_hit.lock();
+            if pending.is_none() {
+                *pending = Some(hit);
+            }
+        }
+    }
+
     /// Read a value from memory
     #[inline]
     pub fn read<T: Copy>(&self, addr: u32) -> Result<T, MemoryError> {
-        self.check_access(addr, std::mem::size_of::<T>() as u32, PageFlags::READ)?;
+        let size = std::mem::size_of::<T>() as u32;
+        self.check_access(addr, size, PageFlags::READ)?;
+        if self.watchpoints.is_active() {
+            let value = self.peek_be(addr, size);
+            self.check_watchpoints(addr, size, AccessKind::Read, value, value);
+        }
         Ok(unsafe { self.read_unchecked(addr) })
     }
 
@@ -238,13 +708,25 @@ impl MemoryManager {
     /// Write a value to memory
     #[inline]
     pub fn write<T: Copy>(&self, addr: u32, value: T) -> Result<(), MemoryError> {
-        self.check_access(addr, std::mem::size_of::<T>() as u32, PageFlags::WRITE)?;
-        unsafe { self.write_unchecked(addr, value) };
+        let size = std::mem::size_of::<T>() as u32;
+        self.check_access(addr, size, PageFlags::WRITE)?;
+        self.mark_dirty(addr, size);
+        if self.watchpoints.is_active() {
+            let old_value = self.peek_be(addr, size);
+            unsafe { self.write_unchecked(addr, value) };
+            let new_value = self.peek_be(addr, size);
+            self.check_watchpoints(addr, size, AccessKind::Write, old_value, new_value);
+        } else {
+            unsafe { self.write_unchecked(addr, value) };
+        }
         Ok(())
     }
 
     /// Write without checking (for hot paths after validation)
     ///
+    /// The page is not marked dirty; call [`MemoryManager::mark_dirty`]
+    /// if the write must be picked up by the next snapshot delta.
+    ///
     /// # Safety
     /// Caller must ensure the address is valid and writable.
     #[inline(always)]
@@ -265,7 +747,7 @@ impl MemoryManager {
         let num_pages = aligned_size / PAGE_SIZE;
 
         let mut allocation_map = self.allocation_map.write();
-        let mut page_flags = self.page_flags.write();
+        let mut page_flags = self.write_page_flags();
 
         // Find contiguous free pages in user memory region
         let start_page = (USER_MEM_BASE / PAGE_SIZE) as usize;
@@ -307,6 +789,9 @@ impl MemoryManager {
             page_flags[page] = flags;
         }
 
+        self.mark_pages_dirty(alloc_start_page, alloc_start_page + num_pages as usize);
+        self.sync_host_pages(&page_flags, alloc_start_page, alloc_start_page + num_pages as usize)?;
+
         Ok((alloc_start_page as u32) * PAGE_SIZE)
     }
 
@@ -316,7 +801,7 @@ impl MemoryManager {
         let num_pages = size.div_ceil(PAGE_SIZE) as usize;
 
         let mut allocation_map = self.allocation_map.write();
-        let mut page_flags = self.page_flags.write();
+        let mut page_flags = self.write_page_flags();
 
         for page in start_page..start_page + num_pages {
             if page < allocation_map.len() * 64 {
@@ -329,26 +814,69 @@ impl MemoryManager {
             }
         }
 
+        self.mark_pages_dirty(start_page, start_page + num_pages);
+        self.sync_host_pages(&page_flags, start_page, start_page + num_pages)?;
+
         Ok(())
     }
 
     /// Get RSX memory pointer
     pub fn rsx_ptr(&self, offset: u32) -> *mut u8 {
-        unsafe { self.rsx_mem.add(offset as usize) }
+        unsafe { self.rsx_mem.base().add(offset as usize) }
+    }
+
+    /// Copy data from RSX local memory at `offset`
+    pub fn read_rsx_bytes(&self, offset: u32, size: u32) -> Result<Vec<u8>, MemoryError> {
+        match offset.checked_add(size) {
+            Some(end) if end <= RSX_MEM_SIZE => {}
+            _ => return Err(MemoryError::InvalidAddress(RSX_MEM_BASE.wrapping_add(offset))),
+        }
+        let mut data = vec![0u8; size as usize];
+        unsafe {
+            std::ptr::copy_nonoverlapping(self.rsx_ptr(offset), data.as_mut_ptr(), size as usize);
+        }
+        Ok(data)
+    }
+
+    /// Copy data to RSX local memory at `offset`
+    pub fn write_rsx_bytes(&self, offset: u32, data: &[u8]) -> Result<(), MemoryError> {
+        match u32::try_from(data.len()).ok().and_then(|size| offset.checked_add(size)) {
+            Some(end) if end <= RSX_MEM_SIZE => {}
+            _ => return Err(MemoryError::InvalidAddress(RSX_MEM_BASE.wrapping_add(offset))),
+        }
+        unsafe {
+            std::ptr::copy_nonoverlapping(data.as_ptr(), self.rsx_ptr(offset), data.len());
+        }
+        Ok(())
     }
 
     /// Copy data to memory
     pub fn write_bytes(&self, addr: u32, data: &[u8]) -> Result<(), MemoryError> {
-        self.check_access(addr, data.len() as u32, PageFlags::WRITE)?;
+        let size = data.len() as u32;
+        self.check_access(addr, size, PageFlags::WRITE)?;
+        self.mark_dirty(addr, size);
+        let old_value = if self.watchpoints.is_active() {
+            self.peek_be(addr, size)
+        } else {
+            None
+        };
         unsafe {
             std::ptr::copy_nonoverlapping(data.as_ptr(), self.ptr(addr), data.len());
         }
+        if self.watchpoints.is_active() {
+            let new_value = self.peek_be(addr, size);
+            self.check_watchpoints(addr, size, AccessKind::Write, old_value, new_value);
+        }
         Ok(())
     }
 
     /// Copy data from memory
     pub fn read_bytes(&self, addr: u32, size: u32) -> Result<Vec<u8>, MemoryError> {
         self.check_access(addr, size, PageFlags::READ)?;
+        if self.watchpoints.is_active() {
+            let value = self.peek_be(addr, size);
+            self.check_watchpoints(addr, size, AccessKind::Read, value, value);
+        }
         let mut data = vec![0u8; size as usize];
         unsafe {
             std::ptr::copy_nonoverlapping(self.ptr(addr), data.as_mut_ptr(), size as usize);
@@ -361,6 +889,112 @@ impl MemoryManager {
         &self.regions
     }
 
+    /// Usage and fragmentation of `[base, base + size)`
+    pub fn region_stats(&self, name: &'static str, base: u32, size: u32) -> RegionStats {
+        let allocation_map = self.allocation_map.read();
+        let page_flags = self.page_flags.read();
+
+        let mut stats = RegionStats {
+            name,
+            base,
+            size,
+            mapped: 0,
+            allocated: 0,
+            free_blocks: 0,
+            largest_free_block: 0,
+        };
+
+        let start_page = (base / PAGE_SIZE) as usize;
+        let end_page = (start_page + (size / PAGE_SIZE) as usize).min(NUM_PAGES);
+        let mut run = 0u32;
+        let mut run_allocated = false;
+        for page in start_page..end_page {
+            if !page_flags[page].is_empty() {
+                stats.mapped += PAGE_SIZE;
+            }
+            let allocated = allocation_map[page / 64] & (1u64 << (page % 64)) != 0;
+            if allocated != run_allocated {
+                stats.add_run(run, run_allocated);
+                run = 0;
+                run_allocated = allocated;
+            }
+            run += 1;
+        }
+        stats.add_run(run, run_allocated);
+
+        stats
+    }
+
+    /// Usage of every memory region
+    pub fn stats(&self) -> MemoryStats {
+        MemoryStats {
+            regions: self
+                .regions
+                .iter()
+                .map(|r| self.region_stats(r.name, r.base, r.size))
+                .collect(),
+        }
+    }
+
+    /// Allocated fraction of each of `buckets` equal slices of a range
+    ///
+    /// Used to draw an allocation map where each cell covers many pages.
+    pub fn allocation_view(&self, base: u32, size: u32, buckets: usize) -> Vec<f32> {
+        let allocation_map = self.allocation_map.read();
+        let start_page = (base / PAGE_SIZE) as usize;
+        let pages = (size / PAGE_SIZE) as usize;
+        if buckets == 0 || pages == 0 {
+            return Vec::new();
+        }
+
+        (0..buckets)
+            .map(|bucket| {
+                let first = start_page + bucket * pages / buckets;
+                let last = (start_page + (bucket + 1) * pages / buckets).max(first + 1);
+                let used = (first..last.min(NUM_PAGES))
+                    .filter(|&page| allocation_map[page / 64] & (1u64 << (page % 64)) != 0)
+                    .count();
+                used as f32 / (last - first) as f32
+            })
+            .collect()
+    }
+
+    /// Read a big-endian value
+    ///
+    /// Works for primitives, arrays and `#[derive(BeValue)]` structs, so a
+    /// whole guest struct can be loaded in one call.
+    pub fn read_be<T: BeValue>(&self, addr: u32) -> Result<T, MemoryError> {
+        let size = T::SIZE as u32;
+        self.check_access(addr, size, PageFlags::READ)?;
+        if self.watchpoints.is_active() {
+            let value = self.peek_be(addr, size);
+            self.check_watchpoints(addr, size, AccessKind::Read, value, value);
+        }
+        let bytes = unsafe { std::slice::from_raw_parts(self.ptr(addr), T::SIZE) };
+        Ok(T::from_be_slice(bytes))
+    }
+
+    /// Write a big-endian value
+    ///
+    /// Struct padding in guest memory is left untouched.
+    pub fn write_be<T: BeValue>(&self, addr: u32, value: T) -> Result<(), MemoryError> {
+        let size = T::SIZE as u32;
+        self.check_access(addr, size, PageFlags::WRITE)?;
+        self.mark_dirty(addr, size);
+        let old_value = if self.watchpoints.is_active() {
+            self.peek_be(addr, size)
+        } else {
+            None
+        };
+        let out = unsafe { std::slice::from_raw_parts_mut(self.ptr(addr), T::SIZE) };
+        value.write_be_slice(out);
+        if self.watchpoints.is_active() {
+            let new_value = self.peek_be(addr, size);
+            self.check_watchpoints(addr, size, AccessKind::Write, old_value, new_value);
+        }
+        Ok(())
+    }
+
     /// Read a big-endian u16 (PS3 is big-endian)
     #[inline]
     pub fn read_be16(&self, addr: u32) -> Result<u16, MemoryError> {
@@ -401,21 +1035,11 @@ impl MemoryManager {
     }
 }
 
-impl Drop for MemoryManager {
-    fn drop(&mut self) {
-        #[cfg(unix)]
-        unsafe {
-            libc::munmap(self.base as *mut libc::c_void, ADDRESS_SPACE_SIZE);
-            libc::munmap(self.rsx_mem as *mut libc::c_void, RSX_MEM_SIZE as usize);
-        }
-
-        #[cfg(windows)]
-        unsafe {
-            use windows_sys::Win32::System::Memory::*;
-            VirtualFree(self.base as *mut _, 0, MEM_RELEASE);
-            VirtualFree(self.rsx_mem as *mut _, 0, MEM_RELEASE);
-        }
-    }
+/// Copy a saved allocation bitmap over the live one
+fn restore_bitmap(live: &mut [u64], saved: &[u64]) {
+    live.fill(0);
+    let len = live.len().min(saved.len());
+    live[..len].copy_from_slice(&saved[..len]);
 }
 
 #[cfg(test)]
@@ -478,6 +1102,205 @@ mod tests {
         assert_eq!(read_data, data);
     }
 
+    #[test]
+    fn test_read_rsx_bytes() {
+        let mem = MemoryManager::new().unwrap();
+        unsafe { mem.rsx_ptr(0x200).copy_from_nonoverlapping([1u8, 2, 3, 4].as_ptr(), 4) };
+        assert_eq!(mem.read_rsx_bytes(0x200, 4).unwrap(), [1, 2, 3, 4]);
+        assert!(mem.read_rsx_bytes(RSX_MEM_SIZE - 2, 4).is_err());
+        mem.write_rsx_bytes(0x202, &[5, 6]).unwrap();
+        assert_eq!(mem.read_rsx_bytes(0x200, 4).unwrap(), [1, 2, 5, 6]);
+        assert!(mem.write_rsx_bytes(RSX_MEM_SIZE - 2, &[0; 4]).is_err());
+    }
+
+    #[test]
+    fn test_read_write_be_struct() {
+        use crate::BeValue;
+
+        #[derive(Debug, Clone, Copy, PartialEq, BeValue)]
+        #[repr(C)]
+        struct GuestHeader {
+            magic: [u8; 4],
+            version: u16,
+            flags: u16,
+            entry: u64,
+        }
+
+        let mem = MemoryManager::new().unwrap();
+        let addr = mem.allocate(0x1000, 0x1000, PageFlags::RW).unwrap();
+
+        let header = GuestHeader {
+            magic: *b"SCE\0",
+            version: 2,
+            flags: 0x8001,
+            entry: 0x1_0000_0200,
+        };
+        mem.write_be(addr, header).unwrap();
+
+        assert_eq!(mem.read_be32(addr).unwrap(), 0x5343_4500);
+        assert_eq!(mem.read_be16(addr + 4).unwrap(), 2);
+        assert_eq!(mem.read_be64(addr + 8).unwrap(), 0x1_0000_0200);
+        assert_eq!(mem.read_be::<GuestHeader>(addr).unwrap(), header);
+
+        mem.write_be(addr + 0x20, -5i32).unwrap();
+        assert_eq!(mem.read_be::<i32>(addr + 0x20).unwrap(), -5);
+        assert!(mem.read_be::<GuestHeader>(0xFFFF_FFF8).is_err());
+    }
+
+    #[test]
+    fn test_fastmem_direct_access() {
+        let mem = MemoryManager::new().unwrap();
+        let addr = mem.allocate(0x2000, 0x1000, PageFlags::RW).unwrap();
+
+        mem.write_be32(addr + 0x1004, 0x12345678).unwrap();
+        let raw = unsafe { (mem.fastmem_base().add(addr as usize + 0x1004) as *const u32).read_unaligned() };
+        assert_eq!(u32::from_be(raw), 0x12345678);
+
+        // Freed pages are no longer accessible
+        mem.free(addr, 0x2000).unwrap();
+        assert!(mem.read_be32(addr + 0x1004).is_err());
+
+        // Re-allocating recommits the host pages
+        let again = mem.allocate(0x2000, 0x1000, PageFlags::RW).unwrap();
+        mem.write_be32(again, 0xCAFEBABE).unwrap();
+        assert_eq!(mem.read_be32(again).unwrap(), 0xCAFEBABE);
+    }
+
+    #[test]
+    fn test_protect_pages() {
+        let mem = MemoryManager::new().unwrap();
+
+        // The null guard rejects every access
+        assert!(matches!(mem.read_be32(0x10), Err(MemoryError::AccessViolation { .. })));
+        assert!(mem.write_be32(0x10, 1).is_err());
+
+        let addr = mem.allocate(0x2000, 0x1000, PageFlags::RW).unwrap();
+        mem.write_be32(addr, 0x11111111).unwrap();
+        mem.protect(addr, 0x1000, PageFlags::READ).unwrap();
+        assert_eq!(mem.page_flags(addr), PageFlags::READ);
+
+        // Read-only page keeps its contents but rejects stores
+        assert_eq!(mem.read_be32(addr).unwrap(), 0x11111111);
+        assert!(matches!(mem.write_be32(addr, 0), Err(MemoryError::AccessViolation { .. })));
+        mem.write_be32(addr + 0x1000, 0x22222222).unwrap();
+
+        // Restoring write access makes the page writable again
+        mem.protect(addr, 0x1000, PageFlags::RW).unwrap();
+        mem.write_be32(addr, 0x33333333).unwrap();
+        assert_eq!(mem.read_be32(addr).unwrap(), 0x33333333);
+    }
+
+    #[test]
+    fn test_region_stats() {
+        let mem = MemoryManager::new().unwrap();
+        let a = mem.allocate(0x2000, 0x1000, PageFlags::RW).unwrap();
+        let b = mem.allocate(0x1000, 0x1000, PageFlags::RW).unwrap();
+        mem.allocate(0x1000, 0x1000, PageFlags::RW).unwrap();
+        mem.free(b, 0x1000).unwrap();
+
+        let stats = mem.stats();
+        let user = stats.region("User Memory").unwrap();
+        assert_eq!(user.base, a);
+        assert_eq!(user.allocated, 0x3000);
+        assert_eq!(user.mapped, USER_MEM_SIZE - 0x1000);
+        // The freed hole plus the rest of the region
+        assert_eq!(user.free_blocks, 2);
+        assert_eq!(user.largest_free_block, USER_MEM_SIZE - 0x4000);
+        assert!(user.fragmentation() > 0.0);
+        assert_eq!(stats.total_allocated(), 0x3000);
+
+        let view = mem.allocation_view(a, 0x4000, 4);
+        assert_eq!(view, [1.0, 1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let mem = MemoryManager::new().unwrap();
+        let addr = mem.allocate(0x2000, 0x1000, PageFlags::RW).unwrap();
+        mem.write_be32(addr, 0x11111111).unwrap();
+        unsafe { mem.rsx_ptr(0x100).write(0x42) };
+
+        let snapshot = mem.snapshot();
+        assert!(mem.dirty_pages().is_empty());
+        assert_eq!(snapshot.pages[&(addr / PAGE_SIZE)].flags, PageFlags::RW);
+
+        // Mutate memory, the allocation map and RSX memory
+        mem.write_be32(addr, 0x22222222).unwrap();
+        let later = mem.allocate(0x1000, 0x1000, PageFlags::RW).unwrap();
+        mem.write_be32(later, 0x33333333).unwrap();
+        unsafe { mem.rsx_ptr(0x100).write(0) };
+
+        mem.restore(&snapshot).unwrap();
+        assert_eq!(mem.read_be32(addr).unwrap(), 0x11111111);
+        assert_eq!(mem.read_be32(later).unwrap(), 0);
+        assert_eq!(unsafe { mem.rsx_ptr(0x100).read() }, 0x42);
+        assert_eq!(mem.generation(), snapshot.generation);
+
+        // The page allocated after the snapshot is free again
+        assert_eq!(mem.allocate(0x1000, 0x1000, PageFlags::RW).unwrap(), later);
+    }
+
+    #[test]
+    fn test_snapshot_delta() {
+        let mem = MemoryManager::new().unwrap();
+        let addr = mem.allocate(0x3000, 0x1000, PageFlags::RW).unwrap();
+        let mut base = mem.snapshot();
+
+        mem.write_be32(addr + 0x1000, 0xAAAAAAAA).unwrap();
+        mem.protect(addr + 0x2000, 0x1000, PageFlags::READ).unwrap();
+        let pages = [(addr + 0x1000) / PAGE_SIZE, (addr + 0x2000) / PAGE_SIZE];
+        assert_eq!(mem.dirty_pages(), pages);
+
+        let delta = mem.snapshot_delta();
+        assert_eq!(delta.base_generation, base.generation);
+        assert_eq!(delta.pages.keys().copied().collect::<Vec<_>>(), pages);
+        assert!(mem.dirty_pages().is_empty());
+
+        // Roll back to the base snapshot, then replay the delta
+        mem.restore(&base).unwrap();
+        assert_eq!(mem.read_be32(addr + 0x1000).unwrap(), 0);
+        mem.apply_delta(&delta).unwrap();
+        assert_eq!(mem.read_be32(addr + 0x1000).unwrap(), 0xAAAAAAAA);
+        assert!(mem.write_be32(addr + 0x2000, 1).is_err());
+
+        // Applying twice is rejected, folding into the snapshot works
+        assert!(matches!(
+            mem.apply_delta(&delta),
+            Err(MemoryError::SnapshotMismatch { .. })
+        ));
+        assert!(base.apply_delta(&delta));
+        assert_eq!(base.pages[&pages[1]].flags, PageFlags::READ);
+    }
+
+    #[test]
+    fn test_watchpoint_hit_latched() {
+        use crate::debug::{WatchpointCondition, WatchpointType};
+
+        let mem = MemoryManager::new().unwrap();
+        let addr = mem.allocate(0x1000, 0x1000, PageFlags::RW).unwrap();
+
+        mem.watchpoints().add_conditional(
+            addr + 8,
+            4,
+            WatchpointType::Write,
+            WatchpointCondition::Equals(0xCAFEBABE),
+        );
+
+        // Non-matching value and unwatched address do not trigger
+        mem.write_be32(addr + 8, 0x12345678).unwrap();
+        mem.write_be32(addr, 0xCAFEBABE).unwrap();
+        assert!(mem.take_watchpoint_hit().is_none());
+
+        // Matching write completes and latches a hit
+        mem.write_be32(addr + 8, 0xCAFEBABE).unwrap();
+        assert_eq!(mem.read_be32(addr + 8).unwrap(), 0xCAFEBABE);
+        let hit = mem.take_watchpoint_hit().unwrap();
+        assert_eq!(hit.access_addr, addr + 8);
+        assert_eq!(hit.kind, AccessKind::Write);
+        assert_eq!(hit.value, Some(0xCAFEBABE));
+        assert!(mem.take_watchpoint_hit().is_none());
+    }
+
     #[test]
     fn test_reservation() {
         let mem = MemoryManager::new().unwrap();
@@ -0,0 +1,333 @@
+//! Guest memory snapshots and deltas
+//!
+//! A [`MemorySnapshot`] captures every mapped guest page (contents and
+//! flags) plus the allocation map, and is the memory half of a savestate.
+//! [`MemoryDelta`] holds only the pages written since the previous
+//! snapshot or delta, using the manager's dirty-page bitmap, so periodic
+//! snapshots for rewind or replay cost time proportional to what changed.
+//!
+//! Each snapshot or delta taken advances the manager's generation. A delta
+//! records the generation it was taken on top of and can only be applied
+//! to memory (or a snapshot) at exactly that generation.
+
+use crate::constants::PAGE_SIZE;
+use crate::pages::PageFlags;
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+
+/// Magic at the start of a serialized snapshot
+const SNAPSHOT_MAGIC: &[u8; 8] = b"OCMEMSN1";
+/// Magic at the start of a serialized delta
+const DELTA_MAGIC: &[u8; 8] = b"OCMEMDL1";
+
+/// Saved state of one guest page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageSnapshot {
+    /// Page flags at the time of the snapshot (empty = unmapped)
+    pub flags: PageFlags,
+    /// Page contents, or `None` if the page was all zeros or unreadable
+    pub data: Option<Box<[u8]>>,
+}
+
+impl PageSnapshot {
+    /// Capture a page, storing all-zero pages without data
+    pub fn capture(flags: PageFlags, contents: &[u8]) -> Self {
+        let data = if contents.iter().all(|&b| b == 0) {
+            None
+        } else {
+            Some(contents.to_vec().into_boxed_slice())
+        };
+        Self { flags, data }
+    }
+
+    /// An unmapped page
+    pub fn unmapped() -> Self {
+        Self {
+            flags: PageFlags::empty(),
+            data: None,
+        }
+    }
+
+    /// Bytes of page data held by this entry
+    pub fn stored_bytes(&self) -> usize {
+        self.data.as_ref().map_or(0, |d| d.len())
+    }
+}
+
+/// Full snapshot of guest memory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemorySnapshot {
+    /// Manager generation captured by this snapshot
+    pub generation: u64,
+    /// Mapped pages by page index; pages not listed are unmapped
+    pub pages: BTreeMap<u32, PageSnapshot>,
+    /// User memory allocation bitmap (one bit per page)
+    pub allocation_map: Vec<u64>,
+    /// RSX local memory pages by page index (all-zero pages omitted)
+    pub rsx_pages: BTreeMap<u32, Box<[u8]>>,
+}
+
+/// Pages changed since a previous snapshot or delta
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryDelta {
+    /// Generation this delta must be applied on top of
+    pub base_generation: u64,
+    /// Generation reached after applying this delta
+    pub generation: u64,
+    /// Changed pages by page index; unmapped pages have empty flags
+    pub pages: BTreeMap<u32, PageSnapshot>,
+    /// User memory allocation bitmap after the change
+    pub allocation_map: Vec<u64>,
+}
+
+impl MemorySnapshot {
+    /// Number of mapped pages
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Bytes of page data held (excluding zero pages)
+    pub fn stored_bytes(&self) -> usize {
+        self.pages.values().map(PageSnapshot::stored_bytes).sum::<usize>()
+            + self.rsx_pages.values().map(|d| d.len()).sum::<usize>()
+    }
+
+    /// Fold a delta into this snapshot, advancing it to the delta's generation
+    ///
+    /// Returns false (leaving the snapshot untouched) if the delta was not
+    /// taken on top of this snapshot's generation.
+    pub fn apply_delta(&mut self, delta: &MemoryDelta) -> bool {
+        if delta.base_generation != self.generation {
+            return false;
+        }
+
+        for (&page, snapshot) in &delta.pages {
+            if snapshot.flags.is_empty() {
+                self.pages.remove(&page);
+            } else {
+                self.pages.insert(page, snapshot.clone());
+            }
+        }
+        self.allocation_map.clone_from(&delta.allocation_map);
+        self.generation = delta.generation;
+        true
+    }
+
+    /// Serialize the snapshot
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(SNAPSHOT_MAGIC)?;
+        w.write_all(&self.generation.to_le_bytes())?;
+        write_bitmap(w, &self.allocation_map)?;
+        write_pages(w, &self.pages)?;
+
+        w.write_all(&(self.rsx_pages.len() as u32).to_le_bytes())?;
+        for (&page, data) in &self.rsx_pages {
+            w.write_all(&page.to_le_bytes())?;
+            w.write_all(data)?;
+        }
+        Ok(())
+    }
+
+    /// Deserialize a snapshot written by [`MemorySnapshot::write_to`]
+    pub fn read_from<R: Read>(r: &mut R) -> io::Result<Self> {
+        check_magic(r, SNAPSHOT_MAGIC)?;
+        let generation = read_u64(r)?;
+        let allocation_map = read_bitmap(r)?;
+        let pages = read_pages(r)?;
+
+        let count = read_u32(r)?;
+        let mut rsx_pages = BTreeMap::new();
+        for _ in 0..count {
+            let page = read_u32(r)?;
+            rsx_pages.insert(page, read_page_data(r)?);
+        }
+
+        Ok(Self {
+            generation,
+            pages,
+            allocation_map,
+            rsx_pages,
+        })
+    }
+}
+
+impl MemoryDelta {
+    /// Number of changed pages
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Bytes of page data held (excluding zero pages)
+    pub fn stored_bytes(&self) -> usize {
+        self.pages.values().map(PageSnapshot::stored_bytes).sum()
+    }
+
+    /// Serialize the delta
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(DELTA_MAGIC)?;
+        w.write_all(&self.base_generation.to_le_bytes())?;
+        w.write_all(&self.generation.to_le_bytes())?;
+        write_bitmap(w, &self.allocation_map)?;
+        write_pages(w, &self.pages)
+    }
+
+    /// Deserialize a delta written by [`MemoryDelta::write_to`]
+    pub fn read_from<R: Read>(r: &mut R) -> io::Result<Self> {
+        check_magic(r, DELTA_MAGIC)?;
+        let base_generation = read_u64(r)?;
+        let generation = read_u64(r)?;
+        let allocation_map = read_bitmap(r)?;
+        let pages = read_pages(r)?;
+
+        Ok(Self {
+            base_generation,
+            generation,
+            pages,
+            allocation_map,
+        })
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn check_magic<R: Read>(r: &mut R, magic: &[u8; 8]) -> io::Result<()> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    if &buf != magic {
+        return Err(invalid("bad memory snapshot magic"));
+    }
+    Ok(())
+}
+
+fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_page_data<R: Read>(r: &mut R) -> io::Result<Box<[u8]>> {
+    let mut data = vec![0u8; PAGE_SIZE as usize].into_boxed_slice();
+    r.read_exact(&mut data)?;
+    Ok(data)
+}
+
+fn write_bitmap<W: Write>(w: &mut W, bitmap: &[u64]) -> io::Result<()> {
+    w.write_all(&(bitmap.len() as u32).to_le_bytes())?;
+    for word in bitmap {
+        w.write_all(&word.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_bitmap<R: Read>(r: &mut R) -> io::Result<Vec<u64>> {
+    let len = read_u32(r)? as usize;
+    (0..len).map(|_| read_u64(r)).collect()
+}
+
+/// Page records: index, flags, has-data byte, then the page contents
+fn write_pages<W: Write>(w: &mut W, pages: &BTreeMap<u32, PageSnapshot>) -> io::Result<()> {
+    w.write_all(&(pages.len() as u32).to_le_bytes())?;
+    for (&page, snapshot) in pages {
+        w.write_all(&page.to_le_bytes())?;
+        w.write_all(&snapshot.flags.bits().to_le_bytes())?;
+        w.write_all(&[snapshot.data.is_some() as u8])?;
+        if let Some(data) = &snapshot.data {
+            if data.len() != PAGE_SIZE as usize {
+                return Err(invalid("page data is not one page long"));
+            }
+            w.write_all(data)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_pages<R: Read>(r: &mut R) -> io::Result<BTreeMap<u32, PageSnapshot>> {
+    let count = read_u32(r)?;
+    let mut pages = BTreeMap::new();
+    for _ in 0..count {
+        let page = read_u32(r)?;
+        let flags = PageFlags::from_bits_truncate(read_u32(r)?);
+        let mut has_data = [0u8; 1];
+        r.read_exact(&mut has_data)?;
+        let data = match has_data[0] {
+            0 => None,
+            1 => Some(read_page_data(r)?),
+            _ => return Err(invalid("bad page record")),
+        };
+        pages.insert(page, PageSnapshot { flags, data });
+    }
+    Ok(pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(fill: u8) -> PageSnapshot {
+        PageSnapshot::capture(PageFlags::RW, &vec![fill; PAGE_SIZE as usize])
+    }
+
+    #[test]
+    fn test_zero_pages_are_sparse() {
+        assert!(page(0).data.is_none());
+        assert_eq!(page(0xAA).stored_bytes(), PAGE_SIZE as usize);
+    }
+
+    #[test]
+    fn test_snapshot_apply_delta() {
+        let mut snapshot = MemorySnapshot {
+            generation: 1,
+            pages: BTreeMap::from([(0x10, page(1)), (0x11, page(2))]),
+            allocation_map: vec![0b11],
+            rsx_pages: BTreeMap::new(),
+        };
+        let delta = MemoryDelta {
+            base_generation: 1,
+            generation: 2,
+            pages: BTreeMap::from([(0x10, PageSnapshot::unmapped()), (0x12, page(3))]),
+            allocation_map: vec![0b110],
+        };
+
+        assert!(snapshot.apply_delta(&delta));
+        assert_eq!(snapshot.generation, 2);
+        assert_eq!(snapshot.pages.keys().copied().collect::<Vec<_>>(), [0x11, 0x12]);
+        assert_eq!(snapshot.allocation_map, [0b110]);
+
+        // A delta for another generation is rejected
+        assert!(!snapshot.apply_delta(&delta));
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        let snapshot = MemorySnapshot {
+            generation: 7,
+            pages: BTreeMap::from([(0x20, page(0)), (0x21, page(0x5A))]),
+            allocation_map: vec![1, 2, 3],
+            rsx_pages: BTreeMap::from([(4, vec![9u8; PAGE_SIZE as usize].into_boxed_slice())]),
+        };
+        let mut buf = Vec::new();
+        snapshot.write_to(&mut buf).unwrap();
+        assert_eq!(MemorySnapshot::read_from(&mut buf.as_slice()).unwrap(), snapshot);
+
+        let delta = MemoryDelta {
+            base_generation: 7,
+            generation: 8,
+            pages: BTreeMap::from([(0x21, PageSnapshot::unmapped())]),
+            allocation_map: vec![],
+        };
+        let mut buf = Vec::new();
+        delta.write_to(&mut buf).unwrap();
+        assert_eq!(MemoryDelta::read_from(&mut buf.as_slice()).unwrap(), delta);
+
+        // Reading a delta as a snapshot fails on the magic
+        assert!(MemorySnapshot::read_from(&mut buf.as_slice()).is_err());
+    }
+}
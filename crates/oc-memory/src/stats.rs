@@ -0,0 +1,117 @@
+//! Memory usage accounting
+//!
+//! Per-region usage and fragmentation figures computed from the page
+//! allocation map, for the UI memory statistics panel and for kernel
+//! calls that report free memory to the game.
+
+use crate::constants::PAGE_SIZE;
+
+/// Usage of one memory region
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionStats {
+    /// Region name
+    pub name: &'static str,
+    /// Base address
+    pub base: u32,
+    /// Size in bytes
+    pub size: u32,
+    /// Bytes in pages with any access rights
+    pub mapped: u32,
+    /// Bytes handed out by the allocator
+    pub allocated: u32,
+    /// Number of separate free runs
+    pub free_blocks: u32,
+    /// Largest contiguous free run in bytes
+    pub largest_free_block: u32,
+}
+
+impl RegionStats {
+    /// Bytes not handed out by the allocator
+    pub fn free(&self) -> u32 {
+        self.size - self.allocated
+    }
+
+    /// Fraction of the region that is allocated (0.0 - 1.0)
+    pub fn usage(&self) -> f32 {
+        if self.size == 0 {
+            return 0.0;
+        }
+        self.allocated as f32 / self.size as f32
+    }
+
+    /// External fragmentation (0.0 - 1.0)
+    ///
+    /// 0 when all free memory is one contiguous block; approaches 1 as
+    /// free memory is split into many small holes.
+    pub fn fragmentation(&self) -> f32 {
+        let free = self.free();
+        if free == 0 {
+            return 0.0;
+        }
+        1.0 - self.largest_free_block as f32 / free as f32
+    }
+
+    /// Accumulate a run of pages (`allocated` or free) into the totals
+    pub(crate) fn add_run(&mut self, pages: u32, allocated: bool) {
+        let bytes = pages * PAGE_SIZE;
+        if allocated {
+            self.allocated += bytes;
+        } else if pages > 0 {
+            self.free_blocks += 1;
+            self.largest_free_block = self.largest_free_block.max(bytes);
+        }
+    }
+}
+
+/// Usage of the whole guest address space
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// Per-region usage, in region order
+    pub regions: Vec<RegionStats>,
+}
+
+impl MemoryStats {
+    /// Look up a region by name
+    pub fn region(&self, name: &str) -> Option<&RegionStats> {
+        self.regions.iter().find(|r| r.name == name)
+    }
+
+    /// Total bytes allocated across all regions
+    pub fn total_allocated(&self) -> u64 {
+        self.regions.iter().map(|r| r.allocated as u64).sum()
+    }
+
+    /// Total bytes mapped across all regions
+    pub fn total_mapped(&self) -> u64 {
+        self.regions.iter().map(|r| r.mapped as u64).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fragmentation() {
+        let mut stats = RegionStats {
+            name: "Test",
+            base: 0,
+            size: 8 * PAGE_SIZE,
+            mapped: 0,
+            allocated: 0,
+            free_blocks: 0,
+            largest_free_block: 0,
+        };
+        // [free x2][used x2][free x1][used x1][free x2]
+        for (pages, allocated) in [(2, false), (2, true), (1, false), (1, true), (2, false)] {
+            stats.add_run(pages, allocated);
+        }
+
+        assert_eq!(stats.allocated, 3 * PAGE_SIZE);
+        assert_eq!(stats.free(), 5 * PAGE_SIZE);
+        assert_eq!(stats.free_blocks, 3);
+        assert_eq!(stats.largest_free_block, 2 * PAGE_SIZE);
+        assert!((stats.fragmentation() - 0.6).abs() < 1e-6);
+        assert!((stats.usage() - 0.375).abs() < 1e-6);
+    }
+}
@@ -8,8 +8,12 @@ use oc_memory::{
 fn test_address_space_boundaries() {
     let mem = MemoryManager::new().unwrap();
     
-    // Test that we can access main memory
-    let addr = MAIN_MEM_BASE;
+    // The null guard at the bottom of main memory is inaccessible
+    assert!(mem.read::<u32>(MAIN_MEM_BASE).is_err());
+    assert!(mem.write::<u32>(MAIN_MEM_BASE, 0).is_err());
+
+    // Test that we can access main memory above the guard
+    let addr = MAIN_MEM_BASE + NULL_PAGE_SIZE;
     mem.write::<u32>(addr, 0xDEADBEEF).unwrap();
     assert_eq!(mem.read::<u32>(addr).unwrap(), 0xDEADBEEF);
     
@@ -32,7 +36,7 @@ fn test_memory_region_isolation() {
     let mem = MemoryManager::new().unwrap();
     
     // Write to main memory
-    let main_addr = MAIN_MEM_BASE + 0x1000;
+    let main_addr = MAIN_MEM_BASE + NULL_PAGE_SIZE + 0x1000;
     mem.write::<u32>(main_addr, 0x11111111).unwrap();
     
     // Write to user memory
@@ -91,7 +95,7 @@ fn test_memory_region_permissions() {
     let mem = MemoryManager::new().unwrap();
     
     // Main memory should be RWX
-    let main_addr = MAIN_MEM_BASE + 0x1000;
+    let main_addr = MAIN_MEM_BASE + NULL_PAGE_SIZE + 0x1000;
     mem.write::<u32>(main_addr, 0x12345678).unwrap();
     assert_eq!(mem.read::<u32>(main_addr).unwrap(), 0x12345678);
     
@@ -105,7 +109,7 @@ fn test_memory_region_permissions() {
 fn test_unaligned_access() {
     let mem = MemoryManager::new().unwrap();
     
-    let addr = MAIN_MEM_BASE + 1; // Unaligned address
+    let addr = MAIN_MEM_BASE + NULL_PAGE_SIZE + 1; // Unaligned address
     
     // Should work due to unaligned read/write support
     mem.write::<u32>(addr, 0x12345678).unwrap();
@@ -142,7 +146,7 @@ fn test_allocation_size_rounding() {
 fn test_big_endian_operations() {
     let mem = MemoryManager::new().unwrap();
     
-    let addr = MAIN_MEM_BASE + 0x1000;
+    let addr = MAIN_MEM_BASE + NULL_PAGE_SIZE + 0x1000;
     
     // Test BE16
     mem.write_be16(addr, 0x1234).unwrap();
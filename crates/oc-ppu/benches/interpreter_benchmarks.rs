@@ -0,0 +1,64 @@
+//! Benchmarks for PPU interpreter throughput
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use oc_memory::{MemoryManager, PageFlags};
+use oc_ppu::{PpuInterpreter, PpuThread};
+
+/// Iterations of the loop kernel per benchmark run
+const ITERATIONS: u64 = 10_000;
+
+/// Loop kernel: integer ops, a record form, a load/store pair and bdnz
+const KERNEL: [u32; 5] = [
+    0x38630001, // addi r3, r3, 1
+    0x7C841A15, // add. r4, r4, r3
+    0x80A60000, // lwz r5, 0(r6)
+    0x90A60004, // stw r5, 4(r6)
+    0x4200FFF0, // bdnz -16
+];
+
+fn setup() -> (PpuInterpreter, PpuThread, u32) {
+    let memory = MemoryManager::new().unwrap();
+    let code = memory.allocate(0x1000, 0x1000, PageFlags::RWX).unwrap();
+    let data = memory.allocate(0x1000, 0x1000, PageFlags::RW).unwrap();
+    for (i, &opcode) in KERNEL.iter().enumerate() {
+        memory.write_be32(code + i as u32 * 4, opcode).unwrap();
+    }
+    let interpreter = PpuInterpreter::new(memory.clone());
+    let mut thread = PpuThread::new(0, memory);
+    thread.set_gpr(6, data as u64);
+    (interpreter, thread, code)
+}
+
+fn bench_interpreter(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ppu_interpreter");
+    group.throughput(Throughput::Elements(ITERATIONS * KERNEL.len() as u64));
+
+    group.bench_function("step", |b| {
+        let (interpreter, mut thread, code) = setup();
+        b.iter(|| {
+            thread.set_pc(code as u64);
+            thread.regs.ctr = ITERATIONS;
+            for _ in 0..ITERATIONS * KERNEL.len() as u64 {
+                interpreter.step(&mut thread).unwrap();
+            }
+            black_box(thread.gpr(4))
+        });
+    });
+
+    group.bench_function("run", |b| {
+        let (interpreter, mut thread, code) = setup();
+        b.iter(|| {
+            thread.set_pc(code as u64);
+            thread.regs.ctr = ITERATIONS;
+            let (executed, result) = interpreter.run(&mut thread, ITERATIONS * KERNEL.len() as u64);
+            result.unwrap();
+            assert_eq!(executed, ITERATIONS * KERNEL.len() as u64);
+            black_box(thread.gpr(4))
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_interpreter);
+criterion_main!(benches);
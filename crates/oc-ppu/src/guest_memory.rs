@@ -0,0 +1,198 @@
+//! Inline guest memory access for the interpreter
+//!
+//! Every checked access through [`MemoryManager`] takes the page flags lock.
+//! The interpreter instead remembers, per thread, the last page it fetched
+//! from, read and wrote after a successful check, and goes straight to the
+//! guest base pointer while the access stays on that page. The memory
+//! manager's protection epoch invalidates the remembered pages whenever any
+//! page flags change; watchpoints always take the checked path.
+
+use oc_core::error::MemoryError;
+use oc_memory::constants::PAGE_SIZE;
+use oc_memory::MemoryManager;
+use std::sync::Arc;
+
+/// Page number no address maps to
+const NO_PAGE: u32 = u32::MAX;
+
+/// Pages a thread accessed since the protection epoch last changed
+#[derive(Debug, Clone)]
+pub struct PageCache {
+    epoch: u64,
+    fetch: u32,
+    read: u32,
+    write: u32,
+}
+
+impl PageCache {
+    /// Create a cache with no pages
+    pub fn new() -> Self {
+        Self {
+            epoch: u64::MAX,
+            fetch: NO_PAGE,
+            read: NO_PAGE,
+            write: NO_PAGE,
+        }
+    }
+
+    /// Forget every page if the flags changed since they were checked
+    #[inline(always)]
+    fn validate(&mut self, epoch: u64) {
+        if self.epoch != epoch {
+            *self = Self { epoch, ..Self::new() };
+        }
+    }
+}
+
+impl Default for PageCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Integer types loaded and stored big-endian
+pub trait GuestValue: Copy {
+    /// Convert between guest (big-endian) and host byte order
+    fn swap_be(self) -> Self;
+}
+
+macro_rules! impl_guest_value {
+    ($($ty:ty),*) => {
+        $(impl GuestValue for $ty {
+            #[inline(always)]
+            fn swap_be(self) -> Self {
+                <$ty>::from_be(self)
+            }
+        })*
+    };
+}
+
+impl_guest_value!(u8, u16, u32, u64);
+
+/// Page holding `addr` if an access of `size` bytes stays on it
+#[inline(always)]
+fn single_page(addr: u32, size: u32) -> Option<u32> {
+    ((addr % PAGE_SIZE) + size <= PAGE_SIZE).then_some(addr / PAGE_SIZE)
+}
+
+/// Guest memory with a cached base pointer
+pub struct GuestMemory {
+    memory: Arc<MemoryManager>,
+    /// Host address of guest address 0
+    base: *mut u8,
+}
+
+// Safety: `base` points into the arena owned by `memory`, which is Send and Sync
+unsafe impl Send for GuestMemory {}
+unsafe impl Sync for GuestMemory {}
+
+impl GuestMemory {
+    /// Wrap a memory manager
+    pub fn new(memory: Arc<MemoryManager>) -> Self {
+        let base = unsafe { memory.ptr(0) };
+        Self { memory, base }
+    }
+
+    /// The wrapped memory manager
+    #[inline(always)]
+    pub fn manager(&self) -> &Arc<MemoryManager> {
+        &self.memory
+    }
+
+    /// Drop stale pages, returning whether cached pages may be used (nothing is watched)
+    #[inline(always)]
+    fn usable(&self, cache: &mut PageCache) -> bool {
+        cache.validate(self.memory.protection_epoch());
+        !self.memory.watchpoints().is_active()
+    }
+
+    /// Fetch an instruction word
+    #[inline(always)]
+    pub fn fetch(&self, cache: &mut PageCache, addr: u32) -> Result<u32, MemoryError> {
+        let page = single_page(addr, 4);
+        let usable = self.usable(cache);
+        if usable && page == Some(cache.fetch) {
+            return Ok(unsafe { self.load_unchecked::<u32>(addr) });
+        }
+        let value = self.memory.read_be32(addr)?;
+        if let (Some(page), true) = (page, usable) {
+            cache.fetch = page;
+        }
+        Ok(value)
+    }
+
+    /// Load a big-endian value
+    #[inline(always)]
+    pub fn read<T: GuestValue>(&self, cache: &mut PageCache, addr: u32) -> Result<T, MemoryError> {
+        let page = single_page(addr, std::mem::size_of::<T>() as u32);
+        let usable = self.usable(cache);
+        if usable && page == Some(cache.read) {
+            return Ok(unsafe { self.load_unchecked::<T>(addr) });
+        }
+        let value = self.memory.read::<T>(addr)?.swap_be();
+        if let (Some(page), true) = (page, usable) {
+            cache.read = page;
+        }
+        Ok(value)
+    }
+
+    /// Store a big-endian value
+    #[inline(always)]
+    pub fn write<T: GuestValue>(&self, cache: &mut PageCache, addr: u32, value: T) -> Result<(), MemoryError> {
+        let size = std::mem::size_of::<T>() as u32;
+        let page = single_page(addr, size);
+        let usable = self.usable(cache);
+        if usable && page == Some(cache.write) {
+            self.memory.mark_dirty(addr, size);
+            unsafe { std::ptr::write_unaligned(self.base.add(addr as usize) as *mut T, value.swap_be()) };
+            return Ok(());
+        }
+        self.memory.write::<T>(addr, value.swap_be())?;
+        if let (Some(page), true) = (page, usable) {
+            cache.write = page;
+        }
+        Ok(())
+    }
+
+    /// # Safety
+    /// The page holding `addr` must have passed a read check in the current epoch.
+    #[inline(always)]
+    unsafe fn load_unchecked<T: GuestValue>(&self, addr: u32) -> T {
+        std::ptr::read_unaligned(self.base.add(addr as usize) as *const T).swap_be()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oc_memory::PageFlags;
+
+    #[test]
+    fn test_page_cache_follows_protection() {
+        let memory = MemoryManager::new().unwrap();
+        let addr = memory.allocate(0x2000, 0x1000, PageFlags::RW).unwrap();
+        let guest = GuestMemory::new(memory.clone());
+        let mut cache = PageCache::new();
+
+        guest.write::<u32>(&mut cache, addr, 0x1234_5678).unwrap();
+        assert_eq!(cache.write, addr / PAGE_SIZE);
+        // Further accesses to the page skip the check but behave the same
+        guest.write::<u16>(&mut cache, addr + 4, 0xABCD).unwrap();
+        assert_eq!(memory.read_be16(addr + 4).unwrap(), 0xABCD);
+        assert_eq!(guest.read::<u32>(&mut cache, addr).unwrap(), 0x1234_5678);
+        assert_eq!(guest.read::<u32>(&mut cache, addr).unwrap(), 0x1234_5678);
+        assert!(memory.dirty_pages().contains(&(addr / PAGE_SIZE)));
+
+        // Protecting the page invalidates the cache, so the next access faults
+        memory.protect(addr, 0x1000, PageFlags::READ).unwrap();
+        assert!(matches!(
+            guest.write::<u32>(&mut cache, addr, 0),
+            Err(MemoryError::AccessViolation { .. })
+        ));
+        assert_eq!(guest.read::<u32>(&mut cache, addr).unwrap(), 0x1234_5678);
+
+        // Accesses straddling a page are always checked
+        assert!(guest.read::<u64>(&mut cache, addr + 0xFFC).is_ok());
+        assert_eq!(cache.read, addr / PAGE_SIZE);
+    }
+}
@@ -33,7 +33,7 @@ pub fn evaluate_branch_condition(thread: &mut PpuThread, bo: u8, bi: u8) -> bool
     let cond_ok = if (bo & 0b10000) != 0 {
         true // Don't test condition
     } else {
-        let cr_bit = (thread.regs.cr >> (31 - bi)) & 1;
+        let cr_bit = (thread.cr() >> (31 - bi)) & 1;
         // BO[3] = 1 means branch if CR[BI] == 1, BO[3] = 0 means branch if CR[BI] == 0
         ((bo >> 3) & 1) != 0 && cr_bit != 0 || ((bo >> 3) & 1) == 0 && cr_bit == 0
     };
@@ -94,7 +94,7 @@ pub fn branch_conditional_ctr(thread: &mut PpuThread, bo: u8, bi: u8, lk: bool)
     let cond_ok = if (bo & 0b10000) != 0 {
         true
     } else {
-        let cr_bit = (thread.regs.cr >> (31 - bi)) & 1;
+        let cr_bit = (thread.cr() >> (31 - bi)) & 1;
         ((bo >> 3) & 1) != 0 && cr_bit != 0 || ((bo >> 3) & 1) == 0 && cr_bit == 0
     };
     
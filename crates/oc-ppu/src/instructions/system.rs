@@ -10,6 +10,7 @@ pub mod spr {
     pub const XER: u16 = 1;
     pub const LR: u16 = 8;
     pub const CTR: u16 = 9;
+    pub const DEC: u16 = 22;      // Decrementer (writes are privileged)
     pub const VRSAVE: u16 = 256;
     pub const SPRG0: u16 = 272;
     pub const SPRG1: u16 = 273;
@@ -36,21 +37,10 @@ pub fn mfspr(thread: &PpuThread, spr_num: u16) -> u64 {
         spr::XER => thread.regs.xer,
         spr::LR => thread.regs.lr,
         spr::CTR => thread.regs.ctr,
+        spr::DEC => thread.decrementer() as u64,
         spr::PVR => CELL_PVR,
-        spr::TB => {
-            // Time base - use system time for now
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_nanos() as u64 / 40 // ~25MHz TB frequency
-        }
-        spr::TBU => {
-            let tb = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_nanos() as u64 / 40;
-            (tb >> 32) & 0xFFFF_FFFF
-        }
+        spr::TB => thread.time_base().ticks(),
+        spr::TBU => thread.time_base().ticks() >> 32,
         spr::VRSAVE => 0, // VMX register save mask
         spr::PIR => thread.id as u64,
         _ => {
@@ -66,6 +56,8 @@ pub fn mtspr(thread: &mut PpuThread, spr_num: u16, value: u64) {
         spr::XER => thread.regs.xer = value,
         spr::LR => thread.regs.lr = value,
         spr::CTR => thread.regs.ctr = value,
+        spr::DEC if thread.is_privileged() => thread.set_decrementer(value as u32),
+        spr::DEC => tracing::warn!("mtspr: Decrementer write in problem state ignored"),
         spr::VRSAVE => { /* Ignored for now */ }
         spr::PVR => { /* Read-only, ignore */ }
         spr::TB | spr::TBU => { /* Time base is read-only in user mode */ }
@@ -77,7 +69,7 @@ pub fn mtspr(thread: &mut PpuThread, spr_num: u16, value: u64) {
 
 /// Move from Condition Register
 pub fn mfcr(thread: &PpuThread) -> u64 {
-    thread.regs.cr as u64
+    thread.cr() as u64
 }
 
 /// Move to Condition Register Fields
@@ -146,66 +138,66 @@ pub fn mtfsb1(thread: &mut PpuThread, bt: u8) {
 
 /// Condition Register AND
 pub fn crand(thread: &mut PpuThread, bt: u8, ba: u8, bb: u8) {
-    let a = (thread.regs.cr >> (31 - ba)) & 1;
-    let b = (thread.regs.cr >> (31 - bb)) & 1;
+    let a = (thread.cr() >> (31 - ba)) & 1;
+    let b = (thread.cr() >> (31 - bb)) & 1;
     let result = a & b;
-    thread.regs.cr = (thread.regs.cr & !(1 << (31 - bt))) | (result << (31 - bt));
+    thread.set_cr((thread.cr() & !(1 << (31 - bt))) | (result << (31 - bt)));
 }
 
 /// Condition Register OR
 pub fn cror(thread: &mut PpuThread, bt: u8, ba: u8, bb: u8) {
-    let a = (thread.regs.cr >> (31 - ba)) & 1;
-    let b = (thread.regs.cr >> (31 - bb)) & 1;
+    let a = (thread.cr() >> (31 - ba)) & 1;
+    let b = (thread.cr() >> (31 - bb)) & 1;
     let result = a | b;
-    thread.regs.cr = (thread.regs.cr & !(1 << (31 - bt))) | (result << (31 - bt));
+    thread.set_cr((thread.cr() & !(1 << (31 - bt))) | (result << (31 - bt)));
 }
 
 /// Condition Register XOR
 pub fn crxor(thread: &mut PpuThread, bt: u8, ba: u8, bb: u8) {
-    let a = (thread.regs.cr >> (31 - ba)) & 1;
-    let b = (thread.regs.cr >> (31 - bb)) & 1;
+    let a = (thread.cr() >> (31 - ba)) & 1;
+    let b = (thread.cr() >> (31 - bb)) & 1;
     let result = a ^ b;
-    thread.regs.cr = (thread.regs.cr & !(1 << (31 - bt))) | (result << (31 - bt));
+    thread.set_cr((thread.cr() & !(1 << (31 - bt))) | (result << (31 - bt)));
 }
 
 /// Condition Register NAND
 pub fn crnand(thread: &mut PpuThread, bt: u8, ba: u8, bb: u8) {
-    let a = (thread.regs.cr >> (31 - ba)) & 1;
-    let b = (thread.regs.cr >> (31 - bb)) & 1;
+    let a = (thread.cr() >> (31 - ba)) & 1;
+    let b = (thread.cr() >> (31 - bb)) & 1;
     let result = !(a & b) & 1;
-    thread.regs.cr = (thread.regs.cr & !(1 << (31 - bt))) | (result << (31 - bt));
+    thread.set_cr((thread.cr() & !(1 << (31 - bt))) | (result << (31 - bt)));
 }
 
 /// Condition Register NOR
 pub fn crnor(thread: &mut PpuThread, bt: u8, ba: u8, bb: u8) {
-    let a = (thread.regs.cr >> (31 - ba)) & 1;
-    let b = (thread.regs.cr >> (31 - bb)) & 1;
+    let a = (thread.cr() >> (31 - ba)) & 1;
+    let b = (thread.cr() >> (31 - bb)) & 1;
     let result = !(a | b) & 1;
-    thread.regs.cr = (thread.regs.cr & !(1 << (31 - bt))) | (result << (31 - bt));
+    thread.set_cr((thread.cr() & !(1 << (31 - bt))) | (result << (31 - bt)));
 }
 
 /// Condition Register EQV (XNOR)
 pub fn creqv(thread: &mut PpuThread, bt: u8, ba: u8, bb: u8) {
-    let a = (thread.regs.cr >> (31 - ba)) & 1;
-    let b = (thread.regs.cr >> (31 - bb)) & 1;
+    let a = (thread.cr() >> (31 - ba)) & 1;
+    let b = (thread.cr() >> (31 - bb)) & 1;
     let result = !(a ^ b) & 1;
-    thread.regs.cr = (thread.regs.cr & !(1 << (31 - bt))) | (result << (31 - bt));
+    thread.set_cr((thread.cr() & !(1 << (31 - bt))) | (result << (31 - bt)));
 }
 
 /// Condition Register AND with Complement
 pub fn crandc(thread: &mut PpuThread, bt: u8, ba: u8, bb: u8) {
-    let a = (thread.regs.cr >> (31 - ba)) & 1;
-    let b = (thread.regs.cr >> (31 - bb)) & 1;
+    let a = (thread.cr() >> (31 - ba)) & 1;
+    let b = (thread.cr() >> (31 - bb)) & 1;
     let result = a & (!b & 1);
-    thread.regs.cr = (thread.regs.cr & !(1 << (31 - bt))) | (result << (31 - bt));
+    thread.set_cr((thread.cr() & !(1 << (31 - bt))) | (result << (31 - bt)));
 }
 
 /// Condition Register OR with Complement
 pub fn crorc(thread: &mut PpuThread, bt: u8, ba: u8, bb: u8) {
-    let a = (thread.regs.cr >> (31 - ba)) & 1;
-    let b = (thread.regs.cr >> (31 - bb)) & 1;
+    let a = (thread.cr() >> (31 - ba)) & 1;
+    let b = (thread.cr() >> (31 - bb)) & 1;
     let result = a | (!b & 1);
-    thread.regs.cr = (thread.regs.cr & !(1 << (31 - bt))) | (result << (31 - bt));
+    thread.set_cr((thread.cr() & !(1 << (31 - bt))) | (result << (31 - bt)));
 }
 
 /// Move Condition Register Field
@@ -311,6 +303,23 @@ mod tests {
         assert_eq!(mfspr(&thread, spr::CTR), 0xDEADBEEF);
     }
 
+    #[test]
+    fn test_mfspr_time_base() {
+        use oc_core::time_base::{TimeBase, TIMEBASE_FREQUENCY};
+
+        let mut thread = create_test_thread();
+        let time_base = TimeBase::new();
+        time_base.pause();
+        time_base.advance(std::time::Duration::from_secs(60));
+        thread.set_time_base(time_base.clone());
+
+        let tb = mfspr(&thread, spr::TB);
+        assert_eq!(mfspr(&thread, spr::TBU), tb >> 32);
+        // The register follows the shared clock, not the host
+        time_base.advance(std::time::Duration::from_secs(1));
+        assert_eq!(mfspr(&thread, spr::TB) - tb, TIMEBASE_FREQUENCY);
+    }
+
     #[test]
     fn test_mfcr_mtcrf() {
         let mut thread = create_test_thread();
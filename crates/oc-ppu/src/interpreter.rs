@@ -4,30 +4,46 @@
 //! instructions to the appropriate handlers in the instruction modules.
 
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::collections::HashSet;
 use parking_lot::RwLock;
 use oc_memory::MemoryManager;
-use oc_core::error::PpuError;
+use oc_core::condition::{Condition, ConditionContext};
+use oc_core::error::{AccessKind, MemoryError, PpuError, PpuExceptionType, ProgramExceptionReason};
 use crate::decoder::{PpuDecoder, InstructionForm};
+use crate::guest_memory::GuestMemory;
 use crate::thread::PpuThread;
 use crate::instructions::{float, system, vector};
 
 /// Breakpoint type
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BreakpointType {
     /// Unconditional breakpoint - always breaks
     Unconditional,
-    /// Conditional breakpoint - breaks when condition is met
-    Conditional(BreakpointCondition),
+    /// Conditional breakpoint - breaks when the expression is non-zero
+    ///
+    /// Besides the thread's registers, the expression can use `icount`,
+    /// the number of instructions executed so far.
+    Conditional(Condition),
 }
 
-/// Breakpoint condition
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum BreakpointCondition {
-    /// Break when GPR equals value
-    GprEquals { reg: usize, value: u64 },
-    /// Break when instruction count reaches value
-    InstructionCount { count: u64 },
+/// Registers and memory of a thread stopping at a breakpoint, plus `icount`
+struct BreakContext<'a> {
+    thread: &'a PpuThread,
+    instruction_count: u64,
+}
+
+impl ConditionContext for BreakContext<'_> {
+    fn variable(&self, name: &str) -> Option<u64> {
+        match name {
+            "icount" => Some(self.instruction_count),
+            _ => self.thread.variable(name),
+        }
+    }
+
+    fn read_memory(&self, addr: u64, size: usize) -> Option<u64> {
+        self.thread.read_memory(addr, size)
+    }
 }
 
 /// Breakpoint information
@@ -39,36 +55,68 @@ pub struct Breakpoint {
     pub bp_type: BreakpointType,
     /// Whether the breakpoint is enabled
     pub enabled: bool,
-    /// Hit count
+    /// Times the breakpoint was reached while enabled, whether or not its
+    /// condition held
     pub hit_count: u64,
 }
 
+/// Convert a failed data access into a PPU error
+///
+/// Protection faults keep the faulting address and access kind so the
+/// report points at the stray access rather than a generic bad instruction.
+fn memory_fault(err: MemoryError, pc: u32, opcode: u32) -> PpuError {
+    match err {
+        MemoryError::AccessViolation { addr, kind } => PpuError::AccessViolation { pc, addr, kind },
+        _ => PpuError::InvalidInstruction { addr: pc, opcode },
+    }
+}
+
+/// Program exception for a privileged instruction executed in problem state
+fn privileged_instruction(thread: &PpuThread) -> PpuError {
+    PpuError::Exception {
+        addr: thread.pc() as u32,
+        exception: PpuExceptionType::Program {
+            reason: ProgramExceptionReason::PrivilegedInstruction,
+        },
+    }
+}
+
+/// System call instruction (`sc`)
+const SC_OPCODE: u32 = 0x4400_0002;
+
 /// PPU interpreter for instruction execution
 pub struct PpuInterpreter {
     /// Memory manager
     memory: Arc<MemoryManager>,
+    /// Guest memory with the inline fast path for loads, stores and fetches
+    guest: GuestMemory,
     /// Breakpoints (address -> breakpoint)
     breakpoints: RwLock<HashSet<u64>>,
+    /// Whether any breakpoint is set, so the hot loop can skip the lookup
+    has_breakpoints: AtomicBool,
     /// Breakpoint details
     breakpoint_details: RwLock<std::collections::HashMap<u64, Breakpoint>>,
     /// Total instruction count (for conditional breakpoints)
-    instruction_count: parking_lot::Mutex<u64>,
+    instruction_count: AtomicU64,
 }
 
 impl PpuInterpreter {
     /// Create a new PPU interpreter
     pub fn new(memory: Arc<MemoryManager>) -> Self {
         Self {
+            guest: GuestMemory::new(memory.clone()),
             memory,
             breakpoints: RwLock::new(HashSet::new()),
+            has_breakpoints: AtomicBool::new(false),
             breakpoint_details: RwLock::new(std::collections::HashMap::new()),
-            instruction_count: parking_lot::Mutex::new(0),
+            instruction_count: AtomicU64::new(0),
         }
     }
 
     /// Add a breakpoint at the specified address
     pub fn add_breakpoint(&self, addr: u64, bp_type: BreakpointType) {
         self.breakpoints.write().insert(addr);
+        self.has_breakpoints.store(true, Ordering::Release);
         self.breakpoint_details.write().insert(
             addr,
             Breakpoint {
@@ -82,7 +130,9 @@ impl PpuInterpreter {
 
     /// Remove a breakpoint at the specified address
     pub fn remove_breakpoint(&self, addr: u64) {
-        self.breakpoints.write().remove(&addr);
+        let mut breakpoints = self.breakpoints.write();
+        breakpoints.remove(&addr);
+        self.has_breakpoints.store(!breakpoints.is_empty(), Ordering::Release);
         self.breakpoint_details.write().remove(&addr);
     }
 
@@ -103,6 +153,7 @@ impl PpuInterpreter {
     /// Clear all breakpoints
     pub fn clear_breakpoints(&self) {
         self.breakpoints.write().clear();
+        self.has_breakpoints.store(false, Ordering::Release);
         self.breakpoint_details.write().clear();
     }
 
@@ -115,36 +166,37 @@ impl PpuInterpreter {
             .collect()
     }
 
-    /// Check if we should break at this address
+    /// Check if we should break at this address, counting the hit
     #[inline]
     fn should_break(&self, thread: &PpuThread) -> bool {
+        // Fast path: no breakpoints at all, or none at this address
+        if !self.has_breakpoints.load(Ordering::Acquire) {
+            return false;
+        }
         let pc = thread.pc();
-        
-        // Fast path: check if there's a breakpoint at this address
         if !self.breakpoints.read().contains(&pc) {
             return false;
         }
 
         // Check breakpoint condition
-        let details = self.breakpoint_details.read();
-        if let Some(bp) = details.get(&pc) {
-            if !bp.enabled {
-                return false;
-            }
-
-            match bp.bp_type {
-                BreakpointType::Unconditional => true,
-                BreakpointType::Conditional(condition) => match condition {
-                    BreakpointCondition::GprEquals { reg, value } => {
-                        thread.gpr(reg) == value
-                    }
-                    BreakpointCondition::InstructionCount { count } => {
-                        *self.instruction_count.lock() >= count
-                    }
-                },
+        let mut details = self.breakpoint_details.write();
+        let Some(bp) = details.get_mut(&pc) else {
+            return false;
+        };
+        if !bp.enabled {
+            return false;
+        }
+        bp.hit_count += 1;
+
+        match &bp.bp_type {
+            BreakpointType::Unconditional => true,
+            BreakpointType::Conditional(condition) => {
+                let ctx = BreakContext {
+                    thread,
+                    instruction_count: self.instruction_count.load(Ordering::Relaxed),
+                };
+                condition.is_met(&ctx, bp.hit_count)
             }
-        } else {
-            false
         }
     }
 
@@ -152,41 +204,106 @@ impl PpuInterpreter {
     pub fn step(&self, thread: &mut PpuThread) -> Result<(), PpuError> {
         // Check for breakpoints before execution
         if self.should_break(thread) {
-            // Update hit count
-            let pc = thread.pc();
-            if let Some(bp) = self.breakpoint_details.write().get_mut(&pc) {
-                bp.hit_count += 1;
-            }
-            return Err(PpuError::Breakpoint { addr: pc });
+            return Err(PpuError::Breakpoint { addr: thread.pc() });
         }
 
         // Increment instruction count for conditional breakpoints
-        *self.instruction_count.lock() += 1;
+        self.instruction_count.fetch_add(1, Ordering::Relaxed);
+
+        let result = self.fetch(thread).and_then(|opcode| self.execute_fetched(thread, opcode));
+        thread.sync_cr();
+        result
+    }
+
+    /// Execute up to `max` instructions back to back
+    ///
+    /// Stops early at a breakpoint, a watchpoint hit, an error, or before a
+    /// system call instruction, which is left for the caller to dispatch.
+    /// Returns the number of instructions executed with the reason it stopped
+    /// early; the thread's registers are up to date either way.
+    pub fn run(&self, thread: &mut PpuThread, max: u64) -> (u64, Result<(), PpuError>) {
+        let mut executed = 0;
+        let mut result = Ok(());
+        while executed < max {
+            if self.should_break(thread) {
+                result = Err(PpuError::Breakpoint { addr: thread.pc() });
+                break;
+            }
+            let opcode = match self.fetch(thread) {
+                Ok(SC_OPCODE) => break,
+                Ok(opcode) => opcode,
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            };
+            executed += 1;
+            if let Err(e) = self.execute_fetched(thread, opcode) {
+                result = Err(e);
+                break;
+            }
+        }
+        // Counted once per batch
+        self.instruction_count.fetch_add(executed, Ordering::Relaxed);
+        thread.sync_cr();
+        (executed, result)
+    }
+
+    /// Fetch the instruction at the thread's PC
+    #[inline(always)]
+    fn fetch(&self, thread: &mut PpuThread) -> Result<u32, PpuError> {
+        let pc = thread.pc() as u32;
+        self.guest.fetch(&mut thread.page_cache, pc).map_err(|e| match e {
+            MemoryError::AccessViolation { .. } => PpuError::AccessViolation {
+                pc,
+                addr: pc,
+                kind: AccessKind::Execute,
+            },
+            _ => PpuError::InvalidInstruction { addr: pc, opcode: 0 },
+        })
+    }
 
-        // Fetch instruction
+    /// Decode and execute the instruction fetched from the thread's PC
+    ///
+    /// CR0 may be left pending; callers sync it before handing the thread back.
+    #[inline(always)]
+    fn execute_fetched(&self, thread: &mut PpuThread, opcode: u32) -> Result<(), PpuError> {
         let pc = thread.pc() as u32;
-        let opcode = self.memory.read_be32(pc).map_err(|_| PpuError::InvalidInstruction {
-            addr: pc,
-            opcode: 0,
-        })?;
 
         // Decode instruction
         let decoded = PpuDecoder::decode(opcode);
 
+        // Drop hits latched outside this instruction (including the fetch above)
+        let watching = self.memory.watchpoints().is_active();
+        if watching {
+            self.memory.take_watchpoint_hit();
+        }
+
         // Execute instruction
         self.execute(thread, opcode, decoded)?;
 
+        // Data watchpoints break after the triggering instruction completes
+        if watching {
+            if let Some(hit) = self.memory.take_watchpoint_hit() {
+                return Err(PpuError::Watchpoint {
+                    addr: hit.access_addr,
+                    kind: hit.kind,
+                    pc,
+                });
+            }
+        }
+
         Ok(())
     }
 
     /// Get the current instruction count
     pub fn instruction_count(&self) -> u64 {
-        *self.instruction_count.lock()
+        self.instruction_count.load(Ordering::Relaxed)
     }
 
     /// Reset the instruction count
     pub fn reset_instruction_count(&self) {
-        *self.instruction_count.lock() = 0;
+        self.instruction_count.store(0, Ordering::Relaxed);
     }
 
     /// Execute a decoded instruction
@@ -260,38 +377,26 @@ impl PpuInterpreter {
             // lwz - Load Word and Zero
             32 => {
                 let ea = if ra == 0 { d as u64 } else { thread.gpr(ra as usize).wrapping_add(d as u64) };
-                let value = self.memory.read_be32(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let value = self.guest.read::<u32>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(rt as usize, value as u64);
             }
             // stw - Store Word
             36 => {
                 let ea = if ra == 0 { d as u64 } else { thread.gpr(ra as usize).wrapping_add(d as u64) };
                 let value = thread.gpr(rt as usize) as u32;
-                self.memory.write_be32(ea as u32, value).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                self.guest.write::<u32>(&mut thread.page_cache, ea as u32, value).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
             }
             // lbz - Load Byte and Zero
             34 => {
                 let ea = if ra == 0 { d as u64 } else { thread.gpr(ra as usize).wrapping_add(d as u64) };
-                let value: u8 = self.memory.read(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let value: u8 = self.guest.read(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(rt as usize, value as u64);
             }
             // stb - Store Byte
             38 => {
                 let ea = if ra == 0 { d as u64 } else { thread.gpr(ra as usize).wrapping_add(d as u64) };
                 let value = thread.gpr(rt as usize) as u8;
-                self.memory.write(ea as u32, value).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                self.guest.write(&mut thread.page_cache, ea as u32, value).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
             }
             // ori - OR Immediate
             24 => {
@@ -332,20 +437,14 @@ impl PpuInterpreter {
             // lwzu - Load Word and Zero with Update
             33 => {
                 let ea = thread.gpr(ra as usize).wrapping_add(d as u64);
-                let value = self.memory.read_be32(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let value = self.guest.read::<u32>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(rt as usize, value as u64);
                 thread.set_gpr(ra as usize, ea);
             }
             // lbzu - Load Byte and Zero with Update
             35 => {
                 let ea = thread.gpr(ra as usize).wrapping_add(d as u64);
-                let value: u8 = self.memory.read(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let value: u8 = self.guest.read(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(rt as usize, value as u64);
                 thread.set_gpr(ra as usize, ea);
             }
@@ -353,57 +452,39 @@ impl PpuInterpreter {
             37 => {
                 let ea = thread.gpr(ra as usize).wrapping_add(d as u64);
                 let value = thread.gpr(rt as usize) as u32;
-                self.memory.write_be32(ea as u32, value).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                self.guest.write::<u32>(&mut thread.page_cache, ea as u32, value).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(ra as usize, ea);
             }
             // stbu - Store Byte with Update
             39 => {
                 let ea = thread.gpr(ra as usize).wrapping_add(d as u64);
                 let value = thread.gpr(rt as usize) as u8;
-                self.memory.write(ea as u32, value).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                self.guest.write(&mut thread.page_cache, ea as u32, value).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(ra as usize, ea);
             }
             // lhz - Load Halfword and Zero
             40 => {
                 let ea = if ra == 0 { d as u64 } else { thread.gpr(ra as usize).wrapping_add(d as u64) };
-                let value = self.memory.read_be16(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let value = self.guest.read::<u16>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(rt as usize, value as u64);
             }
             // lhzu - Load Halfword and Zero with Update
             41 => {
                 let ea = thread.gpr(ra as usize).wrapping_add(d as u64);
-                let value = self.memory.read_be16(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let value = self.guest.read::<u16>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(rt as usize, value as u64);
                 thread.set_gpr(ra as usize, ea);
             }
             // lha - Load Halfword Algebraic
             42 => {
                 let ea = if ra == 0 { d as u64 } else { thread.gpr(ra as usize).wrapping_add(d as u64) };
-                let value = self.memory.read_be16(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let value = self.guest.read::<u16>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(rt as usize, (value as i16) as i64 as u64);
             }
             // lhau - Load Halfword Algebraic with Update
             43 => {
                 let ea = thread.gpr(ra as usize).wrapping_add(d as u64);
-                let value = self.memory.read_be16(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let value = self.guest.read::<u16>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(rt as usize, (value as i16) as i64 as u64);
                 thread.set_gpr(ra as usize, ea);
             }
@@ -411,29 +492,20 @@ impl PpuInterpreter {
             44 => {
                 let ea = if ra == 0 { d as u64 } else { thread.gpr(ra as usize).wrapping_add(d as u64) };
                 let value = thread.gpr(rt as usize) as u16;
-                self.memory.write_be16(ea as u32, value).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                self.guest.write::<u16>(&mut thread.page_cache, ea as u32, value).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
             }
             // sthu - Store Halfword with Update
             45 => {
                 let ea = thread.gpr(ra as usize).wrapping_add(d as u64);
                 let value = thread.gpr(rt as usize) as u16;
-                self.memory.write_be16(ea as u32, value).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                self.guest.write::<u16>(&mut thread.page_cache, ea as u32, value).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(ra as usize, ea);
             }
             // lmw - Load Multiple Word
             46 => {
                 let mut ea = if ra == 0 { d as u64 } else { thread.gpr(ra as usize).wrapping_add(d as u64) };
                 for r in rt..32 {
-                    let value = self.memory.read_be32(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                        addr: thread.pc() as u32,
-                        opcode,
-                    })?;
+                    let value = self.guest.read::<u32>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                     thread.set_gpr(r as usize, value as u64);
                     ea = ea.wrapping_add(4);
                 }
@@ -443,48 +515,33 @@ impl PpuInterpreter {
                 let mut ea = if ra == 0 { d as u64 } else { thread.gpr(ra as usize).wrapping_add(d as u64) };
                 for r in rt..32 {
                     let value = thread.gpr(r as usize) as u32;
-                    self.memory.write_be32(ea as u32, value).map_err(|_| PpuError::InvalidInstruction {
-                        addr: thread.pc() as u32,
-                        opcode,
-                    })?;
+                    self.guest.write::<u32>(&mut thread.page_cache, ea as u32, value).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                     ea = ea.wrapping_add(4);
                 }
             }
             // lfs - Load Floating-Point Single
             48 => {
                 let ea = if ra == 0 { d as u64 } else { thread.gpr(ra as usize).wrapping_add(d as u64) };
-                let bits = self.memory.read_be32(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let bits = self.guest.read::<u32>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_fpr(rt as usize, f32::from_bits(bits) as f64);
             }
             // lfsu - Load Floating-Point Single with Update
             49 => {
                 let ea = thread.gpr(ra as usize).wrapping_add(d as u64);
-                let bits = self.memory.read_be32(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let bits = self.guest.read::<u32>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_fpr(rt as usize, f32::from_bits(bits) as f64);
                 thread.set_gpr(ra as usize, ea);
             }
             // lfd - Load Floating-Point Double
             50 => {
                 let ea = if ra == 0 { d as u64 } else { thread.gpr(ra as usize).wrapping_add(d as u64) };
-                let bits = self.memory.read_be64(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let bits = self.guest.read::<u64>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_fpr(rt as usize, f64::from_bits(bits));
             }
             // lfdu - Load Floating-Point Double with Update
             51 => {
                 let ea = thread.gpr(ra as usize).wrapping_add(d as u64);
-                let bits = self.memory.read_be64(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let bits = self.guest.read::<u64>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_fpr(rt as usize, f64::from_bits(bits));
                 thread.set_gpr(ra as usize, ea);
             }
@@ -492,48 +549,33 @@ impl PpuInterpreter {
             52 => {
                 let ea = if ra == 0 { d as u64 } else { thread.gpr(ra as usize).wrapping_add(d as u64) };
                 let bits = (thread.fpr(rt as usize) as f32).to_bits();
-                self.memory.write_be32(ea as u32, bits).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                self.guest.write::<u32>(&mut thread.page_cache, ea as u32, bits).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
             }
             // stfsu - Store Floating-Point Single with Update
             53 => {
                 let ea = thread.gpr(ra as usize).wrapping_add(d as u64);
                 let bits = (thread.fpr(rt as usize) as f32).to_bits();
-                self.memory.write_be32(ea as u32, bits).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                self.guest.write::<u32>(&mut thread.page_cache, ea as u32, bits).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(ra as usize, ea);
             }
             // stfd - Store Floating-Point Double
             54 => {
                 let ea = if ra == 0 { d as u64 } else { thread.gpr(ra as usize).wrapping_add(d as u64) };
                 let bits = thread.fpr(rt as usize).to_bits();
-                self.memory.write_be64(ea as u32, bits).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                self.guest.write::<u64>(&mut thread.page_cache, ea as u32, bits).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
             }
             // stfdu - Store Floating-Point Double with Update
             55 => {
                 let ea = thread.gpr(ra as usize).wrapping_add(d as u64);
                 let bits = thread.fpr(rt as usize).to_bits();
-                self.memory.write_be64(ea as u32, bits).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                self.guest.write::<u64>(&mut thread.page_cache, ea as u32, bits).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(ra as usize, ea);
             }
             // ld - Load Doubleword (DS-form, but handled here with d & ~3)
             58 => {
                 let ds = (d as i16) & !3;
                 let ea = if ra == 0 { ds as u64 } else { thread.gpr(ra as usize).wrapping_add(ds as i64 as u64) };
-                let value = self.memory.read_be64(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let value = self.guest.read::<u64>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(rt as usize, value);
             }
             // std - Store Doubleword (DS-form, but handled here with d & ~3)
@@ -541,10 +583,7 @@ impl PpuInterpreter {
                 let ds = (d as i16) & !3;
                 let ea = if ra == 0 { ds as u64 } else { thread.gpr(ra as usize).wrapping_add(ds as i64 as u64) };
                 let value = thread.gpr(rt as usize);
-                self.memory.write_be64(ea as u32, value).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                self.guest.write::<u64>(&mut thread.page_cache, ea as u32, value).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
             }
             // xori - XOR Immediate
             26 => {
@@ -640,7 +679,7 @@ impl PpuInterpreter {
         let cond_ok = if (bo & 0x10) != 0 {
             true
         } else {
-            let cr_bit = (thread.regs.cr >> (31 - bi)) & 1;
+            let cr_bit = (thread.cr() >> (31 - bi)) & 1;
             (cr_bit as u8) == ((bo >> 3) & 1)
         };
 
@@ -716,20 +755,14 @@ impl PpuInterpreter {
             // lwzx - Load Word and Zero Indexed
             23 => {
                 let ea = if ra == 0 { thread.gpr(rb as usize) } else { thread.gpr(ra as usize).wrapping_add(thread.gpr(rb as usize)) };
-                let value = self.memory.read_be32(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let value = self.guest.read::<u32>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(rt as usize, value as u64);
             }
             // stwx - Store Word Indexed
             151 => {
                 let ea = if ra == 0 { thread.gpr(rb as usize) } else { thread.gpr(ra as usize).wrapping_add(thread.gpr(rb as usize)) };
                 let value = thread.gpr(rt as usize) as u32;
-                self.memory.write_be32(ea as u32, value).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                self.guest.write::<u32>(&mut thread.page_cache, ea as u32, value).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
             }
             // mfspr - Move From Special Purpose Register
             339 => {
@@ -738,6 +771,7 @@ impl PpuInterpreter {
                     1 => thread.regs.xer,     // XER
                     8 => thread.regs.lr,      // LR
                     9 => thread.regs.ctr,     // CTR
+                    22 => thread.decrementer() as u64, // DEC
                     _ => {
                         tracing::warn!("Unimplemented mfspr SPR {} at 0x{:08x}", spr, thread.pc());
                         0
@@ -753,6 +787,9 @@ impl PpuInterpreter {
                     1 => thread.regs.xer = value,    // XER
                     8 => thread.regs.lr = value,     // LR
                     9 => thread.regs.ctr = value,    // CTR
+                    // DEC, privileged like on the console
+                    22 if thread.is_privileged() => thread.set_decrementer(value as u32),
+                    22 => return Err(privileged_instruction(thread)),
                     _ => {
                         tracing::warn!("Unimplemented mtspr SPR {} at 0x{:08x}", spr, thread.pc());
                     }
@@ -901,84 +938,57 @@ impl PpuInterpreter {
             // lbzx - Load Byte and Zero Indexed
             87 => {
                 let ea = if ra == 0 { thread.gpr(rb as usize) } else { thread.gpr(ra as usize).wrapping_add(thread.gpr(rb as usize)) };
-                let value: u8 = self.memory.read(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let value: u8 = self.guest.read(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(rt as usize, value as u64);
             }
             // lhzx - Load Halfword and Zero Indexed
             279 => {
                 let ea = if ra == 0 { thread.gpr(rb as usize) } else { thread.gpr(ra as usize).wrapping_add(thread.gpr(rb as usize)) };
-                let value = self.memory.read_be16(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let value = self.guest.read::<u16>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(rt as usize, value as u64);
             }
             // lhax - Load Halfword Algebraic Indexed
             343 => {
                 let ea = if ra == 0 { thread.gpr(rb as usize) } else { thread.gpr(ra as usize).wrapping_add(thread.gpr(rb as usize)) };
-                let value = self.memory.read_be16(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let value = self.guest.read::<u16>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(rt as usize, (value as i16) as i64 as u64);
             }
             // lwax - Load Word Algebraic Indexed
             341 => {
                 let ea = if ra == 0 { thread.gpr(rb as usize) } else { thread.gpr(ra as usize).wrapping_add(thread.gpr(rb as usize)) };
-                let value = self.memory.read_be32(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let value = self.guest.read::<u32>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(rt as usize, (value as i32) as i64 as u64);
             }
             // ldx - Load Doubleword Indexed
             21 => {
                 let ea = if ra == 0 { thread.gpr(rb as usize) } else { thread.gpr(ra as usize).wrapping_add(thread.gpr(rb as usize)) };
-                let value = self.memory.read_be64(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let value = self.guest.read::<u64>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(rt as usize, value);
             }
             // stbx - Store Byte Indexed
             215 => {
                 let ea = if ra == 0 { thread.gpr(rb as usize) } else { thread.gpr(ra as usize).wrapping_add(thread.gpr(rb as usize)) };
                 let value = thread.gpr(rt as usize) as u8;
-                self.memory.write(ea as u32, value).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                self.guest.write(&mut thread.page_cache, ea as u32, value).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
             }
             // sthx - Store Halfword Indexed
             407 => {
                 let ea = if ra == 0 { thread.gpr(rb as usize) } else { thread.gpr(ra as usize).wrapping_add(thread.gpr(rb as usize)) };
                 let value = thread.gpr(rt as usize) as u16;
-                self.memory.write_be16(ea as u32, value).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                self.guest.write::<u16>(&mut thread.page_cache, ea as u32, value).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
             }
             // stdx - Store Doubleword Indexed
             149 => {
                 let ea = if ra == 0 { thread.gpr(rb as usize) } else { thread.gpr(ra as usize).wrapping_add(thread.gpr(rb as usize)) };
                 let value = thread.gpr(rt as usize);
-                self.memory.write_be64(ea as u32, value).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                self.guest.write::<u64>(&mut thread.page_cache, ea as u32, value).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
             }
             // lwarx - Load Word and Reserve Indexed
             20 => {
                 let ea = if ra == 0 { thread.gpr(rb as usize) } else { thread.gpr(ra as usize).wrapping_add(thread.gpr(rb as usize)) };
                 let reservation = self.memory.reservation(ea as u32);
                 let _time = reservation.acquire();
-                let value = self.memory.read_be32(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let value = self.guest.read::<u32>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(rt as usize, value as u64);
             }
             // ldarx - Load Doubleword and Reserve Indexed
@@ -986,10 +996,7 @@ impl PpuInterpreter {
                 let ea = if ra == 0 { thread.gpr(rb as usize) } else { thread.gpr(ra as usize).wrapping_add(thread.gpr(rb as usize)) };
                 let reservation = self.memory.reservation(ea as u32);
                 let _time = reservation.acquire();
-                let value = self.memory.read_be64(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let value = self.guest.read::<u64>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_gpr(rt as usize, value);
             }
             // stwcx. - Store Word Conditional Indexed
@@ -999,10 +1006,7 @@ impl PpuInterpreter {
                 let reservation = self.memory.reservation(ea as u32);
                 let time = reservation.acquire();
                 let success = if reservation.try_lock(time) {
-                    self.memory.write_be32(ea as u32, value).map_err(|_| PpuError::InvalidInstruction {
-                        addr: thread.pc() as u32,
-                        opcode,
-                    })?;
+                    self.guest.write::<u32>(&mut thread.page_cache, ea as u32, value).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                     reservation.unlock_and_increment();
                     true
                 } else {
@@ -1018,10 +1022,7 @@ impl PpuInterpreter {
                 let reservation = self.memory.reservation(ea as u32);
                 let time = reservation.acquire();
                 let success = if reservation.try_lock(time) {
-                    self.memory.write_be64(ea as u32, value).map_err(|_| PpuError::InvalidInstruction {
-                        addr: thread.pc() as u32,
-                        opcode,
-                    })?;
+                    self.guest.write::<u64>(&mut thread.page_cache, ea as u32, value).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                     reservation.unlock_and_increment();
                     true
                 } else {
@@ -1033,38 +1034,26 @@ impl PpuInterpreter {
             // lfdx - Load Floating-Point Double Indexed
             599 => {
                 let ea = if ra == 0 { thread.gpr(rb as usize) } else { thread.gpr(ra as usize).wrapping_add(thread.gpr(rb as usize)) };
-                let bits = self.memory.read_be64(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let bits = self.guest.read::<u64>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_fpr(rt as usize, f64::from_bits(bits));
             }
             // lfsx - Load Floating-Point Single Indexed
             535 => {
                 let ea = if ra == 0 { thread.gpr(rb as usize) } else { thread.gpr(ra as usize).wrapping_add(thread.gpr(rb as usize)) };
-                let bits = self.memory.read_be32(ea as u32).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                let bits = self.guest.read::<u32>(&mut thread.page_cache, ea as u32).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
                 thread.set_fpr(rt as usize, f32::from_bits(bits) as f64);
             }
             // stfdx - Store Floating-Point Double Indexed
             727 => {
                 let ea = if ra == 0 { thread.gpr(rb as usize) } else { thread.gpr(ra as usize).wrapping_add(thread.gpr(rb as usize)) };
                 let bits = thread.fpr(rt as usize).to_bits();
-                self.memory.write_be64(ea as u32, bits).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                self.guest.write::<u64>(&mut thread.page_cache, ea as u32, bits).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
             }
             // stfsx - Store Floating-Point Single Indexed
             663 => {
                 let ea = if ra == 0 { thread.gpr(rb as usize) } else { thread.gpr(ra as usize).wrapping_add(thread.gpr(rb as usize)) };
                 let bits = (thread.fpr(rt as usize) as f32).to_bits();
-                self.memory.write_be32(ea as u32, bits).map_err(|_| PpuError::InvalidInstruction {
-                    addr: thread.pc() as u32,
-                    opcode,
-                })?;
+                self.guest.write::<u32>(&mut thread.page_cache, ea as u32, bits).map_err(|e| memory_fault(e, thread.pc() as u32, opcode))?;
             }
             // fmr - Floating Move Register
             72 => {
@@ -1197,7 +1186,7 @@ impl PpuInterpreter {
             }
             // mfcr - Move From Condition Register
             19 => {
-                thread.set_gpr(rt as usize, thread.regs.cr as u64);
+                thread.set_gpr(rt as usize, thread.cr() as u64);
             }
             // mfocrf - Move From One Condition Register Field
             // Same xo as mfcr but with FXM field set
@@ -1216,8 +1205,10 @@ impl PpuInterpreter {
             // Same implementation as mtcrf
             // mfmsr - Move From Machine State Register (privileged)
             83 => {
-                // For emulation, return a fixed MSR value
-                thread.set_gpr(rt as usize, 0x8000_0000_0000_0000); // 64-bit mode
+                if !thread.is_privileged() {
+                    return Err(privileged_instruction(thread));
+                }
+                thread.set_gpr(rt as usize, thread.get_msr());
             }
             // XO-form arithmetic instructions (dispatched as X-form by decoder)
             // Note: These have a 10-bit XO in the decoder, but only 9-bit in the instruction
@@ -1605,7 +1596,7 @@ impl PpuInterpreter {
                 let cond_ok = if (bo & 0x10) != 0 {
                     true
                 } else {
-                    let cr_bit = (thread.regs.cr >> (31 - bi)) & 1;
+                    let cr_bit = (thread.cr() >> (31 - bi)) & 1;
                     (cr_bit as u8) == ((bo >> 3) & 1)
                 };
 
@@ -1624,7 +1615,7 @@ impl PpuInterpreter {
                 let cond_ok = if (bo & 0x10) != 0 {
                     true
                 } else {
-                    let cr_bit = (thread.regs.cr >> (31 - bi)) & 1;
+                    let cr_bit = (thread.cr() >> (31 - bi)) & 1;
                     (cr_bit as u8) == ((bo >> 3) & 1)
                 };
 
@@ -1690,6 +1681,13 @@ impl PpuInterpreter {
                 system::isync(thread);
                 thread.advance_pc();
             }
+            // rfid - Return from Interrupt Doubleword (privileged)
+            18 => {
+                if !thread.is_privileged() {
+                    return Err(privileged_instruction(thread));
+                }
+                thread.return_from_exception();
+            }
             _ => {
                 tracing::warn!(
                     "Unimplemented XL-form xo {} at 0x{:08x} (opcode: 0x{:08x}, bo={}, bi={})",
@@ -1770,10 +1768,7 @@ impl PpuInterpreter {
     /// Update CR0 based on result (for Rc=1 instructions)
     #[inline]
     fn update_cr0(&self, thread: &mut PpuThread, value: u64) {
-        let value = value as i64;
-        let c = if value < 0 { 0b1000 } else if value > 0 { 0b0100 } else { 0b0010 };
-        let c = c | if thread.get_xer_so() { 1 } else { 0 };
-        thread.set_cr_field(0, c);
+        thread.record_cr0(value);
     }
 
     /// Generate 32-bit mask for rotate instructions
@@ -2726,10 +2721,7 @@ mod tests {
         // Add conditional breakpoint that triggers when r3 == 42
         interpreter.add_breakpoint(
             0x2000_0000,
-            BreakpointType::Conditional(BreakpointCondition::GprEquals {
-                reg: 3,
-                value: 42,
-            }),
+            BreakpointType::Conditional(Condition::parse("r3 == 42").unwrap()),
         );
         
         thread.set_gpr(3, 41);
@@ -2747,6 +2739,32 @@ mod tests {
         assert!(matches!(result, Err(PpuError::Breakpoint { .. })));
     }
 
+    #[test]
+    fn test_breakpoint_conditional_expression() {
+        let (interpreter, mut thread) = create_test_env();
+        interpreter.memory.write_be32(0x2000_0000, 0x38600064).unwrap();
+        interpreter.memory.write_be32(0x2000_0170, 7).unwrap();
+        thread.set_gpr(1, 0x2000_0100);
+
+        // Every second hit, while the word on the stack is non-zero
+        let condition = Condition::parse("hits % 2 == 0 && mem32(r1 + 0x70) != 0 && icount >= 1").unwrap();
+        interpreter.add_breakpoint(0x2000_0000, BreakpointType::Conditional(condition));
+
+        let mut broke = Vec::new();
+        for _ in 0..4 {
+            thread.set_pc(0x2000_0000);
+            broke.push(interpreter.step(&mut thread).is_err());
+        }
+        assert_eq!(broke, [false, true, false, true]);
+        assert_eq!(interpreter.get_breakpoints()[0].hit_count, 4);
+
+        interpreter.memory.write_be32(0x2000_0170, 0).unwrap();
+        thread.set_pc(0x2000_0000);
+        let _ = interpreter.step(&mut thread);
+        thread.set_pc(0x2000_0000);
+        assert!(interpreter.step(&mut thread).is_ok());
+    }
+
     #[test]
     fn test_breakpoint_disable() {
         let (interpreter, mut thread) = create_test_env();
@@ -2778,6 +2796,68 @@ mod tests {
         assert_eq!(breakpoints[0].hit_count, 1);
     }
 
+    #[test]
+    fn test_watchpoint_breaks_after_store() {
+        use oc_core::error::AccessKind;
+        use oc_memory::WatchpointType;
+
+        let (interpreter, mut thread) = create_test_env();
+        thread.set_pc(0x2000_0000);
+        thread.set_gpr(3, 0x1234);
+        thread.set_gpr(4, 0x2000_0000);
+
+        // Watching the code page for reads must not trigger on instruction fetch
+        interpreter
+            .memory
+            .watchpoints()
+            .add(0x2000_0000, 4, WatchpointType::Read);
+        interpreter
+            .memory
+            .watchpoints()
+            .add(0x2000_0100, 4, WatchpointType::Write);
+
+        // stw r3, 0x100(r4); nop
+        interpreter.memory.write_be32(0x2000_0000, 0x90640100).unwrap();
+        interpreter.memory.write_be32(0x2000_0004, 0x60000000).unwrap();
+
+        let result = interpreter.step(&mut thread);
+        assert!(matches!(
+            result,
+            Err(PpuError::Watchpoint {
+                addr: 0x2000_0100,
+                kind: AccessKind::Write,
+                pc: 0x2000_0000,
+            })
+        ));
+        // The store completed before breaking
+        assert_eq!(interpreter.memory.read_be32(0x2000_0100).unwrap(), 0x1234);
+        assert_eq!(thread.pc(), 0x2000_0004);
+
+        assert!(interpreter.step(&mut thread).is_ok());
+    }
+
+    #[test]
+    fn test_store_to_null_page_faults() {
+        use oc_core::error::AccessKind;
+
+        let (interpreter, mut thread) = create_test_env();
+        thread.set_pc(0x2000_0000);
+        thread.set_gpr(4, 0);
+
+        // stw r3, 0x10(r4) -> store to 0x00000010
+        interpreter.memory.write_be32(0x2000_0000, 0x90640010).unwrap();
+
+        let result = interpreter.step(&mut thread);
+        assert!(matches!(
+            result,
+            Err(PpuError::AccessViolation {
+                pc: 0x2000_0000,
+                addr: 0x10,
+                kind: AccessKind::Write,
+            })
+        ));
+    }
+
     #[test]
     fn test_instruction_count() {
         let (interpreter, mut thread) = create_test_env();
@@ -2801,4 +2881,35 @@ mod tests {
         interpreter.reset_instruction_count();
         assert_eq!(interpreter.instruction_count(), 0);
     }
+
+    #[test]
+    fn test_run_stops_before_syscall() {
+        let (interpreter, mut thread) = create_test_env();
+        let program = [
+            0x3860FFFF, // li r3, -1
+            0x7C831A15, // add. r4, r3, r3
+            0x60000000, // nop
+            0x44000002, // sc
+        ];
+        for (i, &opcode) in program.iter().enumerate() {
+            interpreter.memory.write_be32(0x2000_0000 + i as u32 * 4, opcode).unwrap();
+        }
+        thread.set_pc(0x2000_0000);
+
+        let (executed, result) = interpreter.run(&mut thread, 1);
+        assert!(result.is_ok());
+        assert_eq!(executed, 1);
+
+        let (executed, result) = interpreter.run(&mut thread, 100);
+        assert!(result.is_ok());
+        assert_eq!(executed, 2);
+        assert_eq!(thread.pc(), 0x2000_000C);
+        assert_eq!(interpreter.instruction_count(), 3);
+        // CR0 from add. is visible in the registers once run returns
+        assert_eq!(thread.regs.cr >> 28, 0b1000);
+
+        // The sc is left for the caller
+        let (executed, _) = interpreter.run(&mut thread, 100);
+        assert_eq!(executed, 0);
+    }
 }
@@ -4,6 +4,7 @@
 //! architecture with VMX/AltiVec SIMD support.
 
 pub mod decoder;
+pub mod guest_memory;
 pub mod instructions;
 pub mod interpreter;
 pub mod thread;
@@ -1,8 +1,25 @@
 //! PPU thread state
 
+use std::io;
 use std::sync::Arc;
 use oc_memory::MemoryManager;
-use oc_core::error::{PpuExceptionType, PowerState};
+use crate::guest_memory::PageCache;
+use oc_core::condition::{register_index, ConditionContext};
+use oc_core::error::{AccessKind, PpuExceptionType, PowerState};
+use oc_core::savestate::{invalid, StateReader, StateWriter};
+use oc_core::time_base::TimeBase;
+
+/// MSR bit selecting 64-bit mode
+pub const MSR_SF: u64 = 0x8000_0000_0000_0000;
+/// MSR bit enabling external and decrementer interrupts
+pub const MSR_EE: u64 = 1 << 15;
+/// MSR bit set in problem (user) state
+pub const MSR_PR: u64 = 1 << 14;
+
+/// DSISR bit for an access the page protection forbids
+pub const DSISR_PROTECTION: u32 = 0x0800_0000;
+/// DSISR bit for a faulting store
+pub const DSISR_STORE: u32 = 0x0200_0000;
 
 /// PPU register set
 #[derive(Debug, Clone)]
@@ -32,7 +49,11 @@ pub struct PpuRegisters {
     /// Save/Restore Registers (for exception handling)
     pub srr0: u64,
     pub srr1: u64,
-    /// Decrementer register
+    /// Data Address Register, the address of the last data storage exception
+    pub dar: u64,
+    /// Data Storage Interrupt Status Register, why that access faulted
+    pub dsisr: u32,
+    /// Decrementer value when it was last written
     pub dec: u32,
     /// Time Base registers (for timing)
     pub tb: u64,
@@ -51,9 +72,11 @@ impl Default for PpuRegisters {
             fpscr: 0,
             vscr: 0,
             cia: 0,
-            msr: 0x8000_0000_0000_0000, // 64-bit mode enabled by default
+            msr: MSR_SF | MSR_EE | MSR_PR, // 64-bit user mode taking interrupts
             srr0: 0,
             srr1: 0,
+            dar: 0,
+            dsisr: 0,
             dec: 0,
             tb: 0,
         }
@@ -220,12 +243,16 @@ pub enum InstructionLatency {
 /// Exception state for full exception model
 #[derive(Debug, Clone, Default)]
 pub struct ExceptionState {
-    /// Pending exception (if any)
+    /// Exception being handled, from its entry until the return from it
     pub pending: Option<PpuExceptionType>,
     /// Exception mask (which exceptions are enabled)
     pub mask: u64,
     /// Exception handler addresses
     pub handlers: [u64; 16],
+    /// External interrupt raised and not taken yet
+    pub external_pending: bool,
+    /// Decrementer written with a positive value and not expired yet
+    pub decrementer_armed: bool,
 }
 
 impl ExceptionState {
@@ -235,6 +262,8 @@ impl ExceptionState {
             pending: None,
             mask: 0xFFFF_FFFF_FFFF_FFFF, // All exceptions enabled by default
             handlers: [0; 16],
+            external_pending: false,
+            decrementer_armed: false,
         }
     }
 
@@ -277,6 +306,28 @@ impl ExceptionState {
     }
 }
 
+/// Address of the handler the PowerPC architecture assigns an exception
+pub fn exception_vector(exception: &PpuExceptionType) -> u64 {
+    match exception {
+        PpuExceptionType::SystemReset => 0x100,
+        PpuExceptionType::MachineCheck => 0x200,
+        PpuExceptionType::DataStorage => 0x300,
+        PpuExceptionType::DataSegment => 0x380,
+        PpuExceptionType::InstructionStorage => 0x400,
+        PpuExceptionType::InstructionSegment => 0x480,
+        PpuExceptionType::ExternalInterrupt => 0x500,
+        PpuExceptionType::Alignment => 0x600,
+        PpuExceptionType::Program { .. } => 0x700,
+        PpuExceptionType::FloatingPointUnavailable => 0x800,
+        PpuExceptionType::Decrementer => 0x900,
+        PpuExceptionType::SystemCall => 0xC00,
+        PpuExceptionType::Trace => 0xD00,
+        PpuExceptionType::FloatingPointAssist => 0xE00,
+        PpuExceptionType::PerformanceMonitor => 0xF00,
+        PpuExceptionType::VmxUnavailable => 0xF20,
+    }
+}
+
 /// Power management state
 #[derive(Debug, Clone)]
 pub struct PowerManagementState {
@@ -318,6 +369,17 @@ impl PowerManagementState {
     }
 }
 
+/// CR0 field for a result compared against zero: LT, GT, EQ, SO
+#[inline]
+fn cr0_bits(value: i64, so: bool) -> u32 {
+    let c = match value.cmp(&0) {
+        std::cmp::Ordering::Less => 0b1000,
+        std::cmp::Ordering::Greater => 0b0100,
+        std::cmp::Ordering::Equal => 0b0010,
+    };
+    c | so as u32
+}
+
 /// PPU thread
 pub struct PpuThread {
     /// Thread ID
@@ -344,6 +406,16 @@ pub struct PpuThread {
     pub exceptions: ExceptionState,
     /// Power management state
     pub power: PowerManagementState,
+    /// Result a record-form instruction compares for CR0, with XER[SO] at the time
+    ///
+    /// Folded into `regs.cr` when CR is read or the interpreter returns.
+    pending_cr0: Option<(i64, bool)>,
+    /// Pages the interpreter accessed without a recheck
+    pub(crate) page_cache: PageCache,
+    /// Emulated clock the time base register reads
+    time_base: TimeBase,
+    /// Time base when the decrementer was last written
+    dec_written_at: u64,
 }
 
 impl PpuThread {
@@ -362,6 +434,10 @@ impl PpuThread {
             timing: TimingState::new(false),
             exceptions: ExceptionState::new(),
             power: PowerManagementState::new(),
+            pending_cr0: None,
+            page_cache: PageCache::new(),
+            time_base: TimeBase::new(),
+            dec_written_at: 0,
         }
     }
 
@@ -372,6 +448,21 @@ impl PpuThread {
         thread
     }
 
+    /// Emulated clock the time base register reads
+    pub fn time_base(&self) -> &TimeBase {
+        &self.time_base
+    }
+
+    /// Read the time base from the emulator's shared clock
+    ///
+    /// The decrementer keeps its value and counts down on the new clock.
+    pub fn set_time_base(&mut self, time_base: TimeBase) {
+        let dec = self.decrementer();
+        self.time_base = time_base;
+        self.regs.dec = dec;
+        self.dec_written_at = self.time_base.ticks();
+    }
+
     /// Get the current instruction address
     pub fn pc(&self) -> u64 {
         self.regs.cia
@@ -445,13 +536,50 @@ impl PpuThread {
         self.state == PpuThreadState::Running
     }
 
+    /// Condition Register, including a CR0 update still pending
+    #[inline]
+    pub fn cr(&self) -> u32 {
+        match self.pending_cr0 {
+            Some((value, so)) => (self.regs.cr & 0x0FFF_FFFF) | (cr0_bits(value, so) << 28),
+            None => self.regs.cr,
+        }
+    }
+
+    /// Set the Condition Register
+    #[inline]
+    pub fn set_cr(&mut self, value: u32) {
+        self.pending_cr0 = None;
+        self.regs.cr = value;
+    }
+
+    /// Set CR0 from a record-form result compared against zero
+    ///
+    /// The comparison is deferred until CR is read, so results overwritten
+    /// by the next record-form instruction are never compared.
+    #[inline]
+    pub fn record_cr0(&mut self, value: u64) {
+        self.pending_cr0 = Some((value as i64, self.get_xer_so()));
+    }
+
+    /// Fold a pending CR0 update into `regs.cr`
+    #[inline]
+    pub fn sync_cr(&mut self) {
+        if self.pending_cr0.is_some() {
+            self.regs.cr = self.cr();
+            self.pending_cr0 = None;
+        }
+    }
+
     /// Get CR field value (0-7)
     pub fn get_cr_field(&self, field: usize) -> u32 {
-        (self.regs.cr >> (28 - field * 4)) & 0xF
+        (self.cr() >> (28 - field * 4)) & 0xF
     }
 
     /// Set CR field value (0-7)
     pub fn set_cr_field(&mut self, field: usize, value: u32) {
+        if field == 0 {
+            self.pending_cr0 = None;
+        }
         let shift = 28 - field * 4;
         self.regs.cr = (self.regs.cr & !(0xF << shift)) | ((value & 0xF) << shift);
     }
@@ -515,20 +643,65 @@ impl PpuThread {
     }
 
     /// Handle exception entry
-    pub fn enter_exception(&mut self, exception: PpuExceptionType, vector: u64) {
+    ///
+    /// SRR0 gets the instruction to return to: the one after `sc` for a
+    /// system call, otherwise the one that faulted or was interrupted.
+    pub fn enter_exception(&mut self, exception: PpuExceptionType) {
         // Save current state to SRR0/SRR1
-        self.regs.srr0 = self.regs.cia;
+        self.regs.srr0 = match exception {
+            PpuExceptionType::SystemCall => self.regs.cia.wrapping_add(4),
+            _ => self.regs.cia,
+        };
         self.regs.srr1 = self.regs.msr;
 
-        // Clear recoverable bits in MSR
-        self.regs.msr &= !(1 << 15); // Clear EE (External Interrupt Enable)
-        self.regs.msr &= !(1 << 14); // Clear PR (Problem State)
+        // Handlers run privileged with interrupts off
+        self.regs.msr &= !(MSR_EE | MSR_PR);
+
+        match exception {
+            PpuExceptionType::ExternalInterrupt => self.exceptions.external_pending = false,
+            PpuExceptionType::Decrementer => self.exceptions.decrementer_armed = false,
+            _ => {}
+        }
 
         // Set pending exception
         self.exceptions.raise(exception);
 
         // Jump to exception vector
-        self.regs.cia = vector;
+        self.regs.cia = exception_vector(&exception);
+    }
+
+    /// Enter a data storage exception for an access to `addr`
+    pub fn enter_data_storage(&mut self, addr: u32, kind: AccessKind) {
+        self.regs.dar = addr as u64;
+        self.regs.dsisr = match kind {
+            AccessKind::Write => DSISR_PROTECTION | DSISR_STORE,
+            _ => DSISR_PROTECTION,
+        };
+        self.enter_exception(PpuExceptionType::DataStorage);
+    }
+
+    /// Raise an external interrupt, taken once interrupts are enabled
+    pub fn raise_external_interrupt(&mut self) {
+        self.exceptions.external_pending = true;
+    }
+
+    /// Asynchronous exception to take before the next instruction, if any
+    ///
+    /// External interrupts take priority over the decrementer; neither is
+    /// taken while MSR[EE] is clear or the exception is masked.
+    pub fn pending_interrupt(&self) -> Option<PpuExceptionType> {
+        if !self.interrupts_enabled() {
+            return None;
+        }
+        let exceptions = &self.exceptions;
+        if exceptions.external_pending && !exceptions.is_masked(&PpuExceptionType::ExternalInterrupt) {
+            return Some(PpuExceptionType::ExternalInterrupt);
+        }
+        let expired = exceptions.decrementer_armed && (self.decrementer() as i32) < 0;
+        if expired && !exceptions.is_masked(&PpuExceptionType::Decrementer) {
+            return Some(PpuExceptionType::Decrementer);
+        }
+        None
     }
 
     /// Return from exception (rfi instruction)
@@ -546,13 +719,20 @@ impl PpuThread {
         self.regs.tb = self.regs.tb.wrapping_add(cycles);
     }
 
-    /// Decrement the decrementer register and check for exception
-    pub fn update_decrementer(&mut self, cycles: u32) -> bool {
-        let old_dec = self.regs.dec;
-        self.regs.dec = self.regs.dec.wrapping_sub(cycles);
+    /// Decrementer, counting down at the time base frequency since it was written
+    pub fn decrementer(&self) -> u32 {
+        let elapsed = self.time_base.ticks().wrapping_sub(self.dec_written_at);
+        self.regs.dec.wrapping_sub(elapsed as u32)
+    }
 
-        // Decrementer exception when it crosses from positive to negative
-        old_dec > 0 && self.regs.dec == 0
+    /// Write the decrementer
+    ///
+    /// A decrementer exception becomes pending when a non-negative value
+    /// counts down past zero.
+    pub fn set_decrementer(&mut self, value: u32) {
+        self.regs.dec = value;
+        self.dec_written_at = self.time_base.ticks();
+        self.exceptions.decrementer_armed = (value as i32) >= 0;
     }
 
     /// Add timing cycles for the current instruction
@@ -586,12 +766,137 @@ impl PpuThread {
 
     /// Check if external interrupts are enabled
     pub fn interrupts_enabled(&self) -> bool {
-        (self.regs.msr & (1 << 15)) != 0
+        (self.regs.msr & MSR_EE) != 0
     }
 
     /// Check if in privileged mode (supervisor)
     pub fn is_privileged(&self) -> bool {
-        (self.regs.msr & (1 << 14)) == 0
+        (self.regs.msr & MSR_PR) == 0
+    }
+
+    /// Save the registers and scheduling state for a savestate
+    ///
+    /// Pipeline, timing and power simulation state is not saved and starts
+    /// over after loading, and so do DAR, DSISR and interrupts not taken yet.
+    /// The decrementer is saved with the value it has counted down to.
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.u32(self.id);
+        w.str(&self.name);
+        w.u8(match self.state {
+            PpuThreadState::Stopped => 0,
+            PpuThreadState::Running => 1,
+            PpuThreadState::Waiting => 2,
+            PpuThreadState::Suspended => 3,
+        });
+        w.u32(self.stack_addr);
+        w.u32(self.stack_size);
+        w.u32(self.priority);
+
+        let regs = &self.regs;
+        for &gpr in &regs.gpr {
+            w.u64(gpr);
+        }
+        for &fpr in &regs.fpr {
+            w.f64(fpr);
+        }
+        for vr in &regs.vr {
+            for &word in vr {
+                w.u32(word);
+            }
+        }
+        w.u32(self.cr());
+        for value in [regs.lr, regs.ctr, regs.xer, regs.fpscr] {
+            w.u64(value);
+        }
+        w.u32(regs.vscr);
+        for value in [regs.cia, regs.msr, regs.srr0, regs.srr1] {
+            w.u64(value);
+        }
+        w.u64(regs.dar);
+        w.u32(regs.dsisr);
+        w.u32(self.decrementer());
+        w.u64(regs.tb);
+    }
+
+    /// Restore state written by [`PpuThread::save_state`]
+    pub fn load_state(&mut self, r: &mut StateReader) -> io::Result<()> {
+        self.id = r.u32()?;
+        self.name = r.str()?;
+        self.state = match r.u8()? {
+            0 => PpuThreadState::Stopped,
+            1 => PpuThreadState::Running,
+            2 => PpuThreadState::Waiting,
+            3 => PpuThreadState::Suspended,
+            state => return Err(invalid(&format!("invalid PPU thread state {}", state))),
+        };
+        self.stack_addr = r.u32()?;
+        self.stack_size = r.u32()?;
+        self.priority = r.u32()?;
+
+        let regs = &mut self.regs;
+        for gpr in &mut regs.gpr {
+            *gpr = r.u64()?;
+        }
+        for fpr in &mut regs.fpr {
+            *fpr = r.f64()?;
+        }
+        for vr in &mut regs.vr {
+            for word in vr {
+                *word = r.u32()?;
+            }
+        }
+        regs.cr = r.u32()?;
+        self.pending_cr0 = None;
+        regs.lr = r.u64()?;
+        regs.ctr = r.u64()?;
+        regs.xer = r.u64()?;
+        regs.fpscr = r.u64()?;
+        regs.vscr = r.u32()?;
+        regs.cia = r.u64()?;
+        regs.msr = r.u64()?;
+        regs.srr0 = r.u64()?;
+        regs.srr1 = r.u64()?;
+        regs.dar = r.u64()?;
+        regs.dsisr = r.u32()?;
+        let dec = r.u32()?;
+        regs.tb = r.u64()?;
+        self.exceptions = ExceptionState::new();
+        self.set_decrementer(dec);
+        Ok(())
+    }
+}
+
+impl ConditionContext for PpuThread {
+    fn variable(&self, name: &str) -> Option<u64> {
+        if let Some(index) = register_index(name, "r", 32) {
+            return Some(self.regs.gpr[index]);
+        }
+        if let Some(index) = register_index(name, "f", 32) {
+            return Some(self.regs.fpr[index].to_bits());
+        }
+        Some(match name {
+            "pc" | "cia" => self.regs.cia,
+            "lr" => self.regs.lr,
+            "ctr" => self.regs.ctr,
+            "cr" => self.cr() as u64,
+            "xer" => self.regs.xer,
+            "fpscr" => self.regs.fpscr,
+            "msr" => self.regs.msr,
+            "sp" => self.regs.gpr[1],
+            "toc" => self.regs.gpr[2],
+            _ => return None,
+        })
+    }
+
+    fn read_memory(&self, addr: u64, size: usize) -> Option<u64> {
+        let addr = u32::try_from(addr).ok()?;
+        match size {
+            1 => self.memory.read::<u8>(addr).ok().map(u64::from),
+            2 => self.memory.read_be16(addr).ok().map(u64::from),
+            4 => self.memory.read_be32(addr).ok().map(u64::from),
+            8 => self.memory.read_be64(addr).ok(),
+            _ => None,
+        }
     }
 }
 
@@ -649,4 +954,80 @@ mod tests {
         thread.set_cr_field(7, 0b0101);
         assert_eq!(thread.get_cr_field(7), 0b0101);
     }
+
+    #[test]
+    fn test_save_state_round_trip() {
+        let mem = create_test_memory();
+        let mut thread = PpuThread::new(3, Arc::clone(&mem));
+        thread.name = "worker".to_string();
+        thread.set_gpr(1, 0xD000_FF00);
+        thread.set_fpr(31, -2.5);
+        thread.set_vr(7, [1, 2, 3, 4]);
+        thread.set_cr_field(2, 0b0100);
+        thread.regs.lr = 0x10230;
+        thread.set_pc(0x10200);
+        thread.stack_size = 0x4000;
+        thread.priority = 1001;
+        thread.start();
+
+        let mut w = StateWriter::new();
+        thread.save_state(&mut w);
+        let data = w.into_bytes();
+
+        let mut loaded = PpuThread::new(0, mem);
+        loaded.load_state(&mut StateReader::new(&data)).unwrap();
+        assert_eq!(loaded.id, 3);
+        assert_eq!(loaded.name, "worker");
+        assert_eq!(loaded.state, PpuThreadState::Running);
+        assert_eq!(loaded.gpr(1), 0xD000_FF00);
+        assert_eq!(loaded.fpr(31), -2.5);
+        assert_eq!(loaded.vr(7), [1, 2, 3, 4]);
+        assert_eq!(loaded.get_cr_field(2), 0b0100);
+        assert_eq!(loaded.regs.lr, 0x10230);
+        assert_eq!(loaded.pc(), 0x10200);
+        assert_eq!((loaded.stack_size, loaded.priority), (0x4000, 1001));
+
+        assert!(loaded.load_state(&mut StateReader::new(&data[..data.len() - 1])).is_err());
+    }
+
+    #[test]
+    fn test_interrupt_delivery() {
+        let mut thread = PpuThread::new(0, create_test_memory());
+        thread.time_base().pause();
+        thread.set_pc(0x10000);
+        assert!(thread.interrupts_enabled() && !thread.is_privileged());
+        assert_eq!(thread.pending_interrupt(), None);
+
+        // The decrementer expires once it counts past zero at the time base frequency
+        thread.set_decrementer(1000);
+        thread.time_base().advance(std::time::Duration::from_micros(10));
+        assert_eq!(thread.decrementer(), 1000 - 798);
+        assert_eq!(thread.pending_interrupt(), None);
+        thread.time_base().advance(std::time::Duration::from_micros(10));
+        assert_eq!(thread.pending_interrupt(), Some(PpuExceptionType::Decrementer));
+
+        // External interrupts come first and return to the interrupted instruction
+        thread.raise_external_interrupt();
+        assert_eq!(thread.pending_interrupt(), Some(PpuExceptionType::ExternalInterrupt));
+        thread.enter_exception(PpuExceptionType::ExternalInterrupt);
+        assert_eq!((thread.pc(), thread.regs.srr0), (0x500, 0x10000));
+        assert!(thread.is_privileged());
+        assert_eq!(thread.pending_interrupt(), None);
+        thread.return_from_exception();
+        assert_eq!(thread.pc(), 0x10000);
+        assert!(thread.interrupts_enabled());
+
+        thread.enter_exception(PpuExceptionType::Decrementer);
+        thread.return_from_exception();
+        assert_eq!(thread.pending_interrupt(), None);
+
+        // System calls return after the sc, data storage faults to the access
+        thread.enter_exception(PpuExceptionType::SystemCall);
+        assert_eq!((thread.pc(), thread.regs.srr0), (0xC00, 0x10004));
+        thread.return_from_exception();
+        thread.enter_data_storage(0x2000_0010, AccessKind::Write);
+        assert_eq!((thread.pc(), thread.regs.srr0), (0x300, 0x10004));
+        assert_eq!(thread.regs.dar, 0x2000_0010);
+        assert_eq!(thread.regs.dsisr, DSISR_PROTECTION | DSISR_STORE);
+    }
 }
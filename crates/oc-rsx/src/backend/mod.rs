@@ -1,8 +1,10 @@
 //! RSX rendering backends
 
 pub mod null;
+pub mod staging;
 pub mod vulkan;
 
+use crate::shader::ShaderStage;
 use crate::vertex::VertexAttribute;
 
 /// Framebuffer data for display
@@ -89,6 +91,15 @@ pub trait GraphicsBackend {
     /// Bind texture to a slot
     fn bind_texture(&mut self, slot: u32, offset: u32);
 
+    /// Upload the decoded RGBA8 texels of the texture at `offset`
+    fn upload_texture(&mut self, offset: u32, width: u32, height: u32, data: &[u8]);
+
+    /// Stream vertex data for `binding` to the draws that follow in this frame
+    fn upload_vertex_data(&mut self, binding: u32, data: &[u8]);
+
+    /// Stream the constants of the bound `stage` program to the draws that follow in this frame
+    fn upload_shader_constants(&mut self, stage: ShaderStage, constants: &[[f32; 4]]);
+
     /// Set viewport
     fn set_viewport(&mut self, x: f32, y: f32, width: f32, height: f32, min_depth: f32, max_depth: f32);
 
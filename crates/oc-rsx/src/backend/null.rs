@@ -1,6 +1,7 @@
 //! Null backend for testing
 
 use super::{GraphicsBackend, FramebufferData, PrimitiveType};
+use crate::shader::ShaderStage;
 use crate::vertex::VertexAttribute;
 
 /// Null graphics backend (does nothing but provides test pattern)
@@ -49,6 +50,12 @@ impl GraphicsBackend for NullBackend {
 
     fn bind_texture(&mut self, _slot: u32, _offset: u32) {}
 
+    fn upload_texture(&mut self, _offset: u32, _width: u32, _height: u32, _data: &[u8]) {}
+
+    fn upload_vertex_data(&mut self, _binding: u32, _data: &[u8]) {}
+
+    fn upload_shader_constants(&mut self, _stage: ShaderStage, _constants: &[[f32; 4]]) {}
+
     fn set_viewport(&mut self, _x: f32, _y: f32, _width: f32, _height: f32, _min_depth: f32, _max_depth: f32) {}
 
     fn set_scissor(&mut self, _x: u32, _y: u32, _width: u32, _height: u32) {}
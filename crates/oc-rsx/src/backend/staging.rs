@@ -0,0 +1,193 @@
+//! Staging ring buffer for GPU uploads
+//!
+//! Texture and vertex data is written into one persistently mapped buffer
+//! instead of a staging allocation per resource. The write head runs around
+//! the ring; the space a frame wrote is reclaimed once that frame's fence
+//! has signalled, so uploads only have to wait when they outrun the GPU by
+//! more than the ring's capacity.
+
+use ash::vk;
+
+/// Default ring capacity
+pub const STAGING_RING_SIZE: u64 = 32 * 1024 * 1024;
+
+/// Sub-allocator for a ring of staging memory shared by the frames in flight
+///
+/// Positions are counted since the ring was created, so the used space is
+/// always `head - tail` and `position % capacity` is the buffer offset.
+#[derive(Debug)]
+pub struct StagingRing {
+    capacity: u64,
+    /// Position the next allocation starts at
+    head: u64,
+    /// Oldest position the GPU may still read
+    tail: u64,
+    /// Head at the end of the last frame recorded into each frame slot
+    frame_ends: Vec<u64>,
+}
+
+impl StagingRing {
+    /// Create a ring of `capacity` bytes for `frames` frames in flight
+    ///
+    /// Allocations are aligned to at most the largest power of two `capacity`
+    /// is a multiple of.
+    pub fn new(capacity: u64, frames: usize) -> Self {
+        Self {
+            capacity,
+            head: 0,
+            tail: 0,
+            frame_ends: vec![0; frames.max(1)],
+        }
+    }
+
+    /// Ring size in bytes
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    /// Bytes written that the GPU may still read
+    pub fn used(&self) -> u64 {
+        self.head - self.tail
+    }
+
+    /// Reserve `size` bytes aligned to `align`, returning their buffer offset
+    ///
+    /// An allocation never straddles the end of the ring; the bytes left
+    /// before it are skipped. Returns None if the ring is full.
+    pub fn allocate(&mut self, size: u64, align: u64) -> Option<u64> {
+        if size == 0 || size > self.capacity {
+            return None;
+        }
+        let mut start = self.head.next_multiple_of(align.max(1));
+        let offset = start % self.capacity;
+        if offset + size > self.capacity {
+            start += self.capacity - offset;
+        }
+        if start + size - self.tail > self.capacity {
+            return None;
+        }
+        self.head = start + size;
+        Some(start % self.capacity)
+    }
+
+    /// Mark everything allocated so far as read by the frame recorded into `slot`
+    pub fn end_frame(&mut self, slot: usize) {
+        self.frame_ends[slot] = self.head;
+    }
+
+    /// Reclaim the space of the frame in `slot` after its fence has signalled
+    pub fn reclaim(&mut self, slot: usize) {
+        self.tail = self.tail.max(self.frame_ends[slot]);
+    }
+
+    /// Reclaim every frame's space after the device went idle
+    pub fn reclaim_all(&mut self) {
+        self.tail = self.head;
+    }
+}
+
+/// Copy of staged texels into a texture image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureCopy {
+    /// Destination image
+    pub image: vk::Image,
+    /// Offset of the texels in the staging ring
+    pub src_offset: u64,
+    /// Image width
+    pub width: u32,
+    /// Image height
+    pub height: u32,
+}
+
+/// Copies recorded in one go before a frame's draws
+#[derive(Debug)]
+pub struct UploadBatch {
+    textures: Vec<TextureCopy>,
+}
+
+impl UploadBatch {
+    /// Create an empty batch
+    pub fn new() -> Self {
+        Self { textures: Vec::new() }
+    }
+
+    /// Queue a texture copy, replacing an earlier copy into the same image
+    pub fn push_texture(&mut self, copy: TextureCopy) {
+        match self.textures.iter_mut().find(|queued| queued.image == copy.image) {
+            Some(queued) => *queued = copy,
+            None => self.textures.push(copy),
+        }
+    }
+
+    /// Queued texture copies
+    pub fn textures(&self) -> &[TextureCopy] {
+        &self.textures
+    }
+
+    /// Whether nothing is queued
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty()
+    }
+
+    /// Drop the queued copies once they are recorded
+    pub fn clear(&mut self) {
+        self.textures.clear();
+    }
+}
+
+impl Default for UploadBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ash::vk::Handle;
+
+    #[test]
+    fn test_staging_ring_wrap() {
+        let mut ring = StagingRing::new(1024, 2);
+        assert_eq!(ring.allocate(400, 16), Some(0));
+        assert_eq!(ring.allocate(10, 16), Some(400));
+        // Alignment pads the head
+        assert_eq!(ring.allocate(100, 16), Some(416));
+        ring.end_frame(0);
+
+        // The next frame fills the ring until frame 0 is reclaimed
+        assert_eq!(ring.allocate(500, 16), None);
+        assert_eq!(ring.allocate(400, 16), Some(528));
+        ring.end_frame(1);
+        assert_eq!(ring.allocate(200, 16), None);
+
+        ring.reclaim(0);
+        assert_eq!(ring.used(), 928 - 516);
+        // Too little is left before the end, so the allocation wraps to the start
+        assert_eq!(ring.allocate(200, 16), Some(0));
+        // The skipped tail of the ring stays used until frame 1 is reclaimed
+        assert_eq!(ring.used(), 928 - 516 + 96 + 200);
+        ring.end_frame(0);
+
+        ring.reclaim(1);
+        ring.reclaim(0);
+        assert_eq!(ring.used(), 0);
+        assert_eq!(ring.allocate(2048, 16), None);
+        assert_eq!(ring.allocate(0, 16), None);
+    }
+
+    #[test]
+    fn test_upload_batch() {
+        let image = |raw| vk::Image::from_raw(raw);
+        let copy = |raw, src_offset| TextureCopy { image: image(raw), src_offset, width: 4, height: 4 };
+        let mut batch = UploadBatch::new();
+        assert!(batch.is_empty());
+        batch.push_texture(copy(1, 0));
+        batch.push_texture(copy(2, 64));
+        batch.push_texture(copy(1, 128));
+        // Only the latest texels of an image are copied
+        assert_eq!(batch.textures(), &[copy(1, 128), copy(2, 64)]);
+        batch.clear();
+        assert!(batch.is_empty());
+    }
+}
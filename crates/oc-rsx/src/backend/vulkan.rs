@@ -2,14 +2,37 @@
 //!
 //! This module contains the Vulkan implementation for RSX rendering.
 
+use super::staging::{StagingRing, TextureCopy, UploadBatch, STAGING_RING_SIZE};
 use super::{GraphicsBackend, PrimitiveType};
+use crate::shader::ShaderStage;
 use crate::vertex::{VertexAttribute, VertexAttributeType};
 use ash::vk;
 use gpu_allocator::vulkan::{Allocation, AllocationCreateDesc, AllocationScheme, Allocator, AllocatorCreateDesc};
 use gpu_allocator::MemoryLocation;
+use std::collections::HashMap;
 use std::ffi::CString;
 use std::sync::{Arc, Mutex};
 
+/// Alignment of uploads in the staging ring, enough for texel and vertex copies
+const STAGING_ALIGN: u64 = 16;
+
+/// Texture image uploaded from the staging ring
+struct VulkanTexture {
+    image: vk::Image,
+    view: vk::ImageView,
+    allocation: Allocation,
+    width: u32,
+    height: u32,
+}
+
+/// Texture upload waiting for room in the staging ring
+struct DeferredUpload {
+    offset: u32,
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
 #[allow(dead_code)]
 /// Vulkan graphics backend
 pub struct VulkanBackend {
@@ -105,6 +128,22 @@ pub struct VulkanBackend {
     anisotropy_level: f32,
     /// Maximum supported anisotropy
     max_anisotropy: f32,
+    /// Persistently mapped buffer uploads are staged in
+    staging_buffer: Option<vk::Buffer>,
+    /// Staging buffer memory allocation
+    staging_allocation: Option<Allocation>,
+    /// Sub-allocator of the staging buffer
+    staging_ring: StagingRing,
+    /// Copies out of the staging buffer recorded before this frame's draws
+    upload_batch: UploadBatch,
+    /// Command buffers recording each frame's uploads
+    upload_command_buffers: Vec<vk::CommandBuffer>,
+    /// Uploaded textures by RSX offset
+    textures: HashMap<u32, VulkanTexture>,
+    /// Texture uploads retried once a frame's staging space is reclaimed
+    deferred_uploads: Vec<DeferredUpload>,
+    /// Staging ring offsets of this frame's vertex and fragment program constants
+    constant_offsets: [Option<u64>; 2],
 }
 
 impl VulkanBackend {
@@ -162,6 +201,14 @@ impl VulkanBackend {
             rtt_framebuffers: Vec::new(),
             anisotropy_level: 1.0,
             max_anisotropy: 16.0,
+            staging_buffer: None,
+            staging_allocation: None,
+            staging_ring: StagingRing::new(STAGING_RING_SIZE, max_frames),
+            upload_batch: UploadBatch::new(),
+            upload_command_buffers: Vec::new(),
+            textures: HashMap::new(),
+            deferred_uploads: Vec::new(),
+            constant_offsets: [None; 2],
         }
     }
 
@@ -225,6 +272,16 @@ impl VulkanBackend {
         self.anisotropy_level
     }
 
+    /// Staging ring uploads are written to
+    pub fn staging_ring(&self) -> &StagingRing {
+        &self.staging_ring
+    }
+
+    /// Number of textures uploaded to the GPU
+    pub fn texture_count(&self) -> usize {
+        self.textures.len()
+    }
+
     /// Create Vulkan instance
     fn create_instance(entry: &ash::Entry) -> Result<ash::Instance, String> {
         let app_name = CString::new("oxidized-cell RSX").unwrap();
@@ -571,6 +628,253 @@ impl VulkanBackend {
         Ok((image, view, allocation))
     }
 
+    /// Create the persistently mapped staging buffer
+    fn create_staging_buffer(
+        device: &ash::Device,
+        allocator: &Arc<Mutex<Allocator>>,
+        size: u64,
+    ) -> Result<(vk::Buffer, Allocation), String> {
+        // Vertex data is read straight out of the ring, so it doubles as a vertex buffer
+        let buffer_info = vk::BufferCreateInfo::default()
+            .size(size)
+            .usage(
+                vk::BufferUsageFlags::TRANSFER_SRC
+                    | vk::BufferUsageFlags::VERTEX_BUFFER
+                    | vk::BufferUsageFlags::INDEX_BUFFER,
+            )
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+
+        let buffer = unsafe {
+            device
+                .create_buffer(&buffer_info, None)
+                .map_err(|e| format!("Failed to create staging buffer: {:?}", e))?
+        };
+
+        let requirements = unsafe { device.get_buffer_memory_requirements(buffer) };
+
+        let allocation = allocator
+            .lock()
+            .unwrap()
+            .allocate(&AllocationCreateDesc {
+                name: "staging_ring",
+                requirements,
+                location: MemoryLocation::CpuToGpu,
+                linear: true,
+                allocation_scheme: AllocationScheme::GpuAllocatorManaged,
+            })
+            .map_err(|e| format!("Failed to allocate memory for staging buffer: {:?}", e))?;
+
+        unsafe {
+            device
+                .bind_buffer_memory(buffer, allocation.memory(), allocation.offset())
+                .map_err(|e| format!("Failed to bind staging buffer memory: {:?}", e))?;
+        }
+
+        Ok((buffer, allocation))
+    }
+
+    /// Create a sampled RGBA8 image for a texture
+    fn create_texture_image(
+        device: &ash::Device,
+        allocator: &Arc<Mutex<Allocator>>,
+        width: u32,
+        height: u32,
+    ) -> Result<VulkanTexture, String> {
+        let image_info = vk::ImageCreateInfo::default()
+            .image_type(vk::ImageType::TYPE_2D)
+            .format(vk::Format::R8G8B8A8_UNORM)
+            .extent(vk::Extent3D {
+                width,
+                height,
+                depth: 1,
+            })
+            .mip_levels(1)
+            .array_layers(1)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .usage(vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .initial_layout(vk::ImageLayout::UNDEFINED);
+
+        let image = unsafe {
+            device
+                .create_image(&image_info, None)
+                .map_err(|e| format!("Failed to create texture image: {:?}", e))?
+        };
+
+        let requirements = unsafe { device.get_image_memory_requirements(image) };
+
+        let allocation = allocator
+            .lock()
+            .unwrap()
+            .allocate(&AllocationCreateDesc {
+                name: "rsx_texture",
+                requirements,
+                location: MemoryLocation::GpuOnly,
+                linear: false,
+                allocation_scheme: AllocationScheme::GpuAllocatorManaged,
+            })
+            .map_err(|e| format!("Failed to allocate memory for texture: {:?}", e))?;
+
+        unsafe {
+            device
+                .bind_image_memory(image, allocation.memory(), allocation.offset())
+                .map_err(|e| format!("Failed to bind texture image memory: {:?}", e))?;
+        }
+
+        let view_info = vk::ImageViewCreateInfo::default()
+            .image(image)
+            .view_type(vk::ImageViewType::TYPE_2D)
+            .format(vk::Format::R8G8B8A8_UNORM)
+            .subresource_range(Self::color_subresource_range());
+
+        let view = unsafe {
+            device
+                .create_image_view(&view_info, None)
+                .map_err(|e| format!("Failed to create texture image view: {:?}", e))?
+        };
+
+        Ok(VulkanTexture { image, view, allocation, width, height })
+    }
+
+    /// Destroy a texture image after the GPU stopped using it
+    fn destroy_texture(device: &ash::Device, allocator: &Arc<Mutex<Allocator>>, texture: VulkanTexture) {
+        unsafe {
+            device.destroy_image_view(texture.view, None);
+            device.destroy_image(texture.image, None);
+        }
+        allocator.lock().unwrap().free(texture.allocation).ok();
+    }
+
+    /// Single mip level and layer of a color image
+    fn color_subresource_range() -> vk::ImageSubresourceRange {
+        vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        }
+    }
+
+    /// Write `data` into the staging ring, returning its buffer offset
+    fn stage(&mut self, data: &[u8]) -> Option<u64> {
+        let offset = self.staging_ring.allocate(data.len() as u64, STAGING_ALIGN)?;
+        let mapped = self.staging_allocation.as_mut()?.mapped_slice_mut()?;
+        mapped[offset as usize..offset as usize + data.len()].copy_from_slice(data);
+        Some(offset)
+    }
+
+    /// Record the batched texture copies into `cmd_buffer`
+    ///
+    /// Every image moves to TRANSFER_DST_OPTIMAL in one barrier, is copied,
+    /// and moves to SHADER_READ_ONLY_OPTIMAL in a second barrier.
+    fn record_uploads(
+        device: &ash::Device,
+        cmd_buffer: vk::CommandBuffer,
+        staging_buffer: vk::Buffer,
+        copies: &[TextureCopy],
+    ) -> Result<(), String> {
+        let barrier = |image, old_layout, new_layout, src_access, dst_access| {
+            vk::ImageMemoryBarrier::default()
+                .src_access_mask(src_access)
+                .dst_access_mask(dst_access)
+                .old_layout(old_layout)
+                .new_layout(new_layout)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .image(image)
+                .subresource_range(Self::color_subresource_range())
+        };
+        let to_transfer: Vec<_> = copies
+            .iter()
+            .map(|copy| {
+                barrier(
+                    copy.image,
+                    vk::ImageLayout::UNDEFINED,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    vk::AccessFlags::empty(),
+                    vk::AccessFlags::TRANSFER_WRITE,
+                )
+            })
+            .collect();
+        let to_shader: Vec<_> = copies
+            .iter()
+            .map(|copy| {
+                barrier(
+                    copy.image,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                    vk::AccessFlags::TRANSFER_WRITE,
+                    vk::AccessFlags::SHADER_READ,
+                )
+            })
+            .collect();
+
+        let begin_info = vk::CommandBufferBeginInfo::default()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+        unsafe {
+            device
+                .begin_command_buffer(cmd_buffer, &begin_info)
+                .map_err(|e| format!("Failed to begin upload command buffer: {:?}", e))?;
+
+            // Earlier frames may still sample an image that is uploaded again
+            device.cmd_pipeline_barrier(
+                cmd_buffer,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &to_transfer,
+            );
+
+            for copy in copies {
+                let region = vk::BufferImageCopy {
+                    buffer_offset: copy.src_offset,
+                    buffer_row_length: 0,
+                    buffer_image_height: 0,
+                    image_subresource: vk::ImageSubresourceLayers {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        mip_level: 0,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    },
+                    image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
+                    image_extent: vk::Extent3D {
+                        width: copy.width,
+                        height: copy.height,
+                        depth: 1,
+                    },
+                };
+                device.cmd_copy_buffer_to_image(
+                    cmd_buffer,
+                    staging_buffer,
+                    copy.image,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &[region],
+                );
+            }
+
+            device.cmd_pipeline_barrier(
+                cmd_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &to_shader,
+            );
+
+            device
+                .end_command_buffer(cmd_buffer)
+                .map_err(|e| format!("Failed to end upload command buffer: {:?}", e))?;
+        }
+
+        Ok(())
+    }
+
     /// Convert RSX vertex attribute type to Vulkan format
     fn vertex_type_to_vk_format(type_: VertexAttributeType, size: u8, normalized: bool) -> vk::Format {
         match (type_, size, normalized) {
@@ -679,6 +983,17 @@ impl GraphicsBackend for VulkanBackend {
                 .map_err(|e| format!("Failed to allocate command buffers: {:?}", e))?
         };
 
+        // Uploads are recorded into their own command buffers, submitted ahead of each frame's draws
+        let upload_command_buffers = unsafe {
+            device
+                .allocate_command_buffers(&alloc_info)
+                .map_err(|e| format!("Failed to allocate upload command buffers: {:?}", e))?
+        };
+
+        // Create the staging ring shared by all texture and vertex uploads
+        let (staging_buffer, staging_allocation) =
+            Self::create_staging_buffer(&device, &allocator, STAGING_RING_SIZE)?;
+
         // Create render pass
         let render_pass = Self::create_render_pass(&device)?;
 
@@ -733,6 +1048,10 @@ impl GraphicsBackend for VulkanBackend {
         self.depth_image = Some(depth_image);
         self.depth_image_view = Some(depth_image_view);
         self.depth_image_allocation = Some(depth_allocation);
+        self.staging_buffer = Some(staging_buffer);
+        self.staging_allocation = Some(staging_allocation);
+        self.staging_ring = StagingRing::new(STAGING_RING_SIZE, self.max_frames_in_flight);
+        self.upload_command_buffers = upload_command_buffers;
         self.allocator = Some(allocator);
         self.initialized = true;
 
@@ -798,6 +1117,19 @@ impl GraphicsBackend for VulkanBackend {
                     }
                 }
 
+                // Destroy uploaded textures and the staging ring
+                if let Some(allocator) = &self.allocator {
+                    for (_, texture) in self.textures.drain() {
+                        Self::destroy_texture(device, allocator, texture);
+                    }
+                    if let Some(buffer) = self.staging_buffer.take() {
+                        device.destroy_buffer(buffer, None);
+                    }
+                    if let Some(allocation) = self.staging_allocation.take() {
+                        allocator.lock().unwrap().free(allocation).ok();
+                    }
+                }
+
                 // Destroy pipeline resources
                 if let Some(pipeline) = self.pipeline.take() {
                     device.destroy_pipeline(pipeline, None);
@@ -835,6 +1167,10 @@ impl GraphicsBackend for VulkanBackend {
         self.graphics_queue = None;
         self.current_cmd_buffer = None;
         self.command_buffers.clear();
+        self.upload_command_buffers.clear();
+        self.upload_batch.clear();
+        self.deferred_uploads.clear();
+        self.staging_ring.reclaim_all();
         self.render_images.clear();
         self.depth_image = None;
         self.allocator = None;
@@ -863,6 +1199,10 @@ impl GraphicsBackend for VulkanBackend {
                 }
             }
 
+            // The frame that last used this slot is done reading its staged uploads
+            self.staging_ring.reclaim(self.current_frame);
+            self.constant_offsets = [None; 2];
+
             // Get current command buffer
             let cmd_buffer = self.command_buffers[self.current_frame];
             self.current_cmd_buffer = Some(cmd_buffer);
@@ -877,6 +1217,10 @@ impl GraphicsBackend for VulkanBackend {
                 }
             }
         }
+
+        for upload in std::mem::take(&mut self.deferred_uploads) {
+            self.upload_texture(upload.offset, upload.width, upload.height, &upload.data);
+        }
     }
 
     fn end_frame(&mut self) {
@@ -906,7 +1250,18 @@ impl GraphicsBackend for VulkanBackend {
                 let signal_semaphores = [self.render_finished_semaphores[self.current_frame]];
                 let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
 
-                let cmd_buffers = [cmd_buffer];
+                // Batched uploads run ahead of the draws that sample them
+                let upload_cmd_buffer = self.upload_command_buffers[self.current_frame];
+                let mut cmd_buffers = vec![cmd_buffer];
+                if let (false, Some(staging_buffer)) = (self.upload_batch.is_empty(), self.staging_buffer) {
+                    match Self::record_uploads(device, upload_cmd_buffer, staging_buffer, self.upload_batch.textures()) {
+                        Ok(()) => cmd_buffers.insert(0, upload_cmd_buffer),
+                        Err(e) => tracing::error!("{}", e),
+                    }
+                }
+                self.upload_batch.clear();
+                self.staging_ring.end_frame(self.current_frame);
+
                 let submit_info = vk::SubmitInfo::default()
                     .wait_semaphores(&wait_semaphores)
                     .wait_dst_stage_mask(&wait_stages)
@@ -1091,10 +1446,100 @@ impl GraphicsBackend for VulkanBackend {
 
         tracing::trace!("Bind texture: slot={}, offset=0x{:08x}", slot, offset);
 
+        if !self.textures.contains_key(&offset) {
+            tracing::trace!("Texture at 0x{:08x} is not uploaded yet", offset);
+        }
+
         // TODO: Bind texture descriptor set
         // This would update descriptor sets with the texture at the given offset
     }
 
+    fn upload_texture(&mut self, offset: u32, width: u32, height: u32, data: &[u8]) {
+        if !self.initialized {
+            return;
+        }
+
+        tracing::trace!("Upload texture: offset=0x{:08x}, {}x{}", offset, width, height);
+
+        if data.len() as u64 != u64::from(width) * u64::from(height) * 4 {
+            tracing::warn!("Texture at 0x{:08x} has {} bytes for {}x{} RGBA8", offset, data.len(), width, height);
+            return;
+        }
+        if data.len() as u64 > self.staging_ring.capacity() {
+            tracing::warn!("Texture at 0x{:08x} is larger than the staging ring", offset);
+            return;
+        }
+        let Some(src_offset) = self.stage(data) else {
+            // Retried once an in-flight frame hands its staging space back
+            tracing::debug!("Staging ring full, deferring texture at 0x{:08x}", offset);
+            self.deferred_uploads.push(DeferredUpload { offset, width, height, data: data.to_vec() });
+            return;
+        };
+
+        let (Some(device), Some(allocator)) = (&self.device, &self.allocator) else {
+            return;
+        };
+        let reusable = self.textures.get(&offset).is_some_and(|t| (t.width, t.height) == (width, height));
+        if !reusable {
+            if let Some(old) = self.textures.remove(&offset) {
+                // Resizing a texture in place is rare; wait for the frames still sampling it
+                unsafe {
+                    device.device_wait_idle().ok();
+                }
+                Self::destroy_texture(device, allocator, old);
+            }
+            match Self::create_texture_image(device, allocator, width, height) {
+                Ok(texture) => {
+                    self.textures.insert(offset, texture);
+                }
+                Err(e) => {
+                    tracing::error!("{}", e);
+                    return;
+                }
+            }
+        }
+
+        let image = self.textures[&offset].image;
+        self.upload_batch.push_texture(TextureCopy { image, src_offset, width, height });
+    }
+
+    fn upload_vertex_data(&mut self, binding: u32, data: &[u8]) {
+        if !self.initialized || data.is_empty() {
+            return;
+        }
+
+        tracing::trace!("Upload vertex data: binding={}, size={}", binding, data.len());
+
+        // Vertex data only lives for this frame's draws, so it is bound straight from the ring
+        let Some(offset) = self.stage(data) else {
+            tracing::warn!("Staging ring full, dropping {} bytes of vertex data", data.len());
+            return;
+        };
+        if let (Some(device), Some(cmd_buffer), Some(buffer)) =
+            (&self.device, self.current_cmd_buffer, self.staging_buffer)
+        {
+            unsafe {
+                device.cmd_bind_vertex_buffers(cmd_buffer, binding, &[buffer], &[offset]);
+            }
+        }
+    }
+
+    fn upload_shader_constants(&mut self, stage: ShaderStage, constants: &[[f32; 4]]) {
+        if !self.initialized || constants.is_empty() {
+            return;
+        }
+
+        tracing::trace!("Upload {:?} constants: count={}", stage, constants.len());
+
+        // Like vertex data, constants are read straight from the ring by this frame's draws
+        let Some(offset) = self.stage(bytemuck::cast_slice(constants)) else {
+            tracing::warn!("Staging ring full, dropping {} shader constants", constants.len());
+            return;
+        };
+        let slot = usize::from(stage == ShaderStage::FRAGMENT);
+        self.constant_offsets[slot] = Some(offset);
+    }
+
     fn set_viewport(&mut self, x: f32, y: f32, width: f32, height: f32, min_depth: f32, max_depth: f32) {
         if !self.initialized {
             return;
@@ -1211,6 +1656,10 @@ mod tests {
         backend.draw_indexed(PrimitiveType::Triangles, 0, 3);
         backend.set_viewport(0.0, 0.0, 800.0, 600.0, 0.0, 1.0);
         backend.set_scissor(0, 0, 800, 600);
+        backend.upload_texture(0x1000, 1, 1, &[0xFF; 4]);
+        backend.upload_vertex_data(0, &[0; 48]);
+        assert_eq!(backend.texture_count(), 0);
+        assert_eq!(backend.staging_ring().used(), 0);
     }
 
     #[test]
@@ -0,0 +1,649 @@
+//! 2D blit engines (NV0039, NV3062, NV309E, NV3089)
+//!
+//! Besides the NV4097 3D class, libgcm binds 2D classes to the other
+//! subchannels. Their methods are addressed here with the subchannel in
+//! bits 13-15, as the FIFO command header encodes it:
+//!
+//! - NV0039 copies lines of bytes between buffers, used for uploads
+//! - NV3062 describes a linear destination surface
+//! - NV309E describes a swizzled destination surface, used to write textures
+//! - NV3089 scales an image from memory onto either destination surface
+//!
+//! Only local memory is read and written. Buffers in main memory are
+//! addressed through the IO mapping, which the RSX does not see yet.
+
+use crate::texture_decode::swizzle_index;
+use oc_core::savestate::{StateReader, StateWriter};
+use oc_memory::MemoryManager;
+use std::io;
+use std::ops::Range;
+
+// NV0039 memory to memory format, subchannel 1
+pub const NV0039_SET_CONTEXT_DMA_BUFFER_IN: u32 = 0x2184;
+pub const NV0039_SET_CONTEXT_DMA_BUFFER_OUT: u32 = 0x2188;
+pub const NV0039_OFFSET_IN: u32 = 0x230C;
+pub const NV0039_OFFSET_OUT: u32 = 0x2310;
+pub const NV0039_PITCH_IN: u32 = 0x2314;
+pub const NV0039_PITCH_OUT: u32 = 0x2318;
+pub const NV0039_LINE_LENGTH_IN: u32 = 0x231C;
+pub const NV0039_LINE_COUNT: u32 = 0x2320;
+pub const NV0039_FORMAT: u32 = 0x2324;
+pub const NV0039_BUFFER_NOTIFY: u32 = 0x2328;
+
+// NV3062 context surfaces 2D, subchannel 3
+pub const NV3062_SET_CONTEXT_DMA_IMAGE_SOURCE: u32 = 0x6184;
+pub const NV3062_SET_CONTEXT_DMA_IMAGE_DESTIN: u32 = 0x6188;
+pub const NV3062_SET_COLOR_FORMAT: u32 = 0x6300;
+pub const NV3062_SET_PITCH: u32 = 0x6304;
+pub const NV3062_SET_OFFSET_SOURCE: u32 = 0x6308;
+pub const NV3062_SET_OFFSET_DESTIN: u32 = 0x630C;
+
+// NV309E swizzled surface, subchannel 4
+pub const NV309E_SET_CONTEXT_DMA_IMAGE: u32 = 0x8180;
+pub const NV309E_SET_FORMAT: u32 = 0x8300;
+pub const NV309E_SET_OFFSET: u32 = 0x8304;
+
+// NV3089 scaled image from memory, subchannel 6
+pub const NV3089_SET_CONTEXT_DMA_IMAGE: u32 = 0xC184;
+pub const NV3089_SET_CONTEXT_SURFACE: u32 = 0xC198;
+pub const NV3089_SET_COLOR_CONVERSION: u32 = 0xC2FC;
+pub const NV3089_SET_COLOR_FORMAT: u32 = 0xC300;
+pub const NV3089_SET_OPERATION: u32 = 0xC304;
+pub const NV3089_CLIP_POINT: u32 = 0xC308;
+pub const NV3089_CLIP_SIZE: u32 = 0xC30C;
+pub const NV3089_IMAGE_OUT_POINT: u32 = 0xC310;
+pub const NV3089_IMAGE_OUT_SIZE: u32 = 0xC314;
+pub const NV3089_DS_DX: u32 = 0xC318;
+pub const NV3089_DT_DY: u32 = 0xC31C;
+pub const NV3089_IMAGE_IN_SIZE: u32 = 0xC400;
+pub const NV3089_IMAGE_IN_FORMAT: u32 = 0xC404;
+pub const NV3089_IMAGE_IN_OFFSET: u32 = 0xC408;
+pub const NV3089_IMAGE_IN: u32 = 0xC40C;
+
+/// Context DMA handle of RSX local memory
+pub const CONTEXT_DMA_LOCAL: u32 = 0xFEED_0000;
+/// Context DMA handle of main memory mapped for the RSX
+pub const CONTEXT_DMA_MAIN: u32 = 0xFEED_0001;
+
+/// NV3089_SET_CONTEXT_SURFACE handle selecting the NV3062 linear surface
+pub const CONTEXT_SURFACE_2D: u32 = 0x3133_71C3;
+/// NV3089_SET_CONTEXT_SURFACE handle selecting the NV309E swizzled surface
+pub const CONTEXT_SWIZZLE_2D: u32 = 0x3133_7A73;
+
+// NV3062 and NV309E surface formats
+pub const SURFACE_FORMAT_R5G6B5: u32 = 0x4;
+pub const SURFACE_FORMAT_A8R8G8B8: u32 = 0xA;
+pub const SURFACE_FORMAT_Y32: u32 = 0xB;
+
+// NV3089 source image formats
+pub const SCALE_FORMAT_A1R5G5B5: u32 = 0x1;
+pub const SCALE_FORMAT_X1R5G5B5: u32 = 0x2;
+pub const SCALE_FORMAT_A8R8G8B8: u32 = 0x3;
+pub const SCALE_FORMAT_X8R8G8B8: u32 = 0x4;
+pub const SCALE_FORMAT_R5G6B5: u32 = 0x7;
+pub const SCALE_FORMAT_A8B8G8R8: u32 = 0xC;
+pub const SCALE_FORMAT_X8B8G8R8: u32 = 0xD;
+
+/// Methods of the 2D classes, on subchannels 1 to 6
+pub const BLIT_METHODS: Range<u32> = 0x2000..0xE000;
+
+/// NV0039 copy of `line_count` lines of `line_length` bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineCopy {
+    pub src_dma: u32,
+    pub src_offset: u32,
+    pub src_pitch: u32,
+    pub dst_dma: u32,
+    pub dst_offset: u32,
+    pub dst_pitch: u32,
+    pub line_length: u32,
+    pub line_count: u32,
+}
+
+/// Destination surface of a scaled blit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlitSurface {
+    /// NV3062 surface stored row by row
+    Linear { dma: u32, offset: u32, pitch: u32, format: u32 },
+    /// NV309E surface stored in Z-order, with power of two sides
+    Swizzled { dma: u32, offset: u32, format: u32, log2_width: u32, log2_height: u32 },
+}
+
+impl BlitSurface {
+    /// Context DMA the surface is in
+    pub fn dma(&self) -> u32 {
+        match *self {
+            Self::Linear { dma, .. } | Self::Swizzled { dma, .. } => dma,
+        }
+    }
+
+    /// Surface format
+    pub fn format(&self) -> u32 {
+        match *self {
+            Self::Linear { format, .. } | Self::Swizzled { format, .. } => format,
+        }
+    }
+}
+
+/// NV3089 scaled copy of an image onto a surface
+///
+/// The source point and steps are fixed point: 12.4 for the point and
+/// 12.20 for the source step per destination pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScaledBlit {
+    pub src_dma: u32,
+    pub src_offset: u32,
+    pub src_pitch: u32,
+    pub src_format: u32,
+    pub src_width: u32,
+    pub src_height: u32,
+    pub src_x: u32,
+    pub src_y: u32,
+    pub ds_dx: u32,
+    pub dt_dy: u32,
+    /// Destination rectangle, x, y, width and height
+    pub out_rect: [i32; 4],
+    /// Clip rectangle in destination coordinates
+    pub clip_rect: [i32; 4],
+    pub dst: BlitSurface,
+}
+
+/// Copy started by a 2D class method
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlitCommand {
+    Lines(LineCopy),
+    Scaled(ScaledBlit),
+}
+
+/// Result of writing a 2D class method
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlitWrite {
+    /// Not a method of the 2D classes
+    Unknown,
+    /// A register was set
+    Register,
+    /// The method starts a copy
+    Blit(BlitCommand),
+}
+
+/// Registers of the 2D classes
+#[derive(Debug, Clone, Default)]
+pub struct BlitState {
+    // NV0039
+    pub buffer_in_dma: u32,
+    pub buffer_out_dma: u32,
+    pub offset_in: u32,
+    pub offset_out: u32,
+    pub pitch_in: u32,
+    pub pitch_out: u32,
+    pub line_length: u32,
+    pub line_count: u32,
+    pub line_format: u32,
+
+    // NV3062
+    pub surface_src_dma: u32,
+    pub surface_dst_dma: u32,
+    pub surface_format: u32,
+    pub surface_pitch: u32,
+    pub surface_src_offset: u32,
+    pub surface_dst_offset: u32,
+
+    // NV309E
+    pub swizzle_dma: u32,
+    pub swizzle_format: u32,
+    pub swizzle_offset: u32,
+
+    // NV3089
+    pub image_dma: u32,
+    pub context_surface: u32,
+    pub color_conversion: u32,
+    pub color_format: u32,
+    pub operation: u32,
+    pub clip_point: u32,
+    pub clip_size: u32,
+    pub out_point: u32,
+    pub out_size: u32,
+    pub ds_dx: u32,
+    pub dt_dy: u32,
+    pub in_size: u32,
+    pub in_format: u32,
+    pub in_offset: u32,
+}
+
+impl BlitState {
+    /// Create the registers with buffers in local memory
+    pub fn new() -> Self {
+        Self {
+            buffer_in_dma: CONTEXT_DMA_LOCAL,
+            buffer_out_dma: CONTEXT_DMA_LOCAL,
+            surface_src_dma: CONTEXT_DMA_LOCAL,
+            surface_dst_dma: CONTEXT_DMA_LOCAL,
+            swizzle_dma: CONTEXT_DMA_LOCAL,
+            image_dma: CONTEXT_DMA_LOCAL,
+            context_surface: CONTEXT_SURFACE_2D,
+            ..Default::default()
+        }
+    }
+
+    /// Apply a 2D class method
+    pub fn write(&mut self, method: u32, data: u32) -> BlitWrite {
+        match method {
+            NV0039_SET_CONTEXT_DMA_BUFFER_IN => self.buffer_in_dma = data,
+            NV0039_SET_CONTEXT_DMA_BUFFER_OUT => self.buffer_out_dma = data,
+            NV0039_OFFSET_IN => self.offset_in = data,
+            NV0039_OFFSET_OUT => self.offset_out = data,
+            NV0039_PITCH_IN => self.pitch_in = data,
+            NV0039_PITCH_OUT => self.pitch_out = data,
+            NV0039_LINE_LENGTH_IN => self.line_length = data,
+            NV0039_LINE_COUNT => self.line_count = data,
+            NV0039_FORMAT => self.line_format = data,
+            NV0039_BUFFER_NOTIFY => return BlitWrite::Blit(BlitCommand::Lines(self.line_copy())),
+
+            NV3062_SET_CONTEXT_DMA_IMAGE_SOURCE => self.surface_src_dma = data,
+            NV3062_SET_CONTEXT_DMA_IMAGE_DESTIN => self.surface_dst_dma = data,
+            NV3062_SET_COLOR_FORMAT => self.surface_format = data,
+            NV3062_SET_PITCH => self.surface_pitch = data,
+            NV3062_SET_OFFSET_SOURCE => self.surface_src_offset = data,
+            NV3062_SET_OFFSET_DESTIN => self.surface_dst_offset = data,
+
+            NV309E_SET_CONTEXT_DMA_IMAGE => self.swizzle_dma = data,
+            NV309E_SET_FORMAT => self.swizzle_format = data,
+            NV309E_SET_OFFSET => self.swizzle_offset = data,
+
+            NV3089_SET_CONTEXT_DMA_IMAGE => self.image_dma = data,
+            NV3089_SET_CONTEXT_SURFACE => self.context_surface = data,
+            NV3089_SET_COLOR_CONVERSION => self.color_conversion = data,
+            NV3089_SET_COLOR_FORMAT => self.color_format = data,
+            NV3089_SET_OPERATION => self.operation = data,
+            NV3089_CLIP_POINT => self.clip_point = data,
+            NV3089_CLIP_SIZE => self.clip_size = data,
+            NV3089_IMAGE_OUT_POINT => self.out_point = data,
+            NV3089_IMAGE_OUT_SIZE => self.out_size = data,
+            NV3089_DS_DX => self.ds_dx = data,
+            NV3089_DT_DY => self.dt_dy = data,
+            NV3089_IMAGE_IN_SIZE => self.in_size = data,
+            NV3089_IMAGE_IN_FORMAT => self.in_format = data,
+            NV3089_IMAGE_IN_OFFSET => self.in_offset = data,
+            NV3089_IMAGE_IN => return BlitWrite::Blit(BlitCommand::Scaled(self.scaled_blit(data))),
+            _ => return BlitWrite::Unknown,
+        }
+        BlitWrite::Register
+    }
+
+    /// Line copy NV0039_BUFFER_NOTIFY starts
+    fn line_copy(&self) -> LineCopy {
+        LineCopy {
+            src_dma: self.buffer_in_dma,
+            src_offset: self.offset_in,
+            src_pitch: self.pitch_in,
+            dst_dma: self.buffer_out_dma,
+            dst_offset: self.offset_out,
+            dst_pitch: self.pitch_out,
+            line_length: self.line_length,
+            line_count: self.line_count,
+        }
+    }
+
+    /// Scaled blit NV3089_IMAGE_IN starts from the source point in `data`
+    fn scaled_blit(&self, data: u32) -> ScaledBlit {
+        let dst = if self.context_surface == CONTEXT_SWIZZLE_2D {
+            BlitSurface::Swizzled {
+                dma: self.swizzle_dma,
+                offset: self.swizzle_offset,
+                format: self.swizzle_format & 0xFFFF,
+                log2_width: (self.swizzle_format >> 16) & 0xFF,
+                log2_height: self.swizzle_format >> 24,
+            }
+        } else {
+            BlitSurface::Linear {
+                dma: self.surface_dst_dma,
+                offset: self.surface_dst_offset,
+                pitch: self.surface_pitch >> 16,
+                format: self.surface_format,
+            }
+        };
+        ScaledBlit {
+            src_dma: self.image_dma,
+            src_offset: self.in_offset,
+            src_pitch: self.in_format & 0xFFFF,
+            src_format: self.color_format,
+            src_width: self.in_size & 0xFFFF,
+            src_height: self.in_size >> 16,
+            src_x: data & 0xFFFF,
+            src_y: data >> 16,
+            ds_dx: self.ds_dx,
+            dt_dy: self.dt_dy,
+            out_rect: rect(self.out_point, self.out_size),
+            clip_rect: rect(self.clip_point, self.clip_size),
+            dst,
+        }
+    }
+
+    /// Save the registers for a savestate
+    pub fn save_state(&self, w: &mut StateWriter) {
+        for value in self.clone().registers_mut() {
+            w.u32(*value);
+        }
+    }
+
+    /// Restore registers written by [`BlitState::save_state`]
+    pub fn load_state(&mut self, r: &mut StateReader) -> io::Result<()> {
+        for value in self.registers_mut() {
+            *value = r.u32()?;
+        }
+        Ok(())
+    }
+
+    /// Every register, in savestate order
+    fn registers_mut(&mut self) -> [&mut u32; 32] {
+        [
+            &mut self.buffer_in_dma, &mut self.buffer_out_dma, &mut self.offset_in, &mut self.offset_out,
+            &mut self.pitch_in, &mut self.pitch_out, &mut self.line_length, &mut self.line_count,
+            &mut self.line_format, &mut self.surface_src_dma, &mut self.surface_dst_dma, &mut self.surface_format,
+            &mut self.surface_pitch, &mut self.surface_src_offset, &mut self.surface_dst_offset,
+            &mut self.swizzle_dma, &mut self.swizzle_format, &mut self.swizzle_offset, &mut self.image_dma,
+            &mut self.context_surface, &mut self.color_conversion, &mut self.color_format, &mut self.operation,
+            &mut self.clip_point, &mut self.clip_size, &mut self.out_point, &mut self.out_size, &mut self.ds_dx,
+            &mut self.dt_dy, &mut self.in_size, &mut self.in_format, &mut self.in_offset,
+        ]
+    }
+}
+
+/// Rectangle from a point register (signed x low, y high) and a size register
+fn rect(point: u32, size: u32) -> [i32; 4] {
+    [
+        i32::from(point as i16),
+        i32::from((point >> 16) as i16),
+        (size & 0xFFFF) as i32,
+        (size >> 16) as i32,
+    ]
+}
+
+/// Bytes per pixel of a surface format
+pub fn surface_bytes_per_pixel(format: u32) -> Option<u32> {
+    match format {
+        SURFACE_FORMAT_R5G6B5 => Some(2),
+        SURFACE_FORMAT_A8R8G8B8 | SURFACE_FORMAT_Y32 => Some(4),
+        _ => None,
+    }
+}
+
+/// Bytes per pixel of an NV3089 source format
+pub fn scale_bytes_per_pixel(format: u32) -> Option<u32> {
+    match format {
+        SCALE_FORMAT_A1R5G5B5 | SCALE_FORMAT_X1R5G5B5 | SCALE_FORMAT_R5G6B5 => Some(2),
+        SCALE_FORMAT_A8R8G8B8 | SCALE_FORMAT_X8R8G8B8 | SCALE_FORMAT_A8B8G8R8 | SCALE_FORMAT_X8B8G8R8 => Some(4),
+        _ => None,
+    }
+}
+
+/// Expand a 5 or 6 bit channel to 8 bits
+fn expand(value: u32, bits: u32) -> u32 {
+    (value << (8 - bits)) | (value >> (2 * bits - 8))
+}
+
+/// Big-endian source pixel as A8R8G8B8
+fn read_pixel(format: u32, data: &[u8]) -> u32 {
+    let half = || u32::from(u16::from_be_bytes([data[0], data[1]]));
+    let word = || u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+    let swap_rb = |v: u32| (v & 0xFF00_FF00) | ((v >> 16) & 0xFF) | ((v & 0xFF) << 16);
+    match format {
+        SCALE_FORMAT_R5G6B5 => {
+            let v = half();
+            0xFF00_0000 | expand(v >> 11, 5) << 16 | expand((v >> 5) & 0x3F, 6) << 8 | expand(v & 0x1F, 5)
+        }
+        SCALE_FORMAT_A1R5G5B5 | SCALE_FORMAT_X1R5G5B5 => {
+            let v = half();
+            let alpha = if format == SCALE_FORMAT_X1R5G5B5 || v & 0x8000 != 0 { 0xFF00_0000 } else { 0 };
+            alpha | expand((v >> 10) & 0x1F, 5) << 16 | expand((v >> 5) & 0x1F, 5) << 8 | expand(v & 0x1F, 5)
+        }
+        SCALE_FORMAT_X8R8G8B8 => word() | 0xFF00_0000,
+        SCALE_FORMAT_A8B8G8R8 => swap_rb(word()),
+        SCALE_FORMAT_X8B8G8R8 => swap_rb(word()) | 0xFF00_0000,
+        _ => word(),
+    }
+}
+
+/// Store an A8R8G8B8 pixel big-endian in a surface format
+fn write_pixel(format: u32, argb: u32, out: &mut [u8]) {
+    if format == SURFACE_FORMAT_R5G6B5 {
+        let v = ((argb >> 8) & 0xF800) | ((argb >> 5) & 0x07E0) | ((argb >> 3) & 0x001F);
+        out[..2].copy_from_slice(&(v as u16).to_be_bytes());
+    } else {
+        out[..4].copy_from_slice(&argb.to_be_bytes());
+    }
+}
+
+/// Run a copy on local memory, returning the range of local memory written
+pub fn run(command: &BlitCommand, memory: &MemoryManager) -> Result<Range<u32>, String> {
+    match command {
+        BlitCommand::Lines(copy) => copy_lines(copy, memory),
+        BlitCommand::Scaled(blit) => scale_image(blit, memory),
+    }
+}
+
+/// Check that a buffer is in local memory
+fn require_local(dma: u32) -> Result<(), String> {
+    match dma {
+        CONTEXT_DMA_LOCAL => Ok(()),
+        CONTEXT_DMA_MAIN => Err("buffers in main memory are not mapped for the RSX".to_string()),
+        _ => Err(format!("unknown context DMA 0x{:08x}", dma)),
+    }
+}
+
+/// Bytes spanned by `lines` lines of `length` bytes, `pitch` apart
+fn span(pitch: u32, length: u32, lines: u32) -> Option<u32> {
+    pitch.checked_mul(lines - 1)?.checked_add(length)
+}
+
+fn copy_lines(copy: &LineCopy, memory: &MemoryManager) -> Result<Range<u32>, String> {
+    require_local(copy.src_dma)?;
+    require_local(copy.dst_dma)?;
+    if copy.line_length == 0 || copy.line_count == 0 {
+        return Ok(copy.dst_offset..copy.dst_offset);
+    }
+    // A zero pitch packs the lines
+    let src_pitch = if copy.src_pitch == 0 { copy.line_length } else { copy.src_pitch };
+    let dst_pitch = if copy.dst_pitch == 0 { copy.line_length } else { copy.dst_pitch };
+    let src_size = span(src_pitch, copy.line_length, copy.line_count).ok_or("source too large")?;
+    let dst_size = span(dst_pitch, copy.line_length, copy.line_count).ok_or("destination too large")?;
+
+    // Reading everything first keeps overlapping copies correct
+    let src = memory.read_rsx_bytes(copy.src_offset, src_size).map_err(|e| e.to_string())?;
+    if src_pitch == copy.line_length && dst_pitch == copy.line_length {
+        memory.write_rsx_bytes(copy.dst_offset, &src).map_err(|e| e.to_string())?;
+    } else {
+        let mut dst = memory.read_rsx_bytes(copy.dst_offset, dst_size).map_err(|e| e.to_string())?;
+        let length = copy.line_length as usize;
+        for line in 0..copy.line_count as usize {
+            let (from, to) = (line * src_pitch as usize, line * dst_pitch as usize);
+            dst[to..to + length].copy_from_slice(&src[from..from + length]);
+        }
+        memory.write_rsx_bytes(copy.dst_offset, &dst).map_err(|e| e.to_string())?;
+    }
+    Ok(copy.dst_offset..copy.dst_offset + dst_size)
+}
+
+fn scale_image(blit: &ScaledBlit, memory: &MemoryManager) -> Result<Range<u32>, String> {
+    require_local(blit.src_dma)?;
+    require_local(blit.dst.dma())?;
+    let src_bpp = scale_bytes_per_pixel(blit.src_format)
+        .ok_or_else(|| format!("unsupported source format {}", blit.src_format))?;
+    let dst_bpp = surface_bytes_per_pixel(blit.dst.format())
+        .ok_or_else(|| format!("unsupported surface format {}", blit.dst.format()))?;
+
+    // Pixels both inside the output rectangle and the clip rectangle, and on the surface
+    let [out_x, out_y, out_w, out_h] = blit.out_rect;
+    let [clip_x, clip_y, clip_w, clip_h] = blit.clip_rect;
+    let (mut x0, mut y0) = (out_x.max(clip_x).max(0), out_y.max(clip_y).max(0));
+    let (mut x1, mut y1) = ((out_x + out_w).min(clip_x + clip_w), (out_y + out_h).min(clip_y + clip_h));
+    if let BlitSurface::Swizzled { log2_width, log2_height, .. } = blit.dst {
+        if log2_width > 11 || log2_height > 11 {
+            return Err("swizzled surface too large".to_string());
+        }
+        x1 = x1.min(1 << log2_width);
+        y1 = y1.min(1 << log2_height);
+    }
+    x0 = x0.min(x1);
+    y0 = y0.min(y1);
+    if x0 == x1 || y0 == y1 || blit.src_width == 0 || blit.src_height == 0 {
+        return Ok(0..0);
+    }
+
+    let src_pitch = if blit.src_pitch == 0 { blit.src_width * src_bpp } else { blit.src_pitch };
+    let src_size = span(src_pitch, blit.src_width * src_bpp, blit.src_height).ok_or("source too large")?;
+    let src = memory.read_rsx_bytes(blit.src_offset, src_size).map_err(|e| e.to_string())?;
+
+    // Destination bytes covering the pixels written
+    let (dst_start, dst_size) = match blit.dst {
+        BlitSurface::Linear { offset, pitch, .. } => {
+            let pitch = if pitch == 0 { x1 as u32 * dst_bpp } else { pitch };
+            let start = offset + y0 as u32 * pitch + x0 as u32 * dst_bpp;
+            (start, span(pitch, (x1 - x0) as u32 * dst_bpp, (y1 - y0) as u32).ok_or("destination too large")?)
+        }
+        BlitSurface::Swizzled { offset, log2_width, log2_height, .. } => {
+            (offset, (1u32 << (log2_width + log2_height)) * dst_bpp)
+        }
+    };
+    let mut dst = memory.read_rsx_bytes(dst_start, dst_size).map_err(|e| e.to_string())?;
+
+    for y in y0..y1 {
+        // Nearest source texel, with the 12.4 start point widened to 12.20
+        let v = (u64::from(blit.src_y) << 16) + (y - out_y) as u64 * u64::from(blit.dt_dy);
+        let sy = ((v >> 20) as u32).min(blit.src_height - 1);
+        for x in x0..x1 {
+            let u = (u64::from(blit.src_x) << 16) + (x - out_x) as u64 * u64::from(blit.ds_dx);
+            let sx = ((u >> 20) as u32).min(blit.src_width - 1);
+            let from = (sy * src_pitch + sx * src_bpp) as usize;
+            let argb = read_pixel(blit.src_format, &src[from..from + src_bpp as usize]);
+
+            let to = match blit.dst {
+                BlitSurface::Linear { pitch, .. } => {
+                    let pitch = if pitch == 0 { x1 as u32 * dst_bpp } else { pitch };
+                    ((y - y0) as u32 * pitch + (x - x0) as u32 * dst_bpp) as usize
+                }
+                BlitSurface::Swizzled { log2_width, log2_height, .. } => {
+                    (swizzle_index(x as u32, y as u32, 1 << log2_width, 1 << log2_height) * dst_bpp) as usize
+                }
+            };
+            write_pixel(blit.dst.format(), argb, &mut dst[to..]);
+        }
+    }
+    memory.write_rsx_bytes(dst_start, &dst).map_err(|e| e.to_string())?;
+    Ok(dst_start..dst_start + dst_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_copy() {
+        let memory = MemoryManager::new().unwrap();
+        memory.write_rsx_bytes(0x100, &[1, 2, 3, 0, 4, 5, 6, 0]).unwrap();
+        let mut state = BlitState::new();
+        for (method, data) in [
+            (NV0039_OFFSET_IN, 0x100),
+            (NV0039_OFFSET_OUT, 0x200),
+            (NV0039_PITCH_IN, 4),
+            (NV0039_PITCH_OUT, 3),
+            (NV0039_LINE_LENGTH_IN, 3),
+            (NV0039_LINE_COUNT, 2),
+        ] {
+            assert_eq!(state.write(method, data), BlitWrite::Register);
+        }
+        let BlitWrite::Blit(command) = state.write(NV0039_BUFFER_NOTIFY, 0) else {
+            panic!("no copy started");
+        };
+        assert_eq!(run(&command, &memory).unwrap(), 0x200..0x206);
+        assert_eq!(memory.read_rsx_bytes(0x200, 6).unwrap(), [1, 2, 3, 4, 5, 6]);
+
+        state.write(NV0039_SET_CONTEXT_DMA_BUFFER_IN, CONTEXT_DMA_MAIN);
+        let BlitWrite::Blit(command) = state.write(NV0039_BUFFER_NOTIFY, 0) else {
+            panic!("no copy started");
+        };
+        assert!(run(&command, &memory).is_err());
+        assert_eq!(state.write(0x2FFC, 0), BlitWrite::Unknown);
+    }
+
+    #[test]
+    fn test_scaled_blit_to_swizzled_surface() {
+        let memory = MemoryManager::new().unwrap();
+        // 2x1 R5G6B5 image: red, blue
+        memory.write_rsx_bytes(0x100, &[0xF8, 0x00, 0x00, 0x1F]).unwrap();
+        let mut state = BlitState::new();
+        for (method, data) in [
+            (NV3089_SET_CONTEXT_SURFACE, CONTEXT_SWIZZLE_2D),
+            (NV309E_SET_FORMAT, (1 << 24) | (2 << 16) | SURFACE_FORMAT_A8R8G8B8),
+            (NV309E_SET_OFFSET, 0x1000),
+            (NV3089_SET_COLOR_FORMAT, SCALE_FORMAT_R5G6B5),
+            (NV3089_CLIP_SIZE, (2 << 16) | 4),
+            (NV3089_IMAGE_OUT_SIZE, (2 << 16) | 4),
+            // Scale 2x1 up to 4x2
+            (NV3089_DS_DX, 1 << 19),
+            (NV3089_DT_DY, 1 << 19),
+            (NV3089_IMAGE_IN_SIZE, (1 << 16) | 2),
+            (NV3089_IMAGE_IN_FORMAT, 4),
+            (NV3089_IMAGE_IN_OFFSET, 0x100),
+        ] {
+            state.write(method, data);
+        }
+        let BlitWrite::Blit(command) = state.write(NV3089_IMAGE_IN, 0) else {
+            panic!("no blit started");
+        };
+        assert_eq!(run(&command, &memory).unwrap(), 0x1000..0x1020);
+
+        // Z-order: (0,0) (1,0) (0,1) (1,1) are red, the right half blue
+        let pixels = memory.read_rsx_bytes(0x1000, 32).unwrap();
+        let pixel = |i: usize| u32::from_be_bytes(pixels[i * 4..i * 4 + 4].try_into().unwrap());
+        assert_eq!([pixel(0), pixel(1), pixel(2), pixel(3)], [0xFFFF_0000; 4]);
+        assert_eq!([pixel(4), pixel(5), pixel(6), pixel(7)], [0xFF00_00FF; 4]);
+    }
+
+    #[test]
+    fn test_scaled_blit_clipped_to_linear_surface() {
+        let memory = MemoryManager::new().unwrap();
+        memory.write_rsx_bytes(0x100, &[0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88]).unwrap();
+        let mut state = BlitState::new();
+        for (method, data) in [
+            (NV3062_SET_COLOR_FORMAT, SURFACE_FORMAT_A8R8G8B8),
+            (NV3062_SET_PITCH, (16 << 16) | 8),
+            (NV3062_SET_OFFSET_DESTIN, 0x2000),
+            (NV3089_SET_COLOR_FORMAT, SCALE_FORMAT_A8B8G8R8),
+            (NV3089_CLIP_POINT, 1),
+            (NV3089_CLIP_SIZE, (1 << 16) | 1),
+            (NV3089_IMAGE_OUT_SIZE, (1 << 16) | 2),
+            (NV3089_DS_DX, 1 << 20),
+            (NV3089_DT_DY, 1 << 20),
+            (NV3089_IMAGE_IN_SIZE, (1 << 16) | 2),
+            (NV3089_IMAGE_IN_OFFSET, 0x100),
+        ] {
+            state.write(method, data);
+        }
+        let BlitWrite::Blit(command) = state.write(NV3089_IMAGE_IN, 0) else {
+            panic!("no blit started");
+        };
+
+        // Only the second pixel is inside the clip rectangle
+        assert_eq!(run(&command, &memory).unwrap(), 0x2004..0x2008);
+        assert_eq!(memory.read_rsx_bytes(0x2000, 8).unwrap(), [0, 0, 0, 0, 0x55, 0x88, 0x77, 0x66]);
+    }
+
+    #[test]
+    fn test_save_state_round_trip() {
+        let mut state = BlitState::new();
+        state.write(NV309E_SET_OFFSET, 0x1234);
+        state.write(NV3089_IMAGE_IN_OFFSET, 0x5678);
+
+        let mut w = StateWriter::new();
+        state.save_state(&mut w);
+        let data = w.into_bytes();
+        let mut loaded = BlitState::new();
+        let mut r = StateReader::new(&data);
+        loaded.load_state(&mut r).unwrap();
+        assert_eq!(r.remaining(), 0);
+        assert_eq!(loaded.swizzle_offset, 0x1234);
+        assert_eq!(loaded.in_offset, 0x5678);
+        assert_eq!(loaded.image_dma, CONTEXT_DMA_LOCAL);
+    }
+}
@@ -0,0 +1,283 @@
+//! Per-frame RSX counters for the profiler and performance overlay
+//!
+//! The RSX thread counts the work each frame hands to the backend: draw
+//! calls and the triangles they produce, textures uploaded for the first
+//! time, and whether the surfaces and shader pipelines a draw uses were
+//! already resident. Residency is tracked by the guest state that would key
+//! the backend's caches, so the numbers are the same on every backend.
+
+use crate::backend::PrimitiveType;
+use crate::state::RsxState;
+use crate::texture_decode::TEXTURE_ENABLE;
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::ops::AddAssign;
+
+/// Most distinct surfaces or textures tracked before the sets start over
+const MAX_RESIDENT: usize = 4096;
+
+/// Work done by the RSX in one frame
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RsxFrameCounters {
+    /// Draw calls issued to the backend
+    pub draw_calls: u64,
+    /// Vertices drawn
+    pub vertices: u64,
+    /// Triangles drawn, after splitting strips, fans, quads and polygons
+    pub triangles: u64,
+    /// Surface clears
+    pub clears: u64,
+    /// Textures uploaded because they were not resident
+    pub texture_uploads: u64,
+    /// Draws and clears whose render targets were already resident
+    pub surface_cache_hits: u64,
+    /// Draws and clears that created render targets
+    pub surface_cache_misses: u64,
+    /// Draws whose shader pipeline was already built
+    pub pipeline_cache_hits: u64,
+    /// Draws that built a shader pipeline
+    pub pipeline_cache_misses: u64,
+}
+
+impl RsxFrameCounters {
+    /// Share of surface lookups that hit, in percent
+    pub fn surface_hit_percent(&self) -> f32 {
+        hit_percent(self.surface_cache_hits, self.surface_cache_misses)
+    }
+
+    /// Share of pipeline lookups that hit, in percent
+    pub fn pipeline_hit_percent(&self) -> f32 {
+        hit_percent(self.pipeline_cache_hits, self.pipeline_cache_misses)
+    }
+}
+
+impl AddAssign for RsxFrameCounters {
+    fn add_assign(&mut self, other: Self) {
+        self.draw_calls += other.draw_calls;
+        self.vertices += other.vertices;
+        self.triangles += other.triangles;
+        self.clears += other.clears;
+        self.texture_uploads += other.texture_uploads;
+        self.surface_cache_hits += other.surface_cache_hits;
+        self.surface_cache_misses += other.surface_cache_misses;
+        self.pipeline_cache_hits += other.pipeline_cache_hits;
+        self.pipeline_cache_misses += other.pipeline_cache_misses;
+    }
+}
+
+fn hit_percent(hits: u64, misses: u64) -> f32 {
+    match hits + misses {
+        0 => 100.0,
+        total => hits as f32 / total as f32 * 100.0,
+    }
+}
+
+/// Triangles `vertices` vertices of `primitive` produce
+pub fn triangle_count(primitive: PrimitiveType, vertices: u32) -> u64 {
+    let vertices = u64::from(vertices);
+    match primitive {
+        PrimitiveType::Points | PrimitiveType::Lines | PrimitiveType::LineLoop | PrimitiveType::LineStrip => 0,
+        PrimitiveType::Triangles => vertices / 3,
+        PrimitiveType::TriangleStrip | PrimitiveType::TriangleFan | PrimitiveType::Polygon => {
+            vertices.saturating_sub(2)
+        }
+        PrimitiveType::Quads => vertices / 4 * 2,
+        PrimitiveType::QuadStrip => vertices.saturating_sub(2) / 2 * 2,
+    }
+}
+
+/// Color buffers enabled by NV4097_SET_SURFACE_COLOR_TARGET
+fn color_targets(target: u32) -> &'static [usize] {
+    match target {
+        0x01 => &[0],
+        0x02 => &[1],
+        0x13 => &[0, 1],
+        0x17 => &[0, 1, 2],
+        0x1F => &[0, 1, 2, 3],
+        _ => &[],
+    }
+}
+
+/// Keys of the resources the backend has seen
+#[derive(Debug)]
+struct ResidentSet<K> {
+    keys: HashSet<K>,
+}
+
+impl<K: Eq + Hash> ResidentSet<K> {
+    fn new() -> Self {
+        Self { keys: HashSet::new() }
+    }
+
+    /// Mark `key` resident, returning whether it already was
+    fn touch(&mut self, key: K) -> bool {
+        if self.keys.contains(&key) {
+            return true;
+        }
+        if self.keys.len() == MAX_RESIDENT {
+            self.keys.clear();
+        }
+        self.keys.insert(key);
+        false
+    }
+}
+
+/// Render target key: offset, pitch, surface format and clip size
+type SurfaceKey = (u32, u32, u32, u16, u16);
+
+/// Texture key: offset, format, control and size
+type TextureKey = (u32, u32, u32, u32);
+
+/// Counts RSX work per frame
+#[derive(Debug)]
+pub struct RsxCounters {
+    /// Counters of the frame being built
+    current: RsxFrameCounters,
+    /// Counters of the last finished frame
+    last: RsxFrameCounters,
+    /// Counters since the thread was created
+    total: RsxFrameCounters,
+    surfaces: ResidentSet<SurfaceKey>,
+    textures: ResidentSet<TextureKey>,
+}
+
+impl RsxCounters {
+    /// Create counters with nothing resident
+    pub fn new() -> Self {
+        Self {
+            current: RsxFrameCounters::default(),
+            last: RsxFrameCounters::default(),
+            total: RsxFrameCounters::default(),
+            surfaces: ResidentSet::new(),
+            textures: ResidentSet::new(),
+        }
+    }
+
+    /// Count a clear of the bound render targets
+    pub fn record_clear(&mut self, state: &RsxState) {
+        self.current.clears += 1;
+        self.record_surfaces(state);
+    }
+
+    /// Count a draw of `count` vertices, `pipeline_hit` telling if its shaders were already built
+    pub fn record_draw(&mut self, state: &RsxState, primitive: PrimitiveType, count: u32, pipeline_hit: bool) {
+        self.current.draw_calls += 1;
+        self.current.vertices += u64::from(count);
+        self.current.triangles += triangle_count(primitive, count);
+        if pipeline_hit {
+            self.current.pipeline_cache_hits += 1;
+        } else {
+            self.current.pipeline_cache_misses += 1;
+        }
+        self.record_surfaces(state);
+        for unit in 0..state.texture_offset.len() {
+            if state.texture_control[unit] & TEXTURE_ENABLE == 0 {
+                continue;
+            }
+            let key = (
+                state.texture_offset[unit],
+                state.texture_format[unit],
+                state.texture_control[unit],
+                state.texture_image_rect[unit],
+            );
+            if !self.textures.touch(key) {
+                self.current.texture_uploads += 1;
+            }
+        }
+    }
+
+    fn record_surfaces(&mut self, state: &RsxState) {
+        let size = (state.surface_clip_width, state.surface_clip_height);
+        let mut hit = true;
+        for &index in color_targets(state.surface_color_target) {
+            let offset = state.surface_offset_color[index];
+            hit &= self.surfaces.touch((offset, state.surface_pitch[index], state.surface_format, size.0, size.1));
+        }
+        // A pitch no color buffer has keeps the depth buffer apart from color buffers at its offset
+        let depth = (state.surface_offset_depth, u32::MAX, state.surface_format, size.0, size.1);
+        hit &= self.surfaces.touch(depth);
+        if hit {
+            self.current.surface_cache_hits += 1;
+        } else {
+            self.current.surface_cache_misses += 1;
+        }
+    }
+
+    /// Finish the frame, making its counters the last frame's
+    pub fn end_frame(&mut self) {
+        self.total += self.current;
+        self.last = std::mem::take(&mut self.current);
+    }
+
+    /// Counters of the last finished frame
+    pub fn last_frame(&self) -> RsxFrameCounters {
+        self.last
+    }
+
+    /// Counters since the thread was created, including the current frame
+    pub fn total(&self) -> RsxFrameCounters {
+        let mut total = self.total;
+        total += self.current;
+        total
+    }
+
+    /// Forget resident surfaces and textures, e.g. after the backend was rebuilt
+    pub fn invalidate(&mut self) {
+        self.surfaces = ResidentSet::new();
+        self.textures = ResidentSet::new();
+    }
+}
+
+impl Default for RsxCounters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_triangle_count() {
+        assert_eq!(triangle_count(PrimitiveType::Triangles, 9), 3);
+        assert_eq!(triangle_count(PrimitiveType::TriangleStrip, 6), 4);
+        assert_eq!(triangle_count(PrimitiveType::TriangleFan, 1), 0);
+        assert_eq!(triangle_count(PrimitiveType::Quads, 8), 4);
+        assert_eq!(triangle_count(PrimitiveType::QuadStrip, 6), 4);
+        assert_eq!(triangle_count(PrimitiveType::Lines, 6), 0);
+    }
+
+    #[test]
+    fn test_frame_counters() {
+        let mut counters = RsxCounters::new();
+        let mut state = RsxState::new();
+        state.surface_color_target = 0x01;
+        state.texture_control[0] = TEXTURE_ENABLE;
+        state.texture_offset[0] = 0x1000;
+
+        counters.record_clear(&state);
+        counters.record_draw(&state, PrimitiveType::Triangles, 6, false);
+        counters.record_draw(&state, PrimitiveType::Triangles, 3, true);
+        counters.end_frame();
+        let frame = counters.last_frame();
+        assert_eq!((frame.draw_calls, frame.vertices, frame.triangles, frame.clears), (2, 9, 3, 1));
+        // The texture and surfaces are created once
+        assert_eq!(frame.texture_uploads, 1);
+        assert_eq!((frame.surface_cache_hits, frame.surface_cache_misses), (2, 1));
+        assert_eq!((frame.pipeline_cache_hits, frame.pipeline_cache_misses), (1, 1));
+        assert!((frame.pipeline_hit_percent() - 50.0).abs() < 0.01);
+
+        // A new render target misses
+        state.surface_offset_color[0] = 0x40_0000;
+        counters.record_draw(&state, PrimitiveType::Quads, 4, true);
+        counters.end_frame();
+        let frame = counters.last_frame();
+        assert_eq!((frame.surface_cache_misses, frame.texture_uploads, frame.triangles), (1, 0, 2));
+        assert_eq!(counters.total().draw_calls, 3);
+
+        counters.invalidate();
+        counters.record_draw(&state, PrimitiveType::Quads, 4, true);
+        assert_eq!(counters.total().texture_uploads, 2);
+    }
+}
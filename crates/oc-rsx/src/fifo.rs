@@ -1,6 +1,8 @@
 //! RSX command FIFO
 
+use oc_core::savestate::{StateReader, StateWriter};
 use std::collections::VecDeque;
+use std::io;
 
 /// RSX command
 #[derive(Debug, Clone, Copy)]
@@ -49,6 +51,11 @@ impl CommandFifo {
         cmd
     }
 
+    /// Next command without removing it
+    pub fn peek(&self) -> Option<RsxCommand> {
+        self.queue.front().copied()
+    }
+
     /// Check if FIFO is empty
     pub fn is_empty(&self) -> bool {
         self.queue.is_empty()
@@ -78,6 +85,33 @@ impl CommandFifo {
     pub fn get_reference(&self) -> u32 {
         self.reference
     }
+
+    /// Save queued commands and the pointers
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.count(self.queue.len());
+        for cmd in &self.queue {
+            w.u32(cmd.method);
+            w.u32(cmd.data);
+        }
+        w.u32(self.get);
+        w.u32(self.put);
+        w.u32(self.reference);
+    }
+
+    /// Restore state written by [`CommandFifo::save_state`]
+    pub fn load_state(&mut self, r: &mut StateReader) -> io::Result<()> {
+        self.queue.clear();
+        for _ in 0..r.count()? {
+            self.queue.push_back(RsxCommand {
+                method: r.u32()?,
+                data: r.u32()?,
+            });
+        }
+        self.get = r.u32()?;
+        self.put = r.u32()?;
+        self.reference = r.u32()?;
+        Ok(())
+    }
 }
 
 impl Default for CommandFifo {
@@ -100,6 +134,9 @@ mod tests {
         assert!(!fifo.is_empty());
         assert_eq!(fifo.len(), 1);
 
+        assert_eq!(fifo.peek().unwrap().method, 0x100);
+        assert_eq!(fifo.len(), 1);
+
         let cmd = fifo.pop().unwrap();
         assert_eq!(cmd.method, 0x100);
         assert_eq!(cmd.data, 0x1234);
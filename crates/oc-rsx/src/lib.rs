@@ -3,18 +3,25 @@
 //! The RSX is based on NVIDIA G70/G71 architecture.
 
 pub mod backend;
+pub mod blit;
 pub mod buffer;
+pub mod counters;
 pub mod fifo;
 pub mod methods;
 pub mod postprocess;
+pub mod report;
 pub mod scaling;
 pub mod shader;
+pub mod spirv_disasm;
 pub mod state;
 pub mod texture;
+pub mod texture_decode;
+pub mod texture_pack;
 pub mod thread;
 pub mod timing;
 pub mod vertex;
 
 pub use backend::FramebufferData;
+pub use counters::RsxFrameCounters;
 pub use state::RsxState;
 pub use thread::RsxThread;
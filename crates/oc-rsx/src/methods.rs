@@ -3,6 +3,7 @@
 //! This module defines constants and handlers for RSX GPU commands.
 //! The NV4097 is the command set for the RSX GPU based on NVIDIA G70/G71.
 
+use crate::report::REPORT_OFFSET_MASK;
 use crate::state::RsxState;
 
 // Surface and render target methods
@@ -93,11 +94,19 @@ pub const NV4097_SET_RESTART_INDEX: u32 = 0x0DF0;
 pub const NV4097_SET_ZPASS_PIXEL_COUNT_ENABLE: u32 = 0x1DA0;
 pub const NV4097_SET_REPORT_SEMAPHORE_OFFSET: u32 = 0x1D00;
 
+// Report and conditional render methods
+pub const NV4097_CLEAR_REPORT_VALUE: u32 = 0x17C8;
+pub const NV4097_GET_REPORT: u32 = 0x1800;
+pub const NV4097_SET_ZCULL_STATS_ENABLE: u32 = 0x1D84;
+pub const NV4097_SET_RENDER_ENABLE: u32 = 0x1E98;
+
 // Vertex program methods
 pub const NV4097_SET_VERTEX_PROGRAM_START_SLOT: u32 = 0x0480;
 pub const NV4097_SET_VERTEX_PROGRAM_LOAD_SLOT: u32 = 0x0484;
 pub const NV4097_SET_VERTEX_ATTRIB_INPUT_MASK: u32 = 0x1640;
 pub const NV4097_SET_VERTEX_ATTRIB_OUTPUT_MASK: u32 = 0x1644;
+pub const NV4097_SET_TRANSFORM_CONSTANT_LOAD: u32 = 0x1EFC;
+pub const NV4097_SET_TRANSFORM_CONSTANT: u32 = 0x1F00;
 
 // Fragment program methods
 pub const NV4097_SET_SHADER_PROGRAM: u32 = 0x0848;
@@ -117,13 +126,14 @@ pub const NV4097_SET_TEXTURE_OFFSET: u32 = 0x1A00;
 pub const NV4097_SET_TEXTURE_FORMAT: u32 = 0x1A04;
 pub const NV4097_SET_TEXTURE_CONTROL0: u32 = 0x1A08;
 pub const NV4097_SET_TEXTURE_FILTER: u32 = 0x1A0C;
+pub const NV4097_SET_TEXTURE_IMAGE_RECT: u32 = 0x1A18;
 
 /// Handler for NV4097 methods
 pub struct MethodHandler;
 
 impl MethodHandler {
-    /// Execute a method
-    pub fn execute(method: u32, data: u32, state: &mut RsxState) {
+    /// Execute a method, returning false if it is not implemented
+    pub fn execute(method: u32, data: u32, state: &mut RsxState) -> bool {
         match method {
             // Surface format and targets
             NV4097_SET_SURFACE_FORMAT => {
@@ -135,9 +145,27 @@ impl MethodHandler {
             NV4097_SET_CONTEXT_DMA_COLOR_A => {
                 state.context_dma_color[0] = data;
             }
+            NV4097_SET_CONTEXT_DMA_COLOR_B => {
+                state.context_dma_color[1] = data;
+            }
+            NV4097_SET_CONTEXT_DMA_COLOR_C => {
+                state.context_dma_color[2] = data;
+            }
+            NV4097_SET_CONTEXT_DMA_COLOR_D => {
+                state.context_dma_color[3] = data;
+            }
             NV4097_SET_SURFACE_COLOR_AOFFSET => {
                 state.surface_offset_color[0] = data;
             }
+            NV4097_SET_SURFACE_COLOR_BOFFSET => {
+                state.surface_offset_color[1] = data;
+            }
+            NV4097_SET_SURFACE_COLOR_COFFSET => {
+                state.surface_offset_color[2] = data;
+            }
+            NV4097_SET_SURFACE_COLOR_DOFFSET => {
+                state.surface_offset_color[3] = data;
+            }
             NV4097_SET_SURFACE_PITCH_A => {
                 state.surface_pitch[0] = data;
             }
@@ -314,7 +342,27 @@ impl MethodHandler {
                 state.occlusion_query_offset = data;
             }
 
+            // Reports and conditional rendering
+            NV4097_SET_ZCULL_STATS_ENABLE => {
+                state.zcull_stats_enable = data != 0;
+            }
+            NV4097_SET_RENDER_ENABLE => {
+                // Mode 1 renders unconditionally, mode 2 only while the report
+                // at the offset is nonzero
+                match data >> 24 {
+                    1 => state.conditional_render_enable = false,
+                    2 => {
+                        state.conditional_render_enable = true;
+                        state.conditional_render_offset = data & REPORT_OFFSET_MASK;
+                    }
+                    _ => return false,
+                }
+            }
+
             // Shader programs
+            NV4097_SET_VERTEX_PROGRAM_START_SLOT => {
+                state.vertex_program_addr = data;
+            }
             NV4097_SET_SHADER_PROGRAM => {
                 state.fragment_program_addr = data;
             }
@@ -324,6 +372,18 @@ impl MethodHandler {
             NV4097_SET_VERTEX_ATTRIB_OUTPUT_MASK => {
                 state.vertex_attrib_output_mask = data;
             }
+            NV4097_SET_TRANSFORM_CONSTANT_LOAD => {
+                state.transform_constant_load = data;
+            }
+            // Up to 8 constants per packet, starting at the load register
+            NV4097_SET_TRANSFORM_CONSTANT..=0x1F7C => {
+                let word = ((method - NV4097_SET_TRANSFORM_CONSTANT) / 4) as usize;
+                let index = state.transform_constant_load as usize + word / 4;
+                match state.transform_constants.get_mut(index) {
+                    Some(constant) => constant[word % 4] = f32::from_bits(data),
+                    None => return false,
+                }
+            }
 
             // Draw commands - These need special handling
             NV4097_DRAW_ARRAYS | NV4097_DRAW_INDEX_ARRAY | NV4097_INLINE_ARRAY => {
@@ -382,12 +442,20 @@ impl MethodHandler {
                     if index < state.texture_filter.len() {
                         state.texture_filter[index] = data;
                     }
+                } else if (NV4097_SET_TEXTURE_IMAGE_RECT..NV4097_SET_TEXTURE_IMAGE_RECT + (16 * 0x20)).contains(&method)
+                    && (method - NV4097_SET_TEXTURE_IMAGE_RECT) & 0x1F == 0 {
+                    let index = ((method - NV4097_SET_TEXTURE_IMAGE_RECT) / 0x20) as usize;
+                    if index < state.texture_image_rect.len() {
+                        state.texture_image_rect[index] = data;
+                    }
                 } else {
                     // Unknown or unimplemented method
                     tracing::trace!("Unimplemented NV4097 method: 0x{:04X}", method);
+                    return false;
                 }
             }
         }
+        true
     }
 }
 
@@ -482,6 +550,22 @@ mod tests {
         assert_eq!(state.texture_format[0], 0x8A);
     }
 
+    #[test]
+    fn test_texture_image_rect() {
+        let mut state = RsxState::new();
+        // Second texture unit, 256x128
+        MethodHandler::execute(NV4097_SET_TEXTURE_IMAGE_RECT + 0x20, (256 << 16) | 128, &mut state);
+        assert_eq!(state.texture_image_rect[1], (256 << 16) | 128);
+    }
+
+    #[test]
+    fn test_surface_color_offsets() {
+        let mut state = RsxState::new();
+        MethodHandler::execute(NV4097_SET_SURFACE_COLOR_AOFFSET, 0x1000, &mut state);
+        MethodHandler::execute(NV4097_SET_SURFACE_COLOR_DOFFSET, 0x4000, &mut state);
+        assert_eq!(state.surface_offset_color, [0x1000, 0, 0, 0x4000]);
+    }
+
     #[test]
     fn test_vertex_attrib_masks() {
         let mut state = RsxState::new();
@@ -491,4 +575,29 @@ mod tests {
         MethodHandler::execute(NV4097_SET_VERTEX_ATTRIB_OUTPUT_MASK, 0x00FF, &mut state);
         assert_eq!(state.vertex_attrib_output_mask, 0x00FF);
     }
+
+    #[test]
+    fn test_transform_constants() {
+        let mut state = RsxState::new();
+        MethodHandler::execute(NV4097_SET_TRANSFORM_CONSTANT_LOAD, 4, &mut state);
+        assert!(MethodHandler::execute(0x1F04, 2.0f32.to_bits(), &mut state));
+        assert!(MethodHandler::execute(0x1F10, 5.0f32.to_bits(), &mut state));
+        assert_eq!(state.transform_constants[4], [0.0, 2.0, 0.0, 0.0]);
+        assert_eq!(state.transform_constants[5], [5.0, 0.0, 0.0, 0.0]);
+
+        MethodHandler::execute(NV4097_SET_TRANSFORM_CONSTANT_LOAD, 468, &mut state);
+        assert!(!MethodHandler::execute(NV4097_SET_TRANSFORM_CONSTANT, 0, &mut state));
+    }
+
+    #[test]
+    fn test_render_enable() {
+        let mut state = RsxState::new();
+        assert!(MethodHandler::execute(NV4097_SET_RENDER_ENABLE, 0x0200_0040, &mut state));
+        assert!(state.conditional_render_enable);
+        assert_eq!(state.conditional_render_offset, 0x40);
+
+        assert!(MethodHandler::execute(NV4097_SET_RENDER_ENABLE, 0x0100_0000, &mut state));
+        assert!(!state.conditional_render_enable);
+        assert!(!MethodHandler::execute(NV4097_SET_RENDER_ENABLE, 0x0300_0000, &mut state));
+    }
 }
@@ -0,0 +1,183 @@
+//! Report buffers and conditional rendering
+//!
+//! NV4097_GET_REPORT writes a 16-byte record to the report area: a
+//! nanosecond timestamp, the value of the requested counter and padding,
+//! all big-endian. Engines read the ZPASS pixel count around a proxy draw to
+//! cull occluded objects, read the timestamps for GPU timing and predicate
+//! later draws on a report with NV4097_SET_RENDER_ENABLE.
+//!
+//! The backends do not read back sample counts, so a counted draw is taken
+//! to cover its whole surface clip area. Occlusion tests then never cull
+//! something that is visible; they only let through more than needed.
+
+use crate::state::RsxState;
+use oc_core::savestate::{StateReader, StateWriter};
+use std::io;
+use std::time::Instant;
+
+/// Report type: samples that passed the depth and stencil tests
+pub const REPORT_ZPASS_PIXEL_COUNT: u32 = 1;
+/// Report types: ZCULL statistics
+pub const REPORT_ZCULL_STATS: u32 = 2;
+pub const REPORT_ZCULL_STATS1: u32 = 3;
+pub const REPORT_ZCULL_STATS2: u32 = 4;
+pub const REPORT_ZCULL_STATS3: u32 = 5;
+
+/// Offset of the report area in RSX local memory, where libgcm keeps reports
+pub const REPORT_AREA_OFFSET: u32 = 0x0E00_0000;
+
+/// Bits of NV4097_GET_REPORT and NV4097_SET_RENDER_ENABLE holding the offset
+pub const REPORT_OFFSET_MASK: u32 = 0x00FF_FFFF;
+
+/// Bytes per report record
+pub const REPORT_SIZE: usize = 16;
+
+/// Offset of the value in a report record
+pub const REPORT_VALUE_OFFSET: u32 = 8;
+
+/// Record written for a report
+pub fn encode_report(timestamp: u64, value: u32) -> [u8; REPORT_SIZE] {
+    let mut record = [0u8; REPORT_SIZE];
+    record[0..8].copy_from_slice(&timestamp.to_be_bytes());
+    record[8..12].copy_from_slice(&value.to_be_bytes());
+    record
+}
+
+/// Offset in RSX local memory of the report at `offset` in the report area
+pub fn report_location(offset: u32) -> u32 {
+    REPORT_AREA_OFFSET + (offset & REPORT_OFFSET_MASK)
+}
+
+/// Counters reports read and the clock they are stamped with
+#[derive(Debug)]
+pub struct RsxReports {
+    /// When the clock started
+    epoch: Instant,
+    /// Clock value at `epoch`, so timestamps keep counting across savestates
+    base_ns: u64,
+    /// Samples counted since the ZPASS counter was last cleared
+    zpass_pixels: u64,
+    /// Samples tested with ZCULL statistics enabled since they were last cleared
+    zcull_pixels: u64,
+}
+
+impl Default for RsxReports {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RsxReports {
+    /// Start the clock with every counter cleared
+    pub fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            base_ns: 0,
+            zpass_pixels: 0,
+            zcull_pixels: 0,
+        }
+    }
+
+    /// Nanoseconds since the RSX started
+    pub fn timestamp(&self) -> u64 {
+        self.base_ns.saturating_add(self.epoch.elapsed().as_nanos() as u64)
+    }
+
+    /// Count the samples of a draw of `vertices` vertices
+    pub fn record_draw(&mut self, state: &RsxState, vertices: u32) {
+        if vertices == 0 || !(state.occlusion_query_enable || state.zcull_stats_enable) {
+            return;
+        }
+        let area = u64::from(state.surface_clip_width) * u64::from(state.surface_clip_height);
+        let samples = area.max(1) * u64::from(state.sample_count.max(1));
+        if state.occlusion_query_enable {
+            self.zpass_pixels = self.zpass_pixels.saturating_add(samples);
+        }
+        if state.zcull_stats_enable {
+            self.zcull_pixels = self.zcull_pixels.saturating_add(samples);
+        }
+    }
+
+    /// Apply NV4097_CLEAR_REPORT_VALUE
+    ///
+    /// Returns false for report types that cannot be cleared.
+    pub fn clear(&mut self, kind: u32) -> bool {
+        match kind {
+            REPORT_ZPASS_PIXEL_COUNT => self.zpass_pixels = 0,
+            REPORT_ZCULL_STATS => self.zcull_pixels = 0,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Value a report of `kind` reads, or None if it only writes the timestamp
+    ///
+    /// Nothing is culled by ZCULL, so its statistics only tell whether
+    /// anything was drawn: the first three read saturated when so, and the
+    /// last one, the culled tiles, reads saturated when not.
+    pub fn value(&self, kind: u32) -> Option<u32> {
+        let drawn = self.zcull_pixels != 0;
+        match kind {
+            REPORT_ZPASS_PIXEL_COUNT => Some(self.zpass_pixels.min(u32::MAX.into()) as u32),
+            REPORT_ZCULL_STATS | REPORT_ZCULL_STATS1 | REPORT_ZCULL_STATS2 => {
+                Some(if drawn { 0xFFFF } else { 0 })
+            }
+            REPORT_ZCULL_STATS3 => Some(if drawn { 0 } else { 0xFFFF }),
+            _ => None,
+        }
+    }
+
+    /// Save the counters and clock for a savestate
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.u64(self.timestamp());
+        w.u64(self.zpass_pixels);
+        w.u64(self.zcull_pixels);
+    }
+
+    /// Restore state written by [`RsxReports::save_state`]
+    pub fn load_state(&mut self, r: &mut StateReader) -> io::Result<()> {
+        self.base_ns = r.u64()?;
+        self.epoch = Instant::now();
+        self.zpass_pixels = r.u64()?;
+        self.zcull_pixels = r.u64()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_counters() {
+        let mut state = RsxState::new();
+        state.surface_clip_width = 64;
+        state.surface_clip_height = 32;
+        let mut reports = RsxReports::new();
+
+        // Draws only count while counting is enabled
+        reports.record_draw(&state, 3);
+        assert_eq!(reports.value(REPORT_ZPASS_PIXEL_COUNT), Some(0));
+        state.occlusion_query_enable = true;
+        reports.record_draw(&state, 3);
+        reports.record_draw(&state, 0);
+        assert_eq!(reports.value(REPORT_ZPASS_PIXEL_COUNT), Some(64 * 32));
+        assert_eq!(reports.value(REPORT_ZCULL_STATS3), Some(0xFFFF));
+
+        state.zcull_stats_enable = true;
+        reports.record_draw(&state, 3);
+        assert_eq!(reports.value(REPORT_ZCULL_STATS), Some(0xFFFF));
+        assert_eq!(reports.value(REPORT_ZCULL_STATS3), Some(0));
+        assert!(reports.clear(REPORT_ZPASS_PIXEL_COUNT));
+        assert_eq!(reports.value(REPORT_ZPASS_PIXEL_COUNT), Some(0));
+        assert!(!reports.clear(7));
+        assert_eq!(reports.value(0), None);
+    }
+
+    #[test]
+    fn test_report_encoding() {
+        let record = encode_report(0x0102_0304_0506_0708, 0x0A0B_0C0D);
+        assert_eq!(record, [1, 2, 3, 4, 5, 6, 7, 8, 0x0A, 0x0B, 0x0C, 0x0D, 0, 0, 0, 0]);
+        assert_eq!(report_location(0x0500_0010), REPORT_AREA_OFFSET + 0x10);
+    }
+}
@@ -4,11 +4,30 @@
 //! to Vulkan SPIR-V shaders.
 
 use bitflags::bitflags;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 
+/// Words per RSX shader instruction, and per inline fragment program constant
+pub const INSTRUCTION_WORDS: usize = 4;
+
+/// Longest fragment program read, in instructions
+pub const MAX_FRAGMENT_INSTRUCTIONS: usize = 512;
+
+/// Fragment program instruction bit marking the last instruction
+const FP_END: u32 = 0x1;
+
+/// Fragment program source register type reading the instruction's inline constant
+const FP_REGISTER_TYPE_CONSTANT: u32 = 2;
+
+/// Fragment program word as the RSX decodes it, with its 16-bit halves swapped back
+fn fp_word(word: u32) -> u32 {
+    word.rotate_left(16)
+}
+
 bitflags! {
     /// Shader stage flags
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -85,14 +104,21 @@ impl Default for VertexProgram {
 }
 
 /// Fragment program descriptor
+///
+/// Fragment programs have no constant registers: a constant is embedded in
+/// the microcode right after the instruction reading it, and games patch
+/// it in place between draws. Parsing pulls these inline constants out
+/// so programs differing only in them share a translation.
 #[derive(Debug, Clone)]
 pub struct FragmentProgram {
-    /// Program instructions
+    /// Program instructions, including the inline constants
     pub instructions: Vec<u32>,
     /// Texture units used
     pub texture_mask: u32,
-    /// Constants data
+    /// Inline constants in program order, as translated programs index them
     pub constants: Vec<[f32; 4]>,
+    /// Word index in `instructions` of each inline constant
+    pub constant_offsets: Vec<usize>,
 }
 
 impl FragmentProgram {
@@ -102,16 +128,48 @@ impl FragmentProgram {
             instructions: Vec::new(),
             texture_mask: 0,
             constants: Vec::new(),
+            constant_offsets: Vec::new(),
         }
     }
 
-    /// Parse instructions from raw data
+    /// Parse instructions from raw data, as the words sit in memory
+    ///
+    /// Parsing stops after the instruction flagged as the last one, so
+    /// `data` may run past the end of the program.
     pub fn from_data(data: &[u32]) -> Self {
-        Self {
-            instructions: data.to_vec(),
-            texture_mask: 0,
-            constants: Vec::new(),
+        let mut program = Self::new();
+        let mut pos = 0;
+        while let Some(instruction) = data.get(pos..pos + INSTRUCTION_WORDS) {
+            pos += INSTRUCTION_WORDS;
+            let reads_constant = instruction[1..]
+                .iter()
+                .any(|&src| fp_word(src) & 0x3 == FP_REGISTER_TYPE_CONSTANT);
+            if reads_constant {
+                let Some(constant) = data.get(pos..pos + INSTRUCTION_WORDS) else {
+                    break;
+                };
+                program.constant_offsets.push(pos);
+                program.constants.push([0, 1, 2, 3].map(|i| f32::from_bits(fp_word(constant[i]))));
+                pos += INSTRUCTION_WORDS;
+            }
+            if fp_word(instruction[0]) & FP_END != 0 {
+                break;
+            }
         }
+        program.instructions = data[..pos].to_vec();
+        program
+    }
+
+    /// Hash of the microcode with the inline constants left out
+    pub fn ucode_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        let mut pos = 0;
+        for &offset in &self.constant_offsets {
+            self.instructions[pos..offset].hash(&mut hasher);
+            pos = offset + INSTRUCTION_WORDS;
+        }
+        self.instructions[pos..].hash(&mut hasher);
+        hasher.finish()
     }
 }
 
@@ -149,8 +207,10 @@ impl SpirVModule {
 pub struct ShaderTranslator {
     /// Vertex program cache
     vertex_cache: Vec<(u32, SpirVModule)>,
-    /// Fragment program cache
-    fragment_cache: Vec<(u32, SpirVModule)>,
+    /// Fragment program cache, by address and microcode hash
+    fragment_cache: Vec<((u32, u64), SpirVModule)>,
+    /// Programs translated because they were not cached
+    compiled: u64,
 }
 
 impl ShaderTranslator {
@@ -159,6 +219,7 @@ impl ShaderTranslator {
         Self {
             vertex_cache: Vec::new(),
             fragment_cache: Vec::new(),
+            compiled: 0,
         }
     }
 
@@ -178,13 +239,20 @@ impl ShaderTranslator {
         };
 
         self.vertex_cache.push((addr, module.clone()));
+        self.compiled += 1;
         Ok(module)
     }
 
     /// Translate fragment program to SPIR-V
-    pub fn translate_fragment(&mut self, _program: &FragmentProgram, addr: u32) -> Result<SpirVModule, String> {
+    ///
+    /// Translations read the inline constants from a buffer instead of
+    /// embedding them, so a program whose constants were patched reuses
+    /// the cached translation and only [`FragmentProgram::constants`]
+    /// need uploading.
+    pub fn translate_fragment(&mut self, program: &FragmentProgram, addr: u32) -> Result<SpirVModule, String> {
         // Check cache first
-        if let Some((_, module)) = self.fragment_cache.iter().find(|(a, _)| *a == addr) {
+        let key = (addr, program.ucode_hash());
+        if let Some((_, module)) = self.fragment_cache.iter().find(|(k, _)| *k == key) {
             return Ok(module.clone());
         }
 
@@ -196,7 +264,8 @@ impl ShaderTranslator {
             stage: ShaderStage::FRAGMENT,
         };
 
-        self.fragment_cache.push((addr, module.clone()));
+        self.fragment_cache.push((key, module.clone()));
+        self.compiled += 1;
         Ok(module)
     }
 
@@ -281,6 +350,11 @@ impl ShaderTranslator {
     pub fn cache_stats(&self) -> (usize, usize) {
         (self.vertex_cache.len(), self.fragment_cache.len())
     }
+
+    /// Programs translated so far, counting ones translated again after the cache was cleared
+    pub fn compiled_count(&self) -> u64 {
+        self.compiled
+    }
 }
 
 impl Default for ShaderTranslator {
@@ -321,9 +395,6 @@ impl ShaderCache {
 
     /// Compute hash for shader data
     fn compute_hash(data: &[u32]) -> u64 {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-        
         let mut hasher = DefaultHasher::new();
         data.hash(&mut hasher);
         hasher.finish()
@@ -529,6 +600,43 @@ mod tests {
         let (v_count, f_count) = translator.cache_stats();
         assert_eq!(v_count, 1);
         assert_eq!(f_count, 1);
+
+        // Cached programs are not translated again
+        translator.translate_vertex(&vp, 0x1000).unwrap();
+        assert_eq!(translator.compiled_count(), 2);
+        translator.clear_cache();
+        translator.translate_vertex(&vp, 0x1000).unwrap();
+        assert_eq!(translator.compiled_count(), 3);
+    }
+
+    #[test]
+    fn test_fragment_constant_patching() {
+        // MOV R0, c (reading an inline constant), then MOV R0, R0 ending the program
+        let mut data = vec![
+            0x3E00_0100, 0xC802_0001, 0xC800_0001, 0xC800_0001,
+            0x0000_3F80, 0x0000_0000, 0x0000_0000, 0x0000_3F80,
+            0x3E01_0100, 0xC800_0001, 0xC800_0001, 0xC800_0001,
+            0xFFFF_FFFF, 0xFFFF_FFFF, 0xFFFF_FFFF, 0xFFFF_FFFF,
+        ];
+        let program = FragmentProgram::from_data(&data);
+        assert_eq!(program.instructions.len(), 12);
+        assert_eq!(program.constant_offsets, [4]);
+        assert_eq!(program.constants, [[1.0, 0.0, 0.0, 1.0]]);
+
+        // Patching the constant keeps the translation
+        let mut translator = ShaderTranslator::new();
+        translator.translate_fragment(&program, 0x2000).unwrap();
+        data[5] = 0x0000_3F00;
+        let patched = FragmentProgram::from_data(&data);
+        assert_eq!(patched.constants, [[1.0, 0.5, 0.0, 1.0]]);
+        assert_eq!(patched.ucode_hash(), program.ucode_hash());
+        translator.translate_fragment(&patched, 0x2000).unwrap();
+        assert_eq!(translator.compiled_count(), 1);
+
+        // Changing an instruction does not
+        data[9] = 0xC801_0001;
+        translator.translate_fragment(&FragmentProgram::from_data(&data), 0x2000).unwrap();
+        assert_eq!(translator.compiled_count(), 2);
     }
 
     #[test]
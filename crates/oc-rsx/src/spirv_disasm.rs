@@ -0,0 +1,228 @@
+//! SPIR-V disassembler
+//!
+//! Turns translated shader modules into text in the spirv-dis style, so
+//! translator output can be read in logs and compared against golden files.
+//! Only the instructions the translator emits have operand layouts; anything
+//! else is printed as its opcode number and raw operand words.
+
+use std::fmt::Write;
+
+/// SPIR-V magic number
+pub const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+/// Words in the module header
+const HEADER_WORDS: usize = 5;
+
+/// Opcode names and operand layouts
+///
+/// Layout letters: `t` result type, `r` result id, `i` id, `l` literal,
+/// `s` string; a trailing `*` repeats ids and `+` literals to the end.
+const OPCODES: &[(u16, &str, &str)] = &[
+    (0, "OpNop", ""),
+    (3, "OpSource", "l l +"),
+    (4, "OpSourceExtension", "s"),
+    (5, "OpName", "i s"),
+    (6, "OpMemberName", "i l s"),
+    (10, "OpExtension", "s"),
+    (11, "OpExtInstImport", "r s"),
+    (12, "OpExtInst", "t r i l *"),
+    (14, "OpMemoryModel", "l l"),
+    (15, "OpEntryPoint", "l i s *"),
+    (16, "OpExecutionMode", "i l +"),
+    (17, "OpCapability", "l"),
+    (19, "OpTypeVoid", "r"),
+    (20, "OpTypeBool", "r"),
+    (21, "OpTypeInt", "r l l"),
+    (22, "OpTypeFloat", "r l"),
+    (23, "OpTypeVector", "r i l"),
+    (24, "OpTypeMatrix", "r i l"),
+    (25, "OpTypeImage", "r i l l l l l +"),
+    (26, "OpTypeSampler", "r"),
+    (27, "OpTypeSampledImage", "r i"),
+    (28, "OpTypeArray", "r i i"),
+    (30, "OpTypeStruct", "r *"),
+    (32, "OpTypePointer", "r l i"),
+    (33, "OpTypeFunction", "r i *"),
+    (41, "OpConstantTrue", "t r"),
+    (42, "OpConstantFalse", "t r"),
+    (43, "OpConstant", "t r +"),
+    (44, "OpConstantComposite", "t r *"),
+    (54, "OpFunction", "t r l i"),
+    (55, "OpFunctionParameter", "t r"),
+    (56, "OpFunctionEnd", ""),
+    (57, "OpFunctionCall", "t r i *"),
+    (59, "OpVariable", "t r l *"),
+    (61, "OpLoad", "t r i +"),
+    (62, "OpStore", "i i +"),
+    (65, "OpAccessChain", "t r i *"),
+    (71, "OpDecorate", "i l +"),
+    (72, "OpMemberDecorate", "i l l +"),
+    (79, "OpVectorShuffle", "t r i i +"),
+    (80, "OpCompositeConstruct", "t r *"),
+    (81, "OpCompositeExtract", "t r i +"),
+    (87, "OpImageSampleImplicitLod", "t r i i +"),
+    (127, "OpFNegate", "t r i"),
+    (129, "OpFAdd", "t r i i"),
+    (131, "OpFSub", "t r i i"),
+    (133, "OpFMul", "t r i i"),
+    (136, "OpFDiv", "t r i i"),
+    (142, "OpVectorTimesScalar", "t r i i"),
+    (145, "OpMatrixTimesVector", "t r i i"),
+    (148, "OpDot", "t r i i"),
+    (169, "OpSelect", "t r i i i"),
+    (184, "OpFOrdLessThan", "t r i i"),
+    (190, "OpFOrdGreaterThanEqual", "t r i i"),
+    (245, "OpPhi", "t r *"),
+    (246, "OpLoopMerge", "i i +"),
+    (247, "OpSelectionMerge", "i l"),
+    (248, "OpLabel", "r"),
+    (249, "OpBranch", "i"),
+    (250, "OpBranchConditional", "i i i +"),
+    (252, "OpKill", ""),
+    (253, "OpReturn", ""),
+    (254, "OpReturnValue", "i"),
+];
+
+/// Disassemble a SPIR-V module, one instruction per line
+pub fn disassemble(words: &[u32]) -> Result<String, String> {
+    if words.len() < HEADER_WORDS {
+        return Err(format!("Module has {} words, shorter than the header", words.len()));
+    }
+    if words[0] != SPIRV_MAGIC {
+        return Err(format!("Invalid SPIR-V magic: 0x{:08X}", words[0]));
+    }
+
+    let mut text = String::new();
+    let version = words[1];
+    writeln!(text, "; SPIR-V").unwrap();
+    writeln!(text, "; Version: {}.{}", (version >> 16) & 0xFF, (version >> 8) & 0xFF).unwrap();
+    writeln!(text, "; Generator: 0x{:08X}", words[2]).unwrap();
+    writeln!(text, "; Bound: {}", words[3]).unwrap();
+    writeln!(text, "; Schema: {}", words[4]).unwrap();
+
+    let mut pos = HEADER_WORDS;
+    while pos < words.len() {
+        let word_count = (words[pos] >> 16) as usize;
+        let opcode = (words[pos] & 0xFFFF) as u16;
+        if word_count == 0 || pos + word_count > words.len() {
+            return Err(format!("Truncated instruction at word {}", pos));
+        }
+        text.push_str(&format_instruction(opcode, &words[pos + 1..pos + word_count]));
+        text.push('\n');
+        pos += word_count;
+    }
+
+    Ok(text)
+}
+
+/// Format one instruction from its opcode and operand words
+fn format_instruction(opcode: u16, operands: &[u32]) -> String {
+    let Some(&(_, name, layout)) = OPCODES.iter().find(|(op, _, _)| *op == opcode) else {
+        let mut line = format!("Op{}", opcode);
+        for word in operands {
+            write!(line, " {}", word).unwrap();
+        }
+        return line;
+    };
+
+    let mut result = None;
+    let mut args = Vec::new();
+    let mut pos = 0;
+    let mut kinds = layout.split_whitespace().peekable();
+    while pos < operands.len() {
+        let kind = match kinds.peek() {
+            Some(&kind @ ("*" | "+")) => kind,
+            Some(_) => kinds.next().unwrap(),
+            // More words than the layout describes
+            None => "+",
+        };
+        match kind {
+            "r" => result = Some(operands[pos]),
+            "t" | "i" | "*" => args.push(format!("%{}", operands[pos])),
+            "s" => {
+                let (string, words) = decode_string(&operands[pos..]);
+                args.push(format!("{:?}", string));
+                pos += words;
+                continue;
+            }
+            _ => args.push(operands[pos].to_string()),
+        }
+        pos += 1;
+    }
+
+    let mut line = match result {
+        Some(id) => format!("%{} = {}", id, name),
+        None => name.to_string(),
+    };
+    for arg in args {
+        write!(line, " {}", arg).unwrap();
+    }
+    line
+}
+
+/// Decode a nul-terminated literal string, returning it and the words it takes
+fn decode_string(words: &[u32]) -> (String, usize) {
+    let mut bytes = Vec::new();
+    for (index, word) in words.iter().enumerate() {
+        for byte in word.to_le_bytes() {
+            if byte == 0 {
+                return (String::from_utf8_lossy(&bytes).into_owned(), index + 1);
+            }
+            bytes.push(byte);
+        }
+    }
+    (String::from_utf8_lossy(&bytes).into_owned(), words.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pack a string into nul-terminated literal words
+    fn string_words(s: &str) -> Vec<u32> {
+        let mut bytes = s.as_bytes().to_vec();
+        bytes.resize(s.len() / 4 * 4 + 4, 0);
+        bytes.chunks(4).map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect()
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let mut words = vec![SPIRV_MAGIC, 0x0001_0000, 0x0008_0001, 16, 0];
+        words.extend([(2 << 16) | 17, 1]);
+        words.extend([(3 << 16) | 14, 0, 1]);
+        let name = string_words("main");
+        words.push(((4 + name.len() as u32) << 16) | 15);
+        words.extend([0, 4]);
+        words.extend(&name);
+        words.push(9);
+        words.extend([(3 << 16) | 22, 6, 32]);
+        words.extend([(4 << 16) | 43, 6, 7, 0x3F80_0000]);
+        words.extend([(3 << 16) | 999, 1, 2]);
+        words.push((1 << 16) | 253);
+
+        let text = disassemble(&words).unwrap();
+        let expected = "\
+; SPIR-V
+; Version: 1.0
+; Generator: 0x00080001
+; Bound: 16
+; Schema: 0
+OpCapability 1
+OpMemoryModel 0 1
+OpEntryPoint 0 %4 \"main\" %9
+%6 = OpTypeFloat 32
+%7 = OpConstant %6 1065353216
+Op999 1 2
+OpReturn
+";
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn test_disassemble_invalid() {
+        assert!(disassemble(&[SPIRV_MAGIC, 0, 0]).is_err());
+        assert!(disassemble(&[0xDEADBEEF, 0x0001_0000, 0, 1, 0]).is_err());
+        // An instruction claiming more words than the module has
+        assert!(disassemble(&[SPIRV_MAGIC, 0x0001_0000, 0, 1, 0, (4 << 16) | 17, 1]).is_err());
+    }
+}
@@ -1,5 +1,11 @@
 //! RSX graphics state
 
+use oc_core::savestate::{StateReader, StateWriter};
+use std::io;
+
+/// Vertex program constant registers, c[0] to c[467]
+pub const VERTEX_CONSTANT_COUNT: usize = 468;
+
 /// RSX graphics state
 #[derive(Debug, Clone, Default)]
 pub struct RsxState {
@@ -59,6 +65,8 @@ pub struct RsxState {
     pub fragment_program_addr: u32,
     pub vertex_attrib_input_mask: u32,
     pub vertex_attrib_output_mask: u32,
+    pub transform_constant_load: u32,
+    pub transform_constants: Vec<[f32; 4]>,
 
     // Vertex attribute state (16 attributes max)
     pub vertex_attrib_format: [u32; 16],
@@ -69,6 +77,7 @@ pub struct RsxState {
     pub texture_format: [u32; 16],
     pub texture_control: [u32; 16],
     pub texture_filter: [u32; 16],
+    pub texture_image_rect: [u32; 16],
     
     // Alpha test state
     pub alpha_test_enable: bool,
@@ -99,6 +108,11 @@ pub struct RsxState {
     // Occlusion query
     pub occlusion_query_enable: bool,
     pub occlusion_query_offset: u32,
+
+    // Reports and conditional rendering
+    pub zcull_stats_enable: bool,
+    pub conditional_render_enable: bool,
+    pub conditional_render_offset: u32,
 }
 
 impl RsxState {
@@ -114,6 +128,7 @@ impl RsxState {
             point_size: 1.0,
             sample_count: 1,
             primitive_restart_index: 0xFFFFFFFF,
+            transform_constants: vec![[0.0; 4]; VERTEX_CONSTANT_COUNT],
             ..Default::default()
         }
     }
@@ -122,6 +137,202 @@ impl RsxState {
     pub fn reset(&mut self) {
         *self = Self::new();
     }
+
+    /// Save every register for a savestate
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.u32(self.surface_color_target);
+        w.u32(self.surface_format);
+        for &value in &self.surface_pitch {
+            w.u32(value);
+        }
+        for &value in &self.surface_offset_color {
+            w.u32(value);
+        }
+        w.u32(self.surface_offset_depth);
+        for &value in &self.context_dma_color {
+            w.u32(value);
+        }
+        w.u32(self.context_dma_depth);
+        w.u16(self.surface_clip_x);
+        w.u16(self.surface_clip_y);
+        w.u16(self.surface_clip_width);
+        w.u16(self.surface_clip_height);
+        w.u32(self.clear_color);
+        w.f32(self.clear_depth);
+        w.u8(self.clear_stencil);
+        w.f32(self.viewport_x);
+        w.f32(self.viewport_y);
+        w.f32(self.viewport_width);
+        w.f32(self.viewport_height);
+        w.f32(self.depth_min);
+        w.f32(self.depth_max);
+        w.u32(self.primitive_type);
+        w.bool(self.blend_enable);
+        w.u32(self.blend_src_factor);
+        w.u32(self.blend_dst_factor);
+        w.u32(self.blend_equation);
+        w.bool(self.depth_test_enable);
+        w.bool(self.depth_write_enable);
+        w.u32(self.depth_func);
+        w.bool(self.stencil_test_enable);
+        w.u32(self.stencil_func);
+        w.u8(self.stencil_ref);
+        w.u8(self.stencil_mask);
+        w.bool(self.cull_face_enable);
+        w.u32(self.cull_face_mode);
+        w.u32(self.front_face);
+        w.u32(self.vertex_program_addr);
+        w.u32(self.fragment_program_addr);
+        w.u32(self.vertex_attrib_input_mask);
+        w.u32(self.vertex_attrib_output_mask);
+        w.u32(self.transform_constant_load);
+        for constant in &self.transform_constants {
+            for &value in constant {
+                w.f32(value);
+            }
+        }
+        for &value in &self.vertex_attrib_format {
+            w.u32(value);
+        }
+        for &value in &self.vertex_attrib_offset {
+            w.u32(value);
+        }
+        for &value in &self.texture_offset {
+            w.u32(value);
+        }
+        for &value in &self.texture_format {
+            w.u32(value);
+        }
+        for &value in &self.texture_control {
+            w.u32(value);
+        }
+        for &value in &self.texture_filter {
+            w.u32(value);
+        }
+        for &value in &self.texture_image_rect {
+            w.u32(value);
+        }
+        w.bool(self.alpha_test_enable);
+        w.u32(self.alpha_test_func);
+        w.f32(self.alpha_test_ref);
+        w.bool(self.polygon_offset_fill_enable);
+        w.bool(self.polygon_offset_line_enable);
+        w.bool(self.polygon_offset_point_enable);
+        w.f32(self.polygon_offset_factor);
+        w.f32(self.polygon_offset_units);
+        w.f32(self.line_width);
+        w.f32(self.point_size);
+        w.bool(self.point_sprite_enable);
+        w.bool(self.multisample_enable);
+        w.bool(self.sample_alpha_to_coverage_enable);
+        w.u8(self.sample_count);
+        w.bool(self.primitive_restart_enable);
+        w.u32(self.primitive_restart_index);
+        w.bool(self.occlusion_query_enable);
+        w.u32(self.occlusion_query_offset);
+        w.bool(self.zcull_stats_enable);
+        w.bool(self.conditional_render_enable);
+        w.u32(self.conditional_render_offset);
+    }
+
+    /// Restore registers written by [`RsxState::save_state`]
+    pub fn load_state(&mut self, r: &mut StateReader) -> io::Result<()> {
+        self.surface_color_target = r.u32()?;
+        self.surface_format = r.u32()?;
+        for value in &mut self.surface_pitch {
+            *value = r.u32()?;
+        }
+        for value in &mut self.surface_offset_color {
+            *value = r.u32()?;
+        }
+        self.surface_offset_depth = r.u32()?;
+        for value in &mut self.context_dma_color {
+            *value = r.u32()?;
+        }
+        self.context_dma_depth = r.u32()?;
+        self.surface_clip_x = r.u16()?;
+        self.surface_clip_y = r.u16()?;
+        self.surface_clip_width = r.u16()?;
+        self.surface_clip_height = r.u16()?;
+        self.clear_color = r.u32()?;
+        self.clear_depth = r.f32()?;
+        self.clear_stencil = r.u8()?;
+        self.viewport_x = r.f32()?;
+        self.viewport_y = r.f32()?;
+        self.viewport_width = r.f32()?;
+        self.viewport_height = r.f32()?;
+        self.depth_min = r.f32()?;
+        self.depth_max = r.f32()?;
+        self.primitive_type = r.u32()?;
+        self.blend_enable = r.bool()?;
+        self.blend_src_factor = r.u32()?;
+        self.blend_dst_factor = r.u32()?;
+        self.blend_equation = r.u32()?;
+        self.depth_test_enable = r.bool()?;
+        self.depth_write_enable = r.bool()?;
+        self.depth_func = r.u32()?;
+        self.stencil_test_enable = r.bool()?;
+        self.stencil_func = r.u32()?;
+        self.stencil_ref = r.u8()?;
+        self.stencil_mask = r.u8()?;
+        self.cull_face_enable = r.bool()?;
+        self.cull_face_mode = r.u32()?;
+        self.front_face = r.u32()?;
+        self.vertex_program_addr = r.u32()?;
+        self.fragment_program_addr = r.u32()?;
+        self.vertex_attrib_input_mask = r.u32()?;
+        self.vertex_attrib_output_mask = r.u32()?;
+        self.transform_constant_load = r.u32()?;
+        self.transform_constants = vec![[0.0; 4]; VERTEX_CONSTANT_COUNT];
+        for constant in &mut self.transform_constants {
+            for value in constant {
+                *value = r.f32()?;
+            }
+        }
+        for value in &mut self.vertex_attrib_format {
+            *value = r.u32()?;
+        }
+        for value in &mut self.vertex_attrib_offset {
+            *value = r.u32()?;
+        }
+        for value in &mut self.texture_offset {
+            *value = r.u32()?;
+        }
+        for value in &mut self.texture_format {
+            *value = r.u32()?;
+        }
+        for value in &mut self.texture_control {
+            *value = r.u32()?;
+        }
+        for value in &mut self.texture_filter {
+            *value = r.u32()?;
+        }
+        for value in &mut self.texture_image_rect {
+            *value = r.u32()?;
+        }
+        self.alpha_test_enable = r.bool()?;
+        self.alpha_test_func = r.u32()?;
+        self.alpha_test_ref = r.f32()?;
+        self.polygon_offset_fill_enable = r.bool()?;
+        self.polygon_offset_line_enable = r.bool()?;
+        self.polygon_offset_point_enable = r.bool()?;
+        self.polygon_offset_factor = r.f32()?;
+        self.polygon_offset_units = r.f32()?;
+        self.line_width = r.f32()?;
+        self.point_size = r.f32()?;
+        self.point_sprite_enable = r.bool()?;
+        self.multisample_enable = r.bool()?;
+        self.sample_alpha_to_coverage_enable = r.bool()?;
+        self.sample_count = r.u8()?;
+        self.primitive_restart_enable = r.bool()?;
+        self.primitive_restart_index = r.u32()?;
+        self.occlusion_query_enable = r.bool()?;
+        self.occlusion_query_offset = r.u32()?;
+        self.zcull_stats_enable = r.bool()?;
+        self.conditional_render_enable = r.bool()?;
+        self.conditional_render_offset = r.u32()?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -134,4 +345,33 @@ mod tests {
         assert_eq!(state.depth_max, 1.0);
         assert!(!state.blend_enable);
     }
+
+    #[test]
+    fn test_save_state_round_trip() {
+        let mut state = RsxState::new();
+        state.surface_pitch[2] = 0x1400;
+        state.clear_color = 0xFF20_4080;
+        state.viewport_width = 1280.0;
+        state.blend_enable = true;
+        state.texture_format[15] = 0x85;
+        state.sample_count = 4;
+        state.transform_constants[467] = [1.0, 2.0, 3.0, 4.0];
+
+        let mut w = StateWriter::new();
+        state.save_state(&mut w);
+        let data = w.into_bytes();
+
+        let mut loaded = RsxState::new();
+        let mut r = StateReader::new(&data);
+        loaded.load_state(&mut r).unwrap();
+        assert_eq!(r.remaining(), 0);
+        assert_eq!(loaded.surface_pitch, state.surface_pitch);
+        assert_eq!(loaded.clear_color, 0xFF20_4080);
+        assert_eq!(loaded.viewport_width, 1280.0);
+        assert!(loaded.blend_enable);
+        assert_eq!(loaded.texture_format[15], 0x85);
+        assert_eq!(loaded.sample_count, 4);
+        assert_eq!(loaded.transform_constants[467], [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(loaded.depth_max, 1.0);
+    }
 }
@@ -1,7 +1,10 @@
 //! RSX texture handling
 
+use std::ops::Range;
+use std::sync::Arc;
 use std::sync::mpsc::{channel, Sender, Receiver};
 use std::thread;
+use crate::texture_decode::DecodeFuture;
 
 /// Texture format constants for RSX
 /// Based on NV40/G70 texture formats used in PS3
@@ -266,14 +269,15 @@ pub struct TextureCache {
 }
 
 /// A cached texture entry
-#[derive(Clone)]
 struct CachedTexture {
     /// GPU memory offset
     offset: u32,
     /// Texture descriptor
     descriptor: Texture,
-    /// Cached texture data
-    data: Vec<u8>,
+    /// Cached texture data, possibly shared with its producer
+    data: Arc<Vec<u8>>,
+    /// Decode still producing `data`
+    pending: Option<DecodeFuture>,
     /// Last access timestamp
     last_used: u64,
 }
@@ -289,17 +293,97 @@ impl TextureCache {
     }
 
     /// Get cached texture
+    ///
+    /// Textures still being decoded are not returned until their data is ready.
     pub fn get(&mut self, offset: u32, timestamp: u64) -> Option<(&Texture, &[u8])> {
-        if let Some(cached) = self.textures.iter_mut().find(|t| t.offset == offset) {
-            cached.last_used = timestamp;
-            Some((&cached.descriptor, cached.data.as_slice()))
-        } else {
-            None
+        let pos = self.textures.iter().position(|t| t.offset == offset)?;
+        if let Some(result) = self.textures[pos].pending.as_mut().map(DecodeFuture::poll) {
+            match result? {
+                Ok(data) => self.resolve(pos, data),
+                Err(e) => {
+                    tracing::warn!("Failed to decode texture at 0x{:08x}: {}", offset, e);
+                    self.textures.remove(pos);
+                    return None;
+                }
+            }
+        }
+        let cached = self.textures.iter_mut().find(|t| t.offset == offset)?;
+        cached.last_used = timestamp;
+        Some((&cached.descriptor, cached.data.as_slice()))
+    }
+
+    /// Descriptor of the texture cached or being decoded at `offset`
+    pub fn descriptor(&self, offset: u32) -> Option<&Texture> {
+        self.textures.iter().find(|t| t.offset == offset).map(|t| &t.descriptor)
+    }
+
+    /// Check if the texture at `offset` is still being decoded
+    pub fn is_pending(&self, offset: u32) -> bool {
+        self.textures.iter().any(|t| t.offset == offset && t.pending.is_some())
+    }
+
+    /// Number of textures still being decoded
+    pub fn pending_count(&self) -> usize {
+        self.textures.iter().filter(|t| t.pending.is_some()).count()
+    }
+
+    /// Insert a texture whose data `future` is still decoding
+    ///
+    /// The texture takes no cache space until its data is ready.
+    pub fn insert_pending(&mut self, offset: u32, descriptor: Texture, future: DecodeFuture, timestamp: u64) {
+        if let Some(pos) = self.textures.iter().position(|t| t.offset == offset) {
+            let old = self.textures.remove(pos);
+            self.current_size -= old.data.len();
+        }
+        self.textures.push(CachedTexture {
+            offset,
+            descriptor,
+            data: Arc::default(),
+            pending: Some(future),
+            last_used: timestamp,
+        });
+    }
+
+    /// Block until the texture at `offset` is decoded, then get it
+    pub fn wait(&mut self, offset: u32, timestamp: u64) -> Option<(&Texture, &[u8])> {
+        let pos = self.textures.iter().position(|t| t.offset == offset)?;
+        if let Some(future) = self.textures[pos].pending.take() {
+            match future.wait() {
+                Ok(data) => self.resolve(pos, data),
+                Err(e) => {
+                    tracing::warn!("Failed to decode texture at 0x{:08x}: {}", offset, e);
+                    self.textures.remove(pos);
+                    return None;
+                }
+            }
+        }
+        self.get(offset, timestamp)
+    }
+
+    /// Store decoded data in the entry at `pos`, evicting others if the cache is full
+    fn resolve(&mut self, pos: usize, data: Vec<u8>) {
+        let cached = &mut self.textures[pos];
+        cached.pending = None;
+        self.current_size += data.len();
+        cached.data = Arc::new(data);
+        let offset = cached.offset;
+        // Waiting decodes cannot be evicted; they have no data yet
+        while self.current_size > self.max_size {
+            let Some(lru_pos) = self.find_lru_except(offset) else {
+                break;
+            };
+            let old = self.textures.remove(lru_pos);
+            self.current_size -= old.data.len();
         }
     }
 
     /// Insert texture into cache
     pub fn insert(&mut self, offset: u32, descriptor: Texture, data: Vec<u8>, timestamp: u64) {
+        self.insert_shared(offset, descriptor, Arc::new(data), timestamp);
+    }
+
+    /// Insert texture data that stays shared with whoever produced it, without copying it
+    pub fn insert_shared(&mut self, offset: u32, descriptor: Texture, data: Arc<Vec<u8>>, timestamp: u64) {
         // Remove existing entry if present
         if let Some(pos) = self.textures.iter().position(|t| t.offset == offset) {
             let old = self.textures.remove(pos);
@@ -311,6 +395,7 @@ impl TextureCache {
             offset,
             descriptor,
             data,
+            pending: None,
             last_used: timestamp,
         });
         self.current_size += data_len;
@@ -324,6 +409,16 @@ impl TextureCache {
         }
     }
 
+    /// Find the least recently used decoded texture other than the one at `offset`
+    fn find_lru_except(&self, offset: u32) -> Option<usize> {
+        self.textures
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.offset != offset && t.pending.is_none())
+            .min_by_key(|(_, t)| t.last_used)
+            .map(|(i, _)| i)
+    }
+
     /// Find least recently used texture
     fn find_lru(&self) -> Option<usize> {
         self.textures
@@ -351,6 +446,19 @@ impl TextureCache {
         });
     }
 
+    /// Invalidate entries overlapping the bytes in `range`, e.g. after the GPU wrote them
+    pub fn invalidate_range(&mut self, range: Range<u32>) {
+        self.textures.retain(|t| {
+            let end = t.offset.saturating_add(t.descriptor.byte_size());
+            if t.offset < range.end && range.start < end {
+                self.current_size -= t.data.len();
+                false
+            } else {
+                true
+            }
+        });
+    }
+
     /// Get cache statistics
     pub fn stats(&self) -> (usize, usize, usize) {
         (self.textures.len(), self.current_size, self.max_size)
@@ -615,6 +723,26 @@ mod tests {
         assert!(cache.get(0x2000, 3).is_some());
     }
 
+    #[test]
+    fn test_texture_cache_pending() {
+        let decoder = crate::texture_decode::TextureDecoder::new(1).unwrap();
+        let mut cache = TextureCache::new(1000);
+        let linear_b8 = format::B8 | crate::texture_decode::TEXTURE_LINEAR;
+        cache.insert_pending(0x1000, Texture::new(), decoder.decode(linear_b8, 2, 2, vec![7; 4]), 1);
+        assert_eq!(cache.pending_count(), 1);
+        assert!(cache.descriptor(0x1000).is_some());
+
+        let (_, data) = cache.wait(0x1000, 2).unwrap();
+        assert_eq!(&data[..4], [7, 7, 7, 255]);
+        assert!(!cache.is_pending(0x1000));
+        assert_eq!(cache.stats().1, 16);
+
+        // Failed decodes drop the entry
+        cache.insert_pending(0x2000, Texture::new(), decoder.decode(linear_b8, 2, 2, Vec::new()), 3);
+        assert!(cache.wait(0x2000, 4).is_none());
+        assert!(cache.descriptor(0x2000).is_none());
+    }
+
     #[test]
     fn test_texture_cache_invalidate() {
         let mut cache = TextureCache::new(1000);
@@ -0,0 +1,306 @@
+//! Texture format conversion on a worker pool
+//!
+//! Guest textures are stored big-endian, often swizzled into Z-order or
+//! DXT compressed. Converting them to linear RGBA is done on a rayon pool
+//! so the RSX thread only hands over the raw bytes and receives a
+//! [`DecodeFuture`] that the texture cache resolves once the pixels are ready.
+
+use crate::texture::format;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+/// NV4097_SET_TEXTURE_CONTROL0 enable bit
+pub const TEXTURE_ENABLE: u32 = 0x8000_0000;
+/// NV4097_SET_TEXTURE_FORMAT flag for linear (not swizzled) layout
+pub const TEXTURE_LINEAR: u8 = 0x20;
+/// NV4097_SET_TEXTURE_FORMAT flag for unnormalized coordinates
+pub const TEXTURE_UNNORMALIZED: u8 = 0x40;
+
+/// Format without the layout and coordinate flags
+pub fn base_format(format_id: u8) -> u8 {
+    format_id & !(TEXTURE_LINEAR | TEXTURE_UNNORMALIZED)
+}
+
+/// Whether texels of a `width` x `height` texture are stored in Z-order rather than row by row
+pub fn is_swizzled(format_id: u8, width: u32, height: u32) -> bool {
+    format_id & TEXTURE_LINEAR == 0
+        && !format::is_compressed(base_format(format_id))
+        && width.is_power_of_two()
+        && height.is_power_of_two()
+}
+
+/// Whether [`decode_texture`] converts textures of this format
+pub fn is_supported(format_id: u8) -> bool {
+    let id = base_format(format_id);
+    match id {
+        format::DXT1 | format::DXT3 | format::DXT5 => true,
+        _ if format::is_compressed(id) => false,
+        _ => decode_texel(id, &[0; 4]).is_some(),
+    }
+}
+
+/// Bytes of the first mipmap level of a texture
+pub fn level_size(format_id: u8, width: u32, height: u32) -> u32 {
+    let id = base_format(format_id);
+    if format::is_compressed(id) {
+        let (block_w, block_h, block_bytes) = format::block_size(id);
+        width.div_ceil(block_w) * height.div_ceil(block_h) * block_bytes
+    } else {
+        width * height * format::bytes_per_pixel(id)
+    }
+}
+
+/// Index of a texel in a swizzled (Z-order) texture with power of two sides
+pub fn swizzle_index(x: u32, y: u32, width: u32, height: u32) -> u32 {
+    let (mut x, mut y, mut width, mut height) = (x, y, width, height);
+    let (mut index, mut shift) = (0, 0);
+    while width > 1 || height > 1 {
+        if width > 1 {
+            index |= (x & 1) << shift;
+            x >>= 1;
+            width >>= 1;
+            shift += 1;
+        }
+        if height > 1 {
+            index |= (y & 1) << shift;
+            y >>= 1;
+            height >>= 1;
+            shift += 1;
+        }
+    }
+    index
+}
+
+/// Convert one uncompressed big-endian texel to RGBA
+pub fn decode_texel(id: u8, data: &[u8]) -> Option<[u8; 4]> {
+    let word = || u16::from_be_bytes([data[0], data[1]]);
+    let rgba = match id {
+        format::B8 => [data[0], data[0], data[0], 255],
+        format::A1R5G5B5 | format::D1R5G5B5 => {
+            let v = word();
+            let alpha = if id == format::D1R5G5B5 || v & 0x8000 != 0 { 255 } else { 0 };
+            [expand5(v >> 10), expand5(v >> 5), expand5(v), alpha]
+        }
+        format::A4R4G4B4 => {
+            let v = word();
+            [((v >> 8) & 0xF) as u8 * 17, ((v >> 4) & 0xF) as u8 * 17, (v & 0xF) as u8 * 17, (v >> 12) as u8 * 17]
+        }
+        format::R5G6B5 => {
+            let v = word();
+            [expand5(v >> 11), expand6(v >> 5), expand5(v), 255]
+        }
+        format::R5G5B5A1 => {
+            let v = word();
+            [expand5(v >> 11), expand5(v >> 6), expand5(v >> 1), if v & 1 != 0 { 255 } else { 0 }]
+        }
+        format::ARGB8 | format::A8R8G8B8 => [data[1], data[2], data[3], data[0]],
+        format::XRGB8 | format::D8R8G8B8 => [data[1], data[2], data[3], 255],
+        _ => return None,
+    };
+    Some(rgba)
+}
+
+/// Convert one texel of a DXT1/3/5 block to RGBA
+pub fn decode_dxt(id: u8, block: &[u8], x: u32, y: u32) -> [u8; 4] {
+    let texel = y * 4 + x;
+    // DXT3/5 blocks hold 8 bytes of alpha before the colors
+    let color = if id == format::DXT1 { block } else { &block[8..] };
+    let c0 = u16::from_le_bytes([color[0], color[1]]);
+    let c1 = u16::from_le_bytes([color[2], color[3]]);
+    let rgb = |c: u16| [expand5(c >> 11), expand6(c >> 5), expand5(c)];
+    let (p0, p1) = (rgb(c0), rgb(c1));
+    let mix = |a: u32, b: u32, d: u32| {
+        let channel = |i: usize| ((p0[i] as u32 * a + p1[i] as u32 * b) / d) as u8;
+        [channel(0), channel(1), channel(2)]
+    };
+    let indices = u32::from_le_bytes([color[4], color[5], color[6], color[7]]);
+    let four_colors = id != format::DXT1 || c0 > c1;
+    let (rgb, transparent) = match (indices >> (texel * 2)) & 3 {
+        0 => (p0, false),
+        1 => (p1, false),
+        2 if four_colors => (mix(2, 1, 3), false),
+        2 => (mix(1, 1, 2), false),
+        3 if four_colors => (mix(1, 2, 3), false),
+        _ => ([0, 0, 0], true),
+    };
+
+    let alpha = match id {
+        format::DXT3 => {
+            let bits = u64::from_le_bytes(block[..8].try_into().unwrap());
+            ((bits >> (texel * 4)) & 0xF) as u8 * 17
+        }
+        format::DXT5 => {
+            let (a0, a1) = (block[0] as u32, block[1] as u32);
+            let mut bits = [0u8; 8];
+            bits[..6].copy_from_slice(&block[2..8]);
+            let code = (u64::from_le_bytes(bits) >> (texel * 3)) & 7;
+            match code {
+                0 => a0 as u8,
+                1 => a1 as u8,
+                c if a0 > a1 => (((8 - c as u32) * a0 + (c as u32 - 1) * a1) / 7) as u8,
+                6 => 0,
+                7 => 255,
+                c => (((6 - c as u32) * a0 + (c as u32 - 1) * a1) / 5) as u8,
+            }
+        }
+        _ if transparent => 0,
+        _ => 255,
+    };
+    [rgb[0], rgb[1], rgb[2], alpha]
+}
+
+/// Expand a 5-bit channel to 8 bits
+pub fn expand5(value: u16) -> u8 {
+    let v = (value & 0x1F) as u8;
+    (v << 3) | (v >> 2)
+}
+
+/// Expand a 6-bit channel to 8 bits
+pub fn expand6(value: u16) -> u8 {
+    let v = (value & 0x3F) as u8;
+    (v << 2) | (v >> 4)
+}
+
+/// Convert the first mipmap level of a texture to linear RGBA
+///
+/// `format_id` is the format byte of NV4097_SET_TEXTURE_FORMAT including its
+/// flags, `data` at least [`level_size`] bytes.
+pub fn decode_texture(format_id: u8, width: u32, height: u32, data: &[u8]) -> Result<Vec<u8>, String> {
+    let id = base_format(format_id);
+    let size = level_size(format_id, width, height) as usize;
+    if width == 0 || height == 0 {
+        return Err(String::from("Empty texture"));
+    }
+    if data.len() < size {
+        return Err(format!("Texture needs {} bytes, got {}", size, data.len()));
+    }
+
+    let mut pixels = vec![0u8; (width * height * 4) as usize];
+    if format::is_compressed(id) {
+        if !matches!(id, format::DXT1 | format::DXT3 | format::DXT5) {
+            return Err(format!("Unsupported compressed texture format 0x{:02X}", id));
+        }
+        let (_, _, block_bytes) = format::block_size(id);
+        let blocks_per_row = width.div_ceil(4);
+        for y in 0..height {
+            for x in 0..width {
+                let start = (((y / 4) * blocks_per_row + x / 4) * block_bytes) as usize;
+                let rgba = decode_dxt(id, &data[start..start + block_bytes as usize], x % 4, y % 4);
+                let i = ((y * width + x) * 4) as usize;
+                pixels[i..i + 4].copy_from_slice(&rgba);
+            }
+        }
+        return Ok(pixels);
+    }
+
+    let bytes = format::bytes_per_pixel(id);
+    let swizzled = is_swizzled(format_id, width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let index = if swizzled { swizzle_index(x, y, width, height) } else { y * width + x };
+            let start = (index * bytes) as usize;
+            let rgba = decode_texel(id, &data[start..start + bytes as usize])
+                .ok_or_else(|| format!("Unsupported texture format 0x{:02X}", id))?;
+            let i = ((y * width + x) * 4) as usize;
+            pixels[i..i + 4].copy_from_slice(&rgba);
+        }
+    }
+    Ok(pixels)
+}
+
+/// RGBA pixels of a texture still being decoded
+pub struct DecodeFuture {
+    receiver: Receiver<Result<Vec<u8>, String>>,
+}
+
+impl DecodeFuture {
+    /// Take the decoded pixels if they are ready
+    ///
+    /// Returns None while the worker is still decoding; after a result is
+    /// returned the future is spent.
+    pub fn poll(&mut self) -> Option<Result<Vec<u8>, String>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(String::from("Texture decode worker exited"))),
+        }
+    }
+
+    /// Block until the pixels are decoded
+    pub fn wait(self) -> Result<Vec<u8>, String> {
+        self.receiver.recv().unwrap_or_else(|_| Err(String::from("Texture decode worker exited")))
+    }
+}
+
+/// Pool of worker threads converting textures
+pub struct TextureDecoder {
+    pool: rayon::ThreadPool,
+}
+
+impl TextureDecoder {
+    /// Create a pool of `threads` workers, 0 for one per core
+    pub fn new(threads: usize) -> Result<Self, String> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("rsx-texture-{}", index))
+            .build()
+            .map_err(|e| format!("Failed to start texture decode workers: {}", e))?;
+        Ok(Self { pool })
+    }
+
+    /// Queue `data` to be converted to RGBA
+    pub fn decode(&self, format_id: u8, width: u32, height: u32, data: Vec<u8>) -> DecodeFuture {
+        let (sender, receiver) = mpsc::channel();
+        self.pool.spawn(move || {
+            let _ = sender.send(decode_texture(format_id, width, height, &data));
+        });
+        DecodeFuture { receiver }
+    }
+
+    /// Number of worker threads
+    pub fn worker_count(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_texture() {
+        // 2x2 swizzled ARGB8: texels (0,0), (1,0), (0,1), (1,1)
+        let data = [255, 255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 128, 1, 2, 3];
+        let pixels = decode_texture(format::ARGB8, 2, 2, &data).unwrap();
+        assert_eq!(pixels, [255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 1, 2, 3, 128]);
+
+        // Z-order and linear layouts match at 2x2 and differ from 4x4 on
+        let linear = decode_texture(format::ARGB8 | TEXTURE_LINEAR, 2, 2, &data).unwrap();
+        assert_eq!(linear, pixels);
+        assert_eq!(swizzle_index(1, 1, 4, 4), 3);
+        assert_eq!(swizzle_index(2, 0, 4, 4), 4);
+
+        // DXT1 block with white and black endpoints, first row white
+        let block = [0xFF, 0xFF, 0x00, 0x00, 0x00, 0x55, 0x55, 0x55];
+        let pixels = decode_texture(format::DXT1, 4, 4, &block).unwrap();
+        assert_eq!(&pixels[..4], [255, 255, 255, 255]);
+        assert_eq!(&pixels[16..20], [0, 0, 0, 255]);
+
+        assert!(decode_texture(format::ARGB8, 4, 4, &data).is_err());
+        assert!(decode_texture(format::ETC1_RGB8, 4, 4, &block).is_err());
+        assert!(is_supported(format::DXT5) && is_supported(format::R5G6B5 | TEXTURE_LINEAR));
+        assert!(!is_supported(format::ETC1_RGB8) && !is_supported(format::X32_FLOAT));
+    }
+
+    #[test]
+    fn test_texture_decoder() {
+        let decoder = TextureDecoder::new(2).unwrap();
+        assert_eq!(decoder.worker_count(), 2);
+        let futures: Vec<_> = (0..8u8)
+            .map(|i| decoder.decode(format::B8 | TEXTURE_LINEAR, 4, 4, vec![i; 16]))
+            .collect();
+        for (i, future) in futures.into_iter().enumerate() {
+            let pixels = future.wait().unwrap();
+            assert_eq!(&pixels[..4], [i as u8, i as u8, i as u8, 255]);
+        }
+    }
+}
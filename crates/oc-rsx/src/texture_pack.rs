@@ -0,0 +1,167 @@
+//! Texture dumping and replacement
+//!
+//! Textures are named by a hash of their guest texels, format and size, so
+//! a texture pack made on one machine matches the same texture on another.
+//! Decoded textures seen for the first time are queued for the frontend to
+//! save, and replacements it loaded are uploaded instead of the decoded
+//! texels, at whatever size they were made.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// FNV-1a offset basis
+const FNV_OFFSET: u64 = 0xCBF2_9CE4_8422_2325;
+/// FNV-1a prime
+const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+/// Stable hash of a guest texture, its file name in dumps and packs
+///
+/// Independent of the host and the Rust version, unlike `DefaultHasher`.
+pub fn texture_hash(format_id: u8, width: u16, height: u16, data: &[u8]) -> u64 {
+    let header = [format_id, (width >> 8) as u8, width as u8, (height >> 8) as u8, height as u8];
+    header.iter().chain(data).fold(FNV_OFFSET, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME))
+}
+
+/// File name of texture `hash` in dumps and packs, without the extension
+pub fn texture_name(hash: u64) -> String {
+    format!("{:016x}", hash)
+}
+
+/// Texture `hash` from a file name written by [`texture_name`]
+pub fn parse_texture_name(stem: &str) -> Option<u64> {
+    if stem.len() != 16 {
+        return None;
+    }
+    u64::from_str_radix(stem, 16).ok()
+}
+
+/// Decoded texture to be saved
+#[derive(Debug, Clone)]
+pub struct TextureDump {
+    pub hash: u64,
+    pub width: u32,
+    pub height: u32,
+    /// RGBA8 rows
+    pub pixels: Vec<u8>,
+}
+
+/// User-provided texture uploaded in place of a guest one
+#[derive(Debug, Clone)]
+pub struct ReplacementTexture {
+    pub width: u32,
+    pub height: u32,
+    /// RGBA8 rows
+    pub pixels: Arc<Vec<u8>>,
+}
+
+/// Dumping and replacement state of the RSX thread
+#[derive(Default)]
+pub struct TexturePack {
+    /// Queue textures seen for the first time for saving
+    dumping: bool,
+    /// Hashes already queued, so each texture is saved once
+    dumped: HashSet<u64>,
+    dumps: Vec<TextureDump>,
+    replacements: HashMap<u64, ReplacementTexture>,
+    /// Hash of the texture being decoded at each offset
+    pending: HashMap<u32, u64>,
+}
+
+impl TexturePack {
+    /// Create a pack that neither dumps nor replaces
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether textures need hashing
+    pub fn is_active(&self) -> bool {
+        self.dumping || !self.replacements.is_empty()
+    }
+
+    /// Queue textures seen for the first time for saving
+    pub fn set_dumping(&mut self, dumping: bool) {
+        self.dumping = dumping;
+        if !dumping {
+            self.dumps.clear();
+        }
+    }
+
+    /// Textures already saved by an earlier session, which are not queued again
+    pub fn mark_dumped(&mut self, hashes: impl IntoIterator<Item = u64>) {
+        self.dumped.extend(hashes);
+    }
+
+    /// Replace textures by hash; an empty map turns replacement off
+    pub fn set_replacements(&mut self, replacements: HashMap<u64, ReplacementTexture>) {
+        self.replacements = replacements;
+    }
+
+    /// Number of replacements loaded
+    pub fn replacement_count(&self) -> usize {
+        self.replacements.len()
+    }
+
+    /// Remember the hash of the texture queued for decoding at `offset`
+    pub fn track(&mut self, offset: u32, hash: u64) {
+        self.pending.insert(offset, hash);
+    }
+
+    /// Hash of the texture decoded at `offset`, once
+    pub fn take_hash(&mut self, offset: u32) -> Option<u64> {
+        self.pending.remove(&offset)
+    }
+
+    /// Replacement for texture `hash`
+    pub fn replacement(&self, hash: u64) -> Option<&ReplacementTexture> {
+        self.replacements.get(&hash)
+    }
+
+    /// Queue a decoded texture for saving if it was not saved before
+    pub fn dump(&mut self, hash: u64, width: u32, height: u32, pixels: &[u8]) {
+        if self.dumping && self.dumped.insert(hash) {
+            self.dumps.push(TextureDump { hash, width, height, pixels: pixels.to_vec() });
+        }
+    }
+
+    /// Take the textures queued for saving
+    pub fn take_dumps(&mut self) -> Vec<TextureDump> {
+        std::mem::take(&mut self.dumps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_texture_hash_and_names() {
+        let hash = texture_hash(0x85, 4, 4, &[0u8; 64]);
+        // Format, size and texels all take part
+        assert_ne!(hash, texture_hash(0x86, 4, 4, &[0u8; 64]));
+        assert_ne!(hash, texture_hash(0x85, 8, 2, &[0u8; 64]));
+        assert_ne!(hash, texture_hash(0x85, 4, 4, &[1u8; 64]));
+        assert_eq!(texture_hash(0, 0, 0, &[]), 0xE4BC_4FD9_252B_E94F);
+
+        let name = texture_name(hash);
+        assert_eq!(name.len(), 16);
+        assert_eq!(parse_texture_name(&name), Some(hash));
+        assert_eq!(parse_texture_name("readme"), None);
+    }
+
+    #[test]
+    fn test_dumps_once() {
+        let mut pack = TexturePack::new();
+        assert!(!pack.is_active());
+        pack.dump(1, 1, 1, &[0; 4]);
+        assert!(pack.take_dumps().is_empty());
+
+        pack.set_dumping(true);
+        pack.mark_dumped([2]);
+        pack.dump(1, 1, 1, &[0; 4]);
+        pack.dump(1, 1, 1, &[0; 4]);
+        pack.dump(2, 1, 1, &[0; 4]);
+        let dumps = pack.take_dumps();
+        assert_eq!(dumps.len(), 1);
+        assert_eq!(dumps[0].hash, 1);
+    }
+}
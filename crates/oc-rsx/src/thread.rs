@@ -1,17 +1,42 @@
 //! RSX thread (command processor)
 
+use std::collections::{BTreeMap, HashMap};
+use std::io;
 use std::sync::Arc;
-use oc_memory::MemoryManager;
+use oc_memory::{MemoryManager, RSX_MEM_SIZE};
+use oc_core::savestate::{invalid, StateReader, StateWriter};
 use crate::state::RsxState;
-use crate::fifo::CommandFifo;
+use crate::fifo::{CommandFifo, RsxCommand};
 use crate::methods::MethodHandler;
+use crate::shader::{
+    FragmentProgram, ShaderStage, ShaderTranslator, VertexProgram, INSTRUCTION_WORDS, MAX_FRAGMENT_INSTRUCTIONS,
+};
 use crate::backend::{GraphicsBackend, null::NullBackend};
+use crate::blit::{self, BlitState, BlitWrite};
+use crate::scaling::RenderScale;
+use crate::counters::{RsxCounters, RsxFrameCounters};
+use crate::report::{self, RsxReports, REPORT_OFFSET_MASK, REPORT_VALUE_OFFSET};
+use crate::texture::{format, Texture, TextureCache};
+use crate::texture_decode::{self, TextureDecoder, TEXTURE_ENABLE, TEXTURE_LINEAR, TEXTURE_UNNORMALIZED};
+use crate::texture_pack::{self, ReplacementTexture, TextureDump, TexturePack};
 
 // Draw command data extraction constants
 const DRAW_FIRST_MASK: u32 = 0xFFFFFF;
 const DRAW_COUNT_SHIFT: u32 = 24;
 const DRAW_COUNT_MASK: u32 = 0xFF;
 
+/// Location bits of NV4097_SET_TEXTURE_FORMAT for textures in main memory
+const TEXTURE_LOCATION_MAIN: u32 = 2;
+
+/// Location bits of NV4097_SET_SHADER_PROGRAM for programs in local memory
+const SHADER_LOCATION_LOCAL: u32 = 1;
+
+/// Bytes of decoded textures kept before the least recently used are dropped
+const TEXTURE_CACHE_SIZE: usize = 256 * 1024 * 1024;
+
+/// Method libgcm writes to the FIFO to flip to a display buffer
+pub const GCM_FLIP_COMMAND: u32 = 0xFEAC;
+
 /// RSX thread state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RsxThreadState {
@@ -32,6 +57,34 @@ pub struct RsxThread {
     memory: Arc<MemoryManager>,
     /// Graphics backend
     backend: Box<dyn GraphicsBackend>,
+    /// Shader programs translated for draws
+    shaders: ShaderTranslator,
+    /// Vertex program constants changed since they were last uploaded this frame
+    vertex_constants_dirty: bool,
+    /// Fragment program constants uploaded this frame, None before the first draw
+    fragment_constants: Option<Vec<[f32; 4]>>,
+    /// Internal resolution surfaces are rendered at
+    render_scale: RenderScale,
+    /// Flips the game has issued
+    flip_count: u64,
+    /// Display buffer of the last flip
+    last_flip_buffer: u32,
+    /// Skip clears and draws while the frame is being dropped
+    skip_draws: bool,
+    /// Work handed to the backend per frame
+    counters: RsxCounters,
+    /// Counters and clock for NV4097_GET_REPORT
+    reports: RsxReports,
+    /// Registers of the 2D blit classes
+    blit_state: BlitState,
+    /// Decoded textures by offset, including ones still decoding
+    textures: TextureCache,
+    /// Workers converting textures (None if they failed to start)
+    decoder: Option<TextureDecoder>,
+    /// Textures being dumped and their replacements
+    texture_pack: TexturePack,
+    /// How often each unimplemented method was written
+    unimplemented_methods: BTreeMap<u32, u64>,
 }
 
 impl RsxThread {
@@ -48,6 +101,22 @@ impl RsxThread {
             fifo: CommandFifo::new(),
             memory,
             backend,
+            shaders: ShaderTranslator::new(),
+            vertex_constants_dirty: true,
+            fragment_constants: None,
+            render_scale: RenderScale::native(),
+            flip_count: 0,
+            last_flip_buffer: 0,
+            skip_draws: false,
+            counters: RsxCounters::new(),
+            reports: RsxReports::new(),
+            blit_state: BlitState::new(),
+            textures: TextureCache::new(TEXTURE_CACHE_SIZE),
+            decoder: TextureDecoder::new(0)
+                .inspect_err(|e| tracing::warn!("Textures will not be decoded: {}", e))
+                .ok(),
+            texture_pack: TexturePack::new(),
+            unimplemented_methods: BTreeMap::new(),
         }
     }
 
@@ -58,7 +127,18 @@ impl RsxThread {
 
     /// Process commands from FIFO
     pub fn process_commands(&mut self) {
-        while let Some(cmd) = self.fifo.pop() {
+        self.process_commands_while(|_, _| true);
+    }
+
+    /// Process commands from FIFO while `allow` accepts the next one, leaving the rest queued
+    ///
+    /// `allow` sees the state before the command executes, e.g. for a debugger.
+    pub fn process_commands_while(&mut self, mut allow: impl FnMut(&RsxState, &RsxCommand) -> bool) {
+        while let Some(cmd) = self.fifo.peek() {
+            if !allow(&self.gfx_state, &cmd) {
+                break;
+            }
+            self.fifo.pop();
             self.execute_command(cmd.method, cmd.data);
         }
     }
@@ -71,6 +151,14 @@ impl RsxThread {
     /// End a frame
     pub fn end_frame(&mut self) {
         self.backend.end_frame();
+        self.counters.end_frame();
+        self.invalidate_constants();
+    }
+
+    /// Upload all shader constants again before the next draw, as uploads only last a frame
+    fn invalidate_constants(&mut self) {
+        self.vertex_constants_dirty = true;
+        self.fragment_constants = None;
     }
 
     /// Execute a single RSX command
@@ -91,6 +179,10 @@ impl RsxThread {
                     self.flush_vertices();
                 }
             }
+            // NV4097_SET_TRANSFORM_CONSTANT
+            0x1F00..=0x1F7C => {
+                self.vertex_constants_dirty = true;
+            }
             // NV4097_DRAW_ARRAYS
             0x1810 => {
                 self.draw_arrays(data);
@@ -101,16 +193,44 @@ impl RsxThread {
                 self.draw_indexed(data);
                 return;
             }
+            // NV4097_GET_REPORT
+            0x1800 => {
+                self.write_report(data);
+                return;
+            }
+            // NV4097_CLEAR_REPORT_VALUE
+            0x17C8 => {
+                if !self.reports.clear(data) {
+                    *self.unimplemented_methods.entry(method).or_insert(0) += 1;
+                }
+                return;
+            }
+            method if blit::BLIT_METHODS.contains(&method) => {
+                self.blit(method, data);
+                return;
+            }
+            GCM_FLIP_COMMAND => {
+                tracing::trace!("Flip to display buffer {}", data);
+                self.flip_count += 1;
+                self.last_flip_buffer = data;
+                return;
+            }
             _ => {}
         }
         
         // Use the method handler for state updates
-        MethodHandler::execute(method, data, &mut self.gfx_state);
+        if !MethodHandler::execute(method, data, &mut self.gfx_state) {
+            *self.unimplemented_methods.entry(method).or_insert(0) += 1;
+        }
     }
 
     /// Clear the surface
     fn clear_surface(&mut self, mask: u32) {
         tracing::trace!("Clear surface with mask 0x{:08x}", mask);
+        if self.skip_draws {
+            return;
+        }
+        self.counters.record_clear(&self.gfx_state);
         
         // Extract clear color from state
         let color_u32 = self.gfx_state.clear_color;
@@ -133,8 +253,15 @@ impl RsxThread {
         let count = (data >> DRAW_COUNT_SHIFT) & DRAW_COUNT_MASK;
         
         tracing::trace!("Draw arrays: first={}, count={}", first, count);
+        if self.skip_draws || !self.render_enabled() {
+            return;
+        }
+        self.reports.record_draw(&self.gfx_state, count);
         
+        let pipeline_hit = self.prepare_shaders();
+        self.prepare_textures();
         let primitive = self.convert_primitive_type();
+        self.counters.record_draw(&self.gfx_state, primitive, count, pipeline_hit);
         self.backend.draw_arrays(primitive, first, count);
     }
 
@@ -144,11 +271,288 @@ impl RsxThread {
         let count = (data >> DRAW_COUNT_SHIFT) & DRAW_COUNT_MASK;
         
         tracing::trace!("Draw indexed: first={}, count={}", first, count);
+        if self.skip_draws || !self.render_enabled() {
+            return;
+        }
+        self.reports.record_draw(&self.gfx_state, count);
         
+        let pipeline_hit = self.prepare_shaders();
+        self.prepare_textures();
         let primitive = self.convert_primitive_type();
+        self.counters.record_draw(&self.gfx_state, primitive, count, pipeline_hit);
         self.backend.draw_indexed(primitive, first, count);
     }
 
+    /// Apply a 2D class method, running the copy it starts
+    ///
+    /// Copies write guest memory, so they run even while draws are skipped.
+    fn blit(&mut self, method: u32, data: u32) {
+        let command = match self.blit_state.write(method, data) {
+            BlitWrite::Register => return,
+            BlitWrite::Unknown => {
+                *self.unimplemented_methods.entry(method).or_insert(0) += 1;
+                return;
+            }
+            BlitWrite::Blit(command) => command,
+        };
+        match blit::run(&command, &self.memory) {
+            Ok(written) => self.textures.invalidate_range(written),
+            Err(e) => tracing::debug!("Skipped blit {:?}: {}", command, e),
+        }
+    }
+
+    /// Write the report NV4097_GET_REPORT asks for: the type in the top byte
+    /// and the offset in the report area below it
+    ///
+    /// Unknown types only update the timestamp, which is how libgcm reads
+    /// the GPU clock.
+    fn write_report(&mut self, data: u32) {
+        let kind = data >> 24;
+        let offset = report::report_location(data & REPORT_OFFSET_MASK);
+        let value = match self.reports.value(kind) {
+            Some(value) => value,
+            None => self.read_report_value(offset).unwrap_or(0),
+        };
+        let record = report::encode_report(self.reports.timestamp(), value);
+        tracing::trace!("Report type {} at 0x{:08x} = {}", kind, offset, value);
+        if let Err(e) = self.memory.write_rsx_bytes(offset, &record) {
+            tracing::debug!("Failed to write report at 0x{:08x}: {}", offset, e);
+        }
+    }
+
+    /// Value of the report record at `offset` in local memory
+    fn read_report_value(&self, offset: u32) -> Option<u32> {
+        let data = self.memory.read_rsx_bytes(offset.checked_add(REPORT_VALUE_OFFSET)?, 4).ok()?;
+        Some(u32::from_be_bytes([data[0], data[1], data[2], data[3]]))
+    }
+
+    /// Whether draws pass the NV4097_SET_RENDER_ENABLE predicate
+    ///
+    /// Reports are written as they are requested, so the predicate reads
+    /// the finished value. A report that cannot be read does not cull.
+    fn render_enabled(&self) -> bool {
+        if !self.gfx_state.conditional_render_enable {
+            return true;
+        }
+        let offset = report::report_location(self.gfx_state.conditional_render_offset);
+        self.read_report_value(offset).is_none_or(|value| value != 0)
+    }
+
+    /// Translate the bound vertex and fragment programs unless already cached
+    /// and upload the constants that changed
+    ///
+    /// Returns whether both were cached.
+    fn prepare_shaders(&mut self) -> bool {
+        let compiled = self.shaders.compiled_count();
+        let fragment_program = self.read_fragment_program();
+        let vertex = self.shaders.translate_vertex(&VertexProgram::new(), self.gfx_state.vertex_program_addr);
+        let fragment = self.shaders.translate_fragment(&fragment_program, self.gfx_state.fragment_program_addr);
+        if let Err(e) = vertex.and(fragment) {
+            tracing::warn!("Failed to translate shaders: {}", e);
+        }
+
+        if self.vertex_constants_dirty {
+            self.backend.upload_shader_constants(ShaderStage::VERTEX, &self.gfx_state.transform_constants);
+            self.vertex_constants_dirty = false;
+        }
+        if self.fragment_constants.as_ref() != Some(&fragment_program.constants) {
+            self.backend.upload_shader_constants(ShaderStage::FRAGMENT, &fragment_program.constants);
+            self.fragment_constants = Some(fragment_program.constants);
+        }
+        self.shaders.compiled_count() == compiled
+    }
+
+    /// Read the bound fragment program from local memory
+    ///
+    /// Programs in main memory are not read yet and translate as empty ones.
+    fn read_fragment_program(&self) -> FragmentProgram {
+        let addr = self.gfx_state.fragment_program_addr;
+        if addr & 0x3 != SHADER_LOCATION_LOCAL {
+            return FragmentProgram::new();
+        }
+        let offset = addr & !0x3;
+        let size = ((MAX_FRAGMENT_INSTRUCTIONS * INSTRUCTION_WORDS * 4) as u32).min(RSX_MEM_SIZE.saturating_sub(offset));
+        match self.memory.read_rsx_bytes(offset, size) {
+            Ok(data) => {
+                let words: Vec<u32> = data
+                    .chunks_exact(4)
+                    .map(|c| u32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+                    .collect();
+                FragmentProgram::from_data(&words)
+            }
+            Err(e) => {
+                tracing::debug!("Failed to read fragment program at 0x{:08x}: {}", offset, e);
+                FragmentProgram::new()
+            }
+        }
+    }
+
+    /// Queue decodes of newly bound textures and upload and bind the ones that are ready
+    ///
+    /// Decoding runs on the worker pool, so a texture is bound from the first
+    /// draw after its pixels are ready rather than stalling this one.
+    fn prepare_textures(&mut self) {
+        for unit in 0..self.gfx_state.texture_offset.len() {
+            if self.gfx_state.texture_control[unit] & TEXTURE_ENABLE == 0 {
+                continue;
+            }
+            let offset = self.gfx_state.texture_offset[unit];
+            let format = self.gfx_state.texture_format[unit];
+            let format_id = ((format >> 8) & 0xFF) as u8;
+            let rect = self.gfx_state.texture_image_rect[unit];
+            let (width, height) = ((rect >> 16) as u16, (rect & 0xFFFF) as u16);
+            // Unnormalized coordinates only change sampling, not the texels
+            let current = self.textures.descriptor(offset).is_some_and(|texture| {
+                (texture.format, texture.width, texture.height) == (format_id & !TEXTURE_UNNORMALIZED, width, height)
+            });
+            if !current {
+                // Textures in main memory are not read yet
+                if format & 0x3 == TEXTURE_LOCATION_MAIN || !self.queue_texture_decode(offset, format_id, width, height) {
+                    continue;
+                }
+            }
+            // A texture is uploaded once, by the draw that finds its decode finished
+            let decoding = self.textures.is_pending(offset);
+            if let Some((texture, data)) = self.textures.get(offset, self.flip_count) {
+                if decoding {
+                    let (width, height) = (texture.width.into(), texture.height.into());
+                    let hash = self.texture_pack.take_hash(offset);
+                    if let Some(hash) = hash {
+                        self.texture_pack.dump(hash, width, height, data);
+                    }
+                    // Unnormalized coordinates address texels, so only a same-size replacement fits
+                    let replacement = hash.and_then(|hash| self.texture_pack.replacement(hash)).filter(|replacement| {
+                        format_id & TEXTURE_UNNORMALIZED == 0 || (replacement.width, replacement.height) == (width, height)
+                    });
+                    match replacement {
+                        Some(replacement) => {
+                            self.backend.upload_texture(offset, replacement.width, replacement.height, &replacement.pixels)
+                        }
+                        None => self.backend.upload_texture(offset, width, height, data),
+                    }
+                }
+                self.backend.bind_texture(unit as u32, offset);
+            }
+        }
+    }
+
+    /// Read a texture from local memory and queue its decode, returning whether it was queued
+    fn queue_texture_decode(&mut self, offset: u32, format_id: u8, width: u16, height: u16) -> bool {
+        let Some(decoder) = &self.decoder else {
+            return false;
+        };
+        if width == 0 || height == 0 || !texture_decode::is_supported(format_id) {
+            return false;
+        }
+        let size = texture_decode::level_size(format_id, width.into(), height.into());
+        let data = match self.memory.read_rsx_bytes(offset, size) {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::debug!("Failed to read texture at 0x{:08x}: {}", offset, e);
+                return false;
+            }
+        };
+        if self.texture_pack.is_active() {
+            let hash = texture_pack::texture_hash(format_id & !TEXTURE_UNNORMALIZED, width, height, &data);
+            self.texture_pack.track(offset, hash);
+        }
+        let descriptor = Texture { offset, format: format_id & !TEXTURE_UNNORMALIZED, width, height, ..Texture::new() };
+        let future = decoder.decode(format_id, width.into(), height.into(), data);
+        self.textures.insert_pending(offset, descriptor, future, self.flip_count);
+        true
+    }
+
+    /// Cache a decoded video frame as the linear ARGB8 texture at `offset`
+    ///
+    /// `pixels` are RGBA8 rows, shared with the video decoder rather than
+    /// copied. The frame is uploaded right away, so draws sampling it neither
+    /// read local memory back nor wait on the decode workers.
+    pub fn import_video_frame(&mut self, offset: u32, width: u16, height: u16, pixels: Arc<Vec<u8>>) {
+        if pixels.len() != usize::from(width) * usize::from(height) * 4 {
+            tracing::warn!("Video frame at 0x{:08x} does not match its {}x{} size", offset, width, height);
+            return;
+        }
+        let descriptor = Texture {
+            offset,
+            format: format::ARGB8 | TEXTURE_LINEAR,
+            width,
+            height,
+            pitch: width.saturating_mul(4),
+            ..Texture::new()
+        };
+        self.backend.upload_texture(offset, width.into(), height.into(), &pixels);
+        self.textures.insert_shared(offset, descriptor, pixels, self.flip_count);
+    }
+
+    /// Queue textures seen for the first time for saving, skipping the `dumped` hashes
+    pub fn set_texture_dumping(&mut self, dumping: bool, dumped: impl IntoIterator<Item = u64>) {
+        self.texture_pack.set_dumping(dumping);
+        self.texture_pack.mark_dumped(dumped);
+    }
+
+    /// Take the decoded textures queued for saving
+    pub fn take_texture_dumps(&mut self) -> Vec<TextureDump> {
+        self.texture_pack.take_dumps()
+    }
+
+    /// Upload these textures in place of the guest ones with the same hash
+    ///
+    /// Cached textures are decoded again, so the change shows right away.
+    pub fn set_texture_replacements(&mut self, replacements: HashMap<u64, ReplacementTexture>) {
+        if self.texture_pack.replacement_count() == 0 && replacements.is_empty() {
+            return;
+        }
+        self.texture_pack.set_replacements(replacements);
+        self.textures.clear();
+    }
+
+    /// Textures cached or still decoding
+    pub fn texture_cache(&self) -> &TextureCache {
+        &self.textures
+    }
+
+    /// Shader programs translated since the thread was created
+    pub fn shader_compile_count(&self) -> u64 {
+        self.shaders.compiled_count()
+    }
+
+    /// Flips processed since the thread was created
+    pub fn flip_count(&self) -> u64 {
+        self.flip_count
+    }
+
+    /// Unimplemented methods the game wrote so far, with how often, by method
+    pub fn unimplemented_methods(&self) -> Vec<(u32, u64)> {
+        self.unimplemented_methods.iter().map(|(&method, &count)| (method, count)).collect()
+    }
+
+    /// Work done in the last finished frame
+    pub fn frame_counters(&self) -> RsxFrameCounters {
+        self.counters.last_frame()
+    }
+
+    /// Work done since the thread was created
+    pub fn total_counters(&self) -> RsxFrameCounters {
+        self.counters.total()
+    }
+
+    /// Display buffer the game last flipped to
+    pub fn last_flip_buffer(&self) -> u32 {
+        self.last_flip_buffer
+    }
+
+    /// Skip clears and draws, e.g. to drop frames while emulation is behind
+    ///
+    /// Register state keeps updating so the next rendered frame is correct.
+    pub fn set_skip_draws(&mut self, skip: bool) {
+        self.skip_draws = skip;
+    }
+
+    /// Check if clears and draws are being skipped
+    pub fn skip_draws(&self) -> bool {
+        self.skip_draws
+    }
+
     /// Convert RSX primitive type to backend format
     fn convert_primitive_type(&self) -> crate::backend::PrimitiveType {
         use crate::backend::PrimitiveType;
@@ -186,6 +590,52 @@ impl RsxThread {
     pub fn get_dimensions(&self) -> (u32, u32) {
         self.backend.get_dimensions()
     }
+
+    /// Internal resolution surfaces are rendered at
+    pub fn render_scale(&self) -> RenderScale {
+        self.render_scale
+    }
+
+    /// Change the internal resolution; surfaces created from now on use it
+    pub fn set_render_scale(&mut self, scale: RenderScale) {
+        if scale != self.render_scale {
+            tracing::info!("Render scale set to {}%", scale.percentage);
+            self.render_scale = scale;
+        }
+    }
+
+    /// Save the registers and pending commands for a savestate
+    ///
+    /// Backend resources are not saved; surfaces and textures are rebuilt from
+    /// guest memory as the next frame draws.
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.u8(match self.state {
+            RsxThreadState::Stopped => 0,
+            RsxThreadState::Running => 1,
+            RsxThreadState::Idle => 2,
+        });
+        self.gfx_state.save_state(w);
+        self.reports.save_state(w);
+        self.blit_state.save_state(w);
+        self.fifo.save_state(w);
+    }
+
+    /// Restore state written by [`RsxThread::save_state`]
+    pub fn load_state(&mut self, r: &mut StateReader) -> io::Result<()> {
+        self.state = match r.u8()? {
+            0 => RsxThreadState::Stopped,
+            1 => RsxThreadState::Running,
+            2 => RsxThreadState::Idle,
+            state => return Err(invalid(&format!("invalid RSX thread state {}", state))),
+        };
+        self.gfx_state.load_state(r)?;
+        self.reports.load_state(r)?;
+        self.blit_state.load_state(r)?;
+        self.counters.invalidate();
+        self.invalidate_constants();
+        self.textures.clear();
+        self.fifo.load_state(r)
+    }
 }
 
 #[cfg(test)]
@@ -199,6 +649,240 @@ mod tests {
         assert_eq!(thread.state, RsxThreadState::Stopped);
     }
 
+    #[test]
+    fn test_process_commands_while() {
+        let memory = MemoryManager::new().unwrap();
+        let mut thread = RsxThread::new(memory);
+        thread.fifo.push(RsxCommand { method: 0x0310, data: 1 });
+        thread.fifo.push(RsxCommand { method: 0x1D94, data: 0xF3 });
+
+        // Stop before the clear
+        thread.process_commands_while(|_, cmd| cmd.method != 0x1D94);
+        assert!(thread.gfx_state.blend_enable);
+        assert_eq!(thread.fifo.len(), 1);
+
+        thread.process_commands();
+        assert!(thread.fifo.is_empty());
+    }
+
+    #[test]
+    fn test_flip_count() {
+        let memory = MemoryManager::new().unwrap();
+        let mut thread = RsxThread::new(memory);
+        thread.fifo.push(RsxCommand { method: GCM_FLIP_COMMAND, data: 0 });
+        thread.fifo.push(RsxCommand { method: GCM_FLIP_COMMAND, data: 1 });
+        thread.process_commands();
+        assert_eq!(thread.flip_count(), 2);
+        assert_eq!(thread.last_flip_buffer(), 1);
+    }
+
+    #[test]
+    fn test_unimplemented_methods_counted() {
+        let memory = MemoryManager::new().unwrap();
+        let mut thread = RsxThread::new(memory);
+        thread.fifo.push(RsxCommand { method: 0x0310, data: 1 });
+        thread.fifo.push(RsxCommand { method: 0x1FFC, data: 0 });
+        thread.fifo.push(RsxCommand { method: 0x1FFC, data: 1 });
+        thread.process_commands();
+        assert_eq!(thread.unimplemented_methods(), [(0x1FFC, 2)]);
+    }
+
+    #[test]
+    fn test_frame_counters() {
+        let memory = MemoryManager::new().unwrap();
+        let mut thread = RsxThread::new(memory);
+        // Two triangles, drawn again in the next frame with draws skipped
+        thread.fifo.push(RsxCommand { method: 0x1810, data: 6 << DRAW_COUNT_SHIFT });
+        thread.fifo.push(RsxCommand { method: 0x1814, data: 6 << DRAW_COUNT_SHIFT });
+        thread.process_commands();
+        thread.end_frame();
+        let frame = thread.frame_counters();
+        assert_eq!((frame.draw_calls, frame.vertices, frame.triangles), (2, 12, 4));
+        assert_eq!((frame.pipeline_cache_hits, frame.pipeline_cache_misses), (1, 1));
+
+        thread.set_skip_draws(true);
+        thread.fifo.push(RsxCommand { method: 0x1810, data: 6 << DRAW_COUNT_SHIFT });
+        thread.process_commands();
+        thread.end_frame();
+        assert_eq!(thread.frame_counters().draw_calls, 0);
+        assert_eq!(thread.total_counters().draw_calls, 2);
+    }
+
+    #[test]
+    fn test_fragment_constant_patch_reuses_pipeline() {
+        let memory = MemoryManager::new().unwrap();
+        let mut thread = RsxThread::new(memory.clone());
+        // MOV R0, {1, 0, 0, 1} ending the program, with its inline constant
+        let mut program = [
+            0x3E01_0100u32, 0xC802_0001, 0xC800_0001, 0xC800_0001,
+            0x0000_3F80, 0x0000_0000, 0x0000_0000, 0x0000_3F80,
+        ];
+        let write_program = |program: &[u32]| {
+            let bytes: Vec<u8> = program.iter().flat_map(|word| word.to_be_bytes()).collect();
+            memory.write_rsx_bytes(0x1000, &bytes).unwrap();
+        };
+        write_program(&program);
+        thread.fifo.push(RsxCommand { method: 0x0848, data: 0x1000 | SHADER_LOCATION_LOCAL });
+        thread.fifo.push(RsxCommand { method: 0x1810, data: 3 << DRAW_COUNT_SHIFT });
+        thread.process_commands();
+        assert_eq!(thread.fragment_constants, Some(vec![[1.0, 0.0, 0.0, 1.0]]));
+
+        // Patching the constant uploads it without translating again
+        program[5] = 0x0000_3F80;
+        write_program(&program);
+        thread.fifo.push(RsxCommand { method: 0x1810, data: 3 << DRAW_COUNT_SHIFT });
+        thread.process_commands();
+        thread.end_frame();
+        assert_eq!(thread.fragment_constants, None);
+        assert_eq!(thread.shader_compile_count(), 2);
+        let frame = thread.frame_counters();
+        assert_eq!((frame.pipeline_cache_hits, frame.pipeline_cache_misses), (1, 1));
+
+        // Vertex constants are uploaded when written
+        thread.fifo.push(RsxCommand { method: 0x1F00, data: 1.0f32.to_bits() });
+        thread.process_commands();
+        assert!(thread.vertex_constants_dirty);
+        thread.fifo.push(RsxCommand { method: 0x1810, data: 3 << DRAW_COUNT_SHIFT });
+        thread.process_commands();
+        assert!(!thread.vertex_constants_dirty);
+    }
+
+    #[test]
+    fn test_blit_invalidates_textures() {
+        let memory = MemoryManager::new().unwrap();
+        let mut thread = RsxThread::new(memory.clone());
+        let descriptor = Texture { offset: 0x1000, format: 0x85, width: 2, height: 2, ..Texture::new() };
+        thread.textures.insert(0x1000, descriptor, vec![0; 16], 0);
+
+        // Copy 4 bytes over the texture's last texel
+        memory.write_rsx_bytes(0x100, &[1, 2, 3, 4]).unwrap();
+        thread.fifo.push(RsxCommand { method: blit::NV0039_OFFSET_IN, data: 0x100 });
+        thread.fifo.push(RsxCommand { method: blit::NV0039_OFFSET_OUT, data: 0x100C });
+        thread.fifo.push(RsxCommand { method: blit::NV0039_LINE_LENGTH_IN, data: 4 });
+        thread.fifo.push(RsxCommand { method: blit::NV0039_LINE_COUNT, data: 1 });
+        thread.fifo.push(RsxCommand { method: blit::NV0039_BUFFER_NOTIFY, data: 0 });
+        thread.fifo.push(RsxCommand { method: 0x2FFC, data: 0 });
+        thread.process_commands();
+        assert_eq!(memory.read_rsx_bytes(0x100C, 4).unwrap(), [1, 2, 3, 4]);
+        assert!(thread.textures.descriptor(0x1000).is_none());
+        assert_eq!(thread.unimplemented_methods(), [(0x2FFC, 1)]);
+    }
+
+    #[test]
+    fn test_import_video_frame() {
+        let memory = MemoryManager::new().unwrap();
+        let mut thread = RsxThread::new(memory);
+        let pixels = Arc::new(vec![0x80; 4 * 2 * 4]);
+        thread.import_video_frame(0x2000, 4, 2, pixels.clone());
+        assert_eq!(Arc::strong_count(&pixels), 2);
+
+        // Sampling it unnormalized uses the frame instead of decoding local memory
+        thread.gfx_state.texture_control[0] = TEXTURE_ENABLE;
+        thread.gfx_state.texture_offset[0] = 0x2000;
+        thread.gfx_state.texture_format[0] = u32::from(format::ARGB8 | TEXTURE_LINEAR | TEXTURE_UNNORMALIZED) << 8 | 1;
+        thread.gfx_state.texture_image_rect[0] = (4 << 16) | 2;
+        thread.prepare_textures();
+        assert_eq!(thread.textures.pending_count(), 0);
+        let (_, data) = thread.textures.get(0x2000, 0).unwrap();
+        assert_eq!(data, pixels.as_slice());
+
+        // Frames of the wrong size are dropped
+        thread.import_video_frame(0x3000, 4, 4, pixels);
+        assert!(thread.textures.descriptor(0x3000).is_none());
+    }
+
+    #[test]
+    fn test_texture_dumping() {
+        let memory = MemoryManager::new().unwrap();
+        let mut thread = RsxThread::new(memory.clone());
+        let texels = [0x11u8; 2 * 2 * 4];
+        memory.write_rsx_bytes(0x4000, &texels).unwrap();
+        let format_id = format::ARGB8 | TEXTURE_LINEAR;
+        let hash = texture_pack::texture_hash(format_id, 2, 2, &texels);
+        thread.set_texture_dumping(true, []);
+
+        thread.gfx_state.texture_control[0] = TEXTURE_ENABLE;
+        thread.gfx_state.texture_offset[0] = 0x4000;
+        thread.gfx_state.texture_format[0] = u32::from(format_id) << 8 | 1;
+        thread.gfx_state.texture_image_rect[0] = (2 << 16) | 2;
+        for _ in 0..1000 {
+            thread.prepare_textures();
+            if !thread.textures.is_pending(0x4000) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        let dumps = thread.take_texture_dumps();
+        assert_eq!(dumps.len(), 1);
+        assert_eq!((dumps[0].hash, dumps[0].width, dumps[0].height), (hash, 2, 2));
+        assert_eq!(dumps[0].pixels.len(), 16);
+
+        // A replacement drops the cached texture so it is decoded and swapped again
+        let replacement = ReplacementTexture { width: 4, height: 4, pixels: Arc::new(vec![0xFF; 64]) };
+        thread.set_texture_replacements(HashMap::from([(hash, replacement)]));
+        assert!(thread.textures.descriptor(0x4000).is_none());
+        thread.prepare_textures();
+        assert!(thread.textures.descriptor(0x4000).is_some());
+        // Each texture is dumped once
+        assert!(thread.take_texture_dumps().is_empty());
+    }
+
+    #[test]
+    fn test_reports_and_conditional_render() {
+        let memory = MemoryManager::new().unwrap();
+        let mut thread = RsxThread::new(memory.clone());
+        thread.gfx_state.surface_clip_width = 16;
+        thread.gfx_state.surface_clip_height = 16;
+        let read_report = |offset: u32| memory.read_rsx_bytes(report::REPORT_AREA_OFFSET + offset, 16).unwrap();
+
+        // Count a draw, report it at 0x10 and a cleared counter at 0x20
+        thread.fifo.push(RsxCommand { method: 0x1DA0, data: 1 });
+        thread.fifo.push(RsxCommand { method: 0x1810, data: 3 << DRAW_COUNT_SHIFT });
+        thread.fifo.push(RsxCommand { method: 0x1800, data: (report::REPORT_ZPASS_PIXEL_COUNT << 24) | 0x10 });
+        thread.fifo.push(RsxCommand { method: 0x17C8, data: report::REPORT_ZPASS_PIXEL_COUNT });
+        thread.fifo.push(RsxCommand { method: 0x1800, data: (report::REPORT_ZPASS_PIXEL_COUNT << 24) | 0x20 });
+        thread.process_commands();
+        assert_eq!(read_report(0x10)[8..12], 256u32.to_be_bytes());
+        assert_eq!(read_report(0x20)[8..12], [0; 4]);
+
+        // Timestamps keep the value and count up
+        thread.fifo.push(RsxCommand { method: 0x1800, data: 0x10 });
+        thread.process_commands();
+        let timestamp = read_report(0x10);
+        assert_eq!(timestamp[8..12], 256u32.to_be_bytes());
+        assert!(u64::from_be_bytes(timestamp[..8].try_into().unwrap()) > 0);
+
+        // Only the draw predicated on the visible report is drawn
+        thread.fifo.push(RsxCommand { method: 0x1E98, data: 0x0200_0020 });
+        thread.fifo.push(RsxCommand { method: 0x1810, data: 3 << DRAW_COUNT_SHIFT });
+        thread.fifo.push(RsxCommand { method: 0x1E98, data: 0x0200_0010 });
+        thread.fifo.push(RsxCommand { method: 0x1810, data: 3 << DRAW_COUNT_SHIFT });
+        thread.fifo.push(RsxCommand { method: 0x1E98, data: 0x0100_0000 });
+        thread.fifo.push(RsxCommand { method: 0x1810, data: 3 << DRAW_COUNT_SHIFT });
+        thread.process_commands();
+        thread.end_frame();
+        assert_eq!(thread.frame_counters().draw_calls, 3);
+    }
+
+    #[test]
+    fn test_texture_decode_on_draw() {
+        let memory = MemoryManager::new().unwrap();
+        let texels = [0xFFu8, 0x10, 0x20, 0x30].repeat(4);
+        unsafe { memory.rsx_ptr(0x1000).copy_from_nonoverlapping(texels.as_ptr(), texels.len()) };
+        let mut thread = RsxThread::new(memory);
+        thread.gfx_state.texture_control[0] = TEXTURE_ENABLE;
+        thread.gfx_state.texture_offset[0] = 0x1000;
+        thread.gfx_state.texture_format[0] = (u32::from(crate::texture::format::ARGB8) << 8) | 1;
+        thread.gfx_state.texture_image_rect[0] = (2 << 16) | 2;
+        thread.fifo.push(RsxCommand { method: 0x1810, data: 3 << DRAW_COUNT_SHIFT });
+        thread.process_commands();
+
+        let cache = &mut thread.textures;
+        let (descriptor, pixels) = cache.wait(0x1000, 0).unwrap();
+        assert_eq!((descriptor.width, descriptor.height), (2, 2));
+        assert_eq!(&pixels[..4], [0x10, 0x20, 0x30, 0xFF]);
+    }
+
     #[test]
     fn test_rsx_thread_init_backend() {
         let memory = MemoryManager::new().unwrap();
@@ -0,0 +1,164 @@
+//! Golden tests for RSX shader translation
+//!
+//! Every microcode blob in `tests/fixtures/shaders` is translated to SPIR-V
+//! and its disassembly compared against the `.spvasm` file next to it. Set
+//! `OC_BLESS_SHADERS=1` to rewrite the golden files after an intended change.
+
+use oc_rsx::shader::{FragmentProgram, ShaderStage, ShaderTranslator, SpirVModule, VertexProgram};
+use oc_rsx::spirv_disasm;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Bytes per RSX shader instruction
+const INSTRUCTION_SIZE: usize = 16;
+
+/// Environment variable that rewrites golden files instead of comparing
+const BLESS_VAR: &str = "OC_BLESS_SHADERS";
+
+/// Captured shader microcode
+#[derive(Debug)]
+struct ShaderFixture {
+    /// Blob path
+    path: PathBuf,
+    /// Stage the microcode is for
+    stage: ShaderStage,
+    /// Microcode words as the RSX reads them
+    words: Vec<u32>,
+}
+
+impl ShaderFixture {
+    /// Load a blob, taking its stage from the `.vp` or `.fp` extension
+    fn load(path: &Path) -> Result<Self, String> {
+        let stage = match path.extension().and_then(|e| e.to_str()) {
+            Some("vp") => ShaderStage::VERTEX,
+            Some("fp") => ShaderStage::FRAGMENT,
+            _ => return Err(format!("{}: not a .vp or .fp blob", path.display())),
+        };
+        let data = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if data.is_empty() || data.len() % INSTRUCTION_SIZE != 0 {
+            return Err(format!(
+                "{}: {} bytes is not a whole number of {}-byte instructions",
+                path.display(),
+                data.len(),
+                INSTRUCTION_SIZE
+            ));
+        }
+        let words = data
+            .chunks_exact(4)
+            .map(|c| u32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+            .collect();
+        Ok(Self { path: path.to_path_buf(), stage, words })
+    }
+
+    /// Path of the golden disassembly
+    fn golden_path(&self) -> PathBuf {
+        let mut name = self.path.file_name().unwrap().to_os_string();
+        name.push(".spvasm");
+        self.path.with_file_name(name)
+    }
+
+    /// Translate the microcode with a fresh translator
+    fn translate(&self) -> Result<SpirVModule, String> {
+        let mut translator = ShaderTranslator::new();
+        if self.stage == ShaderStage::VERTEX {
+            translator.translate_vertex(&VertexProgram::from_data(&self.words), 0)
+        } else {
+            translator.translate_fragment(&FragmentProgram::from_data(&self.words), 0)
+        }
+    }
+}
+
+fn fixture_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/shaders")
+}
+
+/// All microcode blobs, sorted by name
+fn fixtures() -> Vec<ShaderFixture> {
+    let mut paths: Vec<_> = fs::read_dir(fixture_dir())
+        .expect("fixture directory exists")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| matches!(path.extension().and_then(|e| e.to_str()), Some("vp" | "fp")))
+        .collect();
+    paths.sort();
+    paths.iter().map(|path| ShaderFixture::load(path).unwrap()).collect()
+}
+
+/// First line where `expected` and `actual` differ, for the failure message
+fn first_difference(expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    for line in 1.. {
+        match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => break,
+            (e, a) if e != a => {
+                return format!("line {}:\n  expected: {}\n  actual:   {}", line, e.unwrap_or("<end>"), a.unwrap_or("<end>"));
+            }
+            _ => {}
+        }
+    }
+    "trailing whitespace".to_string()
+}
+
+#[test]
+fn test_shader_golden() {
+    let bless = std::env::var_os(BLESS_VAR).is_some();
+    let fixtures = fixtures();
+    assert!(!fixtures.is_empty(), "no shader fixtures in {}", fixture_dir().display());
+
+    let mut failures = Vec::new();
+    for fixture in &fixtures {
+        let name = fixture.path.file_name().unwrap().to_string_lossy();
+        let module = match fixture.translate() {
+            Ok(module) => module,
+            Err(e) => {
+                failures.push(format!("{}: translation failed: {}", name, e));
+                continue;
+            }
+        };
+        assert_eq!(module.stage, fixture.stage, "{}: wrong stage", name);
+        let actual = match spirv_disasm::disassemble(&module.bytecode) {
+            Ok(text) => text,
+            Err(e) => {
+                failures.push(format!("{}: translator produced invalid SPIR-V: {}", name, e));
+                continue;
+            }
+        };
+
+        let golden = fixture.golden_path();
+        if bless {
+            fs::write(&golden, &actual).unwrap();
+            continue;
+        }
+        match fs::read_to_string(&golden) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => failures.push(format!(
+                "{}: output differs from {} at {}",
+                name,
+                golden.file_name().unwrap().to_string_lossy(),
+                first_difference(&expected, &actual)
+            )),
+            Err(_) => failures.push(format!("{}: no golden file", name)),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} shaders failed; rerun with {}=1 to accept the new output\n{}",
+        failures.len(),
+        fixtures.len(),
+        BLESS_VAR,
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn test_golden_files_have_fixtures() {
+    // A golden file without its blob means the blob was renamed or lost
+    for entry in fs::read_dir(fixture_dir()).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().and_then(|e| e.to_str()) == Some("spvasm") {
+            let blob = path.with_extension("");
+            assert!(blob.exists(), "{} has no microcode blob", path.display());
+        }
+    }
+}
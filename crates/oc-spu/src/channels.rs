@@ -1,8 +1,13 @@
 //! SPU channel system
 //!
 //! SPU channels are used for communication between SPU and PPU/MFC.
+//! The MFC channels (MFC_LSA through MFC_RdAtomicStat) need local storage
+//! and main memory, so the channel instructions hand them to the thread's
+//! [`Mfc`](crate::mfc::Mfc) instead.
 
 use std::collections::VecDeque;
+use std::io;
+use oc_core::savestate::{invalid, StateReader, StateWriter};
 
 /// SPU channel numbers
 pub mod channel_ids {
@@ -20,16 +25,38 @@ pub mod channel_ids {
     pub const SPU_WR_DECR: u32 = 7;
     /// SPU Read Decrementer
     pub const SPU_RD_DECR: u32 = 8;
+    /// MFC Write Multisource Synchronization Request
+    pub const MFC_WR_MSSYNC_REQ: u32 = 9;
+    /// SPU Read Event Mask
+    pub const SPU_RD_EVENT_MASK: u32 = 11;
+    /// MFC Read Tag Mask
+    pub const MFC_RD_TAG_MASK: u32 = 12;
+    /// SPU Read Machine Status
+    pub const SPU_RD_MACH_STAT: u32 = 13;
+    /// MFC Local Storage Address of the next command
+    pub const MFC_LSA: u32 = 16;
+    /// MFC Effective Address High of the next command
+    pub const MFC_EAH: u32 = 17;
+    /// MFC Effective Address Low (or list address) of the next command
+    pub const MFC_EAL: u32 = 18;
+    /// MFC Transfer Size (or list size) of the next command
+    pub const MFC_SIZE: u32 = 19;
+    /// MFC Tag ID of the next command
+    pub const MFC_TAG_ID: u32 = 20;
+    /// MFC Command Opcode, issuing the command
+    pub const MFC_CMD: u32 = 21;
     /// MFC Write Tag Mask
-    pub const MFC_WR_TAG_MASK: u32 = 12;
+    pub const MFC_WR_TAG_MASK: u32 = 22;
+    /// MFC Write Tag Status Update Request
+    pub const MFC_WR_TAG_UPDATE: u32 = 23;
     /// MFC Read Tag Status
-    pub const MFC_RD_TAG_STAT: u32 = 13;
+    pub const MFC_RD_TAG_STAT: u32 = 24;
     /// MFC Read List Stall Notify
-    pub const MFC_RD_LIST_STALL: u32 = 14;
+    pub const MFC_RD_LIST_STALL: u32 = 25;
     /// MFC Write List Stall Ack
-    pub const MFC_WR_LIST_STALL_ACK: u32 = 15;
+    pub const MFC_WR_LIST_STALL_ACK: u32 = 26;
     /// MFC Read Atomic Status
-    pub const MFC_RD_ATOMIC_STAT: u32 = 16;
+    pub const MFC_RD_ATOMIC_STAT: u32 = 27;
     /// SPU Write Outbound Mailbox
     pub const SPU_WR_OUT_MBOX: u32 = 28;
     /// SPU Read Inbound Mailbox
@@ -158,8 +185,6 @@ pub struct SpuChannels {
     event_mask: u32,
     /// Event status
     event_status: u32,
-    /// Tag mask for MFC
-    tag_mask: u32,
     /// Decrementer value
     decrementer: u32,
     /// Decrementer start value
@@ -197,7 +222,6 @@ impl SpuChannels {
             channels,
             event_mask: 0,
             event_status: 0,
-            tag_mask: 0,
             decrementer: 0,
             decrementer_start: 0,
             signal1: 0,
@@ -234,7 +258,8 @@ impl SpuChannels {
             SPU_RD_DECR => Some(self.decrementer),
             SPU_RD_SIGNAL1 => self.read_signal1(),
             SPU_RD_SIGNAL2 => self.read_signal2(),
-            MFC_RD_TAG_STAT => Some(0xFFFFFFFF), // All tags complete (simplified)
+            SPU_RD_EVENT_MASK => Some(self.event_mask),
+            SPU_RD_MACH_STAT => Some(0),
             _ if (channel as usize) < NUM_CHANNELS => {
                 let ch = &mut self.channels[channel as usize];
                 let result = ch.pop();
@@ -257,7 +282,8 @@ impl SpuChannels {
             SPU_RD_DECR => Ok(self.decrementer),
             SPU_RD_SIGNAL1 => self.read_signal1().ok_or(()),
             SPU_RD_SIGNAL2 => self.read_signal2().ok_or(()),
-            MFC_RD_TAG_STAT => Ok(0xFFFFFFFF),
+            SPU_RD_EVENT_MASK => Ok(self.event_mask),
+            SPU_RD_MACH_STAT => Ok(0),
             _ if (channel as usize) < NUM_CHANNELS => {
                 self.channels[channel as usize].pop().ok_or(())
             }
@@ -280,10 +306,8 @@ impl SpuChannels {
                 self.set_decrementer(value);
                 true
             }
-            MFC_WR_TAG_MASK => {
-                self.tag_mask = value;
-                true
-            }
+            // DMA completes as it is issued, so there is nothing to wait for
+            MFC_WR_MSSYNC_REQ => true,
             _ if (channel as usize) < NUM_CHANNELS => {
                 let ch = &mut self.channels[channel as usize];
                 let success = ch.push(value);
@@ -310,8 +334,7 @@ impl SpuChannels {
     pub fn get_count(&self, channel: u32) -> u32 {
         match channel {
             SPU_RD_EVENT_STAT => 1,
-            SPU_RD_DECR => 1,
-            MFC_RD_TAG_STAT => 1,
+            SPU_RD_DECR | SPU_RD_EVENT_MASK | SPU_RD_MACH_STAT | MFC_WR_MSSYNC_REQ => 1,
             _ if (channel as usize) < NUM_CHANNELS => {
                 self.channels[channel as usize].count()
             }
@@ -324,6 +347,11 @@ impl SpuChannels {
         self.channels[SPU_WR_OUT_MBOX as usize].pop()
     }
 
+    /// Get outbound interrupt mailbox
+    pub fn get_outbound_interrupt_mailbox(&mut self) -> Option<u32> {
+        self.channels[SPU_WR_OUT_INTR_MBOX as usize].pop()
+    }
+
     /// Put to inbound mailbox
     pub fn put_inbound_mailbox(&mut self, value: u32) -> bool {
         self.channels[SPU_RD_IN_MBOX as usize].push(value)
@@ -334,11 +362,6 @@ impl SpuChannels {
         self.event_mask
     }
 
-    /// Get tag mask
-    pub fn get_tag_mask(&self) -> u32 {
-        self.tag_mask
-    }
-
     /// Send signal notification 1 (from PPU to SPU)
     pub fn send_signal1(&mut self, value: u32) {
         self.signal1 = value;
@@ -488,6 +511,64 @@ impl SpuChannels {
     pub fn has_pending_events(&self) -> bool {
         self.get_event_status() != 0
     }
+    /// Save channel contents and the event and decrementer state
+    pub fn save_state(&self, w: &mut StateWriter) {
+        for channel in &self.channels {
+            w.count(channel.data.len());
+            for &value in &channel.data {
+                w.u32(value);
+            }
+            w.count(channel.max_depth);
+            w.u32(channel.count);
+            w.u64(channel.timeout_cycles);
+            w.u64(channel.wait_start_cycle);
+        }
+        for value in [
+            self.event_mask,
+            self.event_status,
+            self.decrementer,
+            self.decrementer_start,
+            self.signal1,
+            self.signal2,
+        ] {
+            w.u32(value);
+        }
+        w.bool(self.signal1_pending);
+        w.bool(self.signal2_pending);
+        w.bool(self.decrementer_event_pending);
+        w.u64(self.cycle_counter);
+        w.u64(self.last_decr_update);
+    }
+
+    /// Restore state written by [`SpuChannels::save_state`]
+    pub fn load_state(&mut self, r: &mut StateReader) -> io::Result<()> {
+        for channel in &mut self.channels {
+            let len = r.count()?;
+            channel.data.clear();
+            for _ in 0..len {
+                channel.data.push_back(r.u32()?);
+            }
+            channel.max_depth = r.u32()? as usize;
+            if channel.data.len() > channel.max_depth {
+                return Err(invalid("SPU channel holds more entries than its depth"));
+            }
+            channel.count = r.u32()?;
+            channel.timeout_cycles = r.u64()?;
+            channel.wait_start_cycle = r.u64()?;
+        }
+        self.event_mask = r.u32()?;
+        self.event_status = r.u32()?;
+        self.decrementer = r.u32()?;
+        self.decrementer_start = r.u32()?;
+        self.signal1 = r.u32()?;
+        self.signal2 = r.u32()?;
+        self.signal1_pending = r.bool()?;
+        self.signal2_pending = r.bool()?;
+        self.decrementer_event_pending = r.bool()?;
+        self.cycle_counter = r.u64()?;
+        self.last_decr_update = r.u64()?;
+        Ok(())
+    }
 }
 
 impl Default for SpuChannels {
@@ -71,12 +71,14 @@ impl SpuDecoder {
     }
 
     /// Extract RRR-type fields: rc, rb, ra, rt
+    ///
+    /// Unlike the other forms, RRR puts rt in the high field and rc in the low one.
     #[inline]
     pub fn rrr_form(opcode: u32) -> (u8, u8, u8, u8) {
-        let rt = (opcode & 0x7F) as u8;
+        let rc = (opcode & 0x7F) as u8;
         let ra = ((opcode >> 7) & 0x7F) as u8;
         let rb = ((opcode >> 14) & 0x7F) as u8;
-        let rc = ((opcode >> 21) & 0x7F) as u8;
+        let rt = ((opcode >> 21) & 0x7F) as u8;
         (rc, rb, ra, rt)
     }
 
@@ -100,6 +102,15 @@ impl SpuDecoder {
         (i7, ra, rt)
     }
 
+    /// Extract RI8-type fields: i8 (unsigned scale), ra, rt
+    #[inline]
+    pub fn ri8_form(opcode: u32) -> (u8, u8, u8) {
+        let rt = (opcode & 0x7F) as u8;
+        let ra = ((opcode >> 7) & 0x7F) as u8;
+        let i8_val = ((opcode >> 14) & 0xFF) as u8;
+        (i8_val, ra, rt)
+    }
+
     /// Extract RI10-type fields: i10, ra, rt
     #[inline]
     pub fn ri10_form(opcode: u32) -> (i16, u8, u8) {
@@ -188,4 +199,11 @@ mod tests {
         assert_eq!(ra, 0);
         assert_eq!(i10, 0);
     }
+
+    #[test]
+    fn test_rrr_form() {
+        // shufb $4, $1, $2, $3
+        let opcode = (0b1011 << 28) | (4 << 21) | (2 << 14) | (1 << 7) | 3;
+        assert_eq!(SpuDecoder::rrr_form(opcode), (3, 2, 1, 4));
+    }
 }
@@ -3,15 +3,15 @@
 use crate::thread::SpuThread;
 use oc_core::error::SpuError;
 
-/// Multiply - mpy rt, ra, rb
+/// Multiply - mpy rt, ra, rb (signed low halfwords)
 pub fn mpy(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
     let a = thread.regs.read_u32x4(ra as usize);
     let b = thread.regs.read_u32x4(rb as usize);
     let result = [
-        ((a[0] as i32).wrapping_mul(b[0] as i32)) as u32,
-        ((a[1] as i32).wrapping_mul(b[1] as i32)) as u32,
-        ((a[2] as i32).wrapping_mul(b[2] as i32)) as u32,
-        ((a[3] as i32).wrapping_mul(b[3] as i32)) as u32,
+        ((a[0] as i16 as i32) * (b[0] as i16 as i32)) as u32,
+        ((a[1] as i16 as i32) * (b[1] as i16 as i32)) as u32,
+        ((a[2] as i16 as i32) * (b[2] as i16 as i32)) as u32,
+        ((a[3] as i16 as i32) * (b[3] as i16 as i32)) as u32,
     ];
     thread.regs.write_u32x4(rt as usize, result);
     thread.advance_pc();
@@ -53,10 +53,10 @@ pub fn shl(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuErro
     let a = thread.regs.read_u32x4(ra as usize);
     let b = thread.regs.read_u32x4(rb as usize);
     let result = [
-        a[0] << (b[0] & 0x3F),
-        a[1] << (b[1] & 0x3F),
-        a[2] << (b[2] & 0x3F),
-        a[3] << (b[3] & 0x3F),
+        shift_left(a[0], b[0]),
+        shift_left(a[1], b[1]),
+        shift_left(a[2], b[2]),
+        shift_left(a[3], b[3]),
     ];
     thread.regs.write_u32x4(rt as usize, result);
     thread.advance_pc();
@@ -66,12 +66,12 @@ pub fn shl(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuErro
 /// Shift Left Word Immediate - shli rt, ra, i7
 pub fn shli(thread: &mut SpuThread, i7: i8, ra: u8, rt: u8) -> Result<(), SpuError> {
     let a = thread.regs.read_u32x4(ra as usize);
-    let shift = (i7 & 0x3F) as u32;
+    let shift = i7 as u32;
     let result = [
-        a[0] << shift,
-        a[1] << shift,
-        a[2] << shift,
-        a[3] << shift,
+        shift_left(a[0], shift),
+        shift_left(a[1], shift),
+        shift_left(a[2], shift),
+        shift_left(a[3], shift),
     ];
     thread.regs.write_u32x4(rt as usize, result);
     thread.advance_pc();
@@ -138,6 +138,417 @@ pub fn sfh(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuErro
     Ok(())
 }
 
+/// Shift a word left by the low 6 bits of `count`; 32 and up clear it
+fn shift_left(value: u32, count: u32) -> u32 {
+    value.checked_shl(count & 0x3F).unwrap_or(0)
+}
+
+/// Shift a word right by the low 6 bits of the negated `count`, as the rotate and mask forms do
+fn shift_right(value: u32, count: u32) -> u32 {
+    value.checked_shr(count.wrapping_neg() & 0x3F).unwrap_or(0)
+}
+
+/// Shift a word right arithmetically by the low 6 bits of the negated `count`
+fn shift_right_algebraic(value: u32, count: u32) -> u32 {
+    ((value as i32) >> (count.wrapping_neg() & 0x3F).min(31)) as u32
+}
+
+/// Apply `f` to each pair of halfwords
+fn per_halfword(a: [u32; 4], b: [u32; 4], f: impl Fn(u16, u16) -> u16) -> [u32; 4] {
+    std::array::from_fn(|i| {
+        let hi = f((a[i] >> 16) as u16, (b[i] >> 16) as u16);
+        let lo = f(a[i] as u16, b[i] as u16);
+        ((hi as u32) << 16) | lo as u32
+    })
+}
+
+/// Apply `f` to each pair of bytes
+fn per_byte(a: [u32; 4], b: [u32; 4], f: impl Fn(u8, u8) -> u8) -> [u32; 4] {
+    std::array::from_fn(|i| {
+        let (a, b) = (a[i].to_be_bytes(), b[i].to_be_bytes());
+        u32::from_be_bytes(std::array::from_fn(|j| f(a[j], b[j])))
+    })
+}
+
+/// Add Word - a rt, ra, rb
+pub fn a(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    thread.regs.write_u32x4(rt as usize, std::array::from_fn(|i| a[i].wrapping_add(b[i])));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Add Word Immediate - ai rt, ra, i10
+pub fn ai(thread: &mut SpuThread, i10: i16, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let imm = i10 as i32 as u32;
+    thread.regs.write_u32x4(rt as usize, a.map(|a| a.wrapping_add(imm)));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Add Halfword Immediate - ahi rt, ra, i10
+pub fn ahi(thread: &mut SpuThread, i10: i16, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let result = per_halfword(a, [0; 4], |a, _| a.wrapping_add(i10 as u16));
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Subtract From Word - sf rt, ra, rb (rb - ra)
+pub fn sf(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    thread.regs.write_u32x4(rt as usize, std::array::from_fn(|i| b[i].wrapping_sub(a[i])));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Subtract From Word Immediate - sfi rt, ra, i10
+pub fn sfi(thread: &mut SpuThread, i10: i16, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let imm = i10 as i32 as u32;
+    thread.regs.write_u32x4(rt as usize, a.map(|a| imm.wrapping_sub(a)));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Subtract From Halfword Immediate - sfhi rt, ra, i10
+pub fn sfhi(thread: &mut SpuThread, i10: i16, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let result = per_halfword(a, [0; 4], |a, _| (i10 as u16).wrapping_sub(a));
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Carry Generate - cg rt, ra, rb
+pub fn cg(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    thread.regs.write_u32x4(rt as usize, std::array::from_fn(|i| a[i].overflowing_add(b[i]).1 as u32));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Borrow Generate - bg rt, ra, rb
+pub fn bg(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    thread.regs.write_u32x4(rt as usize, std::array::from_fn(|i| (b[i] >= a[i]) as u32));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Add Extended - addx rt, ra, rb (carry in from rt)
+pub fn addx(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    let t = thread.regs.read_u32x4(rt as usize);
+    let result = std::array::from_fn(|i| a[i].wrapping_add(b[i]).wrapping_add(t[i] & 1));
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Subtract From Extended - sfx rt, ra, rb (borrow in from rt)
+pub fn sfx(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    let t = thread.regs.read_u32x4(rt as usize);
+    let result = std::array::from_fn(|i| b[i].wrapping_add(!a[i]).wrapping_add(t[i] & 1));
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Carry Generate Extended - cgx rt, ra, rb
+pub fn cgx(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    let t = thread.regs.read_u32x4(rt as usize);
+    let result = std::array::from_fn(|i| ((a[i] as u64 + b[i] as u64 + (t[i] & 1) as u64) >> 32) as u32);
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Borrow Generate Extended - bgx rt, ra, rb
+pub fn bgx(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    let t = thread.regs.read_u32x4(rt as usize);
+    let result = std::array::from_fn(|i| if t[i] & 1 != 0 { b[i] >= a[i] } else { b[i] > a[i] } as u32);
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Multiply Immediate - mpyi rt, ra, i10
+pub fn mpyi(thread: &mut SpuThread, i10: i16, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    thread.regs.write_u32x4(rt as usize, a.map(|a| ((a as i16 as i32) * i10 as i32) as u32));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Multiply Unsigned Immediate - mpyui rt, ra, i10
+pub fn mpyui(thread: &mut SpuThread, i10: i16, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let imm = i10 as u16 as u32;
+    thread.regs.write_u32x4(rt as usize, a.map(|a| (a & 0xFFFF) * imm));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Multiply and Add - mpya rt, ra, rb, rc
+pub fn mpya(thread: &mut SpuThread, rc: u8, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    let c = thread.regs.read_u32x4(rc as usize);
+    let result = std::array::from_fn(|i| ((a[i] as i16 as i32) * (b[i] as i16 as i32)).wrapping_add(c[i] as i32) as u32);
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Multiply and Shift Right - mpys rt, ra, rb
+pub fn mpys(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    let result = std::array::from_fn(|i| (((a[i] as i16 as i32) * (b[i] as i16 as i32)) >> 16) as u32);
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Multiply High High - mpyhh rt, ra, rb
+pub fn mpyhh(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    let result = std::array::from_fn(|i| (((a[i] >> 16) as i16 as i32) * ((b[i] >> 16) as i16 as i32)) as u32);
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Multiply High High Unsigned - mpyhhu rt, ra, rb
+pub fn mpyhhu(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    thread.regs.write_u32x4(rt as usize, std::array::from_fn(|i| (a[i] >> 16) * (b[i] >> 16)));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Multiply High High and Add - mpyhha rt, ra, rb
+pub fn mpyhha(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    let t = thread.regs.read_u32x4(rt as usize);
+    let result = std::array::from_fn(|i| {
+        (((a[i] >> 16) as i16 as i32) * ((b[i] >> 16) as i16 as i32)).wrapping_add(t[i] as i32) as u32
+    });
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Multiply High High Unsigned and Add - mpyhhau rt, ra, rb
+pub fn mpyhhau(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    let t = thread.regs.read_u32x4(rt as usize);
+    let result = std::array::from_fn(|i| ((a[i] >> 16) * (b[i] >> 16)).wrapping_add(t[i]));
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Count Leading Zeros - clz rt, ra
+pub fn clz(thread: &mut SpuThread, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    thread.regs.write_u32x4(rt as usize, a.map(u32::leading_zeros));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Count Ones in Bytes - cntb rt, ra
+pub fn cntb(thread: &mut SpuThread, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    thread.regs.write_u32x4(rt as usize, per_byte(a, [0; 4], |a, _| a.count_ones() as u8));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Average Bytes - avgb rt, ra, rb
+pub fn avgb(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    let result = per_byte(a, b, |a, b| ((a as u16 + b as u16 + 1) >> 1) as u8);
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Absolute Differences of Bytes - absdb rt, ra, rb
+pub fn absdb(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    thread.regs.write_u32x4(rt as usize, per_byte(a, b, |a, b| a.abs_diff(b)));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Sum Bytes into Halfwords - sumb rt, ra, rb
+pub fn sumb(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    let sum = |word: u32| word.to_be_bytes().iter().map(|&byte| byte as u32).sum::<u32>();
+    thread.regs.write_u32x4(rt as usize, std::array::from_fn(|i| (sum(b[i]) << 16) | sum(a[i])));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Extend Sign Byte to Halfword - xsbh rt, ra
+pub fn xsbh(thread: &mut SpuThread, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    thread.regs.write_u32x4(rt as usize, per_halfword(a, [0; 4], |a, _| a as i8 as u16));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Extend Sign Halfword to Word - xshw rt, ra
+pub fn xshw(thread: &mut SpuThread, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    thread.regs.write_u32x4(rt as usize, a.map(|a| a as i16 as u32));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Extend Sign Word to Doubleword - xswd rt, ra
+pub fn xswd(thread: &mut SpuThread, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let sign = |word: u32| ((word as i32) >> 31) as u32;
+    thread.regs.write_u32x4(rt as usize, [sign(a[1]), a[1], sign(a[3]), a[3]]);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Rotate and Mask Word - rotm rt, ra, rb (logical shift right by -rb)
+pub fn rotm(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    thread.regs.write_u32x4(rt as usize, std::array::from_fn(|i| shift_right(a[i], b[i])));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Rotate and Mask Word Immediate - rotmi rt, ra, i7
+pub fn rotmi(thread: &mut SpuThread, i7: i8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    thread.regs.write_u32x4(rt as usize, a.map(|a| shift_right(a, i7 as u32)));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Rotate and Mask Algebraic Word - rotma rt, ra, rb
+pub fn rotma(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    thread.regs.write_u32x4(rt as usize, std::array::from_fn(|i| shift_right_algebraic(a[i], b[i])));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Rotate and Mask Algebraic Word Immediate - rotmai rt, ra, i7
+pub fn rotmai(thread: &mut SpuThread, i7: i8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    thread.regs.write_u32x4(rt as usize, a.map(|a| shift_right_algebraic(a, i7 as u32)));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Shift Left Halfword - shlh rt, ra, rb
+pub fn shlh(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    let result = per_halfword(a, b, |a, b| a.checked_shl((b & 0x1F) as u32).unwrap_or(0));
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Shift Left Halfword Immediate - shlhi rt, ra, i7
+pub fn shlhi(thread: &mut SpuThread, i7: i8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let result = per_halfword(a, [0; 4], |a, _| a.checked_shl((i7 & 0x1F) as u32).unwrap_or(0));
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Rotate Halfword - roth rt, ra, rb
+pub fn roth(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    let result = per_halfword(a, b, |a, b| a.rotate_left((b & 0xF) as u32));
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Rotate Halfword Immediate - rothi rt, ra, i7
+pub fn rothi(thread: &mut SpuThread, i7: i8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let result = per_halfword(a, [0; 4], |a, _| a.rotate_left((i7 & 0xF) as u32));
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Rotate and Mask Halfword - rothm rt, ra, rb
+pub fn rothm(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    let result = per_halfword(a, b, |a, b| a.checked_shr((b.wrapping_neg() & 0x1F) as u32).unwrap_or(0));
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Rotate and Mask Halfword Immediate - rothmi rt, ra, i7
+pub fn rothmi(thread: &mut SpuThread, i7: i8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let shift = (i7.wrapping_neg() & 0x1F) as u32;
+    let result = per_halfword(a, [0; 4], |a, _| a.checked_shr(shift).unwrap_or(0));
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Rotate and Mask Algebraic Halfword - rotmah rt, ra, rb
+pub fn rotmah(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    let result = per_halfword(a, b, |a, b| ((a as i16) >> (b.wrapping_neg() & 0x1F).min(15)) as u16);
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Rotate and Mask Algebraic Halfword Immediate - rotmahi rt, ra, i7
+pub fn rotmahi(thread: &mut SpuThread, i7: i8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let shift = (i7.wrapping_neg() & 0x1F).min(15);
+    let result = per_halfword(a, [0; 4], |a, _| ((a as i16) >> shift) as u16);
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
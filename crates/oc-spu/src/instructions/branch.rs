@@ -1,5 +1,6 @@
 //! SPU branch instructions
 
+use crate::channels::channel_ids::SPU_RD_EVENT_STAT;
 use crate::thread::SpuThread;
 use oc_core::error::SpuError;
 
@@ -56,6 +57,20 @@ pub fn bisl(thread: &mut SpuThread, ra: u8, rt: u8) -> Result<(), SpuError> {
     Ok(())
 }
 
+/// Branch Indirect and Set Link if External Data - bisled rt, ra
+///
+/// Branches only while an event enabled in the event mask is pending
+/// (SPU_RdEventStat); otherwise execution falls through.
+pub fn bisled(thread: &mut SpuThread, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let status = thread.channels.read(SPU_RD_EVENT_STAT).unwrap_or(0);
+    if status & thread.channels.get_event_mask() != 0 {
+        bisl(thread, ra, rt)
+    } else {
+        thread.advance_pc();
+        Ok(())
+    }
+}
+
 /// Branch if Zero - brz rt, i16
 pub fn brz(thread: &mut SpuThread, i16_val: i16, rt: u8) -> Result<(), SpuError> {
     let value = thread.regs.read_preferred_u32(rt as usize);
@@ -257,4 +272,33 @@ mod tests {
         assert_eq!(thread.pc(), 0x200);
         assert_eq!(thread.regs.read_preferred_u32(5), 0x104);
     }
+
+    #[test]
+    fn test_bisled() {
+        let mut thread = create_test_thread();
+        thread.regs.write_preferred_u32(3, 0x200);
+
+        // Branch taken: signal notification 1 is pending and enabled
+        thread.set_pc(0x100);
+        thread.channels.send_signal1(0x1234);
+        thread.channels.set_event_mask(0x04);
+        bisled(&mut thread, 3, 5).unwrap();
+        assert_eq!(thread.pc(), 0x200);
+        assert_eq!(thread.regs.read_preferred_u32(5), 0x104);
+
+        // Branch not taken: the pending event is masked out
+        thread.set_pc(0x100);
+        thread.regs.write_preferred_u32(5, 0);
+        thread.channels.set_event_mask(0x01);
+        bisled(&mut thread, 3, 5).unwrap();
+        assert_eq!(thread.pc(), 0x104);
+        assert_eq!(thread.regs.read_preferred_u32(5), 0);
+
+        // Branch not taken: nothing pending once the event is acknowledged
+        thread.set_pc(0x100);
+        thread.channels.set_event_mask(0x04);
+        thread.channels.acknowledge_events(0x04);
+        bisled(&mut thread, 3, 5).unwrap();
+        assert_eq!(thread.pc(), 0x104);
+    }
 }
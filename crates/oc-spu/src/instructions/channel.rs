@@ -1,27 +1,43 @@
 //! SPU channel instructions
+//!
+//! A channel that has no data to read, or no room for a write, blocks the
+//! instruction: the program counter stays on it and it runs again on the
+//! next step, the way the SPU stalls until the PPU or MFC catches up.
 
+use std::sync::Arc;
+use crate::channels::channel_ids::*;
 use crate::thread::SpuThread;
 use oc_core::error::SpuError;
 
 /// Read Channel - rdch rt, ca
 pub fn rdch(thread: &mut SpuThread, ca: u8, rt: u8) -> Result<(), SpuError> {
-    // Try to read from channel
-    if let Some(value) = thread.channels.read(ca as u32) {
+    let value = match ca as u32 {
+        MFC_RD_TAG_MASK => Some(thread.mfc.get_tag_mask()),
+        MFC_RD_TAG_STAT => thread.mfc.read_tag_update(),
+        MFC_RD_LIST_STALL => thread.mfc.take_list_stall(),
+        MFC_RD_ATOMIC_STAT => thread.mfc.take_atomic_status(),
+        channel => thread.channels.read(channel),
+    };
+    // An empty channel blocks until it has data
+    if let Some(value) = value {
         thread.regs.write_preferred_u32(rt as usize, value);
         thread.advance_pc();
-        Ok(())
-    } else {
-        // Channel is empty, would block in real hardware
-        // For now, return 0 and continue
-        thread.regs.write_preferred_u32(rt as usize, 0);
-        thread.advance_pc();
-        Ok(())
     }
+    Ok(())
 }
 
 /// Read Channel Count - rchcnt rt, ca
 pub fn rchcnt(thread: &mut SpuThread, ca: u8, rt: u8) -> Result<(), SpuError> {
-    let count = thread.channels.get_count(ca as u32);
+    let count = match ca as u32 {
+        // Commands complete as they are issued, so the queue is never full
+        MFC_CMD => 16,
+        MFC_LSA | MFC_EAH | MFC_EAL | MFC_SIZE | MFC_TAG_ID | MFC_WR_TAG_MASK | MFC_WR_TAG_UPDATE
+        | MFC_WR_LIST_STALL_ACK | MFC_RD_TAG_MASK => 1,
+        MFC_RD_TAG_STAT => thread.mfc.tag_update_count(),
+        MFC_RD_LIST_STALL => thread.mfc.get_list_stall() as u32,
+        MFC_RD_ATOMIC_STAT => thread.mfc.atomic_status_count(),
+        channel => thread.channels.get_count(channel),
+    };
     thread.regs.write_preferred_u32(rt as usize, count);
     thread.advance_pc();
     Ok(())
@@ -30,23 +46,35 @@ pub fn rchcnt(thread: &mut SpuThread, ca: u8, rt: u8) -> Result<(), SpuError> {
 /// Write Channel - wrch ca, rt
 pub fn wrch(thread: &mut SpuThread, ca: u8, rt: u8) -> Result<(), SpuError> {
     let value = thread.regs.read_preferred_u32(rt as usize);
-    // Try to write to channel
-    if thread.channels.write(ca as u32, value) {
-        thread.advance_pc();
-        Ok(())
-    } else {
-        // Channel is full, would block in real hardware
-        // For now, just continue
-        thread.advance_pc();
-        Ok(())
+    let params = thread.mfc.params_mut();
+    match ca as u32 {
+        MFC_LSA => params.lsa = value,
+        MFC_EAH => params.eah = value,
+        MFC_EAL => params.eal = value,
+        MFC_SIZE => params.size = value,
+        MFC_TAG_ID => params.tag = value,
+        MFC_CMD => {
+            let memory = Arc::clone(thread.memory());
+            thread.mfc.issue(value, &mut thread.local_storage[..], &memory)?;
+        }
+        MFC_WR_TAG_MASK => thread.mfc.set_tag_mask(value),
+        MFC_WR_TAG_UPDATE => thread.mfc.request_tag_update(value),
+        MFC_WR_LIST_STALL_ACK => thread.mfc.clear_list_stall(),
+        channel => {
+            // A full channel blocks until it is drained
+            if !thread.channels.write(channel, value) {
+                return Ok(());
+            }
+        }
     }
+    thread.advance_pc();
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use oc_memory::MemoryManager;
-    use crate::channels::channel_ids::*;
 
     fn create_test_thread() -> SpuThread {
         let memory = MemoryManager::new().unwrap();
@@ -132,6 +132,165 @@ pub fn cgth(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuErr
     Ok(())
 }
 
+/// Compare each pair of halfwords, setting all ones where `f` holds
+fn compare_halfwords(a: [u32; 4], b: [u32; 4], f: impl Fn(u16, u16) -> bool) -> [u32; 4] {
+    std::array::from_fn(|i| {
+        let hi = if f((a[i] >> 16) as u16, (b[i] >> 16) as u16) { 0xFFFF_0000 } else { 0 };
+        let lo = if f(a[i] as u16, b[i] as u16) { 0x0000_FFFF } else { 0 };
+        hi | lo
+    })
+}
+
+/// Compare each pair of bytes, setting all ones where `f` holds
+fn compare_bytes(a: [u32; 4], b: [u32; 4], f: impl Fn(u8, u8) -> bool) -> [u32; 4] {
+    std::array::from_fn(|i| {
+        let (a, b) = (a[i].to_be_bytes(), b[i].to_be_bytes());
+        u32::from_be_bytes(std::array::from_fn(|j| if f(a[j], b[j]) { 0xFF } else { 0 }))
+    })
+}
+
+/// Compare Equal Halfword Immediate - ceqhi rt, ra, i10
+pub fn ceqhi(thread: &mut SpuThread, i10: i16, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let result = compare_halfwords(a, [0; 4], |a, _| a == i10 as u16);
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Compare Greater Than Halfword Immediate - cgthi rt, ra, i10
+pub fn cgthi(thread: &mut SpuThread, i10: i16, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let result = compare_halfwords(a, [0; 4], |a, _| a as i16 > i10);
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Compare Logical Greater Than Halfword - clgth rt, ra, rb
+pub fn clgth(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    thread.regs.write_u32x4(rt as usize, compare_halfwords(a, b, |a, b| a > b));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Compare Logical Greater Than Halfword Immediate - clgthi rt, ra, i10
+pub fn clgthi(thread: &mut SpuThread, i10: i16, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let result = compare_halfwords(a, [0; 4], |a, _| a > i10 as u16);
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Compare Equal Byte - ceqb rt, ra, rb
+pub fn ceqb(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    thread.regs.write_u32x4(rt as usize, compare_bytes(a, b, |a, b| a == b));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Compare Equal Byte Immediate - ceqbi rt, ra, i10 (low 8 bits)
+pub fn ceqbi(thread: &mut SpuThread, i10: i16, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let result = compare_bytes(a, [0; 4], |a, _| a == i10 as u8);
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Compare Greater Than Byte - cgtb rt, ra, rb
+pub fn cgtb(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    thread.regs.write_u32x4(rt as usize, compare_bytes(a, b, |a, b| a as i8 > b as i8));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Compare Greater Than Byte Immediate - cgtbi rt, ra, i10 (low 8 bits)
+pub fn cgtbi(thread: &mut SpuThread, i10: i16, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let result = compare_bytes(a, [0; 4], |a, _| a as i8 > i10 as i8);
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Compare Logical Greater Than Byte - clgtb rt, ra, rb
+pub fn clgtb(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    thread.regs.write_u32x4(rt as usize, compare_bytes(a, b, |a, b| a > b));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Compare Logical Greater Than Byte Immediate - clgtbi rt, ra, i10 (low 8 bits)
+pub fn clgtbi(thread: &mut SpuThread, i10: i16, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let result = compare_bytes(a, [0; 4], |a, _| a > i10 as u8);
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Stop the SPU at a halt instruction whose condition held, otherwise move on
+///
+/// A halted SPU keeps its pc on the halt so the debugger shows where it stopped.
+fn halt_if(thread: &mut SpuThread, condition: bool) -> Result<(), SpuError> {
+    if condition {
+        tracing::warn!("SPU {} halted at 0x{:05x}", thread.id, thread.pc());
+        thread.state = crate::thread::SpuThreadState::Halted;
+    } else {
+        thread.advance_pc();
+    }
+    Ok(())
+}
+
+/// Halt If Equal - heq ra, rb
+pub fn heq(thread: &mut SpuThread, rb: u8, ra: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_preferred_u32(ra as usize);
+    let b = thread.regs.read_preferred_u32(rb as usize);
+    halt_if(thread, a == b)
+}
+
+/// Halt If Equal Immediate - heqi ra, i10
+pub fn heqi(thread: &mut SpuThread, i10: i16, ra: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_preferred_u32(ra as usize);
+    halt_if(thread, a == i10 as i32 as u32)
+}
+
+/// Halt If Greater Than - hgt ra, rb
+pub fn hgt(thread: &mut SpuThread, rb: u8, ra: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_preferred_u32(ra as usize) as i32;
+    let b = thread.regs.read_preferred_u32(rb as usize) as i32;
+    halt_if(thread, a > b)
+}
+
+/// Halt If Greater Than Immediate - hgti ra, i10
+pub fn hgti(thread: &mut SpuThread, i10: i16, ra: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_preferred_u32(ra as usize) as i32;
+    halt_if(thread, a > i10 as i32)
+}
+
+/// Halt If Logically Greater Than - hlgt ra, rb
+pub fn hlgt(thread: &mut SpuThread, rb: u8, ra: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_preferred_u32(ra as usize);
+    let b = thread.regs.read_preferred_u32(rb as usize);
+    halt_if(thread, a > b)
+}
+
+/// Halt If Logically Greater Than Immediate - hlgti ra, i10
+pub fn hlgti(thread: &mut SpuThread, i10: i16, ra: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_preferred_u32(ra as usize);
+    halt_if(thread, a > i10 as i32 as u32)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -183,4 +342,23 @@ mod tests {
         assert_eq!(result[2], 0);
         assert_eq!(result[3], 0xFFFFFFFF);
     }
+
+    #[test]
+    fn test_byte_compares_and_halt() {
+        let mut thread = create_test_thread();
+        thread.regs.write_u32x4(1, [0x01FF_7F80, 0, 0, 0]);
+        thread.regs.write_u32x4(2, [0x0100_0000, 0, 0, 0]);
+
+        ceqb(&mut thread, 2, 1, 3).unwrap();
+        assert_eq!(thread.regs.read_u32x4(3), [0xFF00_0000, 0xFFFF_FFFF, 0xFFFF_FFFF, 0xFFFF_FFFF]);
+        cgtb(&mut thread, 2, 1, 3).unwrap();
+        assert_eq!(thread.regs.read_u32x4(3), [0x0000_FF00, 0, 0, 0]);
+        clgtb(&mut thread, 2, 1, 3).unwrap();
+        assert_eq!(thread.regs.read_u32x4(3), [0x00FF_FFFF, 0, 0, 0]);
+
+        let pc = thread.pc();
+        hgti(&mut thread, 0, 2).unwrap();
+        assert_eq!(thread.state, crate::thread::SpuThreadState::Halted);
+        assert_eq!(thread.pc(), pc);
+    }
 }
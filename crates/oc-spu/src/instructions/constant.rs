@@ -0,0 +1,69 @@
+//! SPU constant formation instructions
+
+use crate::thread::SpuThread;
+use oc_core::error::SpuError;
+
+/// Immediate Load Word - il rt, i16
+pub fn il(thread: &mut SpuThread, i16_val: i16, rt: u8) -> Result<(), SpuError> {
+    let value = i16_val as i32 as u32;
+    thread.regs.write_u32x4(rt as usize, [value; 4]);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Immediate Load Halfword - ilh rt, i16
+pub fn ilh(thread: &mut SpuThread, i16_val: i16, rt: u8) -> Result<(), SpuError> {
+    let half = i16_val as u16 as u32;
+    thread.regs.write_u32x4(rt as usize, [(half << 16) | half; 4]);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Immediate Load Halfword Upper - ilhu rt, i16
+pub fn ilhu(thread: &mut SpuThread, i16_val: i16, rt: u8) -> Result<(), SpuError> {
+    let value = (i16_val as u16 as u32) << 16;
+    thread.regs.write_u32x4(rt as usize, [value; 4]);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Immediate Load Address - ila rt, i18 (zero extended)
+pub fn ila(thread: &mut SpuThread, i18: u32, rt: u8) -> Result<(), SpuError> {
+    thread.regs.write_u32x4(rt as usize, [i18 & 0x3FFFF; 4]);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Immediate Or Halfword Lower - iohl rt, i16
+pub fn iohl(thread: &mut SpuThread, i16_val: i16, rt: u8) -> Result<(), SpuError> {
+    let t = thread.regs.read_u32x4(rt as usize);
+    let half = i16_val as u16 as u32;
+    thread.regs.write_u32x4(rt as usize, t.map(|t| t | half));
+    thread.advance_pc();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oc_memory::MemoryManager;
+
+    fn create_test_thread() -> SpuThread {
+        let memory = MemoryManager::new().unwrap();
+        SpuThread::new(0, memory)
+    }
+
+    #[test]
+    fn test_load_32bit_constant() {
+        let mut thread = create_test_thread();
+        ilhu(&mut thread, 0x8001u16 as i16, 1).unwrap();
+        iohl(&mut thread, 0xF00Du16 as i16, 1).unwrap();
+        assert_eq!(thread.regs.read_u32x4(1), [0x8001_F00D; 4]);
+
+        il(&mut thread, -2, 2).unwrap();
+        assert_eq!(thread.regs.read_u32x4(2), [0xFFFF_FFFE; 4]);
+        ila(&mut thread, 0x3FFFF, 2).unwrap();
+        assert_eq!(thread.regs.read_u32x4(2), [0x3FFFF; 4]);
+        assert_eq!(thread.pc(), 16);
+    }
+}
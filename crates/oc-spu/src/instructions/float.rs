@@ -108,6 +108,191 @@ pub fn frsqest(thread: &mut SpuThread, ra: u8, rt: u8) -> Result<(), SpuError> {
     Ok(())
 }
 
+/// Floating Multiply and Subtract - fms rt, ra, rb, rc (ra * rb - rc)
+pub fn fms(thread: &mut SpuThread, rc: u8, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    let c = thread.regs.read_u32x4(rc as usize);
+    let result = std::array::from_fn(|i| {
+        (f32::from_bits(a[i]) * f32::from_bits(b[i]) - f32::from_bits(c[i])).to_bits()
+    });
+    thread.regs.write_u32x4(rt as usize, result);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Floating Interpolate - fi rt, ra, rb
+///
+/// Estimates from frest and frsqest are already exact here, so the
+/// refinement step just passes the estimate in rb through.
+pub fn fi(thread: &mut SpuThread, rb: u8, _ra: u8, rt: u8) -> Result<(), SpuError> {
+    let b = thread.regs.read_u32x4(rb as usize);
+    thread.regs.write_u32x4(rt as usize, b);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Compare each pair of floats, setting all ones where `f` holds
+fn compare_floats(a: [u32; 4], b: [u32; 4], f: impl Fn(f32, f32) -> bool) -> [u32; 4] {
+    std::array::from_fn(|i| if f(f32::from_bits(a[i]), f32::from_bits(b[i])) { 0xFFFFFFFF } else { 0 })
+}
+
+/// Floating Compare Equal - fceq rt, ra, rb
+pub fn fceq(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    thread.regs.write_u32x4(rt as usize, compare_floats(a, b, |a, b| a == b));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Floating Compare Greater Than - fcgt rt, ra, rb
+pub fn fcgt(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    thread.regs.write_u32x4(rt as usize, compare_floats(a, b, |a, b| a > b));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Floating Compare Magnitude Equal - fcmeq rt, ra, rb
+pub fn fcmeq(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    thread.regs.write_u32x4(rt as usize, compare_floats(a, b, |a, b| a.abs() == b.abs()));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Floating Compare Magnitude Greater Than - fcmgt rt, ra, rb
+pub fn fcmgt(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let b = thread.regs.read_u32x4(rb as usize);
+    thread.regs.write_u32x4(rt as usize, compare_floats(a, b, |a, b| a.abs() > b.abs()));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Convert Floating to Signed Integer - cflts rt, ra, i8 (scaled by 2^(173 - i8), saturating)
+pub fn cflts(thread: &mut SpuThread, i8_val: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let scale = 2f64.powi(173 - i8_val as i32);
+    thread.regs.write_u32x4(rt as usize, a.map(|a| (f32::from_bits(a) as f64 * scale) as i32 as u32));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Convert Floating to Unsigned Integer - cfltu rt, ra, i8 (scaled by 2^(173 - i8), saturating)
+pub fn cfltu(thread: &mut SpuThread, i8_val: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let scale = 2f64.powi(173 - i8_val as i32);
+    thread.regs.write_u32x4(rt as usize, a.map(|a| (f32::from_bits(a) as f64 * scale) as u32));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Convert Signed Integer to Floating - csflt rt, ra, i8 (scaled by 2^(i8 - 155))
+pub fn csflt(thread: &mut SpuThread, i8_val: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let scale = 2f64.powi(i8_val as i32 - 155);
+    thread.regs.write_u32x4(rt as usize, a.map(|a| ((a as i32 as f64 * scale) as f32).to_bits()));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Convert Unsigned Integer to Floating - cuflt rt, ra, i8 (scaled by 2^(i8 - 155))
+pub fn cuflt(thread: &mut SpuThread, i8_val: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    let scale = 2f64.powi(i8_val as i32 - 155);
+    thread.regs.write_u32x4(rt as usize, a.map(|a| ((a as f64 * scale) as f32).to_bits()));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Register as two doubles
+fn read_f64x2(thread: &SpuThread, reg: u8) -> [f64; 2] {
+    let w = thread.regs.read_u32x4(reg as usize);
+    [0, 2].map(|i| f64::from_bits(((w[i] as u64) << 32) | w[i + 1] as u64))
+}
+
+/// Two doubles into a register
+fn write_f64x2(thread: &mut SpuThread, reg: u8, value: [f64; 2]) {
+    let [hi, lo] = value.map(f64::to_bits);
+    thread.regs.write_u32x4(reg as usize, [(hi >> 32) as u32, hi as u32, (lo >> 32) as u32, lo as u32]);
+}
+
+/// Double Floating Add - dfa rt, ra, rb
+pub fn dfa(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let (a, b) = (read_f64x2(thread, ra), read_f64x2(thread, rb));
+    write_f64x2(thread, rt, [a[0] + b[0], a[1] + b[1]]);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Double Floating Subtract - dfs rt, ra, rb
+pub fn dfs(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let (a, b) = (read_f64x2(thread, ra), read_f64x2(thread, rb));
+    write_f64x2(thread, rt, [a[0] - b[0], a[1] - b[1]]);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Double Floating Multiply - dfm rt, ra, rb
+pub fn dfm(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let (a, b) = (read_f64x2(thread, ra), read_f64x2(thread, rb));
+    write_f64x2(thread, rt, [a[0] * b[0], a[1] * b[1]]);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Double Floating Multiply and Add - dfma rt, ra, rb (ra * rb + rt)
+pub fn dfma(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let (a, b, t) = (read_f64x2(thread, ra), read_f64x2(thread, rb), read_f64x2(thread, rt));
+    write_f64x2(thread, rt, [a[0] * b[0] + t[0], a[1] * b[1] + t[1]]);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Double Floating Multiply and Subtract - dfms rt, ra, rb (ra * rb - rt)
+pub fn dfms(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let (a, b, t) = (read_f64x2(thread, ra), read_f64x2(thread, rb), read_f64x2(thread, rt));
+    write_f64x2(thread, rt, [a[0] * b[0] - t[0], a[1] * b[1] - t[1]]);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Double Floating Negative Multiply and Subtract - dfnms rt, ra, rb (rt - ra * rb)
+pub fn dfnms(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let (a, b, t) = (read_f64x2(thread, ra), read_f64x2(thread, rb), read_f64x2(thread, rt));
+    write_f64x2(thread, rt, [t[0] - a[0] * b[0], t[1] - a[1] * b[1]]);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Double Floating Negative Multiply and Add - dfnma rt, ra, rb (-(ra * rb + rt))
+pub fn dfnma(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let (a, b, t) = (read_f64x2(thread, ra), read_f64x2(thread, rb), read_f64x2(thread, rt));
+    write_f64x2(thread, rt, [-(a[0] * b[0] + t[0]), -(a[1] * b[1] + t[1])]);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Floating Extend Single to Double - fesd rt, ra (from words 0 and 2)
+pub fn fesd(thread: &mut SpuThread, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    write_f64x2(thread, rt, [f32::from_bits(a[0]) as f64, f32::from_bits(a[2]) as f64]);
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Floating Round Double to Single - frds rt, ra (into words 0 and 2)
+pub fn frds(thread: &mut SpuThread, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let [hi, lo] = read_f64x2(thread, ra).map(|value| (value as f32).to_bits());
+    thread.regs.write_u32x4(rt as usize, [hi, 0, lo, 0]);
+    thread.advance_pc();
+    Ok(())
+}
+
 /// Helper: Compute reciprocal estimate with SPU-compatible special case handling
 fn compute_reciprocal_estimate(x: f32) -> f32 {
     if x.is_nan() {
@@ -206,4 +391,25 @@ mod tests {
         assert!((f32::from_bits(result[2]) - 0.125).abs() < 0.001);
         assert!((f32::from_bits(result[3]) - 0.1).abs() < 0.001);
     }
+
+    #[test]
+    fn test_conversions() {
+        let mut thread = create_test_thread();
+        thread.regs.write_u32x4(1, [(-3i32) as u32, 7, 0, 1 << 20]);
+        // Scale 155 converts without scaling
+        csflt(&mut thread, 155, 1, 2).unwrap();
+        assert_eq!(thread.regs.read_u32x4(2).map(f32::from_bits), [-3.0, 7.0, 0.0, 1048576.0]);
+        cflts(&mut thread, 173, 2, 3).unwrap();
+        assert_eq!(thread.regs.read_u32x4(3), [(-3i32) as u32, 7, 0, 1 << 20]);
+        // Out of range values saturate
+        thread.regs.write_u32x4(2, [1e20f32.to_bits(), (-1.0f32).to_bits(), 0, 0]);
+        cfltu(&mut thread, 173, 2, 3).unwrap();
+        assert_eq!(thread.regs.read_u32x4(3)[..2], [u32::MAX, 0]);
+
+        thread.regs.write_u32x4(1, [1.5f32.to_bits(), 0, (-2.0f32).to_bits(), 0]);
+        fesd(&mut thread, 1, 2).unwrap();
+        dfm(&mut thread, 2, 2, 3).unwrap();
+        frds(&mut thread, 3, 4).unwrap();
+        assert_eq!(thread.regs.read_u32x4(4), [2.25f32.to_bits(), 0, 4.0f32.to_bits(), 0]);
+    }
 }
@@ -0,0 +1,354 @@
+//! SPU quadword instructions: shuffles, whole-register shifts and rotates,
+//! insertion masks, form-select masks and gathers
+
+use crate::thread::SpuThread;
+use oc_core::error::SpuError;
+
+/// Register as 16 bytes, byte 0 most significant
+fn to_bytes(words: [u32; 4]) -> [u8; 16] {
+    std::array::from_fn(|i| words[i / 4].to_be_bytes()[i % 4])
+}
+
+/// 16 bytes back into a register
+fn from_bytes(bytes: [u8; 16]) -> [u32; 4] {
+    std::array::from_fn(|i| u32::from_be_bytes([bytes[i * 4], bytes[i * 4 + 1], bytes[i * 4 + 2], bytes[i * 4 + 3]]))
+}
+
+/// Register as one 128-bit value
+fn to_u128(words: [u32; 4]) -> u128 {
+    words.iter().fold(0, |acc, &word| (acc << 32) | word as u128)
+}
+
+/// 128-bit value back into a register
+fn from_u128(value: u128) -> [u32; 4] {
+    std::array::from_fn(|i| (value >> (96 - i * 32)) as u32)
+}
+
+/// Write a quadword result and move on
+fn write_result(thread: &mut SpuThread, rt: u8, value: u128) -> Result<(), SpuError> {
+    thread.regs.write_u32x4(rt as usize, from_u128(value));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Shift left by `bytes`, clearing the register from 16 up
+fn shift_left_bytes(value: u128, bytes: u32) -> u128 {
+    value.checked_shl(bytes * 8).unwrap_or(0)
+}
+
+/// Shift right by `bytes`, clearing the register from 16 up
+fn shift_right_bytes(value: u128, bytes: u32) -> u128 {
+    value.checked_shr(bytes * 8).unwrap_or(0)
+}
+
+/// Shuffle Bytes - shufb rt, ra, rb, rc
+pub fn shufb(thread: &mut SpuThread, rc: u8, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_bytes(thread.regs.read_u32x4(ra as usize));
+    let b = to_bytes(thread.regs.read_u32x4(rb as usize));
+    let c = to_bytes(thread.regs.read_u32x4(rc as usize));
+    let result = c.map(|sel| {
+        if sel & 0xE0 == 0xE0 {
+            0x80
+        } else if sel & 0xC0 == 0xC0 {
+            0xFF
+        } else if sel & 0x80 == 0x80 {
+            0x00
+        } else if sel & 0x10 == 0 {
+            a[(sel & 0x0F) as usize]
+        } else {
+            b[(sel & 0x0F) as usize]
+        }
+    });
+    thread.regs.write_u32x4(rt as usize, from_bytes(result));
+    thread.advance_pc();
+    Ok(())
+}
+
+/// Shift Left Quadword by Bytes - shlqby rt, ra, rb
+pub fn shlqby(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_u128(thread.regs.read_u32x4(ra as usize));
+    let count = thread.regs.read_preferred_u32(rb as usize) & 0x1F;
+    write_result(thread, rt, shift_left_bytes(a, count))
+}
+
+/// Shift Left Quadword by Bytes Immediate - shlqbyi rt, ra, i7
+pub fn shlqbyi(thread: &mut SpuThread, i7: i8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_u128(thread.regs.read_u32x4(ra as usize));
+    write_result(thread, rt, shift_left_bytes(a, (i7 & 0x1F) as u32))
+}
+
+/// Shift Left Quadword by Bytes from Bit Shift Count - shlqbybi rt, ra, rb
+pub fn shlqbybi(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_u128(thread.regs.read_u32x4(ra as usize));
+    let count = (thread.regs.read_preferred_u32(rb as usize) >> 3) & 0x1F;
+    write_result(thread, rt, shift_left_bytes(a, count))
+}
+
+/// Rotate Quadword by Bytes - rotqby rt, ra, rb
+pub fn rotqby(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_u128(thread.regs.read_u32x4(ra as usize));
+    let count = thread.regs.read_preferred_u32(rb as usize) & 0xF;
+    write_result(thread, rt, a.rotate_left(count * 8))
+}
+
+/// Rotate Quadword by Bytes Immediate - rotqbyi rt, ra, i7
+pub fn rotqbyi(thread: &mut SpuThread, i7: i8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_u128(thread.regs.read_u32x4(ra as usize));
+    write_result(thread, rt, a.rotate_left((i7 & 0xF) as u32 * 8))
+}
+
+/// Rotate Quadword by Bytes from Bit Shift Count - rotqbybi rt, ra, rb
+pub fn rotqbybi(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_u128(thread.regs.read_u32x4(ra as usize));
+    let count = (thread.regs.read_preferred_u32(rb as usize) >> 3) & 0xF;
+    write_result(thread, rt, a.rotate_left(count * 8))
+}
+
+/// Rotate and Mask Quadword by Bytes - rotqmby rt, ra, rb (shift right by -rb bytes)
+pub fn rotqmby(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_u128(thread.regs.read_u32x4(ra as usize));
+    let count = thread.regs.read_preferred_u32(rb as usize).wrapping_neg() & 0x1F;
+    write_result(thread, rt, shift_right_bytes(a, count))
+}
+
+/// Rotate and Mask Quadword by Bytes Immediate - rotqmbyi rt, ra, i7
+pub fn rotqmbyi(thread: &mut SpuThread, i7: i8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_u128(thread.regs.read_u32x4(ra as usize));
+    write_result(thread, rt, shift_right_bytes(a, (i7.wrapping_neg() & 0x1F) as u32))
+}
+
+/// Rotate and Mask Quadword by Bytes from Bit Shift Count - rotqmbybi rt, ra, rb
+pub fn rotqmbybi(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_u128(thread.regs.read_u32x4(ra as usize));
+    let count = (thread.regs.read_preferred_u32(rb as usize) >> 3).wrapping_neg() & 0x1F;
+    write_result(thread, rt, shift_right_bytes(a, count))
+}
+
+/// Shift Left Quadword by Bits - shlqbi rt, ra, rb
+pub fn shlqbi(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_u128(thread.regs.read_u32x4(ra as usize));
+    let count = thread.regs.read_preferred_u32(rb as usize) & 0x7;
+    write_result(thread, rt, a << count)
+}
+
+/// Shift Left Quadword by Bits Immediate - shlqbii rt, ra, i7
+pub fn shlqbii(thread: &mut SpuThread, i7: i8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_u128(thread.regs.read_u32x4(ra as usize));
+    write_result(thread, rt, a << (i7 & 0x7))
+}
+
+/// Rotate Quadword by Bits - rotqbi rt, ra, rb
+pub fn rotqbi(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_u128(thread.regs.read_u32x4(ra as usize));
+    let count = thread.regs.read_preferred_u32(rb as usize) & 0x7;
+    write_result(thread, rt, a.rotate_left(count))
+}
+
+/// Rotate Quadword by Bits Immediate - rotqbii rt, ra, i7
+pub fn rotqbii(thread: &mut SpuThread, i7: i8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_u128(thread.regs.read_u32x4(ra as usize));
+    write_result(thread, rt, a.rotate_left((i7 & 0x7) as u32))
+}
+
+/// Rotate and Mask Quadword by Bits - rotqmbi rt, ra, rb (shift right by -rb bits)
+pub fn rotqmbi(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_u128(thread.regs.read_u32x4(ra as usize));
+    let count = thread.regs.read_preferred_u32(rb as usize).wrapping_neg() & 0x7;
+    write_result(thread, rt, a >> count)
+}
+
+/// Rotate and Mask Quadword by Bits Immediate - rotqmbii rt, ra, i7
+pub fn rotqmbii(thread: &mut SpuThread, i7: i8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_u128(thread.regs.read_u32x4(ra as usize));
+    write_result(thread, rt, a >> (i7.wrapping_neg() & 0x7))
+}
+
+/// Shuffle mask that inserts a `size`-byte element at byte `offset` of the preferred slot
+fn insertion_mask(offset: u32, size: usize) -> u128 {
+    let mut mask: [u8; 16] = std::array::from_fn(|i| 0x10 + i as u8);
+    let start = offset as usize & !(size - 1);
+    let source = 4usize.saturating_sub(size);
+    for i in 0..size {
+        mask[start + i] = (source + i) as u8;
+    }
+    to_u128(from_bytes(mask))
+}
+
+/// Generate Controls for Byte Insertion (d-form) - cbd rt, i7(ra)
+pub fn cbd(thread: &mut SpuThread, i7: i8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let offset = thread.regs.read_preferred_u32(ra as usize).wrapping_add(i7 as i32 as u32) & 0xF;
+    write_result(thread, rt, insertion_mask(offset, 1))
+}
+
+/// Generate Controls for Halfword Insertion (d-form) - chd rt, i7(ra)
+pub fn chd(thread: &mut SpuThread, i7: i8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let offset = thread.regs.read_preferred_u32(ra as usize).wrapping_add(i7 as i32 as u32) & 0xF;
+    write_result(thread, rt, insertion_mask(offset, 2))
+}
+
+/// Generate Controls for Word Insertion (d-form) - cwd rt, i7(ra)
+pub fn cwd(thread: &mut SpuThread, i7: i8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let offset = thread.regs.read_preferred_u32(ra as usize).wrapping_add(i7 as i32 as u32) & 0xF;
+    write_result(thread, rt, insertion_mask(offset, 4))
+}
+
+/// Generate Controls for Doubleword Insertion (d-form) - cdd rt, i7(ra)
+pub fn cdd(thread: &mut SpuThread, i7: i8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let offset = thread.regs.read_preferred_u32(ra as usize).wrapping_add(i7 as i32 as u32) & 0xF;
+    write_result(thread, rt, insertion_mask(offset, 8))
+}
+
+/// Generate Controls for Byte Insertion (x-form) - cbx rt, ra, rb
+pub fn cbx(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let offset = thread.regs.read_preferred_u32(ra as usize).wrapping_add(thread.regs.read_preferred_u32(rb as usize)) & 0xF;
+    write_result(thread, rt, insertion_mask(offset, 1))
+}
+
+/// Generate Controls for Halfword Insertion (x-form) - chx rt, ra, rb
+pub fn chx(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let offset = thread.regs.read_preferred_u32(ra as usize).wrapping_add(thread.regs.read_preferred_u32(rb as usize)) & 0xF;
+    write_result(thread, rt, insertion_mask(offset, 2))
+}
+
+/// Generate Controls for Word Insertion (x-form) - cwx rt, ra, rb
+pub fn cwx(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let offset = thread.regs.read_preferred_u32(ra as usize).wrapping_add(thread.regs.read_preferred_u32(rb as usize)) & 0xF;
+    write_result(thread, rt, insertion_mask(offset, 4))
+}
+
+/// Generate Controls for Doubleword Insertion (x-form) - cdx rt, ra, rb
+pub fn cdx(thread: &mut SpuThread, rb: u8, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let offset = thread.regs.read_preferred_u32(ra as usize).wrapping_add(thread.regs.read_preferred_u32(rb as usize)) & 0xF;
+    write_result(thread, rt, insertion_mask(offset, 8))
+}
+
+/// Expand the low `count` bits of `bits` into `count` equal all-ones or zero fields
+fn expand_mask(bits: u32, count: u32) -> u128 {
+    let width = 128 / count;
+    let field = u128::MAX >> (128 - width);
+    (0..count)
+        .filter(|i| bits & (1 << (count - 1 - i)) != 0)
+        .fold(0, |mask, i| mask | (field << ((count - 1 - i) * width)))
+}
+
+/// Form Select Mask for Words - fsm rt, ra
+pub fn fsm(thread: &mut SpuThread, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let bits = thread.regs.read_preferred_u32(ra as usize);
+    write_result(thread, rt, expand_mask(bits, 4))
+}
+
+/// Form Select Mask for Halfwords - fsmh rt, ra
+pub fn fsmh(thread: &mut SpuThread, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let bits = thread.regs.read_preferred_u32(ra as usize);
+    write_result(thread, rt, expand_mask(bits, 8))
+}
+
+/// Form Select Mask for Bytes - fsmb rt, ra
+pub fn fsmb(thread: &mut SpuThread, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let bits = thread.regs.read_preferred_u32(ra as usize);
+    write_result(thread, rt, expand_mask(bits, 16))
+}
+
+/// Form Select Mask for Bytes Immediate - fsmbi rt, i16
+pub fn fsmbi(thread: &mut SpuThread, i16_val: i16, rt: u8) -> Result<(), SpuError> {
+    write_result(thread, rt, expand_mask(i16_val as u16 as u32, 16))
+}
+
+/// Gather the low bit of each of `count` fields into the preferred slot
+fn gather_bits(value: u128, count: u32) -> u128 {
+    let width = 128 / count;
+    let bits = (0..count).fold(0u32, |bits, i| (bits << 1) | ((value >> ((count - 1 - i) * width)) & 1) as u32);
+    (bits as u128) << 96
+}
+
+/// Gather Bits from Words - gb rt, ra
+pub fn gb(thread: &mut SpuThread, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_u128(thread.regs.read_u32x4(ra as usize));
+    write_result(thread, rt, gather_bits(a, 4))
+}
+
+/// Gather Bits from Halfwords - gbh rt, ra
+pub fn gbh(thread: &mut SpuThread, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_u128(thread.regs.read_u32x4(ra as usize));
+    write_result(thread, rt, gather_bits(a, 8))
+}
+
+/// Gather Bits from Bytes - gbb rt, ra
+pub fn gbb(thread: &mut SpuThread, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = to_u128(thread.regs.read_u32x4(ra as usize));
+    write_result(thread, rt, gather_bits(a, 16))
+}
+
+/// OR Word Across - orx rt, ra
+pub fn orx(thread: &mut SpuThread, ra: u8, rt: u8) -> Result<(), SpuError> {
+    let a = thread.regs.read_u32x4(ra as usize);
+    thread.regs.write_u32x4(rt as usize, [a[0] | a[1] | a[2] | a[3], 0, 0, 0]);
+    thread.advance_pc();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oc_memory::MemoryManager;
+
+    fn create_test_thread() -> SpuThread {
+        let memory = MemoryManager::new().unwrap();
+        SpuThread::new(0, memory)
+    }
+
+    #[test]
+    fn test_quadword_shifts() {
+        let mut thread = create_test_thread();
+        thread.regs.write_u32x4(1, [0x00010203, 0x04050607, 0x08090A0B, 0x0C0D0E0F]);
+
+        shlqbyi(&mut thread, 4, 1, 2).unwrap();
+        assert_eq!(thread.regs.read_u32x4(2), [0x04050607, 0x08090A0B, 0x0C0D0E0F, 0]);
+        // rotqmbyi shifts right by the negated count
+        rotqmbyi(&mut thread, -1, 1, 2).unwrap();
+        assert_eq!(thread.regs.read_u32x4(2), [0x00000102, 0x03040506, 0x0708090A, 0x0B0C0D0E]);
+        rotqbyi(&mut thread, 12, 1, 2).unwrap();
+        assert_eq!(thread.regs.read_u32x4(2), [0x0C0D0E0F, 0x00010203, 0x04050607, 0x08090A0B]);
+        shlqbii(&mut thread, 4, 1, 2).unwrap();
+        assert_eq!(thread.regs.read_u32x4(2), [0x00102030, 0x40506070, 0x8090A0B0, 0xC0D0E0F0]);
+
+        // Byte shifts of 16 and up clear the register
+        thread.regs.write_u32x4(3, [16, 0, 0, 0]);
+        shlqby(&mut thread, 3, 1, 2).unwrap();
+        assert_eq!(thread.regs.read_u32x4(2), [0; 4]);
+    }
+
+    #[test]
+    fn test_insertion_controls() {
+        let mut thread = create_test_thread();
+        thread.regs.write_u32x4(1, [0x1000_0004, 0, 0, 0]);
+
+        cwd(&mut thread, 0, 1, 2).unwrap();
+        assert_eq!(thread.regs.read_u32x4(2), [0x10111213, 0x00010203, 0x18191A1B, 0x1C1D1E1F]);
+        cbd(&mut thread, 3, 1, 2).unwrap();
+        assert_eq!(thread.regs.read_u32x4(2), [0x10111213, 0x14151603, 0x18191A1B, 0x1C1D1E1F]);
+        chd(&mut thread, 3, 1, 2).unwrap();
+        assert_eq!(thread.regs.read_u32x4(2), [0x10111213, 0x14150203, 0x18191A1B, 0x1C1D1E1F]);
+        cdd(&mut thread, 0, 1, 2).unwrap();
+        assert_eq!(thread.regs.read_u32x4(2), [0x00010203, 0x04050607, 0x18191A1B, 0x1C1D1E1F]);
+    }
+
+    #[test]
+    fn test_select_masks_and_gathers() {
+        let mut thread = create_test_thread();
+        fsmbi(&mut thread, 0xF00Fu16 as i16, 2).unwrap();
+        assert_eq!(thread.regs.read_u32x4(2), [0xFFFFFFFF, 0, 0, 0xFFFFFFFF]);
+
+        thread.regs.write_u32x4(1, [0b1010, 0, 0, 0]);
+        fsm(&mut thread, 1, 2).unwrap();
+        assert_eq!(thread.regs.read_u32x4(2), [0xFFFFFFFF, 0, 0xFFFFFFFF, 0]);
+        fsmh(&mut thread, 1, 2).unwrap();
+        assert_eq!(thread.regs.read_u32x4(2), [0, 0, 0xFFFF_0000, 0xFFFF_0000]);
+
+        thread.regs.write_u32x4(1, [1, 0, 3, 2]);
+        gb(&mut thread, 1, 2).unwrap();
+        assert_eq!(thread.regs.read_u32x4(2), [0b1010, 0, 0, 0]);
+        orx(&mut thread, 1, 2).unwrap();
+        assert_eq!(thread.regs.read_u32x4(2), [3, 0, 0, 0]);
+    }
+}
@@ -1,7 +1,13 @@
 //! SPU interpreter implementation
+//!
+//! Each instruction is decoded by its leading opcode bits, longest first
+//! after the four-bit RRR forms, and handed to the matching function in
+//! [`crate::instructions`]. A channel instruction that has to wait leaves
+//! the pc on itself, so running the thread again retries it.
 
 use crate::decoder::SpuDecoder;
-use crate::thread::SpuThread;
+use crate::instructions::{arithmetic, branch, channel, compare, constant, float, logical, memory, quadword};
+use crate::thread::{SpuThread, SpuThreadState};
 use oc_core::error::SpuError;
 
 /// SPU interpreter for instruction execution
@@ -25,358 +31,299 @@ impl SpuInterpreter {
         Ok(())
     }
 
-    /// Execute a decoded instruction
-    fn execute(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
-        let op11 = SpuDecoder::op11(opcode);
-        let op10 = SpuDecoder::op10(opcode);
-        let op8 = SpuDecoder::op8(opcode);
-        let op7 = SpuDecoder::op7(opcode);
-        let op4 = SpuDecoder::op4(opcode);
-
-        // Match based on opcode patterns
-        match op4 {
-            // Branch instructions (RI18 form)
-            0b0100 => self.execute_branch(thread, opcode)?,
-            0b0110 => self.execute_branch_if_zero(thread, opcode)?,
-            0b0010 => self.execute_branch_if_not_zero(thread, opcode)?,
-            0b0001 => self.execute_branch_if_zero_halfword(thread, opcode)?,
-            0b0011 => self.execute_branch_if_not_zero_halfword(thread, opcode)?,
-
-            _ => {
-                // Check other opcode lengths
-                match op7 {
-                    0b0100000..=0b0100001 => self.execute_immediate_load(thread, opcode)?,
-                    _ => {
-                        match op8 {
-                            0b00011100 => self.execute_ai(thread, opcode)?,
-                            0b00011101 => self.execute_ahi(thread, opcode)?,
-                            0b00010100 => self.execute_sfi(thread, opcode)?,
-                            0b00010101 => self.execute_sfhi(thread, opcode)?,
-                            _ => {
-                                match op10 {
-                                    0b0000011000 => self.execute_add(thread, opcode)?,
-                                    0b0000001000 => self.execute_subtract(thread, opcode)?,
-                                    0b0001000001 => self.execute_and(thread, opcode)?,
-                                    0b0001000101 => self.execute_or(thread, opcode)?,
-                                    0b0001001001 => self.execute_xor(thread, opcode)?,
-                                    0b0001001101 => self.execute_nor(thread, opcode)?,
-                                    0b0000100000 => self.execute_stop(thread, opcode)?,
-                                    0b0000000000 => {
-                                        // nop
-                                        thread.advance_pc();
-                                    }
-                                    _ => {
-                                        match op11 {
-                                            0b01111000100 => self.execute_shufb(thread, opcode)?,
-                                            0b01010100101 | 0b01010110101 | 0b01111010101 => {
-                                                // FMA-type instructions
-                                                thread.advance_pc();
-                                            }
-                                            _ => {
-                                                tracing::warn!(
-                                                    "Unknown SPU instruction 0x{:08x} at 0x{:05x}",
-                                                    opcode,
-                                                    thread.pc()
-                                                );
-                                                thread.advance_pc();
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+    /// Execute up to `max` instructions back to back
+    ///
+    /// Stops early when the thread stops or halts, when an instruction
+    /// leaves the pc where it was (a channel waiting on the PPU or the MFC),
+    /// or at an error. Returns the number of instructions executed with the
+    /// reason it stopped early.
+    pub fn run(&self, thread: &mut SpuThread, max: u64) -> (u64, Result<(), SpuError>) {
+        let mut executed = 0;
+        while executed < max && thread.is_running() {
+            let pc = thread.pc();
+            if let Err(e) = self.step(thread) {
+                return (executed, Err(e));
+            }
+            executed += 1;
+            if thread.pc() == pc {
+                break;
             }
         }
-
-        Ok(())
+        (executed, Ok(()))
     }
 
-    /// Execute branch (br)
-    fn execute_branch(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
-        let (i16_val, _rt) = SpuDecoder::ri16_form(opcode);
-        let offset = (i16_val as i32) << 2;
-        let target = (thread.pc() as i32 + offset) as u32;
-        thread.set_pc(target);
-        Ok(())
-    }
-
-    /// Execute branch if zero (brz)
-    fn execute_branch_if_zero(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
-        let (i16_val, rt) = SpuDecoder::ri16_form(opcode);
-        let value = thread.regs.read_preferred_u32(rt as usize);
-        if value == 0 {
-            let offset = (i16_val as i32) << 2;
-            let target = (thread.pc() as i32 + offset) as u32;
-            thread.set_pc(target);
-        } else {
-            thread.advance_pc();
+    /// Execute a decoded instruction
+    fn execute(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
+        let op4 = SpuDecoder::op4(opcode);
+        if op4 >= 0b1000 {
+            let (rc, rb, ra, rt) = SpuDecoder::rrr_form(opcode);
+            return match op4 {
+                0b1000 => logical::selb(thread, rc, rb, ra, rt),
+                0b1011 => quadword::shufb(thread, rc, rb, ra, rt),
+                0b1100 => arithmetic::mpya(thread, rc, rb, ra, rt),
+                0b1101 => float::fnms(thread, rc, rb, ra, rt),
+                0b1110 => float::fma(thread, rc, rb, ra, rt),
+                0b1111 => float::fms(thread, rc, rb, ra, rt),
+                _ => self.unknown(thread, opcode),
+            };
         }
-        Ok(())
-    }
 
-    /// Execute branch if not zero (brnz)
-    fn execute_branch_if_not_zero(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
-        let (i16_val, rt) = SpuDecoder::ri16_form(opcode);
-        let value = thread.regs.read_preferred_u32(rt as usize);
-        if value != 0 {
-            let offset = (i16_val as i32) << 2;
-            let target = (thread.pc() as i32 + offset) as u32;
-            thread.set_pc(target);
-        } else {
-            thread.advance_pc();
+        // RI18 form
+        let (i18, rt) = SpuDecoder::ri18_form(opcode);
+        match SpuDecoder::op7(opcode) {
+            0b0100001 => return constant::ila(thread, i18 as u32, rt),
+            // Branch hints (hbra, hbrr)
+            0b0001000 | 0b0001001 => return self.nop(thread),
+            _ => {}
         }
-        Ok(())
-    }
 
-    /// Execute branch if zero halfword (brhz)
-    fn execute_branch_if_zero_halfword(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
-        let (i16_val, rt) = SpuDecoder::ri16_form(opcode);
-        let value = thread.regs.read_preferred_u32(rt as usize) & 0xFFFF;
-        if value == 0 {
-            let offset = (i16_val as i32) << 2;
-            let target = (thread.pc() as i32 + offset) as u32;
-            thread.set_pc(target);
-        } else {
-            thread.advance_pc();
+        // RI10 form
+        let (i10, ra, rt) = SpuDecoder::ri10_form(opcode);
+        let handled = match SpuDecoder::op8(opcode) {
+            0x04 => Some(logical::ori(thread, i10, ra, rt)),
+            0x05 => Some(logical::orhi(thread, i10, ra, rt)),
+            0x06 => Some(logical::orbi(thread, i10, ra, rt)),
+            0x0C => Some(arithmetic::sfi(thread, i10, ra, rt)),
+            0x0D => Some(arithmetic::sfhi(thread, i10, ra, rt)),
+            0x14 => Some(logical::andi(thread, i10, ra, rt)),
+            0x15 => Some(logical::andhi(thread, i10, ra, rt)),
+            0x16 => Some(logical::andbi(thread, i10, ra, rt)),
+            0x1C => Some(arithmetic::ai(thread, i10, ra, rt)),
+            0x1D => Some(arithmetic::ahi(thread, i10, ra, rt)),
+            0x24 => Some(memory::stqd(thread, i10, ra, rt)),
+            0x34 => Some(memory::lqd(thread, i10, ra, rt)),
+            0x44 => Some(logical::xori(thread, i10, ra, rt)),
+            0x45 => Some(logical::xorhi(thread, i10, ra, rt)),
+            0x46 => Some(logical::xorbi(thread, i10, ra, rt)),
+            0x4C => Some(compare::cgti(thread, i10, ra, rt)),
+            0x4D => Some(compare::cgthi(thread, i10, ra, rt)),
+            0x4E => Some(compare::cgtbi(thread, i10, ra, rt)),
+            0x4F => Some(compare::hgti(thread, i10, ra)),
+            0x5C => Some(compare::clgti(thread, i10, ra, rt)),
+            0x5D => Some(compare::clgthi(thread, i10, ra, rt)),
+            0x5E => Some(compare::clgtbi(thread, i10, ra, rt)),
+            0x5F => Some(compare::hlgti(thread, i10, ra)),
+            0x74 => Some(arithmetic::mpyi(thread, i10, ra, rt)),
+            0x75 => Some(arithmetic::mpyui(thread, i10, ra, rt)),
+            0x7C => Some(compare::ceqi(thread, i10, ra, rt)),
+            0x7D => Some(compare::ceqhi(thread, i10, ra, rt)),
+            0x7E => Some(compare::ceqbi(thread, i10, ra, rt)),
+            0x7F => Some(compare::heqi(thread, i10, ra)),
+            _ => None,
+        };
+        if let Some(result) = handled {
+            return result;
         }
-        Ok(())
-    }
 
-    /// Execute branch if not zero halfword (brhnz)
-    fn execute_branch_if_not_zero_halfword(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
+        // RI16 form
         let (i16_val, rt) = SpuDecoder::ri16_form(opcode);
-        let value = thread.regs.read_preferred_u32(rt as usize) & 0xFFFF;
-        if value != 0 {
-            let offset = (i16_val as i32) << 2;
-            let target = (thread.pc() as i32 + offset) as u32;
-            thread.set_pc(target);
-        } else {
-            thread.advance_pc();
+        let handled = match SpuDecoder::op9(opcode) {
+            0x040 => Some(branch::brz(thread, i16_val, rt)),
+            0x041 => Some(memory::stqa(thread, i16_val, rt)),
+            0x042 => Some(branch::brnz(thread, i16_val, rt)),
+            0x044 => Some(branch::brhz(thread, i16_val, rt)),
+            0x046 => Some(branch::brhnz(thread, i16_val, rt)),
+            0x047 => Some(memory::stqr(thread, i16_val, rt)),
+            0x060 => Some(branch::bra(thread, i16_val)),
+            0x061 => Some(memory::lqa(thread, i16_val, rt)),
+            0x062 => Some(branch::brasl(thread, i16_val, rt)),
+            0x064 => Some(branch::br(thread, i16_val)),
+            0x065 => Some(quadword::fsmbi(thread, i16_val, rt)),
+            0x066 => Some(branch::brsl(thread, i16_val, rt)),
+            0x067 => Some(memory::lqr(thread, i16_val, rt)),
+            0x081 => Some(constant::il(thread, i16_val, rt)),
+            0x082 => Some(constant::ilhu(thread, i16_val, rt)),
+            0x083 => Some(constant::ilh(thread, i16_val, rt)),
+            0x0C1 => Some(constant::iohl(thread, i16_val, rt)),
+            _ => None,
+        };
+        if let Some(result) = handled {
+            return result;
         }
-        Ok(())
-    }
 
-    /// Execute immediate load (il, ila, ilh, iohl)
-    fn execute_immediate_load(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
-        let op7 = SpuDecoder::op7(opcode);
-        let (i16_val, rt) = SpuDecoder::ri16_form(opcode);
-
-        match op7 {
-            // il - Immediate Load Word
-            0b0100000 => {
-                let value = i16_val as i32 as u32;
-                thread.regs.write_u32x4(rt as usize, [value, value, value, value]);
-            }
-            // ilh - Immediate Load Halfword
-            0b0100001 => {
-                let hword = i16_val as u16;
-                let value = ((hword as u32) << 16) | (hword as u32);
-                thread.regs.write_u32x4(rt as usize, [value, value, value, value]);
-            }
+        // RI8 form
+        let (i8_val, ra, rt) = SpuDecoder::ri8_form(opcode);
+        match SpuDecoder::op10(opcode) {
+            0x1D8 => return float::cflts(thread, i8_val, ra, rt),
+            0x1D9 => return float::cfltu(thread, i8_val, ra, rt),
+            0x1DA => return float::csflt(thread, i8_val, ra, rt),
+            0x1DB => return float::cuflt(thread, i8_val, ra, rt),
             _ => {}
         }
 
-        thread.advance_pc();
-        Ok(())
-    }
-
-    /// Execute add immediate (ai)
-    fn execute_ai(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
-        let (i10, ra, rt) = SpuDecoder::ri10_form(opcode);
-        let a = thread.regs.read_u32x4(ra as usize);
-        let result = [
-            (a[0] as i32).wrapping_add(i10 as i32) as u32,
-            (a[1] as i32).wrapping_add(i10 as i32) as u32,
-            (a[2] as i32).wrapping_add(i10 as i32) as u32,
-            (a[3] as i32).wrapping_add(i10 as i32) as u32,
-        ];
-        thread.regs.write_u32x4(rt as usize, result);
-        thread.advance_pc();
-        Ok(())
-    }
-
-    /// Execute add halfword immediate (ahi)
-    fn execute_ahi(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
-        let (i10, ra, rt) = SpuDecoder::ri10_form(opcode);
-        let a = thread.regs.read_u32x4(ra as usize);
-        let mut result = [0u32; 4];
-        for i in 0..4 {
-            let hi = ((a[i] >> 16) as i16).wrapping_add(i10) as u16;
-            let lo = ((a[i] & 0xFFFF) as i16).wrapping_add(i10) as u16;
-            result[i] = ((hi as u32) << 16) | (lo as u32);
-        }
-        thread.regs.write_u32x4(rt as usize, result);
-        thread.advance_pc();
-        Ok(())
-    }
-
-    /// Execute subtract from immediate (sfi)
-    fn execute_sfi(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
-        let (i10, ra, rt) = SpuDecoder::ri10_form(opcode);
-        let a = thread.regs.read_u32x4(ra as usize);
-        let result = [
-            (i10 as i32).wrapping_sub(a[0] as i32) as u32,
-            (i10 as i32).wrapping_sub(a[1] as i32) as u32,
-            (i10 as i32).wrapping_sub(a[2] as i32) as u32,
-            (i10 as i32).wrapping_sub(a[3] as i32) as u32,
-        ];
-        thread.regs.write_u32x4(rt as usize, result);
-        thread.advance_pc();
-        Ok(())
-    }
-
-    /// Execute subtract from halfword immediate (sfhi)
-    fn execute_sfhi(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
-        let (i10, ra, rt) = SpuDecoder::ri10_form(opcode);
-        let a = thread.regs.read_u32x4(ra as usize);
-        let mut result = [0u32; 4];
-        for i in 0..4 {
-            let hi = i10.wrapping_sub((a[i] >> 16) as i16) as u16;
-            let lo = i10.wrapping_sub((a[i] & 0xFFFF) as i16) as u16;
-            result[i] = ((hi as u32) << 16) | (lo as u32);
-        }
-        thread.regs.write_u32x4(rt as usize, result);
-        thread.advance_pc();
-        Ok(())
-    }
-
-    /// Execute add (a)
-    fn execute_add(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
-        let (rb, ra, rt) = SpuDecoder::rr_form(opcode);
-        let a = thread.regs.read_u32x4(ra as usize);
-        let b = thread.regs.read_u32x4(rb as usize);
-        let result = [
-            a[0].wrapping_add(b[0]),
-            a[1].wrapping_add(b[1]),
-            a[2].wrapping_add(b[2]),
-            a[3].wrapping_add(b[3]),
-        ];
-        thread.regs.write_u32x4(rt as usize, result);
-        thread.advance_pc();
-        Ok(())
-    }
-
-    /// Execute subtract (sf)
-    fn execute_subtract(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
-        let (rb, ra, rt) = SpuDecoder::rr_form(opcode);
-        let a = thread.regs.read_u32x4(ra as usize);
-        let b = thread.regs.read_u32x4(rb as usize);
-        let result = [
-            b[0].wrapping_sub(a[0]),
-            b[1].wrapping_sub(a[1]),
-            b[2].wrapping_sub(a[2]),
-            b[3].wrapping_sub(a[3]),
-        ];
-        thread.regs.write_u32x4(rt as usize, result);
-        thread.advance_pc();
-        Ok(())
-    }
-
-    /// Execute and
-    fn execute_and(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
+        // RR and RI7 forms
         let (rb, ra, rt) = SpuDecoder::rr_form(opcode);
-        let a = thread.regs.read_u32x4(ra as usize);
-        let b = thread.regs.read_u32x4(rb as usize);
-        let result = [a[0] & b[0], a[1] & b[1], a[2] & b[2], a[3] & b[3]];
-        thread.regs.write_u32x4(rt as usize, result);
-        thread.advance_pc();
-        Ok(())
+        let (i7, _, _) = SpuDecoder::ri7_form(opcode);
+        match SpuDecoder::op11(opcode) {
+            0x000 => self.execute_stop(thread, opcode),
+            // lnop, sync, dsync, mtspr, nop, hbr
+            0x001 | 0x002 | 0x003 | 0x10C | 0x201 | 0x1AC => self.nop(thread),
+            // mfspr: no special purpose registers are modelled
+            0x00C => self.load_zero(thread, rt),
+            0x00D => channel::rdch(thread, ra, rt),
+            0x00F => channel::rchcnt(thread, ra, rt),
+            0x10D => channel::wrch(thread, ra, rt),
+            0x140 => self.execute_stop(thread, opcode),
+
+            0x040 => arithmetic::sf(thread, rb, ra, rt),
+            0x041 => logical::or(thread, rb, ra, rt),
+            0x042 => arithmetic::bg(thread, rb, ra, rt),
+            0x048 => arithmetic::sfh(thread, rb, ra, rt),
+            0x049 => logical::nor(thread, rb, ra, rt),
+            0x053 => arithmetic::absdb(thread, rb, ra, rt),
+            0x058 => arithmetic::rot(thread, rb, ra, rt),
+            0x059 => arithmetic::rotm(thread, rb, ra, rt),
+            0x05A => arithmetic::rotma(thread, rb, ra, rt),
+            0x05B => arithmetic::shl(thread, rb, ra, rt),
+            0x05C => arithmetic::roth(thread, rb, ra, rt),
+            0x05D => arithmetic::rothm(thread, rb, ra, rt),
+            0x05E => arithmetic::rotmah(thread, rb, ra, rt),
+            0x05F => arithmetic::shlh(thread, rb, ra, rt),
+            0x078 => arithmetic::roti(thread, i7, ra, rt),
+            0x079 => arithmetic::rotmi(thread, i7, ra, rt),
+            0x07A => arithmetic::rotmai(thread, i7, ra, rt),
+            0x07B => arithmetic::shli(thread, i7, ra, rt),
+            0x07C => arithmetic::rothi(thread, i7, ra, rt),
+            0x07D => arithmetic::rothmi(thread, i7, ra, rt),
+            0x07E => arithmetic::rotmahi(thread, i7, ra, rt),
+            0x07F => arithmetic::shlhi(thread, i7, ra, rt),
+            0x0C0 => arithmetic::a(thread, rb, ra, rt),
+            0x0C1 => logical::and(thread, rb, ra, rt),
+            0x0C2 => arithmetic::cg(thread, rb, ra, rt),
+            0x0C8 => arithmetic::ah(thread, rb, ra, rt),
+            0x0C9 => logical::nand(thread, rb, ra, rt),
+            0x0D3 => arithmetic::avgb(thread, rb, ra, rt),
+
+            0x128 => branch::biz(thread, ra, rt),
+            0x129 => branch::binz(thread, ra, rt),
+            0x12A => branch::bihz(thread, ra, rt),
+            0x12B => branch::bihnz(thread, ra, rt),
+            0x144 => memory::stqx(thread, rb, ra, rt),
+            0x1A8 => branch::bi(thread, ra),
+            0x1A9 => branch::bisl(thread, ra, rt),
+            // iret: interrupts are not delivered, so there is nothing to return from
+            0x1AA => self.nop(thread),
+            0x1AB => branch::bisled(thread, ra, rt),
+            0x1B0 => quadword::gb(thread, ra, rt),
+            0x1B1 => quadword::gbh(thread, ra, rt),
+            0x1B2 => quadword::gbb(thread, ra, rt),
+            0x1B4 => quadword::fsm(thread, ra, rt),
+            0x1B5 => quadword::fsmh(thread, ra, rt),
+            0x1B6 => quadword::fsmb(thread, ra, rt),
+            0x1B8 => float::frest(thread, ra, rt),
+            0x1B9 => float::frsqest(thread, ra, rt),
+            0x1C4 => memory::lqx(thread, rb, ra, rt),
+            0x1CC => quadword::rotqbybi(thread, rb, ra, rt),
+            0x1CD => quadword::rotqmbybi(thread, rb, ra, rt),
+            0x1CF => quadword::shlqbybi(thread, rb, ra, rt),
+            0x1D4 => quadword::cbx(thread, rb, ra, rt),
+            0x1D5 => quadword::chx(thread, rb, ra, rt),
+            0x1D6 => quadword::cwx(thread, rb, ra, rt),
+            0x1D7 => quadword::cdx(thread, rb, ra, rt),
+            0x1D8 => quadword::rotqbi(thread, rb, ra, rt),
+            0x1D9 => quadword::rotqmbi(thread, rb, ra, rt),
+            0x1DB => quadword::shlqbi(thread, rb, ra, rt),
+            0x1DC => quadword::rotqby(thread, rb, ra, rt),
+            0x1DD => quadword::rotqmby(thread, rb, ra, rt),
+            0x1DF => quadword::shlqby(thread, rb, ra, rt),
+            0x1F0 => quadword::orx(thread, ra, rt),
+            0x1F4 => quadword::cbd(thread, i7, ra, rt),
+            0x1F5 => quadword::chd(thread, i7, ra, rt),
+            0x1F6 => quadword::cwd(thread, i7, ra, rt),
+            0x1F7 => quadword::cdd(thread, i7, ra, rt),
+            0x1F8 => quadword::rotqbii(thread, i7, ra, rt),
+            0x1F9 => quadword::rotqmbii(thread, i7, ra, rt),
+            0x1FB => quadword::shlqbii(thread, i7, ra, rt),
+            0x1FC => quadword::rotqbyi(thread, i7, ra, rt),
+            0x1FD => quadword::rotqmbyi(thread, i7, ra, rt),
+            0x1FF => quadword::shlqbyi(thread, i7, ra, rt),
+
+            0x240 => compare::cgt(thread, rb, ra, rt),
+            0x241 => logical::xor(thread, rb, ra, rt),
+            0x248 => compare::cgth(thread, rb, ra, rt),
+            0x249 => logical::eqv(thread, rb, ra, rt),
+            0x250 => compare::cgtb(thread, rb, ra, rt),
+            0x253 => arithmetic::sumb(thread, rb, ra, rt),
+            0x258 => compare::hgt(thread, rb, ra),
+            0x2A5 => arithmetic::clz(thread, ra, rt),
+            0x2A6 => arithmetic::xswd(thread, ra, rt),
+            0x2AE => arithmetic::xshw(thread, ra, rt),
+            0x2B4 => arithmetic::cntb(thread, ra, rt),
+            0x2B6 => arithmetic::xsbh(thread, ra, rt),
+            0x2C0 => compare::clgt(thread, rb, ra, rt),
+            0x2C1 => logical::andc(thread, rb, ra, rt),
+            0x2C2 => float::fcgt(thread, rb, ra, rt),
+            0x2C4 => float::fa(thread, rb, ra, rt),
+            0x2C5 => float::fs(thread, rb, ra, rt),
+            0x2C6 => float::fm(thread, rb, ra, rt),
+            0x2C8 => compare::clgth(thread, rb, ra, rt),
+            0x2C9 => logical::orc(thread, rb, ra, rt),
+            0x2CA => float::fcmgt(thread, rb, ra, rt),
+            0x2CC => float::dfa(thread, rb, ra, rt),
+            0x2CD => float::dfs(thread, rb, ra, rt),
+            0x2CE => float::dfm(thread, rb, ra, rt),
+            0x2D0 => compare::clgtb(thread, rb, ra, rt),
+            0x2D8 => compare::hlgt(thread, rb, ra),
+            0x340 => arithmetic::addx(thread, rb, ra, rt),
+            0x341 => arithmetic::sfx(thread, rb, ra, rt),
+            0x342 => arithmetic::cgx(thread, rb, ra, rt),
+            0x343 => arithmetic::bgx(thread, rb, ra, rt),
+            0x346 => arithmetic::mpyhha(thread, rb, ra, rt),
+            0x34E => arithmetic::mpyhhau(thread, rb, ra, rt),
+            0x35C => float::dfma(thread, rb, ra, rt),
+            0x35D => float::dfms(thread, rb, ra, rt),
+            0x35E => float::dfnms(thread, rb, ra, rt),
+            0x35F => float::dfnma(thread, rb, ra, rt),
+            // fscrrd: the FPSCR is not modelled, and fscrwr writes to it are dropped
+            0x398 => self.load_zero(thread, rt),
+            0x3BA => self.nop(thread),
+            0x3B8 => float::fesd(thread, ra, rt),
+            0x3B9 => float::frds(thread, ra, rt),
+            0x3C0 => compare::ceq(thread, rb, ra, rt),
+            0x3C2 => float::fceq(thread, rb, ra, rt),
+            0x3C4 => arithmetic::mpy(thread, rb, ra, rt),
+            0x3C5 => arithmetic::mpyh(thread, rb, ra, rt),
+            0x3C6 => arithmetic::mpyhh(thread, rb, ra, rt),
+            0x3C7 => arithmetic::mpys(thread, rb, ra, rt),
+            0x3C8 => compare::ceqh(thread, rb, ra, rt),
+            0x3CA => float::fcmeq(thread, rb, ra, rt),
+            0x3CC => arithmetic::mpyu(thread, rb, ra, rt),
+            0x3CE => arithmetic::mpyhhu(thread, rb, ra, rt),
+            0x3D0 => compare::ceqb(thread, rb, ra, rt),
+            0x3D4 => float::fi(thread, rb, ra, rt),
+            0x3D8 => compare::heq(thread, rb, ra),
+            _ => self.unknown(thread, opcode),
+        }
     }
 
-    /// Execute or
-    fn execute_or(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
-        let (rb, ra, rt) = SpuDecoder::rr_form(opcode);
-        let a = thread.regs.read_u32x4(ra as usize);
-        let b = thread.regs.read_u32x4(rb as usize);
-        let result = [a[0] | b[0], a[1] | b[1], a[2] | b[2], a[3] | b[3]];
-        thread.regs.write_u32x4(rt as usize, result);
+    /// Execute an instruction with no modelled effect
+    fn nop(&self, thread: &mut SpuThread) -> Result<(), SpuError> {
         thread.advance_pc();
         Ok(())
     }
 
-    /// Execute xor
-    fn execute_xor(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
-        let (rb, ra, rt) = SpuDecoder::rr_form(opcode);
-        let a = thread.regs.read_u32x4(ra as usize);
-        let b = thread.regs.read_u32x4(rb as usize);
-        let result = [a[0] ^ b[0], a[1] ^ b[1], a[2] ^ b[2], a[3] ^ b[3]];
-        thread.regs.write_u32x4(rt as usize, result);
+    /// Execute a read of state that is not modelled, which reads as zero
+    fn load_zero(&self, thread: &mut SpuThread, rt: u8) -> Result<(), SpuError> {
+        thread.regs.write_u32x4(rt as usize, [0; 4]);
         thread.advance_pc();
         Ok(())
     }
 
-    /// Execute nor
-    fn execute_nor(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
-        let (rb, ra, rt) = SpuDecoder::rr_form(opcode);
-        let a = thread.regs.read_u32x4(ra as usize);
-        let b = thread.regs.read_u32x4(rb as usize);
-        let result = [
-            !(a[0] | b[0]),
-            !(a[1] | b[1]),
-            !(a[2] | b[2]),
-            !(a[3] | b[3]),
-        ];
-        thread.regs.write_u32x4(rt as usize, result);
+    /// Skip an instruction the interpreter does not implement
+    fn unknown(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
+        tracing::warn!("Unknown SPU instruction 0x{:08x} at 0x{:05x}", opcode, thread.pc());
         thread.advance_pc();
         Ok(())
     }
 
-    /// Execute shuffle bytes (shufb)
-    fn execute_shufb(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
-        let (rc, rb, ra, rt) = SpuDecoder::rrr_form(opcode);
-        let a = thread.regs.read_u32x4(ra as usize);
-        let b = thread.regs.read_u32x4(rb as usize);
-        let c = thread.regs.read_u32x4(rc as usize);
-
-        // Convert to bytes with big-endian handling
-        let u32x4_to_bytes = |words: [u32; 4]| -> [u8; 16] {
-            let mut bytes = [0u8; 16];
-            for (i, word) in words.iter().enumerate() {
-                let wb = word.to_be_bytes();
-                bytes[i * 4] = wb[0];
-                bytes[i * 4 + 1] = wb[1];
-                bytes[i * 4 + 2] = wb[2];
-                bytes[i * 4 + 3] = wb[3];
-            }
-            bytes
-        };
-
-        let a_bytes = u32x4_to_bytes(a);
-        let b_bytes = u32x4_to_bytes(b);
-        let c_bytes = u32x4_to_bytes(c);
-
-        let mut result = [0u8; 16];
-        for i in 0..16 {
-            let sel = c_bytes[i];
-            result[i] = if sel & 0xC0 == 0xC0 {
-                if sel & 0xE0 == 0xE0 { 0xFF } else { 0x00 }
-            } else if sel & 0x10 == 0 {
-                a_bytes[(sel & 0x0F) as usize]
-            } else {
-                b_bytes[(sel & 0x0F) as usize]
-            };
-        }
-
-        // Convert bytes back to u32x4 with big-endian handling
-        let result_u32x4 = [
-            u32::from_be_bytes([result[0], result[1], result[2], result[3]]),
-            u32::from_be_bytes([result[4], result[5], result[6], result[7]]),
-            u32::from_be_bytes([result[8], result[9], result[10], result[11]]),
-            u32::from_be_bytes([result[12], result[13], result[14], result[15]]),
-        ];
-
-        thread.regs.write_u32x4(rt as usize, result_u32x4);
-        thread.advance_pc();
-        Ok(())
-    }
-
-    /// Execute stop
+    /// Execute stop and stopd
+    ///
+    /// The signal is left for the PPU side to read; the pc moves past the
+    /// stop so the thread resumes after it when restarted.
     fn execute_stop(&self, thread: &mut SpuThread, opcode: u32) -> Result<(), SpuError> {
-        let stop_type = (opcode >> 14) & 0x3FFF;
-        thread.stop_signal = stop_type;
-        thread.state = crate::thread::SpuThreadState::Halted;
+        thread.stop_signal = opcode & 0x3FFF;
+        thread.state = SpuThreadState::Halted;
+        thread.advance_pc();
         Ok(())
     }
 }
@@ -391,6 +338,7 @@ impl Default for SpuInterpreter {
 mod tests {
     use super::*;
     use oc_memory::MemoryManager;
+    use std::sync::Arc;
 
     fn create_test_thread() -> SpuThread {
         let memory = MemoryManager::new().unwrap();
@@ -402,6 +350,30 @@ mod tests {
         let _interpreter = SpuInterpreter::new();
     }
 
+    /// Encode an RRR-form instruction
+    fn rrr(op4: u32, rt: u32, rb: u32, ra: u32, rc: u32) -> u32 {
+        (op4 << 28) | (rt << 21) | (rb << 14) | (ra << 7) | rc
+    }
+
+    /// Encode an RR-form instruction
+    fn rr(op11: u32, rb: u32, ra: u32, rt: u32) -> u32 {
+        (op11 << 21) | (rb << 14) | (ra << 7) | rt
+    }
+
+    /// Encode an RI16-form instruction
+    fn ri16(op9: u32, i16_val: u16, rt: u32) -> u32 {
+        (op9 << 23) | ((i16_val as u32) << 7) | rt
+    }
+
+    /// Place `program` at the start of local storage and start the thread
+    fn load_program(thread: &mut SpuThread, program: &[u32]) {
+        for (i, &opcode) in program.iter().enumerate() {
+            thread.ls_write_u32(i as u32 * 4, opcode);
+        }
+        thread.set_pc(0);
+        thread.start();
+    }
+
     #[test]
     fn test_add_instruction() {
         let mut thread = create_test_thread();
@@ -411,33 +383,35 @@ mod tests {
         thread.regs.write_u32x4(1, [1, 2, 3, 4]);
         thread.regs.write_u32x4(2, [10, 20, 30, 40]);
 
-        // a rt, ra, rb - add r3, r1, r2 (opcode would be constructed)
-        // For now, just verify interpreter creation works
-        drop(interpreter);
+        // a $3, $1, $2
+        thread.ls_write_u32(0, rr(0x0C0, 2, 1, 3));
+        interpreter.step(&mut thread).unwrap();
+        assert_eq!(thread.regs.read_u32x4(3), [11, 22, 33, 44]);
+        assert_eq!(thread.pc(), 4);
+    }
+
+    /// Run shufb $4, $1, $2, $3
+    fn run_shufb(thread: &mut SpuThread) {
+        thread.ls_write_u32(0, rrr(0b1011, 4, 2, 1, 3));
+        thread.set_pc(0);
+        SpuInterpreter::new().step(thread).unwrap();
     }
 
     #[test]
     fn test_shufb_identity() {
         // Test shufb with identity permutation
         let mut thread = create_test_thread();
-        let interpreter = SpuInterpreter::new();
-        
+
         // Set up source registers (ra=1, rb=2)
         // Pattern: bytes 0-15 in a, 16-31 in b
         thread.regs.write_u32x4(1, [0x00010203, 0x04050607, 0x08090A0B, 0x0C0D0E0F]);
         thread.regs.write_u32x4(2, [0x10111213, 0x14151617, 0x18191A1B, 0x1C1D1E1F]);
-        
+
         // Control register (rc=3): identity permutation (select bytes 0-15)
         thread.regs.write_u32x4(3, [0x00010203, 0x04050607, 0x08090A0B, 0x0C0D0E0F]);
-        
-        // shufb rt, ra, rb, rc
-        // RRR form: rc=3, rb=2, ra=1, rt=4
-        // Bits: [rc:7][rb:7][ra:7][rt:7] = [3][2][1][4]
-        // Opcode = (3 << 21) | (2 << 14) | (1 << 7) | 4
-        let opcode = (3 << 21) | (2 << 14) | (1 << 7) | 4;
-        
-        interpreter.execute_shufb(&mut thread, opcode).unwrap();
-        
+
+        run_shufb(&mut thread);
+
         // Result should be same as ra (identity permutation)
         let result = thread.regs.read_u32x4(4);
         assert_eq!(result, [0x00010203, 0x04050607, 0x08090A0B, 0x0C0D0E0F]);
@@ -447,63 +421,141 @@ mod tests {
     fn test_shufb_select_from_second() {
         // Test selecting all bytes from second source (rb)
         let mut thread = create_test_thread();
-        let interpreter = SpuInterpreter::new();
-        
+
         thread.regs.write_u32x4(1, [0x00010203, 0x04050607, 0x08090A0B, 0x0C0D0E0F]);
         thread.regs.write_u32x4(2, [0xAABBCCDD, 0xEEFF0011, 0x22334455, 0x66778899]);
-        
+
         // Control: select bytes 0-15 from second source (indices 16-31 map to rb, using 0x10-0x1F)
         thread.regs.write_u32x4(3, [0x10111213, 0x14151617, 0x18191A1B, 0x1C1D1E1F]);
-        
-        let opcode = (3 << 21) | (2 << 14) | (1 << 7) | 4;
-        interpreter.execute_shufb(&mut thread, opcode).unwrap();
-        
+
+        run_shufb(&mut thread);
+
         let result = thread.regs.read_u32x4(4);
         assert_eq!(result, [0xAABBCCDD, 0xEEFF0011, 0x22334455, 0x66778899]);
     }
 
     #[test]
     fn test_shufb_special_values() {
-        // Test special control byte values (0xC0-0xDF = 0x00, 0xE0-0xFF = 0xFF)
+        // Test special control byte values (0x80-0xBF = 0x00, 0xC0-0xDF = 0xFF, 0xE0-0xFF = 0x80)
         let mut thread = create_test_thread();
-        let interpreter = SpuInterpreter::new();
-        
+
         thread.regs.write_u32x4(1, [0x12345678, 0x12345678, 0x12345678, 0x12345678]);
         thread.regs.write_u32x4(2, [0x12345678, 0x12345678, 0x12345678, 0x12345678]);
-        
-        // Control: 0xC0-0xDF = 0x00, 0xE0-0xFF = 0xFF
-        thread.regs.write_u32x4(3, [0xC0C0C0C0, 0xE0E0E0E0, 0xD0D0D0D0, 0xFFFFFFFF]);
-        
-        let opcode = (3 << 21) | (2 << 14) | (1 << 7) | 4;
-        interpreter.execute_shufb(&mut thread, opcode).unwrap();
-        
+
+        thread.regs.write_u32x4(3, [0x80808080, 0xC0C0C0C0, 0xD0D0D0D0, 0xE0E0E0FF]);
+
+        run_shufb(&mut thread);
+
         let result = thread.regs.read_u32x4(4);
-        // 0xC0 should produce 0x00 (C0-DF range)
+        // 0x80 should produce 0x00 (80-BF range)
         assert_eq!(result[0], 0x00000000);
-        // 0xE0 should produce 0xFF (E0-FF range)
+        // 0xC0 should produce 0xFF (C0-DF range)
         assert_eq!(result[1], 0xFFFFFFFF);
-        // 0xD0 should produce 0x00 (C0-DF range)
-        assert_eq!(result[2], 0x00000000);
-        // 0xFF should produce 0xFF
-        assert_eq!(result[3], 0xFFFFFFFF);
+        // 0xD0 should produce 0xFF (C0-DF range)
+        assert_eq!(result[2], 0xFFFFFFFF);
+        // 0xE0 and 0xFF should produce 0x80
+        assert_eq!(result[3], 0x80808080);
     }
 
     #[test]
     fn test_shufb_reverse_bytes() {
         // Test reversing byte order
         let mut thread = create_test_thread();
-        let interpreter = SpuInterpreter::new();
-        
+
         thread.regs.write_u32x4(1, [0x00010203, 0x04050607, 0x08090A0B, 0x0C0D0E0F]);
         thread.regs.write_u32x4(2, [0, 0, 0, 0]);
-        
+
         // Control: reverse byte order (15, 14, 13, ... 0)
         thread.regs.write_u32x4(3, [0x0F0E0D0C, 0x0B0A0908, 0x07060504, 0x03020100]);
-        
-        let opcode = (3 << 21) | (2 << 14) | (1 << 7) | 4;
-        interpreter.execute_shufb(&mut thread, opcode).unwrap();
-        
+
+        run_shufb(&mut thread);
+
         let result = thread.regs.read_u32x4(4);
         assert_eq!(result, [0x0F0E0D0C, 0x0B0A0908, 0x07060504, 0x03020100]);
     }
+
+    #[test]
+    fn test_dma_program() {
+        use crate::channels::channel_ids::*;
+
+        let mut thread = create_test_thread();
+        let interpreter = SpuInterpreter::new();
+        let memory = Arc::clone(thread.memory());
+        memory.write_bytes(0x0010_0000, &[0x11; 32]).unwrap();
+
+        let wrch = |channel: u32, rt: u32| rr(0x10D, 0, channel, rt);
+        let rdch = |channel: u32, rt: u32| rr(0x00D, 0, channel, rt);
+        let il = |value: u16, rt: u32| ri16(0x081, value, rt);
+        let ilhu = |value: u16, rt: u32| ri16(0x082, value, rt);
+        load_program(
+            &mut thread,
+            &[
+                // GET 32 bytes from 0x100000 into 0x1000 with tag 3
+                il(0x1000, 1),
+                ilhu(0x0010, 2),
+                il(32, 3),
+                il(3, 4),
+                il(0x40, 5),
+                wrch(MFC_LSA, 1),
+                wrch(MFC_EAL, 2),
+                wrch(MFC_SIZE, 3),
+                wrch(MFC_TAG_ID, 4),
+                wrch(MFC_CMD, 5),
+                // Wait for tag 3
+                il(1 << 3, 6),
+                il(2, 7),
+                wrch(MFC_WR_TAG_MASK, 6),
+                wrch(MFC_WR_TAG_UPDATE, 7),
+                rdch(MFC_RD_TAG_STAT, 8),
+                // Wait for the PPU, then PUT the data back after the mailbox value
+                rdch(SPU_RD_IN_MBOX, 9),
+                wrch(MFC_EAL, 9),
+                il(0x20, 5),
+                wrch(MFC_CMD, 5),
+                0, // stop 0
+            ],
+        );
+
+        let (executed, result) = interpreter.run(&mut thread, 1000);
+        assert!(result.is_ok());
+        // Blocked on the empty inbound mailbox
+        assert_eq!(executed, 16);
+        assert_eq!(thread.pc(), 15 * 4);
+        assert!(thread.is_running());
+        assert_eq!(thread.regs.read_preferred_u32(8), 1 << 3);
+        assert_eq!(thread.ls_read_u32(0x1000), 0x1111_1111);
+
+        thread.channels.put_inbound_mailbox(0x0010_0100);
+        let (executed, result) = interpreter.run(&mut thread, 1000);
+        assert!(result.is_ok());
+        assert_eq!(executed, 5);
+        assert_eq!(thread.state, SpuThreadState::Halted);
+        assert_eq!(thread.pc(), 20 * 4);
+        assert_eq!(memory.read_bytes(0x0010_0100, 32).unwrap(), vec![0x11; 32]);
+    }
+
+    #[test]
+    fn test_channel_instructions() {
+        use crate::channels::channel_ids::{SPU_RD_IN_MBOX, SPU_WR_OUT_MBOX};
+
+        let mut thread = create_test_thread();
+        let interpreter = SpuInterpreter::new();
+
+        // wrch $SPU_WrOutMbox, $1
+        thread.regs.write_preferred_u32(1, 0xCAFE);
+        thread.ls_write_u32(0, (0b00100001101 << 21) | (SPU_WR_OUT_MBOX << 7) | 1);
+        // rchcnt $2, $SPU_RdInMbox
+        thread.ls_write_u32(4, (0b00000001111 << 21) | (SPU_RD_IN_MBOX << 7) | 2);
+        // rdch $3, $SPU_RdInMbox
+        thread.ls_write_u32(8, (0b00000001101 << 21) | (SPU_RD_IN_MBOX << 7) | 3);
+        thread.channels.put_inbound_mailbox(0x1234);
+
+        for _ in 0..3 {
+            interpreter.step(&mut thread).unwrap();
+        }
+        assert_eq!(thread.pc(), 12);
+        assert_eq!(thread.channels.get_outbound_mailbox(), Some(0xCAFE));
+        assert_eq!(thread.regs.read_preferred_u32(2), 1);
+        assert_eq!(thread.regs.read_preferred_u32(3), 0x1234);
+    }
 }
@@ -1,8 +1,37 @@
 //! SPU Memory Flow Controller (MFC)
 //!
 //! The MFC handles DMA transfers between SPU local storage and main memory.
+//!
+//! Programs drive it through channels: the parameters of a command go to
+//! MFC_LSA, MFC_EAH/EAL, MFC_Size and MFC_TagID, and writing the opcode to
+//! MFC_Cmd issues it. Commands issued that way run against main memory
+//! right away and complete their tag group at once, so MFC_RdTagStat never
+//! has to wait for a transfer in flight.
 
 use std::collections::VecDeque;
+use std::io;
+use oc_core::error::SpuError;
+use oc_core::savestate::{StateReader, StateWriter};
+use oc_memory::MemoryManager;
+
+/// Largest single DMA transfer
+pub const MFC_MAX_DMA_SIZE: u32 = 16 * 1024;
+
+/// MFC_WrTagUpdate: report the tag status at once
+pub const MFC_TAG_UPDATE_IMMEDIATE: u32 = 0;
+/// MFC_WrTagUpdate: report once any tag group in the mask completed
+pub const MFC_TAG_UPDATE_ANY: u32 = 1;
+/// MFC_WrTagUpdate: report once all tag groups in the mask completed
+pub const MFC_TAG_UPDATE_ALL: u32 = 2;
+
+/// MFC_RdAtomicStat after a PUTLLC that stored
+pub const MFC_PUTLLC_SUCCESS: u32 = 0;
+/// MFC_RdAtomicStat after a PUTLLC that lost its reservation
+pub const MFC_PUTLLC_FAILURE: u32 = 1;
+/// MFC_RdAtomicStat after a PUTLLUC
+pub const MFC_PUTLLUC_SUCCESS: u32 = 2;
+/// MFC_RdAtomicStat after a GETLLAR
+pub const MFC_GETLLAR_SUCCESS: u32 = 4;
 
 /// MFC command opcodes
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -14,6 +43,12 @@ pub enum MfcCommand {
     PutB = 0x21,
     /// Put with fence
     PutF = 0x22,
+    /// Put list
+    PutL = 0x24,
+    /// Put list with barrier
+    PutLB = 0x25,
+    /// Put list with fence
+    PutLF = 0x26,
     /// Put unconditional
     PutU = 0x28,
     /// Get (main to local)
@@ -22,8 +57,16 @@ pub enum MfcCommand {
     GetB = 0x41,
     /// Get with fence
     GetF = 0x42,
+    /// Get list
+    GetL = 0x44,
+    /// Get list with barrier
+    GetLB = 0x45,
+    /// Get list with fence
+    GetLF = 0x46,
     /// Get unconditional
     GetU = 0x48,
+    /// Send signal
+    SndSig = 0xA0,
     /// Get Lock Line Unconditional (atomic reservation)
     GetLLAR = 0xD0,
     /// Put Lock Line Conditional (atomic store)
@@ -32,6 +75,10 @@ pub enum MfcCommand {
     PutLLUC = 0xB0,
     /// Barrier
     Barrier = 0xC0,
+    /// Enforce in-order execution of I/O
+    Eieio = 0xC8,
+    /// Synchronize
+    Sync = 0xCC,
     /// Unknown/Invalid
     Unknown = 0xFF,
 }
@@ -41,12 +88,13 @@ impl MfcCommand {
     pub fn base_latency(&self) -> u64 {
         match self {
             Self::Get | Self::GetU => 100,
-            Self::GetB | Self::GetF => 120,
+            Self::GetB | Self::GetF | Self::GetL | Self::GetLB | Self::GetLF => 120,
             Self::Put | Self::PutU => 80,
-            Self::PutB | Self::PutF => 100,
+            Self::PutB | Self::PutF | Self::PutL | Self::PutLB | Self::PutLF => 100,
             Self::GetLLAR => 150,
             Self::PutLLC | Self::PutLLUC => 120,
-            Self::Barrier => 50,
+            Self::SndSig => 80,
+            Self::Barrier | Self::Eieio | Self::Sync => 50,
             Self::Unknown => 0,
         }
     }
@@ -64,15 +112,24 @@ impl From<u8> for MfcCommand {
             0x20 => Self::Put,
             0x21 => Self::PutB,
             0x22 => Self::PutF,
+            0x24 => Self::PutL,
+            0x25 => Self::PutLB,
+            0x26 => Self::PutLF,
             0x28 => Self::PutU,
             0x40 => Self::Get,
             0x41 => Self::GetB,
             0x42 => Self::GetF,
+            0x44 => Self::GetL,
+            0x45 => Self::GetLB,
+            0x46 => Self::GetLF,
             0x48 => Self::GetU,
+            0xA0 => Self::SndSig,
             0xD0 => Self::GetLLAR,
             0xB4 => Self::PutLLC,
             0xB0 => Self::PutLLUC,
             0xC0 => Self::Barrier,
+            0xC8 => Self::Eieio,
+            0xCC => Self::Sync,
             _ => Self::Unknown,
         }
     }
@@ -106,6 +163,21 @@ pub struct MfcListElement {
     pub size: u16,
 }
 
+/// Parameters of the next command, written to the MFC parameter channels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MfcParams {
+    /// MFC_LSA
+    pub lsa: u32,
+    /// MFC_EAH
+    pub eah: u32,
+    /// MFC_EAL, the local storage address of the list for list commands
+    pub eal: u32,
+    /// MFC_Size, the size of the list for list commands
+    pub size: u32,
+    /// MFC_TagID
+    pub tag: u32,
+}
+
 /// MFC state
 pub struct Mfc {
     /// Command queue
@@ -128,6 +200,14 @@ pub struct Mfc {
     stall_notify_tag: u8,
     /// List stall flag
     list_stall: bool,
+    /// Parameters of the next command
+    params: MfcParams,
+    /// Pending MFC_WrTagUpdate request
+    tag_update: Option<u32>,
+    /// Result of the last atomic command, until read from MFC_RdAtomicStat
+    atomic_status: Option<u32>,
+    /// Timestamp of the main memory reservation taken by GETLLAR
+    reservation_time: u64,
 }
 
 impl Mfc {
@@ -144,6 +224,10 @@ impl Mfc {
             pending_tags: 0,
             stall_notify_tag: 0,
             list_stall: false,
+            params: MfcParams::default(),
+            tag_update: None,
+            atomic_status: None,
+            reservation_time: 0,
         }
     }
 
@@ -260,6 +344,11 @@ impl Mfc {
         self.reservation_valid = false;
     }
 
+    /// Commands in the queue, oldest first
+    pub fn queued_commands(&self) -> impl Iterator<Item = &MfcDmaCommand> {
+        self.queue.iter()
+    }
+
     /// Get queue size
     pub fn queue_len(&self) -> usize {
         self.queue.len()
@@ -474,6 +563,288 @@ impl Mfc {
     pub fn get_stall_notify_tag(&self) -> u8 {
         self.stall_notify_tag
     }
+
+    /// Parameters of the next command
+    pub fn params(&self) -> &MfcParams {
+        &self.params
+    }
+
+    /// Parameters of the next command, for the MFC parameter channels
+    pub fn params_mut(&mut self) -> &mut MfcParams {
+        &mut self.params
+    }
+
+    /// Request a tag status update (MFC_WrTagUpdate)
+    pub fn request_tag_update(&mut self, kind: u32) {
+        self.tag_update = Some(kind);
+    }
+
+    /// Whether the requested tag status update is satisfied
+    fn tag_update_ready(&self) -> bool {
+        let status = self.tag_status & self.tag_query_mask;
+        match self.tag_update {
+            Some(MFC_TAG_UPDATE_ANY) => status != 0,
+            Some(MFC_TAG_UPDATE_ALL) => status == self.tag_query_mask,
+            _ => true,
+        }
+    }
+
+    /// Read MFC_RdTagStat: the completed tag groups of the mask, or None
+    /// while the requested update is not satisfied
+    ///
+    /// Without a request the status is reported at once.
+    pub fn read_tag_update(&mut self) -> Option<u32> {
+        if !self.tag_update_ready() {
+            return None;
+        }
+        self.tag_update = None;
+        Some(self.tag_status & self.tag_query_mask)
+    }
+
+    /// MFC_RdTagStat channel count
+    pub fn tag_update_count(&self) -> u32 {
+        (self.tag_update.is_some() && self.tag_update_ready()) as u32
+    }
+
+    /// Read MFC_RdAtomicStat, None before an atomic command completed
+    pub fn take_atomic_status(&mut self) -> Option<u32> {
+        self.atomic_status.take()
+    }
+
+    /// MFC_RdAtomicStat channel count
+    pub fn atomic_status_count(&self) -> u32 {
+        self.atomic_status.is_some() as u32
+    }
+
+    /// Read MFC_RdListStallStat: the tag groups stalled on a list element
+    /// with the stall-and-notify flag, or None when no list stalled
+    pub fn take_list_stall(&mut self) -> Option<u32> {
+        if !self.list_stall {
+            return None;
+        }
+        self.list_stall = false;
+        Some(1 << self.stall_notify_tag)
+    }
+
+    /// Run the command written to MFC_Cmd with the current parameters
+    ///
+    /// Transfers go straight between `local_storage` and `memory`, so the
+    /// tag group is complete when this returns. List elements flagged
+    /// stall-and-notify are reported on MFC_RdListStallStat, but the rest of
+    /// the list does not wait for the acknowledgement.
+    pub fn issue(&mut self, opcode: u32, local_storage: &mut [u8], memory: &MemoryManager) -> Result<(), SpuError> {
+        let MfcParams { lsa, eal, size, tag, .. } = self.params;
+        let tag = (tag & 0x1F) as u8;
+        match MfcCommand::from(opcode as u8) {
+            MfcCommand::Get | MfcCommand::GetB | MfcCommand::GetF | MfcCommand::GetU => {
+                dma_get(lsa, eal, size, local_storage, memory)?;
+            }
+            MfcCommand::Put | MfcCommand::PutB | MfcCommand::PutF | MfcCommand::PutU => {
+                dma_put(lsa, eal, size, local_storage, memory)?;
+            }
+            cmd @ (MfcCommand::GetL
+            | MfcCommand::GetLB
+            | MfcCommand::GetLF
+            | MfcCommand::PutL
+            | MfcCommand::PutLB
+            | MfcCommand::PutLF) => {
+                let get = matches!(cmd, MfcCommand::GetL | MfcCommand::GetLB | MfcCommand::GetLF);
+                let mut element_lsa = lsa;
+                for index in 0..(size / 8).min(2048) {
+                    let element = ls_slice(local_storage, eal.wrapping_add(index * 8) & !7, 8)?;
+                    let header = u32::from_be_bytes([element[0], element[1], element[2], element[3]]);
+                    let ea = u32::from_be_bytes([element[4], element[5], element[6], element[7]]);
+                    let element_size = header & 0x7FFF;
+                    if element_size != 0 {
+                        // Small transfers keep the alignment of their effective address
+                        let dest = element_lsa | (ea & 0xF);
+                        if get {
+                            dma_get(dest, ea, element_size, local_storage, memory)?;
+                        } else {
+                            dma_put(dest, ea, element_size, local_storage, memory)?;
+                        }
+                    }
+                    element_lsa = element_lsa.wrapping_add(element_size.next_multiple_of(16));
+                    if header & 0x8000_0000 != 0 {
+                        self.set_list_stall(tag);
+                    }
+                }
+            }
+            MfcCommand::GetLLAR => {
+                let line = eal & !127;
+                let data = read_main(memory, line, 128)?;
+                self.reservation_time = memory.reservation(line).acquire();
+                ls_slice_mut(local_storage, lsa & !127, 128)?.copy_from_slice(&data);
+                self.set_reservation(line as u64, &data);
+                self.atomic_status = Some(MFC_GETLLAR_SUCCESS);
+                // Atomic commands have no tag group
+                return Ok(());
+            }
+            MfcCommand::PutLLC => {
+                let line = eal & !127;
+                let locked = self.reservation_valid
+                    && self.reservation_addr == line as u64
+                    && memory.reservation(line).try_lock(self.reservation_time);
+                let mut stored = false;
+                if locked {
+                    // Plain stores do not bump the timestamp, so compare the line too
+                    let result = read_main(memory, line, 128).and_then(|current| {
+                        if current != self.reservation_data {
+                            return Ok(false);
+                        }
+                        write_main(memory, line, ls_slice(local_storage, lsa & !127, 128)?).map(|_| true)
+                    });
+                    memory.reservation(line).unlock_and_increment();
+                    stored = result?;
+                }
+                self.reservation_valid = false;
+                self.atomic_status = Some(if stored { MFC_PUTLLC_SUCCESS } else { MFC_PUTLLC_FAILURE });
+                return Ok(());
+            }
+            MfcCommand::PutLLUC => {
+                let line = eal & !127;
+                let data = ls_slice(local_storage, lsa & !127, 128)?.to_vec();
+                write_main(memory, line, &data)?;
+                memory.reservation(line).invalidate();
+                if self.reservation_addr == line as u64 {
+                    self.reservation_valid = false;
+                }
+                self.atomic_status = Some(MFC_PUTLLUC_SUCCESS);
+                return Ok(());
+            }
+            MfcCommand::SndSig => {
+                tracing::warn!("SPU sndsig to 0x{:08X} is not supported", eal);
+            }
+            MfcCommand::Barrier | MfcCommand::Eieio | MfcCommand::Sync => {}
+            MfcCommand::Unknown => {
+                return Err(SpuError::MfcError(format!("Unknown MFC command 0x{:02X}", opcode & 0xFF)));
+            }
+        }
+        self.complete_tag(tag);
+        Ok(())
+    }
+
+    /// Save queued commands, tag status and the reservation
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.count(self.queue.len());
+        for cmd in &self.queue {
+            w.u32(cmd.lsa);
+            w.u64(cmd.ea);
+            w.u32(cmd.size);
+            w.u8(cmd.tag);
+            w.u8(cmd.cmd as u8);
+            w.u64(cmd.issue_cycle);
+            w.u64(cmd.completion_cycle);
+        }
+        w.u32(self.tag_status);
+        w.u32(self.tag_query_mask);
+        w.u64(self.reservation_addr);
+        w.raw(&self.reservation_data);
+        w.bool(self.reservation_valid);
+        w.u64(self.cycle_counter);
+        w.u32(self.pending_tags);
+        w.u8(self.stall_notify_tag);
+        w.bool(self.list_stall);
+        for value in [self.params.lsa, self.params.eah, self.params.eal, self.params.size, self.params.tag] {
+            w.u32(value);
+        }
+        for value in [self.tag_update, self.atomic_status] {
+            w.bool(value.is_some());
+            w.u32(value.unwrap_or(0));
+        }
+        w.u64(self.reservation_time);
+    }
+
+    /// Restore state written by [`Mfc::save_state`]
+    pub fn load_state(&mut self, r: &mut StateReader) -> io::Result<()> {
+        let len = r.count()?;
+        self.queue.clear();
+        for _ in 0..len {
+            self.queue.push_back(MfcDmaCommand {
+                lsa: r.u32()?,
+                ea: r.u64()?,
+                size: r.u32()?,
+                tag: r.u8()?,
+                cmd: MfcCommand::from(r.u8()?),
+                issue_cycle: r.u64()?,
+                completion_cycle: r.u64()?,
+            });
+        }
+        self.tag_status = r.u32()?;
+        self.tag_query_mask = r.u32()?;
+        self.reservation_addr = r.u64()?;
+        self.reservation_data.copy_from_slice(r.raw(128)?);
+        self.reservation_valid = r.bool()?;
+        self.cycle_counter = r.u64()?;
+        self.pending_tags = r.u32()?;
+        self.stall_notify_tag = r.u8()?;
+        self.list_stall = r.bool()?;
+        self.params = MfcParams {
+            lsa: r.u32()?,
+            eah: r.u32()?,
+            eal: r.u32()?,
+            size: r.u32()?,
+            tag: r.u32()?,
+        };
+        let mut optional = || -> io::Result<Option<u32>> {
+            let present = r.bool()?;
+            let value = r.u32()?;
+            Ok(present.then_some(value))
+        };
+        self.tag_update = optional()?;
+        self.atomic_status = optional()?;
+        self.reservation_time = r.u64()?;
+        Ok(())
+    }
+}
+
+/// `size` bytes of local storage at `lsa`
+fn ls_slice(local_storage: &[u8], lsa: u32, size: u32) -> Result<&[u8], SpuError> {
+    let start = lsa as usize & (local_storage.len() - 1);
+    local_storage
+        .get(start..start + size as usize)
+        .ok_or_else(|| SpuError::MfcError(format!("DMA of 0x{:X} bytes runs past local storage at 0x{:05X}", size, start)))
+}
+
+/// `size` bytes of local storage at `lsa`, for writing
+fn ls_slice_mut(local_storage: &mut [u8], lsa: u32, size: u32) -> Result<&mut [u8], SpuError> {
+    let start = lsa as usize & (local_storage.len() - 1);
+    local_storage
+        .get_mut(start..start + size as usize)
+        .ok_or_else(|| SpuError::MfcError(format!("DMA of 0x{:X} bytes runs past local storage at 0x{:05X}", size, start)))
+}
+
+fn read_main(memory: &MemoryManager, ea: u32, size: u32) -> Result<Vec<u8>, SpuError> {
+    memory
+        .read_bytes(ea, size)
+        .map_err(|e| SpuError::MfcError(format!("DMA read of 0x{:X} bytes at 0x{:08X}: {}", size, ea, e)))
+}
+
+fn write_main(memory: &MemoryManager, ea: u32, data: &[u8]) -> Result<(), SpuError> {
+    memory
+        .write_bytes(ea, data)
+        .map_err(|e| SpuError::MfcError(format!("DMA write of 0x{:X} bytes at 0x{:08X}: {}", data.len(), ea, e)))
+}
+
+fn check_size(size: u32) -> Result<(), SpuError> {
+    if size > MFC_MAX_DMA_SIZE {
+        return Err(SpuError::MfcError(format!("DMA size 0x{:X} is over 16 KB", size)));
+    }
+    Ok(())
+}
+
+/// Copy main memory at `ea` to local storage at `lsa`
+fn dma_get(lsa: u32, ea: u32, size: u32, local_storage: &mut [u8], memory: &MemoryManager) -> Result<(), SpuError> {
+    check_size(size)?;
+    let data = read_main(memory, ea, size)?;
+    ls_slice_mut(local_storage, lsa, size)?.copy_from_slice(&data);
+    Ok(())
+}
+
+/// Copy local storage at `lsa` to main memory at `ea`
+fn dma_put(lsa: u32, ea: u32, size: u32, local_storage: &[u8], memory: &MemoryManager) -> Result<(), SpuError> {
+    check_size(size)?;
+    write_main(memory, ea, ls_slice(local_storage, lsa, size)?)
 }
 
 impl Default for Mfc {
@@ -648,4 +1019,42 @@ mod tests {
         // But at least one is complete (tag 1)
         assert!(mfc.check_tag_status_any());
     }
+
+    #[test]
+    fn test_issue_atomic_and_list() {
+        let memory = MemoryManager::new().unwrap();
+        let mut mfc = Mfc::new();
+        let mut local_storage = vec![0u8; 0x4000];
+        memory.write_bytes(0x0010_0000, &[7; 128]).unwrap();
+
+        // GETLLAR then PUTLLC with nothing in between succeeds
+        *mfc.params_mut() = MfcParams { lsa: 0x1000, eal: 0x0010_0000, ..Default::default() };
+        mfc.issue(MfcCommand::GetLLAR as u32, &mut local_storage, &memory).unwrap();
+        assert_eq!(mfc.take_atomic_status(), Some(MFC_GETLLAR_SUCCESS));
+        assert_eq!(mfc.take_atomic_status(), None);
+        local_storage[0x1000] = 9;
+        mfc.issue(MfcCommand::PutLLC as u32, &mut local_storage, &memory).unwrap();
+        assert_eq!(mfc.take_atomic_status(), Some(MFC_PUTLLC_SUCCESS));
+        assert_eq!(memory.read_bytes(0x0010_0000, 1).unwrap(), [9]);
+
+        // A store by someone else in between makes it fail
+        mfc.issue(MfcCommand::GetLLAR as u32, &mut local_storage, &memory).unwrap();
+        assert_eq!(mfc.take_atomic_status(), Some(MFC_GETLLAR_SUCCESS));
+        memory.write_bytes(0x0010_0040, &[1]).unwrap();
+        mfc.issue(MfcCommand::PutLLC as u32, &mut local_storage, &memory).unwrap();
+        assert_eq!(mfc.take_atomic_status(), Some(MFC_PUTLLC_FAILURE));
+
+        // List GET of two elements, the second flagged stall-and-notify
+        local_storage[0x2000..0x2010].copy_from_slice(&[0, 0, 0, 0x10, 0, 0x10, 0, 0, 0x80, 0, 0, 4, 0, 0x10, 0, 0x44]);
+        *mfc.params_mut() = MfcParams { lsa: 0x3000, eal: 0x2000, size: 16, tag: 5, ..Default::default() };
+        mfc.issue(MfcCommand::GetL as u32, &mut local_storage, &memory).unwrap();
+        assert_eq!(local_storage[0x3000], 9);
+        // The second element lands 16 bytes on, keeping its address alignment
+        assert_eq!(local_storage[0x3014], 7);
+        assert_eq!(mfc.take_list_stall(), Some(1 << 5));
+        assert!(mfc.check_tags(1 << 5));
+
+        *mfc.params_mut() = MfcParams { size: MFC_MAX_DMA_SIZE + 16, ..Default::default() };
+        assert!(mfc.issue(MfcCommand::Get as u32, &mut local_storage, &memory).is_err());
+    }
 }
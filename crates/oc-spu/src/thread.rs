@@ -1,7 +1,10 @@
 //! SPU thread state
 
+use std::io;
 use std::sync::Arc;
 use oc_memory::MemoryManager;
+use oc_core::condition::{register_index, ConditionContext};
+use oc_core::savestate::{invalid, StateReader, StateWriter};
 use crate::channels::SpuChannels;
 use crate::mfc::Mfc;
 
@@ -209,6 +212,74 @@ impl SpuThread {
     pub fn is_running(&self) -> bool {
         self.state == SpuThreadState::Running
     }
+
+    /// Save registers, local storage, MFC and channels for a savestate
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.u32(self.id);
+        w.str(&self.name);
+        w.u8(match self.state {
+            SpuThreadState::Stopped => 0,
+            SpuThreadState::Running => 1,
+            SpuThreadState::Waiting => 2,
+            SpuThreadState::Halted => 3,
+        });
+        for reg in &self.regs.gpr {
+            for &word in reg {
+                w.u32(word);
+            }
+        }
+        w.u32(self.regs.pc);
+        w.raw(&self.local_storage[..]);
+        w.bool(self.interrupt_enabled);
+        w.u32(self.stop_signal);
+        self.mfc.save_state(w);
+        self.channels.save_state(w);
+    }
+
+    /// Restore state written by [`SpuThread::save_state`]
+    pub fn load_state(&mut self, r: &mut StateReader) -> io::Result<()> {
+        self.id = r.u32()?;
+        self.name = r.str()?;
+        self.state = match r.u8()? {
+            0 => SpuThreadState::Stopped,
+            1 => SpuThreadState::Running,
+            2 => SpuThreadState::Waiting,
+            3 => SpuThreadState::Halted,
+            state => return Err(invalid(&format!("invalid SPU thread state {}", state))),
+        };
+        for reg in &mut self.regs.gpr {
+            for word in reg {
+                *word = r.u32()?;
+            }
+        }
+        self.regs.pc = r.u32()?;
+        self.local_storage.copy_from_slice(r.raw(SPU_LS_SIZE)?);
+        self.interrupt_enabled = r.bool()?;
+        self.stop_signal = r.u32()?;
+        self.mfc.load_state(r)?;
+        self.channels.load_state(r)
+    }
+}
+
+/// Registers are their preferred word; memory is local storage
+impl ConditionContext for SpuThread {
+    fn variable(&self, name: &str) -> Option<u64> {
+        if let Some(index) = register_index(name, "r", 128) {
+            return Some(self.regs.gpr[index][0] as u64);
+        }
+        Some(match name {
+            "pc" => self.regs.pc as u64,
+            "lr" => self.regs.gpr[0][0] as u64,
+            "sp" => self.regs.gpr[1][0] as u64,
+            _ => return None,
+        })
+    }
+
+    fn read_memory(&self, addr: u64, size: usize) -> Option<u64> {
+        let start = usize::try_from(addr).ok()?;
+        let bytes = self.local_storage.get(start..start.checked_add(size)?)?;
+        matches!(size, 1 | 2 | 4 | 8).then(|| bytes.iter().fold(0, |value, &b| value << 8 | b as u64))
+    }
 }
 
 #[cfg(test)]
@@ -238,6 +309,18 @@ mod tests {
         assert_eq!(thread.ls_read_u32(0x100), 0x12345678);
     }
 
+    #[test]
+    fn test_condition_context() {
+        use oc_core::Condition;
+
+        let mut thread = SpuThread::new(0, create_test_memory());
+        thread.regs.write_preferred_u32(3, 0x100);
+        thread.ls_write_u32(0x104, 0xCAFE);
+        let condition = Condition::parse("mem16(r3 + 6) == 0xcafe && mem8(0x3ffff) == 0").unwrap();
+        assert!(condition.is_met(&thread, 1));
+        assert_eq!(thread.read_memory(0x3FFFF, 2), None);
+    }
+
     #[test]
     fn test_local_storage_u128() {
         let mem = create_test_memory();
@@ -256,4 +339,31 @@ mod tests {
         thread.set_pc(SPU_LS_SIZE as u32 + 0x100);
         assert_eq!(thread.pc(), 0x100);
     }
+
+    #[test]
+    fn test_save_state_round_trip() {
+        let mem = create_test_memory();
+        let mut thread = SpuThread::new(2, Arc::clone(&mem));
+        thread.name = "spurs".to_string();
+        thread.start();
+        thread.regs.write_u32x4(5, [1, 2, 3, 4]);
+        thread.set_pc(0x1230);
+        thread.ls_write_u32(0x3FFFC, 0xFEEDFACE);
+        thread.stop_signal = 0x101;
+
+        let mut w = StateWriter::new();
+        thread.save_state(&mut w);
+        let data = w.into_bytes();
+
+        let mut loaded = SpuThread::new(0, mem);
+        loaded.load_state(&mut StateReader::new(&data)).unwrap();
+        assert_eq!(loaded.id, 2);
+        assert_eq!(loaded.name, "spurs");
+        assert!(loaded.is_running());
+        assert_eq!(loaded.regs.read_u32x4(5), [1, 2, 3, 4]);
+        assert_eq!(loaded.pc(), 0x1230);
+        assert_eq!(loaded.ls_read_u32(0x3FFFC), 0xFEEDFACE);
+        assert_eq!(loaded.stop_signal, 0x101);
+        assert!(loaded.load_state(&mut StateReader::new(&data[..data.len() - 1])).is_err());
+    }
 }
@@ -1,20 +1,38 @@
 //! Main application
 
 use eframe::egui;
-use oc_core::config::Config;
-use oc_integration::{EmulatorRunner, RunnerState};
+use oc_core::config::{Config, ConfigWatcher, DiscSets, ThemeMode};
+use oc_debug::{CrashKind, CrashReport};
+use oc_integration::savestate::list_slots;
+use oc_integration::{Emulator, MissingFeatureReport, Recovery, RunnerState, SpeedMode};
+use oc_input::keyboard::KeyCode;
+use oc_input::mouse::MouseButtons;
+use oc_input::pad::PadState;
+use oc_input::{HostGamepadInfo, HostInput};
 use std::path::PathBuf;
 use std::sync::Arc;
-use parking_lot::RwLock;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
-use crate::controller_config::ControllerConfig;
+use crate::controller_config::{ControllerConfig, PadTestInput};
 use crate::debugger::DebuggerView;
-use crate::game_list::{GameInfo, GameListView};
+use crate::game_list::{format_last_played, GameInfo, GameListView};
+use crate::game_settings::GameSettingsWindow;
+use crate::thumbnails::GameImage;
 use crate::log_viewer::{LogViewer, LogLevel};
+use crate::memory_stats::MemoryStatsPanel;
+use crate::cheats::CheatWindow;
 use crate::memory_viewer::MemoryViewer;
+use crate::osk::{OskOutcome, OskOverlay};
+use crate::perf_overlay;
+use crate::pkg_installer::PkgInstallWindow;
+use crate::quick_menu::{QuickMenu, QuickMenuAction};
+use crate::rpcs3_import::Rpcs3ImportWindow;
+use crate::savestates::{SavestateWindow, SlotAction};
 use crate::settings::SettingsPanel;
+use crate::sfo_editor::SfoEditorWindow;
 use crate::shader_debugger::ShaderDebugger;
-use crate::themes::Theme;
+use crate::themes::ThemeEditor;
 
 /// Main application state
 pub struct OxidizedCellApp {
@@ -26,34 +44,76 @@ pub struct OxidizedCellApp {
     show_settings: bool,
     /// Show about window
     show_about: bool,
+    /// Show theme editor window
+    show_theme_editor: bool,
     /// Show performance overlay
     show_performance: bool,
     /// Show log viewer window
     show_log_viewer: bool,
     /// Show memory viewer window
     show_memory_viewer: bool,
+    /// Show cheat window
+    show_cheats: bool,
+    /// Show memory statistics window
+    show_memory_stats: bool,
     /// Show shader debugger window
     show_shader_debugger: bool,
     /// Show controller config window
     show_controller_config: bool,
-    /// Current theme
-    theme: Theme,
+    /// Show savestate slots window
+    show_savestates: bool,
+    /// Show package installer window
+    show_pkg_installer: bool,
+    /// Show RPCS3 import window
+    show_rpcs3_import: bool,
+    /// Show PARAM.SFO editor window
+    show_sfo_editor: bool,
+    /// Themes and their editor
+    themes: ThemeEditor,
     /// Game list view
     game_list: GameListView,
     /// Debugger view
     debugger: DebuggerView,
     /// Settings panel
     settings_panel: SettingsPanel,
+    /// Settings window of a single game
+    game_settings: GameSettingsWindow,
     /// Log viewer panel
     log_viewer: LogViewer,
     /// Memory viewer panel
     memory_viewer: MemoryViewer,
+    /// Cheat search and list
+    cheats: CheatWindow,
+    /// Memory statistics panel
+    memory_stats: MemoryStatsPanel,
     /// Shader debugger panel
     shader_debugger: ShaderDebugger,
     /// Controller configuration panel
     controller_config: ControllerConfig,
-    /// Emulator runner (wrapped in Arc<RwLock> for thread safety)
-    emulator: Option<Arc<RwLock<EmulatorRunner>>>,
+    /// Savestate slots of the loaded game
+    savestates: SavestateWindow,
+    /// Packages being installed
+    pkg_installer: PkgInstallWindow,
+    /// Import of an RPCS3 installation
+    rpcs3_import: Rpcs3ImportWindow,
+    /// PARAM.SFO of a game or save being edited
+    sfo_editor: SfoEditorWindow,
+    /// Recorded videos still being written
+    video_exports: Vec<JoinHandle<Result<PathBuf, String>>>,
+    /// On-screen keyboard shown for the game's cellOskDialog
+    osk: OskOverlay,
+    /// Menu over the game opened with its hotkey or the PS button
+    quick_menu: QuickMenu,
+    /// Emulated system and its lifecycle
+    emulator: Emulator,
+    /// Settings changes to the game list and logging
+    config_watcher: ConfigWatcher,
+    /// Runner generation the memory and debugger panels are connected to
+    connected_generation: u64,
+    /// Title ID of the loaded game, if known
+    loaded_title_id: Option<String>,
+    /// Start of the loaded game's playtime not yet added to its history
+    play_started: Option<Instant>,
     /// Currently loaded game path
     loaded_game_path: Option<PathBuf>,
     /// FPS counter
@@ -64,10 +124,20 @@ pub struct OxidizedCellApp {
     emulator_fps: f64,
     /// Error message to display
     error_message: Option<String>,
+    /// Crash shown in the crash dialog
+    crash_report: Option<CrashReport>,
+    /// Unimplemented features the stopped game touched, shown in a summary dialog
+    missing_features: Option<MissingFeatureReport>,
+    /// Autosave offered after the booted game's last session crashed
+    recovery: Option<Recovery>,
+    /// Disc folders of multi-disc titles
+    disc_sets: DiscSets,
     /// Fullscreen mode
     fullscreen: bool,
     /// Enable frame rate limiting
     enable_frame_limiting: bool,
+    /// Slow motion toggled on by its hotkey
+    slow_motion: bool,
     /// Enable frame skipping
     enable_frame_skipping: bool,
     /// Frame skip counter
@@ -95,62 +165,78 @@ impl OxidizedCellApp {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         let config = Config::load().unwrap_or_default();
         
-        let theme = Theme::default();
-        theme.apply(&cc.egui_ctx);
+        let themes = ThemeEditor::new();
+        themes.apply(&cc.egui_ctx, &config.ui);
         
         // Create log viewer and log initial message
         let log_viewer = LogViewer::new();
+        log_viewer.capture_tracing();
         log_viewer.log(LogLevel::Info, "oc-ui", "oxidized-cell UI initialized");
         
-        // Add some sample games for demonstration
+        // Scan the configured game directories
         let mut game_list = GameListView::new();
-        game_list.add_game(GameInfo {
-            title: "Sample Game 1".to_string(),
-            path: "/path/to/game1.elf".into(),
-            id: "BLUS00001".to_string(),
-            version: "1.00".to_string(),
-            region: "US".to_string(),
-            category: "Action".to_string(),
-            icon_data: None,
-            last_played: None,
-        });
-        game_list.add_game(GameInfo {
-            title: "Sample Game 2".to_string(),
-            path: "/path/to/game2.elf".into(),
-            id: "BLES00002".to_string(),
-            version: "1.01".to_string(),
-            region: "EU".to_string(),
-            category: "RPG".to_string(),
-            icon_data: None,
-            last_played: None,
-        });
+        game_list.configure(&config.paths);
+        game_list.set_compat_url(&config.general.compat_db_url);
+        let found = game_list.refresh();
+        log_viewer.log(LogLevel::Info, "oc-ui", &format!("Found {} games", found));
+        let emulator = Emulator::new(config.clone());
+        let config_watcher = emulator.config_bus().watch(&["general", "paths", "debug"]);
         
         Self {
             config,
             current_view: View::GameList,
             show_settings: false,
             show_about: false,
+            show_theme_editor: false,
             show_performance: false,
             show_log_viewer: false,
             show_memory_viewer: false,
+            show_cheats: false,
+            show_memory_stats: false,
             show_shader_debugger: false,
             show_controller_config: false,
-            theme,
+            show_savestates: false,
+            show_pkg_installer: false,
+            show_rpcs3_import: false,
+            show_sfo_editor: false,
+            themes,
             game_list,
             debugger: DebuggerView::new(),
             settings_panel: SettingsPanel::new(),
+            game_settings: GameSettingsWindow::new(),
             log_viewer,
             memory_viewer: MemoryViewer::new(),
+            cheats: CheatWindow::new(),
+            memory_stats: MemoryStatsPanel::new(),
             shader_debugger: ShaderDebugger::new(),
             controller_config: ControllerConfig::new(),
-            emulator: None,
+            savestates: SavestateWindow::new(),
+            pkg_installer: PkgInstallWindow::new(),
+            rpcs3_import: Rpcs3ImportWindow::new(),
+            sfo_editor: SfoEditorWindow::new(),
+            video_exports: Vec::new(),
+            osk: OskOverlay::new(),
+            quick_menu: QuickMenu::new(),
+            emulator,
+            config_watcher,
+            connected_generation: 0,
+            loaded_title_id: None,
+            play_started: None,
             loaded_game_path: None,
             fps: 0.0,
             frame_time: 0.0,
             emulator_fps: 0.0,
             error_message: None,
+            crash_report: None,
+            missing_features: None,
+            recovery: None,
+            disc_sets: DiscSets::load().unwrap_or_else(|e| {
+                tracing::warn!("Failed to load the disc sets: {}", e);
+                DiscSets::default()
+            }),
             fullscreen: false,
             enable_frame_limiting: true,
+            slow_motion: false,
             enable_frame_skipping: false,
             _frame_skip_counter: 0,
             framebuffer_texture: None,
@@ -160,30 +246,20 @@ impl OxidizedCellApp {
 
     /// Get the current emulation state from the runner
     fn emulation_state(&self) -> RunnerState {
-        self.emulator
-            .as_ref()
-            .map(|e| e.read().state())
-            .unwrap_or(RunnerState::Stopped)
+        self.emulator.state()
     }
 
-    /// Initialize the emulator runner
+    /// Create an idle emulator runner if there is none
     fn init_emulator(&mut self) {
-        if self.emulator.is_some() {
+        if self.emulator.runner().is_some() {
             return;
         }
 
         self.log_viewer.log(LogLevel::Info, "oc-ui", "Initializing emulator runner...");
-        
-        match EmulatorRunner::new(self.config.clone()) {
-            Ok(runner) => {
-                // Get memory reference before wrapping in locks
-                let memory = Arc::clone(runner.memory());
-                let runner = Arc::new(RwLock::new(runner));
-                
-                // Connect memory viewer to the emulator's memory
-                self.memory_viewer.connect(memory);
-                
-                self.emulator = Some(runner);
+        self.emulator.set_config(self.config.clone());
+        match self.emulator.ensure_runner() {
+            Ok(_) => {
+                self.connect_panels();
                 self.log_viewer.log(LogLevel::Info, "oc-ui", "Emulator runner initialized successfully");
             }
             Err(e) => {
@@ -194,99 +270,802 @@ impl OxidizedCellApp {
         }
     }
 
+    /// Connect the memory and debugger panels to the current runner if it was replaced
+    fn connect_panels(&mut self) {
+        if self.connected_generation == self.emulator.generation() {
+            return;
+        }
+        let Some(emulator) = self.emulator.runner() else {
+            return;
+        };
+        let runner = emulator.borrow();
+        let memory = Arc::clone(runner.memory());
+        let cheats = Arc::clone(runner.cheats());
+        self.memory_viewer.connect(Arc::clone(&memory), Arc::clone(&cheats));
+        self.cheats.connect(Arc::clone(&memory), cheats);
+        self.debugger.set_profiler(Arc::clone(runner.profiler()));
+        self.debugger.set_patches(Arc::clone(runner.patches()), Arc::clone(&memory));
+        self.debugger.set_spu_debugger(Arc::clone(runner.spu_debugger()));
+        self.shader_debugger.set_rsx_debugger(Arc::clone(runner.rsx_debugger()), Arc::clone(&memory));
+        self.memory_stats.connect(memory);
+        drop(runner);
+        self.connected_generation = self.emulator.generation();
+    }
+
     /// Launch a game from the given path
     fn launch_game(&mut self, game_path: PathBuf) {
         self.log_viewer.log(LogLevel::Info, "oc-ui", &format!("Launching game: {:?}", game_path));
+        self.flush_playtime();
+        self.play_started = None;
         
-        // Initialize emulator if not already done
-        self.init_emulator();
-        
-        if let Some(ref emulator) = self.emulator {
-            // Load the game (uses interior mutability via RwLock for thread management)
-            let load_result = emulator.read().load_game(&game_path);
-            match load_result {
-                Ok(loaded_game) => {
-                    self.log_viewer.log(
-                        LogLevel::Info, 
-                        "oc-ui", 
-                        &format!("Game loaded: entry=0x{:x}, base=0x{:08x}", 
-                            loaded_game.entry_point, 
-                            loaded_game.base_addr)
-                    );
-                    
-                    // Start the emulator
-                    if let Err(e) = emulator.write().start() {
-                        let msg = format!("Failed to start emulator: {}", e);
-                        self.log_viewer.log(LogLevel::Error, "oc-ui", &msg);
-                        self.error_message = Some(msg);
-                    } else {
-                        self.loaded_game_path = Some(game_path);
-                        self.current_view = View::Emulation;
-                        self.log_viewer.log(LogLevel::Info, "oc-ui", "Emulator started");
+        // Every boot gets a fresh runner, created with the game's own settings
+        let title_id = self.game_list.game_by_path(&game_path).map(|game| game.id.clone());
+        self.emulator.set_config(self.title_config(title_id.as_deref()));
+        let booted = self.emulator.boot(&game_path, title_id.as_deref()).map(|booted| booted.game.clone());
+        match booted {
+            Ok(loaded_game) => {
+                self.log_viewer.log(
+                    LogLevel::Info, 
+                    "oc-ui", 
+                    &format!("Game loaded: entry=0x{:x}, base=0x{:08x}", 
+                        loaded_game.entry_point, 
+                        loaded_game.base_addr)
+                );
+                self.on_booted();
+                self.loaded_game_path = Some(game_path);
+                self.current_view = View::Emulation;
+                self.log_viewer.log(LogLevel::Info, "oc-ui", "Emulator started");
+            }
+            Err(e) => {
+                let msg = format!("Failed to boot game: {}", e);
+                self.log_viewer.log(LogLevel::Error, "oc-ui", &msg);
+                self.error_message = Some(msg);
+                self.loaded_game_path = None;
+                self.loaded_title_id = None;
+            }
+        }
+        self.connect_panels();
+    }
+
+    /// Load the booted game's cheats and patches and start counting its playtime
+    fn on_booted(&mut self) {
+        let (Some(emulator), Some(booted)) = (self.emulator.runner(), self.emulator.booted()) else {
+            return;
+        };
+        self.loaded_title_id = booted.title_id.clone();
+        self.recovery = booted.recovery.clone();
+        // Every boot starts a new runner, at normal speed
+        emulator.borrow_mut().set_frame_limited(self.enable_frame_limiting);
+        self.slow_motion = false;
+        if let Some(title_id) = self.loaded_title_id.as_deref() {
+            let runner = emulator.borrow();
+            match runner.cheats().lock().load_for_title(title_id) {
+                Ok(0) => {}
+                Ok(count) => self.log_viewer.log(
+                    LogLevel::Info,
+                    "oc-ui",
+                    &format!("Loaded {} cheats for {}", count, title_id),
+                ),
+                Err(e) => self.log_viewer.log(LogLevel::Warn, "oc-ui", &e),
+            }
+            let mut patches = runner.patches().lock();
+            match patches.load_for_title(title_id) {
+                Ok(0) => {}
+                Ok(count) => self.log_viewer.log(
+                    LogLevel::Info,
+                    "oc-ui",
+                    &format!("Loaded {} code patches for {}", count, title_id),
+                ),
+                Err(e) => self.log_viewer.log(LogLevel::Warn, "oc-ui", &e),
+            }
+            for e in patches.apply_enabled(runner.memory()) {
+                self.log_viewer.log(LogLevel::Warn, "oc-ui", &format!("Patch not applied: {}", e));
+            }
+        }
+        if let Some(title_id) = self.loaded_title_id.clone() {
+            self.game_list.mark_as_played(&title_id);
+            self.play_started = Some(Instant::now());
+        }
+    }
+
+    /// Hand the settings the loaded game runs with to the emulator, which applies them live
+    fn apply_config(&mut self) {
+        let config = self.title_config(self.loaded_title_id.as_deref());
+        self.emulator.set_config(config);
+    }
+
+    /// Apply settings changes to the parts the frontend owns itself
+    fn handle_config_changes(&mut self) {
+        let Some(change) = self.config_watcher.take() else {
+            return;
+        };
+        let config = &change.config;
+        if change.touches("debug") {
+            oc_core::logging::set_log_level(config.debug.log_level);
+        }
+        if change.touches("paths") && self.game_list.configure(&config.paths) {
+            let found = self.game_list.refresh();
+            self.log_viewer.log(LogLevel::Info, "oc-ui", &format!("Found {} games", found));
+        }
+        if change.touches("general") {
+            self.game_list.set_compat_url(&config.general.compat_db_url);
+        }
+    }
+
+    /// The configuration a title runs with, the global one if it has no settings of its own
+    fn title_config(&self, title_id: Option<&str>) -> Config {
+        let Some(title_id) = title_id else {
+            return self.config.clone();
+        };
+        self.config.for_title(title_id).unwrap_or_else(|e| {
+            self.log_viewer.log(LogLevel::Warn, "oc-ui", &format!("Ignoring settings of {}: {}", title_id, e));
+            self.config.clone()
+        })
+    }
+
+    /// Feed keyboard and mouse input over the game view to the emulated pad, instrument and Move
+    fn forward_keyboard_mouse(&self, ui: &egui::Ui, view: &egui::Response) {
+        let Some(emulator) = self.emulator.runner() else {
+            return;
+        };
+        let mut runner = emulator.borrow_mut();
+        // Leave keys alone while a text field has focus
+        let typing = ui.ctx().wants_keyboard_input();
+        ui.input(|i| {
+            let keys: Vec<(u16, bool)> = if typing {
+                Vec::new()
+            } else {
+                i.events
+                    .iter()
+                    .filter_map(|event| match event {
+                        egui::Event::Key { key, pressed, repeat: false, .. } => {
+                            KeyCode::from_name(key.name()).map(|code| (code as u16, *pressed))
+                        }
+                        _ => None,
+                    })
+                    .collect()
+            };
+            let hovered = view.hovered();
+            let buttons = [
+                (MouseButtons::LEFT, hovered && i.pointer.primary_down()),
+                (MouseButtons::RIGHT, hovered && i.pointer.secondary_down()),
+            ];
+
+            if let Some(pad) = runner.keyboard_pad_mut() {
+                if i.focused {
+                    for &(code, pressed) in &keys {
+                        pad.key_event(code, pressed);
+                    }
+                    for (button, pressed) in buttons {
+                        pad.mouse_button(button, pressed);
+                    }
+                    if hovered {
+                        let delta = i.pointer.delta();
+                        pad.mouse_moved(delta.x, delta.y);
                     }
+                } else {
+                    pad.release_all();
                 }
-                Err(e) => {
-                    let msg = format!("Failed to load game: {}", e);
-                    self.log_viewer.log(LogLevel::Error, "oc-ui", &msg);
-                    self.error_message = Some(msg);
+            }
+
+            if let Some(instrument) = runner.instrument_mut() {
+                if i.focused {
+                    for &(code, pressed) in &keys {
+                        instrument.key_event(code, pressed);
+                    }
+                } else {
+                    instrument.release_all();
                 }
             }
-        }
+
+            if let Some(virtual_move) = runner.virtual_move_mut() {
+                if i.focused {
+                    for &(code, pressed) in &keys {
+                        virtual_move.key_event(code, pressed);
+                    }
+                    for (button, pressed) in buttons {
+                        virtual_move.mouse_button(button, pressed);
+                    }
+                } else {
+                    virtual_move.release_all();
+                }
+                // The pointer's place in the game view is the sphere's place in the camera image
+                let rect = view.rect;
+                match i.pointer.hover_pos().filter(|_| hovered && i.focused) {
+                    Some(pos) => virtual_move.pointer_moved(
+                        (pos.x - rect.left()) / rect.width(),
+                        (pos.y - rect.top()) / rect.height(),
+                    ),
+                    None => virtual_move.pointer_left(),
+                }
+            }
+        });
+    }
+
+    /// Connected controllers, the latest physical input and the test port's live input for the controller panel
+    fn controller_panel_input(&self) -> (Vec<HostGamepadInfo>, Option<HostInput>, PadTestInput) {
+        let Some(emulator) = self.emulator.runner() else {
+            return (Vec::new(), None, PadTestInput::default());
+        };
+        // Polling while stopped also keeps the test view live
+        let captured = emulator.borrow_mut().capture_host_input().filter(|_| self.controller_config.is_binding());
+        let runner = emulator.borrow();
+        let port = self.controller_config.test_port();
+        let test = PadTestInput {
+            state: runner.pad_ports().state(port),
+            motion: runner.pad_ports().motion(port),
+            raw_sticks: runner.raw_sticks(port),
+        };
+        (runner.gamepad_devices(), captured, test)
     }
 
     /// Start/Resume emulation
     fn start_emulation(&mut self) {
-        if let Some(ref emulator) = self.emulator {
-            let state = emulator.read().state();
-            let result = match state {
-                RunnerState::Paused => emulator.write().resume(),
-                RunnerState::Stopped => emulator.write().start(),
-                RunnerState::Running => Ok(()),
-            };
-            
-            if let Err(e) = result {
-                let msg = format!("Failed to start emulation: {}", e);
-                self.log_viewer.log(LogLevel::Error, "oc-ui", &msg);
-            } else {
-                self.log_viewer.log(LogLevel::Info, "oc-ui", "Emulation started/resumed");
+        let result = match self.emulator.state() {
+            RunnerState::Paused => self.emulator.resume(),
+            RunnerState::Stopped if self.emulator.booted().is_some() => {
+                self.restart_emulation();
+                return;
             }
+            RunnerState::Stopped | RunnerState::Running => Ok(()),
+        };
+
+        if let Err(e) = result {
+            let msg = format!("Failed to start emulation: {}", e);
+            self.log_viewer.log(LogLevel::Error, "oc-ui", &msg);
+        } else {
+            self.log_viewer.log(LogLevel::Info, "oc-ui", "Emulation started/resumed");
         }
     }
 
     /// Pause emulation
     fn pause_emulation(&mut self) {
-        if let Some(ref emulator) = self.emulator {
-            if let Err(e) = emulator.write().pause() {
-                let msg = format!("Failed to pause emulation: {}", e);
-                self.log_viewer.log(LogLevel::Error, "oc-ui", &msg);
-            } else {
-                self.log_viewer.log(LogLevel::Info, "oc-ui", "Emulation paused");
-            }
+        if let Err(e) = self.emulator.pause() {
+            let msg = format!("Failed to pause emulation: {}", e);
+            self.log_viewer.log(LogLevel::Error, "oc-ui", &msg);
+        } else {
+            self.log_viewer.log(LogLevel::Info, "oc-ui", "Emulation paused");
         }
     }
 
-    /// Stop emulation
+    /// Stop emulation and shut the runner down
     fn stop_emulation(&mut self) {
-        if let Some(ref emulator) = self.emulator {
-            if let Err(e) = emulator.write().stop() {
-                let msg = format!("Failed to stop emulation: {}", e);
+        self.flush_playtime();
+        self.stop_video_recording();
+        self.play_started = None;
+        self.loaded_game_path = None;
+        self.loaded_title_id = None;
+        if let Err(e) = self.emulator.stop() {
+            let msg = format!("Failed to stop emulation: {}", e);
+            self.log_viewer.log(LogLevel::Error, "oc-ui", &msg);
+        } else {
+            self.log_viewer.log(LogLevel::Info, "oc-ui", "Emulation stopped");
+        }
+        self.missing_features = self.emulator.take_missing_features().filter(|report| !report.is_empty());
+    }
+
+    /// Boot the running game again from the start
+    fn restart_emulation(&mut self) {
+        self.flush_playtime();
+        self.stop_video_recording();
+        self.play_started = None;
+        match self.emulator.restart() {
+            Ok(_) => {
+                self.on_booted();
+                self.log_viewer.log(LogLevel::Info, "oc-ui", "Emulation restarted");
+            }
+            Err(e) => {
+                let msg = format!("Failed to restart emulation: {}", e);
                 self.log_viewer.log(LogLevel::Error, "oc-ui", &msg);
-            } else {
-                self.log_viewer.log(LogLevel::Info, "oc-ui", "Emulation stopped");
+                self.error_message = Some(msg);
                 self.loaded_game_path = None;
+                self.loaded_title_id = None;
+            }
+        }
+        self.connect_panels();
+    }
+
+    /// Whether an audio dump is being recorded
+    fn is_recording_audio(&self) -> bool {
+        self.emulator
+            .runner()
+            .is_some_and(|e| e.borrow().audio_recorder().lock().is_recording())
+    }
+
+    /// Start or stop dumping audio to files
+    fn toggle_audio_recording(&mut self) {
+        let Some(emulator) = self.emulator.runner() else {
+            return;
+        };
+        let emulator = emulator.borrow();
+        let mut recorder = emulator.audio_recorder().lock();
+
+        if recorder.is_recording() {
+            let files = recorder.stop();
+            let msg = format!("Audio recording stopped, {} file(s) written", files.len());
+            self.log_viewer.log(LogLevel::Info, "oc-ui", &msg);
+            for file in files {
+                self.log_viewer.log(LogLevel::Info, "oc-ui", &format!("  {}", file.display()));
+            }
+        } else {
+            let audio = &self.config.audio;
+            recorder.set_format(audio.dump_format);
+            recorder.set_record_ports(audio.dump_ports);
+            match recorder.start() {
+                Ok(()) => {
+                    let msg = format!("Recording audio to {}", recorder.directory().display());
+                    self.log_viewer.log(LogLevel::Info, "oc-ui", &msg);
+                }
+                Err(e) => {
+                    self.log_viewer.log(LogLevel::Error, "oc-ui", &e);
+                }
+            }
+        }
+    }
+
+    /// Time the current video has been recording, if one is
+    fn video_recording_time(&self) -> Option<Duration> {
+        self.emulator.runner().and_then(|e| e.borrow().video_recording_time())
+    }
+
+    /// Save the current frame to the capture directory
+    fn take_screenshot(&mut self) {
+        let Some(emulator) = self.emulator.runner() else {
+            return;
+        };
+        match emulator.borrow().save_screenshot(&self.config.capture.output_dir) {
+            Ok(path) => {
+                let msg = format!("Screenshot saved to {}", path.display());
+                self.log_viewer.log(LogLevel::Info, "oc-ui", &msg);
             }
+            Err(e) => self.log_viewer.log(LogLevel::Error, "oc-ui", &e),
         }
     }
 
+    /// Swap the disc in the drive for the disc folder at `path`
+    fn swap_disc(&mut self, path: &std::path::Path) {
+        let Some(emulator) = self.emulator.runner() else {
+            return;
+        };
+        let result = emulator.borrow().insert_disc(path);
+        match result {
+            Ok(()) => {
+                let msg = format!("Inserted disc {}", path.display());
+                self.log_viewer.log(LogLevel::Info, "oc-ui", &msg);
+            }
+            Err(e) => self.log_viewer.log(LogLevel::Error, "oc-ui", &format!("Failed to insert the disc: {}", e)),
+        }
+    }
+
+    /// Pick a disc folder and register it as the next disc of the loaded title
+    fn add_disc_folder(&mut self) {
+        let Some(title_id) = self.loaded_title_id.clone() else {
+            return;
+        };
+        let Some(dir) = rfd::FileDialog::new().set_title("Disc Folder").pick_folder() else {
+            return;
+        };
+        // Start the set with the disc the game booted from
+        if self.disc_sets.discs(&title_id).is_empty() {
+            let boot_disc = self.emulator.runner().and_then(|emulator| emulator.borrow().disc());
+            if let Some(boot_disc) = boot_disc {
+                self.disc_sets.add_disc(&title_id, boot_disc.path);
+            }
+        }
+        if self.disc_sets.add_disc(&title_id, dir) {
+            if let Err(e) = self.disc_sets.save() {
+                self.log_viewer.log(LogLevel::Error, "oc-ui", &format!("Failed to save the disc sets: {}", e));
+            }
+        }
+    }
+
+    /// Disc swapping entries of the Emulation menu
+    fn disc_menu(&mut self, ui: &mut egui::Ui) {
+        let Some(title_id) = self.loaded_title_id.clone() else {
+            ui.label("No game loaded");
+            return;
+        };
+        let current = self.emulator.runner().and_then(|emulator| emulator.borrow().disc()).map(|disc| disc.path);
+        let discs = self.disc_sets.discs(&title_id).to_vec();
+        let mut remove = None;
+        for (index, disc) in discs.iter().enumerate() {
+            ui.horizontal(|ui| {
+                let name = disc.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                let inserted = current.as_ref() == Some(disc);
+                let label = ui.selectable_label(inserted, format!("Disc {}: {}", index + 1, name)).on_hover_text(disc.display().to_string());
+                if label.clicked() && !inserted {
+                    self.swap_disc(disc);
+                    ui.close_menu();
+                }
+                if ui.small_button("🗑").on_hover_text("Remove from the disc set").clicked() {
+                    remove = Some(index);
+                }
+            });
+        }
+        if let Some(index) = remove {
+            self.disc_sets.remove_disc(&title_id, index);
+            if let Err(e) = self.disc_sets.save() {
+                self.log_viewer.log(LogLevel::Error, "oc-ui", &format!("Failed to save the disc sets: {}", e));
+            }
+        }
+        if !discs.is_empty() {
+            ui.separator();
+        }
+        let next = self.disc_sets.next_disc(&title_id, current.as_deref()).filter(|next| current.as_ref() != Some(*next)).cloned();
+        if ui.add_enabled(next.is_some(), egui::Button::new("Swap to Next Disc")).clicked() {
+            if let Some(next) = next {
+                self.swap_disc(&next);
+            }
+            ui.close_menu();
+        }
+        if ui.add_enabled(current.is_some(), egui::Button::new("Eject Disc")).clicked() {
+            if let Some(emulator) = self.emulator.runner() {
+                emulator.borrow().eject_disc();
+            }
+            self.log_viewer.log(LogLevel::Info, "oc-ui", "Disc ejected");
+            ui.close_menu();
+        }
+        if ui.button("Add Disc Folder...").clicked() {
+            ui.close_menu();
+            self.add_disc_folder();
+        }
+    }
+
+    /// Start or stop recording the game output to a video
+    fn toggle_video_recording(&mut self) {
+        if self.video_recording_time().is_some() {
+            self.stop_video_recording();
+            return;
+        }
+        let Some(emulator) = self.emulator.runner() else {
+            return;
+        };
+        let result = emulator.borrow_mut().start_video_recording(&self.config.capture);
+        match result {
+            Ok(path) => {
+                let msg = format!("Recording video to {}", path.display());
+                self.log_viewer.log(LogLevel::Info, "oc-ui", &msg);
+            }
+            Err(e) => self.log_viewer.log(LogLevel::Error, "oc-ui", &e),
+        }
+    }
+
+    /// End the video being recorded; it is written in the background
+    fn stop_video_recording(&mut self) {
+        let Some(emulator) = self.emulator.runner() else {
+            return;
+        };
+        let finishing = emulator.borrow_mut().stop_video_recording();
+        if let Some(handle) = finishing {
+            self.log_viewer.log(LogLevel::Info, "oc-ui", "Video recording stopped, finishing the file...");
+            self.video_exports.push(handle);
+        }
+    }
+
+    /// Report videos that finished writing
+    fn poll_video_exports(&mut self) {
+        let (finished, running): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.video_exports).into_iter().partition(|handle| handle.is_finished());
+        self.video_exports = running;
+        for handle in finished {
+            match handle.join() {
+                Ok(Ok(path)) => {
+                    let msg = format!("Video saved to {}", path.display());
+                    self.log_viewer.log(LogLevel::Info, "oc-ui", &msg);
+                }
+                Ok(Err(e)) => self.log_viewer.log(LogLevel::Error, "oc-ui", &format!("Video recording failed: {}", e)),
+                Err(_) => self.log_viewer.log(LogLevel::Error, "oc-ui", "The video writer thread panicked"),
+            }
+        }
+    }
+
+    /// Screenshot and video hotkeys from the capture settings
+    fn handle_capture_hotkeys(&mut self, ctx: &egui::Context) {
+        if self.emulator.runner().is_none() {
+            return;
+        }
+        let capture = &self.config.capture;
+        let pressed = |name: &str| egui::Key::from_name(name).is_some_and(|key| ctx.input(|i| i.key_pressed(key)));
+        let (screenshot, record) = (pressed(&capture.screenshot_key), pressed(&capture.record_key));
+        if screenshot {
+            self.take_screenshot();
+        }
+        if record {
+            self.toggle_video_recording();
+        }
+    }
+
+    /// Turbo while its hotkey is held, slow motion toggled by its hotkey
+    fn handle_speed_hotkeys(&mut self, ctx: &egui::Context) {
+        let Some(emulator) = self.emulator.runner() else {
+            return;
+        };
+        // Keys typed into text fields are not hotkeys
+        let typing = ctx.wants_keyboard_input();
+        let general = &self.config.general;
+        let key = |name: &str| egui::Key::from_name(name).filter(|_| !typing);
+        let turbo = key(&general.turbo_key).is_some_and(|key| ctx.input(|i| i.key_down(key)));
+        let slow_motion = key(&general.slow_motion_key).is_some_and(|key| ctx.input(|i| i.key_pressed(key)));
+
+        if slow_motion {
+            self.slow_motion = !self.slow_motion;
+        }
+        // Letting go of turbo returns to slow motion if it is toggled on
+        let mode = match (turbo, self.slow_motion) {
+            (true, _) => SpeedMode::Turbo,
+            (false, true) => SpeedMode::SlowMotion,
+            (false, false) => SpeedMode::Normal,
+        };
+        emulator.borrow_mut().set_speed_mode(mode);
+    }
+
+    /// Whether an instruction trace is being recorded
+    fn is_tracing(&self) -> bool {
+        self.emulator.runner().is_some_and(|e| e.borrow().is_tracing())
+    }
+
+    /// Start or stop recording an instruction trace
+    fn toggle_instruction_trace(&mut self) {
+        let Some(emulator) = self.emulator.runner() else {
+            return;
+        };
+        let emulator = emulator.borrow();
+
+        if emulator.is_tracing() {
+            match emulator.stop_trace() {
+                Ok(count) => {
+                    let msg = format!(
+                        "Instruction trace stopped, {} instruction(s) written to {}",
+                        count,
+                        self.config.debug.trace_path.display()
+                    );
+                    self.log_viewer.log(LogLevel::Info, "oc-ui", &msg);
+                }
+                Err(e) => self.log_viewer.log(LogLevel::Error, "oc-ui", &e),
+            }
+        } else {
+            match emulator.start_trace(&self.config.debug) {
+                Ok(()) => {
+                    let msg = format!("Recording instruction trace to {}", self.config.debug.trace_path.display());
+                    self.log_viewer.log(LogLevel::Info, "oc-ui", &msg);
+                }
+                Err(e) => self.log_viewer.log(LogLevel::Error, "oc-ui", &e),
+            }
+        }
+    }
+
+    /// Whether basic block coverage is being recorded
+    fn is_recording_coverage(&self) -> bool {
+        self.emulator.runner().is_some_and(|e| e.borrow().is_recording_coverage())
+    }
+
+    /// Start or stop recording coverage
+    fn toggle_coverage(&mut self) {
+        let Some(emulator) = self.emulator.runner() else {
+            return;
+        };
+        let emulator = emulator.borrow();
+
+        if emulator.is_recording_coverage() {
+            match emulator.stop_coverage() {
+                Ok(Some(msg)) => self.log_viewer.log(LogLevel::Info, "oc-ui", &msg),
+                Ok(None) => {}
+                Err(e) => self.log_viewer.log(LogLevel::Error, "oc-ui", &e),
+            }
+        } else {
+            emulator.start_coverage(&self.config.debug.coverage_path);
+            let msg = format!("Recording coverage to {}", self.config.debug.coverage_path.display());
+            self.log_viewer.log(LogLevel::Info, "oc-ui", &msg);
+        }
+    }
+
+    /// Add the time since the play session started to the loaded game's playtime
+    fn flush_playtime(&mut self) {
+        let (Some(title_id), Some(started)) = (self.loaded_title_id.as_deref(), self.play_started) else {
+            return;
+        };
+        let secs = started.elapsed().as_secs();
+        self.game_list.add_playtime(title_id, secs);
+        // Keep the fraction of a second for the next flush
+        self.play_started = Some(started + Duration::from_secs(secs));
+    }
+
+    /// Load the newest savestate of the game that was just started
+    fn resume_newest_savestate(&mut self) {
+        let Some(title_id) = self.loaded_title_id.clone() else {
+            return;
+        };
+        let newest = list_slots(&self.config.paths.savestates, &title_id)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(slot, info)| Some((slot, info?.created)))
+            .max_by_key(|&(_, created)| created);
+        let Some((slot, _)) = newest else {
+            self.log_viewer.log(LogLevel::Info, "oc-ui", &format!("No savestate to resume {} from", title_id));
+            return;
+        };
+        self.savestates.configure(&self.config.paths.savestates, Some(&title_id));
+        self.load_state_slot(slot);
+    }
+
+    /// Save the running game into savestate `slot`
+    fn save_state_slot(&mut self, slot: usize) {
+        let (Some(emulator), Some(path)) = (self.emulator.runner(), self.savestates.slot_path(slot)) else {
+            return;
+        };
+        if emulator.borrow().state() == RunnerState::Stopped {
+            return;
+        }
+        match emulator.borrow().save_state(&path) {
+            Ok(_) => {
+                self.log_viewer.log(LogLevel::Info, "oc-ui", &format!("Saved state to slot {}", slot));
+                self.savestates.refresh();
+            }
+            Err(e) => self.log_viewer.log(LogLevel::Error, "oc-ui", &format!("Failed to save state: {}", e)),
+        }
+    }
+
+    /// Restore the running game from the autosave its last session left
+    fn restore_autosave(&mut self, recovery: &Recovery) {
+        let Some(emulator) = self.emulator.runner() else {
+            return;
+        };
+        let result = emulator.borrow_mut().load_state(&recovery.path);
+        match result {
+            Ok(info) => {
+                let msg = format!("Restored the autosave of frame {}", info.frame);
+                self.log_viewer.log(LogLevel::Info, "oc-ui", &msg);
+            }
+            Err(e) => {
+                let msg = format!("Failed to restore the autosave: {}", e);
+                self.log_viewer.log(LogLevel::Error, "oc-ui", &msg);
+                self.error_message = Some(msg);
+            }
+        }
+    }
+
+    /// Restore the running game from savestate `slot`
+    fn load_state_slot(&mut self, slot: usize) {
+        let (Some(emulator), Some(path)) = (self.emulator.runner(), self.savestates.slot_path(slot)) else {
+            return;
+        };
+        if emulator.borrow().state() == RunnerState::Stopped {
+            return;
+        }
+        if !path.exists() {
+            self.log_viewer.log(LogLevel::Warn, "oc-ui", &format!("Savestate slot {} is empty", slot));
+            return;
+        }
+        let result = emulator.borrow_mut().load_state(&path);
+        match result {
+            Ok(info) => {
+                let msg = format!("Loaded state from slot {} (frame {})", slot, info.frame);
+                self.log_viewer.log(LogLevel::Info, "oc-ui", &msg);
+            }
+            Err(e) => {
+                let msg = format!("Failed to load state: {}", e);
+                self.log_viewer.log(LogLevel::Error, "oc-ui", &msg);
+                self.error_message = Some(msg);
+            }
+        }
+    }
+
+    /// Handle the quick save and load hotkeys
+    fn handle_savestate_hotkeys(&mut self, ctx: &egui::Context) {
+        let (save, next, load) = ctx.input(|i| {
+            (i.key_pressed(egui::Key::F5), i.key_pressed(egui::Key::F6), i.key_pressed(egui::Key::F8))
+        });
+        if next {
+            let slot = self.savestates.next_quick_slot();
+            self.log_viewer.log(LogLevel::Info, "oc-ui", &format!("Quick savestate slot {}", slot));
+        }
+        if save {
+            self.save_state_slot(self.savestates.quick_slot());
+        }
+        if load {
+            self.load_state_slot(self.savestates.quick_slot());
+        }
+    }
+
+    /// Controller the system overlays are navigated with, the first connected one
+    fn overlay_pad(&self) -> Option<PadState> {
+        let runner = self.emulator.runner()?.borrow();
+        let mask = runner.pad_ports().connected_mask();
+        (mask != 0).then(|| mask.trailing_zeros() as u8).and_then(|port| runner.pad_ports().state(port))
+    }
+
+    /// Open or close the quick menu with its hotkey or a controller's PS button
+    fn handle_quick_menu_toggle(&mut self, ctx: &egui::Context) {
+        let Some(emulator) = self.emulator.runner() else {
+            return;
+        };
+        let ps_button = emulator.borrow_mut().take_ps_button();
+        // Keys typed into text fields are not hotkeys
+        let typing = ctx.wants_keyboard_input();
+        let key = egui::Key::from_name(&self.config.general.quick_menu_key).filter(|_| !typing);
+        let hotkey = key.is_some_and(|key| ctx.input(|i| i.key_pressed(key)));
+        if !ps_button && !hotkey {
+            return;
+        }
+
+        if self.quick_menu.is_open() {
+            self.close_quick_menu();
+        } else if self.current_view == View::Emulation && self.emulation_state() != RunnerState::Stopped {
+            let running = self.emulation_state() == RunnerState::Running;
+            if running {
+                self.pause_emulation();
+            }
+            let pad = self.overlay_pad();
+            self.quick_menu.open(running, pad.as_ref());
+        }
+    }
+
+    /// Close the quick menu, resuming the game if the menu paused it
+    fn close_quick_menu(&mut self) {
+        if self.quick_menu.close() {
+            self.start_emulation();
+        }
+    }
+
+    /// Show the quick menu while it is open and carry out the picked action
+    fn show_quick_menu(&mut self, ctx: &egui::Context) {
+        if !self.quick_menu.is_open() {
+            return;
+        }
+        // The game was stopped from the main window
+        if self.emulation_state() == RunnerState::Stopped {
+            self.quick_menu.close();
+            return;
+        }
+        let pad = self.overlay_pad();
+        let slot = self.savestates.quick_slot();
+        let Some(action) = self.quick_menu.show(ctx, pad.as_ref(), slot) else {
+            // Keep reading the controller while the menu is up
+            ctx.request_repaint();
+            return;
+        };
+        match action {
+            QuickMenuAction::Resume => {}
+            QuickMenuAction::SaveState => self.save_state_slot(slot),
+            QuickMenuAction::LoadState => self.load_state_slot(slot),
+            QuickMenuAction::Screenshot => self.take_screenshot(),
+            QuickMenuAction::Settings => self.show_settings = true,
+            QuickMenuAction::Exit => {
+                self.quick_menu.close();
+                self.stop_emulation();
+                self.current_view = View::GameList;
+                return;
+            }
+        }
+        self.close_quick_menu();
+    }
+
     /// Run one emulator frame (called when running)
     fn run_emulator_frame(&mut self) {
-        if let Some(ref emulator) = self.emulator {
-            if emulator.read().state() == RunnerState::Running {
-                if let Err(e) = emulator.write().run_frame() {
-                    let msg = format!("Emulator frame error: {}", e);
-                    self.log_viewer.log(LogLevel::Error, "oc-ui", &msg);
+        if let Some(emulator) = self.emulator.runner() {
+            // Debuggers can halt or resume the emulator before the frame
+            emulator.borrow_mut().poll_debugger();
+            if emulator.borrow().state() == RunnerState::Running {
+                let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| emulator.borrow_mut().run_frame()));
+                match result {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => {
+                        let msg = format!("Emulator frame error: {}", e);
+                        self.log_viewer.log(LogLevel::Error, "oc-ui", &msg);
+                    }
+                    Err(_) => {
+                        // The runner's state can't be trusted after a panic, so stop it
+                        let message = oc_debug::crash_dump::take_panic().unwrap_or_else(|| String::from("unknown panic"));
+                        let report = emulator.borrow().write_crash_dump(CrashKind::HostPanic, &message, None);
+                        let _ = emulator.borrow_mut().stop();
+                        self.crash_report = Some(report);
+                    }
+                }
+                if let Some(report) = emulator.borrow().take_crash() {
+                    self.log_viewer.log(LogLevel::Error, "oc-ui", &report.summary);
+                    self.crash_report = Some(report);
                 }
-                self.emulator_fps = emulator.read().fps();
+                self.emulator_fps = emulator.borrow().fps();
             }
         }
     }
@@ -301,6 +1080,48 @@ impl eframe::App for OxidizedCellApp {
         // Run emulator frame if running
         self.run_emulator_frame();
 
+        // Finished package installations add games
+        let installed = self.pkg_installer.poll();
+        if !installed.is_empty() {
+            for result in &installed {
+                match result {
+                    Ok(report) => {
+                        let msg = format!("Installed {} to {}", report.content_id, report.install_dir.display());
+                        self.log_viewer.log(LogLevel::Info, "oc-ui", &msg);
+                    }
+                    Err(e) => self.log_viewer.log(LogLevel::Error, "oc-ui", e),
+                }
+            }
+            if installed.iter().any(Result::is_ok) {
+                let found = self.game_list.refresh();
+                self.log_viewer.log(LogLevel::Info, "oc-ui", &format!("Found {} games", found));
+            }
+        }
+        if self.pkg_installer.is_busy() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        }
+
+        // Games copied from RPCS3's dev_hdd0 show up once the copy ends
+        if self.rpcs3_import.poll() {
+            let found = self.game_list.refresh();
+            self.log_viewer.log(LogLevel::Info, "oc-ui", &format!("RPCS3 import finished, found {} games", found));
+        }
+        if self.rpcs3_import.is_busy() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        }
+
+        // Savestates are taken between frames
+        self.savestates.configure(&self.config.paths.savestates, self.loaded_title_id.as_deref());
+        self.handle_savestate_hotkeys(ctx);
+        self.handle_capture_hotkeys(ctx);
+        self.handle_speed_hotkeys(ctx);
+        self.handle_quick_menu_toggle(ctx);
+        self.handle_config_changes();
+        self.poll_video_exports();
+        if !self.video_exports.is_empty() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        }
+
         // Get current emulation state
         let emulation_state = self.emulation_state();
         
@@ -316,6 +1137,20 @@ impl eframe::App for OxidizedCellApp {
                         }
                         ui.close_menu();
                     }
+                    if ui.button("📦 Install .pkg...").clicked() {
+                        self.pkg_installer.set_dev_hdd0(&self.config.paths.dev_hdd0);
+                        self.pkg_installer.pick_packages();
+                        self.show_pkg_installer = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("📥 Import from RPCS3...").clicked() {
+                        self.show_rpcs3_import = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("🗂 PARAM.SFO Editor...").clicked() {
+                        self.show_sfo_editor = true;
+                        ui.close_menu();
+                    }
                     ui.separator();
                     if ui.button("Exit").clicked() {
                         if !self.config.general.confirm_exit
@@ -345,8 +1180,56 @@ impl eframe::App for OxidizedCellApp {
                         ui.close_menu();
                     }
                     ui.separator();
-                    if ui.button("Reset").clicked() {
-                        self.stop_emulation();
+                    let can_restart = self.emulator.booted().is_some();
+                    if ui.add_enabled(can_restart, egui::Button::new("Restart")).clicked() {
+                        self.restart_emulation();
+                        ui.close_menu();
+                    }
+                    ui.add_enabled_ui(can_stop, |ui| ui.menu_button("💿 Swap Disc", |ui| self.disc_menu(ui)));
+                    ui.separator();
+                    let recording = self.is_recording_audio();
+                    let label = if recording { "⏹ Stop Audio Recording" } else { "⏺ Record Audio" };
+                    if ui.add_enabled(self.emulator.runner().is_some(), egui::Button::new(label)).clicked() {
+                        self.toggle_audio_recording();
+                        ui.close_menu();
+                    }
+                    let recording_video = self.video_recording_time().is_some();
+                    let label = if recording_video { "⏹ Stop Video Recording" } else { "⏺ Record Video" };
+                    let button = egui::Button::new(label).shortcut_text(&self.config.capture.record_key);
+                    if ui.add_enabled(self.emulator.runner().is_some(), button).clicked() {
+                        self.toggle_video_recording();
+                        ui.close_menu();
+                    }
+                    let button = egui::Button::new("📷 Screenshot").shortcut_text(&self.config.capture.screenshot_key);
+                    if ui.add_enabled(self.emulator.runner().is_some(), button).clicked() {
+                        self.take_screenshot();
+                        ui.close_menu();
+                    }
+                    let tracing = self.is_tracing();
+                    let label = if tracing { "⏹ Stop Instruction Trace" } else { "⏺ Record Instruction Trace" };
+                    if ui.add_enabled(self.emulator.runner().is_some(), egui::Button::new(label)).clicked() {
+                        self.toggle_instruction_trace();
+                        ui.close_menu();
+                    }
+                    let covering = self.is_recording_coverage();
+                    let label = if covering { "⏹ Stop Coverage" } else { "⏺ Record Coverage" };
+                    if ui.add_enabled(self.emulator.runner().is_some(), egui::Button::new(label)).clicked() {
+                        self.toggle_coverage();
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    let has_game = can_stop && self.loaded_title_id.is_some();
+                    let quick_slot = self.savestates.quick_slot();
+                    if ui.add_enabled(has_game, egui::Button::new("Quick Save").shortcut_text("F5")).clicked() {
+                        self.save_state_slot(quick_slot);
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(has_game, egui::Button::new("Quick Load").shortcut_text("F8")).clicked() {
+                        self.load_state_slot(quick_slot);
+                        ui.close_menu();
+                    }
+                    if ui.button("Savestates...").clicked() {
+                        self.show_savestates = true;
                         ui.close_menu();
                     }
                 });
@@ -419,6 +1302,20 @@ impl eframe::App for OxidizedCellApp {
                         }
                         ui.close_menu();
                     }
+                    if ui.checkbox(&mut self.show_cheats, "Cheats Window").clicked() {
+                        // Initialize emulator if needed
+                        if self.show_cheats {
+                            self.init_emulator();
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.checkbox(&mut self.show_memory_stats, "Memory Statistics Window").clicked() {
+                        // Initialize emulator if needed
+                        if self.show_memory_stats {
+                            self.init_emulator();
+                        }
+                        ui.close_menu();
+                    }
                     if ui.checkbox(&mut self.show_shader_debugger, "Shader Debugger Window").clicked() {
                         ui.close_menu();
                     }
@@ -440,13 +1337,22 @@ impl eframe::App for OxidizedCellApp {
                     }
                     ui.separator();
                     ui.label("Theme:");
-                    for theme in Theme::all() {
-                        if ui.selectable_label(self.theme == *theme, theme.name()).clicked() {
-                            self.theme = *theme;
-                            self.theme.apply(ctx);
+                    for (mode, label) in [
+                        (ThemeMode::Light, "Light"),
+                        (ThemeMode::Dark, "Dark"),
+                        (ThemeMode::System, "Follow System"),
+                    ] {
+                        if ui.selectable_label(self.config.ui.theme_mode == mode, label).clicked() {
+                            self.config.ui.theme_mode = mode;
+                            self.themes.apply(ctx, &self.config.ui);
+                            let _ = self.config.save();
                             ui.close_menu();
                         }
                     }
+                    if ui.button("🎨 Customize Themes...").clicked() {
+                        self.show_theme_editor = true;
+                        ui.close_menu();
+                    }
                 });
                 
                 ui.menu_button("Help", |ui| {
@@ -479,10 +1385,29 @@ impl eframe::App for OxidizedCellApp {
                 }
 
                 // Thread counts
-                if let Some(ref emulator) = self.emulator {
-                    let runner = emulator.read();
+                if let Some(emulator) = self.emulator.runner() {
+                    let runner = emulator.borrow();
                     ui.separator();
                     ui.label(format!("PPU: {} | SPU: {}", runner.ppu_thread_count(), runner.spu_thread_count()));
+                    if let Some(status) = runner.replay_status() {
+                        ui.separator();
+                        ui.label(format!("⏺ {}", status));
+                    }
+                    if let Some(time) = runner.video_recording_time() {
+                        ui.separator();
+                        ui.colored_label(egui::Color32::from_rgb(230, 60, 60), format!("⏺ REC {}", format_duration(time)));
+                    }
+                    match runner.speed_mode() {
+                        SpeedMode::Normal => {}
+                        SpeedMode::Turbo => {
+                            ui.separator();
+                            ui.label("⏩ Turbo");
+                        }
+                        SpeedMode::SlowMotion => {
+                            ui.separator();
+                            ui.label("🐢 Slow motion");
+                        }
+                    }
                 }
                 
                 // Selected game info
@@ -499,19 +1424,57 @@ impl eframe::App for OxidizedCellApp {
             });
         });
         
+        // Feed the RSX state to the shader debugger wherever it is shown
+        let shows_shader_debugger = self.current_view == View::ShaderDebugger || self.show_shader_debugger;
+        if shows_shader_debugger && self.shader_debugger.shows_rsx_state() {
+            if let Some(emulator) = self.emulator.runner() {
+                let state = emulator.borrow().rsx_thread().read().gfx_state.clone();
+                self.shader_debugger.set_rsx_state(state);
+            }
+        }
+
         // Main content
         egui::CentralPanel::default().show(ctx, |ui| {
             match self.current_view {
                 View::GameList => {
                     if let Some(game_path) = self.game_list.show(ctx, ui) {
+                        let resume = self.game_list.take_resume_request();
                         // Launch game using the emulator runner
                         self.launch_game(game_path);
+                        if resume && self.config.general.resume_from_savestate {
+                            self.resume_newest_savestate();
+                        }
+                    }
+                    if let Some(game) = self.game_list.take_configure_request() {
+                        self.game_settings.open(&game.id, &game.title, &self.config);
+                    }
+                    if let Some(path) = self.game_list.take_sfo_request() {
+                        self.sfo_editor.open(&path);
+                        self.show_sfo_editor = true;
                     }
                 }
                 View::Emulation => {
                     self.show_emulation_view(ui, emulation_state);
                 }
                 View::Debugger => {
+                    if let Some(emulator) = self.emulator.runner() {
+                        let runner = emulator.borrow();
+                        let frames = match runner.state() {
+                            RunnerState::Paused => runner.ppu_backtrace(0).unwrap_or_default(),
+                            _ => Vec::new(),
+                        };
+                        self.debugger.set_backtrace(frames);
+                        if self.debugger.shows_spu() {
+                            self.debugger.set_spu_threads(runner.spu_threads());
+                        }
+                        if self.debugger.shows_kernel_objects() {
+                            let kernel = runner.syscall_handler();
+                            self.debugger.set_kernel_objects(
+                                kernel.thread_manager().debug_snapshot(),
+                                kernel.object_manager().debug_snapshot(),
+                            );
+                        }
+                    }
                     self.debugger.show(ui);
                 }
                 View::LogViewer => {
@@ -528,16 +1491,18 @@ impl eframe::App for OxidizedCellApp {
                     self.shader_debugger.show(ui);
                 }
                 View::ControllerConfig => {
-                    if self.controller_config.show(ui) {
+                    let (devices, captured, test) = self.controller_panel_input();
+                    if self.controller_config.show(ui, &mut self.config.input, &devices, captured, &test) {
                         // Config changed, save it
                         let _ = self.config.save();
+                        self.apply_config();
                     }
                 }
             }
         });
         
-        // Performance overlay
-        if self.show_performance {
+        // Performance window outside the game view, which draws the overlay itself
+        if self.show_performance && self.current_view != View::Emulation {
             egui::Window::new("Performance")
                 .default_pos([10.0, 40.0])
                 .collapsible(false)
@@ -548,9 +1513,26 @@ impl eframe::App for OxidizedCellApp {
                     if emulation_state == RunnerState::Running {
                         ui.label(format!("Emulator FPS: {:.1}", self.emulator_fps));
                     }
-                    if let Some(ref emulator) = self.emulator {
-                        let runner = emulator.read();
+                    if let Some(emulator) = self.emulator.runner() {
+                        let runner = emulator.borrow();
+                        let stats = runner.perf_stats();
                         ui.separator();
+                        ui.label(format!("Emulation Speed: {:.1}%", stats.speed_percent));
+                        ui.label(format!(
+                            "Frame Time p50/p95/p99: {:.1} / {:.1} / {:.1}ms",
+                            stats.frame_ms_p50, stats.frame_ms_p95, stats.frame_ms_p99
+                        ));
+                        ui.label(format!("CPU: {:.1}%  GPU: {:.1}%", stats.cpu_percent, stats.gpu_percent));
+                        ui.label(format!("Shaders Compiled: {}", stats.shader_compiles));
+                        ui.label(format!(
+                            "Draw Calls: {}  Triangles: {}  Texture Uploads: {}",
+                            stats.rsx.draw_calls, stats.rsx.triangles, stats.rsx.texture_uploads
+                        ));
+                        ui.label(format!(
+                            "Surface Cache: {:.1}%  Pipeline Cache: {:.1}%",
+                            stats.rsx.surface_hit_percent(),
+                            stats.rsx.pipeline_hit_percent()
+                        ));
                         ui.label(format!("Frame Count: {}", runner.frame_count()));
                         ui.label(format!("Total Cycles: {}", runner.total_cycles()));
                     }
@@ -577,6 +1559,31 @@ impl eframe::App for OxidizedCellApp {
                 });
         }
 
+        // Cheat window (floating)
+        if self.show_cheats {
+            egui::Window::new("Cheats")
+                .open(&mut self.show_cheats)
+                .default_size([550.0, 500.0])
+                .show(ctx, |ui| {
+                    self.cheats.show(ui);
+                });
+        }
+
+        // Memory statistics window (floating)
+        if self.show_memory_stats {
+            if let Some(emulator) = self.emulator.runner() {
+                let kernel_memory = emulator.borrow().syscall_handler().memory_manager().clone();
+                self.memory_stats
+                    .set_user_memory(kernel_memory.user_memory_size(), kernel_memory.available());
+            }
+            egui::Window::new("Memory Statistics")
+                .open(&mut self.show_memory_stats)
+                .default_size([750.0, 450.0])
+                .show(ctx, |ui| {
+                    self.memory_stats.show(ui);
+                });
+        }
+
         // Shader debugger window (floating)
         if self.show_shader_debugger {
             egui::Window::new("Shader Debugger")
@@ -589,42 +1596,174 @@ impl eframe::App for OxidizedCellApp {
 
         // Controller config window (floating)
         if self.show_controller_config {
+            let (devices, captured, test) = self.controller_panel_input();
+            let mut config_changed = false;
+            let ports = self.config.input.controller.ports.clone();
             egui::Window::new("Controller Configuration")
                 .open(&mut self.show_controller_config)
                 .default_size([700.0, 550.0])
                 .show(ctx, |ui| {
-                    if self.controller_config.show(ui) {
-                        // Config changed
-                        let _ = self.config.save();
-                    }
+                    config_changed = self.controller_config.show(ui, &mut self.config.input, &devices, captured, &test);
                 });
+            if config_changed {
+                let _ = self.config.save();
+                self.apply_config();
+                if self.config.input.controller.ports != ports {
+                    if let Some(emulator) = self.emulator.runner() {
+                        emulator.borrow_mut().reassign_ports(&self.config.input);
+                    }
+                }
+            }
         }
         
+        // On-screen keyboard while the game has one open
+        if let Some(emulator) = self.emulator.runner() {
+            let request = emulator.borrow().osk_request();
+            let pad = self.overlay_pad();
+            let open = request.is_some();
+            if let Some(outcome) = self.osk.show(ctx, request, pad.as_ref()) {
+                let text = match outcome {
+                    OskOutcome::Done(text) => Some(text),
+                    OskOutcome::Cancel => None,
+                };
+                emulator.borrow_mut().finish_osk(text.as_deref());
+                self.log_viewer.log(
+                    LogLevel::Info,
+                    "oc-ui",
+                    if text.is_some() { "On-screen keyboard text sent" } else { "On-screen keyboard cancelled" },
+                );
+            } else if open {
+                // Keep reading the controller while the keyboard is up
+                ctx.request_repaint();
+            }
+        }
+
+        // Quick menu over the game
+        self.show_quick_menu(ctx);
+
+        // Game data error the game asked the system to report
+        if let Some(emulator) = self.emulator.runner() {
+            let dialog = emulator.borrow().game_error_dialog();
+            if let Some((generation, dialog)) = dialog {
+                let mut closed = false;
+                egui::Window::new("Game Data Error")
+                    .id(egui::Id::new(("game_error_dialog", generation)))
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                    .show(ctx, |ui| {
+                        ui.label(dialog.message());
+                        if !dialog.dir_name.is_empty() {
+                            ui.weak(format!("Directory: {}", dialog.dir_name));
+                        }
+                        ui.separator();
+                        closed = ui.button("OK").clicked();
+                    });
+                if closed {
+                    emulator.borrow_mut().close_game_error_dialog();
+                    let message = if dialog.exits_game() {
+                        "Game data error dialog closed, the game exits"
+                    } else {
+                        "Game data error dialog closed"
+                    };
+                    self.log_viewer.log(LogLevel::Info, "oc-ui", message);
+                }
+            }
+        }
+
+        // Game data the system is installing for the game
+        if let Some(emulator) = self.emulator.runner() {
+            let install = emulator.borrow().game_data_install();
+            if let Some(install) = install {
+                egui::Window::new("Installing Game Data")
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                    .show(ctx, |ui| {
+                        ui.add(egui::ProgressBar::new(install.progress as f32 / 100.0).show_percentage());
+                        ui.weak(format!("{} / {} KB", install.installed_size_kb, install.total_size_kb));
+                    });
+                ctx.request_repaint();
+            }
+        }
+
+        // Package installer window (floating)
+        if self.show_pkg_installer {
+            self.pkg_installer.set_dev_hdd0(&self.config.paths.dev_hdd0);
+            egui::Window::new("Install Packages")
+                .open(&mut self.show_pkg_installer)
+                .default_size([520.0, 400.0])
+                .show(ctx, |ui| {
+                    self.pkg_installer.show(ui);
+                });
+        }
+
+        // PARAM.SFO editor window (floating)
+        if self.show_sfo_editor {
+            egui::Window::new("PARAM.SFO Editor")
+                .open(&mut self.show_sfo_editor)
+                .default_size([640.0, 520.0])
+                .show(ctx, |ui| {
+                    self.sfo_editor.show(ui);
+                });
+        }
+
+        // RPCS3 import window (floating)
+        if self.show_rpcs3_import {
+            let mut changed = false;
+            egui::Window::new("Import from RPCS3")
+                .open(&mut self.show_rpcs3_import)
+                .default_size([480.0, 300.0])
+                .show(ctx, |ui| {
+                    changed = self.rpcs3_import.show(ui, &mut self.config);
+                });
+            if changed {
+                if let Err(e) = self.config.save() {
+                    self.log_viewer.log(LogLevel::Error, "oc-ui", &format!("Failed to save config: {}", e));
+                }
+                self.apply_config();
+            }
+        }
+
+        // Savestate slots window (floating)
+        if self.show_savestates {
+            let running = emulation_state != RunnerState::Stopped;
+            let mut action = None;
+            egui::Window::new("Savestates")
+                .open(&mut self.show_savestates)
+                .default_size([420.0, 500.0])
+                .show(ctx, |ui| {
+                    action = self.savestates.show(ctx, ui, running);
+                });
+            match action {
+                Some(SlotAction::Save(slot)) => self.save_state_slot(slot),
+                Some(SlotAction::Load(slot)) => self.load_state_slot(slot),
+                None => {}
+            }
+        }
+
+        // Settings window of one game
+        if self.game_settings.is_open() {
+            if let Some(title_id) = self.game_settings.show(ctx, &self.config) {
+                if self.loaded_title_id.as_deref() == Some(title_id.as_str()) {
+                    self.apply_config();
+                }
+            }
+        }
+
         // Settings window
         if self.show_settings {
             let mut close_requested = false;
-            let old_log_level = self.config.debug.log_level;
-            
+            let mut settings_changed = false;
             egui::Window::new("Settings")
                 .open(&mut self.show_settings)
                 .default_width(600.0)
                 .default_height(500.0)
                 .show(ctx, |ui| {
                     if self.settings_panel.show(ui, &mut self.config) {
-                        // Check if log level changed
-                        if self.config.debug.log_level != old_log_level {
-                            // Update the tracing subscriber filter at runtime
-                            if oc_core::logging::set_log_level(self.config.debug.log_level) {
-                                self.log_viewer.log(
-                                    LogLevel::Info, 
-                                    "oc-ui", 
-                                    &format!("Log level changed to: {:?}", self.config.debug.log_level)
-                                );
-                            }
-                        }
-                        
                         // Auto-save on change
                         let _ = self.config.save();
+                        settings_changed = true;
                     }
                     
                     ui.separator();
@@ -642,11 +1781,28 @@ impl eframe::App for OxidizedCellApp {
                         }
                     });
                 });
+            if settings_changed {
+                self.apply_config();
+            }
             if close_requested {
                 self.show_settings = false;
             }
         }
         
+        // Theme editor window (floating)
+        if self.show_theme_editor {
+            let mut changed = false;
+            egui::Window::new("Themes")
+                .open(&mut self.show_theme_editor)
+                .default_size([420.0, 380.0])
+                .show(ctx, |ui| {
+                    changed = self.themes.show(ctx, ui, &mut self.config.ui);
+                });
+            if changed {
+                let _ = self.config.save();
+            }
+        }
+
         // About window
         if self.show_about {
             egui::Window::new("About")
@@ -699,15 +1855,137 @@ impl eframe::App for OxidizedCellApp {
             self.error_message = None;
         }
 
+        // Crash dialog
+        let mut clear_crash = false;
+        if let Some(ref report) = self.crash_report {
+            let mut show_crash = true;
+            egui::Window::new(report.kind.name())
+                .open(&mut show_crash)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    let headline = match report.kind {
+                        CrashKind::GuestFault => "⚠ The game crashed",
+                        CrashKind::HostPanic => "⚠ The emulator crashed",
+                    };
+                    ui.colored_label(egui::Color32::RED, headline);
+                    ui.separator();
+                    ui.monospace(report.summary.as_str());
+                    ui.separator();
+                    match &report.path {
+                        Some(path) => ui.label(format!("Crash dump written to {}", path.display())),
+                        None => ui.label("No crash dump was written; enable crash dumps in the debug settings."),
+                    };
+                    ui.horizontal(|ui| {
+                        if ui.button("📋 Copy Summary").clicked() {
+                            ui.output_mut(|o| o.copied_text = report.summary.clone());
+                        }
+                        if let Some(path) = &report.path {
+                            if ui.button("📋 Copy Path").clicked() {
+                                ui.output_mut(|o| o.copied_text = path.display().to_string());
+                            }
+                        }
+                        if ui.button("OK").clicked() {
+                            clear_crash = true;
+                        }
+                    });
+                });
+            if !show_crash {
+                clear_crash = true;
+            }
+        }
+        if clear_crash {
+            self.crash_report = None;
+        }
+
+        // Summary of what the stopped game needed that is not implemented
+        let mut clear_missing = false;
+        if let Some(ref report) = self.missing_features {
+            let mut show_missing = true;
+            egui::Window::new("Missing Features")
+                .open(&mut show_missing)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    ui.label(format!("{} used features the emulator does not implement:", report.title_id));
+                    ui.label(report.summary());
+                    ui.separator();
+                    egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                        ui.monospace(report.to_text());
+                    });
+                    ui.separator();
+                    if let Some(path) = &report.path {
+                        ui.label(format!("Report written to {}", path.display()));
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("📋 Copy Report").clicked() {
+                            ui.output_mut(|o| o.copied_text = report.to_text());
+                        }
+                        if let Some(path) = &report.path {
+                            if ui.button("📋 Copy Path").clicked() {
+                                ui.output_mut(|o| o.copied_text = path.display().to_string());
+                            }
+                        }
+                        if ui.button("OK").clicked() {
+                            clear_missing = true;
+                        }
+                    });
+                });
+            if !show_missing {
+                clear_missing = true;
+            }
+        }
+        if clear_missing {
+            self.missing_features = None;
+        }
+
+        // Recovery prompt after a session that did not end cleanly
+        let mut restore = None;
+        if let Some(ref recovery) = self.recovery {
+            let mut show_recovery = true;
+            egui::Window::new("Restore Autosave")
+                .open(&mut show_recovery)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label("The last session of this game did not end cleanly.");
+                    ui.label(format!(
+                        "An autosave from {} (frame {}) can be restored.",
+                        format_last_played(recovery.info.created),
+                        recovery.info.frame
+                    ));
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.button("Restore").clicked() {
+                            restore = Some(true);
+                        }
+                        if ui.button("Discard").clicked() {
+                            restore = Some(false);
+                        }
+                    });
+                });
+            if !show_recovery {
+                restore = Some(false);
+            }
+        }
+        if let Some(restore) = restore {
+            match self.recovery.take() {
+                Some(recovery) if restore => self.restore_autosave(&recovery),
+                _ => {}
+            }
+        }
+
         // Request repaint if emulator is running
         if emulation_state == RunnerState::Running {
             ctx.request_repaint();
+        } else if self.emulator.runner().is_some_and(|e| e.borrow().debugger_listening()) {
+            // Keep answering the debugger while paused
+            ctx.request_repaint_after(std::time::Duration::from_millis(20));
         }
     }
     
     fn save(&mut self, _storage: &mut dyn eframe::Storage) {
         // Save config on app exit
         let _ = self.config.save();
+        self.flush_playtime();
     }
 }
 
@@ -737,6 +2015,9 @@ impl OxidizedCellApp {
                 if ui.add_enabled(can_stop, egui::Button::new("⏹ Stop")).clicked() {
                     self.stop_emulation();
                 }
+                if ui.add_enabled(self.emulator.booted().is_some(), egui::Button::new("🔄 Restart")).clicked() {
+                    self.restart_emulation();
+                }
 
                 ui.separator();
 
@@ -759,6 +2040,9 @@ impl OxidizedCellApp {
 
                 // Frame limiting checkbox
                 if ui.checkbox(&mut self.enable_frame_limiting, "Frame Limit").changed() {
+                    if let Some(emulator) = self.emulator.runner() {
+                        emulator.borrow_mut().set_frame_limited(self.enable_frame_limiting);
+                    }
                     self.log_viewer.log(
                         LogLevel::Info,
                         "oc-ui",
@@ -787,16 +2071,19 @@ impl OxidizedCellApp {
                 (available_size.x - 20.0, (available_size.x - 20.0) / aspect_ratio)
             };
             
-            let (rect, _response) = ui.allocate_exact_size(
+            let (rect, response) = ui.allocate_exact_size(
                 egui::vec2(width, height),
                 egui::Sense::hover()
             );
+            if emulation_state == RunnerState::Running {
+                self.forward_keyboard_mouse(ui, &response);
+            }
             
             // Try to get and display framebuffer from emulator
             let mut has_framebuffer = false;
             if emulation_state == RunnerState::Running || emulation_state == RunnerState::Paused {
-                if let Some(ref emulator) = self.emulator {
-                    let runner = emulator.read();
+                if let Some(emulator) = self.emulator.runner() {
+                    let runner = emulator.borrow();
                     if let Some(fb) = runner.get_framebuffer() {
                         // Update texture if dimensions changed or texture doesn't exist
                         let needs_update = self.framebuffer_texture.is_none() 
@@ -851,6 +2138,33 @@ impl OxidizedCellApp {
                 egui::Stroke::new(2.0, egui::Color32::from_gray(60)),
             );
 
+            // Performance overlay over the game output
+            if self.show_performance && emulation_state != RunnerState::Stopped {
+                if let Some(emulator) = self.emulator.runner() {
+                    let stats = emulator.borrow().perf_stats();
+                    perf_overlay::draw(ui.painter(), rect, &stats, self.fps);
+                }
+            }
+
+            // Recording indicator in the corner of the game output
+            if let Some(time) = self.video_recording_time() {
+                let painter = ui.painter();
+                let red = egui::Color32::from_rgb(230, 60, 60);
+                let dot = rect.right_top() + egui::vec2(-96.0, 18.0);
+                // Blink once a second
+                if time.as_millis() % 1000 < 700 {
+                    painter.circle_filled(dot, 6.0, red);
+                }
+                painter.text(
+                    dot + egui::vec2(12.0, 0.0),
+                    egui::Align2::LEFT_CENTER,
+                    format!("REC {}", format_duration(time)),
+                    egui::FontId::monospace(14.0),
+                    red,
+                );
+                ui.ctx().request_repaint_after(std::time::Duration::from_millis(250));
+            }
+
             // Display status text only when not displaying framebuffer
             if !has_framebuffer {
                 let display_text = match emulation_state {
@@ -880,8 +2194,8 @@ impl OxidizedCellApp {
 
             // Show emulator stats when running or paused
             if emulation_state != RunnerState::Stopped {
-                if let Some(ref emulator) = self.emulator {
-                    let runner = emulator.read();
+                if let Some(emulator) = self.emulator.runner() {
+                    let runner = emulator.borrow();
                     let frame_limit_indicator = if self.enable_frame_limiting { "◉" } else { "○" };
                     let frame_skip_indicator = if self.enable_frame_skipping { "◉" } else { "○" };
                     let stats_text = format!(
@@ -942,18 +2256,17 @@ impl OxidizedCellApp {
             })
             .unwrap_or_else(|| "UNKNOWN".to_string());
 
-        // Determine region from ID prefix
-        let region = match id.get(0..2) {
-            Some("BL") => "US",  // BLUS
-            Some("BE") => "EU",  // BLES
-            Some("BJ") | Some("BC") => match id.get(2..3) {
-                Some("U") => "US",
-                Some("E") => "EU", 
-                Some("J") => "JP",
-                _ => "Unknown",
-            },
-            _ => "Unknown",
-        }.to_string();
+        let region = crate::game_list::region_from_id(&id).to_string();
+
+        // Images sit next to USRDIR when an EBOOT.BIN was picked
+        let game_dir = if path.is_dir() {
+            Some(path.as_path())
+        } else {
+            path.parent().and_then(|usrdir| usrdir.parent())
+        };
+        let find_image = |image: GameImage| {
+            game_dir.and_then(|dir| oc_integration::GameScanner::find_image(dir, image.file_name()))
+        };
 
         GameInfo {
             title: file_name,
@@ -962,7 +2275,8 @@ impl OxidizedCellApp {
             version: "1.00".to_string(),
             region,
             category: "Other".to_string(),
-            icon_data: None,
+            icon_path: find_image(GameImage::Icon),
+            background_path: find_image(GameImage::Background),
             last_played: None,
         }
     }
@@ -979,6 +2293,16 @@ impl OxidizedCellApp {
     }
 }
 
+/// Recording length as mm:ss, or h:mm:ss past an hour
+fn format_duration(time: Duration) -> String {
+    let secs = time.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
 /// Run the application
 pub fn run() -> eframe::Result<()> {
     let options = eframe::NativeOptions {
@@ -987,6 +2311,9 @@ pub fn run() -> eframe::Result<()> {
             .with_min_inner_size([800.0, 600.0]),
         ..Default::default()
     };
+
+    // Host panics end up in crash dumps
+    oc_debug::crash_dump::install_panic_hook();
     
     eframe::run_native(
         "oxidized-cell",
@@ -0,0 +1,363 @@
+//! Cheat window: memory search and the cheat list of the running game
+
+use eframe::egui;
+use oc_debug::{Cheat, CheatFormat, CheatManager, MemoryScanner, ScanCondition, ScanValueType};
+use oc_memory::MemoryManager;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+/// Maximum number of scan results listed
+const MAX_LISTED_RESULTS: usize = 200;
+
+/// Search condition picked in the UI, before its values are parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SearchKind {
+    Exact,
+    NotEqual,
+    GreaterThan,
+    LessThan,
+    Between,
+    Unknown,
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+    IncreasedBy,
+    DecreasedBy,
+}
+
+impl SearchKind {
+    const ALL: [SearchKind; 12] = [
+        Self::Exact,
+        Self::NotEqual,
+        Self::GreaterThan,
+        Self::LessThan,
+        Self::Between,
+        Self::Unknown,
+        Self::Changed,
+        Self::Unchanged,
+        Self::Increased,
+        Self::Decreased,
+        Self::IncreasedBy,
+        Self::DecreasedBy,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Exact => "Exact value",
+            Self::NotEqual => "Not equal to",
+            Self::GreaterThan => "Greater than",
+            Self::LessThan => "Less than",
+            Self::Between => "Between",
+            Self::Unknown => "Unknown initial value",
+            Self::Changed => "Changed",
+            Self::Unchanged => "Unchanged",
+            Self::Increased => "Increased",
+            Self::Decreased => "Decreased",
+            Self::IncreasedBy => "Increased by",
+            Self::DecreasedBy => "Decreased by",
+        }
+    }
+
+    /// Number of values the condition takes
+    fn values(&self) -> usize {
+        match self {
+            Self::Between => 2,
+            Self::Unknown | Self::Changed | Self::Unchanged | Self::Increased | Self::Decreased => 0,
+            _ => 1,
+        }
+    }
+}
+
+/// Cheat window state
+pub struct CheatWindow {
+    /// Memory manager reference (optional, may not be connected yet)
+    memory: Option<Arc<MemoryManager>>,
+    /// Cheats of the running game
+    cheats: Option<Arc<Mutex<CheatManager>>>,
+    scanner: MemoryScanner,
+    search_kind: SearchKind,
+    value_input: String,
+    value_input2: String,
+    /// Name given to cheats created from scan results
+    freeze_name: String,
+    /// Text pasted for import
+    import_text: String,
+    status_message: String,
+}
+
+impl CheatWindow {
+    /// Create a new cheat window
+    pub fn new() -> Self {
+        Self {
+            memory: None,
+            cheats: None,
+            scanner: MemoryScanner::new(ScanValueType::U32),
+            search_kind: SearchKind::Exact,
+            value_input: String::new(),
+            value_input2: String::new(),
+            freeze_name: String::new(),
+            import_text: String::new(),
+            status_message: String::from("Emulator not connected"),
+        }
+    }
+
+    /// Connect to the emulator's memory and cheat list
+    pub fn connect(&mut self, memory: Arc<MemoryManager>, cheats: Arc<Mutex<CheatManager>>) {
+        self.memory = Some(memory);
+        self.cheats = Some(cheats);
+        self.scanner.reset();
+        self.status_message = String::from("Connected");
+    }
+
+    /// Build the scan condition from the inputs
+    fn condition(&self) -> Result<ScanCondition, String> {
+        let value_type = self.scanner.value_type();
+        let parse = |input: &str| {
+            value_type
+                .parse(input.trim())
+                .ok_or_else(|| format!("\"{}\" is not a valid {}", input.trim(), value_type.name()))
+        };
+        Ok(match self.search_kind {
+            SearchKind::Exact => ScanCondition::Exact(parse(&self.value_input)?),
+            SearchKind::NotEqual => ScanCondition::NotEqual(parse(&self.value_input)?),
+            SearchKind::GreaterThan => ScanCondition::GreaterThan(parse(&self.value_input)?),
+            SearchKind::LessThan => ScanCondition::LessThan(parse(&self.value_input)?),
+            SearchKind::Between => {
+                ScanCondition::Between(parse(&self.value_input)?, parse(&self.value_input2)?)
+            }
+            SearchKind::Unknown => ScanCondition::Unknown,
+            SearchKind::Changed => ScanCondition::Changed,
+            SearchKind::Unchanged => ScanCondition::Unchanged,
+            SearchKind::Increased => ScanCondition::Increased,
+            SearchKind::Decreased => ScanCondition::Decreased,
+            SearchKind::IncreasedBy => ScanCondition::IncreasedBy(parse(&self.value_input)?),
+            SearchKind::DecreasedBy => ScanCondition::DecreasedBy(parse(&self.value_input)?),
+        })
+    }
+
+    /// Run a scan with the current inputs
+    fn scan(&mut self) {
+        let Some(memory) = self.memory.clone() else {
+            self.status_message = String::from("Emulator not connected");
+            return;
+        };
+        match self.condition() {
+            Ok(condition) => {
+                let count = self.scanner.scan(&memory, condition);
+                self.status_message = if self.scanner.is_truncated() {
+                    format!("Scan {}: {} results (truncated)", self.scanner.scan_count(), count)
+                } else {
+                    format!("Scan {}: {} results", self.scanner.scan_count(), count)
+                };
+            }
+            Err(e) => self.status_message = e,
+        }
+    }
+
+    /// Apply a change to the cheat list and save it
+    fn update_cheats(&mut self, change: impl FnOnce(&mut CheatManager) -> Result<String, String>) {
+        let Some(cheats) = self.cheats.clone() else {
+            self.status_message = String::from("Emulator not connected");
+            return;
+        };
+        let mut cheats = cheats.lock();
+        self.status_message = match change(&mut cheats) {
+            Ok(message) if cheats.title_id().is_none() => format!("{} (not saved, no game loaded)", message),
+            Ok(message) => match cheats.save() {
+                Ok(()) => message,
+                Err(e) => format!("{}, but saving failed: {}", message, e),
+            },
+            Err(e) => e,
+        };
+    }
+
+    /// Show the cheat window
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Search", |ui| self.show_search(ui));
+        ui.separator();
+        self.show_cheat_list(ui);
+        ui.separator();
+        ui.label(&self.status_message);
+    }
+
+    fn show_search(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let mut value_type = self.scanner.value_type();
+            ui.add_enabled_ui(self.scanner.scan_count() == 0, |ui| {
+                egui::ComboBox::from_id_salt("cheat_value_type")
+                    .selected_text(value_type.name())
+                    .show_ui(ui, |ui| {
+                        for t in ScanValueType::ALL {
+                            ui.selectable_value(&mut value_type, t, t.name());
+                        }
+                    });
+            });
+            if value_type != self.scanner.value_type() {
+                self.scanner = MemoryScanner::new(value_type);
+            }
+
+            egui::ComboBox::from_id_salt("cheat_search_kind")
+                .selected_text(self.search_kind.label())
+                .show_ui(ui, |ui| {
+                    for kind in SearchKind::ALL {
+                        ui.selectable_value(&mut self.search_kind, kind, kind.label());
+                    }
+                });
+
+            if self.search_kind.values() > 0 {
+                ui.add(egui::TextEdit::singleline(&mut self.value_input).desired_width(100.0));
+            }
+            if self.search_kind.values() > 1 {
+                ui.label("and");
+                ui.add(egui::TextEdit::singleline(&mut self.value_input2).desired_width(100.0));
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let label = if self.scanner.scan_count() == 0 { "First Scan" } else { "Next Scan" };
+            if ui.add_enabled(self.memory.is_some(), egui::Button::new(label)).clicked() {
+                self.scan();
+            }
+            if ui.button("Reset").clicked() {
+                self.scanner.reset();
+                self.status_message = String::from("Scan reset");
+            }
+            ui.label(format!("{} results", self.scanner.result_count()));
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Cheat name:");
+            ui.add(egui::TextEdit::singleline(&mut self.freeze_name).hint_text("Frozen value"));
+        });
+
+        let memory = self.memory.clone();
+        let value_type = self.scanner.value_type();
+        let mut freeze = None;
+        egui::ScrollArea::vertical()
+            .id_salt("cheat_scan_results")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                egui::Grid::new("cheat_scan_results_grid").striped(true).show(ui, |ui| {
+                    ui.strong("Address");
+                    ui.strong("Value");
+                    ui.strong("Previous");
+                    ui.strong("");
+                    ui.end_row();
+                    for result in self.scanner.results().iter().take(MAX_LISTED_RESULTS) {
+                        let current = memory
+                            .as_ref()
+                            .and_then(|memory| self.scanner.read_value(memory, result.address))
+                            .unwrap_or(result.value);
+                        ui.monospace(format!("0x{:08X}", result.address));
+                        ui.monospace(current.to_string());
+                        ui.monospace(result.previous.map(|v| v.to_string()).unwrap_or_default());
+                        if ui.small_button("Freeze").clicked() {
+                            freeze = Some((result.address, current));
+                        }
+                        ui.end_row();
+                    }
+                });
+                if self.scanner.results().len() > MAX_LISTED_RESULTS {
+                    ui.label(format!("… {} more", self.scanner.results().len() - MAX_LISTED_RESULTS));
+                }
+            });
+
+        if let Some((address, value)) = freeze {
+            let name = match self.freeze_name.trim() {
+                "" => format!("{} at 0x{:08X}", value_type.name(), address),
+                name => name.to_string(),
+            };
+            let cheat = Cheat::freeze(&name, address, &value_type.encode(value));
+            self.update_cheats(|cheats| {
+                cheats.add(cheat)?;
+                Ok(format!("Froze 0x{:08X} at {}", address, value))
+            });
+        }
+    }
+
+    fn show_cheat_list(&mut self, ui: &mut egui::Ui) {
+        let Some(cheats) = self.cheats.clone() else {
+            ui.label("Start the emulator to manage cheats.");
+            return;
+        };
+
+        match cheats.lock().title_id() {
+            Some(title) => ui.strong(format!("Cheats for {}", title)),
+            None => ui.strong("Cheats (no game loaded)"),
+        };
+
+        let mut toggled = None;
+        let mut removed = None;
+        {
+            let cheats = cheats.lock();
+            if cheats.is_empty() {
+                ui.label("No cheats. Freeze a search result or import codes below.");
+            }
+            egui::ScrollArea::vertical()
+                .id_salt("cheat_list")
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("cheat_list_grid").striped(true).show(ui, |ui| {
+                        for (index, cheat) in cheats.cheats().enumerate() {
+                            let mut enabled = cheat.enabled;
+                            if ui.checkbox(&mut enabled, &cheat.name).changed() {
+                                toggled = Some((index, enabled));
+                            }
+                            ui.label(cheat.format.name()).on_hover_text(&cheat.code);
+                            if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                                removed = Some(index);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+        }
+        if let Some((index, enabled)) = toggled {
+            self.update_cheats(|cheats| {
+                cheats.set_enabled(index, enabled);
+                Ok(String::from(if enabled { "Cheat enabled" } else { "Cheat disabled" }))
+            });
+        }
+        if let Some(index) = removed {
+            self.update_cheats(|cheats| {
+                let cheat = cheats.remove(index).ok_or("No such cheat")?;
+                Ok(format!("Removed \"{}\"", cheat.name))
+            });
+        }
+
+        ui.collapsing("Import", |ui| {
+            ui.label(format!(
+                "Paste {} or {} codes; separate cheats with a blank line and start each with its name.",
+                CheatFormat::NetCheat.name(),
+                CheatFormat::Raw.name()
+            ));
+            ui.add(
+                egui::TextEdit::multiline(&mut self.import_text)
+                    .font(egui::TextStyle::Monospace)
+                    .desired_rows(6)
+                    .desired_width(f32::INFINITY),
+            );
+            if ui.button("Import").clicked() {
+                let text = std::mem::take(&mut self.import_text);
+                let mut failed = false;
+                self.update_cheats(|cheats| match cheats.import(&text) {
+                    Ok(count) => Ok(format!("Imported {} cheats", count)),
+                    Err(e) => {
+                        failed = true;
+                        Err(e)
+                    }
+                });
+                if failed {
+                    self.import_text = text;
+                }
+            }
+        });
+    }
+}
+
+impl Default for CheatWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,390 +1,842 @@
 //! Controller configuration UI for gamepad and input mapping
+//!
+//! Edits the named input profiles in [`InputConfig`] and assigns them to
+//! title IDs. Bindings are captured from the physical controller: click
+//! "Bind" and press a button or move an axis. Host controllers are
+//! assigned to PS3 pad ports here, and keyboard and mouse pad emulation,
+//! guitar/drum emulation and the PlayStation Eye are configured here too.
+//! The test view shows a port's pad live and calibrates its dead zones
+//! and gyroscope drift with the controller at rest.
 
 use eframe::egui;
-use std::collections::HashMap;
-
-/// Maximum number of buttons supported by controllers
-const MAX_BUTTONS: usize = 16;
-
-/// Maximum number of axes supported by controllers
-const MAX_AXES: usize = 6;
-
-/// Controller type
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum ControllerType {
-    None,
-    DualShock3,
-    DualSense,
-    Xbox,
-    Generic,
-}
-
-impl ControllerType {
-    fn label(&self) -> &'static str {
-        match self {
-            ControllerType::None => "None",
-            ControllerType::DualShock3 => "DualShock 3",
-            ControllerType::DualSense => "DualSense",
-            ControllerType::Xbox => "Xbox Controller",
-            ControllerType::Generic => "Generic Gamepad",
-        }
-    }
+use oc_core::config::{
+    CameraConfig, InputConfig, InputProfileConfig, InstrumentConfig, InstrumentKind, KeyboardPadConfig, MouseMode, MoveConfig,
+    MoveSource, PadPortConfig, PointerResponse, ResponseCurve, StickResponse,
+};
+use oc_input::calibration::CALIBRATION_SECONDS;
+use oc_input::pad::{PadButtons, PadState, MAX_PADS};
+use oc_input::{
+    HostGamepadInfo, HostInput, InputProfile, InstrumentMapping, InstrumentSource, MidiInputBackend, Ps3Input,
+    RestCalibration, SixaxisData, WebcamCapture,
+};
+use std::time::Instant;
 
-    fn all() -> &'static [ControllerType] {
-        &[
-            ControllerType::None,
-            ControllerType::DualShock3,
-            ControllerType::DualSense,
-            ControllerType::Xbox,
-            ControllerType::Generic,
-        ]
+/// Display label of an instrument kind
+fn instrument_label(kind: InstrumentKind) -> &'static str {
+    match kind {
+        InstrumentKind::None => "Controller",
+        InstrumentKind::Guitar => "Guitar",
+        InstrumentKind::Drums => "Drums",
     }
 }
 
-/// PS3 controller button
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum Ps3Button {
-    Cross,
-    Circle,
-    Square,
-    Triangle,
-    L1,
-    L2,
-    L3,
-    R1,
-    R2,
-    R3,
-    Start,
-    Select,
-    PSButton,
-    DPadUp,
-    DPadDown,
-    DPadLeft,
-    DPadRight,
-}
-
-impl Ps3Button {
-    fn label(&self) -> &'static str {
-        match self {
-            Ps3Button::Cross => "Cross (✕)",
-            Ps3Button::Circle => "Circle (○)",
-            Ps3Button::Square => "Square (□)",
-            Ps3Button::Triangle => "Triangle (△)",
-            Ps3Button::L1 => "L1",
-            Ps3Button::L2 => "L2",
-            Ps3Button::L3 => "L3 (Left Stick)",
-            Ps3Button::R1 => "R1",
-            Ps3Button::R2 => "R2",
-            Ps3Button::R3 => "R3 (Right Stick)",
-            Ps3Button::Start => "Start",
-            Ps3Button::Select => "Select",
-            Ps3Button::PSButton => "PS Button",
-            Ps3Button::DPadUp => "D-Pad Up",
-            Ps3Button::DPadDown => "D-Pad Down",
-            Ps3Button::DPadLeft => "D-Pad Left",
-            Ps3Button::DPadRight => "D-Pad Right",
-        }
+/// Display label of a PS3 input
+fn input_label(input: Ps3Input) -> &'static str {
+    match input {
+        Ps3Input::PadButton(PadButtons::CROSS) => "Cross (✕)",
+        Ps3Input::PadButton(PadButtons::CIRCLE) => "Circle (○)",
+        Ps3Input::PadButton(PadButtons::SQUARE) => "Square (□)",
+        Ps3Input::PadButton(PadButtons::TRIANGLE) => "Triangle (△)",
+        Ps3Input::PadButton(PadButtons::L1) => "L1",
+        Ps3Input::PadButton(PadButtons::L2) => "L2",
+        Ps3Input::PadButton(PadButtons::L3) => "L3 (Left Stick)",
+        Ps3Input::PadButton(PadButtons::R1) => "R1",
+        Ps3Input::PadButton(PadButtons::R2) => "R2",
+        Ps3Input::PadButton(PadButtons::R3) => "R3 (Right Stick)",
+        Ps3Input::PadButton(PadButtons::START) => "Start",
+        Ps3Input::PadButton(PadButtons::SELECT) => "Select",
+        Ps3Input::PadButton(PadButtons::DPAD_UP) => "D-Pad Up",
+        Ps3Input::PadButton(PadButtons::DPAD_DOWN) => "D-Pad Down",
+        Ps3Input::PadButton(PadButtons::DPAD_LEFT) => "D-Pad Left",
+        Ps3Input::PadButton(PadButtons::DPAD_RIGHT) => "D-Pad Right",
+        Ps3Input::PadButton(_) => "Button",
+        Ps3Input::LeftAnalogX => "Left Stick X",
+        Ps3Input::LeftAnalogY => "Left Stick Y",
+        Ps3Input::RightAnalogX => "Right Stick X",
+        Ps3Input::RightAnalogY => "Right Stick Y",
     }
+}
 
-    fn all() -> &'static [Ps3Button] {
-        &[
-            Ps3Button::Cross,
-            Ps3Button::Circle,
-            Ps3Button::Square,
-            Ps3Button::Triangle,
-            Ps3Button::L1,
-            Ps3Button::L2,
-            Ps3Button::L3,
-            Ps3Button::R1,
-            Ps3Button::R2,
-            Ps3Button::R3,
-            Ps3Button::Start,
-            Ps3Button::Select,
-            Ps3Button::PSButton,
-            Ps3Button::DPadUp,
-            Ps3Button::DPadDown,
-            Ps3Button::DPadLeft,
-            Ps3Button::DPadRight,
-        ]
+/// Display label of a response curve
+fn curve_label(curve: ResponseCurve) -> &'static str {
+    match curve {
+        ResponseCurve::Linear => "Linear",
+        ResponseCurve::Quadratic => "Quadratic",
+        ResponseCurve::Cubic => "Cubic",
     }
 }
 
-/// PS3 controller axis
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum Ps3Axis {
-    LeftStickX,
-    LeftStickY,
-    RightStickX,
-    RightStickY,
+/// Display label of a mouse mode
+fn mouse_mode_label(mode: MouseMode) -> &'static str {
+    match mode {
+        MouseMode::Off => "Off",
+        MouseMode::RightStick => "Right Stick",
+        MouseMode::Sixaxis => "Sixaxis Tilt",
+    }
 }
 
-impl Ps3Axis {
-    fn label(&self) -> &'static str {
-        match self {
-            Ps3Axis::LeftStickX => "Left Stick X",
-            Ps3Axis::LeftStickY => "Left Stick Y",
-            Ps3Axis::RightStickX => "Right Stick X",
-            Ps3Axis::RightStickY => "Right Stick Y",
-        }
+fn move_source_label(source: MoveSource) -> &'static str {
+    match source {
+        MoveSource::Off => "Off",
+        MoveSource::Mouse => "Mouse",
+        MoveSource::Ds4 => "DualShock 4 / DualSense",
+        MoveSource::Ds4Pointer => "DualShock 4 / DualSense (pointer)",
     }
+}
 
-    fn all() -> &'static [Ps3Axis] {
-        &[
-            Ps3Axis::LeftStickX,
-            Ps3Axis::LeftStickY,
-            Ps3Axis::RightStickX,
-            Ps3Axis::RightStickY,
-        ]
+/// Left stick key being edited
+fn stick_key_mut(config: &mut KeyboardPadConfig, index: usize) -> &mut String {
+    match index {
+        0 => &mut config.left_stick_up,
+        1 => &mut config.left_stick_down,
+        2 => &mut config.left_stick_left,
+        _ => &mut config.left_stick_right,
     }
 }
 
-/// Button mapping (gamepad button index to PS3 button)
-#[derive(Debug, Clone)]
-pub struct ButtonMapping {
-    /// Gamepad button index
-    pub gamepad_button: u32,
-    /// PS3 button
-    pub ps3_button: Ps3Button,
-}
+/// Labels of the left stick keys, in `stick_key_mut` order
+const STICK_KEY_LABELS: [&str; 4] = ["Left Stick Up", "Left Stick Down", "Left Stick Left", "Left Stick Right"];
 
-/// Axis mapping (gamepad axis index to PS3 axis)
-#[derive(Debug, Clone)]
-pub struct AxisMapping {
-    /// Gamepad axis index
-    pub gamepad_axis: u32,
-    /// PS3 axis
-    pub ps3_axis: Ps3Axis,
-    /// Invert axis
-    pub inverted: bool,
-    /// Dead zone (0.0 to 1.0)
-    pub deadzone: f32,
-}
+/// Labels of the pressure-sensitive buttons, in pressure array order
+const PRESSURE_LABELS: [&str; 12] = [
+    "Up", "Right", "Down", "Left", "L2", "R2", "L1", "R1", "Triangle", "Circle", "Cross", "Square",
+];
 
-/// Controller profile
-#[derive(Debug, Clone)]
-pub struct ControllerProfile {
-    /// Profile name
-    pub name: String,
-    /// Controller type
-    pub controller_type: ControllerType,
-    /// Button mappings
-    pub button_mappings: HashMap<Ps3Button, u32>,
-    /// Axis mappings
-    pub axis_mappings: HashMap<Ps3Axis, (u32, bool, f32)>, // (index, inverted, deadzone)
-    /// Vibration enabled
-    pub vibration_enabled: bool,
-    /// Vibration strength (0.0 to 1.0)
-    pub vibration_strength: f32,
+/// Live input of the port shown in the test view
+#[derive(Debug, Clone, Default)]
+pub struct PadTestInput {
+    /// Pad state cellPad sees (None = nothing connected)
+    pub state: Option<PadState>,
+    /// Motion sensor state, with the calibrated drift removed
+    pub motion: Option<SixaxisData>,
+    /// Stick axes before the dead zone (left X/Y, right X/Y, up positive), for generic gamepads
+    pub raw_sticks: Option<[f32; 4]>,
 }
 
-impl Default for ControllerProfile {
-    fn default() -> Self {
-        let mut button_mappings = HashMap::new();
-        // Default Xbox-style mapping
-        button_mappings.insert(Ps3Button::Cross, 0);      // A
-        button_mappings.insert(Ps3Button::Circle, 1);     // B
-        button_mappings.insert(Ps3Button::Square, 2);     // X
-        button_mappings.insert(Ps3Button::Triangle, 3);   // Y
-        button_mappings.insert(Ps3Button::L1, 4);         // LB
-        button_mappings.insert(Ps3Button::R1, 5);         // RB
-        button_mappings.insert(Ps3Button::Select, 6);     // Back
-        button_mappings.insert(Ps3Button::Start, 7);      // Start
-        button_mappings.insert(Ps3Button::L3, 8);         // Left Stick Click
-        button_mappings.insert(Ps3Button::R3, 9);         // Right Stick Click
-        button_mappings.insert(Ps3Button::DPadUp, 10);
-        button_mappings.insert(Ps3Button::DPadDown, 11);
-        button_mappings.insert(Ps3Button::DPadLeft, 12);
-        button_mappings.insert(Ps3Button::DPadRight, 13);
-        button_mappings.insert(Ps3Button::PSButton, 14);  // Guide
-
-        let mut axis_mappings = HashMap::new();
-        axis_mappings.insert(Ps3Axis::LeftStickX, (0, false, 0.1));
-        axis_mappings.insert(Ps3Axis::LeftStickY, (1, false, 0.1));
-        axis_mappings.insert(Ps3Axis::RightStickX, (2, false, 0.1));
-        axis_mappings.insert(Ps3Axis::RightStickY, (3, false, 0.1));
-
-        Self {
-            name: String::from("Default"),
-            controller_type: ControllerType::Generic,
-            button_mappings,
-            axis_mappings,
-            vibration_enabled: true,
-            vibration_strength: 1.0,
-        }
-    }
+/// Rest calibration in progress
+struct Calibration {
+    port: u8,
+    started: Instant,
+    samples: RestCalibration,
 }
 
-/// Connected controller info
-#[derive(Debug, Clone)]
-pub struct ConnectedController {
-    /// Controller index
-    pub index: usize,
-    /// Controller name
-    pub name: String,
-    /// Is connected
-    pub connected: bool,
-    /// Current button states
-    pub buttons: Vec<bool>,
-    /// Current axis values
-    pub axes: Vec<f32>,
+/// Stick position from PS3 axis bytes, as (x, y) from -1.0 to 1.0 with up positive
+fn stick_from_bytes(x: u8, y: u8) -> (f32, f32) {
+    ((x as f32 - 127.5) / 127.5, (127.5 - y as f32) / 127.5)
 }
 
 /// Controller configuration panel
 pub struct ControllerConfig {
-    /// Current port being configured (0-7 for PS3)
-    current_port: usize,
-    /// Controller profiles per port
-    profiles: [ControllerProfile; 8],
-    /// Connected controllers (detected from system)
-    connected_controllers: Vec<ConnectedController>,
-    /// Currently binding button (waiting for input)
-    binding_button: Option<Ps3Button>,
-    /// Currently binding axis
-    binding_axis: Option<Ps3Axis>,
-    /// Show advanced settings
-    _show_advanced: bool,
+    /// Profile being edited (index into `InputConfig::profiles`, None = built-in standard)
+    selected: Option<usize>,
+    /// PS3 input waiting for a physical input
+    binding: Option<Ps3Input>,
+    /// Left stick key waiting for a key press
+    binding_key: Option<usize>,
+    /// Title ID typed into the game assignment field
+    new_title_id: String,
+    /// MIDI input devices found by the last refresh
+    midi_devices: Vec<String>,
+    /// Instrument whose bindings are shown
+    instrument_tab: InstrumentKind,
+    /// Webcams found by the last refresh
+    webcams: Vec<String>,
+    /// Port shown in the test view
+    test_port: u8,
+    /// Rest calibration in progress
+    calibration: Option<Calibration>,
     /// Status message
     status_message: String,
-    /// Test mode active
-    test_mode: bool,
 }
 
 impl ControllerConfig {
     /// Create a new controller configuration panel
     pub fn new() -> Self {
         Self {
-            current_port: 0,
-            profiles: std::array::from_fn(|_| ControllerProfile::default()),
-            connected_controllers: Vec::new(),
-            binding_button: None,
-            binding_axis: None,
-            _show_advanced: false,
+            selected: None,
+            binding: None,
+            binding_key: None,
+            new_title_id: String::new(),
+            midi_devices: Vec::new(),
+            instrument_tab: InstrumentKind::Guitar,
+            webcams: Vec::new(),
+            test_port: 0,
+            calibration: None,
             status_message: String::from("Controller configuration ready"),
-            test_mode: false,
         }
     }
 
-    /// Refresh connected controllers
-    pub fn refresh_controllers(&mut self) {
-        // In a real implementation, this would query the system for connected controllers
-        // For now, add a mock controller
-        self.connected_controllers = vec![
-            ConnectedController {
-                index: 0,
-                name: String::from("Xbox Controller"),
-                connected: true,
-                buttons: vec![false; MAX_BUTTONS],
-                axes: vec![0.0; MAX_AXES],
-            },
-        ];
-        self.status_message = format!("Found {} controller(s)", self.connected_controllers.len());
+    /// Whether the panel is waiting for a physical controller input
+    pub fn is_binding(&self) -> bool {
+        self.binding.is_some()
     }
 
-    /// Get profile for port
-    pub fn get_profile(&self, port: usize) -> Option<&ControllerProfile> {
-        self.profiles.get(port)
-    }
-
-    /// Get mutable profile for port
-    pub fn get_profile_mut(&mut self, port: usize) -> Option<&mut ControllerProfile> {
-        self.profiles.get_mut(port)
+    /// Port whose live input the test view shows
+    pub fn test_port(&self) -> u8 {
+        self.test_port
     }
 
     /// Show the controller configuration panel
-    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+    ///
+    /// `captured` is the latest physical input from the host controllers,
+    /// used while a binding is in progress, and `test` the live input of
+    /// [`test_port`](Self::test_port). Returns true if the configuration
+    /// changed.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        config: &mut InputConfig,
+        devices: &[HostGamepadInfo],
+        captured: Option<HostInput>,
+        test: &PadTestInput,
+    ) -> bool {
         let mut changed = false;
 
+        if self.selected.is_some_and(|i| i >= config.profiles.len()) {
+            self.selected = None;
+            self.binding = None;
+        }
+
         ui.heading("Controller Configuration");
         ui.add_space(5.0);
 
-        // Port selector
-        ui.horizontal(|ui| {
-            ui.label("Port:");
-            for port in 0..4 {
-                let label = format!("Player {}", port + 1);
-                if ui.selectable_label(self.current_port == port, &label).clicked() {
-                    self.current_port = port;
+        // Connected controllers list
+        ui.collapsing("Connected Controllers", |ui| {
+            if devices.is_empty() {
+                ui.label("No controllers detected.");
+            } else {
+                for device in devices {
+                    let mapping = if device.has_mapping { "" } else { " (no layout mapping)" };
+                    ui.label(format!("🟢 Port {}: {}{}", device.port + 1, device.name, mapping));
                 }
             }
+        });
+
+        changed |= Self::show_ports(ui, config, devices);
+
+        changed |= self.show_pad_test(ui, config, test);
+
+        ui.separator();
 
-            ui.separator();
+        changed |= self.show_profile_selector(ui, config);
 
-            if ui.button("🔄 Refresh Controllers").clicked() {
-                self.refresh_controllers();
+        ui.add_space(10.0);
+
+        changed |= self.show_bindings(ui, config, captured);
+
+        ui.add_space(5.0);
+
+        if let Some(index) = self.selected {
+            let profile = &mut config.profiles[index];
+            ui.collapsing("Analog Sticks", |ui| {
+                changed |= Self::show_stick_response(ui, "Left Stick", "left_stick", &mut profile.left_stick);
+                ui.add_space(5.0);
+                changed |= Self::show_stick_response(ui, "Right Stick", "right_stick", &mut profile.right_stick);
+            });
+            ui.collapsing("Light-Gun Pointer", |ui| {
+                changed |= Self::show_pointer_response(ui, &mut profile.pointer);
+            });
+            ui.add_space(5.0);
+        }
+
+        changed |= self.show_game_profiles(ui, config);
+
+        ui.add_space(5.0);
+
+        changed |= self.show_keyboard_pad(ui, &mut config.keyboard_pad);
+
+        ui.add_space(5.0);
+
+        changed |= self.show_instruments(ui, &mut config.instruments);
+
+        ui.add_space(5.0);
+
+        changed |= Self::show_move(ui, &mut config.move_controller);
+
+        ui.add_space(5.0);
+
+        changed |= self.show_camera(ui, &mut config.camera);
+
+        // Status bar
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new(&self.status_message).small());
+        });
+
+        changed
+    }
+
+    /// Port assignment matrix and per-port profiles
+    fn show_ports(ui: &mut egui::Ui, config: &mut InputConfig, devices: &[HostGamepadInfo]) -> bool {
+        let mut changed = false;
+
+        ui.collapsing("Port Assignment", |ui| {
+            ui.label("Pin controllers to ports so each player keeps theirs. Changes reconnect every controller.");
+
+            // Connected and pinned devices, each listed once
+            let mut names: Vec<String> = Vec::new();
+            let pinned = config.controller.ports.iter().map(|port| &port.device);
+            for name in devices.iter().map(|device| &device.name).chain(pinned) {
+                if !name.is_empty() && !names.iter().any(|known| known.eq_ignore_ascii_case(name)) {
+                    names.push(name.clone());
+                }
             }
+            let occupant = |port: usize| devices.iter().find(|device| device.port as usize == port);
+            let mut ports = config.controller.ports.clone();
+            ports.resize(MAX_PADS, PadPortConfig::default());
 
-            ui.checkbox(&mut self.test_mode, "Test Mode");
+            egui::Grid::new("pad_ports")
+                .num_columns(MAX_PADS + 1)
+                .striped(true)
+                .spacing([12.0, 4.0])
+                .show(ui, |ui| {
+                    ui.label("");
+                    for port in 0..MAX_PADS {
+                        ui.label(format!("Port {}", port + 1));
+                    }
+                    ui.end_row();
+
+                    ui.label("Any controller");
+                    for port in &mut ports {
+                        if ui.selectable_label(port.device.is_empty(), "").clicked() && !port.device.is_empty() {
+                            port.device.clear();
+                            changed = true;
+                        }
+                    }
+                    ui.end_row();
+
+                    for name in &names {
+                        ui.label(name);
+                        for (index, port) in ports.iter_mut().enumerate() {
+                            let here = occupant(index).is_some_and(|device| &device.name == name);
+                            let selected = port.device.eq_ignore_ascii_case(name);
+                            let response = ui
+                                .selectable_label(selected, if here { "🎮" } else { "" })
+                                .on_hover_text(if here { "Connected on this port" } else { "Pin to this port" });
+                            if response.clicked() && !selected {
+                                port.device = name.clone();
+                                changed = true;
+                            }
+                        }
+                        ui.end_row();
+                    }
+
+                    ui.label("Profile");
+                    for (index, port) in ports.iter_mut().enumerate() {
+                        let label = if port.profile.is_empty() { "Game" } else { port.profile.as_str() };
+                        egui::ComboBox::from_id_salt(("port_profile", index))
+                            .selected_text(label)
+                            .width(80.0)
+                            .show_ui(ui, |ui| {
+                                let names = std::iter::once(InputProfile::STANDARD)
+                                    .chain(config.profiles.iter().map(|profile| profile.name.as_str()));
+                                if ui.selectable_label(port.profile.is_empty(), "Game").clicked() {
+                                    port.profile.clear();
+                                    changed = true;
+                                }
+                                for name in names {
+                                    if ui.selectable_label(port.profile == name, name).clicked() {
+                                        port.profile = name.to_string();
+                                        changed = true;
+                                    }
+                                }
+                            });
+                    }
+                    ui.end_row();
+
+                    ui.label("Play as");
+                    for (index, port) in ports.iter_mut().enumerate() {
+                        egui::ComboBox::from_id_salt(("port_instrument", index))
+                            .selected_text(instrument_label(port.instrument))
+                            .width(80.0)
+                            .show_ui(ui, |ui| {
+                                for kind in [InstrumentKind::None, InstrumentKind::Guitar, InstrumentKind::Drums] {
+                                    changed |= ui
+                                        .selectable_value(&mut port.instrument, kind, instrument_label(kind))
+                                        .changed();
+                                }
+                            });
+                    }
+                    ui.end_row();
+                });
+            ui.label(
+                egui::RichText::new(
+                    "Port profiles and instruments apply to generic gamepads; \"Game\" uses the per-game profile.",
+                )
+                .small(),
+            );
+
+            if changed {
+                // Ports past the last customized one take any controller
+                while ports.last().is_some_and(|port| *port == PadPortConfig::default()) {
+                    ports.pop();
+                }
+                config.controller.ports = ports;
+            }
         });
 
-        ui.separator();
+        changed
+    }
 
-        // Connected controllers list
-        ui.collapsing("Connected Controllers", |ui| {
-            if self.connected_controllers.is_empty() {
-                ui.label("No controllers detected.");
-                ui.label("Click 'Refresh Controllers' to scan.");
-            } else {
-                for controller in &self.connected_controllers {
+    /// Live view of one port's pad, with the rest calibration
+    fn show_pad_test(&mut self, ui: &mut egui::Ui, config: &mut InputConfig, test: &PadTestInput) -> bool {
+        let mut changed = false;
+
+        ui.collapsing("Test & Calibrate", |ui| {
+            // Keep the view live while it is open
+            ui.ctx().request_repaint();
+
+            ui.horizontal(|ui| {
+                ui.label("Port:");
+                egui::ComboBox::from_id_salt("test_port")
+                    .selected_text(format!("Port {}", self.test_port + 1))
+                    .show_ui(ui, |ui| {
+                        for port in 0..MAX_PADS as u8 {
+                            ui.selectable_value(&mut self.test_port, port, format!("Port {}", port + 1));
+                        }
+                    });
+            });
+            if self.calibration.as_ref().is_some_and(|c| c.port != self.test_port) {
+                self.calibration = None;
+            }
+
+            let Some(state) = test.state.as_ref() else {
+                ui.label("No controller on this port.");
+                self.calibration = None;
+                return;
+            };
+            let profile = Self::calibration_profile(config, self.test_port, self.selected);
+            let deadzones = profile.map_or([0.0; 2], |index| {
+                let profile = &config.profiles[index];
+                [profile.left_stick.deadzone, profile.right_stick.deadzone]
+            });
+
+            ui.horizontal(|ui| {
+                Self::draw_pad(ui, state, test.raw_sticks, deadzones);
+                ui.vertical(|ui| {
+                    ui.label(egui::RichText::new("Pressure").strong());
+                    egui::Grid::new("test_pressure").num_columns(2).spacing([8.0, 2.0]).show(ui, |ui| {
+                        for (label, pressure) in PRESSURE_LABELS.iter().zip(state.pressure) {
+                            ui.label(egui::RichText::new(*label).small());
+                            ui.add(
+                                egui::ProgressBar::new(pressure as f32 / 255.0)
+                                    .text(pressure.to_string())
+                                    .desired_width(90.0),
+                            );
+                            ui.end_row();
+                        }
+                    });
+                });
+                if let Some(ref motion) = test.motion {
+                    ui.vertical(|ui| {
+                        ui.label(egui::RichText::new("Sixaxis").strong());
+                        Self::draw_motion(ui, motion);
+                    });
+                }
+            });
+            if test.raw_sticks.is_some() {
+                ui.label(
+                    egui::RichText::new("Hollow dots are the raw stick positions, the inner rings the dead zones.")
+                        .small(),
+                );
+            }
+
+            ui.add_space(5.0);
+            let mut cancel = false;
+            match self.calibration {
+                Some(ref mut calibration) => {
+                    match test.raw_sticks {
+                        Some([lx, ly, rx, ry]) => calibration.samples.add_sticks((lx, ly), (rx, ry)),
+                        None => calibration.samples.add_sticks(
+                            stick_from_bytes(state.left_x, state.left_y),
+                            stick_from_bytes(state.right_x, state.right_y),
+                        ),
+                    }
+                    if let Some(ref motion) = test.motion {
+                        calibration.samples.add_motion(motion);
+                    }
+                    let fraction = calibration.started.elapsed().as_secs_f32() / CALIBRATION_SECONDS;
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::ProgressBar::new(fraction.min(1.0))
+                                .text("Leave the controller flat and the sticks untouched...")
+                                .desired_width(320.0),
+                        );
+                        cancel = ui.button("Cancel").clicked();
+                    });
+                    if fraction >= 1.0 {
+                        if let Some(calibration) = self.calibration.take() {
+                            changed |= self.finish_calibration(config, profile, &calibration);
+                        }
+                    }
+                }
+                None => {
                     ui.horizontal(|ui| {
-                        let status = if controller.connected { "🟢" } else { "🔴" };
-                        ui.label(format!("{} {} ({})", status, controller.name, controller.index));
+                        if ui
+                            .button("🎯 Calibrate")
+                            .on_hover_text("Measure stick drift and gyroscope drift with the controller at rest")
+                            .clicked()
+                        {
+                            self.calibration = Some(Calibration {
+                                port: self.test_port,
+                                started: Instant::now(),
+                                samples: RestCalibration::new(),
+                            });
+                        }
+                        let offset = config.controller.gyro_offsets.get(self.test_port as usize).copied();
+                        if offset.is_some_and(|offset| offset != [0; 3]) && ui.button("Reset Gyro Drift").clicked() {
+                            config.controller.gyro_offsets[self.test_port as usize] = [0; 3];
+                            Self::trim_gyro_offsets(config);
+                            changed = true;
+                        }
                     });
+                    match profile {
+                        Some(index) => ui.label(
+                            egui::RichText::new(format!(
+                                "Dead zones are stored in profile \"{}\".",
+                                config.profiles[index].name
+                            ))
+                            .small(),
+                        ),
+                        None => ui.label(
+                            egui::RichText::new(
+                                "Select or assign a custom profile to store calibrated dead zones; \
+                                 only gyroscope drift is calibrated for the standard profile.",
+                            )
+                            .small(),
+                        ),
+                    };
                 }
             }
+            if cancel {
+                self.calibration = None;
+            }
         });
 
-        ui.separator();
+        changed
+    }
+
+    /// Profile a port's calibrated dead zones go to: its own profile,
+    /// else the one being edited (None = the built-in standard profile)
+    fn calibration_profile(config: &InputConfig, port: u8, selected: Option<usize>) -> Option<usize> {
+        let name = config.controller.ports.get(port as usize).map_or("", |port| port.profile.trim());
+        if name.is_empty() {
+            return selected;
+        }
+        config.profiles.iter().position(|profile| profile.name == name)
+    }
+
+    /// Store the results of a finished calibration
+    fn finish_calibration(&mut self, config: &mut InputConfig, profile: Option<usize>, calibration: &Calibration) -> bool {
+        let mut changed = false;
+        let mut results = Vec::new();
+
+        if let (Some(index), Some([left, right])) = (profile, calibration.samples.deadzones()) {
+            let profile = &mut config.profiles[index];
+            profile.left_stick.deadzone = left;
+            profile.right_stick.deadzone = right;
+            results.push(format!("dead zones {:.2} / {:.2}", left, right));
+            changed = true;
+        }
+        if let Some(drift) = calibration.samples.gyro_drift() {
+            let port = calibration.port as usize;
+            let offsets = &mut config.controller.gyro_offsets;
+            if offsets.len() <= port {
+                offsets.resize(port + 1, [0; 3]);
+            }
+            // Readings already have the previous offset removed
+            for (offset, drift) in offsets[port].iter_mut().zip(drift) {
+                *offset = offset.saturating_add(drift);
+            }
+            Self::trim_gyro_offsets(config);
+            results.push(format!("gyro drift {:?}", drift));
+            changed |= drift != [0; 3];
+        }
 
-        // Current port configuration
-        let profile = &mut self.profiles[self.current_port];
+        self.status_message = if results.is_empty() {
+            format!("Port {}: nothing to calibrate", calibration.port + 1)
+        } else {
+            format!("Port {} calibrated: {}", calibration.port + 1, results.join(", "))
+        };
+        changed
+    }
+
+    /// Drop the offsets of trailing ports without drift
+    fn trim_gyro_offsets(config: &mut InputConfig) {
+        let offsets = &mut config.controller.gyro_offsets;
+        while offsets.last() == Some(&[0; 3]) {
+            offsets.pop();
+        }
+    }
+
+    /// Outline of a PS3 pad with the pressed buttons lit and both sticks
+    fn draw_pad(ui: &mut egui::Ui, state: &PadState, raw_sticks: Option<[f32; 4]>, deadzones: [f32; 2]) {
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(340.0, 200.0), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        let visuals = ui.visuals();
+        let idle = visuals.widgets.inactive.bg_fill;
+        let lit = visuals.selection.bg_fill;
+        let stroke = visuals.widgets.noninteractive.fg_stroke;
+        let text_color = visuals.strong_text_color();
+        let at = |x: f32, y: f32| rect.min + egui::vec2(x * rect.width(), y * rect.height());
+        // Pressure lights a button gradually, before it counts as pressed
+        let fill = |button: PadButtons| {
+            if state.is_button_pressed(button) {
+                return lit;
+            }
+            let pressure = button.pressure_index().map_or(0, |index| state.pressure[index]);
+            idle.lerp_to_gamma(lit, pressure as f32 / 255.0 * 0.6)
+        };
+        let label = |pos: egui::Pos2, text: &str| {
+            painter.text(pos, egui::Align2::CENTER_CENTER, text, egui::FontId::proportional(11.0), text_color);
+        };
+
+        painter.rect_filled(rect, 40.0, visuals.extreme_bg_color);
+
+        // Shoulder buttons
+        for (x, y, button, name) in [
+            (0.18, 0.05, PadButtons::L2, "L2"),
+            (0.18, 0.14, PadButtons::L1, "L1"),
+            (0.82, 0.05, PadButtons::R2, "R2"),
+            (0.82, 0.14, PadButtons::R1, "R1"),
+        ] {
+            let button_rect = egui::Rect::from_center_size(at(x, y), egui::vec2(44.0, 14.0));
+            painter.rect_filled(button_rect, 4.0, fill(button));
+            label(button_rect.center(), name);
+        }
+
+        // D-pad
+        for (dx, dy, button) in [
+            (0.0, -1.0, PadButtons::DPAD_UP),
+            (1.0, 0.0, PadButtons::DPAD_RIGHT),
+            (0.0, 1.0, PadButtons::DPAD_DOWN),
+            (-1.0, 0.0, PadButtons::DPAD_LEFT),
+        ] {
+            let center = at(0.18, 0.4) + egui::vec2(dx, dy) * 20.0;
+            painter.rect_filled(egui::Rect::from_center_size(center, egui::vec2(18.0, 18.0)), 3.0, fill(button));
+        }
+
+        // Face buttons
+        for (dx, dy, button, name) in [
+            (0.0, -1.0, PadButtons::TRIANGLE, "△"),
+            (1.0, 0.0, PadButtons::CIRCLE, "○"),
+            (0.0, 1.0, PadButtons::CROSS, "✕"),
+            (-1.0, 0.0, PadButtons::SQUARE, "□"),
+        ] {
+            let center = at(0.82, 0.4) + egui::vec2(dx, dy) * 22.0;
+            painter.circle_filled(center, 10.0, fill(button));
+            label(center, name);
+        }
+
+        // Select and Start
+        for (x, button, name) in [(0.42, PadButtons::SELECT, "SELECT"), (0.58, PadButtons::START, "START")] {
+            let button_rect = egui::Rect::from_center_size(at(x, 0.4), egui::vec2(40.0, 12.0));
+            painter.rect_filled(button_rect, 6.0, fill(button));
+            label(button_rect.center() + egui::vec2(0.0, 14.0), name);
+        }
+
+        // Sticks: the dot is what the game sees, the hollow dot the raw position
+        let sticks = [
+            (0.35, PadButtons::L3, stick_from_bytes(state.left_x, state.left_y), 0),
+            (0.65, PadButtons::R3, stick_from_bytes(state.right_x, state.right_y), 1),
+        ];
+        for (x, button, (sx, sy), index) in sticks {
+            let center = at(x, 0.72);
+            let radius = 30.0;
+            painter.circle(center, radius, fill(button), stroke);
+            if deadzones[index] > 0.0 {
+                painter.circle_stroke(center, radius * deadzones[index], egui::Stroke::new(1.0, visuals.weak_text_color()));
+            }
+            if let Some(raw) = raw_sticks {
+                let raw_pos = center + egui::vec2(raw[index * 2], -raw[index * 2 + 1]) * radius;
+                painter.circle_stroke(raw_pos, 5.0, egui::Stroke::new(1.5, text_color));
+            }
+            painter.circle_filled(center + egui::vec2(sx, -sy) * radius, 5.0, text_color);
+        }
+    }
+
+    /// Tilt shown as an artificial horizon, with the raw sensor values
+    fn draw_motion(ui: &mut egui::Ui, motion: &SixaxisData) {
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(100.0, 100.0), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        let visuals = ui.visuals();
+        let center = rect.center();
+        let radius = rect.width() / 2.0 - 2.0;
+
+        let (x, y, z) = (motion.accel_x as f32, motion.accel_y as f32, motion.accel_z as f32);
+        let roll = x.atan2(z);
+        let pitch = y.atan2(z);
+        painter.circle_filled(center, radius, visuals.extreme_bg_color);
+        // The horizon tilts with roll and moves up and down with pitch
+        let offset = egui::vec2(0.0, (pitch / std::f32::consts::FRAC_PI_2).clamp(-1.0, 1.0) * radius);
+        let direction = egui::vec2(roll.cos(), -roll.sin()) * radius;
+        painter.line_segment(
+            [center + offset - direction, center + offset + direction],
+            egui::Stroke::new(2.0, visuals.selection.bg_fill),
+        );
+        painter.circle_stroke(center, radius, visuals.widgets.noninteractive.fg_stroke);
+
+        ui.label(
+            egui::RichText::new(format!("Roll {:+.0}°  Pitch {:+.0}°", roll.to_degrees(), pitch.to_degrees())).small(),
+        );
+        ui.label(
+            egui::RichText::new(format!("Accel {} {} {}", motion.accel_x, motion.accel_y, motion.accel_z)).small(),
+        );
+        ui.label(egui::RichText::new(format!("Gyro {} {} {}", motion.gyro_x, motion.gyro_y, motion.gyro_z)).small());
+    }
+
+    /// Profile combo box with new (copying the current profile), delete and rename
+    fn show_profile_selector(&mut self, ui: &mut egui::Ui, config: &mut InputConfig) -> bool {
+        let mut changed = false;
 
         ui.horizontal(|ui| {
-            ui.label("Controller Type:");
-            egui::ComboBox::from_id_salt("controller_type")
-                .selected_text(profile.controller_type.label())
+            ui.label("Profile:");
+            let selected_name = match self.selected {
+                Some(i) => config.profiles[i].name.clone(),
+                None => InputProfile::STANDARD.to_string(),
+            };
+            egui::ComboBox::from_id_salt("input_profile")
+                .selected_text(selected_name)
                 .show_ui(ui, |ui| {
-                    for ct in ControllerType::all() {
-                        if ui.selectable_value(&mut profile.controller_type, *ct, ct.label()).changed() {
-                            changed = true;
+                    if ui.selectable_label(self.selected.is_none(), InputProfile::STANDARD).clicked() {
+                        self.selected = None;
+                        self.binding = None;
+                    }
+                    for (i, profile) in config.profiles.iter().enumerate() {
+                        if ui.selectable_label(self.selected == Some(i), &profile.name).clicked() {
+                            self.selected = Some(i);
+                            self.binding = None;
                         }
                     }
                 });
+
+            if ui.button("➕ New").clicked() {
+                let mut profile = match self.selected {
+                    Some(i) => config.profiles[i].clone(),
+                    None => InputProfileConfig::default(),
+                };
+                profile.name = Self::unique_name(config, "Profile");
+                self.status_message = format!("Created profile \"{}\"", profile.name);
+                config.profiles.push(profile);
+                self.selected = Some(config.profiles.len() - 1);
+                changed = true;
+            }
+
+            if let Some(index) = self.selected {
+                if ui.button("🗑 Delete").clicked() {
+                    let removed = config.profiles.remove(index);
+                    config.game_profiles.retain(|_, name| *name != removed.name);
+                    for port in &mut config.controller.ports {
+                        if port.profile == removed.name {
+                            port.profile.clear();
+                        }
+                    }
+                    self.status_message = format!("Deleted profile \"{}\"", removed.name);
+                    self.selected = None;
+                    self.binding = None;
+                    changed = true;
+                }
+            }
         });
 
-        ui.add_space(10.0);
+        if let Some(index) = self.selected {
+            ui.horizontal(|ui| {
+                ui.label("Name:");
+                let mut name = config.profiles[index].name.clone();
+                if ui.text_edit_singleline(&mut name).changed() {
+                    let name_taken = name == InputProfile::STANDARD
+                        || config.profiles.iter().any(|profile| profile.name == name);
+                    if !name.trim().is_empty() && !name_taken {
+                        let old = std::mem::replace(&mut config.profiles[index].name, name.clone());
+                        let ports = config.controller.ports.iter_mut().map(|port| &mut port.profile);
+                        for assigned in config.game_profiles.values_mut().chain(ports) {
+                            if *assigned == old {
+                                *assigned = name.clone();
+                            }
+                        }
+                        changed = true;
+                    }
+                }
+                if ui.button("Reset to Standard").clicked() {
+                    config.profiles[index].bindings.clear();
+                    self.status_message = String::from("Bindings reset to the standard layout");
+                    changed = true;
+                }
+            });
+        } else {
+            ui.label(
+                egui::RichText::new("The standard layout is built in. Create a profile to customize it.")
+                    .small(),
+            );
+        }
+
+        changed
+    }
 
-        // Button mappings
-        ui.collapsing("Button Mappings", |ui| {
-            egui::Grid::new("button_mappings")
+    /// Binding table for the selected profile
+    fn show_bindings(&mut self, ui: &mut egui::Ui, config: &mut InputConfig, captured: Option<HostInput>) -> bool {
+        let mut changed = false;
+
+        // Finish a pending binding with the captured input
+        if let (Some(target), Some(host), Some(index)) = (self.binding, captured, self.selected) {
+            config.profiles[index]
+                .bindings
+                .insert(target.name().to_string(), host.to_string());
+            self.status_message = format!("Bound {} to {}", input_label(target), host);
+            self.binding = None;
+            changed = true;
+        }
+        if self.binding.is_some() {
+            if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                self.binding = None;
+                self.status_message = String::from("Binding cancelled");
+            } else {
+                // Keep polling for controller input
+                ui.ctx().request_repaint();
+            }
+        }
+
+        let profile = match self.selected {
+            Some(index) => InputProfile::from_config(&config.profiles[index]),
+            None => InputProfile::standard(),
+        };
+
+        ui.collapsing("Bindings", |ui| {
+            egui::Grid::new("input_bindings")
                 .num_columns(3)
                 .striped(true)
                 .spacing([20.0, 4.0])
                 .show(ui, |ui| {
-                    ui.strong("PS3 Button");
+                    ui.strong("PS3 Input");
                     ui.strong("Mapped To");
                     ui.strong("Action");
                     ui.end_row();
 
-                    for button in Ps3Button::all() {
-                        ui.label(button.label());
+                    for target in Ps3Input::all() {
+                        ui.label(input_label(target));
 
-                        let mapped = profile.button_mappings.get(button).copied();
-                        let mapping_text = match mapped {
-                            Some(idx) => format!("Button {}", idx),
-                            None => "Not mapped".to_string(),
-                        };
-
-                        // Check if we're currently binding this button
-                        if self.binding_button == Some(*button) {
-                            ui.label(egui::RichText::new("Press button...").color(egui::Color32::YELLOW));
+                        if self.binding == Some(target) {
+                            let prompt = if target.is_axis() { "Move an axis..." } else { "Press a button..." };
+                            ui.label(egui::RichText::new(prompt).color(egui::Color32::YELLOW));
                             if ui.button("Cancel").clicked() {
-                                self.binding_button = None;
+                                self.binding = None;
                             }
                         } else {
-                            ui.label(&mapping_text);
-                            if ui.button("Bind").clicked() {
-                                self.binding_button = Some(*button);
-                                self.status_message = format!("Press a button for {}", button.label());
+                            let mapped = profile.mapping.host_input_for(target);
+                            ui.label(mapped.map_or_else(|| "Not mapped".to_string(), |host| host.to_string()));
+                            if let Some(index) = self.selected {
+                                ui.horizontal(|ui| {
+                                    if ui.button("Bind").clicked() {
+                                        self.binding = Some(target);
+                                        self.status_message = format!("Waiting for input for {}", input_label(target));
+                                    }
+                                    if mapped.is_some() && ui.button("Clear").clicked() {
+                                        config.profiles[index]
+                                            .bindings
+                                            .insert(target.name().to_string(), "none".to_string());
+                                        changed = true;
+                                    }
+                                });
                             }
                         }
 
@@ -393,120 +845,481 @@ impl ControllerConfig {
                 });
         });
 
-        ui.add_space(5.0);
-
-        // Axis mappings
-        ui.collapsing("Axis Mappings", |ui| {
-            egui::Grid::new("axis_mappings")
-                .num_columns(5)
-                .striped(true)
-                .spacing([15.0, 4.0])
-                .show(ui, |ui| {
-                    ui.strong("PS3 Axis");
-                    ui.strong("Mapped To");
-                    ui.strong("Inverted");
-                    ui.strong("Deadzone");
-                    ui.strong("Action");
-                    ui.end_row();
+        changed
+    }
 
-                    for axis in Ps3Axis::all() {
-                        ui.label(axis.label());
+    /// Dead zone, sensitivity, curve and inversion for one stick
+    fn show_stick_response(ui: &mut egui::Ui, label: &str, id: &str, response: &mut StickResponse) -> bool {
+        let mut changed = false;
 
-                        let (idx, inverted, deadzone) = profile.axis_mappings
-                            .get(axis)
-                            .copied()
-                            .unwrap_or((0, false, 0.1));
+        ui.label(egui::RichText::new(label).strong());
+        egui::Grid::new(id)
+            .num_columns(2)
+            .spacing([40.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("Dead zone:");
+                changed |= ui.add(egui::Slider::new(&mut response.deadzone, 0.0..=0.5)).changed();
+                ui.end_row();
 
-                        ui.label(format!("Axis {}", idx));
+                ui.label("Sensitivity:");
+                changed |= ui.add(egui::Slider::new(&mut response.sensitivity, 0.5..=2.0)).changed();
+                ui.end_row();
 
-                        let mut inv = inverted;
-                        if ui.checkbox(&mut inv, "").changed() {
-                            profile.axis_mappings.insert(*axis, (idx, inv, deadzone));
-                            changed = true;
+                ui.label("Curve:");
+                egui::ComboBox::from_id_salt((id, "curve"))
+                    .selected_text(curve_label(response.curve))
+                    .show_ui(ui, |ui| {
+                        for curve in [ResponseCurve::Linear, ResponseCurve::Quadratic, ResponseCurve::Cubic] {
+                            changed |= ui.selectable_value(&mut response.curve, curve, curve_label(curve)).changed();
                         }
+                    });
+                ui.end_row();
+
+                ui.label("Invert:");
+                ui.horizontal(|ui| {
+                    changed |= ui.checkbox(&mut response.invert_x, "X").changed();
+                    changed |= ui.checkbox(&mut response.invert_y, "Y").changed();
+                });
+                ui.end_row();
+            });
+
+        changed
+    }
+
+    /// Pointer response of the emulated Move
+    fn show_pointer_response(ui: &mut egui::Ui, response: &mut PointerResponse) -> bool {
+        let mut changed = false;
+
+        egui::Grid::new("pointer_response")
+            .num_columns(2)
+            .spacing([40.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("Sensitivity:");
+                ui.horizontal(|ui| {
+                    changed |= ui.add(egui::Slider::new(&mut response.sensitivity_x, 0.25..=2.0).text("X")).changed();
+                    changed |= ui.add(egui::Slider::new(&mut response.sensitivity_y, 0.25..=2.0).text("Y")).changed();
+                });
+                ui.end_row();
+
+                ui.label("Offset:");
+                ui.horizontal(|ui| {
+                    changed |= ui.add(egui::Slider::new(&mut response.offset_x, -0.25..=0.25).text("X")).changed();
+                    changed |= ui.add(egui::Slider::new(&mut response.offset_y, -0.25..=0.25).text("Y")).changed();
+                });
+                ui.end_row();
+            });
+        ui.label(
+            egui::RichText::new(
+                "Lines the game's crosshair up with the pointer when the emulated Move is driven by the mouse \
+                 or in pointer mode. Assign the profile to a game to keep its settings per game.",
+            )
+            .small(),
+        );
+
+        changed
+    }
+
+    /// Title ID to profile assignments
+    fn show_game_profiles(&mut self, ui: &mut egui::Ui, config: &mut InputConfig) -> bool {
+        let mut changed = false;
 
-                        let mut dz = deadzone;
-                        if ui.add(egui::Slider::new(&mut dz, 0.0..=0.5).show_value(true)).changed() {
-                            profile.axis_mappings.insert(*axis, (idx, inverted, dz));
+        ui.collapsing("Per-Game Profiles", |ui| {
+            let titles: Vec<String> = config.game_profiles.keys().cloned().collect();
+            if titles.is_empty() {
+                ui.label("No games have a profile assigned; they use the standard layout.");
+            }
+
+            egui::Grid::new("game_profiles")
+                .num_columns(3)
+                .striped(true)
+                .spacing([20.0, 4.0])
+                .show(ui, |ui| {
+                    for title in titles {
+                        ui.label(&title);
+                        let current = config.game_profiles[&title].clone();
+                        egui::ComboBox::from_id_salt(("game_profile", &title))
+                            .selected_text(&current)
+                            .show_ui(ui, |ui| {
+                                for profile in &config.profiles {
+                                    if ui.selectable_label(profile.name == current, &profile.name).clicked() {
+                                        config.game_profiles.insert(title.clone(), profile.name.clone());
+                                        changed = true;
+                                    }
+                                }
+                            });
+                        if ui.button("Remove").clicked() {
+                            config.game_profiles.remove(&title);
                             changed = true;
                         }
+                        ui.end_row();
+                    }
+                });
 
-                        if self.binding_axis == Some(*axis) {
-                            if ui.button("Cancel").clicked() {
-                                self.binding_axis = None;
+            if let Some(index) = self.selected {
+                ui.horizontal(|ui| {
+                    ui.label("Title ID:");
+                    ui.text_edit_singleline(&mut self.new_title_id);
+                    let title = self.new_title_id.trim().to_uppercase();
+                    if ui
+                        .add_enabled(!title.is_empty(), egui::Button::new("Assign Profile"))
+                        .clicked()
+                    {
+                        let name = config.profiles[index].name.clone();
+                        self.status_message = format!("{} now uses \"{}\"", title, name);
+                        config.game_profiles.insert(title, name);
+                        self.new_title_id.clear();
+                        changed = true;
+                    }
+                });
+            }
+        });
+
+        changed
+    }
+
+    /// Keyboard and mouse pad emulation settings
+    fn show_keyboard_pad(&mut self, ui: &mut egui::Ui, config: &mut KeyboardPadConfig) -> bool {
+        let mut changed = false;
+
+        // Finish a pending key binding with the next key press
+        if let Some(index) = self.binding_key {
+            let pressed = ui.input(|i| {
+                i.events.iter().find_map(|event| match event {
+                    egui::Event::Key { key, pressed: true, repeat: false, .. } => Some(*key),
+                    _ => None,
+                })
+            });
+            match pressed {
+                Some(egui::Key::Escape) => {
+                    self.binding_key = None;
+                    self.status_message = String::from("Binding cancelled");
+                }
+                Some(key) => {
+                    *stick_key_mut(config, index) = key.name().to_string();
+                    self.status_message = format!("Bound {} to {}", STICK_KEY_LABELS[index], key.name());
+                    self.binding_key = None;
+                    changed = true;
+                }
+                None => {}
+            }
+        }
+
+        ui.collapsing("Keyboard & Mouse", |ui| {
+            changed |= ui
+                .checkbox(&mut config.enabled, "Emulate a controller with keyboard and mouse")
+                .on_hover_text("Takes a free pad port. Buttons use the keyboard mapping in Settings → Input.")
+                .changed();
+            if !config.enabled {
+                return;
+            }
+
+            egui::Grid::new("keyboard_pad")
+                .num_columns(2)
+                .spacing([40.0, 4.0])
+                .show(ui, |ui| {
+                    for (index, label) in STICK_KEY_LABELS.iter().enumerate() {
+                        ui.label(*label);
+                        ui.horizontal(|ui| {
+                            if self.binding_key == Some(index) {
+                                ui.label(egui::RichText::new("Press a key...").color(egui::Color32::YELLOW));
+                                if ui.button("Cancel").clicked() {
+                                    self.binding_key = None;
+                                }
+                            } else {
+                                ui.label(stick_key_mut(config, index).as_str());
+                                if ui.button("Bind").clicked() {
+                                    self.binding_key = Some(index);
+                                    self.status_message = format!("Press a key for {}", label);
+                                }
                             }
-                        } else {
-                            if ui.button("Bind").clicked() {
-                                self.binding_axis = Some(*axis);
-                                self.status_message = format!("Move an axis for {}", axis.label());
+                        });
+                        ui.end_row();
+                    }
+
+                    ui.label("Stick ramp time (ms):")
+                        .on_hover_text("Time for a held key to reach full deflection");
+                    changed |= ui.add(egui::Slider::new(&mut config.stick_ramp_ms, 0..=300)).changed();
+                    ui.end_row();
+
+                    ui.label("Mouse movement:");
+                    egui::ComboBox::from_id_salt("mouse_mode")
+                        .selected_text(mouse_mode_label(config.mouse_mode))
+                        .show_ui(ui, |ui| {
+                            for mode in [MouseMode::Off, MouseMode::RightStick, MouseMode::Sixaxis] {
+                                changed |= ui
+                                    .selectable_value(&mut config.mouse_mode, mode, mouse_mode_label(mode))
+                                    .changed();
                             }
-                        }
+                        });
+                    ui.end_row();
+
+                    if config.mouse_mode != MouseMode::Off {
+                        ui.label("Mouse sensitivity:");
+                        changed |= ui.add(egui::Slider::new(&mut config.mouse_sensitivity, 0.1..=5.0)).changed();
+                        ui.end_row();
 
+                        ui.label("Spring-back time (ms):")
+                            .on_hover_text("How quickly a mouse flick returns to center");
+                        changed |= ui.add(egui::Slider::new(&mut config.mouse_decay_ms, 0..=500)).changed();
+                        ui.end_row();
+                    }
+
+                    let buttons: Vec<&str> = Ps3Input::all()
+                        .filter(|input| !input.is_axis())
+                        .map(Ps3Input::name)
+                        .collect();
+                    for (label, value) in [("Left mouse button:", &mut config.mouse_left), ("Right mouse button:", &mut config.mouse_right)] {
+                        ui.label(label);
+                        let selected = if value.is_empty() { "None" } else { value.as_str() };
+                        egui::ComboBox::from_id_salt(label)
+                            .selected_text(selected.to_string())
+                            .show_ui(ui, |ui| {
+                                if ui.selectable_label(value.is_empty(), "None").clicked() {
+                                    value.clear();
+                                    changed = true;
+                                }
+                                for &name in &buttons {
+                                    if ui.selectable_label(value.as_str() == name, name).clicked() {
+                                        *value = name.to_string();
+                                        changed = true;
+                                    }
+                                }
+                            });
                         ui.end_row();
                     }
                 });
         });
 
-        ui.add_space(5.0);
+        changed
+    }
 
-        // Vibration settings
-        ui.collapsing("Vibration Settings", |ui| {
-            if ui.checkbox(&mut profile.vibration_enabled, "Enable Vibration").changed() {
-                changed = true;
-            }
+    /// Guitar/drum emulation on the keyboard or a MIDI kit, and instrument bindings
+    fn show_instruments(&mut self, ui: &mut egui::Ui, config: &mut InstrumentConfig) -> bool {
+        let mut changed = false;
+
+        ui.collapsing("Instruments", |ui| {
+            egui::Grid::new("instruments")
+                .num_columns(2)
+                .spacing([40.0, 4.0])
+                .show(ui, |ui| {
+                    ui.label("Keyboard/MIDI instrument:")
+                        .on_hover_text("Connects its own pad port, played on the keyboard or a MIDI kit");
+                    let label = |kind| if kind == InstrumentKind::None { "Off" } else { instrument_label(kind) };
+                    egui::ComboBox::from_id_salt("instrument_device")
+                        .selected_text(label(config.device))
+                        .show_ui(ui, |ui| {
+                            for kind in [InstrumentKind::None, InstrumentKind::Guitar, InstrumentKind::Drums] {
+                                changed |= ui.selectable_value(&mut config.device, kind, label(kind)).changed();
+                            }
+                        });
+                    ui.end_row();
 
+                    if config.device != InstrumentKind::None {
+                        ui.label("MIDI input:");
+                        changed |= ui.checkbox(&mut config.midi, "Enabled").changed();
+                        ui.end_row();
+                    }
+
+                    if config.device != InstrumentKind::None && config.midi {
+                        ui.label("MIDI device:");
+                        ui.horizontal(|ui| {
+                            let selected = if config.midi_device.is_empty() { "First found" } else { config.midi_device.as_str() };
+                            egui::ComboBox::from_id_salt("midi_device")
+                                .selected_text(selected.to_string())
+                                .show_ui(ui, |ui| {
+                                    if ui.selectable_label(config.midi_device.is_empty(), "First found").clicked() {
+                                        config.midi_device.clear();
+                                        changed = true;
+                                    }
+                                    for name in &self.midi_devices {
+                                        if ui.selectable_label(&config.midi_device == name, name).clicked() {
+                                            config.midi_device = name.clone();
+                                            changed = true;
+                                        }
+                                    }
+                                });
+                            if ui.button("🔄").on_hover_text("Look for MIDI devices").clicked() {
+                                self.midi_devices = MidiInputBackend::device_names();
+                                self.status_message = format!("Found {} MIDI input device(s)", self.midi_devices.len());
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+
+            ui.add_space(5.0);
             ui.horizontal(|ui| {
-                ui.label("Strength:");
-                if ui.add(egui::Slider::new(&mut profile.vibration_strength, 0.0..=1.0)
-                    .show_value(true))
-                    .changed()
-                {
-                    changed = true;
+                ui.label("Bindings:");
+                for kind in [InstrumentKind::Guitar, InstrumentKind::Drums] {
+                    ui.selectable_value(&mut self.instrument_tab, kind, instrument_label(kind));
                 }
             });
-
-            if ui.button("Test Vibration").clicked() {
-                self.status_message = String::from("Vibration test sent");
-            }
+            let kind = self.instrument_tab;
+            let bindings = match kind {
+                InstrumentKind::Drums => &mut config.drum_bindings,
+                _ => &mut config.guitar_bindings,
+            };
+            egui::Grid::new("instrument_bindings")
+                .num_columns(2)
+                .striped(true)
+                .spacing([20.0, 4.0])
+                .show(ui, |ui| {
+                    for &(name, _) in InstrumentMapping::inputs(kind) {
+                        ui.label(name);
+                        let mut text = bindings.get(name).cloned().unwrap_or_default();
+                        ui.horizontal(|ui| {
+                            let edit = egui::TextEdit::singleline(&mut text)
+                                .hint_text(InstrumentMapping::default_binding(kind, name))
+                                .desired_width(300.0);
+                            if ui.add(edit).changed() {
+                                if text.trim().is_empty() {
+                                    bindings.remove(name);
+                                } else {
+                                    bindings.insert(name.to_string(), text.clone());
+                                }
+                                changed = true;
+                            }
+                            let valid = text.trim().eq_ignore_ascii_case("none")
+                                || text
+                                    .split(',')
+                                    .filter(|source| !source.trim().is_empty())
+                                    .all(|source| InstrumentSource::parse(source).is_some());
+                            if !valid {
+                                ui.colored_label(egui::Color32::YELLOW, "⚠").on_hover_text("Unknown host input");
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+            ui.label(
+                egui::RichText::new(
+                    "Comma-separated host inputs such as pad:south, axis:right_x, key:F1 or midi:38; \
+                     empty keeps the default shown, \"none\" unbinds.",
+                )
+                .small(),
+            );
         });
 
-        // Test mode display
-        if self.test_mode {
-            ui.add_space(10.0);
-            ui.separator();
-            ui.label(egui::RichText::new("Test Mode").strong());
+        changed
+    }
+
+    /// Emulated PlayStation Move settings
+    fn show_move(ui: &mut egui::Ui, config: &mut MoveConfig) -> bool {
+        let mut changed = false;
 
-            if let Some(controller) = self.connected_controllers.first() {
-                ui.horizontal(|ui| {
-                    ui.label("Buttons: ");
-                    for (i, pressed) in controller.buttons.iter().enumerate() {
-                        let color = if *pressed { egui::Color32::GREEN } else { egui::Color32::GRAY };
-                        ui.colored_label(color, format!("{}", i));
+        ui.collapsing("PlayStation Move", |ui| {
+            egui::Grid::new("move_controller")
+                .num_columns(2)
+                .spacing([40.0, 4.0])
+                .show(ui, |ui| {
+                    ui.label("Emulate with:");
+                    egui::ComboBox::from_id_salt("move_source")
+                        .selected_text(move_source_label(config.source))
+                        .show_ui(ui, |ui| {
+                            for source in [MoveSource::Off, MoveSource::Mouse, MoveSource::Ds4, MoveSource::Ds4Pointer] {
+                                changed |= ui
+                                    .selectable_value(&mut config.source, source, move_source_label(source))
+                                    .changed();
+                            }
+                        });
+                    ui.end_row();
+
+                    if config.source != MoveSource::Off {
+                        ui.label("Tracking distance (mm):")
+                            .on_hover_text("How far from the camera the sphere appears to be");
+                        changed |= ui.add(egui::Slider::new(&mut config.distance_mm, 500.0..=3000.0)).changed();
+                        ui.end_row();
                     }
                 });
 
-                ui.horizontal(|ui| {
-                    ui.label("Axes: ");
-                    for (i, value) in controller.axes.iter().enumerate() {
-                        ui.label(format!("A{}: {:.2}", i, value));
+            let hint = match config.source {
+                MoveSource::Off => None,
+                MoveSource::Mouse => Some(
+                    "Point at the game view to aim. Left button: trigger, right button: Move button. \
+                     Face buttons, Start and Select use the keyboard mapping.",
+                ),
+                MoveSource::Ds4 => Some(
+                    "Needs DS4/DualSense passthrough in Settings → Input. Aim by turning the controller. \
+                     R2: trigger, R1: Move button.",
+                ),
+                MoveSource::Ds4Pointer => Some(
+                    "Needs DS4/DualSense passthrough in Settings → Input. Turn the controller to move the pointer; \
+                     R3 centers it. R2: trigger, R1: Move button. For light-gun games.",
+                ),
+            };
+            if let Some(hint) = hint {
+                ui.label(egui::RichText::new(hint).small());
+                ui.label(
+                    egui::RichText::new("When a game asks to calibrate, point at the center of the screen and hold still.")
+                        .small(),
+                );
+            }
+        });
+
+        changed
+    }
+
+    /// Emulated PlayStation Eye settings
+    fn show_camera(&mut self, ui: &mut egui::Ui, config: &mut CameraConfig) -> bool {
+        let mut changed = false;
+
+        ui.collapsing("PlayStation Eye", |ui| {
+            egui::Grid::new("camera")
+                .num_columns(2)
+                .spacing([40.0, 4.0])
+                .show(ui, |ui| {
+                    ui.label("Camera:");
+                    changed |= ui.checkbox(&mut config.enabled, "Connected").changed();
+                    ui.end_row();
+
+                    if config.enabled {
+                        ui.label("Webcam:");
+                        ui.horizontal(|ui| {
+                            let selected = if config.device.is_empty() { "First found" } else { config.device.as_str() };
+                            egui::ComboBox::from_id_salt("webcam")
+                                .selected_text(selected.to_string())
+                                .show_ui(ui, |ui| {
+                                    if ui.selectable_label(config.device.is_empty(), "First found").clicked() {
+                                        config.device.clear();
+                                        changed = true;
+                                    }
+                                    for name in &self.webcams {
+                                        if ui.selectable_label(&config.device == name, name).clicked() {
+                                            config.device = name.clone();
+                                            changed = true;
+                                        }
+                                    }
+                                });
+                            if ui.button("🔄").on_hover_text("Look for webcams").clicked() {
+                                self.webcams = WebcamCapture::device_names();
+                                self.status_message = format!("Found {} webcam(s)", self.webcams.len());
+                            }
+                        });
+                        ui.end_row();
                     }
                 });
 
-                // Request repaint for live updates
-                ui.ctx().request_repaint();
-            } else {
-                ui.label("No controller connected for testing.");
+            if config.enabled {
+                ui.label(
+                    egui::RichText::new(
+                        "Frames are converted to the resolution and format each game asks for. \
+                         Color bars are shown if the webcam cannot be opened.",
+                    )
+                    .small(),
+                );
             }
-        }
-
-        // Status bar
-        ui.separator();
-        ui.horizontal(|ui| {
-            ui.label(egui::RichText::new(&self.status_message).small());
         });
 
         changed
     }
+
+    /// First free "<base> N" profile name
+    fn unique_name(config: &InputConfig, base: &str) -> String {
+        (1..)
+            .map(|n| format!("{} {}", base, n))
+            .find(|name| config.profiles.iter().all(|profile| &profile.name != name))
+            .unwrap_or_else(|| base.to_string())
+    }
 }
 
 impl Default for ControllerConfig {
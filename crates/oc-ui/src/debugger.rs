@@ -1,8 +1,19 @@
 //! Debugger UI
 
 use eframe::egui;
-use oc_debug::{PpuDebugger, SpuDebugger, RsxDebugger, Profiler, PpuDisassembler};
+use oc_debug::{format_backtrace, PpuDebugger, SpuDebugger, RsxDebugger, Profiler, PpuDisassembler, StackFrame};
+use oc_debug::{CodePatch, PatchManager};
+use oc_debug::spu_debugger::{channel_name, SpuDebugState};
+use parking_lot::{Mutex, RwLock};
+use std::sync::Arc;
+use oc_debug::breakpoint::BreakpointManager;
 use oc_debug::ppu_debugger::DebugState;
+use oc_core::Condition;
+use oc_lv2::objects::{ObjectDebugInfo, ObjectType};
+use oc_lv2::thread::{ThreadDebugInfo, ThreadState};
+use oc_memory::{MemoryManager, WatchpointCondition, WatchpointType};
+use oc_spu::thread::SPU_LS_SIZE;
+use oc_spu::SpuThread;
 
 /// Watchpoint entry for UI display
 #[derive(Debug, Clone)]
@@ -25,6 +36,10 @@ pub struct Watchpoint {
     pub label: String,
     /// Last value seen (for comparison)
     pub last_value: Option<u64>,
+    /// Only trigger on accesses of this width
+    pub access_size: Option<u32>,
+    /// Optional value condition
+    pub condition: Option<WatchpointCondition>,
 }
 
 /// Debugger view state
@@ -39,10 +54,14 @@ pub struct DebuggerView {
     disasm_address: String,
     /// Disassembled instructions
     disassembled: Vec<DisasmLine>,
-    /// Breakpoints with enabled state
-    breakpoints: Vec<(u32, bool)>,
+    /// Execution breakpoints
+    breakpoints: Vec<BreakpointEntry>,
     /// New breakpoint address input
     breakpoint_input: String,
+    /// New breakpoint condition input (empty for none)
+    breakpoint_condition_input: String,
+    /// Processor the new breakpoint is set on
+    breakpoint_target: BreakpointTarget,
     /// Watchpoints list
     watchpoints: Vec<Watchpoint>,
     /// New watchpoint address input
@@ -53,6 +72,12 @@ pub struct DebuggerView {
     watchpoint_on_read: bool,
     /// New watchpoint write flag
     watchpoint_on_write: bool,
+    /// New watchpoint access size filter
+    watchpoint_access_size: Option<u32>,
+    /// New watchpoint "value equals" input (empty for none)
+    watchpoint_value_input: String,
+    /// New watchpoint "value changed" flag
+    watchpoint_on_change: bool,
     /// Next watchpoint ID
     next_watchpoint_id: u32,
     /// Memory breakpoints list (read/write breakpoints)
@@ -65,16 +90,73 @@ pub struct DebuggerView {
     next_mem_bp_id: u32,
     /// PPU Debugger
     ppu_debugger: PpuDebugger,
-    /// SPU Debugger
-    spu_debugger: SpuDebugger,
+    /// SPU debugger, shared with the emulator once it runs
+    spu_debugger: Arc<Mutex<SpuDebugger>>,
     /// RSX Debugger
     rsx_debugger: RsxDebugger,
-    /// Profiler
-    profiler: Profiler,
+    /// Profiler, shared with the emulator once it runs
+    profiler: Arc<Mutex<Profiler>>,
+    /// Unwound stack of the main PPU thread while the emulator is paused
+    backtrace: Vec<StackFrame>,
+    /// Live LV2 threads for the kernel objects tab
+    kernel_threads: Vec<ThreadDebugInfo>,
+    /// Live LV2 objects for the kernel objects tab
+    kernel_objects: Vec<ObjectDebugInfo>,
+    /// Object type shown in the kernel objects tab (None for all)
+    kernel_object_filter: Option<ObjectType>,
+    /// Code patches of the running game
+    patches: Option<Arc<Mutex<PatchManager>>>,
+    /// Memory the patches are written to
+    patch_memory: Option<Arc<MemoryManager>>,
+    /// Patch editor inputs
+    patch_address_input: String,
+    patch_target_input: String,
+    patch_name_input: String,
+    patch_code_input: String,
+    /// SPU threads of the running game
+    spu_threads: Vec<Arc<RwLock<SpuThread>>>,
+    /// SPU shown in the SPU tab
+    spu_selected: usize,
+    /// Local storage address input of the SPU tab
+    spu_ls_input: String,
+    /// First local storage byte shown
+    spu_ls_offset: u32,
     /// Status message
     status_message: String,
 }
 
+/// Processor an execution breakpoint is set on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakpointTarget {
+    Ppu,
+    /// SPU 0-5
+    Spu(usize),
+}
+
+impl BreakpointTarget {
+    fn label(&self) -> String {
+        match self {
+            BreakpointTarget::Ppu => "PPU".to_string(),
+            BreakpointTarget::Spu(spu) => format!("SPU {}", spu),
+        }
+    }
+}
+
+/// Execution breakpoint entry
+#[derive(Debug, Clone)]
+pub struct BreakpointEntry {
+    /// ID in the target's breakpoint manager
+    pub id: u32,
+    pub target: BreakpointTarget,
+    pub address: u32,
+    /// Enabled state
+    pub enabled: bool,
+    /// Condition expression being edited (empty for none)
+    pub condition: String,
+    /// Why `condition` doesn't parse
+    pub condition_error: Option<String>,
+}
+
 /// Memory breakpoint entry
 #[derive(Debug, Clone)]
 pub struct MemoryBreakpoint {
@@ -115,12 +197,15 @@ impl MemoryBreakpointType {
 enum DebuggerTab {
     Registers,
     SpuRegisters,
+    Spu,
     Memory,
     Disassembly,
     Breakpoints,
     Watchpoints,
     MemoryBreakpoints,
     CallStack,
+    KernelObjects,
+    Patches,
     Profiler,
 }
 
@@ -142,24 +227,75 @@ impl DebuggerView {
             disassembled: Vec::new(),
             breakpoints: Vec::new(),
             breakpoint_input: String::new(),
+            breakpoint_condition_input: String::new(),
+            breakpoint_target: BreakpointTarget::Ppu,
             watchpoints: Vec::new(),
             watchpoint_address_input: String::new(),
             watchpoint_size_input: String::from("4"),
             watchpoint_on_read: false,
             watchpoint_on_write: true,
+            watchpoint_access_size: None,
+            watchpoint_value_input: String::new(),
+            watchpoint_on_change: false,
             next_watchpoint_id: 0,
             memory_breakpoints: Vec::new(),
             mem_bp_address_input: String::new(),
             mem_bp_type: MemoryBreakpointType::Write,
             next_mem_bp_id: 0,
             ppu_debugger: PpuDebugger::new(),
-            spu_debugger: SpuDebugger::new(),
+            spu_debugger: Arc::new(Mutex::new(SpuDebugger::new())),
             rsx_debugger: RsxDebugger::new(),
-            profiler: Profiler::new(),
+            profiler: Arc::new(Mutex::new(Profiler::new())),
+            backtrace: Vec::new(),
+            kernel_threads: Vec::new(),
+            kernel_objects: Vec::new(),
+            kernel_object_filter: None,
+            patches: None,
+            patch_memory: None,
+            patch_address_input: String::new(),
+            patch_target_input: String::new(),
+            patch_name_input: String::new(),
+            patch_code_input: String::new(),
+            spu_threads: Vec::new(),
+            spu_selected: 0,
+            spu_ls_input: String::from("0x00000"),
+            spu_ls_offset: 0,
             status_message: String::from("Ready"),
         }
     }
 
+    /// Set the backtrace shown in the call stack tab (empty while running)
+    pub fn set_backtrace(&mut self, frames: Vec<StackFrame>) {
+        self.backtrace = frames;
+    }
+
+    /// Set the LV2 threads and objects shown in the kernel objects tab
+    pub fn set_kernel_objects(&mut self, threads: Vec<ThreadDebugInfo>, objects: Vec<ObjectDebugInfo>) {
+        self.kernel_threads = threads;
+        self.kernel_objects = objects;
+    }
+
+    /// Whether the kernel objects tab is open
+    pub fn shows_kernel_objects(&self) -> bool {
+        self.current_tab == DebuggerTab::KernelObjects
+    }
+
+    /// Connect the patches tab to the emulator's patch list and memory
+    pub fn set_patches(&mut self, patches: Arc<Mutex<PatchManager>>, memory: Arc<MemoryManager>) {
+        self.patches = Some(patches);
+        self.patch_memory = Some(memory);
+    }
+
+    /// Set the SPU threads shown in the SPU tab
+    pub fn set_spu_threads(&mut self, threads: Vec<Arc<RwLock<SpuThread>>>) {
+        self.spu_threads = threads;
+    }
+
+    /// Whether the SPU tab is open
+    pub fn shows_spu(&self) -> bool {
+        self.current_tab == DebuggerTab::Spu
+    }
+
     /// Get reference to PPU debugger
     pub fn ppu_debugger(&self) -> &PpuDebugger {
         &self.ppu_debugger
@@ -171,13 +307,15 @@ impl DebuggerView {
     }
 
     /// Get reference to SPU debugger
-    pub fn spu_debugger(&self) -> &SpuDebugger {
+    pub fn spu_debugger(&self) -> &Arc<Mutex<SpuDebugger>> {
         &self.spu_debugger
     }
 
-    /// Get mutable reference to SPU debugger
-    pub fn spu_debugger_mut(&mut self) -> &mut SpuDebugger {
-        &mut self.spu_debugger
+    /// Debug the emulator's SPUs, keeping the breakpoints already set
+    pub fn set_spu_debugger(&mut self, debugger: Arc<Mutex<SpuDebugger>>) {
+        let breakpoints = std::mem::take(&mut self.spu_debugger.lock().breakpoints);
+        debugger.lock().breakpoints = breakpoints;
+        self.spu_debugger = debugger;
     }
 
     /// Get reference to RSX debugger
@@ -191,13 +329,13 @@ impl DebuggerView {
     }
 
     /// Get reference to profiler
-    pub fn profiler(&self) -> &Profiler {
+    pub fn profiler(&self) -> &Arc<Mutex<Profiler>> {
         &self.profiler
     }
 
-    /// Get mutable reference to profiler
-    pub fn profiler_mut(&mut self) -> &mut Profiler {
-        &mut self.profiler
+    /// Show the emulator's profiler
+    pub fn set_profiler(&mut self, profiler: Arc<Mutex<Profiler>>) {
+        self.profiler = profiler;
     }
 
     /// Show the debugger view
@@ -205,12 +343,15 @@ impl DebuggerView {
         ui.horizontal(|ui| {
             ui.selectable_value(&mut self.current_tab, DebuggerTab::Registers, "PPU Regs");
             ui.selectable_value(&mut self.current_tab, DebuggerTab::SpuRegisters, "SPU Regs");
+            ui.selectable_value(&mut self.current_tab, DebuggerTab::Spu, "SPU");
             ui.selectable_value(&mut self.current_tab, DebuggerTab::Memory, "Memory");
             ui.selectable_value(&mut self.current_tab, DebuggerTab::Disassembly, "Disassembly");
             ui.selectable_value(&mut self.current_tab, DebuggerTab::Breakpoints, "Breakpoints");
             ui.selectable_value(&mut self.current_tab, DebuggerTab::Watchpoints, "Watchpoints");
             ui.selectable_value(&mut self.current_tab, DebuggerTab::MemoryBreakpoints, "Memory BPs");
             ui.selectable_value(&mut self.current_tab, DebuggerTab::CallStack, "Call Stack");
+            ui.selectable_value(&mut self.current_tab, DebuggerTab::KernelObjects, "Kernel Objects");
+            ui.selectable_value(&mut self.current_tab, DebuggerTab::Patches, "Patches");
             ui.selectable_value(&mut self.current_tab, DebuggerTab::Profiler, "Profiler");
         });
 
@@ -270,12 +411,15 @@ impl DebuggerView {
             match self.current_tab {
                 DebuggerTab::Registers => self.show_registers(ui),
                 DebuggerTab::SpuRegisters => self.show_spu_registers(ui),
+                DebuggerTab::Spu => self.show_spu(ui),
                 DebuggerTab::Memory => self.show_memory(ui),
                 DebuggerTab::Disassembly => self.show_disassembly(ui),
                 DebuggerTab::Breakpoints => self.show_breakpoints(ui),
                 DebuggerTab::Watchpoints => self.show_watchpoints(ui),
                 DebuggerTab::MemoryBreakpoints => self.show_memory_breakpoints(ui),
                 DebuggerTab::CallStack => self.show_call_stack(ui),
+                DebuggerTab::KernelObjects => self.show_kernel_objects(ui),
+                DebuggerTab::Patches => self.show_patches(ui),
                 DebuggerTab::Profiler => self.show_profiler(ui),
             }
         });
@@ -501,69 +645,174 @@ impl DebuggerView {
         }).collect();
     }
 
+    /// Run `f` on the breakpoint manager of the PPU or an SPU
+    fn with_breakpoints<R>(&mut self, target: BreakpointTarget, f: impl FnOnce(&mut BreakpointManager) -> R) -> R {
+        match target {
+            BreakpointTarget::Ppu => f(&mut self.ppu_debugger.breakpoints),
+            BreakpointTarget::Spu(spu) => f(&mut self.spu_debugger.lock().breakpoints[spu]),
+        }
+    }
+
+    /// Parse a condition, empty meaning none
+    fn parse_condition(text: &str) -> Result<Option<Condition>, String> {
+        if text.trim().is_empty() {
+            Ok(None)
+        } else {
+            Condition::parse(text).map(Some).map_err(|e| e.to_string())
+        }
+    }
+
     fn show_breakpoints(&mut self, ui: &mut egui::Ui) {
         ui.heading("Breakpoints");
         ui.add_space(10.0);
 
         ui.horizontal(|ui| {
             ui.label("Add Breakpoint:");
+            egui::ComboBox::from_id_salt("breakpoint_target")
+                .selected_text(self.breakpoint_target.label())
+                .show_ui(ui, |ui| {
+                    let targets = std::iter::once(BreakpointTarget::Ppu).chain((0..6).map(BreakpointTarget::Spu));
+                    for target in targets {
+                        ui.selectable_value(&mut self.breakpoint_target, target, target.label());
+                    }
+                });
             ui.text_edit_singleline(&mut self.breakpoint_input);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Condition:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.breakpoint_condition_input)
+                    .hint_text("e.g. r3 == 0x10 && mem32(r1 + 0x70) != 0")
+                    .desired_width(300.0),
+            );
             if ui.button("Add").clicked() {
-                if let Ok(addr) = self.parse_address(&self.breakpoint_input) {
-                    // Add to both UI list and debugger
-                    self.breakpoints.push((addr, true));
-                    self.ppu_debugger.breakpoints.add_execution_breakpoint(addr as u64);
-                    self.breakpoint_input.clear();
-                    self.status_message = format!("Added breakpoint at 0x{:08X}", addr);
-                } else {
+                let Ok(addr) = self.parse_address(&self.breakpoint_input) else {
                     self.status_message = String::from("Invalid address format");
-                }
+                    return;
+                };
+                let condition = match Self::parse_condition(&self.breakpoint_condition_input) {
+                    Ok(condition) => condition,
+                    Err(e) => {
+                        self.status_message = format!("Invalid condition: {}", e);
+                        return;
+                    }
+                };
+                let target = self.breakpoint_target;
+                let id = self.with_breakpoints(target, |manager| {
+                    let id = manager.add_execution_breakpoint(addr as u64);
+                    manager.set_condition(id, condition);
+                    id
+                });
+                self.breakpoints.push(BreakpointEntry {
+                    id,
+                    target,
+                    address: addr,
+                    enabled: true,
+                    condition: self.breakpoint_condition_input.trim().to_string(),
+                    condition_error: None,
+                });
+                self.breakpoint_input.clear();
+                self.breakpoint_condition_input.clear();
+                self.status_message = format!("Added {} breakpoint at 0x{:08X}", target.label(), addr);
             }
         });
+        ui.label(
+            egui::RichText::new(
+                "Conditions use registers (r0-r31, lr, ctr, cr, pc on the PPU; r0-r127 on SPUs), \
+                 hits and mem8/16/32/64(addr)",
+            )
+            .small()
+            .weak(),
+        );
 
         ui.add_space(10.0);
 
         // Show breakpoint count
-        let bp_count = self.ppu_debugger.breakpoints.count();
-        ui.label(format!("Total breakpoints: {}", bp_count));
+        ui.label(format!("Total breakpoints: {}", self.breakpoints.len()));
         ui.add_space(5.0);
 
         if self.breakpoints.is_empty() {
             ui.label("No breakpoints set.");
-        } else {
-            egui::Grid::new("breakpoints_grid")
-                .striped(true)
-                .num_columns(3)
-                .show(ui, |ui| {
-                    ui.strong("Address");
-                    ui.strong("Enabled");
-                    ui.strong("Actions");
-                    ui.end_row();
+            return;
+        }
 
-                    let mut to_remove = None;
+        let mut to_remove = None;
+        let mut changed_condition = None;
+        egui::Grid::new("breakpoints_grid")
+            .striped(true)
+            .num_columns(6)
+            .show(ui, |ui| {
+                ui.strong("Target");
+                ui.strong("Address");
+                ui.strong("Enabled");
+                ui.strong("Condition");
+                ui.strong("Hits");
+                ui.strong("Actions");
+                ui.end_row();
 
-                    for (i, (addr, enabled)) in self.breakpoints.iter_mut().enumerate() {
-                        ui.label(egui::RichText::new(format!("0x{:08X}", addr)).monospace());
-                        if ui.checkbox(enabled, "").changed() {
-                            // Update debugger breakpoint state
-                            // Note: This would need the breakpoint ID in a real implementation
-                            self.status_message = format!(
-                                "Breakpoint at 0x{:08X} {}",
-                                addr,
-                                if *enabled { "enabled" } else { "disabled" }
-                            );
+                for i in 0..self.breakpoints.len() {
+                    let (id, target) = (self.breakpoints[i].id, self.breakpoints[i].target);
+                    let hits = self.with_breakpoints(target, |manager| manager.get(id).map_or(0, |bp| bp.hit_count));
+                    let entry = &mut self.breakpoints[i];
+
+                    ui.label(target.label());
+                    ui.label(egui::RichText::new(format!("0x{:08X}", entry.address)).monospace());
+                    let enabled_changed = ui.checkbox(&mut entry.enabled, "").changed();
+                    ui.vertical(|ui| {
+                        let response = ui.add(
+                            egui::TextEdit::singleline(&mut entry.condition)
+                                .hint_text("always")
+                                .desired_width(250.0),
+                        );
+                        // Apply once editing finishes so a half-typed expression isn't an error
+                        if response.lost_focus() {
+                            changed_condition = Some(i);
                         }
-                        if ui.button("Remove").clicked() {
-                            to_remove = Some(i);
+                        if let Some(error) = &entry.condition_error {
+                            ui.colored_label(egui::Color32::RED, error);
                         }
-                        ui.end_row();
+                    });
+                    ui.label(hits.to_string());
+                    if ui.button("Remove").clicked() {
+                        to_remove = Some(i);
                     }
+                    ui.end_row();
 
-                    if let Some(idx) = to_remove {
-                        let (addr, _) = self.breakpoints.remove(idx);
-                        self.status_message = format!("Removed breakpoint at 0x{:08X}", addr);
+                    if enabled_changed {
+                        let (address, enabled) = (entry.address, entry.enabled);
+                        self.with_breakpoints(target, |manager| {
+                            if enabled {
+                                manager.enable_breakpoint(id);
+                            } else {
+                                manager.disable_breakpoint(id);
+                            }
+                        });
+                        self.status_message = format!(
+                            "Breakpoint at 0x{:08X} {}",
+                            address,
+                            if enabled { "enabled" } else { "disabled" }
+                        );
                     }
-                });
+                }
+            });
+
+        if let Some(idx) = changed_condition {
+            let entry = &self.breakpoints[idx];
+            let (id, target) = (entry.id, entry.target);
+            match Self::parse_condition(&entry.condition) {
+                Ok(condition) => {
+                    self.with_breakpoints(target, |manager| manager.set_condition(id, condition));
+                    self.breakpoints[idx].condition_error = None;
+                }
+                // Keep the previous condition until the new one parses
+                Err(e) => self.breakpoints[idx].condition_error = Some(e),
+            }
+        }
+
+        if let Some(idx) = to_remove {
+            let entry = self.breakpoints.remove(idx);
+            self.with_breakpoints(entry.target, |manager| manager.remove_breakpoint(entry.id));
+            self.status_message = format!("Removed breakpoint at 0x{:08X}", entry.address);
         }
     }
 
@@ -571,27 +820,34 @@ impl DebuggerView {
         ui.heading("Performance Profiler");
         ui.add_space(10.0);
 
+        let profiler = Arc::clone(&self.profiler);
+        let mut profiler = profiler.lock();
+
         // Profiler controls
         ui.horizontal(|ui| {
-            if self.profiler.enabled {
+            if profiler.enabled {
                 if ui.button("⏹ Stop Profiling").clicked() {
-                    self.profiler.disable();
+                    profiler.disable();
                     self.status_message = String::from("Profiling stopped");
                 }
             } else {
                 if ui.button("▶ Start Profiling").clicked() {
-                    self.profiler.enable();
+                    profiler.enable();
                     self.status_message = String::from("Profiling started");
                 }
             }
 
             if ui.button("🔄 Reset").clicked() {
-                self.profiler.reset();
+                profiler.reset();
                 self.status_message = String::from("Profiler reset");
             }
 
+            if ui.button("💾 Export Flamegraph…").clicked() {
+                self.export_flamegraph(&profiler);
+            }
+
             ui.separator();
-            let status = if self.profiler.enabled {
+            let status = if profiler.enabled {
                 egui::RichText::new("● Profiling").color(egui::Color32::RED)
             } else {
                 egui::RichText::new("○ Stopped").color(egui::Color32::GRAY)
@@ -608,23 +864,55 @@ impl DebuggerView {
             .num_columns(2)
             .show(ui, |ui| {
                 ui.label("Average FPS:");
-                ui.label(format!("{:.1}", self.profiler.get_average_fps()));
+                ui.label(format!("{:.1}", profiler.get_average_fps()));
                 ui.end_row();
 
                 ui.label("Frame Time:");
-                ui.label(format!("{:.2} ms", self.profiler.get_average_frame_time_ms()));
+                ui.label(format!("{:.2} ms", profiler.get_average_frame_time_ms()));
                 ui.end_row();
 
                 ui.label("Session Duration:");
-                ui.label(format!("{:.1} s", self.profiler.session_duration().as_secs_f64()));
+                ui.label(format!("{:.1} s", profiler.session_duration().as_secs_f64()));
                 ui.end_row();
             });
 
         ui.add_space(10.0);
 
+        // RSX work in the last profiled frame and since profiling started
+        ui.label(egui::RichText::new("RSX Counters").strong());
+        let last = profiler.get_frame_timings(1).first().map(|timing| timing.rsx).unwrap_or_default();
+        let total = profiler.rsx_totals();
+        egui::Grid::new("rsx_counters")
+            .striped(true)
+            .num_columns(3)
+            .show(ui, |ui| {
+                ui.strong("Counter");
+                ui.strong("Last Frame");
+                ui.strong("Total");
+                ui.end_row();
+
+                for (name, last, total) in [
+                    ("Draw Calls", last.draw_calls, total.draw_calls),
+                    ("Triangles", last.triangles, total.triangles),
+                    ("Clears", last.clears, total.clears),
+                    ("Texture Uploads", last.texture_uploads, total.texture_uploads),
+                    ("Surface Cache Hits", last.surface_cache_hits, total.surface_cache_hits),
+                    ("Surface Cache Misses", last.surface_cache_misses, total.surface_cache_misses),
+                    ("Pipeline Cache Hits", last.pipeline_cache_hits, total.pipeline_cache_hits),
+                    ("Pipeline Cache Misses", last.pipeline_cache_misses, total.pipeline_cache_misses),
+                ] {
+                    ui.label(name);
+                    ui.label(last.to_string());
+                    ui.label(total.to_string());
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(10.0);
+
         // Top profile sections
         ui.label(egui::RichText::new("Top Sections by Time").strong());
-        let entries = self.profiler.get_entries();
+        let entries = profiler.get_entries();
         if entries.is_empty() {
             ui.label("No profiling data yet.");
         } else {
@@ -650,9 +938,39 @@ impl DebuggerView {
 
         ui.add_space(10.0);
 
+        // Functions from call stack samples
+        ui.label(egui::RichText::new(format!("PPU Functions ({} samples)", profiler.sample_count())).strong());
+        let functions = profiler.get_function_hotspots(20);
+        if functions.is_empty() {
+            ui.label("No call stack samples yet.");
+        } else {
+            egui::Grid::new("ppu_function_hotspots")
+                .striped(true)
+                .num_columns(5)
+                .show(ui, |ui| {
+                    ui.strong("Function");
+                    ui.strong("Exclusive");
+                    ui.strong("Inclusive");
+                    ui.strong("Exclusive Time");
+                    ui.strong("Inclusive Time");
+                    ui.end_row();
+
+                    for function in &functions {
+                        ui.label(egui::RichText::new(&function.name).monospace());
+                        ui.label(format!("{:.2}%", function.exclusive_percentage));
+                        ui.label(format!("{:.2}%", function.inclusive_percentage));
+                        ui.label(format!("{:.2} ms", function.exclusive_time.as_secs_f64() * 1000.0));
+                        ui.label(format!("{:.2} ms", function.inclusive_time.as_secs_f64() * 1000.0));
+                        ui.end_row();
+                    }
+                });
+        }
+
+        ui.add_space(10.0);
+
         // Hotspots
         ui.label(egui::RichText::new("PPU Hotspots").strong());
-        let hotspots = self.profiler.get_ppu_hotspots(5);
+        let hotspots = profiler.get_ppu_hotspots(5);
         if hotspots.is_empty() {
             ui.label("No hotspot data yet.");
         } else {
@@ -675,6 +993,25 @@ impl DebuggerView {
         }
     }
 
+    /// Save the call stack samples as folded stacks for flamegraph tools
+    fn export_flamegraph(&mut self, profiler: &Profiler) {
+        if profiler.sample_count() == 0 {
+            self.status_message = String::from("No call stack samples to export");
+            return;
+        }
+        let Some(path) = rfd::FileDialog::new()
+            .set_file_name("profile.folded")
+            .add_filter("Folded stacks", &["folded", "txt"])
+            .save_file()
+        else {
+            return;
+        };
+        self.status_message = match std::fs::write(&path, profiler.export_folded()) {
+            Ok(()) => format!("Flamegraph data saved to {}", path.display()),
+            Err(e) => format!("Failed to write {}: {}", path.display(), e),
+        };
+    }
+
     fn parse_address(&self, s: &str) -> Result<u32, ()> {
         let s = s.trim();
         if s.starts_with("0x") || s.starts_with("0X") {
@@ -684,13 +1021,51 @@ impl DebuggerView {
         }
     }
 
+    /// Push the UI watchpoint list into the attached memory manager
+    fn sync_watchpoints(&self) {
+        let Some(memory) = self.ppu_debugger.memory() else {
+            return;
+        };
+        let manager = memory.watchpoints();
+        manager.clear();
+        for wp in &self.watchpoints {
+            let wp_type = match (wp.on_read, wp.on_write) {
+                (true, true) => WatchpointType::ReadWrite,
+                (true, false) => WatchpointType::Read,
+                (false, true) => WatchpointType::Write,
+                (false, false) => continue,
+            };
+            manager.insert(oc_memory::Watchpoint {
+                addr: wp.address as u32,
+                size: wp.size,
+                wp_type,
+                enabled: wp.enabled,
+                hit_count: wp.hit_count,
+                condition: wp.condition.clone(),
+                size_filter: wp.access_size,
+            });
+        }
+    }
+
     fn show_watchpoints(&mut self, ui: &mut egui::Ui) {
         ui.heading("Watchpoints");
         ui.add_space(10.0);
 
         ui.label("Watchpoints pause execution when a memory location is accessed.");
+        if self.ppu_debugger.memory().is_none() {
+            ui.colored_label(egui::Color32::YELLOW, "No emulator memory attached; watchpoints are inactive.");
+        }
         ui.add_space(5.0);
 
+        // Refresh hit counts from the live watchpoints
+        if let Some(memory) = self.ppu_debugger.memory() {
+            for live in memory.watchpoints().get_all() {
+                if let Some(wp) = self.watchpoints.iter_mut().find(|wp| wp.address as u32 == live.addr) {
+                    wp.hit_count = live.hit_count;
+                }
+            }
+        }
+
         // Add watchpoint form
         ui.horizontal(|ui| {
             ui.label("Address:");
@@ -704,27 +1079,65 @@ impl DebuggerView {
 
             ui.checkbox(&mut self.watchpoint_on_read, "Read");
             ui.checkbox(&mut self.watchpoint_on_write, "Write");
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Access width:");
+            egui::ComboBox::from_id_salt("watchpoint_access_size")
+                .selected_text(match self.watchpoint_access_size {
+                    Some(size) => format!("{} bytes", size),
+                    None => String::from("Any"),
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.watchpoint_access_size, None, "Any");
+                    for size in [1u32, 2, 4, 8] {
+                        ui.selectable_value(&mut self.watchpoint_access_size, Some(size), format!("{} bytes", size));
+                    }
+                });
+
+            ui.label("Value ==");
+            ui.add(egui::TextEdit::singleline(&mut self.watchpoint_value_input)
+                .desired_width(100.0)
+                .hint_text("any"));
+
+            ui.checkbox(&mut self.watchpoint_on_change, "On change");
 
             if ui.button("Add Watchpoint").clicked() {
-                if let Ok(addr) = self.parse_address(&self.watchpoint_address_input) {
-                    let size: u32 = self.watchpoint_size_input.parse().unwrap_or(4);
-                    let watchpoint = Watchpoint {
-                        id: self.next_watchpoint_id,
-                        address: addr as u64,
-                        size,
-                        on_read: self.watchpoint_on_read,
-                        on_write: self.watchpoint_on_write,
-                        enabled: true,
-                        hit_count: 0,
-                        label: String::new(),
-                        last_value: None,
-                    };
-                    self.watchpoints.push(watchpoint);
-                    self.next_watchpoint_id += 1;
-                    self.watchpoint_address_input.clear();
-                    self.status_message = format!("Added watchpoint at 0x{:08X}", addr);
+                let value = if self.watchpoint_value_input.trim().is_empty() {
+                    Ok(None)
                 } else {
-                    self.status_message = String::from("Invalid address format");
+                    self.parse_address(&self.watchpoint_value_input).map(Some)
+                };
+
+                match (self.parse_address(&self.watchpoint_address_input), value) {
+                    (Ok(addr), Ok(value)) => {
+                        let size: u32 = self.watchpoint_size_input.parse().unwrap_or(4);
+                        let condition = match value {
+                            Some(value) => Some(WatchpointCondition::Equals(value as u64)),
+                            None if self.watchpoint_on_change => Some(WatchpointCondition::Changed),
+                            None => None,
+                        };
+                        let watchpoint = Watchpoint {
+                            id: self.next_watchpoint_id,
+                            address: addr as u64,
+                            size,
+                            on_read: self.watchpoint_on_read,
+                            on_write: self.watchpoint_on_write,
+                            enabled: true,
+                            hit_count: 0,
+                            label: String::new(),
+                            last_value: None,
+                            access_size: self.watchpoint_access_size,
+                            condition,
+                        };
+                        self.watchpoints.push(watchpoint);
+                        self.next_watchpoint_id += 1;
+                        self.watchpoint_address_input.clear();
+                        self.sync_watchpoints();
+                        self.status_message = format!("Added watchpoint at 0x{:08X}", addr);
+                    }
+                    (Err(()), _) => self.status_message = String::from("Invalid address format"),
+                    (_, Err(())) => self.status_message = String::from("Invalid value format"),
                 }
             }
         });
@@ -734,13 +1147,16 @@ impl DebuggerView {
         if self.watchpoints.is_empty() {
             ui.label("No watchpoints set.");
         } else {
+            let mut changed = false;
+
             egui::Grid::new("watchpoints_grid")
                 .striped(true)
-                .num_columns(7)
+                .num_columns(8)
                 .show(ui, |ui| {
                     ui.strong("Address");
                     ui.strong("Size");
                     ui.strong("Type");
+                    ui.strong("Condition");
                     ui.strong("Enabled");
                     ui.strong("Hits");
                     ui.strong("Label");
@@ -760,8 +1176,19 @@ impl DebuggerView {
                             (false, false) => "None",
                         };
                         ui.label(type_str);
+
+                        let mut condition_str = match &wp.condition {
+                            Some(WatchpointCondition::Equals(value)) => format!("== 0x{:X}", value),
+                            Some(WatchpointCondition::Changed) => String::from("changed"),
+                            Some(WatchpointCondition::Delta(delta)) => format!("delta {}", delta),
+                            None => String::from("-"),
+                        };
+                        if let Some(size) = wp.access_size {
+                            condition_str.push_str(&format!(" ({}B)", size));
+                        }
+                        ui.label(condition_str);
                         
-                        ui.checkbox(&mut wp.enabled, "");
+                        changed |= ui.checkbox(&mut wp.enabled, "").changed();
                         ui.label(format!("{}", wp.hit_count));
                         ui.text_edit_singleline(&mut wp.label);
                         
@@ -773,6 +1200,7 @@ impl DebuggerView {
 
                     if let Some(idx) = to_remove {
                         let wp = self.watchpoints.remove(idx);
+                        changed = true;
                         self.status_message = format!("Removed watchpoint at 0x{:08X}", wp.address);
                     }
                 });
@@ -781,8 +1209,13 @@ impl DebuggerView {
 
             if ui.button("Clear All Watchpoints").clicked() {
                 self.watchpoints.clear();
+                changed = true;
                 self.status_message = String::from("Cleared all watchpoints");
             }
+
+            if changed {
+                self.sync_watchpoints();
+            }
         }
     }
 
@@ -1002,6 +1435,11 @@ impl DebuggerView {
         ui.heading("Call Stack");
         ui.add_space(10.0);
 
+        if !self.backtrace.is_empty() {
+            self.show_backtrace(ui);
+            return;
+        }
+
         // Get call stack from debugger
         let call_stack = self.ppu_debugger.get_call_stack();
 
@@ -1061,8 +1499,564 @@ impl DebuggerView {
     }
 }
 
+impl DebuggerView {
+    /// Call stack unwound from the main PPU thread's stack
+    fn show_backtrace(&mut self, ui: &mut egui::Ui) {
+        ui.label(format!("Main PPU thread, {} frames", self.backtrace.len()));
+        ui.add_space(10.0);
+
+        egui::Grid::new("backtrace_grid")
+            .striped(true)
+            .num_columns(4)
+            .spacing([10.0, 4.0])
+            .show(ui, |ui| {
+                ui.label(egui::RichText::new("Frame").strong());
+                ui.label(egui::RichText::new("Address").strong());
+                ui.label(egui::RichText::new("Function").strong());
+                ui.label(egui::RichText::new("Stack Pointer").strong());
+                ui.end_row();
+
+                for (i, frame) in self.backtrace.iter().enumerate() {
+                    ui.label(format!("#{}", i));
+                    ui.label(egui::RichText::new(format!("0x{:08X}", frame.pc)).monospace());
+                    match &frame.function {
+                        Some((name, 0)) => ui.label(name),
+                        Some((name, offset)) => ui.label(format!("{}+0x{:X}", name, offset)),
+                        None => ui.label("<unknown>"),
+                    };
+                    ui.label(egui::RichText::new(format!("0x{:08X}", frame.sp)).monospace());
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(10.0);
+        if ui.button("Copy Stack Trace").clicked() {
+            let text = format_backtrace(&self.backtrace);
+            ui.output_mut(|o| o.copied_text = text);
+            self.status_message = String::from("Stack trace copied to clipboard");
+        }
+    }
+}
+
 impl Default for DebuggerView {
     fn default() -> Self {
         Self::new()
     }
 }
+
+/// Object types in the kernel objects tab filter
+const KERNEL_OBJECT_TYPES: [ObjectType; 17] = [
+    ObjectType::Mutex,
+    ObjectType::Cond,
+    ObjectType::RwLock,
+    ObjectType::Semaphore,
+    ObjectType::EventQueue,
+    ObjectType::EventPort,
+    ObjectType::EventFlag,
+    ObjectType::Barrier,
+    ObjectType::Timer,
+    ObjectType::SpuThreadGroup,
+    ObjectType::SpuThread,
+    ObjectType::File,
+    ObjectType::Directory,
+    ObjectType::PrxModule,
+    ObjectType::Config,
+    ObjectType::InterruptTag,
+    ObjectType::InterruptThread,
+];
+
+/// One-line description of a kernel object for the clipboard
+fn describe_object(object: &ObjectDebugInfo) -> String {
+    let mut line = format!("{} {}", object.object_type.name(), object.id);
+    if let Some(owner) = object.owner {
+        line.push_str(&format!(" owner={}", owner));
+    }
+    if !object.waiters.is_empty() {
+        line.push_str(&format!(" waiters={:?}", object.waiters));
+    }
+    for detail in &object.details {
+        line.push_str(&format!(", {}", detail));
+    }
+    line
+}
+
+impl DebuggerView {
+    /// Live LV2 threads and objects, for spotting threads blocked on each other
+    fn show_kernel_objects(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Kernel Objects");
+        ui.add_space(10.0);
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{} threads, {} objects", self.kernel_threads.len(), self.kernel_objects.len()));
+            ui.separator();
+            ui.label("Show:");
+            egui::ComboBox::from_id_salt("kernel_object_filter")
+                .selected_text(self.kernel_object_filter.map_or("All objects", |t| t.name()))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.kernel_object_filter, None, "All objects");
+                    for object_type in KERNEL_OBJECT_TYPES {
+                        ui.selectable_value(&mut self.kernel_object_filter, Some(object_type), object_type.name());
+                    }
+                });
+            if ui.button("Copy").clicked() {
+                let mut text: Vec<String> = self.kernel_threads.iter()
+                    .map(|t| format!("Thread {} \"{}\" {:?} priority={}", t.id, t.name, t.state, t.priority))
+                    .collect();
+                text.extend(self.kernel_objects.iter().map(describe_object));
+                ui.output_mut(|o| o.copied_text = text.join("\n"));
+                self.status_message = String::from("Kernel objects copied to clipboard");
+            }
+        });
+        ui.add_space(10.0);
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.label(egui::RichText::new("Threads").strong());
+            if self.kernel_threads.is_empty() {
+                ui.label("No LV2 threads");
+            }
+            egui::Grid::new("kernel_threads_grid")
+                .striped(true)
+                .num_columns(7)
+                .spacing([10.0, 4.0])
+                .show(ui, |ui| {
+                    ui.label(egui::RichText::new("ID").strong());
+                    ui.label(egui::RichText::new("Name").strong());
+                    ui.label(egui::RichText::new("State").strong());
+                    ui.label(egui::RichText::new("Priority").strong());
+                    ui.label(egui::RichText::new("Entry").strong());
+                    ui.label(egui::RichText::new("Stack").strong());
+                    ui.label(egui::RichText::new("Blocked On").strong());
+                    ui.end_row();
+
+                    for thread in &self.kernel_threads {
+                        let blocked_on: Vec<String> = self.kernel_objects.iter()
+                            .filter(|object| object.waiters.contains(&thread.id))
+                            .map(|object| format!("{} {}", object.object_type.name(), object.id))
+                            .collect();
+                        let state_color = match thread.state {
+                            ThreadState::Running => egui::Color32::GREEN,
+                            ThreadState::Waiting | ThreadState::Suspended => egui::Color32::YELLOW,
+                            ThreadState::Terminated => egui::Color32::GRAY,
+                            ThreadState::Ready => ui.visuals().text_color(),
+                        };
+                        ui.label(thread.id.to_string());
+                        ui.label(&thread.name);
+                        ui.colored_label(state_color, format!("{:?}", thread.state));
+                        ui.label(thread.priority.to_string());
+                        ui.label(egui::RichText::new(format!("0x{:08X}", thread.entry_point)).monospace());
+                        ui.label(egui::RichText::new(
+                            format!("0x{:08X} ({} KB)", thread.stack_addr, thread.stack_size / 1024),
+                        ).monospace());
+                        ui.label(blocked_on.join(", "));
+                        ui.end_row();
+                    }
+                });
+
+            ui.add_space(10.0);
+            ui.label(egui::RichText::new("Objects").strong());
+            let objects: Vec<&ObjectDebugInfo> = self.kernel_objects.iter()
+                .filter(|object| self.kernel_object_filter.is_none() || self.kernel_object_filter == Some(object.object_type))
+                .collect();
+            if objects.is_empty() {
+                ui.label("No kernel objects");
+            }
+            egui::Grid::new("kernel_objects_grid")
+                .striped(true)
+                .num_columns(5)
+                .spacing([10.0, 4.0])
+                .show(ui, |ui| {
+                    ui.label(egui::RichText::new("ID").strong());
+                    ui.label(egui::RichText::new("Type").strong());
+                    ui.label(egui::RichText::new("Owner").strong());
+                    ui.label(egui::RichText::new("Waiters").strong());
+                    ui.label(egui::RichText::new("State").strong());
+                    ui.end_row();
+
+                    for object in objects {
+                        let waiters: Vec<String> = object.waiters.iter().map(u64::to_string).collect();
+                        ui.label(object.id.to_string());
+                        ui.label(object.object_type.name());
+                        ui.label(object.owner.map(|owner| owner.to_string()).unwrap_or_default());
+                        if waiters.is_empty() {
+                            ui.label("");
+                        } else {
+                            ui.colored_label(egui::Color32::YELLOW, waiters.join(", "));
+                        }
+                        ui.label(object.details.join("\n"));
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+}
+
+impl DebuggerView {
+    /// Apply a change to the patch list and save it
+    fn update_patches(&mut self, change: impl FnOnce(&mut PatchManager, &MemoryManager) -> Result<String, String>) {
+        let (Some(patches), Some(memory)) = (self.patches.clone(), self.patch_memory.clone()) else {
+            self.status_message = String::from("Emulator not connected");
+            return;
+        };
+        let mut patches = patches.lock();
+        self.status_message = match change(&mut patches, &memory) {
+            Ok(message) if patches.title_id().is_none() => format!("{} (not saved, no game loaded)", message),
+            Ok(message) => match patches.save() {
+                Ok(()) => message,
+                Err(e) => format!("{}, but saving failed: {}", message, e),
+            },
+            Err(e) => e,
+        };
+    }
+
+    /// Disassemble instruction words as they would sit at `address`
+    fn disassemble_words(address: u32, words: &[u32]) -> String {
+        words
+            .iter()
+            .enumerate()
+            .map(|(i, &word)| {
+                let addr = address.wrapping_add(i as u32 * 4);
+                format!("{:08X}  {}", addr, PpuDisassembler::disassemble(addr as u64, word).to_string())
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Patch from the editor inputs
+    fn patch_from_inputs(&self) -> Result<CodePatch, String> {
+        let address = self
+            .parse_address(&self.patch_address_input)
+            .map_err(|_| String::from("Invalid patch address"))?;
+        let name = match self.patch_name_input.trim() {
+            "" => format!("Patch 0x{:08X}", address),
+            name => name.to_string(),
+        };
+        Ok(CodePatch::new(&name, address, &self.patch_code_input))
+    }
+
+    /// Runtime code patches and hooks of the running game
+    fn show_patches(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Code Patches");
+        ui.add_space(10.0);
+
+        let Some(patches) = self.patches.clone() else {
+            ui.label("Start the emulator to patch code.");
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("Address:");
+            ui.add(egui::TextEdit::singleline(&mut self.patch_address_input).desired_width(100.0));
+            ui.label("Name:");
+            ui.add(egui::TextEdit::singleline(&mut self.patch_name_input).hint_text("Patch name"));
+        });
+        ui.horizontal(|ui| {
+            if ui.button("NOP").on_hover_text("Replace the instruction with a NOP").clicked() {
+                self.patch_code_input = String::from("nop");
+            }
+            ui.separator();
+            ui.label("Branch to:");
+            ui.add(egui::TextEdit::singleline(&mut self.patch_target_input).desired_width(100.0));
+            for (label, mnemonic) in [("b", "b"), ("bl", "bl")] {
+                if ui.button(label).clicked() {
+                    match self.parse_address(&self.patch_target_input) {
+                        Ok(target) => self.patch_code_input = format!("{} 0x{:08X}", mnemonic, target),
+                        Err(()) => self.status_message = String::from("Invalid branch target"),
+                    }
+                }
+            }
+        });
+        ui.add(
+            egui::TextEdit::multiline(&mut self.patch_code_input)
+                .font(egui::TextStyle::Monospace)
+                .hint_text("li r3, 1\nblr")
+                .desired_rows(4)
+                .desired_width(f32::INFINITY),
+        );
+
+        // Preview of what the patch replaces
+        let preview = self.patch_from_inputs().and_then(|patch| Ok((patch.address, patch.assemble()?)));
+        match &preview {
+            Ok((address, words)) => {
+                let original: Vec<u32> = match &self.patch_memory {
+                    Some(memory) => (0..words.len() as u32)
+                        .map_while(|i| memory.read_be32(address.wrapping_add(i * 4)).ok())
+                        .collect(),
+                    None => Vec::new(),
+                };
+                egui::Grid::new("patch_preview_grid").num_columns(2).spacing([20.0, 4.0]).show(ui, |ui| {
+                    ui.strong("Original");
+                    ui.strong("Patched");
+                    ui.end_row();
+                    ui.monospace(Self::disassemble_words(*address, &original));
+                    ui.monospace(Self::disassemble_words(*address, words));
+                    ui.end_row();
+                });
+            }
+            Err(e) if !self.patch_code_input.trim().is_empty() => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+            Err(_) => {}
+        }
+
+        if ui.add_enabled(preview.is_ok(), egui::Button::new("➕ Add Patch")).clicked() {
+            if let Ok(patch) = self.patch_from_inputs() {
+                self.update_patches(|patches, memory| {
+                    let name = patch.name.clone();
+                    patches.add(patch, memory)?;
+                    Ok(format!("Applied \"{}\"", name))
+                });
+            }
+        }
+
+        ui.separator();
+        match patches.lock().title_id() {
+            Some(title) => ui.strong(format!("Patches for {}", title)),
+            None => ui.strong("Patches (no game loaded)"),
+        };
+
+        let mut toggled = None;
+        let mut removed = None;
+        {
+            let patches = patches.lock();
+            if patches.is_empty() {
+                ui.label("No patches.");
+            }
+            egui::Grid::new("patch_list_grid").striped(true).num_columns(4).show(ui, |ui| {
+                for (index, patch) in patches.patches().enumerate() {
+                    let mut enabled = patch.enabled;
+                    if ui.checkbox(&mut enabled, &patch.name).changed() {
+                        toggled = Some((index, enabled));
+                    }
+                    ui.monospace(format!("0x{:08X}", patch.address));
+                    let mut hover = patch.code.clone();
+                    if let Some(original) = patches.original(index) {
+                        hover.push_str("\n\nReplaced:\n");
+                        hover.push_str(&Self::disassemble_words(patch.address, original));
+                    }
+                    if patches.is_applied(index) {
+                        ui.colored_label(egui::Color32::GREEN, "applied").on_hover_text(hover);
+                    } else {
+                        ui.label("not applied").on_hover_text(hover);
+                    }
+                    if ui.small_button("🗑").on_hover_text("Revert and remove").clicked() {
+                        removed = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+        }
+        if let Some((index, enabled)) = toggled {
+            self.update_patches(|patches, memory| {
+                patches.set_enabled(index, enabled, memory)?;
+                Ok(String::from(if enabled { "Patch applied" } else { "Patch reverted" }))
+            });
+        }
+        if let Some(index) = removed {
+            self.update_patches(|patches, memory| {
+                let patch = patches.remove(index, memory)?;
+                Ok(format!("Removed \"{}\"", patch.name))
+            });
+        }
+
+        let patches = patches.lock();
+        if patches.has_hooks() {
+            ui.separator();
+            ui.strong("Hooks");
+            egui::Grid::new("patch_hooks_grid").striped(true).num_columns(2).show(ui, |ui| {
+                for (address, name) in patches.hooks() {
+                    ui.monospace(format!("0x{:08X}", address));
+                    ui.label(name);
+                    ui.end_row();
+                }
+            });
+        }
+    }
+}
+
+/// Local storage bytes shown per page of the SPU tab
+const SPU_LS_PAGE: u32 = 256;
+
+/// Channel accesses listed in the SPU tab
+const MAX_LISTED_CHANNEL_EVENTS: usize = 200;
+
+impl DebuggerView {
+    /// Local storage, channels and MFC queue of one SPU
+    fn show_spu(&mut self, ui: &mut egui::Ui) {
+        ui.heading("SPU");
+        ui.add_space(10.0);
+
+        if self.spu_threads.is_empty() {
+            ui.label("No SPU threads. Start a game that uses SPUs.");
+            return;
+        }
+        self.spu_selected = self.spu_selected.min(self.spu_threads.len() - 1);
+        let spu = self.spu_selected;
+        let thread = Arc::clone(&self.spu_threads[spu]);
+        // Thread before debugger, the order the emulator locks them in
+        let thread = thread.read();
+        let debugger = Arc::clone(&self.spu_debugger);
+        let mut debugger = debugger.lock();
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("spu_select")
+                .selected_text(format!("SPU {} \"{}\"", spu, thread.name))
+                .show_ui(ui, |ui| {
+                    for (i, spu_thread) in self.spu_threads.iter().enumerate() {
+                        // The selected thread is already locked
+                        let name = if i == spu { thread.name.clone() } else { spu_thread.read().name.clone() };
+                        ui.selectable_value(&mut self.spu_selected, i, format!("SPU {} \"{}\"", i, name));
+                    }
+                });
+            ui.separator();
+            let paused = debugger.is_paused(spu);
+            if ui.add_enabled(paused, egui::Button::new("▶ Continue")).clicked() {
+                debugger.resume(spu);
+            }
+            if ui.add_enabled(!paused, egui::Button::new("⏸ Pause")).clicked() {
+                debugger.pause(spu);
+            }
+            if ui.add_enabled(paused, egui::Button::new("⏭ Step")).clicked() {
+                debugger.step(spu);
+            }
+            ui.separator();
+            let state = match debugger.states.get(spu) {
+                Some(SpuDebugState::Paused) => egui::RichText::new("● Paused").color(egui::Color32::YELLOW),
+                Some(SpuDebugState::Stepping) => egui::RichText::new("● Stepping").color(egui::Color32::BLUE),
+                _ => egui::RichText::new("● Running").color(egui::Color32::GREEN),
+            };
+            ui.label(state);
+            ui.monospace(format!("pc 0x{:05X}", thread.pc()));
+        });
+        ui.add_space(10.0);
+
+        egui::ScrollArea::vertical().id_salt("spu_tab").show(ui, |ui| {
+            ui.collapsing("Local Storage", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Address:");
+                    ui.add(egui::TextEdit::singleline(&mut self.spu_ls_input).desired_width(80.0));
+                    if ui.button("Go").clicked() {
+                        match self.parse_address(&self.spu_ls_input) {
+                            Ok(addr) => self.spu_ls_offset = addr & (SPU_LS_SIZE as u32 - 1) & !0xF,
+                            Err(()) => self.status_message = String::from("Invalid address format"),
+                        }
+                    }
+                    if ui.button("◀").clicked() {
+                        self.spu_ls_offset = self.spu_ls_offset.saturating_sub(SPU_LS_PAGE);
+                    }
+                    if ui.button("▶").clicked() {
+                        self.spu_ls_offset = (self.spu_ls_offset + SPU_LS_PAGE).min(SPU_LS_SIZE as u32 - SPU_LS_PAGE);
+                    }
+                    if ui.button("Next Change").clicked() {
+                        let start = (self.spu_ls_offset + SPU_LS_PAGE).min(SPU_LS_SIZE as u32);
+                        let changed = debugger.changed_bytes(spu, start, &thread.local_storage[start as usize..]);
+                        match changed.iter().position(|&c| c) {
+                            Some(i) => self.spu_ls_offset = (start + i as u32) & !0xF,
+                            None => self.status_message = String::from("No more changes"),
+                        }
+                    }
+                    ui.separator();
+                    if debugger.has_ls_baseline(spu) {
+                        let count = debugger.changed_byte_count(spu, &thread.local_storage[..]);
+                        ui.label(format!("{} bytes changed since the last break", count));
+                    } else {
+                        ui.label("Changes are shown from the second break on");
+                    }
+                });
+
+                let offset = self.spu_ls_offset;
+                let data = debugger.get_local_storage_view(&thread, offset, SPU_LS_PAGE as usize);
+                let changed = debugger.changed_bytes(spu, offset, &data);
+                ui.monospace("Address  00 01 02 03 04 05 06 07 08 09 0A 0B 0C 0D 0E 0F  ASCII");
+                for (row, (bytes, changed)) in data.chunks(16).zip(changed.chunks(16)).enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.spacing_mut().item_spacing.x = 0.0;
+                        ui.monospace(format!("0x{:05X}  ", offset as usize + row * 16));
+                        for (byte, &changed) in bytes.iter().zip(changed) {
+                            let text = egui::RichText::new(format!("{:02X} ", byte)).monospace();
+                            ui.label(if changed { text.color(egui::Color32::from_rgb(255, 140, 0)).strong() } else { text });
+                        }
+                        let ascii: String = bytes
+                            .iter()
+                            .map(|&b| if (0x20..=0x7E).contains(&b) { b as char } else { '.' })
+                            .collect();
+                        ui.monospace(format!(" {}", ascii));
+                    });
+                }
+            });
+
+            ui.collapsing("Channels", |ui| {
+                egui::Grid::new("spu_channels_grid").striped(true).num_columns(4).show(ui, |ui| {
+                    ui.strong("Channel");
+                    ui.strong("#");
+                    ui.strong("Value");
+                    ui.strong("Count");
+                    ui.end_row();
+                    for channel in debugger.get_channel_info(&thread) {
+                        ui.label(&channel.name);
+                        ui.monospace(channel.channel.to_string());
+                        ui.monospace(channel.value.map(|v| format!("0x{:08X}", v)).unwrap_or_else(|| String::from("-")));
+                        ui.monospace(channel.count.to_string());
+                        ui.end_row();
+                    }
+                });
+            });
+
+            ui.collapsing("Channel Activity", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} recent accesses", debugger.channel_activity(spu).count()));
+                    if ui.button("Clear").clicked() {
+                        debugger.clear_channel_activity(spu);
+                    }
+                });
+                let events: Vec<_> = debugger.channel_activity(spu).collect();
+                egui::Grid::new("spu_channel_activity_grid").striped(true).num_columns(5).show(ui, |ui| {
+                    ui.strong("#");
+                    ui.strong("PC");
+                    ui.strong("Access");
+                    ui.strong("Channel");
+                    ui.strong("Value");
+                    ui.end_row();
+                    // Newest first
+                    for event in events.iter().rev().take(MAX_LISTED_CHANNEL_EVENTS) {
+                        ui.monospace(event.sequence.to_string());
+                        ui.monospace(format!("0x{:05X}", event.address));
+                        ui.monospace(event.access.mnemonic());
+                        ui.label(channel_name(event.channel));
+                        let value = egui::RichText::new(format!("0x{:08X}", event.value)).monospace();
+                        if event.stalled {
+                            ui.label(value.color(egui::Color32::YELLOW))
+                                .on_hover_text("The channel was empty or full; hardware would have stalled");
+                        } else {
+                            ui.label(value);
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+
+            ui.collapsing("MFC Queue", |ui| {
+                let queue = debugger.get_mfc_queue(&thread);
+                if queue.is_empty() {
+                    ui.label("Queue empty");
+                    return;
+                }
+                egui::Grid::new("spu_mfc_queue_grid").striped(true).num_columns(5).show(ui, |ui| {
+                    ui.strong("LSA");
+                    ui.strong("EA");
+                    ui.strong("Size");
+                    ui.strong("Tag");
+                    ui.strong("Status");
+                    ui.end_row();
+                    for cmd in queue {
+                        ui.monospace(format!("0x{:05X}", cmd.lsa));
+                        ui.monospace(format!("0x{:08X}", cmd.ea));
+                        ui.monospace(format!("0x{:X}", cmd.size));
+                        ui.monospace(cmd.tag.to_string());
+                        ui.label(&cmd.status);
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+    }
+}
@@ -1,7 +1,11 @@
 //! Game list view
 
+use crate::thumbnails::{GameImage, ThumbnailCache};
 use eframe::egui;
-use std::path::PathBuf;
+use oc_core::config::{Config, PathConfig, PlayHistory};
+use oc_integration::{CompatDatabase, CompatEntry, CompatStatus, GameScanner};
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
 
 /// Game metadata
 #[derive(Debug, Clone)]
@@ -13,17 +17,148 @@ pub struct GameInfo {
     pub region: String,
     /// Game category for filtering (e.g., "Action", "RPG", "Sports")
     pub category: String,
-    /// Icon data (PNG format)
-    pub icon_data: Option<Vec<u8>>,
+    /// ICON0.PNG of the game
+    pub icon_path: Option<PathBuf>,
+    /// PIC1.PNG of the game
+    pub background_path: Option<PathBuf>,
     /// Last played timestamp (Unix timestamp)
     pub last_played: Option<u64>,
 }
 
+impl GameInfo {
+    /// Game info of a game found by the scanner
+    pub fn from_scan(game: &oc_integration::GameInfo) -> Self {
+        Self {
+            title: game.title.clone(),
+            path: game.path.clone(),
+            id: game.title_id.clone(),
+            version: game.version.clone(),
+            region: region_from_id(&game.title_id).to_string(),
+            category: category_name(&game.category).to_string(),
+            icon_path: GameScanner::find_image(&game.path, GameImage::Icon.file_name()),
+            background_path: GameScanner::find_image(&game.path, GameImage::Background.file_name()),
+            last_played: None,
+        }
+    }
+
+    /// Path of one of the game's images
+    pub fn image_path(&self, image: GameImage) -> Option<&Path> {
+        match image {
+            GameImage::Icon => self.icon_path.as_deref(),
+            GameImage::Background => self.background_path.as_deref(),
+        }
+    }
+}
+
+/// Region of a title ID such as BLUS00001, from its third letter
+pub fn region_from_id(id: &str) -> &'static str {
+    match id.as_bytes().get(2) {
+        Some(b'U') => "US",
+        Some(b'E') => "EU",
+        Some(b'J') => "JP",
+        Some(b'A') => "Asia",
+        Some(b'K') => "KR",
+        Some(b'H') => "HK",
+        _ => "Unknown",
+    }
+}
+
+/// Display name of a PARAM.SFO category code
+fn category_name(category: &str) -> &str {
+    match category {
+        "DG" => "Disc Game",
+        "HG" => "HDD Game",
+        "GD" => "Game Data",
+        "2G" | "2D" => "PS2 Game",
+        "1P" => "PS1 Game",
+        "MN" => "PSP Minis",
+        "PE" | "PP" => "PSP Game",
+        "AV" | "AM" => "Application",
+        _ => category,
+    }
+}
+
+/// How long ago a Unix timestamp was
+pub(crate) fn format_last_played(timestamp: u64) -> String {
+    match unix_now().saturating_sub(timestamp) {
+        0..=59 => "Just now".to_string(),
+        secs @ 60..=3599 => format!("{} min ago", secs / 60),
+        secs @ 3600..=86399 => format!("{} h ago", secs / 3600),
+        secs => format!("{} days ago", secs / 86400),
+    }
+}
+
+/// Color of a compatibility badge
+fn compat_color(status: CompatStatus) -> egui::Color32 {
+    match status {
+        CompatStatus::Nothing => egui::Color32::from_rgb(200, 60, 60),
+        CompatStatus::Intro => egui::Color32::from_rgb(230, 140, 40),
+        CompatStatus::Ingame => egui::Color32::from_rgb(215, 195, 50),
+        CompatStatus::Playable => egui::Color32::from_rgb(60, 170, 80),
+    }
+}
+
+/// Compatibility badge of a game, or a dimmed "Untested"
+fn compat_badge(ui: &mut egui::Ui, entry: Option<&CompatEntry>, size: f32) -> egui::Response {
+    match entry {
+        Some(entry) => {
+            let response = ui.label(
+                egui::RichText::new(format!(" {} ", entry.status.as_str()))
+                    .size(size)
+                    .strong()
+                    .color(egui::Color32::BLACK)
+                    .background_color(compat_color(entry.status)),
+            );
+            let mut hover = entry.status.description().to_string();
+            if !entry.notes.is_empty() {
+                hover.push_str(&format!("\n{}", entry.notes));
+            }
+            if !entry.date.is_empty() {
+                hover.push_str(&format!("\nTested {}", entry.date));
+                if !entry.version.is_empty() {
+                    hover.push_str(&format!(" on {}", entry.version));
+                }
+            }
+            response.on_hover_text(hover)
+        }
+        None => ui.label(egui::RichText::new("Untested").size(size).weak()),
+    }
+}
+
+/// Compatibility result being reported for a game
+struct CompatReport {
+    title_id: String,
+    title: String,
+    status: CompatStatus,
+    notes: String,
+    /// Whether the game already has a local result
+    existing: bool,
+    error: Option<String>,
+}
+
+/// Time played, in hours and minutes
+pub(crate) fn format_playtime(secs: u64) -> String {
+    match secs {
+        0..=59 => "< 1 min".to_string(),
+        60..=3599 => format!("{} min", secs / 60),
+        _ => format!("{} h {} min", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+/// Seconds since the Unix epoch
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
 /// Display mode for game list
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DisplayMode {
     Grid,
-    List,
+    /// A table next to the box art, backdrop and details of the selected game
+    Details,
 }
 
 /// Filter by game category
@@ -84,6 +219,7 @@ impl CategoryFilter {
 pub enum SortOrder {
     TitleAZ,
     TitleZA,
+    TitleId,
     RecentlyPlayed,
     MostPlayed,
 }
@@ -93,10 +229,21 @@ impl SortOrder {
         match self {
             SortOrder::TitleAZ => "Title (A-Z)",
             SortOrder::TitleZA => "Title (Z-A)",
+            SortOrder::TitleId => "Title ID",
             SortOrder::RecentlyPlayed => "Recently Played",
             SortOrder::MostPlayed => "Most Played",
         }
     }
+
+    pub fn all() -> Vec<SortOrder> {
+        vec![
+            SortOrder::TitleAZ,
+            SortOrder::TitleZA,
+            SortOrder::TitleId,
+            SortOrder::RecentlyPlayed,
+            SortOrder::MostPlayed,
+        ]
+    }
 }
 
 /// Game list view state
@@ -113,10 +260,30 @@ pub struct GameListView {
     category_filter: CategoryFilter,
     /// Sort order
     sort_order: SortOrder,
-    /// Recent games (up to 10 most recently played)
-    recent_games: Vec<String>, // Store game IDs
-    /// Loaded icon textures (game_id -> texture_handle)
-    icon_textures: std::collections::HashMap<String, egui::TextureHandle>,
+    /// Last played times, playtime and favorites of titles
+    history: PlayHistory,
+    /// Show only favorite games
+    favorites_only: bool,
+    /// Whether the last game launched was picked from the Continue row
+    resume_request: bool,
+    /// Icon and background textures
+    thumbnails: ThumbnailCache,
+    /// Directories scanned for games
+    search_dirs: Vec<PathBuf>,
+    /// Game whose settings were asked for
+    configure_request: Option<usize>,
+    /// Game whose PARAM.SFO was asked to be edited
+    sfo_request: Option<usize>,
+    /// Compatibility statuses of titles
+    compat: CompatDatabase,
+    /// Where the compatibility database is refreshed from
+    compat_url: String,
+    /// Download of the compatibility database in progress
+    compat_refresh: Option<JoinHandle<Result<String, String>>>,
+    /// Outcome of the last compatibility refresh
+    compat_message: Option<String>,
+    /// Open compatibility report dialog
+    compat_report: Option<CompatReport>,
 }
 
 impl GameListView {
@@ -129,15 +296,222 @@ impl GameListView {
             selected_game: None,
             category_filter: CategoryFilter::All,
             sort_order: SortOrder::TitleAZ,
-            recent_games: Vec::new(),
-            icon_textures: std::collections::HashMap::new(),
+            history: PlayHistory::load().unwrap_or_else(|e| {
+                tracing::warn!("Failed to load the play history: {}", e);
+                PlayHistory::default()
+            }),
+            favorites_only: false,
+            resume_request: false,
+            thumbnails: ThumbnailCache::new(PathConfig::default().thumbnail_cache),
+            search_dirs: Vec::new(),
+            configure_request: None,
+            sfo_request: None,
+            compat: CompatDatabase::load(Config::config_path().parent().unwrap_or(Path::new("."))),
+            compat_url: String::new(),
+            compat_refresh: None,
+            compat_message: None,
+            compat_report: None,
+        }
+    }
+
+    /// Set where the compatibility database is refreshed from
+    pub fn set_compat_url(&mut self, url: &str) {
+        self.compat_url = url.trim().to_string();
+    }
+
+    /// Download the compatibility database on a background thread
+    fn refresh_compat(&mut self) {
+        if self.compat_refresh.is_some() || self.compat_url.is_empty() {
+            return;
+        }
+        let url = self.compat_url.clone();
+        self.compat_message = None;
+        self.compat_refresh = Some(std::thread::spawn(move || CompatDatabase::fetch(&url)));
+    }
+
+    /// Take the database of a finished refresh
+    fn poll_compat_refresh(&mut self) {
+        if !matches!(&self.compat_refresh, Some(handle) if handle.is_finished()) {
+            return;
+        }
+        let Some(handle) = self.compat_refresh.take() else {
+            return;
+        };
+        let result = handle
+            .join()
+            .unwrap_or_else(|_| Err("Download thread panicked".to_string()))
+            .and_then(|json| self.compat.update_remote(&json));
+        self.compat_message = Some(match result {
+            Ok(count) => format!("Compatibility of {} titles updated", count),
+            Err(e) => {
+                tracing::warn!("Failed to refresh the compatibility database: {}", e);
+                format!("Compatibility refresh failed: {}", e)
+            }
+        });
+    }
+
+    /// Open the dialog reporting how well a game runs
+    fn open_compat_report(&mut self, game: &GameInfo) {
+        let existing = self.compat.local(&game.id);
+        let current = self.compat.get(&game.id);
+        self.compat_report = Some(CompatReport {
+            title_id: game.id.clone(),
+            title: game.title.clone(),
+            status: current.map(|entry| entry.status).unwrap_or(CompatStatus::Nothing),
+            notes: existing.map(|entry| entry.notes.clone()).unwrap_or_default(),
+            existing: existing.is_some(),
+            error: None,
+        });
+    }
+
+    /// Show the compatibility report dialog
+    fn show_compat_report(&mut self, ctx: &egui::Context) {
+        let Some(report) = self.compat_report.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let mut close = false;
+        egui::Window::new("Report Compatibility")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new(&report.title).strong());
+                ui.label(egui::RichText::new(&report.title_id).weak());
+                ui.add_space(4.0);
+
+                for status in CompatStatus::ALL {
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut report.status, status, status.as_str());
+                        ui.label(egui::RichText::new(status.description()).weak());
+                    });
+                }
+                ui.add_space(4.0);
+                ui.label("Notes:");
+                ui.add(
+                    egui::TextEdit::multiline(&mut report.notes)
+                        .desired_rows(3)
+                        .hint_text("Issues, workarounds, settings used..."),
+                );
+                if let Some(error) = &report.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+
+                ui.add_space(4.0);
+                ui.horizontal(|ui| {
+                    if ui.button("💾 Save").clicked() {
+                        let entry = CompatEntry::report(report.status, report.notes.trim());
+                        match self.compat.report(&report.title_id, entry) {
+                            Ok(()) => close = true,
+                            Err(e) => report.error = Some(e),
+                        }
+                    }
+                    if ui
+                        .button("📋 Copy Report")
+                        .on_hover_text("Copy this result as JSON to share it")
+                        .clicked()
+                    {
+                        let entry = CompatEntry::report(report.status, report.notes.trim());
+                        let json = serde_json::json!({ report.title_id.clone(): entry });
+                        ui.output_mut(|o| o.copied_text = serde_json::to_string_pretty(&json).unwrap_or_default());
+                    }
+                    if report.existing && ui.button("🗑 Remove").clicked() {
+                        match self.compat.remove_report(&report.title_id) {
+                            Ok(()) => close = true,
+                            Err(e) => report.error = Some(e),
+                        }
+                    }
+                    if ui.button("Cancel").clicked() {
+                        close = true;
+                    }
+                });
+            });
+        if !open || close {
+            self.compat_report = None;
         }
     }
 
     /// Add a game to the list
-    pub fn add_game(&mut self, game: GameInfo) {
+    pub fn add_game(&mut self, mut game: GameInfo) {
+        game.last_played = self.last_played(&game.id);
         self.games.push(game);
     }
+
+    /// When a title was last started, from the play history
+    fn last_played(&self, title_id: &str) -> Option<u64> {
+        self.history.get(title_id).map(|title| title.last_played).filter(|&time| time > 0)
+    }
+
+    /// Total time a title was played, in seconds
+    fn playtime(&self, title_id: &str) -> u64 {
+        self.history.get(title_id).map_or(0, |title| title.playtime)
+    }
+
+    fn save_history(&self) {
+        if let Err(e) = self.history.save() {
+            tracing::warn!("Failed to save the play history: {}", e);
+        }
+    }
+
+    /// Add time spent playing a title to its playtime
+    pub fn add_playtime(&mut self, game_id: &str, secs: u64) {
+        self.history.add_playtime(game_id, secs);
+        self.save_history();
+    }
+
+    /// Mark or unmark a title as a favorite
+    fn toggle_favorite(&mut self, game_id: &str) {
+        let favorite = !self.history.is_favorite(game_id);
+        self.history.set_favorite(game_id, favorite);
+        self.save_history();
+    }
+
+    /// Take whether the last game launched was picked from the Continue row
+    pub fn take_resume_request(&mut self) -> bool {
+        std::mem::take(&mut self.resume_request)
+    }
+
+    /// Take the game and thumbnail directories from the path settings, returning whether they changed
+    pub fn configure(&mut self, paths: &PathConfig) -> bool {
+        let mut search_dirs = vec![paths.games.clone(), paths.dev_hdd0.join("game")];
+        search_dirs.extend(paths.extra_games.iter().cloned());
+        let changed = search_dirs != self.search_dirs || paths.thumbnail_cache != self.thumbnails.dir();
+        self.search_dirs = search_dirs;
+        self.thumbnails.set_dir(paths.thumbnail_cache.clone());
+        changed
+    }
+
+    /// Scan the search directories again, returning the number of games found
+    ///
+    /// Games added by hand outside the search directories are kept.
+    pub fn refresh(&mut self) -> usize {
+        let mut scanner = GameScanner::new();
+        scanner.set_load_images(false);
+        for dir in &self.search_dirs {
+            scanner.add_search_directory(dir);
+        }
+        let found = match scanner.scan() {
+            Ok(found) => found,
+            Err(e) => {
+                tracing::warn!("Failed to scan for games: {}", e);
+                Vec::new()
+            }
+        };
+
+        let search_dirs = &self.search_dirs;
+        self.games.retain(|game| !search_dirs.iter().any(|dir| game.path.starts_with(dir)));
+        for game in &found {
+            let mut info = GameInfo::from_scan(game);
+            info.last_played = self.last_played(&info.id);
+            self.games.push(info);
+        }
+
+        self.selected_game = None;
+        self.configure_request = None;
+        self.sfo_request = None;
+        self.thumbnails.clear();
+        found.len()
+    }
     
     /// Set category filter
     pub fn set_category_filter(&mut self, category: CategoryFilter) {
@@ -149,60 +523,89 @@ impl GameListView {
         self.sort_order = order;
     }
     
-    /// Mark a game as recently played
+    /// Mark a game as started now
     pub fn mark_as_played(&mut self, game_id: &str) {
-        // Remove if already in list
-        self.recent_games.retain(|id| id != game_id);
-        
-        // Add to front
-        self.recent_games.insert(0, game_id.to_string());
-        
-        // Keep only last 10
-        if self.recent_games.len() > 10 {
-            self.recent_games.truncate(10);
-        }
-        
-        // Update last_played timestamp for the game
-        if let Some(game) = self.games.iter_mut().find(|g| g.id == game_id) {
-            game.last_played = Some(
-                std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs()
-            );
+        let now = unix_now();
+        self.history.mark_played(game_id, now);
+        self.save_history();
+        for game in self.games.iter_mut().filter(|g| g.id == game_id) {
+            game.last_played = Some(now);
         }
     }
-    
-    /// Get recent games list
+
+    /// Most recently played games, newest first
     pub fn recent_games(&self) -> Vec<&GameInfo> {
-        self.recent_games
-            .iter()
-            .filter_map(|id| self.games.iter().find(|g| &g.id == id))
+        self.history
+            .recent(5)
+            .into_iter()
+            .filter_map(|id| self.games.iter().find(|g| g.id == id))
             .collect()
     }
-    
-    /// Load game icon texture from data
-    fn load_icon_texture(&mut self, ctx: &egui::Context, game_id: &str, icon_data: &[u8]) -> Option<egui::TextureHandle> {
-        // Try to decode PNG
-        if let Ok(image) = image::load_from_memory(icon_data) {
-            let size = [image.width() as _, image.height() as _];
-            let image_buffer = image.to_rgba8();
-            let pixels = image_buffer.as_flat_samples();
-            
-            let color_image = egui::ColorImage::from_rgba_unmultiplied(
-                size,
-                pixels.as_slice(),
-            );
-            
-            let texture = ctx.load_texture(
-                game_id,
-                color_image,
-                egui::TextureOptions::LINEAR
+
+    /// Take the PARAM.SFO asked to be edited from a game's menu
+    pub fn take_sfo_request(&mut self) -> Option<PathBuf> {
+        let game = self.sfo_request.take().and_then(|idx| self.games.get(idx))?;
+        GameScanner::find_image(&game.path, "PARAM.SFO")
+    }
+
+    /// Take the game whose settings were asked for from its menu
+    pub fn take_configure_request(&mut self) -> Option<GameInfo> {
+        self.configure_request.take().and_then(|idx| self.games.get(idx)).cloned()
+    }
+
+    /// Menu shown when right-clicking a game
+    fn game_context_menu(&mut self, response: &egui::Response, idx: usize, game: &GameInfo, game_to_launch: &mut Option<PathBuf>) {
+        response.context_menu(|ui| {
+            if ui.button("▶ Launch").clicked() {
+                *game_to_launch = Some(game.path.clone());
+                ui.close_menu();
+            }
+            if ui.button("⚙ Configure...").clicked() {
+                self.configure_request = Some(idx);
+                ui.close_menu();
+            }
+            let favorite_label = if self.history.is_favorite(&game.id) {
+                "☆ Remove from Favorites"
+            } else {
+                "★ Add to Favorites"
+            };
+            if ui.button(favorite_label).clicked() {
+                self.toggle_favorite(&game.id);
+                ui.close_menu();
+            }
+            if ui.button("📝 Report Compatibility...").clicked() {
+                self.open_compat_report(game);
+                ui.close_menu();
+            }
+            let has_sfo = GameScanner::find_image(&game.path, "PARAM.SFO").is_some();
+            if ui.add_enabled(has_sfo, egui::Button::new("🗂 Edit PARAM.SFO...")).clicked() {
+                self.sfo_request = Some(idx);
+                ui.close_menu();
+            }
+        });
+    }
+
+    /// Draw one of a game's images into `rect`, or a placeholder without one
+    fn paint_image(&mut self, ctx: &egui::Context, ui: &egui::Ui, rect: egui::Rect, game: &GameInfo, image: GameImage, placeholder_size: f32) {
+        let texture = game
+            .image_path(image)
+            .and_then(|path| self.thumbnails.get(ctx, path, image));
+        if let Some(texture) = texture {
+            ui.painter().image(
+                texture.id(),
+                rect,
+                egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                egui::Color32::WHITE,
             );
-            
-            Some(texture)
         } else {
-            None
+            ui.painter().rect_filled(rect, 4.0, ui.visuals().window_fill);
+            ui.painter().text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                "📀",
+                egui::FontId::proportional(placeholder_size),
+                ui.visuals().text_color(),
+            );
         }
     }
 
@@ -227,7 +630,9 @@ impl GameListView {
                     _ => game.category.to_lowercase() == self.category_filter.as_str().to_lowercase(),
                 };
                 
-                search_match && category_match
+                let favorite_match = !self.favorites_only || self.history.is_favorite(&game.id);
+
+                search_match && category_match && favorite_match
             })
             .collect();
         
@@ -239,19 +644,21 @@ impl GameListView {
             SortOrder::TitleZA => {
                 games.sort_by(|a, b| b.1.title.to_lowercase().cmp(&a.1.title.to_lowercase()));
             }
+            SortOrder::TitleId => {
+                games.sort_by(|a, b| a.1.id.cmp(&b.1.id));
+            }
             SortOrder::RecentlyPlayed => {
                 games.sort_by(|a, b| {
                     b.1.last_played.unwrap_or(0).cmp(&a.1.last_played.unwrap_or(0))
                 });
             }
             SortOrder::MostPlayed => {
-                // For now, same as recently played
-                // In future, we'd track play count
-                games.sort_by(|a, b| {
-                    b.1.last_played.unwrap_or(0).cmp(&a.1.last_played.unwrap_or(0))
-                });
+                games.sort_by_key(|(_, game)| std::cmp::Reverse(self.playtime(&game.id)));
             }
         }
+
+        // Favorites come first, in the chosen order
+        games.sort_by_key(|(_, game)| !self.history.is_favorite(&game.id));
         
         games
     }
@@ -259,6 +666,7 @@ impl GameListView {
     /// Show the game list view
     pub fn show(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) -> Option<PathBuf> {
         let mut game_to_launch = None;
+        self.poll_compat_refresh();
 
         // Toolbar
         ui.horizontal(|ui| {
@@ -284,12 +692,19 @@ impl GameListView {
             egui::ComboBox::from_id_salt("sort_order")
                 .selected_text(self.sort_order.as_str())
                 .show_ui(ui, |ui| {
-                    ui.selectable_value(&mut self.sort_order, SortOrder::TitleAZ, SortOrder::TitleAZ.as_str());
-                    ui.selectable_value(&mut self.sort_order, SortOrder::TitleZA, SortOrder::TitleZA.as_str());
-                    ui.selectable_value(&mut self.sort_order, SortOrder::RecentlyPlayed, SortOrder::RecentlyPlayed.as_str());
-                    ui.selectable_value(&mut self.sort_order, SortOrder::MostPlayed, SortOrder::MostPlayed.as_str());
+                    for order in SortOrder::all() {
+                        ui.selectable_value(&mut self.sort_order, order, order.as_str());
+                    }
                 });
 
+            if ui
+                .selectable_label(self.favorites_only, "★ Favorites")
+                .on_hover_text("Show only favorite games")
+                .clicked()
+            {
+                self.favorites_only = !self.favorites_only;
+            }
+
             ui.separator();
 
             if ui
@@ -299,86 +714,74 @@ impl GameListView {
                 self.display_mode = DisplayMode::Grid;
             }
             if ui
-                .selectable_label(self.display_mode == DisplayMode::List, "List")
+                .selectable_label(self.display_mode == DisplayMode::Details, "Details")
                 .clicked()
             {
-                self.display_mode = DisplayMode::List;
+                self.display_mode = DisplayMode::Details;
             }
 
             ui.separator();
+            if ui.button("⟳ Rescan").on_hover_text("Scan the game directories again").clicked() {
+                self.refresh();
+            }
             ui.label(format!("{} games", self.games.len()));
+
+            ui.separator();
+            let refreshing = self.compat_refresh.is_some();
+            let hover = if self.compat_url.is_empty() {
+                "Set a compatibility database URL in the general settings".to_string()
+            } else {
+                format!("Download compatibility statuses from {}", self.compat_url)
+            };
+            if ui
+                .add_enabled(!refreshing && !self.compat_url.is_empty(), egui::Button::new("⟳ Compatibility"))
+                .on_hover_text(hover)
+                .clicked()
+            {
+                self.refresh_compat();
+            }
+            if refreshing {
+                ui.spinner();
+            } else if let Some(message) = &self.compat_message {
+                ui.label(egui::RichText::new(message).weak());
+            }
         });
 
         ui.separator();
         
-        // Show recent games section if we have any
-        if !self.recent_games.is_empty() && self.search_query.is_empty() && self.category_filter == CategoryFilter::All {
+        // Continue row with the most recently played games
+        let recent: Vec<GameInfo> = self.recent_games().into_iter().cloned().collect();
+        if !recent.is_empty() && self.search_query.is_empty() && self.category_filter == CategoryFilter::All && !self.favorites_only {
             ui.group(|ui| {
-                ui.label(egui::RichText::new("Recently Played").strong().size(16.0));
+                ui.label(egui::RichText::new("Continue").strong().size(16.0));
                 ui.separator();
-                
+
                 ui.horizontal(|ui| {
-                    // Collect recent games to avoid borrow issues
-                    let recent: Vec<GameInfo> = self.recent_games()
-                        .into_iter()
-                        .cloned()
-                        .collect();
-                    
-                    for game in recent.iter().take(5) {
+                    for game in &recent {
                         let frame = egui::Frame::none()
                             .fill(ui.visuals().faint_bg_color)
                             .stroke(ui.visuals().window_stroke)
                             .rounding(4.0)
                             .inner_margin(4.0);
-                        
+
                         frame.show(ui, |ui| {
                             ui.set_width(120.0);
                             ui.vertical_centered(|ui| {
                                 // Icon or placeholder
-                                let icon_size = egui::vec2(100.0, 100.0);
+                                let icon_size = egui::vec2(112.0, 62.0);
                                 let (rect, response) = ui.allocate_exact_size(icon_size, egui::Sense::click());
-                                
-                                // Try to load texture if we have icon data
-                                if let Some(icon_data) = &game.icon_data {
-                                    if !self.icon_textures.contains_key(&game.id) {
-                                        if let Some(texture) = self.load_icon_texture(ctx, &game.id, icon_data) {
-                                            self.icon_textures.insert(game.id.clone(), texture);
-                                        }
-                                    }
-                                    
-                                    if let Some(texture) = self.icon_textures.get(&game.id) {
-                                        ui.painter().image(
-                                            texture.id(),
-                                            rect,
-                                            egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
-                                            egui::Color32::WHITE,
-                                        );
-                                    } else {
-                                        // Fallback to icon
-                                        ui.painter().rect_filled(rect, 4.0, ui.visuals().window_fill);
-                                        ui.painter().text(
-                                            rect.center(),
-                                            egui::Align2::CENTER_CENTER,
-                                            "📀",
-                                            egui::FontId::proportional(32.0),
-                                            ui.visuals().text_color(),
-                                        );
-                                    }
-                                } else {
-                                    ui.painter().rect_filled(rect, 4.0, ui.visuals().window_fill);
-                                    ui.painter().text(
-                                        rect.center(),
-                                        egui::Align2::CENTER_CENTER,
-                                        "📀",
-                                        egui::FontId::proportional(32.0),
-                                        ui.visuals().text_color(),
-                                    );
-                                }
-                                
+                                self.paint_image(ctx, ui, rect, game, GameImage::Icon, 32.0);
+                                let response = response.on_hover_text(format!(
+                                    "Played {}, {} in total",
+                                    game.last_played.map(format_last_played).unwrap_or_default().to_lowercase(),
+                                    format_playtime(self.playtime(&game.id)),
+                                ));
+
                                 if response.clicked() {
                                     game_to_launch = Some(game.path.clone());
+                                    self.resume_request = true;
                                 }
-                                
+
                                 ui.label(egui::RichText::new(&game.title)
                                     .size(11.0)
                                     .color(ui.visuals().text_color()));
@@ -387,7 +790,7 @@ impl GameListView {
                     }
                 });
             });
-            
+
             ui.add_space(8.0);
             ui.separator();
         }
@@ -404,25 +807,29 @@ impl GameListView {
                 ui.add_space(50.0);
                 if self.games.is_empty() {
                     ui.label("No games found.");
+                    for dir in &self.search_dirs {
+                        ui.label(egui::RichText::new(format!("Searched {}", dir.display())).weak());
+                    }
                     ui.label("Use File > Open Game to add games.");
                 } else {
                     ui.label("No games match your search.");
                 }
             });
         } else {
-            let display_mode = self.display_mode;
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                match display_mode {
-                    DisplayMode::Grid => {
+            match self.display_mode {
+                DisplayMode::Grid => {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
                         self.show_grid(ctx, ui, &filtered, &mut game_to_launch);
-                    }
-                    DisplayMode::List => {
-                        self.show_list(ui, &filtered, &mut game_to_launch);
-                    }
+                    });
                 }
-            });
+                DisplayMode::Details => {
+                    self.show_details(ctx, ui, &filtered, &mut game_to_launch);
+                }
+            }
         }
 
+        self.show_compat_report(ctx);
+
         game_to_launch
     }
 
@@ -450,8 +857,7 @@ impl GameListView {
                         ui.end_row();
                     }
 
-                    let selected = self.selected_game == Some(*idx);
-                    let clicked = self.show_game_card(ctx, ui, game, selected, card_width, card_height, game_to_launch);
+                    let clicked = self.show_game_card(ctx, ui, *idx, game, card_width, card_height, game_to_launch);
                     
                     if clicked {
                         self.selected_game = Some(*idx);
@@ -460,44 +866,140 @@ impl GameListView {
             });
     }
 
-    /// Show games in list mode
-    fn show_list(
+    /// Show games in a table beside the details of the selected game
+    fn show_details(
         &mut self,
+        ctx: &egui::Context,
         ui: &mut egui::Ui,
         games: &[(usize, GameInfo)],
         game_to_launch: &mut Option<PathBuf>,
     ) {
-        egui::Grid::new("game_list")
-            .striped(true)
-            .min_col_width(100.0)
-            .show(ui, |ui| {
-                ui.strong("Title");
-                ui.strong("ID");
-                ui.strong("Version");
-                ui.strong("Region");
-                ui.strong("Actions");
-                ui.end_row();
+        let panel_width = 360.0;
+        let table_width = (ui.available_width() - panel_width - ui.spacing().item_spacing.x).max(200.0);
 
-                for (idx, game) in games {
-                    let selected = self.selected_game == Some(*idx);
+        ui.horizontal_top(|ui| {
+            ui.vertical(|ui| {
+                ui.set_width(table_width);
+                egui::ScrollArea::vertical().id_salt("game_details_table").show(ui, |ui| {
+                    egui::Grid::new("game_list")
+                        .striped(true)
+                        .min_col_width(60.0)
+                        .show(ui, |ui| {
+                            ui.label("");
+                            ui.strong("Title");
+                            ui.strong("ID");
+                            ui.strong("Version");
+                            ui.strong("Region");
+                            ui.strong("Category");
+                            ui.strong("Compatibility");
+                            ui.strong("Last Played");
+                            ui.strong("Playtime");
+                            ui.end_row();
 
-                    if ui
-                        .selectable_label(selected, &game.title)
-                        .clicked()
-                    {
-                        self.selected_game = Some(*idx);
-                    }
+                            for (idx, game) in games {
+                                let selected = self.selected_game == Some(*idx);
+
+                                let (rect, response) = ui.allocate_exact_size(egui::vec2(58.0, 32.0), egui::Sense::click());
+                                self.paint_image(ctx, ui, rect, game, GameImage::Icon, 16.0);
+                                let title = if self.history.is_favorite(&game.id) {
+                                    ui.selectable_label(selected, format!("★ {}", game.title))
+                                } else {
+                                    ui.selectable_label(selected, &game.title)
+                                };
+                                if response.clicked() || title.clicked() {
+                                    self.selected_game = Some(*idx);
+                                }
+                                self.game_context_menu(&title, *idx, game, game_to_launch);
+                                if response.double_clicked() || title.double_clicked() {
+                                    *game_to_launch = Some(game.path.clone());
+                                }
+                                ui.label(&game.id);
+                                ui.label(&game.version);
+                                ui.label(&game.region);
+                                ui.label(&game.category);
+                                compat_badge(ui, self.compat.get(&game.id), 11.0);
+                                ui.label(game.last_played.map(format_last_played).unwrap_or_else(|| "Never".to_string()));
+                                ui.label(format_playtime(self.playtime(&game.id)));
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+
+            ui.separator();
+
+            ui.vertical(|ui| {
+                ui.set_width(panel_width);
+                let Some((idx, game)) = self.selected_game.and_then(|idx| Some((idx, self.games.get(idx)?.clone()))) else {
+                    ui.label(egui::RichText::new("Select a game to see its details.").weak());
+                    return;
+                };
+
+                // Backdrop with the box art over its lower left corner
+                let (rect, _) = ui.allocate_exact_size(egui::vec2(panel_width, panel_width * 9.0 / 16.0), egui::Sense::hover());
+                self.paint_image(ctx, ui, rect, &game, GameImage::Background, 48.0);
+                if game.icon_path.is_some() {
+                    let icon_rect = egui::Rect::from_min_size(
+                        rect.left_bottom() + egui::vec2(8.0, -96.0),
+                        egui::vec2(160.0, 88.0),
+                    );
+                    self.paint_image(ctx, ui, icon_rect, &game, GameImage::Icon, 24.0);
+                }
+
+                ui.add_space(8.0);
+                ui.label(egui::RichText::new(&game.title).strong().size(18.0));
+                egui::Grid::new("game_details").num_columns(2).show(ui, |ui| {
+                    ui.label("Title ID:");
                     ui.label(&game.id);
+                    ui.end_row();
+                    ui.label("Version:");
                     ui.label(&game.version);
+                    ui.end_row();
+                    ui.label("Region:");
                     ui.label(&game.region);
+                    ui.end_row();
+                    ui.label("Category:");
+                    ui.label(&game.category);
+                    ui.end_row();
+                    ui.label("Compatibility:");
+                    let entry = self.compat.get(&game.id);
+                    ui.horizontal(|ui| {
+                        compat_badge(ui, entry, 12.0);
+                        if self.compat.local(&game.id).is_some() {
+                            ui.label(egui::RichText::new("(your result)").weak());
+                        }
+                    });
+                    ui.end_row();
+                    if let Some(notes) = entry.map(|entry| &entry.notes).filter(|notes| !notes.is_empty()) {
+                        ui.label("Notes:");
+                        ui.label(notes);
+                        ui.end_row();
+                    }
+                    ui.label("Last played:");
+                    ui.label(game.last_played.map(format_last_played).unwrap_or_else(|| "Never".to_string()));
+                    ui.end_row();
+                    ui.label("Playtime:");
+                    ui.label(format_playtime(self.playtime(&game.id)));
+                    ui.end_row();
+                    ui.label("Path:");
+                    ui.label(egui::RichText::new(game.path.display().to_string()).small());
+                    ui.end_row();
+                });
 
-                    if ui.button("Launch").clicked() {
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("▶ Launch").clicked() {
                         *game_to_launch = Some(game.path.clone());
                     }
-
-                    ui.end_row();
-                }
+                    if ui.button("⚙ Configure...").clicked() {
+                        self.configure_request = Some(idx);
+                    }
+                    if ui.button("📝 Report Compatibility...").clicked() {
+                        self.open_compat_report(&game);
+                    }
+                });
             });
+        });
     }
 
     /// Show a game card (for grid mode)
@@ -505,12 +1007,13 @@ impl GameListView {
         &mut self,
         ctx: &egui::Context,
         ui: &mut egui::Ui,
+        idx: usize,
         game: &GameInfo,
-        selected: bool,
         width: f32,
         height: f32,
         game_to_launch: &mut Option<PathBuf>,
     ) -> bool {
+        let selected = self.selected_game == Some(idx);
         let frame = if selected {
             egui::Frame::none()
                 .fill(ui.visuals().selection.bg_fill)
@@ -533,46 +1036,11 @@ impl GameListView {
 
             ui.vertical_centered(|ui| {
                 // Icon
-                let icon_size = egui::vec2(width - 16.0, 180.0);
+                // ICON0 is 320x176
+                let icon_size = egui::vec2(width - 16.0, (width - 16.0) * 176.0 / 320.0);
                 let (rect, response) = ui.allocate_exact_size(icon_size, egui::Sense::click());
-                
-                // Try to load texture if we have icon data
-                if let Some(icon_data) = &game.icon_data {
-                    if !self.icon_textures.contains_key(&game.id) {
-                        if let Some(texture) = self.load_icon_texture(ctx, &game.id, icon_data) {
-                            self.icon_textures.insert(game.id.clone(), texture);
-                        }
-                    }
-                    
-                    if let Some(texture) = self.icon_textures.get(&game.id) {
-                        ui.painter().image(
-                            texture.id(),
-                            rect,
-                            egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
-                            egui::Color32::WHITE,
-                        );
-                    } else {
-                        // Fallback to placeholder
-                        ui.painter().rect_filled(rect, 4.0, ui.visuals().window_fill);
-                        ui.painter().text(
-                            rect.center(),
-                            egui::Align2::CENTER_CENTER,
-                            "📀",
-                            egui::FontId::proportional(64.0),
-                            ui.visuals().text_color(),
-                        );
-                    }
-                } else {
-                    // Icon placeholder
-                    ui.painter().rect_filled(rect, 4.0, ui.visuals().window_fill);
-                    ui.painter().text(
-                        rect.center(),
-                        egui::Align2::CENTER_CENTER,
-                        "📀",
-                        egui::FontId::proportional(64.0),
-                        ui.visuals().text_color(),
-                    );
-                }
+                self.paint_image(ctx, ui, rect, game, GameImage::Icon, 64.0);
+                self.game_context_menu(&response, idx, game, game_to_launch);
                 
                 if response.clicked() {
                     clicked = true;
@@ -580,12 +1048,20 @@ impl GameListView {
 
                 ui.add_space(8.0);
 
-                // Title
-                ui.label(
-                    egui::RichText::new(&game.title)
-                        .strong()
-                        .size(14.0),
-                );
+                // Title, starred when a favorite
+                ui.horizontal(|ui| {
+                    let favorite = self.history.is_favorite(&game.id);
+                    let star = if favorite { "★" } else { "☆" };
+                    let hover = if favorite { "Remove from favorites" } else { "Add to favorites" };
+                    if ui.small_button(star).on_hover_text(hover).clicked() {
+                        self.toggle_favorite(&game.id);
+                    }
+                    ui.label(
+                        egui::RichText::new(&game.title)
+                            .strong()
+                            .size(14.0),
+                    );
+                });
 
                 // ID and Version
                 ui.label(
@@ -603,6 +1079,8 @@ impl GameListView {
                     );
                 }
 
+                compat_badge(ui, self.compat.get(&game.id), 11.0);
+
                 ui.add_space(4.0);
 
                 if ui.button("Launch").clicked() {
@@ -623,6 +1101,11 @@ impl GameListView {
     pub fn selected_game(&self) -> Option<&GameInfo> {
         self.selected_game.and_then(|idx| self.games.get(idx))
     }
+
+    /// Find a game by its path
+    pub fn game_by_path(&self, path: &std::path::Path) -> Option<&GameInfo> {
+        self.games.iter().find(|game| game.path == path)
+    }
 }
 
 impl Default for GameListView {
@@ -0,0 +1,111 @@
+//! Settings window scoped to one game
+
+use crate::settings::SettingsPanel;
+use eframe::egui;
+use oc_core::config::{Config, GameConfig};
+
+/// Window editing the CPU, GPU, audio, input and network overrides of one game
+pub struct GameSettingsWindow {
+    /// Overrides of the game being configured
+    game: Option<GameConfig>,
+    /// Title shown in the window caption
+    title: String,
+    /// The global configuration with the game's overrides applied, as edited
+    config: Config,
+    panel: SettingsPanel,
+    /// Last load or save failure
+    error: Option<String>,
+}
+
+impl GameSettingsWindow {
+    /// Create a closed window
+    pub fn new() -> Self {
+        Self {
+            game: None,
+            title: String::new(),
+            config: Config::default(),
+            panel: SettingsPanel::new(),
+            error: None,
+        }
+    }
+
+    /// Start configuring the game with `title_id`
+    pub fn open(&mut self, title_id: &str, title: &str, global: &Config) {
+        self.title = title.to_string();
+        self.error = None;
+        let game = GameConfig::load(title_id).unwrap_or_else(|e| {
+            self.error = Some(format!("Ignoring unreadable {}: {}", GameConfig::path(title_id).display(), e));
+            GameConfig::new(title_id)
+        });
+        self.config = game.apply(global).unwrap_or_else(|_| global.clone());
+        self.game = Some(game);
+    }
+
+    /// Whether a game is being configured
+    pub fn is_open(&self) -> bool {
+        self.game.is_some()
+    }
+
+    /// Show the window, returning the title ID of the game whose overrides were saved
+    pub fn show(&mut self, ctx: &egui::Context, global: &Config) -> Option<String> {
+        let mut game = self.game.take()?;
+        let mut open = true;
+        let mut saved = false;
+
+        egui::Window::new(format!("Configure {}", self.title))
+            .id(egui::Id::new("game_settings"))
+            .open(&mut open)
+            .default_width(600.0)
+            .default_height(500.0)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Settings for {} only. CPU, GPU and audio changes apply the next time it starts.",
+                    game.title_id
+                ));
+                if game.is_empty() {
+                    ui.label(egui::RichText::new("Uses the global configuration").weak());
+                } else {
+                    ui.label(format!("{} settings differ from the global configuration (tabs marked •)", game.len()));
+                }
+                if let Some(ref error) = self.error {
+                    ui.colored_label(egui::Color32::from_rgb(220, 80, 80), error);
+                }
+                ui.separator();
+
+                let overridden: Vec<&str> = GameConfig::SECTIONS
+                    .into_iter()
+                    .filter(|section| game.overrides_section(section))
+                    .collect();
+                let mut changed = self.panel.show_game(ui, &mut self.config, &overridden);
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(!game.is_empty(), egui::Button::new("Reset to Global"))
+                        .clicked()
+                    {
+                        self.config = global.clone();
+                        changed = true;
+                    }
+                });
+
+                if changed {
+                    let result = game.set(global, &self.config).and_then(|_| game.save());
+                    self.error = result.err().map(|e| format!("Failed to save game settings: {}", e));
+                    saved = self.error.is_none();
+                }
+            });
+
+        let title_id = game.title_id.clone();
+        if open {
+            self.game = Some(game);
+        }
+        saved.then_some(title_id)
+    }
+}
+
+impl Default for GameSettingsWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,17 +1,30 @@
 //! User interface for oxidized-cell
 
 pub mod app;
+pub mod cheats;
 pub mod controller_config;
 pub mod debugger;
 pub mod game_list;
+pub mod game_settings;
 pub mod log_viewer;
+pub mod memory_stats;
 pub mod memory_viewer;
+pub mod osk;
+pub mod perf_overlay;
+pub mod pkg_installer;
+pub mod quick_menu;
+pub mod rpcs3_import;
+pub mod savestates;
 pub mod settings;
+pub mod sfo_editor;
 pub mod shader_debugger;
 pub mod themes;
+pub mod thumbnails;
 
 pub use app::OxidizedCellApp;
+pub use cheats::CheatWindow;
 pub use controller_config::ControllerConfig;
 pub use log_viewer::{LogViewer, LogLevel, LogEntry, SharedLogBuffer, create_log_buffer};
+pub use memory_stats::MemoryStatsPanel;
 pub use memory_viewer::MemoryViewer;
 pub use shader_debugger::ShaderDebugger;
@@ -1,8 +1,11 @@
 //! Log viewer panel for displaying emulator logs
 
 use eframe::egui;
-use std::collections::VecDeque;
+use regex::{Regex, RegexBuilder};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Instant;
 use parking_lot::RwLock;
 
 /// Maximum number of log entries to keep
@@ -19,6 +22,8 @@ pub enum LogLevel {
 }
 
 impl LogLevel {
+    const ALL: [LogLevel; 5] = [LogLevel::Trace, LogLevel::Debug, LogLevel::Info, LogLevel::Warn, LogLevel::Error];
+
     fn color(&self) -> egui::Color32 {
         match self {
             LogLevel::Trace => egui::Color32::GRAY,
@@ -40,6 +45,18 @@ impl LogLevel {
     }
 }
 
+impl From<tracing::Level> for LogLevel {
+    fn from(level: tracing::Level) -> Self {
+        match level {
+            tracing::Level::TRACE => LogLevel::Trace,
+            tracing::Level::DEBUG => LogLevel::Debug,
+            tracing::Level::INFO => LogLevel::Info,
+            tracing::Level::WARN => LogLevel::Warn,
+            tracing::Level::ERROR => LogLevel::Error,
+        }
+    }
+}
+
 /// A single log entry
 #[derive(Debug, Clone)]
 pub struct LogEntry {
@@ -71,6 +88,31 @@ pub fn add_log_entry(buffer: &SharedLogBuffer, level: LogLevel, target: &str, me
     });
 }
 
+/// Which levels of one target are shown
+#[derive(Debug, Clone, Copy)]
+struct TargetFilter {
+    shown: bool,
+    /// Overrides the viewer's level (None = use it)
+    min_level: Option<LogLevel>,
+}
+
+impl Default for TargetFilter {
+    fn default() -> Self {
+        Self {
+            shown: true,
+            min_level: None,
+        }
+    }
+}
+
+/// Search pattern compiled from the filter text
+struct Search {
+    text: String,
+    regex_mode: bool,
+    /// Compiled pattern, or why it didn't compile
+    pattern: Result<Regex, String>,
+}
+
 /// Log viewer panel state
 pub struct LogViewer {
     /// Shared log buffer
@@ -79,28 +121,34 @@ pub struct LogViewer {
     min_level: LogLevel,
     /// Filter by text
     filter_text: String,
+    /// Treat the filter text as a regular expression
+    regex_mode: bool,
+    /// Hide entries that don't match the filter text, rather than only highlighting matches
+    hide_unmatched: bool,
+    /// Pattern of the last filter text
+    search: Option<Search>,
+    /// Filters of the targets seen so far
+    targets: BTreeMap<String, TargetFilter>,
     /// Auto-scroll to bottom
     auto_scroll: bool,
+    /// Entries frozen while paused; new entries are still collected
+    paused: Option<Vec<LogEntry>>,
     /// Show timestamps
     show_timestamps: bool,
     /// Show targets (module names)
     show_targets: bool,
     /// Clear logs requested
     clear_requested: bool,
+    /// Result of the last export
+    export_status: Option<Result<String, String>>,
+    /// Timestamps are shown relative to this
+    started: Instant,
 }
 
 impl LogViewer {
     /// Create a new log viewer
     pub fn new() -> Self {
-        Self {
-            log_buffer: create_log_buffer(),
-            min_level: LogLevel::Info,
-            filter_text: String::new(),
-            auto_scroll: true,
-            show_timestamps: false,
-            show_targets: true,
-            clear_requested: false,
-        }
+        Self::with_buffer(create_log_buffer())
     }
 
     /// Create a new log viewer with a shared buffer
@@ -109,10 +157,17 @@ impl LogViewer {
             log_buffer: buffer,
             min_level: LogLevel::Info,
             filter_text: String::new(),
+            regex_mode: false,
+            hide_unmatched: true,
+            search: None,
+            targets: BTreeMap::new(),
             auto_scroll: true,
+            paused: None,
             show_timestamps: false,
             show_targets: true,
             clear_requested: false,
+            export_status: None,
+            started: Instant::now(),
         }
     }
 
@@ -126,42 +181,157 @@ impl LogViewer {
         add_log_entry(&self.log_buffer, level, target, message);
     }
 
+    /// Collect the emulator's tracing events into this viewer
+    pub fn capture_tracing(&self) {
+        let buffer = self.buffer();
+        oc_core::logging::set_log_listener(Some(Box::new(move |level, target, message| {
+            add_log_entry(&buffer, level.into(), target, message);
+        })));
+    }
+
+    /// Compile the filter text if it changed (None = no filter)
+    fn search_pattern(&mut self) -> Option<&Result<Regex, String>> {
+        if self.filter_text.is_empty() {
+            self.search = None;
+            return None;
+        }
+        let current = matches!(
+            self.search,
+            Some(ref search) if search.text == self.filter_text && search.regex_mode == self.regex_mode
+        );
+        if !current {
+            let source = if self.regex_mode {
+                self.filter_text.clone()
+            } else {
+                regex::escape(&self.filter_text)
+            };
+            let pattern = RegexBuilder::new(&source)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| e.to_string());
+            self.search = Some(Search {
+                text: self.filter_text.clone(),
+                regex_mode: self.regex_mode,
+                pattern,
+            });
+        }
+        self.search.as_ref().map(|search| &search.pattern)
+    }
+
+    /// Whether `entry` passes the level and target filters
+    fn level_shown(&self, entry: &LogEntry) -> bool {
+        match self.targets.get(&entry.target) {
+            Some(filter) => filter.shown && entry.level >= filter.min_level.unwrap_or(self.min_level),
+            None => entry.level >= self.min_level,
+        }
+    }
+
+    /// Entries passing the filters, oldest first
+    fn filtered(&self, pattern: Option<&Regex>) -> Vec<LogEntry> {
+        let keep = |entry: &&LogEntry| {
+            let matched = match pattern {
+                Some(pattern) if self.hide_unmatched => {
+                    pattern.is_match(&entry.message) || pattern.is_match(&entry.target)
+                }
+                _ => true,
+            };
+            matched && self.level_shown(entry)
+        };
+        match self.paused {
+            Some(ref snapshot) => snapshot.iter().filter(keep).cloned().collect(),
+            None => self.log_buffer.read().iter().filter(keep).cloned().collect(),
+        }
+    }
+
+    /// Entry as a line of text
+    fn format_entry(&self, entry: &LogEntry) -> String {
+        let mut line = format!("[{}]", entry.level.label());
+        if self.show_timestamps {
+            line.push_str(&format!(" {}", self.timestamp(entry)));
+        }
+        if self.show_targets && !entry.target.is_empty() {
+            line.push_str(&format!(" [{}]", entry.target));
+        }
+        line.push(' ');
+        line.push_str(&entry.message);
+        line
+    }
+
+    /// Time of `entry` since the viewer was created
+    fn timestamp(&self, entry: &LogEntry) -> String {
+        let since = entry.timestamp.saturating_duration_since(self.started);
+        format!("{:.3}s", since.as_secs_f64())
+    }
+
+    /// Write `entries` to `path`, one line each
+    fn export(&self, path: &Path, entries: &[LogEntry]) -> Result<String, String> {
+        let mut text = String::new();
+        for entry in entries {
+            text.push_str(&self.format_entry(entry));
+            text.push('\n');
+        }
+        std::fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(format!("Exported {} entries to {}", entries.len(), path.display()))
+    }
+
     /// Show the log viewer panel
     pub fn show(&mut self, ui: &mut egui::Ui) {
         // Handle clear request
         if self.clear_requested {
             self.log_buffer.write().clear();
+            if let Some(ref mut snapshot) = self.paused {
+                snapshot.clear();
+            }
             self.clear_requested = false;
         }
 
+        // Targets appear in the filter list as they log
+        {
+            let logs = self.log_buffer.read();
+            for entry in logs.iter() {
+                if !self.targets.contains_key(&entry.target) {
+                    self.targets.insert(entry.target.clone(), TargetFilter::default());
+                }
+            }
+        }
+
+        let mut export = false;
+
         // Toolbar
         ui.horizontal(|ui| {
             ui.label("Level:");
             egui::ComboBox::from_id_salt("log_level")
                 .selected_text(self.min_level.label())
                 .show_ui(ui, |ui| {
-                    ui.selectable_value(&mut self.min_level, LogLevel::Trace, "TRACE");
-                    ui.selectable_value(&mut self.min_level, LogLevel::Debug, "DEBUG");
-                    ui.selectable_value(&mut self.min_level, LogLevel::Info, "INFO");
-                    ui.selectable_value(&mut self.min_level, LogLevel::Warn, "WARN");
-                    ui.selectable_value(&mut self.min_level, LogLevel::Error, "ERROR");
+                    for level in LogLevel::ALL {
+                        ui.selectable_value(&mut self.min_level, level, level.label());
+                    }
                 });
 
             ui.separator();
 
-            ui.label("Filter:");
+            ui.label("Search:");
             ui.add(egui::TextEdit::singleline(&mut self.filter_text)
                 .desired_width(150.0)
-                .hint_text("Search logs..."));
+                .hint_text(if self.regex_mode { "Regular expression..." } else { "Search logs..." }));
+            ui.checkbox(&mut self.regex_mode, "Regex");
+            ui.checkbox(&mut self.hide_unmatched, "Only matches");
 
             ui.separator();
 
-            ui.checkbox(&mut self.auto_scroll, "Auto-scroll");
+            ui.checkbox(&mut self.auto_scroll, "Follow");
+            let mut paused = self.paused.is_some();
+            if ui.checkbox(&mut paused, "Pause").changed() {
+                self.paused = paused.then(|| self.log_buffer.read().iter().cloned().collect());
+            }
             ui.checkbox(&mut self.show_timestamps, "Timestamps");
             ui.checkbox(&mut self.show_targets, "Targets");
 
             ui.separator();
 
+            if ui.button("💾 Export...").clicked() {
+                export = true;
+            }
             if ui.button("🗑 Clear").clicked() {
                 self.clear_requested = true;
             }
@@ -169,35 +339,99 @@ impl LogViewer {
             // Show log count
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                 let count = self.log_buffer.read().len();
-                ui.label(format!("{} entries", count));
+                match self.paused {
+                    Some(ref snapshot) => ui.label(format!(
+                        "{} entries ({} new)",
+                        snapshot.len(),
+                        count.saturating_sub(snapshot.len())
+                    )),
+                    None => ui.label(format!("{} entries", count)),
+                };
             });
         });
 
+        // Per-target filters
+        egui::CollapsingHeader::new(format!("Targets ({})", self.targets.len()))
+            .id_salt("log_targets")
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.small_button("Show All").clicked() {
+                        self.targets.values_mut().for_each(|filter| filter.shown = true);
+                    }
+                    if ui.small_button("Hide All").clicked() {
+                        self.targets.values_mut().for_each(|filter| filter.shown = false);
+                    }
+                    if ui.small_button("Reset Levels").clicked() {
+                        self.targets.values_mut().for_each(|filter| filter.min_level = None);
+                    }
+                });
+                egui::ScrollArea::vertical()
+                    .id_salt("log_targets_scroll")
+                    .max_height(160.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("log_target_grid").striped(true).show(ui, |ui| {
+                            for (target, filter) in self.targets.iter_mut() {
+                                let name = if target.is_empty() { "(none)" } else { target.as_str() };
+                                ui.checkbox(&mut filter.shown, egui::RichText::new(name).monospace());
+                                let selected = filter.min_level.map_or("Default", |level| level.label());
+                                egui::ComboBox::from_id_salt(("log_target_level", target.as_str()))
+                                    .selected_text(selected)
+                                    .width(80.0)
+                                    .show_ui(ui, |ui| {
+                                        ui.selectable_value(&mut filter.min_level, None, "Default");
+                                        for level in LogLevel::ALL {
+                                            ui.selectable_value(&mut filter.min_level, Some(level), level.label());
+                                        }
+                                    });
+                                ui.end_row();
+                            }
+                        });
+                    });
+            });
+
         ui.separator();
 
         // Log content
-        let logs = self.log_buffer.read();
-        let filter_lower = self.filter_text.to_lowercase();
-        
-        let filtered_logs: Vec<_> = logs
-            .iter()
-            .filter(|entry| entry.level >= self.min_level)
-            .filter(|entry| {
-                if self.filter_text.is_empty() {
-                    true
-                } else {
-                    entry.message.to_lowercase().contains(&filter_lower)
-                        || entry.target.to_lowercase().contains(&filter_lower)
-                }
-            })
-            .collect();
+        let pattern = match self.search_pattern() {
+            Some(Ok(pattern)) => Some(pattern.clone()),
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::from_rgb(220, 80, 80), format!("Invalid regex: {}", e));
+                None
+            }
+            None => None,
+        };
+        let filtered_logs = self.filtered(pattern.as_ref());
+
+        if export {
+            if let Some(path) = rfd::FileDialog::new()
+                .set_title("Export Log")
+                .set_file_name("oxidized-cell.log")
+                .add_filter("Log Files", &["log", "txt"])
+                .save_file()
+            {
+                self.export_status = Some(self.export(&path, &filtered_logs));
+            }
+        }
+        match self.export_status {
+            Some(Ok(ref message)) => {
+                ui.label(egui::RichText::new(message).weak());
+            }
+            Some(Err(ref e)) => {
+                ui.colored_label(egui::Color32::from_rgb(220, 80, 80), e);
+            }
+            None => {}
+        }
 
         let text_style = egui::TextStyle::Monospace;
         let row_height = ui.text_style_height(&text_style);
-        
+        let font = egui::TextStyle::Monospace.resolve(ui.style());
+        let text_color = ui.visuals().text_color();
+        let highlight = egui::Color32::from_rgb(120, 90, 0);
+
         let scroll_area = egui::ScrollArea::vertical()
+            .id_salt("log_entries")
             .auto_shrink([false; 2])
-            .stick_to_bottom(self.auto_scroll);
+            .stick_to_bottom(self.auto_scroll && self.paused.is_none());
 
         scroll_area.show_rows(ui, row_height, filtered_logs.len(), |ui, row_range| {
             for row in row_range {
@@ -211,9 +445,7 @@ impl LogViewer {
 
                         // Timestamp (optional)
                         if self.show_timestamps {
-                            let elapsed = entry.timestamp.elapsed();
-                            let timestamp = format!("{:.3}s", elapsed.as_secs_f64());
-                            ui.label(egui::RichText::new(timestamp).monospace().weak());
+                            ui.label(egui::RichText::new(self.timestamp(entry)).monospace().weak());
                         }
 
                         // Target (optional)
@@ -223,8 +455,24 @@ impl LogViewer {
                                 .color(egui::Color32::LIGHT_GRAY));
                         }
 
-                        // Message
-                        ui.label(egui::RichText::new(&entry.message).monospace());
+                        // Message, with search matches highlighted
+                        let mut job = egui::text::LayoutJob::default();
+                        let plain = egui::TextFormat::simple(font.clone(), text_color);
+                        let matched = egui::TextFormat {
+                            background: highlight,
+                            ..plain.clone()
+                        };
+                        let mut end = 0;
+                        for found in pattern.iter().flat_map(|pattern| pattern.find_iter(&entry.message)) {
+                            if found.start() == found.end() {
+                                continue;
+                            }
+                            job.append(&entry.message[end..found.start()], 0.0, plain.clone());
+                            job.append(found.as_str(), 0.0, matched.clone());
+                            end = found.end();
+                        }
+                        job.append(&entry.message[end..], 0.0, plain);
+                        ui.label(job);
                     });
                 }
             }
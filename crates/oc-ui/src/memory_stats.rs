@@ -0,0 +1,231 @@
+//! Memory statistics panel showing region usage and fragmentation
+
+use eframe::egui;
+use oc_memory::{MemoryManager, MemoryStats};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Number of cells in the allocation map
+const MAP_CELLS: usize = 512;
+/// Cells per row in the allocation map
+const MAP_COLUMNS: usize = 64;
+/// Interval between automatic refreshes
+const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Memory statistics panel state
+pub struct MemoryStatsPanel {
+    /// Memory manager reference (optional, may not be connected yet)
+    memory: Option<Arc<MemoryManager>>,
+    /// Last computed statistics
+    stats: Option<MemoryStats>,
+    /// Allocation map of the selected region (allocated fraction per cell)
+    allocation_map: Vec<f32>,
+    /// Kernel user memory figures (total, available)
+    user_memory: Option<(usize, usize)>,
+    /// Index of the region shown in the allocation map
+    selected_region: usize,
+    /// Auto-refresh enabled
+    auto_refresh: bool,
+    /// Time of the last refresh
+    last_refresh: Option<Instant>,
+}
+
+impl MemoryStatsPanel {
+    /// Create a new memory statistics panel
+    pub fn new() -> Self {
+        Self {
+            memory: None,
+            stats: None,
+            allocation_map: Vec::new(),
+            user_memory: None,
+            selected_region: 1, // User memory
+            auto_refresh: true,
+            last_refresh: None,
+        }
+    }
+
+    /// Connect to a memory manager
+    pub fn connect(&mut self, memory: Arc<MemoryManager>) {
+        self.memory = Some(memory);
+        self.last_refresh = None;
+    }
+
+    /// Disconnect from memory manager
+    pub fn disconnect(&mut self) {
+        self.memory = None;
+        self.stats = None;
+        self.allocation_map.clear();
+    }
+
+    /// Set the user memory figures reported to the game by the kernel
+    pub fn set_user_memory(&mut self, total: usize, available: usize) {
+        self.user_memory = Some((total, available));
+    }
+
+    /// Recompute statistics from the memory manager
+    fn refresh(&mut self) {
+        let Some(ref memory) = self.memory else {
+            return;
+        };
+
+        let stats = memory.stats();
+        self.selected_region = self.selected_region.min(stats.regions.len().saturating_sub(1));
+        self.allocation_map = stats
+            .regions
+            .get(self.selected_region)
+            .map(|r| memory.allocation_view(r.base, r.size, MAP_CELLS))
+            .unwrap_or_default();
+        self.stats = Some(stats);
+        self.last_refresh = Some(Instant::now());
+    }
+
+    /// Format a byte count for display
+    fn format_bytes(bytes: u64) -> String {
+        if bytes >= 1024 * 1024 {
+            format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+        } else if bytes >= 1024 {
+            format!("{:.1} KB", bytes as f64 / 1024.0)
+        } else {
+            format!("{} B", bytes)
+        }
+    }
+
+    /// Show the memory statistics panel
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let due = self
+            .last_refresh
+            .is_none_or(|t| self.auto_refresh && t.elapsed() >= REFRESH_INTERVAL);
+        if due {
+            self.refresh();
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("🔄 Refresh").clicked() {
+                self.refresh();
+            }
+            ui.checkbox(&mut self.auto_refresh, "Auto-refresh");
+        });
+
+        if self.memory.is_none() {
+            ui.label("Memory not connected");
+            return;
+        }
+
+        if let Some((total, available)) = self.user_memory {
+            ui.label(format!(
+                "Kernel user memory: {} free of {} (sys_memory_get_user_memory_size)",
+                Self::format_bytes(available as u64),
+                Self::format_bytes(total as u64)
+            ));
+        }
+
+        ui.separator();
+
+        let Some(stats) = self.stats.clone() else {
+            return;
+        };
+
+        let mut clicked_region = None;
+        egui::Grid::new("memory_stats_regions")
+            .striped(true)
+            .num_columns(8)
+            .show(ui, |ui| {
+                ui.label(egui::RichText::new("Region").strong());
+                ui.label(egui::RichText::new("Range").strong());
+                ui.label(egui::RichText::new("Mapped").strong());
+                ui.label(egui::RichText::new("Allocated").strong());
+                ui.label(egui::RichText::new("Usage").strong());
+                ui.label(egui::RichText::new("Free blocks").strong());
+                ui.label(egui::RichText::new("Largest free").strong());
+                ui.label(egui::RichText::new("Fragmentation").strong());
+                ui.end_row();
+
+                for (i, region) in stats.regions.iter().enumerate() {
+                    if ui
+                        .selectable_label(i == self.selected_region, region.name)
+                        .clicked()
+                    {
+                        clicked_region = Some(i);
+                    }
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "0x{:08X}-0x{:08X}",
+                            region.base,
+                            region.base as u64 + region.size as u64 - 1
+                        ))
+                        .monospace(),
+                    );
+                    ui.label(Self::format_bytes(region.mapped as u64));
+                    ui.label(Self::format_bytes(region.allocated as u64));
+                    ui.add(
+                        egui::ProgressBar::new(region.usage())
+                            .desired_width(100.0)
+                            .show_percentage(),
+                    );
+                    ui.label(region.free_blocks.to_string());
+                    ui.label(Self::format_bytes(region.largest_free_block as u64));
+                    ui.label(format!("{:.1}%", region.fragmentation() * 100.0));
+                    ui.end_row();
+                }
+            });
+
+        if let Some(i) = clicked_region {
+            self.selected_region = i;
+            self.refresh();
+        }
+
+        ui.label(format!(
+            "Total allocated: {}   Total mapped: {}",
+            Self::format_bytes(stats.total_allocated()),
+            Self::format_bytes(stats.total_mapped())
+        ));
+
+        ui.separator();
+
+        if let Some(region) = stats.regions.get(self.selected_region) {
+            ui.label(
+                egui::RichText::new(format!(
+                    "Allocation map: {} ({} per cell)",
+                    region.name,
+                    Self::format_bytes(region.size as u64 / MAP_CELLS as u64)
+                ))
+                .strong(),
+            );
+            self.show_allocation_map(ui);
+        }
+    }
+
+    /// Draw the allocation map as a grid of cells shaded by occupancy
+    fn show_allocation_map(&self, ui: &mut egui::Ui) {
+        let rows = self.allocation_map.len().div_ceil(MAP_COLUMNS);
+        let cell = (ui.available_width() / MAP_COLUMNS as f32).clamp(4.0, 12.0);
+        let (rect, _) = ui.allocate_exact_size(
+            egui::vec2(cell * MAP_COLUMNS as f32, cell * rows as f32),
+            egui::Sense::hover(),
+        );
+
+        let painter = ui.painter_at(rect);
+        for (i, &used) in self.allocation_map.iter().enumerate() {
+            let x = rect.left() + (i % MAP_COLUMNS) as f32 * cell;
+            let y = rect.top() + (i / MAP_COLUMNS) as f32 * cell;
+            let cell_rect = egui::Rect::from_min_size(
+                egui::pos2(x, y),
+                egui::vec2(cell - 1.0, cell - 1.0),
+            );
+            let color = if used <= 0.0 {
+                egui::Color32::from_gray(40)
+            } else {
+                // Partially used cells show as amber, full cells as red
+                let t = used.clamp(0.0, 1.0);
+                egui::Color32::from_rgb(200 + (55.0 * t) as u8, (180.0 * (1.0 - t)) as u8 + 40, 40)
+            };
+            painter.rect_filled(cell_rect, 0.0, color);
+        }
+    }
+}
+
+impl Default for MemoryStatsPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
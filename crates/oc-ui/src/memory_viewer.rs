@@ -1,9 +1,33 @@
 //! Memory viewer panel for inspecting emulator memory
 
 use eframe::egui;
+use oc_debug::cheats::CheatOp;
+use oc_debug::{Cheat, CheatManager, ScanValueType};
 use oc_memory::MemoryManager;
+use parking_lot::Mutex;
 use std::sync::Arc;
 
+/// Most bytes a selection covers
+const MAX_SELECTION: u32 = 16;
+
+/// Types the inspector shows the selection as
+const INSPECT_TYPES: [ScanValueType; 7] = [
+    ScanValueType::U8,
+    ScanValueType::U16,
+    ScanValueType::U32,
+    ScanValueType::I32,
+    ScanValueType::U64,
+    ScanValueType::F32,
+    ScanValueType::F64,
+];
+
+/// Pane of the hex view being typed into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EditPane {
+    Hex,
+    Ascii,
+}
+
 /// Memory viewer panel state
 pub struct MemoryViewer {
     /// Memory manager reference (optional, may not be connected yet)
@@ -30,6 +54,22 @@ pub struct MemoryViewer {
     display_format: DisplayFormat,
     /// Follow address mode
     follow_address: Option<u32>,
+    /// Cheats of the running game, used to freeze values
+    cheats: Option<Arc<Mutex<CheatManager>>>,
+    /// First selected byte
+    cursor: Option<u32>,
+    /// Bytes selected from the cursor
+    selection_len: u32,
+    /// Pane typed keys edit
+    edit_pane: EditPane,
+    /// High nibble typed into the hex pane, waiting for the low one
+    pending_nibble: Option<u8>,
+    /// Addresses to go back to after following pointers
+    pointer_history: Vec<u32>,
+    /// Type written by the value editor
+    write_type: ScanValueType,
+    /// Value typed into the value editor
+    write_input: String,
 }
 
 /// Display format for memory values
@@ -74,18 +114,28 @@ impl MemoryViewer {
             big_endian: true,
             display_format: DisplayFormat::Hex8,
             follow_address: None,
+            cheats: None,
+            cursor: None,
+            selection_len: 1,
+            edit_pane: EditPane::Hex,
+            pending_nibble: None,
+            pointer_history: Vec::new(),
+            write_type: ScanValueType::U32,
+            write_input: String::new(),
         }
     }
 
-    /// Connect to a memory manager
-    pub fn connect(&mut self, memory: Arc<MemoryManager>) {
+    /// Connect to a memory manager and the cheats used to freeze values
+    pub fn connect(&mut self, memory: Arc<MemoryManager>, cheats: Arc<Mutex<CheatManager>>) {
         self.memory = Some(memory);
+        self.cheats = Some(cheats);
         self.status_message = String::from("Connected to memory");
     }
 
     /// Disconnect from memory manager
     pub fn disconnect(&mut self) {
         self.memory = None;
+        self.cheats = None;
         self.status_message = String::from("Memory not connected");
     }
 
@@ -149,6 +199,271 @@ impl MemoryViewer {
         }
     }
 
+    /// Move the view to `addr` and read it
+    fn go_to(&mut self, addr: u32) {
+        self.set_address(addr);
+        self.refresh_memory();
+    }
+
+    /// Select `len` bytes from `addr`, scrolling the view to keep it visible
+    fn select(&mut self, addr: u32, len: u32) {
+        self.cursor = Some(addr);
+        self.selection_len = len.clamp(1, MAX_SELECTION);
+        self.pending_nibble = None;
+        let end = self.address.saturating_add(self.page_size());
+        if addr < self.address || addr >= end {
+            let row = self.row_size();
+            self.go_to(addr - addr % row);
+        }
+    }
+
+    /// Whether `addr` is selected
+    fn is_selected(&self, addr: u32) -> bool {
+        self.cursor
+            .is_some_and(|cursor| addr >= cursor && addr - cursor < self.selection_len)
+    }
+
+    /// Bytes starting at `addr` (None when unmapped)
+    fn read(&self, addr: u32, len: u32) -> Option<Vec<u8>> {
+        self.memory.as_ref()?.read_bytes(addr, len).ok()
+    }
+
+    /// Write `bytes` at `addr` and re-read the view
+    fn write(&mut self, addr: u32, bytes: &[u8]) -> bool {
+        let Some(ref memory) = self.memory else {
+            return false;
+        };
+        let result = memory.write_bytes(addr, bytes);
+        self.refresh_memory();
+        match result {
+            Ok(()) => {
+                self.status_message = format!("Wrote {} bytes at 0x{:08X}", bytes.len(), addr);
+                true
+            }
+            Err(e) => {
+                self.status_message = format!("Write failed at 0x{:08X}: {}", addr, e);
+                false
+            }
+        }
+    }
+
+    /// Jump to the big-endian address stored at the cursor
+    fn follow_pointer(&mut self) {
+        let Some(cursor) = self.cursor else {
+            return;
+        };
+        match self.read(cursor, 4) {
+            Some(bytes) => {
+                let target = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                self.pointer_history.push(cursor);
+                self.follow_address = None;
+                self.select(target, 4);
+                self.status_message = format!("Followed pointer at 0x{:08X} to 0x{:08X}", cursor, target);
+            }
+            None => self.status_message = format!("Can't read a pointer at 0x{:08X}", cursor),
+        }
+    }
+
+    /// Return to where the last pointer was followed from
+    fn pointer_back(&mut self) {
+        if let Some(addr) = self.pointer_history.pop() {
+            self.select(addr, 4);
+        }
+    }
+
+    /// Byte ranges kept by enabled cheats that write plain addresses
+    fn frozen_ranges(&self) -> Vec<(u32, u32)> {
+        let Some(ref cheats) = self.cheats else {
+            return Vec::new();
+        };
+        let cheats = cheats.lock();
+        let mut ranges = Vec::new();
+        for cheat in cheats.cheats().filter(|cheat| cheat.enabled) {
+            for op in cheat.operations().unwrap_or_default() {
+                match op {
+                    CheatOp::Write { address, bytes } => ranges.push((address, bytes.len() as u32)),
+                    CheatOp::Fill { address, count, address_step: 4, .. } => ranges.push((address, count * 4)),
+                    // Addresses after a pointer move with it
+                    CheatOp::Pointer { .. } => break,
+                    CheatOp::Fill { .. } => {}
+                }
+            }
+        }
+        ranges
+    }
+
+    /// Freeze the selected bytes at their current value
+    fn freeze_selection(&mut self) {
+        let (Some(cursor), Some(cheats)) = (self.cursor, self.cheats.clone()) else {
+            return;
+        };
+        let Some(bytes) = self.read(cursor, self.selection_len) else {
+            self.status_message = format!("Can't read 0x{:08X}", cursor);
+            return;
+        };
+        let cheat = Cheat::freeze(&format!("Memory at 0x{:08X}", cursor), cursor, &bytes);
+        self.status_message = match cheats.lock().add(cheat) {
+            Ok(_) => format!("Froze {} bytes at 0x{:08X}", bytes.len(), cursor),
+            Err(e) => format!("Failed to freeze: {}", e),
+        };
+    }
+
+    /// Remove the freezes that cover the cursor
+    fn unfreeze_selection(&mut self) {
+        let (Some(cursor), Some(cheats)) = (self.cursor, self.cheats.clone()) else {
+            return;
+        };
+        let mut cheats = cheats.lock();
+        let covering: Vec<usize> = cheats
+            .cheats()
+            .enumerate()
+            .filter(|(_, cheat)| {
+                cheat.enabled
+                    && cheat.operations().unwrap_or_default().iter().any(|op| {
+                        matches!(*op, CheatOp::Write { address, ref bytes }
+                            if cursor >= address && cursor - address < bytes.len() as u32)
+                    })
+            })
+            .map(|(index, _)| index)
+            .collect();
+        for &index in covering.iter().rev() {
+            cheats.remove(index);
+        }
+        self.status_message = format!("Removed {} freezes at 0x{:08X}", covering.len(), cursor);
+    }
+
+    /// Handle keys typed while a byte is selected
+    fn handle_edit_keys(&mut self, ui: &egui::Ui) {
+        let Some(cursor) = self.cursor else {
+            return;
+        };
+        // Text fields keep their keys
+        if ui.ctx().memory(|m| m.focused().is_some()) {
+            return;
+        }
+        let events = ui.input(|i| i.events.clone());
+        let row = self.row_size();
+        for event in events {
+            let cursor = self.cursor.unwrap_or(cursor);
+            match event {
+                egui::Event::Text(text) => {
+                    for c in text.chars() {
+                        let cursor = self.cursor.unwrap_or(cursor);
+                        match self.edit_pane {
+                            EditPane::Hex => {
+                                let Some(digit) = c.to_digit(16) else {
+                                    continue;
+                                };
+                                match self.pending_nibble.take() {
+                                    Some(high) => {
+                                        if self.write(cursor, &[(high << 4) | digit as u8]) {
+                                            self.select(cursor.wrapping_add(1), 1);
+                                        }
+                                    }
+                                    None => self.pending_nibble = Some(digit as u8),
+                                }
+                            }
+                            EditPane::Ascii => {
+                                if (' '..='~').contains(&c) && self.write(cursor, &[c as u8]) {
+                                    self.select(cursor.wrapping_add(1), 1);
+                                }
+                            }
+                        }
+                    }
+                }
+                egui::Event::Key { key, pressed: true, modifiers, .. } => match key {
+                    egui::Key::ArrowLeft => self.select(cursor.wrapping_sub(1), 1),
+                    egui::Key::ArrowRight => self.select(cursor.wrapping_add(1), 1),
+                    egui::Key::ArrowUp => self.select(cursor.wrapping_sub(row), 1),
+                    egui::Key::ArrowDown => self.select(cursor.wrapping_add(row), 1),
+                    egui::Key::Tab => {
+                        self.edit_pane = match self.edit_pane {
+                            EditPane::Hex => EditPane::Ascii,
+                            EditPane::Ascii => EditPane::Hex,
+                        };
+                        self.pending_nibble = None;
+                    }
+                    egui::Key::Enter if modifiers.ctrl => self.follow_pointer(),
+                    egui::Key::Backspace if modifiers.ctrl => self.pointer_back(),
+                    egui::Key::Escape => {
+                        self.cursor = None;
+                        self.pending_nibble = None;
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+    }
+
+    /// Show the selection as each inspected type, with a value editor
+    fn show_inspector(&mut self, ui: &mut egui::Ui) {
+        let Some(cursor) = self.cursor else {
+            ui.label(egui::RichText::new("Click a byte to select it; shift-click to extend. Type hex digits or, after Tab, text to edit.").weak());
+            return;
+        };
+        let bytes = self.read(cursor, 8);
+        ui.horizontal_wrapped(|ui| {
+            ui.monospace(format!("0x{:08X} ({} bytes)", cursor, self.selection_len));
+            ui.separator();
+            for value_type in INSPECT_TYPES {
+                let text = match bytes {
+                    Some(ref bytes) => value_type.decode(bytes).to_string(),
+                    None => String::from("--"),
+                };
+                ui.label(egui::RichText::new(format!("{} BE:", value_type.name())).weak());
+                ui.monospace(text);
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("➜ Follow Pointer").on_hover_text("Jump to the big-endian address at the cursor (Ctrl+Enter)").clicked() {
+                self.follow_pointer();
+            }
+            if ui
+                .add_enabled(!self.pointer_history.is_empty(), egui::Button::new("↩ Back"))
+                .on_hover_text("Ctrl+Backspace")
+                .clicked()
+            {
+                self.pointer_back();
+            }
+            ui.separator();
+            let connected = self.cheats.is_some();
+            if ui.add_enabled(connected, egui::Button::new("❄ Freeze")).on_hover_text("Keep the selected bytes at their current value").clicked() {
+                self.freeze_selection();
+            }
+            if ui.add_enabled(connected, egui::Button::new("Unfreeze")).clicked() {
+                self.unfreeze_selection();
+            }
+            ui.separator();
+            egui::ComboBox::from_id_salt("memory_write_type")
+                .selected_text(self.write_type.name())
+                .width(60.0)
+                .show_ui(ui, |ui| {
+                    for value_type in ScanValueType::ALL {
+                        ui.selectable_value(&mut self.write_type, value_type, value_type.name());
+                    }
+                });
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.write_input)
+                    .desired_width(100.0)
+                    .font(egui::TextStyle::Monospace)
+                    .hint_text("Value"),
+            );
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.button("Write").clicked() || submitted {
+                match self.write_type.parse(&self.write_input) {
+                    Some(value) => {
+                        let bytes = self.write_type.encode(value);
+                        self.write(cursor, &bytes);
+                    }
+                    None => {
+                        self.status_message = format!("\"{}\" is not a valid {}", self.write_input.trim(), self.write_type.name());
+                    }
+                }
+            }
+        });
+    }
+
     /// Parse an address from string
     fn parse_address(s: &str) -> Option<u32> {
         let s = s.trim();
@@ -251,12 +566,19 @@ impl MemoryViewer {
 
         ui.separator();
 
+        self.show_inspector(ui);
+        ui.separator();
+
         // Auto-refresh
         if self.auto_refresh && self.memory.is_some() {
             self.refresh_memory();
             ui.ctx().request_repaint();
         }
 
+        if self.display_format == DisplayFormat::Hex8 {
+            self.handle_edit_keys(ui);
+        }
+
         // Memory content
         egui::ScrollArea::vertical()
             .auto_shrink([false; 2])
@@ -298,8 +620,8 @@ impl MemoryViewer {
         }
     }
 
-    /// Show hex8 (byte) grid
-    fn show_hex8_grid(&self, ui: &mut egui::Ui) {
+    /// Show hex8 (byte) grid, where bytes can be selected and edited
+    fn show_hex8_grid(&mut self, ui: &mut egui::Ui) {
         // Header
         ui.horizontal(|ui| {
             ui.monospace("Address    ");
@@ -311,6 +633,12 @@ impl MemoryViewer {
 
         ui.separator();
 
+        let frozen = self.frozen_ranges();
+        let is_frozen = |addr: u32| frozen.iter().any(|&(start, len)| addr >= start && addr - start < len);
+        let selected_color = ui.visuals().selection.bg_fill;
+        let frozen_color = egui::Color32::from_rgb(40, 90, 140);
+        let mut clicked = None;
+
         // Data rows
         for row in 0..self.rows {
             let row_addr = self.address.wrapping_add(self.row_offset(row));
@@ -323,36 +651,64 @@ impl MemoryViewer {
 
                 // Hex bytes
                 for i in row_start..row_end {
+                    let addr = self.address.wrapping_add(i as u32);
                     let byte = self.cached_data.get(i).copied().unwrap_or(0);
                     let color = if byte == 0 {
                         egui::Color32::GRAY
                     } else {
                         ui.visuals().text_color()
                     };
-                    ui.monospace(egui::RichText::new(format!("{:02X} ", byte)).color(color));
+                    let mut text = egui::RichText::new(format!("{:02X}", byte)).monospace().color(color);
+                    if self.is_selected(addr) {
+                        text = text.background_color(selected_color);
+                        if self.cursor == Some(addr) && self.pending_nibble.is_some() {
+                            text = text.underline();
+                        }
+                    } else if is_frozen(addr) {
+                        text = text.background_color(frozen_color);
+                    }
+                    let response = ui.add(egui::Label::new(text).sense(egui::Sense::click()));
+                    if response.clicked() {
+                        clicked = Some((addr, EditPane::Hex, ui.input(|i| i.modifiers.shift)));
+                    }
                 }
 
                 // Padding for incomplete rows
                 for _ in row_end..row_start + self.bytes_per_row {
-                    ui.monospace("   ");
+                    ui.monospace("  ");
                 }
 
                 ui.monospace(" ");
 
                 // ASCII representation
-                let ascii: String = (row_start..row_end)
-                    .map(|i| {
+                ui.horizontal(|ui| {
+                    ui.spacing_mut().item_spacing.x = 0.0;
+                    for i in row_start..row_end {
+                        let addr = self.address.wrapping_add(i as u32);
                         let byte = self.cached_data.get(i).copied().unwrap_or(0);
-                        if byte >= 0x20 && byte <= 0x7E {
-                            byte as char
-                        } else {
-                            '.'
+                        let c = if (0x20..=0x7E).contains(&byte) { byte as char } else { '.' };
+                        let mut text = egui::RichText::new(c.to_string()).monospace();
+                        if self.is_selected(addr) {
+                            text = text.background_color(selected_color);
+                        } else if is_frozen(addr) {
+                            text = text.background_color(frozen_color);
                         }
-                    })
-                    .collect();
-                ui.monospace(ascii);
+                        let response = ui.add(egui::Label::new(text).sense(egui::Sense::click()));
+                        if response.clicked() {
+                            clicked = Some((addr, EditPane::Ascii, ui.input(|i| i.modifiers.shift)));
+                        }
+                    }
+                });
             });
         }
+
+        if let Some((addr, pane, extend)) = clicked {
+            self.edit_pane = pane;
+            match self.cursor {
+                Some(cursor) if extend && addr >= cursor => self.select(cursor, addr - cursor + 1),
+                _ => self.select(addr, 1),
+            }
+        }
     }
 
     /// Show hex16 grid
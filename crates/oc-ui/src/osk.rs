@@ -0,0 +1,501 @@
+//! On-screen keyboard shown while a game has cellOskDialog open
+//!
+//! Keys are picked with the mouse or the controller (D-pad to move, Cross
+//! to type), or typed on the host keyboard. The kana panels turn typed
+//! romaji into kana as it is entered; there is no kanji conversion.
+
+use eframe::egui;
+use oc_hle::cell_osk_dialog::{panel_mode, romaji_to_kana, to_katakana, OskRequest};
+use oc_input::pad::{PadButtons, PadState};
+
+/// Keys of the Latin panel; shift gives upper case
+const LATIN_ROWS: [&str; 5] = ["1234567890", "qwertyuiop", "asdfghjkl'", "zxcvbnm,.-", "@#&()/:;_!"];
+
+/// Keys of the number panel
+const NUMERAL_ROWS: [&str; 4] = ["789", "456", "123", "0.-"];
+
+/// Keys of the hiragana panel, in gojūon columns; the katakana panel is the same
+const KANA_ROWS: [&str; 6] = [
+    "あかさたなはまやらわ",
+    "いきしちにひみゆりを",
+    "うくすつぬふむよるん",
+    "えけせてねへめ、れー",
+    "おこそとのほも。ろ？",
+    "ぁぃぅぇぉっゃゅょ！",
+];
+
+/// Keyboard panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Panel {
+    Latin,
+    Hiragana,
+    Katakana,
+    Numeral,
+}
+
+impl Panel {
+    const ALL: [Panel; 4] = [Panel::Latin, Panel::Hiragana, Panel::Katakana, Panel::Numeral];
+
+    fn label(self) -> &'static str {
+        match self {
+            Panel::Latin => "ABC",
+            Panel::Hiragana => "かな",
+            Panel::Katakana => "カナ",
+            Panel::Numeral => "123",
+        }
+    }
+
+    /// Whether `request` allows the panel
+    fn allowed(self, request: &OskRequest) -> bool {
+        match self {
+            Panel::Latin => request.allows(panel_mode::ALPHABET | panel_mode::ENGLISH | panel_mode::URL),
+            Panel::Hiragana => request.allows(panel_mode::JAPANESE | panel_mode::JAPANESE_HIRAGANA),
+            Panel::Katakana => request.allows(panel_mode::JAPANESE | panel_mode::JAPANESE_KATAKANA),
+            Panel::Numeral => request.allows(panel_mode::NUMERAL),
+        }
+    }
+
+    /// Panel for a `panel_mode` flag
+    fn from_mode(mode: u32) -> Option<Self> {
+        match mode {
+            panel_mode::ALPHABET | panel_mode::ENGLISH | panel_mode::URL => Some(Panel::Latin),
+            panel_mode::JAPANESE | panel_mode::JAPANESE_HIRAGANA => Some(Panel::Hiragana),
+            panel_mode::JAPANESE_KATAKANA => Some(Panel::Katakana),
+            panel_mode::NUMERAL => Some(Panel::Numeral),
+            _ => None,
+        }
+    }
+
+    fn is_kana(self) -> bool {
+        matches!(self, Panel::Hiragana | Panel::Katakana)
+    }
+}
+
+/// A key of the keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Char(char),
+    Shift,
+    /// Voice the last kana (か → が)
+    Dakuten,
+    /// Half-voice the last kana (は → ぱ)
+    Handakuten,
+    Space,
+    Backspace,
+    NextPanel,
+    Cancel,
+    Done,
+}
+
+impl Key {
+    fn label(self) -> String {
+        match self {
+            Key::Char(c) => c.to_string(),
+            Key::Shift => "⇧".to_string(),
+            Key::Dakuten => "゛".to_string(),
+            Key::Handakuten => "゜".to_string(),
+            Key::Space => "Space".to_string(),
+            Key::Backspace => "⌫".to_string(),
+            Key::NextPanel => "Panel".to_string(),
+            Key::Cancel => "Cancel".to_string(),
+            Key::Done => "Done".to_string(),
+        }
+    }
+}
+
+/// How the user left the keyboard
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OskOutcome {
+    /// Confirmed with this text
+    Done(String),
+    Cancel,
+}
+
+/// Voiced form of a kana, if it has one
+fn with_dakuten(c: char) -> Option<char> {
+    match c {
+        'う' => Some('ゔ'),
+        'ウ' => Some('ヴ'),
+        _ if "かきくけこさしすせそたちつてとはひふへほ".contains(c) => char::from_u32(c as u32 + 1),
+        _ if to_katakana("かきくけこさしすせそたちつてとはひふへほ").contains(c) => char::from_u32(c as u32 + 1),
+        _ => None,
+    }
+}
+
+/// Half-voiced form of a kana, if it has one
+fn with_handakuten(c: char) -> Option<char> {
+    match c {
+        _ if "はひふへほハヒフヘホ".contains(c) => char::from_u32(c as u32 + 2),
+        _ => None,
+    }
+}
+
+/// Virtual keyboard for the game's open cellOskDialog
+pub struct OskOverlay {
+    /// Load count of the request being shown
+    generation: Option<u32>,
+    request: Option<OskRequest>,
+    /// Text entered so far
+    text: String,
+    /// Romaji typed on the host keyboard that is not a kana yet
+    pending: String,
+    panel: Panel,
+    shift: bool,
+    /// Key the controller is on, as (row, column)
+    focus: (usize, usize),
+    /// Pad buttons held on the previous frame, so held buttons act once
+    held: u32,
+}
+
+impl OskOverlay {
+    /// Create a closed keyboard
+    pub fn new() -> Self {
+        Self {
+            generation: None,
+            request: None,
+            text: String::new(),
+            pending: String::new(),
+            panel: Panel::Latin,
+            shift: false,
+            focus: (0, 0),
+            held: 0,
+        }
+    }
+
+    /// Start showing a new request
+    fn open(&mut self, generation: u32, request: OskRequest) {
+        let allowed: Vec<Panel> = Panel::ALL.into_iter().filter(|panel| panel.allowed(&request)).collect();
+        self.panel = Panel::from_mode(request.first_panel)
+            .filter(|panel| allowed.contains(panel))
+            .or_else(|| allowed.first().copied())
+            .unwrap_or(Panel::Latin);
+        self.text = request.init_text.clone();
+        self.pending.clear();
+        self.shift = false;
+        self.focus = (0, 0);
+        self.generation = Some(generation);
+        self.request = Some(request);
+    }
+
+    /// Panels the open request allows
+    fn panels(&self) -> Vec<Panel> {
+        let Some(request) = self.request.as_ref() else {
+            return vec![Panel::Latin];
+        };
+        let panels: Vec<Panel> = Panel::ALL.into_iter().filter(|panel| panel.allowed(request)).collect();
+        if panels.is_empty() {
+            vec![Panel::Latin]
+        } else {
+            panels
+        }
+    }
+
+    /// Key grid of the current panel, with the control row last
+    fn keys(&self) -> Vec<Vec<Key>> {
+        let chars = |rows: &[&str]| -> Vec<Vec<Key>> {
+            rows.iter().map(|row| row.chars().map(Key::Char).collect()).collect()
+        };
+        let mut rows = match self.panel {
+            Panel::Latin if self.shift => chars(&LATIN_ROWS.map(str::to_uppercase).each_ref().map(String::as_str)),
+            Panel::Latin => chars(&LATIN_ROWS),
+            Panel::Hiragana => chars(&KANA_ROWS),
+            Panel::Katakana => chars(&KANA_ROWS.map(to_katakana).each_ref().map(String::as_str)),
+            Panel::Numeral => chars(&NUMERAL_ROWS),
+        };
+        let mut controls = match self.panel {
+            Panel::Latin => vec![Key::Shift],
+            Panel::Hiragana | Panel::Katakana => vec![Key::Dakuten, Key::Handakuten],
+            Panel::Numeral => Vec::new(),
+        };
+        controls.extend([Key::Space, Key::Backspace]);
+        if self.panels().len() > 1 {
+            controls.push(Key::NextPanel);
+        }
+        controls.extend([Key::Cancel, Key::Done]);
+        rows.push(controls);
+        rows
+    }
+
+    /// Append text if it fits in the request's limit
+    fn insert(&mut self, text: &str) {
+        let limit = self.request.as_ref().map_or(0, |request| request.limit as usize);
+        for c in text.chars() {
+            if self.text.encode_utf16().count() + c.len_utf16() > limit {
+                break;
+            }
+            self.text.push(c);
+        }
+    }
+
+    /// Turn the romaji typed so far into kana
+    fn compose(&mut self, finish: bool) {
+        let (kana, pending) = romaji_to_kana(&self.pending, self.panel == Panel::Katakana);
+        self.insert(&kana);
+        self.pending = pending;
+        if finish && !self.pending.is_empty() {
+            // A lone trailing "n" is ん; other unfinished romaji stays as typed
+            let rest = std::mem::take(&mut self.pending);
+            let (kana, _) = romaji_to_kana(&format!("{}'", rest), self.panel == Panel::Katakana);
+            self.insert(kana.trim_end_matches('\''));
+        }
+    }
+
+    /// Act on a key, returning the outcome if it closes the keyboard
+    fn press(&mut self, key: Key) -> Option<OskOutcome> {
+        match key {
+            Key::Char(c) => {
+                self.compose(true);
+                self.insert(&c.to_string());
+                if self.panel == Panel::Latin && self.shift {
+                    self.shift = false;
+                }
+            }
+            Key::Shift => self.shift = !self.shift,
+            Key::Dakuten | Key::Handakuten => {
+                self.compose(true);
+                let change = if key == Key::Dakuten { with_dakuten } else { with_handakuten };
+                if let Some(changed) = self.text.chars().last().and_then(change) {
+                    self.text.pop();
+                    self.text.push(changed);
+                }
+            }
+            Key::Space => {
+                self.compose(true);
+                self.insert(if self.panel.is_kana() { "　" } else { " " });
+            }
+            Key::Backspace => {
+                if self.pending.pop().is_none() {
+                    self.text.pop();
+                }
+            }
+            Key::NextPanel => self.switch_panel(1),
+            Key::Cancel => return Some(OskOutcome::Cancel),
+            Key::Done => {
+                self.compose(true);
+                return Some(OskOutcome::Done(self.text.clone()));
+            }
+        }
+        None
+    }
+
+    /// Move `step` panels along the allowed ones
+    fn switch_panel(&mut self, step: isize) {
+        self.compose(true);
+        let panels = self.panels();
+        let current = panels.iter().position(|&panel| panel == self.panel).unwrap_or(0) as isize;
+        let next = (current + step).rem_euclid(panels.len() as isize) as usize;
+        self.panel = panels[next];
+        self.shift = false;
+        self.focus = (0, 0);
+    }
+
+    /// Handle text and keys typed on the host keyboard
+    fn handle_keyboard(&mut self, ctx: &egui::Context) -> Option<OskOutcome> {
+        let events = ctx.input(|i| i.events.clone());
+        for event in events {
+            match event {
+                egui::Event::Text(text) => {
+                    if self.panel.is_kana() {
+                        for c in text.chars() {
+                            if c.is_ascii_alphabetic() || c == '-' || c == '\'' {
+                                self.pending.push(c);
+                                self.compose(false);
+                            } else {
+                                self.compose(true);
+                                self.insert(&c.to_string());
+                            }
+                        }
+                    } else if self.panel == Panel::Numeral {
+                        let digits: String = text.chars().filter(|c| c.is_ascii_digit() || ".-".contains(*c)).collect();
+                        self.insert(&digits);
+                    } else {
+                        self.insert(&text);
+                    }
+                }
+                egui::Event::Key {
+                    key, pressed: true, ..
+                } => {
+                    let outcome = match key {
+                        egui::Key::Backspace => self.press(Key::Backspace),
+                        egui::Key::Enter => self.press(Key::Done),
+                        egui::Key::Escape => self.press(Key::Cancel),
+                        egui::Key::Tab => {
+                            self.switch_panel(1);
+                            None
+                        }
+                        _ => None,
+                    };
+                    if outcome.is_some() {
+                        return outcome;
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Handle controller navigation, acting on newly pressed buttons only
+    fn handle_pad(&mut self, pad: Option<&PadState>) -> Option<OskOutcome> {
+        let buttons = pad.map_or(0, |pad| pad.buttons);
+        let pressed = PadButtons::from_bits_truncate(buttons & !self.held);
+        self.held = buttons;
+        if pressed.is_empty() {
+            return None;
+        }
+
+        let keys = self.keys();
+        let (mut row, mut column) = self.focus;
+        row = row.min(keys.len() - 1);
+        if pressed.contains(PadButtons::DPAD_UP) {
+            row = (row + keys.len() - 1) % keys.len();
+        }
+        if pressed.contains(PadButtons::DPAD_DOWN) {
+            row = (row + 1) % keys.len();
+        }
+        column = column.min(keys[row].len() - 1);
+        if pressed.contains(PadButtons::DPAD_LEFT) {
+            column = (column + keys[row].len() - 1) % keys[row].len();
+        }
+        if pressed.contains(PadButtons::DPAD_RIGHT) {
+            column = (column + 1) % keys[row].len();
+        }
+        self.focus = (row, column);
+
+        if pressed.contains(PadButtons::L1) {
+            self.switch_panel(-1);
+        }
+        if pressed.contains(PadButtons::R1) {
+            self.switch_panel(1);
+        }
+        let key = if pressed.contains(PadButtons::CROSS) {
+            Some(keys[row][column])
+        } else if pressed.contains(PadButtons::SQUARE) {
+            Some(Key::Backspace)
+        } else if pressed.contains(PadButtons::TRIANGLE) {
+            Some(Key::Space)
+        } else if pressed.contains(PadButtons::START) {
+            Some(Key::Done)
+        } else if pressed.contains(PadButtons::CIRCLE) {
+            Some(Key::Cancel)
+        } else {
+            None
+        };
+        key.and_then(|key| self.press(key))
+    }
+
+    /// Show the keyboard while the game has one open, returning how the user left it
+    ///
+    /// `request` is the game's open keyboard with its load count, and `pad`
+    /// the controller used to navigate it.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        request: Option<(u32, OskRequest)>,
+        pad: Option<&PadState>,
+    ) -> Option<OskOutcome> {
+        let Some((generation, request)) = request else {
+            self.generation = None;
+            self.request = None;
+            return None;
+        };
+        if self.generation != Some(generation) {
+            self.open(generation, request);
+            // Buttons already held when the keyboard opens don't type
+            self.held = pad.map_or(0, |pad| pad.buttons);
+        }
+
+        let mut outcome = self.handle_keyboard(ctx).or_else(|| self.handle_pad(pad));
+        let request = self.request.clone()?;
+
+        egui::Window::new("On-Screen Keyboard")
+            .id(egui::Id::new("osk_dialog"))
+            .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -40.0])
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if !request.message.is_empty() {
+                    ui.label(&request.message);
+                }
+
+                // Text field, with the romaji still being composed underlined
+                egui::Frame::none()
+                    .fill(ui.visuals().extreme_bg_color)
+                    .inner_margin(6.0)
+                    .rounding(4.0)
+                    .show(ui, |ui| {
+                        ui.set_min_width(420.0);
+                        ui.horizontal(|ui| {
+                            let text = if request.is_password() {
+                                "•".repeat(self.text.chars().count())
+                            } else {
+                                self.text.clone()
+                            };
+                            ui.label(egui::RichText::new(text).monospace().size(16.0));
+                            if !self.pending.is_empty() {
+                                ui.label(egui::RichText::new(&self.pending).monospace().size(16.0).underline());
+                            }
+                            ui.label(egui::RichText::new("▏").monospace().size(16.0));
+                        });
+                    });
+                ui.horizontal(|ui| {
+                    for panel in self.panels() {
+                        if ui.selectable_label(self.panel == panel, panel.label()).clicked() && self.panel != panel {
+                            self.compose(true);
+                            self.panel = panel;
+                            self.shift = false;
+                            self.focus = (0, 0);
+                        }
+                    }
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        let used = self.text.encode_utf16().count();
+                        ui.label(egui::RichText::new(format!("{} / {}", used, request.limit)).weak());
+                    });
+                });
+                ui.separator();
+
+                let keys = self.keys();
+                for (row_index, row) in keys.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        for (column, &key) in row.iter().enumerate() {
+                            let focused = self.focus == (row_index, column);
+                            let size = match key {
+                                Key::Char(_) | Key::Dakuten | Key::Handakuten | Key::Shift => egui::vec2(32.0, 32.0),
+                                Key::Space => egui::vec2(120.0, 32.0),
+                                _ => egui::vec2(64.0, 32.0),
+                            };
+                            let selected = focused || (key == Key::Shift && self.shift);
+                            let button = egui::Button::new(egui::RichText::new(key.label()).size(16.0))
+                                .min_size(size)
+                                .selected(selected);
+                            if ui.add(button).clicked() {
+                                self.focus = (row_index, column);
+                                outcome = outcome.take().or_else(|| self.press(key));
+                            }
+                        }
+                    });
+                }
+
+                ui.add_space(4.0);
+                ui.label(
+                    egui::RichText::new(
+                        "✕ Type  □ Delete  △ Space  L1/R1 Panel  START Done  ○ Cancel  (or type on the keyboard)",
+                    )
+                    .small()
+                    .weak(),
+                );
+            });
+
+        if outcome.is_some() {
+            self.generation = None;
+            self.request = None;
+        }
+        outcome
+    }
+}
+
+impl Default for OskOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,118 @@
+//! Performance overlay drawn over the game output
+
+use eframe::egui;
+use oc_integration::perf::{FrameSample, PerfStats, PERF_HISTORY_FRAMES};
+
+/// Width of the overlay panel
+const PANEL_WIDTH: f32 = 260.0;
+
+/// Height of each history graph
+const GRAPH_HEIGHT: f32 = 48.0;
+
+/// Height of a line of text
+const LINE_HEIGHT: f32 = 15.0;
+
+const CPU_COLOR: egui::Color32 = egui::Color32::from_rgb(90, 170, 255);
+const GPU_COLOR: egui::Color32 = egui::Color32::from_rgb(240, 140, 60);
+const GOOD_COLOR: egui::Color32 = egui::Color32::from_rgb(80, 200, 120);
+const SLOW_COLOR: egui::Color32 = egui::Color32::from_rgb(220, 80, 80);
+
+/// Draw `stats` into the top left corner of `rect`
+///
+/// `ui_fps` is the frontend's own frame rate.
+pub fn draw(painter: &egui::Painter, rect: egui::Rect, stats: &PerfStats, ui_fps: f32) {
+    let font = egui::FontId::monospace(12.0);
+    let text_color = egui::Color32::from_gray(230);
+    let lines = [
+        format!("FPS {:6.1}   Speed {:5.1}%", stats.fps, stats.speed_percent),
+        format!(
+            "Frame p50 {:5.1}  p95 {:5.1}  p99 {:5.1} ms",
+            stats.frame_ms_p50, stats.frame_ms_p95, stats.frame_ms_p99
+        ),
+        format!("CPU {:5.1}%   GPU {:5.1}%", stats.cpu_percent, stats.gpu_percent),
+        format!("Shaders {}   UI FPS {:.0}", stats.shader_compiles, ui_fps),
+        format!(
+            "Draws {}   Tris {}   Tex {}",
+            stats.rsx.draw_calls, stats.rsx.triangles, stats.rsx.texture_uploads
+        ),
+        format!(
+            "Surface hit {:5.1}%  Pipeline {:5.1}%",
+            stats.rsx.surface_hit_percent(),
+            stats.rsx.pipeline_hit_percent()
+        ),
+    ];
+
+    let height = lines.len() as f32 * LINE_HEIGHT + 2.0 * (GRAPH_HEIGHT + LINE_HEIGHT) + 16.0;
+    let panel = egui::Rect::from_min_size(rect.min + egui::vec2(8.0, 8.0), egui::vec2(PANEL_WIDTH, height))
+        .intersect(rect);
+    let painter = painter.with_clip_rect(panel);
+    painter.rect_filled(panel, 4.0, egui::Color32::from_black_alpha(180));
+
+    let mut y = panel.min.y + 4.0;
+    let x = panel.min.x + 6.0;
+    for line in lines {
+        painter.text(egui::pos2(x, y), egui::Align2::LEFT_TOP, line, font.clone(), text_color);
+        y += LINE_HEIGHT;
+    }
+
+    // Frame times, scaled so the target sits halfway up
+    y += 4.0;
+    painter.text(egui::pos2(x, y), egui::Align2::LEFT_TOP, "Frame time", font.clone(), text_color);
+    y += LINE_HEIGHT;
+    let graph = egui::Rect::from_min_size(egui::pos2(x, y), egui::vec2(PANEL_WIDTH - 12.0, GRAPH_HEIGHT));
+    let scale = (stats.target_frame_ms * 2.0).max(stats.frame_ms_p99).max(1.0);
+    draw_graph_background(&painter, graph);
+    let target_y = graph.max.y - graph.height() * (stats.target_frame_ms / scale).min(1.0);
+    painter.line_segment(
+        [egui::pos2(graph.min.x, target_y), egui::pos2(graph.max.x, target_y)],
+        egui::Stroke::new(1.0, egui::Color32::from_gray(110)),
+    );
+    let bar_width = graph.width() / PERF_HISTORY_FRAMES as f32;
+    let start = PERF_HISTORY_FRAMES.saturating_sub(stats.history.len());
+    for (index, sample) in stats.history.iter().enumerate() {
+        let left = graph.min.x + (start + index) as f32 * bar_width;
+        let top = graph.max.y - graph.height() * (sample.frame_ms / scale).min(1.0);
+        // Frames more than a tenth over the target are slow
+        let color = if sample.frame_ms > stats.target_frame_ms * 1.1 { SLOW_COLOR } else { GOOD_COLOR };
+        painter.rect_filled(
+            egui::Rect::from_min_max(egui::pos2(left, top), egui::pos2(left + bar_width, graph.max.y)),
+            0.0,
+            color,
+        );
+    }
+    y += GRAPH_HEIGHT + 4.0;
+
+    // CPU and GPU busy share of each frame
+    painter.text(egui::pos2(x, y), egui::Align2::LEFT_TOP, "CPU", font.clone(), CPU_COLOR);
+    painter.text(egui::pos2(x + 32.0, y), egui::Align2::LEFT_TOP, "GPU", font, GPU_COLOR);
+    y += LINE_HEIGHT;
+    let graph = egui::Rect::from_min_size(egui::pos2(x, y), egui::vec2(PANEL_WIDTH - 12.0, GRAPH_HEIGHT));
+    draw_graph_background(&painter, graph);
+    let utilization = |busy: fn(&FrameSample) -> f32| -> Vec<egui::Pos2> {
+        stats
+            .history
+            .iter()
+            .enumerate()
+            .map(|(index, sample)| {
+                let share = if sample.frame_ms > 0.0 { (busy(sample) / sample.frame_ms).min(1.0) } else { 0.0 };
+                egui::pos2(
+                    graph.min.x + (start + index) as f32 * bar_width,
+                    graph.max.y - graph.height() * share,
+                )
+            })
+            .collect()
+    };
+    for (points, color) in [
+        (utilization(|sample| sample.cpu_ms), CPU_COLOR),
+        (utilization(|sample| sample.gpu_ms), GPU_COLOR),
+    ] {
+        if points.len() > 1 {
+            painter.add(egui::Shape::line(points, egui::Stroke::new(1.5, color)));
+        }
+    }
+}
+
+fn draw_graph_background(painter: &egui::Painter, graph: egui::Rect) {
+    painter.rect_filled(graph, 2.0, egui::Color32::from_black_alpha(120));
+    painter.rect_stroke(graph, 2.0, egui::Stroke::new(1.0, egui::Color32::from_gray(70)));
+}
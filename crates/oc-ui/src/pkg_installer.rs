@@ -0,0 +1,286 @@
+//! Queue of PSN packages being installed into dev_hdd0
+
+use eframe::egui;
+use oc_loader::pkg::{find_rap, PkgInstallReport, PkgInstaller};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// Packages installed at the same time
+const MAX_PARALLEL_INSTALLS: usize = 2;
+
+/// Progress shared with an installation thread
+#[derive(Default)]
+struct Progress {
+    done: AtomicU64,
+    total: AtomicU64,
+    cancel: AtomicBool,
+}
+
+enum JobState {
+    /// Waiting for a free installation thread
+    Queued(Box<PkgInstaller>),
+    Installing(JoinHandle<Result<PkgInstallReport, String>>),
+    Done(PkgInstallReport),
+    Failed(String),
+}
+
+/// A package in the queue
+struct InstallJob {
+    path: PathBuf,
+    content_id: String,
+    /// Whether the content needs a RAP license
+    needs_license: bool,
+    rap: Option<PathBuf>,
+    /// Waiting for a RAP license to be chosen or declined
+    held: bool,
+    progress: Arc<Progress>,
+    state: JobState,
+}
+
+impl InstallJob {
+    fn file_name(&self) -> String {
+        self.path.file_name().unwrap_or_default().to_string_lossy().to_string()
+    }
+}
+
+/// Window installing queued packages on background threads
+pub struct PkgInstallWindow {
+    jobs: Vec<InstallJob>,
+    /// dev_hdd0 packages are installed into
+    dev_hdd0: PathBuf,
+}
+
+impl PkgInstallWindow {
+    /// Create a window with an empty queue
+    pub fn new() -> Self {
+        Self {
+            jobs: Vec::new(),
+            dev_hdd0: PathBuf::new(),
+        }
+    }
+
+    /// Install packages queued from now on into `dev_hdd0`
+    pub fn set_dev_hdd0(&mut self, dev_hdd0: &Path) {
+        self.dev_hdd0 = dev_hdd0.to_path_buf();
+    }
+
+    /// Ask for packages and queue them
+    pub fn pick_packages(&mut self) {
+        let files = rfd::FileDialog::new()
+            .set_title("Install PS3 Packages")
+            .add_filter("PS3 Packages", &["pkg"])
+            .add_filter("All Files", &["*"])
+            .pick_files();
+        for path in files.unwrap_or_default() {
+            self.queue(path);
+        }
+    }
+
+    /// Queue the package at `path`, with the RAP license found next to it
+    pub fn queue(&mut self, path: PathBuf) {
+        let progress = Arc::new(Progress::default());
+        let job = match PkgInstaller::open(&path) {
+            Ok(installer) => {
+                let needs_license = installer.drm_type().needs_license();
+                let rap = find_rap(&path, installer.content_id());
+                InstallJob {
+                    content_id: installer.content_id().to_string(),
+                    needs_license,
+                    held: needs_license && rap.is_none(),
+                    rap,
+                    path,
+                    progress,
+                    state: JobState::Queued(Box::new(installer)),
+                }
+            }
+            Err(e) => InstallJob {
+                content_id: String::new(),
+                needs_license: false,
+                rap: None,
+                held: false,
+                path,
+                progress,
+                state: JobState::Failed(e.to_string()),
+            },
+        };
+        self.jobs.push(job);
+    }
+
+    /// Whether packages are being installed or waiting to be
+    pub fn is_busy(&self) -> bool {
+        self.jobs
+            .iter()
+            .any(|job| matches!(job.state, JobState::Queued(_) | JobState::Installing(_)))
+    }
+
+    /// Collect finished installations and start queued ones, returning the reports and errors of those that ended
+    pub fn poll(&mut self) -> Vec<Result<PkgInstallReport, String>> {
+        let mut finished = Vec::new();
+        for job in &mut self.jobs {
+            if matches!(&job.state, JobState::Installing(handle) if handle.is_finished()) {
+                let JobState::Installing(handle) = std::mem::replace(&mut job.state, JobState::Failed(String::new()))
+                else {
+                    unreachable!();
+                };
+                let result = handle
+                    .join()
+                    .unwrap_or_else(|_| Err(format!("Installing {} panicked", job.file_name())));
+                finished.push(result.clone());
+                job.state = match result {
+                    Ok(report) => JobState::Done(report),
+                    Err(e) => JobState::Failed(e),
+                };
+            }
+        }
+
+        let mut running = self.jobs.iter().filter(|job| matches!(job.state, JobState::Installing(_))).count();
+        for job in &mut self.jobs {
+            if running >= MAX_PARALLEL_INSTALLS {
+                break;
+            }
+            if job.held || !matches!(job.state, JobState::Queued(_)) {
+                continue;
+            }
+            let JobState::Queued(mut installer) = std::mem::replace(&mut job.state, JobState::Failed(String::new()))
+            else {
+                unreachable!();
+            };
+            let dev_hdd0 = self.dev_hdd0.clone();
+            let rap = job.rap.clone();
+            let progress = Arc::clone(&job.progress);
+            let name = job.file_name();
+            let handle = std::thread::Builder::new()
+                .name(format!("pkg-install-{}", job.content_id))
+                .spawn(move || {
+                    installer
+                        .install(&dev_hdd0, rap.as_deref(), |done, total| {
+                            progress.done.store(done, Ordering::Relaxed);
+                            progress.total.store(total, Ordering::Relaxed);
+                            !progress.cancel.load(Ordering::Relaxed)
+                        })
+                        .map_err(|e| format!("Failed to install {}: {}", name, e))
+                });
+            job.state = match handle {
+                Ok(handle) => {
+                    running += 1;
+                    JobState::Installing(handle)
+                }
+                Err(e) => JobState::Failed(format!("Failed to start installation thread: {}", e)),
+            };
+        }
+        finished
+    }
+
+    /// Show the queue
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("➕ Add Packages...").clicked() {
+                self.pick_packages();
+            }
+            let has_finished = self
+                .jobs
+                .iter()
+                .any(|job| matches!(job.state, JobState::Done(_) | JobState::Failed(_)));
+            if ui.add_enabled(has_finished, egui::Button::new("Clear Finished")).clicked() {
+                self.jobs
+                    .retain(|job| matches!(job.state, JobState::Queued(_) | JobState::Installing(_)));
+            }
+        });
+        ui.label(egui::RichText::new(format!("Installing into {}", self.dev_hdd0.join("game").display())).weak());
+        ui.separator();
+
+        if self.jobs.is_empty() {
+            ui.label("No packages queued.");
+            return;
+        }
+
+        let mut remove = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (index, job) in self.jobs.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.strong(job.file_name());
+                    if !job.content_id.is_empty() {
+                        ui.label(egui::RichText::new(&job.content_id).weak());
+                    }
+                });
+                match &job.state {
+                    JobState::Queued(_) => {
+                        ui.horizontal(|ui| {
+                            ui.label(if job.held { "Waiting for a license" } else { "Queued" });
+                            if ui.small_button("Remove").clicked() {
+                                remove = Some(index);
+                            }
+                        });
+                    }
+                    JobState::Installing(_) => {
+                        let done = job.progress.done.load(Ordering::Relaxed);
+                        let total = job.progress.total.load(Ordering::Relaxed);
+                        let fraction = if total == 0 { 0.0 } else { done as f32 / total as f32 };
+                        ui.horizontal(|ui| {
+                            let text = format!("{:.1} / {:.1} MB", done as f64 / 1e6, total as f64 / 1e6);
+                            ui.add(egui::ProgressBar::new(fraction).text(text).desired_width(300.0));
+                            let cancelling = job.progress.cancel.load(Ordering::Relaxed);
+                            if ui.add_enabled(!cancelling, egui::Button::new("Cancel").small()).clicked() {
+                                job.progress.cancel.store(true, Ordering::Relaxed);
+                            }
+                        });
+                    }
+                    JobState::Done(report) => {
+                        ui.colored_label(
+                            egui::Color32::from_rgb(80, 200, 120),
+                            format!("✅ Installed {} files to {}", report.files, report.install_dir.display()),
+                        );
+                        if report.missing_license {
+                            ui.colored_label(
+                                egui::Color32::from_rgb(220, 180, 60),
+                                "No RAP license was installed; the game may not start",
+                            );
+                        }
+                    }
+                    JobState::Failed(error) => {
+                        ui.colored_label(egui::Color32::from_rgb(220, 80, 80), format!("❌ {}", error));
+                    }
+                }
+
+                // The license can be chosen until installation starts
+                if job.needs_license {
+                    ui.horizontal(|ui| {
+                        match job.rap {
+                            Some(ref rap) => ui.label(format!("🔑 {}", rap.display())),
+                            None => ui.label("🔑 No RAP license"),
+                        };
+                        if !matches!(job.state, JobState::Queued(_)) {
+                            return;
+                        }
+                        if ui.small_button("Choose RAP...").clicked() {
+                            if let Some(rap) = rfd::FileDialog::new()
+                                .set_title("Choose RAP License")
+                                .add_filter("RAP Licenses", &["rap"])
+                                .pick_file()
+                            {
+                                job.rap = Some(rap);
+                                job.held = false;
+                            }
+                        }
+                        if job.held && ui.small_button("Install Without License").clicked() {
+                            job.held = false;
+                        }
+                    });
+                }
+                ui.separator();
+            }
+        });
+
+        if let Some(index) = remove {
+            self.jobs.remove(index);
+        }
+    }
+}
+
+impl Default for PkgInstallWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
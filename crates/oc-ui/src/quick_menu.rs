@@ -0,0 +1,178 @@
+//! Quick menu shown over the running game
+//!
+//! Opened with the quick menu hotkey or a controller's PS button, like the
+//! console's in-game XMB. The game is paused while it is up. Items are
+//! picked with the mouse or the controller (D-pad to move, Cross to pick,
+//! Circle to resume), so common actions don't need the main window.
+
+use eframe::egui;
+use oc_input::pad::{PadButtons, PadState};
+
+/// What the user picked in the menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuickMenuAction {
+    Resume,
+    SaveState,
+    LoadState,
+    Screenshot,
+    Settings,
+    Exit,
+}
+
+impl QuickMenuAction {
+    /// Items in menu order
+    const ALL: [QuickMenuAction; 6] = [
+        QuickMenuAction::Resume,
+        QuickMenuAction::SaveState,
+        QuickMenuAction::LoadState,
+        QuickMenuAction::Screenshot,
+        QuickMenuAction::Settings,
+        QuickMenuAction::Exit,
+    ];
+
+    fn label(self, quick_slot: usize) -> String {
+        match self {
+            QuickMenuAction::Resume => "▶ Resume Game".to_string(),
+            QuickMenuAction::SaveState => format!("💾 Save State (slot {})", quick_slot),
+            QuickMenuAction::LoadState => format!("📂 Load State (slot {})", quick_slot),
+            QuickMenuAction::Screenshot => "📷 Screenshot".to_string(),
+            QuickMenuAction::Settings => "⚙ Settings".to_string(),
+            QuickMenuAction::Exit => "⏹ Exit Game".to_string(),
+        }
+    }
+}
+
+/// Menu over the game with the common in-game actions
+pub struct QuickMenu {
+    open: bool,
+    /// Whether the menu paused the game, so closing it resumes
+    paused_game: bool,
+    /// Item the controller is on
+    focus: usize,
+    /// Pad buttons held on the previous frame, so held buttons act once
+    held: u32,
+}
+
+impl QuickMenu {
+    /// Create a closed menu
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            paused_game: false,
+            focus: 0,
+            held: 0,
+        }
+    }
+
+    /// Whether the menu is up
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Show the menu; `paused_game` is whether opening it paused the game
+    pub fn open(&mut self, paused_game: bool, pad: Option<&PadState>) {
+        self.open = true;
+        self.paused_game = paused_game;
+        self.focus = 0;
+        // Buttons already held when the menu opens don't pick anything
+        self.held = pad.map_or(0, |pad| pad.buttons);
+    }
+
+    /// Hide the menu, returning whether the game should resume
+    pub fn close(&mut self) -> bool {
+        self.open = false;
+        std::mem::take(&mut self.paused_game)
+    }
+
+    /// Handle controller navigation, acting on newly pressed buttons only
+    fn handle_pad(&mut self, pad: Option<&PadState>) -> Option<QuickMenuAction> {
+        let buttons = pad.map_or(0, |pad| pad.buttons);
+        let pressed = PadButtons::from_bits_truncate(buttons & !self.held);
+        self.held = buttons;
+
+        let count = QuickMenuAction::ALL.len();
+        if pressed.contains(PadButtons::DPAD_UP) {
+            self.focus = (self.focus + count - 1) % count;
+        }
+        if pressed.contains(PadButtons::DPAD_DOWN) {
+            self.focus = (self.focus + 1) % count;
+        }
+        if pressed.contains(PadButtons::CROSS) {
+            Some(QuickMenuAction::ALL[self.focus])
+        } else if pressed.contains(PadButtons::CIRCLE) {
+            Some(QuickMenuAction::Resume)
+        } else {
+            None
+        }
+    }
+
+    /// Handle the host keyboard: arrows to move, Enter to pick, Escape to resume
+    fn handle_keyboard(&mut self, ctx: &egui::Context) -> Option<QuickMenuAction> {
+        let (up, down, enter, escape) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::Enter),
+                i.key_pressed(egui::Key::Escape),
+            )
+        });
+        let count = QuickMenuAction::ALL.len();
+        if up {
+            self.focus = (self.focus + count - 1) % count;
+        }
+        if down {
+            self.focus = (self.focus + 1) % count;
+        }
+        if escape {
+            Some(QuickMenuAction::Resume)
+        } else if enter {
+            Some(QuickMenuAction::ALL[self.focus])
+        } else {
+            None
+        }
+    }
+
+    /// Draw the menu over the game while it is open, returning the picked action
+    ///
+    /// `pad` is the controller used to navigate it and `quick_slot` the
+    /// savestate slot the save and load items use. The menu stays open;
+    /// the caller closes it once it has acted.
+    pub fn show(&mut self, ctx: &egui::Context, pad: Option<&PadState>, quick_slot: usize) -> Option<QuickMenuAction> {
+        if !self.open {
+            return None;
+        }
+        let mut action = self.handle_keyboard(ctx).or_else(|| self.handle_pad(pad));
+
+        // Dim the game behind the menu, below the floating windows
+        let dim = egui::LayerId::new(egui::Order::PanelResizeLine, egui::Id::new("quick_menu_dim"));
+        ctx.layer_painter(dim)
+            .rect_filled(ctx.screen_rect(), 0.0, egui::Color32::from_black_alpha(160));
+
+        egui::Window::new("Quick Menu")
+            .id(egui::Id::new("quick_menu"))
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.set_min_width(260.0);
+                for (index, item) in QuickMenuAction::ALL.into_iter().enumerate() {
+                    let button = egui::Button::new(egui::RichText::new(item.label(quick_slot)).size(16.0))
+                        .min_size(egui::vec2(260.0, 32.0))
+                        .selected(self.focus == index);
+                    if ui.add(button).clicked() {
+                        self.focus = index;
+                        action = action.or(Some(item));
+                    }
+                }
+                ui.add_space(4.0);
+                ui.label(egui::RichText::new("✕ Select  ○ Resume  (or arrows, Enter and Esc)").small().weak());
+            });
+        action
+    }
+}
+
+impl Default for QuickMenu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,258 @@
+//! Wizard importing games, saves and settings from RPCS3
+
+use eframe::egui;
+use oc_core::config::Config;
+use oc_integration::rpcs3_import::disc_key_dir;
+use oc_integration::{CopyProgress, CopyReport, ImportOptions, Rpcs3Install};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// What the settings and game import changed
+#[derive(Default)]
+struct Applied {
+    settings: Vec<String>,
+    games: usize,
+}
+
+enum Step {
+    /// Choosing the RPCS3 folder
+    Locate,
+    /// Choosing what to import from a found installation
+    Review(Box<Rpcs3Install>),
+    /// Copying dev_hdd0 and disc keys
+    Copying(Applied, JoinHandle<CopyReport>),
+    Done(Applied, Option<CopyReport>),
+}
+
+/// Window walking through an RPCS3 import
+pub struct Rpcs3ImportWindow {
+    /// RPCS3 folder being imported
+    root: String,
+    options: ImportOptions,
+    step: Step,
+    progress: Arc<CopyProgress>,
+    error: Option<String>,
+}
+
+impl Rpcs3ImportWindow {
+    /// Create a wizard pointed at RPCS3's usual folder
+    pub fn new() -> Self {
+        Self {
+            root: Rpcs3Install::default_location()
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default(),
+            options: ImportOptions::default(),
+            step: Step::Locate,
+            progress: Arc::new(CopyProgress::default()),
+            error: None,
+        }
+    }
+
+    /// Whether files are being copied
+    pub fn is_busy(&self) -> bool {
+        matches!(self.step, Step::Copying(..))
+    }
+
+    /// Collect a finished copy, returning whether it just finished
+    pub fn poll(&mut self) -> bool {
+        if !matches!(&self.step, Step::Copying(_, handle) if handle.is_finished()) {
+            return false;
+        }
+        let Step::Copying(applied, handle) = std::mem::replace(&mut self.step, Step::Locate) else {
+            unreachable!();
+        };
+        let report = handle.join().unwrap_or_else(|_| CopyReport {
+            errors: vec!["The copy thread panicked".to_string()],
+            ..CopyReport::default()
+        });
+        self.step = Step::Done(applied, Some(report));
+        true
+    }
+
+    /// Show the current step, returning whether `config` was changed
+    pub fn show(&mut self, ui: &mut egui::Ui, config: &mut Config) -> bool {
+        match &self.step {
+            Step::Locate => {
+                self.show_locate(ui);
+                false
+            }
+            Step::Review(_) => self.show_review(ui, config),
+            Step::Copying(..) => {
+                self.show_copying(ui);
+                false
+            }
+            Step::Done(..) => {
+                self.show_done(ui);
+                false
+            }
+        }
+    }
+
+    fn show_locate(&mut self, ui: &mut egui::Ui) {
+        ui.label("Pick the folder holding RPCS3's config.yml, games.yml and dev_hdd0.");
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.root);
+            if ui.button("📁 Browse...").clicked() {
+                if let Some(dir) = rfd::FileDialog::new().set_title("RPCS3 Folder").pick_folder() {
+                    self.root = dir.display().to_string();
+                }
+            }
+        });
+        if let Some(error) = &self.error {
+            ui.colored_label(egui::Color32::from_rgb(220, 80, 80), format!("❌ {}", error));
+        }
+        ui.add_space(8.0);
+        if ui.add_enabled(!self.root.trim().is_empty(), egui::Button::new("Next ▶")).clicked() {
+            match Rpcs3Install::open(std::path::Path::new(self.root.trim())) {
+                Ok(install) => {
+                    self.error = None;
+                    self.step = Step::Review(Box::new(install));
+                }
+                Err(e) => self.error = Some(e),
+            }
+        }
+    }
+
+    fn show_review(&mut self, ui: &mut egui::Ui, config: &mut Config) -> bool {
+        let Step::Review(install) = &self.step else {
+            return false;
+        };
+        ui.label(egui::RichText::new(format!("RPCS3 in {}", install.root.display())).strong());
+        ui.add_space(4.0);
+
+        let options = &mut self.options;
+        ui.add_enabled(install.has_config(), egui::Checkbox::new(&mut options.settings, "Settings from config.yml"))
+            .on_hover_text("CPU, GPU and audio settings with an equivalent here; the rest is left alone");
+        ui.add_enabled(
+            !install.games.is_empty(),
+            egui::Checkbox::new(&mut options.games, format!("{} games from games.yml", install.games.len())),
+        )
+        .on_hover_text("The games stay where they are and their folders are scanned for games");
+        ui.checkbox(
+            &mut options.hdd0,
+            format!(
+                "{} installed games and {} saves from dev_hdd0",
+                install.installed_count(),
+                install.save_count()
+            ),
+        )
+        .on_hover_text(format!(
+            "Copies game, update and DLC data, saves and licenses from {} into {}",
+            install.dev_hdd0.display(),
+            config.paths.dev_hdd0.display()
+        ));
+        ui.add_enabled(
+            !install.disc_keys.is_empty(),
+            egui::Checkbox::new(&mut options.disc_keys, format!("{} disc key files", install.disc_keys.len())),
+        )
+        .on_hover_text(format!("Copied into {}", disc_key_dir(&config.paths).display()));
+        ui.checkbox(&mut options.overwrite, "Replace files that already exist");
+
+        ui.add_space(8.0);
+        let mut back = false;
+        let mut start = false;
+        ui.horizontal(|ui| {
+            back = ui.button("◀ Back").clicked();
+            start = ui.button("📥 Import").clicked();
+        });
+        if back {
+            self.step = Step::Locate;
+            return false;
+        }
+        if !start {
+            return false;
+        }
+
+        let Step::Review(install) = std::mem::replace(&mut self.step, Step::Locate) else {
+            unreachable!();
+        };
+        let options = self.options;
+        let mut applied = Applied::default();
+        if options.settings {
+            applied.settings = install.apply_settings(config);
+        }
+        if options.games {
+            applied.games = install.add_games(&mut config.paths);
+        }
+        let changed = !applied.settings.is_empty() || applied.games > 0;
+
+        if !options.hdd0 && !options.disc_keys {
+            self.step = Step::Done(applied, None);
+            return changed;
+        }
+        self.progress = Arc::new(CopyProgress::default());
+        let progress = Arc::clone(&self.progress);
+        let paths = config.paths.clone();
+        let handle = std::thread::Builder::new()
+            .name("rpcs3-import".to_string())
+            .spawn(move || install.copy_data(&paths, options, &progress));
+        self.step = match handle {
+            Ok(handle) => Step::Copying(applied, handle),
+            Err(e) => Step::Done(
+                applied,
+                Some(CopyReport {
+                    errors: vec![format!("Failed to start the copy thread: {}", e)],
+                    ..CopyReport::default()
+                }),
+            ),
+        };
+        changed
+    }
+
+    fn show_copying(&mut self, ui: &mut egui::Ui) {
+        let done = self.progress.done.load(Ordering::Relaxed);
+        let total = self.progress.total.load(Ordering::Relaxed);
+        let fraction = if total == 0 { 0.0 } else { done as f32 / total as f32 };
+        ui.label("Copying files...");
+        ui.horizontal(|ui| {
+            ui.add(egui::ProgressBar::new(fraction).text(format!("{} / {} files", done, total)).desired_width(300.0));
+            let cancelling = self.progress.cancel.load(Ordering::Relaxed);
+            if ui.add_enabled(!cancelling, egui::Button::new("Cancel").small()).clicked() {
+                self.progress.cancel.store(true, Ordering::Relaxed);
+            }
+        });
+    }
+
+    fn show_done(&mut self, ui: &mut egui::Ui) {
+        let Step::Done(applied, report) = &self.step else {
+            return;
+        };
+        ui.colored_label(egui::Color32::from_rgb(80, 200, 120), "✅ Import finished");
+        if !applied.settings.is_empty() {
+            ui.collapsing(format!("{} settings imported", applied.settings.len()), |ui| {
+                for setting in &applied.settings {
+                    ui.label(setting);
+                }
+            });
+        }
+        if applied.games > 0 {
+            ui.label(format!("{} game folders added", applied.games));
+        }
+        if let Some(report) = report {
+            ui.label(format!("{} files copied, {} already there", report.copied, report.skipped));
+            if report.cancelled {
+                ui.colored_label(egui::Color32::from_rgb(220, 180, 60), "The copy was cancelled");
+            }
+            if !report.errors.is_empty() {
+                ui.collapsing(format!("❌ {} files failed", report.errors.len()), |ui| {
+                    egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                        for error in &report.errors {
+                            ui.label(error);
+                        }
+                    });
+                });
+            }
+        }
+        ui.add_space(8.0);
+        if ui.button("Import Another").clicked() {
+            self.step = Step::Locate;
+        }
+    }
+}
+
+impl Default for Rpcs3ImportWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
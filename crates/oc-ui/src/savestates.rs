@@ -0,0 +1,171 @@
+//! Savestate slots of the running game
+
+use crate::game_list::format_last_played;
+use eframe::egui;
+use oc_integration::savestate::{list_slots, slot_path, SLOT_COUNT};
+use oc_integration::SavestateInfo;
+use std::path::{Path, PathBuf};
+
+/// What the user asked to do with a slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotAction {
+    Save(usize),
+    Load(usize),
+}
+
+/// A filled slot and its screenshot
+struct Slot {
+    info: SavestateInfo,
+    texture: Option<egui::TextureHandle>,
+}
+
+/// Window listing the savestate slots of one game with their screenshots
+pub struct SavestateWindow {
+    /// Directory savestates are kept in
+    dir: PathBuf,
+    /// Title ID the slots belong to
+    title_id: Option<String>,
+    /// Slots, None where empty; read again after `refresh`
+    slots: Option<Vec<Option<Slot>>>,
+    /// Slot the quick save and load hotkeys use
+    quick_slot: usize,
+    /// Last delete failure
+    error: Option<String>,
+}
+
+impl SavestateWindow {
+    /// Create a window with no game
+    pub fn new() -> Self {
+        Self {
+            dir: PathBuf::new(),
+            title_id: None,
+            slots: None,
+            quick_slot: 0,
+            error: None,
+        }
+    }
+
+    /// Show the slots of `title_id` kept in `dir`
+    pub fn configure(&mut self, dir: &Path, title_id: Option<&str>) {
+        if dir != self.dir || title_id != self.title_id.as_deref() {
+            self.dir = dir.to_path_buf();
+            self.title_id = title_id.map(str::to_string);
+            self.refresh();
+        }
+    }
+
+    /// Read the slots again, e.g. after one was saved
+    pub fn refresh(&mut self) {
+        self.slots = None;
+        self.error = None;
+    }
+
+    /// Slot the quick save and load hotkeys use
+    pub fn quick_slot(&self) -> usize {
+        self.quick_slot
+    }
+
+    /// Make the hotkeys use the next slot, returning it
+    pub fn next_quick_slot(&mut self) -> usize {
+        self.quick_slot = (self.quick_slot + 1) % SLOT_COUNT;
+        self.quick_slot
+    }
+
+    /// File of `slot` of the configured game
+    pub fn slot_path(&self, slot: usize) -> Option<PathBuf> {
+        self.title_id.as_deref().map(|title_id| slot_path(&self.dir, title_id, slot))
+    }
+
+    /// Show the slot list, returning the save or load the user asked for
+    pub fn show(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, running: bool) -> Option<SlotAction> {
+        let Some(title_id) = self.title_id.clone() else {
+            ui.label("Savestates are available once a game is loaded.");
+            return None;
+        };
+        let slots = self.slots.get_or_insert_with(|| {
+            list_slots(&self.dir, &title_id)
+                .into_iter()
+                .enumerate()
+                .map(|(slot, info)| {
+                    info.map(|info| {
+                        let texture = info.thumbnail.as_ref().map(|thumbnail| {
+                            let size = [thumbnail.width as usize, thumbnail.height as usize];
+                            let image = egui::ColorImage::from_rgba_unmultiplied(size, &thumbnail.rgba);
+                            let name = format!("savestate_{}_{}_{}", title_id, slot, info.created);
+                            ctx.load_texture(name, image, egui::TextureOptions::LINEAR)
+                        });
+                        Slot { info, texture }
+                    })
+                })
+                .collect()
+        });
+
+        ui.label(format!("Savestates of {}. F5 saves and F8 loads the quick slot, F6 picks the next one.", title_id));
+        if let Some(ref error) = self.error {
+            ui.colored_label(egui::Color32::from_rgb(220, 80, 80), error);
+        }
+        ui.separator();
+
+        let mut action = None;
+        let mut delete = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (index, slot) in slots.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let size = egui::vec2(160.0, 90.0);
+                    match slot.as_ref().and_then(|slot| slot.texture.as_ref()) {
+                        Some(texture) => {
+                            ui.add(egui::Image::new(texture).fit_to_exact_size(size));
+                        }
+                        None => {
+                            let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+                            ui.painter().rect_filled(rect, 4.0, ui.visuals().extreme_bg_color);
+                        }
+                    }
+                    ui.vertical(|ui| {
+                        let quick = if index == self.quick_slot { " (quick slot)" } else { "" };
+                        ui.strong(format!("Slot {}{}", index, quick));
+                        match slot {
+                            Some(slot) => {
+                                ui.label(format!("Saved {}", format_last_played(slot.info.created)));
+                                ui.label(egui::RichText::new(format!("Frame {}", slot.info.frame)).weak());
+                            }
+                            None => {
+                                ui.label(egui::RichText::new("Empty").weak());
+                            }
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(running, egui::Button::new("Save")).clicked() {
+                                action = Some(SlotAction::Save(index));
+                            }
+                            if ui.add_enabled(running && slot.is_some(), egui::Button::new("Load")).clicked() {
+                                action = Some(SlotAction::Load(index));
+                            }
+                            if ui.add_enabled(slot.is_some(), egui::Button::new("Delete")).clicked() {
+                                delete = Some(index);
+                            }
+                            if ui.add_enabled(index != self.quick_slot, egui::Button::new("Use for Hotkeys")).clicked() {
+                                self.quick_slot = index;
+                            }
+                        });
+                    });
+                });
+                ui.separator();
+            }
+        });
+
+        if let Some(slot) = delete {
+            let path = slot_path(&self.dir, &title_id, slot);
+            self.refresh();
+            if let Err(e) = std::fs::remove_file(&path) {
+                self.error = Some(format!("Failed to delete {}: {}", path.display(), e));
+            }
+        }
+        action
+    }
+}
+
+impl Default for SavestateWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -2,6 +2,8 @@
 
 use eframe::egui;
 use oc_core::config::*;
+use oc_integration::caches::{self, CacheKind, CacheUsage};
+use oc_integration::capture;
 use std::path::PathBuf;
 
 /// Firmware installation status
@@ -27,6 +29,12 @@ pub struct SettingsPanel {
     firmware_status: FirmwareStatus,
     /// Selected firmware file path
     firmware_file_path: String,
+    /// Whether ffmpeg can be run, checked when the capture tab is first shown
+    ffmpeg_available: Option<bool>,
+    /// Disk usage of the per-game caches, measured when the caches tab is first shown
+    cache_usage: Option<CacheUsage>,
+    /// Outcome of the last cache action
+    cache_message: Option<String>,
 }
 
 /// Settings tabs
@@ -38,10 +46,37 @@ enum SettingsTab {
     Audio,
     Input,
     Paths,
+    Capture,
+    Network,
+    Caches,
     Firmware,
     Debug,
 }
 
+/// Every tab with its label
+const ALL_TABS: [(SettingsTab, &str); 11] = [
+    (SettingsTab::General, "General"),
+    (SettingsTab::Cpu, "CPU"),
+    (SettingsTab::Gpu, "GPU"),
+    (SettingsTab::Audio, "Audio"),
+    (SettingsTab::Input, "Input"),
+    (SettingsTab::Paths, "Paths"),
+    (SettingsTab::Capture, "Capture"),
+    (SettingsTab::Network, "Network"),
+    (SettingsTab::Caches, "Caches"),
+    (SettingsTab::Firmware, "🔑 Firmware"),
+    (SettingsTab::Debug, "Debug"),
+];
+
+/// Tabs of the settings a game can override
+const GAME_TABS: [(SettingsTab, &str); 5] = [
+    (SettingsTab::Cpu, "CPU"),
+    (SettingsTab::Gpu, "GPU"),
+    (SettingsTab::Audio, "Audio"),
+    (SettingsTab::Input, "Input"),
+    (SettingsTab::Network, "Network"),
+];
+
 impl SettingsPanel {
     /// Create a new settings panel
     pub fn new() -> Self {
@@ -49,6 +84,9 @@ impl SettingsPanel {
             current_tab: SettingsTab::General,
             firmware_status: FirmwareStatus::default(),
             firmware_file_path: String::new(),
+            ffmpeg_available: None,
+            cache_usage: None,
+            cache_message: None,
         }
     }
 
@@ -98,17 +136,31 @@ impl SettingsPanel {
 
     /// Show the settings panel
     pub fn show(&mut self, ui: &mut egui::Ui, config: &mut Config) -> bool {
+        self.show_tabs(ui, config, &ALL_TABS, &[])
+    }
+
+    /// Show only the settings a game can override, marking the sections in `overridden`
+    pub fn show_game(&mut self, ui: &mut egui::Ui, config: &mut Config, overridden: &[&str]) -> bool {
+        if !GAME_TABS.iter().any(|(tab, _)| *tab == self.current_tab) {
+            self.current_tab = SettingsTab::Cpu;
+        }
+        self.show_tabs(ui, config, &GAME_TABS, overridden)
+    }
+
+    /// Show the tab bar and the current tab
+    fn show_tabs(&mut self, ui: &mut egui::Ui, config: &mut Config, tabs: &[(SettingsTab, &str)], overridden: &[&str]) -> bool {
         let mut should_save = false;
 
         ui.horizontal(|ui| {
-            ui.selectable_value(&mut self.current_tab, SettingsTab::General, "General");
-            ui.selectable_value(&mut self.current_tab, SettingsTab::Cpu, "CPU");
-            ui.selectable_value(&mut self.current_tab, SettingsTab::Gpu, "GPU");
-            ui.selectable_value(&mut self.current_tab, SettingsTab::Audio, "Audio");
-            ui.selectable_value(&mut self.current_tab, SettingsTab::Input, "Input");
-            ui.selectable_value(&mut self.current_tab, SettingsTab::Paths, "Paths");
-            ui.selectable_value(&mut self.current_tab, SettingsTab::Firmware, "🔑 Firmware");
-            ui.selectable_value(&mut self.current_tab, SettingsTab::Debug, "Debug");
+            for (tab, label) in tabs {
+                // Sections are named like the tab labels, e.g. "cpu" for "CPU"
+                let label = if overridden.contains(&label.to_lowercase().as_str()) {
+                    format!("{} •", label)
+                } else {
+                    label.to_string()
+                };
+                ui.selectable_value(&mut self.current_tab, *tab, label);
+            }
         });
 
         ui.separator();
@@ -133,6 +185,15 @@ impl SettingsPanel {
                 SettingsTab::Paths => {
                     should_save |= self.show_path_settings(ui, &mut config.paths);
                 }
+                SettingsTab::Capture => {
+                    should_save |= self.show_capture_settings(ui, &mut config.capture);
+                }
+                SettingsTab::Network => {
+                    should_save |= self.show_network_settings(ui, &mut config.network);
+                }
+                SettingsTab::Caches => {
+                    self.show_cache_settings(ui, &config.paths);
+                }
                 SettingsTab::Firmware => {
                     should_save |= self.show_firmware_settings(ui, &mut config.paths);
                 }
@@ -159,9 +220,79 @@ impl SettingsPanel {
             .on_hover_text("Show confirmation dialog when closing")
             .changed();
 
-        changed |= ui.checkbox(&mut config.auto_save_state, "Auto Save State")
-            .on_hover_text("Automatically save state on exit")
+        changed |= ui.checkbox(&mut config.auto_save_state, "Autosave")
+            .on_hover_text("Keep background savestates to recover from crashes and lockups, offered on the next boot")
             .changed();
+        ui.add_enabled_ui(config.auto_save_state, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Every:");
+                changed |= ui.add(egui::Slider::new(&mut config.autosave_interval, 30..=1800).suffix(" s")).changed();
+                ui.label("Keep:");
+                changed |= ui.add(egui::Slider::new(&mut config.autosave_count, 1..=10)).changed();
+            });
+        });
+
+        changed |= ui.checkbox(&mut config.resume_from_savestate, "Resume From Savestate")
+            .on_hover_text("Load the newest savestate when continuing a game from the game list")
+            .changed();
+
+        changed |= ui.checkbox(&mut config.complete_background_downloads, "Complete Background Downloads")
+            .on_hover_text("Report background downloads as finished rather than failed; nothing is actually fetched")
+            .changed();
+
+        ui.horizontal(|ui| {
+            ui.label("HDD Free Space:");
+            changed |= ui.add(egui::Slider::new(&mut config.hdd_free_space_gb, 1..=2000).suffix(" GB"))
+                .on_hover_text("Free space games see on the internal drive when checking before installing")
+                .changed();
+        });
+
+        changed |= ui.checkbox(&mut config.copy_disc_game_data, "Copy Disc Data On Install")
+            .on_hover_text("Copy the disc's USRDIR when a game installs its data; otherwise only the progress is shown")
+            .changed();
+
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            ui.label("Console Region:");
+            egui::ComboBox::from_id_salt("console_region")
+                .selected_text(config.console_region.name())
+                .show_ui(ui, |ui| {
+                    for region in ConsoleRegion::ALL {
+                        changed |= ui.selectable_value(&mut config.console_region, region, region.name()).changed();
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Console ID:");
+            changed |= ui.text_edit_singleline(&mut config.console_id)
+                .on_hover_text("IDPS games read from the console as 32 hex digits; leave empty for one matching the region")
+                .changed();
+        });
+
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            ui.label("Compatibility Database URL:");
+            changed |= ui.text_edit_singleline(&mut config.compat_db_url)
+                .on_hover_text("JSON file or http(s) URL the game list refreshes compatibility statuses from")
+                .changed();
+        });
+
+        ui.add_space(10.0);
+        ui.label("Speed Hotkeys:");
+        egui::Grid::new("speed_hotkeys").num_columns(2).show(ui, |ui| {
+            ui.label("Turbo (hold)");
+            changed |= show_key_field(ui, &mut config.turbo_key);
+            ui.end_row();
+            ui.label("Slow Motion (toggle)");
+            changed |= show_key_field(ui, &mut config.slow_motion_key);
+            ui.end_row();
+        });
+        ui.horizontal(|ui| {
+            ui.label("Quick Menu:");
+            changed |= show_key_field(ui, &mut config.quick_menu_key);
+        })
+        .response
+        .on_hover_text("Opens the quick menu over the game; a controller's PS button opens it too");
 
         changed
     }
@@ -250,12 +381,40 @@ impl SettingsPanel {
         changed |= ui.add(
             egui::Slider::new(&mut config.frame_limit, 0..=240)
                 .text("Frame Limit (0 = unlimited)")
+        ).on_hover_text("60 runs at full speed, 30 at half speed").changed();
+
+        changed |= ui.add(
+            egui::Slider::new(&mut config.turbo_speed, 0.0..=8.0)
+                .text("Turbo Speed (0 = unlimited)")
+                .suffix("x")
+        ).on_hover_text("Speed while the turbo hotkey is held").changed();
+
+        changed |= ui.add(
+            egui::Slider::new(&mut config.slow_motion_speed, 0.1..=1.0)
+                .text("Slow Motion Speed")
+                .suffix("x")
         ).changed();
 
+        changed |= ui.checkbox(&mut config.pal_50hz, "PAL (50 Hz)")
+            .on_hover_text("Output 50 Hz PAL video instead of 59.94 Hz NTSC")
+            .changed();
+
+        changed |= ui.add(
+            egui::Slider::new(&mut config.frame_skip, 0..=4)
+                .text("Frame Skip (0 = never)")
+        ).on_hover_text("Most frames dropped in a row while emulation runs behind").changed();
+
         changed |= ui.checkbox(&mut config.shader_cache, "Shader Cache")
             .on_hover_text("Cache compiled shaders to disk")
             .changed();
 
+        changed |= ui.checkbox(&mut config.dump_textures, "Dump Textures")
+            .on_hover_text("Save every texture the game decodes as a PNG named by its hash, for making texture packs")
+            .changed();
+        changed |= ui.checkbox(&mut config.replace_textures, "Load Texture Packs")
+            .on_hover_text("Use PNGs from the game's texture replacement folder in place of its textures, at any resolution")
+            .changed();
+
         ui.add_space(10.0);
 
         ui.label("Write Buffers (Debug):");
@@ -279,6 +438,10 @@ impl SettingsPanel {
         ui.label("Backend:");
         changed |= ui.radio_value(&mut config.backend, AudioBackend::Auto, "Auto")
             .changed();
+        changed |= ui.radio_value(&mut config.backend, AudioBackend::Cpal, "cpal (WASAPI/CoreAudio/ALSA)")
+            .changed();
+        changed |= ui.radio_value(&mut config.backend, AudioBackend::Alsa, "ALSA (native, Linux)")
+            .changed();
         changed |= ui.radio_value(&mut config.backend, AudioBackend::Null, "Null (No audio)")
             .changed();
 
@@ -298,6 +461,53 @@ impl SettingsPanel {
             .on_hover_text("Adjust audio speed to match emulation speed")
             .changed();
 
+        ui.add_space(10.0);
+
+        ui.label("Speaker Layout:");
+        changed |= ui.radio_value(&mut config.channel_layout, AudioChannelLayout::Stereo, "Stereo")
+            .changed();
+        changed |= ui.radio_value(&mut config.channel_layout, AudioChannelLayout::Surround51, "5.1 Surround")
+            .changed();
+        changed |= ui.radio_value(&mut config.channel_layout, AudioChannelLayout::Surround71, "7.1 Surround")
+            .changed();
+
+        ui.label("Downmix (when the device has fewer channels):");
+        changed |= ui.radio_value(&mut config.downmix, AudioDownmix::DolbySurround, "Dolby Surround (Lt/Rt)")
+            .on_hover_text("Matrix-encoded stereo that a Pro Logic II receiver can decode back to surround")
+            .changed();
+        changed |= ui.radio_value(&mut config.downmix, AudioDownmix::Itu, "ITU-R BS.775")
+            .changed();
+
+        ui.add_space(10.0);
+
+        ui.horizontal(|ui| {
+            ui.label("Output Device:");
+            changed |= ui.text_edit_singleline(&mut config.output_device)
+                .on_hover_text("ALSA PCM (e.g. hw:1,0) or cpal device name; leave empty for the system default")
+                .changed();
+        });
+
+        changed |= ui.checkbox(&mut config.exclusive_mode, "Exclusive Mode (low latency)")
+            .on_hover_text("Open the output device directly with a small buffer (native ALSA with a hw: device; cpal ignores the exclusive part)")
+            .changed();
+
+        changed |= ui.checkbox(&mut config.hle_sound_middleware, "HLE Sound Middleware")
+            .on_hover_text("Play SSPlayer and MultiStream (SCREAM) voices on the host instead of their SPU workload")
+            .changed();
+
+        ui.add_space(10.0);
+
+        ui.label("Audio Dump (Emulation → Record Audio):");
+        ui.horizontal(|ui| {
+            changed |= ui.radio_value(&mut config.dump_format, AudioDumpFormat::Wav, "WAV")
+                .changed();
+            changed |= ui.radio_value(&mut config.dump_format, AudioDumpFormat::Flac, "FLAC")
+                .changed();
+        });
+        changed |= ui.checkbox(&mut config.dump_ports, "Also record each cellAudio port")
+            .changed();
+        ui.label(format!("Output folder: {}", config.dump_path.display()));
+
         changed
     }
 
@@ -308,7 +518,83 @@ impl SettingsPanel {
         ui.add_space(10.0);
 
         ui.label("Controller Configuration:");
-        ui.label("(Controller support coming soon)");
+        changed |= ui
+            .checkbox(&mut config.controller.ds3_passthrough, "DualShock 3 passthrough")
+            .on_hover_text("Read DualShock 3 controllers directly over HID for pressure-sensitive buttons, motion and rumble")
+            .changed();
+        changed |= ui
+            .checkbox(&mut config.controller.ds4_passthrough, "DualShock 4 / DualSense passthrough")
+            .on_hover_text("Read DualShock 4 and DualSense controllers over HID so their gyro drives Sixaxis motion")
+            .changed();
+
+        if config.controller.ds4_passthrough {
+            ui.add_space(5.0);
+            ui.label("Extra Button Mapping (PS3 button names, empty = unmapped):");
+
+            egui::Grid::new("ds4_extra_mapping")
+                .num_columns(2)
+                .spacing([40.0, 8.0])
+                .show(ui, |ui| {
+                    let extra = &mut config.controller.ds4_extra;
+
+                    ui.label("Touchpad click:");
+                    changed |= ui.text_edit_singleline(&mut extra.touchpad_click).changed();
+                    ui.end_row();
+
+                    ui.label("Touch left half:");
+                    changed |= ui.text_edit_singleline(&mut extra.touch_left).changed();
+                    ui.end_row();
+
+                    ui.label("Touch right half:");
+                    changed |= ui.text_edit_singleline(&mut extra.touch_right).changed();
+                    ui.end_row();
+
+                    ui.label("PS button:");
+                    changed |= ui.text_edit_singleline(&mut extra.ps).changed();
+                    ui.end_row();
+
+                    ui.label("Mute (DualSense):");
+                    changed |= ui.text_edit_singleline(&mut extra.mute).changed();
+                    ui.end_row();
+                });
+        }
+
+        ui.add_space(5.0);
+        changed |= ui
+            .checkbox(&mut config.controller.usb_passthrough, "USB device passthrough")
+            .on_hover_text("Hand Buzz! buzzers, dance mats and GT steering wheels to games as raw USB devices for their own drivers")
+            .changed();
+        if config.controller.usb_passthrough {
+            ui.horizontal(|ui| {
+                ui.label("Extra devices:");
+                let mut text = config.controller.usb_passthrough_devices.join(", ");
+                let edit = egui::TextEdit::singleline(&mut text).hint_text("VID:PID, e.g. 054c:0002");
+                if ui.add(edit).changed() {
+                    // Empty entries are kept while typing and skipped when devices are opened
+                    config.controller.usb_passthrough_devices = if text.trim().is_empty() {
+                        Vec::new()
+                    } else {
+                        text.split(',').map(|id| id.trim().to_string()).collect()
+                    };
+                    changed = true;
+                }
+            });
+        }
+
+        changed |= ui
+            .checkbox(&mut config.controller.virtual_buzz, "Emulated Buzz! buzzers")
+            .on_hover_text("Play the four buzzers on controller ports 1-4: R1 is the red button, the face buttons the colored ones")
+            .changed();
+
+        ui.add_space(5.0);
+        changed |= ui
+            .checkbox(&mut config.controller.rumble, "Rumble")
+            .on_hover_text("Play game vibration on host controllers that support it")
+            .changed();
+        changed |= ui
+            .checkbox(&mut config.controller.light_bar, "Player color on light bar")
+            .on_hover_text("Light DualShock 4/DualSense controllers in their port's player color (needs passthrough)")
+            .changed();
 
         ui.add_space(10.0);
 
@@ -378,6 +664,128 @@ impl SettingsPanel {
         changed
     }
 
+    fn show_network_settings(&self, ui: &mut egui::Ui, config: &mut NetworkConfig) -> bool {
+        let mut changed = false;
+
+        ui.heading("Network");
+        ui.add_space(10.0);
+
+        ui.label("Traffic Capture:");
+        ui.horizontal(|ui| {
+            for (mode, name, hint) in [
+                (NetCaptureMode::Off, "Off", "Go online normally"),
+                (NetCaptureMode::Record, "Record", "Save every request the game makes and the response it gets"),
+                (NetCaptureMode::Replay, "Replay", "Answer requests from the recording, going online for the rest"),
+            ] {
+                changed |= ui.radio_value(&mut config.capture_mode, mode, name).on_hover_text(hint).changed();
+            }
+        });
+        ui.add_space(5.0);
+        ui.label(
+            egui::RichText::new(
+                "Record a game while its servers are up, then replay the recording to get past the handshakes \
+                 it needs once they are gone. Recordings are kept per game in the Network Captures folder \
+                 (Paths tab) as JSON, where responses can also be edited by hand.",
+            )
+            .small(),
+        );
+
+        ui.add_space(10.0);
+        ui.label("Server Redirection:");
+        let mut removed = None;
+        egui::Grid::new("host_overrides").num_columns(3).show(ui, |ui| {
+            for (index, rule) in config.host_overrides.iter_mut().enumerate() {
+                let host = egui::TextEdit::singleline(&mut rule.host).hint_text("*.example.com");
+                changed |= ui.add(host).on_hover_text("Domain the game asks for").changed();
+                let address = egui::TextEdit::singleline(&mut rule.address).hint_text("Address or domain");
+                changed |= ui.add(address).on_hover_text("Server to send it to instead").changed();
+                if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                    removed = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(index) = removed {
+            config.host_overrides.remove(index);
+            changed = true;
+        }
+        if ui.button("➕ Add Host").clicked() {
+            config.host_overrides.push(HostOverride::default());
+            changed = true;
+        }
+        ui.add_space(5.0);
+        ui.label(
+            egui::RichText::new(
+                "Send the game's requests for a domain to another server, such as a community revival of \
+                 its official one. A domain starting with *. also covers its subdomains.",
+            )
+            .small(),
+        );
+
+        changed
+    }
+
+    fn show_capture_settings(&mut self, ui: &mut egui::Ui, config: &mut CaptureConfig) -> bool {
+        let mut changed = false;
+
+        ui.heading("Screenshots and Video");
+        ui.add_space(10.0);
+
+        changed |= self.show_path_field(ui, "Output Folder:", &mut config.output_dir);
+
+        ui.add_space(10.0);
+        egui::Grid::new("capture_settings")
+            .num_columns(2)
+            .spacing([40.0, 8.0])
+            .show(ui, |ui| {
+                ui.label("Container:");
+                egui::ComboBox::from_id_salt("video_container")
+                    .selected_text(format!("{:?}", config.container))
+                    .show_ui(ui, |ui| {
+                        for container in [VideoContainer::Avi, VideoContainer::Mp4, VideoContainer::Mkv, VideoContainer::WebM] {
+                            changed |= ui.selectable_value(&mut config.container, container, format!("{:?}", container)).changed();
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Codec:");
+                egui::ComboBox::from_id_salt("video_codec")
+                    .selected_text(codec_name(config.codec))
+                    .show_ui(ui, |ui| {
+                        for codec in [VideoCodec::Mjpeg, VideoCodec::H264, VideoCodec::H265, VideoCodec::Vp9, VideoCodec::Ffv1] {
+                            changed |= ui.selectable_value(&mut config.codec, codec, codec_name(codec)).changed();
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Screenshot Key:");
+                changed |= show_key_field(ui, &mut config.screenshot_key);
+                ui.end_row();
+
+                ui.label("Record Key:");
+                changed |= show_key_field(ui, &mut config.record_key);
+                ui.end_row();
+            });
+
+        changed |= ui.checkbox(&mut config.mux_audio, "Record Audio")
+            .on_hover_text("Dump the final mix while recording and mux it into the video")
+            .changed();
+
+        ui.add_space(10.0);
+        let warning = egui::Color32::from_rgb(220, 180, 60);
+        if let Err(e) = capture::check_format(config.container, config.codec) {
+            ui.colored_label(egui::Color32::from_rgb(220, 80, 80), format!("❌ {}", e));
+        } else if capture::is_builtin(config.container, config.codec) {
+            ui.label("Motion JPEG in AVI is written directly, up to about 3.8 GB per recording.");
+        } else if *self.ffmpeg_available.get_or_insert_with(capture::ffmpeg_available) {
+            ui.label("Encoded by ffmpeg.");
+        } else {
+            ui.colored_label(warning, "⚠ This format needs ffmpeg on the PATH, which was not found.");
+        }
+
+        changed
+    }
+
     fn show_path_settings(&self, ui: &mut egui::Ui, config: &mut PathConfig) -> bool {
         let mut changed = false;
 
@@ -392,11 +800,125 @@ impl SettingsPanel {
         changed |= self.show_path_field(ui, "dev_flash:", &mut config.dev_flash);
         changed |= self.show_path_field(ui, "Save Data:", &mut config.save_data);
         changed |= self.show_path_field(ui, "Shader Cache:", &mut config.shader_cache);
+        changed |= self.show_path_field(ui, "Decrypted SELF Cache:", &mut config.self_cache);
+        changed |= self.show_path_field(ui, "Texture Dumps:", &mut config.texture_dumps);
+        changed |= self.show_path_field(ui, "Texture Packs:", &mut config.texture_replacements);
+        changed |= self.show_path_field(ui, "Thumbnail Cache:", &mut config.thumbnail_cache);
+        changed |= self.show_path_field(ui, "Savestates:", &mut config.savestates);
+        changed |= self.show_path_field(ui, "Network Captures:", &mut config.network_captures);
         changed |= self.show_path_field(ui, "Firmware:", &mut config.firmware);
+        changed |= self.show_path_field(ui, "Custom Soundtrack:", &mut config.music);
+
+        ui.add_space(10.0);
+        ui.label("Additional Game Folders:");
+        let mut removed = None;
+        for (index, dir) in config.extra_games.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.small_button("✖").on_hover_text("Stop scanning this folder").clicked() {
+                    removed = Some(index);
+                }
+                ui.label(dir.display().to_string());
+            });
+        }
+        if let Some(index) = removed {
+            config.extra_games.remove(index);
+            changed = true;
+        }
+        if ui.button("📁 Add Folder...").clicked() {
+            if let Some(dir) = rfd::FileDialog::new().set_title("Add Game Folder").pick_folder() {
+                if !config.extra_games.contains(&dir) {
+                    config.extra_games.push(dir);
+                    changed = true;
+                }
+            }
+        }
 
         changed
     }
 
+    fn show_cache_settings(&mut self, ui: &mut egui::Ui, paths: &PathConfig) {
+        ui.heading("Per-Game Caches");
+        ui.add_space(10.0);
+
+        let usage = self.cache_usage.get_or_insert_with(|| caches::scan(paths)).clone();
+        ui.horizontal(|ui| {
+            ui.label(format!("Total disk usage: {}", format_size(usage.grand_total())));
+            if ui.button("🔄 Refresh").clicked() {
+                self.cache_usage = None;
+            }
+        });
+        egui::Grid::new("cache_totals").num_columns(2).show(ui, |ui| {
+            for kind in CacheKind::ALL {
+                ui.label(format!("{}:", kind.name()));
+                ui.label(format_size(usage.total(kind)))
+                    .on_hover_text(kind.dir(paths).display().to_string());
+                ui.end_row();
+            }
+        });
+        if let Some(message) = &self.cache_message {
+            ui.label(message);
+        }
+
+        ui.add_space(10.0);
+        if usage.titles.is_empty() {
+            ui.label("No game has cached anything yet.");
+            return;
+        }
+
+        // Caches to clear, as (title ID, kinds)
+        let mut cleared: Option<(String, Vec<CacheKind>)> = None;
+        egui::Grid::new("title_caches").num_columns(6).striped(true).show(ui, |ui| {
+            ui.strong("Title ID");
+            for kind in CacheKind::ALL {
+                ui.strong(kind.name());
+            }
+            ui.strong("Total");
+            ui.end_row();
+
+            for title in &usage.titles {
+                ui.label(&title.title_id);
+                for kind in CacheKind::ALL {
+                    ui.horizontal(|ui| {
+                        ui.label(format_size(title.size(kind)));
+                        if title.size(kind) > 0 && ui.small_button("✖").on_hover_text("Clear").clicked() {
+                            cleared = Some((title.title_id.clone(), vec![kind]));
+                        }
+                    });
+                }
+                ui.label(format_size(title.total()));
+                ui.horizontal(|ui| {
+                    if ui.small_button("Rebuild")
+                        .on_hover_text("Clear the shader and decrypted SELF caches, rebuilt the next time the game boots")
+                        .clicked()
+                    {
+                        cleared = Some((title.title_id.clone(), vec![CacheKind::Shaders, CacheKind::DecryptedSelf]));
+                    }
+                    if ui.small_button("Clear All").clicked() {
+                        cleared = Some((title.title_id.clone(), CacheKind::ALL.to_vec()));
+                    }
+                });
+                ui.end_row();
+            }
+        });
+
+        if let Some((title_id, kinds)) = cleared {
+            let mut freed = 0;
+            let mut errors = Vec::new();
+            for kind in kinds {
+                match caches::clear(paths, kind, &title_id) {
+                    Ok(bytes) => freed += bytes,
+                    Err(e) => errors.push(e),
+                }
+            }
+            self.cache_message = Some(if errors.is_empty() {
+                format!("Freed {} from {}", format_size(freed), title_id)
+            } else {
+                format!("❌ {}", errors.join("; "))
+            });
+            self.cache_usage = None;
+        }
+    }
+
     fn show_firmware_settings(&mut self, ui: &mut egui::Ui, config: &mut PathConfig) -> bool {
         let mut changed = false;
 
@@ -634,21 +1156,91 @@ impl SettingsPanel {
 
         ui.label("Tracing:");
         changed |= ui.checkbox(&mut config.trace_ppu, "Trace PPU")
-            .on_hover_text("Log all PPU instructions (very slow)")
+            .on_hover_text("Record PPU instructions in instruction traces (very slow)")
             .changed();
 
         changed |= ui.checkbox(&mut config.trace_spu, "Trace SPU")
-            .on_hover_text("Log all SPU instructions (very slow)")
+            .on_hover_text("Record SPU instructions in instruction traces (very slow)")
             .changed();
 
+        ui.add_enabled_ui(config.trace_ppu || config.trace_spu, |ui| {
+            changed |= self.show_path_field(ui, "Trace File:", &mut config.trace_path);
+            egui::Grid::new("trace_triggers").num_columns(2).show(ui, |ui| {
+                ui.label("Start At:");
+                changed |= ui.add(egui::TextEdit::singleline(&mut config.trace_start).hint_text("immediately"))
+                    .on_hover_text("\"breakpoint\", an address or a range like 0x10000-0x10400")
+                    .changed();
+                ui.end_row();
+                ui.label("Stop At:");
+                changed |= ui.add(egui::TextEdit::singleline(&mut config.trace_stop).hint_text("when stopped"))
+                    .on_hover_text("\"breakpoint\", an address or a range like 0x10000-0x10400")
+                    .changed();
+                ui.end_row();
+                ui.label("Keep Last:");
+                changed |= ui.add(egui::DragValue::new(&mut config.trace_ring_size).suffix(" instructions"))
+                    .on_hover_text("Only write the most recent instructions when the trace stops (0 keeps everything)")
+                    .changed();
+                ui.end_row();
+            });
+        });
+
         changed |= ui.checkbox(&mut config.trace_rsx, "Trace RSX")
             .on_hover_text("Log all RSX commands (very slow)")
             .changed();
 
+        changed |= ui.checkbox(&mut config.trace_vfs, "Trace File Access")
+            .on_hover_text("Record every file the game opens into a per-game report")
+            .changed();
+
         changed |= ui.checkbox(&mut config.dump_shaders, "Dump Shaders")
             .on_hover_text("Save shader source code to disk")
             .changed();
 
+        changed |= ui.checkbox(&mut config.crash_dumps, "Crash Dumps")
+            .on_hover_text("Write registers, backtraces and recent log lines to a file when a game crashes")
+            .changed();
+        if config.crash_dumps {
+            changed |= self.show_path_field(ui, "Dump Directory:", &mut config.crash_dump_dir);
+        }
+
+        ui.add_space(10.0);
+
+        ui.label("Record/Replay:");
+        ui.horizontal(|ui| {
+            for (mode, name, hint) in [
+                (ReplayMode::Off, "Off", "Run normally"),
+                (ReplayMode::Record, "Record", "Log controller input, time reads and scheduling from game start"),
+                (ReplayMode::Replay, "Replay", "Feed a recorded run back to reproduce it exactly"),
+            ] {
+                changed |= ui.radio_value(&mut config.replay_mode, mode, name).on_hover_text(hint).changed();
+            }
+        });
+        if config.replay_mode != ReplayMode::Off {
+            changed |= self.show_path_field(ui, "Recording:", &mut config.replay_path);
+        }
+
+        ui.add_space(10.0);
+
+        ui.label("Coverage:");
+        changed |= ui.checkbox(&mut config.coverage, "Record Coverage From Boot")
+            .on_hover_text("Record which basic blocks of the game execute, for IDA/Ghidra coverage colorizers")
+            .changed();
+        changed |= self.show_path_field(ui, "Coverage File:", &mut config.coverage_path);
+
+        ui.add_space(10.0);
+
+        ui.label("Remote Debugging:");
+        changed |= ui.checkbox(&mut config.gdb_server, "GDB Server")
+            .on_hover_text("Let gdb, IDA or Ghidra attach over TCP (localhost only)")
+            .changed();
+        ui.add_enabled_ui(config.gdb_server, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Port:");
+                changed |= ui.add(egui::DragValue::new(&mut config.gdb_port).range(1..=65534)).changed();
+                ui.label(format!("(raw SPUs on {})", config.gdb_port.saturating_add(1)));
+            });
+        });
+
         changed
     }
 }
@@ -658,3 +1250,41 @@ impl Default for SettingsPanel {
         Self::new()
     }
 }
+
+/// Display name of a video codec
+fn codec_name(codec: VideoCodec) -> &'static str {
+    match codec {
+        VideoCodec::Mjpeg => "Motion JPEG",
+        VideoCodec::H264 => "H.264",
+        VideoCodec::H265 => "H.265 / HEVC",
+        VideoCodec::Vp9 => "VP9",
+        VideoCodec::Ffv1 => "FFV1 (lossless)",
+    }
+}
+
+/// Text field for a key name, marked when egui does not know the key
+/// Byte count with a unit for display, e.g. "1.5 MB"
+fn format_size(bytes: u64) -> String {
+    const GB: u64 = 1024 * 1024 * 1024;
+    if bytes >= GB {
+        format!("{:.1} GB", bytes as f64 / GB as f64)
+    } else if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+fn show_key_field(ui: &mut egui::Ui, name: &mut String) -> bool {
+    ui.horizontal(|ui| {
+        let changed = ui.add(egui::TextEdit::singleline(name).desired_width(80.0)).changed();
+        if egui::Key::from_name(name).is_none() {
+            ui.colored_label(egui::Color32::from_rgb(220, 80, 80), "Unknown key")
+                .on_hover_text("Use key names such as F12, Space or A");
+        }
+        changed
+    })
+    .inner
+}
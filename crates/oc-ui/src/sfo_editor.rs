@@ -0,0 +1,360 @@
+//! PARAM.SFO viewer and editor
+
+use eframe::egui;
+use oc_vfs::formats::sfo::{
+    is_valid_key, key_flags, known_key, validate_entry, SfoKind, CATEGORIES, KNOWN_KEYS,
+};
+use oc_vfs::{Sfo, SfoValue};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const ERROR_COLOR: egui::Color32 = egui::Color32::from_rgb(220, 80, 80);
+
+/// Parse a decimal or 0x-prefixed hexadecimal integer
+fn parse_integer(text: &str) -> Option<u32> {
+    let text = text.trim();
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+/// Text shown for an integer, hexadecimal for flag keys
+fn integer_text(key: &str, value: u32) -> String {
+    if key_flags(key).is_some() {
+        format!("0x{:X}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Window inspecting and editing one PARAM.SFO
+pub struct SfoEditorWindow {
+    /// File being edited
+    path: Option<PathBuf>,
+    sfo: Sfo,
+    /// Contents as last loaded or saved
+    saved: Sfo,
+    /// Text of integer fields, which may not parse yet
+    integers: BTreeMap<String, String>,
+    /// Key and type of an entry being added
+    new_key: String,
+    new_kind: SfoKind,
+    /// Outcome of the last open or save, and whether it failed
+    message: Option<(bool, String)>,
+}
+
+impl SfoEditorWindow {
+    /// Create an editor with no file open
+    pub fn new() -> Self {
+        Self {
+            path: None,
+            sfo: Sfo::new(),
+            saved: Sfo::new(),
+            integers: BTreeMap::new(),
+            new_key: String::new(),
+            new_kind: SfoKind::Utf8,
+            message: None,
+        }
+    }
+
+    /// Open `path`, dropping unsaved changes to the current file
+    pub fn open(&mut self, path: &Path) {
+        match Sfo::load(path) {
+            Ok(sfo) => {
+                self.path = Some(path.to_path_buf());
+                self.saved = sfo.clone();
+                self.set_sfo(sfo);
+                self.message = None;
+            }
+            Err(e) => self.message = Some((true, format!("Failed to open {}: {}", path.display(), e))),
+        }
+    }
+
+    /// Whether there are unsaved changes
+    pub fn is_modified(&self) -> bool {
+        self.sfo != self.saved
+    }
+
+    fn set_sfo(&mut self, sfo: Sfo) {
+        self.integers = sfo
+            .entries()
+            .filter_map(|(key, entry)| match entry.value {
+                SfoValue::Integer(value) => Some((key.to_string(), integer_text(key, value))),
+                _ => None,
+            })
+            .collect();
+        self.sfo = sfo;
+    }
+
+    fn save_to(&mut self, path: &Path) {
+        // Keep the first version of the file in case the edit breaks the game
+        let backup = path.with_extension("SFO.bak");
+        if path.is_file() && !backup.exists() {
+            if let Err(e) = std::fs::copy(path, &backup) {
+                self.message = Some((true, format!("Failed to back up {}: {}", path.display(), e)));
+                return;
+            }
+        }
+        match self.sfo.save(path) {
+            Ok(()) => {
+                self.path = Some(path.to_path_buf());
+                self.saved = self.sfo.clone();
+                self.message = Some((false, format!("Saved {}", path.display())));
+            }
+            Err(e) => self.message = Some((true, format!("Failed to save {}: {}", path.display(), e))),
+        }
+    }
+
+    /// Show the editor
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let issues = self.sfo.validate();
+        let unparsed = self.integers.values().any(|text| parse_integer(text).is_none());
+
+        ui.horizontal(|ui| {
+            if ui.button("📂 Open...").clicked() {
+                let picked = rfd::FileDialog::new()
+                    .set_title("Open PARAM.SFO")
+                    .add_filter("PARAM.SFO", &["SFO", "sfo"])
+                    .pick_file();
+                if let Some(path) = picked {
+                    self.open(&path);
+                }
+            }
+            let can_save = self.path.is_some() && self.is_modified() && issues.is_empty() && !unparsed;
+            if ui
+                .add_enabled(can_save, egui::Button::new("💾 Save"))
+                .on_disabled_hover_text("Fix the problems listed below first")
+                .clicked()
+            {
+                if let Some(path) = self.path.clone() {
+                    self.save_to(&path);
+                }
+            }
+            if ui.add_enabled(issues.is_empty() && !unparsed, egui::Button::new("Save As...")).clicked() {
+                if let Some(path) = rfd::FileDialog::new().set_title("Save PARAM.SFO").set_file_name("PARAM.SFO").save_file() {
+                    self.save_to(&path);
+                }
+            }
+            if ui.add_enabled(self.is_modified(), egui::Button::new("↺ Revert")).clicked() {
+                self.set_sfo(self.saved.clone());
+            }
+        });
+
+        match &self.path {
+            Some(path) => {
+                let kind = if self.sfo.is_save() {
+                    "Save data".to_string()
+                } else {
+                    let category = self.sfo.get_string("CATEGORY").unwrap_or_default();
+                    CATEGORIES
+                        .iter()
+                        .find(|(code, _)| *code == category)
+                        .map_or_else(|| "Unknown content".to_string(), |(_, name)| name.to_string())
+                };
+                let modified = if self.is_modified() { " •" } else { "" };
+                ui.label(egui::RichText::new(format!("{}{}", path.display(), modified)).strong());
+                ui.label(format!("{}, {} entries", kind, self.sfo.entries().count()));
+            }
+            None => {
+                ui.label("Open a PARAM.SFO from a game folder or a save directory.");
+            }
+        }
+        if let Some((failed, message)) = &self.message {
+            let color = if *failed { ERROR_COLOR } else { egui::Color32::from_rgb(80, 200, 120) };
+            ui.colored_label(color, message);
+        }
+        if self.path.is_none() {
+            return;
+        }
+        ui.separator();
+
+        let mut removed = None;
+        egui::ScrollArea::vertical().max_height(420.0).show(ui, |ui| {
+            egui::Grid::new("sfo_entries")
+                .num_columns(4)
+                .striped(true)
+                .spacing([12.0, 6.0])
+                .show(ui, |ui| {
+                    ui.strong("Key");
+                    ui.strong("Type");
+                    ui.strong("Value");
+                    ui.label("");
+                    ui.end_row();
+
+                    let keys: Vec<String> = self.sfo.entries().map(|(key, _)| key.to_string()).collect();
+                    for key in keys {
+                        let label = ui.label(egui::RichText::new(&key).monospace());
+                        if let Some(known) = known_key(&key) {
+                            label.on_hover_text(format!("{} ({} bytes)", known.description, known.max_len));
+                        }
+                        self.show_entry(ui, &key);
+                        if ui.small_button("✖").on_hover_text("Remove this key").clicked() {
+                            removed = Some(key);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+        if let Some(key) = removed {
+            self.sfo.remove(&key);
+            self.integers.remove(&key);
+        }
+
+        ui.separator();
+        self.show_add_entry(ui);
+
+        if !issues.is_empty() || unparsed {
+            ui.separator();
+            if unparsed {
+                ui.colored_label(ERROR_COLOR, "❌ An integer field does not hold a number");
+            }
+            for issue in &issues {
+                ui.colored_label(ERROR_COLOR, format!("❌ {}", issue));
+            }
+        }
+    }
+
+    /// Type and value cells of one entry
+    fn show_entry(&mut self, ui: &mut egui::Ui, key: &str) {
+        let Some(value) = self.sfo.get(key).cloned() else {
+            return;
+        };
+        ui.label(SfoKind::of(&value).as_str());
+        let error = validate_entry(key, &value).err();
+
+        ui.vertical(|ui| {
+            match value {
+                SfoValue::Utf8(mut text) | SfoValue::Utf8S(mut text) if key == "CATEGORY" => {
+                    egui::ComboBox::from_id_salt("sfo_category")
+                        .selected_text(&text)
+                        .show_ui(ui, |ui| {
+                            for (code, name) in CATEGORIES {
+                                ui.selectable_value(&mut text, code.to_string(), format!("{} - {}", code, name));
+                            }
+                        });
+                    if Some(text.as_str()) != self.sfo.get_string(key) {
+                        self.sfo.set(key, SfoValue::Utf8(text));
+                    }
+                }
+                SfoValue::Utf8(mut text) => {
+                    let edit = if key == "DETAIL" {
+                        egui::TextEdit::multiline(&mut text).desired_rows(3)
+                    } else {
+                        egui::TextEdit::singleline(&mut text)
+                    };
+                    if ui.add(edit.desired_width(320.0)).changed() {
+                        self.sfo.set(key, SfoValue::Utf8(text));
+                    }
+                }
+                SfoValue::Utf8S(mut text) => {
+                    if ui.add(egui::TextEdit::singleline(&mut text).desired_width(320.0)).changed() {
+                        self.sfo.set(key, SfoValue::Utf8S(text));
+                    }
+                }
+                SfoValue::Integer(number) => self.show_integer(ui, key, number),
+                SfoValue::Binary(data) => {
+                    let hex: Vec<String> = data.iter().take(32).map(|b| format!("{:02X}", b)).collect();
+                    let more = if data.len() > 32 { " ..." } else { "" };
+                    ui.label(egui::RichText::new(format!("{}{}", hex.join(" "), more)).monospace())
+                        .on_hover_text(format!("{} bytes of binary data, kept as they are", data.len()));
+                }
+            }
+            if let Some(error) = error {
+                ui.colored_label(ERROR_COLOR, error);
+            }
+        });
+    }
+
+    fn show_integer(&mut self, ui: &mut egui::Ui, key: &str, number: u32) {
+        let text = self
+            .integers
+            .entry(key.to_string())
+            .or_insert_with(|| integer_text(key, number));
+        let response = ui.add(egui::TextEdit::singleline(text).desired_width(120.0));
+        let parsed = parse_integer(text);
+        if response.changed() {
+            if let Some(value) = parsed {
+                self.sfo.set(key, SfoValue::Integer(value));
+            }
+        }
+        if parsed.is_none() {
+            ui.colored_label(ERROR_COLOR, "not a number");
+        }
+
+        let Some(flags) = key_flags(key) else {
+            return;
+        };
+        let mut bits = parsed.unwrap_or(number);
+        let before = bits;
+        ui.collapsing(format!("Flags of {}", key), |ui| {
+            for (flag, name) in flags {
+                let mut set = bits & flag != 0;
+                if ui.checkbox(&mut set, *name).changed() {
+                    bits ^= flag;
+                }
+            }
+        });
+        if bits != before {
+            self.sfo.set(key, SfoValue::Integer(bits));
+            self.integers.insert(key.to_string(), integer_text(key, bits));
+        }
+    }
+
+    fn show_add_entry(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Add key:");
+            ui.add(egui::TextEdit::singleline(&mut self.new_key).desired_width(160.0).hint_text("KEY_NAME"));
+            egui::ComboBox::from_id_salt("sfo_known_keys")
+                .selected_text("Known keys")
+                .show_ui(ui, |ui| {
+                    for known in KNOWN_KEYS.iter().filter(|known| self.sfo.get(known.name).is_none()) {
+                        if ui.selectable_label(false, known.name).on_hover_text(known.description).clicked() {
+                            self.new_key = known.name.to_string();
+                            self.new_kind = known.kind;
+                        }
+                    }
+                });
+            egui::ComboBox::from_id_salt("sfo_new_kind")
+                .selected_text(self.new_kind.as_str())
+                .show_ui(ui, |ui| {
+                    for kind in [SfoKind::Utf8, SfoKind::Utf8S, SfoKind::Integer] {
+                        ui.selectable_value(&mut self.new_kind, kind, kind.as_str());
+                    }
+                });
+
+            let key = self.new_key.trim().to_string();
+            let value = match self.new_kind {
+                SfoKind::Utf8 => SfoValue::Utf8(String::new()),
+                SfoKind::Utf8S => SfoValue::Utf8S(String::new()),
+                SfoKind::Integer => SfoValue::Integer(0),
+            };
+            let problem = if self.sfo.get(&key).is_some() {
+                Some("This key is already present".to_string())
+            } else if !is_valid_key(&key) {
+                Some("Keys are upper case letters, digits and underscores".to_string())
+            } else {
+                known_key(&key)
+                    .filter(|known| known.kind != self.new_kind)
+                    .map(|known| format!("{} is {}", key, known.kind.as_str()))
+            };
+            let add = ui.add_enabled(!key.is_empty() && problem.is_none(), egui::Button::new("➕ Add"));
+            if let Some(problem) = problem.filter(|_| !key.is_empty()) {
+                ui.colored_label(ERROR_COLOR, problem);
+            }
+            if add.clicked() {
+                if let SfoValue::Integer(number) = value {
+                    self.integers.insert(key.clone(), integer_text(&key, number));
+                }
+                self.sfo.set(&key, value);
+                self.new_key.clear();
+            }
+        });
+    }
+}
+
+impl Default for SfoEditorWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,6 +1,32 @@
-//! Shader debugger panel for inspecting and debugging RSX shaders
+//! Shader debugger panel for inspecting and debugging RSX shaders and graphics state
 
 use eframe::egui;
+use oc_debug::rsx_debugger::RsxDebugState;
+use oc_debug::RsxDebugger;
+use oc_memory::MemoryManager;
+use oc_rsx::{FramebufferData, RsxState};
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+/// Largest side of texture and render target previews
+const PREVIEW_SIZE: u32 = 128;
+
+/// Fragment program bytes shown for a draw call
+const PROGRAM_PREVIEW_BYTES: u32 = 64;
+
+/// Shader debugger tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShaderDebuggerTab {
+    Shaders,
+    RsxState,
+}
+
+/// A texture or render target with its decoded preview
+struct ImagePreview {
+    label: String,
+    details: String,
+    image: Result<egui::TextureHandle, String>,
+}
 
 /// Shader type
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -63,6 +89,28 @@ pub struct ShaderDebugger {
     auto_refresh: bool,
     /// Shader statistics
     stats: ShaderStats,
+    /// Current tab
+    tab: ShaderDebuggerTab,
+    /// RSX debugger of the running emulator
+    rsx_debugger: Option<Arc<Mutex<RsxDebugger>>>,
+    /// Memory textures and render targets are read from
+    memory: Option<Arc<MemoryManager>>,
+    /// Latest RSX graphics state
+    rsx_state: Option<RsxState>,
+    /// Frame and command count the previews were built at
+    previews_at: Option<(u64, u64)>,
+    /// Rebuild the previews on the next frame
+    refresh_previews: bool,
+    /// Bound textures
+    textures: Vec<ImagePreview>,
+    /// Color render targets
+    render_targets: Vec<ImagePreview>,
+    /// Selected draw call
+    selected_draw: Option<u64>,
+    /// Fragment program of the selected draw call
+    draw_program: Option<(u64, Result<Vec<u8>, String>)>,
+    /// Method breakpoint being typed
+    method_breakpoint_input: String,
 }
 
 /// Shader cache statistics
@@ -95,9 +143,39 @@ impl ShaderDebugger {
             status_message: String::from("Shader debugger ready"),
             auto_refresh: false,
             stats: ShaderStats::default(),
+            tab: ShaderDebuggerTab::Shaders,
+            rsx_debugger: None,
+            memory: None,
+            rsx_state: None,
+            previews_at: None,
+            refresh_previews: false,
+            textures: Vec::new(),
+            render_targets: Vec::new(),
+            selected_draw: None,
+            draw_program: None,
+            method_breakpoint_input: String::new(),
         }
     }
 
+    /// Inspect the RSX of a running emulator
+    pub fn set_rsx_debugger(&mut self, debugger: Arc<Mutex<RsxDebugger>>, memory: Arc<MemoryManager>) {
+        self.rsx_debugger = Some(debugger);
+        self.memory = Some(memory);
+        self.rsx_state = None;
+        self.previews_at = None;
+        self.draw_program = None;
+    }
+
+    /// Whether the RSX state tab is open
+    pub fn shows_rsx_state(&self) -> bool {
+        self.tab == ShaderDebuggerTab::RsxState
+    }
+
+    /// Update the RSX graphics state shown
+    pub fn set_rsx_state(&mut self, state: RsxState) {
+        self.rsx_state = Some(state);
+    }
+
     /// Add a shader to the list (called when a shader is compiled)
     pub fn add_shader(&mut self, info: ShaderInfo) {
         // Update stats
@@ -163,6 +241,17 @@ impl ShaderDebugger {
         ui.heading("Shader Debugger");
         ui.add_space(5.0);
 
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.tab, ShaderDebuggerTab::Shaders, "Shaders");
+            ui.selectable_value(&mut self.tab, ShaderDebuggerTab::RsxState, "RSX State");
+        });
+        ui.separator();
+
+        if self.tab == ShaderDebuggerTab::RsxState {
+            self.show_rsx_state(ui);
+            return;
+        }
+
         // Statistics bar
         ui.horizontal(|ui| {
             ui.label(format!("Vertex: {}", self.stats.vertex_shader_count));
@@ -312,6 +401,299 @@ impl ShaderDebugger {
         });
     }
 
+    /// Show the RSX registers, bound textures, render targets and last draw calls
+    fn show_rsx_state(&mut self, ui: &mut egui::Ui) {
+        let (Some(debugger), Some(state)) = (self.rsx_debugger.clone(), self.rsx_state.clone()) else {
+            ui.label("Start a game to inspect the RSX.");
+            return;
+        };
+        let mut debugger = debugger.lock();
+
+        // Controls
+        ui.horizontal(|ui| {
+            let state_text = match debugger.state {
+                RsxDebugState::Running => "Running",
+                RsxDebugState::Paused => "Paused",
+                RsxDebugState::StepCommand => "Stepping command",
+                RsxDebugState::StepFrame => "Stepping frame",
+            };
+            ui.label(format!("RSX: {}", state_text));
+            ui.separator();
+            ui.label(format!("Frame {}", debugger.frame_count));
+            ui.label(format!("Commands this frame: {}", debugger.commands_this_frame));
+            ui.separator();
+            if debugger.is_paused() {
+                if ui.button("▶ Continue").clicked() {
+                    debugger.resume();
+                }
+            } else if ui.button("⏸ Pause").clicked() {
+                debugger.pause();
+            }
+            if ui.button("Step Command").clicked() {
+                debugger.step_command();
+            }
+            if ui.button("Step Frame").clicked() {
+                debugger.step_frame();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Break on method: 0x");
+            ui.add(egui::TextEdit::singleline(&mut self.method_breakpoint_input).desired_width(60.0));
+            if ui.button("Set").clicked() {
+                match u32::from_str_radix(self.method_breakpoint_input.trim(), 16) {
+                    Ok(method) => debugger.break_on_method(method),
+                    Err(_) => self.status_message = String::from("Invalid method address"),
+                }
+            }
+            if ui.button("Clear").clicked() {
+                debugger.clear_method_breakpoint();
+            }
+            if let Some(method) = debugger.method_breakpoint() {
+                ui.label(format!("Breaking on 0x{:04X}", method));
+            }
+            ui.separator();
+            if ui.button("🔄 Refresh Previews").clicked() {
+                self.refresh_previews = true;
+            }
+            ui.checkbox(&mut self.auto_refresh, "Auto");
+        });
+        ui.separator();
+
+        // Previews follow the RSX while paused or on auto refresh, and are decoded once per change
+        let position = (debugger.frame_count, debugger.commands_this_frame);
+        let moved = self.previews_at != Some(position);
+        if self.refresh_previews || self.previews_at.is_none() || (moved && (debugger.is_paused() || self.auto_refresh)) {
+            self.update_previews(ui.ctx(), &debugger, &state);
+            self.previews_at = Some(position);
+            self.refresh_previews = false;
+        }
+
+        egui::ScrollArea::vertical()
+            .id_salt("rsx_state")
+            .show(ui, |ui| {
+                let registers = debugger.get_registers(&state);
+                egui::CollapsingHeader::new(format!("Registers ({})", registers.len()))
+                    .id_salt("rsx_registers")
+                    .show(ui, |ui| {
+                        egui::Grid::new("rsx_register_grid")
+                            .num_columns(3)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for register in &registers {
+                                    ui.monospace(format!("0x{:04X}", register.method));
+                                    ui.monospace(&register.name);
+                                    ui.monospace(&register.value);
+                                    ui.end_row();
+                                }
+                            });
+                    });
+
+                egui::CollapsingHeader::new(format!("Textures ({})", self.textures.len()))
+                    .id_salt("rsx_textures")
+                    .default_open(true)
+                    .show(ui, |ui| Self::show_previews(ui, &self.textures, "No textures bound."));
+
+                egui::CollapsingHeader::new(format!("Render Targets ({})", self.render_targets.len()))
+                    .id_salt("rsx_render_targets")
+                    .default_open(true)
+                    .show(ui, |ui| Self::show_previews(ui, &self.render_targets, "No color targets active."));
+
+                egui::CollapsingHeader::new("Draw Calls")
+                    .id_salt("rsx_draw_calls")
+                    .default_open(true)
+                    .show(ui, |ui| self.show_draw_calls(ui, &mut debugger));
+            });
+    }
+
+    /// Decode the bound textures and active render targets
+    fn update_previews(&mut self, ctx: &egui::Context, debugger: &RsxDebugger, state: &RsxState) {
+        let Some(memory) = self.memory.clone() else {
+            return;
+        };
+        let upload = |name: String, image: Result<FramebufferData, String>| {
+            image.map(|image| {
+                let color_image = egui::ColorImage::from_rgba_unmultiplied(
+                    [image.width as usize, image.height as usize],
+                    &image.pixels,
+                );
+                ctx.load_texture(name, color_image, egui::TextureOptions::NEAREST)
+            })
+        };
+
+        self.textures = debugger
+            .get_textures(state)
+            .into_iter()
+            .filter(|texture| texture.enabled)
+            .map(|texture| {
+                let image = debugger.texture_preview(&memory, &texture, PREVIEW_SIZE);
+                ImagePreview {
+                    label: format!("Unit {}", texture.unit),
+                    details: format!(
+                        "{}x{} {}\n0x{:08X}{}",
+                        texture.width,
+                        texture.height,
+                        texture.format,
+                        texture.offset,
+                        if texture.swizzled() { " swizzled" } else { "" }
+                    ),
+                    image: upload(format!("rsx_texture_{}", texture.unit), image),
+                }
+            })
+            .collect();
+
+        self.render_targets = debugger
+            .get_render_targets(state)
+            .into_iter()
+            .filter(|target| target.active)
+            .map(|target| {
+                let image = debugger.render_target_preview(&memory, &target, PREVIEW_SIZE);
+                ImagePreview {
+                    label: format!("Color {}", target.index),
+                    details: format!("{}x{} {}\n0x{:08X}", target.width, target.height, target.format, target.offset),
+                    image: upload(format!("rsx_render_target_{}", target.index), image),
+                }
+            })
+            .collect();
+    }
+
+    /// Show previews side by side
+    fn show_previews(ui: &mut egui::Ui, previews: &[ImagePreview], empty: &str) {
+        if previews.is_empty() {
+            ui.label(empty);
+            return;
+        }
+        ui.horizontal_wrapped(|ui| {
+            for preview in previews {
+                ui.group(|ui| {
+                    ui.vertical(|ui| {
+                        ui.label(egui::RichText::new(&preview.label).strong());
+                        match &preview.image {
+                            Ok(texture) => {
+                                ui.image((texture.id(), texture.size_vec2()));
+                            }
+                            Err(e) => {
+                                ui.colored_label(egui::Color32::YELLOW, e);
+                            }
+                        }
+                        ui.label(egui::RichText::new(&preview.details).small());
+                    });
+                });
+            }
+        });
+    }
+
+    /// Show the last draw calls, newest first, and the selected one's shaders and bindings
+    fn show_draw_calls(&mut self, ui: &mut egui::Ui, debugger: &mut RsxDebugger) {
+        ui.horizontal(|ui| {
+            ui.label("Keep last");
+            let mut count = debugger.max_draw_calls();
+            if ui.add(egui::DragValue::new(&mut count).range(1..=4096)).changed() {
+                debugger.set_max_draw_calls(count);
+            }
+            ui.label("draw calls");
+            if ui.button("🗑 Clear").clicked() {
+                debugger.clear_draw_calls();
+                self.selected_draw = None;
+            }
+        });
+
+        ui.horizontal(|ui| {
+            egui::ScrollArea::vertical()
+                .id_salt("rsx_draw_call_list")
+                .max_height(250.0)
+                .show(ui, |ui| {
+                    ui.set_min_width(300.0);
+                    if debugger.draw_calls().next().is_none() {
+                        ui.label("No draw calls recorded.");
+                    }
+                    for draw in debugger.draw_calls().rev() {
+                        let label = format!(
+                            "#{} (frame {}) {} {}: first={}, count={}",
+                            draw.index,
+                            draw.frame,
+                            if draw.indexed { "Indexed" } else { "Arrays" },
+                            RsxDebugger::primitive_name(draw.primitive),
+                            draw.first,
+                            draw.count
+                        );
+                        if ui.selectable_label(self.selected_draw == Some(draw.index), label).clicked() {
+                            self.selected_draw = Some(draw.index);
+                        }
+                    }
+                });
+
+            ui.separator();
+
+            let Some(draw) = self
+                .selected_draw
+                .and_then(|index| debugger.draw_calls().find(|draw| draw.index == index))
+            else {
+                ui.label("Select a draw call to view its shaders");
+                return;
+            };
+
+            if self.draw_program.as_ref().map(|(index, _)| *index) != Some(draw.index) {
+                let program = match &self.memory {
+                    Some(memory) => debugger.read_fragment_program(memory, draw.fragment_program, PROGRAM_PREVIEW_BYTES),
+                    None => Err(String::from("No memory")),
+                };
+                self.draw_program = Some((draw.index, program));
+            }
+
+            ui.vertical(|ui| {
+                egui::Grid::new("rsx_draw_call_details")
+                    .num_columns(2)
+                    .spacing([20.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Vertex Program:");
+                        ui.monospace(format!("start slot {}", draw.vertex_program));
+                        ui.end_row();
+
+                        ui.label("Fragment Program:");
+                        ui.monospace(format!(
+                            "0x{:08X} ({})",
+                            draw.fragment_program & !0x3,
+                            if draw.fragment_program & 0x3 == 2 { "main" } else { "local" }
+                        ));
+                        ui.end_row();
+
+                        ui.label("Textures:");
+                        let textures: Vec<_> = draw
+                            .textures
+                            .iter()
+                            .map(|(unit, offset)| format!("{}: 0x{:08X}", unit, offset))
+                            .collect();
+                        ui.monospace(if textures.is_empty() { String::from("none") } else { textures.join("\n") });
+                        ui.end_row();
+
+                        ui.label("Color Targets:");
+                        let targets: Vec<_> = draw
+                            .color_targets
+                            .iter()
+                            .map(|(index, offset)| format!("{}: 0x{:08X}", index, offset))
+                            .collect();
+                        ui.monospace(if targets.is_empty() { String::from("none") } else { targets.join("\n") });
+                        ui.end_row();
+                    });
+
+                ui.add_space(5.0);
+                ui.label(egui::RichText::new("Fragment Program Microcode").strong());
+                match self.draw_program.as_ref().map(|(_, program)| program) {
+                    Some(Ok(bytes)) => {
+                        for (i, chunk) in bytes.chunks(16).enumerate() {
+                            let hex: Vec<_> = chunk.iter().map(|b| format!("{:02X}", b)).collect();
+                            ui.monospace(format!("{:04X}: {}", i * 16, hex.join(" ")));
+                        }
+                    }
+                    Some(Err(e)) => {
+                        ui.label(e);
+                    }
+                    None => {}
+                }
+            });
+        });
+    }
+
     /// Show shader details panel
     fn show_shader_details(&self, ui: &mut egui::Ui, shader: &ShaderInfo) {
         ui.label(egui::RichText::new(format!("Shader #{} - {}", shader.id, shader.shader_type.label())).strong());
@@ -1,43 +1,449 @@
 //! UI themes
+//!
+//! A theme is a light or dark base with an accent color, text size,
+//! spacing and rounding. The built-in Light and Dark themes can be copied
+//! and edited; user themes are TOML files in the themes directory beside
+//! the configuration file.
 
 use eframe::egui;
+use oc_core::config::{Config, ThemeMode, UiConfig};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 
-/// Available themes
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Theme {
-    Light,
-    Dark,
+/// Body text size of egui's default style
+const DEFAULT_FONT_SIZE: f32 = 12.5;
+
+/// A user-editable theme
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub name: String,
+    /// Dark base colors rather than light
+    pub dark: bool,
+    /// Color of selections, links and active widgets
+    pub accent: [u8; 3],
+    /// Body text size; other text scales with it
+    pub font_size: f32,
+    /// Multiplier of the space between widgets
+    pub spacing: f32,
+    /// Corner radius of widgets; windows are rounded three times as much
+    pub rounding: f32,
 }
 
 impl Theme {
-    /// Apply the theme to the egui context
-    pub fn apply(&self, ctx: &egui::Context) {
-        match self {
-            Theme::Light => {
-                ctx.set_visuals(egui::Visuals::light());
-            }
-            Theme::Dark => {
-                ctx.set_visuals(egui::Visuals::dark());
-            }
+    /// Built-in dark theme
+    pub fn dark() -> Self {
+        Self {
+            name: String::from("Dark"),
+            dark: true,
+            accent: [0, 92, 128],
+            font_size: DEFAULT_FONT_SIZE,
+            spacing: 1.0,
+            rounding: 2.0,
+        }
+    }
+
+    /// Built-in light theme
+    pub fn light() -> Self {
+        Self {
+            name: String::from("Light"),
+            dark: false,
+            accent: [144, 209, 255],
+            ..Self::dark()
+        }
+    }
+
+    /// Whether the theme is one of the built-in ones
+    pub fn is_builtin(&self) -> bool {
+        self.name == "Dark" || self.name == "Light"
+    }
+
+    /// egui style of the theme
+    pub fn style(&self) -> egui::Style {
+        let mut style = egui::Style {
+            visuals: if self.dark { egui::Visuals::dark() } else { egui::Visuals::light() },
+            ..egui::Style::default()
+        };
+
+        let accent = egui::Color32::from_rgb(self.accent[0], self.accent[1], self.accent[2]);
+        let visuals = &mut style.visuals;
+        visuals.selection.bg_fill = accent;
+        visuals.hyperlink_color = if self.dark {
+            accent.lerp_to_gamma(egui::Color32::WHITE, 0.5)
+        } else {
+            accent.lerp_to_gamma(egui::Color32::BLACK, 0.5)
+        };
+        visuals.widgets.active.bg_fill = accent;
+        visuals.widgets.active.weak_bg_fill = accent;
+        visuals.widgets.hovered.bg_stroke.color = accent;
+
+        let rounding = egui::Rounding::same(self.rounding.max(0.0));
+        for widget in [
+            &mut visuals.widgets.noninteractive,
+            &mut visuals.widgets.inactive,
+            &mut visuals.widgets.hovered,
+            &mut visuals.widgets.active,
+            &mut visuals.widgets.open,
+        ] {
+            widget.rounding = rounding;
+        }
+        visuals.window_rounding = egui::Rounding::same(self.rounding.max(0.0) * 3.0);
+        visuals.menu_rounding = visuals.window_rounding;
+
+        let scale = self.font_size / DEFAULT_FONT_SIZE;
+        for font in style.text_styles.values_mut() {
+            font.size *= scale;
         }
+        let spacing = &mut style.spacing;
+        spacing.item_spacing *= self.spacing;
+        spacing.button_padding *= self.spacing;
+        spacing.indent *= self.spacing;
+        spacing.interact_size.y *= scale.max(1.0);
+        style
     }
 
-    /// Get theme name for display
-    pub fn name(&self) -> &'static str {
-        match self {
-            Theme::Light => "Light",
-            Theme::Dark => "Dark",
+    /// Read a theme file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let theme: Theme = toml::from_str(&text).map_err(|e| format!("Invalid theme {}: {}", path.display(), e))?;
+        if theme.name.trim().is_empty() {
+            return Err(format!("Theme {} has no name", path.display()));
         }
+        Ok(theme)
     }
 
-    /// Get all available themes
-    pub fn all() -> &'static [Theme] {
-        &[Theme::Light, Theme::Dark]
+    /// Write the theme to `path`
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        let text = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        std::fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
     }
 }
 
 impl Default for Theme {
     fn default() -> Self {
-        Theme::Dark
+        Self::dark()
+    }
+}
+
+/// Built-in and user themes
+pub struct ThemeLibrary {
+    themes: Vec<Theme>,
+    dir: PathBuf,
+}
+
+impl ThemeLibrary {
+    /// Directory user themes are stored in
+    pub fn directory() -> PathBuf {
+        Config::config_path().with_file_name("themes")
+    }
+
+    /// Load the built-in themes and those in the themes directory
+    pub fn load() -> Self {
+        let mut library = Self {
+            themes: vec![Theme::dark(), Theme::light()],
+            dir: Self::directory(),
+        };
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(&library.dir)
+            .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        paths.sort();
+        for path in paths.iter().filter(|path| path.extension().is_some_and(|ext| ext == "toml")) {
+            match Theme::load(path) {
+                Ok(theme) if !theme.is_builtin() => library.insert(theme),
+                Ok(_) => tracing::warn!("Theme {} uses a built-in name", path.display()),
+                Err(e) => tracing::warn!("{}", e),
+            }
+        }
+        library
+    }
+
+    /// All themes, built-in first
+    pub fn themes(&self) -> &[Theme] {
+        &self.themes
+    }
+
+    /// Theme called `name`
+    pub fn get(&self, name: &str) -> Option<&Theme> {
+        self.themes.iter().find(|theme| theme.name == name)
+    }
+
+    /// Theme for `name`, falling back to the built-in of the same brightness
+    fn resolve(&self, name: &str, dark: bool) -> Theme {
+        self.get(name)
+            .cloned()
+            .unwrap_or_else(|| if dark { Theme::dark() } else { Theme::light() })
+    }
+
+    /// Add or replace a theme in the list
+    fn insert(&mut self, theme: Theme) {
+        match self.themes.iter_mut().find(|existing| existing.name == theme.name) {
+            Some(existing) => *existing = theme,
+            None => self.themes.push(theme),
+        }
+    }
+
+    /// File a user theme is saved to
+    fn path(&self, name: &str) -> PathBuf {
+        let file: String = name
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        self.dir.join(format!("{}.toml", file))
+    }
+
+    /// Save a user theme and add it to the list
+    pub fn save(&mut self, theme: Theme) -> Result<(), String> {
+        if theme.is_builtin() {
+            return Err(format!("\"{}\" is a built-in theme; save it under another name", theme.name));
+        }
+        if theme.name.trim().is_empty() {
+            return Err(String::from("The theme needs a name"));
+        }
+        theme.save_to(&self.path(&theme.name))?;
+        self.insert(theme);
+        Ok(())
+    }
+
+    /// Delete a user theme
+    pub fn delete(&mut self, name: &str) -> Result<(), String> {
+        match self.get(name) {
+            Some(theme) if !theme.is_builtin() => {}
+            _ => return Err(format!("\"{}\" can't be deleted", name)),
+        }
+        let path = self.path(name);
+        if path.exists() {
+            std::fs::remove_file(&path).map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+        }
+        self.themes.retain(|theme| theme.name != name);
+        Ok(())
+    }
+
+    /// Copy a theme file into the library, returning the theme's name
+    pub fn import(&mut self, path: &Path) -> Result<String, String> {
+        let mut theme = Theme::load(path)?;
+        if theme.is_builtin() {
+            theme.name = format!("{} (imported)", theme.name);
+        }
+        let name = theme.name.clone();
+        self.save(theme)?;
+        Ok(name)
+    }
+
+    /// Apply the configured themes to `ctx`
+    ///
+    /// Both the dark and light styles are set so egui can switch between
+    /// them when following the OS preference.
+    pub fn apply(&self, ctx: &egui::Context, config: &UiConfig) {
+        ctx.set_style_of(egui::Theme::Dark, self.resolve(&config.dark_theme, true).style());
+        ctx.set_style_of(egui::Theme::Light, self.resolve(&config.light_theme, false).style());
+        ctx.set_theme(match config.theme_mode {
+            ThemeMode::Light => egui::ThemePreference::Light,
+            ThemeMode::Dark => egui::ThemePreference::Dark,
+            ThemeMode::System => egui::ThemePreference::System,
+        });
+    }
+}
+
+/// Window editing the themes and choosing the ones used
+pub struct ThemeEditor {
+    library: ThemeLibrary,
+    /// Theme being edited
+    draft: Theme,
+    status: Option<Result<String, String>>,
+}
+
+impl ThemeEditor {
+    /// Create an editor over the saved themes
+    pub fn new() -> Self {
+        Self {
+            library: ThemeLibrary::load(),
+            draft: Theme::dark(),
+            status: None,
+        }
+    }
+
+    /// Apply the configured themes to `ctx`
+    pub fn apply(&self, ctx: &egui::Context, config: &UiConfig) {
+        self.library.apply(ctx, config);
+    }
+
+    /// Start editing a copy of the theme called `name`
+    fn edit(&mut self, name: &str) {
+        if let Some(theme) = self.library.get(name) {
+            self.draft = theme.clone();
+            if self.draft.is_builtin() {
+                self.draft.name = format!("{} Custom", name);
+            }
+        }
+    }
+
+    /// Show the editor, returning whether `config` changed
+    pub fn show(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, config: &mut UiConfig) -> bool {
+        let before = (config.theme_mode, config.dark_theme.clone(), config.light_theme.clone());
+
+        ui.horizontal(|ui| {
+            ui.label("Mode:");
+            ui.selectable_value(&mut config.theme_mode, ThemeMode::Light, "☀ Light");
+            ui.selectable_value(&mut config.theme_mode, ThemeMode::Dark, "🌙 Dark");
+            ui.selectable_value(&mut config.theme_mode, ThemeMode::System, "🖥 Follow System");
+        });
+        if config.theme_mode == ThemeMode::System {
+            let system = match ctx.system_theme() {
+                Some(egui::Theme::Dark) => "dark",
+                Some(egui::Theme::Light) => "light",
+                None => "unknown (dark is used)",
+            };
+            ui.label(egui::RichText::new(format!("The system prefers {}", system)).weak());
+        }
+
+        egui::Grid::new("theme_choice").num_columns(2).show(ui, |ui| {
+            for (label, choice, dark) in [
+                ("Dark mode theme:", &mut config.dark_theme, true),
+                ("Light mode theme:", &mut config.light_theme, false),
+            ] {
+                ui.label(label);
+                egui::ComboBox::from_id_salt(label)
+                    .selected_text(choice.as_str())
+                    .show_ui(ui, |ui| {
+                        for theme in self.library.themes().iter().filter(|theme| theme.dark == dark) {
+                            ui.selectable_value(choice, theme.name.clone(), &theme.name);
+                        }
+                    });
+                ui.end_row();
+            }
+        });
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.strong("Edit Theme");
+            egui::ComboBox::from_id_salt("theme_edit_base")
+                .selected_text("Copy from...")
+                .show_ui(ui, |ui| {
+                    let names: Vec<String> = self.library.themes().iter().map(|theme| theme.name.clone()).collect();
+                    for name in names {
+                        if ui.selectable_label(false, &name).clicked() {
+                            self.edit(&name);
+                        }
+                    }
+                });
+        });
+
+        let mut edited = false;
+        egui::Grid::new("theme_fields").num_columns(2).show(ui, |ui| {
+            ui.label("Name:");
+            ui.text_edit_singleline(&mut self.draft.name);
+            ui.end_row();
+            ui.label("Base:");
+            ui.horizontal(|ui| {
+                edited |= ui.selectable_value(&mut self.draft.dark, true, "Dark").changed();
+                edited |= ui.selectable_value(&mut self.draft.dark, false, "Light").changed();
+            });
+            ui.end_row();
+            ui.label("Accent:");
+            edited |= ui.color_edit_button_srgb(&mut self.draft.accent).changed();
+            ui.end_row();
+            ui.label("Font size:");
+            edited |= ui.add(egui::Slider::new(&mut self.draft.font_size, 9.0..=24.0).step_by(0.5)).changed();
+            ui.end_row();
+            ui.label("Spacing:");
+            edited |= ui.add(egui::Slider::new(&mut self.draft.spacing, 0.5..=2.0).step_by(0.05)).changed();
+            ui.end_row();
+            ui.label("Rounding:");
+            edited |= ui.add(egui::Slider::new(&mut self.draft.rounding, 0.0..=12.0).step_by(0.5)).changed();
+            ui.end_row();
+        });
+
+        // Preview the draft in place of the theme of its brightness
+        if edited {
+            let theme = if self.draft.dark { egui::Theme::Dark } else { egui::Theme::Light };
+            ctx.set_style_of(theme, self.draft.style());
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("💾 Save").clicked() {
+                let name = self.draft.name.trim().to_string();
+                self.draft.name = name.clone();
+                self.status = Some(self.library.save(self.draft.clone()).map(|()| {
+                    // Use the saved theme for its brightness
+                    if self.draft.dark {
+                        config.dark_theme = name.clone();
+                    } else {
+                        config.light_theme = name.clone();
+                    }
+                    format!("Saved {}", name)
+                }));
+            }
+            if ui.button("Revert Preview").clicked() {
+                self.library.apply(ctx, config);
+            }
+            let deletable = self.library.get(&self.draft.name).is_some_and(|theme| !theme.is_builtin());
+            if ui.add_enabled(deletable, egui::Button::new("🗑 Delete")).clicked() {
+                let name = self.draft.name.clone();
+                self.status = Some(self.library.delete(&name).map(|()| format!("Deleted {}", name)));
+                for choice in [&mut config.dark_theme, &mut config.light_theme] {
+                    if *choice == name {
+                        choice.clear();
+                    }
+                }
+                if config.dark_theme.is_empty() {
+                    config.dark_theme = String::from("Dark");
+                }
+                if config.light_theme.is_empty() {
+                    config.light_theme = String::from("Light");
+                }
+            }
+            ui.separator();
+            if ui.button("Import...").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .set_title("Import Theme")
+                    .add_filter("Themes", &["toml"])
+                    .pick_file()
+                {
+                    self.status = Some(self.library.import(&path).map(|name| {
+                        self.edit(&name);
+                        format!("Imported {}", name)
+                    }));
+                }
+            }
+            if ui.button("Export...").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .set_title("Export Theme")
+                    .set_file_name(format!("{}.toml", self.draft.name))
+                    .add_filter("Themes", &["toml"])
+                    .save_file()
+                {
+                    self.status = Some(
+                        self.draft
+                            .save_to(&path)
+                            .map(|()| format!("Exported {} to {}", self.draft.name, path.display())),
+                    );
+                }
+            }
+        });
+
+        match self.status {
+            Some(Ok(ref message)) => {
+                ui.label(egui::RichText::new(message).weak());
+            }
+            Some(Err(ref e)) => {
+                ui.colored_label(ui.visuals().error_fg_color, e);
+            }
+            None => {}
+        }
+
+        let changed = before != (config.theme_mode, config.dark_theme.clone(), config.light_theme.clone());
+        if changed {
+            self.library.apply(ctx, config);
+        }
+        changed
+    }
+}
+
+impl Default for ThemeEditor {
+    fn default() -> Self {
+        Self::new()
     }
 }
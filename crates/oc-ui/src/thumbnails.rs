@@ -0,0 +1,125 @@
+//! Game icons and backgrounds, downscaled once and cached on disk
+
+use eframe::egui;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Image shipped in a game directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameImage {
+    /// ICON0.PNG, the box art tile
+    Icon,
+    /// PIC1.PNG, the backdrop
+    Background,
+}
+
+impl GameImage {
+    /// File name in the game directory
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            GameImage::Icon => "ICON0.PNG",
+            GameImage::Background => "PIC1.PNG",
+        }
+    }
+
+    /// Largest size the image is cached at
+    fn max_size(&self) -> (u32, u32) {
+        match self {
+            // Icons are natively 320x176
+            GameImage::Icon => (320, 176),
+            GameImage::Background => (640, 360),
+        }
+    }
+}
+
+/// Textures of game images, backed by downscaled copies on disk
+pub struct ThumbnailCache {
+    /// Directory downscaled images are saved in
+    dir: PathBuf,
+    /// Textures by source file, None where the image could not be read
+    textures: HashMap<PathBuf, Option<egui::TextureHandle>>,
+}
+
+impl ThumbnailCache {
+    /// Create a cache saving thumbnails into `dir`
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            textures: HashMap::new(),
+        }
+    }
+
+    /// Directory thumbnails are saved in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Save thumbnails into `dir` from now on
+    pub fn set_dir(&mut self, dir: PathBuf) {
+        self.dir = dir;
+    }
+
+    /// Forget loaded textures so images are read again
+    pub fn clear(&mut self) {
+        self.textures.clear();
+    }
+
+    /// Texture of the image at `source`, loading it on first use
+    pub fn get(&mut self, ctx: &egui::Context, source: &Path, image: GameImage) -> Option<&egui::TextureHandle> {
+        if !self.textures.contains_key(source) {
+            let texture = self.load(source, image).map(|pixels| {
+                let size = [pixels.width() as usize, pixels.height() as usize];
+                let color_image = egui::ColorImage::from_rgba_unmultiplied(size, pixels.as_raw());
+                ctx.load_texture(source.to_string_lossy(), color_image, egui::TextureOptions::LINEAR)
+            });
+            self.textures.insert(source.to_path_buf(), texture);
+        }
+        self.textures.get(source).and_then(Option::as_ref)
+    }
+
+    /// File the thumbnail of `source` is saved as
+    fn cache_path(&self, source: &Path, image: GameImage) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        source.hash(&mut hasher);
+        let stem = image.file_name().trim_end_matches(".PNG").to_lowercase();
+        self.dir.join(format!("{:016x}_{}.png", hasher.finish(), stem))
+    }
+
+    /// Read the thumbnail from disk, or decode and downscale `source` and save it
+    fn load(&self, source: &Path, image: GameImage) -> Option<image::RgbaImage> {
+        let cache_path = self.cache_path(source, image);
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let source_modified = modified(source)?;
+        if modified(&cache_path).unwrap_or(SystemTime::UNIX_EPOCH) >= source_modified {
+            match image::open(&cache_path) {
+                Ok(cached) => return Some(cached.to_rgba8()),
+                Err(e) => tracing::debug!("Ignoring thumbnail {}: {}", cache_path.display(), e),
+            }
+        }
+
+        let decoded = match image::open(source) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                tracing::warn!("Failed to decode {}: {}", source.display(), e);
+                return None;
+            }
+        };
+        let (max_width, max_height) = image.max_size();
+        let pixels = if decoded.width() > max_width || decoded.height() > max_height {
+            decoded.thumbnail(max_width, max_height).to_rgba8()
+        } else {
+            decoded.to_rgba8()
+        };
+
+        let saved = std::fs::create_dir_all(&self.dir)
+            .map_err(|e| e.to_string())
+            .and_then(|_| pixels.save_with_format(&cache_path, image::ImageFormat::Png).map_err(|e| e.to_string()));
+        if let Err(e) = saved {
+            tracing::warn!("Failed to cache thumbnail {}: {}", cache_path.display(), e);
+        }
+        Some(pixels)
+    }
+}
@@ -4,6 +4,7 @@
 
 use crate::devices::bdvd::BdvdDevice;
 use crate::formats::iso::{IsoReader, IsoVolume};
+use crate::formats::sfo::Sfo;
 use crate::VirtualFileSystem;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -129,21 +130,19 @@ impl DiscManager {
 
     /// Extract game information from disc structure
     fn extract_game_info(&self, disc_path: &PathBuf, format: DiscFormat) -> (Option<String>, Option<String>) {
-        // For folder format, look for PS3_GAME directory
+        // For folder format, read PS3_GAME/PARAM.SFO
         if format == DiscFormat::Folder {
-            let ps3_game_path = disc_path.join("PS3_GAME");
-            if ps3_game_path.exists() {
-                // Try to find PARAM.SFO
-                let param_sfo_path = ps3_game_path.join("PARAM.SFO");
-                if param_sfo_path.exists() {
-                    // TODO: Parse PARAM.SFO for title and game ID
-                    // For now, just extract game ID from path if available
-                    let game_id = disc_path
-                        .file_name()
-                        .and_then(|s| s.to_str())
-                        .map(|s| s.to_string());
-                    return (None, game_id);
-                }
+            let param_sfo_path = disc_path.join("PS3_GAME").join("PARAM.SFO");
+            if param_sfo_path.exists() {
+                let sfo = Sfo::load(&param_sfo_path).ok();
+                let title = sfo.as_ref().and_then(|sfo| sfo.title()).map(str::to_string);
+                // Fall back to the folder name for the game ID
+                let game_id = sfo
+                    .as_ref()
+                    .and_then(|sfo| sfo.title_id())
+                    .or_else(|| disc_path.file_name().and_then(|s| s.to_str()))
+                    .map(str::to_string);
+                return (title, game_id);
             }
         }
 
@@ -195,4 +194,29 @@ mod tests {
         let manager = DiscManager::new();
         assert!(!manager.is_disc_mounted());
     }
+
+    #[test]
+    fn test_mount_folder_disc() {
+        use crate::formats::sfo::SfoValue;
+
+        let dir = std::env::temp_dir().join(format!("oc_vfs_disc_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("PS3_GAME")).unwrap();
+        let mut sfo = Sfo::new();
+        sfo.set("TITLE", SfoValue::Utf8("Test Game".to_string()));
+        sfo.set("TITLE_ID", SfoValue::Utf8("BLUS30443".to_string()));
+        sfo.save(&dir.join("PS3_GAME/PARAM.SFO")).unwrap();
+
+        let vfs = VirtualFileSystem::new();
+        let manager = DiscManager::new();
+        manager.mount_disc(&vfs, dir.clone()).unwrap();
+        let info = manager.disc_info().unwrap();
+        assert_eq!(info.title.as_deref(), Some("Test Game"));
+        assert_eq!(info.game_id.as_deref(), Some("BLUS30443"));
+        assert!(vfs.is_mounted("/dev_bdvd"));
+
+        manager.unmount_disc(&vfs);
+        assert!(!manager.is_disc_mounted());
+        assert!(!vfs.is_mounted("/dev_bdvd"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -1,27 +1,244 @@
 //! PARAM.SFO file format
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Data format of UTF-8 strings stored without a terminator
+pub const FMT_UTF8S: u16 = 0x0004;
+/// Data format of NUL-terminated UTF-8 strings
+pub const FMT_UTF8: u16 = 0x0204;
+/// Data format of 32-bit integers
+pub const FMT_INTEGER: u16 = 0x0404;
 
 /// SFO file entry
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SfoValue {
     Utf8(String),
     Utf8S(String),
     Integer(u32),
+    /// Unterminated data that is not text, such as SAVEDATA_FILE_LIST
+    Binary(Vec<u8>),
+}
+
+impl SfoValue {
+    /// Data format stored in the index table
+    pub fn format(&self) -> u16 {
+        match self {
+            Self::Utf8(_) => FMT_UTF8,
+            Self::Utf8S(_) | Self::Binary(_) => FMT_UTF8S,
+            Self::Integer(_) => FMT_INTEGER,
+        }
+    }
+
+    /// Bytes the value takes, including the terminator of a UTF-8 string
+    pub fn data_len(&self) -> u32 {
+        match self {
+            Self::Utf8(s) => s.len() as u32 + 1,
+            Self::Utf8S(s) => s.len() as u32,
+            Self::Integer(_) => 4,
+            Self::Binary(data) => data.len() as u32,
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Utf8(s) => s.bytes().chain([0]).collect(),
+            Self::Utf8S(s) => s.as_bytes().to_vec(),
+            Self::Integer(v) => v.to_le_bytes().to_vec(),
+            Self::Binary(data) => data.clone(),
+        }
+    }
+}
+
+/// Value of a key and the space reserved for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SfoEntry {
+    pub value: SfoValue,
+    /// Bytes reserved in the data table, at least the value's length
+    pub max_len: u32,
+}
+
+/// Type a known key must have
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SfoKind {
+    Utf8,
+    Utf8S,
+    Integer,
+}
+
+impl SfoKind {
+    /// Kind of a value
+    pub fn of(value: &SfoValue) -> Self {
+        match value {
+            SfoValue::Utf8(_) => Self::Utf8,
+            SfoValue::Utf8S(_) | SfoValue::Binary(_) => Self::Utf8S,
+            SfoValue::Integer(_) => Self::Integer,
+        }
+    }
+
+    /// Name used in the PS3 SDK
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf8",
+            Self::Utf8S => "utf8-s",
+            Self::Integer => "int32",
+        }
+    }
 }
 
+/// A key with a known meaning
+#[derive(Debug, Clone, Copy)]
+pub struct KnownKey {
+    pub name: &'static str,
+    pub kind: SfoKind,
+    /// Space the SDK reserves for the value
+    pub max_len: u32,
+    pub description: &'static str,
+}
+
+const fn key(name: &'static str, kind: SfoKind, max_len: u32, description: &'static str) -> KnownKey {
+    KnownKey { name, kind, max_len, description }
+}
+
+/// Keys found in PARAM.SFO files of games, updates and saves
+pub const KNOWN_KEYS: &[KnownKey] = &[
+    key("ACCOUNT_ID", SfoKind::Utf8S, 16, "Account owning the save"),
+    key("APP_VER", SfoKind::Utf8, 8, "Application version, as NN.NN"),
+    key("ATTRIBUTE", SfoKind::Integer, 4, "Feature flags"),
+    key("BOOTABLE", SfoKind::Integer, 4, "Whether the content can be launched"),
+    key("CATEGORY", SfoKind::Utf8, 4, "Content category such as DG or HG"),
+    key("CONTENT_ID", SfoKind::Utf8, 48, "Store content ID"),
+    key("DETAIL", SfoKind::Utf8, 1024, "Save description"),
+    key("GAMEDATA_ID", SfoKind::Utf8, 32, "Game data directory name"),
+    key("ITEM_PRIORITY", SfoKind::Integer, 4, "Order in the XMB"),
+    key("LANG", SfoKind::Integer, 4, "Language"),
+    key("LICENSE", SfoKind::Utf8, 512, "License text"),
+    key("NP_COMMUNICATION_ID", SfoKind::Utf8, 16, "NP communication ID"),
+    key("NPCOMMID", SfoKind::Utf8, 16, "NP communication ID"),
+    key("PADDING", SfoKind::Utf8S, 8, "Reserved"),
+    key("PARAMS", SfoKind::Utf8S, 1024, "Save parameters"),
+    key("PARAMS2", SfoKind::Utf8S, 12, "Save parameters"),
+    key("PARENTAL_LEVEL", SfoKind::Integer, 4, "Parental control level, 0 to 11"),
+    key("PS3_SYSTEM_VER", SfoKind::Utf8, 8, "Required firmware, as NN.NNNN"),
+    key("REGION_DENY", SfoKind::Integer, 4, "Regions the content cannot run in"),
+    key("RESOLUTION", SfoKind::Integer, 4, "Supported video modes"),
+    key("SAVEDATA_DIRECTORY", SfoKind::Utf8, 64, "Save directory name"),
+    key("SAVEDATA_FILE_LIST", SfoKind::Utf8S, 3168, "Protected save files"),
+    key("SAVEDATA_LIST_PARAM", SfoKind::Utf8, 8, "Value sorting saves in the list"),
+    key("SOUND_FORMAT", SfoKind::Integer, 4, "Supported audio formats"),
+    key("SUB_TITLE", SfoKind::Utf8, 128, "Save subtitle"),
+    key("TARGET_APP_VER", SfoKind::Utf8, 8, "Application version an update applies to"),
+    key("TITLE", SfoKind::Utf8, 128, "Title"),
+    key("TITLE_ID", SfoKind::Utf8, 16, "Title ID such as BLUS30443"),
+    key("VERSION", SfoKind::Utf8, 8, "Content version, as NN.NN"),
+    key("XMB_APPS", SfoKind::Integer, 4, "XMB applications"),
+];
+
+/// Localized title key such as TITLE_01
+const LOCALIZED_TITLE: KnownKey = key("TITLE_xx", SfoKind::Utf8, 128, "Localized title");
+
+/// Meaning of `name`, including the localized TITLE_00 to TITLE_20 keys
+pub fn known_key(name: &str) -> Option<&'static KnownKey> {
+    if let Some(lang) = name.strip_prefix("TITLE_") {
+        if lang.len() == 2 && lang.parse::<u32>().is_ok_and(|lang| lang <= 20) {
+            return Some(&LOCALIZED_TITLE);
+        }
+    }
+    KNOWN_KEYS.iter().find(|key| key.name == name)
+}
+
+/// Bits of RESOLUTION
+pub const RESOLUTION_FLAGS: &[(u32, &str)] = &[
+    (0x01, "480p (4:3)"),
+    (0x02, "576p (4:3)"),
+    (0x04, "720p"),
+    (0x08, "1080p"),
+    (0x10, "480p (16:9)"),
+    (0x20, "576p (16:9)"),
+];
+
+/// Bits of SOUND_FORMAT
+pub const SOUND_FORMAT_FLAGS: &[(u32, &str)] = &[
+    (0x001, "LPCM 2.0"),
+    (0x004, "LPCM 5.1"),
+    (0x010, "LPCM 7.1"),
+    (0x100, "Dolby Digital"),
+    (0x200, "DTS"),
+];
+
+/// Bits of ATTRIBUTE
+pub const ATTRIBUTE_FLAGS: &[(u32, &str)] = &[
+    (0x0000_0001, "PSP Remote Play (MPEG-4 SP)"),
+    (0x0000_0002, "PSP Export"),
+    (0x0000_0004, "PSP Remote Play (MPEG-4 AVC)"),
+    (0x0000_0008, "XMB in game forced on"),
+    (0x0000_0010, "XMB in game disabled"),
+    (0x0000_0020, "Custom background music"),
+    (0x0000_0040, "System voice chat"),
+    (0x0000_0080, "PS Vita Remote Play"),
+    (0x0000_0100, "Move controller warning"),
+    (0x0000_0200, "Navigation controller warning"),
+    (0x0000_0400, "PlayStation Eye warning"),
+    (0x0000_0800, "Move calibration notice"),
+    (0x0000_1000, "Stereoscopic 3D warning"),
+    (0x0010_0000, "Install disc"),
+    (0x0020_0000, "Install packages"),
+    (0x0080_0000, "Game purchase enabled"),
+];
+
+/// Known bits of a flag key, if it is one
+pub fn key_flags(name: &str) -> Option<&'static [(u32, &'static str)]> {
+    match name {
+        "RESOLUTION" => Some(RESOLUTION_FLAGS),
+        "SOUND_FORMAT" => Some(SOUND_FORMAT_FLAGS),
+        "ATTRIBUTE" => Some(ATTRIBUTE_FLAGS),
+        _ => None,
+    }
+}
+
+/// Every known bit of a flag key
+fn known_bits(name: &str) -> u32 {
+    key_flags(name).unwrap_or_default().iter().fold(0, |all, (flag, _)| all | flag)
+}
+
+/// Known CATEGORY codes
+pub const CATEGORIES: &[(&str, &str)] = &[
+    ("DG", "Disc game"),
+    ("HG", "HDD game"),
+    ("GD", "Game data"),
+    ("DP", "Disc package"),
+    ("AR", "Additional content"),
+    ("SD", "Save data"),
+    ("AP", "Photo application"),
+    ("AM", "Music application"),
+    ("AV", "Video application"),
+    ("AT", "TV application"),
+    ("CB", "Network application"),
+    ("HM", "PlayStation Home"),
+    ("WT", "Web TV"),
+    ("1P", "PS1 game"),
+    ("2P", "PS2 game"),
+    ("MN", "PSP minis"),
+];
+
 /// PARAM.SFO parser
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Sfo {
-    entries: HashMap<String, SfoValue>,
+    entries: BTreeMap<String, SfoEntry>,
 }
 
 impl Sfo {
+    /// Create an empty SFO
+    pub fn new() -> Self {
+        Self::default()
+    }
+
     /// Parse SFO from reader
     pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, std::io::Error> {
         let mut magic = [0u8; 4];
         reader.read_exact(&mut magic)?;
-        
+
         if &magic != b"\x00PSF" {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
@@ -37,7 +254,7 @@ impl Sfo {
         let data_table_start = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
         let entries_count = u32::from_le_bytes([header[12], header[13], header[14], header[15]]);
 
-        let mut entries = HashMap::new();
+        let mut entries = BTreeMap::new();
 
         for i in 0..entries_count {
             let entry_offset = 20 + i * 16;
@@ -49,7 +266,7 @@ impl Sfo {
             let key_offset = u16::from_le_bytes([entry_data[0], entry_data[1]]);
             let data_fmt = u16::from_le_bytes([entry_data[2], entry_data[3]]);
             let data_len = u32::from_le_bytes([entry_data[4], entry_data[5], entry_data[6], entry_data[7]]);
-            let _data_max_len = u32::from_le_bytes([entry_data[8], entry_data[9], entry_data[10], entry_data[11]]);
+            let data_max_len = u32::from_le_bytes([entry_data[8], entry_data[9], entry_data[10], entry_data[11]]);
             let data_offset = u32::from_le_bytes([entry_data[12], entry_data[13], entry_data[14], entry_data[15]]);
 
             // Read key
@@ -68,37 +285,143 @@ impl Sfo {
             // Read value
             reader.seek(SeekFrom::Start((data_table_start + data_offset) as u64))?;
             let value = match data_fmt {
-                0x0404 => {
+                FMT_INTEGER => {
                     let mut buf = [0u8; 4];
                     reader.read_exact(&mut buf)?;
                     SfoValue::Integer(u32::from_le_bytes(buf))
                 }
-                0x0004 | 0x0204 => {
+                FMT_UTF8S | FMT_UTF8 => {
                     let mut buf = vec![0u8; data_len as usize];
                     reader.read_exact(&mut buf)?;
-                    // Remove null terminator if present
-                    while buf.last() == Some(&0) {
-                        buf.pop();
-                    }
-                    let s = String::from_utf8_lossy(&buf).to_string();
-                    if data_fmt == 0x0004 {
-                        SfoValue::Utf8S(s)
+                    if data_fmt == FMT_UTF8S {
+                        // Special strings may hold binary data, which must survive a rewrite
+                        let text = buf.iter().rposition(|&b| b != 0).map_or(&buf[..0], |end| &buf[..=end]);
+                        match std::str::from_utf8(text) {
+                            Ok(s) if !s.contains('\0') => SfoValue::Utf8S(s.to_string()),
+                            _ => SfoValue::Binary(buf),
+                        }
                     } else {
-                        SfoValue::Utf8(s)
+                        // Remove null terminator if present
+                        while buf.last() == Some(&0) {
+                            buf.pop();
+                        }
+                        SfoValue::Utf8(String::from_utf8_lossy(&buf).to_string())
                     }
                 }
                 _ => continue,
             };
 
-            entries.insert(key, value);
+            let max_len = data_max_len.max(value.data_len());
+            entries.insert(key, SfoEntry { value, max_len });
         }
 
         Ok(Self { entries })
     }
 
+    /// Parse SFO from bytes
+    pub fn from_bytes(data: &[u8]) -> Result<Self, std::io::Error> {
+        Self::parse(&mut std::io::Cursor::new(data))
+    }
+
+    /// Read a PARAM.SFO file
+    pub fn load(path: &Path) -> Result<Self, std::io::Error> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// Write the SFO to a file
+    pub fn save(&self, path: &Path) -> Result<(), std::io::Error> {
+        std::fs::write(path, self.to_bytes())
+    }
+
+    /// Serialize the SFO, with keys in the sorted order the system expects
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let count = self.entries.len() as u32;
+        let mut keys = Vec::new();
+        let mut data = Vec::new();
+        let mut index = Vec::with_capacity(self.entries.len() * 16);
+        for (key, entry) in &self.entries {
+            let max_len = entry.max_len.max(entry.value.data_len()).next_multiple_of(4);
+            index.extend_from_slice(&(keys.len() as u16).to_le_bytes());
+            index.extend_from_slice(&entry.value.format().to_le_bytes());
+            index.extend_from_slice(&entry.value.data_len().to_le_bytes());
+            index.extend_from_slice(&max_len.to_le_bytes());
+            index.extend_from_slice(&(data.len() as u32).to_le_bytes());
+
+            keys.extend_from_slice(key.as_bytes());
+            keys.push(0);
+            let start = data.len();
+            data.extend_from_slice(&entry.value.to_bytes());
+            data.resize(start + max_len as usize, 0);
+        }
+        // The data table starts 4-byte aligned
+        keys.resize(keys.len().next_multiple_of(4), 0);
+
+        let key_table_start = 20 + index.len() as u32;
+        let data_table_start = key_table_start + keys.len() as u32;
+        let mut out = Vec::with_capacity(data_table_start as usize + data.len());
+        out.extend_from_slice(b"\x00PSF");
+        out.extend_from_slice(&0x0101u32.to_le_bytes());
+        out.extend_from_slice(&key_table_start.to_le_bytes());
+        out.extend_from_slice(&data_table_start.to_le_bytes());
+        out.extend_from_slice(&count.to_le_bytes());
+        out.extend_from_slice(&index);
+        out.extend_from_slice(&keys);
+        out.extend_from_slice(&data);
+        out
+    }
+
+    /// Entries in key order
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &SfoEntry)> {
+        self.entries.iter().map(|(key, entry)| (key.as_str(), entry))
+    }
+
+    /// Get a value
+    pub fn get(&self, key: &str) -> Option<&SfoValue> {
+        self.entries.get(key).map(|entry| &entry.value)
+    }
+
+    /// Set a value, keeping the space reserved for the key unless it must grow
+    pub fn set(&mut self, key: &str, value: SfoValue) {
+        let reserved = self
+            .entries
+            .get(key)
+            .map(|entry| entry.max_len)
+            .or_else(|| known_key(key).map(|known| known.max_len))
+            .unwrap_or(0);
+        let max_len = reserved.max(value.data_len());
+        self.entries.insert(key.to_string(), SfoEntry { value, max_len });
+    }
+
+    /// Remove a key, returning its value
+    pub fn remove(&mut self, key: &str) -> Option<SfoValue> {
+        self.entries.remove(key).map(|entry| entry.value)
+    }
+
+    /// Problems of the entries compared with what the known keys allow
+    pub fn validate(&self) -> Vec<String> {
+        let mut issues: Vec<String> = self
+            .entries
+            .iter()
+            .filter_map(|(key, entry)| validate_entry(key, &entry.value).err().map(|e| format!("{}: {}", key, e)))
+            .collect();
+        if self.is_save() {
+            let missing = ["TITLE", "SAVEDATA_DIRECTORY"].into_iter().filter(|key| !self.entries.contains_key(*key));
+            issues.extend(missing.map(|key| format!("{}: required in save data", key)));
+        } else if self.entries.contains_key("CATEGORY") {
+            let missing = ["TITLE", "TITLE_ID"].into_iter().filter(|key| !self.entries.contains_key(*key));
+            issues.extend(missing.map(|key| format!("{}: required", key)));
+        }
+        issues
+    }
+
+    /// Whether the SFO describes save data
+    pub fn is_save(&self) -> bool {
+        self.get_string("CATEGORY") == Some("SD") || self.entries.contains_key("SAVEDATA_DIRECTORY")
+    }
+
     /// Get a string value
     pub fn get_string(&self, key: &str) -> Option<&str> {
-        match self.entries.get(key)? {
+        match self.get(key)? {
             SfoValue::Utf8(s) | SfoValue::Utf8S(s) => Some(s),
             _ => None,
         }
@@ -106,7 +429,7 @@ impl Sfo {
 
     /// Get an integer value
     pub fn get_integer(&self, key: &str) -> Option<u32> {
-        match self.entries.get(key)? {
+        match self.get(key)? {
             SfoValue::Integer(v) => Some(*v),
             _ => None,
         }
@@ -128,10 +451,99 @@ impl Sfo {
     }
 }
 
+/// Whether `key` is made of the upper case letters, digits and underscores keys use
+pub fn is_valid_key(key: &str) -> bool {
+    !key.is_empty() && key.bytes().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_')
+}
+
+/// Check a value against its key's type, size and format
+pub fn validate_entry(key: &str, value: &SfoValue) -> Result<(), String> {
+    if !is_valid_key(key) {
+        return Err("keys are upper case letters, digits and underscores".to_string());
+    }
+    let Some(known) = known_key(key) else {
+        return Ok(());
+    };
+    let kind = SfoKind::of(value);
+    if kind != known.kind {
+        return Err(format!("must be {}, not {}", known.kind.as_str(), kind.as_str()));
+    }
+    if value.data_len() > known.max_len {
+        return Err(format!("{} bytes is longer than the {} allowed", value.data_len(), known.max_len));
+    }
+
+    let is_version = |s: &str| {
+        let mut parts = s.split('.');
+        let (major, minor) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+        parts.next().is_none()
+            && major.len() == 2
+            && !minor.is_empty()
+            && (major.bytes().chain(minor.bytes())).all(|b| b.is_ascii_digit())
+    };
+    let is_title_id = |id: &str| {
+        let bytes = id.as_bytes();
+        bytes.len() == 9 && bytes[..4].iter().all(u8::is_ascii_uppercase) && bytes[4..].iter().all(u8::is_ascii_digit)
+    };
+    let problem = match (key, value) {
+        ("TITLE_ID", SfoValue::Utf8(id)) if !is_title_id(id) => {
+            "must be four letters and five digits, like BLUS30443".to_string()
+        }
+        ("CATEGORY", SfoValue::Utf8(category)) if !CATEGORIES.iter().any(|(code, _)| code == category) => {
+            format!("unknown category {:?}", category)
+        }
+        ("VERSION" | "APP_VER" | "TARGET_APP_VER" | "PS3_SYSTEM_VER", SfoValue::Utf8(version)) if !is_version(version) => {
+            "must look like 01.00".to_string()
+        }
+        ("PARENTAL_LEVEL", SfoValue::Integer(level)) if *level > 11 => "must be 0 to 11".to_string(),
+        ("BOOTABLE", SfoValue::Integer(bootable)) if *bootable > 1 => "must be 0 or 1".to_string(),
+        // ATTRIBUTE has bits beyond the documented ones, so only these are strict
+        ("RESOLUTION" | "SOUND_FORMAT", SfoValue::Integer(bits)) if bits & !known_bits(key) != 0 => {
+            format!("unknown bits 0x{:X}", bits & !known_bits(key))
+        }
+        _ => return Ok(()),
+    };
+    Err(problem)
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
-    fn test_sfo_struct() {
-        // Test would require actual SFO data
+    fn test_sfo_round_trip() {
+        let mut sfo = Sfo::new();
+        sfo.set("TITLE", SfoValue::Utf8("Test Game".to_string()));
+        sfo.set("TITLE_ID", SfoValue::Utf8("BLUS30443".to_string()));
+        sfo.set("CATEGORY", SfoValue::Utf8("DG".to_string()));
+        sfo.set("RESOLUTION", SfoValue::Integer(0x0C));
+        sfo.set("SAVEDATA_FILE_LIST", SfoValue::Binary(vec![1, 0, 2]));
+
+        let bytes = sfo.to_bytes();
+        assert_eq!(&bytes[0..4], b"\x00PSF");
+        let parsed = Sfo::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, sfo);
+        assert_eq!(parsed.title(), Some("Test Game"));
+        assert_eq!(parsed.get_integer("RESOLUTION"), Some(0x0C));
+        // Known keys reserve the SDK's space
+        assert_eq!(parsed.entries().find(|(key, _)| *key == "TITLE").unwrap().1.max_len, 128);
+        assert!(parsed.validate().is_empty());
+
+        assert!(Sfo::from_bytes(b"\x00PSX").is_err());
+    }
+
+    #[test]
+    fn test_sfo_validation() {
+        assert!(validate_entry("TITLE_ID", &SfoValue::Utf8("BLUS3044".to_string())).is_err());
+        assert!(validate_entry("TITLE", &SfoValue::Integer(1)).is_err());
+        assert!(validate_entry("TITLE_05", &SfoValue::Utf8("x".repeat(128))).is_err());
+        assert!(validate_entry("VERSION", &SfoValue::Utf8("01.00".to_string())).is_ok());
+        assert!(validate_entry("VERSION", &SfoValue::Utf8("1.0".to_string())).is_err());
+        assert!(validate_entry("RESOLUTION", &SfoValue::Integer(0x40)).is_err());
+        assert!(validate_entry("CUSTOM_KEY", &SfoValue::Integer(0x40)).is_ok());
+        assert!(validate_entry("lower", &SfoValue::Integer(0)).is_err());
+
+        let mut sfo = Sfo::new();
+        sfo.set("CATEGORY", SfoValue::Utf8("SD".to_string()));
+        assert_eq!(sfo.validate().len(), 2);
     }
 }
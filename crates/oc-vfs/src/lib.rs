@@ -5,12 +5,15 @@ pub mod disc;
 pub mod formats;
 pub mod mount;
 pub mod savedata;
+pub mod trace;
 pub mod trophy;
 pub mod users;
 
 pub use disc::{DiscFormat, DiscInfo, DiscManager};
 pub use formats::iso::{IsoReader, IsoVolume, IsoDirectoryEntry};
+pub use formats::sfo::{Sfo, SfoEntry, SfoValue};
 pub use mount::{devices as ps3_devices, VirtualFileSystem};
 pub use savedata::{SaveDataInfo, SaveDataManager, SaveDataType};
+pub use trace::{VfsAccessKind, VfsAccessRecord, VfsAccessReport, VfsTracer};
 pub use trophy::{Trophy, TrophyGrade, TrophyManager, TrophySet, TrophyType};
 pub use users::{UserManager, UserProfile};
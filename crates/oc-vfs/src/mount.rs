@@ -3,6 +3,7 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
 use parking_lot::RwLock;
+use crate::trace::VfsTracer;
 
 /// Common PS3 device mount points
 pub mod devices {
@@ -32,6 +33,8 @@ pub mod devices {
 pub struct VirtualFileSystem {
     /// Mount points (virtual path -> host path)
     mounts: RwLock<HashMap<String, PathBuf>>,
+    /// File access tracer (disabled by default)
+    tracer: VfsTracer,
 }
 
 impl VirtualFileSystem {
@@ -39,9 +42,15 @@ impl VirtualFileSystem {
     pub fn new() -> Self {
         Self {
             mounts: RwLock::new(HashMap::new()),
+            tracer: VfsTracer::new(),
         }
     }
 
+    /// Get the file access tracer
+    pub fn tracer(&self) -> &VfsTracer {
+        &self.tracer
+    }
+
     /// Mount a device
     pub fn mount(&self, virtual_path: &str, host_path: PathBuf) {
         let mut mounts = self.mounts.write();
@@ -0,0 +1,315 @@
+//! VFS access tracing
+//!
+//! Records every virtual path a game touches together with whether the
+//! path resolved to an existing host file. The resulting report helps
+//! diagnose "missing file" black screens and builds compatibility data.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Kind of file system access
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VfsAccessKind {
+    /// File opened
+    Open,
+    /// File or directory stat
+    Stat,
+    /// Directory opened for listing
+    OpenDir,
+    /// Data read from an open file
+    Read,
+    /// Data written to an open file
+    Write,
+}
+
+impl VfsAccessKind {
+    /// Short label used in reports
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Stat => "stat",
+            Self::OpenDir => "opendir",
+            Self::Read => "read",
+            Self::Write => "write",
+        }
+    }
+}
+
+/// Aggregated accesses for a single virtual path
+#[derive(Debug, Clone)]
+pub struct VfsAccessRecord {
+    /// Virtual (guest) path
+    pub virtual_path: String,
+    /// Host path the virtual path resolved to, if it was mounted
+    pub host_path: Option<PathBuf>,
+    /// Whether the host path existed at the last access
+    pub hit: bool,
+    /// Number of open calls
+    pub opens: u32,
+    /// Number of stat calls
+    pub stats: u32,
+    /// Number of opendir calls
+    pub opendirs: u32,
+    /// Total bytes read
+    pub bytes_read: u64,
+    /// Total bytes written
+    pub bytes_written: u64,
+    /// Order in which the path was first accessed
+    pub first_access: u64,
+}
+
+impl VfsAccessRecord {
+    fn new(virtual_path: &str, first_access: u64) -> Self {
+        Self {
+            virtual_path: virtual_path.to_string(),
+            host_path: None,
+            hit: false,
+            opens: 0,
+            stats: 0,
+            opendirs: 0,
+            bytes_read: 0,
+            bytes_written: 0,
+            first_access,
+        }
+    }
+}
+
+struct TracerState {
+    records: HashMap<String, VfsAccessRecord>,
+    next_order: u64,
+}
+
+impl TracerState {
+    fn entry(&mut self, virtual_path: &str) -> &mut VfsAccessRecord {
+        let next_order = &mut self.next_order;
+        self.records
+            .entry(virtual_path.to_string())
+            .or_insert_with(|| {
+                let record = VfsAccessRecord::new(virtual_path, *next_order);
+                *next_order += 1;
+                record
+            })
+    }
+}
+
+/// VFS access tracer
+///
+/// Disabled by default; recording is a no-op until [`VfsTracer::set_enabled`]
+/// turns it on.
+pub struct VfsTracer {
+    enabled: AtomicBool,
+    state: Mutex<TracerState>,
+}
+
+impl VfsTracer {
+    /// Create a new (disabled) tracer
+    pub fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            state: Mutex::new(TracerState {
+                records: HashMap::new(),
+                next_order: 0,
+            }),
+        }
+    }
+
+    /// Enable or disable tracing
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Check if tracing is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Record a path-based access (open, stat, opendir)
+    ///
+    /// `host_path` is the resolved host path, or `None` if no mount point
+    /// matched. Hit/miss is determined by checking the host file system.
+    pub fn record(&self, kind: VfsAccessKind, virtual_path: &str, host_path: Option<&Path>) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let hit = host_path.map(|p| p.exists()).unwrap_or(false);
+        let mut state = self.state.lock();
+        let record = state.entry(virtual_path);
+        record.host_path = host_path.map(Path::to_path_buf);
+        record.hit = hit;
+        match kind {
+            VfsAccessKind::Open => record.opens += 1,
+            VfsAccessKind::Stat => record.stats += 1,
+            VfsAccessKind::OpenDir => record.opendirs += 1,
+            VfsAccessKind::Read | VfsAccessKind::Write => {}
+        }
+
+        if !hit {
+            tracing::debug!("VFS miss: {} ({})", virtual_path, kind.label());
+        }
+    }
+
+    /// Record bytes transferred through an open file
+    pub fn record_transfer(&self, kind: VfsAccessKind, virtual_path: &str, bytes: u64) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut state = self.state.lock();
+        let record = state.entry(virtual_path);
+        match kind {
+            VfsAccessKind::Read => record.bytes_read += bytes,
+            VfsAccessKind::Write => record.bytes_written += bytes,
+            _ => {}
+        }
+    }
+
+    /// Discard all recorded accesses
+    pub fn clear(&self) {
+        let mut state = self.state.lock();
+        state.records.clear();
+        state.next_order = 0;
+    }
+
+    /// Build a report of all recorded accesses, in first-access order
+    pub fn report(&self, title_id: &str) -> VfsAccessReport {
+        let state = self.state.lock();
+        let mut entries: Vec<VfsAccessRecord> = state.records.values().cloned().collect();
+        entries.sort_by_key(|r| r.first_access);
+
+        VfsAccessReport {
+            title_id: title_id.to_string(),
+            entries,
+        }
+    }
+}
+
+impl Default for VfsTracer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Per-game file access report
+#[derive(Debug, Clone)]
+pub struct VfsAccessReport {
+    /// Title ID of the traced game
+    pub title_id: String,
+    /// Accessed paths, in first-access order
+    pub entries: Vec<VfsAccessRecord>,
+}
+
+impl VfsAccessReport {
+    /// Paths that did not exist on the host
+    pub fn misses(&self) -> impl Iterator<Item = &VfsAccessRecord> {
+        self.entries.iter().filter(|r| !r.hit)
+    }
+
+    /// Number of paths that did not exist on the host
+    pub fn miss_count(&self) -> usize {
+        self.misses().count()
+    }
+
+    /// Render the report as plain text
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "VFS access report for {}", self.title_id);
+        let _ = writeln!(
+            out,
+            "{} paths accessed, {} missing on host",
+            self.entries.len(),
+            self.miss_count()
+        );
+        let _ = writeln!(out);
+
+        for record in &self.entries {
+            let host = record
+                .host_path
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|| "<unmounted>".to_string());
+            let _ = writeln!(
+                out,
+                "[{}] {} -> {} (open={}, stat={}, opendir={}, read={}B, written={}B)",
+                if record.hit { "HIT " } else { "MISS" },
+                record.virtual_path,
+                host,
+                record.opens,
+                record.stats,
+                record.opendirs,
+                record.bytes_read,
+                record.bytes_written,
+            );
+        }
+
+        out
+    }
+
+    /// Write the report to a file
+    pub fn write_to(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_text())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracer_disabled_by_default() {
+        let tracer = VfsTracer::new();
+        tracer.record(VfsAccessKind::Open, "/dev_bdvd/EBOOT.BIN", None);
+        assert!(tracer.report("TEST00000").entries.is_empty());
+    }
+
+    #[test]
+    fn test_tracer_hit_and_miss() {
+        let tracer = VfsTracer::new();
+        tracer.set_enabled(true);
+
+        let existing = std::env::temp_dir();
+        tracer.record(VfsAccessKind::OpenDir, "/dev_hdd0/tmp", Some(&existing));
+        tracer.record(
+            VfsAccessKind::Open,
+            "/dev_bdvd/missing.dat",
+            Some(Path::new("/nonexistent/oc-vfs-missing.dat")),
+        );
+        tracer.record(VfsAccessKind::Stat, "/dev_bdvd/missing.dat", None);
+
+        let report = tracer.report("BLUS00000");
+        assert_eq!(report.entries.len(), 2);
+        assert_eq!(report.entries[0].virtual_path, "/dev_hdd0/tmp");
+        assert!(report.entries[0].hit);
+        assert_eq!(report.miss_count(), 1);
+
+        let missing = report.misses().next().unwrap();
+        assert_eq!(missing.opens, 1);
+        assert_eq!(missing.stats, 1);
+        assert!(report.to_text().contains("MISS"));
+    }
+
+    #[test]
+    fn test_tracer_transfer_accounting() {
+        let tracer = VfsTracer::new();
+        tracer.set_enabled(true);
+
+        tracer.record(VfsAccessKind::Open, "/dev_hdd0/a.bin", None);
+        tracer.record_transfer(VfsAccessKind::Read, "/dev_hdd0/a.bin", 128);
+        tracer.record_transfer(VfsAccessKind::Read, "/dev_hdd0/a.bin", 64);
+        tracer.record_transfer(VfsAccessKind::Write, "/dev_hdd0/b.bin", 16);
+
+        let report = tracer.report("TEST00000");
+        assert_eq!(report.entries.len(), 2);
+        assert_eq!(report.entries[0].bytes_read, 192);
+        assert_eq!(report.entries[1].bytes_written, 16);
+
+        tracer.clear();
+        assert!(tracer.report("TEST00000").entries.is_empty());
+    }
+}
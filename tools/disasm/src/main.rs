@@ -0,0 +1,540 @@
+//! PPU/SPU disassembler for PS3 executables
+//!
+//! Disassembles the code segments of an ELF, SELF or PRX, or of an SPU
+//! program, with function boundaries from the symbol table, call targets and
+//! import stubs. Import stubs are named after the library and the function
+//! their NID resolves to. The output can be limited to an address range and
+//! to the instructions matching a search.
+
+use oc_debug::disassembler::DisassembledInstruction;
+use oc_debug::{FunctionMap, PpuDisassembler, SpuDisassembler};
+use oc_loader::elf::pt;
+use oc_loader::{parse_imports, ElfLoader, ImportedLibrary, PrxLoader, SelfLoader};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: disasm [OPTIONS] <FILE>
+
+Options:
+      --start <ADDR>    First address to disassemble
+      --end <ADDR>      Address to stop disassembling at
+      --search <TEXT>   Only print instructions containing TEXT (case-insensitive)
+      --imports         List the imported libraries and functions
+      --names <FILE>    Extra function names to resolve NIDs with, one per line
+                        as `name` or `0xNID name`
+      --keys <FILE>     Load extra keys from a keys.txt file to decrypt a SELF
+      --spu             Read FILE as a raw SPU local store image
+      --base <ADDR>     Load address of a raw SPU image (default: 0)
+  -h, --help            Print this help
+
+Exit codes:
+  0  success
+  1  the search matched nothing
+  2  the arguments were invalid or the file could not be loaded";
+
+/// Size of SPU local store
+const LS_SIZE: u64 = 0x40000;
+
+/// `li r12, 0`, the first instruction of an import stub
+const STUB_LI_R12: u32 = 0x3980_0000;
+
+/// Disassembler options
+#[derive(Debug, Clone, PartialEq)]
+struct Options {
+    file: PathBuf,
+    start: u64,
+    end: u64,
+    search: Option<String>,
+    imports: bool,
+    names: Option<PathBuf>,
+    keys: Option<PathBuf>,
+    spu: bool,
+    base: u64,
+}
+
+impl Options {
+    /// Parse the arguments after the program name, None for `--help`
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut options = Options {
+            file: PathBuf::new(),
+            start: 0,
+            end: u64::MAX,
+            search: None,
+            imports: false,
+            names: None,
+            keys: None,
+            spu: false,
+            base: 0,
+        };
+        let mut file = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+            let address = |value: String| parse_address(&value).ok_or_else(|| format!("{} is not an address", value));
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "--start" => options.start = address(value()?)?,
+                "--end" => options.end = address(value()?)?,
+                "--search" => options.search = Some(value()?.to_lowercase()),
+                "--imports" => options.imports = true,
+                "--names" => options.names = Some(PathBuf::from(value()?)),
+                "--keys" => options.keys = Some(PathBuf::from(value()?)),
+                "--spu" => options.spu = true,
+                "--base" => options.base = address(value()?)?,
+                _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+                _ if file.is_some() => return Err(format!("Unexpected argument {}", arg)),
+                _ => file = Some(PathBuf::from(arg)),
+            }
+        }
+        options.file = file.ok_or("No file was given")?;
+        if options.start >= options.end {
+            return Err("--start must be below --end".to_string());
+        }
+        Ok(Some(options))
+    }
+}
+
+/// Parse a hex address, with or without `0x`
+fn parse_address(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let digits = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")).unwrap_or(text);
+    u64::from_str_radix(digits, 16).ok()
+}
+
+/// Parse a names file: `name` lines are hashed into NIDs, `0xNID name` lines are taken as is
+fn parse_names(text: &str) -> HashMap<u32, String> {
+    let mut names = HashMap::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        let explicit = line.split_once(char::is_whitespace).and_then(|(nid, name)| {
+            let nid = nid.strip_prefix("0x").or_else(|| nid.strip_prefix("0X"))?;
+            Some((u32::from_str_radix(nid, 16).ok()?, name.trim()))
+        });
+        match explicit {
+            Some((nid, name)) => names.insert(nid, name.to_string()),
+            None => names.insert(PrxLoader::calculate_nid(line), line.to_string()),
+        };
+    }
+    names
+}
+
+/// Instruction set of a program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Arch {
+    Ppu,
+    Spu,
+}
+
+/// Loaded contents of a segment
+#[derive(Debug, Clone)]
+struct Segment {
+    addr: u64,
+    bytes: Vec<u8>,
+    executable: bool,
+}
+
+/// A program ready to disassemble
+struct Program {
+    arch: Arch,
+    entry: u64,
+    segments: Vec<Segment>,
+    /// Function symbols as (address, size, name)
+    symbols: Vec<(u64, u64, String)>,
+    functions: FunctionMap,
+    imports: Vec<ImportedLibrary>,
+}
+
+impl Program {
+    fn read_u32(&self, addr: u64) -> Option<u32> {
+        let segment = self.segments.iter().find(|s| addr >= s.addr && addr + 4 <= s.addr + s.bytes.len() as u64)?;
+        let offset = (addr - segment.addr) as usize;
+        Some(u32::from_be_bytes(segment.bytes[offset..offset + 4].try_into().ok()?))
+    }
+
+    fn is_code(&self, addr: u64) -> bool {
+        self.segments.iter().any(|s| s.executable && addr >= s.addr && addr < s.addr + s.bytes.len() as u64)
+    }
+
+    /// Instructions of the executable segments, in address order
+    fn instructions(&self) -> impl Iterator<Item = (u64, u32)> + '_ {
+        self.segments.iter().filter(|s| s.executable).flat_map(|segment| {
+            segment
+                .bytes
+                .chunks_exact(4)
+                .enumerate()
+                .map(|(i, word)| (segment.addr + i as u64 * 4, u32::from_be_bytes([word[0], word[1], word[2], word[3]])))
+        })
+    }
+
+    /// Name the functions: call targets first, so symbols and stubs get the final say
+    fn find_functions(&mut self, names: &HashMap<u32, String>) {
+        let mut functions = FunctionMap::new();
+        let targets: Vec<u64> = self.instructions().filter_map(|(addr, opcode)| call_target(self.arch, addr, opcode)).collect();
+        for target in targets.into_iter().filter(|&target| self.is_code(target)) {
+            functions.insert(target, 0, format!("sub_{:x}", target));
+        }
+
+        for (addr, size, name) in &self.symbols {
+            if self.is_code(*addr) {
+                functions.insert(*addr, *size, name.clone());
+            } else if let Some(code) = self.read_u32(*addr).map(u64::from).filter(|&code| self.is_code(code)) {
+                // PPC64 function symbols name the descriptor in .opd
+                functions.insert(code, 0, name.clone());
+            }
+        }
+
+        let entry = if self.is_code(self.entry) {
+            Some(self.entry)
+        } else {
+            self.read_u32(self.entry).map(u64::from).filter(|&code| self.is_code(code))
+        };
+        if let Some(entry) = entry {
+            let named = functions.lookup(entry).is_some_and(|f| f.start == entry && !f.name.starts_with("sub_"));
+            if !named {
+                functions.insert(entry, 0, "_start");
+            }
+        }
+
+        if self.arch == Arch::Ppu {
+            let slots: HashMap<u32, String> = self
+                .imports
+                .iter()
+                .flat_map(|library| {
+                    library.functions.iter().map(move |function| {
+                        let name = match names.get(&function.nid) {
+                            Some(name) => format!("{}::{}", library.name, name),
+                            None => format!("{}::0x{:08X}", library.name, function.nid),
+                        };
+                        (function.slot, name)
+                    })
+                })
+                .collect();
+            let words: Vec<(u64, u32)> = self.instructions().collect();
+            for (start, slot) in find_stubs(&words) {
+                if let Some(name) = slots.get(&slot) {
+                    functions.insert(start, 0x20, name.clone());
+                }
+            }
+        }
+        self.functions = functions;
+    }
+}
+
+/// Target of a call instruction
+fn call_target(arch: Arch, addr: u64, opcode: u32) -> Option<u64> {
+    match arch {
+        // bl / bla
+        Arch::Ppu if opcode >> 26 == 18 && opcode & 1 != 0 => {
+            let offset = (((opcode & 0x03FF_FFFC) << 6) as i32 >> 6) as i64 as u64;
+            Some(if opcode & 2 != 0 { offset & 0xFFFF_FFFF } else { addr.wrapping_add(offset) & 0xFFFF_FFFF })
+        }
+        Arch::Ppu => None,
+        Arch::Spu => {
+            let offset = (((opcode >> 7) & 0xFFFF) as i16 as i64 as u64) << 2;
+            match opcode >> 23 {
+                // brsl
+                0x066 => Some(addr.wrapping_add(offset) & (LS_SIZE - 1)),
+                // brasl
+                0x062 => Some(offset & (LS_SIZE - 1)),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// Find the import stubs in PPU code, returning their start and the stub slot they jump through
+///
+/// A stub loads the slot's address into r12 with `li`/`oris` (or `lis`),
+/// reads the function descriptor through it with `lwz r12, lo(r12)` and
+/// branches to it.
+fn find_stubs(words: &[(u64, u32)]) -> Vec<(u64, u32)> {
+    let fields = |opcode: u32| (opcode >> 26, (opcode >> 21) & 31, (opcode >> 16) & 31, opcode & 0xFFFF);
+    let mut stubs = Vec::new();
+    for i in 1..words.len() {
+        let (addr, opcode) = words[i];
+        let (op, rt, ra, lo) = fields(opcode);
+        if (op, rt, ra) != (32, 12, 12) {
+            continue;
+        }
+        let (high_addr, high) = words[i - 1];
+        let hi = match fields(high) {
+            // oris r12, r12, hi / lis r12, hi
+            (25, 12, 12, hi) | (15, 12, 0, hi) => hi,
+            _ => continue,
+        };
+        // Both words must be adjacent; segments can follow one another
+        if high_addr + 4 != addr {
+            continue;
+        }
+        let slot = (hi << 16).wrapping_add(lo as u16 as i16 as i32 as u32);
+        let start = match i.checked_sub(2).map(|j| words[j]) {
+            Some((li_addr, STUB_LI_R12)) if li_addr + 4 == high_addr => li_addr,
+            _ => high_addr,
+        };
+        stubs.push((start, slot));
+    }
+    stubs
+}
+
+fn load_ppu(data: &[u8]) -> Result<Program, String> {
+    let mut cursor = Cursor::new(data);
+    let mut elf = ElfLoader::new(&mut cursor).map_err(|e| e.to_string())?;
+    if let Err(e) = elf.parse_symbols(&mut cursor) {
+        eprintln!("warning: {}", e);
+    }
+    let imports = match parse_imports(&elf, data) {
+        Ok(imports) => imports,
+        Err(e) => {
+            eprintln!("warning: Failed to read the import table: {}", e);
+            Vec::new()
+        }
+    };
+    let segments = elf
+        .phdrs
+        .iter()
+        .filter(|p| p.p_type == pt::LOAD && p.p_filesz > 0)
+        .filter_map(|p| {
+            let bytes = data.get(p.p_offset as usize..(p.p_offset + p.p_filesz) as usize)?;
+            Some(Segment { addr: p.p_vaddr, bytes: bytes.to_vec(), executable: p.p_flags & 0x1 != 0 })
+        })
+        .collect();
+    let symbols: Vec<_> = elf
+        .symbols
+        .iter()
+        .filter(|s| s.is_function() && s.value != 0 && !s.name.is_empty())
+        .map(|s| (s.value, s.size, s.name.clone()))
+        .collect();
+
+    Ok(Program {
+        arch: Arch::Ppu,
+        entry: elf.header.e_entry,
+        segments,
+        symbols,
+        functions: FunctionMap::new(),
+        imports,
+    })
+}
+
+/// Load a 32-bit SPU ELF
+fn load_spu_elf(data: &[u8]) -> Result<Program, String> {
+    let u16_at = |offset: usize| data.get(offset..offset + 2).map(|b| u16::from_be_bytes([b[0], b[1]]));
+    let u32_at = |offset: usize| data.get(offset..offset + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+    let truncated = || "The SPU ELF is truncated".to_string();
+    let entry = u32_at(0x18).ok_or_else(truncated)?;
+    let phoff = u32_at(0x1C).ok_or_else(truncated)? as usize;
+    let phentsize = u16_at(0x2A).ok_or_else(truncated)? as usize;
+    let phnum = u16_at(0x2C).ok_or_else(truncated)? as usize;
+
+    let mut segments = Vec::new();
+    for i in 0..phnum {
+        let phdr = phoff + i * phentsize;
+        let field = |offset: usize| u32_at(phdr + offset).ok_or_else(truncated);
+        if field(0)? != pt::LOAD {
+            continue;
+        }
+        let (offset, vaddr, filesz, flags) = (field(4)? as usize, field(8)?, field(16)? as usize, field(24)?);
+        let bytes = data.get(offset..offset + filesz).ok_or_else(truncated)?;
+        segments.push(Segment { addr: vaddr as u64, bytes: bytes.to_vec(), executable: flags & 0x1 != 0 });
+    }
+    // Some SPU ELFs mark no segment executable
+    if !segments.iter().any(|s| s.executable) {
+        segments.iter_mut().for_each(|s| s.executable = true);
+    }
+    Ok(spu_program(entry as u64, segments))
+}
+
+fn spu_program(entry: u64, segments: Vec<Segment>) -> Program {
+    Program {
+        arch: Arch::Spu,
+        entry,
+        segments,
+        symbols: Vec::new(),
+        functions: FunctionMap::new(),
+        imports: Vec::new(),
+    }
+}
+
+/// Load the file the options name, decrypting it first if it is a SELF
+fn load(options: &Options) -> Result<Program, String> {
+    let data = std::fs::read(&options.file).map_err(|e| format!("Failed to read {}: {}", options.file.display(), e))?;
+    if options.spu {
+        let segment = Segment { addr: options.base, bytes: data, executable: true };
+        let mut program = spu_program(options.base, vec![segment]);
+        program.find_functions(&HashMap::new());
+        return Ok(program);
+    }
+    let data = if SelfLoader::is_self(&data) {
+        let mut loader = SelfLoader::new();
+        if let Some(keys) = &options.keys {
+            loader.crypto_mut().load_keys_file(&keys.to_string_lossy()).map_err(|e| e.to_string())?;
+        }
+        loader.decrypt(&data).map_err(|e| e.to_string())?
+    } else {
+        data
+    };
+    if !data.starts_with(b"\x7FELF") {
+        return Err("Not an ELF, SELF or PRX file; pass --spu for a raw SPU image".to_string());
+    }
+    match data[4] {
+        1 => load_spu_elf(&data),
+        _ => load_ppu(&data),
+    }
+}
+
+fn disassemble(arch: Arch, addr: u64, opcode: u32) -> DisassembledInstruction {
+    match arch {
+        Arch::Ppu => PpuDisassembler::disassemble(addr, opcode),
+        Arch::Spu => SpuDisassembler::disassemble(addr as u32, opcode),
+    }
+}
+
+/// Print the disassembly, returning how many instructions were printed
+fn print_disassembly(program: &Program, options: &Options) -> usize {
+    let mut printed = 0;
+    let mut current: Option<u64> = None;
+    for (addr, opcode) in program.instructions() {
+        if addr < options.start || addr >= options.end {
+            continue;
+        }
+        let instruction = disassemble(program.arch, addr, opcode);
+        let mut comment = instruction.comment.clone();
+        if let Some(function) = call_target(program.arch, addr, opcode).and_then(|target| program.functions.lookup(target)) {
+            comment = Some(function.name.clone());
+        }
+        let text = match &comment {
+            Some(comment) => format!("{:<32} ; {}", instruction.to_string(), comment),
+            None => instruction.to_string(),
+        };
+        if let Some(search) = &options.search {
+            if !text.to_lowercase().contains(search.as_str()) {
+                continue;
+            }
+        }
+        let function = program.functions.lookup(addr);
+        if function.map(|f| f.start) != current {
+            current = function.map(|f| f.start);
+            match function {
+                Some(function) if function.start == addr => println!("\n{}:", function.name),
+                Some(function) => println!("\n{}+0x{:x}:", function.name, addr - function.start),
+                None => println!(),
+            }
+        }
+        println!("  {:08x}:  {:08x}  {}", addr, opcode, text);
+        printed += 1;
+    }
+    printed
+}
+
+fn print_imports(program: &Program, names: &HashMap<u32, String>) {
+    println!("; imports:");
+    for library in &program.imports {
+        println!(";   {} ({} functions, {} variables)", library.name, library.functions.len(), library.variables.len());
+        for function in &library.functions {
+            let name = names.get(&function.nid).map(String::as_str).unwrap_or("?");
+            println!(";     0x{:08X} {:<40} slot 0x{:08x}", function.nid, name, function.slot);
+        }
+    }
+}
+
+fn run(options: &Options) -> Result<bool, String> {
+    let mut program = load(options)?;
+    // The loader's known names, overridden by the names file
+    let prx_loader = PrxLoader::new();
+    let mut names: HashMap<u32, String> = program
+        .imports
+        .iter()
+        .flat_map(|library| &library.functions)
+        .filter_map(|function| Some((function.nid, prx_loader.resolve_nid_to_name(function.nid)?.to_string())))
+        .collect();
+    if let Some(path) = &options.names {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        names.extend(parse_names(&text));
+    }
+    program.find_functions(&names);
+
+    let code: usize = program.segments.iter().filter(|s| s.executable).map(|s| s.bytes.len()).sum();
+    println!(
+        "; {}: {}, entry 0x{:x}, {} bytes of code, {} functions, {} imported libraries",
+        options.file.display(),
+        match program.arch {
+            Arch::Ppu => "PPU",
+            Arch::Spu => "SPU",
+        },
+        program.entry,
+        code,
+        program.functions.len(),
+        program.imports.len()
+    );
+    if options.imports {
+        print_imports(&program, &names);
+    }
+    let printed = print_disassembly(&program, options);
+    Ok(printed > 0 || options.search.is_none())
+}
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match run(&options) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_options() {
+        let parse = |args: &[&str]| Options::parse(args.iter().map(|arg| arg.to_string()));
+        let options = parse(&["--start", "0x10200", "--end", "10300", "--search", "BCTR", "EBOOT.elf"]).unwrap().unwrap();
+        assert_eq!((options.start, options.end), (0x10200, 0x10300));
+        assert_eq!(options.search.as_deref(), Some("bctr"));
+        assert_eq!(parse(&["--help"]), Ok(None));
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["--start", "0x200", "--end", "0x100", "a.elf"]).is_err());
+        assert!(parse(&["--base", "xyz", "--spu", "a.bin"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_names() {
+        let names = parse_names("# cellPad\ncellPadInit\n0x12345678 someFunction\n");
+        assert_eq!(names.get(&0x1CF98800).map(String::as_str), Some("cellPadInit"));
+        assert_eq!(names.get(&0x12345678).map(String::as_str), Some("someFunction"));
+        assert_eq!(names.len(), 2);
+    }
+
+    #[test]
+    fn test_call_target() {
+        // bl -0x10, bla 0x1000, b +0x10
+        assert_eq!(call_target(Arch::Ppu, 0x10020, 0x4BFF_FFF1), Some(0x10010));
+        assert_eq!(call_target(Arch::Ppu, 0x10020, 0x4800_1003), Some(0x1000));
+        assert_eq!(call_target(Arch::Ppu, 0x10020, 0x4800_0010), None);
+        // brsl $0, +0x10
+        assert_eq!(call_target(Arch::Spu, 0x100, (0x066 << 23) | (4 << 7)), Some(0x110));
+    }
+
+    #[test]
+    fn test_find_stubs() {
+        // li r12, 0; oris r12, r12, 0x2; lwz r12, 0x10(r12); ... mtctr r0; bctr
+        let code = [0x3980_0000, 0x658C_0002, 0x818C_0010, 0xF841_0028, 0x800C_0000, 0x804C_0004, 0x7C09_03A6, 0x4E80_0420];
+        let words: Vec<_> = code.iter().enumerate().map(|(i, &word)| (0x1000 + i as u64 * 4, word)).collect();
+        assert_eq!(find_stubs(&words), [(0x1000, 0x20010)]);
+    }
+}
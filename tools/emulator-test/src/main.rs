@@ -0,0 +1,352 @@
+//! Compatibility smoke test
+//!
+//! Boots an EBOOT through the full emulator pipeline headlessly and runs it
+//! until the game issues its first RSX flip, exits, crashes or runs out of
+//! time. The report lists the unimplemented syscalls the game called and the
+//! functions it imports that have no HLE implementation, so compatibility
+//! can be compared between builds.
+
+use oc_core::config::Config;
+use oc_hle::ModuleRegistry;
+use oc_integration::missing_features::{missing_functions, MissingFunction};
+use oc_integration::Emulator;
+use oc_loader::{parse_imports, ElfLoader, ImportedLibrary, SelfLoader};
+use serde::Serialize;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "\
+Usage: emulator-test [OPTIONS] <EBOOT>
+
+Runs the game until its first flip. EBOOT is an ELF or SELF, or a game
+folder holding an EBOOT.BIN.
+
+Options:
+      --timeout <SECONDS> Wall-clock limit of the run (default: 30)
+      --frames <N>        Frames to run before giving up (default: 3600)
+      --json              Print the report as JSON
+  -h, --help              Print this help
+
+Exit codes:
+  0  the game reached its first flip
+  1  the game exited, crashed or ran out of time before flipping
+  2  the arguments were invalid or the game did not boot";
+
+/// Smoke test options
+#[derive(Debug, Clone, PartialEq)]
+struct Options {
+    eboot: PathBuf,
+    timeout: Duration,
+    frames: u64,
+    json: bool,
+}
+
+impl Options {
+    /// Parse the arguments after the program name, None for `--help`
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut options = Options {
+            eboot: PathBuf::new(),
+            timeout: Duration::from_secs(30),
+            frames: 3600,
+            json: false,
+        };
+        let mut eboot = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "--json" => options.json = true,
+                "--frames" => {
+                    let value = value()?;
+                    options.frames = value
+                        .parse()
+                        .ok()
+                        .filter(|&frames| frames > 0)
+                        .ok_or_else(|| format!("--frames takes a frame count, not {}", value))?;
+                }
+                "--timeout" => {
+                    let value = value()?;
+                    let seconds = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|seconds| seconds.is_finite() && *seconds > 0.0)
+                        .ok_or_else(|| format!("--timeout takes a positive number of seconds, not {}", value))?;
+                    options.timeout = Duration::from_secs_f64(seconds);
+                }
+                _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+                _ if eboot.is_some() => return Err(format!("Unexpected argument {}", arg)),
+                _ => eboot = Some(PathBuf::from(arg)),
+            }
+        }
+        options.eboot = eboot.ok_or("No EBOOT was given")?;
+        Ok(Some(options))
+    }
+}
+
+/// The executable to boot for `path`, looking inside game folders
+fn find_eboot(path: &Path) -> Option<PathBuf> {
+    if !path.is_dir() {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    [path.join("EBOOT.BIN"), path.join("USRDIR/EBOOT.BIN"), path.join("PS3_GAME/USRDIR/EBOOT.BIN")]
+        .into_iter()
+        .find(|eboot| eboot.is_file())
+}
+
+/// How the run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum Outcome {
+    /// The game issued its first flip
+    Flipped,
+    /// The game called sys_process_exit
+    Exited,
+    /// The emulator crashed or panicked
+    Crashed,
+    /// The wall-clock limit was reached
+    TimedOut,
+    /// The frame limit was reached
+    FrameLimit,
+}
+
+impl Outcome {
+    fn describe(self) -> &'static str {
+        match self {
+            Outcome::Flipped => "reached the first flip",
+            Outcome::Exited => "exited before flipping",
+            Outcome::Crashed => "crashed before flipping",
+            Outcome::TimedOut => "timed out before flipping",
+            Outcome::FrameLimit => "hit the frame limit before flipping",
+        }
+    }
+}
+
+/// Unimplemented syscall the game called
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct UnknownSyscall {
+    number: u64,
+    calls: u64,
+}
+
+/// What one smoke test run found
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Report {
+    eboot: PathBuf,
+    outcome: Outcome,
+    frames: u64,
+    seconds: f64,
+    /// Seconds from boot to the first flip
+    first_flip_seconds: Option<f64>,
+    exit_code: Option<i32>,
+    crash: Option<String>,
+    unknown_syscalls: Vec<UnknownSyscall>,
+    /// Functions the executable imports
+    imported_functions: usize,
+    missing_functions: Vec<MissingFunction>,
+}
+
+impl Report {
+    /// Share of the imported functions that have an HLE implementation, in percent
+    fn hle_coverage(&self) -> f64 {
+        match self.imported_functions {
+            0 => 100.0,
+            imported => (imported - self.missing_functions.len()) as f64 * 100.0 / imported as f64,
+        }
+    }
+
+    fn print(&self) {
+        println!("{}", self.eboot.display());
+        println!("  result:            {}", self.outcome.describe());
+        println!("  frames:            {} in {:.2}s", self.frames, self.seconds);
+        if let Some(seconds) = self.first_flip_seconds {
+            println!("  first flip after:  {:.2}s", seconds);
+        }
+        if let Some(code) = self.exit_code {
+            println!("  exit code:         {}", code);
+        }
+        if let Some(crash) = &self.crash {
+            println!("  crash:             {}", crash);
+        }
+        println!(
+            "  HLE coverage:      {:.1}% ({} of {} imported functions)",
+            self.hle_coverage(),
+            self.imported_functions - self.missing_functions.len(),
+            self.imported_functions
+        );
+
+        println!("\n  Unimplemented syscalls called: {}", self.unknown_syscalls.len());
+        for syscall in &self.unknown_syscalls {
+            println!("    {:>5}  {} calls", syscall.number, syscall.calls);
+        }
+        println!("\n  Imported functions without HLE: {}", self.missing_functions.len());
+        for function in &self.missing_functions {
+            println!("    {}", function.display_name());
+        }
+    }
+}
+
+/// Read the libraries the executable at `path` imports
+fn read_imports(path: &Path) -> Result<Vec<ImportedLibrary>, String> {
+    let data = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let data = if SelfLoader::is_self(&data) {
+        SelfLoader::new().decrypt(&data).map_err(|e| e.to_string())?
+    } else {
+        data
+    };
+    let elf = ElfLoader::new(&mut Cursor::new(&data)).map_err(|e| e.to_string())?;
+    parse_imports(&elf, &data).map_err(|e| e.to_string())
+}
+
+/// Boot `eboot` and run it until its first flip or a limit
+fn run(eboot: &Path, options: &Options) -> Result<Report, String> {
+    let (imported_functions, missing) = match read_imports(eboot) {
+        Ok(libraries) => {
+            let count = libraries.iter().map(|library| library.functions.len()).sum();
+            (count, missing_functions(&libraries, &ModuleRegistry::new()))
+        }
+        Err(e) => {
+            eprintln!("warning: Failed to read the import table: {}", e);
+            (0, Vec::new())
+        }
+    };
+
+    // Default settings keep results independent of the machine's config
+    let mut config = Config::default();
+    config.audio.enable = false;
+    let mut emulator = Emulator::new(config);
+    emulator.boot(eboot, None).map_err(|e| format!("Boot failed: {}", e))?;
+    let runner = emulator.runner().cloned().ok_or("The emulator has no runner")?;
+
+    let started = Instant::now();
+    let mut crash = None;
+    let outcome = loop {
+        if runner.read().rsx_thread().read().flip_count() > 0 {
+            break Outcome::Flipped;
+        }
+        if runner.read().exit_code().is_some() {
+            break Outcome::Exited;
+        }
+        if runner.read().frame_count() >= options.frames {
+            break Outcome::FrameLimit;
+        }
+        if started.elapsed() >= options.timeout {
+            break Outcome::TimedOut;
+        }
+        match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| runner.write().run_frame())) {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::error!("Emulator frame error: {}", e),
+            Err(_) => {
+                let message = oc_debug::crash_dump::take_panic().unwrap_or_else(|| String::from("unknown panic"));
+                crash = Some(format!("panicked: {}", message.lines().next().unwrap_or_default()));
+                break Outcome::Crashed;
+            }
+        }
+        if let Some(report) = runner.read().take_crash() {
+            crash = Some(report.summary);
+            break Outcome::Crashed;
+        }
+    };
+    let seconds = started.elapsed().as_secs_f64();
+
+    let report = {
+        let runner = runner.read();
+        Report {
+            eboot: eboot.to_path_buf(),
+            outcome,
+            frames: runner.frame_count(),
+            seconds,
+            first_flip_seconds: (outcome == Outcome::Flipped).then_some(seconds),
+            exit_code: runner.exit_code(),
+            crash,
+            unknown_syscalls: runner
+                .syscall_handler()
+                .unknown_syscalls()
+                .into_iter()
+                .map(|(number, calls)| UnknownSyscall { number, calls })
+                .collect(),
+            imported_functions,
+            missing_functions: missing,
+        }
+    };
+    drop(runner);
+    if let Err(e) = emulator.stop() {
+        tracing::warn!("Failed to shut down: {}", e);
+    }
+    Ok(report)
+}
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    oc_core::logging::init_default();
+    oc_debug::crash_dump::install_panic_hook();
+
+    let Some(eboot) = find_eboot(&options.eboot) else {
+        eprintln!("error: No EBOOT found at {}", options.eboot.display());
+        return ExitCode::from(2);
+    };
+    let report = match run(&eboot, &options) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    if options.json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return ExitCode::from(2);
+            }
+        }
+    } else {
+        report.print();
+    }
+    match report.outcome {
+        Outcome::Flipped => ExitCode::SUCCESS,
+        _ => ExitCode::FAILURE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_options() {
+        let parse = |args: &[&str]| Options::parse(args.iter().map(|arg| arg.to_string()));
+        let options = parse(&["--timeout", "2.5", "--frames", "10", "--json", "EBOOT.BIN"]).unwrap().unwrap();
+        assert_eq!(options.eboot, PathBuf::from("EBOOT.BIN"));
+        assert_eq!(options.timeout, Duration::from_millis(2500));
+        assert_eq!(options.frames, 10);
+        assert!(options.json);
+        assert_eq!(parse(&["--help"]), Ok(None));
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["--frames", "0", "EBOOT.BIN"]).is_err());
+    }
+
+    #[test]
+    fn test_find_eboot() {
+        let dir = std::env::temp_dir().join(format!("oc_emulator_test_{}", std::process::id()));
+        let eboot = dir.join("PS3_GAME/USRDIR/EBOOT.BIN");
+        std::fs::create_dir_all(eboot.parent().unwrap()).unwrap();
+        std::fs::write(&eboot, b"\x7FELF").unwrap();
+        assert_eq!(find_eboot(&dir), Some(eboot.clone()));
+        assert_eq!(find_eboot(&eboot), Some(eboot));
+        assert_eq!(find_eboot(&dir.join("missing.elf")), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
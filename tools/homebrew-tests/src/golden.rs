@@ -0,0 +1,120 @@
+//! Golden results of a test
+
+use oc_rsx::FramebufferData;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::path::Path;
+
+/// What a test did in one run, stored as the expected result once blessed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Golden {
+    /// Frames the test runs for unless it exits first
+    pub frames: u64,
+    /// Code passed to sys_process_exit, None if the test was still running
+    pub exit_code: Option<i32>,
+    /// SHA-1 of the last presented frame, None if nothing was presented
+    pub framebuffer: Option<String>,
+    /// Everything written with sys_tty_write
+    pub tty: String,
+}
+
+impl Golden {
+    /// Load the golden result at `path`, None if it was never blessed
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&text)
+            .map(Some)
+            .map_err(|e| format!("{} is not a golden result: {}", path.display(), e))
+    }
+
+    /// Save to `path`
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, json + "\n").map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// How `actual` differs from this expected result, one line each
+    pub fn differences(&self, actual: &Golden) -> Vec<String> {
+        let mut differences = Vec::new();
+        if self.exit_code != actual.exit_code {
+            differences.push(format!("exit code {:?}, expected {:?}", actual.exit_code, self.exit_code));
+        }
+        if self.framebuffer != actual.framebuffer {
+            differences.push(format!(
+                "framebuffer {}, expected {}",
+                actual.framebuffer.as_deref().unwrap_or("none"),
+                self.framebuffer.as_deref().unwrap_or("none")
+            ));
+        }
+        if self.tty != actual.tty {
+            let count = self.tty.lines().count().max(actual.tty.lines().count());
+            let expected = self.tty.lines().chain(std::iter::repeat("<end>"));
+            let line = actual
+                .tty
+                .lines()
+                .chain(std::iter::repeat("<end>"))
+                .zip(expected)
+                .take(count)
+                .enumerate()
+                .find(|(_, (actual, expected))| actual != expected);
+            match line {
+                Some((n, (actual, expected))) => {
+                    differences.push(format!("tty line {}: {:?}, expected {:?}", n + 1, actual, expected))
+                }
+                // Same lines, different line endings
+                None => differences.push("tty output differs in whitespace".to_string()),
+            }
+        }
+        differences
+    }
+}
+
+/// SHA-1 of a frame's size and pixels as lowercase hex
+pub fn framebuffer_hash(frame: &FramebufferData) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(frame.width.to_le_bytes());
+    hasher.update(frame.height.to_le_bytes());
+    hasher.update(&frame.pixels);
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_differences() {
+        let expected = Golden {
+            frames: 60,
+            exit_code: Some(0),
+            framebuffer: Some("ab".to_string()),
+            tty: "start\npass\n".to_string(),
+        };
+        assert!(expected.differences(&expected.clone()).is_empty());
+
+        let actual = Golden {
+            exit_code: None,
+            tty: "start\n".to_string(),
+            ..expected.clone()
+        };
+        assert_eq!(
+            expected.differences(&actual),
+            vec![
+                "exit code None, expected Some(0)".to_string(),
+                "tty line 2: \"<end>\", expected \"pass\"".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_framebuffer_hash() {
+        let frame = FramebufferData { width: 1, height: 1, pixels: vec![0; 4] };
+        let other = FramebufferData { width: 2, height: 1, pixels: vec![0; 4] };
+        assert_eq!(framebuffer_hash(&frame).len(), 40);
+        assert_ne!(framebuffer_hash(&frame), framebuffer_hash(&other));
+    }
+}
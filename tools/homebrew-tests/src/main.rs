@@ -0,0 +1,310 @@
+//! Homebrew regression test harness
+//!
+//! Runs every homebrew ELF or self-test in a folder headlessly and compares
+//! its TTY output, exit code and final framebuffer against the golden result
+//! stored next to it as `<name>.golden.json`. A test is either an
+//! ELF/SELF/BIN file in the folder or a subfolder holding an `EBOOT.BIN`.
+//!
+//! Run with `--bless` to record the current results as the golden ones.
+
+mod golden;
+
+use golden::{framebuffer_hash, Golden};
+use oc_core::config::Config;
+use oc_integration::{capture, Emulator};
+use oc_rsx::FramebufferData;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "\
+Usage: homebrew-tests [OPTIONS] <DIR>
+
+Options:
+      --bless             Save the results as the new golden results
+      --frames <N>        Frames to run tests without a golden result for (default: 300)
+      --timeout <SECONDS> Wall-clock limit of each test (default: 60)
+      --filter <TEXT>     Only run tests whose name contains TEXT
+      --out <DIR>         Folder for actual results and last frames (default: target/homebrew-tests)
+  -h, --help              Print this help";
+
+/// Harness options
+#[derive(Debug, Clone, PartialEq)]
+struct Options {
+    dir: PathBuf,
+    bless: bool,
+    frames: u64,
+    timeout: Duration,
+    filter: Option<String>,
+    out: PathBuf,
+}
+
+impl Options {
+    /// Parse the arguments after the program name, None for `--help`
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut options = Options {
+            dir: PathBuf::new(),
+            bless: false,
+            frames: 300,
+            timeout: Duration::from_secs(60),
+            filter: None,
+            out: PathBuf::from("target/homebrew-tests"),
+        };
+        let mut dir = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "--bless" => options.bless = true,
+                "--frames" => {
+                    let value = value()?;
+                    options.frames = value
+                        .parse()
+                        .ok()
+                        .filter(|&frames| frames > 0)
+                        .ok_or_else(|| format!("--frames takes a frame count, not {}", value))?;
+                }
+                "--timeout" => {
+                    let value = value()?;
+                    let seconds = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|seconds| seconds.is_finite() && *seconds > 0.0)
+                        .ok_or_else(|| format!("--timeout takes a positive number of seconds, not {}", value))?;
+                    options.timeout = Duration::from_secs_f64(seconds);
+                }
+                "--filter" => options.filter = Some(value()?),
+                "--out" => options.out = PathBuf::from(value()?),
+                _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+                _ if dir.is_some() => return Err(format!("Unexpected argument {}", arg)),
+                _ => dir = Some(PathBuf::from(arg)),
+            }
+        }
+        options.dir = dir.ok_or("No test folder was given")?;
+        Ok(Some(options))
+    }
+}
+
+/// One test found in the test folder
+#[derive(Debug, Clone, PartialEq)]
+struct TestCase {
+    name: String,
+    /// Executable to boot
+    path: PathBuf,
+    golden: PathBuf,
+}
+
+/// Find the tests in `dir`, sorted by name
+fn discover(dir: &Path) -> std::io::Result<Vec<TestCase>> {
+    let mut cases = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let executable = if path.is_dir() {
+            [path.join("EBOOT.BIN"), path.join("PS3_GAME/USRDIR/EBOOT.BIN")]
+                .into_iter()
+                .find(|eboot| eboot.is_file())
+        } else {
+            let extension = path.extension().map(|ext| ext.to_string_lossy().to_ascii_lowercase());
+            matches!(extension.as_deref(), Some("elf" | "self" | "bin")).then(|| path.clone())
+        };
+        if let Some(executable) = executable {
+            cases.push(TestCase {
+                golden: dir.join(format!("{}.golden.json", name)),
+                name,
+                path: executable,
+            });
+        }
+    }
+    cases.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(cases)
+}
+
+/// Run a test for `frames` frames or until it exits, returning its result and last frame
+fn run_test(case: &TestCase, frames: u64, timeout: Duration) -> Result<(Golden, Option<FramebufferData>), String> {
+    // Default settings keep results independent of the machine's config
+    let mut config = Config::default();
+    config.audio.enable = false;
+    let mut emulator = Emulator::new(config);
+    emulator.boot(&case.path, None).map_err(|e| format!("boot failed: {}", e))?;
+    let runner = emulator.runner().cloned().ok_or("the emulator has no runner")?;
+
+    let started = Instant::now();
+    let result = loop {
+        let frame = runner.read().frame_count();
+        if frame >= frames || runner.read().exit_code().is_some() {
+            break Ok(());
+        }
+        if started.elapsed() >= timeout {
+            break Err(format!("timed out after {} frames", frame));
+        }
+        match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| runner.write().run_frame())) {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::error!("Emulator frame error: {}", e),
+            Err(_) => {
+                let message = oc_debug::crash_dump::take_panic().unwrap_or_else(|| String::from("unknown panic"));
+                break Err(format!("emulator panicked: {}", message.lines().next().unwrap_or_default()));
+            }
+        }
+        if let Some(report) = runner.read().take_crash() {
+            break Err(format!("crashed: {}", report.summary));
+        }
+    };
+
+    let actual = result.map(|()| {
+        let runner = runner.read();
+        let frame = runner.get_framebuffer();
+        let golden = Golden {
+            frames,
+            exit_code: runner.exit_code(),
+            framebuffer: frame.as_ref().map(framebuffer_hash),
+            tty: runner.syscall_handler().tty_output(),
+        };
+        (golden, frame)
+    });
+    drop(runner);
+    if let Err(e) = emulator.stop() {
+        tracing::warn!("Failed to shut down after {}: {}", case.name, e);
+    }
+    actual
+}
+
+/// Keep the actual result and last frame of a run for inspection
+fn save_actual(out: &Path, case: &TestCase, actual: &Golden, frame: Option<&FramebufferData>) -> Result<(), String> {
+    std::fs::create_dir_all(out).map_err(|e| format!("Failed to create {}: {}", out.display(), e))?;
+    actual.save(&out.join(format!("{}.actual.json", case.name)))?;
+    match frame {
+        Some(frame) => capture::save_screenshot(frame, &out.join(format!("{}.png", case.name))),
+        None => Ok(()),
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    oc_core::logging::init_default();
+    oc_debug::crash_dump::install_panic_hook();
+
+    let cases = match discover(&options.dir) {
+        Ok(cases) => cases,
+        Err(e) => {
+            eprintln!("error: Failed to read {}: {}", options.dir.display(), e);
+            return ExitCode::from(2);
+        }
+    };
+    let cases: Vec<_> = cases
+        .into_iter()
+        .filter(|case| match &options.filter {
+            Some(filter) => case.name.contains(filter.as_str()),
+            None => true,
+        })
+        .collect();
+
+    let (mut passed, mut failed, mut missing) = (0, 0, 0);
+    for case in &cases {
+        let golden = match Golden::load(&case.golden) {
+            Ok(golden) => golden,
+            Err(e) => {
+                println!("FAIL {}: {}", case.name, e);
+                failed += 1;
+                continue;
+            }
+        };
+        let frames = golden.as_ref().map(|golden| golden.frames).filter(|&frames| frames > 0);
+        let (actual, frame) = match run_test(case, frames.unwrap_or(options.frames), options.timeout) {
+            Ok(result) => result,
+            Err(e) => {
+                println!("FAIL {}: {}", case.name, e);
+                failed += 1;
+                continue;
+            }
+        };
+        if let Err(e) = save_actual(&options.out, case, &actual, frame.as_ref()) {
+            eprintln!("warning: {}", e);
+        }
+
+        if options.bless {
+            match actual.save(&case.golden) {
+                Ok(()) => println!("BLESS {}", case.name),
+                Err(e) => {
+                    println!("FAIL {}: {}", case.name, e);
+                    failed += 1;
+                    continue;
+                }
+            }
+            passed += 1;
+            continue;
+        }
+        let Some(golden) = golden else {
+            println!("NEW  {}: no golden result, run with --bless to record one", case.name);
+            missing += 1;
+            continue;
+        };
+        let differences = golden.differences(&actual);
+        if differences.is_empty() {
+            println!("PASS {}", case.name);
+            passed += 1;
+        } else {
+            println!("FAIL {}", case.name);
+            for difference in differences {
+                println!("     {}", difference);
+            }
+            failed += 1;
+        }
+    }
+
+    println!("\n{} passed, {} failed, {} without a golden result", passed, failed, missing);
+    if cases.is_empty() {
+        eprintln!("error: No tests found in {}", options.dir.display());
+        return ExitCode::from(2);
+    }
+    if failed > 0 || missing > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover() {
+        let dir = std::env::temp_dir().join(format!("oc_homebrew_tests_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("gcm_triangle/PS3_GAME/USRDIR")).unwrap();
+        std::fs::create_dir_all(dir.join("empty")).unwrap();
+        std::fs::write(dir.join("gcm_triangle/PS3_GAME/USRDIR/EBOOT.BIN"), b"").unwrap();
+        std::fs::write(dir.join("ppu_alu.elf"), b"").unwrap();
+        std::fs::write(dir.join("ppu_alu.elf.golden.json"), b"{}").unwrap();
+
+        let cases = discover(&dir).unwrap();
+        let names: Vec<_> = cases.iter().map(|case| case.name.as_str()).collect();
+        assert_eq!(names, ["gcm_triangle", "ppu_alu.elf"]);
+        assert_eq!(cases[0].path, dir.join("gcm_triangle/PS3_GAME/USRDIR/EBOOT.BIN"));
+        assert_eq!(cases[1].golden, dir.join("ppu_alu.elf.golden.json"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let parse = |args: &[&str]| Options::parse(args.iter().map(|arg| arg.to_string()));
+        let options = parse(&["--bless", "--frames", "60", "tests/homebrew"]).unwrap().unwrap();
+        assert!(options.bless);
+        assert_eq!(options.frames, 60);
+        assert_eq!(options.dir, PathBuf::from("tests/homebrew"));
+        assert_eq!(parse(&["--help"]), Ok(None));
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["--frames", "0", "dir"]).is_err());
+    }
+}
@@ -0,0 +1,226 @@
+//! PS3 firmware update package analyzer
+//!
+//! Reports the version and entries of a PUP file and, with `--extract`,
+//! writes every entry to disk and unpacks the CoreOS container into its
+//! SELF and SPRX files. Entries are checked against the PUP's HMAC table
+//! when the HMAC key is given.
+
+use oc_loader::{CoreOsContainer, FirmwareVersion, PupEntryId, PupLoader};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: pup-analyzer [OPTIONS] <PUP>
+
+Options:
+      --extract <DIR>   Write every entry and the files of the CoreOS container to DIR
+      --hmac-key <HEX>  PUP HMAC key to verify the entries with
+  -h, --help            Print this help
+
+Exit codes:
+  0  the package was read (and every entry matched its HMAC)
+  1  an entry did not match its HMAC, or could not be extracted
+  2  the arguments were invalid or the package could not be read";
+
+/// Analyzer options
+#[derive(Debug, Clone, PartialEq)]
+struct Options {
+    pup: PathBuf,
+    extract: Option<PathBuf>,
+    hmac_key: Option<Vec<u8>>,
+}
+
+impl Options {
+    /// Parse the arguments after the program name, None for `--help`
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut extract = None;
+        let mut hmac_key = None;
+        let mut pup = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "--extract" => extract = Some(PathBuf::from(value()?)),
+                "--hmac-key" => {
+                    let value = value()?;
+                    hmac_key = Some(parse_hex(&value).ok_or_else(|| format!("--hmac-key takes hex bytes, not {}", value))?);
+                }
+                _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+                _ if pup.is_some() => return Err(format!("Unexpected argument {}", arg)),
+                _ => pup = Some(PathBuf::from(arg)),
+            }
+        }
+        Ok(Some(Options {
+            pup: pup.ok_or("No PUP file was given")?,
+            extract,
+            hmac_key,
+        }))
+    }
+}
+
+fn parse_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+    if text.is_empty() || text.len() % 2 == 1 {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| text.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect()
+}
+
+/// A CoreOS file name that is safe to join to the output folder
+fn safe_name(name: &str) -> Option<&str> {
+    let plain = !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\']);
+    plain.then_some(name)
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Report on the package, extracting it if asked; Ok(false) if anything failed to verify or extract
+fn analyze(options: &Options) -> Result<bool, String> {
+    let data = std::fs::read(&options.pup).map_err(|e| format!("Failed to read {}: {}", options.pup.display(), e))?;
+    let header = PupLoader::parse_header(&data).map_err(|e| e.to_string())?;
+    let mut loader = PupLoader::new();
+    let entries = loader.parse(&data).map_err(|e| e.to_string())?.to_vec();
+
+    println!("{}", options.pup.display());
+    println!("  firmware:        {}", FirmwareVersion::from_u64(header.image_version).to_string());
+    println!("  package version: 0x{:016x}", header.package_version);
+    println!("  entries:         {}", entries.len());
+    if let Some(version) = loader.get_entry(PupEntryId::UpdateVersion) {
+        if let Ok(text) = loader.extract(&data, version) {
+            println!("  version entry:   {}", String::from_utf8_lossy(&text).trim());
+        }
+    }
+
+    let mut ok = true;
+    println!("\n  {:<8} {:<20} {:>12} {:>12}  HMAC", "ID", "NAME", "OFFSET", "SIZE");
+    for entry in &entries {
+        let hmac = match &options.hmac_key {
+            None => "not checked".to_string(),
+            Some(key) => match loader.verify_hmac(&data, entry, key) {
+                Ok(Some(true)) => "ok".to_string(),
+                Ok(Some(false)) => {
+                    ok = false;
+                    "MISMATCH".to_string()
+                }
+                Ok(None) => "no hash".to_string(),
+                Err(e) => {
+                    ok = false;
+                    e.to_string()
+                }
+            },
+        };
+        println!(
+            "  0x{:<6x} {:<20} {:>12} {:>12}  {}",
+            entry.raw_id,
+            entry.id.file_name(entry.raw_id),
+            format!("0x{:x}", entry.offset),
+            entry.size,
+            hmac
+        );
+    }
+
+    // The firmware modules are the files in the CoreOS container
+    let core_os = entries.iter().find(|entry| entry.id == PupEntryId::CoreOs);
+    let container = core_os.map(|entry| loader.extract(&data, entry).and_then(|bytes| {
+        CoreOsContainer::parse(&bytes).map(|container| (container, bytes))
+    }));
+    println!();
+    match &container {
+        None => println!("  No CoreOS entry"),
+        Some(Err(e)) => println!("  CoreOS container not readable: {}", e),
+        Some(Ok((container, _))) => {
+            println!("  CoreOS modules (container version {}):", container.version);
+            for file in &container.files {
+                println!("    {:<32} {:>10} bytes", file.name, file.size);
+            }
+        }
+    }
+
+    let Some(dir) = &options.extract else {
+        return Ok(ok);
+    };
+    for entry in &entries {
+        let path = dir.join(entry.id.file_name(entry.raw_id));
+        match loader.extract(&data, entry).map_err(|e| e.to_string()).and_then(|bytes| write_file(&path, &bytes)) {
+            Ok(()) => println!("  wrote {}", path.display()),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                ok = false;
+            }
+        }
+    }
+    if let Some(Ok((container, bytes))) = &container {
+        for file in &container.files {
+            let Some(name) = safe_name(&file.name) else {
+                eprintln!("error: Skipping CoreOS file with unsafe name {:?}", file.name);
+                ok = false;
+                continue;
+            };
+            let path = dir.join("core_os").join(name);
+            match write_file(&path, container.extract(bytes, file)) {
+                Ok(()) => println!("  wrote {}", path.display()),
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    ok = false;
+                }
+            }
+        }
+    }
+    Ok(ok)
+}
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match analyze(&options) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_options() {
+        let parse = |args: &[&str]| Options::parse(args.iter().map(|arg| arg.to_string()));
+        let options = parse(&["--extract", "out", "--hmac-key", "00ff", "PS3UPDAT.PUP"]).unwrap().unwrap();
+        assert_eq!(options.pup, PathBuf::from("PS3UPDAT.PUP"));
+        assert_eq!(options.extract, Some(PathBuf::from("out")));
+        assert_eq!(options.hmac_key, Some(vec![0x00, 0xff]));
+        assert_eq!(parse(&["--help"]), Ok(None));
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["--hmac-key", "0g", "a.pup"]).is_err());
+    }
+
+    #[test]
+    fn test_safe_name() {
+        assert_eq!(safe_name("lv2_kernel.self"), Some("lv2_kernel.self"));
+        assert_eq!(safe_name("../lv1.self"), None);
+        assert_eq!(safe_name(".."), None);
+        assert_eq!(safe_name(""), None);
+    }
+}
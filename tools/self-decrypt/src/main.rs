@@ -0,0 +1,358 @@
+//! PS3 SELF/SPRX decryption tool
+//!
+//! Decrypts a SELF, EBOOT.BIN or SPRX to a plain ELF with the emulator's
+//! [`SelfLoader`] and prints its SCE, extended and application info headers,
+//! the decrypted metadata section table and the program and section headers
+//! of the ELF. Failures exit with distinct codes so scripts can tell a
+//! missing key from a corrupt file.
+
+use oc_core::error::LoaderError;
+use oc_loader::{ElfLoader, SelfLoader};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: self-decrypt [OPTIONS] <SELF>
+
+Options:
+  -o, --output <ELF>    Where to write the ELF (default: the input with an .elf extension)
+      --info            Only print the headers, do not write the ELF
+      --keys <FILE>     Load extra keys from a keys.txt file
+      --firmware <DIR>  Load the keys of the firmware installed to DIR (dev_flash)
+  -h, --help            Print this help
+
+Exit codes:
+  0  the file was decrypted
+  1  the ELF could not be written
+  2  the arguments were invalid or a file could not be read
+  3  no key for the file is available
+  4  the file is not a valid SELF, or is corrupt";
+
+/// Exit code for a missing key
+const EXIT_MISSING_KEY: u8 = 3;
+
+/// Exit code for a file that is not a valid SELF
+const EXIT_CORRUPT: u8 = 4;
+
+/// Tool options
+#[derive(Debug, Clone, PartialEq)]
+struct Options {
+    input: PathBuf,
+    output: Option<PathBuf>,
+    info: bool,
+    keys: Option<PathBuf>,
+    firmware: Option<PathBuf>,
+}
+
+impl Options {
+    /// Parse the arguments after the program name, None for `--help`
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut options = Options {
+            input: PathBuf::new(),
+            output: None,
+            info: false,
+            keys: None,
+            firmware: None,
+        };
+        let mut input = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().map(PathBuf::from).ok_or_else(|| format!("{} needs a value", arg));
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-o" | "--output" => options.output = Some(value()?),
+                "--info" => options.info = true,
+                "--keys" => options.keys = Some(value()?),
+                "--firmware" => options.firmware = Some(value()?),
+                _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+                _ if input.is_some() => return Err(format!("Unexpected argument {}", arg)),
+                _ => input = Some(PathBuf::from(arg)),
+            }
+        }
+        options.input = input.ok_or("No SELF file was given")?;
+        if options.info && options.output.is_some() {
+            return Err("--info and --output cannot be used together".to_string());
+        }
+        Ok(Some(options))
+    }
+
+    /// Where the ELF goes, None with `--info`
+    fn output_path(&self) -> Option<PathBuf> {
+        if self.info {
+            return None;
+        }
+        Some(self.output.clone().unwrap_or_else(|| self.input.with_extension("elf")))
+    }
+}
+
+/// Exit code for a failed decryption
+fn exit_code(error: &LoaderError) -> u8 {
+    match error {
+        LoaderError::MissingKey(_) => EXIT_MISSING_KEY,
+        _ => EXIT_CORRUPT,
+    }
+}
+
+/// Name of a SELF program type
+fn program_type_name(program_type: u32) -> &'static str {
+    match program_type {
+        1 => "LV0",
+        2 => "LV1",
+        3 => "LV2",
+        4 => "APP",
+        5 => "ISO (isolated SPU module)",
+        6 => "LDR (secure loader)",
+        7 => "UNK7",
+        8 => "NPDRM",
+        _ => "unknown",
+    }
+}
+
+/// Name of an ELF program header type
+fn segment_type_name(p_type: u32) -> String {
+    use oc_loader::elf::pt;
+    match p_type {
+        pt::NULL => "NULL".to_string(),
+        pt::LOAD => "LOAD".to_string(),
+        pt::DYNAMIC => "DYNAMIC".to_string(),
+        pt::INTERP => "INTERP".to_string(),
+        pt::NOTE => "NOTE".to_string(),
+        pt::TLS => "TLS".to_string(),
+        pt::PROC1 => "PRX_PARAM".to_string(),
+        pt::PROC2 => "PROC_PARAM".to_string(),
+        _ => format!("0x{:08x}", p_type),
+    }
+}
+
+/// Print the SCE, extended and application info headers
+fn print_headers(data: &[u8]) -> Result<(), LoaderError> {
+    let header = SelfLoader::parse_header(data)?;
+    println!("SCE header:");
+    println!("  version:         {}", header.version);
+    println!(
+        "  key revision:    0x{:04x}{}",
+        header.key_type & 0x7FFF,
+        if header.key_type & 0x8000 != 0 { " (debug)" } else { "" }
+    );
+    println!("  header type:     {}", header.header_type);
+    println!("  metadata offset: 0x{:x}", header.metadata_offset);
+    println!("  header length:   0x{:x}", header.header_len);
+    println!("  data length:     0x{:x}", header.data_len);
+
+    let ext = SelfLoader::parse_ext_header(data)?;
+    println!("Extended header:");
+    println!("  app info:        0x{:x}", ext.app_info_offset);
+    println!("  ELF header:      0x{:x}", ext.elf_offset);
+    println!("  program headers: 0x{:x}", ext.phdr_offset);
+    println!("  section headers: 0x{:x}", ext.shdr_offset);
+    println!("  section info:    0x{:x}", ext.section_info_offset);
+    println!("  SCE version:     0x{:x}", ext.sce_version_offset);
+    println!("  control info:    0x{:x} ({} bytes)", ext.control_info_offset, ext.control_info_size);
+
+    let app = SelfLoader::parse_app_info(data, ext.app_info_offset as usize)?;
+    println!("App info:");
+    println!("  auth ID:         0x{:016x}", app.auth_id);
+    println!("  vendor ID:       0x{:08x}", app.vendor_id);
+    println!("  program type:    {} ({})", app.self_type, program_type_name(app.self_type));
+    println!("  version:         0x{:016x}", app.version);
+    Ok(())
+}
+
+/// Print the decrypted metadata and its section table
+fn print_metadata(loader: &SelfLoader, data: &[u8]) -> Result<(), LoaderError> {
+    let metadata = loader.decrypt_metadata(data)?;
+    println!("Metadata:");
+    println!("  signed length:   0x{:x}", metadata.header.signature_input_length);
+    println!("  sections:        {}", metadata.header.section_count);
+    println!("  keys:            {}", metadata.header.key_count);
+    println!(
+        "  {:<4} {:>10} {:>10} {:>5} {:>8} {:<9} {:<10} {:<6}",
+        "#", "OFFSET", "SIZE", "TYPE", "SEGMENT", "ENCRYPTED", "COMPRESSED", "HASHED"
+    );
+    for (index, section) in metadata.sections.iter().enumerate() {
+        let yes_no = |flag: bool| if flag { "yes" } else { "no" };
+        println!(
+            "  {:<4} {:>10} {:>10} {:>5} {:>8} {:<9} {:<10} {:<6}",
+            index,
+            format!("0x{:x}", section.data_offset),
+            format!("0x{:x}", section.data_size),
+            section.section_type,
+            section.section_index,
+            yes_no(section.encrypted == 3),
+            yes_no(section.compressed == 2),
+            yes_no(section.hashed == 2),
+        );
+    }
+    Ok(())
+}
+
+/// Print the ELF header and its program and section headers
+///
+/// SELFs keep the section headers outside the segments, so they are read
+/// from the SELF when the rebuilt ELF has none.
+fn print_elf(elf_data: &[u8], self_data: &[u8]) -> Result<(), LoaderError> {
+    let elf = ElfLoader::new(&mut Cursor::new(elf_data))?;
+    println!("ELF:");
+    println!("  type:            0x{:04x}", elf.header.e_type);
+    println!("  machine:         0x{:04x}", elf.header.e_machine);
+    println!("  entry:           0x{:x}", elf.header.e_entry);
+    println!("  size:            {} bytes", elf_data.len());
+
+    println!("Program headers:");
+    println!("  {:<4} {:<12} {:<5} {:>10} {:>10} {:>10} {:>10}", "#", "TYPE", "FLAGS", "OFFSET", "VADDR", "FILESZ", "MEMSZ");
+    for (index, phdr) in elf.phdrs.iter().enumerate() {
+        let flags: String = [(4, 'R'), (2, 'W'), (1, 'X')]
+            .iter()
+            .map(|&(bit, name)| if phdr.p_flags & bit != 0 { name } else { '-' })
+            .collect();
+        println!(
+            "  {:<4} {:<12} {:<5} {:>10} {:>10} {:>10} {:>10}",
+            index,
+            segment_type_name(phdr.p_type),
+            flags,
+            format!("0x{:x}", phdr.p_offset),
+            format!("0x{:x}", phdr.p_vaddr),
+            format!("0x{:x}", phdr.p_filesz),
+            format!("0x{:x}", phdr.p_memsz),
+        );
+    }
+
+    let mut shdrs = elf.shdrs.clone();
+    if shdrs.is_empty() && SelfLoader::is_self(self_data) {
+        let ext = SelfLoader::parse_ext_header(self_data)?;
+        if ext.shdr_offset != 0 {
+            let mut header = elf.header;
+            header.e_shoff = ext.shdr_offset;
+            shdrs = ElfLoader::parse_shdrs(&mut Cursor::new(self_data), &header)?;
+        }
+    }
+    println!("Section headers:");
+    if shdrs.is_empty() {
+        println!("  none");
+        return Ok(());
+    }
+    println!("  {:<4} {:>5} {:>10} {:>10} {:>10} {:>6}", "#", "TYPE", "ADDR", "OFFSET", "SIZE", "FLAGS");
+    for (index, shdr) in shdrs.iter().enumerate() {
+        println!(
+            "  {:<4} {:>5} {:>10} {:>10} {:>10} {:>6}",
+            index,
+            shdr.sh_type,
+            format!("0x{:x}", shdr.sh_addr),
+            format!("0x{:x}", shdr.sh_offset),
+            format!("0x{:x}", shdr.sh_size),
+            format!("0x{:x}", shdr.sh_flags),
+        );
+    }
+    Ok(())
+}
+
+/// Load the keys the options ask for
+fn make_loader(options: &Options) -> Result<SelfLoader, String> {
+    let mut loader = SelfLoader::new();
+    if let Some(firmware) = &options.firmware {
+        loader.crypto_mut().load_firmware_keys(&firmware.to_string_lossy()).map_err(|e| e.to_string())?;
+    }
+    if let Some(keys) = &options.keys {
+        loader.crypto_mut().load_keys_file(&keys.to_string_lossy()).map_err(|e| e.to_string())?;
+    }
+    Ok(loader)
+}
+
+/// Decrypt and report, returning the tool's exit code
+fn run(options: &Options) -> Result<(), (u8, String)> {
+    let loader = make_loader(options).map_err(|e| (2, e))?;
+    let data = std::fs::read(&options.input)
+        .map_err(|e| (2, format!("Failed to read {}: {}", options.input.display(), e)))?;
+    let failed = |e: LoaderError| (exit_code(&e), e.to_string());
+
+    println!("{}", options.input.display());
+    let elf = if SelfLoader::is_self(&data) {
+        print_headers(&data).map_err(failed)?;
+        let header = SelfLoader::parse_header(&data).map_err(failed)?;
+        let embedded = data.get(header.header_len as usize..).is_some_and(|rest| rest.starts_with(b"\x7FELF"));
+        if embedded {
+            println!("Metadata:\n  not encrypted, the ELF is stored as is");
+        } else {
+            print_metadata(&loader, &data).map_err(failed)?;
+        }
+        loader.decrypt(&data).map_err(failed)?
+    } else if data.starts_with(b"\x7FELF") {
+        println!("Already a plain ELF");
+        data.clone()
+    } else {
+        return Err((EXIT_CORRUPT, "Not a SELF or ELF file".to_string()));
+    };
+    print_elf(&elf, &data).map_err(failed)?;
+
+    if let Some(output) = options.output_path() {
+        write_elf(&output, &elf).map_err(|e| (1, e))?;
+        println!("Wrote {} ({} bytes)", output.display(), elf.len());
+    }
+    Ok(())
+}
+
+fn write_elf(path: &Path, elf: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::write(path, elf).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err((code, e)) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_options() {
+        let parse = |args: &[&str]| Options::parse(args.iter().map(|arg| arg.to_string()));
+        let options = parse(&["--keys", "keys.txt", "EBOOT.BIN"]).unwrap().unwrap();
+        assert_eq!(options.input, PathBuf::from("EBOOT.BIN"));
+        assert_eq!(options.keys, Some(PathBuf::from("keys.txt")));
+        assert_eq!(options.output_path(), Some(PathBuf::from("EBOOT.elf")));
+        let options = parse(&["--info", "libfs.sprx"]).unwrap().unwrap();
+        assert_eq!(options.output_path(), None);
+        assert_eq!(parse(&["--help"]), Ok(None));
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["--info", "-o", "out.elf", "EBOOT.BIN"]).is_err());
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(exit_code(&LoaderError::MissingKey(String::new())), EXIT_MISSING_KEY);
+        assert_eq!(exit_code(&LoaderError::InvalidSelf(String::new())), EXIT_CORRUPT);
+        assert_eq!(exit_code(&LoaderError::DecryptionFailed(String::new())), EXIT_CORRUPT);
+
+        let options = Options {
+            input: std::env::temp_dir().join(format!("oc_self_decrypt_{}.bin", std::process::id())),
+            output: None,
+            info: true,
+            keys: None,
+            firmware: None,
+        };
+        std::fs::write(&options.input, b"SCE\0truncated").unwrap();
+        assert_eq!(run(&options).unwrap_err().0, EXIT_CORRUPT);
+        std::fs::remove_file(&options.input).unwrap();
+    }
+}